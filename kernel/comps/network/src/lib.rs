@@ -10,7 +10,7 @@ mod driver;
 
 extern crate alloc;
 
-use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
+use alloc::{string::String, sync::Arc, vec::Vec};
 use core::{any::Any, fmt::Debug};
 
 use aster_bigtcp::device::DeviceCapabilities;
@@ -67,21 +67,27 @@ pub trait AnyNetworkDevice: Send + Sync + Any + Debug {
 
 pub trait NetDeviceCallback = Fn() + Send + Sync + 'static;
 
+/// Registers a network device with a unique name.
+///
+/// If a device with the same name has already been registered, it will be replaced.
 pub fn register_device(
     name: String,
     device: Arc<SpinLock<dyn AnyNetworkDevice, BottomHalfDisabled>>,
 ) {
-    COMPONENT
-        .get()
-        .unwrap()
-        .network_device_table
-        .lock()
-        .insert(name, NetworkDeviceIrqCallbackSet::new(device));
+    let mut device_table = COMPONENT.get().unwrap().network_device_table.lock();
+    if let Some(callbacks) = device_table
+        .iter_mut()
+        .find(|callbacks| callbacks.name == name)
+    {
+        *callbacks = NetworkDeviceIrqCallbackSet::new(name, device);
+    } else {
+        device_table.push(NetworkDeviceIrqCallbackSet::new(name, device));
+    }
 }
 
 pub fn get_device(str: &str) -> Option<Arc<SpinLock<dyn AnyNetworkDevice, BottomHalfDisabled>>> {
     let table = COMPONENT.get().unwrap().network_device_table.lock();
-    let callbacks = table.get(str)?;
+    let callbacks = table.iter().find(|callbacks| callbacks.name == str)?;
     Some(callbacks.device.clone())
 }
 
@@ -91,7 +97,7 @@ pub fn get_device(str: &str) -> Option<Arc<SpinLock<dyn AnyNetworkDevice, Bottom
 /// the callback function should _not_ sleep.
 pub fn register_recv_callback(name: &str, callback: impl NetDeviceCallback) {
     let device_table = COMPONENT.get().unwrap().network_device_table.lock();
-    let Some(callbacks) = device_table.get(name) else {
+    let Some(callbacks) = device_table.iter().find(|callbacks| callbacks.name == name) else {
         return;
     };
     callbacks.recv_callbacks.lock().push(Arc::new(callback));
@@ -107,7 +113,7 @@ pub fn register_recv_callback(name: &str, callback: impl NetDeviceCallback) {
 /// The driver may skip certain callbacks for performance optimization.
 pub fn register_send_callback(name: &str, callback: impl NetDeviceCallback) {
     let device_table = COMPONENT.get().unwrap().network_device_table.lock();
-    let Some(callbacks) = device_table.get(name) else {
+    let Some(callbacks) = device_table.iter().find(|callbacks| callbacks.name == name) else {
         return;
     };
     callbacks.send_callbacks.lock().push(Arc::new(callback));
@@ -118,7 +124,7 @@ fn handle_rx_softirq() {
     // TODO: We should handle network events for just one device per softirq,
     // rather than processing events for all devices.
     // This issue should be addressed once new network devices are added.
-    for callback_set in device_table.values() {
+    for callback_set in device_table.iter() {
         let recv_callbacks = callback_set.recv_callbacks.lock();
         for callback in recv_callbacks.iter() {
             callback();
@@ -131,7 +137,7 @@ fn handle_tx_softirq() {
     // TODO: We should handle network events for just one device per softirq,
     // rather than processing events for all devices.
     // This issue should be addressed once new network devices are added.
-    for callback_set in device_table.values() {
+    for callback_set in device_table.iter() {
         let can_send = {
             let mut device = callback_set.device.lock();
             device.free_processed_tx_buffers();
@@ -159,11 +165,12 @@ pub fn raise_receive_softirq() {
     SoftIrqLine::get(NETWORK_RX_SOFTIRQ_ID).raise();
 }
 
+/// Returns all network devices in the order in which they are registered.
 pub fn all_devices() -> Vec<(String, NetworkDeviceRef)> {
     let network_devs = COMPONENT.get().unwrap().network_device_table.lock();
     network_devs
         .iter()
-        .map(|(name, callbacks)| (name.clone(), callbacks.device.clone()))
+        .map(|callbacks| (callbacks.name.clone(), callbacks.device.clone()))
         .collect()
 }
 
@@ -183,21 +190,22 @@ type NetDeviceCallbackListRef = Arc<SpinLock<Vec<Arc<dyn NetDeviceCallback>>, Bo
 type NetworkDeviceRef = Arc<SpinLock<dyn AnyNetworkDevice, BottomHalfDisabled>>;
 
 struct Component {
-    /// Device list, the key is device name, value is (callbacks, device);
-    network_device_table:
-        SpinLock<BTreeMap<String, NetworkDeviceIrqCallbackSet>, BottomHalfDisabled>,
+    /// Device list in the registration order.
+    network_device_table: SpinLock<Vec<NetworkDeviceIrqCallbackSet>, BottomHalfDisabled>,
 }
 
 /// The send callbacks and recv callbacks for a network device
 struct NetworkDeviceIrqCallbackSet {
+    name: String,
     device: NetworkDeviceRef,
     recv_callbacks: NetDeviceCallbackListRef,
     send_callbacks: NetDeviceCallbackListRef,
 }
 
 impl NetworkDeviceIrqCallbackSet {
-    fn new(device: NetworkDeviceRef) -> Self {
+    fn new(name: String, device: NetworkDeviceRef) -> Self {
         Self {
+            name,
             device,
             recv_callbacks: Arc::new(SpinLock::new(Vec::new())),
             send_callbacks: Arc::new(SpinLock::new(Vec::new())),
@@ -208,7 +216,7 @@ impl NetworkDeviceIrqCallbackSet {
 impl Component {
     pub fn init() -> Result<Self, ComponentInitError> {
         Ok(Self {
-            network_device_table: SpinLock::new(BTreeMap::new()),
+            network_device_table: SpinLock::new(Vec::new()),
         })
     }
 }
@@ -1,9 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{
-    boxed::Box, collections::linked_list::LinkedList, string::ToString, sync::Arc, vec::Vec,
+use alloc::{boxed::Box, collections::linked_list::LinkedList, format, sync::Arc, vec::Vec};
+use core::{
+    fmt::Debug,
+    sync::atomic::{AtomicUsize, Ordering},
 };
-use core::fmt::Debug;
 
 use aster_bigtcp::device::{Checksum, DeviceCapabilities, Medium};
 use aster_network::{AnyNetworkDevice, EthernetAddr, NetError, RxBuffer, TxBuffer, RX_BUFFER_POOL};
@@ -143,8 +144,11 @@ impl NetworkDevice {
 
         device.transport.finish_init();
 
+        // Each device gets a unique name, e.g., `Virtio-Net0`, `Virtio-Net1`, and so on.
+        static DEVICE_INDEX: AtomicUsize = AtomicUsize::new(0);
+        let index = DEVICE_INDEX.fetch_add(1, Ordering::Relaxed);
         aster_network::register_device(
-            super::DEVICE_NAME.to_string(),
+            format!("{}{}", super::DEVICE_NAME, index),
             Arc::new(SpinLock::new(device)),
         );
         Ok(())
@@ -4,4 +4,7 @@ pub mod config;
 pub mod device;
 pub mod header;
 
+/// The prefix of the names of virtio network devices.
+///
+/// The devices are named with this prefix followed by an index, e.g., `Virtio-Net0`.
 pub const DEVICE_NAME: &str = "Virtio-Net";
@@ -112,3 +112,46 @@ pub mod udp {
         }
     }
 }
+
+pub mod raw {
+    /// An error returned by [`RawIpSocket::send`].
+    ///
+    /// [`RawIpSocket::send`]: crate::socket::RawIpSocket::send
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum SendError {
+        Unaddressable,
+        /// The packet is ill-formed.
+        Malformed,
+        BufferFull,
+        /// The packet is too large.
+        TooLarge,
+    }
+
+    /// An error returned by [`RawIpSocket::recv`].
+    ///
+    /// [`RawIpSocket::recv`]: crate::socket::RawIpSocket::recv
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum RecvError {
+        Exhausted,
+    }
+}
+
+pub mod icmp {
+    pub use super::raw::{RecvError, SendError};
+}
+
+pub mod packet {
+    /// An error returned by [`PacketSocket::send`].
+    ///
+    /// [`PacketSocket::send`]: crate::socket::PacketSocket::send
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum SendError {
+        /// The frame is ill-formed.
+        Malformed,
+        BufferFull,
+        /// The frame is too large.
+        TooLarge,
+    }
+
+    pub use super::raw::RecvError;
+}
@@ -6,7 +6,7 @@ use alloc::{
     sync::Arc,
     vec::Vec,
 };
-use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 
 use aster_softirq::BottomHalfDisabled;
 use bitflags::bitflags;
@@ -15,20 +15,30 @@ use ostd::sync::{SpinLock, SpinLockGuard};
 use smoltcp::{
     iface::{packet::Packet, Context},
     phy::Device,
-    wire::{IpAddress, IpEndpoint, Ipv4Address, Ipv4Packet},
+    wire::{
+        EthernetAddress, EthernetFrame, EthernetRepr, HardwareAddress, IpAddress, IpEndpoint,
+        IpListenEndpoint, IpVersion, Ipv4Address, Ipv4Cidr, Ipv6Address,
+    },
 };
 
 use super::{
-    poll::{FnHelper, PollContext, SocketTableAction},
+    dhcp::{DhcpClient, DhcpLeaseChange},
+    filter::FilterHook,
+    neighbor::NeighborCache,
+    poll::{FnHelper, IpPacket, PollContext, SocketTableAction},
     poll_iface::PollableIface,
-    port::BindPortConfig,
+    port::{BindAddr, BindPortConfig},
+    route::RouteTable,
     time::get_network_timestamp,
-    Iface,
+    DhcpLease, FilterTable, Iface, Ipv4Route, Neighbor,
 };
 use crate::{
     errors::BindError,
     ext::Ext,
-    socket::{TcpListenerBg, UdpSocketBg},
+    socket::{
+        IcmpSocketBg, NeedIfacePoll, PacketSocketBg, PacketType, RawIpSocketBg, TcpListenerBg,
+        UdpSocketBg,
+    },
     socket_table::SocketTable,
 };
 
@@ -36,32 +46,53 @@ pub struct IfaceCommon<E: Ext> {
     index: u32,
     name: String,
     type_: InterfaceType,
-    flags: InterfaceFlags,
+    flags: AtomicU32,
+    promiscuity: AtomicUsize,
+    mtu: AtomicUsize,
+    max_mtu: usize,
 
     interface: SpinLock<PollableIface<E>, BottomHalfDisabled>,
-    used_ports: SpinLock<BTreeMap<u16, PortState>, BottomHalfDisabled>,
+    routes: SpinLock<RouteTable, BottomHalfDisabled>,
+    neighbors: SpinLock<NeighborCache, BottomHalfDisabled>,
+    used_ports: SpinLock<BTreeMap<(IpVersion, u16), PortState>, BottomHalfDisabled>,
     sockets: SpinLock<SocketTable<E>, BottomHalfDisabled>,
+    // Packet sockets are not in the socket table because they are accessed in the link layer,
+    // where the socket table may have already been locked.
+    packet_sockets: SpinLock<Vec<Arc<PacketSocketBg<E>>>, BottomHalfDisabled>,
+    filter_table: SpinLock<Option<Arc<FilterTable>>, BottomHalfDisabled>,
     sched_poll: E::ScheduleNextPoll,
 }
 
 impl<E: Ext> IfaceCommon<E> {
     pub(super) fn new(
+        index: u32,
         name: String,
         type_: InterfaceType,
         flags: InterfaceFlags,
-        interface: smoltcp::iface::Interface,
+        mut interface: smoltcp::iface::Interface,
         sched_poll: E::ScheduleNextPoll,
     ) -> Self {
-        let index = INTERFACE_INDEX_ALLOCATOR.fetch_add(1, Ordering::Relaxed);
+        // The initial MTU is the maximum MTU supported by the device.
+        let max_mtu = interface.context().caps.max_transmission_unit;
 
         Self {
             index,
             name,
             type_,
-            flags,
-            interface: SpinLock::new(PollableIface::new(interface)),
+            flags: AtomicU32::new(flags.bits()),
+            promiscuity: AtomicUsize::new(0),
+            mtu: AtomicUsize::new(max_mtu),
+            max_mtu,
+            interface: SpinLock::new(PollableIface::new(
+                interface,
+                type_ == InterfaceType::LOOPBACK,
+            )),
+            routes: SpinLock::new(RouteTable::new()),
+            neighbors: SpinLock::new(NeighborCache::new()),
             used_ports: SpinLock::new(BTreeMap::new()),
             sockets: SpinLock::new(SocketTable::new()),
+            packet_sockets: SpinLock::new(Vec::new()),
+            filter_table: SpinLock::new(None),
             sched_poll,
         }
     }
@@ -79,7 +110,51 @@ impl<E: Ext> IfaceCommon<E> {
     }
 
     pub(super) fn flags(&self) -> InterfaceFlags {
-        self.flags
+        let flags = InterfaceFlags::from_bits_truncate(self.flags.load(Ordering::Relaxed));
+        if self.is_promiscuous() {
+            flags | InterfaceFlags::PROMISC
+        } else {
+            flags
+        }
+    }
+
+    pub(super) fn set_flags(&self, flags: InterfaceFlags) {
+        // The promiscuous mode is managed by the promiscuity counter.
+        let flags = flags - InterfaceFlags::PROMISC;
+        self.flags.store(flags.bits(), Ordering::Relaxed);
+    }
+
+    pub(super) fn is_promiscuous(&self) -> bool {
+        self.promiscuity.load(Ordering::Relaxed) > 0
+    }
+
+    pub(super) fn inc_promiscuity(&self) {
+        self.promiscuity.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn dec_promiscuity(&self) {
+        let old_promiscuity = self.promiscuity.fetch_sub(1, Ordering::Relaxed);
+        debug_assert!(old_promiscuity > 0);
+    }
+
+    pub(super) fn mtu(&self) -> usize {
+        self.mtu.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn max_mtu(&self) -> usize {
+        self.max_mtu
+    }
+
+    pub(super) fn set_mtu(&self, mtu: usize) {
+        debug_assert!(mtu <= self.max_mtu);
+
+        let mut interface = self.interface.lock();
+        interface.context_mut().caps.max_transmission_unit = mtu;
+        self.mtu.store(mtu, Ordering::Relaxed);
+    }
+
+    pub(super) fn hardware_addr(&self) -> HardwareAddress {
+        self.interface.lock().hardware_addr()
     }
 
     pub(super) fn ipv4_addr(&self) -> Option<Ipv4Address> {
@@ -90,17 +165,110 @@ impl<E: Ext> IfaceCommon<E> {
         self.interface.lock().prefix_len()
     }
 
+    pub(super) fn ipv4_gateway(&self) -> Option<Ipv4Address> {
+        self.routes
+            .lock()
+            .routes()
+            .iter()
+            .filter(|route| route.dst.prefix_len() == 0)
+            .filter_map(|route| Some((route.gateway?, route.metric)))
+            .min_by_key(|(_, metric)| *metric)
+            .map(|(gateway, _)| gateway)
+    }
+
+    pub(super) fn ipv4_routes(&self) -> Vec<Ipv4Route> {
+        self.routes.lock().routes().to_vec()
+    }
+
+    pub(super) fn add_ipv4_route(&self, route: Ipv4Route) -> Option<Ipv4Route> {
+        self.routes.lock().insert(route)
+    }
+
+    pub(super) fn remove_ipv4_route(&self, dst: Ipv4Cidr, metric: u32) -> Option<Ipv4Route> {
+        self.routes.lock().remove(dst, metric)
+    }
+
+    pub(super) fn lookup_ipv4_route(&self, dst_addr: &Ipv4Address) -> Option<Ipv4Route> {
+        self.routes.lock().lookup(dst_addr).copied()
+    }
+
+    pub(super) fn lookup_neighbor(
+        &self,
+        ip_addr: &Ipv4Address,
+        now: smoltcp::time::Instant,
+    ) -> Option<EthernetAddress> {
+        self.neighbors.lock().lookup(ip_addr, now)
+    }
+
+    pub(super) fn update_neighbor(
+        &self,
+        ip_addr: Ipv4Address,
+        ether_addr: EthernetAddress,
+        now: smoltcp::time::Instant,
+    ) {
+        self.neighbors.lock().insert(ip_addr, ether_addr, now);
+    }
+
+    pub(super) fn neighbors(&self) -> Vec<Neighbor> {
+        self.neighbors.lock().neighbors(get_network_timestamp())
+    }
+
+    pub(super) fn set_ipv4_cidr(&self, ipv4_cidr: Option<Ipv4Cidr>) {
+        self.interface.lock().set_ipv4_cidr(ipv4_cidr);
+    }
+
+    pub(super) fn ipv6_addr(&self) -> Option<Ipv6Address> {
+        self.interface.lock().ipv6_addr()
+    }
+
+    pub(super) fn ipv6_prefix_len(&self) -> Option<u8> {
+        self.interface.lock().ipv6_prefix_len()
+    }
+
+    pub(super) fn join_multicast_group(&self, group: Ipv4Address) -> NeedIfacePoll {
+        if self.interface.lock().multicast_groups_mut().join(group) {
+            NeedIfacePoll::TRUE
+        } else {
+            NeedIfacePoll::FALSE
+        }
+    }
+
+    pub(super) fn leave_multicast_group(&self, group: Ipv4Address) -> NeedIfacePoll {
+        if self.interface.lock().multicast_groups_mut().leave(group) {
+            NeedIfacePoll::TRUE
+        } else {
+            NeedIfacePoll::FALSE
+        }
+    }
+
+    pub(super) fn start_dhcp(&self) -> NeedIfacePoll {
+        let mut interface = self.interface.lock();
+        let HardwareAddress::Ethernet(ether_addr) = interface.hardware_addr() else {
+            return NeedIfacePoll::FALSE;
+        };
+
+        interface.start_dhcp(ether_addr, get_network_timestamp());
+        NeedIfacePoll::TRUE
+    }
+
+    pub(super) fn dhcp_lease(&self) -> Option<DhcpLease> {
+        self.interface.lock().dhcp()?.lease().cloned()
+    }
+
+    pub(super) fn set_filter_table(&self, filter_table: Option<Arc<FilterTable>>) {
+        *self.filter_table.lock() = filter_table;
+    }
+
     pub(super) fn sched_poll(&self) -> &E::ScheduleNextPoll {
         &self.sched_poll
     }
 }
 
-/// An allocator that allocates a unique index for each interface.
+// Lock order: `interface` -> `sockets` -> `packet_sockets`
 //
-// FIXME: This allocator is specific to each network namespace.
-pub static INTERFACE_INDEX_ALLOCATOR: AtomicU32 = AtomicU32::new(1);
-
-// Lock order: `interface` -> `sockets`
+// The `routes` and `neighbors` locks can be acquired while holding the `interface` lock, but no
+// other locks can be acquired while holding them. The same applies to the lock in the filter table,
+// which can be acquired while holding the `interface` and `sockets` locks.
 impl<E: Ext> IfaceCommon<E> {
     /// Acquires the lock to the interface.
     pub(crate) fn interface(&self) -> SpinLockGuard<'_, PollableIface<E>, BottomHalfDisabled> {
@@ -120,13 +288,16 @@ impl<E: Ext> IfaceCommon<E> {
     pub(super) fn bind(
         &self,
         iface: Arc<dyn Iface<E>>,
+        addr: BindAddr,
         config: BindPortConfig,
     ) -> core::result::Result<BoundPort<E>, BindError> {
-        let (port, can_reuse) = self.bind_port(config)?;
+        let (port, can_reuse) = self.bind_port(addr, config)?;
         Ok(BoundPort {
             iface,
+            addr,
             port,
             can_reuse: AtomicBool::new(can_reuse),
+            other_ports: Vec::new(),
         })
     }
 
@@ -136,11 +307,16 @@ impl<E: Ext> IfaceCommon<E> {
     ///
     /// See <https://en.wikipedia.org/wiki/Ephemeral_port>.
     fn alloc_ephemeral_port(
-        used_ports: &mut BTreeMap<u16, PortState>,
+        used_ports: &BTreeMap<(IpVersion, u16), PortState>,
+        addr: BindAddr,
         _can_reuse: bool,
     ) -> Option<u16> {
         for port in IP_LOCAL_PORT_START..=IP_LOCAL_PORT_END {
-            if let Entry::Vacant(..) = used_ports.entry(port) {
+            let is_vacant = addr
+                .port_versions()
+                .iter()
+                .all(|ip_version| !used_ports.contains_key(&(*ip_version, port)));
+            if is_vacant {
                 return Some(port);
             }
         }
@@ -151,52 +327,67 @@ impl<E: Ext> IfaceCommon<E> {
         None
     }
 
-    fn bind_port(&self, config: BindPortConfig) -> Result<(u16, bool), BindError> {
+    fn bind_port(&self, addr: BindAddr, config: BindPortConfig) -> Result<(u16, bool), BindError> {
         let mut used_ports = self.used_ports.lock();
         let config_can_reuse = config.can_reuse();
 
         let port = if let Some(port) = config.port() {
             port
         } else {
-            match Self::alloc_ephemeral_port(&mut used_ports, config_can_reuse) {
+            match Self::alloc_ephemeral_port(&used_ports, addr, config_can_reuse) {
                 Some(port) => port,
                 None => return Err(BindError::Exhausted),
             }
         };
 
-        if let Some(port_state) = used_ports.get_mut(&port) {
+        // An address that occupies the ports of both IP versions (e.g., `::` without
+        // `IPV6_V6ONLY`) conflicts with the addresses of either version. So all ports must be
+        // checked before any of them are occupied.
+        for ip_version in addr.port_versions() {
+            let Some(port_state) = used_ports.get(&(*ip_version, port)) else {
+                continue;
+            };
             // FIXME: If the socket is not a backlog socket,
             // we should check whether there is a listening socket on the port.
             // If there is, the socket cannot be bound to that port.
             let can_reuse = matches!(config, BindPortConfig::Backlog(_))
                 || (port_state.can_reuse() & config_can_reuse);
-            if can_reuse {
-                port_state.nsocket += 1;
-                if config_can_reuse {
-                    port_state.nreuse += 1;
-                }
-            } else {
+            if !can_reuse {
                 return Err(BindError::InUse);
             }
-        } else {
-            let port_state = PortState::new(config_can_reuse);
-            used_ports.insert(port, port_state);
-        };
+        }
+
+        for ip_version in addr.port_versions() {
+            match used_ports.entry((*ip_version, port)) {
+                Entry::Occupied(mut entry) => {
+                    let port_state = entry.get_mut();
+                    port_state.nsocket += 1;
+                    if config_can_reuse {
+                        port_state.nreuse += 1;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(PortState::new(config_can_reuse));
+                }
+            }
+        }
 
         Ok((port, config_can_reuse))
     }
 
     /// Releases the port so that it can be used again.
-    fn release_port(&self, port: u16, can_reuse: bool) {
+    fn release_port(&self, addr: BindAddr, port: u16, can_reuse: bool) {
         let mut used_ports = self.used_ports.lock();
-        if let Entry::Occupied(mut entry) = used_ports.entry(port) {
-            let port_state = entry.get_mut();
-            port_state.nsocket -= 1;
-            if can_reuse {
-                port_state.nreuse -= 1;
-            }
-            if port_state.nsocket == 0 {
-                entry.remove_entry();
+        for ip_version in addr.port_versions() {
+            if let Entry::Occupied(mut entry) = used_ports.entry((*ip_version, port)) {
+                let port_state = entry.get_mut();
+                port_state.nsocket -= 1;
+                if can_reuse {
+                    port_state.nreuse -= 1;
+                }
+                if port_state.nsocket == 0 {
+                    entry.remove_entry();
+                }
             }
         }
     }
@@ -208,6 +399,24 @@ impl<E: Ext> IfaceCommon<E> {
         sockets.insert_udp_socket(socket);
     }
 
+    pub(crate) fn register_icmp_socket(&self, socket: Arc<IcmpSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        sockets.insert_icmp_socket(socket);
+    }
+
+    pub(crate) fn register_raw_socket(&self, socket: Arc<RawIpSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        sockets.insert_raw_socket(socket);
+    }
+
+    pub(crate) fn register_packet_socket(&self, socket: Arc<PacketSocketBg<E>>) {
+        let mut packet_sockets = self.packet_sockets.lock();
+        debug_assert!(!packet_sockets
+            .iter()
+            .any(|packet_socket| Arc::ptr_eq(packet_socket, &socket)));
+        packet_sockets.push(socket);
+    }
+
     pub(crate) fn remove_tcp_listener(&self, socket: &Arc<TcpListenerBg<E>>) {
         let mut sockets = self.sockets.lock();
         let removed = sockets.remove_listener(socket.listener_key());
@@ -219,6 +428,100 @@ impl<E: Ext> IfaceCommon<E> {
         let removed = sockets.remove_udp_socket(socket);
         debug_assert!(removed.is_some());
     }
+
+    pub(crate) fn remove_icmp_socket(&self, socket: &Arc<IcmpSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        let removed = sockets.remove_icmp_socket(socket);
+        debug_assert!(removed.is_some());
+    }
+
+    pub(crate) fn remove_raw_socket(&self, socket: &Arc<RawIpSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        let removed = sockets.remove_raw_socket(socket);
+        debug_assert!(removed.is_some());
+    }
+
+    pub(crate) fn remove_packet_socket(&self, socket: &Arc<PacketSocketBg<E>>) {
+        let mut packet_sockets = self.packet_sockets.lock();
+        let index = packet_sockets
+            .iter()
+            .position(|packet_socket| Arc::ptr_eq(packet_socket, socket));
+        debug_assert!(index.is_some());
+        if let Some(index) = index {
+            packet_sockets.swap_remove(index);
+        }
+    }
+}
+
+impl<E: Ext> IfaceCommon<E> {
+    /// Delivers copies of a frame to the packet sockets.
+    ///
+    /// If `sender` is specified, the frame will not be delivered to the sender itself.
+    pub(super) fn process_packet(
+        &self,
+        ether_repr: &EthernetRepr,
+        payload: &[u8],
+        pkt_type: PacketType,
+        sender: Option<&Arc<PacketSocketBg<E>>>,
+    ) {
+        // Frames destined for other hosts are visible only in the promiscuous mode.
+        if pkt_type == PacketType::OtherHost && !self.is_promiscuous() {
+            return;
+        }
+
+        let packet_sockets = self.packet_sockets.lock();
+
+        for socket in packet_sockets.iter() {
+            if sender.is_some_and(|sender| Arc::ptr_eq(sender, socket)) {
+                continue;
+            }
+            socket.process(ether_repr, payload, pkt_type);
+        }
+    }
+
+    /// Transmits the frames queued in the packet sockets.
+    ///
+    /// The frames are passed to `transmit_phy` to be transmitted by the device. They are also
+    /// delivered to other packet sockets as outgoing frames.
+    pub(super) fn poll_packet_egress<D, F>(&self, device: &mut D, mut transmit_phy: F)
+    where
+        D: Device + ?Sized,
+        F: FnMut(&EthernetFrame<&[u8]>, D::TxToken<'_>),
+    {
+        let sockets = self
+            .packet_sockets
+            .lock()
+            .iter()
+            .filter(|socket| socket.need_dispatch())
+            .cloned()
+            .collect::<Vec<_>>();
+        let now = get_network_timestamp();
+
+        for socket in sockets.iter() {
+            loop {
+                let Some(tx_token) = device.transmit(now) else {
+                    return;
+                };
+                let Some(frame) = socket.dispatch() else {
+                    break;
+                };
+
+                // The frame has been checked when it is sent by the packet socket.
+                let frame = EthernetFrame::new_unchecked(frame.as_slice());
+                let Ok(ether_repr) = EthernetRepr::parse(&frame) else {
+                    continue;
+                };
+
+                transmit_phy(&frame, tx_token);
+                self.process_packet(
+                    &ether_repr,
+                    frame.payload(),
+                    PacketType::Outgoing,
+                    Some(socket),
+                );
+            }
+        }
+    }
 }
 
 impl<E: Ext> IfaceCommon<E> {
@@ -234,17 +537,26 @@ impl<E: Ext> IfaceCommon<E> {
             &'pkt [u8],
             &'cx mut Context,
             D::TxToken<'tx>,
-            Option<(Ipv4Packet<&'pkt [u8]>, D::TxToken<'tx>)>,
+            Option<(IpPacket<'pkt>, D::TxToken<'tx>)>,
         >,
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
+        let filter_table = self.filter_table.lock().clone();
+        let filter = FilterHook::new(filter_table.as_deref(), &self.name);
+
         let mut interface = self.interface();
         interface.context_mut().now = get_network_timestamp();
 
         let mut sockets = self.sockets.lock();
         let mut socket_actions = Vec::new();
 
-        let mut context = PollContext::new(interface.as_mut(), &sockets, &mut socket_actions);
+        let mut context = PollContext::new(
+            self,
+            interface.as_mut(),
+            &sockets,
+            &mut socket_actions,
+            filter,
+        );
         context.poll_ingress(device, &mut process_phy, &mut dispatch_phy);
         context.poll_egress(device, &mut dispatch_phy);
 
@@ -261,21 +573,51 @@ impl<E: Ext> IfaceCommon<E> {
             }
         }
 
-        // Note that only TCP connections can have timers set, so as far as the time to poll is
-        // concerned, we only need to consider TCP connections.
+        if let Some(change) = interface.dhcp_mut().and_then(DhcpClient::take_lease_change) {
+            self.apply_dhcp_lease_change(&mut interface, change);
+        }
+
+        // Note that only TCP connections and the DHCP client can have timers set, so as far as the
+        // time to poll is concerned, we only need to consider them.
         interface.next_poll_at_ms()
     }
+
+    /// Applies the change of the DHCP lease to the IPv4 address and the default route.
+    fn apply_dhcp_lease_change(&self, interface: &mut PollableIface<E>, change: DhcpLeaseChange) {
+        interface.set_ipv4_cidr(change.new.as_ref().map(|lease| lease.address));
+
+        let mut routes = self.routes.lock();
+        // The old default route is removed only if it has not been changed by others.
+        if let Some(old_route) = change.old.as_ref().and_then(DhcpLease::default_route) {
+            if routes.routes().contains(&old_route) {
+                routes.remove(old_route.dst, old_route.metric);
+            }
+        }
+        if let Some(new_route) = change.new.as_ref().and_then(DhcpLease::default_route) {
+            routes.insert(new_route);
+        }
+    }
 }
 
 /// A port bound to an iface.
 ///
 /// When dropped, the port is automatically released.
+///
+/// IPv4 and IPv6 ports are independent, so the same port number can be bound to both the IPv4
+/// address and the IPv6 address of an iface. The only exception is [`BindAddr::Ipv6Any`] without
+/// `v6only`, which occupies both the IPv4 port and the IPv6 port.
+///
+/// A port bound to [`BindAddr::Ipv6Any`] can carry the ports bound to the same address on other
+/// ifaces (see [`Self::attach_other_ports`]). Sockets created from such a port receive packets
+/// from all these ifaces, while their outgoing packets are sent via [`Self::iface`].
 //
 // FIXME: TCP and UDP ports are independent. Find a way to track the protocol here.
 pub struct BoundPort<E: Ext> {
     iface: Arc<dyn Iface<E>>,
+    addr: BindAddr,
     port: u16,
     can_reuse: AtomicBool,
+    other_ports: Vec<BoundPort<E>>,
 }
 
 impl<E: Ext> BoundPort<E> {
@@ -284,6 +626,36 @@ impl<E: Ext> BoundPort<E> {
         &self.iface
     }
 
+    /// Attaches the ports bound to the unspecified IPv6 address on other ifaces.
+    ///
+    /// All the ports must have the same address and the same port number as `self`.
+    pub fn attach_other_ports(&mut self, other_ports: Vec<BoundPort<E>>) {
+        debug_assert!(matches!(self.addr, BindAddr::Ipv6Any { .. }));
+        for other_port in other_ports.iter() {
+            debug_assert_eq!(other_port.addr, self.addr);
+            debug_assert_eq!(other_port.port, self.port);
+            debug_assert!(other_port.other_ports.is_empty());
+        }
+
+        self.other_ports.extend(other_ports);
+    }
+
+    /// Returns an iterator over this port and the attached ports on other ifaces.
+    pub(crate) fn all_ports(&self) -> impl Iterator<Item = &BoundPort<E>> {
+        core::iter::once(self).chain(self.other_ports.iter())
+    }
+
+    /// Returns the port on the iface whose common part is `common`, if any.
+    pub(crate) fn port_on(&self, common: &IfaceCommon<E>) -> Option<&BoundPort<E>> {
+        self.all_ports()
+            .find(|port| core::ptr::eq(port.iface.common(), common))
+    }
+
+    /// Returns the bound address.
+    pub fn addr(&self) -> BindAddr {
+        self.addr
+    }
+
     /// Returns the port number.
     pub fn port(&self) -> u16 {
         self.port
@@ -291,9 +663,47 @@ impl<E: Ext> BoundPort<E> {
 
     /// Returns the bound endpoint.
     pub fn endpoint(&self) -> Option<IpEndpoint> {
-        let ip_addr = {
-            let ipv4_addr = self.iface().ipv4_addr()?;
-            IpAddress::Ipv4(ipv4_addr)
+        let ip_addr = match self.addr {
+            BindAddr::Ipv4 => IpAddress::Ipv4(self.iface().ipv4_addr()?),
+            BindAddr::Ipv6 => IpAddress::Ipv6(self.iface().ipv6_addr()?),
+            BindAddr::Ipv6Any { .. } => IpAddress::Ipv6(Ipv6Address::UNSPECIFIED),
+        };
+        Some(IpEndpoint::new(ip_addr, self.port))
+    }
+
+    /// Returns the endpoint that accepts incoming packets.
+    ///
+    /// This differs from [`Self::endpoint`] only if the port is bound to the unspecified IPv6
+    /// address, in which case packets to any address are accepted. The caller should then check
+    /// the IP version with [`Self::accepts_version`].
+    pub(crate) fn listen_endpoint(&self) -> Option<IpListenEndpoint> {
+        let ip_addr = match self.addr {
+            BindAddr::Ipv6Any { .. } => None,
+            BindAddr::Ipv4 | BindAddr::Ipv6 => Some(self.endpoint()?.addr),
+        };
+        Some(IpListenEndpoint {
+            addr: ip_addr,
+            port: self.port,
+        })
+    }
+
+    /// Returns whether incoming packets of `ip_version` can be received via the port.
+    pub(crate) fn accepts_version(&self, ip_version: IpVersion) -> bool {
+        self.addr.port_versions().contains(&ip_version)
+    }
+
+    /// Returns the local endpoint to communicate with a remote endpoint of `ip_version`.
+    ///
+    /// This differs from [`Self::endpoint`] only if the port is bound to the unspecified IPv6
+    /// address, in which case the address of the iface is chosen.
+    pub(crate) fn endpoint_for(&self, ip_version: IpVersion) -> Option<IpEndpoint> {
+        if !self.accepts_version(ip_version) {
+            return None;
+        }
+
+        let ip_addr = match ip_version {
+            IpVersion::Ipv4 => IpAddress::Ipv4(self.iface().ipv4_addr()?),
+            IpVersion::Ipv6 => IpAddress::Ipv6(self.iface().ipv6_addr()?),
         };
         Some(IpEndpoint::new(ip_addr, self.port))
     }
@@ -307,15 +717,22 @@ impl<E: Ext> BoundPort<E> {
             return;
         }
 
-        if let Some(port_state) = used_ports.get_mut(&self.port) {
-            if can_reuse {
-                port_state.nreuse += 1;
-            } else {
-                port_state.nreuse -= 1;
+        for ip_version in self.addr.port_versions() {
+            if let Some(port_state) = used_ports.get_mut(&(*ip_version, self.port)) {
+                if can_reuse {
+                    port_state.nreuse += 1;
+                } else {
+                    port_state.nreuse -= 1;
+                }
             }
         }
 
         self.can_reuse.store(can_reuse, Ordering::Relaxed);
+        drop(used_ports);
+
+        for other_port in self.other_ports.iter() {
+            other_port.set_can_reuse(can_reuse);
+        }
     }
 }
 
@@ -323,7 +740,7 @@ impl<E: Ext> Drop for BoundPort<E> {
     fn drop(&mut self) {
         self.iface
             .common()
-            .release_port(self.port, *self.can_reuse.get_mut());
+            .release_port(self.addr, self.port, *self.can_reuse.get_mut());
     }
 }
 
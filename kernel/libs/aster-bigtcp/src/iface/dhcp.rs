@@ -0,0 +1,363 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The DHCPv4 client.
+//!
+//! Reference: <https://datatracker.ietf.org/doc/html/rfc2131>.
+
+use alloc::{vec, vec::Vec};
+
+use jhash::jhash_slice;
+use smoltcp::{
+    time::{Duration, Instant},
+    wire::{
+        DhcpMessageType, DhcpPacket, DhcpRepr, EthernetAddress, IpProtocol, Ipv4Address,
+        Ipv4AddressExt, Ipv4Cidr, Ipv4Repr, UdpRepr, DHCP_CLIENT_PORT, DHCP_SERVER_PORT,
+        UDP_HEADER_LEN,
+    },
+};
+
+use super::Ipv4Route;
+
+/// A lease obtained from a DHCP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhcpLease {
+    /// The leased IPv4 address and the prefix length of the subnet.
+    pub address: Ipv4Cidr,
+    /// The default gateway.
+    pub router: Option<Ipv4Address>,
+    /// The DNS servers.
+    pub dns_servers: Vec<Ipv4Address>,
+    /// The address of the DHCP server that grants the lease.
+    pub server: Ipv4Address,
+}
+
+impl DhcpLease {
+    /// Returns the default route through the router, if any.
+    pub(super) fn default_route(&self) -> Option<Ipv4Route> {
+        Some(Ipv4Route {
+            dst: Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0),
+            gateway: Some(self.router?),
+            metric: 0,
+        })
+    }
+}
+
+/// A change of the lease that has not been applied to the iface.
+pub(super) struct DhcpLeaseChange {
+    pub(super) old: Option<DhcpLease>,
+    pub(super) new: Option<DhcpLease>,
+}
+
+/// The options requested from the server, i.e., the subnet mask, the router, and the DNS servers.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc2132#section-3>.
+const PARAMETER_REQUEST_LIST: &[u8] = &[1, 3, 6];
+
+/// The time to wait before the first retransmission.
+///
+/// The time is doubled after each retransmission, up to 64 seconds, as RFC 2131 suggests.
+const INITIAL_RETRANSMIT_TIMEOUT: Duration = Duration::from_secs(4);
+const MAX_RETRANSMIT_SHIFT: u32 = 4;
+
+/// The number of DHCPREQUEST retransmissions before restarting with DHCPDISCOVER.
+const MAX_REQUEST_RETRIES: u32 = 4;
+
+/// The minimum time to wait before retransmitting a DHCPREQUEST in the RENEWING or REBINDING
+/// state.
+const MIN_RENEW_RETRANSMIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The lease time (in seconds) that is used if the server does not specify one.
+const DEFAULT_LEASE_TIME: u32 = 120;
+
+/// A DHCPv4 client that configures an Ethernet iface.
+pub(crate) struct DhcpClient {
+    ether_addr: EthernetAddress,
+    transaction_id: u32,
+    state: ClientState,
+    /// The time to send the next message.
+    ///
+    /// In the BOUND state, this is initially the renewal time.
+    send_at: Instant,
+    /// The number of retransmissions of the current message.
+    retries: u32,
+    /// The lease that has been applied to the iface.
+    applied_lease: Option<DhcpLease>,
+}
+
+enum ClientState {
+    /// Broadcasting DHCPDISCOVER and waiting for DHCPOFFER.
+    Selecting,
+    /// Broadcasting DHCPREQUEST for an offered address and waiting for DHCPACK.
+    Requesting {
+        server: Ipv4Address,
+        requested_ip: Ipv4Address,
+    },
+    /// Holding a lease.
+    ///
+    /// After the renewal time, the lease is renewed by unicasting DHCPREQUEST to the server (the
+    /// RENEWING state). After `rebind_at`, DHCPREQUEST is broadcast instead (the REBINDING state).
+    Bound {
+        lease: DhcpLease,
+        rebind_at: Instant,
+        expires_at: Instant,
+    },
+}
+
+impl DhcpClient {
+    pub(super) fn new(ether_addr: EthernetAddress, now: Instant) -> Self {
+        Self {
+            ether_addr,
+            transaction_id: new_transaction_id(ether_addr, now),
+            state: ClientState::Selecting,
+            send_at: now,
+            retries: 0,
+            applied_lease: None,
+        }
+    }
+
+    /// Returns the current lease.
+    pub(super) fn lease(&self) -> Option<&DhcpLease> {
+        match &self.state {
+            ClientState::Bound { lease, .. } => Some(lease),
+            ClientState::Selecting | ClientState::Requesting { .. } => None,
+        }
+    }
+
+    /// Takes the change of the lease since the last call, if any.
+    pub(super) fn take_lease_change(&mut self) -> Option<DhcpLeaseChange> {
+        let new = self.lease();
+        if new == self.applied_lease.as_ref() {
+            return None;
+        }
+
+        let new = new.cloned();
+        let old = core::mem::replace(&mut self.applied_lease, new.clone());
+        Some(DhcpLeaseChange { old, new })
+    }
+
+    /// Returns the time when the client needs to be polled.
+    pub(super) fn poll_at(&self) -> Instant {
+        match &self.state {
+            ClientState::Bound { expires_at, .. } => self.send_at.min(*expires_at),
+            ClientState::Selecting | ClientState::Requesting { .. } => self.send_at,
+        }
+    }
+
+    /// Processes an incoming DHCP message.
+    pub(super) fn process(&mut self, data: &[u8], now: Instant) {
+        let Ok(packet) = DhcpPacket::new_checked(data) else {
+            return;
+        };
+        let Ok(repr) = DhcpRepr::parse(&packet) else {
+            return;
+        };
+        if repr.transaction_id != self.transaction_id
+            || repr.client_hardware_address != self.ether_addr
+        {
+            return;
+        }
+
+        match (&self.state, repr.message_type) {
+            (ClientState::Selecting, DhcpMessageType::Offer) => {
+                // TODO: Collect offers from multiple servers and choose the best one. For now, the
+                // first offer is accepted.
+                let Some(server) = repr.server_identifier else {
+                    return;
+                };
+                if !repr.your_ip.x_is_unicast() {
+                    return;
+                }
+                self.state = ClientState::Requesting {
+                    server,
+                    requested_ip: repr.your_ip,
+                };
+                self.send_at = now;
+                self.retries = 0;
+            }
+            (ClientState::Requesting { server, .. }, DhcpMessageType::Ack)
+                if repr.server_identifier == Some(*server) =>
+            {
+                self.bind(&repr, now);
+            }
+            (ClientState::Bound { .. }, DhcpMessageType::Ack) => self.bind(&repr, now),
+            (ClientState::Requesting { server, .. }, DhcpMessageType::Nak)
+                if repr.server_identifier == Some(*server) =>
+            {
+                self.restart(now);
+            }
+            (ClientState::Bound { .. }, DhcpMessageType::Nak) => self.restart(now),
+            _ => (),
+        }
+    }
+
+    fn bind(&mut self, repr: &DhcpRepr, now: Instant) {
+        let Some(lease) = parse_lease(repr) else {
+            return;
+        };
+
+        // If the server does not specify the renewal (T1) and rebinding (T2) times, they default
+        // to 0.5 and 0.875 times the lease time, respectively.
+        let lease_time =
+            Duration::from_secs(repr.lease_duration.unwrap_or(DEFAULT_LEASE_TIME).into());
+        let renew_time = repr
+            .renew_duration
+            .map(|secs| Duration::from_secs(secs.into()))
+            .unwrap_or(lease_time / 2);
+        let rebind_time = repr
+            .rebind_duration
+            .map(|secs| Duration::from_secs(secs.into()))
+            .unwrap_or(lease_time * 7 / 8);
+
+        self.state = ClientState::Bound {
+            lease,
+            rebind_at: now + rebind_time,
+            expires_at: now + lease_time,
+        };
+        self.send_at = now + renew_time;
+        self.retries = 0;
+    }
+
+    /// Drops the current lease (if any) and restarts from the SELECTING state.
+    fn restart(&mut self, now: Instant) {
+        self.state = ClientState::Selecting;
+        self.transaction_id = new_transaction_id(self.ether_addr, now);
+        self.send_at = now;
+        self.retries = 0;
+    }
+
+    /// Returns the next message to be sent, if it is time to send one.
+    pub(super) fn dispatch(&mut self, now: Instant) -> Option<(Ipv4Repr, UdpRepr, Vec<u8>)> {
+        let need_restart = match &self.state {
+            ClientState::Selecting => false,
+            ClientState::Requesting { .. } => self.retries > MAX_REQUEST_RETRIES,
+            ClientState::Bound { expires_at, .. } => now >= *expires_at,
+        };
+        if need_restart {
+            self.restart(now);
+        }
+
+        if now < self.send_at {
+            return None;
+        }
+
+        let mut repr = DhcpRepr {
+            message_type: DhcpMessageType::Discover,
+            transaction_id: self.transaction_id,
+            secs: 0,
+            client_hardware_address: self.ether_addr,
+            client_ip: Ipv4Address::UNSPECIFIED,
+            your_ip: Ipv4Address::UNSPECIFIED,
+            server_ip: Ipv4Address::UNSPECIFIED,
+            router: None,
+            subnet_mask: None,
+            relay_agent_ip: Ipv4Address::UNSPECIFIED,
+            // The iface cannot receive unicast packets before it is configured, so the replies
+            // must be broadcast.
+            broadcast: true,
+            requested_ip: None,
+            client_identifier: Some(self.ether_addr),
+            server_identifier: None,
+            parameter_request_list: Some(PARAMETER_REQUEST_LIST),
+            dns_servers: None,
+            max_size: None,
+            lease_duration: None,
+            renew_duration: None,
+            rebind_duration: None,
+            additional_options: &[],
+        };
+
+        let (src_addr, dst_addr) = match &self.state {
+            ClientState::Selecting => {
+                self.send_at = now + retransmit_timeout(self.retries);
+                (Ipv4Address::UNSPECIFIED, Ipv4Address::BROADCAST)
+            }
+            ClientState::Requesting {
+                server,
+                requested_ip,
+            } => {
+                repr.message_type = DhcpMessageType::Request;
+                repr.requested_ip = Some(*requested_ip);
+                repr.server_identifier = Some(*server);
+                self.send_at = now + retransmit_timeout(self.retries);
+                (Ipv4Address::UNSPECIFIED, Ipv4Address::BROADCAST)
+            }
+            ClientState::Bound {
+                lease,
+                rebind_at,
+                expires_at,
+            } => {
+                repr.message_type = DhcpMessageType::Request;
+                repr.client_ip = lease.address.address();
+                repr.broadcast = false;
+
+                let (dst_addr, deadline) = if now < *rebind_at {
+                    (lease.server, *rebind_at)
+                } else {
+                    (Ipv4Address::BROADCAST, *expires_at)
+                };
+                // RFC 2131 suggests waiting for one-half of the remaining time until the deadline,
+                // down to a minimum of 60 seconds.
+                self.send_at = now + ((deadline - now) / 2).max(MIN_RENEW_RETRANSMIT_TIMEOUT);
+                (lease.address.address(), dst_addr)
+            }
+        };
+        self.retries = self.retries.saturating_add(1);
+
+        let mut payload = vec![0; repr.buffer_len()];
+        repr.emit(&mut DhcpPacket::new_unchecked(payload.as_mut_slice()))
+            .ok()?;
+
+        let udp_repr = UdpRepr {
+            src_port: DHCP_CLIENT_PORT,
+            dst_port: DHCP_SERVER_PORT,
+        };
+        let ip_repr = Ipv4Repr {
+            src_addr,
+            dst_addr,
+            next_header: IpProtocol::Udp,
+            payload_len: UDP_HEADER_LEN + payload.len(),
+            hop_limit: 64,
+        };
+
+        Some((ip_repr, udp_repr, payload))
+    }
+}
+
+/// Extracts the lease from a DHCPACK message.
+fn parse_lease(repr: &DhcpRepr) -> Option<DhcpLease> {
+    let server = repr.server_identifier?;
+    if !repr.your_ip.x_is_unicast() {
+        return None;
+    }
+
+    // TODO: Derive the prefix length from the address class if the server does not provide the
+    // subnet mask.
+    let address = Ipv4Cidr::from_netmask(repr.your_ip, repr.subnet_mask?).ok()?;
+    let router = repr.router.filter(|router| router.x_is_unicast());
+    let dns_servers = repr
+        .dns_servers
+        .iter()
+        .flatten()
+        .filter(|dns_server| dns_server.x_is_unicast())
+        .copied()
+        .collect();
+
+    Some(DhcpLease {
+        address,
+        router,
+        dns_servers,
+        server,
+    })
+}
+
+fn retransmit_timeout(retries: u32) -> Duration {
+    INITIAL_RETRANSMIT_TIMEOUT << retries.min(MAX_RETRANSMIT_SHIFT)
+}
+
+/// Generates a transaction ID.
+///
+/// The ID should be random so that the replies to different clients can be distinguished. Since
+/// there is no random source here, it is derived from the Ethernet address and the current time.
+fn new_transaction_id(ether_addr: EthernetAddress, now: Instant) -> u32 {
+    jhash_slice(&ether_addr.0, now.total_millis() as u32)
+}
@@ -0,0 +1,329 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Packet filtering.
+//!
+//! A [`FilterTable`] contains a list of rules for each [`FilterChain`]. When a packet passes a
+//! hook point, the rules in the corresponding chain are checked in order, and the action of the
+//! first matching rule decides the fate of the packet. If no rule matches, the packet is accepted.
+//!
+//! This is a simplified version of the `filter` table of Linux's netfilter. Only IPv4 packets are
+//! filtered for now.
+//!
+//! TODO: Packets that are delivered to a local address without going through the device skip the
+//! output chain, and TCP and UDP ones skip the input chain as well. Linux filters them as if they
+//! were sent and received by the loopback iface.
+
+use alloc::{string::String, vec::Vec};
+use core::ops::RangeInclusive;
+
+use aster_softirq::BottomHalfDisabled;
+use ostd::sync::SpinLock;
+use smoltcp::{
+    iface::packet::{IpPayload, Packet},
+    wire::{IpProtocol, IpRepr, Ipv4Address, Ipv4Cidr, Ipv4Packet},
+};
+
+/// A chain of filtering rules, which corresponds to a hook point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterChain {
+    /// The chain for incoming packets destined for the local host.
+    Input,
+    /// The chain for incoming packets destined for other hosts.
+    ///
+    /// Note that forwarding is not supported yet. Packets destined for other hosts are answered
+    /// with ICMP unreachable messages unless they are dropped by this chain.
+    Forward,
+    /// The chain for outgoing packets generated by the local host.
+    Output,
+}
+
+impl FilterChain {
+    const ALL: [Self; 3] = [Self::Input, Self::Forward, Self::Output];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The action to take on packets that match a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterAction {
+    /// Lets the packet pass.
+    Accept,
+    /// Discards the packet silently.
+    Drop,
+    /// Discards the packet and notifies the sender.
+    ///
+    /// For incoming packets, an ICMP port unreachable message is sent back, as Linux's `REJECT`
+    /// target does by default. Outgoing packets are discarded silently because the local sockets
+    /// cannot handle the ICMP messages yet.
+    Reject,
+}
+
+/// A filtering rule.
+///
+/// A field that is `None` matches any packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterRule {
+    /// The transport layer protocol.
+    pub protocol: Option<IpProtocol>,
+    /// The source subnet.
+    pub src: Option<Ipv4Cidr>,
+    /// The destination subnet.
+    pub dst: Option<Ipv4Cidr>,
+    /// The range of the source ports.
+    ///
+    /// Only TCP and UDP packets have ports, so packets of other protocols never match the rule if
+    /// this is not `None`.
+    pub src_ports: Option<RangeInclusive<u16>>,
+    /// The range of the destination ports.
+    ///
+    /// See [`Self::src_ports`] for packets without ports.
+    pub dst_ports: Option<RangeInclusive<u16>>,
+    /// The name of the iface.
+    ///
+    /// This is the iface that receives the packet in [`FilterChain::Input`] and
+    /// [`FilterChain::Forward`], or the iface that sends the packet in [`FilterChain::Output`].
+    pub iface: Option<String>,
+    /// The action to take on matching packets.
+    pub action: FilterAction,
+}
+
+/// The counters of a filtering rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterCounters {
+    /// The number of packets that have matched the rule.
+    pub packets: u64,
+    /// The number of bytes (including the IP headers) of the packets that have matched the rule.
+    pub bytes: u64,
+}
+
+/// A table of filtering rules.
+///
+/// A table can be shared by multiple ifaces (e.g., all ifaces in a network namespace) via
+/// [`Iface::set_filter_table`].
+///
+/// [`Iface::set_filter_table`]: super::Iface::set_filter_table
+pub struct FilterTable {
+    chains: SpinLock<[Vec<FilterEntry>; 3], BottomHalfDisabled>,
+}
+
+struct FilterEntry {
+    rule: FilterRule,
+    counters: FilterCounters,
+}
+
+impl Default for FilterTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FilterTable {
+    /// Creates an empty table.
+    pub const fn new() -> Self {
+        Self {
+            chains: SpinLock::new([Vec::new(), Vec::new(), Vec::new()]),
+        }
+    }
+
+    /// Appends a rule to the end of the chain.
+    pub fn append(&self, chain: FilterChain, rule: FilterRule) {
+        self.chains.lock()[chain.index()].push(FilterEntry {
+            rule,
+            counters: FilterCounters::default(),
+        });
+    }
+
+    /// Inserts a rule at `index` in the chain.
+    ///
+    /// This method returns `false` if `index` is greater than the number of rules in the chain.
+    pub fn insert(&self, chain: FilterChain, index: usize, rule: FilterRule) -> bool {
+        let mut chains = self.chains.lock();
+        let entries = &mut chains[chain.index()];
+        if index > entries.len() {
+            return false;
+        }
+
+        entries.insert(
+            index,
+            FilterEntry {
+                rule,
+                counters: FilterCounters::default(),
+            },
+        );
+        true
+    }
+
+    /// Removes the rule at `index` in the chain.
+    pub fn remove(&self, chain: FilterChain, index: usize) -> Option<FilterRule> {
+        let mut chains = self.chains.lock();
+        let entries = &mut chains[chain.index()];
+        if index >= entries.len() {
+            return None;
+        }
+
+        Some(entries.remove(index).rule)
+    }
+
+    /// Removes all rules in the chain.
+    pub fn flush(&self, chain: FilterChain) {
+        self.chains.lock()[chain.index()].clear();
+    }
+
+    /// Resets the counters of all rules in the chain.
+    pub fn zero_counters(&self, chain: FilterChain) {
+        for entry in self.chains.lock()[chain.index()].iter_mut() {
+            entry.counters = FilterCounters::default();
+        }
+    }
+
+    /// Returns the rules in the chain, together with their counters.
+    pub fn rules(&self, chain: FilterChain) -> Vec<(FilterRule, FilterCounters)> {
+        self.chains.lock()[chain.index()]
+            .iter()
+            .map(|entry| (entry.rule.clone(), entry.counters))
+            .collect()
+    }
+
+    /// Returns whether all chains are empty.
+    fn is_empty(&self) -> bool {
+        let chains = self.chains.lock();
+        FilterChain::ALL
+            .iter()
+            .all(|chain| chains[chain.index()].is_empty())
+    }
+
+    /// Checks the packet against the rules in the chain and returns the action to take.
+    ///
+    /// The counters of the matching rule are updated.
+    fn check(&self, chain: FilterChain, packet: &PacketInfo) -> FilterAction {
+        let mut chains = self.chains.lock();
+
+        let Some(entry) = chains[chain.index()]
+            .iter_mut()
+            .find(|entry| packet.matches(&entry.rule))
+        else {
+            return FilterAction::Accept;
+        };
+
+        entry.counters.packets += 1;
+        entry.counters.bytes += packet.len as u64;
+        entry.rule.action
+    }
+}
+
+/// The information of a packet that the rules can match.
+struct PacketInfo<'a> {
+    iface: &'a str,
+    protocol: IpProtocol,
+    src_addr: Ipv4Address,
+    dst_addr: Ipv4Address,
+    /// The source port and the destination port.
+    ports: Option<(u16, u16)>,
+    len: usize,
+}
+
+impl PacketInfo<'_> {
+    fn matches(&self, rule: &FilterRule) -> bool {
+        let port_matches = |range: &Option<RangeInclusive<u16>>, port: fn((u16, u16)) -> u16| {
+            let Some(range) = range else {
+                return true;
+            };
+            self.ports.is_some_and(|ports| range.contains(&port(ports)))
+        };
+
+        rule.protocol
+            .is_none_or(|protocol| protocol == self.protocol)
+            && rule.src.is_none_or(|src| src.contains_addr(&self.src_addr))
+            && rule.dst.is_none_or(|dst| dst.contains_addr(&self.dst_addr))
+            && port_matches(&rule.src_ports, |(src_port, _)| src_port)
+            && port_matches(&rule.dst_ports, |(_, dst_port)| dst_port)
+            && rule.iface.as_ref().is_none_or(|iface| iface == self.iface)
+    }
+}
+
+/// Parses the ports from the beginning of a TCP or UDP header.
+fn parse_ports(protocol: IpProtocol, payload: &[u8]) -> Option<(u16, u16)> {
+    if !matches!(protocol, IpProtocol::Tcp | IpProtocol::Udp) || payload.len() < 4 {
+        return None;
+    }
+
+    let src_port = u16::from_be_bytes([payload[0], payload[1]]);
+    let dst_port = u16::from_be_bytes([payload[2], payload[3]]);
+    Some((src_port, dst_port))
+}
+
+/// The hook points where the rules in a [`FilterTable`] are checked.
+#[derive(Clone, Copy)]
+pub(super) struct FilterHook<'a> {
+    table: Option<&'a FilterTable>,
+    iface: &'a str,
+}
+
+impl<'a> FilterHook<'a> {
+    /// Creates the hook points for the iface named `iface`.
+    ///
+    /// If `table` is `None` or empty, all packets will be accepted.
+    pub(super) fn new(table: Option<&'a FilterTable>, iface: &'a str) -> Self {
+        Self {
+            table: table.filter(|table| !table.is_empty()),
+            iface,
+        }
+    }
+
+    /// Checks an incoming IPv4 packet against the rules in the chain.
+    pub(super) fn check_ipv4(&self, chain: FilterChain, pkt: &Ipv4Packet<&[u8]>) -> FilterAction {
+        let Some(table) = self.table else {
+            return FilterAction::Accept;
+        };
+
+        let protocol = pkt.next_header();
+        // Only the first fragment contains the transport layer header.
+        let ports = if pkt.frag_offset() == 0 {
+            parse_ports(protocol, pkt.payload())
+        } else {
+            None
+        };
+
+        let info = PacketInfo {
+            iface: self.iface,
+            protocol,
+            src_addr: pkt.src_addr(),
+            dst_addr: pkt.dst_addr(),
+            ports,
+            len: usize::from(pkt.total_len()),
+        };
+        table.check(chain, &info)
+    }
+
+    /// Checks an outgoing packet against the rules in [`FilterChain::Output`] and returns whether
+    /// the packet can be sent.
+    pub(super) fn accepts_outgoing(&self, packet: &Packet) -> bool {
+        let Some(table) = self.table else {
+            return true;
+        };
+
+        // TODO: Support filtering IPv6 packets.
+        let IpRepr::Ipv4(ip_repr) = packet.ip_repr() else {
+            return true;
+        };
+
+        let ports = match packet.payload() {
+            IpPayload::Tcp(tcp_repr) => Some((tcp_repr.src_port, tcp_repr.dst_port)),
+            IpPayload::Udp(udp_repr, _) => Some((udp_repr.src_port, udp_repr.dst_port)),
+            IpPayload::Raw(payload) => parse_ports(ip_repr.next_header, payload),
+            _ => None,
+        };
+
+        let info = PacketInfo {
+            iface: self.iface,
+            protocol: ip_repr.next_header,
+            src_addr: ip_repr.src_addr,
+            dst_addr: ip_repr.dst_addr,
+            ports,
+            len: ip_repr.buffer_len() + ip_repr.payload_len,
+        };
+        table.check(FilterChain::Output, &info) == FilterAction::Accept
+    }
+}
@@ -1,11 +1,14 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::sync::Arc;
+use alloc::{sync::Arc, vec::Vec};
 
-use smoltcp::wire::Ipv4Address;
+use smoltcp::wire::{HardwareAddress, Ipv4Address, Ipv4Cidr, Ipv6Address};
 
-use super::{port::BindPortConfig, BoundPort, InterfaceFlags, InterfaceType};
-use crate::{errors::BindError, ext::Ext};
+use super::{
+    port::{BindAddr, BindPortConfig},
+    BoundPort, DhcpLease, FilterTable, InterfaceFlags, InterfaceType, Ipv4Route, Neighbor,
+};
+use crate::{errors::BindError, ext::Ext, socket::NeedIfacePoll};
 
 /// A network interface.
 ///
@@ -16,13 +19,10 @@ use crate::{errors::BindError, ext::Ext};
 pub trait Iface<E>: internal::IfaceInternal<E> + Send + Sync {
     /// Transmits or receives packets queued in the iface, and updates socket status accordingly.
     fn poll(&self);
-
-    /// Returns the maximum transmission unit.
-    fn mtu(&self) -> usize;
 }
 
 impl<E: Ext> dyn Iface<E> {
-    /// Binds a socket to the iface.
+    /// Binds a socket to the address (specified by `addr`) of the iface.
     ///
     /// After binding the socket to the iface, the iface will handle all packets to and from the
     /// socket.
@@ -35,10 +35,11 @@ impl<E: Ext> dyn Iface<E> {
     /// <https://github.com/smoltcp-rs/smoltcp/issues/779>.
     pub fn bind(
         self: &Arc<Self>,
+        addr: BindAddr,
         config: BindPortConfig,
     ) -> core::result::Result<BoundPort<E>, BindError> {
         let common = self.common();
-        common.bind(self.clone(), config)
+        common.bind(self.clone(), addr, config)
     }
 
     /// Returns the interface index.
@@ -63,6 +64,51 @@ impl<E: Ext> dyn Iface<E> {
         self.common().flags()
     }
 
+    /// Sets the interface flags.
+    ///
+    /// The [`InterfaceFlags::PROMISC`] flag is ignored because it is controlled by
+    /// [`Self::inc_promiscuity`] and [`Self::dec_promiscuity`].
+    pub fn set_flags(&self, flags: InterfaceFlags) {
+        self.common().set_flags(flags);
+    }
+
+    /// Returns the maximum transmission unit.
+    pub fn mtu(&self) -> usize {
+        self.common().mtu()
+    }
+
+    /// Returns the largest maximum transmission unit supported by the device.
+    pub fn max_mtu(&self) -> usize {
+        self.common().max_mtu()
+    }
+
+    /// Sets the maximum transmission unit.
+    ///
+    /// The caller must ensure that `mtu` does not exceed [`Self::max_mtu`].
+    pub fn set_mtu(&self, mtu: usize) {
+        self.common().set_mtu(mtu);
+    }
+
+    /// Returns the hardware address of the iface.
+    pub fn hardware_addr(&self) -> HardwareAddress {
+        self.common().hardware_addr()
+    }
+
+    /// Increments the promiscuity counter of the iface.
+    ///
+    /// The iface is in the promiscuous mode if the counter is not zero. In this mode, packet
+    /// sockets can receive frames that are destined for other hosts.
+    pub fn inc_promiscuity(&self) {
+        self.common().inc_promiscuity();
+    }
+
+    /// Decrements the promiscuity counter of the iface.
+    ///
+    /// This must be paired with a previous call to [`Self::inc_promiscuity`].
+    pub fn dec_promiscuity(&self) {
+        self.common().dec_promiscuity();
+    }
+
     /// Gets the IPv4 address of the iface, if any.
     ///
     /// FIXME: One iface may have multiple IPv4 addresses.
@@ -78,6 +124,119 @@ impl<E: Ext> dyn Iface<E> {
         self.common().prefix_len()
     }
 
+    /// Gets the gateway of the default IPv4 route of the iface, if any.
+    ///
+    /// If there are multiple default routes, the one with the lowest metric is used.
+    pub fn ipv4_gateway(&self) -> Option<Ipv4Address> {
+        self.common().ipv4_gateway()
+    }
+
+    /// Returns the IPv4 routes through the iface.
+    ///
+    /// The routes to the subnet of the iface's own IPv4 address are implied and are not included.
+    pub fn ipv4_routes(&self) -> Vec<Ipv4Route> {
+        self.common().ipv4_routes()
+    }
+
+    /// Adds an IPv4 route through the iface.
+    ///
+    /// If there is already a route with the same key (see [`Ipv4Route::same_key`]), it will be
+    /// replaced and returned.
+    pub fn add_ipv4_route(&self, route: Ipv4Route) -> Option<Ipv4Route> {
+        self.common().add_ipv4_route(route)
+    }
+
+    /// Removes the IPv4 route to `dst` with the metric `metric`.
+    pub fn remove_ipv4_route(&self, dst: Ipv4Cidr, metric: u32) -> Option<Ipv4Route> {
+        self.common().remove_ipv4_route(dst, metric)
+    }
+
+    /// Looks up the best IPv4 route to `dst_addr` through the iface.
+    ///
+    /// Like [`Self::ipv4_routes`], the routes to the subnet of the iface's own IPv4 address are not
+    /// considered.
+    pub fn lookup_ipv4_route(&self, dst_addr: &Ipv4Address) -> Option<Ipv4Route> {
+        self.common().lookup_ipv4_route(dst_addr)
+    }
+
+    /// Returns the neighbors whose link-layer addresses have been resolved.
+    pub fn neighbors(&self) -> Vec<Neighbor> {
+        self.common().neighbors()
+    }
+
+    /// Sets the IPv4 address and the prefix length of the iface.
+    ///
+    /// The old IPv4 address, if any, is replaced. If `ipv4_cidr` is `None`, the iface will have
+    /// no IPv4 address.
+    ///
+    /// Sockets that are already bound to the old address are not affected.
+    pub fn set_ipv4_cidr(&self, ipv4_cidr: Option<Ipv4Cidr>) {
+        self.common().set_ipv4_cidr(ipv4_cidr);
+    }
+
+    /// Gets the IPv6 address of the iface, if any.
+    ///
+    /// FIXME: One iface may have multiple IPv6 addresses.
+    pub fn ipv6_addr(&self) -> Option<Ipv6Address> {
+        self.common().ipv6_addr()
+    }
+
+    /// Retrieves the prefix length of the interface's IPv6 address.
+    ///
+    /// Both [`Self::ipv6_addr`] and this method will either return `Some(_)`
+    /// or both will return `None`.
+    pub fn ipv6_prefix_len(&self) -> Option<u8> {
+        self.common().ipv6_prefix_len()
+    }
+
+    /// Joins the IPv4 multicast group on the iface.
+    ///
+    /// The iface counts how many times each group is joined, and leaves the group only after
+    /// [`Self::leave_multicast_group`] is called the same number of times. While the group is
+    /// joined, the iface receives packets destined for the group.
+    ///
+    /// When the group is joined for the first time, an IGMP report will be sent in the next poll,
+    /// so polling the iface is required if this method returns [`NeedIfacePoll::TRUE`].
+    pub fn join_multicast_group(&self, group: Ipv4Address) -> NeedIfacePoll {
+        self.common().join_multicast_group(group)
+    }
+
+    /// Leaves the IPv4 multicast group on the iface.
+    ///
+    /// This must be paired with a previous call to [`Self::join_multicast_group`].
+    ///
+    /// When the group is left for the last time, an IGMP leave message will be sent in the next
+    /// poll, so polling the iface is required if this method returns [`NeedIfacePoll::TRUE`].
+    pub fn leave_multicast_group(&self, group: Ipv4Address) -> NeedIfacePoll {
+        self.common().leave_multicast_group(group)
+    }
+
+    /// Starts configuring the iface via DHCP.
+    ///
+    /// Once a lease is obtained, the leased IPv4 address and the default route through the router
+    /// will be set on the iface. They will be removed if the lease expires. Any running DHCP client
+    /// on the iface will be restarted.
+    ///
+    /// DHCP is supported only on Ethernet ifaces, so this method does nothing on other ifaces.
+    /// Otherwise, the DHCP messages will be sent in the next poll, so polling the iface is required
+    /// if this method returns [`NeedIfacePoll::TRUE`].
+    pub fn start_dhcp(&self) -> NeedIfacePoll {
+        self.common().start_dhcp()
+    }
+
+    /// Returns the lease obtained via DHCP, if any.
+    pub fn dhcp_lease(&self) -> Option<DhcpLease> {
+        self.common().dhcp_lease()
+    }
+
+    /// Sets the table of the rules that filter the packets passing through the iface.
+    ///
+    /// The new table takes effect from the next poll. If `filter_table` is `None`, no packets will
+    /// be filtered.
+    pub fn set_filter_table(&self, filter_table: Option<Arc<FilterTable>>) {
+        self.common().set_filter_table(filter_table);
+    }
+
     /// Returns a reference to the associated [`ScheduleNextPoll`].
     pub fn sched_poll(&self) -> &E::ScheduleNextPoll {
         self.common().sched_poll()
@@ -1,18 +1,28 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod common;
+mod dhcp;
+mod filter;
 #[expect(clippy::module_inception)]
 mod iface;
+mod multicast;
+mod neighbor;
 mod phy;
 mod poll;
 mod poll_iface;
 mod port;
+mod route;
 mod sched;
 mod time;
 
+pub(crate) use common::IfaceCommon;
 pub use common::{BoundPort, InterfaceFlags, InterfaceType};
+pub use dhcp::DhcpLease;
+pub use filter::{FilterAction, FilterChain, FilterCounters, FilterRule, FilterTable};
 pub use iface::Iface;
+pub use neighbor::{Neighbor, NeighborState};
 pub use phy::{EtherIface, IpIface};
 pub(crate) use poll_iface::{PollKey, PollableIfaceMut};
-pub use port::BindPortConfig;
+pub use port::{BindAddr, BindPortConfig};
+pub use route::Ipv4Route;
 pub use sched::ScheduleNextPoll;
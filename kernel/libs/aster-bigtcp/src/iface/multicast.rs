@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! IPv4 multicast group memberships and IGMPv2.
+//!
+//! Reference: <https://datatracker.ietf.org/doc/html/rfc2236>.
+
+use alloc::collections::{btree_map::BTreeMap, vec_deque::VecDeque};
+
+use smoltcp::wire::{IpProtocol, Ipv4Address, Ipv4Repr};
+
+/// The all-hosts group.
+///
+/// All multicast-capable hosts are members of this group, and no IGMP messages are sent for it.
+const ALL_HOSTS_GROUP: Ipv4Address = Ipv4Address::new(224, 0, 0, 1);
+/// The all-routers group, to which IGMP Leave Group messages are sent.
+const ALL_ROUTERS_GROUP: Ipv4Address = Ipv4Address::new(224, 0, 0, 2);
+
+/// The length of an IGMPv2 message.
+const IGMP_MSG_LEN: usize = 8;
+
+const IGMP_MEMBERSHIP_QUERY: u8 = 0x11;
+const IGMPV2_MEMBERSHIP_REPORT: u8 = 0x16;
+const IGMP_LEAVE_GROUP: u8 = 0x17;
+
+/// The multicast groups that an iface has joined.
+pub(crate) struct MulticastGroups {
+    /// The joined groups and how many times each of them has been joined.
+    groups: BTreeMap<Ipv4Address, usize>,
+    /// The IGMP messages that have not been sent.
+    pending_msgs: VecDeque<IgmpMessage>,
+    /// Whether the device delivers transmitted packets back to the iface.
+    ///
+    /// This is true for the loopback device. In this case, outgoing multicast packets must not be
+    /// looped back again by software.
+    is_loopback: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IgmpMessage {
+    Report(Ipv4Address),
+    Leave(Ipv4Address),
+}
+
+impl MulticastGroups {
+    pub(super) fn new(is_loopback: bool) -> Self {
+        Self {
+            groups: BTreeMap::new(),
+            pending_msgs: VecDeque::new(),
+            is_loopback,
+        }
+    }
+
+    /// Joins the group and returns whether an IGMP message is pending to be sent.
+    pub(super) fn join(&mut self, group: Ipv4Address) -> bool {
+        debug_assert!(group.is_multicast());
+
+        let count = self.groups.entry(group).or_insert(0);
+        *count += 1;
+
+        if *count > 1 || group == ALL_HOSTS_GROUP {
+            return false;
+        }
+
+        self.pending_msgs.push_back(IgmpMessage::Report(group));
+        true
+    }
+
+    /// Leaves the group and returns whether an IGMP message is pending to be sent.
+    ///
+    /// This must be paired with a previous call to [`Self::join`].
+    pub(super) fn leave(&mut self, group: Ipv4Address) -> bool {
+        let count = self.groups.get_mut(&group);
+        debug_assert!(count.is_some());
+        let Some(count) = count else {
+            return false;
+        };
+
+        *count -= 1;
+        if *count > 0 {
+            return false;
+        }
+        self.groups.remove(&group);
+
+        if group == ALL_HOSTS_GROUP {
+            return false;
+        }
+
+        // A report that has not been sent is no longer meaningful.
+        self.pending_msgs
+            .retain(|msg| *msg != IgmpMessage::Report(group));
+        self.pending_msgs.push_back(IgmpMessage::Leave(group));
+        true
+    }
+
+    /// Returns whether packets destined for the group should be received.
+    pub(super) fn has_joined(&self, group: Ipv4Address) -> bool {
+        group == ALL_HOSTS_GROUP || self.groups.contains_key(&group)
+    }
+
+    /// Returns whether outgoing multicast packets should be looped back by software.
+    pub(super) fn need_software_loop(&self) -> bool {
+        !self.is_loopback
+    }
+
+    /// Processes an incoming IGMP message.
+    ///
+    /// If the message is a membership query, reports for the queried groups will be pending to be
+    /// sent.
+    //
+    // TODO: Delay the reports by a random time up to the maximum response time, and suppress them
+    // if other hosts have reported the same groups, as RFC 2236 suggests.
+    pub(super) fn process_igmp(&mut self, data: &[u8]) {
+        // IGMPv3 queries are longer, but they start with the same fields as IGMPv2 queries.
+        if data.len() < IGMP_MSG_LEN || checksum(data) != 0 {
+            return;
+        }
+        if data[0] != IGMP_MEMBERSHIP_QUERY {
+            return;
+        }
+
+        let group = Ipv4Address::new(data[4], data[5], data[6], data[7]);
+        let queried_groups = self
+            .groups
+            .keys()
+            .filter(|joined| group.is_unspecified() || **joined == group)
+            .filter(|joined| **joined != ALL_HOSTS_GROUP);
+
+        for joined in queried_groups {
+            let report = IgmpMessage::Report(*joined);
+            if !self.pending_msgs.contains(&report) {
+                self.pending_msgs.push_back(report);
+            }
+        }
+    }
+
+    /// Takes the next IGMP message to be sent and returns its IP header and payload.
+    pub(super) fn pop_igmp(
+        &mut self,
+        src_addr: Ipv4Address,
+    ) -> Option<(Ipv4Repr, [u8; IGMP_MSG_LEN])> {
+        let (msg_type, group, dst_addr) = match self.pending_msgs.pop_front()? {
+            IgmpMessage::Report(group) => (IGMPV2_MEMBERSHIP_REPORT, group, group),
+            IgmpMessage::Leave(group) => (IGMP_LEAVE_GROUP, group, ALL_ROUTERS_GROUP),
+        };
+
+        let mut data = [0u8; IGMP_MSG_LEN];
+        data[0] = msg_type;
+        data[4..8].copy_from_slice(&group.octets());
+        let checksum = checksum(&data);
+        data[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+        let ip_repr = Ipv4Repr {
+            src_addr,
+            dst_addr,
+            next_header: IpProtocol::Igmp,
+            payload_len: IGMP_MSG_LEN,
+            // IGMP messages are never forwarded by routers.
+            hop_limit: 1,
+        };
+
+        Some((ip_repr, data))
+    }
+}
+
+/// Computes the Internet checksum of the data.
+///
+/// If the data contains a valid checksum, the result is zero.
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum = data
+        .chunks(2)
+        .map(|chunk| match chunk {
+            [hi, lo] => u32::from(u16::from_be_bytes([*hi, *lo])),
+            [hi] => u32::from(u16::from_be_bytes([*hi, 0])),
+            _ => unreachable!(),
+        })
+        .sum::<u32>();
+
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    !(sum as u16)
+}
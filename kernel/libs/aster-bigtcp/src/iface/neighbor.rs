@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{collections::btree_map::BTreeMap, vec::Vec};
+
+use smoltcp::{
+    time::{Duration, Instant},
+    wire::{EthernetAddress, Ipv4Address},
+};
+
+/// A neighbor whose link-layer address has been resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Neighbor {
+    /// The IP address of the neighbor.
+    pub ip_addr: Ipv4Address,
+    /// The Ethernet address of the neighbor.
+    pub ether_addr: EthernetAddress,
+    /// The reachability state of the neighbor.
+    pub state: NeighborState,
+}
+
+/// The reachability state of a neighbor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeighborState {
+    /// The neighbor has been confirmed to be reachable recently.
+    Reachable,
+    /// The neighbor has not been confirmed to be reachable recently.
+    ///
+    /// The cached link-layer address can still be used until the entry expires.
+    Stale,
+}
+
+/// The cache that maps IPv4 addresses to Ethernet addresses (i.e., the ARP table).
+pub(super) struct NeighborCache {
+    entries: BTreeMap<Ipv4Address, NeighborEntry>,
+}
+
+struct NeighborEntry {
+    ether_addr: EthernetAddress,
+    updated_at: Instant,
+}
+
+/// The time after which a neighbor is no longer considered reachable.
+///
+/// This is the default value of `base_reachable_time` in Linux.
+const REACHABLE_TIME: Duration = Duration::from_secs(30);
+
+/// The time after which a neighbor entry expires.
+///
+/// An expired entry is no longer used, so its Ethernet address has to be resolved again.
+const EXPIRE_TIME: Duration = Duration::from_secs(60);
+
+impl NeighborCache {
+    pub(super) const fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Inserts or refreshes the mapping between `ip_addr` and `ether_addr`.
+    pub(super) fn insert(
+        &mut self,
+        ip_addr: Ipv4Address,
+        ether_addr: EthernetAddress,
+        now: Instant,
+    ) {
+        self.entries.insert(
+            ip_addr,
+            NeighborEntry {
+                ether_addr,
+                updated_at: now,
+            },
+        );
+    }
+
+    /// Looks up the Ethernet address of `ip_addr`.
+    ///
+    /// If the entry has expired, it will be removed and `None` will be returned.
+    pub(super) fn lookup(
+        &mut self,
+        ip_addr: &Ipv4Address,
+        now: Instant,
+    ) -> Option<EthernetAddress> {
+        let entry = self.entries.get(ip_addr)?;
+        if now >= entry.updated_at + EXPIRE_TIME {
+            self.entries.remove(ip_addr);
+            return None;
+        }
+
+        Some(entry.ether_addr)
+    }
+
+    /// Returns all neighbors whose entries have not expired.
+    pub(super) fn neighbors(&self, now: Instant) -> Vec<Neighbor> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| now < entry.updated_at + EXPIRE_TIME)
+            .map(|(ip_addr, entry)| {
+                let state = if now < entry.updated_at + REACHABLE_TIME {
+                    NeighborState::Reachable
+                } else {
+                    NeighborState::Stale
+                };
+                Neighbor {
+                    ip_addr: *ip_addr,
+                    ether_addr: entry.ether_addr,
+                    state,
+                }
+            })
+            .collect()
+    }
+}
@@ -1,12 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{collections::btree_map::BTreeMap, string::String, sync::Arc};
+use alloc::{string::String, sync::Arc};
 
-use aster_softirq::BottomHalfDisabled;
-use ostd::sync::SpinLock;
 use smoltcp::{
     iface::{packet::Packet, Config, Context},
-    phy::{Device, DeviceCapabilities, TxToken},
+    phy::{DeviceCapabilities, TxToken},
     wire::{
         self, ArpOperation, ArpPacket, ArpRepr, EthernetAddress, EthernetFrame, EthernetProtocol,
         EthernetRepr, IpAddress, Ipv4Address, Ipv4AddressExt, Ipv4Cidr, Ipv4Packet,
@@ -19,24 +17,26 @@ use crate::{
     iface::{
         common::{IfaceCommon, InterfaceType},
         iface::internal::IfaceInternal,
+        poll::IpPacket,
         time::get_network_timestamp,
-        Iface, InterfaceFlags, ScheduleNextPoll,
+        Iface, InterfaceFlags, Ipv4Route, ScheduleNextPoll,
     },
+    socket::PacketType,
 };
 
 pub struct EtherIface<D, E: Ext> {
     driver: D,
     common: IfaceCommon<E>,
     ether_addr: EthernetAddress,
-    arp_table: SpinLock<BTreeMap<Ipv4Address, EthernetAddress>, BottomHalfDisabled>,
 }
 
 impl<D: WithDevice, E: Ext> EtherIface<D, E> {
     pub fn new(
         driver: D,
         ether_addr: EthernetAddress,
-        ip_cidr: Ipv4Cidr,
-        gateway: Ipv4Address,
+        ip_cidr: Option<Ipv4Cidr>,
+        gateway: Option<Ipv4Address>,
+        index: u32,
         name: String,
         sched_poll: E::ScheduleNextPoll,
         flags: InterfaceFlags,
@@ -46,24 +46,35 @@ impl<D: WithDevice, E: Ext> EtherIface<D, E> {
             let now = get_network_timestamp();
 
             let mut interface = smoltcp::iface::Interface::new(config, device, now);
-            interface.update_ip_addrs(|ip_addrs| {
-                debug_assert!(ip_addrs.is_empty());
-                ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
-            });
-            interface
-                .routes_mut()
-                .add_default_ipv4_route(gateway)
-                .unwrap();
+            if let Some(ip_cidr) = ip_cidr {
+                interface.update_ip_addrs(|ip_addrs| {
+                    debug_assert!(ip_addrs.is_empty());
+                    ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
+                });
+            }
             interface
         });
 
-        let common = IfaceCommon::new(name, InterfaceType::ETHER, flags, interface, sched_poll);
+        let common = IfaceCommon::new(
+            index,
+            name,
+            InterfaceType::ETHER,
+            flags,
+            interface,
+            sched_poll,
+        );
+        if let Some(gateway) = gateway {
+            common.add_ipv4_route(Ipv4Route {
+                dst: Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0),
+                gateway: Some(gateway),
+                metric: 0,
+            });
+        }
 
         Arc::new(Self {
             driver,
             common,
             ether_addr,
-            arp_table: SpinLock::new(BTreeMap::new()),
         })
     }
 }
@@ -80,6 +91,12 @@ where
 {
     fn poll(&self) {
         self.driver.with(|device| {
+            self.common
+                .poll_packet_egress(&mut *device, |frame, tx_token| {
+                    let frame = frame.as_ref();
+                    tx_token.consume(frame.len(), |buffer| buffer.copy_from_slice(frame));
+                });
+
             let next_poll = self.common.poll(
                 &mut *device,
                 |data, iface_cx, tx_token| self.process(data, iface_cx, tx_token),
@@ -89,11 +106,6 @@ where
             self.common.sched_poll().schedule_next_poll(next_poll);
         });
     }
-
-    fn mtu(&self) -> usize {
-        self.driver
-            .with(|device| device.capabilities().max_transmission_unit)
-    }
 }
 
 impl<D, E: Ext> EtherIface<D, E> {
@@ -102,11 +114,11 @@ impl<D, E: Ext> EtherIface<D, E> {
         data: &'pkt [u8],
         iface_cx: &mut Context,
         tx_token: T,
-    ) -> Option<(Ipv4Packet<&'pkt [u8]>, T)> {
+    ) -> Option<(IpPacket<'pkt>, T)> {
         match self.parse_ip_or_process_arp(data, iface_cx) {
-            Ok(pkt) => Some((pkt, tx_token)),
+            Ok(pkt) => Some((IpPacket::Ipv4(pkt), tx_token)),
             Err(Some(arp)) => {
-                Self::emit_arp(&arp, tx_token);
+                self.emit_arp(&arp, tx_token);
                 None
             }
             Err(None) => None,
@@ -122,12 +134,33 @@ impl<D, E: Ext> EtherIface<D, E> {
         let frame = EthernetFrame::new_checked(data).map_err(|_| None)?;
         let repr = EthernetRepr::parse(&frame).map_err(|_| None)?;
 
-        // Ignore the Ethernet frame if it is not sent to us.
-        if !repr.dst_addr.is_broadcast() && repr.dst_addr != self.ether_addr {
+        // Packet sockets receive copies of the frames before the frames are processed by the
+        // network layer.
+        let pkt_type = if repr.dst_addr == self.ether_addr {
+            PacketType::Host
+        } else if repr.dst_addr.is_broadcast() {
+            PacketType::Broadcast
+        } else if repr.dst_addr.is_multicast() {
+            PacketType::Multicast
+        } else {
+            PacketType::OtherHost
+        };
+        self.common
+            .process_packet(&repr, frame.payload(), pkt_type, None);
+
+        // Ignore the Ethernet frame if it is not sent to us. Multicast frames are further filtered
+        // in the network layer according to the joined multicast groups.
+        if !matches!(
+            pkt_type,
+            PacketType::Host | PacketType::Broadcast | PacketType::Multicast
+        ) {
             return Err(None);
         }
 
         // Ignore the Ethernet frame if the protocol is not supported.
+        //
+        // TODO: Support IPv6 over Ethernet. This requires the Neighbor Discovery Protocol (NDP) to
+        // resolve the Ethernet addresses of IPv6 neighbors.
         match repr.ethertype {
             EthernetProtocol::Ipv4 => {
                 Ok(Ipv4Packet::new_checked(frame.payload()).map_err(|_| None)?)
@@ -156,12 +189,12 @@ impl<D, E: Ext> EtherIface<D, E> {
                     return None;
                 }
 
-                // Insert the mapping between the Ethernet address and the IP address.
-                //
-                // TODO: Remove the mapping if it expires.
-                self.arp_table
-                    .lock()
-                    .insert(*source_protocol_addr, *source_hardware_addr);
+                // Insert or refresh the mapping between the Ethernet address and the IP address.
+                self.common.update_neighbor(
+                    *source_protocol_addr,
+                    *source_hardware_addr,
+                    iface_cx.now(),
+                );
 
                 None
             }
@@ -199,8 +232,8 @@ impl<D, E: Ext> EtherIface<D, E> {
 
     fn dispatch<T: TxToken>(&self, pkt: &Packet, iface_cx: &mut Context, tx_token: T) {
         match self.resolve_ether_or_generate_arp(pkt, iface_cx) {
-            Ok(ether) => Self::emit_ip(&ether, pkt, &iface_cx.caps, tx_token),
-            Err(Some(arp)) => Self::emit_arp(&arp, tx_token),
+            Ok(ether) => self.emit_ip(&ether, pkt, &iface_cx.caps, tx_token),
+            Err(Some(arp)) => self.emit_arp(&arp, tx_token),
             Err(None) => (),
         }
     }
@@ -210,17 +243,44 @@ impl<D, E: Ext> EtherIface<D, E> {
         pkt: &Packet,
         iface_cx: &mut Context,
     ) -> Result<EthernetRepr, Option<ArpRepr>> {
-        // Resolve the next-hop IP address.
-        let next_hop_ip = match iface_cx.route(&pkt.ip_repr().dst_addr(), iface_cx.now()) {
-            Some(IpAddress::Ipv4(next_hop_ip)) => next_hop_ip,
-            None => return Err(None),
+        let dst_addr = pkt.ip_repr().dst_addr();
+
+        // Multicast packets are sent directly to the Ethernet addresses mapped from the multicast
+        // IP addresses.
+        if let IpAddress::Ipv4(dst_addr) = dst_addr {
+            if dst_addr.is_multicast() {
+                return Ok(EthernetRepr {
+                    src_addr: self.ether_addr,
+                    dst_addr: ipv4_multicast_to_ether(dst_addr),
+                    ethertype: EthernetProtocol::Ipv4,
+                });
+            }
+        }
+
+        // Resolve the next-hop IP address. Destinations in the local subnet are reached directly.
+        // Other destinations are reached via the routes of the iface.
+        let next_hop_ip = match dst_addr {
+            IpAddress::Ipv4(dst_addr)
+                if dst_addr.is_broadcast()
+                    || iface_cx.in_same_network(&IpAddress::Ipv4(dst_addr)) =>
+            {
+                dst_addr
+            }
+            IpAddress::Ipv4(dst_addr) => match self.common.lookup_ipv4_route(&dst_addr) {
+                Some(route) => route.gateway.unwrap_or(dst_addr),
+                None => return Err(None),
+            },
+            // TODO: Resolve IPv6 neighbors. See the comments in `parse_ip_or_process_arp`.
+            IpAddress::Ipv6(_) => return Err(None),
         };
 
         // Resolve the next-hop Ethernet address.
         let next_hop_ether = if next_hop_ip.is_broadcast() {
             EthernetAddress::BROADCAST
-        } else if let Some(next_hop_ether) = self.arp_table.lock().get(&next_hop_ip) {
-            *next_hop_ether
+        } else if let Some(next_hop_ether) =
+            self.common.lookup_neighbor(&next_hop_ip, iface_cx.now())
+        {
+            next_hop_ether
         } else {
             // If the next-hop Ethernet address cannot be resolved, we drop the original packet and
             // send an ARP packet instead. The upper layer should be responsible for detecting the
@@ -243,6 +303,7 @@ impl<D, E: Ext> EtherIface<D, E> {
 
     /// Consumes the token and emits an IP packet.
     fn emit_ip<T: TxToken>(
+        &self,
         ether_repr: &EthernetRepr,
         ip_pkt: &Packet,
         caps: &DeviceCapabilities,
@@ -261,12 +322,15 @@ impl<D, E: Ext> EtherIface<D, E> {
                     &mut frame.payload_mut()[ip_repr.header_len()..],
                     caps,
                 );
+
+                self.common
+                    .process_packet(ether_repr, frame.payload(), PacketType::Outgoing, None);
             },
         );
     }
 
     /// Consumes the token and emits an ARP packet.
-    fn emit_arp<T: TxToken>(arp_repr: &ArpRepr, tx_token: T) {
+    fn emit_arp<T: TxToken>(&self, arp_repr: &ArpRepr, tx_token: T) {
         let ether_repr = match arp_repr {
             ArpRepr::EthernetIpv4 {
                 source_hardware_addr,
@@ -286,6 +350,20 @@ impl<D, E: Ext> EtherIface<D, E> {
 
             let mut pkt = ArpPacket::new_unchecked(frame.payload_mut());
             arp_repr.emit(&mut pkt);
+
+            self.common
+                .process_packet(&ether_repr, frame.payload(), PacketType::Outgoing, None);
         });
     }
 }
+
+/// Maps an IPv4 multicast address to an Ethernet multicast address.
+///
+/// The low-order 23 bits of the IP address are placed into the low-order 23 bits of the Ethernet
+/// address `01:00:5E:00:00:00`.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc1112#section-6.4>.
+fn ipv4_multicast_to_ether(addr: Ipv4Address) -> EthernetAddress {
+    let octets = addr.octets();
+    EthernetAddress([0x01, 0x00, 0x5e, octets[1] & 0x7f, octets[2], octets[3]])
+}
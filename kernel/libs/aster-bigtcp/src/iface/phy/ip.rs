@@ -4,8 +4,11 @@ use alloc::{string::String, sync::Arc};
 
 use smoltcp::{
     iface::Config,
-    phy::{Device, TxToken},
-    wire::{self, Ipv4Cidr, Ipv4Packet},
+    phy::TxToken,
+    wire::{
+        self, EthernetAddress, EthernetProtocol, EthernetRepr, IpVersion, Ipv4Cidr, Ipv4Packet,
+        Ipv6Cidr, Ipv6Packet,
+    },
 };
 
 use crate::{
@@ -14,9 +17,11 @@ use crate::{
     iface::{
         common::{IfaceCommon, InterfaceFlags, InterfaceType},
         iface::internal::IfaceInternal,
+        poll::IpPacket,
         time::get_network_timestamp,
         Iface, ScheduleNextPoll,
     },
+    socket::PacketType,
 };
 
 pub struct IpIface<D, E: Ext> {
@@ -28,6 +33,8 @@ impl<D: WithDevice, E: Ext> IpIface<D, E> {
     pub fn new(
         driver: D,
         ip_cidr: Ipv4Cidr,
+        ipv6_cidr: Option<Ipv6Cidr>,
+        index: u32,
         name: String,
         sched_poll: E::ScheduleNextPoll,
         type_: InterfaceType,
@@ -41,11 +48,14 @@ impl<D: WithDevice, E: Ext> IpIface<D, E> {
             interface.update_ip_addrs(|ip_addrs| {
                 debug_assert!(ip_addrs.is_empty());
                 ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
+                if let Some(ipv6_cidr) = ipv6_cidr {
+                    ip_addrs.push(wire::IpCidr::Ipv6(ipv6_cidr)).unwrap();
+                }
             });
             interface
         });
 
-        let common = IfaceCommon::new(name, type_, flags, interface, sched_poll);
+        let common = IfaceCommon::new(index, name, type_, flags, interface, sched_poll);
 
         Arc::new(Self { driver, common })
     }
@@ -60,9 +70,39 @@ impl<D, E: Ext> IfaceInternal<E> for IpIface<D, E> {
 impl<D: WithDevice + 'static, E: Ext> Iface<E> for IpIface<D, E> {
     fn poll(&self) {
         self.driver.with(|device| {
+            self.common
+                .poll_packet_egress(&mut *device, |frame, tx_token| {
+                    // Only IP packets can be sent without link-layer headers.
+                    if !matches!(
+                        frame.ethertype(),
+                        EthernetProtocol::Ipv4 | EthernetProtocol::Ipv6
+                    ) {
+                        return;
+                    }
+                    let payload = frame.payload();
+                    tx_token.consume(payload.len(), |buffer| buffer.copy_from_slice(payload));
+                });
+
             let next_poll = self.common.poll(
                 device,
-                |data, _iface_cx, tx_token| Some((Ipv4Packet::new_checked(data).ok()?, tx_token)),
+                |data, _iface_cx, tx_token| {
+                    let ip_version = IpVersion::of_packet(data).ok()?;
+                    let pkt = match ip_version {
+                        IpVersion::Ipv4 => IpPacket::Ipv4(Ipv4Packet::new_checked(data).ok()?),
+                        IpVersion::Ipv6 => IpPacket::Ipv6(Ipv6Packet::new_checked(data).ok()?),
+                    };
+
+                    // Packet sockets receive copies of the packets before the packets are
+                    // processed by the network layer.
+                    self.common.process_packet(
+                        &fake_ether_repr(ip_version),
+                        data,
+                        PacketType::Host,
+                        None,
+                    );
+
+                    Some((pkt, tx_token))
+                },
                 |pkt, iface_cx, tx_token| {
                     let ip_repr = pkt.ip_repr();
                     tx_token.consume(ip_repr.buffer_len(), |buffer| {
@@ -72,15 +112,34 @@ impl<D: WithDevice + 'static, E: Ext> Iface<E> for IpIface<D, E> {
                             &mut buffer[ip_repr.header_len()..],
                             &iface_cx.caps,
                         );
+
+                        self.common.process_packet(
+                            &fake_ether_repr(ip_repr.version()),
+                            buffer,
+                            PacketType::Outgoing,
+                            None,
+                        );
                     });
                 },
             );
             self.common.sched_poll().schedule_next_poll(next_poll);
         });
     }
+}
+
+/// Returns a fake Ethernet header for packet sockets.
+///
+/// Packet sockets always see Ethernet frames. Like Linux, we use Ethernet headers with zero
+/// addresses for ifaces without link-layer headers.
+fn fake_ether_repr(ip_version: IpVersion) -> EthernetRepr {
+    let ethertype = match ip_version {
+        IpVersion::Ipv4 => EthernetProtocol::Ipv4,
+        IpVersion::Ipv6 => EthernetProtocol::Ipv6,
+    };
 
-    fn mtu(&self) -> usize {
-        self.driver
-            .with(|device| device.capabilities().max_transmission_unit)
+    EthernetRepr {
+        src_addr: EthernetAddress([0; 6]),
+        dst_addr: EthernetAddress([0; 6]),
+        ethertype,
     }
 }
@@ -9,23 +9,31 @@ use smoltcp::{
     },
     phy::{ChecksumCapabilities, Device, RxToken, TxToken},
     wire::{
-        Icmpv4DstUnreachable, Icmpv4Repr, IpAddress, IpProtocol, IpRepr, Ipv4Address, Ipv4Packet,
-        Ipv4Repr, TcpControl, TcpPacket, TcpRepr, UdpPacket, UdpRepr, IPV4_HEADER_LEN,
-        IPV4_MIN_MTU,
+        Icmpv4DstUnreachable, Icmpv4Message, Icmpv4Packet, Icmpv4Repr, Icmpv6DstUnreachable,
+        Icmpv6Repr, IpAddress, IpEndpoint, IpProtocol, IpRepr, Ipv4Address, Ipv4Packet, Ipv4Repr,
+        Ipv6Address, Ipv6Packet, Ipv6Repr, TcpControl, TcpPacket, TcpRepr, UdpPacket, UdpRepr,
+        DHCP_CLIENT_PORT, DHCP_SERVER_PORT, IPV4_HEADER_LEN, IPV4_MIN_MTU, IPV6_HEADER_LEN,
+        IPV6_MIN_MTU, UDP_HEADER_LEN,
     },
 };
 
-use super::poll_iface::PollableIfaceMut;
+use super::{
+    common::IfaceCommon,
+    filter::{FilterAction, FilterChain, FilterHook},
+    poll_iface::PollableIfaceMut,
+};
 use crate::{
     ext::Ext,
-    socket::{TcpConnectionBg, TcpProcessResult},
+    socket::{IcmpError, TcpConnectionBg, TcpProcessResult, UdpIcmpError},
     socket_table::{ConnectionKey, ListenerKey, SocketTable},
 };
 
 pub(super) struct PollContext<'a, E: Ext> {
+    common: &'a IfaceCommon<E>,
     iface: PollableIfaceMut<'a, E>,
     sockets: &'a SocketTable<E>,
     actions: &'a mut Vec<SocketTableAction<E>>,
+    filter: FilterHook<'a>,
 }
 
 /// Socket table actions such as adding or removing TCP connections.
@@ -39,18 +47,37 @@ pub(super) enum SocketTableAction<E: Ext> {
 
 impl<'a, E: Ext> PollContext<'a, E> {
     pub(super) fn new(
+        common: &'a IfaceCommon<E>,
         iface: PollableIfaceMut<'a, E>,
         sockets: &'a SocketTable<E>,
         actions: &'a mut Vec<SocketTableAction<E>>,
+        filter: FilterHook<'a>,
     ) -> Self {
         Self {
+            common,
             iface,
             sockets,
             actions,
+            filter,
         }
     }
 }
 
+/// An incoming IPv4 or IPv6 packet.
+pub(super) enum IpPacket<'pkt> {
+    Ipv4(Ipv4Packet<&'pkt [u8]>),
+    Ipv6(Ipv6Packet<&'pkt [u8]>),
+}
+
+/// The reason why an incoming packet cannot be delivered.
+#[derive(Debug, Clone, Copy)]
+enum UnreachableReason {
+    /// The destination address is not a local address.
+    Addr,
+    /// No socket is bound to the destination port.
+    Port,
+}
+
 // This works around <https://github.com/rust-lang/rust/issues/49601>.
 // See the issue above for details.
 pub(super) trait FnHelper<A, B, C, O>: FnMut(A, B, C) -> O {}
@@ -68,7 +95,7 @@ impl<E: Ext> PollContext<'_, E> {
             &'pkt [u8],
             &'cx mut Context,
             D::TxToken<'tx>,
-            Option<(Ipv4Packet<&'pkt [u8]>, D::TxToken<'tx>)>,
+            Option<(IpPacket<'pkt>, D::TxToken<'tx>)>,
         >,
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
@@ -79,11 +106,17 @@ impl<E: Ext> PollContext<'_, E> {
                     return;
                 };
 
-                let Some(reply) = self.parse_and_process_ipv4(pkt) else {
+                let reply = match pkt {
+                    IpPacket::Ipv4(pkt) => self.parse_and_process_ipv4(pkt),
+                    IpPacket::Ipv6(pkt) => self.parse_and_process_ipv6(pkt),
+                };
+                let Some(reply) = reply else {
                     return;
                 };
 
-                dispatch_phy(&reply, self.iface.context_mut(), tx_token);
+                if self.filter.accepts_outgoing(&reply) {
+                    dispatch_phy(&reply, self.iface.context_mut(), tx_token);
+                }
             });
         }
     }
@@ -95,22 +128,86 @@ impl<E: Ext> PollContext<'_, E> {
         // Parse the IP header. Ignore the packet if the header is ill-formed.
         let repr = Ipv4Repr::parse(&pkt, &self.iface.context().checksum_caps()).ok()?;
 
-        if !repr.dst_addr.is_broadcast() && !self.is_unicast_local(IpAddress::Ipv4(repr.dst_addr)) {
-            return self.generate_icmp_unreachable(
+        if !repr.dst_addr.is_broadcast()
+            && !self.is_unicast_local(IpAddress::Ipv4(repr.dst_addr))
+            && !self.has_joined_multicast(IpAddress::Ipv4(repr.dst_addr))
+        {
+            // TODO: Forward the packet if the iface is not its final destination. For now, the
+            // packets accepted by the forward chain are answered as if the host is unreachable.
+            let reason = match self.filter.check_ipv4(FilterChain::Forward, &pkt) {
+                FilterAction::Accept => UnreachableReason::Addr,
+                FilterAction::Drop => return None,
+                FilterAction::Reject => UnreachableReason::Port,
+            };
+            return self.generate_icmp_unreachable(&IpRepr::Ipv4(repr), pkt.payload(), reason);
+        }
+
+        match self.filter.check_ipv4(FilterChain::Input, &pkt) {
+            FilterAction::Accept => (),
+            FilterAction::Drop => return None,
+            FilterAction::Reject => {
+                return self.generate_icmp_unreachable(
+                    &IpRepr::Ipv4(repr),
+                    pkt.payload(),
+                    UnreachableReason::Port,
+                );
+            }
+        }
+
+        // Raw sockets receive copies of the packets before the packets are processed by the
+        // transport layer.
+        self.process_raw(&repr, &pkt.as_ref()[..usize::from(pkt.total_len())]);
+
+        let checksum_caps = self.iface.context().checksum_caps();
+        match repr.next_header {
+            IpProtocol::Tcp => {
+                self.parse_and_process_tcp(&IpRepr::Ipv4(repr), pkt.payload(), &checksum_caps)
+            }
+            IpProtocol::Udp => self.parse_and_process_udp(
                 &IpRepr::Ipv4(repr),
+                (pkt.dscp() << 2) | pkt.ecn(),
+                pkt.payload(),
+                &checksum_caps,
+            ),
+            IpProtocol::Icmp => self.parse_and_process_icmpv4(&repr, pkt.payload(), &checksum_caps),
+            IpProtocol::Igmp => {
+                self.iface
+                    .multicast_groups_mut()
+                    .process_igmp(pkt.payload());
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_and_process_ipv6<'pkt>(
+        &mut self,
+        pkt: Ipv6Packet<&'pkt [u8]>,
+    ) -> Option<Packet<'pkt>> {
+        // Parse the IP header. Ignore the packet if the header is ill-formed.
+        let repr = Ipv6Repr::parse(&pkt).ok()?;
+
+        if !self.is_unicast_local(IpAddress::Ipv6(repr.dst_addr)) {
+            return self.generate_icmp_unreachable(
+                &IpRepr::Ipv6(repr),
                 pkt.payload(),
-                Icmpv4DstUnreachable::HostUnreachable,
+                UnreachableReason::Addr,
             );
         }
 
+        // TODO: Support IPv6 extension headers. Packets with extension headers are currently
+        // ignored.
         let checksum_caps = self.iface.context().checksum_caps();
         match repr.next_header {
             IpProtocol::Tcp => {
-                self.parse_and_process_tcp(&IpRepr::Ipv4(repr), pkt.payload(), &checksum_caps)
-            }
-            IpProtocol::Udp => {
-                self.parse_and_process_udp(&IpRepr::Ipv4(repr), pkt.payload(), &checksum_caps)
+                self.parse_and_process_tcp(&IpRepr::Ipv6(repr), pkt.payload(), &checksum_caps)
             }
+            IpProtocol::Udp => self.parse_and_process_udp(
+                &IpRepr::Ipv6(repr),
+                pkt.traffic_class(),
+                pkt.payload(),
+                &checksum_caps,
+            ),
             _ => None,
         }
     }
@@ -221,9 +318,17 @@ impl<E: Ext> PollContext<'_, E> {
         // Process packets that request to create new connections second.
         if tcp_repr.control == TcpControl::Syn && tcp_repr.ack_number.is_none() {
             let listener_key = ListenerKey::new(ip_repr.dst_addr(), tcp_repr.dst_port);
-            if let Some(listener) = self.sockets.lookup_listener(&listener_key) {
+            // Fall back to the listener on the unspecified IPv6 address, which may also accept
+            // IPv4 connections if it is not IPv6-only.
+            let any_listener_key =
+                ListenerKey::new(IpAddress::Ipv6(Ipv6Address::UNSPECIFIED), tcp_repr.dst_port);
+            if let Some(listener) = self
+                .sockets
+                .lookup_listener(&listener_key)
+                .or_else(|| self.sockets.lookup_listener(&any_listener_key))
+            {
                 let (processed, new_tcp_conn) =
-                    listener.process(&mut self.iface, ip_repr, tcp_repr);
+                    listener.process(self.common, &mut self.iface, ip_repr, tcp_repr);
 
                 if let Some(tcp_conn) = new_tcp_conn {
                     self.actions.push(SocketTableAction::AddTcpConn(tcp_conn));
@@ -251,6 +356,7 @@ impl<E: Ext> PollContext<'_, E> {
     fn parse_and_process_udp<'pkt>(
         &mut self,
         ip_repr: &IpRepr,
+        tos: u8,
         ip_payload: &'pkt [u8],
         checksum_caps: &ChecksumCapabilities,
     ) -> Option<Packet<'pkt>> {
@@ -264,18 +370,24 @@ impl<E: Ext> PollContext<'_, E> {
         )
         .ok()?;
 
-        if !self.process_udp(ip_repr, &udp_repr, udp_pkt.payload()) {
-            return self.generate_icmp_unreachable(
-                ip_repr,
-                ip_payload,
-                Icmpv4DstUnreachable::PortUnreachable,
-            );
+        if self.process_dhcp(ip_repr, &udp_repr, udp_pkt.payload()) {
+            return None;
+        }
+
+        if !self.process_udp(ip_repr, tos, &udp_repr, udp_pkt.payload()) {
+            return self.generate_icmp_unreachable(ip_repr, ip_payload, UnreachableReason::Port);
         }
 
         None
     }
 
-    fn process_udp(&mut self, ip_repr: &IpRepr, udp_repr: &UdpRepr, udp_payload: &[u8]) -> bool {
+    fn process_udp(
+        &mut self,
+        ip_repr: &IpRepr,
+        tos: u8,
+        udp_repr: &UdpRepr,
+        udp_payload: &[u8],
+    ) -> bool {
         let mut processed = false;
 
         for socket in self.sockets.udp_socket_iter() {
@@ -283,7 +395,13 @@ impl<E: Ext> PollContext<'_, E> {
                 continue;
             }
 
-            processed |= socket.process(self.iface.context_mut(), ip_repr, udp_repr, udp_payload);
+            processed |= socket.process(
+                self.iface.context_mut(),
+                ip_repr,
+                tos,
+                udp_repr,
+                udp_payload,
+            );
             if processed && ip_repr.dst_addr().is_unicast() {
                 break;
             }
@@ -292,11 +410,161 @@ impl<E: Ext> PollContext<'_, E> {
         processed
     }
 
+    /// Delivers a DHCP message to the DHCP client, if any, and returns whether it is delivered.
+    ///
+    /// While the DHCP client is running, it takes over the DHCP client port, so the messages will
+    /// not be delivered to the UDP sockets.
+    fn process_dhcp(&mut self, ip_repr: &IpRepr, udp_repr: &UdpRepr, udp_payload: &[u8]) -> bool {
+        if !matches!(ip_repr, IpRepr::Ipv4(_))
+            || udp_repr.src_port != DHCP_SERVER_PORT
+            || udp_repr.dst_port != DHCP_CLIENT_PORT
+        {
+            return false;
+        }
+
+        let now = self.iface.context().now();
+        let Some(dhcp) = self.iface.dhcp_mut() else {
+            return false;
+        };
+        dhcp.process(udp_payload, now);
+
+        true
+    }
+
+    fn process_raw(&self, ip_repr: &Ipv4Repr, packet: &[u8]) {
+        for socket in self.sockets.raw_socket_iter() {
+            socket.process(ip_repr, packet);
+        }
+    }
+
+    fn parse_and_process_icmpv4<'pkt>(
+        &mut self,
+        ip_repr: &Ipv4Repr,
+        ip_payload: &'pkt [u8],
+        checksum_caps: &ChecksumCapabilities,
+    ) -> Option<Packet<'pkt>> {
+        // Parse the ICMP header. Ignore the packet if the header is ill-formed.
+        let icmp_pkt = Icmpv4Packet::new_checked(ip_payload).ok()?;
+        if checksum_caps.icmpv4.rx() && !icmp_pkt.verify_checksum() {
+            return None;
+        }
+
+        match icmp_pkt.msg_type() {
+            Icmpv4Message::EchoRequest => {
+                // Linux ignores echo requests sent to broadcast addresses by default. See
+                // <https://docs.kernel.org/networking/ip-sysctl.html#icmp-echo-ignore-broadcasts-boolean>.
+                if !self.is_unicast_local(IpAddress::Ipv4(ip_repr.dst_addr)) {
+                    return None;
+                }
+
+                let Icmpv4Repr::EchoRequest {
+                    ident,
+                    seq_no,
+                    data,
+                } = Icmpv4Repr::parse(&icmp_pkt, checksum_caps).ok()?
+                else {
+                    return None;
+                };
+                let icmp_repr = Icmpv4Repr::EchoReply {
+                    ident,
+                    seq_no,
+                    data,
+                };
+
+                Some(Packet::new_ipv4(
+                    Ipv4Repr {
+                        src_addr: ip_repr.dst_addr,
+                        dst_addr: ip_repr.src_addr,
+                        next_header: IpProtocol::Icmp,
+                        payload_len: icmp_repr.buffer_len(),
+                        hop_limit: 64,
+                    },
+                    IpPayload::Icmpv4(icmp_repr),
+                ))
+            }
+            Icmpv4Message::EchoReply => {
+                let ident = icmp_pkt.echo_ident();
+                for socket in self.sockets.icmp_socket_iter() {
+                    if socket.can_process(ident) && socket.process(ip_repr.src_addr, ip_payload) {
+                        break;
+                    }
+                }
+                None
+            }
+            msg_type => {
+                let error = IcmpError::new(msg_type, icmp_pkt.msg_code())?;
+                self.process_icmpv4_error(error, ip_repr.src_addr, &icmp_pkt);
+                None
+            }
+        }
+    }
+
+    fn process_icmpv4_error(
+        &mut self,
+        error: IcmpError,
+        src_addr: Ipv4Address,
+        icmp_pkt: &Icmpv4Packet<&[u8]>,
+    ) {
+        // The data of an ICMP error message contains the IP header and the first eight bytes of
+        // the original packet. See <https://datatracker.ietf.org/doc/html/rfc792>.
+        let icmp_data = icmp_pkt.data();
+        if icmp_data.len() < IPV4_HEADER_LEN {
+            return;
+        }
+        let orig_pkt = Ipv4Packet::new_unchecked(icmp_data);
+        let orig_header_len = usize::from(orig_pkt.header_len());
+        if orig_pkt.version() != 4 || icmp_data.len() < orig_header_len + 8 {
+            return;
+        }
+        let orig_payload = &icmp_data[orig_header_len..];
+
+        match orig_pkt.next_header() {
+            IpProtocol::Icmp => {
+                let orig_icmp_pkt = Icmpv4Packet::new_unchecked(orig_payload);
+                if orig_icmp_pkt.msg_type() != Icmpv4Message::EchoRequest {
+                    return;
+                }
+
+                let ident = orig_icmp_pkt.echo_ident();
+                for socket in self.sockets.icmp_socket_iter() {
+                    if socket.can_process(ident) {
+                        socket.process_error(error);
+                        break;
+                    }
+                }
+            }
+            IpProtocol::Udp => {
+                let orig_udp_pkt = UdpPacket::new_unchecked(orig_payload);
+                let src_port = orig_udp_pkt.src_port();
+                let udp_error = UdpIcmpError {
+                    error,
+                    icmp_type: icmp_pkt.msg_type().into(),
+                    icmp_code: icmp_pkt.msg_code(),
+                    offender: src_addr,
+                    dst_endpoint: IpEndpoint::new(
+                        IpAddress::Ipv4(orig_pkt.dst_addr()),
+                        orig_udp_pkt.dst_port(),
+                    ),
+                    payload: orig_payload[UDP_HEADER_LEN..].to_vec(),
+                };
+
+                for socket in self.sockets.udp_socket_iter() {
+                    if socket.can_process(src_port) {
+                        socket.process_error(udp_error);
+                        break;
+                    }
+                }
+            }
+            // TODO: Report ICMP errors to TCP sockets.
+            _ => {}
+        }
+    }
+
     fn generate_icmp_unreachable<'pkt>(
         &self,
         ip_repr: &IpRepr,
         ip_payload: &'pkt [u8],
-        reason: Icmpv4DstUnreachable,
+        reason: UnreachableReason,
     ) -> Option<Packet<'pkt>> {
         if !ip_repr.src_addr().is_unicast() || !ip_repr.dst_addr().is_unicast() {
             return None;
@@ -312,35 +580,83 @@ impl<E: Ext> PollContext<'_, E> {
             return None;
         }
 
-        let IpRepr::Ipv4(ipv4_repr) = ip_repr;
+        match ip_repr {
+            IpRepr::Ipv4(ipv4_repr) => {
+                let reason = match reason {
+                    UnreachableReason::Addr => Icmpv4DstUnreachable::HostUnreachable,
+                    UnreachableReason::Port => Icmpv4DstUnreachable::PortUnreachable,
+                };
 
-        let reply_len = icmp_reply_payload_len(ip_payload.len(), IPV4_MIN_MTU, IPV4_HEADER_LEN);
-        let icmp_repr = Icmpv4Repr::DstUnreachable {
-            reason,
-            header: *ipv4_repr,
-            data: &ip_payload[..reply_len],
-        };
+                let reply_len =
+                    icmp_reply_payload_len(ip_payload.len(), IPV4_MIN_MTU, IPV4_HEADER_LEN);
+                let icmp_repr = Icmpv4Repr::DstUnreachable {
+                    reason,
+                    header: *ipv4_repr,
+                    data: &ip_payload[..reply_len],
+                };
+
+                Some(Packet::new_ipv4(
+                    Ipv4Repr {
+                        src_addr: self
+                            .iface
+                            .context()
+                            .ipv4_addr()
+                            .unwrap_or(Ipv4Address::UNSPECIFIED),
+                        dst_addr: ipv4_repr.src_addr,
+                        next_header: IpProtocol::Icmp,
+                        payload_len: icmp_repr.buffer_len(),
+                        hop_limit: 64,
+                    },
+                    IpPayload::Icmpv4(icmp_repr),
+                ))
+            }
+            IpRepr::Ipv6(ipv6_repr) => {
+                let reason = match reason {
+                    UnreachableReason::Addr => Icmpv6DstUnreachable::AddrUnreachable,
+                    UnreachableReason::Port => Icmpv6DstUnreachable::PortUnreachable,
+                };
 
-        Some(Packet::new_ipv4(
-            Ipv4Repr {
-                src_addr: self
-                    .iface
-                    .context()
-                    .ipv4_addr()
-                    .unwrap_or(Ipv4Address::UNSPECIFIED),
-                dst_addr: ipv4_repr.src_addr,
-                next_header: IpProtocol::Icmp,
-                payload_len: icmp_repr.buffer_len(),
-                hop_limit: 64,
-            },
-            IpPayload::Icmpv4(icmp_repr),
-        ))
+                let reply_len =
+                    icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, IPV6_HEADER_LEN);
+                let icmp_repr = Icmpv6Repr::DstUnreachable {
+                    reason,
+                    header: *ipv6_repr,
+                    data: &ip_payload[..reply_len],
+                };
+
+                Some(Packet::new_ipv6(
+                    Ipv6Repr {
+                        src_addr: self
+                            .iface
+                            .context()
+                            .ipv6_addr()
+                            .unwrap_or(Ipv6Address::UNSPECIFIED),
+                        dst_addr: ipv6_repr.src_addr,
+                        next_header: IpProtocol::Icmpv6,
+                        payload_len: icmp_repr.buffer_len(),
+                        hop_limit: 64,
+                    },
+                    IpPayload::Icmpv6(icmp_repr),
+                ))
+            }
+        }
+    }
+
+    /// Returns whether the destination address is a multicast group that the iface has joined.
+    fn has_joined_multicast(&self, dst_addr: IpAddress) -> bool {
+        match dst_addr {
+            IpAddress::Ipv4(dst_addr) => {
+                dst_addr.is_multicast() && self.iface.multicast_groups().has_joined(dst_addr)
+            }
+            // TODO: Support IPv6 multicast.
+            IpAddress::Ipv6(_) => false,
+        }
     }
 
     /// Returns whether the destination address is the unicast address of a local interface.
     ///
     /// Note: "local" means that the IP address belongs to the local interface, not to be confused
-    /// with the localhost IP (127.0.0.1).
+    /// with the localhost IP (127.0.0.1 or ::1).
     fn is_unicast_local(&self, dst_addr: IpAddress) -> bool {
         match dst_addr {
             IpAddress::Ipv4(dst_addr) => self
@@ -348,6 +664,11 @@ impl<E: Ext> PollContext<'_, E> {
                 .context()
                 .ipv4_addr()
                 .is_some_and(|addr| addr == dst_addr),
+            IpAddress::Ipv6(dst_addr) => self
+                .iface
+                .context()
+                .ipv6_addr()
+                .is_some_and(|addr| addr == dst_addr),
         }
     }
 }
@@ -359,26 +680,65 @@ impl<E: Ext> PollContext<'_, E> {
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
         while let Some(tx_token) = device.transmit(self.iface.context().now()) {
-            if !self.dispatch_ipv4(tx_token, dispatch_phy) {
+            if !self.dispatch_ip(tx_token, dispatch_phy) {
                 break;
             }
         }
     }
 
-    fn dispatch_ipv4<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> bool
+    fn dispatch_ip<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> bool
     where
         T: TxToken,
         Q: FnMut(&Packet, &mut Context, T),
     {
+        let mut dispatch_phy = filter_outgoing(self.filter, dispatch_phy);
+        let dispatch_phy = &mut dispatch_phy;
+
         let (did_something_tcp, tx_token) = self.dispatch_tcp(tx_token, dispatch_phy);
 
         let Some(tx_token) = tx_token else {
             return did_something_tcp;
         };
 
-        let (did_something_udp, _tx_token) = self.dispatch_udp(tx_token, dispatch_phy);
+        let (did_something_udp, tx_token) = self.dispatch_udp(tx_token, dispatch_phy);
 
-        did_something_tcp || did_something_udp
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp || did_something_udp;
+        };
+
+        let (did_something_icmp, tx_token) = self.dispatch_icmp(tx_token, dispatch_phy);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp || did_something_udp || did_something_icmp;
+        };
+
+        let (did_something_raw, tx_token) = self.dispatch_raw(tx_token, dispatch_phy);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp
+                || did_something_udp
+                || did_something_icmp
+                || did_something_raw;
+        };
+
+        let (did_something_igmp, tx_token) = self.dispatch_igmp(tx_token, dispatch_phy);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp
+                || did_something_udp
+                || did_something_icmp
+                || did_something_raw
+                || did_something_igmp;
+        };
+
+        let (did_something_dhcp, _tx_token) = self.dispatch_dhcp(tx_token, dispatch_phy);
+
+        did_something_tcp
+            || did_something_udp
+            || did_something_icmp
+            || did_something_raw
+            || did_something_igmp
+            || did_something_dhcp
     }
 
     fn dispatch_tcp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
@@ -402,7 +762,13 @@ impl<E: Ext> PollContext<'_, E> {
 
             let (reply, became_dead) =
                 TcpConnectionBg::dispatch(&socket, &mut self.iface, |iface, ip_repr, tcp_repr| {
-                    let mut this = PollContext::new(iface, self.sockets, self.actions);
+                    let mut this = PollContext::new(
+                        self.common,
+                        iface,
+                        self.sockets,
+                        self.actions,
+                        self.filter,
+                    );
 
                     if !this.is_unicast_local(ip_repr.dst_addr()) {
                         dispatch_phy(
@@ -489,7 +855,9 @@ impl<E: Ext> PollContext<'_, E> {
         let mut actions = Vec::new();
 
         for socket in self.sockets.udp_socket_iter() {
-            if !socket.need_dispatch() {
+            // Sockets bound to the unspecified address are also in the socket tables of other
+            // ifaces, but their packets are only sent via their own ifaces.
+            if !socket.need_dispatch() || !socket.is_bound_to(self.common) {
                 continue;
             }
 
@@ -499,18 +867,31 @@ impl<E: Ext> PollContext<'_, E> {
 
             let mut deferred = None;
 
-            let (cx, pending) = self.iface.inner_mut();
+            // TODO: Support setting the TOS of outgoing packets via `IP_TOS`.
+            let tos = 0;
+
+            let (cx, pending, multicast_groups, dhcp) = self.iface.inner_mut();
             socket.dispatch(cx, |cx, ip_repr, udp_repr, udp_payload| {
-                let iface = PollableIfaceMut::new(cx, pending);
-                let mut this = PollContext::new(iface, self.sockets, &mut actions);
+                let iface = PollableIfaceMut::new(cx, pending, multicast_groups, dhcp);
+                let mut this =
+                    PollContext::new(self.common, iface, self.sockets, &mut actions, self.filter);
 
-                if ip_repr.dst_addr().is_broadcast() || !this.is_unicast_local(ip_repr.dst_addr()) {
+                let dst_addr = ip_repr.dst_addr();
+                if dst_addr.is_broadcast() || !this.is_unicast_local(dst_addr) {
                     dispatch_phy(
                         &Packet::new(ip_repr.clone(), IpPayload::Udp(*udp_repr, udp_payload)),
                         this.iface.context_mut(),
                         tx_token.take().unwrap(),
                     );
-                    if !ip_repr.dst_addr().is_broadcast() {
+
+                    // Broadcast packets are always delivered locally. Multicast packets are
+                    // delivered locally if the socket wants them to be looped back, unless the
+                    // device has already done so.
+                    let need_local_delivery = dst_addr.is_broadcast()
+                        || (socket.multicast_loop()
+                            && this.iface.multicast_groups().need_software_loop()
+                            && this.has_joined_multicast(dst_addr));
+                    if !need_local_delivery {
                         return;
                     }
                 }
@@ -518,7 +899,7 @@ impl<E: Ext> PollContext<'_, E> {
                 if !socket.can_process(udp_repr.dst_port) {
                     // TODO: Generate the ICMP message here once we're able to handle incoming ICMP
                     // messages.
-                    let _ = this.process_udp(ip_repr, udp_repr, udp_payload);
+                    let _ = this.process_udp(ip_repr, tos, udp_repr, udp_payload);
                     return;
                 }
 
@@ -541,6 +922,7 @@ impl<E: Ext> PollContext<'_, E> {
             if let Some((ip_repr, ip_payload)) = deferred {
                 if let Some(reply) = self.parse_and_process_udp(
                     &ip_repr,
+                    tos,
                     &ip_payload,
                     &ChecksumCapabilities::ignored(),
                 ) {
@@ -560,4 +942,176 @@ impl<E: Ext> PollContext<'_, E> {
 
         (did_something, tx_token)
     }
+
+    fn dispatch_icmp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let mut tx_token = Some(tx_token);
+        let mut did_something = false;
+
+        for socket in self.sockets.icmp_socket_iter() {
+            if !socket.need_dispatch() {
+                continue;
+            }
+
+            let Some(packet) = socket.dispatch() else {
+                continue;
+            };
+            did_something = true;
+
+            self.dispatch_ipv4_packet(packet, &mut tx_token, dispatch_phy);
+
+            if tx_token.is_none() {
+                break;
+            }
+        }
+
+        (did_something, tx_token)
+    }
+
+    fn dispatch_raw<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let mut tx_token = Some(tx_token);
+        let mut did_something = false;
+
+        for socket in self.sockets.raw_socket_iter() {
+            if !socket.need_dispatch() {
+                continue;
+            }
+
+            let Some(packet) = socket.dispatch() else {
+                continue;
+            };
+            did_something = true;
+
+            self.dispatch_ipv4_packet(packet, &mut tx_token, dispatch_phy);
+
+            if tx_token.is_none() {
+                break;
+            }
+        }
+
+        (did_something, tx_token)
+    }
+
+    fn dispatch_igmp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let src_addr = self
+            .iface
+            .context()
+            .ipv4_addr()
+            .unwrap_or(Ipv4Address::UNSPECIFIED);
+        let Some((ip_repr, igmp_msg)) = self.iface.multicast_groups_mut().pop_igmp(src_addr) else {
+            return (false, Some(tx_token));
+        };
+
+        dispatch_phy(
+            &Packet::new_ipv4(ip_repr, IpPayload::Raw(&igmp_msg)),
+            self.iface.context_mut(),
+            tx_token,
+        );
+
+        (true, None)
+    }
+
+    fn dispatch_dhcp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let now = self.iface.context().now();
+        let Some(dhcp) = self.iface.dhcp_mut() else {
+            return (false, Some(tx_token));
+        };
+        let Some((ip_repr, udp_repr, dhcp_msg)) = dhcp.dispatch(now) else {
+            return (false, Some(tx_token));
+        };
+
+        dispatch_phy(
+            &Packet::new_ipv4(ip_repr, IpPayload::Udp(udp_repr, &dhcp_msg)),
+            self.iface.context_mut(),
+            tx_token,
+        );
+
+        (true, None)
+    }
+
+    /// Dispatches an outgoing IPv4 packet that is generated by an ICMP socket or a raw socket.
+    ///
+    /// If the packet is destined for a local address, it will be processed immediately, and so
+    /// will its replies, until a reply needs to be sent out via `tx_token`.
+    fn dispatch_ipv4_packet<T, Q>(
+        &mut self,
+        mut packet: Vec<u8>,
+        tx_token: &mut Option<T>,
+        dispatch_phy: &mut Q,
+    ) where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        {
+            let Ok(pkt) = Ipv4Packet::new_checked(packet.as_slice()) else {
+                return;
+            };
+            let Ok(ip_repr) = Ipv4Repr::parse(&pkt, &ChecksumCapabilities::ignored()) else {
+                return;
+            };
+
+            if !self.is_unicast_local(IpAddress::Ipv4(ip_repr.dst_addr)) {
+                dispatch_phy(
+                    &Packet::new_ipv4(ip_repr, IpPayload::Raw(pkt.payload())),
+                    self.iface.context_mut(),
+                    tx_token.take().unwrap(),
+                );
+                return;
+            }
+        }
+
+        loop {
+            let Ok(pkt) = Ipv4Packet::new_checked(packet.as_slice()) else {
+                return;
+            };
+            let Some(reply) = self.parse_and_process_ipv4(pkt) else {
+                return;
+            };
+
+            if !self.is_unicast_local(reply.ip_repr().dst_addr()) {
+                dispatch_phy(&reply, self.iface.context_mut(), tx_token.take().unwrap());
+                return;
+            }
+
+            // The reply is also destined for a local address. We will serialize it and process it
+            // as an incoming packet.
+            let ip_repr = reply.ip_repr();
+            let caps = &self.iface.context().caps;
+            let mut data = vec![0; ip_repr.buffer_len()];
+            ip_repr.emit(&mut data[..], &caps.checksum);
+            reply.emit_payload(&ip_repr, &mut data[ip_repr.header_len()..], caps);
+            packet = data;
+        }
+    }
+}
+
+/// Wraps `dispatch_phy` so that only the outgoing packets accepted by `filter` are dispatched.
+fn filter_outgoing<'a, T, Q>(
+    filter: FilterHook<'a>,
+    dispatch_phy: &'a mut Q,
+) -> impl FnMut(&Packet, &mut Context, T) + 'a
+where
+    T: TxToken + 'a,
+    Q: FnMut(&Packet, &mut Context, T),
+{
+    move |packet, cx, tx_token| {
+        if filter.accepts_outgoing(packet) {
+            dispatch_phy(packet, cx, tx_token);
+        }
+    }
 }
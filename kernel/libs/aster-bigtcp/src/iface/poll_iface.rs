@@ -6,6 +6,12 @@ use core::{
     sync::atomic::{AtomicU64, Ordering},
 };
 
+use smoltcp::{
+    time::Instant,
+    wire::{EthernetAddress, IpCidr, Ipv4Cidr},
+};
+
+use super::{dhcp::DhcpClient, multicast::MulticastGroups};
 use crate::{
     ext::Ext,
     socket::{NeedIfacePoll, TcpConnectionBg},
@@ -18,13 +24,17 @@ use crate::{
 pub(crate) struct PollableIface<E: Ext> {
     interface: smoltcp::iface::Interface,
     pending_conns: PendingConnSet<E>,
+    multicast_groups: MulticastGroups,
+    dhcp: Option<DhcpClient>,
 }
 
 impl<E: Ext> PollableIface<E> {
-    pub(super) fn new(interface: smoltcp::iface::Interface) -> Self {
+    pub(super) fn new(interface: smoltcp::iface::Interface, is_loopback: bool) -> Self {
         Self {
             interface,
             pending_conns: PendingConnSet::new(),
+            multicast_groups: MulticastGroups::new(is_loopback),
+            dhcp: None,
         }
     }
 
@@ -32,9 +42,15 @@ impl<E: Ext> PollableIface<E> {
         PollableIfaceMut {
             context: self.interface.context(),
             pending_conns: &mut self.pending_conns,
+            multicast_groups: &mut self.multicast_groups,
+            dhcp: &mut self.dhcp,
         }
     }
 
+    pub(super) fn hardware_addr(&self) -> smoltcp::wire::HardwareAddress {
+        self.interface.hardware_addr()
+    }
+
     pub(super) fn ipv4_addr(&self) -> Option<smoltcp::wire::Ipv4Address> {
         self.interface.ipv4_addr()
     }
@@ -42,13 +58,71 @@ impl<E: Ext> PollableIface<E> {
     pub(super) fn prefix_len(&self) -> Option<u8> {
         self.interface
             .ip_addrs()
-            .first()
-            .map(|ip_addr| ip_addr.prefix_len())
+            .iter()
+            .find_map(|ip_cidr| match ip_cidr {
+                IpCidr::Ipv4(ipv4_cidr) => Some(ipv4_cidr.prefix_len()),
+                IpCidr::Ipv6(_) => None,
+            })
+    }
+
+    /// Replaces the IPv4 address and the prefix length of the interface.
+    ///
+    /// If `ipv4_cidr` is `None`, the IPv4 address will be removed.
+    pub(super) fn set_ipv4_cidr(&mut self, ipv4_cidr: Option<Ipv4Cidr>) {
+        self.interface.update_ip_addrs(|ip_addrs| {
+            ip_addrs.retain(|ip_cidr| !matches!(ip_cidr, IpCidr::Ipv4(_)));
+            if let Some(ipv4_cidr) = ipv4_cidr {
+                // Removing the old IPv4 address ensures that there is room for the new one.
+                ip_addrs.push(IpCidr::Ipv4(ipv4_cidr)).unwrap();
+            }
+        });
+    }
+
+    pub(super) fn ipv6_addr(&self) -> Option<smoltcp::wire::Ipv6Address> {
+        self.interface.ipv6_addr()
+    }
+
+    pub(super) fn ipv6_prefix_len(&self) -> Option<u8> {
+        self.interface
+            .ip_addrs()
+            .iter()
+            .find_map(|ip_cidr| match ip_cidr {
+                IpCidr::Ipv4(_) => None,
+                IpCidr::Ipv6(ipv6_cidr) => Some(ipv6_cidr.prefix_len()),
+            })
+    }
+
+    pub(super) fn multicast_groups_mut(&mut self) -> &mut MulticastGroups {
+        &mut self.multicast_groups
+    }
+
+    /// Starts configuring the interface via DHCP.
+    ///
+    /// If a DHCP client is already running, it will be restarted.
+    pub(super) fn start_dhcp(&mut self, ether_addr: EthernetAddress, now: Instant) {
+        self.dhcp = Some(DhcpClient::new(ether_addr, now));
+    }
+
+    pub(super) fn dhcp(&self) -> Option<&DhcpClient> {
+        self.dhcp.as_ref()
+    }
+
+    pub(super) fn dhcp_mut(&mut self) -> Option<&mut DhcpClient> {
+        self.dhcp.as_mut()
     }
 
     /// Returns the next poll time.
     pub(super) fn next_poll_at_ms(&self) -> Option<u64> {
-        self.pending_conns.next_poll_at_ms()
+        let dhcp_poll_at_ms = self
+            .dhcp
+            .as_ref()
+            .map(|dhcp| dhcp.poll_at().total_millis() as u64);
+
+        self.pending_conns
+            .next_poll_at_ms()
+            .into_iter()
+            .chain(dhcp_poll_at_ms)
+            .min()
     }
 }
 
@@ -79,6 +153,8 @@ impl<E: Ext> PollableIface<E> {
 pub(crate) struct PollableIfaceMut<'a, E: Ext> {
     context: &'a mut smoltcp::iface::Context,
     pending_conns: &'a mut PendingConnSet<E>,
+    multicast_groups: &'a mut MulticastGroups,
+    dhcp: &'a mut Option<DhcpClient>,
 }
 
 // FIXME: We provide `new()` and `inner_mut()` as `pub(crate)` methods because it's necessary to
@@ -88,15 +164,31 @@ impl<'a, E: Ext> PollableIfaceMut<'a, E> {
     pub(crate) fn new(
         context: &'a mut smoltcp::iface::Context,
         pending_conns: &'a mut PendingConnSet<E>,
+        multicast_groups: &'a mut MulticastGroups,
+        dhcp: &'a mut Option<DhcpClient>,
     ) -> Self {
         Self {
             context,
             pending_conns,
+            multicast_groups,
+            dhcp,
         }
     }
 
-    pub(crate) fn inner_mut(&mut self) -> (&mut smoltcp::iface::Context, &mut PendingConnSet<E>) {
-        (self.context, self.pending_conns)
+    pub(crate) fn inner_mut(
+        &mut self,
+    ) -> (
+        &mut smoltcp::iface::Context,
+        &mut PendingConnSet<E>,
+        &mut MulticastGroups,
+        &mut Option<DhcpClient>,
+    ) {
+        (
+            self.context,
+            self.pending_conns,
+            self.multicast_groups,
+            self.dhcp,
+        )
     }
 }
 
@@ -105,6 +197,18 @@ impl<E: Ext> PollableIfaceMut<'_, E> {
         let now = self.context.now.total_millis() as u64;
         self.pending_conns.pop_tcp_before_now(now)
     }
+
+    pub(super) fn multicast_groups(&self) -> &MulticastGroups {
+        self.multicast_groups
+    }
+
+    pub(super) fn multicast_groups_mut(&mut self) -> &mut MulticastGroups {
+        self.multicast_groups
+    }
+
+    pub(super) fn dhcp_mut(&mut self) -> Option<&mut DhcpClient> {
+        self.dhcp.as_mut()
+    }
 }
 
 impl<E: Ext> PollableIfaceMut<'_, E> {
@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use smoltcp::wire::IpVersion;
+
 /// The configuration using for bind to a TCP/UDP port.
 pub enum BindPortConfig {
     /// Binds to the specified non-reusable port.
@@ -33,3 +35,45 @@ impl BindPortConfig {
         }
     }
 }
+
+/// The address to bind to a TCP/UDP port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindAddr {
+    /// Binds to the IPv4 address of the iface.
+    Ipv4,
+    /// Binds to the IPv6 address of the iface.
+    Ipv6,
+    /// Binds to the unspecified IPv6 address (i.e., `::`).
+    ///
+    /// If `v6only` is false, the port is also bound to the IPv4 address of the iface, so IPv4
+    /// packets can be received via IPv4-mapped IPv6 addresses.
+    Ipv6Any { v6only: bool },
+}
+
+impl BindAddr {
+    /// Returns the IP version of the bound address.
+    pub fn ip_version(&self) -> IpVersion {
+        match self {
+            Self::Ipv4 => IpVersion::Ipv4,
+            Self::Ipv6 | Self::Ipv6Any { .. } => IpVersion::Ipv6,
+        }
+    }
+
+    /// Returns the IP versions whose ports are occupied by the bound address.
+    pub(super) fn port_versions(&self) -> &'static [IpVersion] {
+        match self {
+            Self::Ipv4 => &[IpVersion::Ipv4],
+            Self::Ipv6 | Self::Ipv6Any { v6only: true } => &[IpVersion::Ipv6],
+            Self::Ipv6Any { v6only: false } => &[IpVersion::Ipv4, IpVersion::Ipv6],
+        }
+    }
+}
+
+impl From<IpVersion> for BindAddr {
+    fn from(ip_version: IpVersion) -> Self {
+        match ip_version {
+            IpVersion::Ipv4 => Self::Ipv4,
+            IpVersion::Ipv6 => Self::Ipv6,
+        }
+    }
+}
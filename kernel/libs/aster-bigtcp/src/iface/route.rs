@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::vec::Vec;
+
+use smoltcp::wire::{Ipv4Address, Ipv4Cidr};
+
+/// An IPv4 route that sends packets through an iface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Route {
+    /// The destination subnet.
+    pub dst: Ipv4Cidr,
+    /// The next-hop gateway.
+    ///
+    /// If this is `None`, the destination is directly reachable through the iface.
+    pub gateway: Option<Ipv4Address>,
+    /// The metric (also known as the priority). Lower metrics are preferred.
+    pub metric: u32,
+}
+
+impl Ipv4Route {
+    /// Returns whether the route has the same key as `other`.
+    ///
+    /// Routes in a route table are identified by their destination subnets and their metrics.
+    pub fn same_key(&self, other: &Ipv4Route) -> bool {
+        self.dst == other.dst && self.metric == other.metric
+    }
+}
+
+/// The IPv4 routes of an iface.
+pub(super) struct RouteTable {
+    routes: Vec<Ipv4Route>,
+}
+
+impl RouteTable {
+    pub(super) const fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    pub(super) fn routes(&self) -> &[Ipv4Route] {
+        &self.routes
+    }
+
+    /// Inserts a route.
+    ///
+    /// If there is already a route with the same key, it will be replaced and returned.
+    pub(super) fn insert(&mut self, route: Ipv4Route) -> Option<Ipv4Route> {
+        if let Some(old_route) = self.routes.iter_mut().find(|old| old.same_key(&route)) {
+            return Some(core::mem::replace(old_route, route));
+        }
+
+        self.routes.push(route);
+        None
+    }
+
+    /// Removes the route with the destination subnet `dst` and the metric `metric`.
+    pub(super) fn remove(&mut self, dst: Ipv4Cidr, metric: u32) -> Option<Ipv4Route> {
+        let index = self
+            .routes
+            .iter()
+            .position(|route| route.dst == dst && route.metric == metric)?;
+        Some(self.routes.remove(index))
+    }
+
+    /// Looks up the best route to `dst_addr`.
+    ///
+    /// The route with the longest prefix is the best one. Among routes with the same prefix
+    /// length, the one with the lowest metric is preferred.
+    pub(super) fn lookup(&self, dst_addr: &Ipv4Address) -> Option<&Ipv4Route> {
+        self.routes
+            .iter()
+            .filter(|route| route.dst.contains_addr(dst_addr))
+            .min_by_key(|route| (u8::MAX - route.dst.prefix_len(), route.metric))
+    }
+}
@@ -9,17 +9,18 @@ use takeable::Takeable;
 use crate::{
     define_boolean_value,
     ext::Ext,
-    iface::{BoundPort, Iface},
+    iface::{BoundPort, Iface, IfaceCommon},
     socket::event::{SocketEventObserver, SocketEvents},
 };
 
 pub struct Socket<T: Inner<E>, E: Ext>(pub(super) Takeable<Arc<SocketBg<T, E>>>);
 
-/// [`TcpConnectionInner`], [`TcpListenerInner`], or [`UdpSocketInner`].
+/// [`TcpConnectionInner`], [`TcpListenerInner`], [`UdpSocketInner`], or [`IcmpSocketInner`].
 ///
 /// [`TcpConnectionInner`]: super::tcp_conn::TcpConnectionInner
 /// [`TcpListenerInner`]: super::tcp_listen::TcpListenerInner
 /// [`UdpSocketInner`]: super::udp::UdpSocketInner
+/// [`IcmpSocketInner`]: super::icmp::IcmpSocketInner
 pub trait Inner<E: Ext> {
     type Observer: SocketEventObserver;
 
@@ -28,6 +29,14 @@ pub trait Inner<E: Ext> {
     where
         E: Ext,
         Self: Sized;
+
+    /// Returns the local endpoint, if it is more specific than the bound endpoint.
+    ///
+    /// For example, a connection has a specific local address even if its port is bound to the
+    /// unspecified address.
+    fn local_endpoint(&self) -> Option<IpEndpoint> {
+        None
+    }
 }
 
 /// Common states shared by [`TcpConnectionBg`], [`TcpListenerBg`], and [`UdpSocketBg`].
@@ -92,7 +101,10 @@ impl<T: Inner<E>, E: Ext> Socket<T, E> {
     }
 
     pub fn local_endpoint(&self) -> Option<IpEndpoint> {
-        self.0.bound.endpoint()
+        self.0
+            .inner
+            .local_endpoint()
+            .or_else(|| self.0.bound.endpoint())
     }
 
     pub fn iface(&self) -> &Arc<dyn Iface<E>> {
@@ -138,4 +150,12 @@ impl<T: Inner<E>, E: Ext> SocketBg<T, E> {
     pub(crate) fn can_process(&self, dst_port: u16) -> bool {
         self.bound.port() == dst_port
     }
+
+    /// Returns whether the socket is bound to the iface whose common part is `common`.
+    ///
+    /// A socket bound to the unspecified address may also be in the socket tables of other ifaces
+    /// to receive packets from them.
+    pub(crate) fn is_bound_to(&self, common: &IfaceCommon<E>) -> bool {
+        core::ptr::eq(self.bound.iface().common(), common)
+    }
 }
@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_softirq::BottomHalfDisabled;
+use ostd::sync::SpinLock;
+use smoltcp::wire::{
+    Icmpv4DstUnreachable, Icmpv4Message, Icmpv4Packet, IpAddress, IpProtocol, Ipv4Address, Ipv4Repr,
+};
+
+use super::{
+    common::{Inner, Socket, SocketBg},
+    queue::PacketQueue,
+    raw::new_ipv4_packet,
+};
+use crate::{
+    errors::icmp::{RecvError, SendError},
+    ext::Ext,
+    iface::BoundPort,
+    socket::{
+        event::SocketEvents,
+        unbound::{ICMP_RECV_BUF_LEN, ICMP_SEND_BUF_LEN},
+    },
+};
+
+/// An ICMP socket that sends ICMP echo requests and receives ICMP echo replies.
+///
+/// This is also known as a ping socket. The identifier of the echo requests is the port number of
+/// the bound port.
+pub type IcmpSocket<E> = Socket<IcmpSocketInner, E>;
+
+/// States needed by [`IcmpSocketBg`].
+pub struct IcmpSocketInner {
+    state: SpinLock<IcmpState, BottomHalfDisabled>,
+    need_dispatch: AtomicBool,
+}
+
+struct IcmpState {
+    recv_queue: PacketQueue<IpAddress>,
+    send_queue: PacketQueue<IpAddress>,
+    error: Option<IcmpError>,
+}
+
+/// An ICMP error message received in response to an echo request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcmpError {
+    DstUnreachable(Icmpv4DstUnreachable),
+    TimeExceeded,
+    ParamProblem,
+}
+
+impl IcmpError {
+    /// Creates an error from the type and the code of an ICMP error message.
+    pub(crate) fn new(msg_type: Icmpv4Message, code: u8) -> Option<Self> {
+        match msg_type {
+            Icmpv4Message::DstUnreachable => Some(Self::DstUnreachable(code.into())),
+            Icmpv4Message::TimeExceeded => Some(Self::TimeExceeded),
+            Icmpv4Message::ParamProblem => Some(Self::ParamProblem),
+            _ => None,
+        }
+    }
+}
+
+impl<E: Ext> Inner<E> for IcmpSocketInner {
+    type Observer = E::IcmpEventObserver;
+
+    fn on_drop(this: &Arc<SocketBg<Self, E>>) {
+        // An ICMP socket can be removed immediately.
+        this.bound.iface().common().remove_icmp_socket(this);
+    }
+}
+
+pub(crate) type IcmpSocketBg<E> = SocketBg<IcmpSocketInner, E>;
+
+impl<E: Ext> IcmpSocketBg<E> {
+    /// Tries to process an incoming echo reply and returns whether the reply is processed.
+    pub(crate) fn process(&self, src_addr: Ipv4Address, icmp_packet: &[u8]) -> bool {
+        let mut state = self.inner.state.lock();
+
+        if !state
+            .recv_queue
+            .push(IpAddress::Ipv4(src_addr), icmp_packet.to_vec())
+        {
+            return false;
+        }
+        drop(state);
+
+        self.notify_events(SocketEvents::CAN_RECV);
+
+        true
+    }
+
+    /// Records an incoming ICMP error message.
+    pub(crate) fn process_error(&self, error: IcmpError) {
+        self.inner.state.lock().error = Some(error);
+
+        self.notify_events(SocketEvents::ERROR);
+    }
+
+    /// Dequeues an outgoing packet, which is a whole IP packet including the IP header.
+    pub(crate) fn dispatch(&self) -> Option<Vec<u8>> {
+        let mut state = self.inner.state.lock();
+
+        let packet = state.send_queue.pop();
+        self.inner
+            .need_dispatch
+            .store(!state.send_queue.is_empty(), Ordering::Relaxed);
+        drop(state);
+
+        let (_, packet) = packet?;
+
+        // For ICMP sockets, dequeuing a packet means that we can queue more packets.
+        self.notify_events(SocketEvents::CAN_SEND);
+
+        Some(packet)
+    }
+
+    /// Returns whether the socket _may_ generate an outgoing packet.
+    ///
+    /// The check is intended to be lock-free and fast, but may have false positives.
+    pub(crate) fn need_dispatch(&self) -> bool {
+        self.inner.need_dispatch.load(Ordering::Relaxed)
+    }
+}
+
+impl<E: Ext> IcmpSocket<E> {
+    /// Binds to a specified endpoint, whose port number will be used as the echo identifier.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn new_bind(bound: BoundPort<E>, observer: E::IcmpEventObserver) -> Self {
+        let inner = IcmpSocketInner {
+            state: SpinLock::new(IcmpState {
+                recv_queue: PacketQueue::new(ICMP_RECV_BUF_LEN),
+                send_queue: PacketQueue::new(ICMP_SEND_BUF_LEN),
+                error: None,
+            }),
+            need_dispatch: AtomicBool::new(false),
+        };
+
+        let socket = Self::new(bound, inner);
+        socket.init_observer(observer);
+        socket
+            .iface()
+            .common()
+            .register_icmp_socket(socket.inner().clone());
+
+        socket
+    }
+
+    /// Sends an ICMP echo request.
+    ///
+    /// The echo identifier and the checksum in `icmp_packet` will be filled in.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn send(
+        &self,
+        dst_addr: Ipv4Address,
+        hop_limit: u8,
+        icmp_packet: &[u8],
+    ) -> Result<(), SendError> {
+        let Some(IpAddress::Ipv4(src_addr)) = self.local_endpoint().map(|endpoint| endpoint.addr)
+        else {
+            return Err(SendError::Unaddressable);
+        };
+
+        let mut icmp_packet = icmp_packet.to_vec();
+        {
+            let Ok(mut packet) = Icmpv4Packet::new_checked(icmp_packet.as_mut_slice()) else {
+                return Err(SendError::Malformed);
+            };
+            if packet.msg_type() != Icmpv4Message::EchoRequest || packet.msg_code() != 0 {
+                return Err(SendError::Malformed);
+            }
+            packet.set_echo_ident(self.bound_port().port());
+            packet.fill_checksum();
+        }
+
+        let ip_repr = Ipv4Repr {
+            src_addr,
+            dst_addr,
+            next_header: IpProtocol::Icmp,
+            payload_len: icmp_packet.len(),
+            hop_limit,
+        };
+        let packet = new_ipv4_packet(&ip_repr, &icmp_packet)?;
+
+        let mut state = self.0.inner.state.lock();
+
+        if packet.len() > state.send_queue.capacity() {
+            return Err(SendError::TooLarge);
+        }
+
+        if !state.send_queue.push(IpAddress::Ipv4(dst_addr), packet) {
+            return Err(SendError::BufferFull);
+        }
+
+        self.0.inner.need_dispatch.store(true, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Receives an ICMP echo reply.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn recv<F, R>(&self, f: F) -> Result<R, RecvError>
+    where
+        F: FnOnce(&[u8], IpAddress) -> R,
+    {
+        let mut state = self.0.inner.state.lock();
+
+        let Some((src_addr, packet)) = state.recv_queue.pop() else {
+            return Err(RecvError::Exhausted);
+        };
+        drop(state);
+
+        Ok(f(&packet, src_addr))
+    }
+
+    /// Takes the pending ICMP error, if any.
+    pub fn take_error(&self) -> Option<IcmpError> {
+        self.0.inner.state.lock().error.take()
+    }
+
+    /// Returns whether there are echo replies to receive.
+    pub fn can_recv(&self) -> bool {
+        !self.0.inner.state.lock().recv_queue.is_empty()
+    }
+
+    /// Returns whether there is space to send echo requests.
+    pub fn can_send(&self) -> bool {
+        self.0.inner.state.lock().send_queue.can_push(1)
+    }
+
+    /// Returns whether there is a pending ICMP error.
+    pub fn has_error(&self) -> bool {
+        self.0.inner.state.lock().error.is_some()
+    }
+}
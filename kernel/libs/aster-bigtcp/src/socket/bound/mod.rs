@@ -1,14 +1,24 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod common;
+mod icmp;
+mod packet;
+mod queue;
+mod raw;
 mod tcp_conn;
 mod tcp_listen;
 mod udp;
 
 pub use common::NeedIfacePoll;
+pub(crate) use icmp::IcmpSocketBg;
+pub use icmp::{IcmpError, IcmpSocket};
+pub(crate) use packet::PacketSocketBg;
+pub use packet::{PacketInfo, PacketSocket, PacketType, ETH_P_ALL};
+pub use raw::RawIpSocket;
+pub(crate) use raw::RawIpSocketBg;
 pub use tcp_conn::{ConnectState, RawTcpSocketExt, TcpConnection};
 pub(crate) use tcp_conn::{TcpConnectionBg, TcpProcessResult};
 pub use tcp_listen::TcpListener;
 pub(crate) use tcp_listen::TcpListenerBg;
-pub use udp::UdpSocket;
 pub(crate) use udp::UdpSocketBg;
+pub use udp::{UdpIcmpError, UdpRecvInfo, UdpSocket};
@@ -0,0 +1,298 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_softirq::BottomHalfDisabled;
+use ostd::sync::SpinLock;
+use smoltcp::wire::{
+    EthernetAddress, EthernetFrame, EthernetRepr, HardwareAddress, ETHERNET_HEADER_LEN,
+};
+use spin::once::Once;
+
+use super::queue::PacketQueue;
+use crate::{
+    errors::packet::{RecvError, SendError},
+    ext::Ext,
+    iface::Iface,
+    socket::{
+        event::SocketEvents,
+        unbound::{PACKET_RECV_BUF_LEN, PACKET_SEND_BUF_LEN},
+    },
+};
+
+/// The protocol that matches frames of all protocols.
+///
+/// This is `ETH_P_ALL` in Linux.
+pub const ETH_P_ALL: u16 = 0x0003;
+
+/// The type of a frame received by a packet socket.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/if_packet.h#L26>.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketType {
+    /// The frame is destined for the local host.
+    Host = 0,
+    /// The frame is a broadcast frame.
+    Broadcast = 1,
+    /// The frame is a multicast frame.
+    Multicast = 2,
+    /// The frame is destined for another host.
+    OtherHost = 3,
+    /// The frame is sent by the local host.
+    Outgoing = 4,
+}
+
+/// Information about a frame received by a packet socket.
+#[derive(Debug, Clone, Copy)]
+pub struct PacketInfo {
+    /// The protocol of the frame (i.e., the EtherType).
+    pub protocol: u16,
+    /// The type of the frame.
+    pub pkt_type: PacketType,
+    /// The source hardware address of the frame.
+    pub src_addr: EthernetAddress,
+}
+
+/// A packet socket attached to an iface.
+///
+/// A packet socket receives copies of the link-layer frames on the iface whose protocols match
+/// the protocol of the socket. It can also send link-layer frames to the iface directly.
+///
+/// Frames are always in the Ethernet format. For ifaces without link-layer headers (e.g., the
+/// loopback iface), Ethernet headers with zero addresses are used, as Linux does.
+pub struct PacketSocket<E: Ext>(Arc<PacketSocketBg<E>>);
+
+/// The background part of [`PacketSocket`].
+pub(crate) struct PacketSocketBg<E: Ext> {
+    iface: Arc<dyn Iface<E>>,
+    protocol: u16,
+    is_cooked: bool,
+    state: SpinLock<PacketState<E>, BottomHalfDisabled>,
+    need_dispatch: AtomicBool,
+    observer: Once<E::PacketEventObserver>,
+}
+
+struct PacketState<E: Ext> {
+    recv_queue: PacketQueue<PacketInfo>,
+    send_queue: PacketQueue<()>,
+    filter: Option<Arc<E::PacketFilter>>,
+}
+
+impl<E: Ext> PacketSocketBg<E> {
+    /// Tries to process a frame and returns whether the frame is processed.
+    pub(crate) fn process(
+        &self,
+        ether_repr: &EthernetRepr,
+        payload: &[u8],
+        pkt_type: PacketType,
+    ) -> bool {
+        let protocol = u16::from(ether_repr.ethertype);
+
+        // Outgoing frames are only delivered to sockets that capture all protocols. See
+        // `dev_queue_xmit_nit` in Linux.
+        let is_matched = match self.protocol {
+            0 => false,
+            ETH_P_ALL => true,
+            _ => self.protocol == protocol && pkt_type != PacketType::Outgoing,
+        };
+        if !is_matched {
+            return false;
+        }
+
+        // Cooked sockets receive frames without link-layer headers.
+        let mut packet = if self.is_cooked {
+            payload.to_vec()
+        } else {
+            new_ether_frame(ether_repr, payload)
+        };
+
+        let mut state = self.state.lock();
+
+        if let Some(filter) = state.filter.as_ref() {
+            let accepted_len = filter.run(&packet);
+            if accepted_len == 0 {
+                return false;
+            }
+            packet.truncate(accepted_len);
+        }
+
+        let info = PacketInfo {
+            protocol,
+            pkt_type,
+            src_addr: ether_repr.src_addr,
+        };
+        if !state.recv_queue.push(info, packet) {
+            return false;
+        }
+        drop(state);
+
+        self.notify_events(SocketEvents::CAN_RECV);
+
+        true
+    }
+
+    /// Dequeues an outgoing frame, which includes the Ethernet header.
+    pub(crate) fn dispatch(&self) -> Option<Vec<u8>> {
+        let mut state = self.state.lock();
+
+        let frame = state.send_queue.pop();
+        self.need_dispatch
+            .store(!state.send_queue.is_empty(), Ordering::Relaxed);
+        drop(state);
+
+        let ((), frame) = frame?;
+
+        // For packet sockets, dequeuing a frame means that we can queue more frames.
+        self.notify_events(SocketEvents::CAN_SEND);
+
+        Some(frame)
+    }
+
+    /// Returns whether the socket _may_ generate an outgoing frame.
+    ///
+    /// The check is intended to be lock-free and fast, but may have false positives.
+    pub(crate) fn need_dispatch(&self) -> bool {
+        self.need_dispatch.load(Ordering::Relaxed)
+    }
+
+    fn notify_events(&self, new_events: SocketEvents) {
+        if let Some(observer) = self.observer.get() {
+            observer.on_events(new_events);
+        }
+    }
+}
+
+impl<E: Ext> PacketSocket<E> {
+    /// Creates a packet socket that receives and sends frames of `protocol` on `iface`.
+    ///
+    /// If `protocol` is [`ETH_P_ALL`], the socket will receive frames of all protocols,
+    /// including outgoing frames. If `protocol` is zero, the socket will receive nothing.
+    ///
+    /// If `is_cooked` is true, the Ethernet headers will be removed from the received frames.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn new(
+        iface: Arc<dyn Iface<E>>,
+        protocol: u16,
+        is_cooked: bool,
+        observer: E::PacketEventObserver,
+    ) -> Self {
+        let state = PacketState {
+            recv_queue: PacketQueue::new(PACKET_RECV_BUF_LEN),
+            send_queue: PacketQueue::new(PACKET_SEND_BUF_LEN),
+            filter: None,
+        };
+
+        let socket = Arc::new(PacketSocketBg {
+            iface,
+            protocol,
+            is_cooked,
+            state: SpinLock::new(state),
+            need_dispatch: AtomicBool::new(false),
+            observer: Once::new(),
+        });
+        socket.observer.call_once(|| observer);
+
+        socket.iface.common().register_packet_socket(socket.clone());
+
+        Self(socket)
+    }
+
+    /// Returns a reference to the iface.
+    pub fn iface(&self) -> &Arc<dyn Iface<E>> {
+        &self.0.iface
+    }
+
+    /// Sets the filter for incoming frames.
+    ///
+    /// Frames that are already received are not affected.
+    pub fn set_filter(&self, filter: Option<Arc<E::PacketFilter>>) {
+        self.0.state.lock().filter = filter;
+    }
+
+    /// Sends a frame, which must include the Ethernet header.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn send(&self, frame: &[u8]) -> Result<(), SendError> {
+        if EthernetFrame::new_checked(frame).is_err() {
+            return Err(SendError::Malformed);
+        }
+
+        if frame.len() > self.max_frame_len() {
+            return Err(SendError::TooLarge);
+        }
+
+        let mut state = self.0.state.lock();
+
+        if frame.len() > state.send_queue.capacity() {
+            return Err(SendError::TooLarge);
+        }
+
+        if !state.send_queue.push((), frame.to_vec()) {
+            return Err(SendError::BufferFull);
+        }
+
+        self.0.need_dispatch.store(true, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    fn max_frame_len(&self) -> usize {
+        let mtu = self.0.iface.mtu();
+
+        // For Ethernet devices, the MTU already includes the Ethernet header.
+        match self.0.iface.hardware_addr() {
+            HardwareAddress::Ethernet(_) => mtu,
+            _ => mtu + ETHERNET_HEADER_LEN,
+        }
+    }
+
+    /// Receives a frame.
+    ///
+    /// The frame includes the Ethernet header, unless the socket is cooked.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn recv<F, R>(&self, f: F) -> Result<R, RecvError>
+    where
+        F: FnOnce(&[u8], &PacketInfo) -> R,
+    {
+        let mut state = self.0.state.lock();
+
+        let Some((info, packet)) = state.recv_queue.pop() else {
+            return Err(RecvError::Exhausted);
+        };
+        drop(state);
+
+        Ok(f(&packet, &info))
+    }
+
+    /// Returns whether there are frames to receive.
+    pub fn can_recv(&self) -> bool {
+        !self.0.state.lock().recv_queue.is_empty()
+    }
+
+    /// Returns whether there is space to send frames.
+    pub fn can_send(&self) -> bool {
+        self.0.state.lock().send_queue.can_push(1)
+    }
+}
+
+impl<E: Ext> Drop for PacketSocket<E> {
+    fn drop(&mut self) {
+        // A packet socket can be removed immediately.
+        self.0.iface.common().remove_packet_socket(&self.0);
+    }
+}
+
+/// Builds an Ethernet frame with the header described by `ether_repr` and the payload.
+fn new_ether_frame(ether_repr: &EthernetRepr, payload: &[u8]) -> Vec<u8> {
+    let mut data = vec![0; ether_repr.buffer_len() + payload.len()];
+
+    let mut frame = EthernetFrame::new_unchecked(data.as_mut_slice());
+    ether_repr.emit(&mut frame);
+    frame.payload_mut().copy_from_slice(payload);
+
+    data
+}
@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{collections::vec_deque::VecDeque, vec::Vec};
+
+/// A queue of packets whose total length is limited.
+///
+/// Each packet is stored together with its metadata, such as the remote address of the packet.
+pub(super) struct PacketQueue<M> {
+    packets: VecDeque<(M, Vec<u8>)>,
+    len: usize,
+    capacity: usize,
+}
+
+impl<M> PacketQueue<M> {
+    pub(super) const fn new(capacity: usize) -> Self {
+        Self {
+            packets: VecDeque::new(),
+            len: 0,
+            capacity,
+        }
+    }
+
+    /// Returns the maximum total length of the packets in the queue.
+    pub(super) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns whether a packet of `size` bytes can be pushed into the queue.
+    pub(super) fn can_push(&self, size: usize) -> bool {
+        self.len + size <= self.capacity
+    }
+
+    /// Pushes a packet into the queue.
+    ///
+    /// If there is not enough space, the packet will be dropped and this method will return
+    /// `false`.
+    pub(super) fn push(&mut self, meta: M, packet: Vec<u8>) -> bool {
+        if !self.can_push(packet.len()) {
+            return false;
+        }
+
+        self.len += packet.len();
+        self.packets.push_back((meta, packet));
+
+        true
+    }
+
+    /// Pops the oldest packet from the queue.
+    pub(super) fn pop(&mut self) -> Option<(M, Vec<u8>)> {
+        let (meta, packet) = self.packets.pop_front()?;
+        self.len -= packet.len();
+
+        Some((meta, packet))
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+}
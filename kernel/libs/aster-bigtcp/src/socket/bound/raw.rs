@@ -0,0 +1,316 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_softirq::BottomHalfDisabled;
+use ostd::sync::SpinLock;
+use smoltcp::{
+    phy::ChecksumCapabilities,
+    wire::{IpAddress, IpProtocol, Ipv4Address, Ipv4Packet, Ipv4Repr},
+};
+use spin::once::Once;
+
+use super::queue::PacketQueue;
+use crate::{
+    errors::raw::{RecvError, SendError},
+    ext::Ext,
+    iface::Iface,
+    socket::{
+        event::SocketEvents,
+        unbound::{RAW_RECV_BUF_LEN, RAW_SEND_BUF_LEN},
+    },
+};
+
+const IPPROTO_RAW: IpProtocol = IpProtocol::Unknown(255);
+
+/// A raw IPv4 socket attached to an iface.
+///
+/// Unlike other sockets, a raw socket is not bound to a port. Instead, it receives copies of all
+/// incoming IP packets whose protocol matches the protocol of the socket.
+pub struct RawIpSocket<E: Ext>(Arc<RawIpSocketBg<E>>);
+
+/// The background part of [`RawIpSocket`].
+pub(crate) struct RawIpSocketBg<E: Ext> {
+    iface: Arc<dyn Iface<E>>,
+    ip_protocol: IpProtocol,
+    state: SpinLock<RawIpState<E>, BottomHalfDisabled>,
+    need_dispatch: AtomicBool,
+    observer: Once<E::RawEventObserver>,
+}
+
+struct RawIpState<E: Ext> {
+    local_addr: Option<Ipv4Address>,
+    remote_addr: Option<Ipv4Address>,
+    filter: Option<Arc<E::PacketFilter>>,
+    recv_queue: PacketQueue<IpAddress>,
+    send_queue: PacketQueue<IpAddress>,
+}
+
+impl<E: Ext> RawIpSocketBg<E> {
+    /// Tries to process an incoming packet and returns whether the packet is processed.
+    ///
+    /// The packet should be the whole IP packet, including the IP header.
+    pub(crate) fn process(&self, ip_repr: &Ipv4Repr, packet: &[u8]) -> bool {
+        // Raw sockets with `IPPROTO_RAW` are send-only. See
+        // <https://man7.org/linux/man-pages/man7/raw.7.html>.
+        if self.ip_protocol != ip_repr.next_header || self.ip_protocol == IPPROTO_RAW {
+            return false;
+        }
+
+        let mut state = self.state.lock();
+
+        if state
+            .local_addr
+            .is_some_and(|local_addr| local_addr != ip_repr.dst_addr)
+            || state
+                .remote_addr
+                .is_some_and(|remote_addr| remote_addr != ip_repr.src_addr)
+        {
+            return false;
+        }
+
+        let mut packet = packet.to_vec();
+
+        if let Some(filter) = state.filter.as_ref() {
+            let accepted_len = filter.run(&packet);
+            if accepted_len == 0 {
+                return false;
+            }
+            packet.truncate(accepted_len);
+        }
+
+        if !state
+            .recv_queue
+            .push(IpAddress::Ipv4(ip_repr.src_addr), packet)
+        {
+            return false;
+        }
+        drop(state);
+
+        self.notify_events(SocketEvents::CAN_RECV);
+
+        true
+    }
+
+    /// Dequeues an outgoing packet, which is a whole IP packet including the IP header.
+    pub(crate) fn dispatch(&self) -> Option<Vec<u8>> {
+        let mut state = self.state.lock();
+
+        let packet = state.send_queue.pop();
+        self.need_dispatch
+            .store(!state.send_queue.is_empty(), Ordering::Relaxed);
+        drop(state);
+
+        let (_, packet) = packet?;
+
+        // For raw sockets, dequeuing a packet means that we can queue more packets.
+        self.notify_events(SocketEvents::CAN_SEND);
+
+        Some(packet)
+    }
+
+    /// Returns whether the socket _may_ generate an outgoing packet.
+    ///
+    /// The check is intended to be lock-free and fast, but may have false positives.
+    pub(crate) fn need_dispatch(&self) -> bool {
+        self.need_dispatch.load(Ordering::Relaxed)
+    }
+
+    fn notify_events(&self, new_events: SocketEvents) {
+        if let Some(observer) = self.observer.get() {
+            observer.on_events(new_events);
+        }
+    }
+}
+
+impl<E: Ext> RawIpSocket<E> {
+    /// Creates a raw socket that receives and sends packets of `ip_protocol` on `iface`.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn new(
+        iface: Arc<dyn Iface<E>>,
+        ip_protocol: IpProtocol,
+        observer: E::RawEventObserver,
+    ) -> Self {
+        let state = RawIpState {
+            local_addr: None,
+            remote_addr: None,
+            filter: None,
+            recv_queue: PacketQueue::new(RAW_RECV_BUF_LEN),
+            send_queue: PacketQueue::new(RAW_SEND_BUF_LEN),
+        };
+
+        let socket = Arc::new(RawIpSocketBg {
+            iface,
+            ip_protocol,
+            state: SpinLock::new(state),
+            need_dispatch: AtomicBool::new(false),
+            observer: Once::new(),
+        });
+        socket.observer.call_once(|| observer);
+
+        socket.iface.common().register_raw_socket(socket.clone());
+
+        Self(socket)
+    }
+
+    /// Returns a reference to the iface.
+    pub fn iface(&self) -> &Arc<dyn Iface<E>> {
+        &self.0.iface
+    }
+
+    /// Sets the local address.
+    ///
+    /// If the local address is set, only packets destined for the address will be received, and
+    /// the address will be used as the source address of outgoing packets.
+    pub fn set_local_addr(&self, local_addr: Option<Ipv4Address>) {
+        self.0.state.lock().local_addr = local_addr;
+    }
+
+    /// Sets the remote address.
+    ///
+    /// If the remote address is set, only packets coming from the address will be received.
+    pub fn set_remote_addr(&self, remote_addr: Option<Ipv4Address>) {
+        self.0.state.lock().remote_addr = remote_addr;
+    }
+
+    /// Sets the filter for incoming packets.
+    ///
+    /// Packets that are already received are not affected.
+    pub fn set_filter(&self, filter: Option<Arc<E::PacketFilter>>) {
+        self.0.state.lock().filter = filter;
+    }
+
+    /// Sends a packet whose IP header will be generated.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn send(
+        &self,
+        dst_addr: Ipv4Address,
+        hop_limit: u8,
+        payload: &[u8],
+    ) -> Result<(), SendError> {
+        let ip_repr = Ipv4Repr {
+            src_addr: self.src_addr()?,
+            dst_addr,
+            next_header: self.0.ip_protocol,
+            payload_len: payload.len(),
+            hop_limit,
+        };
+        let packet = new_ipv4_packet(&ip_repr, payload)?;
+
+        self.enqueue(dst_addr, packet)
+    }
+
+    /// Sends a packet whose IP header is included in `packet`.
+    ///
+    /// The checksum and the total length of the IP header will always be filled in. If the
+    /// source address is unspecified, it will also be filled in. The TOS will be kept as is.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn send_with_header(&self, packet: &[u8]) -> Result<(), SendError> {
+        let (mut ip_repr, dscp, ecn, payload) = {
+            let Ok(ip_packet) = Ipv4Packet::new_checked(packet) else {
+                return Err(SendError::Malformed);
+            };
+            let Ok(ip_repr) = Ipv4Repr::parse(&ip_packet, &ChecksumCapabilities::ignored()) else {
+                return Err(SendError::Malformed);
+            };
+            (
+                ip_repr,
+                ip_packet.dscp(),
+                ip_packet.ecn(),
+                ip_packet.payload(),
+            )
+        };
+
+        if ip_repr.src_addr.is_unspecified() {
+            ip_repr.src_addr = self.src_addr()?;
+        }
+        let mut packet = new_ipv4_packet(&ip_repr, payload)?;
+
+        // `Ipv4Repr` does not record the TOS, so it has to be copied separately.
+        let mut ip_packet = Ipv4Packet::new_unchecked(packet.as_mut_slice());
+        ip_packet.set_dscp(dscp);
+        ip_packet.set_ecn(ecn);
+        ip_packet.fill_checksum();
+
+        self.enqueue(ip_repr.dst_addr, packet)
+    }
+
+    fn src_addr(&self) -> Result<Ipv4Address, SendError> {
+        // Note that the lock on the iface cannot be acquired while holding the lock on the socket
+        // state. So we have to release the latter first.
+        let local_addr = self.0.state.lock().local_addr;
+
+        local_addr
+            .or_else(|| self.0.iface.ipv4_addr())
+            .ok_or(SendError::Unaddressable)
+    }
+
+    fn enqueue(&self, dst_addr: Ipv4Address, packet: Vec<u8>) -> Result<(), SendError> {
+        let mut state = self.0.state.lock();
+
+        if packet.len() > state.send_queue.capacity() {
+            return Err(SendError::TooLarge);
+        }
+
+        if !state.send_queue.push(IpAddress::Ipv4(dst_addr), packet) {
+            return Err(SendError::BufferFull);
+        }
+
+        self.0.need_dispatch.store(true, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Receives a packet, which is a whole IP packet including the IP header.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn recv<F, R>(&self, f: F) -> Result<R, RecvError>
+    where
+        F: FnOnce(&[u8], IpAddress) -> R,
+    {
+        let mut state = self.0.state.lock();
+
+        let Some((src_addr, packet)) = state.recv_queue.pop() else {
+            return Err(RecvError::Exhausted);
+        };
+        drop(state);
+
+        Ok(f(&packet, src_addr))
+    }
+
+    /// Returns whether there are packets to receive.
+    pub fn can_recv(&self) -> bool {
+        !self.0.state.lock().recv_queue.is_empty()
+    }
+
+    /// Returns whether there is space to send packets.
+    pub fn can_send(&self) -> bool {
+        self.0.state.lock().send_queue.can_push(1)
+    }
+}
+
+impl<E: Ext> Drop for RawIpSocket<E> {
+    fn drop(&mut self) {
+        // A raw socket can be removed immediately.
+        self.0.iface.common().remove_raw_socket(&self.0);
+    }
+}
+
+/// Builds an IPv4 packet with the header described by `ip_repr` and the payload.
+pub(super) fn new_ipv4_packet(ip_repr: &Ipv4Repr, payload: &[u8]) -> Result<Vec<u8>, SendError> {
+    let total_len = ip_repr.buffer_len() + payload.len();
+    if total_len > u16::MAX as usize {
+        return Err(SendError::TooLarge);
+    }
+
+    let mut data = vec![0; total_len];
+    let mut packet = Ipv4Packet::new_unchecked(data.as_mut_slice());
+    ip_repr.emit(&mut packet, &ChecksumCapabilities::default());
+    packet.payload_mut().copy_from_slice(payload);
+
+    Ok(data)
+}
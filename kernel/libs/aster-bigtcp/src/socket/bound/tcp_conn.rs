@@ -9,6 +9,7 @@ use core::ops::{Deref, DerefMut};
 use aster_softirq::BottomHalfDisabled;
 use ostd::sync::{SpinLock, SpinLockGuard};
 use smoltcp::{
+    iface::Context,
     socket::{tcp::State, PollAt},
     time::Duration,
     wire::{IpEndpoint, IpRepr, TcpControl, TcpRepr},
@@ -24,6 +25,7 @@ use crate::{
     ext::Ext,
     iface::{BoundPort, PollKey, PollableIfaceMut},
     socket::{
+        congestion::{Congestion, CongestionAlgorithm, CongestionInfo},
         event::SocketEvents,
         option::{RawTcpOption, RawTcpSetOption},
         unbound::{new_tcp_socket, RawTcpSocket},
@@ -48,6 +50,7 @@ pub struct RawTcpSocketExt<E: Ext> {
     is_recv_shut: bool,
     /// Indicates if the socket is closed by a RST packet.
     is_rst_closed: bool,
+    congestion: Congestion,
 }
 
 impl<E: Ext> Deref for RawTcpSocketExt<E> {
@@ -106,6 +109,16 @@ impl<E: Ext> RawTcpSocketExt<E> {
     pub fn is_rst_closed(&self) -> bool {
         self.is_rst_closed
     }
+
+    /// Returns the congestion control algorithm.
+    pub fn congestion_algorithm(&self) -> CongestionAlgorithm {
+        self.congestion.algorithm()
+    }
+
+    /// Returns statistics about the congestion control.
+    pub fn congestion_info(&self) -> CongestionInfo {
+        self.congestion.info()
+    }
 }
 
 define_boolean_value!(
@@ -212,11 +225,23 @@ impl<E: Ext> RawTcpSocketExt<E> {
 
         TcpConnBecameDead::FALSE
     }
+
+    /// Processes an incoming packet after the congestion control observes it.
+    fn process_with_congestion(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &IpRepr,
+        tcp_repr: &TcpRepr,
+    ) -> Option<(IpRepr, TcpRepr<'static>)> {
+        let tcp_repr = self.congestion.on_recv(tcp_repr, cx.now());
+        self.socket.process(cx, ip_repr, &tcp_repr)
+    }
 }
 
 impl<E: Ext> TcpConnectionInner<E> {
     pub(super) fn new(
         socket: Box<RawTcpSocket>,
+        congestion: Congestion,
         listener: Option<Arc<TcpListenerBg<E>>>,
         weak_self: &Weak<TcpConnectionBg<E>>,
     ) -> Self {
@@ -235,6 +260,7 @@ impl<E: Ext> TcpConnectionInner<E> {
             has_connected: false,
             is_recv_shut: false,
             is_rst_closed: false,
+            congestion,
         };
 
         TcpConnectionInner {
@@ -274,6 +300,10 @@ impl<E: Ext> Inner<E> for TcpConnectionInner<E> {
             "a connection must be either closed or reset before dropping"
         );
     }
+
+    fn local_endpoint(&self) -> Option<IpEndpoint> {
+        Some(self.connection_key.local_endpoint())
+    }
 }
 
 pub(crate) type TcpConnectionBg<E> = SocketBg<TcpConnectionInner<E>, E>;
@@ -294,7 +324,7 @@ impl<E: Ext> TcpConnection<E> {
         option: &RawTcpOption,
         observer: E::TcpEventObserver,
     ) -> Result<Self, (BoundPort<E>, ConnectError)> {
-        let Some(local_endpoint) = bound.endpoint() else {
+        let Some(local_endpoint) = bound.endpoint_for(remote_endpoint.addr.version()) else {
             return Err((bound, ConnectError::Unaddressable));
         };
 
@@ -323,8 +353,10 @@ impl<E: Ext> TcpConnection<E> {
             socket
         };
 
-        let connection =
-            Self::new_cyclic(bound, |weak| TcpConnectionInner::new(socket, None, weak));
+        let congestion = Congestion::new(option.congestion_algorithm);
+        let connection = Self::new_cyclic(bound, |weak| {
+            TcpConnectionInner::new(socket, congestion, None, weak)
+        });
         interface.update_next_poll_at_ms(&connection.0, PollAt::Now);
         connection.init_observer(observer);
 
@@ -420,10 +452,36 @@ impl<E: Ext> TcpConnection<E> {
         Ok((result, need_poll))
     }
 
+    /// Peeks at some data without removing it from the receive buffer.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn peek<F, R>(&self, f: F) -> Result<R, RecvError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        let mut socket = self.0.inner.lock();
+
+        if socket.is_recv_shut && socket.recv_queue() == 0 {
+            return Err(RecvError::Finished);
+        }
+        let size = socket.recv_queue();
+        let result = match socket.peek(size) {
+            Ok(data) => f(data),
+            Err(_) if socket.is_rst_closed => {
+                socket.is_rst_closed = false;
+                return Err(RecvError::ConnReset);
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(result)
+    }
+
     /// Checks if the socket is closed by a RST packet and clears the flag.
     ///
     /// This flag is set when the socket is closed by a RST packet, and cleared when the connection
-    /// reset error is reported via one of the [`Self::send`], [`Self::recv`], or this method.
+    /// reset error is reported via one of the [`Self::send`], [`Self::recv`], [`Self::peek`], or
+    /// this method.
     pub fn clear_rst_closed(&self) -> bool {
         let mut socket = self.0.inner.lock();
 
@@ -537,6 +595,11 @@ impl<E: Ext> RawTcpSetOption for TcpConnection<E> {
         let mut socket = self.0.inner.lock();
         socket.set_nagle_enabled(enabled);
     }
+
+    fn set_congestion_algorithm(&self, algorithm: CongestionAlgorithm) {
+        let mut socket = self.0.inner.lock();
+        socket.congestion.set_algorithm(algorithm);
+    }
 }
 
 impl<E: Ext> TcpConnectionBg<E> {
@@ -613,7 +676,7 @@ impl<E: Ext> TcpConnectionBg<E> {
         // to be queued.
         let mut events = SocketEvents::CAN_RECV | SocketEvents::CAN_SEND;
 
-        let result = match socket.process(iface.context_mut(), ip_repr, tcp_repr) {
+        let result = match socket.process_with_congestion(iface.context_mut(), ip_repr, tcp_repr) {
             None => TcpProcessResult::Processed,
             Some((ip_repr, tcp_repr)) => TcpProcessResult::ProcessedWithReply(ip_repr, tcp_repr),
         };
@@ -647,10 +710,20 @@ impl<E: Ext> TcpConnectionBg<E> {
         let mut events = SocketEvents::empty();
 
         let mut reply = None;
-        let (cx, pending) = iface.inner_mut();
-        socket
+        let (cx, pending, multicast_groups, dhcp) = iface.inner_mut();
+        let RawTcpSocketExt {
+            socket: raw_socket,
+            congestion,
+            ..
+        } = &mut *socket;
+        raw_socket
             .dispatch(cx, |cx, (ip_repr, tcp_repr)| {
-                reply = dispatch(PollableIfaceMut::new(cx, pending), &ip_repr, &tcp_repr);
+                congestion.on_send(&tcp_repr, cx.now());
+                reply = dispatch(
+                    PollableIfaceMut::new(cx, pending, multicast_groups, dhcp),
+                    &ip_repr,
+                    &tcp_repr,
+                );
                 Ok::<(), ()>(())
             })
             .unwrap();
@@ -663,7 +736,7 @@ impl<E: Ext> TcpConnectionBg<E> {
             }
             is_rst |= tcp_repr.control == TcpControl::Rst;
             events |= SocketEvents::CAN_RECV | SocketEvents::CAN_SEND;
-            reply = socket.process(iface.context_mut(), ip_repr, tcp_repr);
+            reply = socket.process_with_congestion(iface.context_mut(), ip_repr, tcp_repr);
         }
 
         let (state_events, became_dead) =
@@ -17,8 +17,9 @@ use super::{
 use crate::{
     errors::tcp::ListenError,
     ext::Ext,
-    iface::{BindPortConfig, BoundPort, PollableIfaceMut},
+    iface::{BindAddr, BindPortConfig, BoundPort, IfaceCommon, PollableIfaceMut},
     socket::{
+        congestion::{Congestion, CongestionAlgorithm},
         option::{RawTcpOption, RawTcpSetOption},
         unbound::{new_tcp_socket, RawTcpSocket},
     },
@@ -30,6 +31,7 @@ pub type TcpListener<E> = Socket<TcpListenerInner<E>, E>;
 pub struct TcpBacklog<E: Ext> {
     socket: Box<RawTcpSocket>,
     max_conn: usize,
+    congestion_algorithm: CongestionAlgorithm,
     pub(super) connecting: BTreeMap<ConnectionKey, TcpConnection<E>>,
     pub(super) connected: Vec<TcpConnection<E>>,
 }
@@ -73,16 +75,30 @@ impl<E: Ext> TcpListener<E> {
         option: &RawTcpOption,
         observer: E::TcpEventObserver,
     ) -> Result<Self, (BoundPort<E>, ListenError)> {
-        let Some(local_endpoint) = bound.endpoint() else {
+        let (Some(local_endpoint), Some(listen_endpoint)) =
+            (bound.endpoint(), bound.listen_endpoint())
+        else {
             return Err((bound, ListenError::Unaddressable));
         };
 
-        let iface = bound.iface().clone();
-        let mut sockets = iface.common().sockets();
+        // The listener is inserted into the socket tables of all the ifaces that the port is bound
+        // to, so that it can accept connections from all of them. The socket tables are locked in
+        // the same order for the same port, so there are no deadlocks.
+        let ifaces = bound
+            .all_ports()
+            .map(|port| port.iface().clone())
+            .collect::<Vec<_>>();
+        let mut socket_tables = ifaces
+            .iter()
+            .map(|iface| iface.common().sockets())
+            .collect::<Vec<_>>();
 
         let listener_key = ListenerKey::new(local_endpoint.addr, local_endpoint.port);
 
-        if sockets.lookup_listener(&listener_key).is_some() {
+        if socket_tables
+            .iter()
+            .any(|sockets| sockets.lookup_listener(&listener_key).is_some())
+        {
             return Err((bound, ListenError::AddressInUse));
         }
 
@@ -91,7 +107,7 @@ impl<E: Ext> TcpListener<E> {
 
             option.apply(&mut socket);
 
-            if let Err(err) = socket.listen(local_endpoint) {
+            if let Err(err) = socket.listen(listen_endpoint) {
                 return Err((bound, err.into()));
             }
 
@@ -102,6 +118,7 @@ impl<E: Ext> TcpListener<E> {
             let backlog = TcpBacklog {
                 socket,
                 max_conn,
+                congestion_algorithm: option.congestion_algorithm,
                 connecting: BTreeMap::new(),
                 connected: Vec::new(),
             };
@@ -111,8 +128,10 @@ impl<E: Ext> TcpListener<E> {
 
         let listener = Self::new(bound, inner);
         listener.init_observer(observer);
-        let res = sockets.insert_listener(listener.inner().clone());
-        debug_assert!(res.is_ok());
+        for sockets in socket_tables.iter_mut() {
+            let res = sockets.insert_listener(listener.inner().clone());
+            debug_assert!(res.is_ok());
+        }
 
         Ok(listener)
     }
@@ -153,7 +172,9 @@ impl<E: Ext> TcpListener<E> {
     /// leakage.
     pub fn close(&self) {
         // A TCP listener can be removed immediately.
-        self.0.bound.iface().common().remove_tcp_listener(&self.0);
+        for port in self.0.bound.all_ports() {
+            port.iface().common().remove_tcp_listener(&self.0);
+        }
 
         let (connecting, connected) = {
             let mut socket = self.0.inner.backlog.lock();
@@ -182,6 +203,11 @@ impl<E: Ext> RawTcpSetOption for TcpListener<E> {
         let mut backlog = self.0.inner.backlog.lock();
         backlog.socket.set_nagle_enabled(enabled);
     }
+
+    fn set_congestion_algorithm(&self, algorithm: CongestionAlgorithm) {
+        let mut backlog = self.0.inner.backlog.lock();
+        backlog.congestion_algorithm = algorithm;
+    }
 }
 
 impl<E: Ext> TcpListenerBg<E> {
@@ -191,13 +217,23 @@ impl<E: Ext> TcpListenerBg<E> {
 }
 
 impl<E: Ext> TcpListenerBg<E> {
-    /// Tries to process an incoming packet and returns whether the packet is processed.
+    /// Tries to process an incoming packet from the iface whose common part is `common` and
+    /// returns whether the packet is processed.
     pub(crate) fn process(
         self: &Arc<Self>,
+        common: &IfaceCommon<E>,
         iface: &mut PollableIfaceMut<E>,
         ip_repr: &IpRepr,
         tcp_repr: &TcpRepr,
     ) -> (TcpProcessResult, Option<Arc<TcpConnectionBg<E>>>) {
+        // The new connection is bound to the iface from which the packet comes.
+        let Some(port) = self.bound.port_on(common) else {
+            return (TcpProcessResult::NotProcessed, None);
+        };
+        if !port.accepts_version(ip_repr.version()) {
+            return (TcpProcessResult::NotProcessed, None);
+        }
+
         let mut backlog = self.inner.backlog.lock();
 
         if !backlog
@@ -233,14 +269,21 @@ impl<E: Ext> TcpListenerBg<E> {
             socket
         };
 
+        // Let the congestion control learn the options (e.g., the MSS) in the SYN packet.
+        let mut congestion = Congestion::new(backlog.congestion_algorithm);
+        congestion.on_recv(tcp_repr, iface.context_mut().now());
+
         let conn = TcpConnection::new_cyclic(
-            self.bound
-                .iface()
-                .bind(BindPortConfig::Backlog(self.bound.port()))
+            port.iface()
+                .bind(
+                    BindAddr::from(ip_repr.version()),
+                    BindPortConfig::Backlog(port.port()),
+                )
                 .unwrap(),
             |weak| {
                 TcpConnectionInner::new(
                     core::mem::replace(&mut backlog.socket, new_socket),
+                    congestion,
                     Some(self.clone()),
                     weak,
                 )
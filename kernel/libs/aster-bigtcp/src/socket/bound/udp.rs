@@ -1,17 +1,24 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{boxed::Box, sync::Arc};
-use core::sync::atomic::{AtomicBool, Ordering};
+use alloc::{boxed::Box, collections::vec_deque::VecDeque, sync::Arc, vec, vec::Vec};
+use core::{
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+    time::Duration,
+};
 
 use aster_softirq::BottomHalfDisabled;
-use ostd::sync::SpinLock;
+use ostd::{sync::SpinLock, timer::Jiffies};
 use smoltcp::{
     iface::Context,
+    phy::ChecksumCapabilities,
     socket::udp::UdpMetadata,
-    wire::{IpRepr, UdpRepr},
+    wire::{IpEndpoint, IpRepr, Ipv4Address, UdpPacket, UdpRepr},
 };
 
-use super::common::{Inner, Socket, SocketBg};
+use super::{
+    common::{Inner, Socket, SocketBg},
+    icmp::IcmpError,
+};
 use crate::{
     errors::udp::SendError,
     ext::Ext,
@@ -19,26 +26,77 @@ use crate::{
     socket::{event::SocketEvents, unbound::new_udp_socket, RawUdpSocket},
 };
 
-pub type UdpSocket<E> = Socket<UdpSocketInner, E>;
+pub type UdpSocket<E> = Socket<UdpSocketInner<E>, E>;
 
 /// States needed by [`UdpSocketBg`].
-pub struct UdpSocketInner {
+pub struct UdpSocketInner<E: Ext> {
     socket: SpinLock<Box<RawUdpSocket>, BottomHalfDisabled>,
     need_dispatch: AtomicBool,
+    filter: SpinLock<Option<Arc<E::PacketFilter>>, BottomHalfDisabled>,
+    /// The information of the packets in the receive buffer of `socket`, in the same order.
+    ///
+    /// Lock order: `socket` first, `recv_infos` second
+    recv_infos: SpinLock<VecDeque<UdpRecvInfo>, BottomHalfDisabled>,
+    /// The ICMP errors received in response to the sent packets.
+    errors: SpinLock<VecDeque<UdpIcmpError>, BottomHalfDisabled>,
+    /// Whether ICMP errors are queued in `errors`.
+    recv_err: AtomicBool,
+    /// The hop limit of outgoing multicast packets.
+    multicast_ttl: AtomicU8,
+    /// Whether outgoing multicast packets are looped back to local sockets.
+    multicast_loop: AtomicBool,
+}
+
+/// The default hop limit of outgoing multicast packets.
+///
+/// Multicast packets are restricted to the same subnet by default.
+const DEFAULT_MULTICAST_TTL: u8 = 1;
+
+/// The maximum number of ICMP errors that can be queued.
+const MAX_NR_ERRORS: usize = 16;
+
+/// Information about a received UDP packet.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UdpRecvInfo {
+    /// The hop limit (i.e., TTL) in the IP header of the packet.
+    pub hop_limit: u8,
+    /// The type of service (i.e., the traffic class for IPv6) in the IP header of the packet.
+    pub tos: u8,
+    /// The time when the packet was received, measured since boot.
+    pub timestamp: Duration,
+}
+
+/// An ICMP error message received in response to a sent UDP packet.
+#[derive(Debug, Clone)]
+pub struct UdpIcmpError {
+    /// The error reported by the ICMP message.
+    pub error: IcmpError,
+    /// The type of the ICMP message.
+    pub icmp_type: u8,
+    /// The code of the ICMP message.
+    pub icmp_code: u8,
+    /// The address of the host that generated the ICMP message.
+    pub offender: Ipv4Address,
+    /// The destination endpoint of the original packet.
+    pub dst_endpoint: IpEndpoint,
+    /// The payload of the original packet, which is usually truncated.
+    pub payload: Vec<u8>,
 }
 
-impl<E: Ext> Inner<E> for UdpSocketInner {
+impl<E: Ext> Inner<E> for UdpSocketInner<E> {
     type Observer = E::UdpEventObserver;
 
     fn on_drop(this: &Arc<SocketBg<Self, E>>) {
         this.inner.socket.lock().close();
 
         // A UDP socket can be removed immediately.
-        this.bound.iface().common().remove_udp_socket(this);
+        for port in this.bound.all_ports() {
+            port.iface().common().remove_udp_socket(this);
+        }
     }
 }
 
-pub(crate) type UdpSocketBg<E> = SocketBg<UdpSocketInner, E>;
+pub(crate) type UdpSocketBg<E> = SocketBg<UdpSocketInner<E>, E>;
 
 impl<E: Ext> UdpSocketBg<E> {
     /// Tries to process an incoming packet and returns whether the packet is processed.
@@ -46,15 +104,36 @@ impl<E: Ext> UdpSocketBg<E> {
         &self,
         cx: &mut Context,
         ip_repr: &IpRepr,
+        tos: u8,
         udp_repr: &UdpRepr,
         udp_payload: &[u8],
     ) -> bool {
+        if !self.bound.accepts_version(ip_repr.version()) {
+            return false;
+        }
+
         let mut socket = self.inner.socket.lock();
 
         if !socket.accepts(cx, ip_repr, udp_repr) {
             return false;
         }
 
+        // The filter runs on the UDP header and the payload, but the UDP header is never trimmed.
+        // See `udp_queue_rcv_one_skb` in Linux.
+        let udp_payload = match self.inner.filter.lock().as_ref() {
+            None => udp_payload,
+            Some(filter) => {
+                let accepted_len = filter.run(&new_udp_packet(ip_repr, udp_repr, udp_payload));
+                if accepted_len == 0 {
+                    // The packet is dropped by the filter, but it is still consumed by the socket.
+                    return true;
+                }
+                let payload_len = accepted_len.saturating_sub(udp_repr.header_len());
+                &udp_payload[..payload_len.min(udp_payload.len())]
+            }
+        };
+
+        let recv_queue_len = socket.recv_queue();
         socket.process(
             cx,
             smoltcp::phy::PacketMeta::default(),
@@ -63,11 +142,47 @@ impl<E: Ext> UdpSocketBg<E> {
             udp_payload,
         );
 
+        // `RawUdpSocket::process` silently drops the packet if the receive buffer is full, so we
+        // have to find out whether the packet is enqueued. Enqueuing a non-empty packet consumes at
+        // least `udp_payload.len()` bytes, while any padding bytes consumed by a failed attempt
+        // are always fewer than that.
+        let mut recv_infos = self.inner.recv_infos.lock();
+        let is_enqueued = if udp_payload.is_empty() {
+            recv_infos.len() < socket.packet_recv_capacity()
+        } else {
+            socket.recv_queue() >= recv_queue_len + udp_payload.len()
+        };
+        if is_enqueued {
+            recv_infos.push_back(UdpRecvInfo {
+                hop_limit: ip_repr.hop_limit(),
+                tos,
+                timestamp: Jiffies::elapsed().as_duration(),
+            });
+        }
+        drop(recv_infos);
+
         self.notify_events(SocketEvents::CAN_RECV);
 
         true
     }
 
+    /// Records an incoming ICMP error message.
+    pub(crate) fn process_error(&self, error: UdpIcmpError) {
+        if !self.inner.recv_err.load(Ordering::Relaxed) {
+            // TODO: Report hard errors to connected sockets even if `IP_RECVERR` is disabled.
+            return;
+        }
+
+        let mut errors = self.inner.errors.lock();
+        if errors.len() >= MAX_NR_ERRORS {
+            return;
+        }
+        errors.push_back(error);
+        drop(errors);
+
+        self.notify_events(SocketEvents::ERROR);
+    }
+
     /// Tries to generate an outgoing packet and dispatches the generated packet.
     pub(crate) fn dispatch<D>(&self, cx: &mut Context, dispatch: D)
     where
@@ -76,7 +191,13 @@ impl<E: Ext> UdpSocketBg<E> {
         let mut socket = self.inner.socket.lock();
 
         socket
-            .dispatch(cx, |cx, _meta, (ip_repr, udp_repr, udp_payload)| {
+            .dispatch(cx, |cx, _meta, (mut ip_repr, udp_repr, udp_payload)| {
+                // TODO: Support the hop limit of outgoing IPv6 multicast packets.
+                if let IpRepr::Ipv4(ipv4_repr) = &mut ip_repr {
+                    if ipv4_repr.dst_addr.is_multicast() {
+                        ipv4_repr.hop_limit = self.inner.multicast_ttl.load(Ordering::Relaxed);
+                    }
+                }
                 dispatch(cx, &ip_repr, &udp_repr, udp_payload);
                 Ok::<(), ()>(())
             })
@@ -96,6 +217,11 @@ impl<E: Ext> UdpSocketBg<E> {
     pub(crate) fn need_dispatch(&self) -> bool {
         self.inner.need_dispatch.load(Ordering::Relaxed)
     }
+
+    /// Returns whether outgoing multicast packets are looped back to local sockets.
+    pub(crate) fn multicast_loop(&self) -> bool {
+        self.inner.multicast_loop.load(Ordering::Relaxed)
+    }
 }
 
 impl<E: Ext> UdpSocket<E> {
@@ -106,7 +232,7 @@ impl<E: Ext> UdpSocket<E> {
         bound: BoundPort<E>,
         observer: E::UdpEventObserver,
     ) -> Result<Self, (BoundPort<E>, smoltcp::socket::udp::BindError)> {
-        let Some(local_endpoint) = bound.endpoint() else {
+        let Some(local_endpoint) = bound.listen_endpoint() else {
             return Err((bound, smoltcp::socket::udp::BindError::Unaddressable));
         };
 
@@ -123,14 +249,23 @@ impl<E: Ext> UdpSocket<E> {
         let inner = UdpSocketInner {
             socket: SpinLock::new(socket),
             need_dispatch: AtomicBool::new(false),
+            filter: SpinLock::new(None),
+            recv_infos: SpinLock::new(VecDeque::new()),
+            errors: SpinLock::new(VecDeque::new()),
+            recv_err: AtomicBool::new(false),
+            multicast_ttl: AtomicU8::new(DEFAULT_MULTICAST_TTL),
+            multicast_loop: AtomicBool::new(true),
         };
 
         let socket = Self::new(bound, inner);
         socket.init_observer(observer);
-        socket
-            .iface()
-            .common()
-            .register_udp_socket(socket.inner().clone());
+        // The socket is registered to all the ifaces that the port is bound to, so that it can
+        // receive packets from all of them.
+        for port in socket.bound_port().all_ports() {
+            port.iface()
+                .common()
+                .register_udp_socket(socket.inner().clone());
+        }
 
         Ok(socket)
     }
@@ -172,16 +307,82 @@ impl<E: Ext> UdpSocket<E> {
     /// Polling the iface is _not_ required after this method succeeds.
     pub fn recv<F, R>(&self, f: F) -> Result<R, smoltcp::socket::udp::RecvError>
     where
-        F: FnOnce(&[u8], UdpMetadata) -> R,
+        F: FnOnce(&[u8], UdpMetadata, &UdpRecvInfo) -> R,
     {
         let mut socket = self.0.inner.socket.lock();
+        let mut recv_infos = self.0.inner.recv_infos.lock();
 
         let (data, meta) = socket.recv()?;
-        let result = f(data, meta);
+        let info = recv_infos.pop_front().unwrap_or_default();
+        let result = f(data, meta, &info);
+
+        // If the information ever gets out of sync with the receive buffer (see
+        // `UdpSocketBg::process`), it will be resynchronized once the receive buffer is empty.
+        if !socket.can_recv() {
+            recv_infos.clear();
+        }
 
         Ok(result)
     }
 
+    /// Peeks at the next datagram without removing it from the receive buffer.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn peek<F, R>(&self, f: F) -> Result<R, smoltcp::socket::udp::RecvError>
+    where
+        F: FnOnce(&[u8], UdpMetadata, &UdpRecvInfo) -> R,
+    {
+        let mut socket = self.0.inner.socket.lock();
+        let recv_infos = self.0.inner.recv_infos.lock();
+
+        let (data, meta) = socket.peek()?;
+        let info = recv_infos.front().copied().unwrap_or_default();
+
+        Ok(f(data, *meta, &info))
+    }
+
+    /// Sets whether ICMP errors are queued and can be taken by [`Self::take_error`].
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn set_recv_err(&self, recv_err: bool) {
+        self.0.inner.recv_err.store(recv_err, Ordering::Relaxed);
+    }
+
+    /// Takes the oldest queued ICMP error, if any.
+    pub fn take_error(&self) -> Option<UdpIcmpError> {
+        self.0.inner.errors.lock().pop_front()
+    }
+
+    /// Returns whether there are queued ICMP errors.
+    pub fn has_error(&self) -> bool {
+        !self.0.inner.errors.lock().is_empty()
+    }
+
+    /// Sets the hop limit of outgoing multicast packets.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn set_multicast_ttl(&self, ttl: u8) {
+        self.0.inner.multicast_ttl.store(ttl, Ordering::Relaxed);
+    }
+
+    /// Sets whether outgoing multicast packets are looped back to local sockets that have joined
+    /// the multicast group.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn set_multicast_loop(&self, multicast_loop: bool) {
+        self.0
+            .inner
+            .multicast_loop
+            .store(multicast_loop, Ordering::Relaxed);
+    }
+
+    /// Sets the filter for incoming packets.
+    ///
+    /// Packets that are already received are not affected.
+    pub fn set_filter(&self, filter: Option<Arc<E::PacketFilter>>) {
+        *self.0.inner.filter.lock() = filter;
+    }
+
     /// Calls `f` with an immutable reference to the associated [`RawUdpSocket`].
     //
     // NOTE: If a mutable reference is required, add a method above that correctly updates the next
@@ -194,3 +395,17 @@ impl<E: Ext> UdpSocket<E> {
         f(&socket)
     }
 }
+
+/// Builds a UDP packet with the header described by `udp_repr` and the payload.
+fn new_udp_packet(ip_repr: &IpRepr, udp_repr: &UdpRepr, udp_payload: &[u8]) -> Vec<u8> {
+    let mut data = vec![0; udp_repr.header_len() + udp_payload.len()];
+    udp_repr.emit(
+        &mut UdpPacket::new_unchecked(&mut data),
+        &ip_repr.src_addr(),
+        &ip_repr.dst_addr(),
+        udp_payload.len(),
+        |payload| payload.copy_from_slice(udp_payload),
+        &ChecksumCapabilities::default(),
+    );
+    data
+}
@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use smoltcp::time::Duration;
+
+use super::{AckSample, CongestionOps, Instant, Window};
+
+/// A simplified BBR.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/draft-cardwell-iccrg-bbr-congestion-control-00>.
+//
+// Since outgoing packets are not paced, only the congestion window gains are used.
+pub(super) struct Bbr {
+    mode: Mode,
+    /// The number of round trips since the connection is established.
+    round_count: u64,
+    /// The delivery rates of the recent round trips, in bytes per second.
+    bw_samples: [u64; BW_FILTER_LEN],
+    /// The minimum RTT in the recent [`MIN_RTT_WINDOW`].
+    min_rtt: Option<Duration>,
+    /// When `min_rtt` is updated.
+    min_rtt_stamp: Option<Instant>,
+    /// The bandwidth when the pipe is last checked to grow.
+    full_bw: u64,
+    /// The number of round trips in which the bandwidth does not grow.
+    full_bw_cnt: u8,
+    /// Whether the bandwidth of the path has been reached.
+    is_pipe_filled: bool,
+    /// When the [`Mode::ProbeRtt`] mode ends.
+    probe_rtt_done_at: Option<Instant>,
+    /// The congestion window before entering [`Mode::ProbeRtt`] or the loss recovery.
+    prior_cwnd: u32,
+    /// Whether the congestion window has been reduced due to a loss.
+    is_reduced_by_loss: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Grows the sending rate exponentially to find the bandwidth.
+    Startup,
+    /// Drains the queue created in the startup mode.
+    Drain,
+    /// Uses the bandwidth while probing for more.
+    ProbeBw,
+    /// Drains the queue to probe for the minimum RTT.
+    ProbeRtt,
+}
+
+/// The number of round trips in which the maximum bandwidth is kept.
+const BW_FILTER_LEN: usize = 10;
+/// The period after which the minimum RTT expires.
+const MIN_RTT_WINDOW: Duration = Duration::from_secs(10);
+/// The time spent in the [`Mode::ProbeRtt`] mode.
+const PROBE_RTT_DURATION: Duration = Duration::from_millis(200);
+/// The minimum congestion window, which is also used in the [`Mode::ProbeRtt`] mode.
+const MIN_CWND: u32 = 4;
+
+/// The congestion window gain in the startup mode (i.e., `2 / ln(2)`), scaled by 1000.
+const HIGH_GAIN: u64 = 2885;
+/// The congestion window gain in other modes, scaled by 1000.
+const CWND_GAIN: u64 = 2000;
+const GAIN_SCALE: u64 = 1000;
+
+impl Bbr {
+    pub(super) const fn new() -> Self {
+        Self {
+            mode: Mode::Startup,
+            round_count: 0,
+            bw_samples: [0; BW_FILTER_LEN],
+            min_rtt: None,
+            min_rtt_stamp: None,
+            full_bw: 0,
+            full_bw_cnt: 0,
+            is_pipe_filled: false,
+            probe_rtt_done_at: None,
+            prior_cwnd: 0,
+            is_reduced_by_loss: false,
+        }
+    }
+
+    /// Returns the estimated bottleneck bandwidth, in bytes per second.
+    fn btl_bw(&self) -> u64 {
+        self.bw_samples.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Returns the estimated bandwidth-delay product multiplied by the gain, in segments.
+    fn bdp(&self, gain: u64, mss: u32) -> Option<u32> {
+        let bw = self.btl_bw();
+        let min_rtt = self.min_rtt?;
+        if bw == 0 {
+            return None;
+        }
+
+        let bytes = bw as u128 * min_rtt.total_micros() as u128 * gain as u128
+            / (1_000_000 * GAIN_SCALE) as u128;
+        let segs = bytes.div_ceil(mss as u128);
+        Some(segs.min(u32::MAX as u128) as u32)
+    }
+
+    fn update_bw(&mut self, sample: &AckSample) {
+        let Some(delivery_rate) = sample.delivery_rate else {
+            return;
+        };
+
+        self.round_count += 1;
+        self.bw_samples[(self.round_count % BW_FILTER_LEN as u64) as usize] = delivery_rate;
+
+        if self.is_pipe_filled {
+            return;
+        }
+
+        // The pipe is filled if the bandwidth does not grow by 25% for three round trips.
+        let btl_bw = self.btl_bw();
+        if btl_bw >= self.full_bw * 5 / 4 {
+            self.full_bw = btl_bw;
+            self.full_bw_cnt = 0;
+            return;
+        }
+        self.full_bw_cnt += 1;
+        if self.full_bw_cnt >= 3 {
+            self.is_pipe_filled = true;
+        }
+    }
+
+    fn update_min_rtt(&mut self, sample: &AckSample) {
+        let now = sample.now;
+        let is_expired = self
+            .min_rtt_stamp
+            .is_some_and(|stamp| now - stamp > MIN_RTT_WINDOW);
+
+        if let Some(rtt) = sample.rtt {
+            if self.min_rtt.is_none_or(|min_rtt| rtt <= min_rtt) || is_expired {
+                self.min_rtt = Some(rtt);
+                self.min_rtt_stamp = Some(now);
+            }
+        }
+
+        if is_expired && self.mode != Mode::ProbeRtt {
+            self.mode = Mode::ProbeRtt;
+            self.prior_cwnd = 0;
+            self.probe_rtt_done_at = None;
+        }
+    }
+
+    fn update_mode(&mut self, window: &mut Window, sample: &AckSample) {
+        match self.mode {
+            Mode::Startup if self.is_pipe_filled => self.mode = Mode::Drain,
+            Mode::Drain
+                if self
+                    .bdp(GAIN_SCALE, sample.mss)
+                    .is_some_and(|bdp| sample.in_flight <= bdp) =>
+            {
+                self.mode = Mode::ProbeBw
+            }
+            Mode::ProbeRtt => {
+                if self.prior_cwnd == 0 {
+                    self.prior_cwnd = window.cwnd;
+                }
+
+                let done_at = match self.probe_rtt_done_at {
+                    Some(done_at) => done_at,
+                    None if sample.in_flight <= MIN_CWND => {
+                        let done_at = sample.now + PROBE_RTT_DURATION;
+                        self.probe_rtt_done_at = Some(done_at);
+                        done_at
+                    }
+                    None => return,
+                };
+                if sample.now < done_at {
+                    return;
+                }
+
+                self.min_rtt_stamp = Some(sample.now);
+                window.cwnd = window.cwnd.max(self.prior_cwnd);
+                self.mode = if self.is_pipe_filled {
+                    Mode::ProbeBw
+                } else {
+                    Mode::Startup
+                };
+            }
+            _ => (),
+        }
+    }
+
+    fn set_cwnd(&self, window: &mut Window, sample: &AckSample) {
+        if self.mode == Mode::ProbeRtt {
+            window.cwnd = window.cwnd.min(MIN_CWND);
+            return;
+        }
+
+        let gain = match self.mode {
+            Mode::Startup | Mode::Drain => HIGH_GAIN,
+            Mode::ProbeBw | Mode::ProbeRtt => CWND_GAIN,
+        };
+
+        let cwnd = window.cwnd.saturating_add(sample.acked);
+        window.cwnd = match self.bdp(gain, sample.mss) {
+            // Before the pipe is filled, the congestion window is never reduced to the target
+            // because the bandwidth may be underestimated.
+            Some(target) if self.is_pipe_filled => cwnd.min(target),
+            _ => cwnd,
+        }
+        .max(MIN_CWND);
+    }
+}
+
+impl CongestionOps for Bbr {
+    fn on_ack(&mut self, window: &mut Window, sample: &AckSample) {
+        if self.is_reduced_by_loss {
+            self.is_reduced_by_loss = false;
+            window.cwnd = window.cwnd.max(self.prior_cwnd);
+        }
+
+        self.update_bw(sample);
+        self.update_min_rtt(sample);
+        self.update_mode(window, sample);
+        self.set_cwnd(window, sample);
+    }
+
+    fn on_loss(&mut self, window: &mut Window, in_flight: u32) {
+        // Packet conservation: Send one segment for each segment that leaves the network.
+        self.prior_cwnd = window.cwnd;
+        self.is_reduced_by_loss = true;
+        window.cwnd = in_flight.max(MIN_CWND);
+    }
+
+    fn on_timeout(&mut self, window: &mut Window) {
+        self.prior_cwnd = window.cwnd;
+        self.is_reduced_by_loss = true;
+        window.cwnd = 1;
+    }
+}
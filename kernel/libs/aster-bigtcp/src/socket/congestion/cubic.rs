@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{AckSample, CongestionOps, Instant, Window};
+
+/// CUBIC.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc9438>.
+//
+// The implementation follows Linux, except that HyStart is not implemented.
+// Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/ipv4/tcp_cubic.c>.
+pub(super) struct Cubic {
+    /// The congestion window before the last reduction (i.e., `W_max`).
+    last_max_cwnd: u32,
+    /// The beginning of the current congestion avoidance stage.
+    epoch_start: Option<Instant>,
+    /// The congestion window at which the cubic function reaches its plateau.
+    origin_point: u32,
+    /// The time it takes to reach `origin_point`, in milliseconds (i.e., `K`).
+    k_ms: u64,
+    /// Increase the congestion window by one segment after `cnt` segments are acknowledged.
+    cnt: u32,
+    /// The number of segments acknowledged, for the TCP-friendly estimation.
+    ack_cnt: u32,
+    /// The estimated congestion window of Reno (i.e., `W_est`).
+    tcp_cwnd: u32,
+}
+
+/// The multiplicative decrease factor (i.e., `beta_cubic`), scaled by 1024.
+const BETA: u32 = 717;
+const BETA_SCALE: u32 = 1024;
+
+impl Cubic {
+    pub(super) const fn new() -> Self {
+        Self {
+            last_max_cwnd: 0,
+            epoch_start: None,
+            origin_point: 0,
+            k_ms: 0,
+            cnt: 0,
+            ack_cnt: 0,
+            tcp_cwnd: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn update(&mut self, cwnd: u32, acked: u32, sample: &AckSample) {
+        self.ack_cnt = self.ack_cnt.saturating_add(acked);
+
+        let epoch_start = match self.epoch_start {
+            Some(epoch_start) => epoch_start,
+            None => {
+                self.epoch_start = Some(sample.now);
+                self.ack_cnt = acked;
+                self.tcp_cwnd = cwnd;
+
+                if self.last_max_cwnd <= cwnd {
+                    self.k_ms = 0;
+                    self.origin_point = cwnd;
+                } else {
+                    // K = cbrt((W_max - cwnd) / C), where C = 0.4 and K is in seconds.
+                    let diff = (self.last_max_cwnd - cwnd) as u64;
+                    self.k_ms = cube_root(diff * 2_500_000_000);
+                    self.origin_point = self.last_max_cwnd;
+                }
+
+                sample.now
+            }
+        };
+
+        // Estimate the congestion window after one RTT.
+        let min_rtt_ms = sample.min_rtt.map_or(0, |min_rtt| min_rtt.total_millis());
+        let t_ms = (sample.now - epoch_start).total_millis() + min_rtt_ms;
+
+        // W_cubic(t) = C * (t - K)^3 + W_max
+        let offs_ms = t_ms.abs_diff(self.k_ms).min(u32::MAX as u64) as u128;
+        let delta = (4 * offs_ms * offs_ms * offs_ms / 10_000_000_000).min(u32::MAX as u128) as u32;
+        let target = if t_ms < self.k_ms {
+            self.origin_point.saturating_sub(delta)
+        } else {
+            self.origin_point.saturating_add(delta)
+        };
+
+        self.cnt = if target > cwnd {
+            cwnd / (target - cwnd)
+        } else {
+            // Increase the congestion window very slowly.
+            cwnd.saturating_mul(100)
+        };
+
+        // The initial growth of CUBIC should not be too conservative.
+        if self.last_max_cwnd == 0 {
+            self.cnt = self.cnt.min(20);
+        }
+
+        // Keep up with Reno in the TCP-friendly region.
+        let beta_scale = 8 * (BETA_SCALE + BETA) / 3 / (BETA_SCALE - BETA);
+        let delta = ((cwnd * beta_scale) >> 3).max(1);
+        while self.ack_cnt > delta {
+            self.ack_cnt -= delta;
+            self.tcp_cwnd += 1;
+        }
+        if self.tcp_cwnd > cwnd {
+            let max_cnt = cwnd / (self.tcp_cwnd - cwnd);
+            self.cnt = self.cnt.min(max_cnt);
+        }
+
+        self.cnt = self.cnt.max(2);
+    }
+
+    /// Updates `W_max` and returns the slow start threshold after a loss.
+    fn ssthresh(&mut self, window: &Window) -> u32 {
+        self.epoch_start = None;
+
+        // Fast convergence: Release more bandwidth for new flows.
+        self.last_max_cwnd = if window.cwnd < self.last_max_cwnd {
+            (window.cwnd as u64 * (BETA_SCALE + BETA) as u64 / (2 * BETA_SCALE) as u64) as u32
+        } else {
+            window.cwnd
+        };
+
+        ((window.cwnd as u64 * BETA as u64 / BETA_SCALE as u64) as u32).max(2)
+    }
+}
+
+impl CongestionOps for Cubic {
+    fn on_ack(&mut self, window: &mut Window, sample: &AckSample) {
+        let mut acked = sample.acked;
+        if window.in_slow_start() {
+            acked = window.slow_start(acked);
+            if acked == 0 {
+                return;
+            }
+        }
+
+        self.update(window.cwnd, acked, sample);
+        window.cong_avoid_ai(self.cnt, acked);
+    }
+
+    fn on_loss(&mut self, window: &mut Window, _in_flight: u32) {
+        window.ssthresh = self.ssthresh(window);
+        window.cwnd = window.ssthresh;
+        window.cwnd_cnt = 0;
+    }
+
+    fn on_timeout(&mut self, window: &mut Window) {
+        window.ssthresh = self.ssthresh(window);
+        window.cwnd = 1;
+        window.cwnd_cnt = 0;
+
+        // Linux forgets `W_max` after a timeout.
+        self.reset();
+    }
+}
+
+/// Returns the integer cube root of `x`, rounded down.
+fn cube_root(x: u64) -> u64 {
+    // `2_642_245` is the largest number whose cube fits in `u64`.
+    let (mut low, mut high) = (0u64, 2_642_245u64);
+    while low < high {
+        let mid = (low + high + 1) / 2;
+        if mid * mid * mid <= x {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
@@ -0,0 +1,635 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! TCP congestion control.
+//!
+//! The congestion control algorithms are implemented here instead of in smoltcp. They observe the
+//! segments sent and received by a TCP connection to estimate the network conditions. The
+//! resulting congestion window is enforced by clamping the receive window advertised by the peer
+//! before an incoming segment is processed by smoltcp. Since smoltcp never sends data beyond the
+//! window of the peer, the amount of data in flight never exceeds the congestion window.
+//!
+//! Loss recovery (i.e., retransmission) is still done by smoltcp. Losses are detected by observing
+//! duplicate ACKs and retransmitted segments.
+
+mod bbr;
+mod cubic;
+mod reno;
+
+use alloc::boxed::Box;
+
+use smoltcp::{
+    time::{Duration, Instant},
+    wire::{TcpControl, TcpRepr, TcpSeqNumber},
+};
+
+/// A TCP congestion control algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CongestionAlgorithm {
+    /// TCP Reno.
+    ///
+    /// Reference: <https://datatracker.ietf.org/doc/html/rfc5681>.
+    Reno,
+    /// CUBIC.
+    ///
+    /// Reference: <https://datatracker.ietf.org/doc/html/rfc9438>.
+    Cubic,
+    /// A simplified BBR.
+    ///
+    /// The bottleneck bandwidth and the minimum RTT are estimated to size the congestion window.
+    /// Unlike the real BBR, outgoing packets are not paced.
+    ///
+    /// Reference: <https://datatracker.ietf.org/doc/html/draft-cardwell-iccrg-bbr-congestion-control-00>.
+    Bbr,
+}
+
+/// The congestion avoidance state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CongestionState {
+    /// Nothing bad has been observed.
+    Open,
+    /// Duplicate ACKs have been received, but not enough to trigger a fast retransmission.
+    Disorder,
+    /// A fast retransmission is in progress.
+    Recovery,
+    /// A retransmission timeout has occurred.
+    Loss,
+}
+
+/// Statistics about the congestion control of a TCP connection.
+#[derive(Debug, Clone, Copy)]
+pub struct CongestionInfo {
+    /// The congestion avoidance state.
+    pub state: CongestionState,
+    /// The congestion window, in segments.
+    pub cwnd: u32,
+    /// The slow start threshold, in segments.
+    pub ssthresh: u32,
+    /// The maximum segment size used to send data.
+    pub mss: u32,
+    /// The smoothed RTT, or `None` if no RTT has been measured.
+    pub srtt: Option<Duration>,
+    /// The RTT variation.
+    pub rttvar: Duration,
+    /// The minimum RTT, or `None` if no RTT has been measured.
+    pub min_rtt: Option<Duration>,
+    /// The retransmission timeout.
+    pub rto: Duration,
+    /// The number of segments that have been sent but not acknowledged.
+    pub unacked: u32,
+    /// The total number of retransmitted segments.
+    pub total_retrans: u32,
+    /// The total number of segments sent.
+    pub segs_out: u32,
+    /// The total number of segments sent with data.
+    pub data_segs_out: u32,
+    /// The total number of bytes sent, including retransmissions.
+    pub bytes_sent: u64,
+    /// The total number of bytes retransmitted.
+    pub bytes_retrans: u64,
+    /// The total number of bytes acknowledged.
+    pub bytes_acked: u64,
+    /// The most recent delivery rate, in bytes per second.
+    pub delivery_rate: u64,
+    /// The window advertised by the peer, in bytes.
+    pub peer_window: u32,
+}
+
+/// The congestion window and the slow start threshold, in segments.
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    cwnd: u32,
+    ssthresh: u32,
+    /// The number of segments acknowledged since the congestion window was last increased in
+    /// the congestion avoidance phase.
+    cwnd_cnt: u32,
+}
+
+/// The initial congestion window.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc6928>.
+const INIT_CWND: u32 = 10;
+/// The slow start threshold before any loss is detected.
+const INFINITE_SSTHRESH: u32 = 0x7fff_ffff;
+
+impl Window {
+    const fn new() -> Self {
+        Self {
+            cwnd: INIT_CWND,
+            ssthresh: INFINITE_SSTHRESH,
+            cwnd_cnt: 0,
+        }
+    }
+
+    fn in_slow_start(&self) -> bool {
+        self.cwnd < self.ssthresh
+    }
+
+    /// Increases the congestion window in the slow start phase.
+    ///
+    /// This method returns the number of acknowledged segments that are left for the congestion
+    /// avoidance phase.
+    fn slow_start(&mut self, acked: u32) -> u32 {
+        let cwnd = self.cwnd.saturating_add(acked).min(self.ssthresh);
+        let used = cwnd - self.cwnd;
+        self.cwnd = cwnd;
+        acked - used
+    }
+
+    /// Increases the congestion window by one segment after `w` segments are acknowledged.
+    fn cong_avoid_ai(&mut self, w: u32, acked: u32) {
+        // Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/ipv4/tcp_cong.c#L474>.
+        if self.cwnd_cnt >= w {
+            self.cwnd_cnt = 0;
+            self.cwnd += 1;
+        }
+
+        self.cwnd_cnt += acked;
+        if self.cwnd_cnt >= w {
+            let delta = self.cwnd_cnt / w;
+            self.cwnd_cnt -= delta * w;
+            self.cwnd += delta;
+        }
+    }
+}
+
+/// Information about an ACK that acknowledges new data.
+struct AckSample {
+    /// The number of newly acknowledged segments.
+    acked: u32,
+    /// The current time.
+    now: Instant,
+    /// The RTT measured by this ACK, if any.
+    rtt: Option<Duration>,
+    /// The minimum RTT measured so far, if any.
+    min_rtt: Option<Duration>,
+    /// The delivery rate measured by this ACK, in bytes per second.
+    ///
+    /// The delivery rate is measured once per round trip. So this also indicates the start of a
+    /// new round trip.
+    delivery_rate: Option<u64>,
+    /// The number of segments in flight after this ACK.
+    in_flight: u32,
+    /// The maximum segment size.
+    mss: u32,
+}
+
+/// The operations of a congestion control algorithm.
+trait CongestionOps: Send + Sync {
+    /// Called when an ACK acknowledges new data.
+    ///
+    /// This method is not called during loss recovery.
+    fn on_ack(&mut self, window: &mut Window, sample: &AckSample);
+
+    /// Called when a loss is detected by duplicate ACKs.
+    fn on_loss(&mut self, window: &mut Window, in_flight: u32);
+
+    /// Called when the retransmission timer expires.
+    fn on_timeout(&mut self, window: &mut Window);
+}
+
+fn new_ops(algorithm: CongestionAlgorithm) -> Box<dyn CongestionOps> {
+    match algorithm {
+        CongestionAlgorithm::Reno => Box::new(reno::Reno),
+        CongestionAlgorithm::Cubic => Box::new(cubic::Cubic::new()),
+        CongestionAlgorithm::Bbr => Box::new(bbr::Bbr::new()),
+    }
+}
+
+/// The congestion control states of a TCP connection.
+pub(crate) struct Congestion {
+    algorithm: CongestionAlgorithm,
+    ops: Box<dyn CongestionOps>,
+    window: Window,
+    state: CongestionState,
+
+    /// The oldest unacknowledged sequence number.
+    snd_una: Option<TcpSeqNumber>,
+    /// The highest sequence number that has been sent.
+    snd_max: TcpSeqNumber,
+    /// The sequence number that must be acknowledged to end the loss recovery.
+    recovery_point: TcpSeqNumber,
+    dup_acks: u8,
+    /// Whether the oldest unacknowledged segment has been retransmitted.
+    una_retransmitted: bool,
+
+    /// The maximum segment size advertised by the peer.
+    peer_mss: Option<u32>,
+    /// The window scale used by the peer.
+    peer_win_shift: u8,
+    /// The window advertised by the peer.
+    peer_window: u32,
+    /// The number of acknowledged bytes that are not enough to form a full segment.
+    acked_bytes_rem: u32,
+
+    rtt: RttEstimator,
+    /// The segment that is being timed to measure the RTT.
+    timed_seg: Option<TimedSegment>,
+    delivery_rate: u64,
+
+    stats: Stats,
+}
+
+/// The segment that is being timed to measure the RTT and the delivery rate.
+#[derive(Debug, Clone, Copy)]
+struct TimedSegment {
+    /// The sequence number that acknowledges the segment.
+    end_seq: TcpSeqNumber,
+    /// When the segment is sent.
+    sent_at: Instant,
+    /// The number of bytes acknowledged when the segment is sent.
+    delivered: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Stats {
+    total_retrans: u32,
+    segs_out: u32,
+    data_segs_out: u32,
+    bytes_sent: u64,
+    bytes_retrans: u64,
+    bytes_acked: u64,
+}
+
+/// The default maximum segment size.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc9293#section-3.7.1>.
+const DEFAULT_MSS: u32 = 536;
+
+impl Congestion {
+    pub(crate) fn new(algorithm: CongestionAlgorithm) -> Self {
+        Self {
+            algorithm,
+            ops: new_ops(algorithm),
+            window: Window::new(),
+            state: CongestionState::Open,
+            snd_una: None,
+            snd_max: TcpSeqNumber::default(),
+            recovery_point: TcpSeqNumber::default(),
+            dup_acks: 0,
+            una_retransmitted: false,
+            peer_mss: None,
+            peer_win_shift: 0,
+            peer_window: 0,
+            acked_bytes_rem: 0,
+            rtt: RttEstimator::new(),
+            timed_seg: None,
+            delivery_rate: 0,
+            stats: Stats::default(),
+        }
+    }
+
+    pub(crate) fn algorithm(&self) -> CongestionAlgorithm {
+        self.algorithm
+    }
+
+    /// Switches to another congestion control algorithm.
+    ///
+    /// The current congestion window and slow start threshold are kept.
+    pub(crate) fn set_algorithm(&mut self, algorithm: CongestionAlgorithm) {
+        if self.algorithm == algorithm {
+            return;
+        }
+
+        self.algorithm = algorithm;
+        self.ops = new_ops(algorithm);
+        self.window.cwnd_cnt = 0;
+    }
+
+    fn mss(&self) -> u32 {
+        self.peer_mss.unwrap_or(DEFAULT_MSS)
+    }
+
+    fn in_flight_bytes(&self) -> u32 {
+        match self.snd_una {
+            Some(snd_una) => seq_diff(self.snd_max, snd_una).max(0) as u32,
+            None => 0,
+        }
+    }
+
+    fn in_flight(&self) -> u32 {
+        self.in_flight_bytes().div_ceil(self.mss())
+    }
+
+    /// Observes an incoming segment.
+    ///
+    /// This method returns the segment that should be passed to smoltcp, in which the window is
+    /// clamped to the congestion window.
+    pub(crate) fn on_recv<'a>(&mut self, repr: &TcpRepr<'a>, now: Instant) -> TcpRepr<'a> {
+        if repr.control == TcpControl::Rst {
+            return *repr;
+        }
+
+        let win_shift = if repr.control == TcpControl::Syn {
+            // The window in SYN segments is never scaled.
+            if let Some(mss) = repr.max_seg_size.filter(|mss| *mss != 0) {
+                self.peer_mss = Some(mss as u32);
+            }
+            self.peer_win_shift = repr.window_scale.unwrap_or(0);
+            0
+        } else {
+            self.peer_win_shift
+        };
+        self.peer_window = (repr.window_len as u32) << win_shift;
+
+        // The window must be clamped before a duplicate ACK reduces the congestion window. If the
+        // window changes, smoltcp will not count the segment as a duplicate ACK.
+        let clamped_window = if let Some(ack_number) = repr.ack_number {
+            self.on_ack_number(ack_number, repr.payload.is_empty(), now)
+        } else {
+            self.cwnd_bytes()
+        };
+
+        let mut repr = *repr;
+        if self.peer_window > clamped_window {
+            // Round up to avoid advertising a zero window.
+            let window_len = clamped_window.div_ceil(1u32 << win_shift);
+            repr.window_len = window_len.min(u16::MAX as u32) as u16;
+        }
+        repr
+    }
+
+    /// Processes the ACK number and returns the congestion window that should be used to clamp
+    /// the window in the segment.
+    fn on_ack_number(&mut self, ack_number: TcpSeqNumber, is_pure_ack: bool, now: Instant) -> u32 {
+        let Some(snd_una) = self.snd_una else {
+            return self.cwnd_bytes();
+        };
+
+        // Ignore ACKs that acknowledge data that has never been sent.
+        if ack_number > self.snd_max {
+            return self.cwnd_bytes();
+        }
+
+        if ack_number > snd_una {
+            self.on_new_ack(snd_una, ack_number, now);
+            return self.cwnd_bytes();
+        }
+
+        let clamped_window = self.cwnd_bytes();
+        if ack_number == snd_una && is_pure_ack && self.snd_max > snd_una {
+            self.on_dup_ack();
+        }
+        clamped_window
+    }
+
+    fn on_new_ack(&mut self, snd_una: TcpSeqNumber, ack_number: TcpSeqNumber, now: Instant) {
+        let acked_bytes = seq_diff(ack_number, snd_una) as u32;
+        self.snd_una = Some(ack_number);
+        self.dup_acks = 0;
+        self.una_retransmitted = false;
+        self.stats.bytes_acked += acked_bytes as u64;
+
+        let mss = self.mss();
+        self.acked_bytes_rem += acked_bytes;
+        let acked = self.acked_bytes_rem / mss;
+        self.acked_bytes_rem %= mss;
+
+        let (rtt, delivery_rate) = match self.timed_seg {
+            Some(timed_seg) if ack_number >= timed_seg.end_seq => {
+                self.timed_seg = None;
+
+                let rtt = now - timed_seg.sent_at;
+                self.rtt.sample(rtt);
+
+                let delivered = self.stats.bytes_acked - timed_seg.delivered;
+                let interval_us = rtt.total_micros().max(1);
+                self.delivery_rate = delivered * 1_000_000 / interval_us;
+
+                (Some(rtt), Some(self.delivery_rate))
+            }
+            _ => (None, None),
+        };
+
+        match self.state {
+            CongestionState::Recovery | CongestionState::Loss
+                if ack_number < self.recovery_point =>
+            {
+                // After a timeout, the congestion window grows in the slow start phase during
+                // the recovery, as RFC 5681 suggests.
+                if self.state == CongestionState::Loss {
+                    self.window.slow_start(acked);
+                }
+                return;
+            }
+            _ => self.state = CongestionState::Open,
+        }
+
+        if acked == 0 {
+            return;
+        }
+
+        let sample = AckSample {
+            acked,
+            now,
+            rtt,
+            min_rtt: self.rtt.min_rtt,
+            delivery_rate,
+            in_flight: self.in_flight(),
+            mss,
+        };
+        self.ops.on_ack(&mut self.window, &sample);
+    }
+
+    fn on_dup_ack(&mut self) {
+        /// The number of duplicate ACKs that trigger a fast retransmission.
+        ///
+        /// This must be consistent with smoltcp.
+        const DUP_ACK_THRESHOLD: u8 = 3;
+
+        if !matches!(
+            self.state,
+            CongestionState::Open | CongestionState::Disorder
+        ) {
+            return;
+        }
+
+        self.dup_acks += 1;
+        if self.dup_acks < DUP_ACK_THRESHOLD {
+            self.state = CongestionState::Disorder;
+            return;
+        }
+
+        self.state = CongestionState::Recovery;
+        self.recovery_point = self.snd_max;
+        let in_flight = self.in_flight();
+        self.ops.on_loss(&mut self.window, in_flight);
+    }
+
+    /// Observes an outgoing segment.
+    pub(crate) fn on_send(&mut self, repr: &TcpRepr, now: Instant) {
+        self.stats.segs_out = self.stats.segs_out.wrapping_add(1);
+
+        let seg_len = repr.segment_len();
+        if seg_len == 0 {
+            return;
+        }
+
+        let end_seq = repr.seq_number + seg_len;
+        let payload_len = repr.payload.len() as u64;
+
+        if repr.control == TcpControl::Syn {
+            let is_retrans = self.snd_una == Some(repr.seq_number);
+            if is_retrans {
+                self.stats.total_retrans = self.stats.total_retrans.wrapping_add(1);
+            }
+
+            self.snd_una = Some(repr.seq_number);
+            self.snd_max = end_seq;
+            self.timed_seg = (!is_retrans).then_some(TimedSegment {
+                end_seq,
+                sent_at: now,
+                delivered: self.stats.bytes_acked,
+            });
+            return;
+        }
+
+        let Some(snd_una) = self.snd_una else {
+            return;
+        };
+        // Keep-alive segments carry a sequence number that has already been acknowledged.
+        if repr.seq_number < snd_una {
+            return;
+        }
+
+        if payload_len > 0 {
+            self.stats.data_segs_out = self.stats.data_segs_out.wrapping_add(1);
+            self.stats.bytes_sent += payload_len;
+        }
+
+        if repr.seq_number >= self.snd_max {
+            self.snd_max = end_seq;
+            if self.timed_seg.is_none() {
+                self.timed_seg = Some(TimedSegment {
+                    end_seq,
+                    sent_at: now,
+                    delivered: self.stats.bytes_acked,
+                });
+            }
+            return;
+        }
+
+        // This is a retransmission.
+        self.stats.total_retrans = self.stats.total_retrans.wrapping_add(1);
+        self.stats.bytes_retrans += payload_len;
+
+        // Karn's algorithm: RTTs must not be measured using retransmitted segments.
+        if self
+            .timed_seg
+            .is_some_and(|timed_seg| repr.seq_number < timed_seg.end_seq)
+        {
+            self.timed_seg = None;
+        }
+
+        // Whenever smoltcp retransmits, it resends all the data in flight, starting from the
+        // oldest unacknowledged segment. So only the retransmission of that segment can indicate
+        // a retransmission timeout.
+        if repr.seq_number != snd_una {
+            return;
+        }
+        let was_una_retransmitted = core::mem::replace(&mut self.una_retransmitted, true);
+
+        match self.state {
+            CongestionState::Open | CongestionState::Disorder => {
+                self.state = CongestionState::Loss;
+                self.recovery_point = self.snd_max;
+                self.ops.on_timeout(&mut self.window);
+            }
+            // The first retransmission in the recovery is the fast retransmission.
+            CongestionState::Recovery if !was_una_retransmitted => return,
+            CongestionState::Recovery => {
+                self.state = CongestionState::Loss;
+                self.recovery_point = self.snd_max;
+                self.window.cwnd = 1;
+            }
+            // The first retransmission after new data is acknowledged is not due to a timeout,
+            // but because smoltcp is still resending the data in flight.
+            CongestionState::Loss if !was_una_retransmitted => return,
+            CongestionState::Loss => self.window.cwnd = 1,
+        }
+
+        self.dup_acks = 0;
+        self.window.cwnd_cnt = 0;
+        self.rtt.back_off();
+    }
+
+    fn cwnd_bytes(&self) -> u32 {
+        self.window.cwnd.saturating_mul(self.mss())
+    }
+
+    /// Returns statistics about the congestion control.
+    pub(crate) fn info(&self) -> CongestionInfo {
+        CongestionInfo {
+            state: self.state,
+            cwnd: self.window.cwnd,
+            ssthresh: self.window.ssthresh,
+            mss: self.mss(),
+            srtt: self.rtt.srtt,
+            rttvar: self.rtt.rttvar,
+            min_rtt: self.rtt.min_rtt,
+            rto: self.rtt.rto,
+            unacked: self.in_flight(),
+            total_retrans: self.stats.total_retrans,
+            segs_out: self.stats.segs_out,
+            data_segs_out: self.stats.data_segs_out,
+            bytes_sent: self.stats.bytes_sent,
+            bytes_retrans: self.stats.bytes_retrans,
+            bytes_acked: self.stats.bytes_acked,
+            delivery_rate: self.delivery_rate,
+            peer_window: self.peer_window,
+        }
+    }
+}
+
+/// Returns `a - b` as a signed number.
+fn seq_diff(a: TcpSeqNumber, b: TcpSeqNumber) -> i32 {
+    a.0.wrapping_sub(b.0)
+}
+
+/// The RTT estimator.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc6298>.
+struct RttEstimator {
+    srtt: Option<Duration>,
+    rttvar: Duration,
+    min_rtt: Option<Duration>,
+    rto: Duration,
+}
+
+const INIT_RTO: Duration = Duration::from_secs(1);
+// Linux uses a smaller minimum RTO than RFC 6298 does.
+const MIN_RTO: Duration = Duration::from_millis(200);
+const MAX_RTO: Duration = Duration::from_secs(120);
+
+impl RttEstimator {
+    const fn new() -> Self {
+        Self {
+            srtt: None,
+            rttvar: Duration::ZERO,
+            min_rtt: None,
+            rto: INIT_RTO,
+        }
+    }
+
+    fn sample(&mut self, rtt: Duration) {
+        match self.srtt {
+            None => {
+                self.srtt = Some(rtt);
+                self.rttvar = rtt / 2;
+            }
+            Some(srtt) => {
+                let delta = if srtt > rtt { srtt - rtt } else { rtt - srtt };
+                self.rttvar = (self.rttvar * 3 + delta) / 4;
+                self.srtt = Some((srtt * 7 + rtt) / 8);
+            }
+        }
+
+        self.min_rtt = Some(self.min_rtt.map_or(rtt, |min_rtt| min_rtt.min(rtt)));
+
+        let rto = self.srtt.unwrap() + self.rttvar * 4;
+        self.rto = rto.max(MIN_RTO).min(MAX_RTO);
+    }
+
+    fn back_off(&mut self) {
+        self.rto = (self.rto * 2).min(MAX_RTO);
+    }
+}
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{AckSample, CongestionOps, Window};
+
+/// TCP Reno.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc5681>.
+pub(super) struct Reno;
+
+impl CongestionOps for Reno {
+    fn on_ack(&mut self, window: &mut Window, sample: &AckSample) {
+        let mut acked = sample.acked;
+        if window.in_slow_start() {
+            acked = window.slow_start(acked);
+            if acked == 0 {
+                return;
+            }
+        }
+
+        let cwnd = window.cwnd;
+        window.cong_avoid_ai(cwnd, acked);
+    }
+
+    fn on_loss(&mut self, window: &mut Window, _in_flight: u32) {
+        window.ssthresh = ssthresh(window);
+        window.cwnd = window.ssthresh;
+        window.cwnd_cnt = 0;
+    }
+
+    fn on_timeout(&mut self, window: &mut Window) {
+        window.ssthresh = ssthresh(window);
+        window.cwnd = 1;
+        window.cwnd_cnt = 0;
+    }
+}
+
+/// Returns the slow start threshold after a loss.
+fn ssthresh(window: &Window) -> u32 {
+    (window.cwnd / 2).max(2)
+}
@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: MPL-2.0
+
+/// A filter that decides whether and how much of an incoming packet will be accepted by a socket.
+///
+/// This is typically implemented by a classic BPF program attached with `SO_ATTACH_FILTER`.
+pub trait PacketFilter: Send + Sync {
+    /// Runs the filter on the packet.
+    ///
+    /// This method returns the number of bytes that should be accepted. If the returned value is
+    /// zero, the packet should be dropped. If the returned value is larger than the packet length,
+    /// the whole packet should be accepted.
+    fn run(&self, packet: &[u8]) -> usize;
+}
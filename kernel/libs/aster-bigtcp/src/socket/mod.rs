@@ -1,16 +1,26 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod bound;
+mod congestion;
 mod event;
+mod filter;
 mod option;
 mod unbound;
 
 pub use bound::{
-    ConnectState, NeedIfacePoll, RawTcpSocketExt, TcpConnection, TcpListener, UdpSocket,
+    ConnectState, IcmpError, IcmpSocket, NeedIfacePoll, PacketInfo, PacketSocket, PacketType,
+    RawIpSocket, RawTcpSocketExt, TcpConnection, TcpListener, UdpIcmpError, UdpRecvInfo, UdpSocket,
+    ETH_P_ALL,
 };
-pub(crate) use bound::{TcpConnectionBg, TcpListenerBg, TcpProcessResult, UdpSocketBg};
+pub(crate) use bound::{
+    IcmpSocketBg, PacketSocketBg, RawIpSocketBg, TcpConnectionBg, TcpListenerBg, TcpProcessResult,
+    UdpSocketBg,
+};
+pub use congestion::{CongestionAlgorithm, CongestionInfo, CongestionState};
 pub use event::{SocketEventObserver, SocketEvents};
+pub use filter::PacketFilter;
 pub use option::{RawTcpOption, RawTcpSetOption};
+pub use smoltcp::socket::{tcp::State as TcpState, udp::UdpMetadata};
 pub use unbound::{
     RawUdpSocket, TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN,
 };
@@ -50,3 +50,17 @@ pub const TCP_SEND_BUF_LEN: usize = 65536 * 2;
 pub const UDP_SEND_PAYLOAD_LEN: usize = 65536;
 pub const UDP_RECV_PAYLOAD_LEN: usize = 65536;
 const UDP_METADATA_LEN: usize = 256;
+
+// ICMP socket buffer sizes:
+pub(super) const ICMP_SEND_BUF_LEN: usize = 65536;
+pub(super) const ICMP_RECV_BUF_LEN: usize = 65536;
+
+// Raw IP socket buffer sizes:
+pub(super) const RAW_SEND_BUF_LEN: usize = 65536;
+pub(super) const RAW_RECV_BUF_LEN: usize = 65536;
+
+// Packet socket buffer sizes:
+//
+// A packet socket may capture all the frames on an iface, so its receive buffer is larger.
+pub(super) const PACKET_SEND_BUF_LEN: usize = 65536;
+pub(super) const PACKET_RECV_BUF_LEN: usize = 65536 * 4;
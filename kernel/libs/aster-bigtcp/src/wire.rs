@@ -1,5 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
-pub use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, IpEndpoint, Ipv4Address, Ipv4Cidr};
+pub use smoltcp::wire::{
+    EthernetAddress, EthernetFrame, EthernetProtocol, EthernetRepr, HardwareAddress,
+    Icmpv4DstUnreachable, IpAddress, IpCidr, IpEndpoint, IpProtocol, IpVersion, Ipv4Address,
+    Ipv4Cidr, Ipv6Address, Ipv6Cidr, ETHERNET_HEADER_LEN,
+};
 
 pub type PortNum = u16;
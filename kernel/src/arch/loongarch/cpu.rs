@@ -137,6 +137,83 @@ impl SigContext {
     }
 }
 
+/// Represents the general-purpose registers of a traced thread.
+///
+/// This is read and modified by tracers via `ptrace`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/arch/loongarch/include/uapi/asm/ptrace.h>
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub struct UserRegs {
+    zero: usize,
+    ra: usize,
+    tp: usize,
+    sp: usize,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+    a5: usize,
+    a6: usize,
+    a7: usize,
+    t0: usize,
+    t1: usize,
+    t2: usize,
+    t3: usize,
+    t4: usize,
+    t5: usize,
+    t6: usize,
+    t7: usize,
+    t8: usize,
+    r21: usize,
+    fp: usize,
+    s0: usize,
+    s1: usize,
+    s2: usize,
+    s3: usize,
+    s4: usize,
+    s5: usize,
+    s6: usize,
+    s7: usize,
+    s8: usize,
+    orig_a0: usize,
+    csr_era: usize,
+    csr_badv: usize,
+    reserved: [usize; 10],
+}
+
+impl UserRegs {
+    /// Creates the registers from the user context.
+    ///
+    /// On LoongArch, the number of the system call being traced is always kept in `a7`.
+    pub fn new(src: &UserContext, _syscall_num: Option<usize>) -> Self {
+        let gp_regs = src.general_regs();
+
+        let mut regs = Self {
+            orig_a0: gp_regs.a0,
+            csr_era: src.instruction_pointer(),
+            ..Self::default()
+        };
+        copy_gp_regs!(gp_regs, regs);
+
+        regs
+    }
+
+    pub fn copy_user_regs_to(&self, dst: &mut UserContext) {
+        let gp_regs = dst.general_regs_mut();
+        copy_gp_regs!(self, gp_regs);
+        dst.set_instruction_pointer(self.csr_era);
+    }
+
+    /// Returns the number of the system call to execute.
+    ///
+    /// Tracers can change it at syscall-entry stops. `usize::MAX` means to skip the system call.
+    pub fn syscall_num(&self) -> usize {
+        self.a7
+    }
+}
+
 impl TryFrom<&CpuExceptionInfo> for PageFaultInfo {
     // [`Err`] indicates that the [`CpuExceptionInfo`] is not a page fault,
     // with no additional error information.
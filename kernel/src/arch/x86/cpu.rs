@@ -12,7 +12,7 @@ use ostd::{
         tsc_freq,
     },
     cpu::{num_cpus, PinCurrentCpu},
-    mm::Vaddr,
+    mm::{Vaddr, MAX_USERSPACE_VADDR},
     sync::SpinLock,
     task::DisabledPreemptGuard,
     Pod,
@@ -130,6 +130,97 @@ impl SigContext {
     }
 }
 
+/// Represents the general-purpose registers of a traced thread.
+///
+/// This is read and modified by tracers via `ptrace`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/arch/x86/include/asm/user_64.h>
+#[derive(Clone, Copy, Debug, Default, Pod)]
+#[repr(C)]
+pub struct UserRegs {
+    r15: usize,
+    r14: usize,
+    r13: usize,
+    r12: usize,
+    rbp: usize,
+    rbx: usize,
+    r11: usize,
+    r10: usize,
+    r9: usize,
+    r8: usize,
+    rax: usize,
+    rcx: usize,
+    rdx: usize,
+    rsi: usize,
+    rdi: usize,
+    orig_rax: usize,
+    rip: usize,
+    cs: usize,
+    rflags: usize,
+    rsp: usize,
+    ss: usize,
+    fs_base: usize,
+    gs_base: usize,
+    ds: usize,
+    es: usize,
+    fs: usize,
+    gs: usize,
+}
+
+/// The code segment selector of user space (GDT index 5, RPL 3).
+const USER_CS: usize = 0x2b;
+/// The stack segment selector of user space (GDT index 4, RPL 3).
+const USER_SS: usize = 0x23;
+
+/// The flags in `rflags` that can be modified by tracers.
+///
+/// Unlike Linux, the trap flag (TF) is excluded because single-stepping is not supported yet.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/arch/x86/kernel/ptrace.c>
+const USER_RFLAGS_MASK: usize = 0x50cd5;
+
+impl UserRegs {
+    /// Creates the registers from the user context.
+    ///
+    /// `syscall_num` is the number of the system call being traced, if any.
+    pub fn new(src: &UserContext, syscall_num: Option<usize>) -> Self {
+        let gp_regs = src.general_regs();
+
+        let mut regs = Self {
+            orig_rax: syscall_num.unwrap_or(usize::MAX),
+            cs: USER_CS,
+            ss: USER_SS,
+            fs_base: gp_regs.fsbase,
+            gs_base: gp_regs.gsbase,
+            ..Self::default()
+        };
+        copy_gp_regs!(gp_regs, regs);
+
+        regs
+    }
+
+    pub fn copy_user_regs_to(&self, dst: &mut UserContext) {
+        let rflags = dst.rflags();
+
+        let gp_regs = dst.general_regs_mut();
+        copy_gp_regs!(self, gp_regs);
+        gp_regs.rflags = (rflags & !USER_RFLAGS_MASK) | (self.rflags & USER_RFLAGS_MASK);
+
+        // FIXME: Linux reports an error if the new base is not a user space address, while we
+        // silently keep the old base.
+        if self.fs_base < MAX_USERSPACE_VADDR {
+            gp_regs.fsbase = self.fs_base;
+        }
+    }
+
+    /// Returns the number of the system call to execute.
+    ///
+    /// Tracers can change it at syscall-entry stops. `usize::MAX` means to skip the system call.
+    pub fn syscall_num(&self) -> usize {
+        self.orig_rax
+    }
+}
+
 impl From<&RawPageFaultInfo> for PageFaultInfo {
     fn from(raw_info: &RawPageFaultInfo) -> Self {
         let required_perms = if raw_info
@@ -21,6 +21,9 @@ impl From<&CpuException> for FaultSignal {
             CpuException::X87FloatingPointException | CpuException::SIMDFloatingPointException => {
                 (SIGFPE, FPE_FLTDIV, None)
             }
+            // Linux reports `SI_KERNEL` instead of `TRAP_BRKPT` for `int3`.
+            // Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/arch/x86/kernel/traps.c>
+            CpuException::BreakPoint => (SIGTRAP, SI_KERNEL, None),
             CpuException::BoundRangeExceeded => (SIGSEGV, SEGV_BNDERR, None),
             CpuException::AlignmentCheck => (SIGBUS, BUS_ADRALN, None),
             CpuException::InvalidOpcode => (SIGILL, ILL_ILLOPC, None),
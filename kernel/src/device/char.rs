@@ -219,6 +219,13 @@ impl Device for CharFile {
 /// for I/O related traits.
 pub struct OpenCharFile(Arc<dyn FileIo>);
 
+impl OpenCharFile {
+    /// Returns the file-like object that is returned by the char device when opened.
+    pub fn inner(&self) -> &Arc<dyn FileIo> {
+        &self.0
+    }
+}
+
 #[inherit_methods(from = "self.0")]
 impl InodeIo for OpenCharFile {
     fn read_at(
@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `/dev/fuse` device.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/fuse/dev.c>
+
+use device_id::{DeviceId, MinorId};
+
+use crate::{
+    device::char::{CharDevice, DevtmpfsName, OpenCharFile},
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        fuse::FuseConn,
+        inode_handle::{FileIo, InodeHandle},
+        utils::{InodeIo, StatusFlags},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable},
+};
+
+const FUSE_MINOR: u32 = 229;
+
+/// The `/dev/fuse` device.
+#[derive(Debug)]
+pub struct FuseDevice {
+    id: DeviceId,
+}
+
+impl FuseDevice {
+    pub fn new() -> Arc<Self> {
+        let major = super::MISC_MAJOR.get().unwrap().get();
+        let minor = MinorId::new(FUSE_MINOR);
+
+        Arc::new(Self {
+            id: DeviceId::new(major, minor),
+        })
+    }
+}
+
+impl CharDevice for FuseDevice {
+    fn devtmpfs_name(&self) -> DevtmpfsName<'_> {
+        DevtmpfsName::new("fuse", None)
+    }
+
+    fn id(&self) -> DeviceId {
+        self.id
+    }
+
+    fn open(&self) -> Result<Arc<dyn FileIo>> {
+        // Each opened file owns a new connection, which will be taken over by a mount.
+        Ok(Arc::new(FuseDevFile {
+            conn: FuseConn::new(),
+        }))
+    }
+}
+
+/// An opened `/dev/fuse` file, via which a daemon serves a FUSE file system.
+struct FuseDevFile {
+    conn: Arc<FuseConn>,
+}
+
+impl InodeIo for FuseDevFile {
+    fn read_at(
+        &self,
+        _offset: usize,
+        writer: &mut VmWriter,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
+        let is_nonblocking = status_flags.contains(StatusFlags::O_NONBLOCK);
+        self.conn.read_request(writer, is_nonblocking)
+    }
+
+    fn write_at(
+        &self,
+        _offset: usize,
+        reader: &mut VmReader,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        self.conn.write_reply(reader)
+    }
+}
+
+impl Pollable for FuseDevFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.conn.poll(mask, poller)
+    }
+}
+
+impl FileIo for FuseDevFile {
+    fn check_seekable(&self) -> Result<()> {
+        return_errno_with_message!(Errno::ESPIPE, "the FUSE device is not seekable");
+    }
+
+    fn is_offset_aware(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for FuseDevFile {
+    fn drop(&mut self) {
+        // The daemon exits, so the pending and future requests can never be replied.
+        self.conn.abort();
+    }
+}
+
+/// Returns the FUSE connection of an opened `/dev/fuse` file.
+pub fn conn_of_file(file: &dyn FileLike) -> Result<Arc<FuseConn>> {
+    let error = || Error::with_message(Errno::EINVAL, "the file is not an opened FUSE device");
+
+    let file_io = file
+        .downcast_ref::<InodeHandle>()
+        .and_then(|handle| handle.file_io())
+        .ok_or_else(error)?;
+    let dev_file = file_io
+        .downcast_ref::<OpenCharFile>()
+        .and_then(|char_file| char_file.inner().as_ref().downcast_ref::<FuseDevFile>())
+        .ok_or_else(error)?;
+
+    Ok(dev_file.conn.clone())
+}
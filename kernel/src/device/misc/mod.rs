@@ -9,6 +9,8 @@ use spin::Once;
 
 use super::char::{acquire_major, MajorIdOwner};
 
+pub mod fuse;
+
 #[cfg(all(target_arch = "x86_64", feature = "cvm_guest"))]
 pub mod tdxguest;
 
@@ -17,6 +19,8 @@ static MISC_MAJOR: Once<MajorIdOwner> = Once::new();
 pub(super) fn init_in_first_kthread() {
     MISC_MAJOR.call_once(|| acquire_major(MajorId::new(10)).unwrap());
 
+    super::char::register(fuse::FuseDevice::new()).unwrap();
+
     #[cfg(target_arch = "x86_64")]
     ostd::if_tdx_enabled!({
         super::char::register(tdxguest::TdxGuest::new()).unwrap();
@@ -6,6 +6,7 @@ mod evdev;
 mod fb;
 mod mem;
 pub mod misc;
+mod mqueue;
 mod pty;
 mod shm;
 pub mod tty;
@@ -59,6 +60,8 @@ pub fn init_in_first_process(ctx: &Context) -> Result<()> {
 
     shm::init_in_first_process(&fs_resolver, ctx)?;
 
+    mqueue::init_in_first_process(&fs_resolver, ctx)?;
+
     char::init_in_first_process(&fs_resolver)?;
 
     disk::init_in_first_process(&fs_resolver)?;
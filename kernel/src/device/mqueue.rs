@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    fs::{
+        fs_resolver::{FsPath, FsResolver},
+        mqueue::MqueueFs,
+        path::PerMountFlags,
+        utils::{chmod, InodeType},
+    },
+    prelude::*,
+};
+
+/// Initializes "/dev/mqueue" for POSIX message queues.
+pub fn init_in_first_process(fs_resolver: &FsResolver, ctx: &Context) -> Result<()> {
+    let dev_path = fs_resolver.lookup(&FsPath::try_from("/dev")?)?;
+
+    // Create the "mqueue" directory under "/dev" and mount the mqueue file system on it.
+    let mqueue_path =
+        dev_path.new_fs_child("mqueue", InodeType::Dir, chmod!(InodeMode::S_ISVTX, a+rwx))?;
+    mqueue_path.mount(MqueueFs::singleton().clone(), PerMountFlags::default(), ctx)?;
+    log::debug!("Mount MqueueFs at \"/dev/mqueue\"");
+    Ok(())
+}
@@ -1,14 +1,17 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::mem::offset_of;
+
 use id_alloc::IdAlloc;
 use ostd::{const_assert, mm::io_util::HasVmReaderWriter};
 
 use super::{
     block_ptr::Ext2Bid,
     fs::Ext2,
-    inode::{Inode, InodeDesc, RawInode},
+    inode::{Inode, InodeDesc},
     prelude::*,
     super_block::SuperBlock,
+    utils::{crc16, crc32c},
 };
 
 /// Blocks are clustered into block groups in order to reduce fragmentation and minimise
@@ -22,6 +25,7 @@ pub(super) struct BlockGroup {
 struct BlockGroupImpl {
     inode_table_bid: Ext2Bid,
     raw_inodes_size: usize,
+    blocks_count: usize,
     inner: RwMutex<Inner>,
     fs: Weak<Ext2>,
 }
@@ -36,17 +40,42 @@ impl BlockGroup {
         fs: Weak<Ext2>,
     ) -> Result<Self> {
         let raw_inodes_size = (super_block.inodes_per_group() as usize) * super_block.inode_size();
+        let blocks_count = if (idx as u32) < super_block.block_groups_count() - 1 {
+            super_block.blocks_per_group()
+        } else {
+            // The last block group may have less blocks than others.
+            super_block.total_blocks() - super_block.blocks_per_group() * idx as u32
+        } as usize;
 
         let bg_impl = {
             let metadata = {
                 let descriptor = {
                     // Read the block group descriptor
                     // TODO: if the main is corrupted, should we load the backup?
-                    let offset = idx * size_of::<RawGroupDescriptor>();
-                    let raw_descriptor = group_descriptors_segment
-                        .read_val::<RawGroupDescriptor>(offset)
-                        .unwrap();
-                    GroupDescriptor::from(raw_descriptor)
+                    let desc_size = super_block.group_descriptor_size();
+                    let raw_descriptor = RawGroupDescriptor::read_from(
+                        group_descriptors_segment,
+                        idx * desc_size,
+                        desc_size,
+                    );
+                    if super_block.has_group_desc_csum() {
+                        let csum_seed = super_block
+                            .has_metadata_csum()
+                            .then(|| super_block.csum_seed());
+                        let checksum = raw_descriptor.calc_checksum(
+                            idx,
+                            desc_size,
+                            csum_seed,
+                            super_block.uuid(),
+                        );
+                        if raw_descriptor.checksum != checksum {
+                            return_errno_with_message!(
+                                Errno::EBADMSG,
+                                "bad group descriptor checksum"
+                            );
+                        }
+                    }
+                    GroupDescriptor::try_from(raw_descriptor)?
                 };
 
                 let get_bitmap = |bid: Ext2Bid, capacity: usize| -> Result<IdAlloc> {
@@ -58,19 +87,21 @@ impl BlockGroup {
                     Ok(IdAlloc::from_bytes_with_capacity(&buf, capacity))
                 };
 
-                let block_bitmap = {
-                    let num_blocks = if (idx as u32) < super_block.block_groups_count() - 1 {
-                        super_block.blocks_per_group()
-                    } else {
-                        // The last block group may have less blocks than others.
-                        super_block.total_blocks() - super_block.blocks_per_group() * idx as u32
-                    };
-                    get_bitmap(descriptor.block_bitmap_bid, num_blocks as usize)?
+                let block_bitmap = if descriptor.flags.contains(GroupFlags::BLOCK_UNINIT) {
+                    // The bitmap on the disk is not initialized,
+                    // so we build it from the metadata blocks in this group.
+                    init_block_bitmap(&descriptor, idx, blocks_count, super_block)
+                } else {
+                    get_bitmap(descriptor.block_bitmap_bid, blocks_count)?
+                };
+                let inode_bitmap = if descriptor.flags.contains(GroupFlags::INODE_UNINIT) {
+                    IdAlloc::with_capacity(super_block.inodes_per_group() as usize)
+                } else {
+                    get_bitmap(
+                        descriptor.inode_bitmap_bid,
+                        super_block.inodes_per_group() as usize,
+                    )?
                 };
-                let inode_bitmap = get_bitmap(
-                    descriptor.inode_bitmap_bid,
-                    super_block.inodes_per_group() as usize,
-                )?;
 
                 GroupMetadata {
                     descriptor,
@@ -82,6 +113,7 @@ impl BlockGroup {
             Arc::new(BlockGroupImpl {
                 inode_table_bid: metadata.descriptor.inode_table_bid,
                 raw_inodes_size,
+                blocks_count,
                 inner: RwMutex::new(Inner {
                     metadata: Dirty::new(metadata),
                     inode_cache: BTreeMap::new(),
@@ -132,15 +164,10 @@ impl BlockGroup {
     /// This method may load the raw inode metadata from block device.
     fn load_inode(&self, inode_idx: u32) -> Result<Arc<Inode>> {
         let fs = self.fs();
-        let raw_inode = {
-            let offset = (inode_idx as usize) * fs.inode_size();
-            self.raw_inodes_cache
-                .pages()
-                .read_val::<RawInode>(offset)
-                .unwrap()
-        };
-        let inode_desc = Dirty::new(InodeDesc::try_from(raw_inode)?);
         let ino = inode_idx + self.idx as u32 * fs.inodes_per_group() + 1;
+        let mut raw_inode = vec![0u8; fs.inode_size()];
+        self.read_raw_inode(inode_idx, &mut raw_inode);
+        let inode_desc = Dirty::new(InodeDesc::from_raw(&raw_inode, ino, &fs)?);
 
         Ok(Inode::new(ino, self.idx, inode_desc, Arc::downgrade(&fs)))
     }
@@ -164,7 +191,12 @@ impl BlockGroup {
         }
 
         // The slow path
-        self.bg_impl.inner.write().metadata.alloc_inode(is_dir)
+        let inodes_per_group = self.fs().inodes_per_group();
+        self.bg_impl
+            .inner
+            .write()
+            .metadata
+            .alloc_inode(is_dir, inodes_per_group)
     }
 
     /// Frees the allocated inode idx.
@@ -214,15 +246,37 @@ impl BlockGroup {
         inner.metadata.free_blocks(range);
     }
 
-    /// Writes back the raw inode metadata to the raw inode metadata cache.
-    pub fn sync_raw_inode(&self, inode_idx: u32, raw_inode: &RawInode) {
-        let offset = (inode_idx as usize) * self.fs().inode_size();
+    /// Reads the on-disk inode slot from the raw inode metadata cache.
+    ///
+    /// The length of `buf` must be the size of inode.
+    pub fn read_raw_inode(&self, inode_idx: u32, buf: &mut [u8]) {
+        let offset = (inode_idx as usize) * buf.len();
+        self.raw_inodes_cache
+            .pages()
+            .read_bytes(offset, buf)
+            .unwrap();
+    }
+
+    /// Writes back the on-disk inode slot to the raw inode metadata cache.
+    ///
+    /// The length of `buf` must be the size of inode.
+    pub fn write_raw_inode(&self, inode_idx: u32, buf: &[u8]) {
+        let offset = (inode_idx as usize) * buf.len();
         self.raw_inodes_cache
             .pages()
-            .write_val(offset, raw_inode)
+            .write_bytes(offset, buf)
             .unwrap();
     }
 
+    /// Zeroes the on-disk inode slot in the raw inode metadata cache.
+    ///
+    /// This must be done for a newly allocated inode,
+    /// whose slot may contain stale data like the in-inode extended attributes.
+    pub fn clear_raw_inode(&self, inode_idx: u32) {
+        let zeros = vec![0u8; self.fs().inode_size()];
+        self.write_raw_inode(inode_idx, &zeros);
+    }
+
     /// Writes back the metadata of this group.
     pub fn sync_metadata(&self) -> Result<()> {
         if !self.bg_impl.inner.read().metadata.is_dirty() {
@@ -231,24 +285,36 @@ impl BlockGroup {
 
         let mut inner = self.bg_impl.inner.write();
         let fs = self.fs();
+        let inode_bitmap =
+            bitmap_block(&inner.metadata.inode_bitmap, fs.inodes_per_group() as usize);
+        let block_bitmap = bitmap_block(&inner.metadata.block_bitmap, self.bg_impl.blocks_count);
+
         // Writes back the descriptor.
-        let raw_descriptor = RawGroupDescriptor::from(&inner.metadata.descriptor);
-        self.fs().sync_group_descriptor(self.idx, &raw_descriptor)?;
+        let mut raw_descriptor = RawGroupDescriptor::from(&inner.metadata.descriptor);
+        if let Some(seed) = fs.csum_seed() {
+            // The checksums cover the bitmap bits of a full group.
+            let inode_bitmap_csum =
+                crc32c(seed, &inode_bitmap[..fs.inodes_per_group() as usize / 8]);
+            let block_bitmap_csum =
+                crc32c(seed, &block_bitmap[..fs.blocks_per_group() as usize / 8]);
+            raw_descriptor.set_bitmap_checksums(
+                inode_bitmap_csum,
+                block_bitmap_csum,
+                fs.group_descriptor_size(),
+            );
+        }
+        fs.sync_group_descriptor(self.idx, &mut raw_descriptor)?;
 
         let mut bio_waiter = BioWaiter::new();
         // Writes back the inode bitmap.
         let inode_bitmap_bid = Bid::new(inner.metadata.descriptor.inode_bitmap_bid as u64);
-        bio_waiter.concat(fs.block_device().write_bytes_async(
-            inode_bitmap_bid.to_offset(),
-            inner.metadata.inode_bitmap.as_bytes(),
-        )?);
+        bio_waiter
+            .concat(fs.write_metadata_bytes_async(inode_bitmap_bid.to_offset(), &inode_bitmap)?);
 
         // Writes back the block bitmap.
         let block_bitmap_bid = Bid::new(inner.metadata.descriptor.block_bitmap_bid as u64);
-        bio_waiter.concat(fs.block_device().write_bytes_async(
-            block_bitmap_bid.to_offset(),
-            inner.metadata.block_bitmap.as_bytes(),
-        )?);
+        bio_waiter
+            .concat(fs.write_metadata_bytes_async(block_bitmap_bid.to_offset(), &block_bitmap)?);
 
         // Waits for the completion of all submitted bios.
         bio_waiter.wait().ok_or_else(|| {
@@ -349,7 +415,7 @@ impl PageCacheBackend for BlockGroupImpl {
         self.fs
             .upgrade()
             .unwrap()
-            .write_blocks_async(bid, bio_segment)
+            .write_metadata_blocks_async(bid, bio_segment)
     }
 
     fn npages(&self) -> usize {
@@ -375,13 +441,22 @@ impl GroupMetadata {
         self.inode_bitmap.is_allocated(inode_idx as usize)
     }
 
-    pub fn alloc_inode(&mut self, is_dir: bool) -> Option<u32> {
-        let inode_idx = self.inode_bitmap.alloc()?;
+    pub fn alloc_inode(&mut self, is_dir: bool, inodes_per_group: u32) -> Option<u32> {
+        let inode_idx = self.inode_bitmap.alloc()? as u32;
         self.dec_free_inodes();
         if is_dir {
             self.inc_dirs();
         }
-        Some(inode_idx as u32)
+
+        // The inode bitmap will be written back, so it is no longer uninitialized.
+        self.descriptor.flags.remove(GroupFlags::INODE_UNINIT);
+        // Keep the inodes beyond the high watermark unused,
+        // which can be skipped by the checker.
+        let used_inodes = inodes_per_group - self.descriptor.itable_unused as u32;
+        if inode_idx >= used_inodes {
+            self.descriptor.itable_unused = (inodes_per_group - inode_idx - 1) as u16;
+        }
+        Some(inode_idx)
     }
 
     pub fn free_inode(&mut self, inode_idx: u32, is_dir: bool) {
@@ -405,6 +480,8 @@ impl GroupMetadata {
                 continue;
             };
             self.dec_free_blocks(current_count as u16);
+            // The block bitmap will be written back, so it is no longer uninitialized.
+            self.descriptor.flags.remove(GroupFlags::BLOCK_UNINIT);
             return Some((range.start as Ext2Bid)..(range.end as Ext2Bid));
         }
         None
@@ -459,6 +536,58 @@ impl GroupMetadata {
     }
 }
 
+/// Builds the block bitmap of a group whose bitmap on the disk is uninitialized.
+///
+/// Only the metadata blocks are in use in such a group.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/balloc.c#L177>
+fn init_block_bitmap(
+    descriptor: &GroupDescriptor,
+    idx: usize,
+    blocks_count: usize,
+    super_block: &SuperBlock,
+) -> IdAlloc {
+    let mut bitmap = IdAlloc::with_capacity(blocks_count);
+
+    // The superblock, the group descriptor table and its reserved blocks.
+    if idx == 0 || super_block.is_backup_group(idx) {
+        let base_meta_blocks =
+            1 + super_block.group_descriptor_blocks() + super_block.reserved_gdt_blocks();
+        for block_idx in 0..base_meta_blocks.min(blocks_count) {
+            bitmap.alloc_specific(block_idx);
+        }
+    }
+
+    // The bitmaps and the inode table, which may be located in other groups if `FLEX_BG` is set.
+    let group_start = (idx * super_block.blocks_per_group() as usize) as Ext2Bid;
+    let inode_table_blocks = ((super_block.inodes_per_group() as usize * super_block.inode_size())
+        .div_ceil(BLOCK_SIZE)) as Ext2Bid;
+    let inode_table = descriptor.inode_table_bid..descriptor.inode_table_bid + inode_table_blocks;
+    for bid in [descriptor.block_bitmap_bid, descriptor.inode_bitmap_bid]
+        .into_iter()
+        .chain(inode_table)
+    {
+        if let Some(block_idx) = bid.checked_sub(group_start) {
+            if (block_idx as usize) < blocks_count {
+                bitmap.alloc_specific(block_idx as usize);
+            }
+        }
+    }
+
+    bitmap
+}
+
+/// Returns the content of the bitmap block, whose bits beyond the `capacity` are set.
+fn bitmap_block(bitmap: &IdAlloc, capacity: usize) -> Vec<u8> {
+    let mut buf = vec![0xffu8; BLOCK_SIZE];
+    let nbytes = capacity / 8;
+    buf[..nbytes].copy_from_slice(&bitmap.as_bytes()[..nbytes]);
+    if capacity % 8 != 0 {
+        buf[nbytes] = bitmap.as_bytes()[nbytes] | (0xff << (capacity % 8));
+    }
+    buf
+}
+
 /// The in-memory rust block group descriptor.
 ///
 /// The block group descriptor contains information regarding where important data
@@ -477,26 +606,60 @@ struct GroupDescriptor {
     free_inodes_count: u16,
     /// Number of directories in group
     dirs_count: u16,
+    /// Group flags
+    flags: GroupFlags,
+    /// Number of unused inodes at the end of the inode table
+    itable_unused: u16,
+    //
+    // The following fields are not used by the driver,
+    // but must be preserved when writing back the descriptor.
+    //
+    exclude_bitmap: u32,
+    exclude_bitmap_hi: u32,
 }
 
-impl From<RawGroupDescriptor> for GroupDescriptor {
-    fn from(desc: RawGroupDescriptor) -> Self {
-        Self {
+impl TryFrom<RawGroupDescriptor> for GroupDescriptor {
+    type Error = crate::error::Error;
+
+    fn try_from(desc: RawGroupDescriptor) -> Result<Self> {
+        if desc.block_bitmap_hi != 0 || desc.inode_bitmap_hi != 0 || desc.inode_table_hi != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the 64-bit block number is not supported");
+        }
+
+        Ok(Self {
             block_bitmap_bid: desc.block_bitmap,
             inode_bitmap_bid: desc.inode_bitmap,
             inode_table_bid: desc.inode_table,
             free_blocks_count: desc.free_blocks_count,
             free_inodes_count: desc.free_inodes_count,
             dirs_count: desc.dirs_count,
-        }
+            flags: GroupFlags::from_bits_truncate(desc.flags),
+            itable_unused: desc.itable_unused,
+            exclude_bitmap: desc.exclude_bitmap,
+            exclude_bitmap_hi: desc.exclude_bitmap_hi,
+        })
+    }
+}
+
+bitflags! {
+    /// Block group flags.
+    struct GroupFlags: u16 {
+        /// Inode table and bitmap are not initialized
+        const INODE_UNINIT = 1 << 0;
+        /// Block bitmap is not initialized
+        const BLOCK_UNINIT = 1 << 1;
+        /// Inode table is zeroed
+        const ITABLE_ZEROED = 1 << 2;
     }
 }
 
-const_assert!(size_of::<RawGroupDescriptor>() == 32);
+const_assert!(size_of::<RawGroupDescriptor>() == 64);
 
 /// The raw block group descriptor.
 ///
 /// The table starts on the first block following the superblock.
+///
+/// Only the first 32 bytes are stored on the disk unless the 64-bit feature is set.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod)]
 pub(super) struct RawGroupDescriptor {
@@ -506,8 +669,105 @@ pub(super) struct RawGroupDescriptor {
     pub free_blocks_count: u16,
     pub free_inodes_count: u16,
     pub dirs_count: u16,
-    pad: u16,
-    reserved: [u32; 3],
+    pub flags: u16,
+    pub exclude_bitmap: u32,
+    pub block_bitmap_csum: u16,
+    pub inode_bitmap_csum: u16,
+    pub itable_unused: u16,
+    pub checksum: u16,
+    //
+    // The following fields are valid if the 64-bit feature is set.
+    //
+    pub block_bitmap_hi: u32,
+    pub inode_bitmap_hi: u32,
+    pub inode_table_hi: u32,
+    pub free_blocks_count_hi: u16,
+    pub free_inodes_count_hi: u16,
+    pub dirs_count_hi: u16,
+    pub itable_unused_hi: u16,
+    pub exclude_bitmap_hi: u32,
+    pub block_bitmap_csum_hi: u16,
+    pub inode_bitmap_csum_hi: u16,
+    reserved: u32,
+}
+
+impl RawGroupDescriptor {
+    /// Reads a descriptor of `desc_size` bytes at the `offset` of the descriptor table.
+    pub fn read_from(table: &USegment, offset: usize, desc_size: usize) -> Self {
+        let mut desc = Self::new_zeroed();
+        let len = desc_size.min(size_of::<Self>());
+        table
+            .read_bytes(offset, &mut desc.as_bytes_mut()[..len])
+            .unwrap();
+        desc
+    }
+
+    /// Returns the bytes of the descriptor that are stored on the disk.
+    pub fn on_disk_bytes(&self, desc_size: usize) -> &[u8] {
+        &self.as_bytes()[..desc_size.min(size_of::<Self>())]
+    }
+
+    /// Computes the checksum of the descriptor of the `idx`-th group.
+    ///
+    /// The `csum_seed` is the seed of the metadata checksums if the filesystem has them,
+    /// otherwise the legacy CRC16 checksum seeded by the `uuid` is used.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/super.c#L3170>
+    pub fn calc_checksum(
+        &self,
+        idx: usize,
+        desc_size: usize,
+        csum_seed: Option<u32>,
+        uuid: &[u8; 16],
+    ) -> u16 {
+        let bytes = self.on_disk_bytes(desc_size);
+        let checksum_offset = offset_of!(Self, checksum);
+        let (before_checksum, after_checksum) = (
+            &bytes[..checksum_offset],
+            &bytes[checksum_offset + size_of::<u16>()..],
+        );
+        // The descriptor may be larger than the known fields, whose remaining bytes are zeros.
+        let padding = vec![0u8; desc_size - bytes.len()];
+        let group = (idx as u32).to_le_bytes();
+
+        match csum_seed {
+            Some(seed) => {
+                let mut crc = crc32c(seed, &group);
+                crc = crc32c(crc, before_checksum);
+                crc = crc32c(crc, &[0u8; size_of::<u16>()]);
+                crc = crc32c(crc, after_checksum);
+                crc = crc32c(crc, &padding);
+                (crc & 0xffff) as u16
+            }
+            None => {
+                // The checksum field is skipped rather than zeroed.
+                let mut crc = crc16(!0, uuid);
+                crc = crc16(crc, &group);
+                crc = crc16(crc, before_checksum);
+                crc = crc16(crc, after_checksum);
+                crc16(crc, &padding)
+            }
+        }
+    }
+
+    /// Sets the checksums of the bitmaps.
+    ///
+    /// The high halves are only stored if the descriptor is large enough.
+    fn set_bitmap_checksums(
+        &mut self,
+        inode_bitmap_csum: u32,
+        block_bitmap_csum: u32,
+        desc_size: usize,
+    ) {
+        self.inode_bitmap_csum = inode_bitmap_csum as u16;
+        self.block_bitmap_csum = block_bitmap_csum as u16;
+        if desc_size >= offset_of!(Self, block_bitmap_csum_hi) + size_of::<u16>() {
+            self.block_bitmap_csum_hi = (block_bitmap_csum >> 16) as u16;
+        }
+        if desc_size >= offset_of!(Self, inode_bitmap_csum_hi) + size_of::<u16>() {
+            self.inode_bitmap_csum_hi = (inode_bitmap_csum >> 16) as u16;
+        }
+    }
 }
 
 impl From<&GroupDescriptor> for RawGroupDescriptor {
@@ -519,8 +779,23 @@ impl From<&GroupDescriptor> for RawGroupDescriptor {
             free_blocks_count: desc.free_blocks_count,
             free_inodes_count: desc.free_inodes_count,
             dirs_count: desc.dirs_count,
-            pad: 0u16,
-            reserved: [0u32; 3],
+            flags: desc.flags.bits(),
+            exclude_bitmap: desc.exclude_bitmap,
+            block_bitmap_csum: 0,
+            inode_bitmap_csum: 0,
+            itable_unused: desc.itable_unused,
+            checksum: 0,
+            block_bitmap_hi: 0,
+            inode_bitmap_hi: 0,
+            inode_table_hi: 0,
+            free_blocks_count_hi: 0,
+            free_inodes_count_hi: 0,
+            dirs_count_hi: 0,
+            itable_unused_hi: 0,
+            exclude_bitmap_hi: desc.exclude_bitmap_hi,
+            block_bitmap_csum_hi: 0,
+            inode_bitmap_csum_hi: 0,
+            reserved: 0,
         }
     }
 }
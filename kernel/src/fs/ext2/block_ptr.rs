@@ -152,6 +152,38 @@ impl BidPath {
     }
 }
 
+/// The mapping from a run of consecutive file blocks to the device blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockMapping {
+    /// The file blocks are mapped to the device blocks in the range.
+    Mapped(Range<Ext2Bid>),
+    /// The file blocks are mapped to the device blocks in the range, which are
+    /// allocated but not initialized yet. They must be read as zeros.
+    Uninit(Range<Ext2Bid>),
+    /// The given number of file blocks are not mapped (i.e., a hole). They must be
+    /// read as zeros.
+    Hole(Ext2Bid),
+}
+
+impl BlockMapping {
+    /// Returns the number of file blocks in the mapping.
+    pub fn len(&self) -> Ext2Bid {
+        match self {
+            Self::Mapped(range) | Self::Uninit(range) => range.end - range.start,
+            Self::Hole(len) => *len,
+        }
+    }
+
+    /// Shortens the mapping to at most `max_len` file blocks.
+    pub fn truncate(self, max_len: Ext2Bid) -> Self {
+        match self {
+            Self::Mapped(range) => Self::Mapped(range.start..range.end.min(range.start + max_len)),
+            Self::Uninit(range) => Self::Uninit(range.start..range.end.min(range.start + max_len)),
+            Self::Hole(len) => Self::Hole(len.min(max_len)),
+        }
+    }
+}
+
 /// Direct pointers to blocks.
 pub const DIRECT_RANGE: core::ops::Range<usize> = 0..12;
 /// The number of direct blocks.
@@ -2,7 +2,13 @@
 
 #![expect(dead_code)]
 
-use super::{inode::MAX_FNAME_LEN, prelude::*};
+use super::{
+    hash::{dx_hash, DxHashVersion},
+    inode::MAX_FNAME_LEN,
+    prelude::*,
+    utils::crc32c,
+};
+use crate::fs::path::is_dot_or_dotdot;
 
 /// The data structure in a directory's data block. It is stored in a linked list.
 ///
@@ -104,6 +110,54 @@ impl DirEntryHeader {
             inode_type: DirEntryFileType::from(inode_type) as _,
         }
     }
+
+    /// Constructs the header of an unused record spanning `record_len` bytes.
+    fn new_unused(record_len: usize) -> Self {
+        Self {
+            ino: 0,
+            record_len: record_len as _,
+            name_len: 0,
+            inode_type: 0,
+        }
+    }
+}
+
+/// The fake entry at the end of each directory block, which holds the checksum of the block.
+///
+/// It is present only if the file system has metadata checksums.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct DirEntryTail {
+    /// The header of an unused entry with a special file type.
+    header: DirEntryHeader,
+    /// The checksum of the block.
+    checksum: u32,
+}
+
+impl DirEntryTail {
+    const LEN: usize = size_of::<Self>();
+    const OFFSET: usize = BLOCK_SIZE - Self::LEN;
+    const FILE_TYPE: u8 = 0xDE;
+
+    fn new() -> Self {
+        Self {
+            header: DirEntryHeader {
+                ino: 0,
+                record_len: Self::LEN as _,
+                name_len: 0,
+                inode_type: Self::FILE_TYPE,
+            },
+            checksum: 0,
+        }
+    }
+
+    /// Returns whether the header belongs to a `DirEntryTail`.
+    fn is_tail(header: &DirEntryHeader) -> bool {
+        header.ino == 0
+            && header.record_len as usize == Self::LEN
+            && header.name_len == 0
+            && header.inode_type == Self::FILE_TYPE
+    }
 }
 
 /// The type indicator in the `DirEntry`.
@@ -129,7 +183,7 @@ impl From<InodeType> for DirEntryFileType {
             InodeType::Dir => Self::Dir,
             InodeType::BlockDevice => Self::Block,
             InodeType::File => Self::File,
-            InodeType::SymLink => Self::Symlink,
+            InodeType::SymLink => Self::SymLink,
             InodeType::Socket => Self::Socket,
         }
     }
@@ -150,67 +204,128 @@ impl From<DirEntryFileType> for InodeType {
     }
 }
 
+/// The configurations of a directory that affect the layout of its entries.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct DirConfig {
+    /// Whether the entries are indexed by a hash tree.
+    pub is_indexed: bool,
+    /// Whether each block ends with a `DirEntryTail`.
+    pub has_csum_tail: bool,
+    /// The seed of the hash function for the hash tree.
+    pub hash_seed: [u32; 4],
+    /// Whether the hash function treats the characters as unsigned.
+    pub is_hash_unsigned: bool,
+}
+
+impl DirConfig {
+    /// Returns the length reserved for the `DirEntryTail` in each block.
+    fn tail_len(&self) -> usize {
+        if self.has_csum_tail {
+            DirEntryTail::LEN
+        } else {
+            0
+        }
+    }
+}
+
 /// A reader for reading `DirEntry` from the page cache.
 pub struct DirEntryReader<'a> {
     page_cache: &'a PageCache,
     from_offset: usize,
+    config: DirConfig,
     name_buf: Option<[u8; MAX_FNAME_LEN]>,
 }
 
 /// An iterator for iterating `DirEntryItem` from the
 /// page cache given a start offset.
+///
+/// The iteration stops at the first corrupted record.
 pub(super) struct DirEntryIter<'a> {
     page_cache: &'a PageCache,
     offset: usize,
+    end: usize,
+    include_unused: bool,
 }
 
 impl<'a> DirEntryReader<'a> {
-    /// Constructs a reader with the given page cache and offset.
-    pub(super) fn new(page_cache: &'a PageCache, from_offset: usize) -> Self {
+    /// Constructs a reader with the given page cache, offset and configurations.
+    pub(super) fn new(page_cache: &'a PageCache, from_offset: usize, config: DirConfig) -> Self {
         Self {
             page_cache,
             from_offset,
+            config,
             name_buf: None,
         }
     }
 
     /// Returns an iterator for iterating `DirEntryItem`s.
     pub fn iter(&self) -> DirEntryIter<'a> {
-        DirEntryIter {
-            page_cache: self.page_cache,
-            offset: self.from_offset,
-        }
+        DirEntryIter::new(self.page_cache, self.from_offset, usize::MAX, false)
     }
 
-    /// Returns an iterator for iterating `DirEntry`s.
-    pub fn iter_entries(&'a mut self) -> impl Iterator<Item = DirEntry> + 'a {
+    /// Returns an iterator for iterating `DirEntry`s along with the offset of their next records.
+    pub fn iter_entries(&'a mut self) -> impl Iterator<Item = (DirEntry, usize)> + 'a {
         let iter = self.iter();
         iter.filter_map(|entry_item| match self.read_name(&entry_item) {
-            Ok(name_buf) => Some(DirEntry {
-                header: entry_item.header,
-                name: CStr256::from(name_buf),
-            }),
+            Ok(name_buf) => Some((
+                DirEntry {
+                    header: entry_item.header,
+                    name: CStr256::from(name_buf),
+                },
+                entry_item.offset + entry_item.record_len(),
+            )),
             Err(_) => None,
         })
     }
 
     /// Whether the directory contains an entry with the given name.
     pub fn contains_entry(&mut self, name: &str) -> bool {
-        let mut iter = self.iter();
-        iter.any(|entry_item| {
-            if entry_item.name_len() != name.len() {
-                return false;
-            }
-            match self.read_name(&entry_item) {
-                Ok(name_buf) => name_buf == name.as_bytes(),
-                Err(_) => false,
-            }
-        })
+        self.find_entry_item(name).is_some()
     }
 
     /// Returns the target entry with the given name.
     pub fn find_entry_item(&mut self, name: &str) -> Option<DirEntryItem> {
-        let mut iter = self.iter();
+        if self.config.is_indexed && !is_dot_or_dotdot(name) {
+            // If the hash tree is corrupted, the entry may still be found by a linear search.
+            if let Ok(entry_item) = self.find_indexed_entry_item(name) {
+                return entry_item;
+            }
+        }
+
+        let iter = self.iter();
+        self.find_in(iter, name)
+    }
+
+    /// Returns the number of entries in the directory.
+    pub fn entry_count(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Looks up the entry with the given name via the hash tree.
+    fn find_indexed_entry_item(&mut self, name: &str) -> Result<Option<DirEntryItem>> {
+        let tree = DxTree::load(self.page_cache, &self.config)?;
+        let hash = tree.hash(name.as_bytes());
+        let mut frames = tree.probe(hash)?;
+        loop {
+            let block_offset = frames.last().unwrap().block(self.page_cache)? * BLOCK_SIZE;
+            let iter = DirEntryIter::new(
+                self.page_cache,
+                block_offset,
+                block_offset + BLOCK_SIZE,
+                false,
+            );
+            if let Some(entry_item) = self.find_in(iter, name) {
+                return Ok(Some(entry_item));
+            }
+
+            if !tree.next_leaf(&mut frames, hash)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Finds the entry with the given name from the iterator.
+    fn find_in(&mut self, mut iter: DirEntryIter<'a>, name: &str) -> Option<DirEntryItem> {
         let name_len = name.len();
         let name_bytes = name.as_bytes();
         iter.find(|entry_item| {
@@ -225,11 +340,6 @@ impl<'a> DirEntryReader<'a> {
         })
     }
 
-    /// Returns the number of entries in the directory.
-    pub fn entry_count(&self) -> usize {
-        self.iter().count()
-    }
-
     /// Reads the name of the entry from the page cache to the inner buffer.
     fn read_name(&mut self, entry_item: &DirEntryItem) -> Result<&[u8]> {
         if self.name_buf.is_none() {
@@ -245,22 +355,40 @@ impl<'a> DirEntryReader<'a> {
     }
 }
 
-impl DirEntryIter<'_> {
-    /// Reads a `DirEntryItem` at the current offset.
-    fn read_entry_item(&mut self) -> Result<DirEntryItem> {
-        if self.offset >= self.page_cache.pages().size() {
-            return_errno!(Errno::ENOENT);
+impl<'a> DirEntryIter<'a> {
+    /// Constructs an iterator over the records in `[offset, end)`.
+    ///
+    /// The unused records are yielded only if `include_unused` is true.
+    fn new(page_cache: &'a PageCache, offset: usize, end: usize, include_unused: bool) -> Self {
+        Self {
+            page_cache,
+            offset,
+            end: end.min(page_cache.pages().size()),
+            include_unused,
         }
+    }
 
-        let header = self.read_header()?;
-        let record_len = header.record_len as usize;
-        let item = DirEntryItem {
-            header,
-            offset: self.offset,
-        };
+    /// Reads a `DirEntryItem` at the current offset, skipping the `DirEntryTail`.
+    fn read_entry_item(&mut self) -> Result<Option<DirEntryItem>> {
+        loop {
+            if self.offset + DirEntry::HEADER_LEN > self.end {
+                return Ok(None);
+            }
+
+            let header = self.read_header()?;
+            let item = DirEntryItem {
+                header,
+                offset: self.offset,
+            };
+            if !item.is_valid() {
+                return_errno_with_message!(Errno::EUCLEAN, "the directory entry is corrupted");
+            }
 
-        self.offset += record_len;
-        Ok(item)
+            self.offset += item.record_len();
+            if !item.is_tail() {
+                return Ok(Some(item));
+            }
+        }
     }
 
     /// Reads the header of the entry from the page cache.
@@ -269,9 +397,6 @@ impl DirEntryIter<'_> {
             .page_cache
             .pages()
             .read_val::<DirEntryHeader>(self.offset)?;
-        if header.ino == 0 {
-            return_errno!(Errno::ENOENT);
-        }
         Ok(header)
     }
 }
@@ -280,7 +405,15 @@ impl Iterator for DirEntryIter<'_> {
     type Item = DirEntryItem;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.read_entry_item().ok()
+        loop {
+            let Ok(Some(item)) = self.read_entry_item() else {
+                self.offset = self.end;
+                return None;
+            };
+            if item.ino() != 0 || self.include_unused {
+                return Some(item);
+            }
+        }
     }
 }
 
@@ -341,8 +474,30 @@ impl DirEntryItem {
     }
 
     /// Returns the length of the gap between the current entry and the next entry.
+    ///
+    /// The whole record is a gap if the entry is unused.
     pub fn gap_len(&self) -> usize {
-        self.record_len() - self.actual_len()
+        if self.ino() == 0 {
+            self.record_len()
+        } else {
+            self.record_len() - self.actual_len()
+        }
+    }
+
+    /// Returns whether the record is well-formed.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/dir.c#L71>
+    fn is_valid(&self) -> bool {
+        let record_len = self.record_len();
+        record_len >= DirEntry::PARENT_OFFSET
+            && record_len % DirEntry::ALIGN == 0
+            && record_len >= self.actual_len()
+            && self.offset % BLOCK_SIZE + record_len <= BLOCK_SIZE
+    }
+
+    /// Returns whether the record is a `DirEntryTail`.
+    fn is_tail(&self) -> bool {
+        self.offset % BLOCK_SIZE == DirEntryTail::OFFSET && DirEntryTail::is_tail(&self.header)
     }
 }
 
@@ -350,19 +505,28 @@ impl DirEntryItem {
 pub struct DirEntryWriter<'a> {
     page_cache: &'a PageCache,
     offset: usize,
+    config: DirConfig,
     name_buf: Option<[u8; MAX_FNAME_LEN]>,
 }
 
 impl<'a> DirEntryWriter<'a> {
-    /// Constructs a writer with the given page cache and offset.
-    pub(super) fn new(page_cache: &'a PageCache, from_offset: usize) -> Self {
+    /// Constructs a writer with the given page cache, offset and configurations.
+    pub(super) fn new(page_cache: &'a PageCache, from_offset: usize, config: DirConfig) -> Self {
         Self {
             page_cache,
             offset: from_offset,
+            config,
             name_buf: None,
         }
     }
 
+    /// Returns whether the entries are still indexed by a hash tree.
+    ///
+    /// The hash tree is dropped if it has no room for a new leaf block.
+    pub fn is_indexed(&self) -> bool {
+        self.config.is_indexed
+    }
+
     /// Writes a `DirEntry` at the current offset. The name is written after the header.
     pub fn write_entry(&mut self, header: &DirEntryHeader, name: &str) -> Result<()> {
         self.page_cache.pages().write_val(self.offset, header)?;
@@ -381,6 +545,16 @@ impl<'a> DirEntryWriter<'a> {
         Ok(())
     }
 
+    /// Writes the `DirEntryTail` at the end of the block if the directory has one.
+    fn write_tail(&mut self, block_offset: usize) -> Result<()> {
+        if self.config.has_csum_tail {
+            self.page_cache
+                .pages()
+                .write_val(block_offset + DirEntryTail::OFFSET, &DirEntryTail::new())?;
+        }
+        Ok(())
+    }
+
     /// Initializes two special `DirEntry`s ("." and "..") with the given inode numbers.
     pub fn init_dir(&mut self, self_ino: u32, parent_ino: u32) -> Result<()> {
         debug_assert!(self.page_cache.pages().size() == 0 && self.offset == 0);
@@ -392,8 +566,9 @@ impl<'a> DirEntryWriter<'a> {
         debug_assert_eq!(self.offset, DirEntry::PARENT_OFFSET);
 
         let mut parent_header = DirEntryHeader::new(parent_ino, InodeType::Dir, 2);
-        parent_header.record_len = (BLOCK_SIZE - self.offset) as _;
-        self.write_entry(&parent_header, "..")
+        parent_header.record_len = (BLOCK_SIZE - self.config.tail_len() - self.offset) as _;
+        self.write_entry(&parent_header, "..")?;
+        self.write_tail(0)
     }
 
     /// Appends a new `DirEntry` starting from the current offset.
@@ -401,17 +576,34 @@ impl<'a> DirEntryWriter<'a> {
     /// If there is a gap between existing entries, inserts the new entry into the gap;
     /// If there is no available space, expands the size and appends the new entry at the end.
     /// `check_existence` is determined to check if the new entry already exists.
+    ///
+    /// If the directory is indexed, the new entry is inserted into the leaf block
+    /// covering the hash of its name instead.
     pub fn append_new_entry(
         &mut self,
         header: DirEntryHeader,
         name: &str,
         check_existence: bool,
+    ) -> Result<()> {
+        debug_assert_eq!(header.name_len as usize, name.len());
+        if self.config.is_indexed {
+            self.append_indexed_entry(header, name, check_existence)
+        } else {
+            self.append_linear_entry(header, name, check_existence)
+        }
+    }
+
+    fn append_linear_entry(
+        &mut self,
+        header: DirEntryHeader,
+        name: &str,
+        check_existence: bool,
     ) -> Result<()> {
         let name_len = name.len();
-        debug_assert_eq!(header.name_len as usize, name_len);
         let name_bytes = name.as_bytes();
         let mut entry_item_with_enough_gap = None;
-        for entry_item in DirEntryReader::new(self.page_cache, self.offset).iter() {
+        let iter = DirEntryIter::new(self.page_cache, self.offset, usize::MAX, true);
+        for entry_item in iter {
             if entry_item_with_enough_gap.is_none()
                 && entry_item.gap_len() >= header.record_len as usize
             {
@@ -422,6 +614,7 @@ impl<'a> DirEntryWriter<'a> {
             }
 
             if check_existence
+                && entry_item.ino() != 0
                 && entry_item.name_len() == name_len
                 && self.read_name(&entry_item)? == name_bytes
             {
@@ -437,12 +630,204 @@ impl<'a> DirEntryWriter<'a> {
         Ok(())
     }
 
+    /// Inserts a new `DirEntry` into an indexed directory.
+    ///
+    /// The hash tree is never grown. If the leaf block is full and its index block has
+    /// no room for a new leaf block, the index is dropped and the entry is appended as
+    /// in a linear directory.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/namei.c#L2494>
+    fn append_indexed_entry(
+        &mut self,
+        header: DirEntryHeader,
+        name: &str,
+        check_existence: bool,
+    ) -> Result<()> {
+        if check_existence
+            && DirEntryReader::new(self.page_cache, 0, self.config)
+                .find_indexed_entry_item(name)?
+                .is_some()
+        {
+            return_errno!(Errno::EEXIST);
+        }
+
+        let tree = DxTree::load(self.page_cache, &self.config)?;
+        let hash = tree.hash(name.as_bytes());
+        let mut frames = tree.probe(hash)?;
+        let frame = frames.last_mut().unwrap();
+
+        let leaf_offset = frame.block(self.page_cache)? * BLOCK_SIZE;
+        if let Some(entry_item) = self.find_gap_in_block(leaf_offset, header.record_len as _) {
+            return self.append_entry_in_the_gap(entry_item, header, name);
+        }
+
+        if frame.count >= frame.limit {
+            let indirect_levels = tree.indirect_levels;
+            self.remove_index(indirect_levels)?;
+            self.offset = 0;
+            return self.append_linear_entry(header, name, false);
+        }
+
+        let target_offset = self.split_leaf(&tree, frame, hash)?;
+        let Some(entry_item) = self.find_gap_in_block(target_offset, header.record_len as _) else {
+            return_errno_with_message!(Errno::ENOSPC, "no space in the split leaf block");
+        };
+        self.append_entry_in_the_gap(entry_item, header, name)
+    }
+
+    /// Finds a record in the block with a gap large enough for a new entry.
+    fn find_gap_in_block(&self, block_offset: usize, record_len: usize) -> Option<DirEntryItem> {
+        DirEntryIter::new(
+            self.page_cache,
+            block_offset,
+            block_offset + BLOCK_SIZE,
+            true,
+        )
+        .find(|entry_item| entry_item.gap_len() >= record_len)
+    }
+
+    /// Splits the full leaf block pointed by the `frame` into two, moving the entries
+    /// with the larger hashes into a new block.
+    ///
+    /// Returns the offset of the block into which the entry with `hash` should be inserted.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/namei.c#L1992>
+    fn split_leaf(&mut self, tree: &DxTree, frame: &mut DxFrame, hash: u32) -> Result<usize> {
+        let leaf = frame.block(self.page_cache)?;
+
+        let mut entries = Vec::new();
+        let iter = DirEntryIter::new(
+            self.page_cache,
+            leaf * BLOCK_SIZE,
+            (leaf + 1) * BLOCK_SIZE,
+            false,
+        );
+        for entry_item in iter {
+            let name = self.read_name(&entry_item)?.to_vec();
+            entries.push((tree.hash(&name), entry_item, name));
+        }
+        if entries.len() < 2 {
+            return_errno_with_message!(Errno::ENOSPC, "the leaf block cannot be split");
+        }
+        entries.sort_by_key(|(hash, _, _)| *hash);
+
+        // Move the entries occupying about half of the block to the new block,
+        // or split by count if it leaves too few entries in either block.
+        let mut split = entries.len() / 2;
+        let mut moved_size = 0;
+        for (idx, (_, entry_item, _)) in entries.iter().enumerate().rev() {
+            let len = entry_item.actual_len();
+            if moved_size + len / 2 > BLOCK_SIZE / 2 {
+                if idx > 0 {
+                    split = idx + 1;
+                }
+                break;
+            }
+            moved_size += len;
+        }
+        let split = split.clamp(1, entries.len() - 1);
+
+        let split_hash = entries[split].0;
+        let is_continued = split_hash == entries[split - 1].0;
+
+        let new_leaf = self.page_cache.pages().size() / BLOCK_SIZE;
+        self.page_cache.resize((new_leaf + 1) * BLOCK_SIZE)?;
+        self.write_leaf_block(leaf, &entries[..split])?;
+        self.write_leaf_block(new_leaf, &entries[split..])?;
+
+        frame.insert_after(
+            self.page_cache,
+            DxEntry {
+                hash: split_hash | is_continued as u32,
+                block: new_leaf as u32,
+            },
+        )?;
+
+        if hash >= split_hash {
+            Ok(new_leaf * BLOCK_SIZE)
+        } else {
+            Ok(leaf * BLOCK_SIZE)
+        }
+    }
+
+    /// Rewrites the leaf block with the entries packed at its beginning.
+    fn write_leaf_block(
+        &mut self,
+        block: usize,
+        entries: &[(u32, DirEntryItem, Vec<u8>)],
+    ) -> Result<()> {
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        let end = BLOCK_SIZE - self.config.tail_len();
+
+        let mut offset = 0;
+        for (idx, (_, entry_item, name)) in entries.iter().enumerate() {
+            let mut header = entry_item.header;
+            header.record_len = if idx + 1 == entries.len() {
+                (end - offset) as _
+            } else {
+                entry_item.actual_len() as _
+            };
+            buf[offset..offset + DirEntry::HEADER_LEN].copy_from_slice(header.as_bytes());
+            buf[offset + DirEntry::HEADER_LEN..][..name.len()].copy_from_slice(name);
+            offset += header.record_len as usize;
+        }
+        if entries.is_empty() {
+            let header = DirEntryHeader::new_unused(end);
+            buf[..DirEntry::HEADER_LEN].copy_from_slice(header.as_bytes());
+        }
+        if self.config.has_csum_tail {
+            buf[end..].copy_from_slice(DirEntryTail::new().as_bytes());
+        }
+
+        self.page_cache
+            .pages()
+            .write_bytes(block * BLOCK_SIZE, &buf)?;
+        Ok(())
+    }
+
+    /// Drops the hash tree, turning the index blocks into empty leaf blocks.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/namei.c#L2372>
+    fn remove_index(&mut self, indirect_levels: u8) -> Result<()> {
+        let mut index_blocks = Vec::new();
+        if indirect_levels > 0 {
+            let root = DxFrame::load(self.page_cache, DX_ROOT_ENTRIES_OFFSET)?;
+            for idx in 0..root.count {
+                index_blocks.push(root.entry(self.page_cache, idx)?.block(self.page_cache)?);
+            }
+        }
+
+        let mut parent_header = self
+            .page_cache
+            .pages()
+            .read_val::<DirEntryHeader>(DirEntry::PARENT_OFFSET)?;
+        parent_header.record_len =
+            (BLOCK_SIZE - self.config.tail_len() - DirEntry::PARENT_OFFSET) as _;
+        self.offset = DirEntry::PARENT_OFFSET;
+        self.write_header_only(&parent_header)?;
+        self.write_tail(0)?;
+
+        for block in index_blocks {
+            self.write_leaf_block(block, &[])?;
+        }
+
+        self.config.is_indexed = false;
+        Ok(())
+    }
+
     fn append_entry_in_the_gap(
         &mut self,
         mut entry_with_enough_gap: DirEntryItem,
         mut header: DirEntryHeader,
         name: &str,
     ) -> Result<()> {
+        if entry_with_enough_gap.ino() == 0 {
+            // Take over the unused record.
+            header.record_len = entry_with_enough_gap.record_len() as u16;
+            self.offset = entry_with_enough_gap.offset;
+            return self.write_entry(&header, name);
+        }
+
         // Write in the gap between existing entries.
         header.record_len = entry_with_enough_gap.gap_len() as u16;
         entry_with_enough_gap.set_record_len(entry_with_enough_gap.actual_len());
@@ -457,64 +842,68 @@ impl<'a> DirEntryWriter<'a> {
         let old_size = self.page_cache.pages().size();
         let new_size = old_size + BLOCK_SIZE;
         self.page_cache.resize(new_size)?;
-        header.record_len = BLOCK_SIZE as _;
+        header.record_len = (BLOCK_SIZE - self.config.tail_len()) as _;
 
         self.offset = old_size;
-        self.write_entry(&header, name)
+        self.write_entry(&header, name)?;
+        self.write_tail(old_size)
     }
 
-    /// Removes and returns an existing `DirEntry` indicated by `name`.
+    /// Removes and returns the existing `DirEntry` indicated by `name` at the current offset.
+    ///
+    /// The entry is merged into the previous record in the same block, or marked as unused
+    /// if it is the first one. The last block of a linear directory is dropped once empty.
     pub fn remove_entry(&mut self, name: &str) -> Result<DirEntryItem> {
-        let mut pre_entry_item = None;
-        let name_len = name.len();
-        let name_bytes = name.as_bytes();
-        let mut iter = DirEntryReader::new(self.page_cache, DirEntry::PARENT_OFFSET).iter();
-        let Some(target_entry_item) = iter.find(|entry| {
-            if entry.offset < self.offset {
-                pre_entry_item = Some(*entry);
+        let target_entry_item = self.read_entry_item_at(self.offset, name)?;
+        let offset = target_entry_item.offset;
+        let block_offset = offset.align_down(BLOCK_SIZE);
+
+        let pre_entry_item = DirEntryIter::new(self.page_cache, block_offset, offset, true).last();
+        if let Some(mut pre_entry_item) = pre_entry_item {
+            if pre_entry_item.offset + pre_entry_item.record_len() != offset {
+                return_errno_with_message!(Errno::EUCLEAN, "the directory entry is corrupted");
             }
-
-            entry.offset == self.offset
-                && entry.name_len() == name_len
-                && self.read_name(entry).unwrap() == name_bytes
-        }) else {
-            return_errno!(Errno::ENOENT);
-        };
-        let is_last_entry = iter.next().is_none();
-
-        let mut pre_entry_item = pre_entry_item.unwrap();
-        let pre_offset = pre_entry_item.offset;
-        if is_last_entry {
-            // Shrink the size.
-            let new_size = pre_offset.align_up(BLOCK_SIZE);
-            self.page_cache.resize(new_size)?;
-            pre_entry_item.set_record_len(new_size - pre_offset);
-            self.offset = pre_offset;
-            self.write_header_only(&pre_entry_item.header)?;
-        } else {
             // Update the previous entry.
             pre_entry_item
                 .set_record_len(pre_entry_item.record_len() + target_entry_item.record_len());
-            self.offset = pre_offset;
+            self.offset = pre_entry_item.offset;
             self.write_header_only(&pre_entry_item.header)?;
+        } else {
+            let mut unused_entry_item = target_entry_item;
+            unused_entry_item.set_ino(0);
+            self.offset = offset;
+            self.write_header_only(&unused_entry_item.header)?;
+        }
+
+        // Shrink the size if the last block becomes empty. The blocks of an indexed
+        // directory are referenced by the hash tree, so they are kept.
+        let size = self.page_cache.pages().size();
+        if !self.config.is_indexed
+            && block_offset > 0
+            && block_offset + BLOCK_SIZE == size
+            && DirEntryIter::new(self.page_cache, block_offset, size, false)
+                .next()
+                .is_none()
+        {
+            self.page_cache.resize(block_offset)?;
         }
 
         Ok(target_entry_item)
     }
 
-    /// Renames the `DirEntry` from `old_name` to the `new_name` from the current offset.
+    /// Renames the `DirEntry` from `old_name` to the `new_name` at the current offset.
     ///
     /// It will moves the `DirEntry` to another position,
-    /// if the record length is not big enough.
+    /// if the record length is not big enough or the directory is indexed.
     pub fn rename_entry(&mut self, old_name: &str, new_name: &str) -> Result<()> {
-        let entry_item = DirEntryReader::new(self.page_cache, self.offset)
-            .find_entry_item(old_name)
-            .ok_or(Error::new(Errno::ENOENT))?;
+        let entry_item = self.read_entry_item_at(self.offset, old_name)?;
         let old_record_len = entry_item.record_len();
 
-        let mut new_entry_header =
-            DirEntryHeader::new(entry_item.ino(), entry_item.type_(), new_name.len());
-        if new_entry_header.record_len as usize <= old_record_len {
+        let mut new_entry_header = entry_item.header;
+        new_entry_header.name_len = new_name.len() as u8;
+        new_entry_header.record_len =
+            (DirEntry::HEADER_LEN + new_name.len()).align_up(DirEntry::ALIGN) as u16;
+        if !self.config.is_indexed && new_entry_header.record_len as usize <= old_record_len {
             // Just rename the entry.
             new_entry_header.record_len = old_record_len as _;
             self.offset = entry_item.offset;
@@ -523,13 +912,29 @@ impl<'a> DirEntryWriter<'a> {
             // Move to another position.
             self.offset = entry_item.offset;
             self.remove_entry(old_name)?;
-            self.offset = DirEntry::PARENT_OFFSET;
+            self.offset = 0;
             self.append_new_entry(new_entry_header, new_name, false)?;
         }
 
         Ok(())
     }
 
+    /// Reads the `DirEntryItem` at the offset, which must be a used entry named `name`.
+    fn read_entry_item_at(&mut self, offset: usize, name: &str) -> Result<DirEntryItem> {
+        let mut iter = DirEntryIter::new(self.page_cache, offset, usize::MAX, true);
+        let Some(entry_item) = iter.next() else {
+            return_errno!(Errno::ENOENT);
+        };
+        if entry_item.offset != offset
+            || entry_item.ino() == 0
+            || entry_item.name_len() != name.len()
+            || self.read_name(&entry_item)? != name.as_bytes()
+        {
+            return_errno!(Errno::ENOENT);
+        }
+        Ok(entry_item)
+    }
+
     /// Reads the name of the entry from the page cache to the inner buffer.
     fn read_name(&mut self, item: &DirEntryItem) -> Result<&[u8]> {
         if self.name_buf.is_none() {
@@ -545,3 +950,355 @@ impl<'a> DirEntryWriter<'a> {
         Ok(name_buf)
     }
 }
+
+/// The offset of the `DxRootInfo` in the root block of an indexed directory,
+/// which is right after the "." and ".." entries.
+const DX_ROOT_INFO_OFFSET: usize = DirEntry::PARENT_OFFSET + DirEntry::PARENT_OFFSET;
+/// The offset of the index entries in the root block.
+const DX_ROOT_ENTRIES_OFFSET: usize = DX_ROOT_INFO_OFFSET + size_of::<DxRootInfo>();
+/// The offset of the index entries in the other index blocks,
+/// which is right after a fake unused entry spanning the whole block.
+const DX_NODE_ENTRIES_OFFSET: usize = DirEntry::HEADER_LEN;
+/// The maximum levels of index blocks below the root block.
+const DX_MAX_INDIRECT_LEVELS: u8 = 1;
+/// The mask of the block number in the `DxEntry`.
+const DX_BLOCK_MASK: u32 = 0x0fff_ffff;
+
+/// The information about the hash tree stored in the root block.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct DxRootInfo {
+    reserved_zero: u32,
+    hash_version: u8,
+    info_len: u8,
+    indirect_levels: u8,
+    unused_flags: u8,
+}
+
+/// An entry in the index blocks, which maps the hashes starting from `hash`
+/// to the `block`.
+///
+/// The `hash` of the first entry is occupied by the `DxCountLimit`, it covers
+/// all hashes smaller than the second one.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct DxEntry {
+    hash: u32,
+    block: u32,
+}
+
+impl DxEntry {
+    /// Returns the block number, which must point to an existing block other than the root.
+    fn block(&self, page_cache: &PageCache) -> Result<usize> {
+        let block = (self.block & DX_BLOCK_MASK) as usize;
+        if block == 0 || block >= page_cache.pages().size() / BLOCK_SIZE {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid block in the hash tree");
+        }
+        Ok(block)
+    }
+}
+
+/// The number of entries in an index block and its capacity.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct DxCountLimit {
+    limit: u16,
+    count: u16,
+}
+
+/// The hash tree of an indexed directory.
+struct DxTree<'a> {
+    page_cache: &'a PageCache,
+    hash_version: DxHashVersion,
+    hash_seed: [u32; 4],
+    indirect_levels: u8,
+}
+
+impl<'a> DxTree<'a> {
+    /// Loads the hash tree from the root block.
+    fn load(page_cache: &'a PageCache, config: &DirConfig) -> Result<Self> {
+        let info = page_cache
+            .pages()
+            .read_val::<DxRootInfo>(DX_ROOT_INFO_OFFSET)?;
+        if info.reserved_zero != 0
+            || info.info_len as usize != size_of::<DxRootInfo>()
+            || info.indirect_levels > DX_MAX_INDIRECT_LEVELS
+        {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid hash tree root");
+        }
+
+        Ok(Self {
+            page_cache,
+            hash_version: DxHashVersion::new(info.hash_version, config.is_hash_unsigned)?,
+            hash_seed: config.hash_seed,
+            indirect_levels: info.indirect_levels,
+        })
+    }
+
+    /// Computes the hash of the name.
+    fn hash(&self, name: &[u8]) -> u32 {
+        dx_hash(name, self.hash_version, &self.hash_seed)
+    }
+
+    /// Walks from the root to the leaf block covering the `hash`.
+    ///
+    /// Returns the positions in the index blocks along the path.
+    fn probe(&self, hash: u32) -> Result<Vec<DxFrame>> {
+        let mut frames = Vec::new();
+        let mut entries_offset = DX_ROOT_ENTRIES_OFFSET;
+        loop {
+            let mut frame = DxFrame::load(self.page_cache, entries_offset)?;
+            frame.search(self.page_cache, hash)?;
+            let block = frame.block(self.page_cache)?;
+            frames.push(frame);
+            if frames.len() > self.indirect_levels as usize {
+                return Ok(frames);
+            }
+            entries_offset = block * BLOCK_SIZE + DX_NODE_ENTRIES_OFFSET;
+        }
+    }
+
+    /// Moves the frames to the next leaf block if it may also contain the entries with
+    /// the `hash` due to hash collisions.
+    ///
+    /// Returns `false` if there is no such block.
+    fn next_leaf(&self, frames: &mut [DxFrame], hash: u32) -> Result<bool> {
+        let mut level = frames.len() - 1;
+        loop {
+            frames[level].at += 1;
+            if frames[level].at < frames[level].count {
+                break;
+            }
+            if level == 0 {
+                return Ok(false);
+            }
+            level -= 1;
+        }
+
+        let frame = &frames[level];
+        let next_hash = frame.entry(self.page_cache, frame.at)?.hash;
+        if next_hash & !1 != hash {
+            return Ok(false);
+        }
+
+        while level + 1 < frames.len() {
+            let block = frames[level].block(self.page_cache)?;
+            frames[level + 1] =
+                DxFrame::load(self.page_cache, block * BLOCK_SIZE + DX_NODE_ENTRIES_OFFSET)?;
+            level += 1;
+        }
+        Ok(true)
+    }
+}
+
+/// A position in an index block.
+struct DxFrame {
+    /// The offset of the entries in the directory.
+    entries_offset: usize,
+    count: usize,
+    limit: usize,
+    /// The index of the current entry.
+    at: usize,
+}
+
+impl DxFrame {
+    /// Loads the index block whose entries start at `entries_offset`.
+    fn load(page_cache: &PageCache, entries_offset: usize) -> Result<Self> {
+        let count_limit = page_cache
+            .pages()
+            .read_val::<DxCountLimit>(entries_offset)?;
+        let count = count_limit.count as usize;
+        let limit = count_limit.limit as usize;
+        if count == 0
+            || count > limit
+            || entries_offset % BLOCK_SIZE + limit * size_of::<DxEntry>() > BLOCK_SIZE
+        {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid index block in the hash tree");
+        }
+
+        Ok(Self {
+            entries_offset,
+            count,
+            limit,
+            at: 0,
+        })
+    }
+
+    /// Points to the last entry whose hash is not greater than the `hash`.
+    fn search(&mut self, page_cache: &PageCache, hash: u32) -> Result<()> {
+        let mut low = 1;
+        let mut high = self.count - 1;
+        while low <= high {
+            let mid = low + (high - low) / 2;
+            if self.entry(page_cache, mid)?.hash > hash {
+                high = mid - 1;
+            } else {
+                low = mid + 1;
+            }
+        }
+        self.at = low - 1;
+        Ok(())
+    }
+
+    /// Returns the block pointed by the current entry.
+    fn block(&self, page_cache: &PageCache) -> Result<usize> {
+        self.entry(page_cache, self.at)?.block(page_cache)
+    }
+
+    fn entry(&self, page_cache: &PageCache, idx: usize) -> Result<DxEntry> {
+        debug_assert!(idx < self.limit);
+        let offset = self.entries_offset + idx * size_of::<DxEntry>();
+        Ok(page_cache.pages().read_val::<DxEntry>(offset)?)
+    }
+
+    fn write_entry(&self, page_cache: &PageCache, idx: usize, entry: &DxEntry) -> Result<()> {
+        debug_assert!(idx != 0 && idx < self.limit);
+        let offset = self.entries_offset + idx * size_of::<DxEntry>();
+        page_cache.pages().write_val(offset, entry)?;
+        Ok(())
+    }
+
+    /// Inserts a new entry after the current one.
+    fn insert_after(&mut self, page_cache: &PageCache, new_entry: DxEntry) -> Result<()> {
+        debug_assert!(self.count < self.limit);
+        for idx in (self.at + 1..self.count).rev() {
+            let entry = self.entry(page_cache, idx)?;
+            self.write_entry(page_cache, idx + 1, &entry)?;
+        }
+        self.write_entry(page_cache, self.at + 1, &new_entry)?;
+
+        self.count += 1;
+        let count_limit = DxCountLimit {
+            limit: self.limit as _,
+            count: self.count as _,
+        };
+        page_cache
+            .pages()
+            .write_val(self.entries_offset, &count_limit)?;
+        Ok(())
+    }
+}
+
+/// Updates the checksum of a directory block, which is either a leaf block ending
+/// with a `DirEntryTail` or an index block of the hash tree.
+///
+/// Blocks without a place for the checksum are left untouched.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/namei.c#L342>
+pub(super) fn update_block_checksum(block: &mut [u8], csum_seed: u32) {
+    debug_assert_eq!(block.len(), BLOCK_SIZE);
+
+    let tail_header = DirEntryHeader::from_bytes(&block[DirEntryTail::OFFSET..]);
+    if DirEntryTail::is_tail(&tail_header) {
+        let checksum = crc32c(csum_seed, &block[..DirEntryTail::OFFSET]);
+        block[DirEntryTail::OFFSET + DirEntry::HEADER_LEN..]
+            .copy_from_slice(&checksum.to_le_bytes());
+        return;
+    }
+
+    let Some(entries_offset) = dx_entries_offset(block) else {
+        return;
+    };
+    let count_limit = DxCountLimit::from_bytes(&block[entries_offset..]);
+    let count = count_limit.count as usize;
+    let limit = count_limit.limit as usize;
+    // The tail of an index block is `{ reserved: u32, checksum: u32 }`.
+    let tail_offset = entries_offset + limit * size_of::<DxEntry>();
+    if count > limit || tail_offset + 8 > BLOCK_SIZE {
+        return;
+    }
+
+    let checksum = crc32c(
+        csum_seed,
+        &block[..entries_offset + count * size_of::<DxEntry>()],
+    );
+    let checksum = crc32c(checksum, &block[tail_offset..tail_offset + 4]);
+    let checksum = crc32c(checksum, &[0u8; 4]);
+    block[tail_offset + 4..tail_offset + 8].copy_from_slice(&checksum.to_le_bytes());
+}
+
+/// Returns the offset of the index entries if the block is an index block.
+fn dx_entries_offset(block: &[u8]) -> Option<usize> {
+    let first_header = DirEntryHeader::from_bytes(block);
+    if first_header.record_len as usize == BLOCK_SIZE {
+        return Some(DX_NODE_ENTRIES_OFFSET);
+    }
+    if first_header.record_len as usize != DirEntry::PARENT_OFFSET {
+        return None;
+    }
+
+    let parent_header = DirEntryHeader::from_bytes(&block[DirEntry::PARENT_OFFSET..]);
+    let info = DxRootInfo::from_bytes(&block[DX_ROOT_INFO_OFFSET..]);
+    if parent_header.record_len as usize != BLOCK_SIZE - DirEntry::PARENT_OFFSET
+        || info.reserved_zero != 0
+        || info.info_len as usize != size_of::<DxRootInfo>()
+    {
+        return None;
+    }
+    Some(DX_ROOT_ENTRIES_OFFSET)
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+    use crate::fs::ext2::{
+        fs::Ext2,
+        inode::{FileFlags, FilePerm, Inode},
+        test_utils::{new_ext4_disk, LARGE_DIR_NR_FILES},
+    };
+
+    fn file_name(idx: usize) -> String {
+        format!("file_{}", idx)
+    }
+
+    fn open_large_dir(block_device: Arc<dyn BlockDevice>) -> (Arc<Ext2>, Arc<Inode>) {
+        let fs = Ext2::open(block_device).unwrap();
+        let dir = fs.root_inode().unwrap().lookup("large_dir").unwrap();
+        assert!(dir.file_flags().contains(FileFlags::INDEX_DIR));
+        (fs, dir)
+    }
+
+    #[ktest]
+    fn large_hashed_dir() {
+        let block_device = new_ext4_disk();
+        let nr_files = LARGE_DIR_NR_FILES * 2;
+
+        let (fs, dir) = open_large_dir(block_device.clone());
+        for idx in 1..=LARGE_DIR_NR_FILES {
+            dir.lookup(&file_name(idx)).unwrap();
+        }
+        // The new entries split the leaf blocks of the hash tree.
+        for idx in LARGE_DIR_NR_FILES + 1..=nr_files {
+            dir.create(
+                &file_name(idx),
+                InodeType::File,
+                FilePerm::from_bits_truncate(0o644),
+            )
+            .unwrap();
+        }
+        for idx in (1..=nr_files).step_by(2) {
+            dir.unlink(&file_name(idx)).unwrap();
+        }
+        fs.sync_all().unwrap();
+        drop(dir);
+        drop(fs);
+
+        // The hash tree is still valid after remounting.
+        let (_fs, dir) = open_large_dir(block_device);
+        for idx in 1..=nr_files {
+            let result = dir.lookup(&file_name(idx));
+            if idx % 2 == 0 {
+                assert!(result.is_ok());
+            } else {
+                assert_eq!(result.unwrap_err().error(), Errno::ENOENT);
+            }
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        dir.readdir_at(0, &mut names).unwrap();
+        // The entries include "." and "..".
+        assert_eq!(names.len(), nr_files / 2 + 2);
+    }
+}
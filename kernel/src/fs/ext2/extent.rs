@@ -0,0 +1,846 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The extent tree of Ext4.
+//!
+//! An inode with the `EXTENTS` flag maps its file blocks with a B+ tree of extents
+//! instead of the block pointers. The root node is stored inside the inode in place
+//! of the block pointers, while the other nodes occupy a whole block each. The leaf
+//! nodes hold the extents, each of which maps a run of consecutive file blocks to
+//! consecutive device blocks, and the index nodes hold the keys and pointers to
+//! their children.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/ext4_extents.h>
+
+use ostd::const_assert;
+
+use super::{
+    block_ptr::{BlockMapping, BlockPtrs, Ext2Bid},
+    fs::Ext2,
+    indirect_block_cache::{IndirectBlock, IndirectBlockCache},
+    prelude::*,
+    utils::crc32c,
+};
+
+/// The magic number of the extent node headers.
+const EXTENT_MAGIC: u16 = 0xF30A;
+
+/// The maximum depth of an extent tree.
+const MAX_DEPTH: u16 = 5;
+
+/// The maximum length of an initialized extent.
+const MAX_INIT_LEN: u32 = 1 << 15;
+
+/// The maximum length of an uninitialized extent.
+///
+/// The lengths greater than `MAX_INIT_LEN` denote the uninitialized extents.
+const MAX_UNINIT_LEN: u32 = MAX_INIT_LEN - 1;
+
+const HEADER_SIZE: usize = size_of::<RawExtentHeader>();
+const ENTRY_SIZE: usize = size_of::<RawExtent>();
+
+/// The maximum number of entries in the root node.
+const ROOT_MAX_ENTRIES: u16 = ((size_of::<BlockPtrs>() - HEADER_SIZE) / ENTRY_SIZE) as u16;
+
+/// The maximum number of entries in a tree block.
+const BLOCK_MAX_ENTRIES: u16 = ((BLOCK_SIZE - HEADER_SIZE) / ENTRY_SIZE) as u16;
+
+/// Returns the maximum length of an extent.
+pub(super) const fn max_extent_len(uninit: bool) -> u32 {
+    if uninit {
+        MAX_UNINIT_LEN
+    } else {
+        MAX_INIT_LEN
+    }
+}
+
+/// Initializes the block pointers of an inode as the root of an empty extent tree.
+pub(super) fn init_root(block_ptrs: &mut BlockPtrs) {
+    let root = Node {
+        depth: 0,
+        max: ROOT_MAX_ENTRIES,
+        generation: 0,
+        entries: Vec::new(),
+    };
+    *block_ptrs = BlockPtrs::default();
+    root.encode(block_ptrs.as_bytes_mut());
+}
+
+/// Looks up the mapping of the file block `lblk`.
+///
+/// The returned mapping starts at `lblk` and covers as many blocks as possible.
+pub(super) fn lookup(
+    root: &BlockPtrs,
+    blocks: &mut IndirectBlockCache,
+    lblk: Ext2Bid,
+) -> Result<BlockMapping> {
+    let mut node = Node::decode(root.as_bytes(), ROOT_MAX_ENTRIES)?;
+    // The first key after `lblk`, which bounds the length of a hole.
+    let mut next_key = Ext2Bid::MAX;
+    loop {
+        let pos = node.entries.partition_point(|entry| entry.lblk <= lblk);
+        if let Some(next_entry) = node.entries.get(pos) {
+            next_key = next_key.min(next_entry.lblk);
+        }
+        if pos == 0 {
+            return Ok(BlockMapping::Hole(next_key - lblk));
+        }
+
+        let entry = node.entries[pos - 1];
+        if node.depth == 0 {
+            if lblk - entry.lblk >= entry.len {
+                return Ok(BlockMapping::Hole(next_key - lblk));
+            }
+            let device_range = (entry.pblk + (lblk - entry.lblk))..(entry.pblk + entry.len);
+            return Ok(if entry.uninit {
+                BlockMapping::Uninit(device_range)
+            } else {
+                BlockMapping::Mapped(device_range)
+            });
+        }
+        node = Node::load(blocks, entry.pblk, node.depth - 1)?;
+    }
+}
+
+/// Visits the extents of the tree in order of the file blocks, whose tree blocks
+/// are read by `read_block`.
+///
+/// It works without the block cache of an inode, e.g., to map the blocks of the
+/// journal inode before the filesystem is loaded. The `visit` closure receives
+/// the first file block and the mapping of each extent.
+pub(super) fn walk_raw(
+    root: &BlockPtrs,
+    read_block: &dyn Fn(Ext2Bid) -> Result<Vec<u8>>,
+    visit: &mut dyn FnMut(Ext2Bid, BlockMapping) -> Result<()>,
+) -> Result<()> {
+    fn walk_node(
+        node: &Node,
+        read_block: &dyn Fn(Ext2Bid) -> Result<Vec<u8>>,
+        visit: &mut dyn FnMut(Ext2Bid, BlockMapping) -> Result<()>,
+    ) -> Result<()> {
+        for entry in node.entries.iter() {
+            if node.depth == 0 {
+                let device_range = entry.pblk..entry.pblk + entry.len;
+                let mapping = if entry.uninit {
+                    BlockMapping::Uninit(device_range)
+                } else {
+                    BlockMapping::Mapped(device_range)
+                };
+                visit(entry.lblk, mapping)?;
+                continue;
+            }
+
+            let child = Node::decode(&read_block(entry.pblk)?, BLOCK_MAX_ENTRIES)?;
+            if child.depth + 1 != node.depth {
+                return_errno_with_message!(Errno::EUCLEAN, "unexpected extent tree depth");
+            }
+            walk_node(&child, read_block, visit)?;
+        }
+        Ok(())
+    }
+
+    let root = Node::decode(root.as_bytes(), ROOT_MAX_ENTRIES)?;
+    walk_node(&root, read_block, visit)
+}
+
+/// An extent tree to be modified.
+pub(super) struct ExtentTree<'a> {
+    /// The root node, i.e., the block pointers of the inode.
+    root: &'a mut BlockPtrs,
+    /// The cache of the tree blocks.
+    blocks: &'a mut IndirectBlockCache,
+    fs: &'a Ext2,
+    /// The checksum seed of the inode, if the filesystem has metadata checksums.
+    csum_seed: Option<u32>,
+    /// The block group to allocate the new tree blocks from.
+    goal_group: usize,
+}
+
+impl<'a> ExtentTree<'a> {
+    pub fn new(
+        root: &'a mut BlockPtrs,
+        blocks: &'a mut IndirectBlockCache,
+        fs: &'a Ext2,
+        csum_seed: Option<u32>,
+        goal_group: usize,
+    ) -> Self {
+        Self {
+            root,
+            blocks,
+            fs,
+            csum_seed,
+            goal_group,
+        }
+    }
+
+    /// Inserts an extent mapping `len` file blocks starting from `lblk` to the device
+    /// blocks starting from `pblk`.
+    ///
+    /// The file blocks must not be mapped yet. The extent is merged with its neighbors
+    /// if possible. Returns the number of the tree blocks allocated for the insertion.
+    pub fn insert(&mut self, lblk: Ext2Bid, len: u32, pblk: Ext2Bid, uninit: bool) -> Result<u32> {
+        debug_assert!(len > 0 && len <= max_extent_len(uninit));
+        let new_entry = Entry {
+            lblk,
+            len,
+            pblk,
+            uninit,
+        };
+
+        let mut allocated = 0;
+        loop {
+            let mut path = self.find_path(lblk)?;
+            let leaf = path.last_mut().unwrap();
+            let pos = leaf.pos;
+
+            if let Some(left) = pos.checked_sub(1).map(|idx| &leaf.node.entries[idx]) {
+                if lblk - left.lblk < left.len {
+                    return_errno_with_message!(Errno::EUCLEAN, "the extents overlap");
+                }
+            }
+            if let Some(right) = leaf.node.entries.get(pos) {
+                if right.lblk - lblk < len {
+                    return_errno_with_message!(Errno::EUCLEAN, "the extents overlap");
+                }
+            }
+
+            // Merges with the left neighbor.
+            if pos > 0 && leaf.node.entries[pos - 1].can_append(&new_entry) {
+                leaf.node.entries[pos - 1].len += len;
+                self.store(leaf.bid, &leaf.node)?;
+                return Ok(allocated);
+            }
+
+            // Merges with the right neighbor.
+            if pos < leaf.node.entries.len() && new_entry.can_append(&leaf.node.entries[pos]) {
+                let right = &mut leaf.node.entries[pos];
+                right.lblk = lblk;
+                right.pblk = pblk;
+                right.len += len;
+                self.store(leaf.bid, &leaf.node)?;
+                if pos == 0 {
+                    self.fix_keys(&mut path)?;
+                }
+                return Ok(allocated);
+            }
+
+            if leaf.node.entries.len() < leaf.node.max as usize {
+                leaf.node.entries.insert(pos, new_entry);
+                self.store(leaf.bid, &leaf.node)?;
+                if pos == 0 {
+                    self.fix_keys(&mut path)?;
+                }
+                return Ok(allocated);
+            }
+
+            allocated += self.make_room(&mut path, lblk)?;
+        }
+    }
+
+    /// Marks the uninitialized blocks in `range` as initialized.
+    ///
+    /// The range must be within a single uninitialized extent. Returns the number of
+    /// the tree blocks allocated for splitting the extent.
+    pub fn mark_initialized(&mut self, range: Range<Ext2Bid>) -> Result<u32> {
+        let mut path = self.find_path(range.start)?;
+        let leaf = path.last_mut().unwrap();
+        let Some(idx) = leaf.pos.checked_sub(1) else {
+            return_errno_with_message!(Errno::EUCLEAN, "the blocks are not mapped");
+        };
+        let extent = leaf.node.entries[idx];
+        if !extent.uninit || range.end - extent.lblk > extent.len {
+            return_errno_with_message!(Errno::EUCLEAN, "the blocks are not uninitialized");
+        }
+
+        // Splits the extent into at most three pieces, the middle one of which is
+        // initialized.
+        let extent_end = extent.lblk + extent.len;
+        let pieces = [
+            (extent.lblk..range.start, true),
+            (range.start..range.end, false),
+            (range.end..extent_end, true),
+        ];
+        let mut pieces = pieces
+            .into_iter()
+            .filter(|(piece, _)| !piece.is_empty())
+            .map(|(piece, uninit)| Entry {
+                lblk: piece.start,
+                len: piece.end - piece.start,
+                pblk: extent.pblk + (piece.start - extent.lblk),
+                uninit,
+            });
+
+        // Replaces the extent with the first piece in place, then inserts the others.
+        leaf.node.entries[idx] = pieces.next().unwrap();
+        self.store(leaf.bid, &leaf.node)?;
+        let mut allocated = 0;
+        for piece in pieces {
+            allocated += self.insert(piece.lblk, piece.len, piece.pblk, piece.uninit)?;
+        }
+        Ok(allocated)
+    }
+
+    /// Removes the mappings of the file blocks starting from `lblk`.
+    ///
+    /// The device blocks of the removed mappings and the tree blocks that become empty
+    /// are freed. Returns the total number of the freed blocks.
+    pub fn truncate(&mut self, lblk: Ext2Bid) -> Result<u64> {
+        let mut root = Node::decode(self.root.as_bytes(), ROOT_MAX_ENTRIES)?;
+        let freed = self.truncate_node(&mut root, lblk)?;
+        if freed == 0 {
+            return Ok(0);
+        }
+
+        if root.entries.is_empty() {
+            root.depth = 0;
+        }
+        self.store(None, &root)?;
+        Ok(freed)
+    }
+
+    fn truncate_node(&mut self, node: &mut Node, lblk: Ext2Bid) -> Result<u64> {
+        let mut freed = 0;
+        while let Some(entry) = node.entries.last_mut() {
+            if node.depth == 0 {
+                if entry.lblk >= lblk {
+                    self.free_blocks(entry.pblk..entry.pblk + entry.len);
+                    freed += entry.len as u64;
+                    node.entries.pop();
+                    continue;
+                }
+                if lblk - entry.lblk < entry.len {
+                    let kept_len = lblk - entry.lblk;
+                    self.free_blocks(entry.pblk + kept_len..entry.pblk + entry.len);
+                    freed += (entry.len - kept_len) as u64;
+                    entry.len = kept_len;
+                }
+                break;
+            }
+
+            let entry = *entry;
+            let mut child = Node::load(self.blocks, entry.pblk, node.depth - 1)?;
+            let child_freed = self.truncate_node(&mut child, lblk)?;
+            freed += child_freed;
+            if child.entries.is_empty() {
+                self.blocks.remove(entry.pblk);
+                self.free_blocks(entry.pblk..entry.pblk + 1);
+                freed += 1;
+                node.entries.pop();
+            } else if child_freed > 0 {
+                self.store(Some(entry.pblk), &child)?;
+            }
+
+            // The children before are not affected.
+            if entry.lblk < lblk {
+                break;
+            }
+        }
+        Ok(freed)
+    }
+
+    /// Returns the path from the root to the leaf which covers `lblk`.
+    fn find_path(&mut self, lblk: Ext2Bid) -> Result<Vec<PathNode>> {
+        let mut path = Vec::new();
+        let mut node = Node::decode(self.root.as_bytes(), ROOT_MAX_ENTRIES)?;
+        let mut bid = None;
+        loop {
+            let pos = node.entries.partition_point(|entry| entry.lblk <= lblk);
+            if node.depth == 0 {
+                path.push(PathNode { bid, node, pos });
+                return Ok(path);
+            }
+
+            if node.entries.is_empty() {
+                return_errno_with_message!(Errno::EUCLEAN, "empty extent index node");
+            }
+            let pos = pos.saturating_sub(1);
+            let child_bid = node.entries[pos].pblk;
+            let child_depth = node.depth - 1;
+            path.push(PathNode { bid, node, pos });
+
+            node = Node::load(self.blocks, child_bid, child_depth)?;
+            bid = Some(child_bid);
+        }
+    }
+
+    /// Updates the keys of the ancestors after the first key of the leaf in `path`
+    /// has been changed.
+    fn fix_keys(&mut self, path: &mut [PathNode]) -> Result<()> {
+        let mut key = path.last().unwrap().node.entries[0].lblk;
+        for path_node in path.iter_mut().rev().skip(1) {
+            let entry = &mut path_node.node.entries[path_node.pos];
+            if entry.lblk == key {
+                break;
+            }
+            entry.lblk = key;
+            self.store(path_node.bid, &path_node.node)?;
+            if path_node.pos != 0 {
+                break;
+            }
+            key = path_node.node.entries[0].lblk;
+        }
+        Ok(())
+    }
+
+    /// Makes room in the full leaf of `path` for a new extent at `lblk`.
+    ///
+    /// The lowest full node whose parent has room is split. If all the nodes in the
+    /// path are full, the tree grows in depth instead. Returns the number of the tree
+    /// blocks allocated.
+    fn make_room(&mut self, path: &mut [PathNode], lblk: Ext2Bid) -> Result<u32> {
+        let Some(level) = (1..path.len())
+            .rev()
+            .find(|&level| !path[level - 1].node.is_full())
+        else {
+            self.grow_depth()?;
+            return Ok(1);
+        };
+
+        let new_bid = self.alloc_tree_block()?;
+        let (parent, children) = path.split_at_mut(level);
+        let parent = parent.last_mut().unwrap();
+        let node = &mut children[0];
+
+        // When appending to the leaf, leaves the existing extents in place so that the
+        // leaves are densely packed for the sequential writes.
+        let nentries = node.node.entries.len();
+        let is_appending = node.node.entries.last().unwrap().lblk < lblk;
+        let split_at = match (node.node.depth, is_appending) {
+            (0, true) => nentries,
+            (_, true) => nentries - 1,
+            (_, false) => nentries / 2,
+        };
+        let new_node = Node {
+            depth: node.node.depth,
+            max: BLOCK_MAX_ENTRIES,
+            generation: 0,
+            entries: node.node.entries.split_off(split_at),
+        };
+        let new_key = new_node.entries.first().map_or(lblk, |entry| entry.lblk);
+
+        let result = self
+            .store(Some(new_bid), &new_node)
+            .and_then(|_| self.store(node.bid, &node.node));
+        if let Err(err) = result {
+            self.blocks.remove(new_bid);
+            self.free_blocks(new_bid..new_bid + 1);
+            return Err(err);
+        }
+
+        parent.node.entries.insert(
+            parent.pos + 1,
+            Entry {
+                lblk: new_key,
+                len: 0,
+                pblk: new_bid,
+                uninit: false,
+            },
+        );
+        self.store(parent.bid, &parent.node)?;
+        Ok(1)
+    }
+
+    /// Moves the entries of the root to a new tree block, which becomes the only child
+    /// of the root.
+    fn grow_depth(&mut self) -> Result<()> {
+        let mut root = Node::decode(self.root.as_bytes(), ROOT_MAX_ENTRIES)?;
+        if root.depth >= MAX_DEPTH {
+            return_errno_with_message!(Errno::EFBIG, "the extent tree is too deep");
+        }
+
+        let new_bid = self.alloc_tree_block()?;
+        let new_node = Node {
+            depth: root.depth,
+            max: BLOCK_MAX_ENTRIES,
+            generation: 0,
+            entries: core::mem::take(&mut root.entries),
+        };
+        if let Err(err) = self.store(Some(new_bid), &new_node) {
+            self.blocks.remove(new_bid);
+            self.free_blocks(new_bid..new_bid + 1);
+            return Err(err);
+        }
+
+        root.depth += 1;
+        root.entries.push(Entry {
+            lblk: new_node.entries[0].lblk,
+            len: 0,
+            pblk: new_bid,
+            uninit: false,
+        });
+        self.store(None, &root)
+    }
+
+    fn alloc_tree_block(&mut self) -> Result<Ext2Bid> {
+        let bid = self
+            .fs
+            .alloc_blocks(self.goal_group, 1)
+            .ok_or(Error::with_message(
+                Errno::ENOSPC,
+                "no space for extent tree",
+            ))?
+            .start;
+        if let Err(err) = IndirectBlock::alloc().and_then(|block| self.blocks.insert(bid, block)) {
+            self.free_blocks(bid..bid + 1);
+            return Err(err);
+        }
+        Ok(bid)
+    }
+
+    fn free_blocks(&self, range: Range<Ext2Bid>) {
+        if !range.is_empty() {
+            self.fs.free_blocks(range).unwrap();
+        }
+    }
+
+    /// Writes the node back to the root (if `bid` is `None`) or the tree block.
+    fn store(&mut self, bid: Option<Ext2Bid>, node: &Node) -> Result<()> {
+        let Some(bid) = bid else {
+            node.encode(self.root.as_bytes_mut());
+            return Ok(());
+        };
+
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        node.encode(&mut buf);
+        if let Some(csum_seed) = self.csum_seed {
+            let tail_offset = HEADER_SIZE + ENTRY_SIZE * node.max as usize;
+            let checksum = crc32c(csum_seed, &buf[..tail_offset]);
+            buf[tail_offset..tail_offset + size_of::<u32>()]
+                .copy_from_slice(&checksum.to_le_bytes());
+        }
+        self.blocks.find_mut(bid)?.write_bytes(0, &buf)
+    }
+}
+
+/// A node on the path from the root to a leaf.
+struct PathNode {
+    /// The block ID of the node, or `None` for the root.
+    bid: Option<Ext2Bid>,
+    node: Node,
+    /// For an index node, the position of the entry pointing to the next node in
+    /// the path. For a leaf, the position to insert the extent being looked up.
+    pos: usize,
+}
+
+/// An in-memory node of the extent tree.
+#[derive(Debug)]
+struct Node {
+    /// The depth of the node in the tree, which is zero for the leaves.
+    depth: u16,
+    /// The maximum number of entries.
+    max: u16,
+    generation: u32,
+    /// The entries sorted by the file blocks.
+    entries: Vec<Entry>,
+}
+
+/// An entry of the extent tree node.
+///
+/// For a leaf, it is an extent that maps `len` file blocks starting from `lblk`
+/// to the device blocks starting from `pblk`. For an index node, it points to the
+/// child node at `pblk`, which covers the file blocks starting from `lblk`.
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    lblk: Ext2Bid,
+    len: u32,
+    pblk: Ext2Bid,
+    uninit: bool,
+}
+
+impl Entry {
+    /// Returns `true` if the extent `next` can be appended to this extent.
+    fn can_append(&self, next: &Entry) -> bool {
+        self.uninit == next.uninit
+            && self.lblk.checked_add(self.len) == Some(next.lblk)
+            && self.pblk.checked_add(self.len) == Some(next.pblk)
+            && self.len + next.len <= max_extent_len(self.uninit)
+    }
+}
+
+impl Node {
+    /// Loads a tree block as a node of `depth`.
+    fn load(blocks: &mut IndirectBlockCache, bid: Ext2Bid, depth: u16) -> Result<Self> {
+        let block = blocks.find(bid)?;
+        let mut header_buf = [0u8; HEADER_SIZE];
+        block.read_bytes(0, &mut header_buf)?;
+        let header = RawExtentHeader::from_bytes(&header_buf);
+        if header.magic != EXTENT_MAGIC || header.entries > header.max {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid extent header");
+        }
+
+        let mut buf = vec![0u8; HEADER_SIZE + header.entries as usize * ENTRY_SIZE];
+        block.read_bytes(0, &mut buf)?;
+        let node = Self::decode(&buf, BLOCK_MAX_ENTRIES)?;
+        if node.depth != depth {
+            return_errno_with_message!(Errno::EUCLEAN, "unexpected extent tree depth");
+        }
+        Ok(node)
+    }
+
+    /// Decodes the node from the bytes, which can hold up to `capacity` entries.
+    fn decode(buf: &[u8], capacity: u16) -> Result<Self> {
+        let header = RawExtentHeader::from_bytes(&buf[..HEADER_SIZE]);
+        if header.magic != EXTENT_MAGIC
+            || header.max > capacity
+            || header.entries > header.max
+            || header.depth > MAX_DEPTH
+        {
+            return_errno_with_message!(Errno::EUCLEAN, "invalid extent header");
+        }
+
+        let mut entries = Vec::with_capacity(header.entries as usize);
+        for raw_entry in buf[HEADER_SIZE..]
+            .chunks_exact(ENTRY_SIZE)
+            .take(header.entries as usize)
+        {
+            let entry = if header.depth == 0 {
+                let extent = RawExtent::from_bytes(raw_entry);
+                if extent.start_hi != 0 {
+                    return_errno_with_message!(Errno::EUCLEAN, "extent beyond 32-bit blocks");
+                }
+                let (len, uninit) = if extent.len as u32 > MAX_INIT_LEN {
+                    (extent.len as u32 - MAX_INIT_LEN, true)
+                } else {
+                    (extent.len as u32, false)
+                };
+                Entry {
+                    lblk: extent.block,
+                    len,
+                    pblk: extent.start_lo,
+                    uninit,
+                }
+            } else {
+                let index = RawExtentIdx::from_bytes(raw_entry);
+                if index.leaf_hi != 0 {
+                    return_errno_with_message!(Errno::EUCLEAN, "extent beyond 32-bit blocks");
+                }
+                Entry {
+                    lblk: index.block,
+                    len: 0,
+                    pblk: index.leaf_lo,
+                    uninit: false,
+                }
+            };
+            entries.push(entry);
+        }
+
+        Ok(Self {
+            depth: header.depth,
+            max: header.max,
+            generation: header.generation,
+            entries,
+        })
+    }
+
+    /// Encodes the node into the bytes.
+    fn encode(&self, buf: &mut [u8]) {
+        let header = RawExtentHeader {
+            magic: EXTENT_MAGIC,
+            entries: self.entries.len() as u16,
+            max: self.max,
+            depth: self.depth,
+            generation: self.generation,
+        };
+        buf[..HEADER_SIZE].copy_from_slice(header.as_bytes());
+
+        let entry_bufs = buf[HEADER_SIZE..].chunks_exact_mut(ENTRY_SIZE);
+        for (entry, entry_buf) in self.entries.iter().zip(entry_bufs) {
+            if self.depth == 0 {
+                let extent = RawExtent {
+                    block: entry.lblk,
+                    len: if entry.uninit {
+                        (entry.len + MAX_INIT_LEN) as u16
+                    } else {
+                        entry.len as u16
+                    },
+                    start_hi: 0,
+                    start_lo: entry.pblk,
+                };
+                entry_buf.copy_from_slice(extent.as_bytes());
+            } else {
+                let index = RawExtentIdx {
+                    block: entry.lblk,
+                    leaf_lo: entry.pblk,
+                    leaf_hi: 0,
+                    unused: 0,
+                };
+                entry_buf.copy_from_slice(index.as_bytes());
+            }
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.entries.len() >= self.max as usize
+    }
+}
+
+const_assert!(size_of::<RawExtentHeader>() == 12);
+const_assert!(size_of::<RawExtent>() == 12);
+const_assert!(size_of::<RawExtentIdx>() == 12);
+
+/// The header of an extent tree node on device.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RawExtentHeader {
+    magic: u16,
+    /// Number of valid entries.
+    entries: u16,
+    /// Capacity of entries.
+    max: u16,
+    /// Depth of the node, which is zero for the leaves.
+    depth: u16,
+    /// Generation of the tree.
+    generation: u32,
+}
+
+/// The extent in a leaf on device.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RawExtent {
+    /// First file block that the extent covers.
+    block: u32,
+    /// Number of blocks covered by the extent.
+    len: u16,
+    /// High 16 bits of the first device block.
+    start_hi: u16,
+    /// Low 32 bits of the first device block.
+    start_lo: u32,
+}
+
+/// The index entry in an index node on device.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RawExtentIdx {
+    /// The child covers the file blocks from this block.
+    block: u32,
+    /// Low 32 bits of the child block.
+    leaf_lo: u32,
+    /// High 16 bits of the child block.
+    leaf_hi: u16,
+    unused: u16,
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+    use crate::fs::ext2::test_utils::new_ext4_disk;
+
+    const NR_EXTENTS: u32 = 1000;
+
+    struct TestTree {
+        fs: Arc<Ext2>,
+        root: BlockPtrs,
+        blocks: IndirectBlockCache,
+    }
+
+    impl TestTree {
+        fn new() -> Self {
+            let fs = Ext2::open(new_ext4_disk()).unwrap();
+            let mut root = BlockPtrs::default();
+            init_root(&mut root);
+            let blocks = IndirectBlockCache::new(Arc::downgrade(&fs));
+            Self { fs, root, blocks }
+        }
+
+        fn tree(&mut self) -> ExtentTree<'_> {
+            let csum_seed = self.fs.csum_seed();
+            ExtentTree::new(&mut self.root, &mut self.blocks, &self.fs, csum_seed, 0)
+        }
+
+        fn lookup(&mut self, lblk: Ext2Bid) -> BlockMapping {
+            lookup(&self.root, &mut self.blocks, lblk).unwrap()
+        }
+
+        fn depth(&self) -> u16 {
+            Node::decode(self.root.as_bytes(), ROOT_MAX_ENTRIES)
+                .unwrap()
+                .depth
+        }
+
+        fn free_blocks_count(&self) -> u32 {
+            self.fs.super_block().free_blocks_count()
+        }
+    }
+
+    /// Inserts the single-block extents at the even file blocks, which cannot be merged.
+    ///
+    /// The first half is appended in order, and the second half is inserted in reverse
+    /// order, so the leaves are split both at the end and in the middle.
+    fn insert_sparse_extents(tree: &mut TestTree, pblk_start: Ext2Bid) {
+        let half = NR_EXTENTS / 2;
+        for idx in (0..half).chain((half..NR_EXTENTS).rev()) {
+            tree.tree()
+                .insert(idx * 2, 1, pblk_start + idx, false)
+                .unwrap();
+        }
+    }
+
+    #[ktest]
+    fn insert_and_lookup() {
+        let mut tree = TestTree::new();
+        let pblk_start = tree.fs.alloc_blocks(0, NR_EXTENTS).unwrap().start;
+        insert_sparse_extents(&mut tree, pblk_start);
+        assert!(tree.depth() >= 1);
+
+        for idx in 0..NR_EXTENTS {
+            let pblk = pblk_start + idx;
+            assert_eq!(tree.lookup(idx * 2), BlockMapping::Mapped(pblk..pblk + 1));
+            if idx + 1 < NR_EXTENTS {
+                assert_eq!(tree.lookup(idx * 2 + 1), BlockMapping::Hole(1));
+            }
+        }
+
+        // The contiguous extents are merged.
+        let lblk = NR_EXTENTS * 2;
+        let pblk = pblk_start + NR_EXTENTS;
+        tree.tree().insert(lblk, 1, pblk, false).unwrap();
+        tree.tree().insert(lblk + 1, 1, pblk + 1, false).unwrap();
+        assert_eq!(tree.lookup(lblk), BlockMapping::Mapped(pblk..pblk + 2));
+
+        // The overlapping extents are rejected.
+        assert!(tree.tree().insert(lblk + 1, 1, pblk + 3, false).is_err());
+    }
+
+    #[ktest]
+    fn truncate() {
+        let mut tree = TestTree::new();
+        let free_blocks_count = tree.free_blocks_count();
+        let pblk_range = tree.fs.alloc_blocks(0, NR_EXTENTS).unwrap();
+        assert_eq!(pblk_range.len(), NR_EXTENTS as usize);
+        insert_sparse_extents(&mut tree, pblk_range.start);
+
+        // Removes the second half of the extents.
+        let freed = tree.tree().truncate(NR_EXTENTS).unwrap();
+        assert!(freed >= (NR_EXTENTS / 2) as u64);
+        for idx in 0..NR_EXTENTS {
+            let mapping = tree.lookup(idx * 2);
+            if idx < NR_EXTENTS / 2 {
+                assert!(matches!(mapping, BlockMapping::Mapped(_)));
+            } else {
+                assert!(matches!(mapping, BlockMapping::Hole(_)));
+            }
+        }
+
+        // Removes all the extents, including the tree blocks.
+        tree.tree().truncate(0).unwrap();
+        assert_eq!(tree.depth(), 0);
+        assert!(matches!(tree.lookup(0), BlockMapping::Hole(_)));
+        assert_eq!(tree.free_blocks_count(), free_blocks_count);
+    }
+
+    #[ktest]
+    fn truncate_within_extent() {
+        let mut tree = TestTree::new();
+        let free_blocks_count = tree.free_blocks_count();
+        let pblk_range = tree.fs.alloc_blocks(0, 8).unwrap();
+        assert_eq!(pblk_range.len(), 8);
+        let pblk = pblk_range.start;
+        tree.tree().insert(0, 8, pblk, false).unwrap();
+
+        assert_eq!(tree.tree().truncate(3).unwrap(), 5);
+        assert_eq!(tree.lookup(0), BlockMapping::Mapped(pblk..pblk + 3));
+        assert!(matches!(tree.lookup(3), BlockMapping::Hole(_)));
+        assert_eq!(tree.free_blocks_count(), free_blocks_count - 3);
+    }
+}
@@ -5,9 +5,14 @@
 use super::{
     block_group::{BlockGroup, RawGroupDescriptor},
     block_ptr::Ext2Bid,
-    inode::{FilePerm, Inode, InodeDesc, RawInode},
+    inode::{FilePerm, Inode, InodeDesc},
+    journal::{Journal, JournalHandle},
     prelude::*,
-    super_block::{RawSuperBlock, SuperBlock, SUPER_BLOCK_OFFSET},
+    super_block::{
+        FeatureCompatSet, FeatureInCompatSet, FeatureRoCompatSet, RawSuperBlock, SuperBlock,
+        SUPER_BLOCK_OFFSET,
+    },
+    utils::crc32c,
 };
 use crate::fs::{
     registry::{FsProperties, FsType},
@@ -27,7 +32,13 @@ pub struct Ext2 {
     blocks_per_group: Ext2Bid,
     inode_size: usize,
     block_size: usize,
+    group_descriptor_size: usize,
+    feature_incompat: FeatureInCompatSet,
+    feature_ro_compat: FeatureRoCompatSet,
+    csum_seed: u32,
+    uuid: [u8; 16],
     group_descriptors_segment: USegment,
+    journal: Option<Arc<Journal>>,
     self_ref: Weak<Self>,
 }
 
@@ -36,20 +47,47 @@ impl Ext2 {
     pub fn open(block_device: Arc<dyn BlockDevice>) -> Result<Arc<Self>> {
         // Load the superblock
         // TODO: if the main superblock is corrupted, should we load the backup?
-        let super_block = {
+        let load_super_block = |block_device: &dyn BlockDevice| -> Result<SuperBlock> {
             let raw_super_block = block_device.read_val::<RawSuperBlock>(SUPER_BLOCK_OFFSET)?;
-            SuperBlock::try_from(raw_super_block)?
+            SuperBlock::try_from(raw_super_block)
         };
+        let mut super_block = load_super_block(block_device.as_ref())?;
         assert_eq!(
             super_block.block_size(),
             BLOCK_SIZE,
             "currently only support 4096-byte block size"
         );
 
+        // Load the journal, and replay it before loading any other metadata.
+        let journal = if super_block
+            .feature_compat()
+            .contains(FeatureCompatSet::HAS_JOURNAL)
+        {
+            let journal = Journal::load(block_device.clone(), &super_block)?;
+            if super_block.needs_recovery() {
+                journal.recover()?;
+                // The superblock may have been replayed.
+                super_block = load_super_block(block_device.as_ref())?;
+            } else {
+                journal.wipe()?;
+            }
+
+            // Set the flag before the journal is used, so that an unclean shutdown
+            // can be detected. The flag is cleared once the journal is empty at sync
+            // or unmount.
+            super_block.set_needs_recovery();
+            block_device.write_val(SUPER_BLOCK_OFFSET, &RawSuperBlock::from(&super_block))?;
+            block_device.sync()?;
+
+            Some(Arc::new(journal))
+        } else if super_block.needs_recovery() {
+            return_errno_with_message!(Errno::EINVAL, "needs recovery but has no journal");
+        } else {
+            None
+        };
+
         let group_descriptors_segment: USegment = {
-            let npages = ((super_block.block_groups_count() as usize)
-                * size_of::<RawGroupDescriptor>())
-            .div_ceil(BLOCK_SIZE);
+            let npages = super_block.group_descriptor_blocks();
             let segment = FrameAllocOptions::new()
                 .zeroed(false)
                 .alloc_segment(npages)?;
@@ -89,6 +127,11 @@ impl Ext2 {
             blocks_per_group: super_block.blocks_per_group(),
             inode_size: super_block.inode_size(),
             block_size: super_block.block_size(),
+            group_descriptor_size: super_block.group_descriptor_size(),
+            feature_incompat: super_block.feature_incompat(),
+            feature_ro_compat: super_block.feature_ro_compat(),
+            csum_seed: super_block.csum_seed(),
+            uuid: *super_block.uuid(),
             block_groups: load_block_groups(
                 weak_ref.clone(),
                 block_device.as_ref(),
@@ -98,8 +141,12 @@ impl Ext2 {
             block_device,
             super_block: RwMutex::new(Dirty::new(super_block)),
             group_descriptors_segment,
+            journal,
             self_ref: weak_ref.clone(),
         });
+        if let Some(journal) = ext2.journal.as_ref() {
+            journal.spawn_commit_thread(Arc::downgrade(&ext2));
+        }
         Ok(ext2)
     }
 
@@ -128,11 +175,75 @@ impl Ext2 {
         self.blocks_per_group
     }
 
+    /// Returns the size of group descriptors.
+    pub fn group_descriptor_size(&self) -> usize {
+        self.group_descriptor_size
+    }
+
+    /// Returns `true` if new files should be mapped by extents instead of block pointers.
+    pub fn has_extents(&self) -> bool {
+        self.feature_incompat.contains(FeatureInCompatSet::EXTENTS)
+    }
+
+    /// Returns `true` if directories can have an unlimited number of subdirectories.
+    pub fn has_dir_nlink(&self) -> bool {
+        self.feature_ro_compat
+            .contains(FeatureRoCompatSet::DIR_NLINK)
+    }
+
+    /// Returns `true` if the block counts of inodes can exceed 32 bits.
+    pub fn has_huge_file(&self) -> bool {
+        self.feature_ro_compat
+            .contains(FeatureRoCompatSet::HUGE_FILE)
+    }
+
+    /// Returns the seed of the metadata checksums.
+    ///
+    /// Returns `None` if the filesystem has no metadata checksums.
+    pub fn csum_seed(&self) -> Option<u32> {
+        self.feature_ro_compat
+            .contains(FeatureRoCompatSet::METADATA_CSUM)
+            .then_some(self.csum_seed)
+    }
+
+    /// Returns the seed of the checksums of the metadata belonging to an inode,
+    /// such as the inode itself, its extent tree blocks and directory blocks.
+    ///
+    /// Returns `None` if the filesystem has no metadata checksums.
+    pub(super) fn inode_csum_seed(&self, ino: u32, generation: u32) -> Option<u32> {
+        let seed = self.csum_seed()?;
+        let seed = crc32c(seed, &ino.to_le_bytes());
+        Some(crc32c(seed, &generation.to_le_bytes()))
+    }
+
     /// Returns the super block.
     pub fn super_block(&self) -> RwMutexReadGuard<Dirty<SuperBlock>> {
         self.super_block.read()
     }
 
+    /// Returns whether the filesystem has a journal, i.e., whether it is an Ext3.
+    pub fn has_journal(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Starts a journal handle for an operation that updates at most `nblocks` metadata blocks.
+    ///
+    /// Returns `None` if the filesystem has no journal.
+    /// See [`Journal::start`] for more details.
+    pub(super) fn journal_start(&self, nblocks: usize) -> Option<JournalHandle> {
+        self.journal.as_ref().map(|journal| journal.start(nblocks))
+    }
+
+    /// Marks the filesystem clean if the journal is empty.
+    ///
+    /// See [`Journal::mark_clean`] for more details.
+    pub(super) fn mark_clean(&self) -> Result<()> {
+        match self.journal.as_ref() {
+            Some(journal) => journal.mark_clean(),
+            None => Ok(()),
+        }
+    }
+
     /// Returns the root inode.
     pub fn root_inode(&self) -> Result<Arc<Inode>> {
         self.lookup_inode(ROOT_INO)
@@ -154,11 +265,12 @@ impl Ext2 {
     ) -> Result<Arc<Inode>> {
         let (block_group_idx, ino) =
             self.alloc_ino(dir_block_group_idx, inode_type == InodeType::Dir)?;
+        let block_group = &self.block_groups[block_group_idx];
+        block_group.clear_raw_inode(self.inode_idx(ino));
         let inode = {
-            let inode_desc = InodeDesc::new(inode_type, file_perm);
+            let inode_desc = InodeDesc::new(inode_type, file_perm, self);
             Inode::new(ino, block_group_idx, inode_desc, self.self_ref.clone())
         };
-        let block_group = &self.block_groups[block_group_idx];
         block_group.insert_cache(self.inode_idx(ino), inode.clone());
         Ok(inode)
     }
@@ -204,19 +316,41 @@ impl Ext2 {
     pub(super) fn sync_inode(&self, ino: u32, inode: &InodeDesc) -> Result<()> {
         let (_, block_group) = self.block_group_of_ino(ino)?;
         let inode_idx = self.inode_idx(ino);
-        block_group.sync_raw_inode(inode_idx, &RawInode::from(inode));
+        // The fields unknown to the driver (e.g., the in-inode extended attributes)
+        // in the on-disk inode are preserved.
+        let mut raw_inode = vec![0u8; self.inode_size];
+        block_group.read_raw_inode(inode_idx, &mut raw_inode);
+        inode.write_raw(
+            &mut raw_inode,
+            self.inode_csum_seed(ino, inode.generation()),
+        );
+        block_group.write_raw_inode(inode_idx, &raw_inode);
         Ok(())
     }
 
     /// Writes back the block group descriptor to the descriptors table.
+    ///
+    /// The checksum of the descriptor is updated if the filesystem has one.
     pub(super) fn sync_group_descriptor(
         &self,
         block_group_idx: usize,
-        raw_descriptor: &RawGroupDescriptor,
+        raw_descriptor: &mut RawGroupDescriptor,
     ) -> Result<()> {
-        let offset = block_group_idx * size_of::<RawGroupDescriptor>();
+        let desc_size = self.group_descriptor_size;
+        if self
+            .feature_ro_compat
+            .intersects(FeatureRoCompatSet::METADATA_CSUM | FeatureRoCompatSet::GDT_CSUM)
+        {
+            raw_descriptor.checksum = raw_descriptor.calc_checksum(
+                block_group_idx,
+                desc_size,
+                self.csum_seed(),
+                &self.uuid,
+            );
+        }
+        let offset = block_group_idx * desc_size;
         self.group_descriptors_segment
-            .write_val(offset, raw_descriptor)?;
+            .write_bytes(offset, raw_descriptor.on_disk_bytes(desc_size))?;
         Ok(())
     }
 
@@ -306,11 +440,17 @@ impl Ext2 {
     pub(super) fn read_blocks(&self, bid: Ext2Bid, bio_segment: BioSegment) -> Result<()> {
         let status = self
             .block_device
-            .read_blocks(Bid::new(bid as u64), bio_segment)?;
+            .read_blocks(Bid::new(bid as u64), bio_segment.clone())?;
         match status {
-            BioStatus::Complete => Ok(()),
-            err_status => Err(Error::from(err_status)),
+            BioStatus::Complete => (),
+            err_status => return Err(Error::from(err_status)),
+        }
+
+        // The blocks in the journal are newer than the ones on the disk.
+        if let Some(journal) = self.journal.as_ref() {
+            journal.patch_read(bid, &bio_segment)?;
         }
+        Ok(())
     }
 
     /// Reads contiguous blocks starting from the `bid` asynchronously.
@@ -319,14 +459,23 @@ impl Ext2 {
         bid: Ext2Bid,
         bio_segment: BioSegment,
     ) -> Result<BioWaiter> {
+        if let Some(journal) = self.journal.as_ref() {
+            let range = bid..bid + bio_segment.nblocks() as Ext2Bid;
+            if journal.contains_any(range) {
+                self.read_blocks(bid, bio_segment)?;
+                return Ok(BioWaiter::new());
+            }
+        }
+
         let waiter = self
             .block_device
             .read_blocks_async(Bid::new(bid as u64), bio_segment)?;
         Ok(waiter)
     }
 
-    /// Writes contiguous blocks starting from the `bid` synchronously.
+    /// Writes contiguous data blocks starting from the `bid` synchronously.
     pub(super) fn write_blocks(&self, bid: Ext2Bid, bio_segment: BioSegment) -> Result<()> {
+        self.forget_journaled_blocks(bid, &bio_segment);
         let status = self
             .block_device
             .write_blocks(Bid::new(bid as u64), bio_segment)?;
@@ -336,18 +485,75 @@ impl Ext2 {
         }
     }
 
-    /// Writes contiguous blocks starting from the `bid` asynchronously.
+    /// Writes contiguous data blocks starting from the `bid` asynchronously.
     pub(super) fn write_blocks_async(
         &self,
         bid: Ext2Bid,
         bio_segment: BioSegment,
     ) -> Result<BioWaiter> {
+        self.forget_journaled_blocks(bid, &bio_segment);
         let waiter = self
             .block_device
             .write_blocks_async(Bid::new(bid as u64), bio_segment)?;
         Ok(waiter)
     }
 
+    /// Writes contiguous metadata blocks starting from the `bid` asynchronously.
+    ///
+    /// If the filesystem has a journal, the blocks are logged into the running transaction,
+    /// and will reach the disk when the transaction is committed.
+    pub(super) fn write_metadata_blocks_async(
+        &self,
+        bid: Ext2Bid,
+        bio_segment: BioSegment,
+    ) -> Result<BioWaiter> {
+        let Some(journal) = self.journal.as_ref() else {
+            return self.write_blocks_async(bid, bio_segment);
+        };
+
+        journal.log_blocks(bid, &bio_segment)?;
+        Ok(BioWaiter::new())
+    }
+
+    /// Writes metadata bytes at the `offset` of the device asynchronously.
+    ///
+    /// See [`Self::write_metadata_blocks_async`] for the case with a journal.
+    pub(super) fn write_metadata_bytes_async(
+        &self,
+        offset: usize,
+        buf: &[u8],
+    ) -> Result<BioWaiter> {
+        let Some(journal) = self.journal.as_ref() else {
+            return Ok(self.block_device.write_bytes_async(offset, buf)?);
+        };
+
+        journal.log_bytes(offset, buf)?;
+        Ok(BioWaiter::new())
+    }
+
+    /// Removes the blocks to be overwritten by data from the running transaction.
+    ///
+    /// The blocks may be freed metadata blocks that are reused to store data.
+    fn forget_journaled_blocks(&self, bid: Ext2Bid, bio_segment: &BioSegment) {
+        if let Some(journal) = self.journal.as_ref() {
+            journal.forget_blocks(bid..bid + bio_segment.nblocks() as Ext2Bid);
+        }
+    }
+
+    /// Writes back all the data and metadata to the block device.
+    ///
+    /// If the filesystem has a journal, the metadata is committed to the journal.
+    pub fn sync_all(&self) -> Result<()> {
+        if let Some(journal) = self.journal.as_ref() {
+            return journal.commit(self);
+        }
+
+        self.sync_all_inodes()?;
+        self.sync_metadata()?;
+        self.block_device.sync()?;
+        Ok(())
+    }
+
     /// Writes back the metadata to the block device.
     pub fn sync_metadata(&self) -> Result<()> {
         // If the superblock is clean, the block groups must be clean.
@@ -365,15 +571,14 @@ impl Ext2 {
         let mut bio_waiter = BioWaiter::new();
         let raw_super_block = RawSuperBlock::from((*super_block).deref());
         bio_waiter.concat(
-            self.block_device
-                .write_bytes_async(SUPER_BLOCK_OFFSET, raw_super_block.as_bytes())?,
+            self.write_metadata_bytes_async(SUPER_BLOCK_OFFSET, raw_super_block.as_bytes())?,
         );
         let group_descriptors_bio_segment = BioSegment::new_from_segment(
             self.group_descriptors_segment.clone(),
             BioDirection::ToDevice,
         );
-        bio_waiter.concat(self.block_device.write_blocks_async(
-            super_block.group_descriptors_bid(0),
+        bio_waiter.concat(self.write_metadata_blocks_async(
+            super_block.group_descriptors_bid(0).to_raw() as Ext2Bid,
             group_descriptors_bio_segment.clone(),
         )?);
         bio_waiter
@@ -382,11 +587,13 @@ impl Ext2 {
         drop(bio_waiter);
 
         // Writes back the backups of superblock and group descriptor table.
+        // The backups are written to the disk directly without the journal.
         let mut raw_super_block_backup = raw_super_block;
         for idx in 1..super_block.block_groups_count() {
             if super_block.is_backup_group(idx as usize) {
                 let mut bio_waiter = BioWaiter::new();
                 raw_super_block_backup.block_group_idx = idx as u16;
+                raw_super_block_backup.update_checksum();
                 bio_waiter.concat(self.block_device.write_bytes_async(
                     super_block.bid(idx as usize).to_offset(),
                     raw_super_block_backup.as_bytes(),
@@ -439,6 +646,16 @@ impl Ext2 {
     }
 }
 
+impl Drop for Ext2 {
+    fn drop(&mut self) {
+        // The filesystem is unmounted. The inodes cannot be written back here since they
+        // refer to the filesystem, but the committed metadata is already on the disk.
+        if let Err(err) = self.mark_clean() {
+            warn!("failed to mark the ext3 filesystem clean: {:?}", err);
+        }
+    }
+}
+
 pub(super) struct Ext2Type;
 
 impl FsType for Ext2Type {
@@ -463,3 +680,57 @@ impl FsType for Ext2Type {
         None
     }
 }
+
+pub(super) struct Ext3Type;
+
+impl FsType for Ext3Type {
+    fn name(&self) -> &'static str {
+        "ext3"
+    }
+
+    fn properties(&self) -> FsProperties {
+        FsProperties::NEED_DISK
+    }
+
+    fn create(
+        &self,
+        _flags: FsFlags,
+        _args: Option<CString>,
+        disk: Option<Arc<dyn BlockDevice>>,
+    ) -> Result<Arc<dyn FileSystem>> {
+        let fs = Ext2::open(disk.unwrap())?;
+        if !fs.has_journal() {
+            return_errno_with_message!(Errno::EINVAL, "ext3 requires a journal");
+        }
+        Ok(fs)
+    }
+
+    fn sysnode(&self) -> Option<Arc<dyn aster_systree::SysNode>> {
+        None
+    }
+}
+
+pub(super) struct Ext4Type;
+
+impl FsType for Ext4Type {
+    fn name(&self) -> &'static str {
+        "ext4"
+    }
+
+    fn properties(&self) -> FsProperties {
+        FsProperties::NEED_DISK
+    }
+
+    fn create(
+        &self,
+        _flags: FsFlags,
+        _args: Option<CString>,
+        disk: Option<Arc<dyn BlockDevice>>,
+    ) -> Result<Arc<dyn FileSystem>> {
+        Ext2::open(disk.unwrap()).map(|fs| fs as _)
+    }
+
+    fn sysnode(&self) -> Option<Arc<dyn aster_systree::SysNode>> {
+        None
+    }
+}
@@ -0,0 +1,324 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The hash functions for the names of the entries in hashed directories.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/hash.c>
+
+use super::prelude::*;
+
+/// The hash algorithm of a hashed directory.
+///
+/// The unsigned variants are never stored on disk, they are selected if the
+/// super block indicates that the characters of names are treated as unsigned.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, TryFromInt)]
+pub(super) enum DxHashVersion {
+    Legacy = 0,
+    HalfMd4 = 1,
+    Tea = 2,
+    LegacyUnsigned = 3,
+    HalfMd4Unsigned = 4,
+    TeaUnsigned = 5,
+}
+
+impl DxHashVersion {
+    /// Returns the hash version given the version stored on disk.
+    pub fn new(on_disk_version: u8, is_unsigned: bool) -> Result<Self> {
+        let version = Self::try_from(on_disk_version).map_err(|_| {
+            Error::with_message(Errno::EOPNOTSUPP, "unsupported directory hash version")
+        })?;
+        if !is_unsigned {
+            return Ok(version);
+        }
+
+        Ok(match version {
+            Self::Legacy => Self::LegacyUnsigned,
+            Self::HalfMd4 => Self::HalfMd4Unsigned,
+            Self::Tea => Self::TeaUnsigned,
+            _ => return_errno_with_message!(Errno::EUCLEAN, "invalid directory hash version"),
+        })
+    }
+}
+
+/// Computes the hash of the `name` in a hashed directory.
+///
+/// The lowest bit of the returned hash is always zero, it is reserved for marking
+/// hash collisions in the index.
+pub(super) fn dx_hash(name: &[u8], version: DxHashVersion, seed: &[u32; 4]) -> u32 {
+    let mut buf = if seed.iter().all(|word| *word == 0) {
+        DEFAULT_SEED
+    } else {
+        *seed
+    };
+
+    let hash = match version {
+        DxHashVersion::Legacy => dx_hack_hash(name, false),
+        DxHashVersion::LegacyUnsigned => dx_hack_hash(name, true),
+        DxHashVersion::HalfMd4 | DxHashVersion::HalfMd4Unsigned => {
+            let is_unsigned = version == DxHashVersion::HalfMd4Unsigned;
+            let mut input = [0u32; 8];
+            for chunk in chunks(name, 32) {
+                str_to_hash_buf(chunk, &mut input, is_unsigned);
+                half_md4_transform(&mut buf, &input);
+            }
+            buf[1]
+        }
+        DxHashVersion::Tea | DxHashVersion::TeaUnsigned => {
+            let is_unsigned = version == DxHashVersion::TeaUnsigned;
+            let mut input = [0u32; 4];
+            for chunk in chunks(name, 16) {
+                str_to_hash_buf(chunk, &mut input, is_unsigned);
+                tea_transform(&mut buf, &input);
+            }
+            buf[0]
+        }
+    };
+
+    let hash = hash & !1;
+    // The maximum hash value is reserved to indicate the end of the directory.
+    if hash == HTREE_EOF_32BIT << 1 {
+        (HTREE_EOF_32BIT - 1) << 1
+    } else {
+        hash
+    }
+}
+
+const DEFAULT_SEED: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+const HTREE_EOF_32BIT: u32 = 0x7fffffff;
+
+/// Returns the chunks of the name to be hashed.
+///
+/// Unlike `<[u8]>::chunks`, each chunk extends to the end of the name, since
+/// the length of the remaining name is involved in the padding.
+fn chunks(name: &[u8], chunk_size: usize) -> impl Iterator<Item = &[u8]> {
+    (0..name.len())
+        .step_by(chunk_size)
+        .map(move |offset| &name[offset..])
+}
+
+/// Converts the characters into the value as the C code does.
+fn char_value(ch: u8, is_unsigned: bool) -> u32 {
+    if is_unsigned {
+        ch as u32
+    } else {
+        ch as i8 as i32 as u32
+    }
+}
+
+/// The legacy hash function.
+fn dx_hack_hash(name: &[u8], is_unsigned: bool) -> u32 {
+    let mut hash0: u32 = 0x12a3fe2d;
+    let mut hash1: u32 = 0x37abe8f9;
+
+    for ch in name {
+        let mut hash =
+            hash1.wrapping_add(hash0 ^ char_value(*ch, is_unsigned).wrapping_mul(7152373));
+        if hash & 0x80000000 != 0 {
+            hash = hash.wrapping_sub(0x7fffffff);
+        }
+        hash1 = hash0;
+        hash0 = hash;
+    }
+
+    hash0 << 1
+}
+
+/// Packs the characters of the name into the input buffer of the hash transforms.
+fn str_to_hash_buf(msg: &[u8], buf: &mut [u32], is_unsigned: bool) {
+    let len = msg.len() as u32;
+    let mut pad = len | (len << 8);
+    pad |= pad << 16;
+
+    let msg = &msg[..msg.len().min(buf.len() * 4)];
+    let mut words = buf.iter_mut();
+    let mut val = pad;
+    for (i, ch) in msg.iter().enumerate() {
+        val = char_value(*ch, is_unsigned).wrapping_add(val << 8);
+        if i % 4 == 3 {
+            *words.next().unwrap() = val;
+            val = pad;
+        }
+    }
+    if msg.len() % 4 != 0 {
+        *words.next().unwrap() = val;
+    }
+    for word in words {
+        *word = pad;
+    }
+}
+
+fn tea_transform(buf: &mut [u32; 4], input: &[u32; 4]) {
+    const DELTA: u32 = 0x9E3779B9;
+
+    let [a, b, c, d] = *input;
+    let mut sum: u32 = 0;
+    let mut b0 = buf[0];
+    let mut b1 = buf[1];
+
+    for _ in 0..16 {
+        sum = sum.wrapping_add(DELTA);
+        b0 = b0.wrapping_add(
+            ((b1 << 4).wrapping_add(a)) ^ (b1.wrapping_add(sum)) ^ ((b1 >> 5).wrapping_add(b)),
+        );
+        b1 = b1.wrapping_add(
+            ((b0 << 4).wrapping_add(c)) ^ (b0.wrapping_add(sum)) ^ ((b0 >> 5).wrapping_add(d)),
+        );
+    }
+
+    buf[0] = buf[0].wrapping_add(b0);
+    buf[1] = buf[1].wrapping_add(b1);
+}
+
+fn half_md4_transform(buf: &mut [u32; 4], input: &[u32; 8]) {
+    const K1: u32 = 0;
+    const K2: u32 = 0o13240474631;
+    const K3: u32 = 0o15666365641;
+
+    fn f(x: u32, y: u32, z: u32) -> u32 {
+        z ^ (x & (y ^ z))
+    }
+    fn g(x: u32, y: u32, z: u32) -> u32 {
+        (x & y).wrapping_add((x ^ y) & z)
+    }
+    fn h(x: u32, y: u32, z: u32) -> u32 {
+        x ^ y ^ z
+    }
+
+    let [mut a, mut b, mut c, mut d] = *buf;
+    macro_rules! round {
+        ($f:ident, $a:ident, $b:ident, $c:ident, $d:ident, $x:expr, $s:expr) => {
+            $a = $a.wrapping_add($f($b, $c, $d)).wrapping_add($x);
+            $a = $a.rotate_left($s);
+        };
+    }
+
+    // Round 1
+    round!(f, a, b, c, d, input[0].wrapping_add(K1), 3);
+    round!(f, d, a, b, c, input[1].wrapping_add(K1), 7);
+    round!(f, c, d, a, b, input[2].wrapping_add(K1), 11);
+    round!(f, b, c, d, a, input[3].wrapping_add(K1), 19);
+    round!(f, a, b, c, d, input[4].wrapping_add(K1), 3);
+    round!(f, d, a, b, c, input[5].wrapping_add(K1), 7);
+    round!(f, c, d, a, b, input[6].wrapping_add(K1), 11);
+    round!(f, b, c, d, a, input[7].wrapping_add(K1), 19);
+
+    // Round 2
+    round!(g, a, b, c, d, input[1].wrapping_add(K2), 3);
+    round!(g, d, a, b, c, input[3].wrapping_add(K2), 5);
+    round!(g, c, d, a, b, input[5].wrapping_add(K2), 9);
+    round!(g, b, c, d, a, input[7].wrapping_add(K2), 13);
+    round!(g, a, b, c, d, input[0].wrapping_add(K2), 3);
+    round!(g, d, a, b, c, input[2].wrapping_add(K2), 5);
+    round!(g, c, d, a, b, input[4].wrapping_add(K2), 9);
+    round!(g, b, c, d, a, input[6].wrapping_add(K2), 13);
+
+    // Round 3
+    round!(h, a, b, c, d, input[3].wrapping_add(K3), 3);
+    round!(h, d, a, b, c, input[7].wrapping_add(K3), 9);
+    round!(h, c, d, a, b, input[2].wrapping_add(K3), 11);
+    round!(h, b, c, d, a, input[6].wrapping_add(K3), 15);
+    round!(h, a, b, c, d, input[1].wrapping_add(K3), 3);
+    round!(h, d, a, b, c, input[5].wrapping_add(K3), 9);
+    round!(h, c, d, a, b, input[0].wrapping_add(K3), 11);
+    round!(h, b, c, d, a, input[4].wrapping_add(K3), 15);
+
+    buf[0] = buf[0].wrapping_add(a);
+    buf[1] = buf[1].wrapping_add(b);
+    buf[2] = buf[2].wrapping_add(c);
+    buf[3] = buf[3].wrapping_add(d);
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    /// A name that spans multiple chunks of both the TEA and the half-MD4 hashes.
+    const LONG_NAME: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCD";
+
+    /// A name whose hashes depend on the signedness of the characters.
+    const NON_ASCII_NAME: &[u8] = b"\xe4\xb8\xad\xe6\x96\x87";
+
+    /// The seed given by the UUID `6d5b8f1e-3c2a-4b7d-9e0f-1a2b3c4d5e6f`.
+    const SEED: [u32; 4] = [0x1e8f5b6d, 0x7d4b2a3c, 0x2b1a0f9e, 0x6f5e4d3c];
+
+    /// A zero seed, which selects the default seed.
+    const NO_SEED: [u32; 4] = [0; 4];
+
+    // The expected hashes are computed by `debugfs -R "dx_hash -h <version> -s <seed> <name>"`,
+    // which shares the hash functions with Linux.
+    fn check(version: DxHashVersion, seed: &[u32; 4], cases: &[(&[u8], u32)]) {
+        for (name, hash) in cases {
+            assert_eq!(dx_hash(name, version, seed), *hash, "name: {:?}", name);
+        }
+    }
+
+    #[ktest]
+    fn legacy() {
+        let cases: &[(&[u8], u32)] = &[
+            (b"a", 0xe74b53e2),
+            (b"lost+found", 0x5e2aba24),
+            (LONG_NAME, 0xd3aa8e04),
+            (NON_ASCII_NAME, 0x68841632),
+        ];
+        check(DxHashVersion::Legacy, &NO_SEED, cases);
+        // The seed is not used by the legacy hash.
+        check(DxHashVersion::Legacy, &SEED, cases);
+
+        check(
+            DxHashVersion::LegacyUnsigned,
+            &NO_SEED,
+            &[(b"lost+found", 0x5e2aba24), (NON_ASCII_NAME, 0x45dbc5f8)],
+        );
+    }
+
+    #[ktest]
+    fn half_md4() {
+        check(
+            DxHashVersion::HalfMd4,
+            &NO_SEED,
+            &[
+                (b"a", 0xd5fa7d7a),
+                (b"lost+found", 0x591de422),
+                (LONG_NAME, 0x4059ff28),
+                (NON_ASCII_NAME, 0x54113b10),
+            ],
+        );
+        check(
+            DxHashVersion::HalfMd4,
+            &SEED,
+            &[(b"lost+found", 0xbc8446e2), (LONG_NAME, 0x24300510)],
+        );
+        check(
+            DxHashVersion::HalfMd4Unsigned,
+            &NO_SEED,
+            &[(b"lost+found", 0x591de422), (NON_ASCII_NAME, 0xb18d9d9c)],
+        );
+    }
+
+    #[ktest]
+    fn tea() {
+        check(
+            DxHashVersion::Tea,
+            &NO_SEED,
+            &[
+                (b"a", 0x6d0ea4c0),
+                (b"lost+found", 0x2dbf9e80),
+                (LONG_NAME, 0x4d79b500),
+                (NON_ASCII_NAME, 0x95700484),
+            ],
+        );
+        check(
+            DxHashVersion::Tea,
+            &SEED,
+            &[(b"lost+found", 0x1bd9d50a), (LONG_NAME, 0x9fc42098)],
+        );
+        check(
+            DxHashVersion::TeaUnsigned,
+            &NO_SEED,
+            &[(b"lost+found", 0x2dbf9e80), (NON_ASCII_NAME, 0xa9b174e8)],
+        );
+    }
+}
@@ -12,15 +12,18 @@ use crate::{
 
 impl FileSystem for Ext2 {
     fn name(&self) -> &'static str {
-        "ext2"
+        if self.has_extents() {
+            "ext4"
+        } else if self.has_journal() {
+            "ext3"
+        } else {
+            "ext2"
+        }
     }
 
     fn sync(&self) -> Result<()> {
-        self.sync_all_inodes()?;
-        self.sync_metadata()?;
-
-        self.block_device().sync()?;
-        Ok(())
+        self.sync_all()?;
+        self.mark_clean()
     }
 
     fn root_inode(&self) -> Arc<dyn Inode> {
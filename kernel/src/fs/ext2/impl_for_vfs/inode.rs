@@ -4,7 +4,10 @@ use core::time::Duration;
 
 use crate::{
     fs::{
-        ext2::{FilePerm, Inode as Ext2Inode},
+        ext2::{
+            journal::{data_credits, ATTR_CREDITS, DIR_ENTRY_CREDITS, INODE_CREDITS},
+            FilePerm, Inode as Ext2Inode,
+        },
         utils::{
             DirentVisitor, Extension, FallocMode, FileSystem, Inode, InodeIo, InodeMode, InodeType,
             Metadata, MknodType, StatusFlags, SymbolicLink, XattrName, XattrNamespace,
@@ -36,6 +39,9 @@ impl InodeIo for Ext2Inode {
         reader: &mut VmReader,
         status_flags: StatusFlags,
     ) -> Result<usize> {
+        let _handle = self
+            .fs()
+            .journal_start(data_credits(offset, reader.remain()));
         if status_flags.contains(StatusFlags::O_DIRECT) {
             self.write_direct_at(offset, reader)
         } else {
@@ -50,6 +56,7 @@ impl Inode for Ext2Inode {
     }
 
     fn resize(&self, new_size: usize) -> Result<()> {
+        let _handle = self.fs().journal_start(INODE_CREDITS);
         self.resize(new_size)
     }
 
@@ -62,6 +69,7 @@ impl Inode for Ext2Inode {
     }
 
     fn set_atime(&self, time: Duration) {
+        let _handle = self.fs().journal_start(ATTR_CREDITS);
         self.set_atime(time)
     }
 
@@ -70,6 +78,7 @@ impl Inode for Ext2Inode {
     }
 
     fn set_mtime(&self, time: Duration) {
+        let _handle = self.fs().journal_start(ATTR_CREDITS);
         self.set_mtime(time)
     }
 
@@ -78,6 +87,7 @@ impl Inode for Ext2Inode {
     }
 
     fn set_ctime(&self, time: Duration) {
+        let _handle = self.fs().journal_start(ATTR_CREDITS);
         self.set_ctime(time)
     }
 
@@ -94,6 +104,7 @@ impl Inode for Ext2Inode {
     }
 
     fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        let _handle = self.fs().journal_start(ATTR_CREDITS);
         self.set_file_perm(mode.into());
         Ok(())
     }
@@ -103,6 +114,7 @@ impl Inode for Ext2Inode {
     }
 
     fn set_owner(&self, uid: Uid) -> Result<()> {
+        let _handle = self.fs().journal_start(ATTR_CREDITS);
         self.set_uid(uid.into());
         Ok(())
     }
@@ -112,6 +124,7 @@ impl Inode for Ext2Inode {
     }
 
     fn set_group(&self, gid: Gid) -> Result<()> {
+        let _handle = self.fs().journal_start(ATTR_CREDITS);
         self.set_gid(gid.into());
         Ok(())
     }
@@ -121,10 +134,12 @@ impl Inode for Ext2Inode {
     }
 
     fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
+        let _handle = self.fs().journal_start(DIR_ENTRY_CREDITS);
         Ok(self.create(name, type_, mode.into())?)
     }
 
     fn mknod(&self, name: &str, mode: InodeMode, type_: MknodType) -> Result<Arc<dyn Inode>> {
+        let _handle = self.fs().journal_start(DIR_ENTRY_CREDITS);
         let inode_type = type_.inode_type();
         let inode = match type_ {
             MknodType::CharDevice(dev) | MknodType::BlockDevice(dev) => {
@@ -150,14 +165,17 @@ impl Inode for Ext2Inode {
         let old = old
             .downcast_ref::<Ext2Inode>()
             .ok_or_else(|| Error::with_message(Errno::EXDEV, "not same fs"))?;
+        let _handle = self.fs().journal_start(DIR_ENTRY_CREDITS);
         self.link(old, name)
     }
 
     fn unlink(&self, name: &str) -> Result<()> {
+        let _handle = self.fs().journal_start(DIR_ENTRY_CREDITS);
         self.unlink(name)
     }
 
     fn rmdir(&self, name: &str) -> Result<()> {
+        let _handle = self.fs().journal_start(DIR_ENTRY_CREDITS);
         self.rmdir(name)
     }
 
@@ -165,6 +183,8 @@ impl Inode for Ext2Inode {
         let target = target
             .downcast_ref::<Ext2Inode>()
             .ok_or_else(|| Error::with_message(Errno::EXDEV, "not same fs"))?;
+        // The entry is removed from a directory and added to another one.
+        let _handle = self.fs().journal_start(2 * DIR_ENTRY_CREDITS);
         self.rename(old_name, target, new_name)
     }
 
@@ -173,22 +193,37 @@ impl Inode for Ext2Inode {
     }
 
     fn write_link(&self, target: &str) -> Result<()> {
+        let _handle = self.fs().journal_start(data_credits(0, target.len()));
         self.write_link(target)
     }
 
     fn fallocate(&self, mode: FallocMode, offset: usize, len: usize) -> Result<()> {
+        let _handle = self.fs().journal_start(data_credits(offset, len));
         self.fallocate(mode, offset, len)
     }
 
     fn sync_all(&self) -> Result<()> {
+        let fs = self.fs();
+        // The metadata can only reach the disk through the journal.
+        if fs.has_journal() {
+            return fs.sync_all();
+        }
+
         self.sync_all()?;
-        self.fs().block_device().sync()?;
+        fs.block_device().sync()?;
         Ok(())
     }
 
     fn sync_data(&self) -> Result<()> {
+        let fs = self.fs();
+        // The metadata required to read the data back can only reach the disk
+        // through the journal.
+        if fs.has_journal() {
+            return fs.sync_all();
+        }
+
         self.sync_data()?;
-        self.fs().block_device().sync()?;
+        fs.block_device().sync()?;
         Ok(())
     }
 
@@ -206,6 +241,7 @@ impl Inode for Ext2Inode {
         value_reader: &mut VmReader,
         flags: XattrSetFlags,
     ) -> Result<()> {
+        let _handle = self.fs().journal_start(INODE_CREDITS);
         self.set_xattr(name, value_reader, flags)
     }
 
@@ -218,6 +254,7 @@ impl Inode for Ext2Inode {
     }
 
     fn remove_xattr(&self, name: XattrName) -> Result<()> {
+        let _handle = self.fs().journal_start(INODE_CREDITS);
         self.remove_xattr(name)
     }
 }
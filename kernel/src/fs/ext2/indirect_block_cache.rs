@@ -117,7 +117,7 @@ impl IndirectBlockCache {
                     Segment::<()>::from(block.frame.clone()).into(),
                     BioDirection::ToDevice,
                 );
-                bio_waiter.concat(self.fs().write_blocks_async(bid, bio_segment)?);
+                bio_waiter.concat(self.fs().write_metadata_blocks_async(bid, bio_segment)?);
             }
         }
 
@@ -181,6 +181,23 @@ impl IndirectBlock {
         self.state = State::Dirty;
         Ok(())
     }
+
+    /// Reads the bytes at a specified `offset` into `buf`.
+    pub fn read_bytes(&self, offset: usize, buf: &mut [u8]) -> Result<()> {
+        assert!(self.state != State::Uninit);
+        self.frame.read_bytes(offset, buf)?;
+        Ok(())
+    }
+
+    /// Writes the bytes of `buf` at a specified `offset`.
+    ///
+    /// After a successful write operation, the block's state will be marked as dirty.
+    pub fn write_bytes(&mut self, offset: usize, buf: &[u8]) -> Result<()> {
+        assert!(self.state != State::Uninit);
+        self.frame.write_bytes(offset, buf)?;
+        self.state = State::Dirty;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -4,18 +4,25 @@
 #![expect(unused_variables)]
 
 use alloc::{borrow::ToOwned, rc::Rc};
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::{
+    mem::offset_of,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
+use aster_block::SECTOR_SIZE;
 use inherit_methods_macro::inherit_methods;
 use ostd::{const_assert, mm::io_util::HasVmReaderWriter};
 
 use super::{
-    block_ptr::{BidPath, BlockPtrs, Ext2Bid, BID_SIZE, MAX_BLOCK_PTRS},
-    dir::{DirEntryHeader, DirEntryItem, DirEntryReader, DirEntryWriter},
+    block_ptr::{BidPath, BlockMapping, BlockPtrs, Ext2Bid, BID_SIZE, MAX_BLOCK_PTRS},
+    dir::{self, DirConfig, DirEntryHeader, DirEntryItem, DirEntryReader, DirEntryWriter},
+    extent::{self, ExtentTree},
     fs::Ext2,
     indirect_block_cache::{IndirectBlock, IndirectBlockCache},
+    journal::ATTR_CREDITS,
     prelude::*,
-    utils::now,
+    super_block::FeatureCompatSet,
+    utils::{crc32c, now},
     xattr::Xattr,
 };
 use crate::{
@@ -35,6 +42,12 @@ pub const MAX_FNAME_LEN: usize = 255;
 /// Max path length of the fast symlink.
 pub const MAX_FAST_SYMLINK_LEN: usize = MAX_BLOCK_PTRS * BID_SIZE;
 
+/// Max number of hard links to an inode.
+///
+/// With the `DIR_NLINK` feature, a directory can have more subdirectories,
+/// whose hard links are then set to 1.
+pub const MAX_LINKS: u16 = 65000;
+
 /// The Ext2 inode.
 pub struct Inode {
     ino: u32,
@@ -53,6 +66,10 @@ impl Inode {
         desc: Dirty<InodeDesc>,
         fs: Weak<Ext2>,
     ) -> Arc<Self> {
+        let csum_seed = fs
+            .upgrade()
+            .unwrap()
+            .inode_csum_seed(ino, desc.generation());
         Arc::new_cyclic(|weak_self| Self {
             ino,
             type_: desc.type_,
@@ -60,7 +77,12 @@ impl Inode {
             xattr: desc
                 .acl
                 .map(|acl| Xattr::new(acl, weak_self.clone(), fs.clone())),
-            inner: RwMutex::new(InodeInner::new(desc, weak_self.clone(), fs.clone())),
+            inner: RwMutex::new(InodeInner::new(
+                desc,
+                csum_seed,
+                weak_self.clone(),
+                fs.clone(),
+            )),
             fs,
             extension: Extension::new(),
         })
@@ -94,7 +116,7 @@ impl Inode {
             ino: self.ino() as _,
             size: inner.file_size() as _,
             blk_size: BLOCK_SIZE,
-            blocks: inner.blocks_count() as _,
+            blocks: inner.allocated_blocks() as _,
             atime: inner.atime(),
             mtime: inner.mtime(),
             ctime: inner.ctime(),
@@ -220,6 +242,9 @@ impl Inode {
         if inode_type == InodeType::Dir {
             return_errno!(Errno::EPERM);
         }
+        if inode.hard_links() >= MAX_LINKS {
+            return_errno!(Errno::EMLINK);
+        }
 
         let mut inner = inner.upgrade();
         inner.append_new_entry(inode.ino, inode_type, name, true)?;
@@ -317,8 +342,8 @@ impl Inode {
         let now = now();
         self_inner.set_mtime(now);
         self_inner.set_ctime(now);
-        dir_inner.dec_hard_links();
-        dir_inner.dec_hard_links(); // For "."
+        // The hard links may be 1 if the directory once had too many subdirectories.
+        dir_inner.clear_hard_links();
 
         Ok(())
     }
@@ -415,9 +440,10 @@ impl Inode {
         self_inner.set_mtime(now);
         self_inner.set_ctime(now);
 
-        dst_inner.dec_hard_links();
         if dst_inode_typ == InodeType::Dir {
-            dst_inner.dec_hard_links(); // For "."
+            dst_inner.clear_hard_links();
+        } else {
+            dst_inner.dec_hard_links();
         }
         dst_inner.set_ctime(now);
         drop(self_inner);
@@ -583,7 +609,11 @@ impl Inode {
         self_inner.remove_entry_at(old_name, src_offset)?;
         target_inner.remove_entry_at(new_name, dst_offset)?;
         target_inner.append_new_entry(src_inode.ino, src_inode_typ, new_name, false)?;
-        dst_inner.dec_hard_links();
+        if is_dir {
+            dst_inner.clear_hard_links();
+        } else {
+            dst_inner.dec_hard_links();
+        }
         let now = now();
         self_inner.set_mtime(now);
         self_inner.set_ctime(now);
@@ -592,7 +622,6 @@ impl Inode {
         dst_inner.set_ctime(now);
 
         if is_dir {
-            dst_inner.dec_hard_links(); // For "."
             let mut src_inner = write_guards.pop().unwrap();
             src_inner.set_parent_ino(target.ino)?;
             src_inner.set_ctime(now);
@@ -618,15 +647,16 @@ impl Inode {
             }
 
             let try_readdir = |offset: &mut usize, visitor: &mut dyn DirentVisitor| -> Result<()> {
-                let mut dir_entry_reader = DirEntryReader::new(&inner.page_cache, *offset);
-                for dir_entry in dir_entry_reader.iter_entries() {
+                let mut dir_entry_reader =
+                    DirEntryReader::new(&inner.page_cache, *offset, inner.dir_config());
+                for (dir_entry, next_offset) in dir_entry_reader.iter_entries() {
                     visitor.visit(
                         dir_entry.name(),
                         dir_entry.ino() as u64,
                         dir_entry.type_(),
-                        dir_entry.record_len(),
+                        next_offset - *offset,
                     )?;
-                    *offset += dir_entry.record_len();
+                    *offset = next_offset;
                 }
 
                 Ok(())
@@ -639,6 +669,7 @@ impl Inode {
             }?
         };
 
+        let _handle = self.fs().journal_start(ATTR_CREDITS);
         self.set_atime(now());
 
         Ok(offset_read)
@@ -688,6 +719,7 @@ impl Inode {
 
         let bytes_read = self.inner.read().read_at(offset, writer)?;
 
+        let _handle = self.fs().journal_start(ATTR_CREDITS);
         self.set_atime(now());
 
         Ok(bytes_read)
@@ -704,6 +736,7 @@ impl Inode {
 
         let bytes_read = self.inner.read().read_direct_at(offset, writer)?;
 
+        let _handle = self.fs().journal_start(ATTR_CREDITS);
         self.set_atime(now());
 
         Ok(bytes_read)
@@ -722,6 +755,11 @@ impl Inode {
             let mut inner = inner.upgrade();
             let len = inner.extend_write_at(offset, reader)?;
             (len, inner)
+        } else if inner.has_holes(offset..new_size)? {
+            let mut inner = inner.upgrade();
+            inner.fill_holes(offset..new_size)?;
+            let len = inner.write_at(offset, reader)?;
+            (len, inner)
         } else {
             let len = inner.write_at(offset, reader)?;
             (len, inner.upgrade())
@@ -808,7 +846,8 @@ impl Inode {
                 if new_size > self.file_size() {
                     self.resize(new_size)?;
                 }
-                Ok(())
+                // The blocks are allocated as uninitialized ones if the file uses extents.
+                self.inner.write().preallocate(offset..new_size)
             }
             FallocMode::AllocateKeepSize => Ok(()),
             _ => {
@@ -871,7 +910,7 @@ impl Inode {
     pub fn gid(&self) -> u32;
     pub fn file_flags(&self) -> FileFlags;
     pub fn hard_links(&self) -> u16;
-    pub fn blocks_count(&self) -> Ext2Bid;
+    pub fn allocated_blocks(&self) -> u64;
     pub fn acl(&self) -> Option<Bid>;
     pub fn atime(&self) -> Duration;
     pub fn mtime(&self) -> Duration;
@@ -956,9 +995,14 @@ struct InodeInner {
 }
 
 impl InodeInner {
-    pub fn new(desc: Dirty<InodeDesc>, weak_self: Weak<Inode>, fs: Weak<Ext2>) -> Self {
+    pub fn new(
+        desc: Dirty<InodeDesc>,
+        csum_seed: Option<u32>,
+        weak_self: Weak<Inode>,
+        fs: Weak<Ext2>,
+    ) -> Self {
         let num_page_bytes = desc.num_page_bytes();
-        let inode_impl = InodeImpl::new(desc, weak_self, fs);
+        let inode_impl = InodeImpl::new(desc, csum_seed, weak_self, fs);
         Self {
             page_cache: PageCache::with_capacity(
                 num_page_bytes,
@@ -1020,6 +1064,7 @@ impl InodeInner {
         let write_len = reader.remain();
         let new_size = offset + write_len;
         self.page_cache.resize(new_size.align_up(BLOCK_SIZE))?;
+        self.fill_holes(offset..new_size)?;
         self.page_cache.pages().write(offset, reader)?;
         self.inode_impl.resize(new_size)?;
         Ok(write_len)
@@ -1041,6 +1086,9 @@ impl InodeInner {
 
         let start_bid = Bid::from_offset(offset).to_raw() as Ext2Bid;
         let buf_nblocks = write_len / BLOCK_SIZE;
+        // The blocks are fully overwritten, so there is no need to zero them.
+        self.inode_impl
+            .fill_holes(start_bid..start_bid + buf_nblocks as Ext2Bid, false)?;
         self.inode_impl
             .write_blocks(start_bid, buf_nblocks, reader)?;
 
@@ -1075,23 +1123,89 @@ impl InodeInner {
         Ok(String::from_utf8(symlink)?)
     }
 
+    /// Returns whether the byte `range` covers holes or uninitialized blocks.
+    pub fn has_holes(&self, range: Range<usize>) -> Result<bool> {
+        if range.is_empty() {
+            return Ok(false);
+        }
+        self.inode_impl.has_holes(bytes_to_blocks(range))
+    }
+
+    /// Allocates the blocks for the holes in the byte `range`, which is about to be written.
+    ///
+    /// The newly allocated blocks that are partially covered by the range are zeroed,
+    /// since their contents on the device are undefined.
+    pub fn fill_holes(&mut self, range: Range<usize>) -> Result<()> {
+        if range.is_empty() {
+            return Ok(());
+        }
+
+        let filled_ranges = self
+            .inode_impl
+            .fill_holes(bytes_to_blocks(range.clone()), false)?;
+        for filled_range in filled_ranges {
+            let start = filled_range.start as usize * BLOCK_SIZE;
+            let end = filled_range.end as usize * BLOCK_SIZE;
+            if start < range.start {
+                self.page_cache.pages().clear(start..start + BLOCK_SIZE)?;
+            }
+            if range.end < end {
+                self.page_cache.pages().clear(end - BLOCK_SIZE..end)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Preallocates the blocks for the byte `range`.
+    pub fn preallocate(&mut self, range: Range<usize>) -> Result<()> {
+        if range.is_empty() {
+            return Ok(());
+        }
+        self.inode_impl.preallocate(bytes_to_blocks(range))
+    }
+
+    /// Returns the configurations of the directory.
+    fn dir_config(&self) -> DirConfig {
+        let fs = self.inode_impl.fs();
+        let has_csum_tail = fs.csum_seed().is_some();
+        let super_block = fs.super_block();
+        DirConfig {
+            is_indexed: self.file_flags().contains(FileFlags::INDEX_DIR)
+                && super_block
+                    .feature_compat()
+                    .contains(FeatureCompatSet::DIR_INDEX),
+            has_csum_tail,
+            hash_seed: super_block.hash_seed(),
+            is_hash_unsigned: super_block.is_hash_unsigned(),
+        }
+    }
+
+    /// Clears the index flag if the hash tree is removed by the `DirEntryWriter`.
+    fn update_index_flag(&mut self, config: DirConfig, is_indexed: bool) {
+        if config.is_indexed && !is_indexed {
+            let flags = self.file_flags() - FileFlags::INDEX_DIR;
+            self.inode_impl.set_file_flags(flags);
+        }
+    }
+
     fn init_dir(&mut self, self_ino: u32, parent_ino: u32) -> Result<()> {
         debug_assert_eq!(self.inode_type(), InodeType::Dir);
-        DirEntryWriter::new(&self.page_cache, 0).init_dir(self_ino, parent_ino)?;
+        DirEntryWriter::new(&self.page_cache, 0, self.dir_config())
+            .init_dir(self_ino, parent_ino)?;
         self.inc_hard_links(); // for ".."
         Ok(())
     }
 
     pub fn contains_entry(&self, name: &str) -> bool {
-        DirEntryReader::new(&self.page_cache, 0).contains_entry(name)
+        DirEntryReader::new(&self.page_cache, 0, self.dir_config()).contains_entry(name)
     }
 
     pub fn find_entry_item(&self, name: &str) -> Option<DirEntryItem> {
-        DirEntryReader::new(&self.page_cache, 0).find_entry_item(name)
+        DirEntryReader::new(&self.page_cache, 0, self.dir_config()).find_entry_item(name)
     }
 
     pub fn entry_count(&self) -> usize {
-        DirEntryReader::new(&self.page_cache, 0).entry_count()
+        DirEntryReader::new(&self.page_cache, 0, self.dir_config()).entry_count()
     }
 
     pub fn append_new_entry(
@@ -1101,42 +1215,54 @@ impl InodeInner {
         name: &str,
         check_existence: bool,
     ) -> Result<()> {
+        let is_dir = inode_type == InodeType::Dir;
+        let is_parent = name == "..";
+        if is_dir && !is_parent {
+            self.inc_subdir_links()?; // for ".."
+        }
+
         let entry_header = DirEntryHeader::new(ino, inode_type, name.len());
-        DirEntryWriter::new(&self.page_cache, 0).append_new_entry(
-            entry_header,
-            name,
-            check_existence,
-        )?;
+        let config = self.dir_config();
+        let mut dir_entry_writer = DirEntryWriter::new(&self.page_cache, 0, config);
+        let res = dir_entry_writer.append_new_entry(entry_header, name, check_existence);
+        let is_indexed = dir_entry_writer.is_indexed();
+        self.update_index_flag(config, is_indexed);
+        if let Err(e) = res {
+            if is_dir && !is_parent {
+                self.dec_subdir_links();
+            }
+            return Err(e);
+        }
 
         let file_size = self.file_size();
         let page_cache_size = self.page_cache.pages().size();
         if page_cache_size > file_size {
             self.inode_impl.resize(page_cache_size)?;
         }
-
-        let is_dir = inode_type == InodeType::Dir;
-        let is_parent = name == "..";
-        if is_dir && !is_parent {
-            self.inc_hard_links(); // for ".."
-        }
         Ok(())
     }
 
     pub fn remove_entry_at(&mut self, name: &str, offset: usize) -> Result<()> {
-        let removed_entry = DirEntryWriter::new(&self.page_cache, offset).remove_entry(name)?;
+        let removed_entry =
+            DirEntryWriter::new(&self.page_cache, offset, self.dir_config()).remove_entry(name)?;
         let file_size = self.file_size();
         let page_cache_size = self.page_cache.pages().size();
         if page_cache_size < file_size {
             self.inode_impl.resize(page_cache_size)?;
         }
         if removed_entry.type_() == InodeType::Dir {
-            self.dec_hard_links(); // for ".."
+            self.dec_subdir_links(); // for ".."
         }
         Ok(())
     }
 
     pub fn rename_entry_at(&mut self, old_name: &str, new_name: &str, offset: usize) -> Result<()> {
-        DirEntryWriter::new(&self.page_cache, offset).rename_entry(old_name, new_name)?;
+        let config = self.dir_config();
+        let mut dir_entry_writer = DirEntryWriter::new(&self.page_cache, offset, config);
+        let res = dir_entry_writer.rename_entry(old_name, new_name);
+        let is_indexed = dir_entry_writer.is_indexed();
+        self.update_index_flag(config, is_indexed);
+        res?;
         let file_size = self.file_size();
         let page_cache_size = self.page_cache.pages().size();
         if page_cache_size != file_size {
@@ -1148,7 +1274,7 @@ impl InodeInner {
     pub fn set_parent_ino(&mut self, parent_ino: u32) -> Result<()> {
         let mut entry_item = self.find_entry_item("..").unwrap();
         entry_item.set_ino(parent_ino);
-        DirEntryWriter::new(&self.page_cache, entry_item.offset())
+        DirEntryWriter::new(&self.page_cache, entry_item.offset(), self.dir_config())
             .write_header_only(entry_item.header())?;
         Ok(())
     }
@@ -1172,10 +1298,14 @@ impl InodeInner {
     pub fn gid(&self) -> u32;
     pub fn set_gid(&mut self, gid: u32);
     pub fn file_flags(&self) -> FileFlags;
+    pub fn set_file_flags(&mut self, flags: FileFlags);
     pub fn hard_links(&self) -> u16;
     pub fn inc_hard_links(&mut self);
     pub fn dec_hard_links(&mut self);
-    pub fn blocks_count(&self) -> Ext2Bid;
+    pub fn inc_subdir_links(&mut self) -> Result<()>;
+    pub fn dec_subdir_links(&mut self);
+    pub fn clear_hard_links(&mut self);
+    pub fn allocated_blocks(&self) -> u64;
     pub fn acl(&self) -> Option<Bid>;
     pub fn set_acl(&mut self, bid: Bid);
     pub fn atime(&self) -> Duration;
@@ -1198,9 +1328,18 @@ struct InodeImpl {
 }
 
 impl InodeImpl {
-    pub fn new(desc: Dirty<InodeDesc>, weak_self: Weak<Inode>, fs: Weak<Ext2>) -> Self {
+    pub fn new(
+        desc: Dirty<InodeDesc>,
+        csum_seed: Option<u32>,
+        weak_self: Weak<Inode>,
+        fs: Weak<Ext2>,
+    ) -> Self {
         let block_manager = InodeBlockManager {
             nblocks: AtomicUsize::new(desc.blocks_count() as _),
+            has_metadata: matches!(desc.type_, InodeType::Dir | InodeType::SymLink),
+            is_dir: desc.type_ == InodeType::Dir,
+            uses_extents: desc.uses_extents(),
+            csum_seed,
             block_ptrs: RwMutex::new(desc.block_ptrs),
             indirect_blocks: RwMutex::new(IndirectBlockCache::new(fs.clone())),
             fs,
@@ -1258,6 +1397,10 @@ impl InodeImpl {
         self.desc.flags
     }
 
+    pub fn set_file_flags(&mut self, flags: FileFlags) {
+        self.desc.flags = flags;
+    }
+
     pub fn hard_links(&self) -> u16 {
         self.desc.hard_links
     }
@@ -1271,8 +1414,43 @@ impl InodeImpl {
         self.desc.hard_links -= 1;
     }
 
-    pub fn blocks_count(&self) -> Ext2Bid {
-        self.desc.blocks_count()
+    /// Increases the hard links of the directory for a new subdirectory.
+    ///
+    /// With the `DIR_NLINK` feature, the hard links are set to 1 once they reach
+    /// `MAX_LINKS`, meaning that the number of subdirectories is no longer tracked.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/namei.c#L2375>
+    pub fn inc_subdir_links(&mut self) -> Result<()> {
+        let has_dir_nlink = self.fs().has_dir_nlink();
+        if has_dir_nlink && self.desc.hard_links == 1 {
+            return Ok(());
+        }
+
+        if self.desc.hard_links >= MAX_LINKS {
+            if !has_dir_nlink {
+                return_errno_with_message!(Errno::EMLINK, "too many subdirectories");
+            }
+            self.desc.hard_links = 1;
+        } else {
+            self.desc.hard_links += 1;
+        }
+        Ok(())
+    }
+
+    /// Decreases the hard links of the directory for a removed subdirectory.
+    pub fn dec_subdir_links(&mut self) {
+        // The hard links of 1 indicates that the subdirectories are not tracked.
+        if self.desc.hard_links > 2 {
+            self.desc.hard_links -= 1;
+        }
+    }
+
+    pub fn clear_hard_links(&mut self) {
+        self.desc.hard_links = 0;
+    }
+
+    pub fn allocated_blocks(&self) -> u64 {
+        self.desc.allocated_blocks
     }
 
     pub fn acl(&self) -> Option<Bid> {
@@ -1280,6 +1458,11 @@ impl InodeImpl {
     }
 
     pub fn set_acl(&mut self, bid: Bid) {
+        // The xattr block is accounted as an allocated block of the inode.
+        let had_acl = matches!(self.desc.acl, Some(acl) if acl.to_raw() != 0);
+        if !had_acl && bid.to_raw() != 0 {
+            self.desc.allocated_blocks += 1;
+        }
         self.desc.acl = Some(bid);
     }
 
@@ -1372,7 +1555,7 @@ impl InodeImpl {
         if new_size > old_size {
             self.expand(new_size)?;
         } else {
-            self.shrink(new_size);
+            self.shrink(new_size)?;
         }
         Ok(())
     }
@@ -1381,16 +1564,28 @@ impl InodeImpl {
     ///
     /// After a successful expansion, the size will be enlarged to `new_size`,
     /// which may result in an increased block count.
+    ///
+    /// The blocks of a regular file using extents are not allocated here,
+    /// the file is left sparse until the blocks are written.
     fn expand(&mut self, new_size: usize) -> Result<()> {
         let new_blocks = self.desc.size_to_blocks(new_size);
         let old_blocks = self.desc.blocks_count();
 
         // Expands block count if necessary
-        if new_blocks > old_blocks {
+        if new_blocks > old_blocks
+            && !(self.desc.uses_extents() && self.desc.type_ == InodeType::File)
+        {
             if new_blocks - old_blocks > self.fs().super_block().free_blocks_count() {
                 return_errno_with_message!(Errno::ENOSPC, "not enough free blocks");
             }
-            self.expand_blocks(old_blocks..new_blocks)?;
+            if self.desc.uses_extents() {
+                if let Err(e) = self.fill_holes(old_blocks..new_blocks, false) {
+                    self.truncate_extents(old_blocks)?;
+                    return Err(e);
+                }
+            } else {
+                self.expand_blocks(old_blocks..new_blocks)?;
+            }
         }
 
         // Expands the size
@@ -1442,13 +1637,7 @@ impl InodeImpl {
             (max_cnt, indirect_cnt)
         };
 
-        // Calculates the block_group_idx to advise the filesystem on which group
-        // to prioritize for allocation.
-        let block_group_idx = self
-            .last_alloc_device_bid
-            .map_or(self.inode().block_group_idx, |id| {
-                ((id + 1) / self.fs().blocks_per_group()) as usize
-            });
+        let block_group_idx = self.goal_block_group_idx();
 
         // Allocates the blocks only, no indirect blocks are required.
         if indirect_cnt == 0 {
@@ -1460,7 +1649,7 @@ impl InodeImpl {
                 self.fs().free_blocks(device_range).unwrap();
                 return Err(e);
             }
-            self.desc.blocks_count = range.start + device_range.len() as Ext2Bid;
+            self.desc.allocated_blocks += device_range.len() as u64;
             self.last_alloc_device_bid = Some(device_range.end - 1);
             return Ok(device_range.len() as Ext2Bid);
         }
@@ -1506,11 +1695,22 @@ impl InodeImpl {
             return Err(e);
         }
 
-        self.desc.blocks_count = range.start + device_range.len() as Ext2Bid;
+        self.desc.allocated_blocks += (device_range.len() + indirect_bids.len()) as u64;
         self.last_alloc_device_bid = Some(device_range.end - 1);
         Ok(device_range.len() as Ext2Bid)
     }
 
+    /// Returns the index of the block group to allocate the blocks from.
+    ///
+    /// It prefers the group following the last allocated block, so that the
+    /// blocks of the inode are kept close to each other.
+    fn goal_block_group_idx(&self) -> usize {
+        self.last_alloc_device_bid
+            .map_or(self.inode().block_group_idx, |id| {
+                ((id + 1) / self.fs().blocks_per_group()) as usize
+            })
+    }
+
     /// Sets the device block IDs for a specified range.
     ///
     /// It updates the mapping between the file's block IDs and the device's block IDs
@@ -1638,17 +1838,21 @@ impl InodeImpl {
     ///
     /// After the reduction, the size will be shrunk to `new_size`,
     /// which may result in an decreased block count.
-    fn shrink(&mut self, new_size: usize) {
+    fn shrink(&mut self, new_size: usize) -> Result<()> {
         let new_blocks = self.desc.size_to_blocks(new_size);
         let old_blocks = self.desc.blocks_count();
 
         // Shrinks block count if necessary
-        if new_blocks < old_blocks {
+        if self.desc.uses_extents() {
+            // The blocks beyond the end of file, if any, are also freed.
+            self.truncate_extents(new_blocks)?;
+        } else if new_blocks < old_blocks {
             self.shrink_blocks(new_blocks..old_blocks);
         }
 
         // Shrinks the size
         self.update_size(new_size);
+        Ok(())
     }
 
     fn update_size(&mut self, new_size: usize) {
@@ -1668,17 +1872,17 @@ impl InodeImpl {
             current_range.end -= free_cnt;
         }
 
-        self.desc.blocks_count = range.start;
         self.last_alloc_device_bid = if range.start == 0 {
             None
         } else {
-            Some(
-                DeviceRangeReader::new(&self.block_manager, (range.start - 1)..range.start)
-                    .unwrap()
-                    .read()
-                    .unwrap()
-                    .start,
-            )
+            match DeviceRangeReader::new(&self.block_manager, (range.start - 1)..range.start)
+                .unwrap()
+                .read()
+                .unwrap()
+            {
+                BlockMapping::Mapped(device_range) => Some(device_range.start),
+                BlockMapping::Uninit(_) | BlockMapping::Hole(_) => None,
+            }
         };
     }
 
@@ -1696,49 +1900,56 @@ impl InodeImpl {
         };
 
         let fs = self.fs();
+        let mut freed_cnt = 0;
         let device_range_reader =
             DeviceRangeReader::new(&self.block_manager, range.clone()).unwrap();
-        for device_range in device_range_reader {
-            fs.free_blocks(device_range.clone()).unwrap();
+        for mapping in device_range_reader {
+            if let BlockMapping::Mapped(device_range) = mapping {
+                freed_cnt += device_range.len() as u64;
+                fs.free_blocks(device_range).unwrap();
+            }
         }
 
-        self.free_indirect_blocks_required_by(range.start).unwrap();
+        freed_cnt += self.free_indirect_blocks_required_by(range.start).unwrap();
+        self.desc.allocated_blocks = self.desc.allocated_blocks.saturating_sub(freed_cnt);
         range.len() as Ext2Bid
     }
 
     /// Frees the indirect blocks required by the specified block ID.
     ///
     /// It ensures that the indirect blocks that are required by the block ID
-    /// are properly released.
-    fn free_indirect_blocks_required_by(&mut self, bid: Ext2Bid) -> Result<()> {
+    /// are properly released. Returns the number of the freed indirect blocks.
+    fn free_indirect_blocks_required_by(&mut self, bid: Ext2Bid) -> Result<u64> {
         let bid_path = BidPath::from(bid);
         if bid_path.last_lvl_idx() != 0 {
-            return Ok(());
+            return Ok(0);
         }
         if bid == 0 {
-            return Ok(());
+            return Ok(0);
         }
 
         let mut block_ptrs = self.block_manager.block_ptrs.write();
         let mut indirect_blocks = self.block_manager.indirect_blocks.write();
+        let mut freed_cnt = 0;
         match bid_path {
             BidPath::Indirect(_) => {
                 let indirect_bid = self.desc.block_ptrs.indirect();
                 if indirect_bid == 0 {
-                    return Ok(());
+                    return Ok(0);
                 }
 
-                self.desc.block_ptrs.set_indirect(bid);
-                block_ptrs.set_indirect(bid);
+                self.desc.block_ptrs.set_indirect(0);
+                block_ptrs.set_indirect(0);
                 indirect_blocks.remove(indirect_bid);
                 self.fs()
                     .free_blocks(indirect_bid..indirect_bid + 1)
                     .unwrap();
+                freed_cnt += 1;
             }
             BidPath::DbIndirect(lvl1_idx, _) => {
                 let db_indirect_bid = self.desc.block_ptrs.db_indirect();
                 if db_indirect_bid == 0 {
-                    return Ok(());
+                    return Ok(0);
                 }
 
                 let fs = self.fs();
@@ -1750,6 +1961,7 @@ impl InodeImpl {
                     indirect_blocks.remove(lvl1_indirect_bid);
                     fs.free_blocks(lvl1_indirect_bid..lvl1_indirect_bid + 1)
                         .unwrap();
+                    freed_cnt += 1;
                 }
                 if lvl1_idx == 0 {
                     self.desc.block_ptrs.set_db_indirect(0);
@@ -1757,12 +1969,13 @@ impl InodeImpl {
                     indirect_blocks.remove(db_indirect_bid);
                     fs.free_blocks(db_indirect_bid..db_indirect_bid + 1)
                         .unwrap();
+                    freed_cnt += 1;
                 }
             }
             BidPath::TbIndirect(lvl1_idx, lvl2_idx, _) => {
                 let tb_indirect_bid = self.desc.block_ptrs.tb_indirect();
                 if tb_indirect_bid == 0 {
-                    return Ok(());
+                    return Ok(0);
                 }
 
                 let fs = self.fs();
@@ -1779,11 +1992,13 @@ impl InodeImpl {
                         indirect_blocks.remove(lvl2_indirect_bid);
                         fs.free_blocks(lvl2_indirect_bid..lvl2_indirect_bid + 1)
                             .unwrap();
+                        freed_cnt += 1;
                     }
                     if lvl2_idx == 0 {
                         indirect_blocks.remove(lvl1_indirect_bid);
                         fs.free_blocks(lvl1_indirect_bid..lvl1_indirect_bid + 1)
                             .unwrap();
+                        freed_cnt += 1;
                     }
                 }
 
@@ -1793,13 +2008,149 @@ impl InodeImpl {
                     indirect_blocks.remove(tb_indirect_bid);
                     fs.free_blocks(tb_indirect_bid..tb_indirect_bid + 1)
                         .unwrap();
+                    freed_cnt += 1;
                 }
             }
             BidPath::Direct(_) => panic!(),
         }
 
+        Ok(freed_cnt)
+    }
+}
+
+// Implementation for holes and extents.
+impl InodeImpl {
+    /// Returns whether the block `range` covers holes or uninitialized blocks.
+    pub fn has_holes(&self, range: Range<Ext2Bid>) -> Result<bool> {
+        let range = range.start..range.end.min(self.desc.blocks_count());
+        if range.is_empty() {
+            return Ok(false);
+        }
+
+        let mut device_range_reader = DeviceRangeReader::new(&self.block_manager, range)?;
+        while !device_range_reader.is_empty() {
+            if !matches!(device_range_reader.read()?, BlockMapping::Mapped(_)) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Allocates the blocks for the holes in the block `range`.
+    ///
+    /// If `uninit` is true, the new blocks are allocated as uninitialized ones.
+    /// Otherwise, the uninitialized blocks in the range are also marked as initialized.
+    ///
+    /// Returns the ranges of the blocks whose contents become undefined,
+    /// i.e., the newly allocated or initialized blocks.
+    pub fn fill_holes(
+        &mut self,
+        range: Range<Ext2Bid>,
+        uninit: bool,
+    ) -> Result<Vec<Range<Ext2Bid>>> {
+        if !self.desc.uses_extents() {
+            // The blocks within the file size are always allocated, except for
+            // the sparse files created by others.
+            if self.has_holes(range)? {
+                return_errno_with_message!(
+                    Errno::EOPNOTSUPP,
+                    "writing into holes is not supported without extents"
+                );
+            }
+            return Ok(Vec::new());
+        }
+
+        let mut filled_ranges = Vec::new();
+        let mut bid = range.start;
+        while bid < range.end {
+            let mapping = {
+                let mut indirect_blocks = self.block_manager.indirect_blocks.write();
+                extent::lookup(&self.desc.block_ptrs, &mut indirect_blocks, bid)?
+                    .truncate(range.end - bid)
+            };
+            let len = match mapping {
+                BlockMapping::Mapped(device_range) => device_range.len() as Ext2Bid,
+                BlockMapping::Uninit(device_range) => {
+                    let len = device_range.len() as Ext2Bid;
+                    if !uninit {
+                        let tree_cnt =
+                            self.update_extents(|tree| tree.mark_initialized(bid..bid + len))?;
+                        self.desc.allocated_blocks += tree_cnt as u64;
+                        filled_ranges.push(bid..bid + len);
+                    }
+                    len
+                }
+                BlockMapping::Hole(len) => {
+                    let len = len.min(extent::max_extent_len(uninit));
+                    let device_range = self
+                        .fs()
+                        .alloc_blocks(self.goal_block_group_idx(), len)
+                        .ok_or_else(|| Error::new(Errno::ENOSPC))?;
+                    let len = device_range.len() as Ext2Bid;
+                    let tree_cnt = match self
+                        .update_extents(|tree| tree.insert(bid, len, device_range.start, uninit))
+                    {
+                        Ok(tree_cnt) => tree_cnt,
+                        Err(e) => {
+                            self.fs().free_blocks(device_range).unwrap();
+                            return Err(e);
+                        }
+                    };
+                    self.desc.allocated_blocks += (len + tree_cnt) as u64;
+                    self.last_alloc_device_bid = Some(device_range.end - 1);
+                    filled_ranges.push(bid..bid + len);
+                    len
+                }
+            };
+            bid += len;
+        }
+
+        Ok(filled_ranges)
+    }
+
+    /// Preallocates the blocks in the block `range` as uninitialized ones.
+    ///
+    /// It does nothing if the inode does not use extents, whose blocks are
+    /// always allocated on expansion.
+    pub fn preallocate(&mut self, range: Range<Ext2Bid>) -> Result<()> {
+        if !self.desc.uses_extents() {
+            return Ok(());
+        }
+        self.fill_holes(range, true)?;
         Ok(())
     }
+
+    /// Frees the extent-mapped blocks starting from `bid`.
+    fn truncate_extents(&mut self, bid: Ext2Bid) -> Result<()> {
+        let freed_cnt = self.update_extents(|tree| tree.truncate(bid))?;
+        if freed_cnt > 0 {
+            self.desc.allocated_blocks = self.desc.allocated_blocks.saturating_sub(freed_cnt);
+            self.last_alloc_device_bid = None;
+        }
+        Ok(())
+    }
+
+    /// Updates the extent tree with `op`.
+    ///
+    /// Both copies of the block pointers are updated, even if `op` fails halfway.
+    fn update_extents<T>(
+        &mut self,
+        op: impl FnOnce(&mut ExtentTree<'_>) -> Result<T>,
+    ) -> Result<T> {
+        let fs = self.fs();
+        let goal_group = self.goal_block_group_idx();
+        let mut block_ptrs = self.block_manager.block_ptrs.write();
+        let mut indirect_blocks = self.block_manager.indirect_blocks.write();
+        let res = op(&mut ExtentTree::new(
+            &mut block_ptrs,
+            &mut indirect_blocks,
+            &fs,
+            self.block_manager.csum_seed,
+            goal_group,
+        ));
+        self.desc.block_ptrs = *block_ptrs;
+        res
+    }
 }
 
 #[inherit_methods(from = "self.block_manager")]
@@ -1832,11 +2183,22 @@ struct InodeBlockManager {
     /// frequent reads access the `InodeDesc` copy without locking.
     block_ptrs: RwMutex<BlockPtrs>,
     indirect_blocks: RwMutex<IndirectBlockCache>,
+    /// Whether the blocks hold metadata (i.e., directory entries or symlink targets),
+    /// which are written through the journal if any.
+    has_metadata: bool,
+    /// Whether the blocks hold directory entries, whose checksums are updated on writes.
+    is_dir: bool,
+    /// Whether the blocks are mapped by an extent tree.
+    uses_extents: bool,
+    /// The checksum seed of the inode, if the filesystem has metadata checksums.
+    csum_seed: Option<u32>,
     fs: Weak<Ext2>,
 }
 
 impl InodeBlockManager {
     /// Reads one or multiple blocks to the segment start from `bid` asynchronously.
+    ///
+    /// The holes and the uninitialized blocks are read as zeros.
     pub fn read_blocks_async(
         &self,
         bid: Ext2Bid,
@@ -1846,7 +2208,11 @@ impl InodeBlockManager {
         debug_assert!(nblocks * BLOCK_SIZE <= writer.avail());
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + nblocks as Ext2Bid)? {
+        for mapping in DeviceRangeReader::new(self, bid..bid + nblocks as Ext2Bid)? {
+            let BlockMapping::Mapped(dev_range) = mapping else {
+                writer.fill_zeros(mapping.len() as usize * BLOCK_SIZE);
+                continue;
+            };
             let start_bid = dev_range.start as Ext2Bid;
             let range_nblocks = dev_range.len();
 
@@ -1870,7 +2236,11 @@ impl InodeBlockManager {
     pub fn read_block_async(&self, bid: Ext2Bid, frame: &CachePage) -> Result<BioWaiter> {
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + 1 as Ext2Bid)? {
+        for mapping in DeviceRangeReader::new(self, bid..bid + 1 as Ext2Bid)? {
+            let BlockMapping::Mapped(dev_range) = mapping else {
+                frame.writer().fill_zeros(BLOCK_SIZE);
+                continue;
+            };
             let start_bid = dev_range.start as Ext2Bid;
             // TODO: Should we allocate the bio segment from the pool on reads?
             // This may require an additional copy to the requested frame in the completion callback.
@@ -1886,6 +2256,8 @@ impl InodeBlockManager {
     }
 
     /// Writes one or multiple blocks from the segment start from `bid` asynchronously.
+    ///
+    /// The blocks must have been allocated and initialized.
     pub fn write_blocks_async(
         &self,
         bid: Ext2Bid,
@@ -1895,14 +2267,17 @@ impl InodeBlockManager {
         debug_assert_eq!(nblocks * BLOCK_SIZE, reader.remain());
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + nblocks as Ext2Bid)? {
+        for mapping in DeviceRangeReader::new(self, bid..bid + nblocks as Ext2Bid)? {
+            let BlockMapping::Mapped(dev_range) = mapping else {
+                return_errno_with_message!(Errno::EIO, "the blocks to write are not allocated");
+            };
             let start_bid = dev_range.start as Ext2Bid;
             let range_nblocks = dev_range.len();
 
             let bio_segment = BioSegment::alloc(range_nblocks, BioDirection::ToDevice);
             bio_segment.writer().unwrap().write_fallible(reader)?;
 
-            let waiter = self.fs().write_blocks_async(start_bid, bio_segment)?;
+            let waiter = self.write_device_blocks_async(start_bid, bio_segment)?;
             bio_waiter.concat(waiter);
         }
 
@@ -1919,7 +2294,19 @@ impl InodeBlockManager {
     pub fn write_block_async(&self, bid: Ext2Bid, frame: &CachePage) -> Result<BioWaiter> {
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + 1 as Ext2Bid)? {
+        for mapping in DeviceRangeReader::new(self, bid..bid + 1 as Ext2Bid)? {
+            let BlockMapping::Mapped(dev_range) = mapping else {
+                // The pages of holes are zeroed by, e.g., punching holes, which
+                // need not be written.
+                if is_zeroed_page(frame)? {
+                    continue;
+                }
+                warn!(
+                    "ext2: the dirty page of the unallocated block {} is not written",
+                    bid
+                );
+                return_errno_with_message!(Errno::EIO, "the block to write is not allocated");
+            };
             let start_bid = dev_range.start as Ext2Bid;
             let bio_segment = BioSegment::alloc(1, BioDirection::ToDevice);
             // This requires an additional copy to the pooled bio segment.
@@ -1927,13 +2314,32 @@ impl InodeBlockManager {
                 .writer()
                 .unwrap()
                 .write_fallible(&mut frame.reader().to_fallible())?;
-            let waiter = self.fs().write_blocks_async(start_bid, bio_segment)?;
+            let waiter = self.write_device_blocks_async(start_bid, bio_segment)?;
             bio_waiter.concat(waiter);
         }
 
         Ok(bio_waiter)
     }
 
+    fn write_device_blocks_async(
+        &self,
+        start_bid: Ext2Bid,
+        bio_segment: BioSegment,
+    ) -> Result<BioWaiter> {
+        if self.is_dir {
+            if let Some(csum_seed) = self.csum_seed {
+                update_dir_block_checksums(&bio_segment, csum_seed);
+            }
+        }
+
+        if self.has_metadata {
+            self.fs()
+                .write_metadata_blocks_async(start_bid, bio_segment)
+        } else {
+            self.fs().write_blocks_async(start_bid, bio_segment)
+        }
+    }
+
     pub fn nblocks(&self) -> usize {
         self.nblocks.load(Ordering::Acquire)
     }
@@ -1943,6 +2349,29 @@ impl InodeBlockManager {
     }
 }
 
+/// Returns whether the page is filled with zeros.
+fn is_zeroed_page(frame: &CachePage) -> Result<bool> {
+    let mut buf = vec![0u8; BLOCK_SIZE];
+    frame.read_bytes(0, &mut buf)?;
+    Ok(buf.iter().all(|byte| *byte == 0))
+}
+
+/// Updates the checksums of the directory blocks in the bio segment.
+fn update_dir_block_checksums(bio_segment: &BioSegment, csum_seed: u32) {
+    let mut buf = vec![0u8; bio_segment.nbytes()];
+    bio_segment
+        .reader()
+        .unwrap()
+        .read(&mut VmWriter::from(buf.as_mut_slice()));
+    for block in buf.chunks_exact_mut(BLOCK_SIZE) {
+        dir::update_block_checksum(block, csum_seed);
+    }
+    bio_segment
+        .writer()
+        .unwrap()
+        .write(&mut VmReader::from(buf.as_slice()));
+}
+
 impl PageCacheBackend for InodeBlockManager {
     fn read_page_async(&self, idx: usize, frame: &CachePage) -> Result<BioWaiter> {
         let bid = idx as Ext2Bid;
@@ -1961,14 +2390,25 @@ impl PageCacheBackend for InodeBlockManager {
 
 /// A reader to get the corresponding device block IDs for a specified range.
 ///
-/// It calculates and returns the range of block IDs on the device that would map to
-/// the file's block range. This is useful for translating file-level block addresses
-/// to their locations on the physical storage device.
+/// It calculates and returns the mappings from the file's block range to the block IDs
+/// on the device. This is useful for translating file-level block addresses to their
+/// locations on the physical storage device.
 struct DeviceRangeReader<'a> {
     block_ptrs: RwMutexReadGuard<'a, BlockPtrs>,
     indirect_blocks: RwMutexWriteGuard<'a, IndirectBlockCache>,
     range: Range<Ext2Bid>,
-    indirect_block: Option<IndirectBlock>,
+    uses_extents: bool,
+    bid_source: BidSource,
+}
+
+/// The source to read the device block IDs from, if the blocks are not mapped by extents.
+enum BidSource {
+    /// The direct block pointers.
+    Direct,
+    /// The block IDs in an indirect block.
+    Indirect(IndirectBlock),
+    /// The indirect block is absent, so all the blocks it covers are holes.
+    Hole,
 }
 
 impl<'a> DeviceRangeReader<'a> {
@@ -1984,17 +2424,36 @@ impl<'a> DeviceRangeReader<'a> {
             block_ptrs: block_manager.block_ptrs.read(),
             indirect_blocks: block_manager.indirect_blocks.write(),
             range,
-            indirect_block: None,
+            uses_extents: block_manager.uses_extents,
+            bid_source: BidSource::Direct,
         };
-        reader.update_indirect_block()?;
+        if !reader.uses_extents {
+            reader.update_bid_source()?;
+        }
         Ok(reader)
     }
 
-    /// Reads the corresponding device block IDs for a specified range.
+    /// Returns whether all the blocks in the range have been read.
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// Reads the mapping of the blocks at the start of the remaining range.
     ///
-    /// Note that the returned device range size may be smaller than the requested range
+    /// Note that the returned mapping may be smaller than the requested range
     /// due to possible inconsecutive block allocation.
-    pub fn read(&mut self) -> Result<Range<Ext2Bid>> {
+    pub fn read(&mut self) -> Result<BlockMapping> {
+        if self.uses_extents {
+            let mapping = extent::lookup(
+                &self.block_ptrs,
+                &mut self.indirect_blocks,
+                self.range.start,
+            )?
+            .truncate(self.range.len() as Ext2Bid);
+            self.range.start += mapping.len();
+            return Ok(mapping);
+        }
+
         let bid_path = BidPath::from(self.range.start);
         let max_cnt = self
             .range
@@ -2002,87 +2461,93 @@ impl<'a> DeviceRangeReader<'a> {
             .min(bid_path.cnt_to_next_indirect() as usize);
         let start_idx = bid_path.last_lvl_idx();
 
-        // Reads the device block ID range
-        let mut device_range: Option<Range<Ext2Bid>> = None;
-        for i in start_idx..start_idx + max_cnt {
-            let device_bid = match &self.indirect_block {
-                None => self.block_ptrs.direct(i),
-                Some(indirect_block) => indirect_block.read_bid(i)?,
+        // Reads the device block ID range, in which the zero IDs indicate a hole.
+        let first_bid = self.read_bid(start_idx)?;
+        let mut cnt = 1;
+        while cnt < max_cnt {
+            let device_bid = self.read_bid(start_idx + cnt)?;
+            let is_consecutive = if first_bid == 0 {
+                device_bid == 0
+            } else {
+                device_bid == first_bid + cnt as Ext2Bid
             };
-            match device_range {
-                Some(ref mut range) => {
-                    if device_bid == range.end {
-                        range.end += 1;
-                    } else {
-                        break;
-                    }
-                }
-                None => {
-                    device_range = Some(device_bid..device_bid + 1);
-                }
+            if !is_consecutive {
+                break;
             }
+            cnt += 1;
         }
-        let device_range = device_range.unwrap();
 
         // Updates the range
-        self.range.start += device_range.len() as Ext2Bid;
-        if device_range.len() == max_cnt {
+        self.range.start += cnt as Ext2Bid;
+        if cnt == max_cnt && !self.range.is_empty() {
             // Updates the indirect block
-            self.update_indirect_block()?;
+            self.update_bid_source()?;
         }
 
-        Ok(device_range)
+        Ok(if first_bid == 0 {
+            BlockMapping::Hole(cnt as Ext2Bid)
+        } else {
+            BlockMapping::Mapped(first_bid..first_bid + cnt as Ext2Bid)
+        })
     }
 
-    fn update_indirect_block(&mut self) -> Result<()> {
+    fn read_bid(&self, idx: usize) -> Result<Ext2Bid> {
+        match &self.bid_source {
+            BidSource::Direct => Ok(self.block_ptrs.direct(idx)),
+            BidSource::Indirect(indirect_block) => indirect_block.read_bid(idx),
+            BidSource::Hole => Ok(0),
+        }
+    }
+
+    fn update_bid_source(&mut self) -> Result<()> {
         let bid_path = BidPath::from(self.range.start);
-        match bid_path {
+        let indirect_bid = match bid_path {
             BidPath::Direct(_) => {
-                self.indirect_block = None;
-            }
-            BidPath::Indirect(_) => {
-                let indirect_bid = self.block_ptrs.indirect();
-                let indirect_block = self.indirect_blocks.find(indirect_bid)?;
-                self.indirect_block = Some(indirect_block.clone());
+                self.bid_source = BidSource::Direct;
+                return Ok(());
             }
+            BidPath::Indirect(_) => self.block_ptrs.indirect(),
             BidPath::DbIndirect(lvl1_idx, _) => {
-                let lvl1_indirect_bid = {
-                    let db_indirect_block =
-                        self.indirect_blocks.find(self.block_ptrs.db_indirect())?;
-                    db_indirect_block.read_bid(lvl1_idx as usize)?
-                };
-                let lvl1_indirect_block = self.indirect_blocks.find(lvl1_indirect_bid)?;
-                self.indirect_block = Some(lvl1_indirect_block.clone())
+                let db_indirect_bid = self.block_ptrs.db_indirect();
+                self.read_indirect_bid(db_indirect_bid, lvl1_idx)?
             }
             BidPath::TbIndirect(lvl1_idx, lvl2_idx, _) => {
-                let lvl2_indirect_bid = {
-                    let lvl1_indirect_bid = {
-                        let tb_indirect_block =
-                            self.indirect_blocks.find(self.block_ptrs.tb_indirect())?;
-                        tb_indirect_block.read_bid(lvl1_idx as usize)?
-                    };
-                    let lvl1_indirect_block = self.indirect_blocks.find(lvl1_indirect_bid)?;
-                    lvl1_indirect_block.read_bid(lvl2_idx as usize)?
-                };
-                let lvl2_indirect_block = self.indirect_blocks.find(lvl2_indirect_bid)?;
-                self.indirect_block = Some(lvl2_indirect_block.clone())
+                let tb_indirect_bid = self.block_ptrs.tb_indirect();
+                let lvl1_indirect_bid = self.read_indirect_bid(tb_indirect_bid, lvl1_idx)?;
+                self.read_indirect_bid(lvl1_indirect_bid, lvl2_idx)?
             }
-        }
+        };
 
+        self.bid_source = if indirect_bid == 0 {
+            BidSource::Hole
+        } else {
+            BidSource::Indirect(self.indirect_blocks.find(indirect_bid)?.clone())
+        };
         Ok(())
     }
+
+    /// Reads the block ID at `idx` in the indirect block, which is zero if
+    /// the indirect block is absent.
+    fn read_indirect_bid(&mut self, indirect_bid: Ext2Bid, idx: u16) -> Result<Ext2Bid> {
+        if indirect_bid == 0 {
+            return Ok(0);
+        }
+        self.indirect_blocks
+            .find(indirect_bid)?
+            .read_bid(idx as usize)
+    }
 }
 
 impl Iterator for DeviceRangeReader<'_> {
-    type Item = Range<Ext2Bid>;
+    type Item = BlockMapping;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.range.is_empty() {
             return None;
         }
 
-        let range = self.read().unwrap();
-        Some(range)
+        let mapping = self.read().unwrap();
+        Some(mapping)
     }
 }
 
@@ -2113,55 +2578,40 @@ pub(super) struct InodeDesc {
     mtime: Duration,
     /// Deletion time.
     dtime: Duration,
+    /// Creation time.
+    crtime: Duration,
     /// Hard links count.
     hard_links: u16,
-    /// Number of blocks.
-    blocks_count: Ext2Bid,
+    /// Number of blocks allocated to the inode, including the blocks holding the
+    /// metadata (e.g., the indirect blocks, the extent tree blocks and the xattr block).
+    allocated_blocks: u64,
     /// File flags.
     flags: FileFlags,
-    /// Pointers to blocks.
+    /// Pointers to blocks, or the root of the extent tree.
     block_ptrs: BlockPtrs,
     /// File or directory acl block.
     acl: Option<Bid>,
-}
-
-impl TryFrom<RawInode> for InodeDesc {
-    type Error = crate::error::Error;
-
-    fn try_from(inode: RawInode) -> Result<Self> {
-        let inode_type = InodeType::from_raw_mode(inode.mode)?;
-        Ok(Self {
-            type_: inode_type,
-            perm: FilePerm::from_raw_mode(inode.mode)?,
-            uid: ((inode.os_dependent_2.uid_high as u32) << 16) | inode.uid as u32,
-            gid: ((inode.os_dependent_2.gid_high as u32) << 16) | inode.gid as u32,
-            size: if inode_type == InodeType::File {
-                ((inode.size_high as usize) << 32) | inode.size_low as usize
-            } else {
-                inode.size_low as usize
-            },
-            atime: Duration::from(inode.atime),
-            ctime: Duration::from(inode.ctime),
-            mtime: Duration::from(inode.mtime),
-            dtime: Duration::from(inode.dtime),
-            hard_links: inode.hard_links,
-            blocks_count: inode.blocks_count,
-            flags: FileFlags::from_bits(inode.flags)
-                .ok_or(Error::with_message(Errno::EINVAL, "invalid file flags"))?,
-            block_ptrs: inode.block_ptrs,
-            acl: match inode_type {
-                InodeType::File => Some(Bid::new(inode.file_acl as _)),
-                InodeType::Dir => Some(Bid::new(inode.size_high as _)),
-                _ => None,
-            },
-        })
-    }
+    /// File version (for NFS), which also contributes to the checksum seed of the inode.
+    generation: u32,
+    /// Size of the extra fields beyond the original 128-byte inode.
+    extra_isize: u16,
 }
 
 impl InodeDesc {
-    pub fn new(type_: InodeType, perm: FilePerm) -> Dirty<Self> {
+    pub fn new(type_: InodeType, perm: FilePerm, fs: &Ext2) -> Dirty<Self> {
         let now = now();
         let credentials = current_thread!().as_posix_thread().unwrap().credentials();
+
+        let uses_extents = fs.has_extents() && matches!(type_, InodeType::File | InodeType::Dir);
+        let mut block_ptrs = BlockPtrs::default();
+        if uses_extents {
+            extent::init_root(&mut block_ptrs);
+        }
+        let extra_isize = fs
+            .super_block()
+            .want_extra_isize()
+            .min(fs.inode_size() - size_of::<RawInode>());
+
         Dirty::new_dirty(Self {
             type_,
             perm,
@@ -2172,28 +2622,183 @@ impl InodeDesc {
             ctime: now,
             mtime: now,
             dtime: Duration::ZERO,
+            crtime: now,
             hard_links: 1,
-            blocks_count: 0,
-            flags: FileFlags::empty(),
-            block_ptrs: BlockPtrs::default(),
+            allocated_blocks: 0,
+            flags: if uses_extents {
+                FileFlags::EXTENTS
+            } else {
+                FileFlags::empty()
+            },
+            block_ptrs,
             acl: match type_ {
                 InodeType::File | InodeType::Dir => Some(Bid::new(0)),
                 _ => None,
             },
+            generation: 0,
+            extra_isize: extra_isize as u16,
         })
     }
 
+    /// Parses the on-disk inode.
+    ///
+    /// The `slot` holds the whole on-disk inode, whose size is the inode size of the
+    /// filesystem. The checksum is verified if the filesystem has metadata checksums.
+    pub fn from_raw(slot: &[u8], ino: u32, fs: &Ext2) -> Result<Self> {
+        let inode = RawInode::from_bytes(slot);
+        let inode_type = InodeType::from_raw_mode(inode.mode)?;
+
+        let (extra_isize, extra) = {
+            let extra_slot = &slot[size_of::<RawInode>()..];
+            let extra_isize = match extra_slot {
+                [lo, hi, ..] => u16::from_le_bytes([*lo, *hi]),
+                _ => 0,
+            };
+            if extra_isize as usize > extra_slot.len() || extra_isize % 4 != 0 {
+                return_errno_with_message!(Errno::EUCLEAN, "invalid extra inode size");
+            }
+
+            // The fields beyond `extra_isize` are absent and treated as zeros.
+            let mut extra = RawInodeExtra::default();
+            let len = (extra_isize as usize).min(size_of::<RawInodeExtra>());
+            extra.as_bytes_mut()[..len].copy_from_slice(&extra_slot[..len]);
+            (extra_isize, extra)
+        };
+
+        if let Some(csum_seed) = fs.inode_csum_seed(ino, inode.generation) {
+            let has_checksum_hi = extra_isize as usize >= CHECKSUM_HI_END;
+            let checksum_lo = inode.os_dependent_2.checksum_lo as u32;
+            let (checksum, expected) = if has_checksum_hi {
+                (
+                    inode_checksum(slot, csum_seed, true),
+                    ((extra.checksum_hi as u32) << 16) | checksum_lo,
+                )
+            } else {
+                (inode_checksum(slot, csum_seed, false) & 0xffff, checksum_lo)
+            };
+            if checksum != expected {
+                return_errno_with_message!(Errno::EBADMSG, "bad inode checksum");
+            }
+        }
+
+        let flags = FileFlags::from_bits(inode.flags)
+            .ok_or(Error::with_message(Errno::EINVAL, "invalid file flags"))?;
+        let allocated_blocks = if fs.has_huge_file() {
+            let blocks =
+                ((inode.os_dependent_2.blocks_high as u64) << 32) | inode.blocks_count as u64;
+            if flags.contains(FileFlags::HUGE_FILE) {
+                blocks
+            } else {
+                blocks / SECTORS_PER_BLOCK
+            }
+        } else {
+            inode.blocks_count as u64 / SECTORS_PER_BLOCK
+        };
+        let acl = ((inode.os_dependent_2.file_acl_high as u64) << 32) | inode.file_acl as u64;
+
+        Ok(Self {
+            type_: inode_type,
+            perm: FilePerm::from_raw_mode(inode.mode)?,
+            uid: ((inode.os_dependent_2.uid_high as u32) << 16) | inode.uid as u32,
+            gid: ((inode.os_dependent_2.gid_high as u32) << 16) | inode.gid as u32,
+            size: ((inode.size_high as usize) << 32) | inode.size_low as usize,
+            atime: decode_time(inode.atime, extra.atime_extra),
+            ctime: decode_time(inode.ctime, extra.ctime_extra),
+            mtime: decode_time(inode.mtime, extra.mtime_extra),
+            dtime: Duration::from_secs(inode.dtime as u64),
+            crtime: decode_time(extra.crtime, extra.crtime_extra),
+            hard_links: inode.hard_links,
+            allocated_blocks,
+            flags: flags - FileFlags::HUGE_FILE,
+            block_ptrs: inode.block_ptrs,
+            acl: match inode_type {
+                InodeType::File | InodeType::Dir => Some(Bid::new(acl)),
+                _ => None,
+            },
+            generation: inode.generation,
+            extra_isize,
+        })
+    }
+
+    /// Writes the inode to the on-disk inode `slot`.
+    ///
+    /// The fields unknown to the descriptor (e.g., the in-inode extended attributes)
+    /// are preserved. The checksum is updated if `csum_seed` is provided.
+    pub fn write_raw(&self, slot: &mut [u8], csum_seed: Option<u32>) {
+        let (atime, atime_extra) = encode_time(self.atime);
+        let (ctime, ctime_extra) = encode_time(self.ctime);
+        let (mtime, mtime_extra) = encode_time(self.mtime);
+        let (crtime, crtime_extra) = encode_time(self.crtime);
+        let sectors = self.allocated_blocks * SECTORS_PER_BLOCK;
+
+        let mut inode = RawInode::from_bytes(slot);
+        inode.mode = self.type_ as u16 | self.perm.bits();
+        inode.uid = self.uid as u16;
+        inode.size_low = self.size as u32;
+        inode.size_high = (self.size >> 32) as u32;
+        inode.atime = atime;
+        inode.ctime = ctime;
+        inode.mtime = mtime;
+        inode.dtime = self.dtime.as_secs() as u32;
+        inode.gid = self.gid as u16;
+        inode.hard_links = self.hard_links;
+        inode.blocks_count = sectors as u32;
+        inode.flags = self.flags.bits();
+        inode.block_ptrs = self.block_ptrs;
+        inode.generation = self.generation;
+        if let Some(acl) = self.acl {
+            inode.file_acl = acl.to_raw() as u32;
+            inode.os_dependent_2.file_acl_high = (acl.to_raw() >> 32) as u16;
+        }
+        inode.os_dependent_2.blocks_high = (sectors >> 32) as u16;
+        inode.os_dependent_2.uid_high = (self.uid >> 16) as u16;
+        inode.os_dependent_2.gid_high = (self.gid >> 16) as u16;
+        slot[..size_of::<RawInode>()].copy_from_slice(inode.as_bytes());
+
+        let extra_len = (self.extra_isize as usize).min(size_of::<RawInodeExtra>());
+        if extra_len > 0 {
+            let extra_slot = &mut slot[size_of::<RawInode>()..][..extra_len];
+            let mut extra = RawInodeExtra::default();
+            extra.as_bytes_mut()[..extra_len].copy_from_slice(extra_slot);
+            extra.extra_isize = self.extra_isize;
+            extra.ctime_extra = ctime_extra;
+            extra.mtime_extra = mtime_extra;
+            extra.atime_extra = atime_extra;
+            extra.crtime = crtime;
+            extra.crtime_extra = crtime_extra;
+            extra_slot.copy_from_slice(&extra.as_bytes()[..extra_len]);
+        }
+
+        if let Some(csum_seed) = csum_seed {
+            let has_checksum_hi = self.extra_isize as usize >= CHECKSUM_HI_END;
+            let checksum = inode_checksum(slot, csum_seed, has_checksum_hi);
+            slot[CHECKSUM_LO_OFFSET..CHECKSUM_LO_OFFSET + 2]
+                .copy_from_slice(&(checksum as u16).to_le_bytes());
+            if has_checksum_hi {
+                slot[CHECKSUM_HI_OFFSET..CHECKSUM_HI_OFFSET + 2]
+                    .copy_from_slice(&((checksum >> 16) as u16).to_le_bytes());
+            }
+        }
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Returns whether the blocks are mapped by an extent tree.
+    pub fn uses_extents(&self) -> bool {
+        self.flags.contains(FileFlags::EXTENTS)
+    }
+
     pub fn num_page_bytes(&self) -> usize {
         (self.blocks_count() as usize) * BLOCK_SIZE
     }
 
-    /// Returns the actual number of blocks utilized.
+    /// Returns the number of blocks covered by the file size.
     ///
-    /// Ext2 allows the `block_count` to exceed the actual number of blocks utilized.
+    /// Some of the blocks may be holes, and the metadata blocks are not counted.
     pub fn blocks_count(&self) -> Ext2Bid {
-        let blocks = self.size_to_blocks(self.size);
-        assert!(blocks <= self.blocks_count);
-        blocks
+        self.size_to_blocks(self.size)
     }
 
     fn size_to_blocks(&self, size: usize) -> Ext2Bid {
@@ -2204,6 +2809,57 @@ impl InodeDesc {
     }
 }
 
+/// The number of bits in the extra timestamp fields that extend the seconds.
+const EPOCH_BITS: u32 = 2;
+const EPOCH_MASK: u32 = (1 << EPOCH_BITS) - 1;
+
+/// Decodes a timestamp, of which the extra field holds the nanoseconds and extends
+/// the signed 32-bit seconds beyond the year 2038.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/ext4.h#L874>
+fn decode_time(secs: u32, extra: u32) -> Duration {
+    let secs = secs as i32 as i64 + (((extra & EPOCH_MASK) as i64) << 32);
+    let nanos = (extra >> EPOCH_BITS).min(999_999_999);
+    // Timestamps before the epoch are not representable.
+    if secs < 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(secs as u64, nanos)
+}
+
+/// Encodes a timestamp into the seconds and the extra field.
+fn encode_time(time: Duration) -> (u32, u32) {
+    let secs = time.as_secs() as i64;
+    let epoch = ((secs - secs as i32 as i64) >> 32) as u32 & EPOCH_MASK;
+    (secs as u32, epoch | (time.subsec_nanos() << EPOCH_BITS))
+}
+
+const SECTORS_PER_BLOCK: u64 = (BLOCK_SIZE / SECTOR_SIZE) as u64;
+
+/// The offset of the lower 16 bits of the checksum in the on-disk inode.
+const CHECKSUM_LO_OFFSET: usize =
+    offset_of!(RawInode, os_dependent_2) + offset_of!(Osd2, checksum_lo);
+/// The offset of the higher 16 bits of the checksum in the on-disk inode.
+const CHECKSUM_HI_OFFSET: usize = size_of::<RawInode>() + offset_of!(RawInodeExtra, checksum_hi);
+/// The minimum `extra_isize` to hold the higher 16 bits of the checksum.
+const CHECKSUM_HI_END: usize = offset_of!(RawInodeExtra, checksum_hi) + size_of::<u16>();
+
+/// Computes the checksum of the on-disk inode, treating the checksum fields as zeros.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/inode.c#L59>
+fn inode_checksum(slot: &[u8], csum_seed: u32, has_checksum_hi: bool) -> u32 {
+    let zeros = [0u8; 2];
+    let checksum = crc32c(csum_seed, &slot[..CHECKSUM_LO_OFFSET]);
+    let checksum = crc32c(checksum, &zeros);
+    if !has_checksum_hi {
+        return crc32c(checksum, &slot[CHECKSUM_LO_OFFSET + 2..]);
+    }
+
+    let checksum = crc32c(checksum, &slot[CHECKSUM_LO_OFFSET + 2..CHECKSUM_HI_OFFSET]);
+    let checksum = crc32c(checksum, &zeros);
+    crc32c(checksum, &slot[CHECKSUM_HI_OFFSET + 2..])
+}
+
 bitflags! {
     pub struct FilePerm: u16 {
         /// set-user-ID
@@ -2279,6 +2935,24 @@ bitflags! {
         const DIR_SYNC = 1 << 16;
         /// Top of directory hierarchies.
         const TOP_DIR = 1 << 17;
+        /// The block count is in the unit of filesystem blocks instead of sectors.
+        const HUGE_FILE = 1 << 18;
+        /// The blocks are mapped by an extent tree.
+        const EXTENTS = 1 << 19;
+        /// Verity protected file.
+        const VERITY = 1 << 20;
+        /// The inode stores a large extended attribute value.
+        const EA_INODE = 1 << 21;
+        /// Do not copy on write.
+        const NO_COW = 1 << 23;
+        /// Direct access.
+        const DAX = 1 << 25;
+        /// The data are stored inline in the inode.
+        const INLINE_DATA = 1 << 28;
+        /// Create with the parent's project id.
+        const PROJ_INHERIT = 1 << 29;
+        /// Casefolded directory.
+        const CASEFOLD = 1 << 30;
         /// Reserved for ext2 lib.
         const RESERVED = 1 << 31;
     }
@@ -2297,16 +2971,17 @@ pub(super) struct RawInode {
     /// Lower 32 bits of size in bytes.
     pub size_low: u32,
     /// Access time.
-    pub atime: UnixTime,
+    pub atime: u32,
     /// Change time.
-    pub ctime: UnixTime,
+    pub ctime: u32,
     /// Modification time.
-    pub mtime: UnixTime,
+    pub mtime: u32,
     /// Deletion time.
-    pub dtime: UnixTime,
+    pub dtime: u32,
     /// Low 16 bits of Group Id.
     pub gid: u16,
     pub hard_links: u16,
+    /// Lower 32 bits of the number of 512-byte sectors allocated.
     pub blocks_count: u32,
     /// File flags.
     pub flags: u32,
@@ -2320,8 +2995,7 @@ pub(super) struct RawInode {
     /// In revision 1, File ACL.
     pub file_acl: u32,
     /// In revision 0, this field is reserved.
-    /// In revision 1, Upper 32 bits of file size (if feature bit set)
-    /// if it's a file, Directory ACL if it's a directory.
+    /// In revision 1, Upper 32 bits of file size (if feature bit set).
     pub size_high: u32,
     /// Fragment address.
     pub frag_addr: u32,
@@ -2329,55 +3003,56 @@ pub(super) struct RawInode {
     pub os_dependent_2: Osd2,
 }
 
-impl From<&InodeDesc> for RawInode {
-    fn from(inode: &InodeDesc) -> Self {
-        Self {
-            mode: inode.type_ as u16 | inode.perm.bits(),
-            uid: inode.uid as u16,
-            size_low: inode.size as u32,
-            atime: UnixTime::from(inode.atime),
-            ctime: UnixTime::from(inode.ctime),
-            mtime: UnixTime::from(inode.mtime),
-            dtime: UnixTime::from(inode.dtime),
-            gid: inode.gid as u16,
-            hard_links: inode.hard_links,
-            blocks_count: inode.blocks_count,
-            flags: inode.flags.bits(),
-            block_ptrs: inode.block_ptrs,
-            file_acl: match inode.acl {
-                Some(acl) if inode.type_ == InodeType::File => acl.to_raw() as u32,
-                _ => Default::default(),
-            },
-            size_high: match inode.acl {
-                Some(acl) if inode.type_ == InodeType::Dir => acl.to_raw() as u32,
-                _ => Default::default(),
-            },
-            os_dependent_2: Osd2 {
-                uid_high: (inode.uid >> 16) as u16,
-                gid_high: (inode.gid >> 16) as u16,
-                ..Default::default()
-            },
-            ..Default::default()
-        }
-    }
-}
-
 /// OS dependent Value 2
 #[repr(C)]
 #[derive(Clone, Copy, Default, Debug, Pod)]
 pub(super) struct Osd2 {
-    /// Fragment number.
-    pub frag_num: u8,
-    /// Fragment size.
-    pub frag_size: u8,
-    pad1: u16,
+    /// High 16 bits of the number of sectors allocated.
+    pub blocks_high: u16,
+    /// High 16 bits of File ACL.
+    pub file_acl_high: u16,
     /// High 16 bits of User Id.
     pub uid_high: u16,
     /// High 16 bits of Group Id.
     pub gid_high: u16,
-    reserved2: u32,
+    /// Lower 16 bits of the inode checksum.
+    pub checksum_lo: u16,
+    reserved: u16,
+}
+
+const_assert!(size_of::<RawInodeExtra>() == 32);
+
+/// The extra fields following the original 128-byte inode.
+///
+/// Only the first `extra_isize` bytes of them are present on the disk.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+pub(super) struct RawInodeExtra {
+    /// Size of the present extra fields.
+    pub extra_isize: u16,
+    /// Higher 16 bits of the inode checksum.
+    pub checksum_hi: u16,
+    /// Extra change time bits.
+    pub ctime_extra: u32,
+    /// Extra modification time bits.
+    pub mtime_extra: u32,
+    /// Extra access time bits.
+    pub atime_extra: u32,
+    /// Creation time.
+    pub crtime: u32,
+    /// Extra creation time bits.
+    pub crtime_extra: u32,
+    /// Higher 32 bits of the file version.
+    pub version_hi: u32,
+    /// Project Id.
+    pub projid: u32,
 }
 
 fn is_block_aligned(offset: usize) -> bool {
     offset % BLOCK_SIZE == 0
 }
+
+/// Returns the range of the blocks covering the byte `range`.
+fn bytes_to_blocks(range: Range<usize>) -> Range<Ext2Bid> {
+    (range.start / BLOCK_SIZE) as Ext2Bid..range.end.div_ceil(BLOCK_SIZE) as Ext2Bid
+}
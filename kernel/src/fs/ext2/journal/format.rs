@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The on-disk format of JBD2, which is the journal used by Ext3 and Ext4.
+//!
+//! All the fields in the journal are stored in big-endian byte order.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.17/source/include/linux/jbd2.h>
+
+use ostd::const_assert;
+
+use crate::fs::ext2::prelude::*;
+
+/// The magic number of JBD2.
+pub(super) const JBD2_MAGIC_NUM: u32 = 0xc03b3998;
+
+/// The size of the journal superblock.
+pub(super) const JOURNAL_SUPER_BLOCK_SIZE: usize = 1024;
+
+/// The size of the UUID that follows the first tag in a descriptor block.
+pub(super) const UUID_SIZE: usize = 16;
+
+macro_rules! define_be_type {
+    ($name:ident, $ty:ty) => {
+        #[doc = concat!("A big-endian `", stringify!($ty), "`.")]
+        #[repr(C)]
+        #[derive(Clone, Copy, Debug, Default, Pod)]
+        pub(super) struct $name([u8; size_of::<$ty>()]);
+
+        impl $name {
+            pub(super) fn new(value: $ty) -> Self {
+                Self(value.to_be_bytes())
+            }
+
+            pub(super) fn get(self) -> $ty {
+                <$ty>::from_be_bytes(self.0)
+            }
+        }
+    };
+}
+
+define_be_type!(Be16, u16);
+define_be_type!(Be32, u32);
+define_be_type!(Be64, u64);
+
+/// The types of the journal blocks.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, TryFromInt)]
+pub(super) enum BlockType {
+    /// A descriptor block, which describes the home locations of the following data blocks.
+    Descriptor = 1,
+    /// A commit block, which marks the end of a transaction.
+    Commit = 2,
+    /// A journal superblock of version 1.
+    SuperBlockV1 = 3,
+    /// A journal superblock of version 2.
+    SuperBlockV2 = 4,
+    /// A revoke block, which lists the blocks that must not be replayed.
+    Revoke = 5,
+}
+
+const_assert!(size_of::<RawHeader>() == 12);
+
+/// The header of all the journal metadata blocks.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct RawHeader {
+    pub magic: Be32,
+    pub block_type: Be32,
+    pub sequence: Be32,
+}
+
+impl RawHeader {
+    pub(super) fn new(block_type: BlockType, sequence: u32) -> Self {
+        Self {
+            magic: Be32::new(JBD2_MAGIC_NUM),
+            block_type: Be32::new(block_type as u32),
+            sequence: Be32::new(sequence),
+        }
+    }
+
+    /// Returns the block type if the header is valid.
+    pub(super) fn block_type(&self) -> Option<BlockType> {
+        if self.magic.get() != JBD2_MAGIC_NUM {
+            return None;
+        }
+        BlockType::try_from(self.block_type.get()).ok()
+    }
+}
+
+const_assert!(size_of::<RawJournalSuperBlock>() == JOURNAL_SUPER_BLOCK_SIZE);
+
+/// The raw journal superblock, which is stored in the first block of the journal.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct RawJournalSuperBlock {
+    pub header: RawHeader,
+    /// The journal device block size.
+    pub block_size: Be32,
+    /// The total number of blocks in the journal.
+    pub max_len: Be32,
+    /// The first block of log information.
+    pub first: Be32,
+    /// The first commit ID expected in the log.
+    pub sequence: Be32,
+    /// The block number of the start of the log, or zero if the log is empty.
+    pub start: Be32,
+    /// The error value, as set by `jbd2_journal_abort`.
+    pub errno: Be32,
+    //
+    // These fields are valid for the version 2 superblock only.
+    //
+    pub feature_compat: Be32,
+    pub feature_incompat: Be32,
+    pub feature_ro_compat: Be32,
+    /// The 128-bit UUID of the journal.
+    pub uuid: [u8; UUID_SIZE],
+    /// The number of filesystems sharing the journal.
+    pub nr_users: Be32,
+    /// The block number of the dynamic superblock copy.
+    pub dyn_super: Be32,
+    /// The limit of journal blocks per transaction.
+    pub max_transaction: Be32,
+    /// The limit of data blocks per transaction.
+    pub max_trans_data: Be32,
+    pub checksum_type: u8,
+    padding1: [u8; 3],
+    /// The number of fast commit blocks in the journal.
+    pub num_fc_blocks: Be32,
+    /// The block number of the head (first unused block) of the journal.
+    pub head: Be32,
+    padding2: [u8; 160],
+    pub checksum: Be32,
+    /// The UUIDs of the filesystems sharing the journal.
+    pub users: [u8; 16 * 48],
+}
+
+bitflags! {
+    /// Incompatible journal feature set.
+    pub(super) struct JournalFeatureInCompatSet: u32 {
+        /// The journal has revoke blocks
+        const REVOKE = 1 << 0;
+        /// The journal uses 64-bit block numbers
+        const BIT64 = 1 << 1;
+        /// The commit blocks are written without waiting for the data blocks
+        const ASYNC_COMMIT = 1 << 2;
+        /// The journal blocks have checksums of version 2
+        const CSUM_V2 = 1 << 3;
+        /// The journal blocks have checksums of version 3
+        const CSUM_V3 = 1 << 4;
+        /// The journal has fast commit blocks
+        const FAST_COMMIT = 1 << 5;
+    }
+}
+
+impl JournalFeatureInCompatSet {
+    /// The features supported by the driver.
+    pub(super) const SUPPORTED: Self = Self::REVOKE.union(Self::BIT64);
+}
+
+bitflags! {
+    /// The flags of a block tag in a descriptor block.
+    pub(super) struct TagFlags: u16 {
+        /// The first four bytes of the block are the magic number, which are zeroed in the log
+        const ESCAPE = 1 << 0;
+        /// The block has the same UUID as the previous one, so the UUID is omitted
+        const SAME_UUID = 1 << 1;
+        /// The block is deleted by this transaction
+        const DELETED = 1 << 2;
+        /// The tag is the last one in the descriptor block
+        const LAST_TAG = 1 << 3;
+    }
+}
+
+/// The raw block tag in a descriptor block.
+///
+/// The `block_nr_high` field is present only if the 64-bit feature is set.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct RawBlockTag {
+    pub block_nr: Be32,
+    pub checksum: Be16,
+    pub flags: Be16,
+    pub block_nr_high: Be32,
+}
+
+impl RawBlockTag {
+    /// Returns the size of a tag in a journal with the `features`.
+    pub(super) fn size(features: JournalFeatureInCompatSet) -> usize {
+        if features.contains(JournalFeatureInCompatSet::BIT64) {
+            size_of::<Self>()
+        } else {
+            size_of::<Self>() - size_of::<Be32>()
+        }
+    }
+}
+
+/// The raw commit block.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct RawCommitBlock {
+    pub header: RawHeader,
+    pub checksum_type: u8,
+    pub checksum_size: u8,
+    padding: [u8; 2],
+    pub checksum: [Be32; 8],
+    pub commit_sec: Be64,
+    pub commit_nsec: Be32,
+}
+
+impl RawCommitBlock {
+    pub(super) fn new(sequence: u32, commit_time: Duration) -> Self {
+        Self {
+            header: RawHeader::new(BlockType::Commit, sequence),
+            checksum_type: 0,
+            checksum_size: 0,
+            padding: [0; 2],
+            checksum: [Be32::default(); 8],
+            commit_sec: Be64::new(commit_time.as_secs()),
+            commit_nsec: Be32::new(commit_time.subsec_nanos()),
+        }
+    }
+}
+
+/// The raw header of a revoke block.
+///
+/// The header is followed by the block numbers to be revoked, each of which is 8 bytes
+/// if the 64-bit feature is set, or 4 bytes otherwise.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct RawRevokeHeader {
+    pub header: RawHeader,
+    /// The number of bytes used in the block, including this header.
+    pub count: Be32,
+}
@@ -0,0 +1,865 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The journal of Ext3.
+//!
+//! The journal uses the on-disk format of JBD2, so a filesystem journaled here
+//! can be checked and recovered by the host tools, and vice versa.
+//!
+//! Only the metadata blocks go through the journal. The data blocks are written to
+//! their home locations before the metadata referring to them is committed,
+//! which is known as the ordered data mode.
+//!
+//! A metadata block goes through the following stages:
+//! 1. The block is logged into the running transaction instead of being written
+//!    to the disk.
+//! 2. When the transaction is committed, the block is written to the journal area,
+//!    followed by a commit block.
+//! 3. After the commit block reaches the disk, the block is written to its home
+//!    location (i.e., checkpointed), and then the journal is marked empty.
+//!
+//! If the system crashes before the journal is marked empty, the committed
+//! transactions are replayed at the next mount.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/jbd2>
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use ostd::sync::WaitQueue;
+
+use self::format::{
+    Be16, Be32, BlockType, JournalFeatureInCompatSet, RawBlockTag, RawCommitBlock, RawHeader,
+    RawJournalSuperBlock, TagFlags, JBD2_MAGIC_NUM, UUID_SIZE,
+};
+use super::{
+    block_group::RawGroupDescriptor,
+    block_ptr::{BlockMapping, Ext2Bid, BID_SIZE, DIRECT_RANGE},
+    extent,
+    fs::Ext2,
+    inode::{FileFlags, RawInode},
+    prelude::*,
+    super_block::{FeatureInCompatSet, RawSuperBlock, SuperBlock, SUPER_BLOCK_OFFSET},
+    utils::now,
+};
+use crate::thread::kernel_thread::ThreadOptions;
+
+mod format;
+mod recovery;
+
+/// The interval to commit the running transaction.
+///
+/// Use the same value as `JBD2_DEFAULT_MAX_COMMIT_AGE`.
+const COMMIT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The journal credits of an operation that updates an inode.
+///
+/// Besides the inode, the extended attribute blocks and the blocks on a path
+/// of the block mapping may be updated.
+pub(super) const INODE_CREDITS: usize = 8;
+
+/// The journal credits of an operation that adds or removes a directory entry.
+///
+/// Besides the directory and the inode of the entry, adding an entry to a hashed
+/// directory may split a leaf block and the index blocks above it.
+pub(super) const DIR_ENTRY_CREDITS: usize = 2 * INODE_CREDITS + 4;
+
+/// The journal credits of an operation that only updates the attributes of an inode.
+pub(super) const ATTR_CREDITS: usize = 1;
+
+/// Returns the journal credits of an operation that maps the data blocks
+/// of an inode in the range of `len` bytes starting from `offset`.
+pub(super) fn data_credits(offset: usize, len: usize) -> usize {
+    let nblocks = (offset + len).div_ceil(BLOCK_SIZE) - offset / BLOCK_SIZE;
+    INODE_CREDITS + nblocks.div_ceil(BLOCK_SIZE / BID_SIZE)
+}
+
+/// A copy of a metadata block to be written through the journal.
+type LoggedBlock = Arc<Vec<u8>>;
+
+/// The journal of an Ext3 filesystem.
+pub(super) struct Journal {
+    block_device: Arc<dyn BlockDevice>,
+    /// The device block IDs of the journal blocks, indexed by the journal block numbers.
+    block_map: Vec<Ext2Bid>,
+    /// The incompatible features of the journal.
+    features: JournalFeatureInCompatSet,
+    /// The first journal block of the log.
+    first: u32,
+    /// The total number of journal blocks.
+    max_len: u32,
+    /// The maximum number of metadata blocks in a transaction.
+    max_transaction_blocks: usize,
+    /// The maximum number of credits that the operations in a transaction can take.
+    ///
+    /// The bitmaps, the group descriptors and the superblock are updated in memory,
+    /// and are logged when the transaction is committed. So the blocks for them are
+    /// reserved in every transaction instead of being charged to the operations.
+    max_transaction_credits: usize,
+    /// The journal superblock.
+    ///
+    /// The lock also serializes the commits.
+    super_block: Mutex<RawJournalSuperBlock>,
+    /// The metadata blocks logged in the running transaction, indexed by their home locations.
+    ///
+    /// The blocks are kept here until they are checkpointed, so the readers always see
+    /// the latest version of the metadata.
+    running: SpinLock<BTreeMap<Ext2Bid, LoggedBlock>>,
+    updates: SpinLock<Updates>,
+    updates_wait_queue: WaitQueue,
+    is_commit_requested: AtomicBool,
+    commit_wait_queue: WaitQueue,
+    /// Whether the `RECOVER` flag of the filesystem is cleared on the disk.
+    is_fs_clean: AtomicBool,
+}
+
+/// The file system operations that are updating the metadata.
+#[derive(Debug, Default)]
+struct Updates {
+    /// The number of the ongoing operations.
+    count: usize,
+    /// The credits taken by the operations in the running transaction.
+    ///
+    /// The credits are not returned when the operations finish, since the metadata
+    /// updated in memory is not logged until the transaction is committed.
+    credits: usize,
+    /// Whether new operations are blocked because a commit is in progress.
+    is_locked: bool,
+}
+
+impl Journal {
+    /// Loads the journal of the filesystem described by `super_block`.
+    pub(super) fn load(
+        block_device: Arc<dyn BlockDevice>,
+        super_block: &SuperBlock,
+    ) -> Result<Self> {
+        if super_block.journal_ino() == 0 {
+            return_errno_with_message!(Errno::EINVAL, "external journal is not supported");
+        }
+
+        let block_map = read_block_map(block_device.as_ref(), super_block)?;
+        let raw_super_block =
+            block_device.read_val::<RawJournalSuperBlock>(block_map[0] as usize * BLOCK_SIZE)?;
+
+        let features = match raw_super_block.header.block_type() {
+            Some(BlockType::SuperBlockV1) => JournalFeatureInCompatSet::empty(),
+            Some(BlockType::SuperBlockV2) => {
+                JournalFeatureInCompatSet::from_bits(raw_super_block.feature_incompat.get())
+                    .filter(|features| JournalFeatureInCompatSet::SUPPORTED.contains(*features))
+                    .ok_or_else(|| {
+                        Error::with_message(Errno::EINVAL, "unsupported journal features")
+                    })?
+            }
+            _ => return_errno_with_message!(Errno::EINVAL, "bad journal superblock"),
+        };
+        if raw_super_block.block_size.get() as usize != BLOCK_SIZE {
+            return_errno_with_message!(Errno::EINVAL, "unsupported journal block size");
+        }
+        let max_len = raw_super_block.max_len.get();
+        let first = raw_super_block.first.get();
+        if max_len as usize > block_map.len() || first == 0 || first >= max_len {
+            return_errno_with_message!(Errno::EINVAL, "invalid journal length");
+        }
+
+        let max_transaction_blocks = {
+            // Each transaction needs descriptor blocks and a commit block besides the
+            // metadata blocks.
+            let tags_per_descriptor = tags_per_descriptor(features);
+            let available = (max_len - first - 1) as usize;
+            let mut nblocks = available * tags_per_descriptor / (tags_per_descriptor + 1);
+            while nblocks + nblocks.div_ceil(tags_per_descriptor) > available {
+                nblocks -= 1;
+            }
+            nblocks
+        };
+        let reserved_blocks = 1
+            + super_block.group_descriptor_blocks()
+            + 2 * super_block.block_groups_count() as usize;
+        if max_transaction_blocks < reserved_blocks + 2 * DIR_ENTRY_CREDITS {
+            return_errno_with_message!(Errno::EINVAL, "the journal is too small");
+        }
+
+        Ok(Self {
+            block_device,
+            block_map,
+            features,
+            first,
+            max_len,
+            max_transaction_blocks,
+            max_transaction_credits: max_transaction_blocks - reserved_blocks,
+            super_block: Mutex::new(raw_super_block),
+            running: SpinLock::new(BTreeMap::new()),
+            updates: SpinLock::new(Updates::default()),
+            updates_wait_queue: WaitQueue::new(),
+            is_commit_requested: AtomicBool::new(false),
+            commit_wait_queue: WaitQueue::new(),
+            is_fs_clean: AtomicBool::new(false),
+        })
+    }
+
+    /// Spawns a kernel thread that commits the running transaction periodically,
+    /// or when the transaction becomes too large.
+    ///
+    /// The thread exits once the filesystem is dropped.
+    pub(super) fn spawn_commit_thread(self: &Arc<Self>, fs: Weak<Ext2>) {
+        let journal = self.clone();
+        let task_fn = move || loop {
+            let _ = journal.commit_wait_queue.wait_until_or_timeout(
+                || {
+                    journal
+                        .is_commit_requested
+                        .swap(false, Ordering::Relaxed)
+                        .then_some(())
+                },
+                &COMMIT_INTERVAL,
+            );
+
+            let Some(fs) = fs.upgrade() else {
+                break;
+            };
+            if let Err(err) = journal.commit(&fs) {
+                warn!("failed to commit the ext3 journal: {:?}", err);
+            }
+        };
+
+        ThreadOptions::new(task_fn).spawn();
+    }
+
+    /// Starts a file system operation that updates at most `nblocks` metadata blocks.
+    ///
+    /// The running transaction will not be committed until the returned handle is dropped,
+    /// so the updates of the operation are committed atomically. If the operation may
+    /// overflow the running transaction, the transaction is committed first.
+    ///
+    /// The handles must not be nested, otherwise the caller may deadlock with the commit.
+    pub(super) fn start(self: &Arc<Self>, nblocks: usize) -> JournalHandle {
+        // An operation that is too large can only run alone in a transaction.
+        let nblocks = nblocks.min(self.max_transaction_credits);
+        self.updates_wait_queue.wait_until(|| {
+            let mut updates = self.updates.lock();
+            if updates.is_locked {
+                return None;
+            }
+            if updates.credits + nblocks > self.max_transaction_credits {
+                drop(updates);
+                self.request_commit();
+                return None;
+            }
+            updates.count += 1;
+            updates.credits += nblocks;
+            Some(())
+        });
+
+        JournalHandle {
+            journal: self.clone(),
+        }
+    }
+
+    /// Logs the metadata blocks starting from `bid` into the running transaction.
+    pub(super) fn log_blocks(&self, bid: Ext2Bid, bio_segment: &BioSegment) -> Result<()> {
+        for idx in 0..bio_segment.nblocks() {
+            let mut block = vec![0u8; BLOCK_SIZE];
+            bio_segment.read_bytes(idx * BLOCK_SIZE, &mut block)?;
+            self.insert(bid + idx as Ext2Bid, Arc::new(block));
+        }
+        Ok(())
+    }
+
+    /// Logs the metadata bytes at `offset` of the device into the running transaction.
+    ///
+    /// The blocks that are partially covered by `buf` are read first.
+    pub(super) fn log_bytes(&self, offset: usize, buf: &[u8]) -> Result<()> {
+        let mut offset = offset;
+        let mut buf = buf;
+        while !buf.is_empty() {
+            let bid = (offset / BLOCK_SIZE) as Ext2Bid;
+            let offset_in_block = offset % BLOCK_SIZE;
+            let len = buf.len().min(BLOCK_SIZE - offset_in_block);
+
+            let mut block = if len == BLOCK_SIZE {
+                vec![0u8; BLOCK_SIZE]
+            } else {
+                let logged_block = self.running.lock().get(&bid).cloned();
+                match logged_block {
+                    Some(block) => block.as_ref().clone(),
+                    None => {
+                        let mut block = vec![0u8; BLOCK_SIZE];
+                        self.block_device
+                            .read_bytes(bid as usize * BLOCK_SIZE, &mut block)?;
+                        block
+                    }
+                }
+            };
+            block[offset_in_block..offset_in_block + len].copy_from_slice(&buf[..len]);
+            self.insert(bid, Arc::new(block));
+
+            offset += len;
+            buf = &buf[len..];
+        }
+        Ok(())
+    }
+
+    fn insert(&self, bid: Ext2Bid, block: LoggedBlock) {
+        let nblocks = {
+            let mut running = self.running.lock();
+            running.insert(bid, block);
+            running.len()
+        };
+
+        // Leave enough room for the blocks logged during the commit.
+        if nblocks >= self.max_transaction_blocks / 4 {
+            self.request_commit();
+        }
+    }
+
+    /// Removes the blocks in `range` from the running transaction.
+    ///
+    /// This is required when the blocks are freed and then reused to store data,
+    /// since the logged copies must not overwrite the data.
+    pub(super) fn forget_blocks(&self, range: Range<Ext2Bid>) {
+        let mut running = self.running.lock();
+        if running.range(range.clone()).next().is_none() {
+            return;
+        }
+        for bid in range {
+            running.remove(&bid);
+        }
+    }
+
+    /// Returns `true` if any block in `range` is logged in the running transaction.
+    pub(super) fn contains_any(&self, range: Range<Ext2Bid>) -> bool {
+        self.running.lock().range(range).next().is_some()
+    }
+
+    /// Overwrites the blocks read from `bid` into `bio_segment` with the logged ones,
+    /// which are newer than the ones on the disk.
+    pub(super) fn patch_read(&self, bid: Ext2Bid, bio_segment: &BioSegment) -> Result<()> {
+        // The segment is not writable through its `VmWriter` since the DMA direction is
+        // from the device, so we write to the underlying segment directly.
+        let dma_slice = bio_segment.inner_dma_slice();
+        let segment = dma_slice.mem_obj().segment();
+        let base_offset = dma_slice.offset().start;
+
+        let nblocks = bio_segment.nblocks() as Ext2Bid;
+        let running = self.running.lock();
+        for (logged_bid, block) in running.range(bid..bid + nblocks) {
+            let offset = base_offset + (logged_bid - bid) as usize * BLOCK_SIZE;
+            segment.write_bytes(offset, block)?;
+        }
+        Ok(())
+    }
+
+    /// Requests the commit thread to commit the running transaction.
+    fn request_commit(&self) {
+        self.is_commit_requested.store(true, Ordering::Relaxed);
+        self.commit_wait_queue.wake_all();
+    }
+
+    /// Commits the running transaction and checkpoints it.
+    ///
+    /// All the dirty data and metadata of `fs` are written back when the method returns.
+    pub(super) fn commit(&self, fs: &Ext2) -> Result<()> {
+        let mut super_block = self.super_block.lock();
+        let _updates_guard = self.lock_updates();
+
+        // Flush the in-memory metadata into the running transaction,
+        // and write the data to the home locations.
+        fs.sync_all_inodes()?;
+        fs.sync_metadata()?;
+        let blocks: Vec<(Ext2Bid, LoggedBlock)> = self
+            .running
+            .lock()
+            .iter()
+            .map(|(bid, block)| (*bid, block.clone()))
+            .collect();
+
+        // The data must reach the disk before the metadata referring to them is committed.
+        self.flush()?;
+        if blocks.is_empty() {
+            return Ok(());
+        }
+
+        // The transaction can only be committed atomically as a whole. This is guaranteed
+        // by the credits of the operations, unless the metadata is updated without them.
+        if blocks.len() > self.max_transaction_blocks {
+            return_errno_with_message!(Errno::ENOSPC, "the transaction is too large");
+        }
+
+        // The journal is about to be non-empty.
+        if self.is_fs_clean.load(Ordering::Relaxed) {
+            self.write_needs_recovery(true)?;
+        }
+        self.write_transaction(&mut super_block, &blocks)?;
+        self.checkpoint(&mut super_block, &blocks)
+    }
+
+    /// Clears the `RECOVER` flag of the filesystem if the journal is empty,
+    /// so that the filesystem can be mounted without recovery.
+    ///
+    /// The flag is set again before the next transaction is committed.
+    pub(super) fn mark_clean(&self) -> Result<()> {
+        let super_block = self.super_block.lock();
+        // The committed transaction may have failed to be checkpointed.
+        if super_block.start.get() != 0 || self.is_fs_clean.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        self.write_needs_recovery(false)
+    }
+
+    /// Sets or clears the `RECOVER` flag of the filesystem.
+    ///
+    /// Only the flag in the superblock on the disk is updated, bypassing the journal,
+    /// so the uncommitted metadata does not reach the disk.
+    fn write_needs_recovery(&self, needs_recovery: bool) -> Result<()> {
+        let mut raw_super_block = self
+            .block_device
+            .read_val::<RawSuperBlock>(SUPER_BLOCK_OFFSET)?;
+        if needs_recovery {
+            raw_super_block.feature_incompat |= FeatureInCompatSet::RECOVER.bits();
+        } else {
+            raw_super_block.feature_incompat &= !FeatureInCompatSet::RECOVER.bits();
+        }
+        raw_super_block.update_checksum();
+        self.block_device
+            .write_val(SUPER_BLOCK_OFFSET, &raw_super_block)?;
+        self.flush()?;
+
+        self.is_fs_clean.store(!needs_recovery, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Blocks new operations and waits for the ongoing ones to finish.
+    fn lock_updates(&self) -> UpdatesGuard<'_> {
+        self.updates.lock().is_locked = true;
+        self.updates_wait_queue
+            .wait_until(|| (self.updates.lock().count == 0).then_some(()));
+        UpdatesGuard { journal: self }
+    }
+
+    /// Writes the transaction, including the descriptor blocks, the metadata blocks
+    /// and the commit block, into the journal area.
+    fn write_transaction(
+        &self,
+        super_block: &mut RawJournalSuperBlock,
+        blocks: &[(Ext2Bid, LoggedBlock)],
+    ) -> Result<()> {
+        let sequence = super_block.sequence.get();
+        let mut bio_waiter = BioWaiter::new();
+
+        // Mark the journal as non-empty.
+        super_block.start = Be32::new(self.first);
+        bio_waiter.concat(self.write_super_block_async(super_block)?);
+
+        let tag_size = RawBlockTag::size(self.features);
+        let tags_per_descriptor = tags_per_descriptor(self.features);
+        let mut journal_bid = self.first;
+        for blocks in blocks.chunks(tags_per_descriptor) {
+            let mut descriptor = vec![0u8; BLOCK_SIZE];
+            descriptor[..size_of::<RawHeader>()]
+                .copy_from_slice(RawHeader::new(BlockType::Descriptor, sequence).as_bytes());
+            let mut tag_offset = size_of::<RawHeader>();
+
+            for (idx, (bid, block)) in blocks.iter().enumerate() {
+                let mut flags = TagFlags::empty();
+                if idx > 0 {
+                    flags |= TagFlags::SAME_UUID;
+                }
+                if idx == blocks.len() - 1 {
+                    flags |= TagFlags::LAST_TAG;
+                }
+
+                // A block starting with the magic number would be mistaken for
+                // a journal metadata block, so the magic number is escaped.
+                let data_bid = journal_bid + 1 + idx as u32;
+                if block[..size_of::<u32>()] == JBD2_MAGIC_NUM.to_be_bytes() {
+                    flags |= TagFlags::ESCAPE;
+                    let mut escaped_block = block.as_ref().clone();
+                    escaped_block[..size_of::<u32>()].fill(0);
+                    bio_waiter.concat(self.write_block_async(data_bid, &escaped_block)?);
+                } else {
+                    bio_waiter.concat(self.write_block_async(data_bid, block)?);
+                }
+
+                let tag = RawBlockTag {
+                    block_nr: Be32::new(*bid),
+                    checksum: Be16::default(),
+                    flags: Be16::new(flags.bits()),
+                    block_nr_high: Be32::default(),
+                };
+                descriptor[tag_offset..tag_offset + tag_size]
+                    .copy_from_slice(&tag.as_bytes()[..tag_size]);
+                tag_offset += tag_size;
+                if idx == 0 {
+                    let uuid = &super_block.uuid;
+                    descriptor[tag_offset..tag_offset + UUID_SIZE].copy_from_slice(uuid);
+                    tag_offset += UUID_SIZE;
+                }
+            }
+
+            bio_waiter.concat(self.write_block_async(journal_bid, &descriptor)?);
+            journal_bid += 1 + blocks.len() as u32;
+        }
+
+        Self::wait(bio_waiter)?;
+        self.flush()?;
+
+        // The transaction is committed once the commit block reaches the disk.
+        let mut commit_block = vec![0u8; BLOCK_SIZE];
+        commit_block[..size_of::<RawCommitBlock>()]
+            .copy_from_slice(RawCommitBlock::new(sequence, now()).as_bytes());
+        Self::wait(self.write_block_async(journal_bid, &commit_block)?)?;
+        self.flush()
+    }
+
+    /// Writes the committed blocks to their home locations, and then marks the journal empty.
+    fn checkpoint(
+        &self,
+        super_block: &mut RawJournalSuperBlock,
+        blocks: &[(Ext2Bid, LoggedBlock)],
+    ) -> Result<()> {
+        let mut bio_waiter = BioWaiter::new();
+        for (bid, block) in blocks {
+            // The block has been overwritten by data since it is committed.
+            if !self.running.lock().contains_key(bid) {
+                continue;
+            }
+            bio_waiter.concat(
+                self.block_device
+                    .write_bytes_async(*bid as usize * BLOCK_SIZE, block)?,
+            );
+        }
+        Self::wait(bio_waiter)?;
+        self.flush()?;
+
+        super_block.start = Be32::new(0);
+        super_block.sequence = Be32::new(super_block.sequence.get().wrapping_add(1));
+        Self::wait(self.write_super_block_async(super_block)?)?;
+        self.flush()?;
+
+        // Remove the checkpointed blocks unless they are logged again during the commit.
+        let mut running = self.running.lock();
+        for (bid, block) in blocks {
+            if running
+                .get(bid)
+                .is_some_and(|logged_block| Arc::ptr_eq(logged_block, block))
+            {
+                running.remove(bid);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the block at `journal_bid` of the journal.
+    fn read_block(&self, journal_bid: u32) -> Result<Vec<u8>> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        self.block_device
+            .read_bytes(self.device_offset(journal_bid), &mut block)?;
+        Ok(block)
+    }
+
+    fn write_block_async(&self, journal_bid: u32, block: &[u8]) -> Result<BioWaiter> {
+        let bio_waiter = self
+            .block_device
+            .write_bytes_async(self.device_offset(journal_bid), block)?;
+        Ok(bio_waiter)
+    }
+
+    fn write_super_block_async(&self, super_block: &RawJournalSuperBlock) -> Result<BioWaiter> {
+        self.write_block_async(0, super_block.as_bytes())
+    }
+
+    fn device_offset(&self, journal_bid: u32) -> usize {
+        self.block_map[journal_bid as usize] as usize * BLOCK_SIZE
+    }
+
+    fn flush(&self) -> Result<()> {
+        match self.block_device.sync()? {
+            BioStatus::Complete => Ok(()),
+            err_status => Err(Error::from(err_status)),
+        }
+    }
+
+    fn wait(bio_waiter: BioWaiter) -> Result<()> {
+        bio_waiter
+            .wait()
+            .ok_or_else(|| Error::with_message(Errno::EIO, "failed to write the journal"))?;
+        Ok(())
+    }
+}
+
+impl Debug for Journal {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Journal")
+            .field("len", &self.block_map.len())
+            .field("features", &self.features)
+            .field("running_blocks", &self.running.lock().len())
+            .finish()
+    }
+}
+
+/// A handle of a file system operation that updates the metadata.
+///
+/// See [`Journal::start`] for more details.
+pub(super) struct JournalHandle {
+    journal: Arc<Journal>,
+}
+
+impl Drop for JournalHandle {
+    fn drop(&mut self) {
+        let mut updates = self.journal.updates.lock();
+        updates.count -= 1;
+        if updates.count == 0 {
+            drop(updates);
+            self.journal.updates_wait_queue.wake_all();
+        }
+    }
+}
+
+struct UpdatesGuard<'a> {
+    journal: &'a Journal,
+}
+
+impl Drop for UpdatesGuard<'_> {
+    fn drop(&mut self) {
+        let mut updates = self.journal.updates.lock();
+        updates.is_locked = false;
+        // A new transaction starts.
+        updates.credits = 0;
+        drop(updates);
+        self.journal.updates_wait_queue.wake_all();
+    }
+}
+
+/// Returns the number of tags that fit in a descriptor block.
+fn tags_per_descriptor(features: JournalFeatureInCompatSet) -> usize {
+    (BLOCK_SIZE - size_of::<RawHeader>() - UUID_SIZE) / RawBlockTag::size(features)
+}
+
+/// Reads the device block IDs of the blocks of the journal inode.
+///
+/// This is done before the block groups are loaded, so the metadata of the inode
+/// is read from the disk directly.
+fn read_block_map(
+    block_device: &dyn BlockDevice,
+    super_block: &SuperBlock,
+) -> Result<Vec<Ext2Bid>> {
+    let read_block = |bid: Ext2Bid| -> Result<Vec<u8>> {
+        if bid == 0 || bid >= super_block.total_blocks() {
+            return_errno_with_message!(Errno::EINVAL, "invalid block in the journal inode");
+        }
+        let mut block = vec![0u8; BLOCK_SIZE];
+        block_device.read_bytes(bid as usize * BLOCK_SIZE, &mut block)?;
+        Ok(block)
+    };
+
+    let raw_inode = {
+        let ino = super_block.journal_ino();
+        if ino > super_block.total_inodes() {
+            return_errno_with_message!(Errno::EINVAL, "invalid journal inode number");
+        }
+        let block_group_idx = ((ino - 1) / super_block.inodes_per_group()) as usize;
+        let inode_idx = ((ino - 1) % super_block.inodes_per_group()) as usize;
+
+        let raw_descriptor = {
+            let desc_size = super_block.group_descriptor_size();
+            let offset = block_group_idx * desc_size;
+            let bid = super_block.group_descriptors_bid(0).to_raw() as Ext2Bid
+                + (offset / BLOCK_SIZE) as Ext2Bid;
+            let block = read_block(bid)?;
+            // The fields beyond the descriptor size are absent.
+            let len = desc_size.min(size_of::<RawGroupDescriptor>());
+            let mut raw_descriptor = RawGroupDescriptor::new_zeroed();
+            raw_descriptor.as_bytes_mut()[..len]
+                .copy_from_slice(&block[offset % BLOCK_SIZE..][..len]);
+            raw_descriptor
+        };
+
+        let offset = inode_idx * super_block.inode_size();
+        let block = read_block(raw_descriptor.inode_table + (offset / BLOCK_SIZE) as Ext2Bid)?;
+        RawInode::from_bytes(&block[offset % BLOCK_SIZE..])
+    };
+
+    let nblocks = ((raw_inode.size_low as usize) | ((raw_inode.size_high as usize) << 32))
+        .div_ceil(BLOCK_SIZE);
+    if nblocks == 0 {
+        return_errno_with_message!(Errno::EINVAL, "the journal inode is empty");
+    }
+
+    if raw_inode.flags & FileFlags::EXTENTS.bits() != 0 {
+        // Walks through the extents. The journal inode must have no holes.
+        let mut block_map = Vec::with_capacity(nblocks);
+        extent::walk_raw(&raw_inode.block_ptrs, &read_block, &mut |lblk, mapping| {
+            let BlockMapping::Mapped(device_range) = mapping else {
+                return_errno_with_message!(Errno::EINVAL, "the journal inode has holes");
+            };
+            if lblk as usize != block_map.len() {
+                return_errno_with_message!(Errno::EINVAL, "the journal inode has holes");
+            }
+            block_map.extend(device_range);
+            Ok(())
+        })?;
+
+        if block_map.len() < nblocks {
+            return_errno_with_message!(Errno::EINVAL, "the journal inode is too large");
+        }
+        block_map.truncate(nblocks);
+        return Ok(block_map);
+    }
+
+    // Walks through the block pointers. The journal inode must have no holes.
+    fn walk(
+        bid: Ext2Bid,
+        level: usize,
+        nblocks: usize,
+        block_map: &mut Vec<Ext2Bid>,
+        read_block: &dyn Fn(Ext2Bid) -> Result<Vec<u8>>,
+    ) -> Result<()> {
+        if block_map.len() >= nblocks {
+            return Ok(());
+        }
+        if level == 0 {
+            if bid == 0 {
+                return_errno_with_message!(Errno::EINVAL, "the journal inode has holes");
+            }
+            block_map.push(bid);
+            return Ok(());
+        }
+
+        let block = read_block(bid)?;
+        for bid_bytes in block.chunks_exact(BID_SIZE) {
+            walk(
+                Ext2Bid::from_bytes(bid_bytes),
+                level - 1,
+                nblocks,
+                block_map,
+                read_block,
+            )?;
+        }
+        Ok(())
+    }
+
+    let block_ptrs = &raw_inode.block_ptrs;
+    let mut block_map = Vec::with_capacity(nblocks);
+    for idx in DIRECT_RANGE {
+        walk(
+            block_ptrs.direct(idx),
+            0,
+            nblocks,
+            &mut block_map,
+            &read_block,
+        )?;
+    }
+    walk(
+        block_ptrs.indirect(),
+        1,
+        nblocks,
+        &mut block_map,
+        &read_block,
+    )?;
+    walk(
+        block_ptrs.db_indirect(),
+        2,
+        nblocks,
+        &mut block_map,
+        &read_block,
+    )?;
+    walk(
+        block_ptrs.tb_indirect(),
+        3,
+        nblocks,
+        &mut block_map,
+        &read_block,
+    )?;
+
+    if block_map.len() < nblocks {
+        return_errno_with_message!(Errno::EINVAL, "the journal inode is too large");
+    }
+    Ok(block_map)
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+    use crate::fs::ext2::test_utils::new_ext3_disk;
+
+    fn load_journal(block_device: &Arc<dyn BlockDevice>) -> (SuperBlock, Journal) {
+        let raw_super_block = block_device
+            .read_val::<RawSuperBlock>(SUPER_BLOCK_OFFSET)
+            .unwrap();
+        let super_block = SuperBlock::try_from(raw_super_block).unwrap();
+        let journal = Journal::load(block_device.clone(), &super_block).unwrap();
+        (super_block, journal)
+    }
+
+    fn read_home_block(block_device: &Arc<dyn BlockDevice>, bid: Ext2Bid) -> Vec<u8> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        block_device
+            .read_bytes(bid as usize * BLOCK_SIZE, &mut block)
+            .unwrap();
+        block
+    }
+
+    /// Returns a block that starts with the magic number, so it must be escaped in the journal.
+    fn new_logged_block() -> LoggedBlock {
+        let mut block = vec![0x5a; BLOCK_SIZE];
+        block[..size_of::<u32>()].copy_from_slice(&JBD2_MAGIC_NUM.to_be_bytes());
+        Arc::new(block)
+    }
+
+    #[ktest]
+    fn replay_committed_transaction() {
+        let block_device = new_ext3_disk();
+        let (super_block, journal) = load_journal(&block_device);
+        // The last block is not used by the empty filesystem.
+        let bid = super_block.total_blocks() - 1;
+        let block = new_logged_block();
+
+        // Crash after the transaction is committed but before it is checkpointed.
+        journal.write_needs_recovery(true).unwrap();
+        journal
+            .write_transaction(&mut journal.super_block.lock(), &[(bid, block.clone())])
+            .unwrap();
+        assert_ne!(read_home_block(&block_device, bid), *block);
+
+        let (super_block, journal) = load_journal(&block_device);
+        assert!(super_block.needs_recovery());
+        journal.recover().unwrap();
+        assert_eq!(read_home_block(&block_device, bid), *block);
+
+        // The journal is empty after the recovery, so the filesystem can be marked clean.
+        journal.mark_clean().unwrap();
+        let (super_block, journal) = load_journal(&block_device);
+        assert!(!super_block.needs_recovery());
+        assert_eq!(journal.super_block.lock().start.get(), 0);
+    }
+
+    #[ktest]
+    fn skip_uncommitted_transaction() {
+        let block_device = new_ext3_disk();
+        let (super_block, journal) = load_journal(&block_device);
+        let bid = super_block.total_blocks() - 1;
+        let home_block = read_home_block(&block_device, bid);
+
+        // Crash before the commit block reaches the disk. The commit block follows
+        // the descriptor block and the logged block.
+        journal.write_needs_recovery(true).unwrap();
+        journal
+            .write_transaction(
+                &mut journal.super_block.lock(),
+                &[(bid, new_logged_block())],
+            )
+            .unwrap();
+        let commit_bid = journal.first + 2;
+        Journal::wait(
+            journal
+                .write_block_async(commit_bid, &[0u8; BLOCK_SIZE])
+                .unwrap(),
+        )
+        .unwrap();
+
+        let (_, journal) = load_journal(&block_device);
+        journal.recover().unwrap();
+        assert_eq!(read_home_block(&block_device, bid), home_block);
+    }
+}
@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The recovery of the journal at mount time.
+//!
+//! The recovery scans the journal from its start, replays the committed transactions,
+//! and skips the blocks revoked by the same or later transactions.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/jbd2/recovery.c>
+
+use super::{
+    format::{
+        Be32, BlockType, JournalFeatureInCompatSet, RawBlockTag, RawHeader, RawRevokeHeader,
+        TagFlags, JBD2_MAGIC_NUM, UUID_SIZE,
+    },
+    Journal,
+};
+use crate::fs::ext2::{block_ptr::Ext2Bid, prelude::*};
+
+/// A metadata block of a committed transaction in the journal.
+#[derive(Clone, Copy, Debug)]
+struct LoggedTag {
+    /// The journal block number where the block is logged.
+    journal_bid: u32,
+    /// The home location of the block.
+    bid: Ext2Bid,
+    /// Whether the magic number at the beginning of the block is escaped.
+    is_escaped: bool,
+}
+
+impl Journal {
+    /// Replays the committed transactions in the journal, and then marks the journal empty.
+    pub(in crate::fs::ext2) fn recover(&self) -> Result<()> {
+        let mut super_block = self.super_block.lock();
+        if super_block.start.get() == 0 {
+            return Ok(());
+        }
+
+        // Pass 1: Find the committed transactions and the revoked blocks.
+        let mut sequence = super_block.sequence.get();
+        let mut journal_bid = super_block.start.get();
+        let mut committed_tags: Vec<(u32, Vec<LoggedTag>)> = Vec::new();
+        let mut revoked_blocks: BTreeMap<Ext2Bid, u32> = BTreeMap::new();
+        let mut tags = Vec::new();
+        let mut revokes = Vec::new();
+        loop {
+            let block = self.read_block(journal_bid)?;
+            let header = RawHeader::from_bytes(&block);
+            if header.sequence.get() != sequence {
+                break;
+            }
+
+            match header.block_type() {
+                Some(BlockType::Descriptor) => {
+                    for (bid, flags) in self.parse_descriptor(&block)? {
+                        journal_bid = self.next_journal_bid(journal_bid);
+                        tags.push(LoggedTag {
+                            journal_bid,
+                            bid,
+                            is_escaped: flags.contains(TagFlags::ESCAPE),
+                        });
+                    }
+                }
+                Some(BlockType::Revoke) => {
+                    revokes.extend(self.parse_revoke(&block)?);
+                }
+                Some(BlockType::Commit) => {
+                    committed_tags.push((sequence, core::mem::take(&mut tags)));
+                    for bid in revokes.drain(..) {
+                        revoked_blocks.insert(bid, sequence);
+                    }
+                    sequence = sequence.wrapping_add(1);
+                }
+                _ => break,
+            }
+            journal_bid = self.next_journal_bid(journal_bid);
+        }
+
+        // Pass 2: Replay the blocks that are not revoked. Only the latest copy of
+        // each block is written, so the writes can be submitted at once.
+        let mut latest_tags = BTreeMap::new();
+        for (tag_sequence, tags) in committed_tags {
+            for tag in tags {
+                let is_revoked = revoked_blocks.get(&tag.bid).is_some_and(|revoke_sequence| {
+                    // The sequence numbers may wrap around.
+                    revoke_sequence.wrapping_sub(tag_sequence) as i32 >= 0
+                });
+                if is_revoked {
+                    latest_tags.remove(&tag.bid);
+                } else {
+                    latest_tags.insert(tag.bid, tag);
+                }
+            }
+        }
+
+        let mut bio_waiter = BioWaiter::new();
+        for tag in latest_tags.values() {
+            let mut block = self.read_block(tag.journal_bid)?;
+            if tag.is_escaped {
+                block[..size_of::<u32>()].copy_from_slice(&JBD2_MAGIC_NUM.to_be_bytes());
+            }
+            bio_waiter.concat(
+                self.block_device
+                    .write_bytes_async(tag.bid as usize * BLOCK_SIZE, &block)?,
+            );
+        }
+        Self::wait(bio_waiter)?;
+        self.flush()?;
+
+        // Skip the sequence number of the uncommitted transaction, if any.
+        super_block.start = Be32::new(0);
+        super_block.sequence = Be32::new(sequence.wrapping_add(1));
+        Self::wait(self.write_super_block_async(&super_block)?)?;
+        self.flush()?;
+
+        if !latest_tags.is_empty() {
+            info!(
+                "ext3: recovered {} blocks from the journal",
+                latest_tags.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// Discards the contents of the journal without replaying them.
+    ///
+    /// This is needed if the filesystem does not require recovery, but the journal is not empty.
+    pub(in crate::fs::ext2) fn wipe(&self) -> Result<()> {
+        let mut super_block = self.super_block.lock();
+        if super_block.start.get() == 0 {
+            return Ok(());
+        }
+
+        warn!("ext3: the journal is not empty, but the filesystem does not need recovery");
+        super_block.start = Be32::new(0);
+        Self::wait(self.write_super_block_async(&super_block)?)?;
+        self.flush()
+    }
+
+    /// Parses the tags in a descriptor block.
+    fn parse_descriptor(&self, block: &[u8]) -> Result<Vec<(Ext2Bid, TagFlags)>> {
+        let tag_size = RawBlockTag::size(self.features);
+        let mut tags = Vec::new();
+        let mut offset = size_of::<RawHeader>();
+        while offset + tag_size <= BLOCK_SIZE {
+            let mut tag_bytes = [0u8; size_of::<RawBlockTag>()];
+            tag_bytes[..tag_size].copy_from_slice(&block[offset..offset + tag_size]);
+            let tag = RawBlockTag::from_bytes(&tag_bytes);
+            offset += tag_size;
+
+            let bid = {
+                let bid_high = if self.features.contains(JournalFeatureInCompatSet::BIT64) {
+                    tag.block_nr_high.get() as u64
+                } else {
+                    0
+                };
+                Ext2Bid::try_from(bid_high << 32 | tag.block_nr.get() as u64).map_err(|_| {
+                    Error::with_message(Errno::EINVAL, "invalid block number in the journal")
+                })?
+            };
+            let flags = TagFlags::from_bits_truncate(tag.flags.get());
+            tags.push((bid, flags));
+
+            if !flags.contains(TagFlags::SAME_UUID) {
+                offset += UUID_SIZE;
+            }
+            if flags.contains(TagFlags::LAST_TAG) {
+                break;
+            }
+        }
+        Ok(tags)
+    }
+
+    /// Parses the revoked block numbers in a revoke block.
+    fn parse_revoke(&self, block: &[u8]) -> Result<Vec<Ext2Bid>> {
+        let header = RawRevokeHeader::from_bytes(block);
+        let count = header.count.get() as usize;
+        if count > BLOCK_SIZE || count < size_of::<RawRevokeHeader>() {
+            return_errno_with_message!(Errno::EINVAL, "invalid revoke block in the journal");
+        }
+
+        let record_size = if self.features.contains(JournalFeatureInCompatSet::BIT64) {
+            size_of::<u64>()
+        } else {
+            size_of::<u32>()
+        };
+        block[size_of::<RawRevokeHeader>()..count]
+            .chunks_exact(record_size)
+            .map(|record| {
+                let mut bytes = [0u8; size_of::<u64>()];
+                bytes[size_of::<u64>() - record_size..].copy_from_slice(record);
+                Ext2Bid::try_from(u64::from_be_bytes(bytes)).map_err(|_| {
+                    Error::with_message(Errno::EINVAL, "invalid block number in the journal")
+                })
+            })
+            .collect()
+    }
+
+    fn next_journal_bid(&self, journal_bid: u32) -> u32 {
+        let next = journal_bid + 1;
+        if next == self.max_len {
+            self.first
+        } else {
+            next
+        }
+    }
+}
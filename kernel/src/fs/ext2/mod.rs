@@ -14,6 +14,13 @@
 //!    stored in PageCache, which accelerates the performance of data access.
 //! 3. Compatible with queue-based block device. The filesystem can submits multiple
 //!    BIO requests to be block device at once, thereby enhancing I/O performance.
+//! 4. Supports the journal of Ext3. If the filesystem has a journal, the metadata is
+//!    committed to the journal in the JBD2 format with the ordered data mode,
+//!    and the journal is replayed at mount time after a crash.
+//! 5. Supports the common features of Ext4, including the extent-mapped files, the
+//!    hashed directories, the 64-bit layout, the flexible block groups and the
+//!    metadata checksums, so that an Ext4 without the journal can be mounted
+//!    read-write.
 //!
 //! # Example
 //!
@@ -35,25 +42,34 @@
 //! Here we summarizes the features that need to be implemented in the future.
 //! 1. Supports merging small read/write operations.
 //! 2. Handles the intermediate failure status correctly.
+//! 3. Supports the block sizes other than 4096 bytes and the device blocks beyond 32 bits.
+//! 4. Supports the inline data, the encryption and the case-folded directories of Ext4.
 
 pub use fs::Ext2;
 pub use inode::{FilePerm, Inode};
 pub use super_block::{SuperBlock, MAGIC_NUM};
 
-use crate::fs::ext2::fs::Ext2Type;
+use crate::fs::ext2::fs::{Ext2Type, Ext3Type, Ext4Type};
 
 mod block_group;
 mod block_ptr;
 mod dir;
+mod extent;
 mod fs;
+mod hash;
 mod impl_for_vfs;
 mod indirect_block_cache;
 mod inode;
+mod journal;
 mod prelude;
 mod super_block;
+#[cfg(ktest)]
+mod test_utils;
 mod utils;
 mod xattr;
 
 pub(super) fn init() {
     super::registry::register(&Ext2Type).unwrap();
+    super::registry::register(&Ext3Type).unwrap();
+    super::registry::register(&Ext4Type).unwrap();
 }
@@ -1,8 +1,14 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::mem::offset_of;
+
 use ostd::const_assert;
 
-use super::{inode::RawInode, prelude::*};
+use super::{
+    inode::{RawInode, RawInodeExtra},
+    prelude::*,
+    utils::crc32c,
+};
 
 /// The magic number of Ext2.
 pub const MAGIC_NUM: u16 = 0xef53;
@@ -12,6 +18,12 @@ pub const SUPER_BLOCK_OFFSET: usize = 1024;
 
 const SUPER_BLOCK_SIZE: usize = 1024;
 
+/// The size of group descriptors if the 64-bit feature is not set.
+const MIN_DESC_SIZE: usize = 32;
+
+/// The size of group descriptors with the upper halves if the 64-bit feature is set.
+const MIN_DESC_SIZE_64BIT: usize = 64;
+
 /// The in-memory rust superblock.
 ///
 /// It contains all information about the layout of the Ext2.
@@ -93,12 +105,93 @@ pub struct SuperBlock {
     prealloc_file_blocks: u8,
     /// Number of blocks to preallocate for directories.
     prealloc_dir_blocks: u8,
+    ///
+    /// These fields are for journaling support in Ext3.
+    ///
+    /// Uuid of journal superblock.
+    journal_uuid: [u8; 16],
+    /// Inode number of journal file.
+    journal_ino: u32,
+    /// Device number of journal file.
+    journal_dev: u32,
+    /// Start of list of inodes to delete.
+    last_orphan: u32,
+    ///
+    /// These fields are for hashed directories.
+    ///
+    /// The seed of the hash function.
+    hash_seed: [u32; 4],
+    /// Default hash version to use.
+    def_hash_version: u8,
+    /// Miscellaneous flags.
+    flags: u32,
+    ///
+    /// These fields are valid if the FeatureInCompatSet::BIT64 is set.
+    ///
+    /// Size of group descriptors.
+    desc_size: u16,
+    ///
+    /// These fields are valid if the FeatureRoCompatSet::EXTRA_ISIZE is set.
+    ///
+    /// All inodes have at least this many extra bytes.
+    min_extra_isize: u16,
+    /// New inodes should reserve this many extra bytes.
+    want_extra_isize: u16,
+    /// The seed of the metadata checksums (if FeatureInCompatSet::CSUM_SEED is set).
+    checksum_seed: u32,
+    //
+    // The following fields are not used by the driver,
+    // but must be preserved when writing back the superblock.
+    //
+    min_rev_level: u16,
+    algorithm_usage_bitmap: u32,
+    reserved_gdt_blocks: u16,
+    jnl_backup_type: u8,
+    default_mount_opts: u32,
+    first_meta_bg: u32,
+    mkfs_time: UnixTime,
+    jnl_blocks: [u32; 17],
+    reserved: [u32; 67],
+    reserved2: [u32; 98],
 }
 
 impl TryFrom<RawSuperBlock> for SuperBlock {
     type Error = crate::error::Error;
 
     fn try_from(sb: RawSuperBlock) -> Result<Self> {
+        let feature_ro_compat = {
+            let feature_ro_compat = FeatureRoCompatSet::from_bits(sb.feature_ro_compat)
+                .filter(|features| FeatureRoCompatSet::SUPPORTED.contains(*features))
+                .ok_or(Error::with_message(
+                    Errno::EINVAL,
+                    "unsupported feature ro compat set",
+                ))?;
+            if feature_ro_compat.contains(FeatureRoCompatSet::METADATA_CSUM)
+                && sb.checksum != sb.calc_checksum()
+            {
+                return_errno_with_message!(Errno::EBADMSG, "bad superblock checksum");
+            }
+            feature_ro_compat
+        };
+        let feature_incompat = FeatureInCompatSet::from_bits(sb.feature_incompat)
+            .filter(|features| FeatureInCompatSet::SUPPORTED.contains(*features))
+            .ok_or(Error::with_message(
+                Errno::EINVAL,
+                "unsupported feature incompat set",
+            ))?;
+        if feature_incompat.contains(FeatureInCompatSet::BIT64) {
+            let desc_size = sb.desc_size as usize;
+            if desc_size < MIN_DESC_SIZE_64BIT
+                || desc_size > BLOCK_SIZE
+                || !desc_size.is_power_of_two()
+            {
+                return_errno_with_message!(Errno::EINVAL, "invalid group descriptor size");
+            }
+            if sb.blocks_count_hi != 0 {
+                return_errno_with_message!(Errno::EFBIG, "too many blocks");
+            }
+        }
+
         Ok(Self {
             inodes_count: sb.inodes_count,
             blocks_count: sb.blocks_count,
@@ -163,17 +256,34 @@ impl TryFrom<RawSuperBlock> for SuperBlock {
             feature_compat: FeatureCompatSet::from_bits(sb.feature_compat).ok_or(
                 Error::with_message(Errno::EINVAL, "invalid feature compat set"),
             )?,
-            feature_incompat: FeatureInCompatSet::from_bits(sb.feature_incompat).ok_or(
-                Error::with_message(Errno::EINVAL, "invalid feature incompat set"),
-            )?,
-            feature_ro_compat: FeatureRoCompatSet::from_bits(sb.feature_ro_compat).ok_or(
-                Error::with_message(Errno::EINVAL, "invalid feature ro compat set"),
-            )?,
+            feature_incompat,
+            feature_ro_compat,
             uuid: sb.uuid,
             volume_name: sb.volume_name,
             last_mounted_dir: sb.last_mounted_dir,
             prealloc_file_blocks: sb.prealloc_file_blocks,
             prealloc_dir_blocks: sb.prealloc_dir_blocks,
+            journal_uuid: sb.journal_uuid,
+            journal_ino: sb.journal_ino,
+            journal_dev: sb.journal_dev,
+            last_orphan: sb.last_orphan,
+            hash_seed: sb.hash_seed,
+            def_hash_version: sb.def_hash_version,
+            flags: sb.flags,
+            desc_size: sb.desc_size,
+            min_extra_isize: sb.min_extra_isize,
+            want_extra_isize: sb.want_extra_isize,
+            checksum_seed: sb.checksum_seed,
+            min_rev_level: sb.min_rev_level,
+            algorithm_usage_bitmap: sb.algorithm_usage_bitmap,
+            reserved_gdt_blocks: sb.reserved_gdt_blocks,
+            jnl_backup_type: sb.jnl_backup_type,
+            default_mount_opts: sb.default_mount_opts,
+            first_meta_bg: sb.first_meta_bg,
+            mkfs_time: sb.mkfs_time,
+            jnl_blocks: sb.jnl_blocks,
+            reserved: sb.reserved,
+            reserved2: sb.reserved2,
         })
     }
 }
@@ -232,23 +342,124 @@ impl SuperBlock {
     }
 
     /// Returns the compatible feature set.
-    #[expect(dead_code)]
     pub fn feature_compat(&self) -> FeatureCompatSet {
         self.feature_compat
     }
 
     /// Returns the incompatible feature set.
-    #[expect(dead_code)]
     pub fn feature_incompat(&self) -> FeatureInCompatSet {
         self.feature_incompat
     }
 
+    /// Returns the inode number of the journal file.
+    ///
+    /// Zero means that the journal is on an external device.
+    pub fn journal_ino(&self) -> u32 {
+        self.journal_ino
+    }
+
+    /// Returns `true` if the journal needs to be replayed before using the filesystem.
+    pub fn needs_recovery(&self) -> bool {
+        self.feature_incompat.contains(FeatureInCompatSet::RECOVER)
+    }
+
+    /// Marks that the journal needs to be replayed before using the filesystem.
+    ///
+    /// The flag is kept set in memory while the filesystem is in use, so the superblock
+    /// committed through the journal always carries it. On the disk, the flag is cleared
+    /// while the journal is empty, see [`Journal::mark_clean`].
+    ///
+    /// [`Journal::mark_clean`]: super::journal::Journal::mark_clean
+    pub(super) fn set_needs_recovery(&mut self) {
+        self.feature_incompat |= FeatureInCompatSet::RECOVER;
+    }
+
     /// Returns the readonly-compatible feature set.
-    #[expect(dead_code)]
     pub fn feature_ro_compat(&self) -> FeatureRoCompatSet {
         self.feature_ro_compat
     }
 
+    /// Returns `true` if new files should be mapped by extents instead of block pointers.
+    pub fn has_extents(&self) -> bool {
+        self.feature_incompat.contains(FeatureInCompatSet::EXTENTS)
+    }
+
+    /// Returns `true` if the metadata blocks are protected by checksums.
+    pub fn has_metadata_csum(&self) -> bool {
+        self.feature_ro_compat
+            .contains(FeatureRoCompatSet::METADATA_CSUM)
+    }
+
+    /// Returns `true` if the group descriptors are protected by checksums,
+    /// which also allows the block groups to be left uninitialized.
+    pub fn has_group_desc_csum(&self) -> bool {
+        self.feature_ro_compat
+            .intersects(FeatureRoCompatSet::METADATA_CSUM | FeatureRoCompatSet::GDT_CSUM)
+    }
+
+    /// Returns the seed of the metadata checksums.
+    pub fn csum_seed(&self) -> u32 {
+        if self
+            .feature_incompat
+            .contains(FeatureInCompatSet::CSUM_SEED)
+        {
+            self.checksum_seed
+        } else {
+            crc32c(!0, &self.uuid)
+        }
+    }
+
+    /// Returns the 128-bit uuid of the volume.
+    pub fn uuid(&self) -> &[u8; 16] {
+        &self.uuid
+    }
+
+    /// Returns the size of group descriptors.
+    pub fn group_descriptor_size(&self) -> usize {
+        if self.feature_incompat.contains(FeatureInCompatSet::BIT64) {
+            self.desc_size as usize
+        } else {
+            MIN_DESC_SIZE
+        }
+    }
+
+    /// Returns the number of blocks occupied by the group descriptor table.
+    pub fn group_descriptor_blocks(&self) -> usize {
+        (self.block_groups_count() as usize * self.group_descriptor_size()).div_ceil(BLOCK_SIZE)
+    }
+
+    /// Returns the number of blocks reserved for the growth of the group descriptor table.
+    pub fn reserved_gdt_blocks(&self) -> usize {
+        self.reserved_gdt_blocks as usize
+    }
+
+    /// Returns the number of extra bytes that new inodes should reserve
+    /// beyond the original 128-byte inode.
+    pub fn want_extra_isize(&self) -> usize {
+        let extra_isize = (self.want_extra_isize as usize).max(self.min_extra_isize as usize);
+        if extra_isize == 0 {
+            // Use the size of all the extra fields by default.
+            size_of::<RawInodeExtra>()
+        } else {
+            extra_isize
+        }
+    }
+
+    /// Returns the seed of the hash function for hashed directories.
+    pub fn hash_seed(&self) -> [u32; 4] {
+        self.hash_seed
+    }
+
+    /// Returns the default hash version for hashed directories.
+    pub fn def_hash_version(&self) -> u8 {
+        self.def_hash_version
+    }
+
+    /// Returns `true` if the hash of directory entries treats the characters as unsigned.
+    pub fn is_hash_unsigned(&self) -> bool {
+        self.flags & FLAGS_UNSIGNED_HASH != 0
+    }
+
     /// Returns the number of free blocks.
     pub fn free_blocks_count(&self) -> u32 {
         self.free_blocks_count
@@ -344,6 +555,20 @@ bitflags! {
         const RESIZE_INO = 1 << 4;
         /// Directories use hash index
         const DIR_INDEX = 1 << 5;
+        /// Block groups are initialized lazily (obsolete)
+        const LAZY_BG = 1 << 6;
+        /// Snapshot exclusion inode (not used)
+        const EXCLUDE_INODE = 1 << 7;
+        /// Snapshot exclusion bitmaps
+        const EXCLUDE_BITMAP = 1 << 8;
+        /// Sparse superblocks with at most two backups
+        const SPARSE_SUPER2 = 1 << 9;
+        /// Journal has fast commit blocks
+        const FAST_COMMIT = 1 << 10;
+        /// Inode numbers do not change
+        const STABLE_INODES = 1 << 11;
+        /// Orphan inodes are tracked by a file
+        const ORPHAN_FILE = 1 << 12;
     }
 }
 
@@ -360,9 +585,41 @@ bitflags! {
         const JOURNAL_DEV = 1 << 3;
         /// Metablock block group
         const META_BG = 1 << 4;
+        /// Files are mapped by extents
+        const EXTENTS = 1 << 6;
+        /// File system can have more than 2^32 blocks
+        const BIT64 = 1 << 7;
+        /// Multiple mount protection
+        const MMP = 1 << 8;
+        /// Metadata of block groups are packed together
+        const FLEX_BG = 1 << 9;
+        /// Large xattr values are stored in inodes
+        const EA_INODE = 1 << 10;
+        /// Directory entries contain extra data
+        const DIRDATA = 1 << 12;
+        /// Checksum seed is stored in the superblock
+        const CSUM_SEED = 1 << 13;
+        /// Directories can be larger than 2GB or have a 3-level htree
+        const LARGEDIR = 1 << 14;
+        /// Data is stored in inodes
+        const INLINE_DATA = 1 << 15;
+        /// Encrypted inodes exist
+        const ENCRYPT = 1 << 16;
+        /// Directories can be case-insensitive
+        const CASEFOLD = 1 << 17;
     }
 }
 
+impl FeatureInCompatSet {
+    /// The features that are supported by the driver.
+    const SUPPORTED: Self = Self::FILETYPE
+        .union(Self::RECOVER)
+        .union(Self::EXTENTS)
+        .union(Self::BIT64)
+        .union(Self::FLEX_BG)
+        .union(Self::CSUM_SEED);
+}
+
 bitflags! {
     /// Readonly-compatible feature set.
     pub struct FeatureRoCompatSet: u32 {
@@ -372,9 +629,46 @@ bitflags! {
         const LARGE_FILE = 1 << 1;
         /// Directory contents are stored in the form of a Binary Tree
         const BTREE_DIR = 1 << 2;
+        /// File system can have files larger than 2TB
+        const HUGE_FILE = 1 << 3;
+        /// Group descriptors are protected by checksums
+        const GDT_CSUM = 1 << 4;
+        /// Directories can have more than 65000 subdirectories
+        const DIR_NLINK = 1 << 5;
+        /// Inodes have the extra fields
+        const EXTRA_ISIZE = 1 << 6;
+        /// Quota is stored in hidden inodes
+        const QUOTA = 1 << 8;
+        /// Blocks are allocated in clusters
+        const BIGALLOC = 1 << 9;
+        /// Metadata are protected by checksums
+        const METADATA_CSUM = 1 << 10;
+        /// File system must be mounted read-only
+        const READONLY = 1 << 12;
+        /// Project quota is supported
+        const PROJECT = 1 << 13;
+        /// Verity inodes exist
+        const VERITY = 1 << 15;
+        /// Orphan file may be non-empty
+        const ORPHAN_PRESENT = 1 << 16;
     }
 }
 
+impl FeatureRoCompatSet {
+    /// The features that are supported by the driver.
+    const SUPPORTED: Self = Self::SPARSE_SUPER
+        .union(Self::LARGE_FILE)
+        .union(Self::BTREE_DIR)
+        .union(Self::HUGE_FILE)
+        .union(Self::GDT_CSUM)
+        .union(Self::DIR_NLINK)
+        .union(Self::EXTRA_ISIZE)
+        .union(Self::METADATA_CSUM);
+}
+
+/// The hash of directory entries treats the characters as unsigned.
+const FLAGS_UNSIGNED_HASH: u32 = 1 << 1;
+
 #[repr(u16)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, TryFromInt)]
 pub enum FsState {
@@ -426,7 +720,7 @@ const_assert!(size_of::<RawSuperBlock>() == SUPER_BLOCK_SIZE);
 
 /// The raw superblock, it must be exactly 1024 bytes in length.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Pod, Default)]
+#[derive(Clone, Copy, Debug, Pod)]
 pub(super) struct RawSuperBlock {
     pub inodes_count: u32,
     pub blocks_count: u32,
@@ -470,7 +764,8 @@ pub(super) struct RawSuperBlock {
     pub algorithm_usage_bitmap: u32,
     pub prealloc_file_blocks: u8,
     pub prealloc_dir_blocks: u8,
-    padding1: u16,
+    /// Number of blocks reserved for the growth of the group descriptor table.
+    pub reserved_gdt_blocks: u16,
     ///
     /// This fields are for journaling support in Ext3.
     ///
@@ -486,18 +781,56 @@ pub(super) struct RawSuperBlock {
     pub hash_seed: [u32; 4],
     /// Default hash version to use
     pub def_hash_version: u8,
-    reserved_char_pad: u8,
-    reserved_word_pad: u16,
+    /// The type of the journal backup in `reserved`.
+    pub jnl_backup_type: u8,
+    /// Size of group descriptors (if the 64-bit feature is set).
+    pub desc_size: u16,
     /// Default mount options.
     pub default_mount_opts: u32,
     /// First metablock block group.
     pub first_meta_bg: u32,
-    reserved: Reserved,
+    /// When the filesystem was created.
+    pub mkfs_time: UnixTime,
+    /// Backup of the block pointers of the journal inode.
+    pub jnl_blocks: [u32; 17],
+    /// High 32 bits of the block count (if the 64-bit feature is set).
+    pub blocks_count_hi: u32,
+    /// High 32 bits of the reserved block count (if the 64-bit feature is set).
+    pub reserved_blocks_count_hi: u32,
+    /// High 32 bits of the free block count (if the 64-bit feature is set).
+    pub free_blocks_count_hi: u32,
+    /// All inodes have at least this many extra bytes.
+    pub min_extra_isize: u16,
+    /// New inodes should reserve this many extra bytes.
+    pub want_extra_isize: u16,
+    /// Miscellaneous flags.
+    pub flags: u32,
+    reserved: [u32; 67],
+    /// The seed of the metadata checksums (if the `CSUM_SEED` feature is set).
+    pub checksum_seed: u32,
+    reserved2: [u32; 98],
+    /// The checksum of the superblock (if the `METADATA_CSUM` feature is set).
+    pub checksum: u32,
+}
+
+impl RawSuperBlock {
+    /// Updates the checksum if the filesystem has metadata checksums.
+    ///
+    /// This must be called after modifying any field of the raw superblock.
+    pub fn update_checksum(&mut self) {
+        if self.feature_ro_compat & FeatureRoCompatSet::METADATA_CSUM.bits() != 0 {
+            self.checksum = self.calc_checksum();
+        }
+    }
+
+    fn calc_checksum(&self) -> u32 {
+        crc32c(!0, &self.as_bytes()[..offset_of!(Self, checksum)])
+    }
 }
 
 impl From<&SuperBlock> for RawSuperBlock {
     fn from(sb: &SuperBlock) -> Self {
-        Self {
+        let mut raw_super_block = Self {
             inodes_count: sb.inodes_count,
             blocks_count: sb.blocks_count,
             reserved_blocks_count: sb.reserved_blocks_count,
@@ -533,17 +866,33 @@ impl From<&SuperBlock> for RawSuperBlock {
             last_mounted_dir: sb.last_mounted_dir,
             prealloc_file_blocks: sb.prealloc_file_blocks,
             prealloc_dir_blocks: sb.prealloc_dir_blocks,
-            ..Default::default()
-        }
-    }
-}
-
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Pod)]
-struct Reserved([u32; 190]);
-
-impl Default for Reserved {
-    fn default() -> Self {
-        Self([0u32; 190])
+            reserved_gdt_blocks: sb.reserved_gdt_blocks,
+            journal_uuid: sb.journal_uuid,
+            journal_ino: sb.journal_ino,
+            journal_dev: sb.journal_dev,
+            last_orphan: sb.last_orphan,
+            min_rev_level: sb.min_rev_level,
+            algorithm_usage_bitmap: sb.algorithm_usage_bitmap,
+            hash_seed: sb.hash_seed,
+            def_hash_version: sb.def_hash_version,
+            jnl_backup_type: sb.jnl_backup_type,
+            desc_size: sb.desc_size,
+            default_mount_opts: sb.default_mount_opts,
+            first_meta_bg: sb.first_meta_bg,
+            mkfs_time: sb.mkfs_time,
+            jnl_blocks: sb.jnl_blocks,
+            blocks_count_hi: 0,
+            reserved_blocks_count_hi: 0,
+            free_blocks_count_hi: 0,
+            min_extra_isize: sb.min_extra_isize,
+            want_extra_isize: sb.want_extra_isize,
+            flags: sb.flags,
+            reserved: sb.reserved,
+            checksum_seed: sb.checksum_seed,
+            reserved2: sb.reserved2,
+            checksum: 0,
+        };
+        raw_super_block.update_checksum();
+        raw_super_block
     }
 }
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The utilities for the ktests of the filesystem.
+
+use aster_block::{
+    bio::{BioEnqueueError, BioType, SubmittedBio},
+    BlockDeviceMeta, SECTOR_SIZE,
+};
+use device_id::DeviceId;
+use ostd::mm::{io_util::HasVmReaderWriter, PAGE_SIZE};
+
+use super::prelude::*;
+
+/// The Ext3 image with 4096-byte blocks, made by `mke2fs -t ext3`.
+static EXT3_IMAGE: &[u8] = include_bytes!("../../../../test/build/ext3.img");
+
+/// The Ext4 image with 4096-byte blocks, made by `mkfs.ext4 -O ^has_journal`.
+///
+/// The root directory contains a hashed directory `large_dir`, which has
+/// [`LARGE_DIR_NR_FILES`] empty files named from `file_1`.
+static EXT4_IMAGE: &[u8] = include_bytes!("../../../../test/build/ext4.img");
+
+/// The number of files in `large_dir` of the Ext4 image.
+pub(super) const LARGE_DIR_NR_FILES: usize = 1000;
+
+/// A block device in memory.
+pub(super) struct MemoryDisk {
+    segment: Segment<()>,
+}
+
+impl MemoryDisk {
+    /// Creates a disk with the contents of `image`.
+    pub(super) fn new(image: &[u8]) -> Self {
+        let segment = FrameAllocOptions::new()
+            .zeroed(false)
+            .alloc_segment(image.len().div_ceil(PAGE_SIZE))
+            .unwrap();
+        segment.write_bytes(0, image).unwrap();
+        Self { segment }
+    }
+}
+
+impl Debug for MemoryDisk {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("MemoryDisk")
+            .field("size", &self.segment.size())
+            .finish()
+    }
+}
+
+impl BlockDevice for MemoryDisk {
+    fn enqueue(&self, bio: SubmittedBio) -> core::result::Result<(), BioEnqueueError> {
+        let mut offset = bio.sid_range().start.to_raw() as usize * SECTOR_SIZE;
+        for seg in bio.segments() {
+            let size = match bio.type_() {
+                BioType::Read => seg
+                    .inner_segment()
+                    .writer()
+                    .write(self.segment.reader().skip(offset)),
+                BioType::Write => self
+                    .segment
+                    .writer()
+                    .skip(offset)
+                    .write(&mut seg.inner_segment().reader()),
+                _ => 0,
+            };
+            offset += size;
+        }
+        bio.complete(BioStatus::Complete);
+        Ok(())
+    }
+
+    fn metadata(&self) -> BlockDeviceMeta {
+        BlockDeviceMeta {
+            max_nr_segments_per_bio: usize::MAX,
+            nr_sectors: self.segment.size() / SECTOR_SIZE,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "ext2_memory_disk"
+    }
+
+    fn id(&self) -> DeviceId {
+        todo!()
+    }
+}
+
+/// Creates a disk with a clean Ext3.
+pub(super) fn new_ext3_disk() -> Arc<dyn BlockDevice> {
+    Arc::new(MemoryDisk::new(EXT3_IMAGE))
+}
+
+/// Creates a disk with a clean Ext4 without the journal.
+pub(super) fn new_ext4_disk() -> Arc<dyn BlockDevice> {
+    Arc::new(MemoryDisk::new(EXT4_IMAGE))
+}
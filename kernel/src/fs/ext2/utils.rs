@@ -31,6 +31,66 @@ macro_rules! impl_ipo_for {
 
 impl_ipo_for!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, isize, usize);
 
+/// Computes the CRC32C (Castagnoli) checksum of `buf`, continuing from `crc`.
+///
+/// Like `ext4_chksum` in Linux, the checksum is neither pre- nor post-inverted,
+/// so the callers are responsible for passing the initial seed.
+pub fn crc32c(crc: u32, buf: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0x82f6_3b78
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    buf.iter().fold(crc, |crc, byte| {
+        TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Computes the CRC16 (ANSI) checksum of `buf`, continuing from `crc`.
+///
+/// This is used by the group descriptors of the filesystems
+/// with the `GDT_CSUM` feature but without the `METADATA_CSUM` feature.
+pub fn crc16(crc: u16, buf: &[u8]) -> u16 {
+    const TABLE: [u16; 256] = {
+        let mut table = [0u16; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u16;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xa001
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    buf.iter().fold(crc, |crc, byte| {
+        TABLE[((crc ^ *byte as u16) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
 /// The `Dirty` wraps a value of type `T` with functions similar to that of a rw-lock,
 /// but simply sets a dirty flag on `write()`.
 pub struct Dirty<T: Debug> {
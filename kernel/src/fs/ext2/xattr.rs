@@ -1,8 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::mem::offset_of;
+
 use ostd::mm::{io_util::HasVmReaderWriter, HasSize};
 
-use super::{block_ptr::Ext2Bid, prelude::*, Ext2, Inode};
+use super::{block_ptr::Ext2Bid, prelude::*, utils::crc32c, Ext2, Inode};
 use crate::fs::utils::{XattrName, XattrNamespace, XattrSetFlags, XATTR_NAME_MAX_LEN};
 
 const EXT2_XATTR_MAGIC: u32 = 0xEA020000;
@@ -16,7 +18,9 @@ struct XattrHeader {
     ref_count: u32,
     nblocks: u32,
     hash: u32,
-    reserved: [u32; 4],
+    /// The checksum of the block, if the filesystem has metadata checksums.
+    checksum: u32,
+    reserved: [u32; 3],
 }
 
 const XATTR_HEADER_SIZE: usize = size_of::<XattrHeader>();
@@ -132,8 +136,8 @@ impl Xattr {
             self.inode().set_acl(new_bid);
         // Need to load the xattr block from device
         } else if cache.header.is_none() {
-            fs.block_device().read_blocks(
-                cache.bid,
+            fs.read_blocks(
+                cache.bid.to_raw() as Ext2Bid,
                 BioSegment::new_from_segment(self.blocks_buf.clone(), BioDirection::FromDevice),
             )?;
 
@@ -328,10 +332,16 @@ impl Xattr {
     pub fn flush(&self) -> Result<()> {
         let cache = self.cache.upread();
         if cache.is_dirty() {
-            self.fs().block_device().write_blocks(
-                cache.bid,
+            if let Some(csum_seed) = self.fs().csum_seed() {
+                self.update_checksum(cache.bid, csum_seed)?;
+            }
+            let bio_waiter = self.fs().write_metadata_blocks_async(
+                cache.bid.to_raw() as Ext2Bid,
                 BioSegment::new_from_segment(self.blocks_buf.clone(), BioDirection::ToDevice),
             )?;
+            bio_waiter.wait().ok_or_else(|| {
+                Error::with_message(Errno::EIO, "failed to write back the xattr block")
+            })?;
             cache.upgrade().clear_dirty();
         }
         Ok(())
@@ -343,11 +353,26 @@ impl Xattr {
         if bid == 0 {
             return Ok(());
         }
-        self.fs().free_blocks(bid..bid)?;
+        self.fs().free_blocks(bid..bid + XATTR_NBLOCKS as Ext2Bid)?;
         cache.upgrade().bid = Bid::new(0);
         Ok(())
     }
 
+    /// Updates the checksum of the xattr block, which covers the block ID and the block.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/ext4/xattr.c#L136>
+    fn update_checksum(&self, bid: Bid, csum_seed: u32) -> Result<()> {
+        const CHECKSUM_OFFSET: usize = offset_of!(XattrHeader, checksum);
+
+        let mut block = vec![0u8; XATTR_NBLOCKS * BLOCK_SIZE];
+        self.blocks_buf.read_bytes(0, &mut block)?;
+        block[CHECKSUM_OFFSET..CHECKSUM_OFFSET + size_of::<u32>()].fill(0);
+        let checksum = crc32c(csum_seed, &bid.to_raw().to_le_bytes());
+        let checksum = crc32c(checksum, &block);
+        self.blocks_buf.write_val(CHECKSUM_OFFSET, &checksum)?;
+        Ok(())
+    }
+
     fn fs(&self) -> Arc<Ext2> {
         self.fs.upgrade().unwrap()
     }
@@ -482,6 +507,7 @@ impl Default for XattrHeader {
             nblocks: XATTR_NBLOCKS as _,
             ref_count: Default::default(),
             hash: Default::default(),
+            checksum: Default::default(),
             reserved: Default::default(),
         }
     }
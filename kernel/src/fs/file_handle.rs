@@ -12,6 +12,7 @@ use super::inode_handle::InodeHandle;
 use crate::{
     fs::{
         file_table::FdFlags,
+        notify::{publish_fs_events, FsEvents},
         utils::{AccessMode, FallocMode, Inode, IoctlCmd, SeekFrom, StatusFlags},
     },
     net::socket::Socket,
@@ -148,6 +149,15 @@ impl dyn FileLike {
             Error::with_message(Errno::EINVAL, "the file is not related to an inode")
         })
     }
+
+    /// Publishes the file system events that happen on the file.
+    pub fn notify_fs_events(&self, events: FsEvents) {
+        if let Some(inode_handle) = self.downcast_ref::<InodeHandle>() {
+            inode_handle.path().notify_fs_events(events);
+        } else {
+            publish_fs_events(self.inode().as_ref(), events, 0, None);
+        }
+    }
 }
 
 /// An object that may be memory mapped into the user address space.
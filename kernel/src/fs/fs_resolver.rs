@@ -11,6 +11,7 @@ use super::{
 };
 use crate::{
     fs::{
+        notify::{publish_fs_events, FsEvents},
         path::MountNamespace,
         ramfs::memfd::MemfdInode,
         utils::{Inode, SymbolicLink},
@@ -55,6 +56,19 @@ impl FsResolver {
         self.root = path;
     }
 
+    /// Replaces the root directory and the current working directory with `new_root` if
+    /// they are `old_root`.
+    ///
+    /// This is used after the root mount is changed by [`MountNamespace::pivot_root`].
+    pub fn replace_root(&mut self, old_root: &Path, new_root: &Path) {
+        if self.root == *old_root {
+            self.root = new_root.clone();
+        }
+        if self.cwd == *old_root {
+            self.cwd = new_root.clone();
+        }
+    }
+
     /// Switches the `FsResolver` to the given mount namespace.
     ///
     /// If the target namespace already owns both the current root and working directory's
@@ -404,6 +418,14 @@ impl PathOrInode {
         }
     }
 
+    /// Publishes the file system events that happen on the item.
+    pub fn notify_fs_events(&self, events: FsEvents) {
+        match self {
+            PathOrInode::Path(path) => path.notify_fs_events(events),
+            PathOrInode::Inode(inode) => publish_fs_events(inode.as_ref(), events, 0, None),
+        }
+    }
+
     pub fn display_name(&self) -> String {
         match self {
             PathOrInode::Path(path) => path.abs_path(),
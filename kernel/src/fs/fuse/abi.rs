@@ -0,0 +1,402 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The FUSE protocol between the kernel and the userspace daemons.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.17/source/include/uapi/linux/fuse.h>
+
+use crate::{fs::utils::InodeType, prelude::*};
+
+/// The major version of the protocol.
+pub(super) const FUSE_KERNEL_VERSION: u32 = 7;
+
+/// The minor version of the protocol that the kernel speaks.
+///
+/// The structures below follow the layouts of this minor version. Newer fields (e.g., the
+/// extended `setxattr` arguments) are not used.
+pub(super) const FUSE_KERNEL_MINOR_VERSION: u32 = 31;
+
+/// The minimum minor version of the protocol that the daemons must speak.
+pub(super) const FUSE_KERNEL_MIN_MINOR_VERSION: u32 = 12;
+
+/// The node ID of the root directory.
+pub(super) const FUSE_ROOT_ID: u64 = 1;
+
+/// The bit in the unique ID that marks an interrupt request.
+pub(super) const FUSE_INT_REQ_BIT: u64 = 1 << 0;
+
+/// The step between the unique IDs of two adjacent requests.
+///
+/// Since the lowest bit is reserved for [`FUSE_INT_REQ_BIT`], the unique IDs of normal requests
+/// are always even.
+pub(super) const FUSE_REQ_ID_STEP: u64 = 1 << 1;
+
+/// The minimum size of the buffer that the daemons use to read requests.
+pub(super) const FUSE_MIN_READ_BUFFER: usize = 8192;
+
+/// The magic number of FUSE file systems.
+pub(super) const FUSE_SUPER_MAGIC: u64 = 0x65735546;
+
+/// The flag in [`FuseInitIn`] and [`FuseInitOut`] that allows writes larger than a page.
+pub(super) const FUSE_BIG_WRITES: u32 = 1 << 5;
+
+/// The operation codes of the requests.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum FuseOpcode {
+    Lookup = 1,
+    Forget = 2,
+    Getattr = 3,
+    Setattr = 4,
+    Readlink = 5,
+    Mkdir = 9,
+    Unlink = 10,
+    Rmdir = 11,
+    Rename = 12,
+    Link = 13,
+    Open = 14,
+    Read = 15,
+    Write = 16,
+    Statfs = 17,
+    Release = 18,
+    Fsync = 20,
+    Setxattr = 21,
+    Getxattr = 22,
+    Listxattr = 23,
+    Removexattr = 24,
+    Init = 26,
+    Opendir = 27,
+    Readdir = 28,
+    Releasedir = 29,
+    Create = 35,
+    Interrupt = 36,
+}
+
+/// The header of every request.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseInHeader {
+    pub(super) len: u32,
+    pub(super) opcode: u32,
+    pub(super) unique: u64,
+    pub(super) nodeid: u64,
+    pub(super) uid: u32,
+    pub(super) gid: u32,
+    pub(super) pid: u32,
+    pub(super) padding: u32,
+}
+
+/// The header of every reply.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseOutHeader {
+    pub(super) len: u32,
+    pub(super) error: i32,
+    pub(super) unique: u64,
+}
+
+/// The attributes of a node.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Pod)]
+pub(super) struct FuseAttr {
+    pub(super) ino: u64,
+    pub(super) size: u64,
+    pub(super) blocks: u64,
+    pub(super) atime: u64,
+    pub(super) mtime: u64,
+    pub(super) ctime: u64,
+    pub(super) atimensec: u32,
+    pub(super) mtimensec: u32,
+    pub(super) ctimensec: u32,
+    pub(super) mode: u32,
+    pub(super) nlink: u32,
+    pub(super) uid: u32,
+    pub(super) gid: u32,
+    pub(super) rdev: u32,
+    pub(super) blksize: u32,
+    pub(super) padding: u32,
+}
+
+impl FuseAttr {
+    /// Returns the file type in the mode, or `None` if the file type is invalid.
+    pub(super) fn type_(&self) -> Option<InodeType> {
+        const S_IFMT: u32 = 0o170000;
+
+        InodeType::try_from((self.mode & S_IFMT) as u16)
+            .ok()
+            .filter(|type_| *type_ != InodeType::Unknown)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseEntryOut {
+    pub(super) nodeid: u64,
+    pub(super) generation: u64,
+    pub(super) entry_valid: u64,
+    pub(super) attr_valid: u64,
+    pub(super) entry_valid_nsec: u32,
+    pub(super) attr_valid_nsec: u32,
+    pub(super) attr: FuseAttr,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseForgetIn {
+    pub(super) nlookup: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseGetattrIn {
+    pub(super) getattr_flags: u32,
+    pub(super) dummy: u32,
+    pub(super) fh: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseAttrOut {
+    pub(super) attr_valid: u64,
+    pub(super) attr_valid_nsec: u32,
+    pub(super) dummy: u32,
+    pub(super) attr: FuseAttr,
+}
+
+bitflags! {
+    /// The fields that are valid in [`FuseSetattrIn`].
+    pub(super) struct SetattrValid: u32 {
+        const MODE      = 1 << 0;
+        const UID       = 1 << 1;
+        const GID       = 1 << 2;
+        const SIZE      = 1 << 3;
+        const ATIME     = 1 << 4;
+        const MTIME     = 1 << 5;
+        const FH        = 1 << 6;
+        const ATIME_NOW = 1 << 7;
+        const MTIME_NOW = 1 << 8;
+        const LOCKOWNER = 1 << 9;
+        const CTIME     = 1 << 10;
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Pod)]
+pub(super) struct FuseSetattrIn {
+    pub(super) valid: u32,
+    pub(super) padding: u32,
+    pub(super) fh: u64,
+    pub(super) size: u64,
+    pub(super) lock_owner: u64,
+    pub(super) atime: u64,
+    pub(super) mtime: u64,
+    pub(super) ctime: u64,
+    pub(super) atimensec: u32,
+    pub(super) mtimensec: u32,
+    pub(super) ctimensec: u32,
+    pub(super) mode: u32,
+    pub(super) unused4: u32,
+    pub(super) uid: u32,
+    pub(super) gid: u32,
+    pub(super) unused5: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseMkdirIn {
+    pub(super) mode: u32,
+    pub(super) umask: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseRenameIn {
+    pub(super) newdir: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseLinkIn {
+    pub(super) oldnodeid: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseOpenIn {
+    pub(super) flags: u32,
+    pub(super) open_flags: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseCreateIn {
+    pub(super) flags: u32,
+    pub(super) mode: u32,
+    pub(super) umask: u32,
+    pub(super) open_flags: u32,
+}
+
+bitflags! {
+    /// The flags in [`FuseOpenOut`].
+    pub(super) struct FopenFlags: u32 {
+        /// Bypasses the page cache for this open file.
+        const DIRECT_IO   = 1 << 0;
+        /// Does not invalidate the cached data on open.
+        const KEEP_CACHE  = 1 << 1;
+        /// The file is not seekable.
+        const NONSEEKABLE = 1 << 2;
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseOpenOut {
+    pub(super) fh: u64,
+    pub(super) open_flags: u32,
+    pub(super) padding: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseReleaseIn {
+    pub(super) fh: u64,
+    pub(super) flags: u32,
+    pub(super) release_flags: u32,
+    pub(super) lock_owner: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseReadIn {
+    pub(super) fh: u64,
+    pub(super) offset: u64,
+    pub(super) size: u32,
+    pub(super) read_flags: u32,
+    pub(super) lock_owner: u64,
+    pub(super) flags: u32,
+    pub(super) padding: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseWriteIn {
+    pub(super) fh: u64,
+    pub(super) offset: u64,
+    pub(super) size: u32,
+    pub(super) write_flags: u32,
+    pub(super) lock_owner: u64,
+    pub(super) flags: u32,
+    pub(super) padding: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseWriteOut {
+    pub(super) size: u32,
+    pub(super) padding: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseStatfsOut {
+    pub(super) blocks: u64,
+    pub(super) bfree: u64,
+    pub(super) bavail: u64,
+    pub(super) files: u64,
+    pub(super) ffree: u64,
+    pub(super) bsize: u32,
+    pub(super) namelen: u32,
+    pub(super) frsize: u32,
+    pub(super) padding: u32,
+    pub(super) spare: [u32; 6],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseFsyncIn {
+    pub(super) fh: u64,
+    pub(super) fsync_flags: u32,
+    pub(super) padding: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseSetxattrIn {
+    pub(super) size: u32,
+    pub(super) flags: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseGetxattrIn {
+    pub(super) size: u32,
+    pub(super) padding: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseGetxattrOut {
+    pub(super) size: u32,
+    pub(super) padding: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseInitIn {
+    pub(super) major: u32,
+    pub(super) minor: u32,
+    pub(super) max_readahead: u32,
+    pub(super) flags: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Pod)]
+pub(super) struct FuseInitOut {
+    pub(super) major: u32,
+    pub(super) minor: u32,
+    pub(super) max_readahead: u32,
+    pub(super) flags: u32,
+    pub(super) max_background: u16,
+    pub(super) congestion_threshold: u16,
+    pub(super) max_write: u32,
+    pub(super) time_gran: u32,
+    pub(super) max_pages: u16,
+    pub(super) map_alignment: u16,
+    pub(super) flags2: u32,
+    pub(super) unused: [u32; 7],
+}
+
+/// The minimum size of [`FuseInitOut`] that the daemons may reply with.
+///
+/// Daemons speaking older minor versions reply with a truncated structure.
+pub(super) const FUSE_COMPAT_22_INIT_OUT_SIZE: usize = 24;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseInterruptIn {
+    pub(super) unique: u64,
+}
+
+/// The header of a directory entry in the reply of `FUSE_READDIR`.
+///
+/// The header is followed by the name, which is padded to be 8-byte aligned.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct FuseDirent {
+    pub(super) ino: u64,
+    pub(super) off: u64,
+    pub(super) namelen: u32,
+    pub(super) type_: u32,
+}
+
+/// Returns the size of a directory entry whose name has `namelen` bytes.
+pub(super) const fn fuse_dirent_size(namelen: usize) -> usize {
+    (size_of::<FuseDirent>() + namelen).next_multiple_of(size_of::<u64>())
+}
+
+/// Parses the structure at the beginning of a reply.
+pub(super) fn parse_reply<T: Pod>(reply: &[u8]) -> Result<T> {
+    let Some(bytes) = reply.get(..size_of::<T>()) else {
+        return_errno_with_message!(Errno::EIO, "the FUSE reply is too short");
+    };
+    Ok(T::from_bytes(bytes))
+}
@@ -0,0 +1,543 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::VecDeque;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use ostd::{sync::WaitQueue, task::Task};
+
+use super::abi::{
+    FuseInHeader, FuseInitIn, FuseInitOut, FuseInterruptIn, FuseOpcode, FuseOutHeader,
+    FUSE_BIG_WRITES, FUSE_COMPAT_22_INIT_OUT_SIZE, FUSE_INT_REQ_BIT, FUSE_KERNEL_MINOR_VERSION,
+    FUSE_KERNEL_MIN_MINOR_VERSION, FUSE_KERNEL_VERSION, FUSE_MIN_READ_BUFFER, FUSE_REQ_ID_STEP,
+};
+use crate::{
+    events::IoEvents,
+    prelude::*,
+    process::{
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+};
+
+/// A connection between the kernel and a FUSE daemon.
+///
+/// A new connection is created each time `/dev/fuse` is opened. The file system mounted with the
+/// opened file sends requests via the connection, and the daemon reads the requests from and
+/// writes the replies to the opened file.
+pub struct FuseConn {
+    state: SpinLock<ConnState>,
+    next_unique: AtomicU64,
+    /// The wait queue for the requests that cannot be sent until the connection is initialized.
+    init_wait_queue: WaitQueue,
+    pollee: Pollee,
+}
+
+struct ConnState {
+    is_connected: bool,
+    is_mounted: bool,
+    /// The negotiated parameters, which are available once the `FUSE_INIT` request is replied.
+    init_info: Option<InitInfo>,
+    /// The requests that have not been read by the daemon.
+    pending: VecDeque<Arc<Request>>,
+    /// The requests that have been read by the daemon and are waiting for the replies.
+    processing: BTreeMap<u64, Arc<Request>>,
+}
+
+/// The parameters negotiated by the `FUSE_INIT` request.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct InitInfo {
+    /// The maximum size of the data in a `FUSE_WRITE` request.
+    pub(super) max_write: usize,
+}
+
+impl FuseConn {
+    /// Creates a new connection that is not mounted.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            state: SpinLock::new(ConnState {
+                is_connected: true,
+                is_mounted: false,
+                init_info: None,
+                pending: VecDeque::new(),
+                processing: BTreeMap::new(),
+            }),
+            next_unique: AtomicU64::new(FUSE_REQ_ID_STEP),
+            init_wait_queue: WaitQueue::new(),
+            pollee: Pollee::new(),
+        })
+    }
+
+    /// Mounts the connection and starts the initialization.
+    ///
+    /// The method does not wait for the daemon to reply to the `FUSE_INIT` request, since the
+    /// daemon typically starts to read requests only after the mount succeeds.
+    pub(super) fn mount(&self) -> Result<()> {
+        let mut state = self.state.lock();
+        if !state.is_connected {
+            return_errno_with_message!(Errno::EINVAL, "the FUSE connection is aborted");
+        }
+        if state.is_mounted {
+            return_errno_with_message!(Errno::EINVAL, "the FUSE connection is already mounted");
+        }
+        state.is_mounted = true;
+        drop(state);
+
+        let init_in = FuseInitIn {
+            major: FUSE_KERNEL_VERSION,
+            minor: FUSE_KERNEL_MINOR_VERSION,
+            max_readahead: MAX_READAHEAD as u32,
+            flags: FUSE_BIG_WRITES,
+        };
+        self.send_background(FuseOpcode::Init, 0, &[init_in.as_bytes()])
+    }
+
+    /// Waits for the connection to be initialized and returns the negotiated parameters.
+    pub(super) fn init_info(&self) -> Result<InitInfo> {
+        self.init_wait_queue.pause_until(|| {
+            let state = self.state.lock();
+            if !state.is_connected {
+                return Some(Err(Error::with_message(
+                    Errno::ENOTCONN,
+                    "the FUSE connection is aborted",
+                )));
+            }
+            state.init_info.map(Ok)
+        })?
+    }
+
+    /// Sends a request and waits for the reply.
+    ///
+    /// If the waiting is interrupted by a signal, the request is removed if the daemon has not
+    /// read it yet. Otherwise, a `FUSE_INTERRUPT` request is sent to the daemon, and the method
+    /// waits for the daemon to complete the request, which typically fails with [`EINTR`].
+    ///
+    /// [`EINTR`]: Errno::EINTR
+    pub(super) fn send(&self, opcode: FuseOpcode, nodeid: u64, args: &[&[u8]]) -> Result<Vec<u8>> {
+        self.init_info()?;
+
+        let request = Arc::new(Request::new(
+            self.alloc_unique(),
+            opcode,
+            nodeid,
+            RequestKind::Sync,
+            args,
+        ));
+        self.enqueue(request.clone())?;
+
+        match request.wait_queue.pause_until(|| request.take_reply()) {
+            Ok(reply) => reply,
+            Err(err) if err.error() == Errno::EINTR => self.interrupt(&request),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Sends a request whose reply will be discarded.
+    pub(super) fn send_background(
+        &self,
+        opcode: FuseOpcode,
+        nodeid: u64,
+        args: &[&[u8]],
+    ) -> Result<()> {
+        let request = Request::new(
+            self.alloc_unique(),
+            opcode,
+            nodeid,
+            RequestKind::Background,
+            args,
+        );
+        self.enqueue(Arc::new(request))
+    }
+
+    /// Sends a request that the daemon will not reply to.
+    pub(super) fn send_no_reply(
+        &self,
+        opcode: FuseOpcode,
+        nodeid: u64,
+        args: &[&[u8]],
+    ) -> Result<()> {
+        let request = Request::new(
+            self.alloc_unique(),
+            opcode,
+            nodeid,
+            RequestKind::NoReply,
+            args,
+        );
+        self.enqueue(Arc::new(request))
+    }
+
+    fn alloc_unique(&self) -> u64 {
+        self.next_unique
+            .fetch_add(FUSE_REQ_ID_STEP, Ordering::Relaxed)
+    }
+
+    fn enqueue(&self, request: Arc<Request>) -> Result<()> {
+        let mut state = self.state.lock();
+        if !state.is_connected {
+            return_errno_with_message!(Errno::ENOTCONN, "the FUSE connection is aborted");
+        }
+        state.pending.push_back(request);
+        drop(state);
+
+        self.pollee.notify(IoEvents::IN);
+        Ok(())
+    }
+
+    fn interrupt(&self, request: &Arc<Request>) -> Result<Vec<u8>> {
+        let mut state = self.state.lock();
+
+        if let Some(pos) = state
+            .pending
+            .iter()
+            .position(|pending| Arc::ptr_eq(pending, request))
+        {
+            state.pending.remove(pos);
+            return_errno_with_message!(
+                Errno::EINTR,
+                "the FUSE request is interrupted before being read"
+            );
+        }
+
+        if state.processing.contains_key(&request.unique) {
+            // Interrupt requests take precedence over the other requests.
+            state
+                .pending
+                .push_front(Arc::new(Request::new_interrupt(request.unique)));
+            drop(state);
+            self.pollee.notify(IoEvents::IN);
+        } else {
+            drop(state);
+        }
+
+        // Like Linux, wait for the reply without being interrupted again. The pending signal
+        // would wake us up immediately otherwise.
+        request.wait_queue.wait_until(|| request.take_reply())
+    }
+
+    /// Reads a request into the buffer of the daemon.
+    ///
+    /// The buffer must be large enough to hold any request. Otherwise, this method fails with
+    /// [`EINVAL`].
+    ///
+    /// [`EINVAL`]: Errno::EINVAL
+    pub fn read_request(&self, writer: &mut VmWriter, is_nonblocking: bool) -> Result<usize> {
+        if writer.avail() < FUSE_MIN_READ_BUFFER {
+            return_errno_with_message!(Errno::EINVAL, "the buffer is too small for FUSE requests");
+        }
+
+        match self.try_read_request(writer) {
+            Err(err) if err.error() == Errno::EAGAIN && !is_nonblocking => {
+                self.wait_events(IoEvents::IN, None, || self.try_read_request(writer))
+            }
+            result => result,
+        }
+    }
+
+    fn try_read_request(&self, writer: &mut VmWriter) -> Result<usize> {
+        loop {
+            let mut state = self.state.lock();
+            if !state.is_connected {
+                return_errno_with_message!(Errno::ENODEV, "the FUSE connection is aborted");
+            }
+
+            let Some(request) = state.pending.pop_front() else {
+                return_errno_with_message!(Errno::EAGAIN, "no FUSE requests are pending");
+            };
+            if state.pending.is_empty() {
+                self.pollee.invalidate();
+            }
+
+            if request.bytes.len() > writer.avail() {
+                drop(state);
+                request.complete(Err(Error::with_message(
+                    Errno::EIO,
+                    "the FUSE request does not fit in the buffer",
+                )));
+                continue;
+            }
+
+            if request.kind != RequestKind::NoReply {
+                state.processing.insert(request.unique, request.clone());
+            }
+            drop(state);
+
+            if let Err(err) = writer.write_fallible(&mut VmReader::from(request.bytes.as_slice())) {
+                self.state.lock().processing.remove(&request.unique);
+                request.complete(Err(Error::with_message(
+                    Errno::EIO,
+                    "the FUSE request cannot be copied to the buffer",
+                )));
+                return Err(err.into());
+            }
+
+            return Ok(request.bytes.len());
+        }
+    }
+
+    /// Writes a reply from the daemon.
+    pub fn write_reply(&self, reader: &mut VmReader) -> Result<usize> {
+        let len = reader.remain();
+        if len < size_of::<FuseOutHeader>() {
+            return_errno_with_message!(Errno::EINVAL, "the FUSE reply is too short");
+        }
+
+        let header = reader.read_val::<FuseOutHeader>()?;
+        if header.len as usize != len {
+            return_errno_with_message!(Errno::EINVAL, "the FUSE reply length is invalid");
+        }
+        if header.unique == 0 {
+            // TODO: Support notifications from the daemon.
+            return_errno_with_message!(Errno::EINVAL, "FUSE notifications are not supported");
+        }
+        if header.error > 0 || header.error <= -(Errno::ERESTARTSYS as i32) {
+            return_errno_with_message!(Errno::EINVAL, "the FUSE reply error is invalid");
+        }
+
+        let mut payload = vec![0u8; reader.remain()];
+        reader.read_fallible(&mut VmWriter::from(payload.as_mut_slice()))?;
+
+        let mut state = self.state.lock();
+        if !state.is_connected {
+            return_errno_with_message!(Errno::ENOENT, "the FUSE connection is aborted");
+        }
+
+        if header.unique & FUSE_INT_REQ_BIT != 0 {
+            // The daemon asks us to resend the interrupt request if it has not seen the
+            // interrupted request yet. Other replies to interrupt requests are ignored.
+            let unique = header.unique & !FUSE_INT_REQ_BIT;
+            if header.error == -(Errno::EAGAIN as i32) && state.processing.contains_key(&unique) {
+                state
+                    .pending
+                    .push_back(Arc::new(Request::new_interrupt(unique)));
+                drop(state);
+                self.pollee.notify(IoEvents::IN);
+            }
+            return Ok(len);
+        }
+
+        let Some(request) = state.processing.remove(&header.unique) else {
+            return_errno_with_message!(Errno::ENOENT, "the FUSE request does not exist");
+        };
+        drop(state);
+
+        let reply = if header.error == 0 {
+            Ok(payload)
+        } else {
+            let errno = Errno::try_from(-header.error).unwrap_or(Errno::EIO);
+            Err(Error::with_message(
+                errno,
+                "the FUSE daemon replies with an error",
+            ))
+        };
+
+        if request.opcode == FuseOpcode::Init {
+            self.finish_init(reply);
+        } else {
+            request.complete(reply);
+        }
+
+        Ok(len)
+    }
+
+    fn finish_init(&self, reply: Result<Vec<u8>>) {
+        let init_out = match reply {
+            Ok(bytes) if bytes.len() >= FUSE_COMPAT_22_INIT_OUT_SIZE => {
+                // Older daemons reply with truncated structures, whose missing fields are zeros.
+                let mut init_out = FuseInitOut::new_zeroed();
+                let len = bytes.len().min(size_of::<FuseInitOut>());
+                init_out.as_bytes_mut()[..len].copy_from_slice(&bytes[..len]);
+                init_out
+            }
+            _ => {
+                warn!("the FUSE daemon fails to initialize the connection");
+                self.abort();
+                return;
+            }
+        };
+
+        if init_out.major != FUSE_KERNEL_VERSION || init_out.minor < FUSE_KERNEL_MIN_MINOR_VERSION {
+            warn!(
+                "the FUSE protocol version {}.{} is not supported",
+                init_out.major, init_out.minor
+            );
+            self.abort();
+            return;
+        }
+
+        let init_info = InitInfo {
+            max_write: (init_out.max_write as usize).max(PAGE_SIZE),
+        };
+        self.state.lock().init_info = Some(init_info);
+        self.init_wait_queue.wake_all();
+    }
+
+    /// Aborts the connection.
+    ///
+    /// All the requests that are not completed fail with [`ECONNABORTED`], and the new requests
+    /// fail with [`ENOTCONN`]. The daemon will fail to read requests with [`ENODEV`].
+    ///
+    /// [`ECONNABORTED`]: Errno::ECONNABORTED
+    /// [`ENOTCONN`]: Errno::ENOTCONN
+    /// [`ENODEV`]: Errno::ENODEV
+    pub fn abort(&self) {
+        let mut state = self.state.lock();
+        if !state.is_connected {
+            return;
+        }
+        state.is_connected = false;
+
+        let mut requests: Vec<_> = state.pending.drain(..).collect();
+        requests.extend(core::mem::take(&mut state.processing).into_values());
+        drop(state);
+
+        for request in requests {
+            request.complete(Err(Error::with_message(
+                Errno::ECONNABORTED,
+                "the FUSE connection is aborted",
+            )));
+        }
+
+        self.init_wait_queue.wake_all();
+        self.pollee.notify(IoEvents::ERR);
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let state = self.state.lock();
+        if !state.is_connected {
+            return IoEvents::ERR;
+        }
+
+        let mut events = IoEvents::OUT;
+        if !state.pending.is_empty() {
+            events |= IoEvents::IN;
+        }
+        events
+    }
+}
+
+impl Pollable for FuseConn {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl Debug for FuseConn {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("FuseConn")
+            .field("is_connected", &state.is_connected)
+            .field("is_mounted", &state.is_mounted)
+            .field("init_info", &state.init_info)
+            .field("num_pending", &state.pending.len())
+            .field("num_processing", &state.processing.len())
+            .finish()
+    }
+}
+
+/// The maximum size of the readahead, which the daemon may take as a hint.
+const MAX_READAHEAD: usize = 32 * PAGE_SIZE;
+
+struct Request {
+    unique: u64,
+    opcode: FuseOpcode,
+    kind: RequestKind,
+    /// The bytes of the request, including the header.
+    bytes: Vec<u8>,
+    /// The reply, which is available once the request is completed.
+    reply: SpinLock<Option<Result<Vec<u8>>>>,
+    wait_queue: WaitQueue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RequestKind {
+    /// The request is sent on behalf of the current thread, which waits for the reply.
+    Sync,
+    /// The request expects a reply, but nobody waits for it.
+    Background,
+    /// The request does not expect a reply.
+    NoReply,
+}
+
+impl Request {
+    fn new(
+        unique: u64,
+        opcode: FuseOpcode,
+        nodeid: u64,
+        kind: RequestKind,
+        args: &[&[u8]],
+    ) -> Self {
+        // Like Linux, only the requests sent on behalf of the current thread carry the
+        // credentials.
+        let (uid, gid, pid) = if kind == RequestKind::Sync {
+            current_ids()
+        } else {
+            (0, 0, 0)
+        };
+
+        let len = size_of::<FuseInHeader>() + args.iter().map(|arg| arg.len()).sum::<usize>();
+        let header = FuseInHeader {
+            len: len as u32,
+            opcode: opcode as u32,
+            unique,
+            nodeid,
+            uid,
+            gid,
+            pid,
+            padding: 0,
+        };
+
+        let mut bytes = Vec::with_capacity(len);
+        bytes.extend_from_slice(header.as_bytes());
+        for arg in args {
+            bytes.extend_from_slice(arg);
+        }
+
+        Self {
+            unique,
+            opcode,
+            kind,
+            bytes,
+            reply: SpinLock::new(None),
+            wait_queue: WaitQueue::new(),
+        }
+    }
+
+    /// Creates a `FUSE_INTERRUPT` request for the request with the unique ID.
+    fn new_interrupt(unique: u64) -> Self {
+        let interrupt_in = FuseInterruptIn { unique };
+        Self::new(
+            unique | FUSE_INT_REQ_BIT,
+            FuseOpcode::Interrupt,
+            0,
+            RequestKind::NoReply,
+            &[interrupt_in.as_bytes()],
+        )
+    }
+
+    fn complete(&self, reply: Result<Vec<u8>>) {
+        *self.reply.lock() = Some(reply);
+        self.wait_queue.wake_all();
+    }
+
+    fn take_reply(&self) -> Option<Result<Vec<u8>>> {
+        self.reply.lock().take()
+    }
+}
+
+/// Returns the filesystem user ID, the filesystem group ID, and the thread ID of the current
+/// thread.
+fn current_ids() -> (u32, u32, u32) {
+    let Some(task) = Task::current() else {
+        return (0, 0, 0);
+    };
+    let Some(thread) = task.as_posix_thread() else {
+        return (0, 0, 0);
+    };
+
+    let credentials = thread.credentials();
+    (
+        credentials.fsuid().into(),
+        credentials.fsgid().into(),
+        thread.tid(),
+    )
+}
@@ -0,0 +1,317 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::task::Task;
+
+use super::{
+    abi::{
+        FuseAttr, FuseEntryOut, FuseForgetIn, FuseOpcode, FuseStatfsOut, FUSE_ROOT_ID,
+        FUSE_SUPER_MAGIC,
+    },
+    conn::FuseConn,
+    inode::FuseInode,
+};
+use crate::{
+    device::misc::fuse::conn_of_file,
+    fs::{
+        file_table::FileDesc,
+        registry::{FsProperties, FsType},
+        utils::{FileSystem, FsFlags, Inode, InodeType, SuperBlock, NAME_MAX},
+    },
+    prelude::*,
+    process::{Gid, Uid},
+};
+
+/// A file system whose operations are served by a userspace daemon.
+pub(super) struct FuseFs {
+    conn: Arc<FuseConn>,
+    root: Arc<FuseInode>,
+    /// The inodes that are alive, indexed by their node IDs.
+    inodes: Mutex<BTreeMap<u64, Weak<FuseInode>>>,
+    config: FuseConfig,
+}
+
+/// The configurations specified by the mount options.
+#[derive(Debug)]
+pub(super) struct FuseConfig {
+    /// The user ID of the mount owner.
+    pub(super) user_id: Uid,
+    /// The group ID of the mount owner.
+    pub(super) group_id: Gid,
+    /// Whether the kernel checks the permissions according to the file modes.
+    ///
+    /// Otherwise, the permission checks are left to the daemon.
+    pub(super) default_permissions: bool,
+    /// Whether users other than the mount owner can access the file system.
+    pub(super) allow_other: bool,
+    /// The maximum size of the data in a `FUSE_READ` request.
+    pub(super) max_read: usize,
+}
+
+impl FuseFs {
+    fn new(conn: Arc<FuseConn>, root_mode: u32, config: FuseConfig) -> Arc<Self> {
+        // The attributes of the root will be fetched from the daemon when needed.
+        let root_attr = FuseAttr {
+            ino: FUSE_ROOT_ID,
+            mode: root_mode,
+            nlink: 1,
+            uid: config.user_id.into(),
+            gid: config.group_id.into(),
+            ..Default::default()
+        };
+
+        let fs = Arc::new_cyclic(|weak_fs| Self {
+            conn: conn.clone(),
+            root: FuseInode::new(
+                FUSE_ROOT_ID,
+                InodeType::Dir,
+                root_attr,
+                weak_fs.clone(),
+                conn,
+            ),
+            inodes: Mutex::new(BTreeMap::new()),
+            config,
+        });
+        fs.inodes
+            .lock()
+            .insert(FUSE_ROOT_ID, Arc::downgrade(&fs.root));
+
+        fs
+    }
+
+    pub(super) fn config(&self) -> &FuseConfig {
+        &self.config
+    }
+
+    /// Gets the inode for a directory entry replied by the daemon.
+    ///
+    /// If the inode is alive, its attributes are updated and the lookup count is increased.
+    /// Otherwise, a new inode is created.
+    pub(super) fn get_or_create_inode(
+        self: &Arc<Self>,
+        entry: &FuseEntryOut,
+    ) -> Result<Arc<FuseInode>> {
+        let Some(type_) = entry.attr.type_() else {
+            // The daemon has counted the lookup, which must be undone.
+            let forget_in = FuseForgetIn { nlookup: 1 };
+            let _ =
+                self.conn
+                    .send_no_reply(FuseOpcode::Forget, entry.nodeid, &[forget_in.as_bytes()]);
+            return_errno_with_message!(Errno::EIO, "the file type of the entry is invalid");
+        };
+
+        let mut inodes = self.inodes.lock();
+
+        if let Some(inode) = inodes.get(&entry.nodeid).and_then(Weak::upgrade) {
+            // The lock must be released before the inode may be dropped.
+            drop(inodes);
+
+            inode.inc_nlookup();
+            if inode.type_() != type_ {
+                return_errno_with_message!(Errno::EIO, "the file type of the entry is changed");
+            }
+            inode.update_attr(&entry.attr, entry.attr_valid, entry.attr_valid_nsec);
+            return Ok(inode);
+        }
+
+        let inode = FuseInode::new(
+            entry.nodeid,
+            type_,
+            entry.attr,
+            Arc::downgrade(self),
+            self.conn.clone(),
+        );
+        inode.set_attr_valid(entry.attr_valid, entry.attr_valid_nsec);
+        inodes.insert(entry.nodeid, Arc::downgrade(&inode));
+
+        Ok(inode)
+    }
+
+    /// Removes the inode from the table of alive inodes.
+    pub(super) fn remove_inode(&self, nodeid: u64, inode: &Weak<FuseInode>) {
+        let mut inodes = self.inodes.lock();
+
+        // A new inode may have been created for the same node ID after the old one is dead.
+        if inodes
+            .get(&nodeid)
+            .is_some_and(|alive| Weak::ptr_eq(alive, inode))
+        {
+            inodes.remove(&nodeid);
+        }
+    }
+
+    fn alive_inodes(&self) -> Vec<Arc<FuseInode>> {
+        self.inodes
+            .lock()
+            .values()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+}
+
+impl FileSystem for FuseFs {
+    fn name(&self) -> &'static str {
+        "fuse"
+    }
+
+    fn sync(&self) -> Result<()> {
+        for inode in self.alive_inodes() {
+            inode.flush_page_cache()?;
+        }
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sb(&self) -> SuperBlock {
+        let mut sb = SuperBlock::new(FUSE_SUPER_MAGIC, PAGE_SIZE, NAME_MAX);
+
+        let Ok(reply) = self.conn.send(FuseOpcode::Statfs, FUSE_ROOT_ID, &[]) else {
+            return sb;
+        };
+        if reply.len() < size_of::<FuseStatfsOut>() {
+            return sb;
+        }
+
+        let statfs_out = FuseStatfsOut::from_bytes(&reply);
+        sb.bsize = statfs_out.bsize as usize;
+        sb.blocks = statfs_out.blocks as usize;
+        sb.bfree = statfs_out.bfree as usize;
+        sb.bavail = statfs_out.bavail as usize;
+        sb.files = statfs_out.files as usize;
+        sb.ffree = statfs_out.ffree as usize;
+        sb.namelen = statfs_out.namelen as usize;
+        sb.frsize = statfs_out.frsize as usize;
+        sb
+    }
+}
+
+impl Drop for FuseFs {
+    fn drop(&mut self) {
+        // The daemon will be notified that the file system is unmounted.
+        self.conn.abort();
+    }
+}
+
+pub(super) struct FuseFsType;
+
+impl FsType for FuseFsType {
+    fn name(&self) -> &'static str {
+        "fuse"
+    }
+
+    fn properties(&self) -> FsProperties {
+        FsProperties::empty()
+    }
+
+    fn create(
+        &self,
+        _flags: FsFlags,
+        args: Option<CString>,
+        _disk: Option<Arc<dyn aster_block::BlockDevice>>,
+    ) -> Result<Arc<dyn FileSystem>> {
+        let args = args.ok_or(Error::with_message(
+            Errno::EINVAL,
+            "the FUSE mount options are missing",
+        ))?;
+        let options = MountOptions::parse(&args.to_string_lossy())?;
+
+        let conn = {
+            let task = Task::current().unwrap();
+            let thread_local = task.as_thread_local().unwrap();
+            let file_table = thread_local.borrow_file_table();
+            let file_table_locked = file_table.unwrap().read();
+            let file = file_table_locked.get_file(options.fd)?;
+            conn_of_file(file.as_ref())?
+        };
+
+        let config = FuseConfig {
+            user_id: options.user_id,
+            group_id: options.group_id,
+            default_permissions: options.default_permissions,
+            allow_other: options.allow_other,
+            // Like Linux, at least one page can be read in a request.
+            max_read: options.max_read.max(PAGE_SIZE),
+        };
+        conn.mount()?;
+
+        Ok(FuseFs::new(conn, options.root_mode, config))
+    }
+
+    fn sysnode(&self) -> Option<Arc<dyn aster_systree::SysNode>> {
+        None
+    }
+}
+
+/// The mount options of FUSE file systems.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/fuse/inode.c#L776>
+struct MountOptions {
+    fd: FileDesc,
+    root_mode: u32,
+    user_id: Uid,
+    group_id: Gid,
+    default_permissions: bool,
+    allow_other: bool,
+    max_read: usize,
+}
+
+impl MountOptions {
+    fn parse(args: &str) -> Result<Self> {
+        let mut fd = None;
+        let mut root_mode = None;
+        let mut user_id = None;
+        let mut group_id = None;
+        let mut default_permissions = false;
+        let mut allow_other = false;
+        let mut max_read = u32::MAX as usize;
+
+        for entry in args.split(',').filter(|entry| !entry.is_empty()) {
+            let (key, value) = match entry.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (entry, None),
+            };
+
+            match (key, value) {
+                ("fd", Some(value)) => fd = Some(parse_value(value, 10)?),
+                ("rootmode", Some(value)) => root_mode = Some(parse_value(value, 8)?),
+                ("user_id", Some(value)) => user_id = Some(Uid::new(parse_value(value, 10)?)),
+                ("group_id", Some(value)) => group_id = Some(Gid::new(parse_value(value, 10)?)),
+                ("default_permissions", None) => default_permissions = true,
+                ("allow_other", None) => allow_other = true,
+                ("max_read", Some(value)) => max_read = parse_value(value, 10)? as usize,
+                // The subtype is only used to show the file system type.
+                ("subtype", Some(_)) => (),
+                _ => return_errno_with_message!(Errno::EINVAL, "the FUSE mount option is invalid"),
+            }
+        }
+
+        let (Some(fd), Some(root_mode), Some(user_id), Some(group_id)) =
+            (fd, root_mode, user_id, group_id)
+        else {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the FUSE mount options must contain fd, rootmode, user_id, and group_id"
+            );
+        };
+        if InodeType::from_raw_mode(root_mode as u16)? != InodeType::Dir {
+            return_errno_with_message!(Errno::EINVAL, "the FUSE root mode is not a directory");
+        }
+
+        Ok(Self {
+            fd: fd as FileDesc,
+            root_mode,
+            user_id,
+            group_id,
+            default_permissions,
+            allow_other,
+            max_read,
+        })
+    }
+}
+
+fn parse_value(value: &str, radix: u32) -> Result<u32> {
+    u32::from_str_radix(value, radix)
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the FUSE mount option value is invalid"))
+}
@@ -0,0 +1,1358 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use align_ext::AlignExt;
+use aster_block::bio::BioWaiter;
+use ostd::{mm::io_util::HasVmReaderWriter, task::Task};
+
+use super::{
+    abi::{
+        fuse_dirent_size, parse_reply, FopenFlags, FuseAttr, FuseAttrOut, FuseCreateIn, FuseDirent,
+        FuseEntryOut, FuseForgetIn, FuseFsyncIn, FuseGetattrIn, FuseGetxattrIn, FuseGetxattrOut,
+        FuseLinkIn, FuseMkdirIn, FuseOpcode, FuseOpenIn, FuseOpenOut, FuseReadIn, FuseReleaseIn,
+        FuseRenameIn, FuseSetattrIn, FuseSetxattrIn, FuseWriteIn, FuseWriteOut, SetattrValid,
+    },
+    conn::FuseConn,
+    fs::FuseFs,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        inode_handle::FileIo,
+        utils::{
+            check_permission_by_mode, AccessMode, CachePage, CreationFlags, DirentVisitor,
+            Extension, FileSystem, Inode, InodeIo, InodeMode, InodeType, Metadata, PageCache,
+            PageCacheBackend, Permission, StatusFlags, SymbolicLink, XattrName, XattrNamespace,
+            XattrSetFlags, NAME_MAX, XATTR_LIST_MAX_LEN,
+        },
+    },
+    prelude::*,
+    process::{
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable},
+        Gid, Uid,
+    },
+    time::clocks::MonotonicCoarseClock,
+    vm::vmo::Vmo,
+};
+
+/// The maximum size of the data in a `FUSE_READ` request.
+///
+/// This corresponds to the default `max_pages` in Linux.
+const MAX_READ_SIZE: usize = 32 * PAGE_SIZE;
+
+/// An inode of a FUSE file system.
+///
+/// The attributes are cached until they expire, as specified by the daemon. The data of regular
+/// files are cached in the page cache unless the direct I/O is used. The dirty pages are written
+/// through to the daemon once a write finishes.
+pub(super) struct FuseInode {
+    nodeid: u64,
+    type_: InodeType,
+    attr: SpinLock<CachedAttr>,
+    /// The number of times that the daemon has replied with the node ID.
+    ///
+    /// The count is sent back to the daemon by a `FUSE_FORGET` request when the inode is dropped.
+    nlookup: AtomicU64,
+    /// The page cache, which is only available for regular files.
+    page_cache: Option<PageCache>,
+    /// The handles of the opened files.
+    handles: SpinLock<Vec<Arc<OpenHandle>>>,
+    /// The handle opened by a `FUSE_CREATE` request, which will be taken by the next open.
+    created_handle: SpinLock<Option<Arc<OpenHandle>>>,
+    extension: Extension,
+    conn: Arc<FuseConn>,
+    fs: Weak<FuseFs>,
+    this: Weak<FuseInode>,
+}
+
+struct CachedAttr {
+    attr: FuseAttr,
+    /// The time (in the monotonic clock) before which the attributes are valid.
+    valid_until: Duration,
+    /// The version that is increased whenever the attributes are modified locally.
+    ///
+    /// The attributes fetched by a `FUSE_GETATTR` request are dropped if the version has changed
+    /// during the request, since they may be outdated.
+    version: u64,
+}
+
+impl FuseInode {
+    pub(super) fn new(
+        nodeid: u64,
+        type_: InodeType,
+        attr: FuseAttr,
+        fs: Weak<FuseFs>,
+        conn: Arc<FuseConn>,
+    ) -> Arc<Self> {
+        Arc::new_cyclic(|weak_self| {
+            let page_cache = (type_ == InodeType::File).then(|| {
+                PageCache::with_capacity(attr.size as usize, weak_self.clone() as _).unwrap()
+            });
+
+            Self {
+                nodeid,
+                type_,
+                attr: SpinLock::new(CachedAttr {
+                    attr,
+                    valid_until: Duration::ZERO,
+                    version: 0,
+                }),
+                nlookup: AtomicU64::new(1),
+                page_cache,
+                handles: SpinLock::new(Vec::new()),
+                created_handle: SpinLock::new(None),
+                extension: Extension::new(),
+                conn,
+                fs,
+                this: weak_self.clone(),
+            }
+        })
+    }
+
+    pub(super) fn inc_nlookup(&self) {
+        self.nlookup.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Updates the attributes replied by the daemon.
+    ///
+    /// If the file size is changed, the page cache will be invalidated.
+    pub(super) fn update_attr(&self, attr: &FuseAttr, valid_secs: u64, valid_nsecs: u32) {
+        let old_size = {
+            let mut cached = self.attr.lock();
+            let old_size = cached.attr.size;
+            cached.attr = *attr;
+            cached.valid_until = valid_until(valid_secs, valid_nsecs);
+            old_size
+        };
+
+        if attr.size != old_size {
+            self.invalidate_page_cache(attr.size as usize);
+        }
+    }
+
+    /// Sets how long the current attributes are valid.
+    pub(super) fn set_attr_valid(&self, valid_secs: u64, valid_nsecs: u32) {
+        self.attr.lock().valid_until = valid_until(valid_secs, valid_nsecs);
+    }
+
+    /// Marks the attributes as expired, so they will be fetched again when needed.
+    fn expire_attr(&self) {
+        self.attr.lock().valid_until = Duration::ZERO;
+    }
+
+    /// Returns the attributes, which are fetched from the daemon if they have expired.
+    ///
+    /// If the attributes cannot be fetched, the stale attributes are returned.
+    fn attr(&self) -> FuseAttr {
+        let (attr, valid_until, version) = {
+            let cached = self.attr.lock();
+            (cached.attr, cached.valid_until, cached.version)
+        };
+        if MonotonicCoarseClock::get().read_time() < valid_until {
+            return attr;
+        }
+
+        let getattr_in = FuseGetattrIn {
+            getattr_flags: 0,
+            dummy: 0,
+            fh: 0,
+        };
+        let Ok(attr_out) = self
+            .conn
+            .send(FuseOpcode::Getattr, self.nodeid, &[getattr_in.as_bytes()])
+            .and_then(|reply| parse_reply::<FuseAttrOut>(&reply))
+        else {
+            return attr;
+        };
+        if attr_out.attr.type_() != Some(self.type_) {
+            return attr;
+        }
+
+        if self.attr.lock().version != version {
+            // The attributes have been modified locally during the request.
+            return attr_out.attr;
+        }
+        self.update_attr(
+            &attr_out.attr,
+            attr_out.attr_valid,
+            attr_out.attr_valid_nsec,
+        );
+
+        attr_out.attr
+    }
+
+    /// Sends a `FUSE_SETATTR` request and updates the attributes with the reply.
+    fn setattr(&self, setattr_in: FuseSetattrIn) -> Result<()> {
+        let reply = self
+            .conn
+            .send(FuseOpcode::Setattr, self.nodeid, &[setattr_in.as_bytes()])?;
+        let attr_out: FuseAttrOut = parse_reply(&reply)?;
+
+        self.attr.lock().version += 1;
+        self.update_attr(
+            &attr_out.attr,
+            attr_out.attr_valid,
+            attr_out.attr_valid_nsec,
+        );
+
+        Ok(())
+    }
+
+    fn fs_ref(&self) -> Arc<FuseFs> {
+        self.fs.upgrade().unwrap()
+    }
+
+    fn check_dir(&self) -> Result<()> {
+        if self.type_ != InodeType::Dir {
+            return_errno_with_message!(Errno::ENOTDIR, "the inode is not a directory");
+        }
+        Ok(())
+    }
+
+    /// Gets the inode of the entry replied by the daemon.
+    fn entry_inode(&self, entry: &FuseEntryOut) -> Result<Arc<FuseInode>> {
+        if entry.nodeid == 0 {
+            // This is a negative entry, which the daemon uses to cache the nonexistence.
+            return_errno_with_message!(Errno::ENOENT, "the FUSE entry does not exist");
+        }
+        self.fs_ref().get_or_create_inode(entry)
+    }
+
+    /// Calls the closure with an opened handle of the inode.
+    ///
+    /// If no handle is given, an opened handle that is readable (or writable if `is_write` is
+    /// true) is used. If there is no such handle, a temporary handle will be opened.
+    fn with_handle<T>(
+        &self,
+        handle: Option<&OpenHandle>,
+        is_write: bool,
+        f: impl FnOnce(&OpenHandle) -> Result<T>,
+    ) -> Result<T> {
+        if let Some(handle) = handle {
+            return f(handle);
+        }
+
+        let opened = self
+            .handles
+            .lock()
+            .iter()
+            .find(|handle| {
+                if is_write {
+                    handle.access_mode.is_writable()
+                } else {
+                    handle.access_mode.is_readable()
+                }
+            })
+            .cloned();
+        let handle = match opened {
+            Some(handle) => handle,
+            None => {
+                let access_mode = if is_write {
+                    AccessMode::O_WRONLY
+                } else {
+                    AccessMode::O_RDONLY
+                };
+                OpenHandle::open(self, access_mode, StatusFlags::empty())?
+            }
+        };
+
+        f(&handle)
+    }
+
+    fn open_file(
+        &self,
+        access_mode: AccessMode,
+        status_flags: StatusFlags,
+    ) -> Result<Box<dyn FileIo>> {
+        let created = self.created_handle.lock().take();
+        let handle = match created {
+            Some(handle) => handle,
+            None => OpenHandle::open(self, access_mode, status_flags)?,
+        };
+
+        if self.type_ == InodeType::File && !handle.open_flags.contains(FopenFlags::KEEP_CACHE) {
+            // The file may have been modified by others.
+            self.expire_attr();
+            let size = self.attr.lock().attr.size as usize;
+            self.invalidate_page_cache(size);
+        }
+
+        self.handles.lock().push(handle.clone());
+
+        Ok(Box::new(FuseFile {
+            inode: self.this.upgrade().unwrap(),
+            handle,
+        }))
+    }
+
+    fn read(
+        &self,
+        handle: Option<&OpenHandle>,
+        offset: usize,
+        writer: &mut VmWriter,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
+        match self.type_ {
+            InodeType::File => (),
+            InodeType::Dir => return_errno_with_message!(Errno::EISDIR, "the inode is a directory"),
+            _ => return_errno_with_message!(Errno::EINVAL, "the inode is not a regular file"),
+        }
+
+        if is_direct_io(handle, status_flags) {
+            return self.with_handle(handle, false, |handle| {
+                self.read_direct(handle, offset, writer)
+            });
+        }
+
+        // The file may have been extended by others.
+        let file_size = self.size();
+        let start = offset.min(file_size);
+        let end = offset.saturating_add(writer.avail()).min(file_size);
+        let read_len = end - start;
+        if read_len == 0 {
+            return Ok(0);
+        }
+
+        let pages = self.page_cache.as_ref().unwrap().pages();
+        pages.read(start, writer.limit(read_len))?;
+
+        Ok(read_len)
+    }
+
+    /// Reads the data from the daemon, bypassing the page cache.
+    fn read_direct(
+        &self,
+        handle: &OpenHandle,
+        offset: usize,
+        writer: &mut VmWriter,
+    ) -> Result<usize> {
+        let chunk_size = self.fs_ref().config().max_read.min(MAX_READ_SIZE);
+
+        let mut read_len = 0;
+        while writer.has_avail() {
+            let size = writer.avail().min(chunk_size);
+            let read_in = FuseReadIn {
+                fh: handle.fh,
+                offset: (offset + read_len) as u64,
+                size: size as u32,
+                read_flags: 0,
+                lock_owner: 0,
+                flags: handle.flags,
+                padding: 0,
+            };
+
+            let data = match self
+                .conn
+                .send(FuseOpcode::Read, self.nodeid, &[read_in.as_bytes()])
+            {
+                Ok(data) => data,
+                Err(_) if read_len > 0 => break,
+                Err(err) => return Err(err),
+            };
+            let len = data.len().min(size);
+            writer.write_fallible(&mut VmReader::from(&data[..len]))?;
+            read_len += len;
+
+            if len < size {
+                break;
+            }
+        }
+
+        Ok(read_len)
+    }
+
+    fn write(
+        &self,
+        handle: Option<&OpenHandle>,
+        offset: usize,
+        reader: &mut VmReader,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
+        match self.type_ {
+            InodeType::File => (),
+            InodeType::Dir => return_errno_with_message!(Errno::EISDIR, "the inode is a directory"),
+            _ => return_errno_with_message!(Errno::EINVAL, "the inode is not a regular file"),
+        }
+
+        let write_len = reader.remain();
+        let Some(end) = offset.checked_add(write_len) else {
+            return_errno_with_message!(Errno::EFBIG, "the write range is too large");
+        };
+
+        if is_direct_io(handle, status_flags) {
+            // The cached pages will be outdated after the write.
+            self.invalidate_page_cache_range(offset..end);
+            return self.with_handle(handle, true, |handle| {
+                self.write_direct(handle, offset, reader)
+            });
+        }
+
+        let page_cache = self.page_cache.as_ref().unwrap();
+        self.extend_size(end)?;
+        page_cache.pages().write(offset, reader)?;
+
+        // Write through the dirty pages, since the daemon owns the file data.
+        if let Err(err) = page_cache.evict_range(offset..end) {
+            self.expire_attr();
+            return Err(err);
+        }
+
+        Ok(write_len)
+    }
+
+    /// Writes the data to the daemon, bypassing the page cache.
+    fn write_direct(
+        &self,
+        handle: &OpenHandle,
+        offset: usize,
+        reader: &mut VmReader,
+    ) -> Result<usize> {
+        let max_write = self.conn.init_info()?.max_write;
+        let mut buf = vec![0u8; reader.remain().min(max_write)];
+
+        let mut written_len = 0;
+        while reader.has_remain() {
+            let size = reader.remain().min(max_write);
+            let chunk = &mut buf[..size];
+            reader.read_fallible(&mut VmWriter::from(&mut *chunk))?;
+
+            let len = match self.write_chunk(handle, offset + written_len, chunk) {
+                Ok(len) => len,
+                Err(_) if written_len > 0 => break,
+                Err(err) => return Err(err),
+            };
+            written_len += len;
+
+            if len < size {
+                break;
+            }
+        }
+
+        self.extend_size(offset + written_len)?;
+
+        Ok(written_len)
+    }
+
+    /// Sends a `FUSE_WRITE` request and returns the number of bytes written.
+    fn write_chunk(&self, handle: &OpenHandle, offset: usize, data: &[u8]) -> Result<usize> {
+        let write_in = FuseWriteIn {
+            fh: handle.fh,
+            offset: offset as u64,
+            size: data.len() as u32,
+            write_flags: 0,
+            lock_owner: 0,
+            flags: handle.flags,
+            padding: 0,
+        };
+        let reply = self
+            .conn
+            .send(FuseOpcode::Write, self.nodeid, &[write_in.as_bytes(), data])?;
+        let write_out: FuseWriteOut = parse_reply(&reply)?;
+
+        Ok((write_out.size as usize).min(data.len()))
+    }
+
+    /// Extends the cached file size after a write.
+    fn extend_size(&self, end: usize) -> Result<()> {
+        {
+            let mut cached = self.attr.lock();
+            // The modification time is changed by the write.
+            cached.valid_until = Duration::ZERO;
+            cached.version += 1;
+            if end as u64 <= cached.attr.size {
+                return Ok(());
+            }
+            cached.attr.size = end as u64;
+        }
+
+        let pages = self.page_cache.as_ref().unwrap().pages();
+        if pages.size() < end {
+            pages.resize(end)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes back the dirty pages to the daemon.
+    pub(super) fn flush_page_cache(&self) -> Result<()> {
+        let Some(page_cache) = &self.page_cache else {
+            return Ok(());
+        };
+
+        let size = self.attr.lock().attr.size as usize;
+        page_cache.evict_range(0..size)
+    }
+
+    /// Drops all the cached pages and resizes the page cache to the new file size.
+    ///
+    /// The dirty pages will be written back before being dropped.
+    fn invalidate_page_cache(&self, new_size: usize) {
+        let Some(page_cache) = &self.page_cache else {
+            return;
+        };
+
+        self.invalidate_page_cache_range(0..usize::MAX);
+        if let Err(err) = page_cache.pages().resize(new_size) {
+            warn!("failed to resize the FUSE page cache: {:?}", err);
+        }
+    }
+
+    /// Drops the cached pages in the range.
+    ///
+    /// The dirty pages will be written back before being dropped.
+    fn invalidate_page_cache_range(&self, range: Range<usize>) {
+        let Some(page_cache) = &self.page_cache else {
+            return;
+        };
+
+        let pages = page_cache.pages();
+        let start = range.start.align_down(PAGE_SIZE);
+        let end = range.end.min(pages.size()).align_up(PAGE_SIZE);
+        if start >= end {
+            return;
+        }
+
+        if let Err(err) = pages.decommit(start..end) {
+            warn!("failed to write back the FUSE page cache: {:?}", err);
+        }
+        // The pages that are read ahead but not committed are dropped here.
+        page_cache.discard_range(start..end);
+    }
+
+    /// Sends a `FUSE_FSYNC` request after writing back the dirty pages.
+    fn fsync(&self, is_datasync: bool) -> Result<()> {
+        self.flush_page_cache()?;
+
+        if self.type_ != InodeType::File {
+            return Ok(());
+        }
+        // The data can only be synchronized via an opened handle.
+        let Some(handle) = self.handles.lock().first().cloned() else {
+            return Ok(());
+        };
+
+        let fsync_in = FuseFsyncIn {
+            fh: handle.fh,
+            fsync_flags: is_datasync as u32,
+            padding: 0,
+        };
+        match self
+            .conn
+            .send(FuseOpcode::Fsync, self.nodeid, &[fsync_in.as_bytes()])
+        {
+            // Like Linux, the daemons that do not implement `FUSE_FSYNC` are considered to have
+            // synchronized the data.
+            Err(err) if err.error() == Errno::ENOSYS => Ok(()),
+            result => result.map(|_| ()),
+        }
+    }
+
+    fn try_readdir(&self, offset: &mut usize, visitor: &mut dyn DirentVisitor) -> Result<()> {
+        self.with_handle(None, false, |handle| loop {
+            let read_in = FuseReadIn {
+                fh: handle.fh,
+                offset: *offset as u64,
+                size: PAGE_SIZE as u32,
+                read_flags: 0,
+                lock_owner: 0,
+                flags: handle.flags,
+                padding: 0,
+            };
+            let reply = self
+                .conn
+                .send(FuseOpcode::Readdir, self.nodeid, &[read_in.as_bytes()])?;
+            if reply.is_empty() {
+                return Ok(());
+            }
+
+            let mut pos = 0;
+            while pos < reply.len() {
+                let dirent: FuseDirent = parse_reply(&reply[pos..])?;
+                let namelen = dirent.namelen as usize;
+                let name_start = pos + size_of::<FuseDirent>();
+                let Some(name_bytes) = reply.get(name_start..name_start + namelen) else {
+                    return_errno_with_message!(Errno::EIO, "the FUSE directory entry is truncated");
+                };
+                if namelen == 0 || namelen > NAME_MAX {
+                    return_errno_with_message!(
+                        Errno::EIO,
+                        "the FUSE directory entry name is invalid"
+                    );
+                }
+                let Ok(name) = core::str::from_utf8(name_bytes) else {
+                    return_errno_with_message!(
+                        Errno::EIO,
+                        "the FUSE directory entry name is invalid"
+                    );
+                };
+                // The offsets are used as the positions of the opened directory.
+                if dirent.off as usize <= *offset {
+                    return_errno_with_message!(
+                        Errno::EIO,
+                        "the FUSE directory offset is not increasing"
+                    );
+                }
+
+                let type_ =
+                    InodeType::try_from((dirent.type_ << 12) as u16).unwrap_or(InodeType::Unknown);
+                visitor.visit(name, dirent.ino, type_, dirent.off as usize)?;
+
+                *offset = dirent.off as usize;
+                pos += fuse_dirent_size(namelen);
+            }
+        })
+    }
+
+    /// Sends a request that changes the directory entries.
+    fn send_dir_op(&self, opcode: FuseOpcode, args: &[&[u8]]) -> Result<Vec<u8>> {
+        self.check_dir()?;
+
+        let result = self.conn.send(opcode, self.nodeid, args);
+        // The modification time and the link count of the directory may be changed.
+        self.expire_attr();
+
+        result
+    }
+}
+
+/// Returns the time before which the attributes are valid.
+fn valid_until(valid_secs: u64, valid_nsecs: u32) -> Duration {
+    MonotonicCoarseClock::get()
+        .read_time()
+        .saturating_add(duration(valid_secs, valid_nsecs))
+}
+
+fn duration(secs: u64, nsecs: u32) -> Duration {
+    Duration::from_secs(secs).saturating_add(Duration::from_nanos(nsecs as u64))
+}
+
+fn is_direct_io(handle: Option<&OpenHandle>, status_flags: StatusFlags) -> bool {
+    status_flags.contains(StatusFlags::O_DIRECT)
+        || handle.is_some_and(|handle| handle.open_flags.contains(FopenFlags::DIRECT_IO))
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.len() > NAME_MAX {
+        return_errno_with_message!(Errno::ENAMETOOLONG, "the file name is too long");
+    }
+    Ok(())
+}
+
+/// Maps the error of an unimplemented extended attribute operation to [`EOPNOTSUPP`].
+///
+/// [`EOPNOTSUPP`]: Errno::EOPNOTSUPP
+fn map_xattr_error(err: Error) -> Error {
+    if err.error() == Errno::ENOSYS {
+        Error::with_message(Errno::EOPNOTSUPP, "the FUSE daemon does not support xattrs")
+    } else {
+        err
+    }
+}
+
+impl PageCacheBackend for FuseInode {
+    fn read_page_async(&self, idx: usize, frame: &CachePage) -> Result<BioWaiter> {
+        let offset = idx * PAGE_SIZE;
+        let len = PAGE_SIZE.min((self.attr.lock().attr.size as usize).saturating_sub(offset));
+
+        let mut buf = vec![0u8; PAGE_SIZE];
+        if len > 0 {
+            let mut writer = VmWriter::from(&mut buf[..len]).to_fallible();
+            self.with_handle(None, false, |handle| {
+                self.read_direct(handle, offset, &mut writer)
+            })?;
+        }
+        frame.writer().write(&mut VmReader::from(buf.as_slice()));
+
+        Ok(BioWaiter::new())
+    }
+
+    fn write_page_async(&self, idx: usize, frame: &CachePage) -> Result<BioWaiter> {
+        let offset = idx * PAGE_SIZE;
+        let len = PAGE_SIZE.min((self.attr.lock().attr.size as usize).saturating_sub(offset));
+        if len == 0 {
+            return Ok(BioWaiter::new());
+        }
+
+        let mut buf = vec![0u8; len];
+        frame.reader().read(&mut VmWriter::from(buf.as_mut_slice()));
+        let written_len =
+            self.with_handle(None, true, |handle| self.write_chunk(handle, offset, &buf))?;
+        if written_len < len {
+            return_errno_with_message!(Errno::EIO, "the FUSE daemon writes the page partially");
+        }
+
+        Ok(BioWaiter::new())
+    }
+
+    fn npages(&self) -> usize {
+        (self.attr.lock().attr.size as usize).div_ceil(PAGE_SIZE)
+    }
+}
+
+impl InodeIo for FuseInode {
+    fn read_at(
+        &self,
+        offset: usize,
+        writer: &mut VmWriter,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
+        self.read(None, offset, writer, status_flags)
+    }
+
+    fn write_at(
+        &self,
+        offset: usize,
+        reader: &mut VmReader,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
+        self.write(None, offset, reader, status_flags)
+    }
+}
+
+impl Inode for FuseInode {
+    fn size(&self) -> usize {
+        self.attr().size as usize
+    }
+
+    fn resize(&self, new_size: usize) -> Result<()> {
+        match self.type_ {
+            InodeType::File => (),
+            InodeType::Dir => return_errno_with_message!(Errno::EISDIR, "the inode is a directory"),
+            _ => return_errno_with_message!(Errno::EINVAL, "the inode is not a regular file"),
+        }
+
+        // The dirty pages must reach the daemon before the file is truncated.
+        self.flush_page_cache()?;
+
+        self.setattr(FuseSetattrIn {
+            valid: SetattrValid::SIZE.bits(),
+            size: new_size as u64,
+            ..Default::default()
+        })
+    }
+
+    fn metadata(&self) -> Metadata {
+        let attr = self.attr();
+        let blk_size = if attr.blksize >= 512 && attr.blksize.is_power_of_two() {
+            attr.blksize as usize
+        } else {
+            PAGE_SIZE
+        };
+
+        Metadata {
+            dev: 0,
+            ino: attr.ino,
+            size: attr.size as usize,
+            blk_size,
+            blocks: (attr.blocks as usize).div_ceil(blk_size / 512),
+            atime: duration(attr.atime, attr.atimensec),
+            mtime: duration(attr.mtime, attr.mtimensec),
+            ctime: duration(attr.ctime, attr.ctimensec),
+            type_: self.type_,
+            mode: InodeMode::from_bits_truncate(attr.mode as u16),
+            nlinks: attr.nlink as usize,
+            uid: Uid::new(attr.uid),
+            gid: Gid::new(attr.gid),
+            rdev: attr.rdev as u64,
+        }
+    }
+
+    fn ino(&self) -> u64 {
+        self.attr.lock().attr.ino
+    }
+
+    fn type_(&self) -> InodeType {
+        self.type_
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(InodeMode::from_bits_truncate(self.attr().mode as u16))
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.setattr(FuseSetattrIn {
+            valid: SetattrValid::MODE.bits(),
+            mode: self.type_ as u32 | mode.bits() as u32,
+            ..Default::default()
+        })
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(Uid::new(self.attr().uid))
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.setattr(FuseSetattrIn {
+            valid: SetattrValid::UID.bits(),
+            uid: uid.into(),
+            ..Default::default()
+        })
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(Gid::new(self.attr().gid))
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.setattr(FuseSetattrIn {
+            valid: SetattrValid::GID.bits(),
+            gid: gid.into(),
+            ..Default::default()
+        })
+    }
+
+    fn atime(&self) -> Duration {
+        let attr = self.attr();
+        duration(attr.atime, attr.atimensec)
+    }
+
+    fn set_atime(&self, time: Duration) {
+        let result = self.setattr(FuseSetattrIn {
+            valid: SetattrValid::ATIME.bits(),
+            atime: time.as_secs(),
+            atimensec: time.subsec_nanos(),
+            ..Default::default()
+        });
+        if let Err(err) = result {
+            warn!("failed to set the FUSE access time: {:?}", err);
+        }
+    }
+
+    fn mtime(&self) -> Duration {
+        let attr = self.attr();
+        duration(attr.mtime, attr.mtimensec)
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        let result = self.setattr(FuseSetattrIn {
+            valid: SetattrValid::MTIME.bits(),
+            mtime: time.as_secs(),
+            mtimensec: time.subsec_nanos(),
+            ..Default::default()
+        });
+        if let Err(err) = result {
+            warn!("failed to set the FUSE modification time: {:?}", err);
+        }
+    }
+
+    fn ctime(&self) -> Duration {
+        let attr = self.attr();
+        duration(attr.ctime, attr.ctimensec)
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        let result = self.setattr(FuseSetattrIn {
+            valid: SetattrValid::CTIME.bits(),
+            ctime: time.as_secs(),
+            ctimensec: time.subsec_nanos(),
+            ..Default::default()
+        });
+        if let Err(err) = result {
+            warn!("failed to set the FUSE change time: {:?}", err);
+        }
+    }
+
+    fn page_cache(&self) -> Option<Arc<Vmo>> {
+        self.page_cache
+            .as_ref()
+            .map(|page_cache| page_cache.pages().clone())
+    }
+
+    fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
+        check_name(name)?;
+
+        let inode = match type_ {
+            InodeType::File => {
+                let flags = AccessMode::O_RDWR as u32
+                    | (CreationFlags::O_CREAT | CreationFlags::O_EXCL).bits();
+                let create_in = FuseCreateIn {
+                    flags,
+                    mode: type_ as u32 | mode.bits() as u32,
+                    umask: 0,
+                    open_flags: 0,
+                };
+                let reply = self.send_dir_op(
+                    FuseOpcode::Create,
+                    &[create_in.as_bytes(), name.as_bytes(), &[0]],
+                )?;
+                let entry: FuseEntryOut = parse_reply(&reply)?;
+                let open_out: FuseOpenOut = parse_reply(&reply[size_of::<FuseEntryOut>()..])?;
+
+                // The handle is created first, so that it will be released if the inode cannot
+                // be created.
+                let handle = Arc::new(OpenHandle {
+                    fh: open_out.fh,
+                    access_mode: AccessMode::O_RDWR,
+                    open_flags: FopenFlags::from_bits_truncate(open_out.open_flags),
+                    flags,
+                    is_dir: false,
+                    nodeid: entry.nodeid,
+                    conn: self.conn.clone(),
+                });
+                let inode = self.entry_inode(&entry)?;
+                *inode.created_handle.lock() = Some(handle);
+                inode
+            }
+            InodeType::Dir => {
+                let mkdir_in = FuseMkdirIn {
+                    mode: mode.bits() as u32,
+                    umask: 0,
+                };
+                let reply = self.send_dir_op(
+                    FuseOpcode::Mkdir,
+                    &[mkdir_in.as_bytes(), name.as_bytes(), &[0]],
+                )?;
+                self.entry_inode(&parse_reply(&reply)?)?
+            }
+            _ => return_errno_with_message!(
+                Errno::EPERM,
+                "creating this type of files is not supported in FUSE"
+            ),
+        };
+
+        if inode.type_ != type_ {
+            return_errno_with_message!(Errno::EIO, "the FUSE daemon creates a wrong file type");
+        }
+
+        Ok(inode)
+    }
+
+    fn open(
+        &self,
+        access_mode: AccessMode,
+        status_flags: StatusFlags,
+    ) -> Option<Result<Box<dyn FileIo>>> {
+        match self.type_ {
+            InodeType::File | InodeType::Dir => Some(self.open_file(access_mode, status_flags)),
+            _ => None,
+        }
+    }
+
+    fn readdir_at(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        self.check_dir()?;
+
+        let mut iterate_offset = offset;
+        match self.try_readdir(&mut iterate_offset, visitor) {
+            Err(err) if iterate_offset == offset => Err(err),
+            _ => Ok(iterate_offset - offset),
+        }
+    }
+
+    fn link(&self, old: &Arc<dyn Inode>, name: &str) -> Result<()> {
+        check_name(name)?;
+        let Some(old) = old.downcast_ref::<FuseInode>() else {
+            return_errno_with_message!(Errno::EXDEV, "not the same file system");
+        };
+        if !old.fs.ptr_eq(&self.fs) {
+            return_errno_with_message!(Errno::EXDEV, "not the same file system");
+        }
+
+        let link_in = FuseLinkIn {
+            oldnodeid: old.nodeid,
+        };
+        let reply = self.send_dir_op(
+            FuseOpcode::Link,
+            &[link_in.as_bytes(), name.as_bytes(), &[0]],
+        )?;
+        // The link count of the old inode is changed.
+        old.expire_attr();
+        self.entry_inode(&parse_reply(&reply)?)?;
+
+        Ok(())
+    }
+
+    fn unlink(&self, name: &str) -> Result<()> {
+        check_name(name)?;
+        self.send_dir_op(FuseOpcode::Unlink, &[name.as_bytes(), &[0]])?;
+        Ok(())
+    }
+
+    fn rmdir(&self, name: &str) -> Result<()> {
+        check_name(name)?;
+        self.send_dir_op(FuseOpcode::Rmdir, &[name.as_bytes(), &[0]])?;
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>> {
+        self.check_dir()?;
+        check_name(name)?;
+
+        let reply = self
+            .conn
+            .send(FuseOpcode::Lookup, self.nodeid, &[name.as_bytes(), &[0]])?;
+        let inode = self.entry_inode(&parse_reply(&reply)?)?;
+
+        Ok(inode)
+    }
+
+    fn rename(&self, old_name: &str, target: &Arc<dyn Inode>, new_name: &str) -> Result<()> {
+        check_name(old_name)?;
+        check_name(new_name)?;
+        let Some(target) = target.downcast_ref::<FuseInode>() else {
+            return_errno_with_message!(Errno::EXDEV, "not the same file system");
+        };
+        if !target.fs.ptr_eq(&self.fs) {
+            return_errno_with_message!(Errno::EXDEV, "not the same file system");
+        }
+        target.check_dir()?;
+
+        let rename_in = FuseRenameIn {
+            newdir: target.nodeid,
+        };
+        self.send_dir_op(
+            FuseOpcode::Rename,
+            &[
+                rename_in.as_bytes(),
+                old_name.as_bytes(),
+                &[0],
+                new_name.as_bytes(),
+                &[0],
+            ],
+        )?;
+        target.expire_attr();
+
+        Ok(())
+    }
+
+    fn read_link(&self) -> Result<SymbolicLink> {
+        if self.type_ != InodeType::SymLink {
+            return_errno_with_message!(Errno::EINVAL, "the inode is not a symbolic link");
+        }
+
+        let reply = self.conn.send(FuseOpcode::Readlink, self.nodeid, &[])?;
+        let Ok(target) = String::from_utf8(reply) else {
+            return_errno_with_message!(Errno::EIO, "the FUSE symbolic link target is invalid");
+        };
+
+        Ok(SymbolicLink::Plain(target))
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        self.fsync(false)
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        self.fsync(true)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs_ref()
+    }
+
+    fn is_dentry_cacheable(&self) -> bool {
+        // The directory entries can be changed by the daemon without notifying the kernel.
+        false
+    }
+
+    fn extension(&self) -> Option<&Extension> {
+        Some(&self.extension)
+    }
+
+    fn set_xattr(
+        &self,
+        name: XattrName,
+        value_reader: &mut VmReader,
+        flags: XattrSetFlags,
+    ) -> Result<()> {
+        let mut value = vec![0u8; value_reader.remain()];
+        value_reader.read_fallible(&mut VmWriter::from(value.as_mut_slice()))?;
+
+        let setxattr_in = FuseSetxattrIn {
+            size: value.len() as u32,
+            flags: flags.bits() as u32,
+        };
+        self.conn
+            .send(
+                FuseOpcode::Setxattr,
+                self.nodeid,
+                &[
+                    setxattr_in.as_bytes(),
+                    name.full_name().as_bytes(),
+                    &[0],
+                    &value,
+                ],
+            )
+            .map_err(map_xattr_error)?;
+        // The change time is changed.
+        self.expire_attr();
+
+        Ok(())
+    }
+
+    fn get_xattr(&self, name: XattrName, value_writer: &mut VmWriter) -> Result<usize> {
+        // A zero size asks the daemon for the size of the value.
+        let size = value_writer.avail();
+        let getxattr_in = FuseGetxattrIn {
+            size: size as u32,
+            padding: 0,
+        };
+        let reply = self
+            .conn
+            .send(
+                FuseOpcode::Getxattr,
+                self.nodeid,
+                &[getxattr_in.as_bytes(), name.full_name().as_bytes(), &[0]],
+            )
+            .map_err(map_xattr_error)?;
+
+        if size == 0 {
+            let getxattr_out: FuseGetxattrOut = parse_reply(&reply)?;
+            return Ok(getxattr_out.size as usize);
+        }
+        if reply.len() > size {
+            return_errno_with_message!(Errno::ERANGE, "the xattr value buffer is too small");
+        }
+        value_writer.write_fallible(&mut VmReader::from(reply.as_slice()))?;
+
+        Ok(reply.len())
+    }
+
+    fn list_xattr(&self, namespace: XattrNamespace, list_writer: &mut VmWriter) -> Result<usize> {
+        // The full list is fetched, since the names need to be filtered by the namespace.
+        let getxattr_in = FuseGetxattrIn {
+            size: XATTR_LIST_MAX_LEN as u32,
+            padding: 0,
+        };
+        let reply = self
+            .conn
+            .send(
+                FuseOpcode::Listxattr,
+                self.nodeid,
+                &[getxattr_in.as_bytes()],
+            )
+            .map_err(map_xattr_error)?;
+
+        let names: Vec<&[u8]> = reply
+            .split(|byte| *byte == 0)
+            .filter(|name| !name.is_empty())
+            .filter(|name| !namespace.is_user() || name.starts_with(b"user."))
+            .collect();
+        let list_len = names.iter().map(|name| name.len() + 1).sum();
+
+        if list_writer.avail() == 0 {
+            return Ok(list_len);
+        }
+        if list_len > list_writer.avail() {
+            return_errno_with_message!(Errno::ERANGE, "the xattr list buffer is too small");
+        }
+        for name in names {
+            list_writer.write_fallible(&mut VmReader::from(name))?;
+            list_writer.write_val(&0u8)?;
+        }
+
+        Ok(list_len)
+    }
+
+    fn remove_xattr(&self, name: XattrName) -> Result<()> {
+        self.conn
+            .send(
+                FuseOpcode::Removexattr,
+                self.nodeid,
+                &[name.full_name().as_bytes(), &[0]],
+            )
+            .map_err(map_xattr_error)?;
+        // The change time is changed.
+        self.expire_attr();
+
+        Ok(())
+    }
+
+    fn check_permission(&self, perm: Permission) -> Result<()> {
+        let fs = self.fs_ref();
+        let config = fs.config();
+
+        // Like Linux, only the mount owner can access the file system unless `allow_other` is
+        // specified.
+        if !config.allow_other {
+            let credentials = Task::current()
+                .and_then(|task| task.as_posix_thread().map(|thread| thread.credentials()));
+            let is_owner = credentials.is_some_and(|credentials| {
+                [credentials.ruid(), credentials.euid(), credentials.suid()]
+                    .iter()
+                    .all(|uid| *uid == config.user_id)
+                    && [credentials.rgid(), credentials.egid(), credentials.sgid()]
+                        .iter()
+                        .all(|gid| *gid == config.group_id)
+            });
+            if !is_owner {
+                return_errno_with_message!(
+                    Errno::EACCES,
+                    "only the mount owner can access the FUSE file system"
+                );
+            }
+        }
+
+        // Otherwise, the permissions are checked by the daemon.
+        if config.default_permissions {
+            check_permission_by_mode(self, perm)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for FuseInode {
+    fn drop(&mut self) {
+        if let Some(fs) = self.fs.upgrade() {
+            fs.remove_inode(self.nodeid, &self.this);
+        }
+
+        // The handle that is never opened is released here.
+        self.created_handle.lock().take();
+
+        let forget_in = FuseForgetIn {
+            nlookup: self.nlookup.load(Ordering::Relaxed),
+        };
+        // The connection may have been aborted, in which case the daemon has gone.
+        let _ = self
+            .conn
+            .send_no_reply(FuseOpcode::Forget, self.nodeid, &[forget_in.as_bytes()]);
+    }
+}
+
+impl Debug for FuseInode {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("FuseInode")
+            .field("nodeid", &self.nodeid)
+            .field("type_", &self.type_)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A file handle opened by the daemon.
+struct OpenHandle {
+    fh: u64,
+    access_mode: AccessMode,
+    open_flags: FopenFlags,
+    /// The flags with which the handle is opened.
+    flags: u32,
+    is_dir: bool,
+    nodeid: u64,
+    conn: Arc<FuseConn>,
+}
+
+impl OpenHandle {
+    /// Opens a handle by sending a `FUSE_OPEN` (or `FUSE_OPENDIR`) request.
+    fn open(
+        inode: &FuseInode,
+        access_mode: AccessMode,
+        status_flags: StatusFlags,
+    ) -> Result<Arc<Self>> {
+        let is_dir = inode.type_ == InodeType::Dir;
+        let opcode = if is_dir {
+            FuseOpcode::Opendir
+        } else {
+            FuseOpcode::Open
+        };
+
+        let flags = access_mode as u32 | status_flags.bits();
+        let open_in = FuseOpenIn {
+            flags,
+            open_flags: 0,
+        };
+        let reply = inode
+            .conn
+            .send(opcode, inode.nodeid, &[open_in.as_bytes()])?;
+        let open_out: FuseOpenOut = parse_reply(&reply)?;
+
+        Ok(Arc::new(Self {
+            fh: open_out.fh,
+            access_mode,
+            open_flags: FopenFlags::from_bits_truncate(open_out.open_flags),
+            flags,
+            is_dir,
+            nodeid: inode.nodeid,
+            conn: inode.conn.clone(),
+        }))
+    }
+}
+
+impl Drop for OpenHandle {
+    fn drop(&mut self) {
+        let release_in = FuseReleaseIn {
+            fh: self.fh,
+            flags: self.flags,
+            release_flags: 0,
+            lock_owner: 0,
+        };
+        let opcode = if self.is_dir {
+            FuseOpcode::Releasedir
+        } else {
+            FuseOpcode::Release
+        };
+
+        // The connection may have been aborted, in which case the daemon has gone.
+        let _ = self
+            .conn
+            .send_background(opcode, self.nodeid, &[release_in.as_bytes()]);
+    }
+}
+
+/// An opened file of a FUSE file system.
+struct FuseFile {
+    inode: Arc<FuseInode>,
+    handle: Arc<OpenHandle>,
+}
+
+impl InodeIo for FuseFile {
+    fn read_at(
+        &self,
+        offset: usize,
+        writer: &mut VmWriter,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
+        self.inode
+            .read(Some(&*self.handle), offset, writer, status_flags)
+    }
+
+    fn write_at(
+        &self,
+        offset: usize,
+        reader: &mut VmReader,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
+        self.inode
+            .write(Some(&*self.handle), offset, reader, status_flags)
+    }
+}
+
+impl Pollable for FuseFile {
+    fn poll(&self, mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
+        let events = IoEvents::IN | IoEvents::OUT;
+        events & mask
+    }
+}
+
+impl FileIo for FuseFile {
+    fn check_seekable(&self) -> Result<()> {
+        if self.handle.open_flags.contains(FopenFlags::NONSEEKABLE) {
+            return_errno_with_message!(Errno::ESPIPE, "the FUSE file is not seekable");
+        }
+        Ok(())
+    }
+
+    fn is_offset_aware(&self) -> bool {
+        true
+    }
+}
+
+impl Drop for FuseFile {
+    fn drop(&mut self) {
+        if self.handle.access_mode.is_writable() {
+            // The dirty pages (e.g., written via shared mappings) must be written back before
+            // the handle is released.
+            let _ = self.inode.flush_page_cache();
+        }
+
+        self.inode
+            .handles
+            .lock()
+            .retain(|handle| !Arc::ptr_eq(handle, &self.handle));
+    }
+}
@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! FUSE (Filesystem in Userspace).
+//!
+//! A FUSE file system forwards the file system operations as requests to a userspace daemon,
+//! which reads the requests from and writes the replies to an opened `/dev/fuse` file.
+//!
+//! Reference: <https://docs.kernel.org/filesystems/fuse.html>
+
+pub use self::conn::FuseConn;
+
+mod abi;
+mod conn;
+mod fs;
+mod inode;
+
+pub(super) fn init() {
+    super::registry::register(&fs::FuseFsType).unwrap();
+}
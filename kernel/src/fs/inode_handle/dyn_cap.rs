@@ -5,12 +5,13 @@ use core::{fmt::Display, sync::atomic::AtomicU32};
 use aster_rights::Rights;
 use inherit_methods_macro::inherit_methods;
 
-use super::HandleInner;
+use super::{FileIo, HandleInner};
 use crate::{
     events::IoEvents,
     fs::{
         file_handle::{FileLike, Mappable},
         file_table::FdFlags,
+        notify::FsEvents,
         path::Path,
         utils::{
             AccessMode, CreationFlags, DirentVisitor, FallocMode, FlockItem, Inode, InodeType,
@@ -52,6 +53,10 @@ impl InodeHandle {
             (file_io, rights)
         };
 
+        if !rights.is_empty() {
+            path.notify_fs_events(FsEvents::OPEN);
+        }
+
         let inner = HandleInner {
             path,
             file_io,
@@ -118,6 +123,23 @@ impl InodeHandle {
     pub fn offset(&self) -> usize {
         self.0.offset()
     }
+
+    /// Returns the file-specific I/O object, if the file provides one.
+    pub fn file_io(&self) -> Option<&dyn FileIo> {
+        self.0.file_io.as_deref()
+    }
+
+    fn notify_read(&self, read_len: usize) {
+        if read_len > 0 {
+            self.0.path.notify_fs_events(FsEvents::ACCESS);
+        }
+    }
+
+    fn notify_write(&self, write_len: usize) {
+        if write_len > 0 {
+            self.0.path.notify_fs_events(FsEvents::MODIFY);
+        }
+    }
 }
 
 #[inherit_methods(from = "self.0")]
@@ -133,28 +155,36 @@ impl FileLike for InodeHandle {
         if !self.1.contains(Rights::READ) {
             return_errno_with_message!(Errno::EBADF, "the file is not opened readable");
         }
-        self.0.read(writer)
+        let read_len = self.0.read(writer)?;
+        self.notify_read(read_len);
+        Ok(read_len)
     }
 
     fn write(&self, reader: &mut VmReader) -> Result<usize> {
         if !self.1.contains(Rights::WRITE) {
             return_errno_with_message!(Errno::EBADF, "the file is not opened writable");
         }
-        self.0.write(reader)
+        let write_len = self.0.write(reader)?;
+        self.notify_write(write_len);
+        Ok(write_len)
     }
 
     fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
         if !self.1.contains(Rights::READ) {
             return_errno_with_message!(Errno::EBADF, "the file is not opened readable");
         }
-        self.0.read_at(offset, writer)
+        let read_len = self.0.read_at(offset, writer)?;
+        self.notify_read(read_len);
+        Ok(read_len)
     }
 
     fn write_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
         if !self.1.contains(Rights::WRITE) {
             return_errno_with_message!(Errno::EBADF, "the file is not opened writable");
         }
-        self.0.write_at(offset, reader)
+        let write_len = self.0.write_at(offset, reader)?;
+        self.notify_write(write_len);
+        Ok(write_len)
     }
 
     fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
@@ -178,7 +208,9 @@ impl FileLike for InodeHandle {
         if !self.1.contains(Rights::WRITE) {
             return_errno_with_message!(Errno::EINVAL, "the file is not opened writable");
         }
-        self.0.resize(new_size)
+        self.0.resize(new_size)?;
+        self.0.path.notify_fs_events(FsEvents::MODIFY);
+        Ok(())
     }
 
     fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
@@ -201,7 +233,9 @@ impl FileLike for InodeHandle {
         if !self.1.contains(Rights::WRITE) {
             return_errno_with_message!(Errno::EBADF, "the file is not opened writable");
         }
-        self.0.fallocate(mode, offset, len)
+        self.0.fallocate(mode, offset, len)?;
+        self.0.path.notify_fs_events(FsEvents::MODIFY);
+        Ok(())
     }
 
     fn inode(&self) -> &Arc<dyn Inode> {
@@ -239,5 +273,11 @@ impl Drop for InodeHandle {
     fn drop(&mut self) {
         self.0.release_range_locks();
         self.0.unlock_flock(self);
+
+        if self.1.contains(Rights::WRITE) {
+            self.0.path.notify_fs_events(FsEvents::CLOSE_WRITE);
+        } else if !self.1.is_empty() {
+            self.0.path.notify_fs_events(FsEvents::CLOSE_NOWRITE);
+        }
     }
 }
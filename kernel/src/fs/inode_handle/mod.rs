@@ -60,8 +60,7 @@ impl HandleInner {
 
         let mut offset = self.offset.lock();
 
-        // FIXME: How can we deal with the `O_APPEND` flag if `file_io` is set?
-        if status_flags.contains(StatusFlags::O_APPEND) && self.file_io.is_none() {
+        if status_flags.contains(StatusFlags::O_APPEND) && self.is_appendable() {
             // FIXME: `O_APPEND` should ensure that new content is appended even if another process
             // is writing to the file concurrently.
             *offset = self.path.size();
@@ -73,6 +72,14 @@ impl HandleInner {
         Ok(len)
     }
 
+    /// Returns whether the `O_APPEND` flag should move the offset to the end of the file.
+    fn is_appendable(&self) -> bool {
+        // FIXME: How can we deal with the `O_APPEND` flag if `file_io` is set for special files?
+        // For now, only regular files (whose `file_io` is provided by file systems like FUSE)
+        // know where their ends are.
+        self.file_io.is_none() || self.path.type_() == InodeType::File
+    }
+
     fn inode_io_and_is_offset_aware(&self) -> (&dyn InodeIo, bool) {
         if let Some(ref file_io) = self.file_io {
             let is_offset_aware = file_io.is_offset_aware();
@@ -95,8 +102,7 @@ impl HandleInner {
         let inode_io = self.inode_io_and_check_seekable()?;
         let status_flags = self.status_flags();
 
-        // FIXME: How can we deal with the `O_APPEND` flag if `file_io` is set?
-        if status_flags.contains(StatusFlags::O_APPEND) && self.file_io.is_none() {
+        if status_flags.contains(StatusFlags::O_APPEND) && self.is_appendable() {
             // If the file has the `O_APPEND` flag, the offset is ignored.
             // FIXME: `O_APPEND` should ensure that new content is appended even if another process
             // is writing to the file concurrently.
@@ -127,8 +133,8 @@ impl HandleInner {
             file_io.check_seekable()?;
             if file_io.is_offset_aware() {
                 // TODO: Figure out whether we need to add support for seeking from the end of
-                // special files.
-                return do_seek_util(&self.offset, pos, None);
+                // special files. For now, only regular files can be seeked from the end.
+                return do_seek_util(&self.offset, pos, self.path.inode().seek_end());
             } else {
                 return Ok(0);
             }
@@ -302,7 +308,7 @@ impl Debug for HandleInner {
 ///
 /// This trait is typically implemented for special files like devices or
 /// named pipes (FIFOs), which have behaviors different from regular on-disk files.
-pub trait FileIo: Pollable + InodeIo + Send + Sync + 'static {
+pub trait FileIo: Pollable + InodeIo + Any + Send + Sync + 'static {
     /// Checks whether the `seek()` operation should fail.
     fn check_seekable(&self) -> Result<()>;
 
@@ -323,6 +329,12 @@ pub trait FileIo: Pollable + InodeIo + Send + Sync + 'static {
     }
 }
 
+impl dyn FileIo {
+    pub fn downcast_ref<T: FileIo>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref::<T>()
+    }
+}
+
 pub(super) fn do_seek_util(
     offset: &Mutex<usize>,
     pos: SeekFrom,
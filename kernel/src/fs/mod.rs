@@ -10,7 +10,10 @@ pub mod ext2;
 pub mod file_handle;
 pub mod file_table;
 pub mod fs_resolver;
+pub mod fuse;
 pub mod inode_handle;
+pub mod mqueue;
+pub mod notify;
 pub mod overlayfs;
 pub mod path;
 pub mod pipe;
@@ -43,11 +46,13 @@ pub fn init() {
     ramfs::init();
     tmpfs::init();
     devpts::init();
+    mqueue::init();
     pseudofs::init();
 
     ext2::init();
     exfat::init();
     overlayfs::init();
+    fuse::init();
 
     path::init();
 }
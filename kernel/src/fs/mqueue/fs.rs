@@ -0,0 +1,548 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use aster_util::{printer::VmPrinter, slot_vec::SlotVec};
+use spin::Once;
+
+use super::{
+    queue::{MessageQueue, MqAttr},
+    BLOCK_SIZE, DEFAULT_MSGS, DEFAULT_MSGSIZE, HARD_MSGSIZE_MAX, HARD_MSG_MAX, MQUEUE_MAGIC,
+    MSGSIZE_MAX, MSG_MAX, QUEUES_MAX, ROOT_INO,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        inode_handle::FileIo,
+        registry::{FsProperties, FsType},
+        utils::{
+            chmod, AccessMode, DirEntryVecExt, DirentVisitor, FileSystem, FsFlags, Inode, InodeIo,
+            InodeMode, InodeType, Metadata, StatusFlags, SuperBlock, NAME_MAX,
+        },
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable},
+        Gid, Uid,
+    },
+    time::clocks::RealTimeCoarseClock,
+};
+
+/// The mqueue file system, which contains POSIX message queues.
+///
+/// Each message queue appears as a regular file in the root directory. Reading the file gives the
+/// status of the queue. The file system is normally mounted at "/dev/mqueue".
+pub struct MqueueFs {
+    sb: SuperBlock,
+    root: Arc<RootInode>,
+    next_ino: AtomicU64,
+}
+
+impl MqueueFs {
+    /// Returns the singleton instance of the mqueue file system.
+    //
+    // TODO: Each IPC namespace should have its own instance.
+    pub fn singleton() -> &'static Arc<Self> {
+        static MQUEUE_FS: Once<Arc<MqueueFs>> = Once::new();
+
+        MQUEUE_FS.call_once(|| {
+            Arc::new_cyclic(|weak_fs| Self {
+                sb: SuperBlock::new(MQUEUE_MAGIC, BLOCK_SIZE, NAME_MAX),
+                root: RootInode::new(weak_fs.clone()),
+                next_ino: AtomicU64::new(ROOT_INO + 1),
+            })
+        })
+    }
+
+    /// Creates a message queue with the name in the root directory.
+    ///
+    /// If `attr` is `None`, the queue will be created with the default attributes.
+    pub fn create_queue(
+        &self,
+        name: &str,
+        mode: InodeMode,
+        attr: Option<&MqAttr>,
+    ) -> Result<Arc<dyn Inode>> {
+        self.root.create_queue(name, mode, attr)
+    }
+
+    fn alloc_ino(&self) -> u64 {
+        self.next_ino.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+impl FileSystem for MqueueFs {
+    fn name(&self) -> &'static str {
+        "mqueue"
+    }
+
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sb(&self) -> SuperBlock {
+        self.sb.clone()
+    }
+}
+
+pub(super) struct MqueueFsType;
+
+impl FsType for MqueueFsType {
+    fn name(&self) -> &'static str {
+        "mqueue"
+    }
+
+    fn properties(&self) -> FsProperties {
+        FsProperties::empty()
+    }
+
+    fn create(
+        &self,
+        _flags: FsFlags,
+        _args: Option<CString>,
+        _disk: Option<Arc<dyn aster_block::BlockDevice>>,
+    ) -> Result<Arc<dyn FileSystem>> {
+        // All mounts of mqueue share the same queues, just like Linux does within an IPC
+        // namespace.
+        Ok(MqueueFs::singleton().clone())
+    }
+
+    fn sysnode(&self) -> Option<Arc<dyn aster_systree::SysNode>> {
+        None
+    }
+}
+
+/// The root directory of the mqueue file system.
+struct RootInode {
+    queues: RwLock<SlotVec<(String, Arc<dyn Inode>)>>,
+    metadata: RwLock<Metadata>,
+    fs: Weak<MqueueFs>,
+}
+
+impl RootInode {
+    fn new(fs: Weak<MqueueFs>) -> Arc<Self> {
+        Arc::new(Self {
+            queues: RwLock::new(SlotVec::new()),
+            metadata: RwLock::new(Metadata::new_dir(
+                ROOT_INO,
+                chmod!(InodeMode::S_ISVTX, a+rwx),
+                BLOCK_SIZE,
+            )),
+            fs,
+        })
+    }
+
+    fn create_queue(
+        &self,
+        name: &str,
+        mode: InodeMode,
+        attr: Option<&MqAttr>,
+    ) -> Result<Arc<dyn Inode>> {
+        if name.len() > NAME_MAX {
+            return_errno_with_message!(Errno::ENAMETOOLONG, "the queue name is too long");
+        }
+
+        let credentials = current_thread!().as_posix_thread().unwrap().credentials();
+        let has_sys_resource = credentials
+            .effective_capset()
+            .contains(CapSet::SYS_RESOURCE);
+
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/mqueue.c>
+        let (max_msgs, max_msg_size) = if let Some(attr) = attr {
+            let (msg_max, msgsize_max) = if has_sys_resource {
+                (HARD_MSG_MAX, HARD_MSGSIZE_MAX)
+            } else {
+                (MSG_MAX, MSGSIZE_MAX)
+            };
+            if attr.mq_maxmsg <= 0
+                || attr.mq_msgsize <= 0
+                || attr.mq_maxmsg as usize > msg_max
+                || attr.mq_msgsize as usize > msgsize_max
+            {
+                return_errno_with_message!(Errno::EINVAL, "the queue attributes are invalid");
+            }
+            (attr.mq_maxmsg as usize, attr.mq_msgsize as usize)
+        } else {
+            (DEFAULT_MSGS, DEFAULT_MSGSIZE)
+        };
+
+        let mut queues = self.queues.write();
+        if queues.find_entry_by_name(name).is_some() {
+            return_errno_with_message!(Errno::EEXIST, "the queue already exists");
+        }
+        if queues.len() >= QUEUES_MAX && !has_sys_resource {
+            return_errno_with_message!(Errno::ENOSPC, "too many message queues");
+        }
+
+        let fs = self.fs.upgrade().unwrap();
+        let inode: Arc<dyn Inode> = Arc::new(QueueInode {
+            queue: Arc::new(MessageQueue::new(max_msgs, max_msg_size)),
+            metadata: RwLock::new(Metadata {
+                uid: credentials.fsuid(),
+                gid: credentials.fsgid(),
+                ..Metadata::new_file(fs.alloc_ino(), mode, BLOCK_SIZE)
+            }),
+            fs: self.fs.clone(),
+        });
+        queues.put((String::from(name), inode.clone()));
+
+        let now = now();
+        let mut metadata = self.metadata.write();
+        metadata.mtime = now;
+        metadata.ctime = now;
+
+        Ok(inode)
+    }
+}
+
+impl InodeIo for RootInode {
+    fn read_at(
+        &self,
+        _offset: usize,
+        _writer: &mut VmWriter,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        Err(Error::new(Errno::EISDIR))
+    }
+
+    fn write_at(
+        &self,
+        _offset: usize,
+        _reader: &mut VmReader,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        Err(Error::new(Errno::EISDIR))
+    }
+}
+
+impl Inode for RootInode {
+    fn size(&self) -> usize {
+        self.metadata.read().size
+    }
+
+    fn resize(&self, _new_size: usize) -> Result<()> {
+        Err(Error::new(Errno::EISDIR))
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino
+    }
+
+    fn type_(&self) -> InodeType {
+        self.metadata.read().type_
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
+        if type_ != InodeType::File {
+            return_errno_with_message!(Errno::EPERM, "only message queues can be created");
+        }
+
+        self.create_queue(name, mode, None)
+    }
+
+    fn readdir_at(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        let try_readdir = |offset: &mut usize, visitor: &mut dyn DirentVisitor| -> Result<()> {
+            // Read the 2 special entries.
+            if *offset == 0 {
+                visitor.visit(".", self.ino(), self.type_(), *offset)?;
+                *offset += 1;
+            }
+            if *offset == 1 {
+                visitor.visit("..", self.ino(), self.type_(), *offset)?;
+                *offset += 1;
+            }
+
+            // Read the queues.
+            let queues = self.queues.read();
+            let start_offset = *offset;
+            for (idx, (name, inode)) in queues
+                .idxes_and_items()
+                .map(|(idx, (name, inode))| (idx + 2, (name, inode)))
+                .skip_while(|(idx, _)| idx < &start_offset)
+            {
+                visitor.visit(name.as_ref(), inode.ino(), inode.type_(), idx)?;
+                *offset = idx + 1;
+            }
+            Ok(())
+        };
+
+        let mut iterate_offset = offset;
+        match try_readdir(&mut iterate_offset, visitor) {
+            Err(e) if offset == iterate_offset => Err(e),
+            _ => Ok(iterate_offset - offset),
+        }
+    }
+
+    fn unlink(&self, name: &str) -> Result<()> {
+        if self.queues.write().remove_entry_by_name(name).is_none() {
+            return_errno_with_message!(Errno::ENOENT, "the queue does not exist");
+        }
+
+        let now = now();
+        let mut metadata = self.metadata.write();
+        metadata.mtime = now;
+        metadata.ctime = now;
+
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>> {
+        match name {
+            "." | ".." => Ok(self.fs().root_inode()),
+            name => self
+                .queues
+                .read()
+                .find_entry_by_name(name)
+                .cloned()
+                .ok_or_else(|| Error::with_message(Errno::ENOENT, "the queue does not exist")),
+        }
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+
+    /// Do not cache dentries in DCACHE.
+    ///
+    /// Message queues can be created and removed by `mq_open` and `mq_unlink` without going
+    /// through the VFS layer.
+    fn is_dentry_cacheable(&self) -> bool {
+        false
+    }
+}
+
+/// The inode of a message queue.
+pub struct QueueInode {
+    queue: Arc<MessageQueue>,
+    metadata: RwLock<Metadata>,
+    fs: Weak<MqueueFs>,
+}
+
+impl QueueInode {
+    /// Returns the message queue.
+    pub fn queue(&self) -> &Arc<MessageQueue> {
+        &self.queue
+    }
+}
+
+impl InodeIo for QueueInode {
+    fn read_at(
+        &self,
+        offset: usize,
+        writer: &mut VmWriter,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        read_queue_status(&self.queue, offset, writer)
+    }
+
+    fn write_at(
+        &self,
+        _offset: usize,
+        _reader: &mut VmReader,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        return_errno_with_message!(Errno::EINVAL, "message queue files cannot be written")
+    }
+}
+
+impl Inode for QueueInode {
+    fn size(&self) -> usize {
+        self.metadata.read().size
+    }
+
+    fn resize(&self, _new_size: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino
+    }
+
+    fn type_(&self) -> InodeType {
+        self.metadata.read().type_
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    fn open(
+        &self,
+        _access_mode: AccessMode,
+        _status_flags: StatusFlags,
+    ) -> Option<Result<Box<dyn FileIo>>> {
+        Some(Ok(Box::new(QueueFile {
+            queue: self.queue.clone(),
+        })))
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+}
+
+/// An opened message queue, i.e., a message queue descriptor.
+struct QueueFile {
+    queue: Arc<MessageQueue>,
+}
+
+impl Pollable for QueueFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.queue.poll(mask, poller)
+    }
+}
+
+impl InodeIo for QueueFile {
+    fn read_at(
+        &self,
+        offset: usize,
+        writer: &mut VmWriter,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        read_queue_status(&self.queue, offset, writer)
+    }
+
+    fn write_at(
+        &self,
+        _offset: usize,
+        _reader: &mut VmReader,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        return_errno_with_message!(Errno::EINVAL, "message queue files cannot be written")
+    }
+}
+
+impl FileIo for QueueFile {
+    fn check_seekable(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_offset_aware(&self) -> bool {
+        true
+    }
+}
+
+fn read_queue_status(queue: &MessageQueue, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+    let mut printer = VmPrinter::new_skip(writer, offset);
+    queue.write_status(&mut printer)?;
+    Ok(printer.bytes_written())
+}
+
+fn now() -> Duration {
+    RealTimeCoarseClock::get().read_time()
+}
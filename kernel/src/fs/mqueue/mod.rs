@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! POSIX message queues and the mqueue file system.
+
+use spin::Once;
+
+pub use self::{
+    fs::{MqueueFs, QueueInode},
+    queue::{MqAttr, Notification, NotifyKind, NOTIFY_COOKIE_LEN},
+};
+use crate::{
+    fs::path::{Mount, Path},
+    prelude::*,
+};
+
+mod fs;
+mod queue;
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/magic.h>
+const MQUEUE_MAGIC: u64 = 0x1980_0202;
+const BLOCK_SIZE: usize = 4096;
+const ROOT_INO: u64 = 1;
+
+// The following constant values are derived from the default values in Linux.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/ipc_namespace.h>
+
+/// Maximum number of queues.
+const QUEUES_MAX: usize = 256;
+/// Default number of messages of a queue.
+const DEFAULT_MSGS: usize = 10;
+/// Maximum number of messages of a queue.
+const MSG_MAX: usize = 10;
+/// Maximum number of messages of a queue for privileged processes.
+const HARD_MSG_MAX: usize = 65536;
+/// Default size in bytes of a message.
+const DEFAULT_MSGSIZE: usize = 8192;
+/// Maximum size in bytes of a message.
+const MSGSIZE_MAX: usize = 8192;
+/// Maximum size in bytes of a message for privileged processes.
+const HARD_MSGSIZE_MAX: usize = 16 * 1024 * 1024;
+
+/// The upper bound (exclusive) of message priorities.
+pub const MQ_PRIO_MAX: u32 = 32768;
+
+/// Returns the root directory of the mqueue file system.
+///
+/// The directory belongs to an internal mount that is not visible to user space. It is used to
+/// open message queues by names, regardless of where the file system is mounted.
+pub fn root_path() -> &'static Path {
+    static ROOT_PATH: Once<Path> = Once::new();
+
+    ROOT_PATH.call_once(|| {
+        let mount = Mount::new_root(MqueueFs::singleton().clone(), Weak::new());
+        Path::new_fs_root(mount)
+    })
+}
+
+pub(super) fn init() {
+    super::registry::register(&fs::MqueueFsType).unwrap();
+}
@@ -0,0 +1,399 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{fmt::Write, time::Duration};
+
+use super::MQ_PRIO_MAX;
+use crate::{
+    events::IoEvents,
+    net::socket::netlink::RawMessageReceiver,
+    prelude::*,
+    process::{
+        signal::{
+            c_types::{siginfo_t, sigval_t, SigNotify},
+            constants::SI_MESGQ,
+            sig_num::SigNum,
+            signals::Signal,
+            PollHandle, Pollable, Pollee,
+        },
+        Pid, Process, Uid,
+    },
+};
+
+/// The attributes of a message queue.
+///
+/// This has the same layout as `struct mq_attr` in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Pod)]
+pub struct MqAttr {
+    /// Flags of the message queue description (i.e., `O_NONBLOCK` or 0)
+    pub mq_flags: i64,
+    /// Maximum number of messages in the queue
+    pub mq_maxmsg: i64,
+    /// Maximum size in bytes of a message
+    pub mq_msgsize: i64,
+    /// Current number of messages in the queue
+    pub mq_curmsgs: i64,
+    _reserved: [i64; 4],
+}
+
+/// A POSIX message queue.
+pub struct MessageQueue {
+    max_msgs: usize,
+    max_msg_size: usize,
+    inner: Mutex<QueueInner>,
+    pollee: Pollee,
+}
+
+struct QueueInner {
+    /// Messages grouped by their priorities, in the order of their arrival.
+    messages: BTreeMap<u32, VecDeque<Box<[u8]>>>,
+    num_msgs: usize,
+    total_bytes: usize,
+    /// The process that will be notified when a message arrives at the empty queue
+    notification: Option<Notification>,
+    /// The number of receivers that are blocked waiting for messages
+    num_blocked_receivers: usize,
+}
+
+impl MessageQueue {
+    pub(super) fn new(max_msgs: usize, max_msg_size: usize) -> Self {
+        Self {
+            max_msgs,
+            max_msg_size,
+            inner: Mutex::new(QueueInner {
+                messages: BTreeMap::new(),
+                num_msgs: 0,
+                total_bytes: 0,
+                notification: None,
+                num_blocked_receivers: 0,
+            }),
+            pollee: Pollee::new(),
+        }
+    }
+
+    /// Returns the attributes of the queue.
+    ///
+    /// The `mq_flags` field is always zero since it belongs to the queue description.
+    pub fn attr(&self) -> MqAttr {
+        MqAttr {
+            mq_maxmsg: self.max_msgs as i64,
+            mq_msgsize: self.max_msg_size as i64,
+            mq_curmsgs: self.inner.lock().num_msgs as i64,
+            ..MqAttr::default()
+        }
+    }
+
+    /// Sends a message with the priority to the queue.
+    ///
+    /// If the queue is full, this method blocks until there is free space or the timeout is
+    /// reached, unless `is_nonblocking` is true.
+    pub fn send(
+        &self,
+        message: Box<[u8]>,
+        priority: u32,
+        is_nonblocking: bool,
+        timeout: Option<&Duration>,
+    ) -> Result<()> {
+        if message.len() > self.max_msg_size {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+        }
+        debug_assert!(priority < MQ_PRIO_MAX);
+
+        let mut message = Some(message);
+        let mut try_send = || self.try_send(&mut message, priority);
+
+        if is_nonblocking {
+            try_send()
+        } else {
+            self.wait_events(IoEvents::OUT, timeout, try_send)
+        }
+    }
+
+    fn try_send(&self, message: &mut Option<Box<[u8]>>, priority: u32) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.num_msgs >= self.max_msgs {
+            return_errno_with_message!(Errno::EAGAIN, "the message queue is full");
+        }
+
+        let message = message.take().unwrap();
+        let was_empty = inner.num_msgs == 0;
+        inner.num_msgs += 1;
+        inner.total_bytes += message.len();
+        inner
+            .messages
+            .entry(priority)
+            .or_default()
+            .push_back(message);
+
+        // The notification is only triggered if no receiver is waiting for the message.
+        let notification = if was_empty && inner.num_blocked_receivers == 0 {
+            inner.notification.take()
+        } else {
+            None
+        };
+        drop(inner);
+
+        self.pollee.notify(IoEvents::IN);
+        if let Some(notification) = notification {
+            notification.notify();
+        }
+
+        Ok(())
+    }
+
+    /// Receives the oldest message of the highest priority from the queue.
+    ///
+    /// If the queue is empty, this method blocks until a message arrives or the timeout is
+    /// reached, unless `is_nonblocking` is true.
+    pub fn receive(
+        &self,
+        max_len: usize,
+        is_nonblocking: bool,
+        timeout: Option<&Duration>,
+    ) -> Result<(Box<[u8]>, u32)> {
+        if max_len < self.max_msg_size {
+            return_errno_with_message!(
+                Errno::EMSGSIZE,
+                "the buffer is smaller than the maximum message size"
+            );
+        }
+
+        if is_nonblocking {
+            return self.try_receive();
+        }
+
+        self.inner.lock().num_blocked_receivers += 1;
+        let res = self.wait_events(IoEvents::IN, timeout, || self.try_receive());
+        self.inner.lock().num_blocked_receivers -= 1;
+
+        res
+    }
+
+    fn try_receive(&self) -> Result<(Box<[u8]>, u32)> {
+        let mut inner = self.inner.lock();
+
+        let Some(mut entry) = inner.messages.last_entry() else {
+            return_errno_with_message!(Errno::EAGAIN, "the message queue is empty");
+        };
+        let priority = *entry.key();
+        let message = entry.get_mut().pop_front().unwrap();
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+
+        inner.num_msgs -= 1;
+        inner.total_bytes -= message.len();
+        drop(inner);
+
+        self.pollee.notify(IoEvents::OUT);
+
+        Ok((message, priority))
+    }
+
+    /// Registers the process to be notified when a message arrives at the empty queue.
+    ///
+    /// Only one process can be registered at a time.
+    pub fn register_notification(&self, notification: Notification) -> Result<()> {
+        let mut inner = self.inner.lock();
+
+        if inner
+            .notification
+            .as_ref()
+            .is_some_and(|old| old.process.strong_count() > 0)
+        {
+            return_errno_with_message!(Errno::EBUSY, "a process has been registered");
+        }
+        inner.notification = Some(notification);
+
+        Ok(())
+    }
+
+    /// Removes the registration if the process is the registered one.
+    pub fn unregister_notification(&self, pid: Pid) {
+        let mut inner = self.inner.lock();
+
+        if inner
+            .notification
+            .as_ref()
+            .is_some_and(|notification| notification.pid == pid)
+        {
+            let notification = inner.notification.take().unwrap();
+            drop(inner);
+            notification.remove();
+        }
+    }
+
+    /// Writes the status of the queue, which is the content of the queue file.
+    pub(super) fn write_status(&self, printer: &mut dyn Write) -> core::fmt::Result {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/mqueue.c>
+        let inner = self.inner.lock();
+
+        let (notify, signo, pid) = match inner.notification.as_ref() {
+            Some(Notification {
+                kind: NotifyKind::Signal { num, .. },
+                pid,
+                ..
+            }) => (SigNotify::SIGEV_SIGNAL as i32, num.as_u8() as i32, *pid),
+            Some(Notification {
+                kind: NotifyKind::Thread { socket_fd, .. },
+                pid,
+                ..
+            }) => (SigNotify::SIGEV_THREAD as i32, *socket_fd, *pid),
+            Some(Notification {
+                kind: NotifyKind::None,
+                pid,
+                ..
+            }) => (SigNotify::SIGEV_NONE as i32, 0, *pid),
+            None => (0, 0, 0),
+        };
+
+        writeln!(
+            printer,
+            "QSIZE:{:<10} NOTIFY:{:<5} SIGNO:{:<5} NOTIFY_PID:{:<6}",
+            inner.total_bytes, notify, signo, pid
+        )
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let inner = self.inner.lock();
+
+        let mut events = IoEvents::empty();
+        if inner.num_msgs > 0 {
+            events |= IoEvents::IN;
+        }
+        if inner.num_msgs < self.max_msgs {
+            events |= IoEvents::OUT;
+        }
+
+        events
+    }
+}
+
+impl Pollable for MessageQueue {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+/// A registration for the message arrival notification.
+pub struct Notification {
+    process: Weak<Process>,
+    pid: Pid,
+    kind: NotifyKind,
+}
+
+/// How a process is notified of the message arrival.
+pub enum NotifyKind {
+    /// The process is not notified, but the registration is still taken.
+    None,
+    /// A signal is sent to the process.
+    Signal { num: SigNum, value: sigval_t },
+    /// A cookie is sent to the netlink socket.
+    ///
+    /// This is how C libraries implement `SIGEV_THREAD`. They receive the cookie from the socket
+    /// and start a thread to run the notification function.
+    Thread {
+        socket_fd: i32,
+        receiver: Box<dyn RawMessageReceiver>,
+        cookie: [u8; NOTIFY_COOKIE_LEN],
+    },
+}
+
+/// The length of the cookie sent to the netlink socket.
+pub const NOTIFY_COOKIE_LEN: usize = 32;
+
+// The last byte of the cookie indicates why the cookie is sent.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/mqueue.c#L59>
+const NOTIFY_WOKENUP: u8 = 1;
+const NOTIFY_REMOVED: u8 = 2;
+
+impl Notification {
+    pub fn new(process: &Arc<Process>, kind: NotifyKind) -> Self {
+        Self {
+            process: Arc::downgrade(process),
+            pid: process.pid(),
+            kind,
+        }
+    }
+
+    /// Notifies the registered process on behalf of the current process.
+    fn notify(self) {
+        let (num, value) = match self.kind {
+            NotifyKind::None => return,
+            NotifyKind::Signal { num, value } => (num, value),
+            NotifyKind::Thread {
+                receiver,
+                mut cookie,
+                ..
+            } => {
+                cookie[NOTIFY_COOKIE_LEN - 1] = NOTIFY_WOKENUP;
+                receiver.enqueue_raw(Box::new(cookie));
+                return;
+            }
+        };
+        let Some(process) = self.process.upgrade() else {
+            return;
+        };
+
+        let sender = current!();
+        let uid = current_thread!()
+            .as_posix_thread()
+            .unwrap()
+            .credentials()
+            .ruid();
+        process.enqueue_signal(MqueueSignal {
+            num,
+            pid: sender.pid(),
+            uid,
+            value,
+        });
+    }
+
+    /// Tells the registered process that the registration is removed.
+    fn remove(self) {
+        // C libraries release the resources of the notification (e.g., the thread attributes)
+        // when they receive the cookie.
+        if let NotifyKind::Thread {
+            receiver,
+            mut cookie,
+            ..
+        } = self.kind
+        {
+            cookie[NOTIFY_COOKIE_LEN - 1] = NOTIFY_REMOVED;
+            receiver.enqueue_raw(Box::new(cookie));
+        }
+    }
+}
+
+/// The signal sent to notify the message arrival.
+#[derive(Clone, Copy)]
+struct MqueueSignal {
+    num: SigNum,
+    pid: Pid,
+    uid: Uid,
+    value: sigval_t,
+}
+
+impl Debug for MqueueSignal {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MqueueSignal")
+            .field("num", &self.num)
+            .field("pid", &self.pid)
+            .field("uid", &self.uid)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signal for MqueueSignal {
+    fn num(&self) -> SigNum {
+        self.num
+    }
+
+    fn to_info(&self) -> siginfo_t {
+        let mut info = siginfo_t::new(self.num, SI_MESGQ);
+        info.set_pid_uid(self.pid, self.uid);
+        info.set_si_value(self.value);
+        info
+    }
+}
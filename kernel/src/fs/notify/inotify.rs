@@ -0,0 +1,481 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The inotify API.
+//!
+//! An inotify instance ([`InotifyFile`]) holds a list of watches and a queue of events.
+//! Each watch subscribes to the [`FsEventPublisher`] of the watched inode, and turns the
+//! published events into `inotify_event`s that can be read from the instance.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man7/inotify.7.html>
+
+use core::{
+    fmt::Display,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+};
+
+use super::{FsEventPublisher, FsEventSubscriber, FsEvents};
+use crate::{
+    current_userspace,
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        file_table::FdFlags,
+        path::RESERVED_MOUNT_ID,
+        pseudofs::anon_inodefs_shared_inode,
+        utils::{CreationFlags, Inode, IoctlCmd, StatusFlags},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+};
+
+bitflags! {
+    /// The special flags in the mask of `inotify_add_watch`.
+    pub struct InotifyWatchFlags: u32 {
+        /// Only watches the path if it is a directory.
+        const IN_ONLYDIR     = 0x0100_0000;
+        /// Does not dereference the path if it is a symbolic link.
+        const IN_DONT_FOLLOW = 0x0200_0000;
+        /// Excludes the events on children after they have been unlinked.
+        const IN_EXCL_UNLINK = 0x0400_0000;
+        /// Only creates new watches, fails if the watch already exists.
+        const IN_MASK_CREATE = 0x1000_0000;
+        /// Adds the events to the existing watch mask instead of replacing it.
+        const IN_MASK_ADD    = 0x2000_0000;
+        /// Removes the watch after one event.
+        const IN_ONESHOT     = 0x8000_0000;
+    }
+}
+
+/// An inotify instance.
+pub struct InotifyFile {
+    watches: SpinLock<InotifyWatches>,
+    event_queue: SpinLock<VecDeque<InotifyEvent>>,
+    pollee: Pollee,
+    is_nonblocking: AtomicBool,
+    this: Weak<InotifyFile>,
+}
+
+struct InotifyWatches {
+    next_wd: u32,
+    entries: BTreeMap<u32, InotifyWatchEntry>,
+}
+
+impl InotifyWatches {
+    /// Allocates a watch descriptor that is not in use.
+    ///
+    /// Like `idr_alloc_cyclic` in Linux, the descriptors are allocated cyclically, skipping the
+    /// ones that are still in use. The caller must ensure that there are fewer than
+    /// [`InotifyFile::MAX_WATCHES`] watches.
+    fn alloc_wd(&mut self) -> u32 {
+        debug_assert!(self.entries.len() < InotifyFile::MAX_WATCHES);
+
+        // At most `MAX_WATCHES - 1` descriptors are in use, so an unused one will be found within
+        // `MAX_WATCHES` tries.
+        for _ in 0..InotifyFile::MAX_WATCHES {
+            let wd = self.next_wd;
+            self.next_wd = wd
+                .checked_add(1)
+                .filter(|next_wd| *next_wd <= i32::MAX as u32)
+                .unwrap_or(1);
+
+            if !self.entries.contains_key(&wd) {
+                return wd;
+            }
+        }
+
+        unreachable!("there are too many watches")
+    }
+}
+
+struct InotifyWatchEntry {
+    watch: Arc<InotifyWatch>,
+    publisher: Weak<FsEventPublisher>,
+}
+
+impl InotifyFile {
+    /// The maximum number of queued events.
+    ///
+    /// This is the default value of `/proc/sys/fs/inotify/max_queued_events` in Linux.
+    const MAX_QUEUED_EVENTS: usize = 16384;
+
+    /// The maximum number of watches per instance.
+    ///
+    /// This is the default value of `/proc/sys/fs/inotify/max_user_watches` in Linux.
+    const MAX_WATCHES: usize = 8192;
+
+    /// Creates a new inotify instance.
+    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+        Arc::new_cyclic(|weak_self| Self {
+            watches: SpinLock::new(InotifyWatches {
+                next_wd: 1,
+                entries: BTreeMap::new(),
+            }),
+            event_queue: SpinLock::new(VecDeque::new()),
+            pollee: Pollee::new(),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            this: weak_self.clone(),
+        })
+    }
+
+    /// Adds a new watch or modifies an existing watch for the inode.
+    ///
+    /// Returns the watch descriptor on success.
+    pub fn add_watch(
+        &self,
+        inode: &Arc<dyn Inode>,
+        events: FsEvents,
+        flags: InotifyWatchFlags,
+    ) -> Result<u32> {
+        let Some(publisher) = inode.fs_event_publisher_or_default() else {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the file cannot be watched");
+        };
+
+        let mut watches = self.watches.lock();
+
+        let existing = watches
+            .entries
+            .iter()
+            .find(|(_, entry)| core::ptr::eq(entry.publisher.as_ptr(), Arc::as_ptr(&publisher)));
+        if let Some((wd, entry)) = existing {
+            if flags.contains(InotifyWatchFlags::IN_MASK_CREATE) {
+                return_errno_with_message!(Errno::EEXIST, "the watch already exists");
+            }
+
+            let new_events = if flags.contains(InotifyWatchFlags::IN_MASK_ADD) {
+                entry.watch.interest() | events
+            } else {
+                events
+            };
+            entry.watch.set_interest(new_events, flags);
+            return Ok(*wd);
+        }
+
+        if watches.entries.len() >= Self::MAX_WATCHES {
+            return_errno_with_message!(Errno::ENOSPC, "too many watches");
+        }
+
+        let wd = watches.alloc_wd();
+
+        let watch = Arc::new(InotifyWatch {
+            wd,
+            interest: AtomicU32::new(0),
+            flags: AtomicU32::new(0),
+            owner: self.this.clone(),
+        });
+        watch.set_interest(events, flags);
+        publisher.add_subscriber(watch.clone());
+
+        watches.entries.insert(
+            wd,
+            InotifyWatchEntry {
+                watch,
+                publisher: Arc::downgrade(&publisher),
+            },
+        );
+
+        Ok(wd)
+    }
+
+    /// Removes the watch with the watch descriptor.
+    pub fn remove_watch(&self, wd: u32) -> Result<()> {
+        let Some(entry) = self.watches.lock().entries.remove(&wd) else {
+            return_errno_with_message!(Errno::EINVAL, "the watch descriptor is not valid");
+        };
+
+        if let Some(publisher) = entry.publisher.upgrade() {
+            publisher.remove_subscriber(&(entry.watch as Arc<dyn FsEventSubscriber>));
+        }
+        self.push_event(InotifyEvent::new(wd as i32, FsEvents::IGNORED, 0, None));
+
+        Ok(())
+    }
+
+    /// Handles a watch that has been detached by the publisher.
+    fn on_watch_detached(&self, wd: u32) {
+        if self.watches.lock().entries.remove(&wd).is_none() {
+            return;
+        }
+
+        self.push_event(InotifyEvent::new(wd as i32, FsEvents::IGNORED, 0, None));
+    }
+
+    fn push_event(&self, event: InotifyEvent) {
+        let mut event_queue = self.event_queue.lock();
+
+        // Merge the event with the last one if they are identical, as Linux does.
+        if event_queue.back().is_some_and(|last| *last == event) {
+            return;
+        }
+
+        if event_queue.len() >= Self::MAX_QUEUED_EVENTS {
+            let overflow = InotifyEvent::new(-1, FsEvents::Q_OVERFLOW, 0, None);
+            if event_queue.back() != Some(&overflow) {
+                event_queue.push_back(overflow);
+            }
+        } else {
+            event_queue.push_back(event);
+        }
+        drop(event_queue);
+
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let events = {
+            let mut event_queue = self.event_queue.lock();
+            if event_queue.is_empty() {
+                return_errno_with_message!(Errno::EAGAIN, "there are no inotify events");
+            }
+
+            let mut events = Vec::new();
+            let mut total_len = 0;
+            while let Some(event) = event_queue.front() {
+                if total_len + event.len() > writer.avail() {
+                    break;
+                }
+                total_len += event.len();
+                events.push(event_queue.pop_front().unwrap());
+            }
+
+            if events.is_empty() {
+                return_errno_with_message!(Errno::EINVAL, "the buffer is too small");
+            }
+
+            events
+        };
+        self.pollee.invalidate();
+
+        // Write the events after releasing the spin lock,
+        // since writing to the user space may sleep.
+        let mut read_len = 0;
+        for event in events.iter() {
+            read_len += event.write_to(writer)?;
+        }
+
+        Ok(read_len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        if self.event_queue.lock().is_empty() {
+            IoEvents::empty()
+        } else {
+            IoEvents::IN
+        }
+    }
+
+    fn queued_bytes(&self) -> usize {
+        self.event_queue.lock().iter().map(InotifyEvent::len).sum()
+    }
+}
+
+impl Pollable for InotifyFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for InotifyFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        if self.is_nonblocking.load(Ordering::Relaxed) {
+            self.try_read(writer)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(writer))
+        }
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        match cmd {
+            IoctlCmd::FIONREAD => {
+                let len = self.queued_bytes() as i32;
+                current_userspace!().write_val(arg, &len)?;
+                Ok(0)
+            }
+            _ => return_errno_with_message!(Errno::EINVAL, "the ioctl command is not supported"),
+        }
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking.load(Ordering::Relaxed) {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        self.is_nonblocking.store(
+            new_flags.contains(StatusFlags::O_NONBLOCK),
+            Ordering::Relaxed,
+        );
+        Ok(())
+    }
+
+    fn inode(&self) -> &Arc<dyn Inode> {
+        anon_inodefs_shared_inode()
+    }
+
+    fn dump_proc_fdinfo(self: Arc<Self>, fd_flags: FdFlags) -> Box<dyn Display> {
+        struct FdInfo {
+            flags: u32,
+            ino: u64,
+            watches: Vec<(u32, u32)>,
+        }
+
+        impl Display for FdInfo {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                writeln!(f, "pos:\t{}", 0)?;
+                writeln!(f, "flags:\t0{:o}", self.flags)?;
+                // TODO: This should be the mount ID of the pseudo filesystem.
+                writeln!(f, "mnt_id:\t{}", RESERVED_MOUNT_ID)?;
+                writeln!(f, "ino:\t{}", self.ino)?;
+                for (wd, mask) in self.watches.iter() {
+                    writeln!(f, "inotify wd:{:x} mask:{:x}", wd, mask)?;
+                }
+                Ok(())
+            }
+        }
+
+        let mut flags = self.status_flags().bits() | self.access_mode() as u32;
+        if fd_flags.contains(FdFlags::CLOEXEC) {
+            flags |= CreationFlags::O_CLOEXEC.bits();
+        }
+
+        let watches = self
+            .watches
+            .lock()
+            .entries
+            .iter()
+            .map(|(wd, entry)| (*wd, entry.watch.interest().bits()))
+            .collect();
+
+        Box::new(FdInfo {
+            flags,
+            ino: self.inode().ino(),
+            watches,
+        })
+    }
+}
+
+impl Drop for InotifyFile {
+    fn drop(&mut self) {
+        let entries = core::mem::take(&mut self.watches.get_mut().entries);
+        for (_, entry) in entries {
+            if let Some(publisher) = entry.publisher.upgrade() {
+                publisher.remove_subscriber(&(entry.watch as Arc<dyn FsEventSubscriber>));
+            }
+        }
+    }
+}
+
+/// A watch of an inotify instance.
+struct InotifyWatch {
+    wd: u32,
+    interest: AtomicU32,
+    flags: AtomicU32,
+    owner: Weak<InotifyFile>,
+}
+
+impl InotifyWatch {
+    fn set_interest(&self, events: FsEvents, flags: InotifyWatchFlags) {
+        self.interest.store(events.bits(), Ordering::Relaxed);
+        self.flags.store(flags.bits(), Ordering::Relaxed);
+    }
+
+    fn flags(&self) -> InotifyWatchFlags {
+        InotifyWatchFlags::from_bits_truncate(self.flags.load(Ordering::Relaxed))
+    }
+}
+
+impl FsEventSubscriber for InotifyWatch {
+    fn interest(&self) -> FsEvents {
+        FsEvents::from_bits_truncate(self.interest.load(Ordering::Relaxed))
+    }
+
+    fn deliver_event(&self, events: FsEvents, cookie: u32, name: Option<&str>) {
+        let Some(owner) = self.owner.upgrade() else {
+            return;
+        };
+
+        if events.contains(FsEvents::IGNORED) {
+            owner.on_watch_detached(self.wd);
+            return;
+        }
+
+        let reported = events & (self.interest() | FsEvents::ISDIR | FsEvents::UNMOUNT);
+        if (reported - FsEvents::ISDIR).is_empty() {
+            return;
+        }
+        owner.push_event(InotifyEvent::new(self.wd as i32, reported, cookie, name));
+
+        if self.flags().contains(InotifyWatchFlags::IN_ONESHOT) {
+            let _ = owner.remove_watch(self.wd);
+        }
+    }
+}
+
+/// An event in the queue of an inotify instance.
+#[derive(Debug, PartialEq, Eq)]
+struct InotifyEvent {
+    wd: i32,
+    mask: u32,
+    cookie: u32,
+    name: Option<String>,
+}
+
+impl InotifyEvent {
+    fn new(wd: i32, events: FsEvents, cookie: u32, name: Option<&str>) -> Self {
+        Self {
+            wd,
+            mask: events.bits(),
+            cookie,
+            name: name.map(String::from),
+        }
+    }
+
+    /// Returns the length of the name field, including the padding null bytes.
+    fn name_len(&self) -> usize {
+        // Linux pads the name with null bytes to align the next event.
+        const ALIGN: usize = size_of::<InotifyEventHeader>();
+
+        match self.name.as_ref() {
+            Some(name) => (name.len() + 1).next_multiple_of(ALIGN),
+            None => 0,
+        }
+    }
+
+    /// Returns the total length of the event when read from the user space.
+    fn len(&self) -> usize {
+        size_of::<InotifyEventHeader>() + self.name_len()
+    }
+
+    fn write_to(&self, writer: &mut VmWriter) -> Result<usize> {
+        let header = InotifyEventHeader {
+            wd: self.wd,
+            mask: self.mask,
+            cookie: self.cookie,
+            len: self.name_len() as u32,
+        };
+        writer.write_val(&header)?;
+
+        if let Some(name) = self.name.as_ref() {
+            let mut name_buf = vec![0u8; self.name_len()];
+            name_buf[..name.len()].copy_from_slice(name.as_bytes());
+            writer.write_fallible(&mut name_buf.as_slice().into())?;
+        }
+
+        Ok(self.len())
+    }
+}
+
+/// The header of an event read from an inotify instance.
+///
+/// This is the `inotify_event` structure in Linux, without the trailing name.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct InotifyEventHeader {
+    wd: i32,
+    mask: u32,
+    cookie: u32,
+    len: u32,
+}
@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! File system event notification.
+//!
+//! Each watched inode owns an [`FsEventPublisher`] stored in its [`Extension`].
+//! The VFS layer (mainly [`Dentry`] and [`InodeHandle`]) publishes [`FsEvents`]
+//! to the publisher, which forwards them to all the interested
+//! [`FsEventSubscriber`]s, e.g., the watches of an inotify instance.
+//!
+//! Since the events are published at the VFS layer, the notification works
+//! uniformly for all file systems whose inodes provide an [`Extension`].
+//!
+//! [`Extension`]: crate::fs::utils::Extension
+//! [`Dentry`]: crate::fs::path
+//! [`InodeHandle`]: crate::fs::inode_handle::InodeHandle
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+pub use inotify::InotifyFile;
+
+use crate::{fs::utils::Inode, prelude::*};
+
+pub mod inotify;
+
+bitflags! {
+    /// File system events.
+    ///
+    /// The values are the same as the ones used by inotify in Linux.
+    pub struct FsEvents: u32 {
+        /// The file was accessed (e.g., read).
+        const ACCESS        = 0x0000_0001;
+        /// The file was modified (e.g., written or truncated).
+        const MODIFY        = 0x0000_0002;
+        /// The metadata (e.g., permissions, timestamps, xattrs, link count) changed.
+        const ATTRIB        = 0x0000_0004;
+        /// A file opened for writing was closed.
+        const CLOSE_WRITE   = 0x0000_0008;
+        /// A file not opened for writing was closed.
+        const CLOSE_NOWRITE = 0x0000_0010;
+        /// The file was opened.
+        const OPEN          = 0x0000_0020;
+        /// A file was moved out of the watched directory.
+        const MOVED_FROM    = 0x0000_0040;
+        /// A file was moved into the watched directory.
+        const MOVED_TO      = 0x0000_0080;
+        /// A file was created in the watched directory.
+        const CREATE        = 0x0000_0100;
+        /// A file was deleted from the watched directory.
+        const DELETE        = 0x0000_0200;
+        /// The watched file itself was deleted.
+        const DELETE_SELF   = 0x0000_0400;
+        /// The watched file itself was moved.
+        const MOVE_SELF     = 0x0000_0800;
+        /// The file system containing the watched file was unmounted.
+        const UNMOUNT       = 0x0000_2000;
+        /// The event queue overflowed.
+        const Q_OVERFLOW    = 0x0000_4000;
+        /// The watch was removed.
+        const IGNORED       = 0x0000_8000;
+        /// The subject of the event is a directory.
+        const ISDIR         = 0x4000_0000;
+
+        const CLOSE = Self::CLOSE_WRITE.bits | Self::CLOSE_NOWRITE.bits;
+        const MOVE = Self::MOVED_FROM.bits | Self::MOVED_TO.bits;
+        const ALL_EVENTS = 0x0000_0fff;
+    }
+}
+
+/// A subscriber of file system events.
+pub trait FsEventSubscriber: Send + Sync {
+    /// Returns the events that the subscriber is interested in.
+    fn interest(&self) -> FsEvents;
+
+    /// Delivers the events to the subscriber.
+    ///
+    /// `name` is the name of the child within the watched directory that the events
+    /// happen on, or `None` if the events happen on the watched file itself.
+    fn deliver_event(&self, events: FsEvents, cookie: u32, name: Option<&str>);
+}
+
+/// A publisher of file system events.
+///
+/// There is at most one publisher for each inode.
+pub struct FsEventPublisher {
+    subscribers: RwLock<Vec<Arc<dyn FsEventSubscriber>>>,
+}
+
+impl FsEventPublisher {
+    /// Creates a publisher without any subscribers.
+    pub fn new() -> Self {
+        Self {
+            subscribers: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Adds a subscriber.
+    pub fn add_subscriber(&self, subscriber: Arc<dyn FsEventSubscriber>) {
+        self.subscribers.write().push(subscriber);
+    }
+
+    /// Removes a subscriber.
+    ///
+    /// Returns whether the subscriber was found and removed.
+    pub fn remove_subscriber(&self, subscriber: &Arc<dyn FsEventSubscriber>) -> bool {
+        let mut subscribers = self.subscribers.write();
+        let Some(pos) = subscribers
+            .iter()
+            .position(|existing| Arc::ptr_eq(existing, subscriber))
+        else {
+            return false;
+        };
+        subscribers.swap_remove(pos);
+        true
+    }
+
+    /// Returns whether there are any subscribers.
+    pub fn has_subscribers(&self) -> bool {
+        !self.subscribers.read().is_empty()
+    }
+
+    /// Publishes the events to all the interested subscribers.
+    pub fn publish(&self, events: FsEvents, cookie: u32, name: Option<&str>) {
+        // Clone the subscribers so that they can remove themselves
+        // (e.g., one-shot watches) during the delivery.
+        let subscribers = self.subscribers.read().clone();
+        for subscriber in subscribers.iter() {
+            if subscriber.interest().intersects(events - FsEvents::ISDIR) {
+                subscriber.deliver_event(events, cookie, name);
+            }
+        }
+    }
+
+    /// Detaches all the subscribers, notifying each of them with `IGNORED`.
+    ///
+    /// This is called when the file is gone, so no more events will be published.
+    pub fn detach_all(&self) {
+        let subscribers = core::mem::take(&mut *self.subscribers.write());
+        for subscriber in subscribers.iter() {
+            subscriber.deliver_event(FsEvents::IGNORED, 0, None);
+        }
+    }
+}
+
+impl Default for FsEventPublisher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for FsEventPublisher {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("FsEventPublisher")
+            .field("num_subscribers", &self.subscribers.read().len())
+            .finish()
+    }
+}
+
+/// Publishes the events to the subscribers of the inode.
+pub fn publish_fs_events(inode: &dyn Inode, events: FsEvents, cookie: u32, name: Option<&str>) {
+    if let Some(publisher) = inode.fs_event_publisher() {
+        publisher.publish(events, cookie, name);
+    }
+}
+
+/// Allocates a cookie to associate the `MOVED_FROM` and `MOVED_TO` events of a rename.
+pub fn alloc_move_cookie() -> u32 {
+    static NEXT_COOKIE: AtomicU32 = AtomicU32::new(1);
+
+    NEXT_COOKIE.fetch_add(1, Ordering::Relaxed)
+}
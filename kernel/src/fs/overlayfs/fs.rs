@@ -24,9 +24,10 @@ use crate::{
         path::Path,
         registry::{FsProperties, FsType},
         utils::{
-            mkmod, AccessMode, DirentCounter, DirentVisitor, FallocMode, FileSystem, FsFlags,
-            Inode, InodeIo, InodeMode, InodeType, Metadata, MknodType, StatusFlags, SuperBlock,
-            SymbolicLink, XattrName, XattrNamespace, XattrSetFlags, NAME_MAX, XATTR_VALUE_MAX_LEN,
+            mkmod, AccessMode, DirentCounter, DirentVisitor, Extension, FallocMode, FileSystem,
+            FsFlags, Inode, InodeIo, InodeMode, InodeType, Metadata, MknodType, StatusFlags,
+            SuperBlock, SymbolicLink, XattrName, XattrNamespace, XattrSetFlags, NAME_MAX,
+            XATTR_VALUE_MAX_LEN,
         },
     },
     prelude::*,
@@ -95,6 +96,8 @@ struct OverlayInode {
     upper_is_opaque: bool,
     /// The immutable lower layered regular inodes.
     lowers: Vec<Arc<dyn Inode>>,
+    /// The extension of the overlay inode.
+    extension: Extension,
     /// Weak fs reference.
     fs: Weak<OverlayFs>,
     /// Weak self reference.
@@ -176,6 +179,7 @@ impl FileSystem for OverlayFs {
                 .map(|path| path.inode())
                 .cloned()
                 .collect(),
+            extension: Extension::new(),
             fs: self.self_.clone(),
             self_: weak.clone(),
         })
@@ -271,6 +275,7 @@ impl OverlayInode {
             upper: Mutex::new(Some(new_upper)),
             upper_is_opaque,
             lowers: Vec::new(),
+            extension: Extension::new(),
             fs: self.fs.clone(),
             self_: weak.clone(),
         });
@@ -680,6 +685,7 @@ impl OverlayInode {
             upper: Mutex::new(upper_child),
             upper_is_opaque,
             lowers: lower_children,
+            extension: Extension::new(),
             fs: self.fs.clone(),
             self_: weak.clone(),
         });
@@ -959,6 +965,10 @@ impl Inode for OverlayInode {
     fn get_xattr(&self, name: XattrName, value_writer: &mut VmWriter) -> Result<usize>;
     fn list_xattr(&self, namespace: XattrNamespace, list_writer: &mut VmWriter) -> Result<usize>;
     fn remove_xattr(&self, name: XattrName) -> Result<()>;
+
+    fn extension(&self) -> Option<&Extension> {
+        Some(&self.extension)
+    }
 }
 
 /// The index of the layer of an `OverlayFs`.
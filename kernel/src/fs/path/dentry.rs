@@ -7,7 +7,10 @@ use ostd::sync::RwMutexWriteGuard;
 
 use super::is_dot_or_dotdot;
 use crate::{
-    fs::utils::{Inode, InodeMode, InodeType, MknodType},
+    fs::{
+        notify::{alloc_move_cookie, publish_fs_events, FsEvents},
+        utils::{Inode, InodeMode, InodeType, MknodType},
+    },
     prelude::*,
 };
 
@@ -155,6 +158,7 @@ impl Dentry {
         }
 
         let new_inode = self.inode.create(name, type_, mode)?;
+        self.notify_child_fs_events(name, type_, FsEvents::CREATE, 0);
         let name = String::from(name);
         let new_child = Dentry::new(new_inode, DentryOptions::Leaf((name.clone(), self.this())));
 
@@ -205,7 +209,9 @@ impl Dentry {
             return_errno!(Errno::EEXIST);
         }
 
+        let inode_type = type_.inode_type();
         let inode = self.inode.mknod(name, mode, type_)?;
+        self.notify_child_fs_events(name, inode_type, FsEvents::CREATE, 0);
         let name = String::from(name);
         let new_child = Dentry::new(inode, DentryOptions::Leaf((name.clone(), self.this())));
 
@@ -229,6 +235,8 @@ impl Dentry {
 
         let old_inode = old.inode();
         self.inode.link(old_inode, name)?;
+        publish_fs_events(old_inode.as_ref(), FsEvents::ATTRIB, 0, None);
+        self.notify_child_fs_events(name, old.type_(), FsEvents::CREATE, 0);
         let name = String::from(name);
         let dentry = Dentry::new(
             old_inode.clone(),
@@ -253,7 +261,15 @@ impl Dentry {
         self.inode.unlink(name)?;
 
         let mut children = children.upgrade();
-        children.delete(name);
+        let deleted = children.delete(name);
+        drop(children);
+
+        if let Some(dentry) = deleted {
+            self.notify_child_fs_events(name, dentry.type_(), FsEvents::DELETE, 0);
+            dentry.notify_unlinked();
+        } else {
+            self.notify_child_fs_events(name, InodeType::Unknown, FsEvents::DELETE, 0);
+        }
         Ok(())
     }
 
@@ -269,7 +285,13 @@ impl Dentry {
         self.inode.rmdir(name)?;
 
         let mut children = children.upgrade();
-        children.delete(name);
+        let deleted = children.delete(name);
+        drop(children);
+
+        self.notify_child_fs_events(name, InodeType::Dir, FsEvents::DELETE, 0);
+        if let Some(dentry) = deleted {
+            dentry.notify_unlinked();
+        }
         Ok(())
     }
 
@@ -293,6 +315,7 @@ impl Dentry {
             children.check_mountpoint(new_name)?;
 
             self.inode.rename(old_name, &self.inode, new_name)?;
+            self.notify_rename(old_name, self, new_name, old_dentry.as_ref());
 
             let mut children = children.upgrade();
             match old_dentry.as_ref() {
@@ -315,6 +338,7 @@ impl Dentry {
             new_dir_children.check_mountpoint(new_name)?;
 
             self.inode.rename(old_name, &new_dir.inode, new_name)?;
+            self.notify_rename(old_name, new_dir, new_name, old_dentry.as_ref());
             match old_dentry.as_ref() {
                 Some(dentry) => {
                     self_children.delete(old_name);
@@ -331,6 +355,78 @@ impl Dentry {
         Ok(())
     }
 
+    /// Publishes the events that happen on this `Dentry`.
+    ///
+    /// The events are delivered to the watchers of this `Dentry`'s inode and
+    /// to the watchers of its parent directory.
+    pub(super) fn notify_fs_events(&self, events: FsEvents) {
+        let events = fs_events_with_type(events, self.type_);
+        publish_fs_events(self.inode.as_ref(), events, 0, None);
+
+        let name_and_parent = self.name_and_parent.read().clone();
+        if let Some((name, parent)) = name_and_parent {
+            publish_fs_events(parent.inode.as_ref(), events, 0, Some(&name));
+        }
+    }
+
+    /// Publishes the events that happen on the child named `name` to the
+    /// watchers of this directory.
+    fn notify_child_fs_events(
+        &self,
+        name: &str,
+        child_type: InodeType,
+        events: FsEvents,
+        cookie: u32,
+    ) {
+        let events = fs_events_with_type(events, child_type);
+        publish_fs_events(self.inode.as_ref(), events, cookie, Some(name));
+    }
+
+    /// Publishes the events after a name of this `Dentry` is removed.
+    ///
+    /// If this is the last link of the inode, its watchers will be detached.
+    fn notify_unlinked(&self) {
+        let Some(publisher) = self.inode.fs_event_publisher() else {
+            return;
+        };
+
+        if self.type_ != InodeType::Dir && self.inode.metadata().nlinks > 0 {
+            publisher.publish(FsEvents::ATTRIB, 0, None);
+            return;
+        }
+
+        publisher.publish(
+            fs_events_with_type(FsEvents::DELETE_SELF, self.type_),
+            0,
+            None,
+        );
+        publisher.detach_all();
+    }
+
+    /// Publishes the events after the child `old_name` is renamed to `new_name`
+    /// in `new_dir`.
+    fn notify_rename(
+        &self,
+        old_name: &str,
+        new_dir: &Dentry,
+        new_name: &str,
+        old_dentry: Option<&Arc<Dentry>>,
+    ) {
+        let child_type = old_dentry.map_or(InodeType::Unknown, |dentry| dentry.type_());
+        let cookie = alloc_move_cookie();
+
+        self.notify_child_fs_events(old_name, child_type, FsEvents::MOVED_FROM, cookie);
+        new_dir.notify_child_fs_events(new_name, child_type, FsEvents::MOVED_TO, cookie);
+        if let Some(dentry) = old_dentry {
+            publish_fs_events(
+                dentry.inode.as_ref(),
+                fs_events_with_type(FsEvents::MOVE_SELF, child_type),
+                0,
+                None,
+            );
+        }
+    }
+
     /// Gets the absolute path name of this `Dentry` within the filesystem.
     pub(super) fn path_name(&self) -> String {
         let mut path_name = self.name().to_string();
@@ -388,6 +484,15 @@ impl DentryKey {
     }
 }
 
+/// Adds the `ISDIR` flag to the events if they happen on a directory.
+fn fs_events_with_type(events: FsEvents, type_: InodeType) -> FsEvents {
+    if type_ == InodeType::Dir {
+        events | FsEvents::ISDIR
+    } else {
+        events
+    }
+}
+
 bitflags! {
     struct DentryFlags: u32 {
         const MOUNTED = 1 << 0;
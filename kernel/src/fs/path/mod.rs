@@ -11,7 +11,8 @@ pub use mount_namespace::MountNamespace;
 use crate::{
     fs::{
         inode_handle::InodeHandle,
-        path::dentry::Dentry,
+        notify::FsEvents,
+        path::{dentry::Dentry, mount::MountCloneFlags},
         utils::{
             CreationFlags, FileSystem, FsFlags, Inode, InodeMode, InodeType, Metadata, MknodType,
             OpenArgs, Permission, StatusFlags, XattrName, XattrNamespace, XattrSetFlags, NAME_MAX,
@@ -55,6 +56,15 @@ impl Path {
         Ok(Self::new(self.mount.clone(), new_child_dentry))
     }
 
+    /// Creates a new `Path` to represent the root directory of a file system in a new
+    /// detached mount tree.
+    ///
+    /// The detached mount tree does not belong to any mount namespace until it is
+    /// attached via [`Self::move_mount_to`].
+    pub fn new_detached(fs: Arc<dyn FileSystem>, flags: PerMountFlags) -> Self {
+        Self::new_fs_root(Mount::new_detached(fs, flags))
+    }
+
     fn new(mount: Arc<Mount>, dentry: Arc<Dentry>) -> Self {
         Self { mount, dentry }
     }
@@ -111,6 +121,7 @@ impl Path {
             && !open_args.status_flags.contains(StatusFlags::O_PATH)
         {
             self.resize(0)?;
+            self.notify_fs_events(FsEvents::MODIFY);
         }
 
         InodeHandle::new(self.clone(), open_args.access_mode, open_args.status_flags)
@@ -207,11 +218,34 @@ impl Path {
         Some(corresponding_path)
     }
 
+    /// Returns true if the current `Path` is `ancestor` or one of its descendants.
+    ///
+    /// Mountpoints are crossed when going up, so the two `Path`s can be on different mounts.
+    fn is_equal_or_descendant_of(&self, ancestor: &Self) -> bool {
+        let mut current = Some(self.this());
+        while let Some(path) = current {
+            if path == *ancestor {
+                return true;
+            }
+            current = path.effective_parent();
+        }
+
+        false
+    }
+
     fn this(&self) -> Self {
         self.clone()
     }
 }
 
+impl PartialEq for Path {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.mount, &other.mount) && Arc::ptr_eq(&self.dentry, &other.dentry)
+    }
+}
+
+impl Eq for Path {}
+
 /// Checks if the given `Inode` can be opened with the given `OpenArgs`.
 pub(super) fn check_open_util(inode: &dyn Inode, open_args: &OpenArgs) -> Result<()> {
     let inode_type = inode.type_();
@@ -344,11 +378,13 @@ impl Path {
     /// Creates a new mount tree that mirrors either the root mount (non-recursive)
     /// or the entire mount subtree (recursive), and attaches it to the destination path.
     ///
+    /// Unbindable mounts in the subtree are skipped in the recursive case.
+    ///
     /// # Errors
     ///
     /// Returns `ENOTDIR` if the `dst_path` is not a directory.
-    /// Returns `EINVAL` if either source or destination path is not in the
-    /// current mount namespace.
+    /// Returns `EINVAL` if the mount of the current path is unbindable, or
+    /// if either source or destination path is not in the current mount namespace.
     pub fn bind_mount_to(&self, dst_path: &Self, recursive: bool, ctx: &Context) -> Result<()> {
         let can_bind = {
             let src_is_dir = self.type_() == InodeType::Dir;
@@ -377,34 +413,97 @@ impl Path {
             );
         }
 
-        let new_mount = self.mount.clone_mount_tree(&self.dentry, None, recursive);
+        if self.mount.is_unbindable() {
+            return_errno_with_message!(Errno::EINVAL, "the source mount is unbindable");
+        }
+
+        let new_mount =
+            self.mount
+                .clone_mount_tree(&self.dentry, None, recursive, MountCloneFlags::empty());
         new_mount.graft_mount_tree(dst_path)?;
         Ok(())
     }
 
+    /// Creates a detached copy of the mount tree at the current path.
+    ///
+    /// The copy mirrors either the mount of the current path (non-recursive) or the entire
+    /// mount subtree (recursive), like [`Self::bind_mount_to`], but it is not attached
+    /// anywhere. Returns the root `Path` of the copy.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EINVAL` if the mount of the current path is unbindable or is not in the
+    /// current mount namespace.
+    pub fn clone_detached(&self, recursive: bool, ctx: &Context) -> Result<Self> {
+        let current_ns_proxy = ctx.thread_local.borrow_ns_proxy();
+        let current_mnt_ns = current_ns_proxy.unwrap().mnt_ns();
+        if !current_mnt_ns.owns(&self.mount) {
+            return_errno_with_message!(Errno::EINVAL, "the path is not in this mount namespace");
+        }
+
+        if self.mount.is_unbindable() {
+            return_errno_with_message!(Errno::EINVAL, "the source mount is unbindable");
+        }
+
+        let new_mount = self.mount.clone_mount_tree(
+            &self.dentry,
+            Some(&Weak::new()),
+            recursive,
+            MountCloneFlags::empty(),
+        );
+        Ok(Self::new_fs_root(new_mount))
+    }
+
     /// Moves a mount tree from the current path to the destination path.
     ///
+    /// The mount tree can also be a detached one, in which case the current path must be
+    /// the root of the detached mount tree, and the tree will be attached to the
+    /// destination path.
+    ///
     /// # Errors
     ///
     /// Returns `ENOTDIR` if the `dst_path` is not a directory.
     /// Returns `EINVAL` in the following cases:
     /// - The current path is not a mount root.
     /// - The mount of the current path is the root mount.
+    /// - The mount of the current path is in a detached mount tree but is not its root.
+    /// - The parent mount of the current path is shared.
+    /// - The destination mount is shared and the moved tree contains unbindable mounts.
     /// - Either source or destination path is not in the current mount namespace
     pub fn move_mount_to(&self, dst_path: &Self, ctx: &Context) -> Result<()> {
         if !self.is_mount_root() {
             return_errno_with_message!(Errno::EINVAL, "the path is not a mount root");
         };
-        if self.mount_node().parent().is_none() {
-            return_errno_with_message!(Errno::EINVAL, "the root mount can not be moved");
-        }
 
         let current_ns_proxy = ctx.thread_local.borrow_ns_proxy();
         let current_mnt_ns = current_ns_proxy.unwrap().mnt_ns();
-        if !current_mnt_ns.owns(&self.mount) {
+
+        if self.mount.is_detached() {
+            if self.mount.parent().is_some() {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the mount is not the root of the detached mount tree"
+                );
+            }
+        } else {
+            let Some(parent) = self.mount_node().parent() else {
+                return_errno_with_message!(Errno::EINVAL, "the root mount can not be moved");
+            };
+            if parent.upgrade().unwrap().is_shared() {
+                return_errno_with_message!(Errno::EINVAL, "the parent mount is shared");
+            }
+            if !current_mnt_ns.owns(&self.mount) {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the source path is not in this mount namespace"
+                );
+            }
+        }
+
+        if dst_path.mount.is_shared() && self.mount.tree_contains_unbindable() {
             return_errno_with_message!(
                 Errno::EINVAL,
-                "the source path is not in this mount namespace"
+                "unbindable mounts cannot be moved to a shared mount"
             );
         }
         if !current_mnt_ns.owns(&dst_path.mount) {
@@ -418,6 +517,16 @@ impl Path {
     }
 
     /// Sets the propagation type of the mount of this `Path`.
+    ///
+    /// If `recursive` is set to `true`, the propagation types of all the mounts in
+    /// the subtree will be set as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EINVAL` in the following cases:
+    /// - The current path is not a mount root.
+    /// - The current path is neither in the current mount namespace nor in a detached
+    ///   mount tree.
     pub fn set_mount_propagation(
         &self,
         prop: MountPropType,
@@ -430,7 +539,7 @@ impl Path {
 
         let current_ns_proxy = ctx.thread_local.borrow_ns_proxy();
         let current_mnt_ns = current_ns_proxy.unwrap().mnt_ns();
-        if !current_mnt_ns.owns(&self.mount) {
+        if !self.mount.is_detached() && !current_mnt_ns.owns(&self.mount) {
             return_errno_with_message!(Errno::EINVAL, "the path is not in this mount namespace");
         }
 
@@ -438,6 +547,41 @@ impl Path {
 
         Ok(())
     }
+
+    /// Changes the `PerMountFlags` of the mount of this `Path`.
+    ///
+    /// The flags in `set` are added and the flags in `clear` are removed. If `recursive` is
+    /// set to `true`, the flags of all the mounts in the subtree will be changed as well.
+    ///
+    /// Unlike [`Self::remount`], this also works on mounts in detached mount trees.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EINVAL` in the following cases:
+    /// - The current path is not a mount root.
+    /// - The current path is neither in the current mount namespace nor in a detached
+    ///   mount tree.
+    pub fn change_mount_flags(
+        &self,
+        set: PerMountFlags,
+        clear: PerMountFlags,
+        recursive: bool,
+        ctx: &Context,
+    ) -> Result<()> {
+        if !self.is_mount_root() {
+            return_errno_with_message!(Errno::EINVAL, "the path is not a mount root");
+        };
+
+        let current_ns_proxy = ctx.thread_local.borrow_ns_proxy();
+        let current_mnt_ns = current_ns_proxy.unwrap().mnt_ns();
+        if !self.mount.is_detached() && !current_mnt_ns.owns(&self.mount) {
+            return_errno_with_message!(Errno::EINVAL, "the path is not in this mount namespace");
+        }
+
+        self.mount.change_flags(set, clear, recursive);
+
+        Ok(())
+    }
 }
 
 // Methods inherited from `Dentry`.
@@ -447,6 +591,7 @@ impl Path {
     pub fn type_(&self) -> InodeType;
     pub fn unlink(&self, name: &str) -> Result<()>;
     pub fn rmdir(&self, name: &str) -> Result<()>;
+    pub fn notify_fs_events(&self, events: FsEvents);
 
     /// Creates a `Path` by making an inode of the `type_` with the `mode`.
     pub fn mknod(&self, name: &str, mode: InodeMode, type_: MknodType) -> Result<Self> {
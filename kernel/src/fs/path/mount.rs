@@ -24,12 +24,26 @@ use crate::{
 ///
 /// This type defines how mount and unmount events are propagated
 /// from this mount to other mounts.
+///
+/// Reference: <https://www.kernel.org/doc/html/v6.17/filesystems/sharedsubtree.html>
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MountPropType {
     /// A private type is the default mount type. Mount and unmount events
     /// do not propagate to or from the private mounts.
     Private,
-    // TODO: Implement other propagation types.
+    /// A shared mount belongs to a peer group. Mount and unmount events
+    /// propagate among all the peers in the group.
+    ///
+    /// If the mount is already a slave, it remains a slave at the same time.
+    Shared,
+    /// A slave mount receives mount and unmount events from its master
+    /// peer group, but its own events do not propagate back to the master.
+    ///
+    /// If the mount is shared, its peer group becomes its master. If it is the
+    /// only mount in the peer group, it keeps its current master (if any).
+    Slave,
+    /// An unbindable mount is a private mount that cannot be bind mounted.
+    Unbindable,
 }
 
 impl Default for MountPropType {
@@ -40,9 +54,14 @@ impl Default for MountPropType {
 
 static ID_ALLOCATOR: Once<SpinLock<IdAlloc>> = Once::new();
 
+static PEER_GROUP_ID_ALLOCATOR: Once<SpinLock<IdAlloc>> = Once::new();
+
 /// The reserved mount ID, which represents an invalid mount.
 pub static RESERVED_MOUNT_ID: usize = 0;
 
+/// The reserved peer group ID. Like Linux, peer group IDs start from 1.
+const RESERVED_PEER_GROUP_ID: usize = 0;
+
 pub(super) fn init() {
     // TODO: Make it configurable.
     const MAX_MOUNT_NUM: usize = 10000;
@@ -51,8 +70,22 @@ pub(super) fn init() {
     let _ = id_allocator.alloc_specific(RESERVED_MOUNT_ID).unwrap(); // Reserve mount ID 0.
 
     ID_ALLOCATOR.call_once(|| SpinLock::new(id_allocator));
+
+    // Each peer group contains at least one mount when it is created.
+    let mut group_id_allocator = IdAlloc::with_capacity(MAX_MOUNT_NUM);
+    let _ = group_id_allocator
+        .alloc_specific(RESERVED_PEER_GROUP_ID)
+        .unwrap();
+
+    PEER_GROUP_ID_ALLOCATOR.call_once(|| SpinLock::new(group_id_allocator));
 }
 
+/// A lock that serializes the changes of the mount trees and the propagation relationships.
+///
+/// A mount or unmount event may change the mount trees of several mount namespaces due to
+/// propagation, so a global lock is used, like the `namespace_sem` in Linux.
+static MOUNT_LOCK: Mutex<()> = Mutex::new(());
+
 bitflags! {
     pub struct PerMountFlags: u32 {
         /// Mount read-only.
@@ -150,6 +183,80 @@ define_atomic_version_of_integer_like_type!(PerMountFlags, {
     pub struct AtomicPerMountFlags(AtomicU32);
 });
 
+bitflags! {
+    /// Flags that control how a mount tree is cloned.
+    pub(super) struct MountCloneFlags: u32 {
+        /// Clones unbindable mounts as well. Otherwise, they are skipped with their subtrees.
+        const COPY_UNBINDABLE = 1 << 0;
+        /// Clones shared mounts as slaves of the originals. Otherwise, they are cloned as peers.
+        const SHARED_TO_SLAVE = 1 << 1;
+    }
+}
+
+/// A group of shared mounts that propagate mount and unmount events to each other.
+struct PeerGroup {
+    id: usize,
+    /// The shared mounts in the group.
+    members: SpinLock<Vec<Weak<Mount>>>,
+    /// The slave mounts that receive events from the group.
+    slaves: SpinLock<Vec<Weak<Mount>>>,
+}
+
+impl PeerGroup {
+    fn new() -> Arc<Self> {
+        let id = PEER_GROUP_ID_ALLOCATOR
+            .get()
+            .unwrap()
+            .lock()
+            .alloc()
+            .unwrap();
+        Arc::new(Self {
+            id,
+            members: SpinLock::new(Vec::new()),
+            slaves: SpinLock::new(Vec::new()),
+        })
+    }
+
+    /// Returns the alive mounts in the group.
+    fn members(&self) -> Vec<Arc<Mount>> {
+        Self::upgrade_all(&self.members)
+    }
+
+    /// Returns the alive slave mounts of the group.
+    fn slaves(&self) -> Vec<Arc<Mount>> {
+        Self::upgrade_all(&self.slaves)
+    }
+
+    fn upgrade_all(mounts: &SpinLock<Vec<Weak<Mount>>>) -> Vec<Arc<Mount>> {
+        let mut mounts = mounts.lock();
+        mounts.retain(|mount| mount.strong_count() > 0);
+        mounts.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    fn remove(mounts: &SpinLock<Vec<Weak<Mount>>>, mount: &Mount) {
+        mounts
+            .lock()
+            .retain(|weak| weak.strong_count() > 0 && !core::ptr::eq(weak.as_ptr(), mount));
+    }
+}
+
+impl Drop for PeerGroup {
+    fn drop(&mut self) {
+        PEER_GROUP_ID_ALLOCATOR.get().unwrap().lock().free(self.id);
+    }
+}
+
+/// The propagation state of a mount.
+#[derive(Default)]
+struct Propagation {
+    /// The peer group that the mount belongs to, if the mount is shared.
+    peers: Option<Arc<PeerGroup>>,
+    /// The peer group that the mount receives events from, if the mount is a slave.
+    master: Option<Arc<PeerGroup>>,
+    /// Whether the mount is unbindable.
+    is_unbindable: bool,
+}
+
 /// A `Mount` represents a mounted filesystem instance in the VFS.
 ///
 /// Each `Mount` can be viewed as a node in the mount tree, maintaining
@@ -169,9 +276,12 @@ pub struct Mount {
     /// Child mount nodes which are mounted on one dentry of self.
     pub(super) children: RwLock<HashMap<DentryKey, Arc<Self>>>,
     /// The associated mount namespace.
-    mnt_ns: Weak<MountNamespace>,
-    /// Propagation type of this mount (e.g., private, shared).
-    propagation: RwLock<MountPropType>,
+    ///
+    /// The mount nodes in a detached mount tree do not belong to any mount namespace, so
+    /// this is [`Weak::new`] for them.
+    mnt_ns: RwLock<Weak<MountNamespace>>,
+    /// Propagation state of this mount (e.g., private, shared).
+    propagation: RwLock<Propagation>,
     /// The flags of this mount.
     flags: AtomicPerMountFlags,
     /// Reference to self.
@@ -193,6 +303,13 @@ impl Mount {
         Self::new(fs, PerMountFlags::default(), None, mnt_ns)
     }
 
+    /// Creates a mount node with an associated FS as the root of a detached mount tree.
+    ///
+    /// A detached mount tree can be attached to a mount namespace via [`Self::graft_mount_tree`].
+    pub(super) fn new_detached(fs: Arc<dyn FileSystem>, flags: PerMountFlags) -> Arc<Self> {
+        Self::new(fs, flags, None, Weak::new())
+    }
+
     /// The internal constructor.
     ///
     /// Root mount node has no mountpoint which other mount nodes must have mountpoint.
@@ -214,9 +331,9 @@ impl Mount {
             mountpoint: RwLock::new(None),
             parent: RwLock::new(parent_mount),
             children: RwLock::new(HashMap::new()),
-            propagation: RwLock::new(MountPropType::default()),
+            propagation: RwLock::new(Propagation::default()),
             fs,
-            mnt_ns,
+            mnt_ns: RwLock::new(mnt_ns),
             flags: AtomicPerMountFlags::new(flags),
             this: weak_self.clone(),
         })
@@ -237,6 +354,8 @@ impl Mount {
     /// It is allowed to mount a fs even if the fs has been provided to another
     /// mountpoint. It is the fs's responsibility to ensure the data consistency.
     ///
+    /// If this mount node is shared, the mount event is propagated to its peers and slaves.
+    ///
     /// Return the mounted child mount.
     pub(super) fn do_mount(
         self: &Arc<Self>,
@@ -248,18 +367,26 @@ impl Mount {
             return_errno!(Errno::ENOTDIR);
         }
 
+        let _guard = MOUNT_LOCK.lock();
+
         let key = mountpoint.key();
-        let child_mount = Self::new(fs, flags, Some(Arc::downgrade(self)), self.mnt_ns.clone());
+        let child_mount = Self::new(fs, flags, Some(Arc::downgrade(self)), self.mnt_ns());
         self.children.write().insert(key, child_mount.clone());
         child_mount.set_mountpoint(mountpoint);
 
+        self.propagate_mount(mountpoint, &child_mount);
+
         Ok(child_mount)
     }
 
     /// Unmounts a child mount node from the mountpoint and returns it.
     ///
     /// The mountpoint should belong to this mount node, or an error is returned.
+    ///
+    /// If this mount node is shared, the unmount event is propagated to its peers and slaves.
     pub(super) fn do_unmount(&self, mountpoint: &Dentry) -> Result<Arc<Self>> {
+        let _guard = MOUNT_LOCK.lock();
+
         let child_mount = self
             .children
             .write()
@@ -267,6 +394,10 @@ impl Mount {
             .ok_or_else(|| Error::with_message(Errno::ENOENT, "can not find child mount"))?;
 
         child_mount.clear_mountpoint();
+        // The unmounted mount nodes should no longer receive any events.
+        child_mount.do_set_propagation(MountPropType::Private, true);
+
+        self.propagate_unmount(mountpoint);
 
         Ok(child_mount)
     }
@@ -278,23 +409,47 @@ impl Mount {
     ///
     /// If the `new_ns` is set, the new mount will belong to the given mount namespace.
     /// Otherwise, it will belong to the same mount namespace as the current mount.
+    ///
+    /// The propagation state of the new mount node is inherited from the original one
+    /// according to the `flags`.
     fn clone_mount(
         &self,
         root_dentry: &Arc<Dentry>,
         new_ns: Option<&Weak<MountNamespace>>,
+        flags: MountCloneFlags,
     ) -> Arc<Self> {
-        Arc::new_cyclic(|weak_self| Self {
+        let new_mount = Arc::new_cyclic(|weak_self| Self {
             id: ID_ALLOCATOR.get().unwrap().lock().alloc().unwrap(),
             root_dentry: root_dentry.clone(),
             mountpoint: RwLock::new(None),
             parent: RwLock::new(None),
             children: RwLock::new(HashMap::new()),
-            propagation: RwLock::new(MountPropType::default()),
+            propagation: RwLock::new(Propagation::default()),
             fs: self.fs.clone(),
-            mnt_ns: new_ns.cloned().unwrap_or_else(|| self.mnt_ns.clone()),
+            mnt_ns: RwLock::new(new_ns.cloned().unwrap_or_else(|| self.mnt_ns())),
             flags: AtomicPerMountFlags::new(self.flags.load(Ordering::Relaxed)),
             this: weak_self.clone(),
-        })
+        });
+
+        let (peers, master, is_unbindable) = {
+            let propagation = self.propagation.read();
+            if flags.contains(MountCloneFlags::SHARED_TO_SLAVE) && propagation.peers.is_some() {
+                (None, propagation.peers.clone(), propagation.is_unbindable)
+            } else {
+                (
+                    propagation.peers.clone(),
+                    propagation.master.clone(),
+                    propagation.is_unbindable,
+                )
+            }
+        };
+        if let Some(peers) = peers {
+            new_mount.join_peer_group(peers);
+        }
+        new_mount.set_master(master);
+        new_mount.propagation.write().is_unbindable = is_unbindable;
+
+        new_mount
     }
 
     /// Clones a mount tree starting from the specified root `Dentry`.
@@ -313,8 +468,20 @@ impl Mount {
         root_dentry: &Arc<Dentry>,
         new_ns: Option<&Weak<MountNamespace>>,
         recursive: bool,
+        flags: MountCloneFlags,
+    ) -> Arc<Self> {
+        let _guard = MOUNT_LOCK.lock();
+        self.do_clone_mount_tree(root_dentry, new_ns, recursive, flags)
+    }
+
+    fn do_clone_mount_tree(
+        &self,
+        root_dentry: &Arc<Dentry>,
+        new_ns: Option<&Weak<MountNamespace>>,
+        recursive: bool,
+        flags: MountCloneFlags,
     ) -> Arc<Self> {
-        let new_root_mount = self.clone_mount(root_dentry, new_ns);
+        let new_root_mount = self.clone_mount(root_dentry, new_ns, flags);
         if !recursive {
             return new_root_mount;
         }
@@ -329,8 +496,13 @@ impl Mount {
                 if !mountpoint.is_equal_or_descendant_of(old_mount.root_dentry()) {
                     continue;
                 }
+                if old_child_mount.is_unbindable()
+                    && !flags.contains(MountCloneFlags::COPY_UNBINDABLE)
+                {
+                    continue;
+                }
                 let new_child_mount =
-                    old_child_mount.clone_mount(old_child_mount.root_dentry(), new_ns);
+                    old_child_mount.clone_mount(old_child_mount.root_dentry(), new_ns, flags);
                 let key = mountpoint.key();
                 new_parent_mount
                     .children
@@ -348,18 +520,261 @@ impl Mount {
 
     /// Sets the propagation type of this mount.
     pub(super) fn set_propagation(&self, prop: MountPropType, recursive: bool) {
-        *self.propagation.write() = prop;
+        let _guard = MOUNT_LOCK.lock();
+        self.do_set_propagation(prop, recursive);
+    }
+
+    fn do_set_propagation(&self, prop: MountPropType, recursive: bool) {
+        self.change_propagation(prop);
         if !recursive {
             return;
         }
 
         let mut worklist: VecDeque<Arc<Mount>> = self.children.read().values().cloned().collect();
         while let Some(mount) = worklist.pop_front() {
-            *mount.propagation.write() = prop;
+            mount.change_propagation(prop);
             worklist.extend(mount.children.read().values().cloned());
         }
     }
 
+    /// Changes the propagation type of this mount only.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/pnode.c>
+    fn change_propagation(&self, prop: MountPropType) {
+        if prop == MountPropType::Shared {
+            self.propagation.write().is_unbindable = false;
+            if !self.is_shared() {
+                self.join_peer_group(PeerGroup::new());
+            }
+            return;
+        }
+
+        self.make_slave();
+        if prop != MountPropType::Slave {
+            self.set_master(None);
+            self.propagation.write().is_unbindable = prop == MountPropType::Unbindable;
+        }
+    }
+
+    /// Makes this mount a member of the peer group.
+    ///
+    /// This mount should not belong to any peer group.
+    fn join_peer_group(&self, group: Arc<PeerGroup>) {
+        group.members.lock().push(self.this.clone());
+        self.propagation.write().peers = Some(group);
+    }
+
+    /// Makes this mount leave its peer group and become a slave of the peer group.
+    ///
+    /// If there are no other peers in the group, this mount keeps its current master,
+    /// and the slaves of the group become slaves of that master instead.
+    fn make_slave(&self) {
+        let Some(group) = self.propagation.write().peers.take() else {
+            return;
+        };
+
+        PeerGroup::remove(&group.members, self);
+        if !group.members().is_empty() {
+            self.set_master(Some(group));
+            return;
+        }
+
+        let master = self.propagation.read().master.clone();
+        for slave in group.slaves() {
+            slave.set_master(master.clone());
+        }
+    }
+
+    /// Sets the peer group that this mount receives events from.
+    fn set_master(&self, master: Option<Arc<PeerGroup>>) {
+        let mut propagation = self.propagation.write();
+        if let Some(old_master) = propagation.master.take() {
+            PeerGroup::remove(&old_master.slaves, self);
+        }
+        if let Some(new_master) = master.as_ref() {
+            new_master.slaves.lock().push(self.this.clone());
+        }
+        propagation.master = master;
+    }
+
+    /// Returns whether this mount is shared.
+    pub(super) fn is_shared(&self) -> bool {
+        self.propagation.read().peers.is_some()
+    }
+
+    /// Returns whether this mount is unbindable.
+    pub(super) fn is_unbindable(&self) -> bool {
+        self.propagation.read().is_unbindable
+    }
+
+    /// Returns whether the mount tree rooted at this mount contains unbindable mounts.
+    pub(super) fn tree_contains_unbindable(&self) -> bool {
+        let mut stack = vec![self.this()];
+        while let Some(mount) = stack.pop() {
+            if mount.is_unbindable() {
+                return true;
+            }
+            stack.extend(mount.children.read().values().cloned());
+        }
+
+        false
+    }
+
+    /// Returns the mounts that receive mount and unmount events from this mount.
+    ///
+    /// They are the peers of this mount, the slaves of the peer group, and recursively,
+    /// the mounts that receive events from the slaves that are shared.
+    fn receivers(&self) -> Vec<Arc<Mount>> {
+        let Some(group) = self.propagation.read().peers.clone() else {
+            return Vec::new();
+        };
+
+        let mut receivers = Vec::new();
+        let mut visited = vec![group.clone()];
+        let mut worklist = vec![group];
+        while let Some(group) = worklist.pop() {
+            for member in group.members() {
+                if !core::ptr::eq(Arc::as_ptr(&member), self) {
+                    receivers.push(member);
+                }
+            }
+
+            for slave in group.slaves() {
+                let Some(slave_group) = slave.propagation.read().peers.clone() else {
+                    receivers.push(slave);
+                    continue;
+                };
+                if !visited.iter().any(|group| Arc::ptr_eq(group, &slave_group)) {
+                    visited.push(slave_group.clone());
+                    worklist.push(slave_group);
+                }
+            }
+        }
+
+        receivers
+    }
+
+    /// Propagates the mount event of `source`, which has been attached to the `mountpoint`
+    /// of this mount, to the mounts that receive events from this mount.
+    ///
+    /// If this mount is shared, the mounts in the `source` tree become shared as well.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/pnode.c>
+    fn propagate_mount(&self, mountpoint: &Arc<Dentry>, source: &Arc<Mount>) {
+        let Some(group) = self.propagation.read().peers.clone() else {
+            return;
+        };
+
+        source.do_set_propagation(MountPropType::Shared, true);
+
+        // Each peer group that receives the event is paired with a template tree. The copies
+        // for the group are cloned from the template as peers, so that they are peers of each
+        // other.
+        let mut visited = vec![group.clone()];
+        let mut worklist = vec![(group, source.clone())];
+        let mut slave_templates = Vec::new();
+        while let Some((group, template)) = worklist.pop() {
+            for member in group.members() {
+                if core::ptr::eq(Arc::as_ptr(&member), self) || Arc::ptr_eq(&member, source) {
+                    continue;
+                }
+                member.attach_propagated_copy(mountpoint, &template, MountCloneFlags::empty());
+            }
+
+            for slave in group.slaves() {
+                let Some(slave_group) = slave.propagation.read().peers.clone() else {
+                    slave.attach_propagated_copy(
+                        mountpoint,
+                        &template,
+                        MountCloneFlags::SHARED_TO_SLAVE,
+                    );
+                    continue;
+                };
+                if visited.iter().any(|group| Arc::ptr_eq(group, &slave_group)) {
+                    continue;
+                }
+                visited.push(slave_group.clone());
+
+                // The copies for a shared slave are slaves of the copies for its master, and
+                // they form a new peer group at the same time.
+                let slave_template = template.do_clone_mount_tree(
+                    template.root_dentry(),
+                    None,
+                    true,
+                    MountCloneFlags::SHARED_TO_SLAVE,
+                );
+                slave_template.do_set_propagation(MountPropType::Shared, true);
+                worklist.push((slave_group, slave_template.clone()));
+                slave_templates.push(slave_template);
+            }
+        }
+
+        // The templates are never attached, so they should not stay in any peer groups.
+        for template in slave_templates {
+            template.discard_tree();
+        }
+    }
+
+    /// Attaches a copy of the `template` tree to the `mountpoint` of this mount.
+    ///
+    /// Nothing is done if the mountpoint is not visible in this mount.
+    fn attach_propagated_copy(
+        &self,
+        mountpoint: &Arc<Dentry>,
+        template: &Mount,
+        flags: MountCloneFlags,
+    ) {
+        if !mountpoint.is_equal_or_descendant_of(self.root_dentry()) {
+            return;
+        }
+        // TODO: Linux mounts the copy on top of the existing child mount. This is not
+        // supported yet because the child mounts are indexed by their mountpoints.
+        if self.get(mountpoint).is_some() {
+            return;
+        }
+
+        let copy =
+            template.do_clone_mount_tree(template.root_dentry(), Some(&self.mnt_ns()), true, flags);
+        copy.attach_to_path(&Path::new(self.this(), mountpoint.clone()));
+    }
+
+    /// Propagates the unmount event at the `mountpoint` of this mount to the mounts that
+    /// receive events from this mount.
+    ///
+    /// Like Linux, the child mounts of the receivers are not unmounted if they have child
+    /// mounts of their own.
+    fn propagate_unmount(&self, mountpoint: &Dentry) {
+        for receiver in self.receivers() {
+            let Some(child_mount) = receiver.get(mountpoint) else {
+                continue;
+            };
+            if !child_mount.children.read().is_empty() {
+                continue;
+            }
+
+            receiver.children.write().remove(&mountpoint.key());
+            child_mount.clear_mountpoint();
+            child_mount.do_set_propagation(MountPropType::Private, true);
+        }
+    }
+
+    /// Discards a detached mount tree.
+    ///
+    /// All the mount nodes in the tree are made private and detached from each other.
+    fn discard_tree(&self) {
+        self.do_set_propagation(MountPropType::Private, true);
+
+        let mut worklist = vec![self.this()];
+        while let Some(mount) = worklist.pop() {
+            let mut children = mount.children.write();
+            for (_, child) in children.drain() {
+                child.set_parent(None);
+                child.clear_mountpoint();
+                worklist.push(child);
+            }
+        }
+    }
+
     /// Detaches the mount node from the parent mount node.
     pub(super) fn detach_from_parent(&self) {
         if let Some(parent) = self.parent() {
@@ -388,12 +803,74 @@ impl Mount {
     }
 
     /// Grafts the mount node tree to the mountpoint.
+    ///
+    /// If the mount node tree is a detached one, it will belong to the mount namespace of
+    /// the mountpoint afterwards.
+    ///
+    /// If the mount node of the mountpoint is shared, the mount event is propagated to
+    /// its peers and slaves.
     pub(super) fn graft_mount_tree(&self, target_path: &Path) -> Result<()> {
+        let _guard = MOUNT_LOCK.lock();
+
+        if self.is_detached() {
+            self.set_tree_mnt_ns(target_path.mount_node().mnt_ns());
+        }
         self.detach_from_parent();
         self.attach_to_path(target_path);
+        target_path
+            .mount_node()
+            .propagate_mount(&target_path.dentry, &self.this());
         Ok(())
     }
 
+    /// Swaps the root mount node of the mount namespace with `new_root`.
+    ///
+    /// This mount node, which must be the current root, is moved to `put_old`, and `new_root`
+    /// is moved to where this mount node was mounted.
+    ///
+    /// The caller should guarantee that `put_old` is under `new_root`, and `new_root` is a
+    /// descendant of this mount node.
+    pub(super) fn pivot_root(&self, new_root: &Arc<Self>, put_old: &Path) {
+        let _guard = MOUNT_LOCK.lock();
+
+        let root_parent = self.parent().unwrap().upgrade().unwrap();
+        let root_mountpoint = Path::new(root_parent, self.mountpoint().unwrap());
+
+        new_root.detach_from_parent();
+        self.detach_from_parent();
+        self.attach_to_path(put_old);
+        new_root.attach_to_path(&root_mountpoint);
+    }
+
+    /// Changes the flags of this mount node.
+    ///
+    /// The flags in `set` are added and the flags in `clear` are removed. If `recursive` is
+    /// `true`, the flags of all the descendant mount nodes are changed as well.
+    pub(super) fn change_flags(&self, set: PerMountFlags, clear: PerMountFlags, recursive: bool) {
+        let _guard = MOUNT_LOCK.lock();
+
+        let mut worklist = vec![self.this()];
+        while let Some(mount) = worklist.pop() {
+            let old_flags = mount.flags.load(Ordering::Relaxed);
+            mount
+                .flags
+                .store((old_flags - clear) | set, Ordering::Relaxed);
+
+            if recursive {
+                worklist.extend(mount.children.read().values().cloned());
+            }
+        }
+    }
+
+    /// Sets the mount namespace of all the mount nodes in the tree.
+    fn set_tree_mnt_ns(&self, mnt_ns: Weak<MountNamespace>) {
+        let mut worklist = vec![self.this()];
+        while let Some(mount) = worklist.pop() {
+            *mount.mnt_ns.write() = mnt_ns.clone();
+            worklist.extend(mount.children.read().values().cloned());
+        }
+    }
+
     /// Gets a child mount node from the mountpoint if any.
     pub(super) fn get(&self, mountpoint: &Dentry) -> Option<Arc<Self>> {
         self.children.read().get(&mountpoint.key()).cloned()
@@ -481,8 +958,13 @@ impl Mount {
     }
 
     /// Gets the associated mount namespace.
-    pub(super) fn mnt_ns(&self) -> &Weak<MountNamespace> {
-        &self.mnt_ns
+    pub(super) fn mnt_ns(&self) -> Weak<MountNamespace> {
+        self.mnt_ns.read().clone()
+    }
+
+    /// Returns whether the mount node belongs to a detached mount tree.
+    pub(super) fn is_detached(&self) -> bool {
+        self.mnt_ns.read().ptr_eq(&Weak::new())
     }
 
     /// Gets the associated FS.
@@ -554,6 +1036,14 @@ impl Mount {
             let mount_flags = self.flags.load(Ordering::Relaxed);
             let fs_type = mount.fs().name();
             let fs_flags = mount.fs().flags();
+            let (peer_group_id, master_id, is_unbindable) = {
+                let propagation = mount.propagation.read();
+                (
+                    propagation.peers.as_ref().map(|group| group.id),
+                    propagation.master.as_ref().map(|group| group.id),
+                    propagation.is_unbindable,
+                )
+            };
 
             // The following fields are dummy for now.
             let major = 0;
@@ -568,6 +1058,9 @@ impl Mount {
                 root: &root,
                 mount_point: &mount_point,
                 mount_flags,
+                peer_group_id,
+                master_id,
+                is_unbindable,
                 fs_type,
                 source,
                 fs_flags,
@@ -601,6 +1094,13 @@ impl Debug for Mount {
 
 impl Drop for Mount {
     fn drop(&mut self) {
+        // The child mount nodes may outlive this one (e.g., if they are referenced by some
+        // `Path`s), but their mountpoints should no longer be occupied.
+        for (_, child) in self.children.write().drain() {
+            child.set_parent(None);
+            child.clear_mountpoint();
+        }
+
         ID_ALLOCATOR.get().unwrap().lock().free(self.id);
     }
 }
@@ -621,6 +1121,12 @@ struct MountInfoEntry<'a> {
     mount_point: &'a str,
     /// Per-mount flags.
     mount_flags: PerMountFlags,
+    /// The ID of the peer group if the mount is shared.
+    peer_group_id: Option<usize>,
+    /// The ID of the master peer group if the mount is a slave.
+    master_id: Option<usize>,
+    /// Whether the mount is unbindable.
+    is_unbindable: bool,
     /// The type of the filesystem in the form "type[.subtype]".
     fs_type: &'a str,
     /// Filesystem-specific information or "none".
@@ -633,7 +1139,7 @@ impl core::fmt::Display for MountInfoEntry<'_> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
-            "{} {} {}:{} {} {} {} - {} {} {}",
+            "{} {} {}:{} {} {} {}",
             self.mount_id,
             self.parent_id,
             self.major,
@@ -641,9 +1147,23 @@ impl core::fmt::Display for MountInfoEntry<'_> {
             &self.root,
             &self.mount_point,
             &self.mount_flags,
-            &self.fs_type,
-            &self.source,
-            &self.fs_flags,
+        )?;
+
+        // Optional fields.
+        if let Some(peer_group_id) = self.peer_group_id {
+            write!(f, " shared:{}", peer_group_id)?;
+        }
+        if let Some(master_id) = self.master_id {
+            write!(f, " master:{}", master_id)?;
+        }
+        if self.is_unbindable {
+            write!(f, " unbindable")?;
+        }
+
+        write!(
+            f,
+            " - {} {} {}",
+            &self.fs_type, &self.source, &self.fs_flags
         )
     }
 }
@@ -5,8 +5,9 @@ use spin::Once;
 use crate::{
     fs::{
         fs_resolver::FsResolver,
-        path::{Mount, Path},
+        path::{mount::MountCloneFlags, Mount, MountPropType, Path},
         ramfs::RamFs,
+        utils::InodeType,
     },
     prelude::*,
     process::{credentials::capabilities::CapSet, posix_thread::PosixThread, UserNamespace},
@@ -70,10 +71,21 @@ impl MountNamespace {
     ) -> Result<Arc<MountNamespace>> {
         owner.check_cap(CapSet::SYS_ADMIN, posix_thread)?;
 
+        // If the new namespace is owned by a different user namespace, the mount events in it
+        // should not propagate back to this namespace. So the shared mounts become slaves.
+        let mut clone_flags = MountCloneFlags::COPY_UNBINDABLE;
+        if !Arc::ptr_eq(&owner, &self.owner) {
+            clone_flags |= MountCloneFlags::SHARED_TO_SLAVE;
+        }
+
         let root_mount = &self.root;
         let new_mnt_ns = Arc::new_cyclic(|weak_self| {
-            let new_root =
-                root_mount.clone_mount_tree(root_mount.root_dentry(), Some(weak_self), true);
+            let new_root = root_mount.clone_mount_tree(
+                root_mount.root_dentry(),
+                Some(weak_self),
+                true,
+                clone_flags,
+            );
 
             MountNamespace {
                 root: new_root,
@@ -107,6 +119,78 @@ impl MountNamespace {
         Ok(())
     }
 
+    /// Changes the root mount of the current root directory.
+    ///
+    /// The mount of `root`, which is the current root directory, is moved to `put_old`, and
+    /// the mount of `new_root` is moved to where the mount of `root` was mounted.
+    ///
+    /// The caller is responsible for updating the root directories and the current working
+    /// directories that are `root` to `new_root`.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/namespace.c>
+    ///
+    /// # Errors
+    ///
+    /// Returns `ENOTDIR` if `new_root` or `put_old` is not a directory.
+    /// Returns `EBUSY` if `new_root` or `put_old` is on the mount of `root`.
+    /// Returns `EINVAL` in the following cases:
+    /// - Any of the `Path`s is not in this mount namespace.
+    /// - The mount of `put_old`, or the parent mount of `root` or `new_root` is shared.
+    /// - `root` or `new_root` is not a mount root, or its mount has no parent.
+    /// - `put_old` is not under `new_root`, or `new_root` is not under `root`.
+    pub fn pivot_root(
+        self: &Arc<Self>,
+        root: &Path,
+        new_root: &Path,
+        put_old: &Path,
+    ) -> Result<()> {
+        if new_root.type_() != InodeType::Dir || put_old.type_() != InodeType::Dir {
+            return_errno_with_message!(Errno::ENOTDIR, "the path is not a directory");
+        }
+
+        let root_mount = root.mount_node();
+        let new_root_mount = new_root.mount_node();
+        let put_old_mount = put_old.mount_node();
+
+        if !self.owns(root_mount) || !self.owns(new_root_mount) || !self.owns(put_old_mount) {
+            return_errno_with_message!(Errno::EINVAL, "the path is not in this mount namespace");
+        }
+
+        let is_parent_shared = |mount: &Mount| {
+            mount
+                .parent()
+                .is_some_and(|parent| parent.upgrade().unwrap().is_shared())
+        };
+        if put_old_mount.is_shared()
+            || is_parent_shared(new_root_mount)
+            || is_parent_shared(root_mount)
+        {
+            return_errno_with_message!(Errno::EINVAL, "the mount or its parent mount is shared");
+        }
+
+        if Arc::ptr_eq(new_root_mount, root_mount) || Arc::ptr_eq(put_old_mount, root_mount) {
+            return_errno_with_message!(Errno::EBUSY, "the path is on the current root mount");
+        }
+
+        if !root.is_mount_root() || root_mount.parent().is_none() {
+            return_errno_with_message!(Errno::EINVAL, "the current root is not a mounted root");
+        }
+        if !new_root.is_mount_root() || new_root_mount.parent().is_none() {
+            return_errno_with_message!(Errno::EINVAL, "the new root is not a mounted root");
+        }
+
+        if !put_old.is_equal_or_descendant_of(new_root) {
+            return_errno_with_message!(Errno::EINVAL, "the old root is not under the new root");
+        }
+        if !new_root.is_equal_or_descendant_of(root) {
+            return_errno_with_message!(Errno::EINVAL, "the new root is not under the current root");
+        }
+
+        root_mount.pivot_root(new_root_mount, put_old);
+
+        Ok(())
+    }
+
     /// Returns the owner user namespace of the namespace.
     pub fn owner(&self) -> &Arc<UserNamespace> {
         &self.owner
@@ -120,9 +204,12 @@ impl MountNamespace {
 
 // When a mount namespace is dropped, it means that the corresponding mount
 // tree is no longer valid. Therefore, all mounts in its mount tree should be
+// made private so that they no longer receive propagated events, and be
 // detached from their parents and cleared of their mountpoints.
 impl Drop for MountNamespace {
     fn drop(&mut self) {
+        self.root.set_propagation(MountPropType::Private, true);
+
         let mut worklist = VecDeque::new();
         worklist.push_back(self.root.clone());
         while let Some(current_mount) = worklist.pop_front() {
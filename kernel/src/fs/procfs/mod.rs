@@ -11,9 +11,11 @@ use self::{
     cpuinfo::CpuInfoFileOps,
     loadavg::LoadAvgFileOps,
     meminfo::MemInfoFileOps,
+    net::NetDirOps,
     pid::PidDirOps,
     self_::SelfSymOps,
     sys::SysDirOps,
+    sysvipc::SysvipcDirOps,
     template::{DirOps, ProcDir, ProcDirBuilder, ProcSymBuilder, SymOps},
     thread_self::ThreadSelfSymOps,
     uptime::UptimeFileOps,
@@ -37,10 +39,12 @@ mod cpuinfo;
 mod filesystems;
 mod loadavg;
 mod meminfo;
+mod net;
 mod pid;
 mod self_;
 mod stat;
 mod sys;
+mod sysvipc;
 mod template;
 mod thread_self;
 mod uptime;
@@ -148,9 +152,11 @@ impl RootDirOps {
         ("filesystems", FileSystemsFileOps::new_inode),
         ("loadavg", LoadAvgFileOps::new_inode),
         ("meminfo", MemInfoFileOps::new_inode),
+        ("net", NetDirOps::new_inode),
         ("self", SelfSymOps::new_inode),
         ("stat", StatFileOps::new_inode),
         ("sys", SysDirOps::new_inode),
+        ("sysvipc", SysvipcDirOps::new_inode),
         ("thread-self", ThreadSelfSymOps::new_inode),
         ("uptime", UptimeFileOps::new_inode),
     ];
@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/net/pnp` file support, which tells the user space about the network
+//! configuration obtained at boot time. The file is in the format of `/etc/resolv.conf`.
+//!
+//! Reference: <https://www.kernel.org/doc/html/v6.4/admin-guide/nfs/nfsroot.html>
+
+use aster_util::printer::VmPrinter;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{mkmod, Inode},
+    },
+    net::iface::boot_net_info,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/net/pnp`.
+pub struct PnpFileOps;
+
+impl PnpFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/net/ipv4/ipconfig.c>
+        ProcFileBuilder::new(Self, mkmod!(a+r))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for PnpFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        let info = boot_net_info();
+        if info.is_dhcp {
+            writeln!(printer, "#PROTO: DHCP")?;
+        } else {
+            writeln!(printer, "#MANUAL")?;
+        }
+        // TODO: Report the domain name obtained via DHCP.
+        for dns_server in info.dns_servers.iter() {
+            writeln!(printer, "nameserver {}", dns_server)?;
+        }
+        if let Some(server) = info.server {
+            writeln!(printer, "bootserver {}", server)?;
+        }
+
+        Ok(printer.bytes_written())
+    }
+}
@@ -108,7 +108,11 @@ impl FileOps for StatusFileOps {
         writeln!(printer, "Tgid:\t{}", process.pid())?;
         writeln!(printer, "Pid:\t{}", posix_thread.tid())?;
         writeln!(printer, "PPid:\t{}", process.parent().pid())?;
-        writeln!(printer, "TracerPid:\t{}", 0)?;
+        writeln!(
+            printer,
+            "TracerPid:\t{}",
+            posix_thread.tracee().tracer_pid()
+        )?;
         writeln!(
             printer,
             "FDSize:\t{}",
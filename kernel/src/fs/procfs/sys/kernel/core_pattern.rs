@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{mkmod, Inode},
+    },
+    prelude::*,
+    process::coredump::{core_pattern, set_core_pattern, CORENAME_MAX_SIZE},
+};
+
+/// Represents the inode at `/proc/sys/kernel/core_pattern`.
+pub struct CorePatternFileOps;
+
+impl CorePatternFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/coredump.c#L1085>
+        ProcFileBuilder::new(Self, mkmod!(a+r, u+w))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for CorePatternFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        writeln!(printer, "{}", core_pattern())?;
+
+        Ok(printer.bytes_written())
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        // Like Linux, a pattern that is too long is truncated.
+        let (cstr, read_bytes) = reader.read_cstring_until_end(CORENAME_MAX_SIZE - 1)?;
+        let pattern = cstr
+            .to_str()
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the pattern is not valid UTF-8"))?;
+
+        // The string ends at the first newline character.
+        let pattern = pattern.split('\n').next().unwrap();
+        set_core_pattern(pattern.to_string());
+
+        Ok(read_bytes)
+    }
+}
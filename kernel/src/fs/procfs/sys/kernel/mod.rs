@@ -6,7 +6,10 @@ use ostd::sync::RwMutexUpgradeableGuard;
 use crate::{
     fs::{
         procfs::{
-            sys::kernel::{cap_last_cap::CapLastCapFileOps, pid_max::PidMaxFileOps},
+            sys::kernel::{
+                cap_last_cap::CapLastCapFileOps, core_pattern::CorePatternFileOps,
+                pid_max::PidMaxFileOps, shmall::ShmAllFileOps, shmmax::ShmMaxFileOps,
+            },
             template::{
                 lookup_child_from_table, populate_children_from_table, DirOps, ProcDirBuilder,
             },
@@ -18,7 +21,10 @@ use crate::{
 };
 
 mod cap_last_cap;
+mod core_pattern;
 mod pid_max;
+mod shmall;
+mod shmmax;
 
 /// Represents the inode at `/proc/sys/kernel`.
 pub struct KernelDirOps;
@@ -37,7 +43,10 @@ impl KernelDirOps {
     #[expect(clippy::type_complexity)]
     const STATIC_ENTRIES: &'static [(&'static str, fn(Weak<dyn Inode>) -> Arc<dyn Inode>)] = &[
         ("cap_last_cap", CapLastCapFileOps::new_inode),
+        ("core_pattern", CorePatternFileOps::new_inode),
         ("pid_max", PidMaxFileOps::new_inode),
+        ("shmall", ShmAllFileOps::new_inode),
+        ("shmmax", ShmMaxFileOps::new_inode),
     ];
 }
 
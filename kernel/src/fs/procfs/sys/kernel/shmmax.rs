@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::Ordering;
+
+use aster_util::printer::VmPrinter;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{mkmod, Inode},
+    },
+    ipc::shm::SHMMAX,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sys/kernel/shmmax`.
+pub struct ShmMaxFileOps;
+
+impl ShmMaxFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/ipc_sysctl.c>
+        ProcFileBuilder::new(Self, mkmod!(a+r, u+w))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for ShmMaxFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        writeln!(printer, "{}", SHMMAX.load(Ordering::Relaxed))?;
+
+        Ok(printer.bytes_written())
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        let (cstr, read_bytes) = reader.read_cstring_until_end(BUF_SIZE_U64 - 1)?;
+        let val = cstr
+            .to_str()
+            .ok()
+            .and_then(|str| str.trim().parse::<usize>().ok())
+            .ok_or_else(|| {
+                Error::with_message(Errno::EINVAL, "the value is not a valid integer")
+            })?;
+
+        SHMMAX.store(val, Ordering::Relaxed);
+
+        Ok(read_bytes)
+    }
+}
+
+/// Worst case buffer size needed for holding an unsigned long integer.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/kernel/sysctl.c>.
+const BUF_SIZE_U64: usize = 22;
@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{mkmod, Inode},
+    },
+    net::{socket::ip::stream_options::CongestionControl, NetNamespace},
+    prelude::*,
+    process::posix_thread::AsPosixThread,
+};
+
+/// Represents the inode at `/proc/sys/net/ipv4/tcp_congestion_control`.
+pub struct TcpCongestionControlFileOps;
+
+impl TcpCongestionControlFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/net/ipv4/sysctl_net_ipv4.c#L539>
+        ProcFileBuilder::new(Self, mkmod!(a+r, u+w))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+/// The maximum length of a congestion control algorithm name, including the trailing NUL byte.
+const TCP_CA_NAME_MAX: usize = 16;
+
+impl FileOps for TcpCongestionControlFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        let congestion = current_net_ns().tcp_congestion_control();
+        writeln!(printer, "{}", congestion.name())?;
+
+        Ok(printer.bytes_written())
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        let (cstr, read_bytes) = reader.read_cstring_until_end(TCP_CA_NAME_MAX - 1)?;
+        let name = cstr
+            .to_str()
+            .map_err(|_| Error::with_message(Errno::ENOENT, "the name is not valid UTF-8"))?;
+
+        // The string ends at the first newline character.
+        let name = name.split('\n').next().unwrap();
+        let congestion = CongestionControl::new(name)?;
+        current_net_ns().set_tcp_congestion_control(congestion);
+
+        Ok(read_bytes)
+    }
+}
+
+/// Returns the network namespace of the current thread.
+///
+/// Like Linux, the file shows the value of the network namespace that the reader or writer is
+/// in.
+fn current_net_ns() -> Arc<NetNamespace> {
+    let current = current_thread!();
+    let ns_proxy = current.as_posix_thread().unwrap().ns_proxy().lock();
+    // The current thread is running, so its namespaces cannot have been released.
+    ns_proxy.as_ref().unwrap().net_ns().clone()
+}
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{mkmod, Inode},
+    },
+    ipc::msg::all_msg_queues,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sysvipc/msg`.
+pub struct MsgFileOps;
+
+impl MsgFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/msg.c>
+        ProcFileBuilder::new(Self, mkmod!(a+r))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for MsgFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        writeln!(
+            printer,
+            "       key      msqid perms      cbytes       qnum lspid lrpid   uid   gid  cuid  cgid      stime      rtime      ctime"
+        )?;
+
+        for queue in all_msg_queues() {
+            let msqid_ds = queue.msqid_ds();
+            let perm = &msqid_ds.msg_perm;
+            writeln!(
+                printer,
+                "{:>10} {:>10}  {:>4o}  {:>10} {:>10} {:>5} {:>5} {:>5} {:>5} {:>5} {:>5} {:>10} {:>10} {:>10}",
+                perm.key as i32,
+                queue.id(),
+                perm.mode,
+                msqid_ds.msg_cbytes,
+                msqid_ds.msg_qnum,
+                msqid_ds.msg_lspid,
+                msqid_ds.msg_lrpid,
+                perm.uid,
+                perm.gid,
+                perm.cuid,
+                perm.cgid,
+                msqid_ds.msg_stime,
+                msqid_ds.msg_rtime,
+                msqid_ds.msg_ctime,
+            )?;
+        }
+
+        Ok(printer.bytes_written())
+    }
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+
+use crate::{
+    fs::{
+        procfs::template::{FileOps, ProcFileBuilder},
+        utils::{mkmod, Inode},
+    },
+    ipc::shm::all_shm,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sysvipc/shm`.
+pub struct ShmFileOps;
+
+impl ShmFileOps {
+    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/shm.c>
+        ProcFileBuilder::new(Self, mkmod!(a+r))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for ShmFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        writeln!(
+            printer,
+            "       key      shmid perms                  size  cpid  lpid nattch   uid   gid  cuid  cgid      atime      dtime      ctime                   rss                  swap"
+        )?;
+
+        for shm in all_shm() {
+            let shmid_ds = shm.shmid_ds();
+            let perm = &shmid_ds.shm_perm;
+            // TODO: Report the resident and swapped sizes of the segment.
+            writeln!(
+                printer,
+                "{:>10} {:>10}  {:>4o} {:>21} {:>5} {:>5}  {:>5} {:>5} {:>5} {:>5} {:>5} {:>10} {:>10} {:>10} {:>21} {:>21}",
+                perm.key as i32,
+                shm.id(),
+                perm.mode,
+                shmid_ds.shm_segsz,
+                shmid_ds.shm_cpid,
+                shmid_ds.shm_lpid,
+                shmid_ds.shm_nattch,
+                perm.uid,
+                perm.gid,
+                perm.cuid,
+                perm.cgid,
+                shmid_ds.shm_atime,
+                shmid_ds.shm_dtime,
+                shmid_ds.shm_ctime,
+                0,
+                0,
+            )?;
+        }
+
+        Ok(printer.bytes_written())
+    }
+}
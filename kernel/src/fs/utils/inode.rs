@@ -16,6 +16,7 @@ use crate::{
         device::{Device, DeviceType},
         fs_resolver::PathOrInode,
         inode_handle::FileIo,
+        notify::FsEventPublisher,
         path::Path,
         utils::StatusFlags,
     },
@@ -434,67 +435,74 @@ pub trait Inode: Any + InodeIo + Send + Sync {
     ///
     /// Similar to Linux, using "fsuid" here allows setting filesystem permissions
     /// without changing the "normal" uids for other tasks.
-    fn check_permission(&self, mut perm: Permission) -> Result<()> {
-        let creds = match Task::current() {
-            Some(task) => match task.as_posix_thread() {
-                Some(thread) => thread.credentials(),
-                None => return Ok(()),
-            },
-            None => return Ok(()),
-        };
+    fn check_permission(&self, perm: Permission) -> Result<()> {
+        check_permission_by_mode(self, perm)
+    }
+}
 
-        // With DAC_OVERRIDE capability, the user can bypass some permission checks.
-        if creds.effective_capset().contains(CapSet::DAC_OVERRIDE) {
-            // Read/write DACs are always overridable.
-            perm -= Permission::MAY_READ | Permission::MAY_WRITE;
-
-            // Executable DACs are overridable when there is at least one exec bit set.
-            if perm.may_exec() {
-                let metadata = self.metadata();
-                let mode = metadata.mode;
-
-                if mode.is_owner_executable()
-                    || mode.is_group_executable()
-                    || mode.is_other_executable()
-                {
-                    perm -= Permission::MAY_EXEC;
-                } else {
-                    return_errno_with_message!(
-                        Errno::EACCES,
-                        "root execute permission denied: no execute bits set"
-                    );
-                }
+/// Checks for read/write/execute permissions on a file according to its mode, owner, and group.
+///
+/// This is the default implementation of [`Inode::check_permission`]. File systems that need
+/// additional checks can call it in their own implementations.
+pub fn check_permission_by_mode<I: Inode + ?Sized>(inode: &I, mut perm: Permission) -> Result<()> {
+    let creds = match Task::current() {
+        Some(task) => match task.as_posix_thread() {
+            Some(thread) => thread.credentials(),
+            None => return Ok(()),
+        },
+        None => return Ok(()),
+    };
+
+    // With DAC_OVERRIDE capability, the user can bypass some permission checks.
+    if creds.effective_capset().contains(CapSet::DAC_OVERRIDE) {
+        // Read/write DACs are always overridable.
+        perm -= Permission::MAY_READ | Permission::MAY_WRITE;
+
+        // Executable DACs are overridable when there is at least one exec bit set.
+        if perm.may_exec() {
+            let metadata = inode.metadata();
+            let mode = metadata.mode;
+
+            if mode.is_owner_executable()
+                || mode.is_group_executable()
+                || mode.is_other_executable()
+            {
+                perm -= Permission::MAY_EXEC;
+            } else {
+                return_errno_with_message!(
+                    Errno::EACCES,
+                    "root execute permission denied: no execute bits set"
+                );
             }
         }
+    }
 
-        perm =
-            perm.intersection(Permission::MAY_READ | Permission::MAY_WRITE | Permission::MAY_EXEC);
-        let metadata = self.metadata();
-        let mode = metadata.mode;
+    perm = perm.intersection(Permission::MAY_READ | Permission::MAY_WRITE | Permission::MAY_EXEC);
+    let metadata = inode.metadata();
+    let mode = metadata.mode;
 
-        if metadata.uid == creds.fsuid() {
-            if (perm.may_read() && !mode.is_owner_readable())
-                || (perm.may_write() && !mode.is_owner_writable())
-                || (perm.may_exec() && !mode.is_owner_executable())
-            {
-                return_errno_with_message!(Errno::EACCES, "owner permission check failed");
-            }
-        } else if metadata.gid == creds.fsgid() {
-            if (perm.may_read() && !mode.is_group_readable())
-                || (perm.may_write() && !mode.is_group_writable())
-                || (perm.may_exec() && !mode.is_group_executable())
-            {
-                return_errno_with_message!(Errno::EACCES, "group permission check failed");
-            }
-        } else if (perm.may_read() && !mode.is_other_readable())
-            || (perm.may_write() && !mode.is_other_writable())
-            || (perm.may_exec() && !mode.is_other_executable())
+    if metadata.uid == creds.fsuid() {
+        if (perm.may_read() && !mode.is_owner_readable())
+            || (perm.may_write() && !mode.is_owner_writable())
+            || (perm.may_exec() && !mode.is_owner_executable())
         {
-            return_errno_with_message!(Errno::EACCES, "other permission check failed");
+            return_errno_with_message!(Errno::EACCES, "owner permission check failed");
         }
-
-        Ok(())
+    } else if metadata.gid == creds.fsgid() {
+        if (perm.may_read() && !mode.is_group_readable())
+            || (perm.may_write() && !mode.is_group_writable())
+            || (perm.may_exec() && !mode.is_group_executable())
+        {
+            return_errno_with_message!(Errno::EACCES, "group permission check failed");
+        }
+    } else if (perm.may_read() && !mode.is_other_readable())
+        || (perm.may_write() && !mode.is_other_writable())
+        || (perm.may_exec() && !mode.is_other_executable())
+    {
+        return_errno_with_message!(Errno::EACCES, "other permission check failed");
     }
+
+    Ok(())
 }
 
 impl dyn Inode {
@@ -528,6 +536,18 @@ impl dyn Inode {
         let mut reader = VmReader::from(buf).to_fallible();
         self.write_at(offset, &mut reader, StatusFlags::O_DIRECT)
     }
+
+    /// Gets the event publisher of the inode, if someone has subscribed to it.
+    pub fn fs_event_publisher(&self) -> Option<Arc<FsEventPublisher>> {
+        self.extension()?.get::<FsEventPublisher>()
+    }
+
+    /// Gets the event publisher of the inode, creating one if it does not exist.
+    ///
+    /// Returns `None` if the inode does not support event notification.
+    pub fn fs_event_publisher_or_default(&self) -> Option<Arc<FsEventPublisher>> {
+        Some(self.extension()?.get_or_put_default::<FsEventPublisher>())
+    }
 }
 
 pub struct InodeWriter<'a> {
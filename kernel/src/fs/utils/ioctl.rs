@@ -72,4 +72,6 @@ pub enum IoctlCmd {
     PANDISPLAY = 0x4606,
     /// Blank or unblank the framebuffer display
     FBIOBLANK = 0x4611,
+    /// Map an interface name to its index
+    SIOCGIFINDEX = 0x8933,
 }
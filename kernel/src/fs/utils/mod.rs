@@ -12,7 +12,8 @@ pub use file_creation_mask::{AtomicFileCreationMask, FileCreationMask};
 pub use flock::{FlockItem, FlockList, FlockType};
 pub use fs::{FileSystem, FsFlags, SuperBlock};
 pub use inode::{
-    Extension, Inode, InodeIo, InodeType, Metadata, MknodType, Permission, SymbolicLink,
+    check_permission_by_mode, Extension, Inode, InodeIo, InodeType, Metadata, MknodType,
+    Permission, SymbolicLink,
 };
 pub use inode_mode::InodeMode;
 pub(crate) use inode_mode::{chmod, mkmod, perms_to_mask, who_and_perms_to_mask, who_to_mask};
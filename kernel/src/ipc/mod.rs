@@ -1,11 +1,15 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use aster_rights::ReadOp;
+
 use crate::{
     prelude::*,
-    process::{Gid, Uid},
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Uid},
 };
 
+pub mod msg;
 pub mod semaphore;
+pub mod shm;
 
 #[expect(non_camel_case_types)]
 pub type key_t = i32;
@@ -85,7 +89,7 @@ impl IpcPermission {
         self.mode
     }
 
-    pub(self) fn new_sem_perm(key: key_t, uid: Uid, gid: Gid, mode: u16) -> Self {
+    pub(self) fn new(key: key_t, uid: Uid, gid: Gid, mode: u16) -> Self {
         Self {
             key,
             uid,
@@ -95,8 +99,107 @@ impl IpcPermission {
             mode,
         }
     }
+
+    /// Returns whether the credentials belong to the owner or the creator.
+    pub fn is_owner_or_creator(&self, credentials: &Credentials<ReadOp>) -> bool {
+        let euid = credentials.euid();
+        euid == self.uid || euid == self.cuid
+    }
+
+    /// Checks whether the credentials are allowed to change or remove the IPC object.
+    pub fn check_owner(&self, credentials: &Credentials<ReadOp>) -> Result<()> {
+        if !self.is_owner_or_creator(credentials)
+            && !credentials.effective_capset().contains(CapSet::SYS_ADMIN)
+        {
+            return_errno_with_message!(Errno::EPERM, "the caller is not the owner or the creator");
+        }
+        Ok(())
+    }
+
+    /// Checks whether the credentials are granted the access in `flag`.
+    ///
+    /// The requested access is encoded as permission bits, like `0o444` for
+    /// reading and `0o666` for reading and writing, and is checked against the
+    /// owner, group, or other bits of the mode.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/util.c>
+    pub fn check_access(&self, credentials: &Credentials<ReadOp>, flag: u16) -> Result<()> {
+        let requested_mode = (flag >> 6) | (flag >> 3) | flag;
+
+        let mut granted_mode = self.mode;
+        if self.is_owner_or_creator(credentials) {
+            granted_mode >>= 6;
+        } else if self.is_in_group(credentials) {
+            granted_mode >>= 3;
+        }
+
+        if (requested_mode & !granted_mode & 0o007) != 0
+            && !credentials.effective_capset().contains(CapSet::IPC_OWNER)
+        {
+            return_errno_with_message!(Errno::EACCES, "the IPC permission check failed");
+        }
+
+        Ok(())
+    }
+
+    fn is_in_group(&self, credentials: &Credentials<ReadOp>) -> bool {
+        let egid = credentials.egid();
+        if egid == self.gid || egid == self.cguid {
+            return true;
+        }
+
+        let groups = credentials.groups();
+        groups.contains(&self.gid) || groups.contains(&self.cguid)
+    }
+
+    pub(self) fn set_key(&mut self, key: key_t) {
+        self.key = key;
+    }
+
+    /// Sets the owner and the permission bits, as `IPC_SET` does.
+    pub(self) fn set_owner_and_mode(&mut self, uid: Uid, gid: Gid, mode: u16) {
+        self.uid = uid;
+        self.gid = gid;
+        self.mode = (self.mode & !0o777) | (mode & 0o777);
+    }
+
+    pub(self) fn set_mode(&mut self, mode: u16) {
+        self.mode = mode;
+    }
+
+    /// Returns the permission in the layout of `struct ipc64_perm`.
+    pub fn to_ipc_perm(&self) -> IpcPerm {
+        IpcPerm {
+            key: self.key as u32,
+            uid: self.uid.into(),
+            gid: self.gid.into(),
+            cuid: self.cuid.into(),
+            cgid: self.cguid.into(),
+            mode: self.mode,
+            ..IpcPerm::default()
+        }
+    }
+}
+
+// https://github.com/torvalds/linux/blob/master/include/uapi/asm-generic/ipcbuf.h
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Pod)]
+pub struct IpcPerm {
+    pub key: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub cuid: u32,
+    pub cgid: u32,
+    pub mode: u16,
+    _pad1: u16,
+    pub seq: u16,
+    _pad2: u16,
+    _unused1: u64,
+    _unused2: u64,
 }
 
 pub(super) fn init_in_first_kthread() {
+    msg::init_in_first_kthread();
     semaphore::init_in_first_kthread();
+    shm::init_in_first_kthread();
 }
@@ -0,0 +1,523 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! System V message queue.
+
+use aster_rights::ReadOp;
+use id_alloc::IdAlloc;
+use ostd::sync::WaitQueue;
+use spin::Once;
+
+use crate::{
+    ipc::{key_t, IpcFlags, IpcPerm, IpcPermission},
+    prelude::*,
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Pid, Uid},
+    time::clocks::RealTimeCoarseClock,
+};
+
+// The following constant values are derived from the default values in Linux.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/msg.h>
+
+/// Maximum number of message queues.
+pub const MSGMNI: usize = 32000;
+/// Maximum size in bytes of a message.
+pub const MSGMAX: usize = 8192;
+/// Default maximum size in bytes of a message queue.
+pub const MSGMNB: usize = 16384;
+
+/// The key that always creates a new message queue.
+const IPC_PRIVATE: key_t = 0;
+
+bitflags! {
+    /// Flags for `msgsnd` and `msgrcv`.
+    pub struct MsgFlags: u32 {
+        /// Return immediately if the operation would block.
+        const IPC_NOWAIT  = IpcFlags::IPC_NOWAIT.bits();
+        /// Truncate the message if it is longer than the buffer.
+        const MSG_NOERROR = 0o10000;
+        /// Receive the first message whose type is not the requested one.
+        const MSG_EXCEPT  = 0o20000;
+        /// Copy the message without removing it from the queue.
+        const MSG_COPY    = 0o40000;
+    }
+}
+
+/// Commands for `msgctl`.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[expect(non_camel_case_types)]
+pub enum MsgControlCmd {
+    IPC_RMID = 0,
+    IPC_SET = 1,
+    IPC_STAT = 2,
+    IPC_INFO = 3,
+
+    MSG_STAT = 11,
+    MSG_INFO = 12,
+    MSG_STAT_ANY = 13,
+}
+
+// In Linux, all 64-bit architectures adopt the same layout of `msqid_ds`.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/asm-generic/msgbuf.h>
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Pod)]
+pub struct MsqidDs {
+    pub msg_perm: IpcPerm,
+    pub msg_stime: u64,
+    pub msg_rtime: u64,
+    pub msg_ctime: u64,
+    pub msg_cbytes: u64,
+    pub msg_qnum: u64,
+    pub msg_qbytes: u64,
+    pub msg_lspid: i32,
+    pub msg_lrpid: i32,
+    _unused4: u64,
+    _unused5: u64,
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/msg.h>
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Pod)]
+pub struct MsgInfo {
+    msgpool: i32,
+    msgmap: i32,
+    msgmax: i32,
+    msgmnb: i32,
+    msgmni: i32,
+    msgssz: i32,
+    msgtql: i32,
+    msgseg: u16,
+    _pad: u16,
+}
+
+impl MsgInfo {
+    /// Returns the limits of message queues.
+    pub fn current() -> Self {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/msg.c>
+        const MSGPOOL: i32 = (MSGMNI * MSGMNB / 1024) as i32;
+        const MSGSSZ: i32 = 16;
+        const MSGSEG: u16 = 0xffff;
+
+        Self {
+            msgpool: MSGPOOL,
+            msgmap: MSGMNB as i32,
+            msgmax: MSGMAX as i32,
+            msgmnb: MSGMNB as i32,
+            msgmni: MSGMNI as i32,
+            msgssz: MSGSSZ,
+            msgtql: MSGMNB as i32,
+            msgseg: MSGSEG,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the limits of message queues with the resource usage of all the
+    /// queues, as `MSG_INFO` does.
+    pub fn usage() -> Self {
+        let queues = MSG_QUEUES.read();
+
+        let mut num_messages = 0;
+        let mut num_bytes = 0;
+        for queue in queues.values() {
+            let inner = queue.inner.lock();
+            num_messages += inner.messages.len();
+            num_bytes += inner.cbytes;
+        }
+
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/msg.c>
+        Self {
+            msgpool: queues.len() as i32,
+            msgmap: num_messages as i32,
+            msgtql: num_bytes as i32,
+            ..Self::current()
+        }
+    }
+}
+
+/// A message in a message queue.
+#[derive(Debug)]
+pub struct Message {
+    mtype: i64,
+    mtext: Box<[u8]>,
+}
+
+impl Message {
+    pub fn new(mtype: i64, mtext: Box<[u8]>) -> Self {
+        debug_assert!(mtype > 0);
+        Self { mtype, mtext }
+    }
+
+    pub fn mtype(&self) -> i64 {
+        self.mtype
+    }
+
+    pub fn mtext(&self) -> &[u8] {
+        &self.mtext
+    }
+}
+
+/// A System V message queue.
+#[derive(Debug)]
+pub struct MessageQueue {
+    /// The message queue ID
+    id: key_t,
+    /// Inner
+    inner: SpinLock<MsgQueueInner>,
+    /// Senders that wait for free space in the queue
+    send_wait_queue: WaitQueue,
+    /// Receivers that wait for a suitable message
+    recv_wait_queue: WaitQueue,
+}
+
+#[derive(Debug)]
+struct MsgQueueInner {
+    /// The message queue permission
+    permission: IpcPermission,
+    /// Messages in the order of their arrival
+    messages: VecDeque<Message>,
+    /// Current number of bytes in the queue
+    cbytes: usize,
+    /// Maximum number of bytes allowed in the queue
+    qbytes: usize,
+    /// The PID of the last `msgsnd` caller
+    lspid: Pid,
+    /// The PID of the last `msgrcv` caller
+    lrpid: Pid,
+    /// Last `msgsnd` time
+    stime: u64,
+    /// Last `msgrcv` time
+    rtime: u64,
+    /// Creation time or last modification via `msgctl`
+    ctime: u64,
+    /// Whether the queue has been removed
+    is_removed: bool,
+}
+
+/// The selector of messages for `msgrcv`.
+#[derive(Debug, Clone, Copy)]
+pub enum MsgSelector {
+    /// The first message in the queue.
+    Any,
+    /// The first message of the type.
+    Type(i64),
+    /// The first message that is not of the type.
+    ExceptType(i64),
+    /// The first message of the lowest type that is less than or equal to the
+    /// value.
+    LowestType(i64),
+}
+
+impl MsgSelector {
+    pub fn new(msgtyp: i64, flags: MsgFlags) -> Self {
+        match msgtyp {
+            0 => Self::Any,
+            typ if typ > 0 && flags.contains(MsgFlags::MSG_EXCEPT) => Self::ExceptType(typ),
+            typ if typ > 0 => Self::Type(typ),
+            // Note that `i64::MIN.unsigned_abs()` does not fit in `i64`, but
+            // all message types are positive `i64` values.
+            typ => Self::LowestType(typ.unsigned_abs().min(i64::MAX as u64) as i64),
+        }
+    }
+
+    fn find(&self, messages: &VecDeque<Message>) -> Option<usize> {
+        match *self {
+            Self::Any => (!messages.is_empty()).then_some(0),
+            Self::Type(typ) => messages.iter().position(|msg| msg.mtype == typ),
+            Self::ExceptType(typ) => messages.iter().position(|msg| msg.mtype != typ),
+            Self::LowestType(typ) => messages
+                .iter()
+                .enumerate()
+                .filter(|(_, msg)| msg.mtype <= typ)
+                // `min_by_key` returns the first one among the equal elements.
+                .min_by_key(|(_, msg)| msg.mtype)
+                .map(|(index, _)| index),
+        }
+    }
+}
+
+impl MessageQueue {
+    fn new(id: key_t, key: key_t, mode: u16, credentials: &Credentials<ReadOp>) -> Self {
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
+
+        Self {
+            id,
+            inner: SpinLock::new(MsgQueueInner {
+                permission,
+                messages: VecDeque::new(),
+                cbytes: 0,
+                qbytes: MSGMNB,
+                lspid: 0,
+                lrpid: 0,
+                stime: 0,
+                rtime: 0,
+                ctime: now(),
+                is_removed: false,
+            }),
+            send_wait_queue: WaitQueue::new(),
+            recv_wait_queue: WaitQueue::new(),
+        }
+    }
+
+    /// Returns the message queue ID.
+    pub fn id(&self) -> key_t {
+        self.id
+    }
+
+    /// Checks whether the credentials are granted the access in `flag`.
+    pub fn check_access(&self, credentials: &Credentials<ReadOp>, flag: u16) -> Result<()> {
+        self.inner.lock().permission.check_access(credentials, flag)
+    }
+
+    /// Sends a message to the queue.
+    ///
+    /// If the queue is full, this method blocks until there is enough space
+    /// unless `IPC_NOWAIT` is specified.
+    pub fn send(
+        &self,
+        message: Message,
+        flags: MsgFlags,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<()> {
+        debug_assert!(message.mtext.len() <= MSGMAX);
+
+        self.check_access(credentials, 0o222)?;
+
+        let mut message = Some(message);
+        let mut try_send = || {
+            let mut inner = self.inner.lock();
+            if inner.is_removed {
+                return_errno_with_message!(Errno::EIDRM, "the message queue is removed");
+            }
+
+            let len = message.as_ref().unwrap().mtext.len();
+            // Each message also counts as one byte towards the limit, which
+            // prevents the queue from being flooded by zero-length messages.
+            if inner.cbytes + len > inner.qbytes || inner.messages.len() + 1 > inner.qbytes {
+                return_errno_with_message!(Errno::EAGAIN, "the message queue is full");
+            }
+
+            inner.cbytes += len;
+            inner.messages.push_back(message.take().unwrap());
+            inner.lspid = pid;
+            inner.stime = now();
+            drop(inner);
+
+            self.recv_wait_queue.wake_all();
+            Ok(())
+        };
+
+        if flags.contains(MsgFlags::IPC_NOWAIT) {
+            return try_send();
+        }
+
+        self.send_wait_queue.pause_until(|| match try_send() {
+            Err(err) if err.error() == Errno::EAGAIN => None,
+            result => Some(result),
+        })?
+    }
+
+    /// Receives a message from the queue.
+    ///
+    /// If no message is selected, this method blocks until one arrives unless
+    /// `IPC_NOWAIT` is specified. If the selected message is longer than
+    /// `max_len`, the message is truncated if `MSG_NOERROR` is specified, or
+    /// left in the queue otherwise.
+    pub fn receive(
+        &self,
+        selector: MsgSelector,
+        max_len: usize,
+        flags: MsgFlags,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<Message> {
+        self.check_access(credentials, 0o444)?;
+
+        let try_receive = || {
+            let mut inner = self.inner.lock();
+            if inner.is_removed {
+                return_errno_with_message!(Errno::EIDRM, "the message queue is removed");
+            }
+
+            let Some(index) = selector.find(&inner.messages) else {
+                return_errno_with_message!(Errno::ENOMSG, "no message is available");
+            };
+            if inner.messages[index].mtext.len() > max_len && !flags.contains(MsgFlags::MSG_NOERROR)
+            {
+                return_errno_with_message!(Errno::E2BIG, "the message is too long");
+            }
+
+            let mut message = inner.messages.remove(index).unwrap();
+            inner.cbytes -= message.mtext.len();
+            inner.lrpid = pid;
+            inner.rtime = now();
+            drop(inner);
+
+            self.send_wait_queue.wake_all();
+
+            if message.mtext.len() > max_len {
+                message.mtext = message.mtext[..max_len].into();
+            }
+            Ok(message)
+        };
+
+        if flags.contains(MsgFlags::IPC_NOWAIT) {
+            return try_receive();
+        }
+
+        self.recv_wait_queue.pause_until(|| match try_receive() {
+            Err(err) if err.error() == Errno::ENOMSG => None,
+            result => Some(result),
+        })?
+    }
+
+    /// Returns the status of the queue in the layout of `struct msqid64_ds`.
+    pub fn msqid_ds(&self) -> MsqidDs {
+        let inner = self.inner.lock();
+
+        MsqidDs {
+            msg_perm: inner.permission.to_ipc_perm(),
+            msg_stime: inner.stime,
+            msg_rtime: inner.rtime,
+            msg_ctime: inner.ctime,
+            msg_cbytes: inner.cbytes as u64,
+            msg_qnum: inner.messages.len() as u64,
+            msg_qbytes: inner.qbytes as u64,
+            msg_lspid: inner.lspid as i32,
+            msg_lrpid: inner.lrpid as i32,
+            ..MsqidDs::default()
+        }
+    }
+
+    /// Sets the owner, the permission bits, and the maximum number of bytes of
+    /// the queue, as `IPC_SET` does.
+    pub fn set(&self, msqid_ds: &MsqidDs, credentials: &Credentials<ReadOp>) -> Result<()> {
+        let mut inner = self.inner.lock();
+        inner.permission.check_owner(credentials)?;
+
+        let new_qbytes = msqid_ds.msg_qbytes as usize;
+        if new_qbytes > MSGMNB
+            && new_qbytes > inner.qbytes
+            && !credentials
+                .effective_capset()
+                .contains(CapSet::SYS_RESOURCE)
+        {
+            return_errno_with_message!(
+                Errno::EPERM,
+                "raising the queue size beyond the limit requires `CAP_SYS_RESOURCE`"
+            );
+        }
+
+        let new_perm = &msqid_ds.msg_perm;
+        inner.permission.set_owner_and_mode(
+            Uid::new(new_perm.uid),
+            Gid::new(new_perm.gid),
+            new_perm.mode,
+        );
+        inner.qbytes = new_qbytes;
+        inner.ctime = now();
+        drop(inner);
+
+        // The queue may be able to hold more messages now.
+        self.send_wait_queue.wake_all();
+
+        Ok(())
+    }
+}
+
+/// Gets a message queue with the key, and creates one if necessary.
+///
+/// Returns the ID of the message queue.
+pub fn get_or_create_msg_queue(
+    key: key_t,
+    flags: IpcFlags,
+    mode: u16,
+    credentials: &Credentials<ReadOp>,
+) -> Result<key_t> {
+    let mut queues = MSG_QUEUES.write();
+
+    if key != IPC_PRIVATE
+        && let Some(queue) = queues
+            .values()
+            .find(|queue| queue.inner.lock().permission.key() == key)
+    {
+        if flags.contains(IpcFlags::IPC_CREAT | IpcFlags::IPC_EXCL) {
+            return_errno_with_message!(Errno::EEXIST, "the message queue already exists");
+        }
+        queue.check_access(credentials, mode)?;
+        return Ok(queue.id());
+    }
+
+    if key != IPC_PRIVATE && !flags.contains(IpcFlags::IPC_CREAT) {
+        return_errno_with_message!(Errno::ENOENT, "the message queue does not exist");
+    }
+
+    let id = ID_ALLOCATOR
+        .get()
+        .unwrap()
+        .lock()
+        .alloc()
+        .ok_or_else(|| Error::with_message(Errno::ENOSPC, "too many message queues"))?
+        as key_t;
+    queues.insert(id, Arc::new(MessageQueue::new(id, key, mode, credentials)));
+
+    Ok(id)
+}
+
+/// Gets the message queue with the ID.
+pub fn get_msg_queue(id: key_t) -> Result<Arc<MessageQueue>> {
+    MSG_QUEUES
+        .read()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the message queue does not exist"))
+}
+
+/// Returns all the existing message queues.
+pub fn all_msg_queues() -> Vec<Arc<MessageQueue>> {
+    MSG_QUEUES.read().values().cloned().collect()
+}
+
+/// Returns the largest ID of the existing message queues.
+pub fn max_msg_queue_id() -> Option<key_t> {
+    MSG_QUEUES.read().keys().next_back().copied()
+}
+
+/// Removes the message queue, as `IPC_RMID` does.
+///
+/// All the waiting senders and receivers will fail with `EIDRM`.
+pub fn remove_msg_queue(id: key_t, credentials: &Credentials<ReadOp>) -> Result<()> {
+    let queue = {
+        let mut queues = MSG_QUEUES.write();
+        let queue = queues.get(&id).ok_or_else(|| {
+            Error::with_message(Errno::EINVAL, "the message queue does not exist")
+        })?;
+
+        let mut inner = queue.inner.lock();
+        inner.permission.check_owner(credentials)?;
+        inner.is_removed = true;
+        inner.messages.clear();
+        inner.cbytes = 0;
+        drop(inner);
+
+        ID_ALLOCATOR.get().unwrap().lock().free(id as usize);
+        queues.remove(&id).unwrap()
+    };
+
+    queue.send_wait_queue.wake_all();
+    queue.recv_wait_queue.wake_all();
+
+    Ok(())
+}
+
+fn now() -> u64 {
+    RealTimeCoarseClock::get().read_time().as_secs()
+}
+
+static ID_ALLOCATOR: Once<SpinLock<IdAlloc>> = Once::new();
+
+/// Message queues in system
+static MSG_QUEUES: RwLock<BTreeMap<key_t, Arc<MessageQueue>>> = RwLock::new(BTreeMap::new());
+
+pub(super) fn init_in_first_kthread() {
+    ID_ALLOCATOR.call_once(|| SpinLock::new(IdAlloc::with_capacity(MSGMNI)));
+}
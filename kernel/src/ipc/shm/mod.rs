@@ -0,0 +1,609 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! System V shared memory.
+//!
+//! Each shared memory segment is backed by an anonymous [`Vmo`]. Attaching a
+//! segment creates a shared mapping of the VMO in the VMAR of the process, so
+//! the pages are shared among all the attachments, including the ones inherited
+//! by forked processes.
+//!
+//! Each mapping of a segment owns a [`ShmAttachment`], which is duplicated
+//! along with the mapping (e.g., when forking) and dropped once the mapping is
+//! unmapped (e.g., by `shmdt`, or when the process exits). The number of
+//! attachments is reported as `shm_nattch`.
+//!
+//! A segment itself only holds a strong reference to its VMO before it is
+//! removed by `IPC_RMID`. After that, the VMO is kept alive by the remaining
+//! attachments and is freed as soon as the last one is unmapped.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use align_ext::AlignExt;
+use aster_rights::ReadOp;
+use id_alloc::IdAlloc;
+use spin::Once;
+
+use crate::{
+    ipc::{key_t, IpcFlags, IpcPerm, IpcPermission},
+    prelude::*,
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Pid, Uid},
+    time::clocks::RealTimeCoarseClock,
+    vm::{
+        perms::VmPerms,
+        vmar::{is_userspace_vaddr, Vmar},
+        vmo::{Vmo, VmoOptions},
+    },
+};
+
+// The following constant values are derived from the default values in Linux.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/shm.h>
+
+/// Minimum size in bytes of a shared memory segment.
+pub const SHMMIN: usize = 1;
+/// Maximum number of shared memory segments.
+pub const SHMMNI: usize = 4096;
+/// Maximum number of shared memory segments that a process can attach.
+pub const SHMSEG: usize = SHMMNI;
+/// Default value of [`SHMMAX`].
+const SHMMAX_DEFAULT: usize = usize::MAX - (1 << 24);
+/// Default value of [`SHMALL`].
+const SHMALL_DEFAULT: usize = usize::MAX - (1 << 24);
+
+/// Maximum size in bytes of a shared memory segment.
+///
+/// This limit can be changed via `/proc/sys/kernel/shmmax`.
+pub static SHMMAX: AtomicUsize = AtomicUsize::new(SHMMAX_DEFAULT);
+/// Maximum number of pages of all shared memory segments.
+///
+/// This limit can be changed via `/proc/sys/kernel/shmall`.
+pub static SHMALL: AtomicUsize = AtomicUsize::new(SHMALL_DEFAULT);
+
+/// The key that always creates a new segment.
+const IPC_PRIVATE: key_t = 0;
+
+/// The segment will be destroyed after the last detachment.
+const SHM_DEST: u16 = 0o1000;
+/// The segment is locked in memory.
+const SHM_LOCKED: u16 = 0o2000;
+
+bitflags! {
+    /// Flags for `shmat`.
+    pub struct ShmAtFlags: u32 {
+        /// Attach the segment for read-only access.
+        const SHM_RDONLY = 0o10000;
+        /// Round the attach address down to a multiple of `SHMLBA`.
+        const SHM_RND    = 0o20000;
+        /// Take over the existing mappings in the region.
+        const SHM_REMAP  = 0o40000;
+        /// Allow the contents of the segment to be executed.
+        const SHM_EXEC   = 0o100000;
+    }
+}
+
+/// Commands for `shmctl`.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[expect(non_camel_case_types)]
+pub enum ShmControlCmd {
+    IPC_RMID = 0,
+    IPC_SET = 1,
+    IPC_STAT = 2,
+    IPC_INFO = 3,
+
+    SHM_LOCK = 11,
+    SHM_UNLOCK = 12,
+    SHM_STAT = 13,
+    SHM_INFO = 14,
+    SHM_STAT_ANY = 15,
+}
+
+/// Segment low boundary address multiple.
+pub const SHMLBA: usize = PAGE_SIZE;
+
+// In Linux, all 64-bit architectures adopt the same layout of `shmid_ds`.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/asm-generic/shmbuf.h>
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Pod)]
+pub struct ShmidDs {
+    pub shm_perm: IpcPerm,
+    pub shm_segsz: u64,
+    pub shm_atime: u64,
+    pub shm_dtime: u64,
+    pub shm_ctime: u64,
+    pub shm_cpid: i32,
+    pub shm_lpid: i32,
+    pub shm_nattch: u64,
+    _unused4: u64,
+    _unused5: u64,
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/asm-generic/shmbuf.h>
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Pod)]
+pub struct ShmInfo {
+    shmmax: u64,
+    shmmin: u64,
+    shmmni: u64,
+    shmseg: u64,
+    shmall: u64,
+    _unused1: u64,
+    _unused2: u64,
+    _unused3: u64,
+    _unused4: u64,
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/shm.h#L76>
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, Pod)]
+pub struct ShmUsage {
+    used_ids: i32,
+    _padding: u32,
+    shm_tot: u64,
+    shm_rss: u64,
+    shm_swp: u64,
+    swap_attempts: u64,
+    swap_successes: u64,
+}
+
+impl ShmInfo {
+    /// Returns the current limits of shared memory.
+    pub fn current() -> Self {
+        Self {
+            shmmax: SHMMAX.load(Ordering::Relaxed) as u64,
+            shmmin: SHMMIN as u64,
+            shmmni: SHMMNI as u64,
+            shmseg: SHMSEG as u64,
+            shmall: SHMALL.load(Ordering::Relaxed) as u64,
+            ..Self::default()
+        }
+    }
+}
+
+/// A System V shared memory segment.
+#[derive(Debug)]
+pub struct SharedMemory {
+    /// The segment ID
+    id: key_t,
+    /// The size in bytes requested by `shmget`
+    size: usize,
+    /// The PID of the creator
+    creator_pid: Pid,
+    /// The VMO that provides the pages of the segment
+    vmo: Weak<Vmo>,
+    /// Inner
+    inner: SpinLock<SharedMemoryInner>,
+}
+
+#[derive(Debug)]
+struct SharedMemoryInner {
+    /// The segment permission
+    permission: IpcPermission,
+    /// The strong reference to the VMO, which is dropped once the segment is
+    /// marked to be destroyed
+    vmo: Option<Arc<Vmo>>,
+    /// The number of attachments
+    nattch: usize,
+    /// The PID of the last `shmat` or `shmdt` caller
+    last_pid: Pid,
+    /// Last attach time
+    atime: u64,
+    /// Last detach time
+    dtime: u64,
+    /// Creation time or last modification via `shmctl`
+    ctime: u64,
+}
+
+impl SharedMemory {
+    fn new(
+        id: key_t,
+        key: key_t,
+        size: usize,
+        mode: u16,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<Self> {
+        let vmo = VmoOptions::new(size.align_up(PAGE_SIZE)).alloc()?;
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
+
+        Ok(Self {
+            id,
+            size,
+            creator_pid: pid,
+            vmo: Arc::downgrade(&vmo),
+            inner: SpinLock::new(SharedMemoryInner {
+                permission,
+                vmo: Some(vmo),
+                nattch: 0,
+                last_pid: 0,
+                atime: 0,
+                dtime: 0,
+                ctime: now(),
+            }),
+        })
+    }
+
+    /// Returns the segment ID.
+    pub fn id(&self) -> key_t {
+        self.id
+    }
+
+    /// Returns the size in bytes of the segment.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn is_destroyed(&self, inner: &SharedMemoryInner) -> bool {
+        inner.permission.mode() & SHM_DEST != 0
+    }
+
+    fn is_orphan(&self, inner: &SharedMemoryInner) -> bool {
+        self.is_destroyed(inner) && inner.nattch == 0
+    }
+
+    /// Returns the status of the segment in the layout of `struct shmid64_ds`.
+    pub fn shmid_ds(&self) -> ShmidDs {
+        let inner = self.inner.lock();
+
+        ShmidDs {
+            shm_perm: inner.permission.to_ipc_perm(),
+            shm_segsz: self.size as u64,
+            shm_atime: inner.atime,
+            shm_dtime: inner.dtime,
+            shm_ctime: inner.ctime,
+            shm_cpid: self.creator_pid as i32,
+            shm_lpid: inner.last_pid as i32,
+            shm_nattch: inner.nattch as u64,
+            ..ShmidDs::default()
+        }
+    }
+
+    /// Checks whether the credentials are granted the access in `flag`.
+    pub fn check_access(&self, credentials: &Credentials<ReadOp>, flag: u16) -> Result<()> {
+        self.inner.lock().permission.check_access(credentials, flag)
+    }
+
+    /// Attaches the segment to the VMAR.
+    ///
+    /// If `addr` is zero, a suitable address is chosen automatically.
+    /// Otherwise, `addr` must be page-aligned.
+    pub fn attach(
+        self: &Arc<Self>,
+        vmar: &Vmar,
+        addr: Vaddr,
+        flags: ShmAtFlags,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<Vaddr> {
+        debug_assert!(addr % SHMLBA == 0);
+
+        let (perms, acc_mode) = if flags.contains(ShmAtFlags::SHM_RDONLY) {
+            (VmPerms::READ, 0o444)
+        } else {
+            (VmPerms::READ | VmPerms::WRITE, 0o666)
+        };
+        let (perms, acc_mode) = if flags.contains(ShmAtFlags::SHM_EXEC) {
+            (perms | VmPerms::EXEC, acc_mode | 0o111)
+        } else {
+            (perms, acc_mode)
+        };
+
+        let (vmo, attachment) = {
+            let mut inner = self.inner.lock();
+            inner.permission.check_access(credentials, acc_mode)?;
+            // The segment may have been destroyed after all its attachments
+            // are gone, in which case it is no longer accessible.
+            let vmo = self
+                .vmo
+                .upgrade()
+                .filter(|_| !self.is_orphan(&inner))
+                .ok_or_else(|| Error::with_message(Errno::EIDRM, "the segment is removed"))?;
+
+            inner.nattch += 1;
+            (vmo, ShmAttachment(self.clone()))
+        };
+
+        let map_size = self.size.align_up(PAGE_SIZE);
+        let mut may_perms = VmPerms::ALL_MAY_PERMS;
+        if flags.contains(ShmAtFlags::SHM_RDONLY) {
+            may_perms.remove(VmPerms::MAY_WRITE);
+        }
+
+        let mut options = vmar
+            .new_map(map_size, perms)?
+            .may_perms(may_perms)
+            .vmo(vmo)
+            .shm_attachment(attachment)
+            .is_shared(true);
+        if addr != 0 {
+            let end = addr
+                .checked_add(map_size)
+                .ok_or_else(|| Error::with_message(Errno::EINVAL, "the address overflows"))?;
+            if !is_userspace_vaddr(addr) || !is_userspace_vaddr(end - 1) {
+                return_errno_with_message!(Errno::EINVAL, "the address is not in user space");
+            }
+
+            let can_overwrite = flags.contains(ShmAtFlags::SHM_REMAP);
+            if !can_overwrite && vmar.query(addr..end).iter().next().is_some() {
+                return_errno_with_message!(Errno::EINVAL, "the region is already mapped");
+            }
+            options = options.offset(addr).can_overwrite(can_overwrite);
+        }
+        let map_addr = options.build()?;
+
+        let mut inner = self.inner.lock();
+        inner.atime = now();
+        inner.last_pid = pid;
+
+        Ok(map_addr)
+    }
+
+    /// Sets the owner and the permission bits of the segment, as `IPC_SET` does.
+    pub fn set(&self, shmid_ds: &ShmidDs, credentials: &Credentials<ReadOp>) -> Result<()> {
+        let mut inner = self.inner.lock();
+        inner.permission.check_owner(credentials)?;
+
+        let new_perm = &shmid_ds.shm_perm;
+        inner.permission.set_owner_and_mode(
+            Uid::new(new_perm.uid),
+            Gid::new(new_perm.gid),
+            new_perm.mode,
+        );
+        inner.ctime = now();
+
+        Ok(())
+    }
+
+    /// Locks or unlocks the segment in memory, as `SHM_LOCK` and
+    /// `SHM_UNLOCK` do.
+    ///
+    /// Since the pages of a segment are never swapped out, this only updates
+    /// the `SHM_LOCKED` bit of the mode.
+    pub fn set_locked(&self, is_locked: bool, credentials: &Credentials<ReadOp>) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if !credentials.effective_capset().contains(CapSet::IPC_LOCK)
+            && !inner.permission.is_owner_or_creator(credentials)
+        {
+            return_errno_with_message!(Errno::EPERM, "the segment cannot be locked or unlocked");
+        }
+
+        let mode = inner.permission.mode();
+        let new_mode = if is_locked {
+            mode | SHM_LOCKED
+        } else {
+            mode & !SHM_LOCKED
+        };
+        inner.permission.set_mode(new_mode);
+
+        Ok(())
+    }
+}
+
+/// An attachment of a shared memory segment.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Debug)]
+pub struct ShmAttachment(Arc<SharedMemory>);
+
+impl Clone for ShmAttachment {
+    fn clone(&self) -> Self {
+        self.0.inner.lock().nattch += 1;
+        Self(self.0.clone())
+    }
+}
+
+impl Drop for ShmAttachment {
+    fn drop(&mut self) {
+        let is_orphan = {
+            let mut inner = self.0.inner.lock();
+            inner.nattch -= 1;
+            self.0.is_orphan(&inner)
+        };
+        if is_orphan {
+            reap_destroyed(&mut SHM_SEGMENTS.write());
+        }
+    }
+}
+
+/// Gets a segment with the key, and creates one if necessary.
+///
+/// Returns the ID of the segment.
+pub fn get_or_create_shm(
+    key: key_t,
+    size: usize,
+    flags: IpcFlags,
+    mode: u16,
+    credentials: &Credentials<ReadOp>,
+    pid: Pid,
+) -> Result<key_t> {
+    let mut segments = SHM_SEGMENTS.write();
+    reap_destroyed(&mut segments);
+
+    if key != IPC_PRIVATE
+        && let Some(shm) = segments
+            .values()
+            .find(|shm| shm.inner.lock().permission.key() == key)
+    {
+        if flags.contains(IpcFlags::IPC_CREAT | IpcFlags::IPC_EXCL) {
+            return_errno_with_message!(Errno::EEXIST, "the segment already exists");
+        }
+        shm.check_access(credentials, mode)?;
+        if size > shm.size() {
+            return_errno_with_message!(Errno::EINVAL, "the segment is smaller than the size");
+        }
+        return Ok(shm.id());
+    }
+
+    if key != IPC_PRIVATE && !flags.contains(IpcFlags::IPC_CREAT) {
+        return_errno_with_message!(Errno::ENOENT, "the segment does not exist");
+    }
+
+    if size < SHMMIN || size > SHMMAX.load(Ordering::Relaxed) {
+        return_errno_with_message!(Errno::EINVAL, "the size is out of range");
+    }
+    let total_pages = segments
+        .values()
+        .map(|shm| shm.size().div_ceil(PAGE_SIZE))
+        .sum::<usize>();
+    if total_pages.saturating_add(size.div_ceil(PAGE_SIZE)) > SHMALL.load(Ordering::Relaxed) {
+        return_errno_with_message!(Errno::ENOSPC, "the total size of segments is too large");
+    }
+
+    let id = ID_ALLOCATOR
+        .get()
+        .unwrap()
+        .lock()
+        .alloc()
+        .ok_or_else(|| Error::with_message(Errno::ENOSPC, "too many segments"))?
+        as key_t;
+    let shm = match SharedMemory::new(id, key, size, mode, credentials, pid) {
+        Ok(shm) => shm,
+        Err(err) => {
+            ID_ALLOCATOR.get().unwrap().lock().free(id as usize);
+            return Err(err);
+        }
+    };
+    segments.insert(id, Arc::new(shm));
+
+    Ok(id)
+}
+
+/// Gets the segment with the ID.
+pub fn get_shm(id: key_t) -> Result<Arc<SharedMemory>> {
+    SHM_SEGMENTS
+        .read()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the segment does not exist"))
+}
+
+/// Returns all the segments, ordered by their IDs.
+pub fn all_shm() -> Vec<Arc<SharedMemory>> {
+    let mut segments = SHM_SEGMENTS.write();
+    reap_destroyed(&mut segments);
+    segments.values().cloned().collect()
+}
+
+/// Returns the resource usage of all the segments, as `SHM_INFO` does.
+pub fn shm_usage() -> ShmUsage {
+    let mut segments = SHM_SEGMENTS.write();
+    reap_destroyed(&mut segments);
+
+    // TODO: Report the number of resident pages. Since the pages are never
+    // swapped out, the number of swapped pages is always zero.
+    ShmUsage {
+        used_ids: segments.len() as i32,
+        shm_tot: segments
+            .values()
+            .map(|shm| shm.size().div_ceil(PAGE_SIZE) as u64)
+            .sum(),
+        ..ShmUsage::default()
+    }
+}
+
+/// Returns the largest ID of the existing segments.
+pub fn max_shm_id() -> Option<key_t> {
+    SHM_SEGMENTS.read().keys().next_back().copied()
+}
+
+/// Marks the segment to be destroyed, as `IPC_RMID` does.
+///
+/// The segment is destroyed immediately if it is not attached. Otherwise, it
+/// is destroyed after the last detachment, and a new `shmget` call can no
+/// longer find it by its key.
+pub fn remove_shm(id: key_t, credentials: &Credentials<ReadOp>) -> Result<()> {
+    let mut segments = SHM_SEGMENTS.write();
+    let shm = segments
+        .get(&id)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the segment does not exist"))?;
+
+    {
+        let mut inner = shm.inner.lock();
+        inner.permission.check_owner(credentials)?;
+
+        let mode = inner.permission.mode();
+        inner.permission.set_key(IPC_PRIVATE);
+        inner.permission.set_mode(mode | SHM_DEST);
+        inner.vmo = None;
+        inner.ctime = now();
+    }
+
+    reap_destroyed(&mut segments);
+
+    Ok(())
+}
+
+/// Detaches the segment that is attached at `addr` from the VMAR.
+pub fn detach_shm(vmar: &Vmar, addr: Vaddr, pid: Pid) -> Result<()> {
+    if addr % SHMLBA != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the address is not aligned");
+    }
+
+    let vmo = {
+        let guard = vmar.query(addr..addr + 1);
+        guard
+            .iter()
+            .find_map(|vm_mapping| match vm_mapping.mapped_vmo() {
+                Some((vmo, 0)) if vm_mapping.map_to_addr() == addr => Some(vmo.clone()),
+                _ => None,
+            })
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "no segment is attached"))?
+    };
+
+    let shm = SHM_SEGMENTS
+        .read()
+        .values()
+        .find(|shm| shm.vmo.as_ptr() == Arc::as_ptr(&vmo))
+        .cloned()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "no segment is attached"))?;
+
+    // Unmap all the parts of the attachment, which may have been split into
+    // several mappings.
+    let ranges = {
+        let end = addr.saturating_add(shm.size().align_up(PAGE_SIZE));
+        let guard = vmar.query(addr..end);
+        guard
+            .iter()
+            .filter(|vm_mapping| {
+                matches!(vm_mapping.mapped_vmo(), Some((mapped_vmo, offset))
+                    if Arc::ptr_eq(mapped_vmo, &vmo)
+                        && offset == vm_mapping.map_to_addr() - addr)
+            })
+            .map(|vm_mapping| vm_mapping.map_to_addr()..vm_mapping.map_end().min(end))
+            .collect::<Vec<_>>()
+    };
+    drop(vmo);
+
+    for range in ranges {
+        vmar.remove_mapping(range)?;
+    }
+
+    let mut inner = shm.inner.lock();
+    inner.dtime = now();
+    inner.last_pid = pid;
+
+    Ok(())
+}
+
+/// Removes the segments that are marked to be destroyed and no longer attached.
+fn reap_destroyed(segments: &mut BTreeMap<key_t, Arc<SharedMemory>>) {
+    segments.retain(|id, shm| {
+        let should_remove = shm.is_orphan(&shm.inner.lock());
+        if should_remove {
+            ID_ALLOCATOR.get().unwrap().lock().free(*id as usize);
+        }
+        !should_remove
+    });
+}
+
+fn now() -> u64 {
+    RealTimeCoarseClock::get().read_time().as_secs()
+}
+
+static ID_ALLOCATOR: Once<SpinLock<IdAlloc>> = Once::new();
+
+/// Shared memory segments in system
+static SHM_SEGMENTS: RwLock<BTreeMap<key_t, Arc<SharedMemory>>> = RwLock::new(BTreeMap::new());
+
+pub(super) fn init_in_first_kthread() {
+    ID_ALLOCATOR.call_once(|| SpinLock::new(IdAlloc::with_capacity(SHMMNI)));
+}
@@ -39,6 +39,7 @@ pub enum ModuleArg {
 pub struct KCmdlineArg {
     initproc: InitprocArgs,
     module_args: BTreeMap<String, Vec<ModuleArg>>,
+    ip_config: Option<String>,
 }
 
 // Define get APIs.
@@ -55,6 +56,10 @@ impl KCmdlineArg {
     pub fn get_initproc_envp(&self) -> &Vec<CString> {
         &self.initproc.envp
     }
+    /// Gets the network configuration specified by the `ip=` parameter.
+    pub fn get_ip_config(&self) -> Option<&str> {
+        self.ip_config.as_deref()
+    }
     /// Gets the argument vector of a kernel module.
     #[expect(dead_code)]
     pub fn get_module_args(&self, module: &str) -> Option<&Vec<ModuleArg>> {
@@ -87,6 +92,7 @@ impl From<&str> for KCmdlineArg {
                 envp: Vec::new(),
             },
             module_args: BTreeMap::new(),
+            ip_config: None,
         };
 
         // Every thing after the "--" mark is the initproc arguments.
@@ -162,6 +168,10 @@ impl From<&str> for KCmdlineArg {
                         }
                         result.initproc.path = Some(value.to_string());
                     }
+                    "ip" => {
+                        // Like Linux, the last `ip=` parameter takes effect.
+                        result.ip_config = Some(value.to_string());
+                    }
                     _ => {
                         // If the option is not recognized, it is passed to the initproc.
                         // Pattern 'option=value' is treated as the init environment.
@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    net::socket::netlink::message::{Attribute, CAttrHeader, ContinueRead},
+    prelude::*,
+    util::MultiRead,
+};
+
+/// The maximum length of iface names, including the trailing null character.
+const IFNAME_SIZE: usize = 16;
+
+/// Packet filter attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+#[repr(u16)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+pub(super) enum FilterAttrClass {
+    UNSPEC = 0,
+    CHAIN = 1,
+    INDEX = 2,
+    PROTO = 3,
+    SRC_ADDR = 4,
+    SRC_PREFIX_LEN = 5,
+    DST_ADDR = 6,
+    DST_PREFIX_LEN = 7,
+    SRC_PORT_MIN = 8,
+    SRC_PORT_MAX = 9,
+    DST_PORT_MIN = 10,
+    DST_PORT_MAX = 11,
+    IFNAME = 12,
+    ACTION = 13,
+    PACKETS = 14,
+    BYTES = 15,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum FilterAttr {
+    Chain(u32),
+    Index(u32),
+    Proto(u8),
+    SrcAddr([u8; 4]),
+    SrcPrefixLen(u8),
+    DstAddr([u8; 4]),
+    DstPrefixLen(u8),
+    SrcPortMin(u16),
+    SrcPortMax(u16),
+    DstPortMin(u16),
+    DstPortMax(u16),
+    IfName(CString),
+    Action(u32),
+    Packets(u64),
+    Bytes(u64),
+}
+
+impl FilterAttr {
+    fn class(&self) -> FilterAttrClass {
+        match self {
+            FilterAttr::Chain(_) => FilterAttrClass::CHAIN,
+            FilterAttr::Index(_) => FilterAttrClass::INDEX,
+            FilterAttr::Proto(_) => FilterAttrClass::PROTO,
+            FilterAttr::SrcAddr(_) => FilterAttrClass::SRC_ADDR,
+            FilterAttr::SrcPrefixLen(_) => FilterAttrClass::SRC_PREFIX_LEN,
+            FilterAttr::DstAddr(_) => FilterAttrClass::DST_ADDR,
+            FilterAttr::DstPrefixLen(_) => FilterAttrClass::DST_PREFIX_LEN,
+            FilterAttr::SrcPortMin(_) => FilterAttrClass::SRC_PORT_MIN,
+            FilterAttr::SrcPortMax(_) => FilterAttrClass::SRC_PORT_MAX,
+            FilterAttr::DstPortMin(_) => FilterAttrClass::DST_PORT_MIN,
+            FilterAttr::DstPortMax(_) => FilterAttrClass::DST_PORT_MAX,
+            FilterAttr::IfName(_) => FilterAttrClass::IFNAME,
+            FilterAttr::Action(_) => FilterAttrClass::ACTION,
+            FilterAttr::Packets(_) => FilterAttrClass::PACKETS,
+            FilterAttr::Bytes(_) => FilterAttrClass::BYTES,
+        }
+    }
+}
+
+impl Attribute for FilterAttr {
+    fn type_(&self) -> u16 {
+        self.class() as u16
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        match self {
+            FilterAttr::Chain(chain) => chain.as_bytes(),
+            FilterAttr::Index(index) => index.as_bytes(),
+            FilterAttr::Proto(proto) => proto.as_bytes(),
+            FilterAttr::SrcAddr(addr) | FilterAttr::DstAddr(addr) => addr,
+            FilterAttr::SrcPrefixLen(prefix_len) | FilterAttr::DstPrefixLen(prefix_len) => {
+                prefix_len.as_bytes()
+            }
+            FilterAttr::SrcPortMin(port)
+            | FilterAttr::SrcPortMax(port)
+            | FilterAttr::DstPortMin(port)
+            | FilterAttr::DstPortMax(port) => port.as_bytes(),
+            FilterAttr::IfName(name) => name.as_bytes_with_nul(),
+            FilterAttr::Action(action) => action.as_bytes(),
+            FilterAttr::Packets(packets) => packets.as_bytes(),
+            FilterAttr::Bytes(bytes) => bytes.as_bytes(),
+        }
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        let payload_len = header.payload_len();
+
+        let Ok(class) = FilterAttrClass::try_from(header.type_()) else {
+            reader.skip_some(payload_len);
+            return Ok(ContinueRead::Skipped);
+        };
+
+        let res = match (class, payload_len) {
+            (FilterAttrClass::CHAIN, 4) => Self::Chain(reader.read_val_opt::<u32>()?.unwrap()),
+            (FilterAttrClass::INDEX, 4) => Self::Index(reader.read_val_opt::<u32>()?.unwrap()),
+            (FilterAttrClass::PROTO, 1) => Self::Proto(reader.read_val_opt::<u8>()?.unwrap()),
+            (FilterAttrClass::SRC_ADDR, 4) => {
+                Self::SrcAddr(reader.read_val_opt::<[u8; 4]>()?.unwrap())
+            }
+            (FilterAttrClass::SRC_PREFIX_LEN, 1) => {
+                Self::SrcPrefixLen(reader.read_val_opt::<u8>()?.unwrap())
+            }
+            (FilterAttrClass::DST_ADDR, 4) => {
+                Self::DstAddr(reader.read_val_opt::<[u8; 4]>()?.unwrap())
+            }
+            (FilterAttrClass::DST_PREFIX_LEN, 1) => {
+                Self::DstPrefixLen(reader.read_val_opt::<u8>()?.unwrap())
+            }
+            (FilterAttrClass::SRC_PORT_MIN, 2) => {
+                Self::SrcPortMin(reader.read_val_opt::<u16>()?.unwrap())
+            }
+            (FilterAttrClass::SRC_PORT_MAX, 2) => {
+                Self::SrcPortMax(reader.read_val_opt::<u16>()?.unwrap())
+            }
+            (FilterAttrClass::DST_PORT_MIN, 2) => {
+                Self::DstPortMin(reader.read_val_opt::<u16>()?.unwrap())
+            }
+            (FilterAttrClass::DST_PORT_MAX, 2) => {
+                Self::DstPortMax(reader.read_val_opt::<u16>()?.unwrap())
+            }
+            (FilterAttrClass::IFNAME, 1..=IFNAME_SIZE) => {
+                let (name, name_len) = reader.read_cstring_until_end(payload_len)?;
+                if name_len != payload_len {
+                    reader.skip_some(payload_len - name_len);
+                }
+                Self::IfName(name)
+            }
+            (FilterAttrClass::ACTION, 4) => Self::Action(reader.read_val_opt::<u32>()?.unwrap()),
+
+            // The counters are read-only.
+            (FilterAttrClass::UNSPEC | FilterAttrClass::PACKETS | FilterAttrClass::BYTES, _) => {
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::Skipped);
+            }
+
+            (_, _) => {
+                warn!(
+                    "packet filter attribute `{:?}` contains invalid payload",
+                    class
+                );
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::skipped_with_error(
+                    Errno::EINVAL,
+                    "the packet filter attribute is invalid",
+                ));
+            }
+        };
+
+        Ok(ContinueRead::Parsed(res))
+    }
+}
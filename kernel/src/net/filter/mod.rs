@@ -0,0 +1,459 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The generic netlink family to configure packet filtering.
+//!
+//! Each network namespace has a packet filter table (see [`NetNamespace::filter_table`]), which
+//! is shared by all ifaces in the namespace. User space manages the rules in the table via the
+//! generic netlink family named [`FILTER_FAMILY_NAME`].
+//!
+//! Each rule is described by the attributes in [`FilterAttrClass`], and the meaning of the values
+//! is as follows:
+//! - `CHAIN` (u32): 0 for the input chain, 1 for the forward chain, and 2 for the output chain.
+//! - `INDEX` (u32): The zero-based position of the rule in the chain.
+//! - `PROTO` (u8): The IP protocol number.
+//! - `SRC_ADDR` and `DST_ADDR` (4 bytes in network byte order), with `SRC_PREFIX_LEN` and
+//!   `DST_PREFIX_LEN` (u8, 32 if absent): The source and destination subnets.
+//! - `SRC_PORT_MIN`, `SRC_PORT_MAX`, `DST_PORT_MIN` and `DST_PORT_MAX` (u16 in host byte order):
+//!   The inclusive port ranges. If the maximum port is absent, it equals the minimum port.
+//! - `IFNAME` (string): The name of the iface.
+//! - `ACTION` (u32): 0 to accept, 1 to drop, and 2 to reject the matching packets.
+//! - `PACKETS` and `BYTES` (u64): The counters of the rule, which are read-only.
+//!
+//! This is not compatible with Linux, whose netfilter is configured via the `NETLINK_NETFILTER`
+//! protocol with a much more complex interface.
+
+use core::ops::RangeInclusive;
+
+use aster_bigtcp::{
+    iface::{FilterAction, FilterChain, FilterCounters, FilterRule},
+    wire::{IpProtocol, Ipv4Address, Ipv4Cidr},
+};
+use attr::{FilterAttr, FilterAttrClass};
+use spin::Once;
+
+use crate::{
+    net::{
+        socket::netlink::generic::{
+            register_family, GenlFamily, GenlFamilyHandle, GenlOp, GenlOpFlags, GenlReply,
+            GenlRequest,
+        },
+        NetNamespace,
+    },
+    prelude::*,
+};
+
+mod attr;
+
+/// The name of the generic netlink family.
+const FILTER_FAMILY_NAME: &str = "pktfilter";
+
+/// Packet filter commands.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+enum FilterCmd {
+    UNSPEC = 0,
+    /// Appends a rule to a chain, or inserts it at `INDEX`.
+    NEWRULE = 1,
+    /// Removes the rule at `INDEX` in a chain.
+    DELRULE = 2,
+    /// Dumps the rules with their counters, optionally in the specified chain only.
+    GETRULE = 3,
+    /// Removes all rules, optionally in the specified chain only.
+    FLUSH = 4,
+    /// Resets the counters, optionally in the specified chain only.
+    ZERO = 5,
+}
+
+struct FilterFamily;
+
+impl GenlFamily for FilterFamily {
+    type Attr = FilterAttr;
+
+    fn name(&self) -> &'static str {
+        FILTER_FAMILY_NAME
+    }
+
+    fn max_attr(&self) -> u32 {
+        FilterAttrClass::BYTES as u32
+    }
+
+    fn ops(&self) -> &[GenlOp] {
+        // Like Linux's netfilter, both reading and modifying the rules require `CAP_NET_ADMIN`.
+        const DO: GenlOpFlags = GenlOpFlags::CMD_CAP_DO.union(GenlOpFlags::UNS_ADMIN_PERM);
+        const DUMP: GenlOpFlags = GenlOpFlags::CMD_CAP_DUMP.union(GenlOpFlags::UNS_ADMIN_PERM);
+        const OPS: &[GenlOp] = &[
+            GenlOp {
+                cmd: FilterCmd::NEWRULE as u8,
+                flags: DO,
+            },
+            GenlOp {
+                cmd: FilterCmd::DELRULE as u8,
+                flags: DO,
+            },
+            GenlOp {
+                cmd: FilterCmd::GETRULE as u8,
+                flags: DUMP,
+            },
+            GenlOp {
+                cmd: FilterCmd::FLUSH as u8,
+                flags: DO,
+            },
+            GenlOp {
+                cmd: FilterCmd::ZERO as u8,
+                flags: DO,
+            },
+        ];
+        OPS
+    }
+
+    fn mcast_groups(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn handle_request(
+        &self,
+        request: &GenlRequest<FilterAttr>,
+    ) -> Result<Vec<GenlReply<FilterAttr>>> {
+        let net_ns = request.net_ns();
+        let attrs = request.attrs();
+
+        match FilterCmd::try_from(request.cmd()) {
+            Ok(FilterCmd::NEWRULE) => do_new_rule(net_ns, attrs),
+            Ok(FilterCmd::DELRULE) => do_del_rule(net_ns, attrs),
+            Ok(FilterCmd::GETRULE) => do_get_rules(net_ns, attrs),
+            Ok(FilterCmd::FLUSH) => {
+                for chain in requested_chains(attrs)? {
+                    net_ns.filter_table().flush(chain);
+                }
+                Ok(Vec::new())
+            }
+            Ok(FilterCmd::ZERO) => {
+                for chain in requested_chains(attrs)? {
+                    net_ns.filter_table().zero_counters(chain);
+                }
+                Ok(Vec::new())
+            }
+            _ => return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "the packet filter command is not supported"
+            ),
+        }
+    }
+}
+
+fn do_new_rule(net_ns: &NetNamespace, attrs: &[FilterAttr]) -> Result<Vec<GenlReply<FilterAttr>>> {
+    let chain = required_chain(attrs)?;
+    let rule = parse_rule(attrs)?;
+
+    let index = attrs.iter().find_map(|attr| match attr {
+        FilterAttr::Index(index) => Some(*index as usize),
+        _ => None,
+    });
+
+    let table = net_ns.filter_table();
+    match index {
+        Some(index) => {
+            if !table.insert(chain, index, rule) {
+                return_errno_with_message!(Errno::ERANGE, "the rule index is out of range");
+            }
+        }
+        None => table.append(chain, rule),
+    }
+
+    Ok(Vec::new())
+}
+
+fn do_del_rule(net_ns: &NetNamespace, attrs: &[FilterAttr]) -> Result<Vec<GenlReply<FilterAttr>>> {
+    let chain = required_chain(attrs)?;
+
+    let Some(index) = attrs.iter().find_map(|attr| match attr {
+        FilterAttr::Index(index) => Some(*index as usize),
+        _ => None,
+    }) else {
+        return_errno_with_message!(Errno::EINVAL, "the rule index is required");
+    };
+
+    if net_ns.filter_table().remove(chain, index).is_none() {
+        return_errno_with_message!(Errno::ENOENT, "the rule does not exist");
+    }
+
+    Ok(Vec::new())
+}
+
+fn do_get_rules(net_ns: &NetNamespace, attrs: &[FilterAttr]) -> Result<Vec<GenlReply<FilterAttr>>> {
+    let mut replies = Vec::new();
+
+    for chain in requested_chains(attrs)? {
+        let rules = net_ns.filter_table().rules(chain);
+        replies.extend(
+            rules
+                .iter()
+                .enumerate()
+                .map(|(index, (rule, counters))| new_rule_reply(chain, index, rule, counters)),
+        );
+    }
+
+    Ok(replies)
+}
+
+/// Creates a reply that describes the rule.
+fn new_rule_reply(
+    chain: FilterChain,
+    index: usize,
+    rule: &FilterRule,
+    counters: &FilterCounters,
+) -> GenlReply<FilterAttr> {
+    let mut attrs = vec![
+        FilterAttr::Chain(chain_to_u32(chain)),
+        FilterAttr::Index(index as u32),
+    ];
+
+    if let Some(protocol) = rule.protocol {
+        attrs.push(FilterAttr::Proto(u8::from(protocol)));
+    }
+    if let Some(src) = rule.src {
+        attrs.push(FilterAttr::SrcAddr(src.address().octets()));
+        attrs.push(FilterAttr::SrcPrefixLen(src.prefix_len()));
+    }
+    if let Some(dst) = rule.dst {
+        attrs.push(FilterAttr::DstAddr(dst.address().octets()));
+        attrs.push(FilterAttr::DstPrefixLen(dst.prefix_len()));
+    }
+    if let Some(ports) = rule.src_ports.as_ref() {
+        attrs.push(FilterAttr::SrcPortMin(*ports.start()));
+        attrs.push(FilterAttr::SrcPortMax(*ports.end()));
+    }
+    if let Some(ports) = rule.dst_ports.as_ref() {
+        attrs.push(FilterAttr::DstPortMin(*ports.start()));
+        attrs.push(FilterAttr::DstPortMax(*ports.end()));
+    }
+    if let Some(iface) = rule.iface.as_ref() {
+        attrs.push(FilterAttr::IfName(CString::new(iface.as_str()).unwrap()));
+    }
+
+    attrs.push(FilterAttr::Action(action_to_u32(rule.action)));
+    attrs.push(FilterAttr::Packets(counters.packets));
+    attrs.push(FilterAttr::Bytes(counters.bytes));
+
+    GenlReply::new(FilterCmd::NEWRULE as u8, attrs)
+}
+
+/// Parses the rule from the attributes.
+fn parse_rule(attrs: &[FilterAttr]) -> Result<FilterRule> {
+    let mut protocol = None;
+    let mut src_addr = None;
+    let mut src_prefix_len = None;
+    let mut dst_addr = None;
+    let mut dst_prefix_len = None;
+    let mut src_port_min = None;
+    let mut src_port_max = None;
+    let mut dst_port_min = None;
+    let mut dst_port_max = None;
+    let mut iface = None;
+    let mut action = None;
+
+    for attr in attrs {
+        match attr {
+            FilterAttr::Proto(proto) => protocol = Some(IpProtocol::from(*proto)),
+            FilterAttr::SrcAddr(addr) => src_addr = Some(Ipv4Address::from(*addr)),
+            FilterAttr::SrcPrefixLen(prefix_len) => src_prefix_len = Some(*prefix_len),
+            FilterAttr::DstAddr(addr) => dst_addr = Some(Ipv4Address::from(*addr)),
+            FilterAttr::DstPrefixLen(prefix_len) => dst_prefix_len = Some(*prefix_len),
+            FilterAttr::SrcPortMin(port) => src_port_min = Some(*port),
+            FilterAttr::SrcPortMax(port) => src_port_max = Some(*port),
+            FilterAttr::DstPortMin(port) => dst_port_min = Some(*port),
+            FilterAttr::DstPortMax(port) => dst_port_max = Some(*port),
+            FilterAttr::IfName(name) => iface = Some(name),
+            FilterAttr::Action(value) => action = Some(action_from_u32(*value)?),
+            _ => (),
+        }
+    }
+
+    let Some(action) = action else {
+        return_errno_with_message!(Errno::EINVAL, "the rule action is required");
+    };
+
+    let iface = match iface {
+        Some(name) => match name.to_str() {
+            Ok(name) => Some(name.to_string()),
+            Err(_) => {
+                return_errno_with_message!(Errno::EINVAL, "the iface name is not valid UTF-8")
+            }
+        },
+        None => None,
+    };
+
+    Ok(FilterRule {
+        protocol,
+        src: parse_cidr(src_addr, src_prefix_len)?,
+        dst: parse_cidr(dst_addr, dst_prefix_len)?,
+        src_ports: parse_port_range(src_port_min, src_port_max)?,
+        dst_ports: parse_port_range(dst_port_min, dst_port_max)?,
+        iface,
+        action,
+    })
+}
+
+fn parse_cidr(addr: Option<Ipv4Address>, prefix_len: Option<u8>) -> Result<Option<Ipv4Cidr>> {
+    match (addr, prefix_len) {
+        (None, None) => Ok(None),
+        (None, Some(_)) => {
+            return_errno_with_message!(Errno::EINVAL, "the prefix length requires an address")
+        }
+        (Some(_), Some(33..)) => {
+            return_errno_with_message!(Errno::EINVAL, "the prefix length is too large")
+        }
+        (Some(addr), prefix_len) => Ok(Some(Ipv4Cidr::new(addr, prefix_len.unwrap_or(32)))),
+    }
+}
+
+fn parse_port_range(min: Option<u16>, max: Option<u16>) -> Result<Option<RangeInclusive<u16>>> {
+    match (min, max) {
+        (None, None) => Ok(None),
+        (None, Some(_)) => {
+            return_errno_with_message!(Errno::EINVAL, "the maximum port requires a minimum port")
+        }
+        (Some(min), max) => {
+            let max = max.unwrap_or(min);
+            if min > max {
+                return_errno_with_message!(Errno::EINVAL, "the port range is empty");
+            }
+            Ok(Some(min..=max))
+        }
+    }
+}
+
+/// Returns the chain specified in the attributes, which must be present.
+fn required_chain(attrs: &[FilterAttr]) -> Result<FilterChain> {
+    let Some(chain) = attrs.iter().find_map(|attr| match attr {
+        FilterAttr::Chain(chain) => Some(*chain),
+        _ => None,
+    }) else {
+        return_errno_with_message!(Errno::EINVAL, "the chain is required");
+    };
+
+    chain_from_u32(chain)
+}
+
+/// Returns the chain specified in the attributes, or all chains if none is specified.
+fn requested_chains(attrs: &[FilterAttr]) -> Result<Vec<FilterChain>> {
+    let chain = attrs.iter().find_map(|attr| match attr {
+        FilterAttr::Chain(chain) => Some(*chain),
+        _ => None,
+    });
+
+    match chain {
+        Some(chain) => Ok(vec![chain_from_u32(chain)?]),
+        None => Ok(vec![
+            FilterChain::Input,
+            FilterChain::Forward,
+            FilterChain::Output,
+        ]),
+    }
+}
+
+fn chain_from_u32(value: u32) -> Result<FilterChain> {
+    match value {
+        0 => Ok(FilterChain::Input),
+        1 => Ok(FilterChain::Forward),
+        2 => Ok(FilterChain::Output),
+        _ => return_errno_with_message!(Errno::EINVAL, "the chain is invalid"),
+    }
+}
+
+fn chain_to_u32(chain: FilterChain) -> u32 {
+    match chain {
+        FilterChain::Input => 0,
+        FilterChain::Forward => 1,
+        FilterChain::Output => 2,
+    }
+}
+
+fn action_from_u32(value: u32) -> Result<FilterAction> {
+    match value {
+        0 => Ok(FilterAction::Accept),
+        1 => Ok(FilterAction::Drop),
+        2 => Ok(FilterAction::Reject),
+        _ => return_errno_with_message!(Errno::EINVAL, "the action is invalid"),
+    }
+}
+
+fn action_to_u32(action: FilterAction) -> u32 {
+    match action {
+        FilterAction::Accept => 0,
+        FilterAction::Drop => 1,
+        FilterAction::Reject => 2,
+    }
+}
+
+static FILTER_FAMILY: Once<GenlFamilyHandle<FilterFamily>> = Once::new();
+
+pub(super) fn init() {
+    FILTER_FAMILY.call_once(|| register_family(Arc::new(FilterFamily)).unwrap());
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    #[ktest]
+    fn parse_rule_ranges() {
+        let rule = parse_rule(&[
+            FilterAttr::Proto(17),
+            FilterAttr::DstAddr([10, 0, 2, 0]),
+            FilterAttr::DstPrefixLen(24),
+            FilterAttr::DstPortMin(53),
+            FilterAttr::SrcPortMin(1024),
+            FilterAttr::SrcPortMax(65535),
+            FilterAttr::Action(1),
+        ])
+        .unwrap();
+        assert_eq!(rule.protocol, Some(IpProtocol::Udp));
+        assert_eq!(rule.src, None);
+        assert_eq!(
+            rule.dst,
+            Some(Ipv4Cidr::new(Ipv4Address::new(10, 0, 2, 0), 24))
+        );
+        assert_eq!(rule.src_ports, Some(1024..=65535));
+        assert_eq!(rule.dst_ports, Some(53..=53));
+        assert_eq!(rule.iface, None);
+        assert_eq!(rule.action, FilterAction::Drop);
+    }
+
+    #[ktest]
+    fn parse_rule_invalid() {
+        // The action is required.
+        assert!(parse_rule(&[FilterAttr::Proto(6)]).is_err());
+        assert!(parse_rule(&[FilterAttr::Action(3)]).is_err());
+
+        assert!(parse_rule(&[FilterAttr::DstPrefixLen(8), FilterAttr::Action(0)]).is_err());
+        assert!(parse_rule(&[
+            FilterAttr::SrcAddr([10, 0, 0, 0]),
+            FilterAttr::SrcPrefixLen(33),
+            FilterAttr::Action(0),
+        ])
+        .is_err());
+        assert!(parse_rule(&[FilterAttr::DstPortMax(80), FilterAttr::Action(0)]).is_err());
+        assert!(parse_rule(&[
+            FilterAttr::DstPortMin(81),
+            FilterAttr::DstPortMax(80),
+            FilterAttr::Action(0),
+        ])
+        .is_err());
+    }
+
+    #[ktest]
+    fn chains() {
+        assert_eq!(requested_chains(&[]).unwrap().len(), 3);
+        assert_eq!(
+            requested_chains(&[FilterAttr::Chain(2)]).unwrap(),
+            vec![FilterChain::Output]
+        );
+        assert!(requested_chains(&[FilterAttr::Chain(3)]).is_err());
+        assert!(required_chain(&[]).is_err());
+    }
+}
@@ -1,7 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use super::sched::PollScheduler;
-use crate::net::socket::ip::{DatagramObserver, StreamObserver};
+use crate::net::socket::{
+    ip::{DatagramObserver, StreamObserver},
+    util::SocketFilter,
+};
 
 pub struct BigtcpExt;
 
@@ -10,4 +13,9 @@ impl aster_bigtcp::ext::Ext for BigtcpExt {
 
     type TcpEventObserver = StreamObserver;
     type UdpEventObserver = DatagramObserver;
+    type IcmpEventObserver = DatagramObserver;
+    type RawEventObserver = DatagramObserver;
+    type PacketEventObserver = DatagramObserver;
+
+    type PacketFilter = SocketFilter;
 }
@@ -1,68 +1,70 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use alloc::{borrow::ToOwned, sync::Arc};
-use core::slice::Iter;
 
 use aster_bigtcp::{
     device::WithDevice,
     iface::{InterfaceFlags, InterfaceType},
 };
+use aster_network::AnyNetworkDevice;
 use aster_softirq::BottomHalfDisabled;
-use spin::Once;
 
-use super::{poll::poll_ifaces, Iface};
-use crate::{net::iface::sched::PollScheduler, prelude::*};
-
-static IFACES: Once<Vec<Arc<Iface>>> = Once::new();
-
-pub fn loopback_iface() -> &'static Arc<Iface> {
-    &IFACES.get().unwrap()[0]
-}
+use super::{ipconfig::IpConfig, poll::poll_ifaces, Iface};
+use crate::{
+    net::{iface::sched::PollScheduler, NetNamespace},
+    prelude::*,
+};
 
-pub fn virtio_iface() -> Option<&'static Arc<Iface>> {
-    IFACES.get().unwrap().get(1)
-}
+pub fn init() {
+    // Initialize the ifaces of the initial network namespace.
+    NetNamespace::get_init_singleton();
 
-pub fn iter_all_ifaces() -> Iter<'static, Arc<Iface>> {
-    IFACES.get().unwrap().iter()
+    poll_ifaces();
 }
 
-// TODO: Support multiple network devices and avoid the hardcoded device name.
-const VIRTIO_DEVICE_NAME: &str = aster_virtio::device::network::DEVICE_NAME;
-
-pub fn init() {
-    IFACES.call_once(|| {
-        let mut ifaces = Vec::with_capacity(2);
+/// Creates the ifaces of the initial network namespace.
+pub(in crate::net) fn new_init_ifaces() -> Vec<Arc<Iface>> {
+    let mut ifaces = Vec::new();
 
-        // Initialize loopback before virtio
-        // to ensure the loopback interface index is ahead of virtio.
-        ifaces.push(new_loopback());
+    // The loopback iface should be the first iface.
+    ifaces.push(new_loopback(LOOPBACK_IFACE_INDEX, true));
 
-        if let Some(iface_virtio) = new_virtio() {
-            ifaces.push(iface_virtio);
-        }
+    for (index, (device_name, device)) in aster_network::all_devices().into_iter().enumerate() {
+        let iface = new_ether(index, device);
 
-        ifaces
-    });
+        let recv_iface = iface.clone();
+        aster_network::register_recv_callback(&device_name, move || recv_iface.poll());
+        let send_iface = iface.clone();
+        aster_network::register_send_callback(&device_name, move || send_iface.poll());
 
-    if let Some(iface_virtio) = virtio_iface() {
-        let callback = || iface_virtio.poll();
-        aster_network::register_recv_callback(VIRTIO_DEVICE_NAME, callback);
-        aster_network::register_send_callback(VIRTIO_DEVICE_NAME, callback);
+        ifaces.push(iface);
     }
 
-    poll_ifaces();
+    // The Ethernet ifaces have no address until they are configured by the `ip=` kernel parameter
+    // or via netlink.
+    IpConfig::from_kcmdline().apply(&ifaces);
+
+    ifaces
 }
 
-fn new_loopback() -> Arc<Iface> {
+/// The index of the loopback iface, which is the first iface in each network namespace.
+pub(in crate::net) const LOOPBACK_IFACE_INDEX: u32 = 1;
+
+/// Creates a loopback iface.
+///
+/// If `is_up` is false, the iface will be down until it is brought up via netlink, as the
+/// loopback iface in a new network namespace is.
+pub(in crate::net) fn new_loopback(index: u32, is_up: bool) -> Arc<Iface> {
     use aster_bigtcp::{
         device::{Loopback, Medium},
         iface::IpIface,
-        wire::{Ipv4Address, Ipv4Cidr},
+        wire::{Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr},
     };
 
     const LOOPBACK_ADDRESS: Ipv4Address = Ipv4Address::new(127, 0, 0, 1);
     const LOOPBACK_ADDRESS_PREFIX_LEN: u8 = 8; // mask: 255.0.0.0
+    const LOOPBACK_IPV6_ADDRESS: Ipv6Address = Ipv6Address::LOCALHOST;
+    const LOOPBACK_IPV6_ADDRESS_PREFIX_LEN: u8 = 128;
 
     struct Wrapper(Mutex<Loopback>);
 
@@ -78,16 +80,23 @@ fn new_loopback() -> Arc<Iface> {
         }
     }
 
-    // FIXME: These flags are currently hardcoded.
-    // In the future, we should set appropriate values.
-    let flags = InterfaceFlags::UP
-        | InterfaceFlags::LOOPBACK
-        | InterfaceFlags::RUNNING
-        | InterfaceFlags::LOWER_UP;
+    let flags = if is_up {
+        InterfaceFlags::UP
+            | InterfaceFlags::LOOPBACK
+            | InterfaceFlags::RUNNING
+            | InterfaceFlags::LOWER_UP
+    } else {
+        InterfaceFlags::LOOPBACK
+    };
 
     IpIface::new(
         Wrapper(Mutex::new(Loopback::new(Medium::Ip))),
         Ipv4Cidr::new(LOOPBACK_ADDRESS, LOOPBACK_ADDRESS_PREFIX_LEN),
+        Some(Ipv6Cidr::new(
+            LOOPBACK_IPV6_ADDRESS,
+            LOOPBACK_IPV6_ADDRESS_PREFIX_LEN,
+        )),
+        index,
         "lo".to_owned(),
         PollScheduler::new(),
         InterfaceType::LOOPBACK,
@@ -95,20 +104,13 @@ fn new_loopback() -> Arc<Iface> {
     ) as Arc<Iface>
 }
 
-fn new_virtio() -> Option<Arc<Iface>> {
-    use aster_bigtcp::{
-        iface::EtherIface,
-        wire::{EthernetAddress, Ipv4Address, Ipv4Cidr},
-    };
-    use aster_network::AnyNetworkDevice;
-
-    const VIRTIO_ADDRESS: Ipv4Address = Ipv4Address::new(10, 0, 2, 15);
-    const VIRTIO_ADDRESS_PREFIX_LEN: u8 = 24; // mask: 255.255.255.0
-    const VIRTIO_GATEWAY: Ipv4Address = Ipv4Address::new(10, 0, 2, 2);
-
-    let virtio_net = aster_network::get_device(VIRTIO_DEVICE_NAME)?;
+fn new_ether(
+    index: usize,
+    device: Arc<SpinLock<dyn AnyNetworkDevice, BottomHalfDisabled>>,
+) -> Arc<Iface> {
+    use aster_bigtcp::{iface::EtherIface, wire::EthernetAddress};
 
-    let ether_addr = virtio_net.lock().mac_addr().0;
+    let ether_addr = device.lock().mac_addr().0;
 
     struct Wrapper(Arc<SpinLock<dyn AnyNetworkDevice, BottomHalfDisabled>>);
 
@@ -132,13 +134,15 @@ fn new_virtio() -> Option<Arc<Iface>> {
         | InterfaceFlags::MULTICAST
         | InterfaceFlags::LOWER_UP;
 
-    Some(EtherIface::new(
-        Wrapper(virtio_net),
+    EtherIface::new(
+        Wrapper(device),
         EthernetAddress(ether_addr),
-        Ipv4Cidr::new(VIRTIO_ADDRESS, VIRTIO_ADDRESS_PREFIX_LEN),
-        VIRTIO_GATEWAY,
-        "eth0".to_owned(),
+        None,
+        None,
+        // The Ethernet ifaces are placed after the loopback iface.
+        LOOPBACK_IFACE_INDEX + 1 + index as u32,
+        format!("eth{}", index),
         PollScheduler::new(),
         flags,
-    ))
+    )
 }
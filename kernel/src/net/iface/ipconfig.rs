@@ -0,0 +1,353 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Network configuration at boot time via the `ip=` kernel parameter.
+//!
+//! The format of the parameter is the same as in Linux:
+//!
+//! ```text
+//! ip=<client-ip>:<server-ip>:<gw-ip>:<netmask>:<hostname>:<device>:<autoconf>:<dns0-ip>:<dns1-ip>:<ntp0-ip>
+//! ```
+//!
+//! The parameter can also be `<autoconf>` alone (e.g., `ip=dhcp`).
+//!
+//! Reference: <https://www.kernel.org/doc/html/v6.4/admin-guide/nfs/nfsroot.html>.
+
+use aster_bigtcp::{
+    iface::{InterfaceType, Ipv4Route},
+    wire::{Ipv4Address, Ipv4Cidr},
+};
+use ostd::boot::boot_info;
+use spin::Once;
+
+use super::Iface;
+use crate::{kcmdline::KCmdlineArg, prelude::*};
+
+/// The network configuration specified by the `ip=` kernel parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct IpConfig {
+    /// The name of the iface to configure.
+    ///
+    /// If this is `None`, the first Ethernet iface will be configured.
+    device: Option<String>,
+    method: ConfigMethod,
+    /// The address of the server (i.e., `<server-ip>`).
+    server: Option<Ipv4Address>,
+    /// The DNS servers (i.e., `<dns0-ip>` and `<dns1-ip>`).
+    dns_servers: Vec<Ipv4Address>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigMethod {
+    /// The iface is not configured.
+    Off,
+    /// The iface is configured with a static address and an optional default gateway.
+    Static {
+        address: Ipv4Cidr,
+        gateway: Option<Ipv4Address>,
+    },
+    /// The iface is configured via DHCP.
+    Dhcp,
+}
+
+impl Default for IpConfig {
+    /// Returns the configuration that is used if the `ip=` parameter is absent.
+    ///
+    /// The first Ethernet iface is configured as in the QEMU user networking.
+    fn default() -> Self {
+        const ETH0_ADDRESS: Ipv4Address = Ipv4Address::new(10, 0, 2, 15);
+        const ETH0_ADDRESS_PREFIX_LEN: u8 = 24; // mask: 255.255.255.0
+        const ETH0_GATEWAY: Ipv4Address = Ipv4Address::new(10, 0, 2, 2);
+
+        Self {
+            device: None,
+            method: ConfigMethod::Static {
+                address: Ipv4Cidr::new(ETH0_ADDRESS, ETH0_ADDRESS_PREFIX_LEN),
+                gateway: Some(ETH0_GATEWAY),
+            },
+            server: None,
+            dns_servers: Vec::new(),
+        }
+    }
+}
+
+impl IpConfig {
+    /// Gets the configuration from the kernel command line.
+    ///
+    /// If the `ip=` parameter is absent or invalid, the default configuration will be used.
+    pub(super) fn from_kcmdline() -> Self {
+        let karg = KCmdlineArg::from(boot_info().kernel_cmdline.as_str());
+        let Some(param) = karg.get_ip_config() else {
+            return Self::default();
+        };
+
+        match Self::parse(param) {
+            Ok(config) => config,
+            Err(err) => {
+                warn!("ignoring the invalid `ip={}` parameter: {:?}", param, err);
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(param: &str) -> Result<Self> {
+        // The parameter is `<autoconf>` alone unless it is the client address.
+        if !param.contains(':') && param.parse::<Ipv4Address>().is_err() {
+            return Ok(Self {
+                device: None,
+                method: parse_autoconf(param)?,
+                server: None,
+                dns_servers: Vec::new(),
+            });
+        }
+
+        let fields: Vec<&str> = param.split(':').collect();
+        let field = |index: usize| fields.get(index).copied().unwrap_or("");
+
+        let client = parse_addr(field(0))?;
+        let server = parse_addr(field(1))?;
+        let gateway = parse_addr(field(2))?;
+        let netmask = parse_addr(field(3))?;
+        // TODO: Support setting the hostname (i.e., `<hostname>`).
+        let device = Some(field(5))
+            .filter(|device| !device.is_empty())
+            .map(String::from);
+        let autoconf = parse_autoconf(field(6))?;
+        let dns_servers = [parse_addr(field(7))?, parse_addr(field(8))?]
+            .into_iter()
+            .flatten()
+            .collect();
+        // TODO: Support NTP servers (i.e., `<ntp0-ip>`).
+
+        // Like Linux, the static configuration is used if the client address is specified.
+        let method = if let Some(client) = client {
+            let prefix_len = match netmask {
+                Some(netmask) => match Ipv4Cidr::from_netmask(client, netmask) {
+                    Ok(cidr) => cidr.prefix_len(),
+                    Err(_) => {
+                        return_errno_with_message!(Errno::EINVAL, "the netmask is invalid")
+                    }
+                },
+                None => classful_prefix_len(client)?,
+            };
+            ConfigMethod::Static {
+                address: Ipv4Cidr::new(client, prefix_len),
+                gateway,
+            }
+        } else {
+            autoconf
+        };
+
+        Ok(Self {
+            device,
+            method,
+            server,
+            dns_servers,
+        })
+    }
+
+    /// Applies the configuration to the matching iface in `ifaces`.
+    pub(super) fn apply(self, ifaces: &[Arc<Iface>]) {
+        let iface = ifaces.iter().find(|iface| match self.device.as_ref() {
+            Some(device) => iface.name() == device,
+            None => iface.type_() == InterfaceType::ETHER,
+        });
+
+        match (iface, self.method) {
+            (_, ConfigMethod::Off) => (),
+            (None, _) => warn!("no iface can be configured by the `ip=` parameter"),
+            (Some(iface), ConfigMethod::Static { address, gateway }) => {
+                iface.set_ipv4_cidr(Some(address));
+                if let Some(gateway) = gateway {
+                    iface.add_ipv4_route(Ipv4Route {
+                        dst: Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0),
+                        gateway: Some(gateway),
+                        metric: 0,
+                    });
+                }
+            }
+            (Some(iface), ConfigMethod::Dhcp) => {
+                // The DHCP messages will be sent when all the ifaces are polled for the first
+                // time.
+                let _ = iface.start_dhcp();
+            }
+        }
+
+        let iface = iface.cloned();
+        BOOT_CONFIG.call_once(|| BootConfig {
+            iface,
+            ip_config: self,
+        });
+    }
+}
+
+/// Parses `<autoconf>`.
+fn parse_autoconf(autoconf: &str) -> Result<ConfigMethod> {
+    match autoconf {
+        "off" | "none" => Ok(ConfigMethod::Off),
+        // Like Linux, the autoconfiguration is enabled if the field is empty.
+        "" | "on" | "any" | "dhcp" => Ok(ConfigMethod::Dhcp),
+        // TODO: Support BOOTP and RARP.
+        "bootp" | "rarp" | "both" => {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "BOOTP and RARP are not supported")
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the autoconfiguration method is invalid"),
+    }
+}
+
+/// Parses an optional IPv4 address in the dotted-decimal notation.
+fn parse_addr(addr: &str) -> Result<Option<Ipv4Address>> {
+    if addr.is_empty() {
+        return Ok(None);
+    }
+
+    match addr.parse() {
+        Ok(addr) => Ok(Some(addr)),
+        Err(_) => return_errno_with_message!(Errno::EINVAL, "the IPv4 address is invalid"),
+    }
+}
+
+/// Returns the prefix length of the classful network that `addr` belongs to.
+///
+/// This is used when the netmask is not specified, as Linux does.
+fn classful_prefix_len(addr: Ipv4Address) -> Result<u8> {
+    match addr.octets()[0] {
+        0..=127 => Ok(8),
+        128..=191 => Ok(16),
+        192..=223 => Ok(24),
+        _ => return_errno_with_message!(Errno::EINVAL, "the client address is not unicast"),
+    }
+}
+
+/// The network configuration applied at boot time.
+struct BootConfig {
+    iface: Option<Arc<Iface>>,
+    ip_config: IpConfig,
+}
+
+static BOOT_CONFIG: Once<BootConfig> = Once::new();
+
+/// The information about the network configuration obtained at boot time.
+///
+/// This is what Linux reports in `/proc/net/pnp`.
+pub struct BootNetInfo {
+    /// Whether the configuration is obtained via DHCP.
+    pub is_dhcp: bool,
+    /// The DNS servers.
+    pub dns_servers: Vec<Ipv4Address>,
+    /// The address of the server.
+    ///
+    /// If the configuration is obtained via DHCP, this is the address of the DHCP server.
+    pub server: Option<Ipv4Address>,
+}
+
+/// Returns the information about the network configuration obtained at boot time.
+pub fn boot_net_info() -> BootNetInfo {
+    let Some(boot_config) = BOOT_CONFIG.get() else {
+        return BootNetInfo {
+            is_dhcp: false,
+            dns_servers: Vec::new(),
+            server: None,
+        };
+    };
+
+    let ip_config = &boot_config.ip_config;
+    if ip_config.method != ConfigMethod::Dhcp {
+        return BootNetInfo {
+            is_dhcp: false,
+            dns_servers: ip_config.dns_servers.clone(),
+            server: ip_config.server,
+        };
+    }
+
+    let lease = boot_config
+        .iface
+        .as_ref()
+        .and_then(|iface| iface.dhcp_lease());
+    // Like Linux, the DNS servers specified in the parameter take precedence.
+    let dns_servers = if !ip_config.dns_servers.is_empty() {
+        ip_config.dns_servers.clone()
+    } else {
+        lease
+            .as_ref()
+            .map(|lease| lease.dns_servers.clone())
+            .unwrap_or_default()
+    };
+
+    BootNetInfo {
+        is_dhcp: true,
+        dns_servers,
+        server: lease.map(|lease| lease.server),
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use ostd::prelude::*;
+
+    use super::*;
+
+    fn addr(a: u8, b: u8, c: u8, d: u8) -> Ipv4Address {
+        Ipv4Address::new(a, b, c, d)
+    }
+
+    #[ktest]
+    fn parse_autoconf_only() {
+        let config = IpConfig::parse("dhcp").unwrap();
+        assert_eq!(config.method, ConfigMethod::Dhcp);
+        assert_eq!(config.device, None);
+
+        let config = IpConfig::parse("off").unwrap();
+        assert_eq!(config.method, ConfigMethod::Off);
+
+        assert!(IpConfig::parse("bootp").is_err());
+        assert!(IpConfig::parse("foo").is_err());
+    }
+
+    #[ktest]
+    fn parse_static() {
+        let config =
+            IpConfig::parse("10.0.2.15:10.0.2.2:10.0.2.2:255.255.255.0::eth0:off:10.0.2.3")
+                .unwrap();
+        assert_eq!(config.device.as_deref(), Some("eth0"));
+        assert_eq!(
+            config.method,
+            ConfigMethod::Static {
+                address: Ipv4Cidr::new(addr(10, 0, 2, 15), 24),
+                gateway: Some(addr(10, 0, 2, 2)),
+            }
+        );
+        assert_eq!(config.server, Some(addr(10, 0, 2, 2)));
+        assert_eq!(config.dns_servers, vec![addr(10, 0, 2, 3)]);
+    }
+
+    #[ktest]
+    fn parse_static_classful() {
+        let config = IpConfig::parse("172.16.0.1").unwrap();
+        assert_eq!(
+            config.method,
+            ConfigMethod::Static {
+                address: Ipv4Cidr::new(addr(172, 16, 0, 1), 16),
+                gateway: None,
+            }
+        );
+
+        assert!(IpConfig::parse("224.0.0.1::").is_err());
+    }
+
+    #[ktest]
+    fn parse_without_client() {
+        let config = IpConfig::parse(":::::eth1:").unwrap();
+        assert_eq!(config.device.as_deref(), Some("eth1"));
+        assert_eq!(config.method, ConfigMethod::Dhcp);
+
+        let config = IpConfig::parse(":::::eth1:none").unwrap();
+        assert_eq!(config.method, ConfigMethod::Off);
+    }
+
+    #[ktest]
+    fn parse_invalid() {
+        assert!(IpConfig::parse("10.0.2.256").is_err());
+        assert!(IpConfig::parse("10.0.2.15:::255.0.255.0").is_err());
+        assert!(IpConfig::parse("10.0.2.15::::::dhcp:dns").is_err());
+    }
+}
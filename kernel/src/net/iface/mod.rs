@@ -2,11 +2,18 @@
 
 mod ext;
 mod init;
+mod ipconfig;
 mod poll;
+mod route;
 mod sched;
+mod veth;
 
-pub use init::{init, iter_all_ifaces, loopback_iface, virtio_iface};
-pub(super) use poll::init_in_first_kthread;
+pub use init::init;
+pub(super) use init::{new_init_ifaces, new_loopback, LOOPBACK_IFACE_INDEX};
+pub use ipconfig::{boot_net_info, BootNetInfo};
+pub(super) use poll::{init_in_first_kthread, spawn_background_poll_thread};
+pub(super) use route::route_iface;
+pub(super) use veth::new_veth_pair;
 
 pub type Iface = dyn aster_bigtcp::iface::Iface<ext::BigtcpExt>;
 pub type BoundPort = aster_bigtcp::iface::BoundPort<ext::BigtcpExt>;
@@ -16,3 +23,6 @@ pub type RawTcpSocketExt = aster_bigtcp::socket::RawTcpSocketExt<ext::BigtcpExt>
 pub type TcpConnection = aster_bigtcp::socket::TcpConnection<ext::BigtcpExt>;
 pub type TcpListener = aster_bigtcp::socket::TcpListener<ext::BigtcpExt>;
 pub type UdpSocket = aster_bigtcp::socket::UdpSocket<ext::BigtcpExt>;
+pub type IcmpSocket = aster_bigtcp::socket::IcmpSocket<ext::BigtcpExt>;
+pub type RawIpSocket = aster_bigtcp::socket::RawIpSocket<ext::BigtcpExt>;
+pub type PacketSocket = aster_bigtcp::socket::PacketSocket<ext::BigtcpExt>;
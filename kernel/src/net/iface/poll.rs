@@ -6,30 +6,37 @@ use core::time::Duration;
 use log::trace;
 use ostd::timer::Jiffies;
 
-use super::{iter_all_ifaces, Iface};
+use super::Iface;
 use crate::{
+    net::NetNamespace,
     sched::{Nice, SchedPolicy},
     thread::kernel_thread::ThreadOptions,
     time::wait::WaitTimeout,
 };
 
 pub fn init_in_first_kthread() {
-    for iface in iter_all_ifaces() {
-        spawn_background_poll_thread(iface.clone());
+    // Clone the ifaces so that the spin lock is not held while spawning the threads.
+    let ifaces = NetNamespace::get_init_singleton().ifaces().clone();
+    for iface in ifaces {
+        spawn_background_poll_thread(iface);
     }
 }
 
 pub(super) fn poll_ifaces() {
-    for iface in iter_all_ifaces() {
+    let ifaces = NetNamespace::get_init_singleton().ifaces().clone();
+    for iface in ifaces.iter() {
         iface.poll();
     }
 }
 
-fn spawn_background_poll_thread(iface: Arc<Iface>) {
-    let task_fn = move || {
-        trace!("spawn background poll thread for {}", iface.name());
+pub(in crate::net) fn spawn_background_poll_thread(iface: Arc<Iface>) {
+    trace!("spawn background poll thread for {}", iface.name());
+
+    // The thread only holds a weak reference to the iface, so it exits once the iface is dropped.
+    let sched_poll = iface.sched_poll().state().clone();
+    let iface = Arc::downgrade(&iface);
 
-        let sched_poll = iface.sched_poll();
+    let task_fn = move || {
         let wait_queue = sched_poll.polling_wait_queue();
 
         loop {
@@ -39,6 +46,10 @@ fn spawn_background_poll_thread(iface: Arc<Iface>) {
                 wait_queue.wait_until(|| sched_poll.next_poll_at_ms())
             };
 
+            if sched_poll.is_iface_dropped() {
+                break;
+            }
+
             let now_as_ms = Jiffies::elapsed().as_duration().as_millis() as u64;
 
             // FIXME: Ideally, we should perform the `poll` just before `next_poll_at_ms`.
@@ -50,6 +61,10 @@ fn spawn_background_poll_thread(iface: Arc<Iface>) {
             // For a more in-depth discussion, please refer to the following link:
             // <https://github.com/asterinas/asterinas/pull/630#discussion_r1496817030>.
             if now_as_ms >= next_poll_at_ms {
+                let Some(iface) = iface.upgrade() else {
+                    break;
+                };
+                sched_poll.clear_poll_request();
                 iface.poll();
                 continue;
             }
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The routing table.
+//!
+//! The routing table decides which iface should be used to send packets to a destination. Each
+//! network namespace has its own routing table, which currently consists of the following routes:
+//!  - Local routes: A destination that is one of the local addresses is reached via the iface
+//!    that owns the address.
+//!  - Connected routes: A destination in the subnet of an iface is reached via that iface.
+//!  - Static routes: IPv4 routes (including the default routes) that are added to the ifaces,
+//!    either during initialization or via netlink.
+//!
+//! Local routes take precedence. Otherwise, the connected or static route with the longest prefix
+//! is chosen. If there are still multiple candidates, the one with the lowest metric is chosen,
+//! where connected routes have a metric of zero.
+//!
+//! Ifaces that are not up are never chosen.
+
+use aster_bigtcp::{
+    iface::InterfaceFlags,
+    wire::{IpAddress, Ipv4Cidr, Ipv6Cidr},
+};
+
+use super::Iface;
+use crate::prelude::*;
+
+/// Looks up the routing table of `ifaces` and returns the iface to reach `dst_addr`.
+///
+/// This method returns `None` if there is no route to `dst_addr`.
+pub(in crate::net) fn route_iface<'a>(
+    ifaces: &'a [Arc<Iface>],
+    dst_addr: &IpAddress,
+) -> Option<&'a Arc<Iface>> {
+    let up_ifaces = || {
+        ifaces
+            .iter()
+            .filter(|iface| iface.flags().contains(InterfaceFlags::UP))
+    };
+
+    if let Some(iface) = up_ifaces().find(|iface| is_local_addr(iface, dst_addr)) {
+        return Some(iface);
+    }
+
+    let connected_routes =
+        up_ifaces().filter_map(|iface| Some((iface, connected_prefix_len(iface, dst_addr)?, 0)));
+    let static_routes = up_ifaces().filter_map(|iface| {
+        // TODO: Support static routes for IPv6.
+        let IpAddress::Ipv4(ipv4_addr) = dst_addr else {
+            return None;
+        };
+        let route = iface.lookup_ipv4_route(ipv4_addr)?;
+        Some((iface, route.dst.prefix_len(), route.metric))
+    });
+
+    connected_routes
+        .chain(static_routes)
+        // `min_by_key` returns the first minimum element, so the first iface is preferred if
+        // there are multiple candidates with the same prefix length and the same metric.
+        .min_by_key(|(_, prefix_len, metric)| (u8::MAX - *prefix_len, *metric))
+        .map(|(iface, _, _)| iface)
+}
+
+fn is_local_addr(iface: &Iface, addr: &IpAddress) -> bool {
+    match addr {
+        IpAddress::Ipv4(ipv4_addr) => iface.ipv4_addr() == Some(*ipv4_addr),
+        IpAddress::Ipv6(ipv6_addr) => iface.ipv6_addr() == Some(*ipv6_addr),
+    }
+}
+
+/// Returns the prefix length of the iface's subnet if the subnet contains `addr`.
+fn connected_prefix_len(iface: &Iface, addr: &IpAddress) -> Option<u8> {
+    match addr {
+        IpAddress::Ipv4(ipv4_addr) => {
+            let cidr = Ipv4Cidr::new(iface.ipv4_addr()?, iface.prefix_len()?);
+            cidr.contains_addr(ipv4_addr).then_some(cidr.prefix_len())
+        }
+        IpAddress::Ipv6(ipv6_addr) => {
+            let cidr = Ipv6Cidr::new(iface.ipv6_addr()?, iface.ipv6_prefix_len()?);
+            cidr.contains_addr(ipv6_addr).then_some(cidr.prefix_len())
+        }
+    }
+}
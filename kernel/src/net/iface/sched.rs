@@ -1,27 +1,74 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::sync::atomic::{AtomicU64, Ordering};
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 use aster_bigtcp::iface::ScheduleNextPoll;
 use ostd::sync::WaitQueue;
 
 pub struct PollScheduler {
+    state: Arc<PollState>,
+}
+
+/// The polling state shared with the background polling thread.
+///
+/// The background polling thread holds the state instead of the iface, so that the iface can be
+/// dropped while the thread is sleeping.
+pub(super) struct PollState {
     /// The time when we should do the next poll.
     /// We store the total number of milliseconds since the system booted.
     next_poll_at_ms: AtomicU64,
+    /// Whether a poll is requested to be performed as soon as possible.
+    is_poll_requested: AtomicBool,
+    /// Whether the iface has been dropped.
+    is_iface_dropped: AtomicBool,
     /// The wait queue that the background polling thread will sleep on.
     polling_wait_queue: WaitQueue,
 }
 
 impl PollScheduler {
     pub(super) fn new() -> Self {
-        Self {
+        let state = PollState {
             next_poll_at_ms: AtomicU64::new(0),
+            is_poll_requested: AtomicBool::new(false),
+            is_iface_dropped: AtomicBool::new(false),
             polling_wait_queue: WaitQueue::new(),
+        };
+
+        Self {
+            state: Arc::new(state),
         }
     }
 
+    pub(super) fn state(&self) -> &Arc<PollState> {
+        &self.state
+    }
+
+    /// Requests the background polling thread to poll the iface as soon as possible.
+    ///
+    /// Unlike [`ScheduleNextPoll::schedule_next_poll`], the request will not be overwritten by
+    /// the poll that is in progress.
+    pub(super) fn request_poll(&self) {
+        self.state.is_poll_requested.store(true, Ordering::Release);
+        self.state.polling_wait_queue.wake_all();
+    }
+}
+
+impl Drop for PollScheduler {
+    fn drop(&mut self) {
+        self.state.is_iface_dropped.store(true, Ordering::Release);
+        self.state.polling_wait_queue.wake_all();
+    }
+}
+
+impl PollState {
     pub(super) fn next_poll_at_ms(&self) -> Option<u64> {
+        if self.is_poll_requested.load(Ordering::Acquire)
+            || self.is_iface_dropped.load(Ordering::Acquire)
+        {
+            return Some(0);
+        }
+
         let millis = self.next_poll_at_ms.load(Ordering::Relaxed);
         if millis == 0 {
             None
@@ -30,6 +77,17 @@ impl PollScheduler {
         }
     }
 
+    /// Clears the poll request.
+    ///
+    /// This method should be called just before polling the iface.
+    pub(super) fn clear_poll_request(&self) {
+        self.is_poll_requested.store(false, Ordering::Release);
+    }
+
+    pub(super) fn is_iface_dropped(&self) -> bool {
+        self.is_iface_dropped.load(Ordering::Acquire)
+    }
+
     pub(super) fn polling_wait_queue(&self) -> &WaitQueue {
         &self.polling_wait_queue
     }
@@ -37,16 +95,18 @@ impl PollScheduler {
 
 impl ScheduleNextPoll for PollScheduler {
     fn schedule_next_poll(&self, poll_at: Option<u64>) {
+        let state = &self.state;
+
         let Some(new_instant) = poll_at else {
-            self.next_poll_at_ms.store(0, Ordering::Relaxed);
+            state.next_poll_at_ms.store(0, Ordering::Relaxed);
             return;
         };
 
-        let old_instant = self.next_poll_at_ms.load(Ordering::Relaxed);
-        self.next_poll_at_ms.store(new_instant, Ordering::Relaxed);
+        let old_instant = state.next_poll_at_ms.load(Ordering::Relaxed);
+        state.next_poll_at_ms.store(new_instant, Ordering::Relaxed);
 
         if old_instant == 0 || new_instant < old_instant {
-            self.polling_wait_queue.wake_all();
+            state.polling_wait_queue.wake_all();
         }
     }
 }
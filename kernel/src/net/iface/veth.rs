@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Virtual Ethernet (veth) ifaces.
+//!
+//! Veth ifaces are always created in pairs. Frames transmitted on one iface of the pair are
+//! received on the other iface. The two ifaces can be placed in different network namespaces,
+//! which allows the namespaces to communicate with each other.
+
+use aster_bigtcp::{
+    device::{self, DeviceCapabilities, Medium, NotifyDevice, WithDevice},
+    iface::{EtherIface, InterfaceFlags},
+    time::Instant,
+    wire::EthernetAddress,
+};
+use aster_softirq::BottomHalfDisabled;
+use spin::Once;
+
+use super::{sched::PollScheduler, Iface};
+use crate::{prelude::*, util::random::getrandom};
+
+/// The MTU of veth ifaces, including the Ethernet header.
+const VETH_MTU: usize = 1514;
+
+/// The maximum number of frames that can be queued on one end of the pair.
+///
+/// Frames that are sent when the queue is full are dropped.
+const VETH_QUEUE_LEN: usize = 1000;
+
+/// Creates a pair of veth ifaces.
+///
+/// The ifaces are down until they are brought up via netlink.
+pub(in crate::net) fn new_veth_pair(
+    index: u32,
+    name: String,
+    peer_index: u32,
+    peer_name: String,
+) -> (Arc<Iface>, Arc<Iface>) {
+    let end = Arc::new(VethEnd::new());
+    let peer_end = Arc::new(VethEnd::new());
+
+    let iface = new_veth(index, name, end.clone(), peer_end.clone());
+    let peer_iface = new_veth(peer_index, peer_name, peer_end.clone(), end.clone());
+
+    end.iface.call_once(|| Arc::downgrade(&iface));
+    peer_end.iface.call_once(|| Arc::downgrade(&peer_iface));
+
+    (iface, peer_iface)
+}
+
+fn new_veth(index: u32, name: String, local: Arc<VethEnd>, peer: Arc<VethEnd>) -> Arc<Iface> {
+    struct Wrapper(Mutex<VethDevice>);
+
+    impl WithDevice for Wrapper {
+        type Device = VethDevice;
+
+        fn with<F, R>(&self, f: F) -> R
+        where
+            F: FnOnce(&mut Self::Device) -> R,
+        {
+            let mut device = self.0.lock();
+            f(&mut device)
+        }
+    }
+
+    let device = VethDevice {
+        local,
+        peer,
+        has_sent: false,
+    };
+
+    let flags = InterfaceFlags::BROADCAST | InterfaceFlags::MULTICAST;
+
+    EtherIface::new(
+        Wrapper(Mutex::new(device)),
+        random_ether_addr(),
+        None,
+        None,
+        index,
+        name,
+        PollScheduler::new(),
+        flags,
+    )
+}
+
+/// Generates a random, locally administered, unicast Ethernet address.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/linux/etherdevice.h#L230>.
+fn random_ether_addr() -> EthernetAddress {
+    let mut addr = [0u8; 6];
+    getrandom(&mut addr);
+
+    // Clear the multicast bit and set the locally administered bit.
+    addr[0] &= 0xfe;
+    addr[0] |= 0x02;
+
+    EthernetAddress(addr)
+}
+
+/// One end of a veth pair.
+struct VethEnd {
+    /// The frames that are sent from the peer and have not been received.
+    rx_queue: SpinLock<VecDeque<Vec<u8>>, BottomHalfDisabled>,
+    /// The iface at this end.
+    iface: Once<Weak<Iface>>,
+}
+
+impl VethEnd {
+    fn new() -> Self {
+        Self {
+            rx_queue: SpinLock::new(VecDeque::new()),
+            iface: Once::new(),
+        }
+    }
+}
+
+struct VethDevice {
+    local: Arc<VethEnd>,
+    peer: Arc<VethEnd>,
+    /// Whether frames have been sent to the peer since the last poll ended.
+    has_sent: bool,
+}
+
+impl device::Device for VethDevice {
+    type RxToken<'a> = RxToken;
+    type TxToken<'a> = TxToken<'a>;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let frame = self.local.rx_queue.lock().pop_front()?;
+        Some((RxToken(frame), TxToken(self)))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(TxToken(self))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+
+        caps.max_burst_size = None;
+        caps.medium = Medium::Ethernet;
+        caps.max_transmission_unit = VETH_MTU;
+
+        caps
+    }
+}
+
+impl NotifyDevice for VethDevice {
+    fn notify_poll_end(&mut self) {
+        if !core::mem::take(&mut self.has_sent) {
+            return;
+        }
+
+        // The peer iface should be polled to receive the frames that we have sent.
+        if let Some(peer_iface) = self.peer.iface.get().and_then(Weak::upgrade) {
+            peer_iface.sched_poll().request_poll();
+        }
+    }
+}
+
+struct RxToken(Vec<u8>);
+
+impl device::RxToken for RxToken {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        f(&self.0)
+    }
+}
+
+struct TxToken<'a>(&'a mut VethDevice);
+
+impl device::TxToken for TxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut frame = vec![0u8; len];
+        let res = f(&mut frame);
+
+        let mut peer_queue = self.0.peer.rx_queue.lock();
+        if peer_queue.len() < VETH_QUEUE_LEN {
+            peer_queue.push_back(frame);
+            self.0.has_sent = true;
+        }
+
+        res
+    }
+}
@@ -1,14 +1,18 @@
 // SPDX-License-Identifier: MPL-2.0
 
+mod filter;
 pub mod iface;
+mod net_ns;
 pub mod socket;
 mod uts_ns;
 
+pub use net_ns::NetNamespace;
 pub use uts_ns::UtsNamespace;
 
 pub fn init() {
     iface::init();
     socket::netlink::init();
+    filter::init();
     socket::vsock::init();
 }
 
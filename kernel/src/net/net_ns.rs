@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use aster_bigtcp::{iface::FilterTable, wire::IpAddress};
+use ostd::sync::{PreemptDisabled, RwLockReadGuard};
+use spin::Once;
+
+use super::{
+    iface::{self, Iface},
+    socket::ip::stream_options::CongestionControl,
+};
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::PosixThread, UserNamespace},
+};
+
+/// The network namespace.
+///
+/// Each network namespace has its own ifaces. Since the socket tables and the used ports are
+/// managed by ifaces, sockets in different network namespaces are isolated from each other.
+pub struct NetNamespace {
+    ifaces: RwLock<Vec<Arc<Iface>>>,
+    next_iface_index: AtomicU32,
+    owner: Arc<UserNamespace>,
+    /// The default congestion control algorithm of new TCP sockets.
+    tcp_congestion_control: RwLock<CongestionControl>,
+    /// The packet filtering rules, which are shared by all ifaces in the namespace.
+    filter_table: Arc<FilterTable>,
+}
+
+impl NetNamespace {
+    /// Returns a reference to the singleton initial network namespace.
+    pub fn get_init_singleton() -> &'static Arc<NetNamespace> {
+        static INIT: Once<Arc<NetNamespace>> = Once::new();
+
+        INIT.call_once(|| {
+            let owner = UserNamespace::get_init_singleton().clone();
+            Arc::new(Self::new(
+                iface::new_init_ifaces(),
+                owner,
+                CongestionControl::Cubic,
+            ))
+        })
+    }
+
+    /// Creates a new network namespace.
+    ///
+    /// Unlike other namespaces, almost nothing is copied from `self`. The new network namespace
+    /// contains only a loopback iface, which is down until it is brought up via netlink. Like
+    /// Linux, the default TCP congestion control algorithm is inherited from the initial network
+    /// namespace.
+    pub fn new_clone(
+        &self,
+        owner: Arc<UserNamespace>,
+        posix_thread: &PosixThread,
+    ) -> Result<Arc<Self>> {
+        owner.check_cap(CapSet::SYS_ADMIN, posix_thread)?;
+
+        let loopback = iface::new_loopback(iface::LOOPBACK_IFACE_INDEX, false);
+        iface::spawn_background_poll_thread(loopback.clone());
+
+        let tcp_congestion_control = Self::get_init_singleton().tcp_congestion_control();
+
+        Ok(Arc::new(Self::new(
+            vec![loopback],
+            owner,
+            tcp_congestion_control,
+        )))
+    }
+
+    fn new(
+        ifaces: Vec<Arc<Iface>>,
+        owner: Arc<UserNamespace>,
+        tcp_congestion_control: CongestionControl,
+    ) -> Self {
+        let max_iface_index = ifaces.iter().map(|iface| iface.index()).max();
+
+        let filter_table = Arc::new(FilterTable::new());
+        for iface in ifaces.iter() {
+            iface.set_filter_table(Some(filter_table.clone()));
+        }
+
+        Self {
+            ifaces: RwLock::new(ifaces),
+            next_iface_index: AtomicU32::new(max_iface_index.unwrap_or(0) + 1),
+            owner,
+            tcp_congestion_control: RwLock::new(tcp_congestion_control),
+            filter_table,
+        }
+    }
+
+    /// Returns the owner user namespace of the namespace.
+    pub fn owner_ns(&self) -> &Arc<UserNamespace> {
+        &self.owner
+    }
+
+    /// Returns the default congestion control algorithm of new TCP sockets.
+    pub fn tcp_congestion_control(&self) -> CongestionControl {
+        *self.tcp_congestion_control.read()
+    }
+
+    /// Sets the default congestion control algorithm of new TCP sockets.
+    pub fn set_tcp_congestion_control(&self, congestion: CongestionControl) {
+        *self.tcp_congestion_control.write() = congestion;
+    }
+
+    /// Returns the packet filtering rules of the namespace.
+    pub fn filter_table(&self) -> &Arc<FilterTable> {
+        &self.filter_table
+    }
+
+    /// Returns the ifaces in the namespace.
+    ///
+    /// The loopback iface is always the first iface.
+    pub fn ifaces(&self) -> RwLockReadGuard<'_, Vec<Arc<Iface>>, PreemptDisabled> {
+        self.ifaces.read()
+    }
+
+    /// Returns the iface with the specified index.
+    pub fn get_iface_by_index(&self, index: u32) -> Option<Arc<Iface>> {
+        self.ifaces
+            .read()
+            .iter()
+            .find(|iface| iface.index() == index)
+            .cloned()
+    }
+
+    /// Returns the iface with the specified name.
+    pub fn get_iface_by_name(&self, name: &str) -> Option<Arc<Iface>> {
+        self.ifaces
+            .read()
+            .iter()
+            .find(|iface| iface.name() == name)
+            .cloned()
+    }
+
+    /// Looks up the routing table and returns the iface to reach `dst_addr`.
+    ///
+    /// This method returns `None` if there is no route to `dst_addr`.
+    pub fn route_iface(&self, dst_addr: &IpAddress) -> Option<Arc<Iface>> {
+        iface::route_iface(&self.ifaces.read(), dst_addr).cloned()
+    }
+
+    /// Creates a pair of veth ifaces, one in `self` and the other in `peer_ns`.
+    ///
+    /// If the name of an iface is not specified, a name like `veth0` will be allocated.
+    ///
+    /// This method returns the iface in `self` and the iface in `peer_ns`.
+    pub fn new_veth_pair(
+        &self,
+        name: Option<&str>,
+        peer_ns: &NetNamespace,
+        peer_name: Option<&str>,
+    ) -> Result<(Arc<Iface>, Arc<Iface>)> {
+        // New ifaces can only be added while holding the lock, so the names checked or allocated
+        // below will remain unique.
+        static NEW_IFACE_LOCK: Mutex<()> = Mutex::new(());
+        let _guard = NEW_IFACE_LOCK.lock();
+
+        let name = self.resolve_iface_name(name, None)?;
+        let peer_name = if core::ptr::eq(self, peer_ns) {
+            peer_ns.resolve_iface_name(peer_name, Some(&name))?
+        } else {
+            peer_ns.resolve_iface_name(peer_name, None)?
+        };
+
+        let (iface, peer_iface) = iface::new_veth_pair(
+            self.next_iface_index.fetch_add(1, Ordering::Relaxed),
+            name,
+            peer_ns.next_iface_index.fetch_add(1, Ordering::Relaxed),
+            peer_name,
+        );
+
+        iface.set_filter_table(Some(self.filter_table.clone()));
+        peer_iface.set_filter_table(Some(peer_ns.filter_table.clone()));
+
+        iface::spawn_background_poll_thread(iface.clone());
+        iface::spawn_background_poll_thread(peer_iface.clone());
+
+        self.ifaces.write().push(iface.clone());
+        peer_ns.ifaces.write().push(peer_iface.clone());
+
+        Ok((iface, peer_iface))
+    }
+
+    /// Checks whether the iface name is available, or allocates one if it is not specified.
+    ///
+    /// `reserved_name` is treated as if it were used by an existing iface.
+    fn resolve_iface_name(
+        &self,
+        name: Option<&str>,
+        reserved_name: Option<&str>,
+    ) -> Result<String> {
+        let ifaces = self.ifaces.read();
+        let is_used = |name: &str| {
+            reserved_name == Some(name) || ifaces.iter().any(|iface| iface.name() == name)
+        };
+
+        if let Some(name) = name {
+            if is_used(name) {
+                return_errno_with_message!(Errno::EEXIST, "the iface name is already used");
+            }
+            return Ok(name.to_string());
+        }
+
+        // Allocate the first unused name, as Linux does.
+        // Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/core/dev.c#L1107>.
+        let mut n = 0;
+        loop {
+            let name = format!("veth{}", n);
+            if !is_used(&name) {
+                return Ok(name);
+            }
+            n += 1;
+        }
+    }
+}
@@ -1,37 +1,60 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use aster_bigtcp::wire::{IpAddress, IpEndpoint, Ipv4Address};
+use aster_bigtcp::wire::{IpAddress, IpEndpoint, IpVersion, Ipv4Address, Ipv6Address};
 
 use crate::{net::socket::util::SocketAddr, prelude::*, return_errno_with_message};
 
-impl TryFrom<SocketAddr> for IpEndpoint {
-    type Error = Error;
-
-    fn try_from(value: SocketAddr) -> Result<Self> {
-        match value {
-            SocketAddr::IPv4(addr, port) => Ok(IpEndpoint::new(addr.into(), port)),
-            _ => return_errno_with_message!(
-                Errno::EAFNOSUPPORT,
-                "the address is in an unsupported address family"
-            ),
+/// Converts a socket address to an IP endpoint for a socket of `ip_version`.
+///
+/// For IPv6 sockets, IPv4-mapped IPv6 addresses (i.e., `::ffff:a.b.c.d`) are converted to IPv4
+/// endpoints, unless the socket is restricted to IPv6 communication only (i.e., `IPV6_V6ONLY`).
+pub(super) fn socket_addr_to_endpoint(
+    socket_addr: SocketAddr,
+    ip_version: IpVersion,
+    is_v6only: bool,
+) -> Result<IpEndpoint> {
+    match (socket_addr, ip_version) {
+        (SocketAddr::IPv4(addr, port), IpVersion::Ipv4) => Ok(IpEndpoint::new(addr.into(), port)),
+        (SocketAddr::IPv6(addr, port), IpVersion::Ipv6) => {
+            let Some(ipv4_addr) = addr.to_ipv4_mapped() else {
+                return Ok(IpEndpoint::new(addr.into(), port));
+            };
+            if is_v6only {
+                return_errno_with_message!(
+                    Errno::ENETUNREACH,
+                    "IPv4-mapped addresses cannot be used by IPv6-only sockets"
+                );
+            }
+            Ok(IpEndpoint::new(ipv4_addr.into(), port))
         }
+        _ => return_errno_with_message!(
+            Errno::EAFNOSUPPORT,
+            "the address is in an unsupported address family"
+        ),
     }
 }
 
-impl From<IpEndpoint> for SocketAddr {
-    fn from(endpoint: IpEndpoint) -> Self {
-        let port = endpoint.port;
-        match endpoint.addr {
-            IpAddress::Ipv4(addr) => SocketAddr::IPv4(addr, port),
-            // TODO: support IPv6
-        }
+/// Converts an IP endpoint to a socket address for a socket of `ip_version`.
+///
+/// For IPv6 sockets, IPv4 endpoints are reported as IPv4-mapped IPv6 addresses.
+pub(super) fn endpoint_to_socket_addr(endpoint: IpEndpoint, ip_version: IpVersion) -> SocketAddr {
+    let port = endpoint.port;
+    match (endpoint.addr, ip_version) {
+        (IpAddress::Ipv4(addr), IpVersion::Ipv4) => SocketAddr::IPv4(addr, port),
+        (IpAddress::Ipv4(addr), IpVersion::Ipv6) => SocketAddr::IPv6(addr.to_ipv6_mapped(), port),
+        (IpAddress::Ipv6(addr), _) => SocketAddr::IPv6(addr, port),
     }
 }
 
-/// A local endpoint, which indicates that the local endpoint is unspecified.
+/// Returns a local endpoint, which indicates that the local endpoint is unspecified.
 ///
 /// According to the Linux man pages and the Linux implementation, `getsockname()` will _not_ fail
 /// even if the socket is unbound. Instead, it will return an unspecified socket address. This
 /// unspecified endpoint helps with that.
-pub(super) const UNSPECIFIED_LOCAL_ENDPOINT: IpEndpoint =
-    IpEndpoint::new(IpAddress::Ipv4(Ipv4Address::UNSPECIFIED), 0);
+pub(super) const fn unspecified_local_endpoint(ip_version: IpVersion) -> IpEndpoint {
+    let addr = match ip_version {
+        IpVersion::Ipv4 => IpAddress::Ipv4(Ipv4Address::UNSPECIFIED),
+        IpVersion::Ipv6 => IpAddress::Ipv6(Ipv6Address::UNSPECIFIED),
+    };
+    IpEndpoint::new(addr, 0)
+}
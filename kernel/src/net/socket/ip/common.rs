@@ -2,66 +2,118 @@
 
 use aster_bigtcp::{
     errors::BindError,
-    iface::BindPortConfig,
+    iface::{BindAddr, BindPortConfig},
     wire::{IpAddress, IpEndpoint},
 };
 
 use crate::{
-    net::iface::{iter_all_ifaces, loopback_iface, virtio_iface, BoundPort, Iface},
+    net::{
+        iface::{BoundPort, Iface},
+        NetNamespace,
+    },
     prelude::*,
 };
 
-pub(super) fn get_iface_to_bind(ip_addr: &IpAddress) -> Option<Arc<Iface>> {
-    let IpAddress::Ipv4(ipv4_addr) = ip_addr;
-    iter_all_ifaces()
-        .find(|iface| {
-            if let Some(iface_ipv4_addr) = iface.ipv4_addr() {
-                iface_ipv4_addr == *ipv4_addr
-            } else {
-                false
-            }
-        })
+pub(super) fn get_iface_to_bind(net_ns: &NetNamespace, ip_addr: &IpAddress) -> Option<Arc<Iface>> {
+    net_ns
+        .ifaces()
+        .iter()
+        .find(|iface| iface_has_addr(iface, ip_addr))
         .map(Clone::clone)
 }
 
-/// Get a suitable iface to deal with sendto/connect request if the socket is not bound to an iface.
-/// If the remote address is the same as that of some iface, we will use the iface.
-/// Otherwise, we will use a default interface.
-fn get_ephemeral_iface(remote_ip_addr: &IpAddress) -> Arc<Iface> {
-    let IpAddress::Ipv4(remote_ipv4_addr) = remote_ip_addr;
-    if let Some(iface) = iter_all_ifaces().find(|iface| {
-        if let Some(iface_ipv4_addr) = iface.ipv4_addr() {
-            iface_ipv4_addr == *remote_ipv4_addr
-        } else {
-            false
-        }
-    }) {
-        return iface.clone();
+fn iface_has_addr(iface: &Iface, ip_addr: &IpAddress) -> bool {
+    match ip_addr {
+        IpAddress::Ipv4(ipv4_addr) => iface.ipv4_addr() == Some(*ipv4_addr),
+        IpAddress::Ipv6(ipv6_addr) => iface.ipv6_addr() == Some(*ipv6_addr),
     }
+}
 
-    // FIXME: Instead of hardcoding the rules here, we should choose the
-    // default interface according to the routing table.
-    if let Some(virtio_iface) = virtio_iface() {
-        virtio_iface.clone()
-    } else {
-        loopback_iface().clone()
-    }
+/// Get a suitable iface to deal with sendto/connect request if the socket is not bound to an iface.
+/// The iface is chosen according to the routing table.
+pub(super) fn get_ephemeral_iface(
+    net_ns: &NetNamespace,
+    remote_ip_addr: &IpAddress,
+) -> Result<Arc<Iface>> {
+    let Some(iface) = net_ns.route_iface(remote_ip_addr) else {
+        return_errno_with_message!(Errno::ENETUNREACH, "no route to the remote address");
+    };
+
+    Ok(iface)
 }
 
-pub(super) fn bind_port(endpoint: &IpEndpoint, can_reuse: bool) -> Result<BoundPort> {
-    let iface = match get_iface_to_bind(&endpoint.addr) {
-        Some(iface) => iface,
-        None => {
-            return_errno_with_message!(
-                Errno::EADDRNOTAVAIL,
-                "the address is not available from the local machine"
-            );
+/// Binds a port to `endpoint`.
+///
+/// If `endpoint` has the unspecified IPv6 address, `is_v6only` determines whether IPv4 packets
+/// can also be received via the port.
+pub(super) fn bind_port(
+    net_ns: &NetNamespace,
+    endpoint: &IpEndpoint,
+    can_reuse: bool,
+    is_v6only: bool,
+) -> Result<BoundPort> {
+    if let IpAddress::Ipv6(ipv6_addr) = endpoint.addr {
+        if ipv6_addr.is_unspecified() {
+            return bind_port_ipv6_any(net_ns, endpoint.port, can_reuse, is_v6only);
         }
+    }
+
+    let Some(iface) = get_iface_to_bind(net_ns, &endpoint.addr) else {
+        return_errno_with_message!(
+            Errno::EADDRNOTAVAIL,
+            "the address is not available from the local machine"
+        );
     };
 
     let bind_port_config = BindPortConfig::new(endpoint.port, can_reuse);
 
-    Ok(iface.bind(bind_port_config)?)
+    Ok(iface.bind(BindAddr::from(endpoint.addr.version()), bind_port_config)?)
+}
+
+/// Binds a port to the unspecified IPv6 address (i.e., `::`).
+///
+/// A socket bound to the unspecified address should receive packets from all ifaces, so the port
+/// is bound on every iface in the network namespace. The first iface with an IPv6 address (i.e.,
+/// the loopback iface in practice) is used to send packets.
+//
+// TODO: Ifaces that are added after binding cannot receive packets via the port.
+fn bind_port_ipv6_any(
+    net_ns: &NetNamespace,
+    port: u16,
+    can_reuse: bool,
+    is_v6only: bool,
+) -> Result<BoundPort> {
+    let ifaces = net_ns.ifaces();
+    let Some(main_iface) = ifaces.iter().find(|iface| iface.ipv6_addr().is_some()) else {
+        return_errno_with_message!(
+            Errno::EADDRNOTAVAIL,
+            "the address is not available from the local machine"
+        );
+    };
+
+    let bind_addr = BindAddr::Ipv6Any { v6only: is_v6only };
+
+    // An ephemeral port on the main iface may be in use on other ifaces. In this case, the port is
+    // kept bound so that the next try will pick a different one.
+    let mut rejected_ports = Vec::new();
+    loop {
+        let mut bound_port = main_iface.bind(bind_addr, BindPortConfig::new(port, can_reuse))?;
+
+        let other_ports = ifaces
+            .iter()
+            .filter(|iface| !Arc::ptr_eq(iface, main_iface))
+            .map(|iface| iface.bind(bind_addr, BindPortConfig::new(bound_port.port(), can_reuse)))
+            .collect::<core::result::Result<Vec<_>, _>>();
+
+        match other_ports {
+            Ok(other_ports) => {
+                bound_port.attach_other_ports(other_ports);
+                return Ok(bound_port);
+            }
+            Err(BindError::InUse) if port == 0 => rejected_ports.push(bound_port),
+            Err(err) => return Err(err.into()),
+        }
+    }
 }
 
 impl From<BindError> for Error {
@@ -77,8 +129,28 @@ impl From<BindError> for Error {
     }
 }
 
-pub(super) fn get_ephemeral_endpoint(remote_endpoint: &IpEndpoint) -> IpEndpoint {
-    let iface = get_ephemeral_iface(&remote_endpoint.addr);
-    let ip_addr = iface.ipv4_addr().unwrap();
-    IpEndpoint::new(IpAddress::Ipv4(ip_addr), 0)
+pub(super) fn get_ephemeral_endpoint(
+    net_ns: &NetNamespace,
+    remote_endpoint: &IpEndpoint,
+) -> Result<IpEndpoint> {
+    let iface = get_ephemeral_iface(net_ns, &remote_endpoint.addr)?;
+    get_ephemeral_endpoint_on_iface(&iface, remote_endpoint)
+}
+
+/// Gets an ephemeral endpoint on `iface` to communicate with `remote_endpoint`.
+pub(super) fn get_ephemeral_endpoint_on_iface(
+    iface: &Iface,
+    remote_endpoint: &IpEndpoint,
+) -> Result<IpEndpoint> {
+    let ip_addr = match remote_endpoint.addr {
+        IpAddress::Ipv4(_) => iface.ipv4_addr().map(IpAddress::Ipv4),
+        IpAddress::Ipv6(_) => iface.ipv6_addr().map(IpAddress::Ipv6),
+    };
+    let Some(ip_addr) = ip_addr else {
+        return_errno_with_message!(
+            Errno::ENETUNREACH,
+            "the interface has no address in the address family"
+        );
+    };
+    Ok(IpEndpoint::new(ip_addr, 0))
 }
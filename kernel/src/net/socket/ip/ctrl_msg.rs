@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use aster_bigtcp::wire::Ipv4Address;
+use ostd::timer::Jiffies;
+
+use crate::{
+    net::socket::util::{CControlHeader, ControlMessage},
+    prelude::*,
+    time::{clocks::RealTimeClock, timespec_t, timeval_t, Clock},
+    util::net::{CSocketAddrFamily, CSocketOptionLevel},
+};
+
+#[derive(Debug)]
+pub struct IpControlMessage(Message);
+
+#[derive(Debug)]
+enum Message {
+    PktInfo(CInPktInfo),
+    Ttl(i32),
+    Tos(u8),
+    Timestamp(timeval_t),
+    TimestampNs(timespec_t),
+    RecvErr(CSockExtendedErr),
+}
+
+impl IpControlMessage {
+    pub fn read_from(header: &CControlHeader, reader: &mut VmReader) -> Result<Option<Self>> {
+        debug_assert_eq!(header.level(), Some(CSocketOptionLevel::SOL_IP));
+
+        let Ok(type_) = CIpControlType::try_from(header.type_()) else {
+            warn!("unsupported control message type in {:?}", header);
+            reader.skip(header.payload_len());
+            return Ok(None);
+        };
+
+        // This follows `ip_cmsg_send` in Linux. See
+        // <https://elixir.bootlin.com/linux/v6.13/source/net/ipv4/ip_sockglue.c#L247>.
+        match type_ {
+            CIpControlType::IP_PKTINFO => {
+                if header.payload_len() != size_of::<CInPktInfo>() {
+                    return_errno_with_message!(Errno::EINVAL, "the IP_PKTINFO message is invalid");
+                }
+                let pkt_info = reader.read_val::<CInPktInfo>()?;
+                Ok(Some(Self(Message::PktInfo(pkt_info))))
+            }
+            CIpControlType::IP_TTL => {
+                if header.payload_len() != size_of::<i32>() {
+                    return_errno_with_message!(Errno::EINVAL, "the IP_TTL message is invalid");
+                }
+                let ttl = reader.read_val::<i32>()?;
+                if !(1..=255).contains(&ttl) {
+                    return_errno_with_message!(Errno::EINVAL, "the TTL is out of bounds");
+                }
+                Ok(Some(Self(Message::Ttl(ttl))))
+            }
+            CIpControlType::IP_TOS => {
+                let tos = match header.payload_len() {
+                    1 => reader.read_val::<u8>()? as i32,
+                    4 => reader.read_val::<i32>()?,
+                    _ => {
+                        return_errno_with_message!(Errno::EINVAL, "the IP_TOS message is invalid")
+                    }
+                };
+                if !(0..=255).contains(&tos) {
+                    return_errno_with_message!(Errno::EINVAL, "the TOS is out of bounds");
+                }
+                Ok(Some(Self(Message::Tos(tos as u8))))
+            }
+            _ => {
+                warn!("unsupported control message type in {:?}", header);
+                reader.skip(header.payload_len());
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn write_to(&self, writer: &mut VmWriter) -> Result<CControlHeader> {
+        let (level, type_, payload) = match &self.0 {
+            Message::PktInfo(pkt_info) => (
+                CSocketOptionLevel::SOL_IP,
+                CIpControlType::IP_PKTINFO as i32,
+                pkt_info.as_bytes(),
+            ),
+            Message::Ttl(ttl) => (
+                CSocketOptionLevel::SOL_IP,
+                CIpControlType::IP_TTL as i32,
+                ttl.as_bytes(),
+            ),
+            Message::Tos(tos) => (
+                CSocketOptionLevel::SOL_IP,
+                CIpControlType::IP_TOS as i32,
+                tos.as_bytes(),
+            ),
+            Message::Timestamp(timeval) => (
+                CSocketOptionLevel::SOL_SOCKET,
+                CSocketControlType::SCM_TIMESTAMP as i32,
+                timeval.as_bytes(),
+            ),
+            Message::TimestampNs(timespec) => (
+                CSocketOptionLevel::SOL_SOCKET,
+                CSocketControlType::SCM_TIMESTAMPNS as i32,
+                timespec.as_bytes(),
+            ),
+            Message::RecvErr(extended_err) => (
+                CSocketOptionLevel::SOL_IP,
+                CIpControlType::IP_RECVERR as i32,
+                extended_err.as_bytes(),
+            ),
+        };
+
+        let payload_len = payload
+            .len()
+            .min(CControlHeader::payload_len_from_total(writer.avail())?);
+        if payload_len != payload.len() {
+            warn!("setting MSG_CTRUNC is not supported");
+        }
+
+        let header = CControlHeader::new(level, type_, payload_len);
+        writer.write_val(&header)?;
+        writer.write_fallible(&mut VmReader::from(&payload[..payload_len]))?;
+
+        Ok(header)
+    }
+
+    /// Creates an `IP_PKTINFO` message.
+    pub(super) fn new_pkt_info(ifindex: u32, spec_dst: Ipv4Address, addr: Ipv4Address) -> Self {
+        Self(Message::PktInfo(CInPktInfo {
+            ipi_ifindex: ifindex as i32,
+            ipi_spec_dst: spec_dst.octets(),
+            ipi_addr: addr.octets(),
+        }))
+    }
+
+    /// Creates an `IP_TTL` message.
+    pub(super) fn new_ttl(ttl: u8) -> Self {
+        Self(Message::Ttl(ttl as i32))
+    }
+
+    /// Creates an `IP_TOS` message.
+    pub(super) fn new_tos(tos: u8) -> Self {
+        Self(Message::Tos(tos))
+    }
+
+    /// Creates an `SCM_TIMESTAMP` or `SCM_TIMESTAMPNS` message.
+    ///
+    /// The timestamp is the time when the packet was received, measured since boot. It will be
+    /// converted to the real time.
+    pub(super) fn new_timestamp(timestamp: Duration, is_ns: bool) -> Self {
+        let elapsed = Jiffies::elapsed().as_duration().saturating_sub(timestamp);
+        let real_time = RealTimeClock::get().read_time().saturating_sub(elapsed);
+
+        if is_ns {
+            Self(Message::TimestampNs(timespec_t::from(real_time)))
+        } else {
+            Self(Message::Timestamp(timeval_t::from(real_time)))
+        }
+    }
+
+    /// Creates an `IP_RECVERR` message for an ICMP error.
+    pub(super) fn new_icmp_error(
+        errno: Errno,
+        icmp_type: u8,
+        icmp_code: u8,
+        offender: Ipv4Address,
+    ) -> Self {
+        Self(Message::RecvErr(CSockExtendedErr {
+            ee_errno: errno as u32,
+            ee_origin: SO_EE_ORIGIN_ICMP,
+            ee_type: icmp_type,
+            ee_code: icmp_code,
+            ee_pad: 0,
+            ee_info: 0,
+            ee_data: 0,
+            offender_family: CSocketAddrFamily::AF_INET as u16,
+            offender_port: 0,
+            offender_addr: offender.octets(),
+            offender_zero: [0; 8],
+        }))
+    }
+}
+
+/// Options for sending a packet, which are specified in the control messages.
+#[derive(Debug, Default)]
+pub(super) struct IpSendOptions {
+    /// The source address of the packet.
+    pub(super) src_addr: Option<Ipv4Address>,
+}
+
+impl IpSendOptions {
+    /// Builds the send options from the control messages.
+    pub(super) fn from_control(ctrl_msgs: &[ControlMessage]) -> Self {
+        let mut options = Self::default();
+
+        for ctrl_msg in ctrl_msgs.iter() {
+            // Control messages of other protocols are ignored, as Linux does.
+            let ControlMessage::Ip(ip_ctrl_msg) = ctrl_msg else {
+                continue;
+            };
+
+            match &ip_ctrl_msg.0 {
+                Message::PktInfo(pkt_info) => {
+                    // TODO: Support sending packets via the iface specified by `ipi_ifindex`.
+                    let spec_dst = Ipv4Address::from(pkt_info.ipi_spec_dst);
+                    options.src_addr = (!spec_dst.is_unspecified()).then_some(spec_dst);
+                }
+                Message::Ttl(_) | Message::Tos(_) => {
+                    // TODO: Support per-packet TTL and TOS values.
+                    warn!("per-packet TTL and TOS values are not supported");
+                }
+                Message::Timestamp(_) | Message::TimestampNs(_) | Message::RecvErr(_) => {
+                    unreachable!("the control message cannot be received from the user space")
+                }
+            }
+        }
+
+        options
+    }
+}
+
+/// `struct in_pktinfo` in Linux.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/in.h#L170>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CInPktInfo {
+    /// The interface index.
+    ipi_ifindex: i32,
+    /// The local address (i.e., the address to be used as the source address).
+    ipi_spec_dst: [u8; 4],
+    /// The destination address in the IP header.
+    ipi_addr: [u8; 4],
+}
+
+/// `struct sock_extended_err` in Linux, followed by the address of the offender
+/// (`SO_EE_OFFENDER`).
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/errqueue.h#L21>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CSockExtendedErr {
+    ee_errno: u32,
+    ee_origin: u8,
+    ee_type: u8,
+    ee_code: u8,
+    ee_pad: u8,
+    ee_info: u32,
+    ee_data: u32,
+    // The following fields form a `struct sockaddr_in`.
+    offender_family: u16,
+    offender_port: u16,
+    offender_addr: [u8; 4],
+    offender_zero: [u8; 8],
+}
+
+/// The error originates from an ICMP message.
+const SO_EE_ORIGIN_ICMP: u8 = 2;
+
+/// IP-level control message types.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/in.h#L94>.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt, PartialEq, Eq)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+enum CIpControlType {
+    IP_TOS = 1,
+    IP_TTL = 2,
+    IP_PKTINFO = 8,
+    IP_RECVERR = 11,
+}
+
+/// Socket-level control message types for IP sockets.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/asm-generic/socket.h#L150>.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[expect(non_camel_case_types)]
+enum CSocketControlType {
+    SCM_TIMESTAMP = 29,
+    SCM_TIMESTAMPNS = 35,
+}
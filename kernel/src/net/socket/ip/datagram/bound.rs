@@ -2,14 +2,15 @@
 
 use aster_bigtcp::{
     errors::udp::{RecvError, SendError},
-    wire::IpEndpoint,
+    socket::{UdpIcmpError, UdpMetadata, UdpRecvInfo},
+    wire::{IpAddress, IpEndpoint, Ipv4Address},
 };
 
 use crate::{
     events::IoEvents,
     net::{
         iface::{BoundPort, Iface, UdpSocket},
-        socket::util::{datagram_common, SendRecvFlags},
+        socket::util::{datagram_common, SendRecvFlags, SocketFilter},
     },
     prelude::*,
     util::{MultiRead, MultiWrite},
@@ -18,6 +19,15 @@ use crate::{
 pub(super) struct BoundDatagram {
     bound_socket: UdpSocket,
     remote_endpoint: Option<IpEndpoint>,
+    filter: Option<Arc<SocketFilter>>,
+}
+
+/// Metadata of a received datagram.
+pub(super) struct RecvMeta {
+    /// The destination address in the IP header of the datagram.
+    pub(super) dst_addr: Option<IpAddress>,
+    /// The information recorded when the datagram was received.
+    pub(super) info: UdpRecvInfo,
 }
 
 impl BoundDatagram {
@@ -25,6 +35,7 @@ impl BoundDatagram {
         Self {
             bound_socket,
             remote_endpoint: None,
+            filter: None,
         }
     }
 
@@ -35,37 +46,53 @@ impl BoundDatagram {
     pub(super) fn bound_port(&self) -> &BoundPort {
         self.bound_socket.bound_port()
     }
-}
-
-impl datagram_common::Bound for BoundDatagram {
-    type Endpoint = IpEndpoint;
 
-    fn local_endpoint(&self) -> Self::Endpoint {
-        self.bound_socket.local_endpoint().unwrap()
+    pub(super) fn set_multicast_options(&self, multicast_ttl: u8, multicast_loop: bool) {
+        self.bound_socket.set_multicast_ttl(multicast_ttl);
+        self.bound_socket.set_multicast_loop(multicast_loop);
     }
 
-    fn remote_endpoint(&self) -> Option<&Self::Endpoint> {
-        self.remote_endpoint.as_ref()
+    pub(super) fn set_recv_err(&self, recv_err: bool) {
+        self.bound_socket.set_recv_err(recv_err);
     }
 
-    fn set_remote_endpoint(&mut self, endpoint: &Self::Endpoint) {
-        self.remote_endpoint = Some(*endpoint)
+    /// Takes the oldest ICMP error in the error queue, if any.
+    pub(super) fn take_error(&self) -> Option<UdpIcmpError> {
+        self.bound_socket.take_error()
     }
 
-    fn try_recv(
+    /// Receives a datagram and returns its metadata as well.
+    pub(super) fn try_recv_with_meta(
         &self,
         writer: &mut dyn MultiWrite,
-        _flags: SendRecvFlags,
-    ) -> Result<(usize, Self::Endpoint)> {
-        let result = self.bound_socket.recv(|packet, udp_metadata| {
+        flags: SendRecvFlags,
+    ) -> Result<(usize, IpEndpoint, RecvMeta)> {
+        let recv = |packet: &[u8], udp_metadata: UdpMetadata, recv_info: &UdpRecvInfo| {
             let copied_res = writer.write(&mut VmReader::from(packet));
+            // If `MSG_TRUNC` is specified, the real length of the datagram should be returned,
+            // even if it is longer than the buffer.
+            let copied_res = if flags.contains(SendRecvFlags::MSG_TRUNC) {
+                copied_res.map(|_| packet.len())
+            } else {
+                copied_res
+            };
             let endpoint = udp_metadata.endpoint;
-            (copied_res, endpoint)
-        });
+            let meta = RecvMeta {
+                dst_addr: udp_metadata.local_address,
+                info: *recv_info,
+            };
+            (copied_res, endpoint, meta)
+        };
+
+        let result = if flags.contains(SendRecvFlags::MSG_PEEK) {
+            self.bound_socket.peek(recv)
+        } else {
+            self.bound_socket.recv(recv)
+        };
 
         match result {
-            Ok((Ok(res), endpoint)) => Ok((res, endpoint)),
-            Ok((Err(e), _)) => Err(e),
+            Ok((Ok(res), endpoint, meta)) => Ok((res, endpoint, meta)),
+            Ok((Err(e), _, _)) => Err(e),
             Err(RecvError::Exhausted) => {
                 return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty")
             }
@@ -75,15 +102,27 @@ impl datagram_common::Bound for BoundDatagram {
         }
     }
 
-    fn try_send(
+    /// Sends a datagram with the source address, if any.
+    pub(super) fn try_send_from(
         &self,
         reader: &mut dyn MultiRead,
-        remote: &Self::Endpoint,
+        remote: &IpEndpoint,
+        src_addr: Option<Ipv4Address>,
         _flags: SendRecvFlags,
     ) -> Result<usize> {
+        if let Some(src_addr) = src_addr {
+            // TODO: Support source addresses that belong to other ifaces.
+            if self.iface().ipv4_addr() != Some(src_addr) {
+                return_errno_with_message!(Errno::EINVAL, "the source address is invalid");
+            }
+        }
+
+        let mut udp_metadata = UdpMetadata::from(*remote);
+        udp_metadata.local_address = src_addr.map(IpAddress::Ipv4);
+
         let result = self
             .bound_socket
-            .send(reader.sum_lens(), *remote, |socket_buffer| {
+            .send(reader.sum_lens(), udp_metadata, |socket_buffer| {
                 // FIXME: If copy failed, we should not send any packet.
                 // But current smoltcp API seems not to support this behavior.
                 reader
@@ -107,8 +146,51 @@ impl datagram_common::Bound for BoundDatagram {
         }
     }
 
+    /// Sets the filter for incoming packets and returns the old one.
+    pub(super) fn set_filter(
+        &mut self,
+        filter: Option<Arc<SocketFilter>>,
+    ) -> Option<Arc<SocketFilter>> {
+        self.bound_socket.set_filter(filter.clone());
+        core::mem::replace(&mut self.filter, filter)
+    }
+}
+
+impl datagram_common::Bound for BoundDatagram {
+    type Endpoint = IpEndpoint;
+
+    fn local_endpoint(&self) -> Self::Endpoint {
+        self.bound_socket.local_endpoint().unwrap()
+    }
+
+    fn remote_endpoint(&self) -> Option<&Self::Endpoint> {
+        self.remote_endpoint.as_ref()
+    }
+
+    fn set_remote_endpoint(&mut self, endpoint: &Self::Endpoint) {
+        self.remote_endpoint = Some(*endpoint)
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, Self::Endpoint)> {
+        self.try_recv_with_meta(writer, flags)
+            .map(|(recv_bytes, endpoint, _)| (recv_bytes, endpoint))
+    }
+
+    fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote: &Self::Endpoint,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        self.try_send_from(reader, remote, None, flags)
+    }
+
     fn check_io_events(&self) -> IoEvents {
-        self.bound_socket.raw_with(|socket| {
+        let mut events = self.bound_socket.raw_with(|socket| {
             let mut events = IoEvents::empty();
 
             if socket.can_recv() {
@@ -120,6 +202,12 @@ impl datagram_common::Bound for BoundDatagram {
             }
 
             events
-        })
+        });
+
+        if self.bound_socket.has_error() {
+            events |= IoEvents::ERR;
+        }
+
+        events
     }
 }
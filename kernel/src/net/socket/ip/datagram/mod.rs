@@ -2,25 +2,40 @@
 
 use core::sync::atomic::{AtomicBool, Ordering};
 
-use aster_bigtcp::wire::IpEndpoint;
-use bound::BoundDatagram;
+use aster_bigtcp::{
+    socket::NeedIfacePoll,
+    wire::{IpAddress, IpEndpoint, IpVersion, Ipv4Address},
+};
+use bound::{BoundDatagram, RecvMeta};
 use unbound::{BindOptions, UnboundDatagram};
 
-use super::addr::UNSPECIFIED_LOCAL_ENDPOINT;
+use super::{
+    addr::{endpoint_to_socket_addr, socket_addr_to_endpoint, unspecified_local_endpoint},
+    common::{get_ephemeral_endpoint_on_iface, get_iface_to_bind},
+    ctrl_msg::{IpControlMessage, IpSendOptions},
+    options::{
+        AddMembership, CIpMreqn, DropMembership, IpOptionSet, Ipv6OptionSet, SetIpLevelOption,
+        SetIpv6LevelOption,
+    },
+};
 use crate::{
     events::IoEvents,
     fs::utils::Inode,
-    match_sock_option_mut,
-    net::socket::{
-        new_pseudo_inode,
-        options::{Error as SocketError, SocketOption},
-        private::SocketPrivate,
-        util::{
-            datagram_common::{select_remote_and_bind, Bound, Inner},
-            options::{GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet},
-            MessageHeader, SendRecvFlags, SocketAddr,
+    match_sock_option_mut, match_sock_option_ref,
+    net::{
+        iface::Iface,
+        socket::{
+            new_pseudo_inode,
+            options::{AttachFilter, DetachFilter, Error as SocketError, SocketOption},
+            private::SocketPrivate,
+            util::{
+                datagram_common::{select_remote_and_bind, Inner},
+                options::{GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet},
+                ControlMessage, MessageHeader, SendRecvFlags, SocketAddr, SocketFilter,
+            },
+            Socket,
         },
-        Socket,
+        NetNamespace,
     },
     prelude::*,
     process::signal::{PollHandle, Pollable, Pollee},
@@ -35,7 +50,10 @@ pub struct DatagramSocket {
     // Lock order: `inner` first, `options` second
     inner: RwMutex<Inner<UnboundDatagram, BoundDatagram>>,
     options: RwLock<OptionSet>,
+    memberships: Mutex<Vec<Membership>>,
 
+    net_ns: Arc<NetNamespace>,
+    ip_version: IpVersion,
     is_nonblocking: AtomicBool,
     pollee: Pollee,
     pseudo_inode: Arc<dyn Inode>,
@@ -44,22 +62,81 @@ pub struct DatagramSocket {
 #[derive(Debug, Clone)]
 struct OptionSet {
     socket: SocketOptionSet,
+    ip: IpOptionSet,
+    ipv6: Ipv6OptionSet,
     // TODO: UDP option set
 }
 
 impl OptionSet {
     fn new() -> Self {
         let socket = SocketOptionSet::new_udp();
-        OptionSet { socket }
+        let ip = IpOptionSet::new_udp();
+        let ipv6 = Ipv6OptionSet::new();
+        OptionSet { socket, ip, ipv6 }
+    }
+
+    /// Generates the control messages for a received datagram according to the options.
+    fn generate_control(&self, meta: &RecvMeta, iface: &Iface) -> Vec<ControlMessage> {
+        let mut ctrl_msgs = Vec::new();
+
+        if self.socket.timestamp() {
+            let msg =
+                IpControlMessage::new_timestamp(meta.info.timestamp, self.socket.timestamp_ns());
+            ctrl_msgs.push(ControlMessage::Ip(msg));
+        }
+
+        if self.ip.pkt_info() {
+            if let Some(IpAddress::Ipv4(dst_addr)) = meta.dst_addr {
+                // For broadcast and multicast packets, the local address is the address of the
+                // iface. See `ipv4_pktinfo_prepare` in Linux.
+                let spec_dst = if dst_addr.is_unicast() {
+                    dst_addr
+                } else {
+                    iface.ipv4_addr().unwrap_or(Ipv4Address::UNSPECIFIED)
+                };
+                let msg = IpControlMessage::new_pkt_info(iface.index(), spec_dst, dst_addr);
+                ctrl_msgs.push(ControlMessage::Ip(msg));
+            }
+        }
+
+        if self.ip.recv_ttl() {
+            let msg = IpControlMessage::new_ttl(meta.info.hop_limit);
+            ctrl_msgs.push(ControlMessage::Ip(msg));
+        }
+
+        if self.ip.recv_tos() {
+            let msg = IpControlMessage::new_tos(meta.info.tos);
+            ctrl_msgs.push(ControlMessage::Ip(msg));
+        }
+
+        ctrl_msgs
     }
 }
 
+/// A multicast group that the socket has joined.
+struct Membership {
+    group: Ipv4Address,
+    iface: Arc<Iface>,
+}
+
+/// The maximum number of multicast groups that a socket can join.
+///
+/// Reference: <https://docs.kernel.org/networking/ip-sysctl.html#igmp-max-memberships-integer>.
+const MAX_MEMBERSHIPS: usize = 20;
+
 impl DatagramSocket {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
-        let unbound_datagram = UnboundDatagram::new();
+    pub fn new(
+        is_nonblocking: bool,
+        ip_version: IpVersion,
+        net_ns: Arc<NetNamespace>,
+    ) -> Arc<Self> {
+        let unbound_datagram = UnboundDatagram::new(net_ns.clone());
         Arc::new(Self {
             inner: RwMutex::new(Inner::Unbound(unbound_datagram)),
             options: RwLock::new(OptionSet::new()),
+            memberships: Mutex::new(Vec::new()),
+            net_ns,
+            ip_version,
             is_nonblocking: AtomicBool::new(is_nonblocking),
             pollee: Pollee::new(),
             pseudo_inode: new_pseudo_inode(),
@@ -70,23 +147,73 @@ impl DatagramSocket {
         &self,
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
-    ) -> Result<(usize, SocketAddr)> {
-        let recv_bytes = self
-            .inner
+    ) -> Result<(usize, MessageHeader)> {
+        let inner = self.inner.read();
+        let Inner::Bound(bound_datagram) = &*inner else {
+            return_errno_with_message!(Errno::EAGAIN, "the socket is not bound");
+        };
+
+        let (recv_bytes, remote_endpoint, recv_meta) =
+            bound_datagram.try_recv_with_meta(writer, flags)?;
+        let control_messages = self
+            .options
             .read()
-            .try_recv(writer, flags)
-            .map(|(recv_bytes, remote_endpoint)| (recv_bytes, remote_endpoint.into()))?;
+            .generate_control(&recv_meta, bound_datagram.iface());
+        drop(inner);
+
+        self.pollee.invalidate();
+
+        let remote_addr = endpoint_to_socket_addr(remote_endpoint, self.ip_version);
+        Ok((
+            recv_bytes,
+            MessageHeader::new(Some(remote_addr), control_messages),
+        ))
+    }
+
+    /// Receives an error from the error queue.
+    fn recv_error(&self, writer: &mut dyn MultiWrite) -> Result<(usize, MessageHeader)> {
+        let error = match &*self.inner.read() {
+            Inner::Bound(bound_datagram) => bound_datagram.take_error(),
+            Inner::Unbound(_) => None,
+        };
+        let Some(error) = error else {
+            return_errno_with_message!(Errno::EAGAIN, "the error queue is empty");
+        };
         self.pollee.invalidate();
 
-        Ok(recv_bytes)
+        let recv_bytes = writer.write(&mut VmReader::from(error.payload.as_slice()))?;
+
+        let errno = Error::from(error.error).error();
+        let msg = IpControlMessage::new_icmp_error(
+            errno,
+            error.icmp_type,
+            error.icmp_code,
+            error.offender,
+        );
+        let dst_addr = endpoint_to_socket_addr(error.dst_endpoint, self.ip_version);
+
+        Ok((
+            recv_bytes,
+            MessageHeader::new(Some(dst_addr), vec![ControlMessage::Ip(msg)]),
+        ))
     }
 
     fn try_send(
         &self,
         reader: &mut dyn MultiRead,
         remote: Option<&IpEndpoint>,
+        send_options: &IpSendOptions,
         flags: SendRecvFlags,
     ) -> Result<usize> {
+        let (multicast_ttl, multicast_loop, recv_err) = {
+            let options = self.options.read();
+            (
+                options.ip.multicast_ttl(),
+                options.ip.multicast_loop(),
+                options.ip.recv_err(),
+            )
+        };
+
         let (sent_bytes, iface_to_poll) = select_remote_and_bind(
             &self.inner,
             remote,
@@ -97,12 +224,18 @@ impl DatagramSocket {
                         "the destination address is not specified",
                     )
                 })?;
-                self.inner
-                    .write()
-                    .bind_ephemeral(remote_endpoint, &self.pollee)
+                self.bind_ephemeral(remote_endpoint)
             },
             |bound_datagram, remote_endpoint| {
-                let sent_bytes = bound_datagram.try_send(reader, remote_endpoint, flags)?;
+                bound_datagram.set_multicast_options(multicast_ttl, multicast_loop);
+                // ICMP errors can only be received after packets are sent.
+                bound_datagram.set_recv_err(recv_err);
+                let sent_bytes = bound_datagram.try_send_from(
+                    reader,
+                    remote_endpoint,
+                    send_options.src_addr,
+                    flags,
+                )?;
                 let iface_to_poll = bound_datagram.iface().clone();
                 Ok((sent_bytes, iface_to_poll))
             },
@@ -113,6 +246,130 @@ impl DatagramSocket {
 
         Ok(sent_bytes)
     }
+
+    fn bind_ephemeral(&self, remote_endpoint: &IpEndpoint) -> Result<()> {
+        let mut inner = self.inner.write();
+
+        // Multicast packets are sent via the iface specified by `IP_MULTICAST_IF`, if any.
+        let multicast_iface = match remote_endpoint.addr {
+            IpAddress::Ipv4(dst_addr) if dst_addr.is_multicast() => {
+                let multicast_if = self.options.read().ip.multicast_if();
+                self.find_multicast_iface(&multicast_if, dst_addr, false)
+            }
+            _ => None,
+        };
+
+        match multicast_iface {
+            Some(iface) if matches!(*inner, Inner::Unbound(_)) => {
+                let endpoint = get_ephemeral_endpoint_on_iface(&iface, remote_endpoint)?;
+                inner.bind(
+                    &endpoint,
+                    &self.pollee,
+                    BindOptions {
+                        can_reuse: false,
+                        is_v6only: false,
+                    },
+                )
+            }
+            _ => inner.bind_ephemeral(remote_endpoint, &self.pollee),
+        }
+    }
+
+    /// Finds the iface specified by the interface index or the local address in `mreqn`.
+    ///
+    /// If neither is specified, the iface to reach `group` is returned if `use_route` is true.
+    fn find_multicast_iface(
+        &self,
+        mreqn: &CIpMreqn,
+        group: Ipv4Address,
+        use_route: bool,
+    ) -> Option<Arc<Iface>> {
+        if mreqn.imr_ifindex != 0 {
+            return self.net_ns.get_iface_by_index(mreqn.imr_ifindex as u32);
+        }
+
+        let address = mreqn.address();
+        if !address.is_unspecified() {
+            return get_iface_to_bind(&self.net_ns, &IpAddress::Ipv4(address));
+        }
+
+        if use_route {
+            self.net_ns.route_iface(&IpAddress::Ipv4(group))
+        } else {
+            None
+        }
+    }
+
+    /// Sets the filter for incoming packets and returns the old one.
+    fn set_filter(&self, filter: Option<Arc<SocketFilter>>) -> Option<Arc<SocketFilter>> {
+        match &mut *self.inner.write() {
+            Inner::Unbound(unbound_datagram) => unbound_datagram.set_filter(filter),
+            Inner::Bound(bound_datagram) => bound_datagram.set_filter(filter),
+        }
+    }
+
+    fn add_membership(&self, mreqn: &CIpMreqn) -> Result<()> {
+        let group = mreqn.multiaddr();
+        if !group.is_multicast() {
+            return_errno_with_message!(Errno::EINVAL, "the address is not a multicast address");
+        }
+        let Some(iface) = self.find_multicast_iface(mreqn, group, true) else {
+            return_errno_with_message!(Errno::ENODEV, "the interface does not exist");
+        };
+
+        let mut memberships = self.memberships.lock();
+
+        if memberships
+            .iter()
+            .any(|m| m.group == group && m.iface.index() == iface.index())
+        {
+            return_errno_with_message!(Errno::EADDRINUSE, "the group has already been joined");
+        }
+        if memberships.len() >= MAX_MEMBERSHIPS {
+            return_errno_with_message!(Errno::ENOBUFS, "too many groups have been joined");
+        }
+
+        let need_iface_poll = iface.join_multicast_group(group);
+        memberships.push(Membership {
+            group,
+            iface: iface.clone(),
+        });
+        drop(memberships);
+
+        if *need_iface_poll {
+            iface.poll();
+        }
+
+        Ok(())
+    }
+
+    fn drop_membership(&self, mreqn: &CIpMreqn) -> Result<()> {
+        let group = mreqn.multiaddr();
+        let Some(iface) = self.find_multicast_iface(mreqn, group, true) else {
+            return_errno_with_message!(Errno::ENODEV, "the interface does not exist");
+        };
+
+        let mut memberships = self.memberships.lock();
+
+        let Some(pos) = memberships
+            .iter()
+            .position(|m| m.group == group && m.iface.index() == iface.index())
+        else {
+            return_errno_with_message!(Errno::EADDRNOTAVAIL, "the group has not been joined");
+        };
+
+        let membership = memberships.remove(pos);
+        drop(memberships);
+
+        leave_membership(&membership);
+
+        Ok(())
+    }
+
+    fn socket_addr_to_endpoint(&self, socket_addr: SocketAddr) -> Result<IpEndpoint> {
+        let is_v6only = self.options.read().ipv6.v6only();
+        socket_addr_to_endpoint(socket_addr, self.ip_version, is_v6only)
+    }
 }
 
 impl Pollable for DatagramSocket {
@@ -134,16 +391,24 @@ impl SocketPrivate for DatagramSocket {
 
 impl Socket for DatagramSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
-        let can_reuse = self.options.read().socket.reuse_addr();
+        let endpoint = self.socket_addr_to_endpoint(socket_addr)?;
+        let (can_reuse, is_v6only) = {
+            let options = self.options.read();
+            (options.socket.reuse_addr(), options.ipv6.v6only())
+        };
 
-        self.inner
-            .write()
-            .bind(&endpoint, &self.pollee, BindOptions { can_reuse })
+        self.inner.write().bind(
+            &endpoint,
+            &self.pollee,
+            BindOptions {
+                can_reuse,
+                is_v6only,
+            },
+        )
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
+        let endpoint = self.socket_addr_to_endpoint(socket_addr)?;
 
         self.inner.write().connect(&endpoint, &self.pollee)
     }
@@ -153,9 +418,9 @@ impl Socket for DatagramSocket {
             .inner
             .read()
             .addr()
-            .unwrap_or(UNSPECIFIED_LOCAL_ENDPOINT);
+            .unwrap_or(unspecified_local_endpoint(self.ip_version));
 
-        Ok(endpoint.into())
+        Ok(endpoint_to_socket_addr(endpoint, self.ip_version))
     }
 
     fn peer_addr(&self) -> Result<SocketAddr> {
@@ -164,7 +429,7 @@ impl Socket for DatagramSocket {
                 Error::with_message(Errno::ENOTCONN, "the socket is not connected")
             })?;
 
-        Ok(endpoint.into())
+        Ok(endpoint_to_socket_addr(endpoint, self.ip_version))
     }
 
     fn sendmsg(
@@ -173,10 +438,13 @@ impl Socket for DatagramSocket {
         message_header: MessageHeader,
         flags: SendRecvFlags,
     ) -> Result<usize> {
-        // TODO: Deal with flags
+        // TODO: Support corking datagrams with `MSG_MORE`
         if !flags.is_all_supported() {
             warn!("unsupported flags: {:?}", flags);
         }
+        if flags.contains(SendRecvFlags::MSG_OOB) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "UDP sockets do not support MSG_OOB");
+        }
 
         let MessageHeader {
             addr,
@@ -184,17 +452,14 @@ impl Socket for DatagramSocket {
         } = message_header;
 
         let endpoint = match addr {
-            Some(addr) => Some(addr.try_into()?),
+            Some(addr) => Some(self.socket_addr_to_endpoint(addr)?),
             None => None,
         };
 
-        if !control_messages.is_empty() {
-            // TODO: Support sending control message
-            warn!("sending control message is not supported");
-        }
+        let send_options = IpSendOptions::from_control(&control_messages);
 
         // TODO: Block if the send buffer is full
-        self.try_send(reader, endpoint.as_ref(), flags)
+        self.try_send(reader, endpoint.as_ref(), &send_options, flags)
     }
 
     fn recvmsg(
@@ -202,19 +467,19 @@ impl Socket for DatagramSocket {
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
     ) -> Result<(usize, MessageHeader)> {
-        // TODO: Deal with flags
-        if !flags.is_all_supported() {
+        if !flags
+            .sub(SendRecvFlags::MSG_PEEK | SendRecvFlags::MSG_TRUNC | SendRecvFlags::MSG_ERRQUEUE)
+            .is_all_supported()
+        {
             warn!("unsupported flags: {:?}", flags);
         }
 
-        let (received_bytes, peer_addr) =
-            self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
-
-        // TODO: Receive control message
-
-        let message_header = MessageHeader::new(Some(peer_addr), Vec::new());
+        // Receiving errors from the error queue never blocks.
+        if flags.contains(SendRecvFlags::MSG_ERRQUEUE) {
+            return self.recv_error(writer);
+        }
 
-        Ok((received_bytes, message_header))
+        self.block_on_with_flags(IoEvents::IN, flags, || self.try_recv(writer, flags))
     }
 
     fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
@@ -228,14 +493,65 @@ impl Socket for DatagramSocket {
         });
 
         let inner = self.inner.read();
-        self.options.read().socket.get_option(option, &*inner)
+        let options = self.options.read();
+
+        // Deal with socket-level options
+        match options.socket.get_option(option, &*inner) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        // Deal with IP-level options
+        match options.ip.get_option(option) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        // Deal with IPv6-level options
+        Ipv6OptionSet::check_get_option(option, self.ip_version)?;
+        options.ipv6.get_option(option)
     }
 
     fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            attach_filter: AttachFilter => {
+                let filter = attach_filter.get().unwrap();
+                self.set_filter(Some(filter.clone()));
+                return Ok(());
+            },
+            _detach_filter: DetachFilter => {
+                if self.set_filter(None).is_none() {
+                    return_errno_with_message!(Errno::ENOENT, "no filter is attached");
+                }
+                return Ok(());
+            },
+            add_membership: AddMembership => {
+                return self.add_membership(add_membership.get().unwrap());
+            },
+            drop_membership: DropMembership => {
+                return self.drop_membership(drop_membership.get().unwrap());
+            },
+            _ => ()
+        });
+
         let inner = self.inner.read();
         let mut options = self.options.write();
 
-        match options.socket.set_option(option, &*inner) {
+        let res = match options.socket.set_option(option, &*inner) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => {
+                // Deal with IP-level options
+                match options.ip.set_option(option, self) {
+                    Err(err) if err.error() == Errno::ENOPROTOOPT => {
+                        // Deal with IPv6-level options
+                        do_ipv6_setsockopt(option, &mut options, &*inner, self.ip_version)
+                    }
+                    res => res,
+                }
+            }
+            res => res,
+        };
+
+        match res {
             Err(e) => Err(e),
             Ok(need_iface_poll) => {
                 let iface_to_poll = need_iface_poll
@@ -262,6 +578,59 @@ impl Socket for DatagramSocket {
     }
 }
 
+impl Drop for DatagramSocket {
+    fn drop(&mut self) {
+        for membership in self.memberships.get_mut().drain(..) {
+            leave_membership(&membership);
+        }
+    }
+}
+
+fn leave_membership(membership: &Membership) {
+    let need_iface_poll = membership.iface.leave_multicast_group(membership.group);
+    if *need_iface_poll {
+        membership.iface.poll();
+    }
+}
+
+fn do_ipv6_setsockopt(
+    option: &dyn SocketOption,
+    options: &mut OptionSet,
+    inner: &Inner<UnboundDatagram, BoundDatagram>,
+    ip_version: IpVersion,
+) -> Result<NeedIfacePoll> {
+    if ip_version != IpVersion::Ipv6 {
+        return_errno_with_message!(Errno::ENOPROTOOPT, "the socket is not an IPv6 socket");
+    }
+
+    options.ipv6.set_option(option, inner)
+}
+
+impl SetIpLevelOption for DatagramSocket {
+    fn set_hdrincl(&self, _hdrincl: bool) -> Result<()> {
+        return_errno_with_message!(
+            Errno::ENOPROTOOPT,
+            "IP_HDRINCL cannot be set on UDP sockets"
+        );
+    }
+
+    fn check_multicast_if(&self, multicast_if: &CIpMreqn) -> Result<()> {
+        // Resetting the iface is always allowed.
+        if multicast_if.imr_ifindex == 0 && multicast_if.address().is_unspecified() {
+            return Ok(());
+        }
+
+        if self
+            .find_multicast_iface(multicast_if, Ipv4Address::UNSPECIFIED, false)
+            .is_none()
+        {
+            return_errno_with_message!(Errno::EADDRNOTAVAIL, "the interface does not exist");
+        }
+
+        Ok(())
+    }
+}
+
 impl GetSocketLevelOption for Inner<UnboundDatagram, BoundDatagram> {
     fn is_listening(&self) -> bool {
         false
@@ -277,3 +646,16 @@ impl SetSocketLevelOption for Inner<UnboundDatagram, BoundDatagram> {
         bound.bound_port().set_can_reuse(reuse_addr);
     }
 }
+
+impl SetIpv6LevelOption for Inner<UnboundDatagram, BoundDatagram> {
+    fn set_v6only(&self, _v6only: bool) -> Result<()> {
+        if matches!(self, Inner::Unbound(_)) {
+            return Ok(());
+        }
+
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "IPV6_V6ONLY cannot be set after the socket is bound"
+        );
+    }
+}
@@ -5,26 +5,42 @@ use aster_bigtcp::{socket::UdpSocket, wire::IpEndpoint};
 use super::{bound::BoundDatagram, observer::DatagramObserver};
 use crate::{
     events::IoEvents,
-    net::socket::{
-        ip::common::{bind_port, get_ephemeral_endpoint},
-        util::datagram_common,
+    net::{
+        socket::{
+            ip::common::{bind_port, get_ephemeral_endpoint},
+            util::{datagram_common, SocketFilter},
+        },
+        NetNamespace,
     },
     prelude::*,
     process::signal::Pollee,
 };
 
 pub(super) struct UnboundDatagram {
-    _private: (),
+    net_ns: Arc<NetNamespace>,
+    filter: Option<Arc<SocketFilter>>,
 }
 
 impl UnboundDatagram {
-    pub(super) fn new() -> Self {
-        Self { _private: () }
+    pub(super) fn new(net_ns: Arc<NetNamespace>) -> Self {
+        Self {
+            net_ns,
+            filter: None,
+        }
+    }
+
+    /// Sets the filter for incoming packets and returns the old one.
+    pub(super) fn set_filter(
+        &mut self,
+        filter: Option<Arc<SocketFilter>>,
+    ) -> Option<Arc<SocketFilter>> {
+        core::mem::replace(&mut self.filter, filter)
     }
 }
 
 pub(super) struct BindOptions {
     pub(super) can_reuse: bool,
+    pub(super) is_v6only: bool,
 }
 
 impl datagram_common::Unbound for UnboundDatagram {
@@ -39,7 +55,7 @@ impl datagram_common::Unbound for UnboundDatagram {
         pollee: &Pollee,
         options: BindOptions,
     ) -> Result<Self::Bound> {
-        let bound_port = bind_port(endpoint, options.can_reuse)?;
+        let bound_port = bind_port(&self.net_ns, endpoint, options.can_reuse, options.is_v6only)?;
 
         let bound_socket =
             match UdpSocket::new_bind(bound_port, DatagramObserver::new(pollee.clone())) {
@@ -49,7 +65,10 @@ impl datagram_common::Unbound for UnboundDatagram {
                 }
             };
 
-        Ok(BoundDatagram::new(bound_socket))
+        let mut bound_datagram = BoundDatagram::new(bound_socket);
+        bound_datagram.set_filter(self.filter.clone());
+
+        Ok(bound_datagram)
     }
 
     fn bind_ephemeral(
@@ -57,8 +76,15 @@ impl datagram_common::Unbound for UnboundDatagram {
         remote_endpoint: &Self::Endpoint,
         pollee: &Pollee,
     ) -> Result<Self::Bound> {
-        let endpoint = get_ephemeral_endpoint(remote_endpoint);
-        self.bind(&endpoint, pollee, BindOptions { can_reuse: false })
+        let endpoint = get_ephemeral_endpoint(&self.net_ns, remote_endpoint)?;
+        self.bind(
+            &endpoint,
+            pollee,
+            BindOptions {
+                can_reuse: false,
+                is_v6only: false,
+            },
+        )
     }
 
     fn check_io_events(&self) -> IoEvents {
@@ -2,11 +2,17 @@
 
 mod addr;
 mod common;
+mod ctrl_msg;
 mod datagram;
 pub mod options;
+mod ping;
+mod raw;
 mod stream;
 
+pub(super) use ctrl_msg::IpControlMessage;
 pub(in crate::net) use datagram::observer::DatagramObserver;
 pub use datagram::DatagramSocket;
+pub use ping::PingSocket;
+pub use raw::RawSocket;
 pub(in crate::net) use stream::observer::StreamObserver;
 pub use stream::{options as stream_options, StreamSocket};
@@ -2,7 +2,10 @@
 
 use core::num::NonZeroU8;
 
-use aster_bigtcp::socket::NeedIfacePoll;
+use aster_bigtcp::{
+    socket::NeedIfacePoll,
+    wire::{IpVersion, Ipv4Address},
+};
 
 use crate::{
     impl_socket_options, match_sock_option_mut, match_sock_option_ref,
@@ -17,17 +20,44 @@ pub(super) struct IpOptionSet {
     tos: u8,
     ttl: IpTtl,
     hdrincl: bool,
+    multicast_if: CIpMreqn,
+    multicast_ttl: u8,
+    multicast_loop: bool,
+    pkt_info: bool,
+    recv_ttl: bool,
+    recv_tos: bool,
+    recv_err: bool,
 }
 
 const DEFAULT_TTL: u8 = 64;
+const DEFAULT_MULTICAST_TTL: u8 = 1;
 pub(super) const INET_ECN_MASK: u8 = 3;
 
 impl IpOptionSet {
     pub(super) const fn new_tcp() -> Self {
+        Self::new(false)
+    }
+
+    pub(super) const fn new_udp() -> Self {
+        Self::new(false)
+    }
+
+    pub(super) const fn new_raw(hdrincl: bool) -> Self {
+        Self::new(hdrincl)
+    }
+
+    const fn new(hdrincl: bool) -> Self {
         Self {
             tos: 0,
             ttl: IpTtl(None),
-            hdrincl: false,
+            hdrincl,
+            multicast_if: CIpMreqn::new_unspecified(),
+            multicast_ttl: DEFAULT_MULTICAST_TTL,
+            multicast_loop: true,
+            pkt_info: false,
+            recv_ttl: false,
+            recv_tos: false,
+            recv_err: false,
         }
     }
 
@@ -45,6 +75,34 @@ impl IpOptionSet {
                 let hdrincl = self.hdrincl();
                 ip_hdrincl.set(hdrincl);
             },
+            ip_multicast_if: MulticastIf => {
+                let multicast_if = self.multicast_if();
+                ip_multicast_if.set(multicast_if);
+            },
+            ip_multicast_ttl: MulticastTtl => {
+                let multicast_ttl = self.multicast_ttl();
+                ip_multicast_ttl.set(multicast_ttl as _);
+            },
+            ip_multicast_loop: MulticastLoop => {
+                let multicast_loop = self.multicast_loop();
+                ip_multicast_loop.set(multicast_loop);
+            },
+            ip_pkt_info: PktInfo => {
+                let pkt_info = self.pkt_info();
+                ip_pkt_info.set(pkt_info);
+            },
+            ip_recv_ttl: RecvTtl => {
+                let recv_ttl = self.recv_ttl();
+                ip_recv_ttl.set(recv_ttl);
+            },
+            ip_recv_tos: RecvTos => {
+                let recv_tos = self.recv_tos();
+                ip_recv_tos.set(recv_tos);
+            },
+            ip_recv_err: RecvErr => {
+                let recv_err = self.recv_err();
+                ip_recv_err.set(recv_err);
+            },
             _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown")
         });
 
@@ -73,6 +131,39 @@ impl IpOptionSet {
                 socket.set_hdrincl(*hdrincl)?;
                 self.set_hdrincl(*hdrincl);
             },
+            ip_multicast_if: MulticastIf => {
+                let multicast_if = ip_multicast_if.get().unwrap();
+                socket.check_multicast_if(multicast_if)?;
+                self.set_multicast_if(*multicast_if);
+            },
+            ip_multicast_ttl: MulticastTtl => {
+                let multicast_ttl = match *ip_multicast_ttl.get().unwrap() {
+                    -1 => DEFAULT_MULTICAST_TTL,
+                    val @ 0..=255 => val as u8,
+                    _ => return_errno_with_message!(Errno::EINVAL, "the multicast TTL is out of bounds"),
+                };
+                self.set_multicast_ttl(multicast_ttl);
+            },
+            ip_multicast_loop: MulticastLoop => {
+                let multicast_loop = ip_multicast_loop.get().unwrap();
+                self.set_multicast_loop(*multicast_loop);
+            },
+            ip_pkt_info: PktInfo => {
+                let pkt_info = ip_pkt_info.get().unwrap();
+                self.set_pkt_info(*pkt_info);
+            },
+            ip_recv_ttl: RecvTtl => {
+                let recv_ttl = ip_recv_ttl.get().unwrap();
+                self.set_recv_ttl(*recv_ttl);
+            },
+            ip_recv_tos: RecvTos => {
+                let recv_tos = ip_recv_tos.get().unwrap();
+                self.set_recv_tos(*recv_tos);
+            },
+            ip_recv_err: RecvErr => {
+                let recv_err = ip_recv_err.get().unwrap();
+                self.set_recv_err(*recv_err);
+            },
             _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
         });
 
@@ -84,6 +175,154 @@ impl_socket_options!(
     pub struct Tos(i32);
     pub struct Ttl(IpTtl);
     pub struct Hdrincl(bool);
+    pub struct MulticastIf(CIpMreqn);
+    pub struct MulticastTtl(i32);
+    pub struct MulticastLoop(bool);
+    pub struct AddMembership(CIpMreqn);
+    pub struct DropMembership(CIpMreqn);
+    pub struct PktInfo(bool);
+    pub struct RecvTtl(bool);
+    pub struct RecvTos(bool);
+    pub struct RecvErr(bool);
+);
+
+/// A request to join or leave an IPv4 multicast group, or to select the iface for outgoing
+/// multicast packets.
+///
+/// This corresponds to `struct ip_mreqn` in Linux. The shorter `struct ip_mreq` is also accepted
+/// from the user space, in which case the interface index is zero.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, PartialEq, Eq)]
+pub struct CIpMreqn {
+    /// The IPv4 address of the multicast group.
+    pub imr_multiaddr: [u8; 4],
+    /// The IPv4 address of the local iface.
+    pub imr_address: [u8; 4],
+    /// The interface index.
+    pub imr_ifindex: i32,
+}
+
+impl CIpMreqn {
+    pub(super) const fn new_unspecified() -> Self {
+        Self {
+            imr_multiaddr: [0; 4],
+            imr_address: [0; 4],
+            imr_ifindex: 0,
+        }
+    }
+
+    pub(super) fn multiaddr(&self) -> Ipv4Address {
+        Ipv4Address::from(self.imr_multiaddr)
+    }
+
+    pub(super) fn address(&self) -> Ipv4Address {
+        Ipv4Address::from(self.imr_address)
+    }
+}
+
+/// IPv6-level socket options.
+#[derive(Debug, Clone, Copy, CopyGetters, Setters)]
+#[get_copy = "pub"]
+#[set = "pub"]
+pub(super) struct Ipv6OptionSet {
+    v6only: bool,
+    unicast_hops: Option<u8>,
+    tclass: u8,
+}
+
+const DEFAULT_HOP_LIMIT: u8 = 64;
+
+impl Ipv6OptionSet {
+    pub(super) const fn new() -> Self {
+        Self {
+            v6only: false,
+            unicast_hops: None,
+            tclass: 0,
+        }
+    }
+
+    /// Checks whether `option` is an IPv6-level option that can be got from a socket of
+    /// `ip_version`.
+    ///
+    /// Getting IPv6-level options from IPv4 sockets fails with [`Errno::EOPNOTSUPP`] (see
+    /// `ip_getsockopt` in Linux). Other options fail with [`Errno::ENOPROTOOPT`].
+    pub(super) fn check_get_option(option: &dyn SocketOption, ip_version: IpVersion) -> Result<()> {
+        let is_ipv6_option = match_sock_option_ref!(option, {
+            _ipv6_v6only: V6Only => true,
+            _ipv6_unicast_hops: UnicastHops => true,
+            _ipv6_tclass: Tclass => true,
+            _ => false
+        });
+
+        match (is_ipv6_option, ip_version) {
+            (true, IpVersion::Ipv6) => Ok(()),
+            (true, IpVersion::Ipv4) => {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "the socket is not an IPv6 socket")
+            }
+            (false, _) => {
+                return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown")
+            }
+        }
+    }
+
+    pub(super) fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            ipv6_v6only: V6Only => {
+                let v6only = self.v6only();
+                ipv6_v6only.set(v6only);
+            },
+            ipv6_unicast_hops: UnicastHops => {
+                let hops = self.unicast_hops().unwrap_or(DEFAULT_HOP_LIMIT);
+                ipv6_unicast_hops.set(hops as _);
+            },
+            ipv6_tclass: Tclass => {
+                let tclass = self.tclass();
+                ipv6_tclass.set(tclass as _);
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown")
+        });
+
+        Ok(())
+    }
+
+    pub(super) fn set_option(
+        &mut self,
+        option: &dyn SocketOption,
+        socket: &dyn SetIpv6LevelOption,
+    ) -> Result<NeedIfacePoll> {
+        match_sock_option_ref!(option, {
+            ipv6_v6only: V6Only => {
+                let v6only = ipv6_v6only.get().unwrap();
+                socket.set_v6only(*v6only)?;
+                self.set_v6only(*v6only);
+            },
+            ipv6_unicast_hops: UnicastHops => {
+                let hops = match *ipv6_unicast_hops.get().unwrap() {
+                    -1 => None,
+                    val @ 0..=255 => Some(val as u8),
+                    _ => return_errno_with_message!(Errno::EINVAL, "the hop limit is out of bounds"),
+                };
+                self.set_unicast_hops(hops);
+            },
+            ipv6_tclass: Tclass => {
+                let tclass = match *ipv6_tclass.get().unwrap() {
+                    -1 => 0,
+                    val @ 0..=255 => val as u8,
+                    _ => return_errno_with_message!(Errno::EINVAL, "the traffic class is out of bounds"),
+                };
+                self.set_tclass(tclass);
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
+        });
+
+        Ok(NeedIfacePoll::FALSE)
+    }
+}
+
+impl_socket_options!(
+    pub struct V6Only(bool);
+    pub struct UnicastHops(i32);
+    pub struct Tclass(i32);
 );
 
 #[derive(Debug, Clone, Copy)]
@@ -105,4 +344,12 @@ impl IpTtl {
 
 pub(super) trait SetIpLevelOption {
     fn set_hdrincl(&self, _hdrincl: bool) -> Result<()>;
+
+    fn check_multicast_if(&self, _multicast_if: &CIpMreqn) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub(super) trait SetIpv6LevelOption {
+    fn set_v6only(&self, _v6only: bool) -> Result<()>;
 }
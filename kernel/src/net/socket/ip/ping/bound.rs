@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::{
+    errors::icmp::{RecvError, SendError},
+    socket::IcmpError,
+    wire::{Icmpv4DstUnreachable, IpAddress, IpEndpoint},
+};
+
+use crate::{
+    events::IoEvents,
+    net::{
+        iface::{BoundPort, IcmpSocket, Iface},
+        socket::util::{datagram_common, SendRecvFlags},
+    },
+    prelude::*,
+    util::{MultiRead, MultiWrite},
+};
+
+pub(super) struct BoundPing {
+    bound_socket: IcmpSocket,
+    remote_endpoint: Option<IpEndpoint>,
+}
+
+/// The hop limit of outgoing echo requests.
+//
+// TODO: Support `IP_TTL` for ping sockets.
+const DEFAULT_HOP_LIMIT: u8 = 64;
+
+impl BoundPing {
+    pub(super) fn new(bound_socket: IcmpSocket) -> Self {
+        Self {
+            bound_socket,
+            remote_endpoint: None,
+        }
+    }
+
+    pub(super) fn iface(&self) -> &Arc<Iface> {
+        self.bound_socket.iface()
+    }
+
+    pub(super) fn bound_port(&self) -> &BoundPort {
+        self.bound_socket.bound_port()
+    }
+
+    pub(super) fn test_and_clear_error(&self) -> Option<Error> {
+        self.bound_socket.take_error().map(Error::from)
+    }
+}
+
+impl datagram_common::Bound for BoundPing {
+    type Endpoint = IpEndpoint;
+
+    fn local_endpoint(&self) -> Self::Endpoint {
+        self.bound_socket.local_endpoint().unwrap()
+    }
+
+    fn remote_endpoint(&self) -> Option<&Self::Endpoint> {
+        self.remote_endpoint.as_ref()
+    }
+
+    fn set_remote_endpoint(&mut self, endpoint: &Self::Endpoint) {
+        self.remote_endpoint = Some(*endpoint)
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        _flags: SendRecvFlags,
+    ) -> Result<(usize, Self::Endpoint)> {
+        if let Some(error) = self.test_and_clear_error() {
+            return Err(error);
+        }
+
+        let result = self.bound_socket.recv(|packet, src_addr| {
+            let copied_res = writer.write(&mut VmReader::from(packet));
+            // Linux reports the source address of echo replies with a zero port number.
+            let endpoint = IpEndpoint::new(src_addr, 0);
+            (copied_res, endpoint)
+        });
+
+        match result {
+            Ok((Ok(res), endpoint)) => Ok((res, endpoint)),
+            Ok((Err(e), _)) => Err(e),
+            Err(RecvError::Exhausted) => {
+                return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty")
+            }
+        }
+    }
+
+    fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote: &Self::Endpoint,
+        _flags: SendRecvFlags,
+    ) -> Result<usize> {
+        let IpAddress::Ipv4(dst_addr) = remote.addr else {
+            return_errno_with_message!(Errno::EAFNOSUPPORT, "the address is not an IPv4 address");
+        };
+
+        let mut packet = vec![0u8; reader.sum_lens()];
+        let len = reader.read(&mut VmWriter::from(packet.as_mut_slice()))?;
+
+        match self
+            .bound_socket
+            .send(dst_addr, DEFAULT_HOP_LIMIT, &packet[..len])
+        {
+            Ok(()) => Ok(len),
+            Err(SendError::Malformed) => {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the message is not a valid ICMP echo request"
+                );
+            }
+            Err(SendError::TooLarge) => {
+                return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+            }
+            Err(SendError::Unaddressable) => {
+                return_errno_with_message!(Errno::EINVAL, "the source address is invalid");
+            }
+            Err(SendError::BufferFull) => {
+                return_errno_with_message!(Errno::EAGAIN, "the send buffer is full");
+            }
+        }
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::empty();
+
+        if self.bound_socket.can_recv() {
+            events |= IoEvents::IN;
+        }
+
+        if self.bound_socket.can_send() {
+            events |= IoEvents::OUT;
+        }
+
+        if self.bound_socket.has_error() {
+            events |= IoEvents::ERR;
+        }
+
+        events
+    }
+}
+
+impl From<IcmpError> for Error {
+    // This follows `icmp_err_convert` in Linux. See
+    // <https://elixir.bootlin.com/linux/v6.0.9/source/net/ipv4/icmp.c#L120>.
+    fn from(value: IcmpError) -> Self {
+        match value {
+            IcmpError::DstUnreachable(reason) => {
+                let errno = match reason {
+                    Icmpv4DstUnreachable::NetUnreachable
+                    | Icmpv4DstUnreachable::DstNetUnknown
+                    | Icmpv4DstUnreachable::NetProhibited
+                    | Icmpv4DstUnreachable::NetUnreachToS => Errno::ENETUNREACH,
+                    Icmpv4DstUnreachable::ProtoUnreachable => Errno::ENOPROTOOPT,
+                    Icmpv4DstUnreachable::PortUnreachable => Errno::ECONNREFUSED,
+                    Icmpv4DstUnreachable::FragRequired => Errno::EMSGSIZE,
+                    Icmpv4DstUnreachable::SrcRouteFailed => Errno::EOPNOTSUPP,
+                    Icmpv4DstUnreachable::DstHostUnknown => Errno::EHOSTDOWN,
+                    Icmpv4DstUnreachable::SrcHostIsolated => Errno::ENONET,
+                    _ => Errno::EHOSTUNREACH,
+                };
+                Error::with_message(errno, "the destination is unreachable")
+            }
+            IcmpError::TimeExceeded => {
+                Error::with_message(Errno::EHOSTUNREACH, "the time to live is exceeded")
+            }
+            IcmpError::ParamProblem => {
+                Error::with_message(Errno::EPROTO, "the remote host reports a parameter problem")
+            }
+        }
+    }
+}
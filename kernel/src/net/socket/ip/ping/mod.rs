@@ -0,0 +1,279 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_bigtcp::{
+    socket::NeedIfacePoll,
+    wire::{IpEndpoint, IpVersion},
+};
+use bound::BoundPing;
+use unbound::UnboundPing;
+
+use super::addr::{endpoint_to_socket_addr, socket_addr_to_endpoint, unspecified_local_endpoint};
+use crate::{
+    events::IoEvents,
+    fs::utils::Inode,
+    match_sock_option_mut,
+    net::{
+        socket::{
+            new_pseudo_inode,
+            options::{Error as SocketError, SocketOption},
+            private::SocketPrivate,
+            util::{
+                datagram_common::{select_remote_and_bind, Bound, Inner},
+                options::{GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet},
+                MessageHeader, SendRecvFlags, SocketAddr,
+            },
+            Socket,
+        },
+        NetNamespace,
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    util::{MultiRead, MultiWrite},
+};
+
+mod bound;
+mod unbound;
+
+/// An ICMP socket that sends echo requests and receives echo replies.
+///
+/// Unlike raw sockets, ping sockets can be created without the `CAP_NET_RAW` capability. See
+/// <https://lwn.net/Articles/422330/> for details.
+//
+// TODO: Support ICMPv6 ping sockets.
+pub struct PingSocket {
+    inner: RwMutex<Inner<UnboundPing, BoundPing>>,
+    options: RwLock<SocketOptionSet>,
+
+    is_nonblocking: AtomicBool,
+    pollee: Pollee,
+    pseudo_inode: Arc<dyn Inode>,
+}
+
+impl PingSocket {
+    pub fn new(is_nonblocking: bool, net_ns: Arc<NetNamespace>) -> Arc<Self> {
+        let unbound_ping = UnboundPing::new(net_ns);
+        Arc::new(Self {
+            inner: RwMutex::new(Inner::Unbound(unbound_ping)),
+            options: RwLock::new(SocketOptionSet::new_udp()),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            pollee: Pollee::new(),
+            pseudo_inode: new_pseudo_inode(),
+        })
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, SocketAddr)> {
+        let (recv_bytes, remote_endpoint) = self.inner.read().try_recv(writer, flags)?;
+        self.pollee.invalidate();
+
+        let remote_addr = endpoint_to_socket_addr(remote_endpoint, IpVersion::Ipv4);
+        Ok((recv_bytes, remote_addr))
+    }
+
+    fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote: Option<&IpEndpoint>,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        let (sent_bytes, iface_to_poll) = select_remote_and_bind(
+            &self.inner,
+            remote,
+            || {
+                let remote_endpoint = remote.ok_or_else(|| {
+                    Error::with_message(
+                        Errno::EDESTADDRREQ,
+                        "the destination address is not specified",
+                    )
+                })?;
+                self.inner
+                    .write()
+                    .bind_ephemeral(remote_endpoint, &self.pollee)
+            },
+            |bound_ping, remote_endpoint| {
+                let sent_bytes = bound_ping.try_send(reader, remote_endpoint, flags)?;
+                let iface_to_poll = bound_ping.iface().clone();
+                Ok((sent_bytes, iface_to_poll))
+            },
+        )?;
+
+        self.pollee.invalidate();
+        iface_to_poll.poll();
+
+        Ok(sent_bytes)
+    }
+
+    fn test_and_clear_error(&self) -> Option<Error> {
+        match &*self.inner.read() {
+            Inner::Unbound(_) => None,
+            Inner::Bound(bound_ping) => bound_ping.test_and_clear_error(),
+        }
+    }
+}
+
+impl Pollable for PingSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.inner.read().check_io_events())
+    }
+}
+
+impl SocketPrivate for PingSocket {
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+}
+
+impl Socket for PingSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let endpoint = socket_addr_to_endpoint(socket_addr, IpVersion::Ipv4, false)?;
+
+        self.inner.write().bind(&endpoint, &self.pollee, ())
+    }
+
+    fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
+        let endpoint = socket_addr_to_endpoint(socket_addr, IpVersion::Ipv4, false)?;
+
+        self.inner.write().connect(&endpoint, &self.pollee)
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let endpoint = self
+            .inner
+            .read()
+            .addr()
+            .unwrap_or(unspecified_local_endpoint(IpVersion::Ipv4));
+
+        Ok(endpoint_to_socket_addr(endpoint, IpVersion::Ipv4))
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        let endpoint =
+            *self.inner.read().peer_addr().ok_or_else(|| {
+                Error::with_message(Errno::ENOTCONN, "the socket is not connected")
+            })?;
+
+        Ok(endpoint_to_socket_addr(endpoint, IpVersion::Ipv4))
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let endpoint = match addr {
+            Some(addr) => Some(socket_addr_to_endpoint(addr, IpVersion::Ipv4, false)?),
+            None => None,
+        };
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        // TODO: Block if the send buffer is full
+        self.try_send(reader, endpoint.as_ref(), flags)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (received_bytes, peer_addr) =
+            self.block_on_with_flags(IoEvents::IN | IoEvents::ERR, flags, || {
+                self.try_recv(writer, flags)
+            })?;
+
+        // TODO: Receive control message
+
+        let message_header = MessageHeader::new(Some(peer_addr), Vec::new());
+
+        Ok((received_bytes, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                socket_errors.set(self.test_and_clear_error());
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        let inner = self.inner.read();
+        let options = self.options.read();
+
+        // Deal with socket-level options
+        options.get_option(option, &*inner)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        let inner = self.inner.read();
+        let mut options = self.options.write();
+
+        // Deal with socket-level options
+        let need_iface_poll = options.set_option(option, &*inner)?;
+
+        let iface_to_poll = need_iface_poll
+            .then(|| match &*inner {
+                Inner::Unbound(_) => None,
+                Inner::Bound(bound_ping) => Some(bound_ping.iface().clone()),
+            })
+            .flatten();
+
+        drop(inner);
+        drop(options);
+
+        if let Some(iface) = iface_to_poll {
+            iface.poll();
+        }
+
+        Ok(())
+    }
+
+    fn pseudo_inode(&self) -> &Arc<dyn Inode> {
+        &self.pseudo_inode
+    }
+}
+
+impl GetSocketLevelOption for Inner<UnboundPing, BoundPing> {
+    fn is_listening(&self) -> bool {
+        false
+    }
+}
+
+impl SetSocketLevelOption for Inner<UnboundPing, BoundPing> {
+    fn set_reuse_addr(&self, reuse_addr: bool) {
+        let Inner::Bound(bound) = self else {
+            return;
+        };
+
+        bound.bound_port().set_can_reuse(reuse_addr);
+    }
+}
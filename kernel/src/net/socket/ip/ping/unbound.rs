@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::IpEndpoint;
+
+use super::bound::BoundPing;
+use crate::{
+    events::IoEvents,
+    net::{
+        iface::IcmpSocket,
+        socket::{
+            ip::{
+                common::{bind_port, get_ephemeral_endpoint},
+                DatagramObserver,
+            },
+            util::datagram_common,
+        },
+        NetNamespace,
+    },
+    prelude::*,
+    process::signal::Pollee,
+};
+
+pub(super) struct UnboundPing {
+    net_ns: Arc<NetNamespace>,
+}
+
+impl UnboundPing {
+    pub(super) fn new(net_ns: Arc<NetNamespace>) -> Self {
+        Self { net_ns }
+    }
+}
+
+impl datagram_common::Unbound for UnboundPing {
+    type Endpoint = IpEndpoint;
+    type BindOptions = ();
+
+    type Bound = BoundPing;
+
+    fn bind(
+        &mut self,
+        endpoint: &Self::Endpoint,
+        pollee: &Pollee,
+        _options: (),
+    ) -> Result<Self::Bound> {
+        let bound_port = bind_port(&self.net_ns, endpoint, false, false)?;
+
+        let bound_socket = IcmpSocket::new_bind(bound_port, DatagramObserver::new(pollee.clone()));
+
+        Ok(BoundPing::new(bound_socket))
+    }
+
+    fn bind_ephemeral(
+        &mut self,
+        remote_endpoint: &Self::Endpoint,
+        pollee: &Pollee,
+    ) -> Result<Self::Bound> {
+        let endpoint = get_ephemeral_endpoint(&self.net_ns, remote_endpoint)?;
+        self.bind(&endpoint, pollee, ())
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        IoEvents::OUT
+    }
+}
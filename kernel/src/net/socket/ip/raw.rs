@@ -0,0 +1,408 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_bigtcp::{
+    errors::raw::{RecvError, SendError},
+    wire::{IpAddress, IpProtocol, IpVersion, Ipv4Address},
+};
+
+use super::{
+    addr::socket_addr_to_endpoint,
+    common::{get_ephemeral_iface, get_iface_to_bind},
+    options::{IpOptionSet, SetIpLevelOption},
+    DatagramObserver,
+};
+use crate::{
+    events::IoEvents,
+    fs::utils::Inode,
+    match_sock_option_mut, match_sock_option_ref,
+    net::{
+        iface::RawIpSocket,
+        socket::{
+            new_pseudo_inode,
+            options::{AttachFilter, DetachFilter, Error as SocketError, SocketOption},
+            private::SocketPrivate,
+            util::{
+                options::{GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet},
+                MessageHeader, SendRecvFlags, SocketAddr, SocketFilter,
+            },
+            Socket,
+        },
+        NetNamespace,
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    util::{MultiRead, MultiWrite},
+};
+
+/// A raw IPv4 socket.
+///
+/// A raw socket receives copies of all incoming IP packets whose protocol matches the protocol of
+/// the socket, including the IP headers. Creating a raw socket requires the `CAP_NET_RAW`
+/// capability.
+//
+// TODO: Support raw IPv6 sockets.
+pub struct RawSocket {
+    protocol: u8,
+    net_ns: Arc<NetNamespace>,
+    /// The underlying sockets, one for each iface.
+    //
+    // TODO: Ifaces that are added to the network namespace after the socket is created (e.g.,
+    // veth ifaces) are not covered.
+    sockets: Vec<RawIpSocket>,
+    addrs: RwMutex<Addrs>,
+    filter: Mutex<Option<Arc<SocketFilter>>>,
+    options: RwLock<OptionSet>,
+
+    is_nonblocking: AtomicBool,
+    pollee: Pollee,
+    pseudo_inode: Arc<dyn Inode>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Addrs {
+    local_addr: Option<Ipv4Address>,
+    remote_addr: Option<Ipv4Address>,
+}
+
+#[derive(Debug, Clone)]
+struct OptionSet {
+    socket: SocketOptionSet,
+    ip: IpOptionSet,
+}
+
+const IPPROTO_RAW: u8 = 255;
+
+impl RawSocket {
+    pub fn new(is_nonblocking: bool, protocol: u8, net_ns: Arc<NetNamespace>) -> Arc<Self> {
+        let pollee = Pollee::new();
+
+        let sockets = net_ns
+            .ifaces()
+            .iter()
+            .map(|iface| {
+                RawIpSocket::new(
+                    iface.clone(),
+                    IpProtocol::from(protocol),
+                    DatagramObserver::new(pollee.clone()),
+                )
+            })
+            .collect();
+
+        // `IPPROTO_RAW` implies that the IP header is included. See
+        // <https://man7.org/linux/man-pages/man7/raw.7.html>.
+        let options = OptionSet {
+            socket: SocketOptionSet::new_udp(),
+            ip: IpOptionSet::new_raw(protocol == IPPROTO_RAW),
+        };
+
+        Arc::new(Self {
+            protocol,
+            net_ns,
+            sockets,
+            addrs: RwMutex::new(Addrs::default()),
+            filter: Mutex::new(None),
+            options: RwLock::new(options),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            pollee,
+            pseudo_inode: new_pseudo_inode(),
+        })
+    }
+
+    fn try_recv(&self, writer: &mut dyn MultiWrite) -> Result<(usize, SocketAddr)> {
+        for socket in self.sockets.iter() {
+            let result = socket.recv(|packet, src_addr| {
+                let copied_res = writer.write(&mut VmReader::from(packet));
+                (copied_res, src_addr)
+            });
+
+            let (copied_res, src_addr) = match result {
+                Ok(res) => res,
+                Err(RecvError::Exhausted) => continue,
+            };
+            self.pollee.invalidate();
+
+            let IpAddress::Ipv4(src_addr) = src_addr else {
+                unreachable!("raw sockets only receive IPv4 packets");
+            };
+            // Linux reports the source address of raw packets with a zero port number.
+            return Ok((copied_res?, SocketAddr::IPv4(src_addr, 0)));
+        }
+
+        return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty")
+    }
+
+    fn try_send(&self, reader: &mut dyn MultiRead, remote: Option<Ipv4Address>) -> Result<usize> {
+        let Some(dst_addr) = remote.or(self.addrs.read().remote_addr) else {
+            return_errno_with_message!(
+                Errno::EDESTADDRREQ,
+                "the destination address is not specified"
+            );
+        };
+
+        let iface = get_ephemeral_iface(&self.net_ns, &IpAddress::Ipv4(dst_addr))?;
+        let Some(socket) = self
+            .sockets
+            .iter()
+            .find(|socket| socket.iface().index() == iface.index())
+        else {
+            return_errno_with_message!(Errno::ENETUNREACH, "the interface is not available");
+        };
+
+        let mut packet = vec![0u8; reader.sum_lens()];
+        let len = reader.read(&mut VmWriter::from(packet.as_mut_slice()))?;
+
+        let ip_options = self.options.read().ip;
+        let result = if ip_options.hdrincl() || self.protocol == IPPROTO_RAW {
+            socket.send_with_header(&packet[..len])
+        } else {
+            socket.send(dst_addr, ip_options.ttl().get(), &packet[..len])
+        };
+
+        match result {
+            Ok(()) => (),
+            Err(SendError::Malformed) => {
+                return_errno_with_message!(Errno::EINVAL, "the IP header is invalid");
+            }
+            Err(SendError::TooLarge) => {
+                return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+            }
+            Err(SendError::Unaddressable) => {
+                return_errno_with_message!(Errno::EINVAL, "the source address is invalid");
+            }
+            Err(SendError::BufferFull) => {
+                return_errno_with_message!(Errno::EAGAIN, "the send buffer is full");
+            }
+        }
+
+        self.pollee.invalidate();
+        socket.iface().poll();
+
+        Ok(len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::empty();
+
+        if self.sockets.iter().any(|socket| socket.can_recv()) {
+            events |= IoEvents::IN;
+        }
+
+        if self.sockets.iter().any(|socket| socket.can_send()) {
+            events |= IoEvents::OUT;
+        }
+
+        events
+    }
+
+    /// Sets the filter for incoming packets and returns the old one.
+    fn set_filter(&self, filter: Option<Arc<SocketFilter>>) -> Option<Arc<SocketFilter>> {
+        let mut old_filter = self.filter.lock();
+
+        for socket in self.sockets.iter() {
+            socket.set_filter(filter.clone());
+        }
+
+        core::mem::replace(&mut *old_filter, filter)
+    }
+
+    fn socket_addr_to_ipv4_addr(&self, socket_addr: SocketAddr) -> Result<Ipv4Address> {
+        let endpoint = socket_addr_to_endpoint(socket_addr, IpVersion::Ipv4, false)?;
+        let IpAddress::Ipv4(addr) = endpoint.addr else {
+            unreachable!("IPv4 sockets only accept IPv4 addresses");
+        };
+        Ok(addr)
+    }
+}
+
+impl Pollable for RawSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl SocketPrivate for RawSocket {
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+}
+
+impl Socket for RawSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = self.socket_addr_to_ipv4_addr(socket_addr)?;
+
+        let local_addr = if addr.is_unspecified() {
+            None
+        } else if get_iface_to_bind(&self.net_ns, &IpAddress::Ipv4(addr)).is_some() {
+            Some(addr)
+        } else {
+            return_errno_with_message!(
+                Errno::EADDRNOTAVAIL,
+                "the address is not available from the local machine"
+            );
+        };
+
+        let mut addrs = self.addrs.write();
+        addrs.local_addr = local_addr;
+        for socket in self.sockets.iter() {
+            socket.set_local_addr(local_addr);
+        }
+
+        Ok(())
+    }
+
+    fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = self.socket_addr_to_ipv4_addr(socket_addr)?;
+
+        let mut addrs = self.addrs.write();
+        addrs.remote_addr = Some(addr);
+        for socket in self.sockets.iter() {
+            socket.set_remote_addr(Some(addr));
+        }
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let local_addr = self
+            .addrs
+            .read()
+            .local_addr
+            .unwrap_or(Ipv4Address::UNSPECIFIED);
+
+        // Linux reports the protocol as the port number of raw sockets.
+        Ok(SocketAddr::IPv4(local_addr, self.protocol as u16))
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        let Some(remote_addr) = self.addrs.read().remote_addr else {
+            return_errno_with_message!(Errno::ENOTCONN, "the socket is not connected");
+        };
+
+        Ok(SocketAddr::IPv4(remote_addr, 0))
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote = match addr {
+            Some(addr) => Some(self.socket_addr_to_ipv4_addr(addr)?),
+            None => None,
+        };
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        // TODO: Block if the send buffer is full
+        self.try_send(reader, remote)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (received_bytes, peer_addr) =
+            self.block_on_with_flags(IoEvents::IN, flags, || self.try_recv(writer))?;
+
+        // TODO: Receive control message
+
+        let message_header = MessageHeader::new(Some(peer_addr), Vec::new());
+
+        Ok((received_bytes, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                // TODO: Support socket errors for raw sockets
+                socket_errors.set(None);
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        let options = self.options.read();
+
+        // Deal with socket-level options
+        match options.socket.get_option(option, self) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        // Deal with IP-level options
+        options.ip.get_option(option)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            attach_filter: AttachFilter => {
+                let filter = attach_filter.get().unwrap();
+                self.set_filter(Some(filter.clone()));
+                return Ok(());
+            },
+            _detach_filter: DetachFilter => {
+                if self.set_filter(None).is_none() {
+                    return_errno_with_message!(Errno::ENOENT, "no filter is attached");
+                }
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        let mut options = self.options.write();
+
+        // Deal with socket-level options
+        match options.socket.set_option(option, self) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res.map(|_| ()),
+        }
+
+        // Deal with IP-level options
+        options.ip.set_option(option, self).map(|_| ())
+    }
+
+    fn pseudo_inode(&self) -> &Arc<dyn Inode> {
+        &self.pseudo_inode
+    }
+}
+
+impl GetSocketLevelOption for RawSocket {
+    fn is_listening(&self) -> bool {
+        false
+    }
+}
+
+impl SetSocketLevelOption for RawSocket {}
+
+impl SetIpLevelOption for RawSocket {
+    fn set_hdrincl(&self, _hdrincl: bool) -> Result<()> {
+        Ok(())
+    }
+}
@@ -72,14 +72,20 @@ impl ConnectedStream {
     pub(super) fn try_recv(
         &self,
         writer: &mut dyn MultiWrite,
-        _flags: SendRecvFlags,
+        flags: SendRecvFlags,
     ) -> Result<(usize, NeedIfacePoll)> {
-        let result = self.tcp_conn.recv(|socket_buffer| {
-            match writer.write(&mut VmReader::from(&*socket_buffer)) {
-                Ok(len) => (len, Ok(len)),
-                Err(e) => (0, Err(e)),
-            }
-        });
+        let result = if flags.contains(SendRecvFlags::MSG_PEEK) {
+            self.tcp_conn
+                .peek(|socket_buffer| writer.write(&mut VmReader::from(socket_buffer)))
+                .map(|res| (res, NeedIfacePoll::FALSE))
+        } else {
+            self.tcp_conn.recv(|socket_buffer| {
+                match writer.write(&mut VmReader::from(&*socket_buffer)) {
+                    Ok(len) => (len, Ok(len)),
+                    Err(e) => (0, Err(e)),
+                }
+            })
+        };
 
         match result {
             Ok((Ok(0), need_poll)) => {
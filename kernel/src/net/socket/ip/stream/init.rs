@@ -16,6 +16,7 @@ use crate::{
             ip::common::{bind_port, get_ephemeral_endpoint},
             util::SocketAddr,
         },
+        NetNamespace,
     },
     prelude::*,
 };
@@ -69,12 +70,18 @@ impl InitStream {
         }
     }
 
-    pub(super) fn bind(&mut self, endpoint: &IpEndpoint, can_reuse: bool) -> Result<()> {
+    pub(super) fn bind(
+        &mut self,
+        net_ns: &NetNamespace,
+        endpoint: &IpEndpoint,
+        can_reuse: bool,
+        is_v6only: bool,
+    ) -> Result<()> {
         if self.bound_port.is_some() {
             return_errno_with_message!(Errno::EINVAL, "the socket is already bound to an address");
         }
 
-        self.bound_port = Some(bind_port(endpoint, can_reuse)?);
+        self.bound_port = Some(bind_port(net_ns, endpoint, can_reuse, is_v6only)?);
 
         Ok(())
     }
@@ -85,6 +92,7 @@ impl InitStream {
 
     pub(super) fn connect(
         self,
+        net_ns: &NetNamespace,
         remote_endpoint: &IpEndpoint,
         option: &RawTcpOption,
         can_reuse: bool,
@@ -98,8 +106,11 @@ impl InitStream {
         let bound_port = if let Some(bound_port) = self.bound_port {
             bound_port
         } else {
-            let endpoint = get_ephemeral_endpoint(remote_endpoint);
-            match bind_port(&endpoint, can_reuse) {
+            let endpoint = match get_ephemeral_endpoint(net_ns, remote_endpoint) {
+                Ok(endpoint) => endpoint,
+                Err(err) => return Err((err, self)),
+            };
+            match bind_port(net_ns, &endpoint, can_reuse, false) {
                 Ok(bound_port) => bound_port,
                 Err(err) => return Err((err, self)),
             }
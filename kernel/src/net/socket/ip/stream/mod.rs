@@ -4,7 +4,7 @@ use core::sync::atomic::{AtomicBool, Ordering};
 
 use aster_bigtcp::{
     socket::{NeedIfacePoll, RawTcpOption, RawTcpSetOption},
-    wire::IpEndpoint,
+    wire::{IpEndpoint, IpVersion},
 };
 use connected::{close_and_linger, ConnectedStream};
 use connecting::{ConnResult, ConnectingStream};
@@ -12,23 +12,23 @@ use init::InitStream;
 use listen::ListenStream;
 use observer::StreamObserver;
 use options::{
-    Congestion, DeferAccept, Inq, KeepIdle, MaxSegment, NoDelay, SynCnt, UserTimeout, WindowClamp,
-    KEEPALIVE_INTERVAL,
+    CTcpInfo, Congestion, DeferAccept, Inq, KeepIdle, MaxSegment, NoDelay, SynCnt, TcpInfo,
+    UserTimeout, WindowClamp, KEEPALIVE_INTERVAL,
 };
 use ostd::sync::{PreemptDisabled, RwLockReadGuard, RwLockWriteGuard};
 use takeable::Takeable;
 use util::{Retrans, TcpOptionSet};
 
 use super::{
-    addr::UNSPECIFIED_LOCAL_ENDPOINT,
-    options::{IpOptionSet, SetIpLevelOption},
+    addr::{endpoint_to_socket_addr, socket_addr_to_endpoint, unspecified_local_endpoint},
+    options::{IpOptionSet, Ipv6OptionSet, SetIpLevelOption, SetIpv6LevelOption},
 };
 use crate::{
     events::IoEvents,
     fs::{file_handle::FileLike, utils::Inode},
     match_sock_option_mut, match_sock_option_ref,
     net::{
-        iface::Iface,
+        iface::{Iface, RawTcpSocketExt},
         socket::{
             new_pseudo_inode,
             options::{Error as SocketError, SocketOption},
@@ -39,6 +39,7 @@ use crate::{
             },
             Socket,
         },
+        NetNamespace,
     },
     prelude::*,
     process::signal::{PollHandle, Pollable, Pollee},
@@ -60,6 +61,8 @@ pub struct StreamSocket {
     state: RwLock<Takeable<State>>,
     options: RwLock<OptionSet>,
 
+    ip_version: IpVersion,
+    net_ns: Arc<NetNamespace>,
     is_nonblocking: AtomicBool,
     pollee: Pollee,
     pseudo_inode: Arc<dyn Inode>,
@@ -80,6 +83,7 @@ enum State {
 struct OptionSet {
     socket: SocketOptionSet,
     ip: IpOptionSet,
+    ipv6: Ipv6OptionSet,
     tcp: TcpOptionSet,
 }
 
@@ -87,33 +91,56 @@ impl OptionSet {
     fn new() -> Self {
         let socket = SocketOptionSet::new_tcp();
         let ip = IpOptionSet::new_tcp();
+        let ipv6 = Ipv6OptionSet::new();
         let tcp = TcpOptionSet::new();
-        OptionSet { socket, ip, tcp }
+        OptionSet {
+            socket,
+            ip,
+            ipv6,
+            tcp,
+        }
     }
 
     fn raw(&self) -> RawTcpOption {
         RawTcpOption {
             keep_alive: self.socket.keep_alive().then_some(KEEPALIVE_INTERVAL),
             is_nagle_enabled: !self.tcp.no_delay(),
+            congestion_algorithm: self.tcp.congestion().into(),
         }
     }
 }
 
 impl StreamSocket {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+    pub fn new(
+        is_nonblocking: bool,
+        ip_version: IpVersion,
+        net_ns: Arc<NetNamespace>,
+    ) -> Arc<Self> {
         let init_stream = InitStream::new();
+
+        let mut options = OptionSet::new();
+        options.tcp.set_congestion(net_ns.tcp_congestion_control());
+
         Arc::new(Self {
             state: RwLock::new(Takeable::new(State::Init(init_stream))),
-            options: RwLock::new(OptionSet::new()),
+            options: RwLock::new(options),
+            ip_version,
+            net_ns,
             is_nonblocking: AtomicBool::new(is_nonblocking),
             pollee: Pollee::new(),
             pseudo_inode: new_pseudo_inode(),
         })
     }
 
-    fn new_accepted(connected_stream: ConnectedStream) -> Arc<Self> {
+    fn new_accepted(
+        connected_stream: ConnectedStream,
+        ip_version: IpVersion,
+        net_ns: Arc<NetNamespace>,
+        ipv6_options: Ipv6OptionSet,
+    ) -> Arc<Self> {
         let options = connected_stream.raw_with(|raw_tcp_socket| {
             let mut options = OptionSet::new();
+            options.ipv6 = ipv6_options;
 
             if raw_tcp_socket.keep_alive().is_some() {
                 options.socket.set_keep_alive(true);
@@ -123,6 +150,10 @@ impl StreamSocket {
                 options.tcp.set_no_delay(true);
             }
 
+            options
+                .tcp
+                .set_congestion(raw_tcp_socket.congestion_algorithm().into());
+
             // TODO: Update other options for a newly-accepted socket
 
             options
@@ -134,6 +165,8 @@ impl StreamSocket {
         Arc::new(Self {
             options: RwLock::new(options),
             state: RwLock::new(Takeable::new(State::Connected(connected_stream))),
+            ip_version,
+            net_ns,
             is_nonblocking: AtomicBool::new(false),
             pollee,
             pseudo_inode: new_pseudo_inode(),
@@ -237,6 +270,7 @@ impl StreamSocket {
             }
 
             let (target_state, iface_to_poll) = match init_stream.connect(
+                &self.net_ns,
                 remote_endpoint,
                 &raw_option,
                 options.socket.reuse_addr(),
@@ -300,10 +334,17 @@ impl StreamSocket {
             return_errno_with_message!(Errno::EINVAL, "the socket is not listening");
         };
 
+        let ipv6_options = self.options.read().ipv6;
         let accepted = listen_stream.try_accept().map(|connected_stream| {
             let remote_endpoint = connected_stream.remote_endpoint();
-            let accepted_socket = Self::new_accepted(connected_stream);
-            (accepted_socket as _, remote_endpoint.into())
+            let accepted_socket = Self::new_accepted(
+                connected_stream,
+                self.ip_version,
+                self.net_ns.clone(),
+                ipv6_options,
+            );
+            let remote_addr = endpoint_to_socket_addr(remote_endpoint, self.ip_version);
+            (accepted_socket as _, remote_addr)
         });
         let iface_to_poll = listen_stream.iface().clone();
 
@@ -348,7 +389,10 @@ impl StreamSocket {
             iface.poll();
         }
 
-        Ok((recv_bytes, remote_endpoint.into()))
+        Ok((
+            recv_bytes,
+            endpoint_to_socket_addr(remote_endpoint, self.ip_version),
+        ))
     }
 
     fn try_send(&self, reader: &mut dyn MultiRead, flags: SendRecvFlags) -> Result<usize> {
@@ -428,19 +472,27 @@ impl SocketPrivate for StreamSocket {
 
 impl Socket for StreamSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
-
         let mut state = self.write_updated_state();
+
+        let options = self.options.read();
+        let endpoint =
+            socket_addr_to_endpoint(socket_addr, self.ip_version, options.ipv6.v6only())?;
+
         let State::Init(init_stream) = state.as_mut() else {
             return_errno_with_message!(Errno::EINVAL, "the socket is already bound to an address");
         };
 
-        let can_reuse = self.options.read().socket.reuse_addr();
-        init_stream.bind(&endpoint, can_reuse)
+        init_stream.bind(
+            &self.net_ns,
+            &endpoint,
+            options.socket.reuse_addr(),
+            options.ipv6.v6only(),
+        )
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let remote_endpoint = socket_addr.try_into()?;
+        let is_v6only = self.options.read().ipv6.v6only();
+        let remote_endpoint = socket_addr_to_endpoint(socket_addr, self.ip_version, is_v6only)?;
 
         if let Some(result) = self.start_connect(&remote_endpoint) {
             return result;
@@ -517,12 +569,12 @@ impl Socket for StreamSocket {
         let local_endpoint = match state.as_ref() {
             State::Init(init_stream) => init_stream
                 .local_endpoint()
-                .unwrap_or(UNSPECIFIED_LOCAL_ENDPOINT),
+                .unwrap_or(unspecified_local_endpoint(self.ip_version)),
             State::Connecting(connecting_stream) => connecting_stream.local_endpoint(),
             State::Listen(listen_stream) => listen_stream.local_endpoint(),
             State::Connected(connected_stream) => connected_stream.local_endpoint(),
         };
-        Ok(local_endpoint.into())
+        Ok(endpoint_to_socket_addr(local_endpoint, self.ip_version))
     }
 
     fn peer_addr(&self) -> Result<SocketAddr> {
@@ -534,7 +586,7 @@ impl Socket for StreamSocket {
             State::Connecting(connecting_stream) => connecting_stream.remote_endpoint(),
             State::Connected(connected_stream) => connected_stream.remote_endpoint(),
         };
-        Ok(remote_endpoint.into())
+        Ok(endpoint_to_socket_addr(remote_endpoint, self.ip_version))
     }
 
     fn sendmsg(
@@ -543,10 +595,14 @@ impl Socket for StreamSocket {
         message_header: MessageHeader,
         flags: SendRecvFlags,
     ) -> Result<usize> {
-        // TODO: Deal with flags
-        if !flags.is_all_supported() {
+        // `MSG_MORE` is only a hint, so it is fine to send the data immediately.
+        if !flags.sub(SendRecvFlags::MSG_MORE).is_all_supported() {
             warn!("unsupported flags: {:?}", flags);
         }
+        if flags.contains(SendRecvFlags::MSG_OOB) {
+            // TODO: Support sending urgent data
+            return_errno_with_message!(Errno::EOPNOTSUPP, "sending urgent data is not supported");
+        }
 
         let MessageHeader {
             control_messages, ..
@@ -561,9 +617,7 @@ impl Socket for StreamSocket {
             warn!("sending control message is not supported");
         }
 
-        self.block_on(IoEvents::OUT, || self.try_send(reader, flags))
-
-        // TODO: Trigger `SIGPIPE` if the error code is `EPIPE` and `MSG_NOSIGNAL` is not specified
+        self.send_stream(flags, || self.try_send(reader, flags))
     }
 
     fn recvmsg(
@@ -571,12 +625,20 @@ impl Socket for StreamSocket {
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
     ) -> Result<(usize, MessageHeader)> {
-        // TODO: Deal with flags
-        if !flags.is_all_supported() {
+        if !flags
+            .sub(SendRecvFlags::MSG_PEEK | SendRecvFlags::MSG_WAITALL)
+            .is_all_supported()
+        {
             warn!("unsupported flags: {:?}", flags);
         }
+        if flags.contains(SendRecvFlags::MSG_OOB) {
+            // Urgent data are never received separately, so there are no urgent data to read.
+            return_errno_with_message!(Errno::EINVAL, "there are no urgent data to receive");
+        }
 
-        let (received_bytes, _) = self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
+        let received_bytes = self.recv_stream(writer, flags, |writer| {
+            self.try_recv(writer, flags).map(|(len, _)| len)
+        })?;
 
         // TODO: Receive control message
 
@@ -611,6 +673,14 @@ impl Socket for StreamSocket {
             res => return res,
         }
 
+        // Deal with IPv6-level options
+        match Ipv6OptionSet::check_get_option(option, self.ip_version)
+            .and_then(|()| options.ipv6.get_option(option))
+        {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
         // Deal with TCP-level options
         // FIXME: Here we only return the previously set values, without actually
         // asking the underlying sockets for the real, effective values.
@@ -663,6 +733,10 @@ impl Socket for StreamSocket {
                 let inq = options.tcp.receive_inq();
                 tcp_inq.set(inq);
             },
+            tcp_info: TcpInfo => {
+                let info = state.tcp_info();
+                tcp_info.set(info);
+            },
             _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to get is unknown")
         });
 
@@ -679,8 +753,20 @@ impl Socket for StreamSocket {
                 // Deal with IP-level options
                 match options.ip.set_option(option, state.as_ref()) {
                     Err(err) if err.error() == Errno::ENOPROTOOPT => {
-                        // Deal with TCP-level options
-                        do_tcp_setsockopt(option, &mut options, state.as_mut())?
+                        // Deal with IPv6-level options
+                        match do_ipv6_setsockopt(
+                            option,
+                            &mut options,
+                            state.as_ref(),
+                            self.ip_version,
+                        ) {
+                            Err(err) if err.error() == Errno::ENOPROTOOPT => {
+                                // Deal with TCP-level options
+                                do_tcp_setsockopt(option, &mut options, state.as_mut())?
+                            }
+                            Err(err) => return Err(err),
+                            Ok(need_iface_poll) => need_iface_poll,
+                        }
                     }
                     Err(err) => return Err(err),
                     Ok(need_iface_poll) => need_iface_poll,
@@ -707,6 +793,19 @@ impl Socket for StreamSocket {
     }
 }
 
+fn do_ipv6_setsockopt(
+    option: &dyn SocketOption,
+    options: &mut OptionSet,
+    state: &State,
+    ip_version: IpVersion,
+) -> Result<NeedIfacePoll> {
+    if ip_version != IpVersion::Ipv6 {
+        return_errno_with_message!(Errno::ENOPROTOOPT, "the socket is not an IPv6 socket");
+    }
+
+    options.ipv6.set_option(option, state)
+}
+
 fn do_tcp_setsockopt(
     option: &dyn SocketOption,
     options: &mut OptionSet,
@@ -769,6 +868,9 @@ fn do_tcp_setsockopt(
         tcp_congestion: Congestion => {
             let congestion = tcp_congestion.get().unwrap();
             options.tcp.set_congestion(*congestion);
+            state.set_raw_option(|raw_socket: &dyn RawTcpSetOption| {
+                raw_socket.set_congestion_algorithm((*congestion).into())
+            });
         },
         tcp_user_timeout: UserTimeout => {
             let user_timeout = tcp_user_timeout.get().unwrap();
@@ -803,6 +905,19 @@ impl State {
         }
     }
 
+    fn tcp_info(&self) -> CTcpInfo {
+        let raw_with = |raw_socket: &RawTcpSocketExt| {
+            CTcpInfo::new_connection(raw_socket.state(), &raw_socket.congestion_info())
+        };
+
+        match self {
+            State::Init(_) => CTcpInfo::new_unconnected(false),
+            State::Connecting(connecting_stream) => connecting_stream.raw_with(raw_with),
+            State::Connected(connected_stream) => connected_stream.raw_with(raw_with),
+            State::Listen(_) => CTcpInfo::new_unconnected(true),
+        }
+    }
+
     fn iface(&self) -> Option<&Arc<Iface>> {
         match self {
             State::Init(_) => None,
@@ -862,6 +977,19 @@ impl SetIpLevelOption for State {
     }
 }
 
+impl SetIpv6LevelOption for State {
+    fn set_v6only(&self, _v6only: bool) -> Result<()> {
+        if matches!(self, State::Init(init_stream) if init_stream.bound_port().is_none()) {
+            return Ok(());
+        }
+
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "IPV6_V6ONLY cannot be set after the socket is bound"
+        );
+    }
+}
+
 impl Drop for StreamSocket {
     fn drop(&mut self) {
         let state = self.state.get_mut().take();
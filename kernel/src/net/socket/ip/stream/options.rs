@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use aster_bigtcp::socket::{CongestionAlgorithm, CongestionInfo, CongestionState, TcpState};
+
 use crate::{impl_socket_options, prelude::*};
 
 impl_socket_options!(
@@ -12,22 +14,26 @@ impl_socket_options!(
     pub struct Congestion(CongestionControl);
     pub struct UserTimeout(u32);
     pub struct Inq(bool);
+    pub struct TcpInfo(CTcpInfo);
 );
 
 #[derive(Debug, Clone, Copy)]
 pub enum CongestionControl {
     Reno,
     Cubic,
+    Bbr,
 }
 
 impl CongestionControl {
     const RENO: &'static str = "reno";
     const CUBIC: &'static str = "cubic";
+    const BBR: &'static str = "bbr";
 
     pub fn new(name: &str) -> Result<Self> {
         let congestion = match name {
             Self::RENO => Self::Reno,
             Self::CUBIC => Self::Cubic,
+            Self::BBR => Self::Bbr,
             _ => return_errno_with_message!(Errno::ENOENT, "unsupported congestion name"),
         };
 
@@ -38,6 +44,176 @@ impl CongestionControl {
         match self {
             Self::Reno => Self::RENO,
             Self::Cubic => Self::CUBIC,
+            Self::Bbr => Self::BBR,
+        }
+    }
+}
+
+impl From<CongestionControl> for CongestionAlgorithm {
+    fn from(value: CongestionControl) -> Self {
+        match value {
+            CongestionControl::Reno => Self::Reno,
+            CongestionControl::Cubic => Self::Cubic,
+            CongestionControl::Bbr => Self::Bbr,
+        }
+    }
+}
+
+impl From<CongestionAlgorithm> for CongestionControl {
+    fn from(value: CongestionAlgorithm) -> Self {
+        match value {
+            CongestionAlgorithm::Reno => Self::Reno,
+            CongestionAlgorithm::Cubic => Self::Cubic,
+            CongestionAlgorithm::Bbr => Self::Bbr,
+        }
+    }
+}
+
+/// Information about a TCP connection.
+///
+/// This corresponds to `struct tcp_info` in Linux.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/tcp.h#L232>.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+pub struct CTcpInfo {
+    tcpi_state: u8,
+    tcpi_ca_state: u8,
+    tcpi_retransmits: u8,
+    tcpi_probes: u8,
+    tcpi_backoff: u8,
+    tcpi_options: u8,
+    /// `tcpi_snd_wscale : 4, tcpi_rcv_wscale : 4`
+    tcpi_wscale: u8,
+    /// `tcpi_delivery_rate_app_limited : 1, tcpi_fastopen_client_fail : 2`
+    tcpi_flags: u8,
+
+    tcpi_rto: u32,
+    tcpi_ato: u32,
+    tcpi_snd_mss: u32,
+    tcpi_rcv_mss: u32,
+
+    tcpi_unacked: u32,
+    tcpi_sacked: u32,
+    tcpi_lost: u32,
+    tcpi_retrans: u32,
+    tcpi_fackets: u32,
+
+    tcpi_last_data_sent: u32,
+    tcpi_last_ack_sent: u32,
+    tcpi_last_data_recv: u32,
+    tcpi_last_ack_recv: u32,
+
+    tcpi_pmtu: u32,
+    tcpi_rcv_ssthresh: u32,
+    tcpi_rtt: u32,
+    tcpi_rttvar: u32,
+    tcpi_snd_ssthresh: u32,
+    tcpi_snd_cwnd: u32,
+    tcpi_advmss: u32,
+    tcpi_reordering: u32,
+
+    tcpi_rcv_rtt: u32,
+    tcpi_rcv_space: u32,
+
+    tcpi_total_retrans: u32,
+
+    tcpi_pacing_rate: u64,
+    tcpi_max_pacing_rate: u64,
+    tcpi_bytes_acked: u64,
+    tcpi_bytes_received: u64,
+    tcpi_segs_out: u32,
+    tcpi_segs_in: u32,
+
+    tcpi_notsent_bytes: u32,
+    tcpi_min_rtt: u32,
+    tcpi_data_segs_in: u32,
+    tcpi_data_segs_out: u32,
+
+    tcpi_delivery_rate: u64,
+
+    tcpi_busy_time: u64,
+    tcpi_rwnd_limited: u64,
+    tcpi_sndbuf_limited: u64,
+
+    tcpi_delivered: u32,
+    tcpi_delivered_ce: u32,
+
+    tcpi_bytes_sent: u64,
+    tcpi_bytes_retrans: u64,
+    tcpi_dsack_dups: u32,
+    tcpi_reord_seen: u32,
+
+    tcpi_rcv_ooopack: u32,
+
+    tcpi_snd_wnd: u32,
+}
+
+impl CTcpInfo {
+    /// Creates the information of a socket that is not connected.
+    pub(super) fn new_unconnected(is_listening: bool) -> Self {
+        // Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/net/tcp_states.h#L12>.
+        const TCP_CLOSE: u8 = 7;
+        const TCP_LISTEN: u8 = 10;
+
+        Self {
+            tcpi_state: if is_listening { TCP_LISTEN } else { TCP_CLOSE },
+            ..Default::default()
+        }
+    }
+
+    /// Creates the information of a connection.
+    pub(super) fn new_connection(state: TcpState, info: &CongestionInfo) -> Self {
+        // Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/net/tcp_states.h#L12>.
+        let tcpi_state = match state {
+            TcpState::Established => 1,
+            TcpState::SynSent => 2,
+            TcpState::SynReceived => 3,
+            TcpState::FinWait1 => 4,
+            TcpState::FinWait2 => 5,
+            TcpState::TimeWait => 6,
+            TcpState::Closed => 7,
+            TcpState::CloseWait => 8,
+            TcpState::LastAck => 9,
+            TcpState::Listen => 10,
+            TcpState::Closing => 11,
+        };
+
+        // Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/tcp.h#L196>.
+        let tcpi_ca_state = match info.state {
+            CongestionState::Open => 0,
+            CongestionState::Disorder => 1,
+            CongestionState::Recovery => 3,
+            CongestionState::Loss => 4,
+        };
+
+        let to_usecs = |duration: aster_bigtcp::time::Duration| {
+            duration.total_micros().min(u32::MAX as u64) as u32
+        };
+
+        Self {
+            tcpi_state,
+            tcpi_ca_state,
+            tcpi_rto: to_usecs(info.rto),
+            tcpi_snd_mss: info.mss,
+            tcpi_rcv_mss: info.mss,
+            tcpi_unacked: info.unacked,
+            tcpi_rtt: info.srtt.map_or(0, to_usecs),
+            tcpi_rttvar: to_usecs(info.rttvar),
+            tcpi_snd_ssthresh: info.ssthresh,
+            tcpi_snd_cwnd: info.cwnd,
+            tcpi_advmss: info.mss,
+            tcpi_total_retrans: info.total_retrans,
+            tcpi_bytes_acked: info.bytes_acked,
+            tcpi_segs_out: info.segs_out,
+            // Linux reports `~0U` if the minimum RTT is unknown.
+            tcpi_min_rtt: info.min_rtt.map_or(u32::MAX, to_usecs),
+            tcpi_data_segs_out: info.data_segs_out,
+            tcpi_delivery_rate: info.delivery_rate,
+            tcpi_bytes_sent: info.bytes_sent,
+            tcpi_bytes_retrans: info.bytes_retrans,
+            tcpi_snd_wnd: info.peer_window,
+            ..Default::default()
         }
     }
 }
@@ -34,7 +34,7 @@ impl TcpOptionSet {
             syn_cnt: DEFAULT_SYN_CNT,
             defer_accept: Retrans(0),
             window_clamp: DEFAULT_WINDOW_CLAMP,
-            congestion: CongestionControl::Reno,
+            congestion: CongestionControl::Cubic,
             user_timeout: 0,
             receive_inq: false,
         }
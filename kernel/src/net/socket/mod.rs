@@ -11,7 +11,7 @@ use crate::{
         file_table::FdFlags,
         path::RESERVED_MOUNT_ID,
         pseudofs::{sockfs_singleton, PseudoInode},
-        utils::{mkmod, CreationFlags, Inode, InodeType, StatusFlags},
+        utils::{mkmod, CreationFlags, Inode, InodeType, IoctlCmd, StatusFlags},
     },
     prelude::*,
     process::{Gid, Uid},
@@ -21,12 +21,26 @@ use crate::{
 pub mod ip;
 pub mod netlink;
 pub mod options;
+pub mod packet;
 pub mod unix;
 pub mod util;
 pub mod vsock;
 
 mod private {
-    use crate::{events::IoEvents, prelude::*, process::signal::Pollable};
+    use super::util::SendRecvFlags;
+    use crate::{
+        events::IoEvents,
+        prelude::*,
+        process::{
+            posix_thread::AsPosixThread,
+            signal::{
+                constants::SIGPIPE,
+                signals::user::{UserSignal, UserSignalKind},
+                Pollable,
+            },
+        },
+        util::MultiWrite,
+    };
 
     /// Common methods for sockets, but private to the network module.
     ///
@@ -57,6 +71,92 @@ mod private {
                 self.wait_events(events, None, try_op)
             }
         }
+
+        /// Blocks until some events occur to complete I/O operations with the given flags.
+        ///
+        /// This method is similar to [`Self::block_on`], but it will never block if
+        /// `MSG_DONTWAIT` is specified in `flags`.
+        #[track_caller]
+        fn block_on_with_flags<F, R>(
+            &self,
+            events: IoEvents,
+            flags: SendRecvFlags,
+            mut try_op: F,
+        ) -> Result<R>
+        where
+            Self: Sized,
+            F: FnMut() -> Result<R>,
+        {
+            if flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+                try_op()
+            } else {
+                self.block_on(events, try_op)
+            }
+        }
+
+        /// Blocks until some bytes are sent via a stream socket.
+        ///
+        /// If the operation fails with [`EPIPE`], `SIGPIPE` will be sent to the current thread
+        /// unless `MSG_NOSIGNAL` is specified in `flags`.
+        ///
+        /// [`EPIPE`]: crate::error::Errno::EPIPE
+        fn send_stream<F>(&self, flags: SendRecvFlags, try_send: F) -> Result<usize>
+        where
+            Self: Sized,
+            F: FnMut() -> Result<usize>,
+        {
+            let result = self.block_on_with_flags(IoEvents::OUT, flags, try_send);
+
+            let is_epipe = matches!(&result, Err(err) if err.error() == Errno::EPIPE);
+            if is_epipe && !flags.contains(SendRecvFlags::MSG_NOSIGNAL) {
+                if let Some(posix_thread) = current_thread!().as_posix_thread() {
+                    posix_thread.enqueue_signal(Box::new(UserSignal::new(
+                        SIGPIPE,
+                        UserSignalKind::Kill,
+                        posix_thread.process().pid(),
+                        posix_thread.credentials().ruid(),
+                    )));
+                }
+            }
+
+            result
+        }
+
+        /// Blocks until some bytes are received via a stream socket.
+        ///
+        /// If `MSG_WAITALL` is specified in `flags`, this method will keep receiving bytes until
+        /// `writer` is full, the end of the stream is reached, or an error (e.g., a signal
+        /// interruption) occurs. In the latter two cases, the bytes received so far are returned.
+        fn recv_stream<F>(
+            &self,
+            writer: &mut dyn MultiWrite,
+            flags: SendRecvFlags,
+            mut try_recv: F,
+        ) -> Result<usize>
+        where
+            Self: Sized,
+            F: FnMut(&mut dyn MultiWrite) -> Result<usize>,
+        {
+            let mut recv_bytes =
+                self.block_on_with_flags(IoEvents::IN, flags, || try_recv(writer))?;
+
+            // Peeking always starts from the beginning of the stream, so it makes no sense to wait
+            // for more bytes.
+            if !flags.contains(SendRecvFlags::MSG_WAITALL)
+                || flags.contains(SendRecvFlags::MSG_PEEK)
+            {
+                return Ok(recv_bytes);
+            }
+
+            while recv_bytes > 0 && !writer.is_empty() {
+                match self.block_on_with_flags(IoEvents::IN, flags, || try_recv(writer)) {
+                    Ok(0) | Err(_) => break,
+                    Ok(len) => recv_bytes += len,
+                }
+            }
+
+            Ok(recv_bytes)
+        }
     }
 }
 
@@ -172,6 +272,10 @@ impl<T: Socket + 'static> FileLike for T {
         Ok(())
     }
 
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        util::ioctl(cmd, arg)
+    }
+
     fn as_socket(&self) -> Option<&dyn Socket> {
         Some(self)
     }
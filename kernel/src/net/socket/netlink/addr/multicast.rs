@@ -19,6 +19,19 @@ impl GroupIdSet {
         Self(groups)
     }
 
+    /// Creates a new `GroupIdSet` with a single group specified by the group number.
+    ///
+    /// Group numbers start from one, so the group number `n` corresponds to the group ID `n - 1`.
+    /// Group numbers are used in `NETLINK_ADD_MEMBERSHIP` and `NETLINK_DROP_MEMBERSHIP`, whereas
+    /// group IDs are used in the bitmask of the netlink socket address.
+    pub fn try_from_group_num(group_num: u32) -> Result<Self> {
+        if group_num == 0 || group_num > MAX_GROUPS {
+            return_errno_with_message!(Errno::EINVAL, "the group number is invalid");
+        }
+
+        Ok(Self(1 << (group_num - 1)))
+    }
+
     /// Creates an iterator over all group IDs.
     pub const fn ids_iter(&self) -> GroupIdIter {
         GroupIdIter::new(self)
@@ -44,6 +57,11 @@ impl GroupIdSet {
         self.0 = 0;
     }
 
+    /// Checks if the set contains all the groups in `groups`.
+    pub fn contains(&self, groups: GroupIdSet) -> bool {
+        self.0 & groups.0 == groups.0
+    }
+
     /// Checks if the set of group IDs is empty.
     pub fn is_empty(&self) -> bool {
         self.0 == 0
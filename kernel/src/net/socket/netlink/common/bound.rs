@@ -2,8 +2,12 @@
 
 use crate::{
     events::IoEvents,
-    net::socket::netlink::{
-        receiver::MessageQueue, table::BoundHandle, GroupIdSet, NetlinkSocketAddr,
+    net::{
+        socket::{
+            netlink::{receiver::MessageQueue, table::BoundHandle, GroupIdSet, NetlinkSocketAddr},
+            util::SocketFilter,
+        },
+        NetNamespace,
     },
     prelude::*,
 };
@@ -12,17 +16,20 @@ pub struct BoundNetlink<Message: 'static> {
     pub(in crate::net::socket::netlink) handle: BoundHandle<Message>,
     pub(in crate::net::socket::netlink) remote_addr: NetlinkSocketAddr,
     pub(in crate::net::socket::netlink) receive_queue: Arc<Mutex<MessageQueue<Message>>>,
+    pub(in crate::net::socket::netlink) net_ns: Arc<NetNamespace>,
 }
 
 impl<Message: 'static> BoundNetlink<Message> {
     pub(super) fn new(
         handle: BoundHandle<Message>,
         message_queue: Arc<Mutex<MessageQueue<Message>>>,
+        net_ns: Arc<NetNamespace>,
     ) -> Self {
         Self {
             handle,
             remote_addr: NetlinkSocketAddr::new_unspecified(),
             receive_queue: message_queue,
+            net_ns,
         }
     }
 
@@ -64,4 +71,11 @@ impl<Message: 'static> BoundNetlink<Message> {
     pub(super) fn drop_groups(&mut self, groups: GroupIdSet) {
         self.handle.drop_groups(groups);
     }
+
+    pub(super) fn set_filter(
+        &mut self,
+        filter: Option<Arc<SocketFilter>>,
+    ) -> Option<Arc<SocketFilter>> {
+        self.receive_queue.lock().set_filter(filter)
+    }
 }
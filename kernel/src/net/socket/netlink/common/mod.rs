@@ -10,16 +10,23 @@ use crate::{
     events::IoEvents,
     fs::utils::Inode,
     match_sock_option_ref,
-    net::socket::{
-        netlink::{table::SupportedNetlinkProtocol, AddMembership, DropMembership},
-        new_pseudo_inode,
-        options::SocketOption,
-        private::SocketPrivate,
-        util::{
-            datagram_common::{select_remote_and_bind, Bound, Inner},
-            MessageHeader, SendRecvFlags, SocketAddr,
+    net::{
+        socket::{
+            netlink::{
+                receiver::{MessageQueue, QueueableMessage, RawMessageReceiver},
+                table::SupportedNetlinkProtocol,
+                AddMembership, DropMembership,
+            },
+            new_pseudo_inode,
+            options::{AttachFilter, DetachFilter, SocketOption},
+            private::SocketPrivate,
+            util::{
+                datagram_common::{select_remote_and_bind, Bound, Inner},
+                MessageHeader, SendRecvFlags, SocketAddr, SocketFilter,
+            },
+            Socket,
         },
-        Socket,
+        NetNamespace,
     },
     prelude::*,
     process::signal::{PollHandle, Pollable, Pollee},
@@ -41,8 +48,8 @@ impl<P: SupportedNetlinkProtocol> NetlinkSocket<P>
 where
     BoundNetlink<P::Message>: Bound<Endpoint = NetlinkSocketAddr>,
 {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
-        let unbound = UnboundNetlink::new();
+    pub fn new(is_nonblocking: bool, net_ns: Arc<NetNamespace>) -> Arc<Self> {
+        let unbound = UnboundNetlink::new(net_ns);
         Arc::new(Self {
             inner: RwMutex::new(Inner::Unbound(unbound)),
             is_nonblocking: AtomicBool::new(is_nonblocking),
@@ -89,6 +96,31 @@ where
     }
 }
 
+impl<P: SupportedNetlinkProtocol> NetlinkSocket<P>
+where
+    P::Message: QueueableMessage,
+    BoundNetlink<P::Message>: Bound<Endpoint = NetlinkSocketAddr>,
+{
+    /// Returns a receiver that delivers raw bytes to the socket.
+    ///
+    /// The socket will be bound to an ephemeral port if it has not been bound, because the receive
+    /// queue is only available after binding. In contrast, Linux can deliver raw bytes to unbound
+    /// sockets.
+    pub(in crate::net::socket::netlink) fn raw_receiver(
+        &self,
+    ) -> Result<Box<dyn RawMessageReceiver>> {
+        let mut inner = self.inner.write();
+        inner.bind_ephemeral(&NetlinkSocketAddr::new_unspecified(), &self.pollee)?;
+
+        let Inner::Bound(bound) = &*inner else {
+            unreachable!("the socket has been bound");
+        };
+        let receiver = MessageQueue::new_receiver(&bound.receive_queue, self.pollee.clone());
+
+        Ok(Box::new(receiver))
+    }
+}
+
 impl<P: SupportedNetlinkProtocol> Socket for NetlinkSocket<P>
 where
     BoundNetlink<P::Message>: Bound<Endpoint = NetlinkSocketAddr>,
@@ -160,7 +192,8 @@ where
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
     ) -> Result<(usize, MessageHeader)> {
-        let (received_len, addr) = self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
+        let (received_len, addr) =
+            self.block_on_with_flags(IoEvents::IN, flags, || self.try_recv(writer, flags))?;
 
         // TODO: Receive control message
 
@@ -170,6 +203,21 @@ where
     }
 
     fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            attach_filter: AttachFilter => {
+                let filter = attach_filter.get().unwrap();
+                self.inner.write().set_filter(Some(filter.clone()));
+                return Ok(());
+            },
+            _detach_filter: DetachFilter => {
+                if self.inner.write().set_filter(None).is_none() {
+                    return_errno_with_message!(Errno::ENOENT, "no filter is attached");
+                }
+                return Ok(());
+            },
+            _ => ()
+        });
+
         match do_set_netlink_option(&self.inner, option) {
             Ok(()) => Ok(()),
             Err(e) => {
@@ -224,6 +272,13 @@ impl<P: SupportedNetlinkProtocol> Inner<UnboundNetlink<P>, BoundNetlink<P::Messa
             Inner::Bound(bound_socket) => bound_socket.drop_groups(groups),
         }
     }
+
+    fn set_filter(&mut self, filter: Option<Arc<SocketFilter>>) -> Option<Arc<SocketFilter>> {
+        match self {
+            Inner::Unbound(unbound_socket) => unbound_socket.set_filter(filter),
+            Inner::Bound(bound_socket) => bound_socket.set_filter(filter),
+        }
+    }
 }
 
 fn do_set_netlink_option<P: SupportedNetlinkProtocol>(
@@ -232,12 +287,12 @@ fn do_set_netlink_option<P: SupportedNetlinkProtocol>(
 ) -> Result<()> {
     match_sock_option_ref!(option, {
         add_membership: AddMembership => {
-            let groups = add_membership.get().unwrap();
-            inner.write().add_groups(GroupIdSet::new(*groups));
+            let group_num = add_membership.get().unwrap();
+            inner.write().add_groups(GroupIdSet::try_from_group_num(*group_num)?);
         },
         drop_membership: DropMembership => {
-            let groups = drop_membership.get().unwrap();
-            inner.write().drop_groups(GroupIdSet::new(*groups));
+            let group_num = drop_membership.get().unwrap();
+            inner.write().drop_groups(GroupIdSet::try_from_group_num(*group_num)?);
         },
         _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
     });
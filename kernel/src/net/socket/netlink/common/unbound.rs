@@ -4,12 +4,15 @@ use core::marker::PhantomData;
 
 use crate::{
     events::IoEvents,
-    net::socket::{
-        netlink::{
-            common::bound::BoundNetlink, receiver::MessageQueue, table::SupportedNetlinkProtocol,
-            GroupIdSet, NetlinkSocketAddr,
+    net::{
+        socket::{
+            netlink::{
+                common::bound::BoundNetlink, receiver::MessageQueue,
+                table::SupportedNetlinkProtocol, GroupIdSet, NetlinkSocketAddr,
+            },
+            util::{datagram_common, SocketFilter},
         },
-        util::datagram_common,
+        NetNamespace,
     },
     prelude::*,
     process::signal::Pollee,
@@ -17,13 +20,17 @@ use crate::{
 
 pub(super) struct UnboundNetlink<P: SupportedNetlinkProtocol> {
     groups: GroupIdSet,
+    filter: Option<Arc<SocketFilter>>,
+    net_ns: Arc<NetNamespace>,
     phantom: PhantomData<BoundNetlink<P::Message>>,
 }
 
 impl<P: SupportedNetlinkProtocol> UnboundNetlink<P> {
-    pub(super) const fn new() -> Self {
+    pub(super) fn new(net_ns: Arc<NetNamespace>) -> Self {
         Self {
             groups: GroupIdSet::new_empty(),
+            filter: None,
+            net_ns,
             phantom: PhantomData,
         }
     }
@@ -39,6 +46,13 @@ impl<P: SupportedNetlinkProtocol> UnboundNetlink<P> {
     pub(super) fn drop_groups(&mut self, groups: GroupIdSet) {
         self.groups.drop_groups(groups);
     }
+
+    pub(super) fn set_filter(
+        &mut self,
+        filter: Option<Arc<SocketFilter>>,
+    ) -> Option<Arc<SocketFilter>> {
+        core::mem::replace(&mut self.filter, filter)
+    }
 }
 
 impl<P: SupportedNetlinkProtocol> datagram_common::Unbound for UnboundNetlink<P> {
@@ -54,7 +68,7 @@ impl<P: SupportedNetlinkProtocol> datagram_common::Unbound for UnboundNetlink<P>
         _options: Self::BindOptions,
     ) -> Result<Self::Bound> {
         let (message_queue, message_receiver) =
-            MessageQueue::<P::Message>::new_pair(pollee.clone());
+            MessageQueue::<P::Message>::new_pair(pollee.clone(), self.filter.clone());
 
         let bound_handle = {
             let endpoint = {
@@ -65,7 +79,11 @@ impl<P: SupportedNetlinkProtocol> datagram_common::Unbound for UnboundNetlink<P>
             <P as SupportedNetlinkProtocol>::bind(&endpoint, message_receiver)?
         };
 
-        Ok(BoundNetlink::new(bound_handle, message_queue))
+        Ok(BoundNetlink::new(
+            bound_handle,
+            message_queue,
+            self.net_ns.clone(),
+        ))
     }
 
     fn bind_ephemeral(
@@ -74,7 +92,7 @@ impl<P: SupportedNetlinkProtocol> datagram_common::Unbound for UnboundNetlink<P>
         pollee: &Pollee,
     ) -> Result<Self::Bound> {
         let (message_queue, message_receiver) =
-            MessageQueue::<P::Message>::new_pair(pollee.clone());
+            MessageQueue::<P::Message>::new_pair(pollee.clone(), self.filter.clone());
 
         let bound_handle = {
             let endpoint = {
@@ -85,7 +103,11 @@ impl<P: SupportedNetlinkProtocol> datagram_common::Unbound for UnboundNetlink<P>
             <P as SupportedNetlinkProtocol>::bind(&endpoint, message_receiver)?
         };
 
-        Ok(BoundNetlink::new(bound_handle, message_queue))
+        Ok(BoundNetlink::new(
+            bound_handle,
+            message_queue,
+            self.net_ns.clone(),
+        ))
     }
 
     fn check_io_events(&self) -> IoEvents {
@@ -0,0 +1,440 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The generic netlink controller.
+//!
+//! The controller is a generic netlink family with a fixed family ID ([`GENL_ID_CTRL`]). User
+//! space queries it to look up other families (e.g., their family IDs and multicast group IDs) by
+//! their names. The controller also notifies its multicast group when families are registered or
+//! unregistered.
+
+use spin::Once;
+
+use super::family::{
+    self, register_family_with_id, GenlFamily, GenlFamilyHandle, GenlOp, GenlOpFlags, GenlReply,
+    GenlRequest, RegisteredFamily, GENL_NAMSIZ,
+};
+use crate::{
+    net::socket::netlink::message::{Attribute, CAttrHeader, ContinueRead},
+    prelude::*,
+    util::{MultiRead, MultiWrite},
+};
+
+/// The family ID of the controller.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/genetlink.h#L30>.
+pub(super) const GENL_ID_CTRL: u16 = 0x10;
+
+/// Controller commands.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/genetlink.h#L40>.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+enum CtrlCmd {
+    UNSPEC = 0,
+    NEWFAMILY = 1,
+    DELFAMILY = 2,
+    GETFAMILY = 3,
+    NEWOPS = 4,
+    DELOPS = 5,
+    GETOPS = 6,
+    NEWMCAST_GRP = 7,
+    DELMCAST_GRP = 8,
+    GETMCAST_GRP = 9,
+    GETPOLICY = 10,
+}
+
+struct CtrlFamily;
+
+impl GenlFamily for CtrlFamily {
+    type Attr = CtrlAttr;
+
+    fn name(&self) -> &'static str {
+        "nlctrl"
+    }
+
+    fn version(&self) -> u8 {
+        2
+    }
+
+    fn max_attr(&self) -> u32 {
+        CtrlAttrClass::OP as u32
+    }
+
+    fn ops(&self) -> &[GenlOp] {
+        const OPS: &[GenlOp] = &[GenlOp {
+            cmd: CtrlCmd::GETFAMILY as u8,
+            flags: GenlOpFlags::CMD_CAP_DO.union(GenlOpFlags::CMD_CAP_DUMP),
+        }];
+        OPS
+    }
+
+    fn mcast_groups(&self) -> &[&'static str] {
+        &["notify"]
+    }
+
+    fn handle_request(&self, request: &GenlRequest<CtrlAttr>) -> Result<Vec<GenlReply<CtrlAttr>>> {
+        match CtrlCmd::try_from(request.cmd()) {
+            Ok(CtrlCmd::GETFAMILY) => do_get_family(request),
+            _ => return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "the controller command is not supported"
+            ),
+        }
+    }
+}
+
+fn do_get_family(request: &GenlRequest<CtrlAttr>) -> Result<Vec<GenlReply<CtrlAttr>>> {
+    if request.is_dump() {
+        let replies = family::all_families()
+            .iter()
+            .map(|family| new_family_reply(family, CtrlCmd::NEWFAMILY))
+            .collect();
+        return Ok(replies);
+    }
+
+    let mut family_id = None;
+    let mut family_name = None;
+    for attr in request.attrs() {
+        match attr {
+            CtrlAttr::FamilyId(id) => family_id = Some(*id),
+            CtrlAttr::FamilyName(name) => family_name = Some(name),
+            _ => (),
+        }
+    }
+
+    // Like Linux, the family name takes precedence over the family ID.
+    let family = if let Some(name) = family_name {
+        name.to_str()
+            .ok()
+            .and_then(family::find_family_by_name)
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "the family name does not exist"))?
+    } else if let Some(id) = family_id {
+        family::find_family_by_id(id)
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "the family ID does not exist"))?
+    } else {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the family ID or the family name is required"
+        );
+    };
+
+    Ok(vec![new_family_reply(&family, CtrlCmd::NEWFAMILY)])
+}
+
+/// Creates a reply that describes the family.
+fn new_family_reply(family: &RegisteredFamily, cmd: CtrlCmd) -> GenlReply<CtrlAttr> {
+    let mut attrs = vec![
+        CtrlAttr::FamilyName(CString::new(family.name).unwrap()),
+        CtrlAttr::FamilyId(family.id),
+        CtrlAttr::Version(family.version as u32),
+        // TODO: Support families with user-specific headers.
+        CtrlAttr::HdrSize(0),
+        CtrlAttr::MaxAttr(family.max_attr),
+    ];
+
+    if !family.ops.is_empty() {
+        let ops = family
+            .ops
+            .iter()
+            .enumerate()
+            .map(|(index, op)| {
+                IndexedAttrs::new(
+                    index,
+                    vec![
+                        CtrlOpAttr::Id(op.cmd as u32),
+                        CtrlOpAttr::Flags(op.flags.bits()),
+                    ],
+                )
+            })
+            .collect();
+        attrs.push(CtrlAttr::Ops(ops));
+    }
+
+    if !family.mcast_groups.is_empty() {
+        let groups = family
+            .mcast_groups
+            .iter()
+            .enumerate()
+            .map(|(index, (name, id))| {
+                IndexedAttrs::new(
+                    index,
+                    vec![
+                        CtrlMcastGrpAttr::Name(CString::new(*name).unwrap()),
+                        CtrlMcastGrpAttr::Id(*id),
+                    ],
+                )
+            })
+            .collect();
+        attrs.push(CtrlAttr::McastGroups(groups));
+    }
+
+    GenlReply::new(cmd as u8, attrs)
+}
+
+static CTRL_FAMILY: Once<GenlFamilyHandle<CtrlFamily>> = Once::new();
+
+pub(super) fn init() {
+    CTRL_FAMILY.call_once(|| register_family_with_id(Arc::new(CtrlFamily), GENL_ID_CTRL).unwrap());
+}
+
+/// Notifies user space that the family has been registered or unregistered.
+pub(super) fn notify_family(family: &RegisteredFamily, is_new: bool) {
+    let Some(ctrl_family) = CTRL_FAMILY.get() else {
+        return;
+    };
+
+    let cmd = if is_new {
+        CtrlCmd::NEWFAMILY
+    } else {
+        CtrlCmd::DELFAMILY
+    };
+    // The controller has exactly one multicast group.
+    ctrl_family
+        .multicast(0, new_family_reply(family, cmd))
+        .unwrap();
+}
+
+/// Controller attributes.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/genetlink.h#L57>.
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[repr(u16)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+enum CtrlAttrClass {
+    UNSPEC = 0,
+    FAMILY_ID = 1,
+    FAMILY_NAME = 2,
+    VERSION = 3,
+    HDRSIZE = 4,
+    MAXATTR = 5,
+    OPS = 6,
+    MCAST_GROUPS = 7,
+    POLICY = 8,
+    OP_POLICY = 9,
+    OP = 10,
+}
+
+#[derive(Debug)]
+enum CtrlAttr {
+    FamilyId(u16),
+    FamilyName(CString),
+    Version(u32),
+    HdrSize(u32),
+    MaxAttr(u32),
+    Ops(Vec<IndexedAttrs<CtrlOpAttr>>),
+    McastGroups(Vec<IndexedAttrs<CtrlMcastGrpAttr>>),
+}
+
+impl CtrlAttr {
+    fn class(&self) -> CtrlAttrClass {
+        match self {
+            CtrlAttr::FamilyId(_) => CtrlAttrClass::FAMILY_ID,
+            CtrlAttr::FamilyName(_) => CtrlAttrClass::FAMILY_NAME,
+            CtrlAttr::Version(_) => CtrlAttrClass::VERSION,
+            CtrlAttr::HdrSize(_) => CtrlAttrClass::HDRSIZE,
+            CtrlAttr::MaxAttr(_) => CtrlAttrClass::MAXATTR,
+            CtrlAttr::Ops(_) => CtrlAttrClass::OPS,
+            CtrlAttr::McastGroups(_) => CtrlAttrClass::MCAST_GROUPS,
+        }
+    }
+}
+
+impl Attribute for CtrlAttr {
+    fn type_(&self) -> u16 {
+        self.class() as u16
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        match self {
+            CtrlAttr::FamilyId(id) => id.as_bytes(),
+            CtrlAttr::FamilyName(name) => name.as_bytes_with_nul(),
+            CtrlAttr::Version(version) => version.as_bytes(),
+            CtrlAttr::HdrSize(hdr_size) => hdr_size.as_bytes(),
+            CtrlAttr::MaxAttr(max_attr) => max_attr.as_bytes(),
+            CtrlAttr::Ops(_) | CtrlAttr::McastGroups(_) => {
+                unreachable!("nested attributes cannot be represented as bytes")
+            }
+        }
+    }
+
+    fn payload_len(&self) -> usize {
+        match self {
+            CtrlAttr::Ops(ops) => ops.iter().map(Attribute::total_len_with_padding).sum(),
+            CtrlAttr::McastGroups(groups) => {
+                groups.iter().map(Attribute::total_len_with_padding).sum()
+            }
+            _ => self.payload_as_bytes().len(),
+        }
+    }
+
+    fn write_payload_to(&self, writer: &mut dyn MultiWrite) -> Result<()> {
+        match self {
+            CtrlAttr::Ops(ops) => ops.iter().try_for_each(|op| op.write_to(writer)),
+            CtrlAttr::McastGroups(groups) => {
+                groups.iter().try_for_each(|group| group.write_to(writer))
+            }
+            _ => {
+                writer.write(&mut VmReader::from(self.payload_as_bytes()))?;
+                Ok(())
+            }
+        }
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        let payload_len = header.payload_len();
+
+        let Ok(class) = CtrlAttrClass::try_from(header.type_()) else {
+            reader.skip_some(payload_len);
+            return Ok(ContinueRead::Skipped);
+        };
+
+        let res = match (class, payload_len) {
+            (CtrlAttrClass::FAMILY_ID, 2) => Self::FamilyId(reader.read_val_opt::<u16>()?.unwrap()),
+            (CtrlAttrClass::FAMILY_NAME, 1..=GENL_NAMSIZ) => {
+                let (name, name_len) = reader.read_cstring_until_end(payload_len)?;
+                if name_len != payload_len {
+                    reader.skip_some(payload_len - name_len);
+                }
+                Self::FamilyName(name)
+            }
+
+            (CtrlAttrClass::FAMILY_ID | CtrlAttrClass::FAMILY_NAME, _) => {
+                warn!(
+                    "controller attribute `{:?}` contains invalid payload",
+                    class
+                );
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::skipped_with_error(
+                    Errno::EINVAL,
+                    "the controller attribute is invalid",
+                ));
+            }
+
+            (_, _) => {
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::Skipped);
+            }
+        };
+
+        Ok(ContinueRead::Parsed(res))
+    }
+}
+
+/// Attributes nested in each entry of [`CtrlAttr::Ops`].
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/genetlink.h#L74>.
+#[derive(Debug)]
+enum CtrlOpAttr {
+    Id(u32),
+    Flags(u32),
+}
+
+impl Attribute for CtrlOpAttr {
+    fn type_(&self) -> u16 {
+        match self {
+            CtrlOpAttr::Id(_) => 1,
+            CtrlOpAttr::Flags(_) => 2,
+        }
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        match self {
+            CtrlOpAttr::Id(id) => id.as_bytes(),
+            CtrlOpAttr::Flags(flags) => flags.as_bytes(),
+        }
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        reader.skip_some(header.payload_len());
+        Ok(ContinueRead::Skipped)
+    }
+}
+
+/// Attributes nested in each entry of [`CtrlAttr::McastGroups`].
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/genetlink.h#L83>.
+#[derive(Debug)]
+enum CtrlMcastGrpAttr {
+    Name(CString),
+    Id(u32),
+}
+
+impl Attribute for CtrlMcastGrpAttr {
+    fn type_(&self) -> u16 {
+        match self {
+            CtrlMcastGrpAttr::Name(_) => 1,
+            CtrlMcastGrpAttr::Id(_) => 2,
+        }
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        match self {
+            CtrlMcastGrpAttr::Name(name) => name.as_bytes_with_nul(),
+            CtrlMcastGrpAttr::Id(id) => id.as_bytes(),
+        }
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        reader.skip_some(header.payload_len());
+        Ok(ContinueRead::Skipped)
+    }
+}
+
+/// An array entry that consists of nested attributes.
+///
+/// The attribute type of the entry is its one-based index in the array.
+#[derive(Debug)]
+struct IndexedAttrs<A> {
+    index: u16,
+    attrs: Vec<A>,
+}
+
+impl<A> IndexedAttrs<A> {
+    fn new(index: usize, attrs: Vec<A>) -> Self {
+        Self {
+            index: (index + 1) as u16,
+            attrs,
+        }
+    }
+}
+
+impl<A: Attribute> Attribute for IndexedAttrs<A> {
+    fn type_(&self) -> u16 {
+        self.index
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        unreachable!("nested attributes cannot be represented as bytes")
+    }
+
+    fn payload_len(&self) -> usize {
+        self.attrs
+            .iter()
+            .map(Attribute::total_len_with_padding)
+            .sum()
+    }
+
+    fn write_payload_to(&self, writer: &mut dyn MultiWrite) -> Result<()> {
+        self.attrs.iter().try_for_each(|attr| attr.write_to(writer))
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        reader.skip_some(header.payload_len());
+        Ok(ContinueRead::Skipped)
+    }
+}
@@ -0,0 +1,465 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Generic netlink families.
+//!
+//! A generic netlink family is a kernel subsystem that talks to user space through the generic
+//! netlink protocol. Each family is identified by a unique name. When a family is registered, it
+//! is assigned a family ID, which is used as the segment type of its messages, and an ID for each
+//! of its multicast groups. User space resolves these IDs from the family name through the
+//! controller (see [`super::ctrl`]).
+
+use core::marker::PhantomData;
+
+use super::{
+    ctrl,
+    message::{GenlFamilySegment, GenlMessage, GenlSegment, GenlSegmentBody},
+};
+use crate::{
+    net::{
+        socket::netlink::{
+            message::{Attribute, CMsgSegHdr, ContinueRead, RawAttr, SegHdrCommonFlags},
+            table::{NetlinkGenericProtocol, SupportedNetlinkProtocol},
+            GroupIdSet,
+        },
+        NetNamespace,
+    },
+    prelude::*,
+};
+
+/// A generic netlink family.
+pub trait GenlFamily: Send + Sync + 'static {
+    /// The attributes carried by the messages of the family.
+    type Attr: Attribute;
+
+    /// Returns the name of the family.
+    ///
+    /// The name must be shorter than [`GENL_NAMSIZ`] bytes.
+    fn name(&self) -> &'static str;
+
+    /// Returns the version of the family.
+    fn version(&self) -> u8 {
+        1
+    }
+
+    /// Returns the maximum attribute type of the family.
+    fn max_attr(&self) -> u32;
+
+    /// Returns the operations supported by the family.
+    fn ops(&self) -> &[GenlOp];
+
+    /// Returns the names of the multicast groups of the family.
+    fn mcast_groups(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// Handles a request from user space.
+    ///
+    /// The command of the request has been checked against [`Self::ops`], including the
+    /// permissions and the request capabilities. The returned replies will be sent back to the
+    /// sender of the request.
+    fn handle_request(
+        &self,
+        request: &GenlRequest<Self::Attr>,
+    ) -> Result<Vec<GenlReply<Self::Attr>>>;
+}
+
+/// An operation of a generic netlink family.
+#[derive(Debug, Clone, Copy)]
+pub struct GenlOp {
+    pub cmd: u8,
+    pub flags: GenlOpFlags,
+}
+
+bitflags! {
+    /// Flags of a generic netlink operation.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/genetlink.h#L21>.
+    pub struct GenlOpFlags: u32 {
+        /// The operation requires `CAP_NET_ADMIN`
+        const ADMIN_PERM = 0x01;
+        /// The operation can handle non-dump requests
+        const CMD_CAP_DO = 0x02;
+        /// The operation can handle dump requests
+        const CMD_CAP_DUMP = 0x04;
+        /// The operation has an attribute policy
+        const CMD_CAP_HASPOL = 0x08;
+        /// The operation requires `CAP_NET_ADMIN` in the owner user namespace of the network
+        /// namespace
+        const UNS_ADMIN_PERM = 0x10;
+    }
+}
+
+/// A request sent to a generic netlink family.
+pub struct GenlRequest<'a, Attr> {
+    net_ns: &'a NetNamespace,
+    cmd: u8,
+    is_dump: bool,
+    attrs: Vec<Attr>,
+}
+
+impl<Attr> GenlRequest<'_, Attr> {
+    /// Returns the network namespace of the sender.
+    pub fn net_ns(&self) -> &NetNamespace {
+        self.net_ns
+    }
+
+    /// Returns the command.
+    pub fn cmd(&self) -> u8 {
+        self.cmd
+    }
+
+    /// Returns whether the request asks to dump all matching objects.
+    pub fn is_dump(&self) -> bool {
+        self.is_dump
+    }
+
+    /// Returns the attributes.
+    pub fn attrs(&self) -> &[Attr] {
+        &self.attrs
+    }
+}
+
+/// A reply or a notification sent by a generic netlink family.
+#[derive(Debug)]
+pub struct GenlReply<Attr> {
+    cmd: u8,
+    attrs: Vec<Attr>,
+}
+
+impl<Attr> GenlReply<Attr> {
+    /// Creates a new reply with the command and the attributes.
+    pub fn new(cmd: u8, attrs: Vec<Attr>) -> Self {
+        Self { cmd, attrs }
+    }
+}
+
+impl<Attr: Attribute> GenlReply<Attr> {
+    fn into_raw(self) -> GenlReply<RawAttr> {
+        let attrs = self.attrs.iter().map(RawAttr::from_attr).collect();
+        GenlReply::new(self.cmd, attrs)
+    }
+}
+
+/// Registers a generic netlink family.
+///
+/// The family will be assigned a family ID and a multicast group ID for each of its multicast
+/// groups. It will be unregistered when the returned handle is dropped.
+pub fn register_family<F: GenlFamily>(family: Arc<F>) -> Result<GenlFamilyHandle<F>> {
+    let registered = GENL_FAMILY_TABLE.write().register(family, None)?;
+
+    ctrl::notify_family(&registered, true);
+
+    Ok(GenlFamilyHandle::new(registered))
+}
+
+/// Registers a generic netlink family with a fixed family ID.
+///
+/// The IDs of its multicast groups will start from the family ID.
+pub(super) fn register_family_with_id<F: GenlFamily>(
+    family: Arc<F>,
+    id: u16,
+) -> Result<GenlFamilyHandle<F>> {
+    let registered = GENL_FAMILY_TABLE.write().register(family, Some(id))?;
+
+    Ok(GenlFamilyHandle::new(registered))
+}
+
+/// Finds a registered generic netlink family by its ID.
+pub(super) fn find_family_by_id(id: u16) -> Option<Arc<RegisteredFamily>> {
+    GENL_FAMILY_TABLE.read().families.get(&id).cloned()
+}
+
+/// Finds a registered generic netlink family by its name.
+pub(super) fn find_family_by_name(name: &str) -> Option<Arc<RegisteredFamily>> {
+    GENL_FAMILY_TABLE
+        .read()
+        .families
+        .values()
+        .find(|family| family.name == name)
+        .cloned()
+}
+
+/// Returns all registered generic netlink families.
+pub(super) fn all_families() -> Vec<Arc<RegisteredFamily>> {
+    GENL_FAMILY_TABLE
+        .read()
+        .families
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// A handle to a registered generic netlink family.
+///
+/// When dropping a `GenlFamilyHandle`, the family will be unregistered.
+pub struct GenlFamilyHandle<F: GenlFamily> {
+    family: Arc<RegisteredFamily>,
+    phantom: PhantomData<F>,
+}
+
+impl<F: GenlFamily> GenlFamilyHandle<F> {
+    fn new(family: Arc<RegisteredFamily>) -> Self {
+        Self {
+            family,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the family ID.
+    #[cfg_attr(not(ktest), expect(dead_code))]
+    pub fn id(&self) -> u16 {
+        self.family.id
+    }
+
+    /// Sends a notification to the multicast group at `group_index` in [`GenlFamily::mcast_groups`].
+    pub fn multicast(&self, group_index: usize, notification: GenlReply<F::Attr>) -> Result<()> {
+        let Some((_, group_id)) = self.family.mcast_groups.get(group_index) else {
+            return_errno_with_message!(Errno::EINVAL, "the multicast group does not exist");
+        };
+
+        let header = CMsgSegHdr {
+            len: 0,
+            type_: self.family.id,
+            flags: 0,
+            seq: 0,
+            pid: 0,
+        };
+        let segment = self.family.new_segment(&header, notification.into_raw());
+        let message = GenlMessage::new(vec![GenlSegment::Family(segment)]);
+
+        NetlinkGenericProtocol::multicast(GroupIdSet::try_from_group_num(*group_id)?, message)
+    }
+}
+
+impl<F: GenlFamily> Drop for GenlFamilyHandle<F> {
+    fn drop(&mut self) {
+        GENL_FAMILY_TABLE.write().unregister(&self.family);
+
+        ctrl::notify_family(&self.family, false);
+    }
+}
+
+/// A registered generic netlink family.
+pub(super) struct RegisteredFamily {
+    pub(super) id: u16,
+    pub(super) name: &'static str,
+    pub(super) version: u8,
+    pub(super) max_attr: u32,
+    pub(super) ops: Vec<GenlOp>,
+    /// The names and IDs of the multicast groups.
+    pub(super) mcast_groups: Vec<(&'static str, u32)>,
+    handler: Arc<dyn RawRequestHandler>,
+}
+
+impl RegisteredFamily {
+    /// Handles a request from user space and returns the reply segments.
+    pub(super) fn handle_request(
+        &self,
+        net_ns: &NetNamespace,
+        request_segment: &GenlFamilySegment,
+        is_dump: bool,
+    ) -> Result<Vec<GenlFamilySegment>> {
+        let request_header = request_segment.header();
+
+        let replies = self
+            .handler
+            .handle_raw_request(net_ns, request_segment, is_dump)?;
+
+        let reply_segments = replies
+            .into_iter()
+            .map(|reply| self.new_segment(request_header, reply))
+            .collect();
+
+        Ok(reply_segments)
+    }
+
+    /// Creates a new segment that replies to the request specified by `request_header`.
+    fn new_segment(
+        &self,
+        request_header: &CMsgSegHdr,
+        reply: GenlReply<RawAttr>,
+    ) -> GenlFamilySegment {
+        let header = CMsgSegHdr {
+            len: 0,
+            type_: self.id,
+            flags: SegHdrCommonFlags::empty().bits(),
+            seq: request_header.seq,
+            pid: request_header.pid,
+        };
+
+        let body = GenlSegmentBody {
+            cmd: reply.cmd,
+            version: self.version,
+        };
+
+        GenlFamilySegment::new(header, body, reply.attrs)
+    }
+}
+
+/// A [`GenlFamily`] whose attribute type is erased.
+trait RawRequestHandler: Send + Sync {
+    fn handle_raw_request(
+        &self,
+        net_ns: &NetNamespace,
+        request_segment: &GenlFamilySegment,
+        is_dump: bool,
+    ) -> Result<Vec<GenlReply<RawAttr>>>;
+}
+
+impl<F: GenlFamily> RawRequestHandler for F {
+    fn handle_raw_request(
+        &self,
+        net_ns: &NetNamespace,
+        request_segment: &GenlFamilySegment,
+        is_dump: bool,
+    ) -> Result<Vec<GenlReply<RawAttr>>> {
+        let mut attrs = Vec::with_capacity(request_segment.attrs().len());
+        for raw_attr in request_segment.attrs() {
+            match raw_attr.parse::<F::Attr>()? {
+                ContinueRead::Parsed(attr) => attrs.push(attr),
+                ContinueRead::Skipped => (),
+                ContinueRead::SkippedErr(err) => return Err(err),
+            }
+        }
+
+        let request = GenlRequest {
+            net_ns,
+            cmd: request_segment.body().cmd,
+            is_dump,
+            attrs,
+        };
+
+        let replies = self.handle_request(&request)?;
+
+        Ok(replies.into_iter().map(GenlReply::into_raw).collect())
+    }
+}
+
+static GENL_FAMILY_TABLE: RwMutex<GenlFamilyTable> = RwMutex::new(GenlFamilyTable::new());
+
+/// All registered generic netlink families.
+struct GenlFamilyTable {
+    families: BTreeMap<u16, Arc<RegisteredFamily>>,
+    used_groups: GroupIdSet,
+}
+
+impl GenlFamilyTable {
+    const fn new() -> Self {
+        Self {
+            families: BTreeMap::new(),
+            used_groups: GroupIdSet::new_empty(),
+        }
+    }
+
+    fn register<F: GenlFamily>(
+        &mut self,
+        family: Arc<F>,
+        fixed_id: Option<u16>,
+    ) -> Result<Arc<RegisteredFamily>> {
+        let name = family.name();
+        if name.is_empty() || name.len() >= GENL_NAMSIZ || name.contains('\0') {
+            return_errno_with_message!(Errno::EINVAL, "the family name is invalid");
+        }
+        if self.families.values().any(|family| family.name == name) {
+            return_errno_with_message!(Errno::EEXIST, "the family name is already in use");
+        }
+
+        let id = match fixed_id {
+            Some(id) if self.families.contains_key(&id) => {
+                return_errno_with_message!(Errno::EEXIST, "the family ID is already in use")
+            }
+            Some(id) => id,
+            None => (GENL_START_ALLOC..=GENL_MAX_ID)
+                .find(|id| !self.families.contains_key(id))
+                .ok_or_else(|| {
+                    Error::with_message(Errno::ENOSPC, "there are no available family IDs")
+                })?,
+        };
+
+        let mcast_groups = self.alloc_groups(family.mcast_groups(), fixed_id)?;
+
+        let registered = Arc::new(RegisteredFamily {
+            id,
+            name,
+            version: family.version(),
+            max_attr: family.max_attr(),
+            ops: family.ops().to_vec(),
+            mcast_groups,
+            handler: family,
+        });
+        self.families.insert(id, registered.clone());
+
+        Ok(registered)
+    }
+
+    /// Allocates IDs for the multicast groups.
+    ///
+    /// If `first_id` is specified, the group IDs will be consecutive, starting from `first_id`.
+    /// Otherwise, the lowest unused group IDs will be allocated.
+    fn alloc_groups(
+        &mut self,
+        names: &[&'static str],
+        first_id: Option<u16>,
+    ) -> Result<Vec<(&'static str, u32)>> {
+        let mut used_groups = self.used_groups;
+        let mut groups = Vec::with_capacity(names.len());
+
+        for (index, name) in names.iter().enumerate() {
+            let group_id = match first_id {
+                Some(first_id) => u32::from(first_id) + index as u32,
+                None => {
+                    let free_groups = !(used_groups.as_u32() | RESERVED_GROUPS);
+                    if free_groups == 0 {
+                        return_errno_with_message!(
+                            Errno::ENOSPC,
+                            "there are no available multicast groups"
+                        );
+                    }
+                    free_groups.trailing_zeros() + 1
+                }
+            };
+
+            let group = GroupIdSet::try_from_group_num(group_id)?;
+            if used_groups.contains(group) {
+                return_errno_with_message!(Errno::EEXIST, "the multicast group is already in use");
+            }
+
+            used_groups.add_groups(group);
+            groups.push((*name, group_id));
+        }
+
+        self.used_groups = used_groups;
+
+        Ok(groups)
+    }
+
+    fn unregister(&mut self, family: &RegisteredFamily) {
+        self.families.remove(&family.id);
+
+        for (_, group_id) in family.mcast_groups.iter() {
+            // The group ID has been validated during registration.
+            self.used_groups
+                .drop_groups(GroupIdSet::try_from_group_num(*group_id).unwrap());
+        }
+    }
+}
+
+/// The multicast groups that are reserved for the families with fixed IDs.
+///
+/// Like Linux, the controller uses the group whose ID is the same as its family ID.
+const RESERVED_GROUPS: u32 = 1 << (ctrl::GENL_ID_CTRL as u32 - 1);
+
+/// The maximum length of a family name, including the null terminator.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/genetlink.h#L8>.
+pub(super) const GENL_NAMSIZ: usize = 16;
+
+/// The minimum family ID that can be dynamically allocated.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/genetlink.h#L34>.
+const GENL_START_ALLOC: u16 = 0x13;
+
+/// The maximum family ID.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/genetlink.h#L11>.
+const GENL_MAX_ID: u16 = 1023;
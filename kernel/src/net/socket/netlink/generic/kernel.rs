@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module defines the kernel socket,
+//! which is responsible for dispatching requests from user space to generic netlink families.
+
+use core::marker::PhantomData;
+
+use super::{
+    family::{self, GenlOpFlags},
+    message::{GenlFamilySegment, GenlMessage, GenlSegment},
+};
+use crate::{
+    net::{
+        socket::netlink::{
+            addr::PortNum,
+            message::{
+                CMsgSegHdr, DoneSegment, ErrorSegment, GetRequestFlags, ProtocolSegment,
+                SegHdrCommonFlags,
+            },
+            table::{NetlinkGenericProtocol, SupportedNetlinkProtocol},
+        },
+        NetNamespace,
+    },
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+};
+
+pub(super) struct NetlinkGenericKernelSocket {
+    _private: PhantomData<()>,
+}
+
+impl NetlinkGenericKernelSocket {
+    const fn new() -> Self {
+        Self {
+            _private: PhantomData,
+        }
+    }
+
+    pub(super) fn handle_request(
+        &self,
+        net_ns: &NetNamespace,
+        request: &GenlSegment,
+        dst_port: PortNum,
+    ) {
+        debug!("netlink generic request: {:?}", request);
+
+        let request_header = request.header();
+
+        let response_segments = match request {
+            GenlSegment::Family(request_segment) => do_family_request(net_ns, request_segment),
+            _ => Err(Error::with_message(
+                Errno::EOPNOTSUPP,
+                "the netlink generic request is not supported",
+            )),
+        };
+
+        let response = match response_segments {
+            Ok(mut segments) => {
+                // Like Linux, an acknowledgment follows the replies if the `ACK` flag is set,
+                // unless the request is a dump request.
+                let flags = SegHdrCommonFlags::from_bits_truncate(request_header.flags);
+                if flags.contains(SegHdrCommonFlags::ACK) && !is_dump_request(request_header) {
+                    let ack_segment = ErrorSegment::new_from_request(request_header, None);
+                    segments.push(GenlSegment::Error(ack_segment));
+                }
+                if segments.is_empty() {
+                    return;
+                }
+                GenlMessage::new(segments)
+            }
+            Err(error) => {
+                let err_segment = ErrorSegment::new_from_request(request_header, Some(error));
+                self.report_error(err_segment, dst_port);
+                return;
+            }
+        };
+
+        debug!("netlink generic response: {:?}", response);
+
+        NetlinkGenericProtocol::unicast(dst_port, response).unwrap();
+    }
+
+    pub(super) fn report_error(&self, err_segment: ErrorSegment, dst_port: PortNum) {
+        let response = GenlMessage::new(vec![GenlSegment::Error(err_segment)]);
+
+        debug!("netlink generic error: {:?}", response);
+
+        NetlinkGenericProtocol::unicast(dst_port, response).unwrap();
+    }
+}
+
+fn do_family_request(
+    net_ns: &NetNamespace,
+    request_segment: &GenlFamilySegment,
+) -> Result<Vec<GenlSegment>> {
+    let request_header = request_segment.header();
+
+    let Some(family) = family::find_family_by_id(request_header.type_) else {
+        return_errno_with_message!(Errno::ENOENT, "the generic netlink family does not exist");
+    };
+
+    let cmd = request_segment.body().cmd;
+    let Some(op) = family.ops.iter().find(|op| op.cmd == cmd) else {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "the command is not supported by the generic netlink family"
+        );
+    };
+
+    // TODO: Linux checks `GENL_ADMIN_PERM` against the initial user namespace. Here we check both
+    // flags against the owner user namespace of the network namespace.
+    if op
+        .flags
+        .intersects(GenlOpFlags::ADMIN_PERM | GenlOpFlags::UNS_ADMIN_PERM)
+    {
+        let current = current_thread!();
+        let posix_thread = current.as_posix_thread().unwrap();
+        net_ns
+            .owner_ns()
+            .check_cap(CapSet::NET_ADMIN, posix_thread)?;
+    }
+
+    let is_dump = is_dump_request(request_header);
+    let required_flag = if is_dump {
+        GenlOpFlags::CMD_CAP_DUMP
+    } else {
+        GenlOpFlags::CMD_CAP_DO
+    };
+    if !op.flags.contains(required_flag) {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "the command does not support the request type"
+        );
+    }
+
+    let mut response_segments: Vec<GenlSegment> = family
+        .handle_request(net_ns, request_segment, is_dump)?
+        .into_iter()
+        .map(GenlSegment::Family)
+        .collect();
+
+    if is_dump {
+        for segment in response_segments.iter_mut() {
+            segment.header_mut().flags |= SegHdrCommonFlags::MULTI.bits();
+        }
+        let done_segment = DoneSegment::new_from_request(request_header, None);
+        response_segments.push(GenlSegment::Done(done_segment));
+    }
+
+    Ok(response_segments)
+}
+
+fn is_dump_request(request_header: &CMsgSegHdr) -> bool {
+    let flags = GetRequestFlags::from_bits_truncate(request_header.flags);
+    flags.contains(GetRequestFlags::DUMP)
+}
+
+// The kernel socket is stateless, so it can be shared among network namespaces. Requests are
+// handled in the network namespace of the sending socket.
+static NETLINK_GENERIC_KERNEL: NetlinkGenericKernelSocket = NetlinkGenericKernelSocket::new();
+
+pub(super) fn get_netlink_generic_kernel() -> &'static NetlinkGenericKernelSocket {
+    &NETLINK_GENERIC_KERNEL
+}
@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Netlink message types for the generic netlink protocol.
+//!
+//! A generic netlink segment consists of a segment header, a [`CGenlMsgHdr`] body, and the
+//! attributes. The segment type is the ID of the family to which the segment belongs, so the
+//! attributes cannot be interpreted until the family is known. Therefore, the attributes are
+//! kept as [`RawAttr`]s and each family parses them with its own attribute types.
+
+use crate::{
+    net::socket::netlink::{
+        message::{
+            CMsgSegHdr, ContinueRead, DoneSegment, ErrorSegment, Message, ProtocolSegment, RawAttr,
+            SegmentBody, SegmentCommon,
+        },
+        table::MulticastMessage,
+    },
+    prelude::*,
+    util::{MultiRead, MultiWrite},
+};
+
+/// A generic netlink message.
+pub(in crate::net::socket::netlink) type GenlMessage = Message<GenlSegment>;
+
+impl MulticastMessage for GenlMessage {}
+
+/// The generic netlink segment, which is the basic unit of a generic netlink message.
+#[derive(Debug, Clone)]
+pub enum GenlSegment {
+    Family(GenlFamilySegment),
+    Done(DoneSegment),
+    Error(ErrorSegment),
+}
+
+/// A segment that belongs to a generic netlink family.
+pub type GenlFamilySegment = SegmentCommon<GenlSegmentBody, RawAttr>;
+
+impl SegmentBody for GenlSegmentBody {
+    type CType = CGenlMsgHdr;
+}
+
+/// `genlmsghdr` in Linux.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/genetlink.h#L13>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct CGenlMsgHdr {
+    /// Command
+    pub cmd: u8,
+    /// Version of the family
+    pub version: u8,
+    /// Reserved
+    pub _reserved: u16,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GenlSegmentBody {
+    pub cmd: u8,
+    pub version: u8,
+}
+
+impl TryFrom<CGenlMsgHdr> for GenlSegmentBody {
+    type Error = Error;
+
+    fn try_from(value: CGenlMsgHdr) -> Result<Self> {
+        Ok(Self {
+            cmd: value.cmd,
+            version: value.version,
+        })
+    }
+}
+
+impl From<GenlSegmentBody> for CGenlMsgHdr {
+    fn from(value: GenlSegmentBody) -> Self {
+        CGenlMsgHdr {
+            cmd: value.cmd,
+            version: value.version,
+            _reserved: 0,
+        }
+    }
+}
+
+/// The minimum segment type that can be used by protocols.
+///
+/// Segment types below this value are reserved for control segments (e.g., `NLMSG_DONE`).
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/netlink.h#L117>.
+pub const NLMSG_MIN_TYPE: u16 = 0x10;
+
+impl ProtocolSegment for GenlSegment {
+    fn header(&self) -> &CMsgSegHdr {
+        match self {
+            GenlSegment::Family(family_segment) => family_segment.header(),
+            GenlSegment::Done(done_segment) => done_segment.header(),
+            GenlSegment::Error(error_segment) => error_segment.header(),
+        }
+    }
+
+    fn header_mut(&mut self) -> &mut CMsgSegHdr {
+        match self {
+            GenlSegment::Family(family_segment) => family_segment.header_mut(),
+            GenlSegment::Done(done_segment) => done_segment.header_mut(),
+            GenlSegment::Error(error_segment) => error_segment.header_mut(),
+        }
+    }
+
+    fn read_from(reader: &mut dyn MultiRead) -> Result<ContinueRead<Self, ErrorSegment>> {
+        let header = reader
+            .read_val_opt::<CMsgSegHdr>()?
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the reader length is too small"))?;
+
+        // Like Linux, control segments sent from user space are silently ignored.
+        if header.type_ < NLMSG_MIN_TYPE {
+            let payload_len = header.calc_payload_len_with_padding(reader)?;
+            reader.skip_some(payload_len);
+            return Ok(ContinueRead::Skipped);
+        }
+
+        let segment = GenlFamilySegment::read_from(&header, reader)?.map(GenlSegment::Family);
+
+        Ok(segment.map_err(|error| ErrorSegment::new_from_request(&header, Some(error))))
+    }
+
+    fn write_to(&self, writer: &mut dyn MultiWrite) -> Result<()> {
+        match self {
+            GenlSegment::Family(family_segment) => family_segment.write_to(writer)?,
+            GenlSegment::Done(done_segment) => done_segment.write_to(writer)?,
+            GenlSegment::Error(error_segment) => error_segment.write_to(writer)?,
+        }
+        Ok(())
+    }
+}
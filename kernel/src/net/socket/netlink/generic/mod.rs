@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Netlink Generic Socket.
+//!
+//! The generic netlink protocol multiplexes multiple families over a single netlink protocol.
+//! Kernel subsystems can register their own families with [`register_family`], and user space
+//! can look up the registered families through the controller family.
+
+pub use family::{
+    register_family, GenlFamily, GenlFamilyHandle, GenlOp, GenlOpFlags, GenlReply, GenlRequest,
+};
+pub(super) use message::GenlMessage;
+
+use crate::net::socket::netlink::{common::NetlinkSocket, table::NetlinkGenericProtocol};
+
+mod bound;
+mod ctrl;
+mod family;
+mod kernel;
+mod message;
+#[cfg(ktest)]
+mod test;
+
+pub type NetlinkGenericSocket = NetlinkSocket<NetlinkGenericProtocol>;
+
+pub(super) fn init() {
+    ctrl::init();
+}
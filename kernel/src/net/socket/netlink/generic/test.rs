@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::vec;
+
+use ostd::{mm::VmWriter, prelude::*};
+
+use super::{
+    ctrl::GENL_ID_CTRL,
+    family::{self, GenlFamily, GenlOp, GenlOpFlags, GenlReply, GenlRequest},
+    message::{GenlFamilySegment, GenlSegmentBody},
+    register_family, NetlinkGenericSocket,
+};
+use crate::{
+    net::{
+        socket::{
+            netlink::{
+                message::{CMsgSegHdr, NoAttr},
+                GroupIdSet, NetlinkSocketAddr,
+            },
+            util::{SendRecvFlags, SocketAddr},
+            Socket,
+        },
+        NetNamespace,
+    },
+    prelude::*,
+};
+
+const TEST_CMD: u8 = 1;
+
+struct TestFamily(&'static str);
+
+impl GenlFamily for TestFamily {
+    type Attr = NoAttr;
+
+    fn name(&self) -> &'static str {
+        self.0
+    }
+
+    fn max_attr(&self) -> u32 {
+        0
+    }
+
+    fn ops(&self) -> &[GenlOp] {
+        const OPS: &[GenlOp] = &[GenlOp {
+            cmd: TEST_CMD,
+            flags: GenlOpFlags::CMD_CAP_DO,
+        }];
+        OPS
+    }
+
+    fn mcast_groups(&self) -> &[&'static str] {
+        &["events"]
+    }
+
+    fn handle_request(&self, request: &GenlRequest<NoAttr>) -> Result<Vec<GenlReply<NoAttr>>> {
+        assert!(core::ptr::eq(
+            request.net_ns(),
+            NetNamespace::get_init_singleton().as_ref()
+        ));
+        assert!(!request.is_dump());
+
+        Ok(vec![GenlReply::new(request.cmd(), Vec::new())])
+    }
+}
+
+#[ktest]
+fn register_and_unregister() {
+    crate::net::socket::netlink::init();
+
+    let handle = register_family(Arc::new(TestFamily("test_reg"))).unwrap();
+    assert_ne!(handle.id(), GENL_ID_CTRL);
+
+    let registered = family::find_family_by_name("test_reg").unwrap();
+    assert_eq!(registered.id, handle.id());
+    assert_eq!(registered.mcast_groups.len(), 1);
+    // The multicast group of the controller is reserved.
+    assert_ne!(registered.mcast_groups[0].1, GENL_ID_CTRL as u32);
+
+    // Family names must be unique.
+    let res = register_family(Arc::new(TestFamily("test_reg")));
+    assert!(res.is_err_and(|err| err.error() == Errno::EEXIST));
+
+    // Family names must fit in `GENL_NAMSIZ` bytes.
+    let res = register_family(Arc::new(TestFamily("test_name_too_long")));
+    assert!(res.is_err_and(|err| err.error() == Errno::EINVAL));
+
+    let id = handle.id();
+    drop(handle);
+    assert!(family::find_family_by_name("test_reg").is_none());
+    assert!(family::find_family_by_id(id).is_none());
+}
+
+#[ktest]
+fn handle_family_request() {
+    crate::net::socket::netlink::init();
+
+    let handle = register_family(Arc::new(TestFamily("test_req"))).unwrap();
+    let registered = family::find_family_by_id(handle.id()).unwrap();
+
+    let header = CMsgSegHdr {
+        len: 0,
+        type_: handle.id(),
+        flags: 0,
+        seq: 42,
+        pid: 100,
+    };
+    let body = GenlSegmentBody {
+        cmd: TEST_CMD,
+        version: 1,
+    };
+    let request = GenlFamilySegment::new(header, body, Vec::new());
+
+    let replies = registered
+        .handle_request(NetNamespace::get_init_singleton(), &request, false)
+        .unwrap();
+    assert_eq!(replies.len(), 1);
+
+    let reply_header = replies[0].header();
+    assert_eq!(reply_header.type_, handle.id());
+    assert_eq!(reply_header.seq, 42);
+    assert_eq!(reply_header.pid, 100);
+    assert_eq!(replies[0].body().cmd, TEST_CMD);
+}
+
+#[ktest]
+fn notify_family() {
+    crate::net::socket::netlink::init();
+
+    // Creates a new generic netlink socket and joins the multicast group of the controller.
+    let socket = NetlinkGenericSocket::new(true, NetNamespace::get_init_singleton().clone());
+    let groups = GroupIdSet::try_from_group_num(GENL_ID_CTRL as u32).unwrap();
+    let socket_addr = SocketAddr::Netlink(NetlinkSocketAddr::new(100, groups));
+    socket.bind(socket_addr).unwrap();
+
+    let mut buffer = vec![0u8; 1024];
+    let mut recv_segment_type_and_cmd = || {
+        let mut writer = VmWriter::from(buffer.as_mut_slice()).to_fallible();
+        let (len, _) = socket
+            .try_recv(&mut writer, SendRecvFlags::empty())
+            .unwrap();
+        assert!(len >= size_of::<CMsgSegHdr>() + 4);
+        let type_ = u16::from_ne_bytes([buffer[4], buffer[5]]);
+        let cmd = buffer[size_of::<CMsgSegHdr>()];
+        (type_, cmd)
+    };
+
+    // Registering a family notifies the controller group with `CTRL_CMD_NEWFAMILY`.
+    let handle = register_family(Arc::new(TestFamily("test_notify"))).unwrap();
+    assert_eq!(recv_segment_type_and_cmd(), (GENL_ID_CTRL, 1));
+
+    // Unregistering a family notifies the controller group with `CTRL_CMD_DELFAMILY`.
+    drop(handle);
+    assert_eq!(recv_segment_type_and_cmd(), (GENL_ID_CTRL, 2));
+}
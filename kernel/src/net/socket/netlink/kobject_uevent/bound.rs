@@ -6,7 +6,11 @@ use super::message::UeventMessage;
 use crate::{
     events::IoEvents,
     net::socket::{
-        netlink::{common::BoundNetlink, NetlinkSocketAddr},
+        netlink::{
+            common::BoundNetlink,
+            receiver::{QueueableMessage, QueuedMessage},
+            NetlinkSocketAddr,
+        },
         util::{datagram_common, SendRecvFlags},
     },
     prelude::*,
@@ -73,7 +77,10 @@ impl datagram_common::Bound for BoundNetlinkUevent {
             let len = response_len.min(writer.sum_lens());
             response.write_to(writer)?;
 
-            let remote = *response.src_addr();
+            let remote = match response {
+                QueuedMessage::Protocol(message) => *message.src_addr(),
+                QueuedMessage::Raw(_) => NetlinkSocketAddr::new_unspecified(),
+            };
 
             let should_dequeue = !flags.contains(SendRecvFlags::MSG_PEEK);
             Ok((should_dequeue, (len, remote)))
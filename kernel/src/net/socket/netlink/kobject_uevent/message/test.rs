@@ -6,20 +6,23 @@ use core::str::FromStr;
 use ostd::{mm::VmWriter, prelude::*};
 
 use crate::{
-    net::socket::{
-        netlink::{
-            kobject_uevent::{
-                message::{
-                    syn_uevent::{SyntheticUevent, Uuid},
-                    uevent::Uevent,
+    net::{
+        socket::{
+            netlink::{
+                kobject_uevent::{
+                    message::{
+                        syn_uevent::{SyntheticUevent, Uuid},
+                        uevent::Uevent,
+                    },
+                    UeventMessage,
                 },
-                UeventMessage,
+                table::{NetlinkUeventProtocol, SupportedNetlinkProtocol},
+                GroupIdSet, NetlinkSocketAddr, NetlinkUeventSocket,
             },
-            table::{NetlinkUeventProtocol, SupportedNetlinkProtocol},
-            GroupIdSet, NetlinkSocketAddr, NetlinkUeventSocket,
+            util::{SendRecvFlags, SocketAddr},
+            Socket,
         },
-        util::{SendRecvFlags, SocketAddr},
-        Socket,
+        NetNamespace,
     },
     prelude::*,
 };
@@ -53,7 +56,7 @@ fn multicast_synthetic_uevent() {
     crate::net::socket::netlink::init();
 
     // Creates a new netlink uevent socket and joins the group for kobject uevents.
-    let socket = NetlinkUeventSocket::new(true);
+    let socket = NetlinkUeventSocket::new(true, NetNamespace::get_init_singleton().clone());
     let socket_addr = SocketAddr::Netlink(NetlinkSocketAddr::new(100, GroupIdSet::new(0x1)));
     socket.bind(socket_addr).unwrap();
 
@@ -33,6 +33,7 @@ use crate::{
 };
 
 pub mod noattr;
+pub mod raw;
 
 /// Netlink attribute header.
 ///
@@ -100,13 +101,27 @@ pub trait Attribute: Debug + Send + Sync {
     /// Returns the byte representation of the payload.
     fn payload_as_bytes(&self) -> &[u8];
 
+    /// Returns the length of the payload (excluding padding).
+    ///
+    /// Attributes whose payloads consist of nested attributes cannot represent their payloads
+    /// as a single byte slice. Such attributes should override this method and
+    /// [`Self::write_payload_to`] instead of implementing [`Self::payload_as_bytes`].
+    fn payload_len(&self) -> usize {
+        self.payload_as_bytes().len()
+    }
+
+    /// Writes the payload (excluding padding) to the `writer`.
+    fn write_payload_to(&self, writer: &mut dyn MultiWrite) -> Result<()> {
+        writer.write(&mut VmReader::from(self.payload_as_bytes()))?;
+        Ok(())
+    }
+
     /// Returns the total length of the attribute (header + payload, including padding).
     fn total_len_with_padding(&self) -> usize {
         // We don't care the attribute type when calculating the attribute length.
         const DUMMY_TYPE: u16 = 0;
 
-        CAttrHeader::from_payload_len(DUMMY_TYPE, self.payload_as_bytes().len())
-            .total_len_with_padding()
+        CAttrHeader::from_payload_len(DUMMY_TYPE, self.payload_len()).total_len_with_padding()
     }
 
     /// Reads the attribute from the `reader`.
@@ -189,11 +204,10 @@ pub trait Attribute: Debug + Send + Sync {
     /// Writes the attribute to the `writer`.
     fn write_to(&self, writer: &mut dyn MultiWrite) -> Result<()> {
         let type_ = self.type_();
-        let payload = self.payload_as_bytes();
 
-        let header = CAttrHeader::from_payload_len(type_, payload.len());
+        let header = CAttrHeader::from_payload_len(type_, self.payload_len());
         writer.write_val_trunc(&header)?;
-        writer.write(&mut VmReader::from(payload))?;
+        self.write_payload_to(writer)?;
 
         let padding_len = header.padding_len();
         writer.skip_some(padding_len);
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{Attribute, CAttrHeader};
+use crate::{net::socket::netlink::message::ContinueRead, prelude::*, util::MultiRead};
+
+/// An attribute whose payload is kept as uninterpreted bytes.
+///
+/// Raw attributes are useful when the attribute class cannot be determined while parsing the
+/// segment (e.g., it depends on the generic netlink family). A raw attribute can be converted to
+/// and from a typed attribute with [`RawAttr::parse`] and [`RawAttr::from_attr`].
+#[derive(Debug, Clone)]
+pub struct RawAttr {
+    type_: u16,
+    payload: Vec<u8>,
+}
+
+impl RawAttr {
+    /// Creates a raw attribute from a typed attribute.
+    pub fn from_attr<A: Attribute>(attr: &A) -> Self {
+        let mut payload = vec![0; attr.payload_len()];
+        let mut writer = VmWriter::from(payload.as_mut_slice()).to_fallible();
+        // Writing to a kernel buffer will never fail.
+        attr.write_payload_to(&mut writer).unwrap();
+
+        Self {
+            type_: attr.type_(),
+            payload,
+        }
+    }
+
+    /// Parses the raw attribute as a typed attribute.
+    pub fn parse<A: Attribute>(&self) -> Result<ContinueRead<A>> {
+        let header = CAttrHeader::from_payload_len(self.type_, self.payload.len());
+        let mut reader = VmReader::from(self.payload.as_slice()).to_fallible();
+
+        A::read_from(&header, &mut reader)
+    }
+}
+
+impl Attribute for RawAttr {
+    fn type_(&self) -> u16 {
+        self.type_
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        &self.payload
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        let mut payload = vec![0; header.payload_len()];
+        reader.read(&mut VmWriter::from(payload.as_mut_slice()))?;
+
+        // Keep the nested and byte order flags so that they can be checked by the typed
+        // attribute.
+        Ok(ContinueRead::Parsed(Self {
+            type_: header.type_,
+            payload,
+        }))
+    }
+}
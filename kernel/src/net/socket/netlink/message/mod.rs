@@ -9,12 +9,13 @@ mod attr;
 mod result;
 mod segment;
 
-pub(super) use attr::{noattr::NoAttr, Attribute, CAttrHeader};
-pub(super) use result::ContinueRead;
+pub(super) use attr::{noattr::NoAttr, raw::RawAttr};
+pub use attr::{Attribute, CAttrHeader};
+pub use result::ContinueRead;
 pub(super) use segment::{
     ack::{DoneSegment, ErrorSegment},
     common::SegmentCommon,
-    header::{CMsgSegHdr, GetRequestFlags, SegHdrCommonFlags},
+    header::{CMsgSegHdr, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags},
     CSegmentType, SegmentBody,
 };
 
@@ -28,7 +29,7 @@ use crate::{
 ///
 /// A netlink message can be transmitted to and from user space using a single send/receive syscall.
 /// It consists of one or more [`ProtocolSegment`]s.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Message<T> {
     segments: Vec<T>,
 }
@@ -39,18 +40,8 @@ impl<T> Message<T> {
     }
 }
 
-impl<T: ProtocolSegment> Message<T> {
-    // We do not provide a `read_from` method here. Netlink sockets should use `T::read_from` to
-    // read the request segments one by one instead.
-
-    pub(super) fn write_to(&self, writer: &mut dyn MultiWrite) -> Result<()> {
-        for segment in self.segments.iter() {
-            segment.write_to(writer)?;
-        }
-
-        Ok(())
-    }
-}
+// We do not provide a `read_from` method for `Message`. Netlink sockets should use `T::read_from`
+// to read the request segments one by one instead.
 
 impl<T: ProtocolSegment> QueueableMessage for Message<T> {
     fn total_len(&self) -> usize {
@@ -59,6 +50,14 @@ impl<T: ProtocolSegment> QueueableMessage for Message<T> {
             .map(|segment| segment.header().len as usize)
             .sum()
     }
+
+    fn write_to(&self, writer: &mut dyn MultiWrite) -> Result<()> {
+        for segment in self.segments.iter() {
+            segment.write_to(writer)?;
+        }
+
+        Ok(())
+    }
 }
 
 pub trait ProtocolSegment: Sized {
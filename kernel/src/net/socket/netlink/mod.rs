@@ -37,8 +37,11 @@
 //! The destination address must be specified when dispatching a message.
 //!
 
+use crate::{fs::file_handle::FileLike, prelude::*};
+
 mod addr;
 mod common;
+mod generic;
 mod kobject_uevent;
 mod message;
 mod options;
@@ -47,11 +50,35 @@ mod route;
 mod table;
 
 pub use addr::{GroupIdSet, NetlinkSocketAddr};
+pub use generic::{
+    register_family, GenlFamily, GenlFamilyHandle, GenlOp, GenlOpFlags, GenlReply, GenlRequest,
+    NetlinkGenericSocket,
+};
 pub use kobject_uevent::NetlinkUeventSocket;
 pub use options::{AddMembership, DropMembership};
+pub use receiver::RawMessageReceiver;
 pub use route::NetlinkRouteSocket;
 pub use table::{is_valid_protocol, StandardNetlinkProtocol};
 
+/// Returns a receiver that delivers raw bytes to the netlink socket.
+///
+/// This is used to deliver the notifications of POSIX message queues (see `SIGEV_THREAD` in
+/// `mq_notify(3)`).
+pub fn raw_receiver_of(file: &dyn FileLike) -> Result<Box<dyn RawMessageReceiver>> {
+    if let Some(socket) = file.downcast_ref::<NetlinkRouteSocket>() {
+        socket.raw_receiver()
+    } else if let Some(socket) = file.downcast_ref::<NetlinkUeventSocket>() {
+        socket.raw_receiver()
+    } else if let Some(socket) = file.downcast_ref::<NetlinkGenericSocket>() {
+        socket.raw_receiver()
+    } else if file.as_socket().is_some() {
+        return_errno_with_message!(Errno::EINVAL, "the socket is not a netlink socket");
+    } else {
+        return_errno_with_message!(Errno::ENOTSOCK, "the file is not a socket");
+    }
+}
+
 pub(in crate::net) fn init() {
     table::init();
+    generic::init();
 }
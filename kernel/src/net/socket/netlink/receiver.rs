@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::{events::IoEvents, prelude::*, process::signal::Pollee};
+use crate::{
+    events::IoEvents, net::socket::util::SocketFilter, prelude::*, process::signal::Pollee,
+    util::MultiWrite,
+};
 
 pub struct MessageReceiver<Message> {
     message_queue: Arc<Mutex<MessageQueue<Message>>>,
@@ -8,18 +11,25 @@ pub struct MessageReceiver<Message> {
 }
 
 pub(super) struct MessageQueue<Message> {
-    messages: VecDeque<Message>,
+    messages: VecDeque<QueuedMessage<Message>>,
     total_length: usize,
     error: Option<Error>,
+    filter: Option<Arc<SocketFilter>>,
 }
 
 impl<Message> MessageQueue<Message> {
     /// Creates a pair of a [`MessageQueue`] and a [`MessageReceiver`].
-    pub(super) fn new_pair(pollee: Pollee) -> (Arc<Mutex<Self>>, MessageReceiver<Message>) {
+    ///
+    /// Incoming messages will be filtered by `filter`, if any.
+    pub(super) fn new_pair(
+        pollee: Pollee,
+        filter: Option<Arc<SocketFilter>>,
+    ) -> (Arc<Mutex<Self>>, MessageReceiver<Message>) {
         let queue = Arc::new(Mutex::new(Self {
             messages: VecDeque::new(),
             total_length: 0,
             error: None,
+            filter,
         }));
         let receiver = MessageReceiver {
             message_queue: queue.clone(),
@@ -40,12 +50,67 @@ impl<Message> MessageQueue<Message> {
     pub(super) fn has_errors(&self) -> bool {
         self.error.is_some()
     }
+
+    /// Sets the filter for incoming messages and returns the old one.
+    pub(super) fn set_filter(
+        &mut self,
+        filter: Option<Arc<SocketFilter>>,
+    ) -> Option<Arc<SocketFilter>> {
+        core::mem::replace(&mut self.filter, filter)
+    }
+
+    /// Creates another [`MessageReceiver`] that enqueues messages to the queue.
+    pub(super) fn new_receiver(
+        this: &Arc<Mutex<Self>>,
+        pollee: Pollee,
+    ) -> MessageReceiver<Message> {
+        MessageReceiver {
+            message_queue: this.clone(),
+            pollee,
+        }
+    }
+}
+
+/// A message in the [`MessageQueue`].
+pub(super) enum QueuedMessage<Message> {
+    /// A message of the netlink protocol.
+    Protocol(Message),
+    /// Raw bytes that are received as is.
+    ///
+    /// The kernel uses raw bytes to send notifications that are not netlink messages (e.g., the
+    /// notifications of POSIX message queues).
+    Raw(Box<[u8]>),
 }
 
 /// Messages that fit into the [`MessageQueue`].
 pub trait QueueableMessage {
     /// Counts and returns the length of the message.
     fn total_len(&self) -> usize;
+
+    /// Writes the message to the `writer`.
+    ///
+    /// If the `writer` does not have enough space, the message will be truncated.
+    fn write_to(&self, writer: &mut dyn MultiWrite) -> Result<()>;
+}
+
+impl<Message: QueueableMessage> QueueableMessage for QueuedMessage<Message> {
+    fn total_len(&self) -> usize {
+        match self {
+            Self::Protocol(message) => message.total_len(),
+            Self::Raw(bytes) => bytes.len(),
+        }
+    }
+
+    fn write_to(&self, writer: &mut dyn MultiWrite) -> Result<()> {
+        match self {
+            Self::Protocol(message) => message.write_to(writer),
+            Self::Raw(bytes) => {
+                let _nbytes = writer.write(&mut VmReader::from(&bytes[..]))?;
+                // `_nbytes` may be smaller than the length. We ignore it to truncate the bytes.
+                Ok(())
+            }
+        }
+    }
 }
 
 impl<Message: QueueableMessage> MessageQueue<Message> {
@@ -58,7 +123,7 @@ impl<Message: QueueableMessage> MessageQueue<Message> {
     /// returned. In this case, the closure will not be executed.
     pub(super) fn dequeue_if<F, R>(&mut self, f: F) -> Result<R>
     where
-        F: FnOnce(&Message, usize) -> Result<(bool, R)>,
+        F: FnOnce(&QueuedMessage<Message>, usize) -> Result<(bool, R)>,
     {
         if let Some(error) = self.error.take() {
             return Err(error);
@@ -78,9 +143,26 @@ impl<Message: QueueableMessage> MessageQueue<Message> {
         Ok(result)
     }
 
+    /// Returns whether the message passes the filter, if any.
+    //
+    // TODO: Linux trims the message to the length returned by the filter. We deliver either the
+    // whole message or nothing, because the messages are not stored as raw bytes.
+    fn passes_filter(&self, message: &Message) -> bool {
+        let Some(filter) = self.filter.as_ref() else {
+            return true;
+        };
+
+        let mut bytes = vec![0; message.total_len()];
+        let mut writer = VmWriter::from(bytes.as_mut_slice()).to_fallible();
+        // Writing to a kernel buffer will never fail.
+        message.write_to(&mut writer).unwrap();
+
+        filter.run_trim_cap(&bytes, 1).is_some()
+    }
+
     /// Tries to enqueue a new message. Returns `false` if the buffer is full.
     #[must_use]
-    pub(self) fn enqueue(&mut self, message: Message) -> bool {
+    pub(self) fn enqueue(&mut self, message: QueuedMessage<Message>) -> bool {
         let length = message.total_len();
 
         // Currently, we don't support sending netlink messages between user spaces, so only the
@@ -103,7 +185,18 @@ impl<Message: QueueableMessage> MessageQueue<Message> {
 
 impl<Message: QueueableMessage> MessageReceiver<Message> {
     pub(super) fn enqueue_message(&self, message: Message) {
-        let is_ok = self.message_queue.lock().enqueue(message);
+        let mut message_queue = self.message_queue.lock();
+        // Messages that are dropped by the filter are silently discarded.
+        if !message_queue.passes_filter(&message) {
+            return;
+        }
+        let is_ok = message_queue.enqueue(QueuedMessage::Protocol(message));
+        drop(message_queue);
+
+        self.notify_enqueued(is_ok);
+    }
+
+    fn notify_enqueued(&self, is_ok: bool) {
         if is_ok {
             self.pollee.notify(IoEvents::IN);
         } else {
@@ -112,4 +205,20 @@ impl<Message: QueueableMessage> MessageReceiver<Message> {
     }
 }
 
+/// A receiver that accepts raw bytes, regardless of the netlink protocol.
+pub trait RawMessageReceiver: Send + Sync {
+    /// Enqueues the raw bytes, which will be received as is.
+    ///
+    /// Unlike netlink messages, the raw bytes are not checked by the socket filter.
+    fn enqueue_raw(&self, bytes: Box<[u8]>);
+}
+
+impl<Message: QueueableMessage + Send> RawMessageReceiver for MessageReceiver<Message> {
+    fn enqueue_raw(&self, bytes: Box<[u8]>) {
+        let is_ok = self.message_queue.lock().enqueue(QueuedMessage::Raw(bytes));
+
+        self.notify_enqueued(is_ok);
+    }
+}
+
 const NETLINK_DEFAULT_BUF_SIZE: usize = 65536;
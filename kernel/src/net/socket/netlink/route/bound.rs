@@ -9,6 +9,7 @@ use crate::{
         netlink::{
             common::BoundNetlink,
             message::{ContinueRead, ProtocolSegment},
+            receiver::QueueableMessage,
             route::kernel::get_netlink_route_kernel,
             NetlinkSocketAddr,
         },
@@ -91,7 +92,7 @@ impl datagram_common::Bound for BoundNetlinkRoute {
                 header.pid = local_port;
             }
 
-            rtnl_kernel.handle_request(&segment, local_port);
+            rtnl_kernel.handle_request(&self.net_ns, &segment, local_port);
         }
 
         Ok(sum_lens)
@@ -4,22 +4,30 @@
 
 use core::num::NonZeroU32;
 
-use super::util::finish_response;
+use aster_bigtcp::wire::{Ipv4Address, Ipv4Cidr};
+
+use super::util::{check_net_admin, finish_response, notify, RtnlGroup};
 use crate::{
     net::{
-        iface::{iter_all_ifaces, Iface},
+        iface::Iface,
         socket::netlink::{
-            message::{CMsgSegHdr, CSegmentType, GetRequestFlags, SegHdrCommonFlags},
+            message::{
+                CMsgSegHdr, CSegmentType, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags,
+            },
             route::message::{
                 AddrAttr, AddrMessageFlags, AddrSegment, AddrSegmentBody, RtScope, RtnlSegment,
             },
         },
+        NetNamespace,
     },
     prelude::*,
     util::net::CSocketAddrFamily,
 };
 
-pub(super) fn do_get_addr(request_segment: &AddrSegment) -> Result<Vec<RtnlSegment>> {
+pub(super) fn do_get_addr(
+    net_ns: &NetNamespace,
+    request_segment: &AddrSegment,
+) -> Result<Vec<RtnlSegment>> {
     let dump_all = {
         let flags = GetRequestFlags::from_bits_truncate(request_segment.header().flags);
         flags.contains(GetRequestFlags::DUMP)
@@ -28,9 +36,26 @@ pub(super) fn do_get_addr(request_segment: &AddrSegment) -> Result<Vec<RtnlSegme
         return_errno_with_message!(Errno::EOPNOTSUPP, "GETADDR only supports dump requests");
     }
 
-    let mut response_segments: Vec<RtnlSegment> = iter_all_ifaces()
-        // GETADDR only supports dump mode, so we're going to report all addresses.
-        .filter_map(|iface| iface_to_new_addr(request_segment.header(), iface))
+    // Like Linux, unknown address families are treated as `AF_UNSPEC`, which dumps addresses in
+    // all address families.
+    let (dump_ipv4, dump_ipv6) = match CSocketAddrFamily::try_from(request_segment.body().family) {
+        Ok(CSocketAddrFamily::AF_INET) => (true, false),
+        Ok(CSocketAddrFamily::AF_INET6) => (false, true),
+        _ => (true, true),
+    };
+
+    // GETADDR only supports dump mode, so we're going to report all addresses.
+    let ifaces = net_ns.ifaces();
+    let ipv4_segments = ifaces
+        .iter()
+        .filter(|_| dump_ipv4)
+        .filter_map(|iface| iface_to_new_addr(request_segment.header(), iface));
+    let ipv6_segments = ifaces
+        .iter()
+        .filter(|_| dump_ipv6)
+        .filter_map(|iface| iface_to_new_addr6(request_segment.header(), iface));
+    let mut response_segments: Vec<RtnlSegment> = ipv4_segments
+        .chain(ipv6_segments)
         .map(RtnlSegment::NewAddr)
         .collect();
 
@@ -39,6 +64,113 @@ pub(super) fn do_get_addr(request_segment: &AddrSegment) -> Result<Vec<RtnlSegme
     Ok(response_segments)
 }
 
+pub(super) fn do_new_addr(
+    net_ns: &NetNamespace,
+    request_segment: &AddrSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let body = request_segment.body();
+
+    // TODO: Support adding IPv6 addresses.
+    if body.family != CSocketAddrFamily::AF_INET as i32 {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "NEWADDR only supports IPv4 addresses");
+    }
+
+    if body.prefix_len > 32 {
+        return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid");
+    }
+
+    let Some(iface) = body
+        .index
+        .and_then(|index| net_ns.get_iface_by_index(index.get()))
+    else {
+        return_errno_with_message!(Errno::ENODEV, "the interface does not exist");
+    };
+
+    // Like Linux, `IFA_LOCAL` takes precedence over `IFA_ADDRESS`. They only differ for
+    // point-to-point interfaces.
+    let local_addr = request_segment.attrs().iter().find_map(|attr| match attr {
+        AddrAttr::Local(addr) => Some(*addr),
+        _ => None,
+    });
+    let addr = local_addr.or_else(|| {
+        request_segment.attrs().iter().find_map(|attr| match attr {
+            AddrAttr::Address(addr) => Some(*addr),
+            _ => None,
+        })
+    });
+    let Some(addr) = addr else {
+        return_errno_with_message!(Errno::EINVAL, "the address is not specified");
+    };
+    let ipv4_cidr = Ipv4Cidr::new(Ipv4Address::from(addr), body.prefix_len);
+
+    if iface.ipv4_addr() == Some(ipv4_cidr.address()) {
+        let flags = NewRequestFlags::from_bits_truncate(request_segment.header().flags);
+        if flags.contains(NewRequestFlags::EXCL) || !flags.contains(NewRequestFlags::REPLACE) {
+            return_errno_with_message!(Errno::EEXIST, "the address already exists");
+        }
+    }
+
+    // FIXME: Linux allows an interface to have multiple IPv4 addresses, but we only support one
+    // address per interface for now. So the old address, if any, is replaced.
+    iface.set_ipv4_cidr(Some(ipv4_cidr));
+
+    let new_addr = iface_to_new_addr(request_segment.header(), &iface).unwrap();
+    notify(RtnlGroup::IPV4_IFADDR, RtnlSegment::NewAddr(new_addr));
+
+    Ok(Vec::new())
+}
+
+pub(super) fn do_del_addr(
+    net_ns: &NetNamespace,
+    request_segment: &AddrSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let body = request_segment.body();
+
+    // TODO: Support deleting IPv6 addresses.
+    if body.family != CSocketAddrFamily::AF_INET as i32 {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "DELADDR only supports IPv4 addresses");
+    }
+
+    let Some(iface) = body
+        .index
+        .and_then(|index| net_ns.get_iface_by_index(index.get()))
+    else {
+        return_errno_with_message!(Errno::ENODEV, "the interface does not exist");
+    };
+
+    let (Some(ipv4_addr), Some(prefix_len)) = (iface.ipv4_addr(), iface.prefix_len()) else {
+        return_errno_with_message!(Errno::EADDRNOTAVAIL, "the address does not exist");
+    };
+
+    // Like Linux, the address is matched against the specified attributes only. The prefix length
+    // is checked only if `IFA_ADDRESS` is specified.
+    let is_matched = request_segment.attrs().iter().all(|attr| match attr {
+        AddrAttr::Local(addr) => Ipv4Address::from(*addr) == ipv4_addr,
+        AddrAttr::Address(addr) => {
+            let cidr = Ipv4Cidr::new(ipv4_addr, prefix_len);
+            body.prefix_len == prefix_len && cidr.contains_addr(&Ipv4Address::from(*addr))
+        }
+        AddrAttr::Label(label) => label.to_bytes() == iface.name().as_bytes(),
+        AddrAttr::Address6(_) => false,
+    });
+    if !is_matched {
+        return_errno_with_message!(Errno::EADDRNOTAVAIL, "the address does not exist");
+    }
+
+    let mut del_addr = iface_to_new_addr(request_segment.header(), &iface).unwrap();
+    del_addr.header_mut().type_ = CSegmentType::DELADDR as _;
+
+    iface.set_ipv4_cidr(None);
+
+    notify(RtnlGroup::IPV4_IFADDR, RtnlSegment::DelAddr(del_addr));
+
+    Ok(Vec::new())
+}
+
 fn iface_to_new_addr(request_header: &CMsgSegHdr, iface: &Arc<Iface>) -> Option<AddrSegment> {
     let ipv4_addr = iface.ipv4_addr()?;
 
@@ -66,3 +198,28 @@ fn iface_to_new_addr(request_header: &CMsgSegHdr, iface: &Arc<Iface>) -> Option<
 
     Some(AddrSegment::new(header, addr_message, attrs))
 }
+
+fn iface_to_new_addr6(request_header: &CMsgSegHdr, iface: &Arc<Iface>) -> Option<AddrSegment> {
+    let ipv6_addr = iface.ipv6_addr()?;
+
+    let header = CMsgSegHdr {
+        len: 0,
+        type_: CSegmentType::NEWADDR as _,
+        flags: SegHdrCommonFlags::empty().bits(),
+        seq: request_header.seq,
+        pid: request_header.pid,
+    };
+
+    let addr_message = AddrSegmentBody {
+        family: CSocketAddrFamily::AF_INET6 as _,
+        prefix_len: iface.ipv6_prefix_len().unwrap(),
+        flags: AddrMessageFlags::PERMANENT,
+        scope: RtScope::HOST,
+        index: NonZeroU32::new(iface.index()),
+    };
+
+    // Linux does not report the `IFA_LABEL` and `IFA_LOCAL` attributes for IPv6 addresses.
+    let attrs = vec![AddrAttr::Address6(ipv6_addr.octets())];
+
+    Some(AddrSegment::new(header, addr_message, attrs))
+}
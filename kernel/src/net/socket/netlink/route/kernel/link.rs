@@ -4,25 +4,36 @@
 
 use core::num::NonZero;
 
-use aster_bigtcp::iface::InterfaceType;
+use aster_bigtcp::iface::{InterfaceFlags, InterfaceType};
 
-use super::util::finish_response;
+use super::util::{check_net_admin, finish_response, notify, RtnlGroup};
 use crate::{
     net::{
-        iface::{iter_all_ifaces, Iface},
+        iface::Iface,
         socket::netlink::{
-            message::{CMsgSegHdr, CSegmentType, GetRequestFlags, SegHdrCommonFlags},
-            route::message::{LinkAttr, LinkSegment, LinkSegmentBody, RtnlSegment},
+            message::{
+                CMsgSegHdr, CSegmentType, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags,
+            },
+            route::message::{
+                LinkAttr, LinkInfoAttr, LinkSegment, LinkSegmentBody, RtnlSegment, VethInfoAttr,
+            },
         },
+        NetNamespace,
     },
     prelude::*,
+    process::{process_table, Pid},
     util::net::CSocketAddrFamily,
 };
 
-pub(super) fn do_get_link(request_segment: &LinkSegment) -> Result<Vec<RtnlSegment>> {
+pub(super) fn do_get_link(
+    net_ns: &NetNamespace,
+    request_segment: &LinkSegment,
+) -> Result<Vec<RtnlSegment>> {
     let filter_by = FilterBy::from_request(request_segment)?;
 
-    let mut response_segments: Vec<RtnlSegment> = iter_all_ifaces()
+    let ifaces = net_ns.ifaces();
+    let mut response_segments: Vec<RtnlSegment> = ifaces
+        .iter()
         // Filter to include only requested links.
         .filter(|iface| match &filter_by {
             FilterBy::Index(index) => *index == iface.index(),
@@ -44,6 +55,284 @@ pub(super) fn do_get_link(request_segment: &LinkSegment) -> Result<Vec<RtnlSegme
     Ok(response_segments)
 }
 
+pub(super) fn do_new_link(
+    net_ns: &NetNamespace,
+    request_segment: &LinkSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let iface = find_link(net_ns, request_segment);
+
+    let flags = NewRequestFlags::from_bits_truncate(request_segment.header().flags);
+
+    let Some(iface) = iface else {
+        if !flags.contains(NewRequestFlags::CREATE) {
+            return_errno_with_message!(Errno::ENODEV, "no link found");
+        }
+        if request_segment.body().index.is_some() {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "creating links with specified indexes is not supported"
+            );
+        }
+        create_link(net_ns, request_segment)?;
+        return Ok(Vec::new());
+    };
+
+    if flags.contains(NewRequestFlags::EXCL) {
+        return_errno_with_message!(Errno::EEXIST, "the link already exists");
+    }
+
+    change_link(&iface, request_segment)?;
+
+    Ok(Vec::new())
+}
+
+pub(super) fn do_set_link(
+    net_ns: &NetNamespace,
+    request_segment: &LinkSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let Some(iface) = find_link(net_ns, request_segment) else {
+        return_errno_with_message!(Errno::ENODEV, "no link found");
+    };
+
+    change_link(&iface, request_segment)?;
+
+    Ok(Vec::new())
+}
+
+/// Finds the link specified by the index or the name in the request.
+fn find_link(net_ns: &NetNamespace, request_segment: &LinkSegment) -> Option<Arc<Iface>> {
+    // `index` takes precedence over `required_name`.
+    if let Some(required_index) = request_segment.body().index {
+        net_ns.get_iface_by_index(required_index.get())
+    } else if let Some(required_name) = find_name(request_segment.attrs()) {
+        net_ns.get_iface_by_name(required_name)
+    } else {
+        None
+    }
+}
+
+/// The minimum MTU of links that carry IPv4 packets.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc791>.
+const IPV4_MIN_MTU: usize = 68;
+
+/// Changes the attributes and the flags of an existing link as requested.
+///
+/// If anything is changed, a notification will be sent to the [`RtnlGroup::LINK`] group.
+fn change_link(iface: &Arc<Iface>, request_segment: &LinkSegment) -> Result<()> {
+    let mut new_mtu = None;
+
+    for attr in request_segment.attrs() {
+        match attr {
+            LinkAttr::Name(name) if name.as_bytes() != iface.name().as_bytes() => {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "renaming links is not supported");
+            }
+            LinkAttr::Mtu(mtu) => {
+                let mtu = *mtu as usize;
+                if !(IPV4_MIN_MTU..=iface.max_mtu()).contains(&mtu) {
+                    return_errno_with_message!(Errno::EINVAL, "the MTU is out of range");
+                }
+                new_mtu = Some(mtu);
+            }
+            _ => (),
+        }
+    }
+
+    let mut is_changed = false;
+
+    if let Some(mtu) = new_mtu.filter(|mtu| *mtu != iface.mtu()) {
+        iface.set_mtu(mtu);
+        is_changed = true;
+    }
+
+    let body = request_segment.body();
+    if !body.flags.is_empty() || !body.change.is_empty() {
+        let new_flags = combine_flags(iface, body.flags, body.change);
+        if new_flags != iface.flags() {
+            iface.set_flags(new_flags);
+            is_changed = true;
+        }
+    }
+
+    if is_changed {
+        let new_link = iface_to_new_link(request_segment.header(), iface);
+        notify(RtnlGroup::LINK, RtnlSegment::NewLink(new_link));
+    }
+
+    Ok(())
+}
+
+/// Creates a new link as requested.
+///
+/// Currently, only veth pairs can be created.
+fn create_link(net_ns: &NetNamespace, request_segment: &LinkSegment) -> Result<()> {
+    let Some(link_info) = request_segment.attrs().iter().find_map(|attr| {
+        if let LinkAttr::LinkInfo(link_info) = attr {
+            Some(link_info)
+        } else {
+            None
+        }
+    }) else {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the link kind is not specified");
+    };
+
+    let kind = link_info.iter().find_map(|attr| {
+        if let LinkInfoAttr::Kind(kind) = attr {
+            Some(kind.to_str().unwrap_or_default())
+        } else {
+            None
+        }
+    });
+    if kind != Some("veth") {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the link kind is not supported");
+    }
+
+    let peer = link_info
+        .iter()
+        .filter_map(|attr| {
+            if let LinkInfoAttr::VethData(veth_data) = attr {
+                Some(veth_data)
+            } else {
+                None
+            }
+        })
+        .flatten()
+        .map(|attr| {
+            let VethInfoAttr::Peer(body, attrs) = attr;
+            (body, attrs)
+        })
+        .next();
+
+    let (peer_ns, peer_name) = if let Some((peer_body, peer_attrs)) = peer {
+        if peer_body.index.is_some() {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "creating links with specified indexes is not supported"
+            );
+        }
+        (
+            get_target_net_ns(peer_attrs)?,
+            find_name(peer_attrs).map(ToString::to_string),
+        )
+    } else {
+        (None, None)
+    };
+
+    // Linux ignores the namespace attributes of the link itself unless the link is moved to
+    // another namespace, which is not supported. Therefore, we reject such requests.
+    if get_target_net_ns(request_segment.attrs())?.is_some() {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "creating links in other network namespaces is not supported"
+        );
+    }
+
+    let name = find_name(request_segment.attrs());
+    let (iface, peer_iface) = match peer_ns {
+        Some(peer_ns) => {
+            check_net_admin(&peer_ns)?;
+            net_ns.new_veth_pair(name, &peer_ns, peer_name.as_deref())?
+        }
+        None => net_ns.new_veth_pair(name, net_ns, peer_name.as_deref())?,
+    };
+
+    // FIXME: Notifications should only be sent to the sockets in the same network namespace as
+    // the iface, but netlink multicast groups are not per-network namespace yet.
+    for iface in [iface, peer_iface] {
+        let new_link = iface_to_new_link(request_segment.header(), &iface);
+        notify(RtnlGroup::LINK, RtnlSegment::NewLink(new_link));
+    }
+
+    Ok(())
+}
+
+/// Returns the network namespace specified by the link attributes, if any.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/core/rtnetlink.c#L2512>.
+fn get_target_net_ns(attrs: &[LinkAttr]) -> Result<Option<Arc<NetNamespace>>> {
+    for attr in attrs {
+        match attr {
+            LinkAttr::NetNsPid(pid) => {
+                let Some(process) = process_table::get_process(*pid as Pid) else {
+                    return_errno_with_message!(Errno::ESRCH, "the process does not exist");
+                };
+                let main_thread = process.main_thread();
+                let ns_proxy = main_thread.as_posix_thread().unwrap().ns_proxy().lock();
+                let Some(ns_proxy) = ns_proxy.as_ref() else {
+                    return_errno_with_message!(Errno::ESRCH, "the process has exited");
+                };
+                return Ok(Some(ns_proxy.net_ns().clone()));
+            }
+            LinkAttr::NetNsFd(_) => {
+                // TODO: Support specifying network namespaces via file descriptors.
+                return_errno_with_message!(
+                    Errno::EOPNOTSUPP,
+                    "specifying network namespaces via file descriptors is not supported"
+                );
+            }
+            _ => (),
+        }
+    }
+
+    Ok(None)
+}
+
+fn find_name(attrs: &[LinkAttr]) -> Option<&str> {
+    attrs.iter().find_map(|attr| {
+        if let LinkAttr::Name(name) = attr {
+            // Non-UTF-8 names are rejected when parsing the attributes.
+            name.to_str().ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// Combines the current flags of the iface with the requested flags.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/core/rtnetlink.c#L1110>.
+fn combine_flags(
+    iface: &Iface,
+    requested_flags: InterfaceFlags,
+    change: InterfaceFlags,
+) -> InterfaceFlags {
+    // For backward compatibility, an empty change mask means that all flags are changed.
+    let change = if change.is_empty() {
+        InterfaceFlags::all()
+    } else {
+        change
+    };
+
+    // Only these flags can be changed by user space.
+    // Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/core/dev.c#L9006>.
+    const CHANGEABLE_FLAGS: InterfaceFlags = InterfaceFlags::UP
+        .union(InterfaceFlags::DEBUG)
+        .union(InterfaceFlags::NOTRAILERS)
+        .union(InterfaceFlags::NOARP)
+        .union(InterfaceFlags::DYNAMIC)
+        .union(InterfaceFlags::MULTICAST)
+        .union(InterfaceFlags::PORTSEL)
+        .union(InterfaceFlags::AUTOMEDIA);
+    let change = change & CHANGEABLE_FLAGS;
+
+    let old_flags = iface.flags();
+    let mut new_flags = (old_flags - change) | (requested_flags & change);
+
+    // The operational state follows the administrative state since there is no carrier
+    // detection for now.
+    if new_flags.contains(InterfaceFlags::UP) {
+        new_flags |= InterfaceFlags::RUNNING | InterfaceFlags::LOWER_UP;
+    } else {
+        new_flags -= InterfaceFlags::RUNNING | InterfaceFlags::LOWER_UP;
+    }
+
+    new_flags
+}
+
 enum FilterBy<'a> {
     Index(u32),
     Name(&'a str),
@@ -69,14 +358,7 @@ impl<'a> FilterBy<'a> {
             return Ok(Self::Index(required_index.get()));
         }
 
-        let required_name = request_segment.attrs().iter().find_map(|attr| {
-            if let LinkAttr::Name(name) = attr {
-                Some(name.to_str().unwrap())
-            } else {
-                None
-            }
-        });
-        if let Some(required_name) = required_name {
+        if let Some(required_name) = find_name(request_segment.attrs()) {
             return Ok(Self::Name(required_name));
         }
 
@@ -91,9 +373,8 @@ impl<'a> FilterBy<'a> {
 // Reference: <https://docs.kernel.org/userspace-api/netlink/intro.html#strict-checking>.
 
 fn validate_getlink_request(body: &LinkSegmentBody) -> Result<()> {
-    // FIXME: The Linux implementation also checks the `padding` and `change` fields,
-    // but these fields are lost during the conversion of a `CIfInfoMsg` to `LinkSegmentBody`.
-    // We should consider including the `change` field in `LinkSegmentBody`.
+    // FIXME: The Linux implementation also checks the `padding` and `change` fields.
+    // The `padding` field is lost during the conversion of a `CIfInfoMsg` to `LinkSegmentBody`.
     // Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/core/rtnetlink.c#L4043>.
     if !body.flags.is_empty() || body.type_ != InterfaceType::NETROM {
         return_errno_with_message!(Errno::EINVAL, "the flags or the type is not valid");
@@ -134,6 +415,7 @@ fn iface_to_new_link(request_header: &CMsgSegHdr, iface: &Arc<Iface>) -> LinkSeg
         type_: iface.type_(),
         index: NonZero::new(iface.index()),
         flags: iface.flags(),
+        change: InterfaceFlags::empty(),
     };
 
     let attrs = vec![
@@ -7,16 +7,21 @@ use core::marker::PhantomData;
 
 use super::message::{RtnlMessage, RtnlSegment};
 use crate::{
-    net::socket::netlink::{
-        addr::PortNum,
-        message::{ErrorSegment, ProtocolSegment},
-        table::{NetlinkRouteProtocol, SupportedNetlinkProtocol},
+    net::{
+        socket::netlink::{
+            addr::PortNum,
+            message::{ErrorSegment, ProtocolSegment, SegHdrCommonFlags},
+            table::{NetlinkRouteProtocol, SupportedNetlinkProtocol},
+        },
+        NetNamespace,
     },
     prelude::*,
 };
 
 mod addr;
 mod link;
+mod neigh;
+mod route;
 mod util;
 
 pub(super) struct NetlinkRouteKernelSocket {
@@ -30,14 +35,27 @@ impl NetlinkRouteKernelSocket {
         }
     }
 
-    pub(super) fn handle_request(&self, request: &RtnlSegment, dst_port: PortNum) {
+    pub(super) fn handle_request(
+        &self,
+        net_ns: &NetNamespace,
+        request: &RtnlSegment,
+        dst_port: PortNum,
+    ) {
         debug!("netlink route request: {:?}", request);
 
         let request_header = request.header();
 
         let response_segments = match request {
-            RtnlSegment::GetLink(request_segment) => link::do_get_link(request_segment),
-            RtnlSegment::GetAddr(request_segment) => addr::do_get_addr(request_segment),
+            RtnlSegment::NewLink(request_segment) => link::do_new_link(net_ns, request_segment),
+            RtnlSegment::GetLink(request_segment) => link::do_get_link(net_ns, request_segment),
+            RtnlSegment::SetLink(request_segment) => link::do_set_link(net_ns, request_segment),
+            RtnlSegment::NewAddr(request_segment) => addr::do_new_addr(net_ns, request_segment),
+            RtnlSegment::DelAddr(request_segment) => addr::do_del_addr(net_ns, request_segment),
+            RtnlSegment::GetAddr(request_segment) => addr::do_get_addr(net_ns, request_segment),
+            RtnlSegment::NewRoute(request_segment) => route::do_new_route(net_ns, request_segment),
+            RtnlSegment::DelRoute(request_segment) => route::do_del_route(net_ns, request_segment),
+            RtnlSegment::GetRoute(request_segment) => route::do_get_route(net_ns, request_segment),
+            RtnlSegment::GetNeigh(request_segment) => neigh::do_get_neigh(net_ns, request_segment),
             _ => Err(Error::with_message(
                 Errno::EOPNOTSUPP,
                 "the netlink route request is not supported",
@@ -45,11 +63,24 @@ impl NetlinkRouteKernelSocket {
         };
 
         let response = match response_segments {
-            Ok(segments) => RtnlMessage::new(segments),
-            Err(error) => {
-                // TODO: Deal with the `NetlinkMessageCommonFlags::ACK` flag.
-                // Should we return `ErrorSegment` if ACK flag does not exist?
+            Ok(mut segments) => {
+                // Requests with the `ACK` flag expect an acknowledgment after the responses, if
+                // any. Dump responses end with a DONE segment instead, so they are not
+                // acknowledged.
                 // Reference: <https://docs.kernel.org/userspace-api/netlink/intro.html#netlink-message-types>.
+                let flags = SegHdrCommonFlags::from_bits_truncate(request_header.flags);
+                let is_dump = matches!(segments.last(), Some(RtnlSegment::Done(_)));
+                if flags.contains(SegHdrCommonFlags::ACK) && !is_dump {
+                    let ack_segment = ErrorSegment::new_from_request(request_header, None);
+                    segments.push(RtnlSegment::Error(ack_segment));
+                }
+                if segments.is_empty() {
+                    return;
+                }
+                RtnlMessage::new(segments)
+            }
+            Err(error) => {
+                // Errors are always reported, regardless of whether the `ACK` flag is set.
                 let err_segment = ErrorSegment::new_from_request(request_header, Some(error));
                 self.report_error(err_segment, dst_port);
                 return;
@@ -70,7 +101,10 @@ impl NetlinkRouteKernelSocket {
     }
 }
 
-/// FIXME: NETLINK_ROUTE_KERNEL should be a per-network namespace socket
+// The kernel socket is stateless, so it can be shared among network namespaces. Requests are
+// handled in the network namespace of the sending socket.
+//
+// FIXME: Netlink port numbers and multicast groups should also be per-network namespace.
 static NETLINK_ROUTE_KERNEL: NetlinkRouteKernelSocket = NetlinkRouteKernelSocket::new();
 
 pub(super) fn get_netlink_route_kernel() -> &'static NetlinkRouteKernelSocket {
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handle neighbor-related requests.
+
+use core::num::NonZeroU32;
+
+use aster_bigtcp::iface::{Neighbor, NeighborState};
+
+use super::util::finish_response;
+use crate::{
+    net::{
+        iface::Iface,
+        socket::netlink::{
+            message::{CMsgSegHdr, CSegmentType, GetRequestFlags, SegHdrCommonFlags},
+            route::message::{
+                NeighAttr, NeighSegment, NeighSegmentBody, NeighState, RouteType, RtnlSegment,
+            },
+        },
+        NetNamespace,
+    },
+    prelude::*,
+    util::net::CSocketAddrFamily,
+};
+
+pub(super) fn do_get_neigh(
+    net_ns: &NetNamespace,
+    request_segment: &NeighSegment,
+) -> Result<Vec<RtnlSegment>> {
+    let dump_all = {
+        let flags = GetRequestFlags::from_bits_truncate(request_segment.header().flags);
+        flags.contains(GetRequestFlags::DUMP)
+    };
+    if !dump_all {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "GETNEIGH only supports dump requests");
+    }
+
+    // TODO: Support dumping IPv6 neighbors.
+    let dump_ipv4 = request_segment.body().family != CSocketAddrFamily::AF_INET6 as i32;
+
+    let ifaces = net_ns.ifaces();
+    let mut response_segments: Vec<RtnlSegment> = ifaces
+        .iter()
+        .filter(|_| dump_ipv4)
+        .flat_map(|iface| {
+            iface
+                .neighbors()
+                .into_iter()
+                .map(|neighbor| neighbor_to_new_neigh(request_segment.header(), iface, &neighbor))
+        })
+        .map(RtnlSegment::NewNeigh)
+        .collect();
+
+    finish_response(request_segment.header(), dump_all, &mut response_segments);
+
+    Ok(response_segments)
+}
+
+fn neighbor_to_new_neigh(
+    request_header: &CMsgSegHdr,
+    iface: &Iface,
+    neighbor: &Neighbor,
+) -> NeighSegment {
+    let header = CMsgSegHdr {
+        len: 0,
+        type_: CSegmentType::NEWNEIGH as _,
+        flags: SegHdrCommonFlags::empty().bits(),
+        seq: request_header.seq,
+        pid: request_header.pid,
+    };
+
+    let state = match neighbor.state {
+        NeighborState::Reachable => NeighState::REACHABLE,
+        NeighborState::Stale => NeighState::STALE,
+    };
+
+    let neigh_message = NeighSegmentBody {
+        family: CSocketAddrFamily::AF_INET as _,
+        ifindex: NonZeroU32::new(iface.index()),
+        state,
+        flags: 0,
+        type_: RouteType::UNICAST as _,
+    };
+
+    let attrs = vec![
+        NeighAttr::Dst(neighbor.ip_addr.octets()),
+        NeighAttr::LlAddr(neighbor.ether_addr.0),
+    ];
+
+    NeighSegment::new(header, neigh_message, attrs)
+}
@@ -0,0 +1,337 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handle route-related requests.
+
+use aster_bigtcp::{
+    iface::{InterfaceFlags, Ipv4Route},
+    wire::{Ipv4Address, Ipv4Cidr},
+};
+
+use super::util::{check_net_admin, finish_response, notify, RtnlGroup};
+use crate::{
+    net::{
+        iface::Iface,
+        socket::netlink::{
+            message::{
+                CMsgSegHdr, CSegmentType, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags,
+            },
+            route::message::{
+                RouteAttr, RouteSegment, RouteSegmentBody, RouteType, RtScope, RtnlSegment,
+                RTPROT_BOOT, RTPROT_KERNEL, RT_TABLE_MAIN,
+            },
+        },
+        NetNamespace,
+    },
+    prelude::*,
+    util::net::CSocketAddrFamily,
+};
+
+pub(super) fn do_get_route(
+    net_ns: &NetNamespace,
+    request_segment: &RouteSegment,
+) -> Result<Vec<RtnlSegment>> {
+    let dump_all = {
+        let flags = GetRequestFlags::from_bits_truncate(request_segment.header().flags);
+        flags.contains(GetRequestFlags::DUMP)
+    };
+    if !dump_all {
+        // TODO: Support looking up the route to a specific destination.
+        return_errno_with_message!(Errno::EOPNOTSUPP, "GETROUTE only supports dump requests");
+    }
+
+    // TODO: Support dumping IPv6 routes.
+    if request_segment.body().family == CSocketAddrFamily::AF_INET6 as i32 {
+        let mut response_segments = Vec::new();
+        finish_response(request_segment.header(), dump_all, &mut response_segments);
+        return Ok(response_segments);
+    }
+
+    let request_header = request_segment.header();
+
+    let ifaces = net_ns.ifaces();
+    let connected_routes = ifaces
+        .iter()
+        .filter_map(|iface| Some((iface, connected_route(iface)?, RouteKind::Connected)));
+    let static_routes = ifaces.iter().flat_map(|iface| {
+        iface
+            .ipv4_routes()
+            .into_iter()
+            .map(move |route| (iface, route, RouteKind::Static))
+    });
+    let mut response_segments: Vec<RtnlSegment> = connected_routes
+        .chain(static_routes)
+        .map(|(iface, route, kind)| {
+            let header = new_header(request_header, CSegmentType::NEWROUTE);
+            RtnlSegment::NewRoute(new_route_segment(header, iface, &route, kind))
+        })
+        .collect();
+
+    finish_response(request_header, dump_all, &mut response_segments);
+
+    Ok(response_segments)
+}
+
+pub(super) fn do_new_route(
+    net_ns: &NetNamespace,
+    request_segment: &RouteSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let body = request_segment.body();
+    let attrs = RouteAttrs::new(request_segment)?;
+
+    if body.type_ != RouteType::UNICAST {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "only unicast routes are supported");
+    }
+
+    let dst = attrs.dst;
+    if dst.network() != dst {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the destination address has bits set outside the prefix"
+        );
+    }
+
+    let iface = match (attrs.oif, attrs.gateway) {
+        (Some(oif), gateway) => {
+            let Some(iface) = net_ns.get_iface_by_index(oif) else {
+                return_errno_with_message!(Errno::ENODEV, "the output interface does not exist");
+            };
+            if gateway.is_some_and(|gateway| !is_connected(&iface, &gateway)) {
+                return_errno_with_message!(Errno::ENETUNREACH, "the gateway is unreachable");
+            }
+            iface
+        }
+        (None, Some(gateway)) => {
+            let ifaces = net_ns.ifaces();
+            let Some(iface) = ifaces.iter().find(|iface| is_connected(iface, &gateway)) else {
+                return_errno_with_message!(Errno::ENETUNREACH, "the gateway is unreachable");
+            };
+            iface.clone()
+        }
+        (None, None) => {
+            return_errno_with_message!(Errno::ENODEV, "the output interface is not specified");
+        }
+    };
+
+    let route = Ipv4Route {
+        dst,
+        gateway: attrs.gateway,
+        metric: attrs.priority.unwrap_or(0),
+    };
+
+    let flags = NewRequestFlags::from_bits_truncate(request_segment.header().flags);
+
+    // Routes in the main routing table are identified by their destination subnets and their
+    // metrics, regardless of which ifaces they go through.
+    let old_route = net_ns.ifaces().iter().find_map(|old_iface| {
+        let old_route = old_iface
+            .ipv4_routes()
+            .into_iter()
+            .find(|old_route| old_route.same_key(&route))?;
+        Some((old_iface.clone(), old_route))
+    });
+    match old_route {
+        // FIXME: Linux allows adding multiple routes with the same key if they have different
+        // next hops, unless the `REPLACE` flag is set. We reject such requests for now.
+        Some(_) if !flags.contains(NewRequestFlags::REPLACE) => {
+            return_errno_with_message!(Errno::EEXIST, "the route already exists");
+        }
+        Some(_) if flags.contains(NewRequestFlags::EXCL) => {
+            return_errno_with_message!(Errno::EEXIST, "the route already exists");
+        }
+        Some((old_iface, old_route)) => {
+            old_iface.remove_ipv4_route(old_route.dst, old_route.metric);
+        }
+        None if !flags.contains(NewRequestFlags::CREATE) => {
+            return_errno_with_message!(Errno::ENOENT, "the route does not exist");
+        }
+        None => (),
+    }
+
+    iface.add_ipv4_route(route);
+
+    let header = new_header(request_segment.header(), CSegmentType::NEWROUTE);
+    let new_route = new_route_segment(header, &iface, &route, RouteKind::Static);
+    notify(RtnlGroup::IPV4_ROUTE, RtnlSegment::NewRoute(new_route));
+
+    Ok(Vec::new())
+}
+
+pub(super) fn do_del_route(
+    net_ns: &NetNamespace,
+    request_segment: &RouteSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let attrs = RouteAttrs::new(request_segment)?;
+
+    // Like Linux, the route is matched against the specified attributes only.
+    let matched = net_ns
+        .ifaces()
+        .iter()
+        .filter(|iface| attrs.oif.is_none_or(|oif| oif == iface.index()))
+        .find_map(|iface| {
+            let route = iface.ipv4_routes().into_iter().find(|route| {
+                route.dst == attrs.dst
+                    && attrs
+                        .priority
+                        .is_none_or(|priority| priority == route.metric)
+                    && attrs
+                        .gateway
+                        .is_none_or(|gateway| Some(gateway) == route.gateway)
+            })?;
+            Some((iface.clone(), route))
+        });
+    let Some((iface, route)) = matched else {
+        return_errno_with_message!(Errno::ESRCH, "the route does not exist");
+    };
+
+    iface.remove_ipv4_route(route.dst, route.metric);
+
+    let header = new_header(request_segment.header(), CSegmentType::DELROUTE);
+    let del_route = new_route_segment(header, &iface, &route, RouteKind::Static);
+    notify(RtnlGroup::IPV4_ROUTE, RtnlSegment::DelRoute(del_route));
+
+    Ok(Vec::new())
+}
+
+/// The attributes of a NEWROUTE or DELROUTE request.
+struct RouteAttrs {
+    dst: Ipv4Cidr,
+    oif: Option<u32>,
+    gateway: Option<Ipv4Address>,
+    priority: Option<u32>,
+}
+
+impl RouteAttrs {
+    fn new(request_segment: &RouteSegment) -> Result<Self> {
+        let body = request_segment.body();
+
+        // TODO: Support IPv6 routes.
+        if body.family != CSocketAddrFamily::AF_INET as i32 {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "only IPv4 routes are supported");
+        }
+
+        if body.dst_len > 32 {
+            return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid");
+        }
+
+        let mut table = body.table as u32;
+        let mut dst_addr = Ipv4Address::UNSPECIFIED;
+        let mut oif = None;
+        let mut gateway = None;
+        let mut priority = None;
+
+        for attr in request_segment.attrs() {
+            match attr {
+                RouteAttr::Dst(addr) => dst_addr = Ipv4Address::from(*addr),
+                RouteAttr::Oif(index) => oif = Some(*index),
+                RouteAttr::Gateway(addr) => gateway = Some(Ipv4Address::from(*addr)),
+                RouteAttr::Priority(metric) => priority = Some(*metric),
+                // `RTA_TABLE` takes precedence over the table ID in the segment body, which
+                // cannot represent table IDs larger than 255.
+                RouteAttr::Table(id) => table = *id,
+                RouteAttr::PrefSrc(_) => (),
+            }
+        }
+
+        // Like Linux, routes are added to the main routing table by default.
+        // TODO: Support other routing tables (e.g., the local routing table).
+        if table != 0 && table != RT_TABLE_MAIN {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "only the main routing table is supported"
+            );
+        }
+
+        Ok(Self {
+            dst: Ipv4Cidr::new(dst_addr, body.dst_len),
+            oif,
+            gateway,
+            priority,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RouteKind {
+    /// A route to the subnet of the iface's own address, which is added implicitly.
+    Connected,
+    /// A route that is added explicitly.
+    Static,
+}
+
+/// Returns the route to the subnet of the iface's IPv4 address, if any.
+///
+/// Like Linux, there is no such route if the iface is down.
+fn connected_route(iface: &Iface) -> Option<Ipv4Route> {
+    if !iface.flags().contains(InterfaceFlags::UP) {
+        return None;
+    }
+
+    let cidr = Ipv4Cidr::new(iface.ipv4_addr()?, iface.prefix_len()?);
+    Some(Ipv4Route {
+        dst: cidr.network(),
+        gateway: None,
+        metric: 0,
+    })
+}
+
+/// Returns whether `addr` is in the subnet of the iface's IPv4 address.
+fn is_connected(iface: &Iface, addr: &Ipv4Address) -> bool {
+    connected_route(iface).is_some_and(|route| route.dst.contains_addr(addr))
+}
+
+fn new_header(request_header: &CMsgSegHdr, type_: CSegmentType) -> CMsgSegHdr {
+    CMsgSegHdr {
+        len: 0,
+        type_: type_ as _,
+        flags: SegHdrCommonFlags::empty().bits(),
+        seq: request_header.seq,
+        pid: request_header.pid,
+    }
+}
+
+fn new_route_segment(
+    header: CMsgSegHdr,
+    iface: &Iface,
+    route: &Ipv4Route,
+    kind: RouteKind,
+) -> RouteSegment {
+    let (protocol, scope) = match (kind, route.gateway) {
+        (RouteKind::Connected, _) => (RTPROT_KERNEL, RtScope::LINK),
+        (RouteKind::Static, Some(_)) => (RTPROT_BOOT, RtScope::UNIVERSE),
+        (RouteKind::Static, None) => (RTPROT_BOOT, RtScope::LINK),
+    };
+
+    let route_message = RouteSegmentBody {
+        family: CSocketAddrFamily::AF_INET as _,
+        dst_len: route.dst.prefix_len(),
+        src_len: 0,
+        tos: 0,
+        table: RT_TABLE_MAIN as u8,
+        protocol,
+        scope,
+        type_: RouteType::UNICAST,
+        flags: 0,
+    };
+
+    // The attributes are in the same order as in Linux.
+    let mut attrs = vec![RouteAttr::Table(RT_TABLE_MAIN)];
+    if route.dst.prefix_len() != 0 {
+        attrs.push(RouteAttr::Dst(route.dst.address().octets()));
+    }
+    if route.metric != 0 {
+        attrs.push(RouteAttr::Priority(route.metric));
+    }
+    if kind == RouteKind::Connected {
+        attrs.push(RouteAttr::PrefSrc(iface.ipv4_addr().unwrap().octets()));
+    }
+    if let Some(gateway) = route.gateway {
+        attrs.push(RouteAttr::Gateway(gateway.octets()));
+    }
+    attrs.push(RouteAttr::Oif(iface.index()));
+
+    RouteSegment::new(header, route_message, attrs)
+}
@@ -1,13 +1,30 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use crate::{
-    net::socket::netlink::{
-        message::{CMsgSegHdr, DoneSegment, ProtocolSegment, SegHdrCommonFlags},
-        route::message::RtnlSegment,
+    net::{
+        socket::netlink::{
+            addr::GroupIdSet,
+            message::{CMsgSegHdr, DoneSegment, ProtocolSegment, SegHdrCommonFlags},
+            route::message::{RtnlMessage, RtnlSegment},
+            table::{NetlinkRouteProtocol, SupportedNetlinkProtocol},
+        },
+        NetNamespace,
     },
     prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
 };
 
+/// Checks whether the current process can modify the network configuration of `net_ns`.
+///
+/// Like Linux, requests other than GET requests require the `CAP_NET_ADMIN` capability in the
+/// owner user namespace of the network namespace.
+pub fn check_net_admin(net_ns: &NetNamespace) -> Result<()> {
+    let current = current_thread!();
+    let posix_thread = current.as_posix_thread().unwrap();
+
+    net_ns.owner_ns().check_cap(CapSet::NET_ADMIN, posix_thread)
+}
+
 /// Finishes a response message.
 pub fn finish_response(
     request_header: &CMsgSegHdr,
@@ -37,3 +54,27 @@ fn add_multi_flag(response_segments: &mut [RtnlSegment]) {
         header.flags = flags.bits();
     }
 }
+
+/// Multicast groups of the netlink route protocol.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.1/source/include/uapi/linux/rtnetlink.h#L698>.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+pub enum RtnlGroup {
+    LINK = 1,
+    IPV4_IFADDR = 5,
+    IPV4_ROUTE = 7,
+}
+
+/// Notifies the members of the multicast `group` about a configuration change.
+///
+/// Like Linux, the notification carries the sequence number and the port number of the request
+/// that causes the change.
+pub fn notify(group: RtnlGroup, notification: RtnlSegment) {
+    let groups = GroupIdSet::try_from_group_num(group as u32).unwrap();
+    let message = RtnlMessage::new(vec![notification]);
+
+    NetlinkRouteProtocol::multicast(groups, message).unwrap();
+}
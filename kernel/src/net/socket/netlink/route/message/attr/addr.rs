@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use super::IFNAME_SIZE;
 use crate::{
     net::socket::netlink::message::{Attribute, CAttrHeader, ContinueRead},
     prelude::*,
@@ -27,9 +28,10 @@ enum AddrAttrClass {
     TARGET_NETNSID = 10,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AddrAttr {
     Address([u8; 4]),
+    Address6([u8; 16]),
     Local([u8; 4]),
     Label(CString),
 }
@@ -37,7 +39,7 @@ pub enum AddrAttr {
 impl AddrAttr {
     fn class(&self) -> AddrAttrClass {
         match self {
-            AddrAttr::Address(_) => AddrAttrClass::ADDRESS,
+            AddrAttr::Address(_) | AddrAttr::Address6(_) => AddrAttrClass::ADDRESS,
             AddrAttr::Local(_) => AddrAttrClass::LOCAL,
             AddrAttr::Label(_) => AddrAttrClass::LABEL,
         }
@@ -52,6 +54,7 @@ impl Attribute for AddrAttr {
     fn payload_as_bytes(&self) -> &[u8] {
         match self {
             AddrAttr::Address(address) => address,
+            AddrAttr::Address6(address) => address,
             AddrAttr::Local(local) => local,
             AddrAttr::Label(label) => label.as_bytes_with_nul(),
         }
@@ -62,26 +65,36 @@ impl Attribute for AddrAttr {
         Self: Sized,
     {
         let payload_len = header.payload_len();
-        reader.skip_some(payload_len);
 
-        // GETADDR only supports dump requests. These requests do not have any attributes.
-        // According to the Linux behavior, we should just ignore all the attributes.
+        // GETADDR requests should ignore all the attributes, according to the Linux behavior. So
+        // unknown or invalid attributes are skipped without reporting errors here. NEWADDR
+        // requests will fail later if any required attributes are missing.
+        let Ok(class) = AddrAttrClass::try_from(header.type_()) else {
+            reader.skip_some(payload_len);
+            return Ok(ContinueRead::Skipped);
+        };
 
-        Ok(ContinueRead::Skipped)
-    }
-
-    fn read_all_from(
-        reader: &mut dyn MultiRead,
-        total_len: usize,
-    ) -> Result<ContinueRead<Vec<Self>>>
-    where
-        Self: Sized,
-    {
-        reader.skip_some(total_len);
-
-        // GETADDR only supports dump requests. These requests do not have any attributes.
-        // According to the Linux behavior, we should just ignore all the attributes.
+        let res = match (class, payload_len) {
+            (AddrAttrClass::ADDRESS, 4) => {
+                Self::Address(reader.read_val_opt::<[u8; 4]>()?.unwrap())
+            }
+            (AddrAttrClass::ADDRESS, 16) => {
+                Self::Address6(reader.read_val_opt::<[u8; 16]>()?.unwrap())
+            }
+            (AddrAttrClass::LOCAL, 4) => Self::Local(reader.read_val_opt::<[u8; 4]>()?.unwrap()),
+            (AddrAttrClass::LABEL, 1..=IFNAME_SIZE) => {
+                let (label, label_len) = reader.read_cstring_until_end(payload_len)?;
+                if label_len != payload_len {
+                    reader.skip_some(payload_len - label_len);
+                }
+                Self::Label(label)
+            }
+            (_, _) => {
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::Skipped);
+            }
+        };
 
-        Ok(ContinueRead::Skipped)
+        Ok(ContinueRead::Parsed(res))
     }
 }
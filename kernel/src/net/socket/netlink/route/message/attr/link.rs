@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::IFNAME_SIZE;
+use super::{
+    super::segment::link::{CIfinfoMsg, LinkSegmentBody},
+    IFNAME_SIZE,
+};
 use crate::{
     net::socket::netlink::message::{Attribute, CAttrHeader, ContinueRead},
     prelude::*,
@@ -82,12 +85,15 @@ enum LinkAttrClass {
     PARENT_DEV_BUS_NAME = 57,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum LinkAttr {
     Name(CString),
     Mtu(u32),
     TxqLen(u32),
     LinkMode(u8),
+    LinkInfo(Vec<LinkInfoAttr>),
+    NetNsPid(u32),
+    NetNsFd(u32),
     ExtMask(RtExtFilter),
 }
 
@@ -98,6 +104,9 @@ impl LinkAttr {
             LinkAttr::Mtu(_) => LinkAttrClass::MTU,
             LinkAttr::TxqLen(_) => LinkAttrClass::TXQLEN,
             LinkAttr::LinkMode(_) => LinkAttrClass::LINKMODE,
+            LinkAttr::LinkInfo(_) => LinkAttrClass::LINKINFO,
+            LinkAttr::NetNsPid(_) => LinkAttrClass::NET_NS_PID,
+            LinkAttr::NetNsFd(_) => LinkAttrClass::NET_NS_FD,
             LinkAttr::ExtMask(_) => LinkAttrClass::EXT_MASK,
         }
     }
@@ -114,7 +123,12 @@ impl Attribute for LinkAttr {
             LinkAttr::Mtu(mtu) => mtu.as_bytes(),
             LinkAttr::TxqLen(txq_len) => txq_len.as_bytes(),
             LinkAttr::LinkMode(link_mode) => link_mode.as_bytes(),
+            LinkAttr::NetNsPid(pid) => pid.as_bytes(),
+            LinkAttr::NetNsFd(fd) => fd.as_bytes(),
             LinkAttr::ExtMask(ext_filter) => ext_filter.as_bytes(),
+            LinkAttr::LinkInfo(_) => {
+                unreachable!("kernel should not write link info attributes to user space")
+            }
         }
     }
 
@@ -145,11 +159,27 @@ impl Attribute for LinkAttr {
                         "the link attribute is invalid",
                     ));
                 }
+                if name.to_str().is_err() {
+                    return Ok(ContinueRead::skipped_with_error(
+                        Errno::EINVAL,
+                        "the link name is not valid UTF-8",
+                    ));
+                }
                 Self::Name(name)
             }
             (LinkAttrClass::MTU, 4) => Self::Mtu(reader.read_val_opt::<u32>()?.unwrap()),
             (LinkAttrClass::TXQLEN, 4) => Self::TxqLen(reader.read_val_opt::<u32>()?.unwrap()),
             (LinkAttrClass::LINKMODE, 1) => Self::LinkMode(reader.read_val_opt::<u8>()?.unwrap()),
+            (LinkAttrClass::LINKINFO, _) => match LinkInfoAttr::read_all_from(reader, payload_len)?
+            {
+                ContinueRead::Parsed(attrs) => Self::LinkInfo(attrs),
+                ContinueRead::Skipped => return Ok(ContinueRead::Skipped),
+                ContinueRead::SkippedErr(err) => return Ok(ContinueRead::SkippedErr(err)),
+            },
+            (LinkAttrClass::NET_NS_PID, 4) => {
+                Self::NetNsPid(reader.read_val_opt::<u32>()?.unwrap())
+            }
+            (LinkAttrClass::NET_NS_FD, 4) => Self::NetNsFd(reader.read_val_opt::<u32>()?.unwrap()),
             (LinkAttrClass::EXT_MASK, 4) => {
                 const { assert!(size_of::<RtExtFilter>() == 4) };
                 Self::ExtMask(reader.read_val_opt::<RtExtFilter>()?.unwrap())
@@ -160,6 +190,8 @@ impl Attribute for LinkAttr {
                 | LinkAttrClass::MTU
                 | LinkAttrClass::TXQLEN
                 | LinkAttrClass::LINKMODE
+                | LinkAttrClass::NET_NS_PID
+                | LinkAttrClass::NET_NS_FD
                 | LinkAttrClass::EXT_MASK,
                 _,
             ) => {
@@ -186,6 +218,156 @@ impl Attribute for LinkAttr {
     }
 }
 
+/// Attributes nested in [`LinkAttr::LinkInfo`].
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/if_link.h#L1181>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+#[repr(u16)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+enum LinkInfoAttrClass {
+    UNSPEC = 0,
+    KIND = 1,
+    DATA = 2,
+    XSTATS = 3,
+    SLAVE_KIND = 4,
+    SLAVE_DATA = 5,
+}
+
+#[derive(Debug, Clone)]
+pub enum LinkInfoAttr {
+    Kind(CString),
+    /// The kind-specific data.
+    ///
+    /// Since veth is the only supported kind, the data is always parsed as veth data.
+    VethData(Vec<VethInfoAttr>),
+}
+
+impl Attribute for LinkInfoAttr {
+    fn type_(&self) -> u16 {
+        match self {
+            LinkInfoAttr::Kind(_) => LinkInfoAttrClass::KIND as u16,
+            LinkInfoAttr::VethData(_) => LinkInfoAttrClass::DATA as u16,
+        }
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        unreachable!("kernel should not write link info attributes to user space")
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        let payload_len = header.payload_len();
+
+        let Ok(class) = LinkInfoAttrClass::try_from(header.type_()) else {
+            reader.skip_some(payload_len);
+            return Ok(ContinueRead::Skipped);
+        };
+
+        let res = match (class, payload_len) {
+            (LinkInfoAttrClass::KIND, 1..) => {
+                let (kind, kind_len) = reader.read_cstring_until_end(payload_len)?;
+                if kind_len != payload_len {
+                    reader.skip_some(payload_len - kind_len);
+                }
+                Self::Kind(kind)
+            }
+            (LinkInfoAttrClass::DATA, _) => {
+                match VethInfoAttr::read_all_from(reader, payload_len)? {
+                    ContinueRead::Parsed(attrs) => Self::VethData(attrs),
+                    ContinueRead::Skipped => return Ok(ContinueRead::Skipped),
+                    ContinueRead::SkippedErr(err) => return Ok(ContinueRead::SkippedErr(err)),
+                }
+            }
+
+            (LinkInfoAttrClass::KIND, _) => {
+                warn!("link info attribute `{:?}` contains invalid payload", class);
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::skipped_with_error(
+                    Errno::EINVAL,
+                    "the link info attribute is invalid",
+                ));
+            }
+
+            (_, _) => {
+                warn!("link info attribute `{:?}` is not supported", class);
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::Skipped);
+            }
+        };
+
+        Ok(ContinueRead::Parsed(res))
+    }
+}
+
+/// Attributes nested in [`LinkInfoAttr::VethData`].
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/veth.h#L5>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+#[repr(u16)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+enum VethInfoAttrClass {
+    UNSPEC = 0,
+    PEER = 1,
+}
+
+#[derive(Debug, Clone)]
+pub enum VethInfoAttr {
+    /// The peer link, which is described by a `ifinfomsg` followed by link attributes.
+    Peer(LinkSegmentBody, Vec<LinkAttr>),
+}
+
+impl Attribute for VethInfoAttr {
+    fn type_(&self) -> u16 {
+        match self {
+            VethInfoAttr::Peer(..) => VethInfoAttrClass::PEER as u16,
+        }
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        unreachable!("kernel should not write veth info attributes to user space")
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        let payload_len = header.payload_len();
+
+        match VethInfoAttrClass::try_from(header.type_()) {
+            Ok(VethInfoAttrClass::PEER) if payload_len >= size_of::<CIfinfoMsg>() => {}
+            Ok(VethInfoAttrClass::PEER) => {
+                warn!("veth info attribute `PEER` contains invalid payload");
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::skipped_with_error(
+                    Errno::EINVAL,
+                    "the veth info attribute is invalid",
+                ));
+            }
+            _ => {
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::Skipped);
+            }
+        }
+
+        let c_body = reader.read_val_opt::<CIfinfoMsg>()?.unwrap();
+        let attrs_len = payload_len - size_of::<CIfinfoMsg>();
+
+        let body = match LinkSegmentBody::try_from(c_body) {
+            Ok(body) => body,
+            Err(err) => {
+                reader.skip_some(attrs_len);
+                return Ok(ContinueRead::SkippedErr(err));
+            }
+        };
+
+        Ok(LinkAttr::read_all_from(reader, attrs_len)?.map(|attrs| Self::Peer(body, attrs)))
+    }
+}
+
 bitflags! {
     /// New extended info filters for [`NlLinkAttr::ExtMask`].
     ///
@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    net::socket::netlink::message::{Attribute, CAttrHeader, ContinueRead},
+    prelude::*,
+    util::MultiRead,
+};
+
+/// Neighbor-related attributes.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.1/source/include/uapi/linux/neighbour.h#L18>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+#[repr(u16)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+enum NeighAttrClass {
+    UNSPEC = 0,
+    DST = 1,
+    LLADDR = 2,
+    CACHEINFO = 3,
+    PROBES = 4,
+    VLAN = 5,
+    PORT = 6,
+    VNI = 7,
+    IFINDEX = 8,
+    MASTER = 9,
+    LINK_NETNSID = 10,
+    SRC_VNI = 11,
+    /// Originator of the entry
+    PROTOCOL = 12,
+    NH_ID = 13,
+    FDB_EXT_ATTRS = 14,
+    FLAGS_EXT = 15,
+    NDM_STATE_MASK = 16,
+    NDM_FLAGS_MASK = 17,
+}
+
+#[derive(Debug, Clone)]
+pub enum NeighAttr {
+    Dst([u8; 4]),
+    LlAddr([u8; 6]),
+}
+
+impl NeighAttr {
+    fn class(&self) -> NeighAttrClass {
+        match self {
+            NeighAttr::Dst(_) => NeighAttrClass::DST,
+            NeighAttr::LlAddr(_) => NeighAttrClass::LLADDR,
+        }
+    }
+}
+
+impl Attribute for NeighAttr {
+    fn type_(&self) -> u16 {
+        self.class() as u16
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        match self {
+            NeighAttr::Dst(dst) => dst,
+            NeighAttr::LlAddr(lladdr) => lladdr,
+        }
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        let payload_len = header.payload_len();
+
+        // GETNEIGH dump requests should ignore all the attributes. So unknown or invalid
+        // attributes are skipped without reporting errors here.
+        let Ok(class) = NeighAttrClass::try_from(header.type_()) else {
+            reader.skip_some(payload_len);
+            return Ok(ContinueRead::Skipped);
+        };
+
+        let res = match (class, payload_len) {
+            (NeighAttrClass::DST, 4) => Self::Dst(reader.read_val_opt::<[u8; 4]>()?.unwrap()),
+            (NeighAttrClass::LLADDR, 6) => Self::LlAddr(reader.read_val_opt::<[u8; 6]>()?.unwrap()),
+            (_, _) => {
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::Skipped);
+            }
+        };
+
+        Ok(ContinueRead::Parsed(res))
+    }
+}
@@ -8,14 +8,23 @@
 mod attr;
 mod segment;
 
-pub(super) use attr::{addr::AddrAttr, link::LinkAttr};
+pub(super) use attr::{
+    addr::AddrAttr,
+    link::{LinkAttr, LinkInfoAttr, VethInfoAttr},
+    neigh::NeighAttr,
+    route::RouteAttr,
+};
 pub(super) use segment::{
     addr::{AddrMessageFlags, AddrSegment, AddrSegmentBody, RtScope},
     link::{LinkSegment, LinkSegmentBody},
+    neigh::{NeighSegment, NeighSegmentBody, NeighState},
+    route::{RouteSegment, RouteSegmentBody, RouteType, RTPROT_BOOT, RTPROT_KERNEL, RT_TABLE_MAIN},
     RtnlSegment,
 };
 
-use crate::net::socket::netlink::message::Message;
+use crate::net::socket::netlink::{message::Message, table::MulticastMessage};
 
 /// A netlink route message.
 pub(in crate::net::socket::netlink) type RtnlMessage = Message<RtnlSegment>;
+
+impl MulticastMessage for RtnlMessage {}
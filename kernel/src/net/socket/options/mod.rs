@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::util::LingerOption;
+use super::util::{LingerOption, SocketFilter};
 use crate::{impl_socket_options, net::socket::unix::CUserCred, prelude::*, process::Gid};
 
 mod macros;
@@ -22,9 +22,13 @@ impl_socket_options!(
     pub struct Linger(LingerOption);
     pub struct KeepAlive(bool);
     pub struct PassCred(bool);
+    pub struct Timestamp(bool);
+    pub struct TimestampNs(bool);
     pub struct PeerCred(CUserCred);
     pub struct AcceptConn(bool);
     pub struct SendBufForce(u32);
     pub struct RecvBufForce(u32);
     pub struct PeerGroups(Arc<[Gid]>);
+    pub struct AttachFilter(Arc<SocketFilter>);
+    pub struct DetachFilter(());
 );
@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::EthernetAddress;
+
+use crate::{net::socket::util::SocketAddr, prelude::*};
+
+/// A link-layer socket address.
+///
+/// This corresponds to `struct sockaddr_ll` in Linux.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacketSocketAddr {
+    /// The protocol (i.e., the EtherType) in host byte order.
+    pub protocol: u16,
+    /// The index of the iface, or zero for any iface.
+    pub ifindex: u32,
+    /// The ARP hardware type of the iface.
+    pub hatype: u16,
+    /// The type of the frame.
+    pub pkttype: u8,
+    /// The hardware address.
+    pub hardware_addr: Option<EthernetAddress>,
+}
+
+impl TryFrom<SocketAddr> for PacketSocketAddr {
+    type Error = Error;
+
+    fn try_from(value: SocketAddr) -> Result<Self> {
+        let SocketAddr::Packet(packet_addr) = value else {
+            return_errno_with_message!(Errno::EINVAL, "invalid packet socket addr");
+        };
+        Ok(packet_addr)
+    }
+}
+
+impl From<PacketSocketAddr> for SocketAddr {
+    fn from(value: PacketSocketAddr) -> Self {
+        SocketAddr::Packet(value)
+    }
+}
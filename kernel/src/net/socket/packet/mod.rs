@@ -0,0 +1,517 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Packet sockets.
+//!
+//! A packet socket (`AF_PACKET`) receives and sends link-layer frames directly, bypassing the
+//! network protocols. A `SOCK_RAW` packet socket handles frames including the link-layer header,
+//! while a `SOCK_DGRAM` packet socket (also known as a cooked packet socket) handles frames with
+//! the link-layer header removed.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man7/packet.7.html>.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_bigtcp::{
+    errors::packet::{RecvError, SendError},
+    wire::{EthernetAddress, EthernetFrame, EthernetProtocol, EthernetRepr, HardwareAddress},
+};
+
+use crate::{
+    events::IoEvents,
+    fs::utils::Inode,
+    match_sock_option_mut, match_sock_option_ref,
+    net::{
+        iface::{Iface, PacketSocket as PacketIfaceSocket},
+        socket::{
+            ip::DatagramObserver,
+            new_pseudo_inode,
+            options::{AttachFilter, DetachFilter, Error as SocketError, SocketOption},
+            private::SocketPrivate,
+            util::{
+                options::{GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet},
+                MessageHeader, SendRecvFlags, SocketAddr, SocketFilter,
+            },
+            Socket,
+        },
+        NetNamespace,
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    util::{MultiRead, MultiWrite},
+};
+
+mod addr;
+mod options;
+
+pub use addr::PacketSocketAddr;
+use options::CPacketMreqType;
+pub use options::{AddMembership, CPacketMreq, DropMembership};
+
+/// A packet socket.
+///
+/// Creating a packet socket requires the `CAP_NET_RAW` capability.
+pub struct PacketSocket {
+    is_cooked: bool,
+    net_ns: Arc<NetNamespace>,
+    state: RwMutex<State>,
+    memberships: Mutex<Vec<Membership>>,
+    options: RwLock<SocketOptionSet>,
+
+    is_nonblocking: AtomicBool,
+    pollee: Pollee,
+    pseudo_inode: Arc<dyn Inode>,
+}
+
+struct State {
+    /// The protocol in host byte order.
+    protocol: u16,
+    /// The index of the bound iface, or zero if the socket is not bound to an iface.
+    ifindex: u32,
+    filter: Option<Arc<SocketFilter>>,
+    /// The underlying sockets, one for each iface.
+    ///
+    /// If the socket is bound to an iface, the sockets for other ifaces will only be used to send
+    /// frames, so they receive nothing.
+    //
+    // TODO: Ifaces that are added to the network namespace after the sockets are created (e.g.,
+    // veth ifaces) are not covered.
+    sockets: Vec<PacketIfaceSocket>,
+}
+
+struct Membership {
+    mreq: CPacketMreq,
+    count: usize,
+}
+
+impl PacketSocket {
+    pub fn new(
+        is_nonblocking: bool,
+        is_cooked: bool,
+        protocol: u16,
+        net_ns: Arc<NetNamespace>,
+    ) -> Arc<Self> {
+        let pollee = Pollee::new();
+
+        let sockets = new_iface_sockets(&net_ns, &pollee, is_cooked, protocol, 0, None);
+        let state = State {
+            protocol,
+            ifindex: 0,
+            filter: None,
+            sockets,
+        };
+
+        Arc::new(Self {
+            is_cooked,
+            net_ns,
+            state: RwMutex::new(state),
+            memberships: Mutex::new(Vec::new()),
+            options: RwLock::new(SocketOptionSet::new_udp()),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            pollee,
+            pseudo_inode: new_pseudo_inode(),
+        })
+    }
+
+    fn try_recv(&self, writer: &mut dyn MultiWrite) -> Result<(usize, SocketAddr)> {
+        let state = self.state.read();
+
+        for socket in state.sockets.iter() {
+            let result = socket.recv(|packet, info| {
+                let copied_res = writer.write(&mut VmReader::from(packet));
+                (copied_res, *info)
+            });
+
+            let (copied_res, info) = match result {
+                Ok(res) => res,
+                Err(RecvError::Exhausted) => continue,
+            };
+            self.pollee.invalidate();
+
+            let iface = socket.iface();
+            let src_addr = PacketSocketAddr {
+                protocol: info.protocol,
+                ifindex: iface.index(),
+                hatype: iface.type_() as u16,
+                pkttype: info.pkt_type as u8,
+                hardware_addr: Some(info.src_addr),
+            };
+            return Ok((copied_res?, SocketAddr::Packet(src_addr)));
+        }
+
+        return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty")
+    }
+
+    fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote: Option<PacketSocketAddr>,
+    ) -> Result<usize> {
+        let state = self.state.read();
+
+        let (ifindex, protocol, dst_addr) = match remote {
+            Some(addr) => (addr.ifindex, addr.protocol, addr.hardware_addr),
+            None => (state.ifindex, state.protocol, None),
+        };
+        if ifindex == 0 {
+            return_errno_with_message!(Errno::ENXIO, "the interface is not specified");
+        }
+
+        let Some(socket) = state
+            .sockets
+            .iter()
+            .find(|socket| socket.iface().index() == ifindex)
+        else {
+            return_errno_with_message!(Errno::ENXIO, "the interface does not exist");
+        };
+
+        let mut frame = vec![0u8; reader.sum_lens()];
+        let len = reader.read(&mut VmWriter::from(frame.as_mut_slice()))?;
+        frame.truncate(len);
+
+        // Cooked sockets send frames without link-layer headers, so we have to generate them.
+        if self.is_cooked {
+            let Some(dst_addr) = dst_addr else {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the destination hardware address is not specified"
+                );
+            };
+            let ether_repr = EthernetRepr {
+                src_addr: ether_addr_of(socket.iface()),
+                dst_addr,
+                ethertype: EthernetProtocol::from(protocol),
+            };
+
+            let mut data = vec![0u8; ether_repr.buffer_len()];
+            ether_repr.emit(&mut EthernetFrame::new_unchecked(data.as_mut_slice()));
+            data.extend_from_slice(&frame);
+            frame = data;
+        }
+
+        match socket.send(&frame) {
+            Ok(()) => (),
+            Err(SendError::Malformed) => {
+                return_errno_with_message!(Errno::EINVAL, "the link-layer header is invalid");
+            }
+            Err(SendError::TooLarge) => {
+                return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+            }
+            Err(SendError::BufferFull) => {
+                return_errno_with_message!(Errno::EAGAIN, "the send buffer is full");
+            }
+        }
+
+        let iface = socket.iface().clone();
+        drop(state);
+
+        self.pollee.invalidate();
+        iface.poll();
+
+        Ok(len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let state = self.state.read();
+        let mut events = IoEvents::empty();
+
+        if state.sockets.iter().any(|socket| socket.can_recv()) {
+            events |= IoEvents::IN;
+        }
+
+        if state.sockets.iter().any(|socket| socket.can_send()) {
+            events |= IoEvents::OUT;
+        }
+
+        events
+    }
+
+    fn set_filter(&self, filter: Option<Arc<SocketFilter>>) {
+        let mut state = self.state.write();
+
+        for socket in state.sockets.iter() {
+            socket.set_filter(filter.clone());
+        }
+        state.filter = filter;
+    }
+
+    fn add_membership(&self, mreq: &CPacketMreq) -> Result<()> {
+        let iface = get_iface(&self.net_ns, mreq.mr_ifindex as u32)?;
+        if mreq.mr_alen as usize > mreq.mr_address.len() {
+            return_errno_with_message!(Errno::EINVAL, "the address is too long");
+        }
+
+        let mut memberships = self.memberships.lock();
+
+        if let Some(membership) = memberships.iter_mut().find(|m| m.mreq == *mreq) {
+            membership.count += 1;
+            return Ok(());
+        }
+
+        apply_membership(&iface, mreq, true);
+        memberships.push(Membership {
+            mreq: *mreq,
+            count: 1,
+        });
+
+        Ok(())
+    }
+
+    fn drop_membership(&self, mreq: &CPacketMreq) -> Result<()> {
+        let mut memberships = self.memberships.lock();
+
+        let Some(pos) = memberships.iter().position(|m| m.mreq == *mreq) else {
+            return_errno_with_message!(Errno::EADDRNOTAVAIL, "the membership does not exist");
+        };
+
+        memberships[pos].count -= 1;
+        if memberships[pos].count == 0 {
+            let membership = memberships.remove(pos);
+            if let Ok(iface) = get_iface(&self.net_ns, membership.mreq.mr_ifindex as u32) {
+                apply_membership(&iface, &membership.mreq, false);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for PacketSocket {
+    fn drop(&mut self) {
+        for membership in self.memberships.get_mut().drain(..) {
+            if let Ok(iface) = get_iface(&self.net_ns, membership.mreq.mr_ifindex as u32) {
+                apply_membership(&iface, &membership.mreq, false);
+            }
+        }
+    }
+}
+
+impl Pollable for PacketSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl SocketPrivate for PacketSocket {
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+}
+
+impl Socket for PacketSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = PacketSocketAddr::try_from(socket_addr)?;
+        if addr.ifindex != 0 {
+            get_iface(&self.net_ns, addr.ifindex)?;
+        }
+
+        let mut state = self.state.write();
+
+        // A zero protocol means that the protocol is not changed.
+        let protocol = if addr.protocol != 0 {
+            addr.protocol
+        } else {
+            state.protocol
+        };
+
+        // Drop the old sockets first so that they no longer receive frames.
+        state.sockets.clear();
+        state.sockets = new_iface_sockets(
+            &self.net_ns,
+            &self.pollee,
+            self.is_cooked,
+            protocol,
+            addr.ifindex,
+            state.filter.as_ref(),
+        );
+        state.protocol = protocol;
+        state.ifindex = addr.ifindex;
+
+        self.pollee.invalidate();
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let state = self.state.read();
+
+        let mut addr = PacketSocketAddr {
+            protocol: state.protocol,
+            ifindex: state.ifindex,
+            ..Default::default()
+        };
+        if let Ok(iface) = get_iface(&self.net_ns, state.ifindex) {
+            addr.hatype = iface.type_() as u16;
+            addr.hardware_addr = Some(ether_addr_of(&iface));
+        }
+
+        Ok(SocketAddr::Packet(addr))
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote = match addr {
+            Some(addr) => Some(PacketSocketAddr::try_from(addr)?),
+            None => None,
+        };
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        // TODO: Block if the send buffer is full
+        self.try_send(reader, remote)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (received_bytes, peer_addr) =
+            self.block_on_with_flags(IoEvents::IN, flags, || self.try_recv(writer))?;
+
+        // TODO: Receive control message
+
+        let message_header = MessageHeader::new(Some(peer_addr), Vec::new());
+
+        Ok((received_bytes, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                // TODO: Support socket errors for packet sockets
+                socket_errors.set(None);
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        self.options.read().get_option(option, self)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            attach_filter: AttachFilter => {
+                let filter = attach_filter.get().unwrap();
+                self.set_filter(Some(filter.clone()));
+                return Ok(());
+            },
+            _detach_filter: DetachFilter => {
+                if self.state.read().filter.is_none() {
+                    return_errno_with_message!(Errno::ENOENT, "no filter is attached");
+                }
+                self.set_filter(None);
+                return Ok(());
+            },
+            add_membership: AddMembership => {
+                return self.add_membership(add_membership.get().unwrap());
+            },
+            drop_membership: DropMembership => {
+                return self.drop_membership(drop_membership.get().unwrap());
+            },
+            _ => ()
+        });
+
+        self.options.write().set_option(option, self).map(|_| ())
+    }
+
+    fn pseudo_inode(&self) -> &Arc<dyn Inode> {
+        &self.pseudo_inode
+    }
+}
+
+impl GetSocketLevelOption for PacketSocket {
+    fn is_listening(&self) -> bool {
+        false
+    }
+}
+
+impl SetSocketLevelOption for PacketSocket {}
+
+/// Creates the underlying sockets for all ifaces in the network namespace.
+///
+/// If `ifindex` is not zero, only the socket for the iface with the index will receive frames.
+fn new_iface_sockets(
+    net_ns: &NetNamespace,
+    pollee: &Pollee,
+    is_cooked: bool,
+    protocol: u16,
+    ifindex: u32,
+    filter: Option<&Arc<SocketFilter>>,
+) -> Vec<PacketIfaceSocket> {
+    net_ns
+        .ifaces()
+        .iter()
+        .map(|iface| {
+            let protocol = if ifindex == 0 || iface.index() == ifindex {
+                protocol
+            } else {
+                0
+            };
+            let socket = PacketIfaceSocket::new(
+                iface.clone(),
+                protocol,
+                is_cooked,
+                DatagramObserver::new(pollee.clone()),
+            );
+            socket.set_filter(filter.cloned());
+            socket
+        })
+        .collect()
+}
+
+fn get_iface(net_ns: &NetNamespace, ifindex: u32) -> Result<Arc<Iface>> {
+    let Some(iface) = net_ns.get_iface_by_index(ifindex) else {
+        return_errno_with_message!(Errno::ENODEV, "the interface does not exist");
+    };
+    Ok(iface)
+}
+
+/// Returns the Ethernet address of the iface.
+///
+/// For ifaces without hardware addresses (e.g., the loopback iface), the address is all zeros, as
+/// Linux does.
+fn ether_addr_of(iface: &Iface) -> EthernetAddress {
+    match iface.hardware_addr() {
+        HardwareAddress::Ethernet(addr) => addr,
+        _ => EthernetAddress([0; 6]),
+    }
+}
+
+fn apply_membership(iface: &Iface, mreq: &CPacketMreq, is_add: bool) {
+    match CPacketMreqType::try_from(mreq.mr_type) {
+        Ok(CPacketMreqType::PACKET_MR_PROMISC) if is_add => iface.inc_promiscuity(),
+        Ok(CPacketMreqType::PACKET_MR_PROMISC) => iface.dec_promiscuity(),
+        // TODO: Support multicast filtering. For now, all multicast frames are received, so
+        // these memberships have no effects.
+        Ok(_) => (),
+        // Linux ignores unknown membership types.
+        Err(_) => warn!("unknown packet membership type: {}", mreq.mr_type),
+    }
+}
@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{impl_socket_options, prelude::*};
+
+impl_socket_options!(
+    pub struct AddMembership(CPacketMreq);
+    pub struct DropMembership(CPacketMreq);
+);
+
+/// A request to add or drop a membership of a packet socket.
+///
+/// This corresponds to `struct packet_mreq` in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, PartialEq, Eq)]
+pub struct CPacketMreq {
+    /// The interface index.
+    pub mr_ifindex: i32,
+    /// The type of the membership.
+    pub mr_type: u16,
+    /// The length of the address.
+    pub mr_alen: u16,
+    /// The physical-layer address.
+    pub mr_address: [u8; 8],
+}
+
+/// The type of a membership.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/if_packet.h#L300>.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, TryFromInt, PartialEq, Eq)]
+#[expect(non_camel_case_types)]
+pub(super) enum CPacketMreqType {
+    PACKET_MR_MULTICAST = 0,
+    PACKET_MR_PROMISC = 1,
+    PACKET_MR_ALLMULTI = 2,
+    PACKET_MR_UNICAST = 3,
+}
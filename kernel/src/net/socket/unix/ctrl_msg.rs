@@ -215,8 +215,10 @@ impl AuxiliaryData {
         let mut cred = None;
 
         for ctrl_msg in ctrl_msgs.into_iter() {
-            let ControlMessage::Unix(unix_ctrl_msg) = ctrl_msg;
-            // TODO: What should we do if there are control messages of other protocols?
+            // Control messages of other protocols are ignored, as Linux does.
+            let ControlMessage::Unix(unix_ctrl_msg) = ctrl_msg else {
+                continue;
+            };
 
             match unix_ctrl_msg.0 {
                 Message::Files(FileMessage {
@@ -274,14 +276,27 @@ impl AuxiliaryData {
 
     /// Generates the control messages from the auxiliary data.
     pub(super) fn generate_control(&mut self, is_pass_cred: bool) -> Vec<ControlMessage> {
-        let mut ctrl_msgs = Vec::new();
+        let files = core::mem::take(&mut self.files);
+        Self::build_control(files, self.cred.as_ref(), is_pass_cred)
+    }
 
-        let Self { files, cred } = self;
+    /// Generates the control messages from the auxiliary data without consuming the files.
+    ///
+    /// This is used when peeking messages. Like Linux, the files are duplicated.
+    pub(super) fn peek_control(&self, is_pass_cred: bool) -> Vec<ControlMessage> {
+        Self::build_control(self.files.clone(), self.cred.as_ref(), is_pass_cred)
+    }
+
+    fn build_control(
+        files: Vec<Arc<dyn FileLike>>,
+        cred: Option<&SocketCred>,
+        is_pass_cred: bool,
+    ) -> Vec<ControlMessage> {
+        let mut ctrl_msgs = Vec::new();
 
         if is_pass_cred {
             let unix_ctrl_msg = UnixControlMessage(Message::Cred(CredMessage {
                 cred: cred
-                    .as_ref()
                     .map(SocketCred::to_real_c_cred)
                     .unwrap_or_else(CUserCred::new_overflow),
             }));
@@ -289,9 +304,7 @@ impl AuxiliaryData {
         }
 
         if !files.is_empty() {
-            let unix_ctrl_msg = UnixControlMessage(Message::Files(FileMessage {
-                files: core::mem::take(files),
-            }));
+            let unix_ctrl_msg = UnixControlMessage(Message::Files(FileMessage { files }));
             ctrl_msgs.push(ControlMessage::Unix(unix_ctrl_msg));
         }
 
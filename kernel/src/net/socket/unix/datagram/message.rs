@@ -17,7 +17,7 @@ use crate::{
             ctrl_msg::AuxiliaryData,
             UnixSocketAddr,
         },
-        util::ControlMessage,
+        util::{ControlMessage, SendRecvFlags, SocketFilter},
     },
     prelude::*,
     process::signal::Pollee,
@@ -36,6 +36,7 @@ struct Inner {
     messages: VecDeque<Message>,
     total_length: usize,
     is_shutdown: bool,
+    filter: Option<Arc<SocketFilter>>,
 }
 
 struct Message {
@@ -81,6 +82,14 @@ impl MessageQueue {
             let mut bytes = vec![0; len];
             reader.read(&mut VmWriter::from(bytes.as_mut_slice()))?;
 
+            if let Some(filter) = inner.filter.as_ref() {
+                let Some(accepted_len) = filter.run_trim_cap(&bytes, 1) else {
+                    // The message is dropped by the filter without reporting errors to the sender.
+                    return Ok(len);
+                };
+                bytes.truncate(accepted_len);
+            }
+
             let mut aux = core::mem::take(aux_data);
             if self.is_pass_cred.load(Ordering::Relaxed)
                 || source.queue.is_pass_cred.load(Ordering::Relaxed)
@@ -133,6 +142,7 @@ impl MessageReceiver {
             messages: VecDeque::new(),
             total_length: 0,
             is_shutdown: false,
+            filter: None,
         };
 
         let queue = MessageQueue {
@@ -167,6 +177,7 @@ impl MessageReceiver {
     pub(super) fn try_recv(
         &self,
         writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
     ) -> Result<(usize, Vec<ControlMessage>, UnixSocketAddr)> {
         let mut inner = self.queue.inner.lock();
         let inner = inner.as_mut().unwrap();
@@ -179,15 +190,25 @@ impl MessageReceiver {
             }
         };
 
-        let len = writer.write(&mut VmReader::from(msg.bytes.as_slice()))?;
+        let mut len = writer.write(&mut VmReader::from(msg.bytes.as_slice()))?;
         if len != msg.bytes.len() {
             warn!("setting MSG_TRUNC is not supported");
+            // If `MSG_TRUNC` is specified, the real length of the message is returned.
+            if flags.contains(SendRecvFlags::MSG_TRUNC) {
+                len = msg.bytes.len();
+            }
+        }
+
+        let is_pass_cred = self.queue.is_pass_cred.load(Ordering::Relaxed);
+
+        if flags.contains(SendRecvFlags::MSG_PEEK) {
+            let ctrl_msgs = msg.aux.peek_control(is_pass_cred);
+            return Ok((len, ctrl_msgs, msg.src.clone()));
         }
 
         let mut msg = inner.messages.pop_front().unwrap();
         inner.total_length -= msg.bytes.len();
 
-        let is_pass_cred = self.queue.is_pass_cred.load(Ordering::Relaxed);
         let ctrl_msgs = msg.aux.generate_control(is_pass_cred);
 
         self.queue.pollee.invalidate();
@@ -214,6 +235,17 @@ impl MessageReceiver {
             .store(is_pass_cred, Ordering::Relaxed);
     }
 
+    /// Sets the filter for incoming messages and returns the old one.
+    pub(super) fn set_filter(
+        &self,
+        filter: Option<Arc<SocketFilter>>,
+    ) -> Option<Arc<SocketFilter>> {
+        let mut inner = self.queue.inner.lock();
+        let inner = inner.as_mut().unwrap();
+
+        core::mem::replace(&mut inner.filter, filter)
+    }
+
     pub(super) fn addr(&self) -> UnixSocketAddr {
         self.queue.addr()
     }
@@ -6,9 +6,10 @@ use super::message::{MessageQueue, MessageReceiver};
 use crate::{
     events::IoEvents,
     fs::utils::Inode,
+    match_sock_option_ref,
     net::socket::{
         new_pseudo_inode,
-        options::SocketOption,
+        options::{AttachFilter, DetachFilter, SocketOption},
         private::SocketPrivate,
         unix::{ctrl_msg::AuxiliaryData, UnixSocketAddr},
         util::{
@@ -79,7 +80,7 @@ impl UnixDatagramSocket {
         reader: &mut dyn MultiRead,
         mut aux_data: AuxiliaryData,
         remote: Option<UnixSocketAddr>,
-        _flags: SendRecvFlags,
+        flags: SendRecvFlags,
     ) -> Result<usize> {
         if self.is_write_shutdown.load(Ordering::Relaxed) {
             return_errno_with_message!(Errno::EPIPE, "the socket is shut down for writing");
@@ -95,7 +96,7 @@ impl UnixDatagramSocket {
             })?
         };
 
-        let res = if self.is_nonblocking() {
+        let res = if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
             queue.try_send(reader, &mut aux_data, &self.local_receiver)
         } else {
             queue.block_send(|| queue.try_send(reader, &mut aux_data, &self.local_receiver))
@@ -214,6 +215,21 @@ impl Socket for UnixDatagramSocket {
     }
 
     fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            attach_filter: AttachFilter => {
+                let filter = attach_filter.get().unwrap();
+                self.local_receiver.set_filter(Some(filter.clone()));
+                return Ok(());
+            },
+            _detach_filter: DetachFilter => {
+                if self.local_receiver.set_filter(None).is_none() {
+                    return_errno_with_message!(Errno::ENOENT, "no filter is attached");
+                }
+                return Ok(());
+            },
+            _ => ()
+        });
+
         let mut options = self.options.write();
 
         match options.socket.set_option(option, &self.local_receiver) {
@@ -236,10 +252,15 @@ impl Socket for UnixDatagramSocket {
         message_header: MessageHeader,
         flags: SendRecvFlags,
     ) -> Result<usize> {
-        // TODO: Deal with flags
         if !flags.is_all_supported() {
             warn!("unsupported flags: {:?}", flags);
         }
+        if flags.contains(SendRecvFlags::MSG_OOB) {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "UNIX datagram sockets do not support MSG_OOB"
+            );
+        }
 
         let MessageHeader {
             addr,
@@ -261,13 +282,23 @@ impl Socket for UnixDatagramSocket {
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
     ) -> Result<(usize, MessageHeader)> {
-        // TODO: Deal with flags
-        if !flags.is_all_supported() {
+        if !flags
+            .sub(SendRecvFlags::MSG_PEEK | SendRecvFlags::MSG_TRUNC)
+            .is_all_supported()
+        {
             warn!("unsupported flags: {:?}", flags);
         }
+        if flags.contains(SendRecvFlags::MSG_OOB) {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "UNIX datagram sockets do not support MSG_OOB"
+            );
+        }
 
         let (received_bytes, control_messages, peer_addr) =
-            self.block_on(IoEvents::IN, || self.local_receiver.try_recv(writer))?;
+            self.block_on_with_flags(IoEvents::IN, flags, || {
+                self.local_receiver.try_recv(writer, flags)
+            })?;
 
         let message_header = MessageHeader::new(Some(peer_addr.into()), control_messages);
 
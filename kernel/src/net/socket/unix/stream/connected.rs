@@ -14,7 +14,7 @@ use crate::{
         unix::{
             addr::UnixSocketAddrBound, cred::SocketCred, ctrl_msg::AuxiliaryData, UnixSocketAddr,
         },
-        util::{options::SocketOptionSet, ControlMessage, SockShutdownCmd},
+        util::{options::SocketOptionSet, ControlMessage, SendRecvFlags, SockShutdownCmd},
     },
     prelude::*,
     process::signal::Pollee,
@@ -118,7 +118,12 @@ impl Connected {
         &self,
         writer: &mut dyn MultiWrite,
         is_seqpacket: bool,
+        flags: SendRecvFlags,
     ) -> Result<(usize, Vec<ControlMessage>)> {
+        if flags.contains(SendRecvFlags::MSG_PEEK) {
+            return self.try_peek(writer, is_seqpacket, flags);
+        }
+
         let is_empty = writer.is_empty();
         if is_empty && !is_seqpacket {
             if self.inner.this_end().reader.lock().is_empty() {
@@ -200,6 +205,10 @@ impl Connected {
                 if read_len < aux_len {
                     warn!("setting MSG_TRUNC is not supported");
                     reader.skip(aux_len - read_len);
+                    // If `MSG_TRUNC` is specified, the real length of the message is returned.
+                    if flags.contains(SendRecvFlags::MSG_TRUNC) {
+                        read_tot_len += aux_len - read_len;
+                    }
                 }
                 break aux_prev_data.as_mut().unwrap();
             } else if let Some(front) = aux_front {
@@ -227,6 +236,64 @@ impl Connected {
         Ok((read_tot_len, ctrl_msgs))
     }
 
+    /// Peeks the bytes and the auxiliary data without removing them.
+    ///
+    /// Unlike [`Self::try_read`], this method will not receive bytes beyond the boundary of the
+    /// auxiliary data, even if the auxiliary data are the same.
+    fn try_peek(
+        &self,
+        writer: &mut dyn MultiWrite,
+        is_seqpacket: bool,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, Vec<ControlMessage>)> {
+        let is_empty = writer.is_empty();
+        if is_empty && !is_seqpacket {
+            if self.inner.this_end().reader.lock().is_empty() {
+                return_errno_with_message!(Errno::EAGAIN, "the channel is empty");
+            }
+            return Ok((0, Vec::new()));
+        }
+
+        let this_end = self.inner.this_end();
+        let peer_end = self.inner.peer_end();
+
+        let reader = this_end.reader.lock();
+        let all_aux = peer_end.all_aux.lock();
+
+        let is_pass_cred = this_end.is_pass_cred.load(Ordering::Relaxed);
+
+        let read_start = reader.head();
+        let (aux_len, aux_data) = match all_aux.front() {
+            Some(front) if front.start == read_start => {
+                ((front.end - read_start).0, Some(&front.data))
+            }
+            Some(front) => ((front.start - read_start).0, None),
+            None => (reader.len(), None),
+        };
+
+        // Zero-length messages in `SOCK_SEQPACKET` sockets have no payload bytes to peek.
+        let read_len = if !is_empty && aux_len > 0 {
+            self.inner
+                .read_with(|| reader.peek_fallible_with_max_len(writer, aux_len))?
+        } else if aux_data.is_some() {
+            0
+        } else {
+            self.inner.read_with(|| Ok(0))?
+        };
+
+        let ctrl_msgs = match aux_data {
+            Some(aux_data) => aux_data.peek_control(is_pass_cred),
+            None => AuxiliaryData::default().peek_control(is_pass_cred),
+        };
+
+        // If `MSG_TRUNC` is specified, the real length of the message is returned.
+        if is_seqpacket && flags.contains(SendRecvFlags::MSG_TRUNC) {
+            return Ok((aux_len, ctrl_msgs));
+        }
+
+        Ok((read_len, ctrl_msgs))
+    }
+
     pub(super) fn try_write(
         &self,
         reader: &mut dyn MultiRead,
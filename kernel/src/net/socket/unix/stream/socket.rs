@@ -205,10 +205,10 @@ impl UnixStreamSocket {
     fn try_recv(
         &self,
         buf: &mut dyn MultiWrite,
-        _flags: SendRecvFlags,
+        flags: SendRecvFlags,
     ) -> Result<(usize, Vec<ControlMessage>)> {
         match self.state.read().as_ref() {
-            State::Connected(connected) => connected.try_read(buf, self.is_seqpacket),
+            State::Connected(connected) => connected.try_read(buf, self.is_seqpacket, flags),
             State::Init(_) | State::Listen(_) => {
                 return_errno_with_message!(Errno::EINVAL, "the socket is not connected")
             }
@@ -432,10 +432,14 @@ impl Socket for UnixStreamSocket {
         message_header: MessageHeader,
         flags: SendRecvFlags,
     ) -> Result<usize> {
-        // TODO: Deal with flags
-        if !flags.is_all_supported() {
+        // `MSG_MORE` is only a hint, so it is fine to send the data immediately.
+        if !flags.sub(SendRecvFlags::MSG_MORE).is_all_supported() {
             warn!("unsupported flags: {:?}", flags);
         }
+        if flags.contains(SendRecvFlags::MSG_OOB) {
+            // TODO: Support out-of-band data on UNIX stream sockets
+            return_errno_with_message!(Errno::EOPNOTSUPP, "out-of-band data are not supported");
+        }
 
         let MessageHeader {
             control_messages,
@@ -459,9 +463,14 @@ impl Socket for UnixStreamSocket {
         }
         let mut auxiliary_data = AuxiliaryData::from_control(control_messages)?;
 
-        self.block_on(IoEvents::OUT, || {
-            self.try_send(reader, &mut auxiliary_data, flags)
-        })
+        let try_send = || self.try_send(reader, &mut auxiliary_data, flags);
+
+        // `SIGPIPE` is only sent for `SOCK_STREAM` sockets, as Linux does.
+        if self.is_seqpacket {
+            self.block_on_with_flags(IoEvents::OUT, flags, try_send)
+        } else {
+            self.send_stream(flags, try_send)
+        }
     }
 
     fn recvmsg(
@@ -469,13 +478,34 @@ impl Socket for UnixStreamSocket {
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
     ) -> Result<(usize, MessageHeader)> {
-        // TODO: Deal with flags
-        if !flags.is_all_supported() {
+        if !flags
+            .sub(SendRecvFlags::MSG_PEEK | SendRecvFlags::MSG_TRUNC | SendRecvFlags::MSG_WAITALL)
+            .is_all_supported()
+        {
             warn!("unsupported flags: {:?}", flags);
         }
+        if flags.contains(SendRecvFlags::MSG_OOB) {
+            // TODO: Support out-of-band data on UNIX stream sockets
+            return_errno_with_message!(Errno::EOPNOTSUPP, "out-of-band data are not supported");
+        }
 
-        let (received_bytes, control_messages) =
-            self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
+        if self.is_seqpacket {
+            let (received_bytes, control_messages) =
+                self.block_on_with_flags(IoEvents::IN, flags, || self.try_recv(writer, flags))?;
+            return Ok((received_bytes, MessageHeader::new(None, control_messages)));
+        }
+
+        let mut control_messages = Vec::new();
+        let received_bytes = self.recv_stream(writer, flags, |writer| {
+            // Even with `MSG_WAITALL`, we stop receiving at the boundary of auxiliary data. Linux
+            // behaves similarly.
+            if !control_messages.is_empty() {
+                return Ok(0);
+            }
+            let received_bytes;
+            (received_bytes, control_messages) = self.try_recv(writer, flags)?;
+            Ok(received_bytes)
+        })?;
 
         let message_header = MessageHeader::new(None, control_messages);
 
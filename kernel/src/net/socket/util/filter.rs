@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Classic BPF socket filters.
+//!
+//! A socket filter is a program of the classic Berkeley Packet Filter (cBPF). It is attached to a
+//! socket via the `SO_ATTACH_FILTER` socket option and decides, for each incoming packet, how many
+//! bytes of the packet should be accepted by the socket.
+//!
+//! Reference: <https://www.kernel.org/doc/html/v6.0/networking/filter.html>.
+
+use crate::prelude::*;
+
+/// A cBPF instruction.
+///
+/// This corresponds to `struct sock_filter` in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct CSockFilter {
+    /// The opcode.
+    pub code: u16,
+    /// The offset to jump to if the condition is true.
+    pub jt: u8,
+    /// The offset to jump to if the condition is false.
+    pub jf: u8,
+    /// The generic multiuse field.
+    pub k: u32,
+}
+
+/// A socket filter that runs a cBPF program.
+#[derive(Debug)]
+pub struct SocketFilter {
+    insns: Box<[CSockFilter]>,
+}
+
+/// The maximum number of instructions in a cBPF program.
+///
+/// This is `BPF_MAXINSNS` in Linux.
+const BPF_MAXINSNS: usize = 4096;
+
+/// The number of words in the scratch memory.
+const BPF_MEMWORDS: usize = 16;
+
+// Instruction classes.
+const BPF_LD: u16 = 0x00;
+const BPF_LDX: u16 = 0x01;
+const BPF_ST: u16 = 0x02;
+const BPF_STX: u16 = 0x03;
+const BPF_ALU: u16 = 0x04;
+const BPF_JMP: u16 = 0x05;
+const BPF_RET: u16 = 0x06;
+const BPF_MISC: u16 = 0x07;
+
+// Sizes of load instructions.
+const BPF_W: u16 = 0x00;
+const BPF_H: u16 = 0x08;
+const BPF_B: u16 = 0x10;
+
+// Modes of load instructions.
+const BPF_IMM: u16 = 0x00;
+const BPF_ABS: u16 = 0x20;
+const BPF_IND: u16 = 0x40;
+const BPF_MEM: u16 = 0x60;
+const BPF_LEN: u16 = 0x80;
+const BPF_MSH: u16 = 0xa0;
+
+// Operations of ALU instructions.
+const BPF_ADD: u16 = 0x00;
+const BPF_SUB: u16 = 0x10;
+const BPF_MUL: u16 = 0x20;
+const BPF_DIV: u16 = 0x30;
+const BPF_OR: u16 = 0x40;
+const BPF_AND: u16 = 0x50;
+const BPF_LSH: u16 = 0x60;
+const BPF_RSH: u16 = 0x70;
+const BPF_NEG: u16 = 0x80;
+const BPF_MOD: u16 = 0x90;
+const BPF_XOR: u16 = 0xa0;
+
+// Operations of jump instructions.
+const BPF_JA: u16 = 0x00;
+const BPF_JEQ: u16 = 0x10;
+const BPF_JGT: u16 = 0x20;
+const BPF_JGE: u16 = 0x30;
+const BPF_JSET: u16 = 0x40;
+
+// Sources of ALU and jump instructions.
+const BPF_K: u16 = 0x00;
+const BPF_X: u16 = 0x08;
+
+// Sources of return instructions.
+const BPF_A: u16 = 0x10;
+
+// Operations of miscellaneous instructions.
+const BPF_TAX: u16 = 0x00;
+const BPF_TXA: u16 = 0x80;
+
+const fn bpf_class(code: u16) -> u16 {
+    code & 0x07
+}
+
+const fn bpf_size(code: u16) -> u16 {
+    code & 0x18
+}
+
+const fn bpf_mode(code: u16) -> u16 {
+    code & 0xe0
+}
+
+const fn bpf_op(code: u16) -> u16 {
+    code & 0xf0
+}
+
+const fn bpf_src(code: u16) -> u16 {
+    code & 0x08
+}
+
+const fn bpf_rval(code: u16) -> u16 {
+    code & 0x18
+}
+
+const fn bpf_miscop(code: u16) -> u16 {
+    code & 0xf8
+}
+
+impl SocketFilter {
+    /// Creates a socket filter from the cBPF instructions.
+    ///
+    /// The program is verified before it is accepted, as `bpf_check_classic` in Linux does. In
+    /// particular, all jumps must land inside the program, all accesses to the scratch memory
+    /// must be in bounds, and the program must end with a return instruction.
+    pub fn new(insns: Vec<CSockFilter>) -> Result<Self> {
+        if insns.is_empty() || insns.len() > BPF_MAXINSNS {
+            return_errno_with_message!(Errno::EINVAL, "the number of instructions is invalid");
+        }
+
+        for (pc, insn) in insns.iter().enumerate() {
+            check_insn(insn, insns.len() - pc - 1)?;
+        }
+
+        if bpf_class(insns.last().unwrap().code) != BPF_RET {
+            return_errno_with_message!(Errno::EINVAL, "the program does not end with a return");
+        }
+
+        Ok(Self {
+            insns: insns.into_boxed_slice(),
+        })
+    }
+
+    /// Runs the filter on the packet and returns the length to which the packet is trimmed.
+    ///
+    /// This method returns `None` if the packet should be dropped. Otherwise, the returned length
+    /// is no less than `cap` and no greater than the packet length, as `sk_filter_trim_cap` in
+    /// Linux does.
+    pub fn run_trim_cap(&self, packet: &[u8], cap: usize) -> Option<usize> {
+        let accepted_len = self.run(packet) as usize;
+        if accepted_len == 0 {
+            return None;
+        }
+
+        Some(accepted_len.max(cap).min(packet.len()))
+    }
+
+    /// Runs the filter on the packet.
+    ///
+    /// This method returns the number of bytes to accept. Zero means that the packet should be
+    /// dropped.
+    ///
+    /// Invalid operations at runtime, such as out-of-bounds loads and divisions by zero, cause
+    /// the packet to be dropped, as Linux does.
+    pub fn run(&self, packet: &[u8]) -> u32 {
+        self.interpret(packet).unwrap_or(0)
+    }
+
+    fn interpret(&self, packet: &[u8]) -> Option<u32> {
+        let mut a: u32 = 0;
+        let mut x: u32 = 0;
+        let mut mem = [0u32; BPF_MEMWORDS];
+
+        let mut pc = 0;
+        loop {
+            let insn = self.insns.get(pc)?;
+            pc += 1;
+
+            let code = insn.code;
+            let k = insn.k;
+
+            match bpf_class(code) {
+                BPF_LD => {
+                    a = match bpf_mode(code) {
+                        BPF_IMM => k,
+                        BPF_ABS => load(packet, k, bpf_size(code))?,
+                        BPF_IND => load(packet, x.wrapping_add(k), bpf_size(code))?,
+                        BPF_MEM => *mem.get(k as usize)?,
+                        BPF_LEN => packet.len() as u32,
+                        _ => return None,
+                    };
+                }
+                BPF_LDX => {
+                    x = match bpf_mode(code) {
+                        BPF_IMM => k,
+                        BPF_MEM => *mem.get(k as usize)?,
+                        BPF_LEN => packet.len() as u32,
+                        // This loads the IPv4 header length, i.e., `4 * (P[k:1] & 0xf)`.
+                        BPF_MSH => (load(packet, k, BPF_B)? & 0xf) << 2,
+                        _ => return None,
+                    };
+                }
+                BPF_ST => *mem.get_mut(k as usize)? = a,
+                BPF_STX => *mem.get_mut(k as usize)? = x,
+                BPF_ALU => {
+                    let src = if bpf_src(code) == BPF_X { x } else { k };
+                    a = match bpf_op(code) {
+                        BPF_ADD => a.wrapping_add(src),
+                        BPF_SUB => a.wrapping_sub(src),
+                        BPF_MUL => a.wrapping_mul(src),
+                        BPF_DIV => a.checked_div(src)?,
+                        BPF_MOD => a.checked_rem(src)?,
+                        BPF_OR => a | src,
+                        BPF_AND => a & src,
+                        BPF_XOR => a ^ src,
+                        BPF_LSH => a.checked_shl(src).unwrap_or(0),
+                        BPF_RSH => a.checked_shr(src).unwrap_or(0),
+                        BPF_NEG => a.wrapping_neg(),
+                        _ => return None,
+                    };
+                }
+                BPF_JMP => {
+                    let src = if bpf_src(code) == BPF_X { x } else { k };
+                    let offset = match bpf_op(code) {
+                        BPF_JA => k as usize,
+                        op => {
+                            let cond = match op {
+                                BPF_JEQ => a == src,
+                                BPF_JGT => a > src,
+                                BPF_JGE => a >= src,
+                                BPF_JSET => a & src != 0,
+                                _ => return None,
+                            };
+                            if cond {
+                                insn.jt as usize
+                            } else {
+                                insn.jf as usize
+                            }
+                        }
+                    };
+                    pc = pc.checked_add(offset)?;
+                }
+                BPF_RET => {
+                    return match bpf_rval(code) {
+                        BPF_K => Some(k),
+                        BPF_X => Some(x),
+                        BPF_A => Some(a),
+                        _ => None,
+                    };
+                }
+                BPF_MISC => match bpf_miscop(code) {
+                    BPF_TAX => x = a,
+                    BPF_TXA => a = x,
+                    _ => return None,
+                },
+                _ => unreachable!("the instruction class has only three bits"),
+            }
+        }
+    }
+}
+
+/// Checks whether the instruction is valid.
+///
+/// `remaining` is the number of instructions after this instruction.
+fn check_insn(insn: &CSockFilter, remaining: usize) -> Result<()> {
+    let code = insn.code;
+    let k = insn.k;
+
+    let is_valid = code <= 0xff
+        && match bpf_class(code) {
+            BPF_LD => match bpf_mode(code) {
+                BPF_ABS | BPF_IND => matches!(bpf_size(code), BPF_W | BPF_H | BPF_B),
+                BPF_IMM | BPF_LEN => bpf_size(code) == BPF_W,
+                BPF_MEM => bpf_size(code) == BPF_W && (k as usize) < BPF_MEMWORDS,
+                _ => false,
+            },
+            BPF_LDX => match bpf_mode(code) {
+                BPF_IMM | BPF_LEN => bpf_size(code) == BPF_W,
+                BPF_MEM => bpf_size(code) == BPF_W && (k as usize) < BPF_MEMWORDS,
+                BPF_MSH => bpf_size(code) == BPF_B,
+                _ => false,
+            },
+            BPF_ST | BPF_STX => code & !0x07 == 0 && (k as usize) < BPF_MEMWORDS,
+            BPF_ALU => match bpf_op(code) {
+                // Divisions by a constant zero are rejected, but divisions by `X` are checked at
+                // runtime.
+                BPF_DIV | BPF_MOD if bpf_src(code) == BPF_K => k != 0,
+                BPF_LSH | BPF_RSH if bpf_src(code) == BPF_K => k < 32,
+                BPF_ADD | BPF_SUB | BPF_MUL | BPF_DIV | BPF_MOD | BPF_OR | BPF_AND | BPF_LSH
+                | BPF_RSH | BPF_XOR => true,
+                BPF_NEG => bpf_src(code) == BPF_K,
+                _ => false,
+            },
+            BPF_JMP => match bpf_op(code) {
+                BPF_JA => bpf_src(code) == BPF_K && (k as usize) < remaining,
+                BPF_JEQ | BPF_JGT | BPF_JGE | BPF_JSET => {
+                    (insn.jt as usize) < remaining && (insn.jf as usize) < remaining
+                }
+                _ => false,
+            },
+            BPF_RET => code & !0x18 == BPF_RET && matches!(bpf_rval(code), BPF_K | BPF_A),
+            BPF_MISC => code & !0x07 == BPF_TAX || code & !0x07 == BPF_TXA,
+            _ => unreachable!("the instruction class has only three bits"),
+        };
+
+    if !is_valid {
+        return_errno_with_message!(Errno::EINVAL, "the instruction is invalid");
+    }
+
+    Ok(())
+}
+
+/// Loads a big-endian value of `size` at `offset` from the packet.
+///
+/// This method returns `None` if the load is out of bounds.
+//
+// TODO: Support negative offsets, which are used to access ancillary data (e.g.,
+// `SKF_AD_PROTOCOL`) and the network or link-layer headers (e.g., `SKF_NET_OFF`).
+fn load(packet: &[u8], offset: u32, size: u16) -> Option<u32> {
+    let len = match size {
+        BPF_W => 4,
+        BPF_H => 2,
+        BPF_B => 1,
+        _ => return None,
+    };
+
+    let start = offset as usize;
+    let bytes = packet.get(start..start.checked_add(len)?)?;
+
+    Some(
+        bytes
+            .iter()
+            .fold(0u32, |value, byte| (value << 8) | *byte as u32),
+    )
+}
+
+impl aster_bigtcp::socket::PacketFilter for SocketFilter {
+    fn run(&self, packet: &[u8]) -> usize {
+        SocketFilter::run(self, packet) as usize
+    }
+}
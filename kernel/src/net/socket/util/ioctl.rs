@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{fs::utils::IoctlCmd, prelude::*, process::posix_thread::AsPosixThread};
+
+/// The maximum length of an interface name, including the trailing null byte.
+const IFNAMSIZ: usize = 16;
+
+/// The prefix of `struct ifreq` used by `SIOCGIFINDEX`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.17/source/include/uapi/linux/if.h#L234>
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIfReqIndex {
+    ifr_name: [u8; IFNAMSIZ],
+    ifr_ifindex: i32,
+}
+
+/// Performs the ioctl commands that are common to all sockets.
+pub(in crate::net::socket) fn ioctl(cmd: IoctlCmd, arg: Vaddr) -> Result<i32> {
+    match cmd {
+        IoctlCmd::SIOCGIFINDEX => {
+            let mut ifreq: CIfReqIndex = current_userspace!().read_val(arg)?;
+            // Like Linux, the name is truncated if it is not null-terminated.
+            ifreq.ifr_name[IFNAMSIZ - 1] = 0;
+            let name = CStr::from_bytes_until_nul(&ifreq.ifr_name).unwrap();
+
+            // TODO: Linux looks up the interface in the network namespace of the socket, not the
+            // one of the current thread.
+            let net_ns = {
+                let current = current_thread!();
+                let ns_proxy = current.as_posix_thread().unwrap().ns_proxy().lock();
+                // The current thread is running, so its namespaces cannot have been released.
+                ns_proxy.as_ref().unwrap().net_ns().clone()
+            };
+
+            let Some(iface) = name
+                .to_str()
+                .ok()
+                .and_then(|name| net_ns.get_iface_by_name(name))
+            else {
+                return_errno_with_message!(Errno::ENODEV, "the interface does not exist");
+            };
+
+            ifreq.ifr_ifindex = iface.index() as i32;
+            current_userspace!().write_val(arg, &ifreq)?;
+            Ok(0)
+        }
+        _ => return_errno_with_message!(Errno::ENOTTY, "the ioctl command is not supported"),
+    }
+}
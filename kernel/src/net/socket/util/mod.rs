@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
 pub(super) mod datagram_common;
+mod filter;
+mod ioctl;
 mod linger_option;
 mod message_header;
 pub(super) mod options;
@@ -8,6 +10,8 @@ mod send_recv_flags;
 mod shutdown_cmd;
 mod socket_addr;
 
+pub use filter::{CSockFilter, SocketFilter};
+pub(super) use ioctl::ioctl;
 pub use linger_option::LingerOption;
 pub(super) use message_header::CControlHeader;
 pub use message_header::{ControlMessage, MessageHeader};
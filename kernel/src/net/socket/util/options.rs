@@ -12,7 +12,8 @@ use crate::{
     net::socket::{
         options::{
             AcceptConn, KeepAlive, Linger, PassCred, PeerCred, PeerGroups, Priority, RecvBuf,
-            RecvBufForce, ReuseAddr, ReusePort, SendBuf, SendBufForce, SocketOption,
+            RecvBufForce, ReuseAddr, ReusePort, SendBuf, SendBufForce, SocketOption, Timestamp,
+            TimestampNs,
         },
         unix::{CUserCred, UNIX_DATAGRAM_DEFAULT_BUF_SIZE, UNIX_STREAM_DEFAULT_BUF_SIZE},
     },
@@ -32,6 +33,10 @@ pub struct SocketOptionSet {
     keep_alive: bool,
     pass_cred: bool,
     priority: i32,
+    /// Whether received packets are timestamped (`SOCK_RCVTSTAMP` in Linux).
+    timestamp: bool,
+    /// Whether the timestamps are in nanoseconds (`SOCK_RCVTSTAMPNS` in Linux).
+    timestamp_ns: bool,
 }
 
 impl Default for SocketOptionSet {
@@ -45,6 +50,8 @@ impl Default for SocketOptionSet {
             keep_alive: false,
             pass_cred: false,
             priority: 0,
+            timestamp: false,
+            timestamp_ns: false,
         }
     }
 }
@@ -131,6 +138,14 @@ impl SocketOptionSet {
                 let pass_cred = self.pass_cred();
                 socket_pass_cred.set(pass_cred);
             },
+            socket_timestamp: Timestamp => {
+                let timestamp = self.timestamp() && !self.timestamp_ns();
+                socket_timestamp.set(timestamp);
+            },
+            socket_timestamp_ns: TimestampNs => {
+                let timestamp_ns = self.timestamp_ns();
+                socket_timestamp_ns.set(timestamp_ns);
+            },
             socket_peer_cred: PeerCred => {
                 let peer_cred = CUserCred::new_invalid();
                 socket_peer_cred.set(peer_cred);
@@ -210,6 +225,18 @@ impl SocketOptionSet {
                 self.set_pass_cred(*pass_cred);
                 socket.set_pass_cred(*pass_cred);
             },
+            // The two timestamp options share the same flags. See `sock_set_timestamp` in Linux
+            // (https://elixir.bootlin.com/linux/v6.13/source/net/core/sock.c#L818).
+            socket_timestamp: Timestamp => {
+                let timestamp = socket_timestamp.get().unwrap();
+                self.set_timestamp(*timestamp);
+                self.set_timestamp_ns(false);
+            },
+            socket_timestamp_ns: TimestampNs => {
+                let timestamp_ns = socket_timestamp_ns.get().unwrap();
+                self.set_timestamp(*timestamp_ns);
+                self.set_timestamp_ns(*timestamp_ns);
+            },
             socket_sendbuf_force: SendBufForce => {
                 check_current_privileged()?;
                 let send_buf = socket_sendbuf_force.get().unwrap();
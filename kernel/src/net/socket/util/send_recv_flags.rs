@@ -36,8 +36,11 @@ bitflags! {
 }
 
 impl SendRecvFlags {
+    /// Returns the flags that are supported by all sockets.
+    ///
+    /// Other flags should be checked and handled by each socket individually.
     fn supported_flags() -> Self {
-        SendRecvFlags::empty()
+        SendRecvFlags::MSG_DONTWAIT | SendRecvFlags::MSG_NOSIGNAL
     }
 
     pub fn is_all_supported(&self) -> bool {
@@ -52,10 +52,15 @@ impl Connected {
         self.id
     }
 
-    pub fn try_recv(&self, writer: &mut dyn MultiWrite) -> Result<usize> {
+    pub fn try_recv(&self, writer: &mut dyn MultiWrite, flags: SendRecvFlags) -> Result<usize> {
         let mut connection = self.connection.disable_irq().lock();
-        let bytes_read = connection.buffer.read_fallible(writer)?;
-        connection.info.done_forwarding(bytes_read);
+        let bytes_read = if flags.contains(SendRecvFlags::MSG_PEEK) {
+            connection.buffer.peek_fallible(writer)?
+        } else {
+            let bytes_read = connection.buffer.read_fallible(writer)?;
+            connection.info.done_forwarding(bytes_read);
+            bytes_read
+        };
         self.pollee.invalidate();
 
         match bytes_read {
@@ -72,8 +77,8 @@ impl Connected {
 
     pub fn send(&self, reader: &mut dyn MultiRead, flags: SendRecvFlags) -> Result<usize> {
         let mut connection = self.connection.disable_irq().lock();
-        // TODO: Deal with flags
-        if !flags.is_all_supported() {
+        // `MSG_MORE` is only a hint, so it is fine to send the data immediately.
+        if !flags.sub(SendRecvFlags::MSG_MORE).is_all_supported() {
             warn!("unsupported flags: {:?}", flags);
         }
         let buf_len = reader.sum_lens();
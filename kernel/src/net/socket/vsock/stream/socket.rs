@@ -95,7 +95,7 @@ impl VsockStreamSocket {
     fn try_recv(
         &self,
         writer: &mut dyn MultiWrite,
-        _flags: SendRecvFlags,
+        flags: SendRecvFlags,
     ) -> Result<(usize, SocketAddr)> {
         let connected = match &*self.status.read() {
             Status::Connected(connected) => connected.clone(),
@@ -104,7 +104,7 @@ impl VsockStreamSocket {
             }
         };
 
-        let read_size = connected.try_recv(writer)?;
+        let read_size = connected.try_recv(writer, flags)?;
 
         let peer_addr = self.peer_addr()?;
         // If buffer is now empty and the peer requested shutdown, finish shutting down the
@@ -252,9 +252,8 @@ impl Socket for VsockStreamSocket {
         message_header: MessageHeader,
         flags: SendRecvFlags,
     ) -> Result<usize> {
-        // TODO: Deal with flags
-        if !flags.is_all_supported() {
-            warn!("unsupported flags: {:?}", flags);
+        if flags.contains(SendRecvFlags::MSG_OOB) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "vsock sockets do not support MSG_OOB");
         }
 
         let MessageHeader {
@@ -266,7 +265,7 @@ impl Socket for VsockStreamSocket {
             warn!("sending control message is not supported");
         }
 
-        self.send(reader, flags)
+        self.send_stream(flags, || self.send(reader, flags))
     }
 
     fn recvmsg(
@@ -274,12 +273,19 @@ impl Socket for VsockStreamSocket {
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
     ) -> Result<(usize, MessageHeader)> {
-        // TODO: Deal with flags
-        if !flags.is_all_supported() {
+        if !flags
+            .sub(SendRecvFlags::MSG_PEEK | SendRecvFlags::MSG_WAITALL)
+            .is_all_supported()
+        {
             warn!("unsupported flags: {:?}", flags);
         }
+        if flags.contains(SendRecvFlags::MSG_OOB) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "vsock sockets do not support MSG_OOB");
+        }
 
-        let (received_bytes, _) = self.block_on(IoEvents::IN, || self.try_recv(writer, flags))?;
+        let received_bytes = self.recv_stream(writer, flags, |writer| {
+            self.try_recv(writer, flags).map(|(len, _)| len)
+        })?;
 
         // TODO: Receive control message
 
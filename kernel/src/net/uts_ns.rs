@@ -112,6 +112,13 @@ pub struct UtsName {
     domainname: [u8; UTS_FIELD_LEN],
 }
 
+impl UtsName {
+    /// Returns the node name (i.e., the hostname).
+    pub fn nodename(&self) -> &CStr {
+        CStr::from_bytes_until_nul(&self.nodename).unwrap()
+    }
+}
+
 fn copy_uts_field_from_user(addr: Vaddr, len: u32, ctx: &Context) -> Result<[u8; UTS_FIELD_LEN]> {
     if len.cast_signed() < 0 {
         return_errno_with_message!(Errno::EINVAL, "the buffer length cannot be negative");
@@ -25,7 +25,7 @@ use crate::{
     prelude::*,
     process::{
         pid_file::PidFile,
-        posix_thread::{allocate_posix_tid, PosixThread, ThreadLocal},
+        posix_thread::{allocate_posix_tid, ptrace::trace_child, PosixThread, ThreadLocal},
         stats::PROCESS_CREATION_COUNTER,
         NsProxy, UserNamespace,
     },
@@ -260,7 +260,9 @@ impl CloneFlags {
             | CloneFlags::CLONE_CHILD_CLEARTID
             | CloneFlags::CLONE_VFORK
             | CloneFlags::CLONE_NEWNS
-            | CloneFlags::CLONE_PARENT;
+            | CloneFlags::CLONE_PARENT
+            | CloneFlags::CLONE_PTRACE
+            | CloneFlags::CLONE_UNTRACED;
         let unsupported_flags = *self - supported_flags;
         if !unsupported_flags.is_empty() {
             warn!("contains unsupported clone flags: {:?}", unsupported_flags);
@@ -280,9 +282,13 @@ pub fn clone_child(
 ) -> Result<Tid> {
     clone_args.check(ctx)?;
 
-    if clone_args.flags.contains(CloneFlags::CLONE_THREAD) {
+    let clone_flags = clone_args.flags;
+    let exit_signal = clone_args.exit_signal;
+
+    if clone_flags.contains(CloneFlags::CLONE_THREAD) {
         let child_task = clone_child_task(ctx, parent_context, clone_args)?;
         let child_thread = child_task.as_thread().unwrap();
+        trace_child(ctx, child_thread, clone_flags, exit_signal);
         child_thread.run();
 
         let child_tid = child_thread.as_posix_thread().unwrap().tid();
@@ -298,10 +304,11 @@ pub fn clone_child(
         }
         drop(cgroup_guard);
 
-        if clone_args.flags.contains(CloneFlags::CLONE_VFORK) {
+        if clone_flags.contains(CloneFlags::CLONE_VFORK) {
             child_process.status().set_vfork_child(true);
         }
 
+        trace_child(ctx, &child_process.main_thread(), clone_flags, exit_signal);
         child_process.run();
 
         PROCESS_CREATION_COUNTER
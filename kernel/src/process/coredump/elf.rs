@@ -0,0 +1,557 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The ELF format of core files.
+//!
+//! A core file contains a `PT_NOTE` segment, which holds the status of the process and its
+//! threads, followed by a `PT_LOAD` segment for each memory mapping.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/binfmt_elf.c#L2005>
+
+use core::{ops::Range, sync::atomic::Ordering};
+
+use align_ext::AlignExt;
+use ostd::mm::MAX_USERSPACE_VADDR;
+
+use super::ThreadStatus;
+use crate::{
+    arch::cpu::UserRegs,
+    fs::utils::Inode,
+    prelude::*,
+    process::{process_vm::ProcessVmarGuard, signal::sig_num::SigNum},
+    time::timeval_t,
+    vm::{perms::VmPerms, vmar::Vmar},
+};
+
+/// Writes the core of the current process to `inode`.
+///
+/// At most `limit` bytes will be written. If the core does not fit in the limit, an error will be
+/// returned and the written core file will be truncated.
+pub(super) fn write_core(
+    inode: Arc<dyn Inode>,
+    limit: usize,
+    vmar_guard: &ProcessVmarGuard,
+    ctx: &Context,
+    sig_num: SigNum,
+    threads: &[ThreadStatus],
+) -> Result<()> {
+    let vmar = vmar_guard.unwrap();
+
+    let segments = collect_segments(vmar);
+    let notes = build_notes(vmar_guard, ctx, sig_num, threads, &segments)?;
+
+    // TODO: Support more segments with the `PN_XNUM` extension.
+    let nr_phdrs = segments.len() + 1;
+    let Some(phnum) = u16::try_from(nr_phdrs)
+        .ok()
+        .filter(|phnum| *phnum < PN_XNUM)
+    else {
+        return_errno_with_message!(Errno::EFBIG, "there are too many memory mappings");
+    };
+
+    let notes_offset = size_of::<Elf64Ehdr>() + nr_phdrs * size_of::<Elf64Phdr>();
+    let data_offset = (notes_offset + notes.len()).align_up(PAGE_SIZE);
+
+    let mut file = CoreFile::new(inode, limit);
+
+    let ehdr = Elf64Ehdr::new_core(phnum);
+    file.write(ehdr.as_bytes())?;
+
+    let note_phdr = Elf64Phdr {
+        p_type: PT_NOTE,
+        p_flags: 0,
+        p_offset: notes_offset as u64,
+        p_vaddr: 0,
+        p_paddr: 0,
+        p_filesz: notes.len() as u64,
+        p_memsz: 0,
+        p_align: 4,
+    };
+    file.write(note_phdr.as_bytes())?;
+
+    let mut segment_offset = data_offset;
+    for segment in segments.iter() {
+        let load_phdr = Elf64Phdr {
+            p_type: PT_LOAD,
+            p_flags: segment.elf_flags(),
+            p_offset: segment_offset as u64,
+            p_vaddr: segment.range.start as u64,
+            p_paddr: 0,
+            p_filesz: segment.dump_size as u64,
+            p_memsz: segment.range.len() as u64,
+            p_align: PAGE_SIZE as u64,
+        };
+        file.write(load_phdr.as_bytes())?;
+
+        segment_offset += segment.dump_size;
+    }
+
+    file.write(&notes)?;
+    file.skip(data_offset - file.offset)?;
+
+    let mut page_buf = vec![0u8; PAGE_SIZE];
+    for segment in segments.iter() {
+        let dump_range = segment.range.start..segment.range.start + segment.dump_size;
+        for addr in dump_range.step_by(PAGE_SIZE) {
+            if segment.read_page(vmar, addr, &mut page_buf) {
+                file.write(&page_buf)?;
+            } else {
+                file.skip(PAGE_SIZE)?;
+            }
+        }
+    }
+
+    file.finish()
+}
+
+/// A memory mapping to be dumped as a `PT_LOAD` segment.
+struct Segment {
+    range: Range<Vaddr>,
+    perms: VmPerms,
+    /// The number of bytes to dump from the start of the mapping.
+    dump_size: usize,
+    /// Whether the pages that are not present in memory should be read from the file.
+    ///
+    /// Otherwise, the absent pages are left as holes in the core file.
+    is_file_backed: bool,
+    /// The path of the mapped file and the offset in it, which is reported in `NT_FILE`.
+    file: Option<(String, usize)>,
+}
+
+impl Segment {
+    fn elf_flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.perms.contains(VmPerms::READ) {
+            flags |= PF_R;
+        }
+        if self.perms.contains(VmPerms::WRITE) {
+            flags |= PF_W;
+        }
+        if self.perms.contains(VmPerms::EXEC) {
+            flags |= PF_X;
+        }
+        flags
+    }
+
+    /// Reads the page at `addr` into `buf`, returning `false` if it should be a hole.
+    fn read_page(&self, vmar: &Vmar, addr: Vaddr, buf: &mut [u8]) -> bool {
+        let mut writer = VmWriter::from(&mut *buf).to_fallible();
+        if vmar.read_present_page(addr, &mut writer).unwrap_or(false) {
+            return true;
+        }
+
+        if !self.is_file_backed {
+            return false;
+        }
+        let mut writer = VmWriter::from(buf).to_fallible();
+        vmar.read_remote(addr, &mut writer).is_ok()
+    }
+}
+
+/// Decides how many bytes of each memory mapping should be dumped.
+///
+/// This follows Linux's default `coredump_filter`, which dumps anonymous mappings and the ELF
+/// headers of file-backed mappings.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/coredump.c#L1260>
+fn collect_segments(vmar: &Vmar) -> Vec<Segment> {
+    let executable_file = vmar.process_vm().executable_file();
+
+    let mut segments = Vec::new();
+    let mut elf_header_candidates = Vec::new();
+
+    let query_guard = vmar.query(0..MAX_USERSPACE_VADDR);
+    for vm_mapping in query_guard.iter() {
+        let range = vm_mapping.map_to_addr()..vm_mapping.map_end();
+        let perms = vm_mapping.perms();
+        let inode = vm_mapping.inode();
+        let vmo_offset = vm_mapping.mapped_vmo().map(|(_, offset)| offset);
+
+        let dump_size = if vm_mapping.is_dontdump() || !perms.contains(VmPerms::READ) {
+            0
+        } else if inode.is_none() || (!vm_mapping.is_shared() && perms.contains(VmPerms::WRITE)) {
+            // FIXME: Linux dumps private file-backed mappings only if some pages have been
+            // copied on write. We dump them if they are writable instead.
+            range.len()
+        } else {
+            if vmo_offset == Some(0) {
+                elf_header_candidates.push(segments.len());
+            }
+            0
+        };
+
+        // FIXME: Report all the mapped files in `NT_FILE`. Currently, we only know the path of
+        // the executable file since the mappings only keep the inodes.
+        let file = inode
+            .filter(|inode| Arc::ptr_eq(*inode, executable_file.inode()))
+            .zip(vmo_offset)
+            .map(|(_, offset)| (executable_file.display_name(), offset));
+
+        segments.push(Segment {
+            range,
+            perms,
+            dump_size,
+            is_file_backed: inode.is_some(),
+            file,
+        });
+    }
+    drop(query_guard);
+
+    // Dump the first page if it contains an ELF header, which helps debuggers identify the file.
+    // The memory is read after dropping the query guard, since it may cause page faults.
+    for index in elf_header_candidates {
+        let segment = &mut segments[index];
+
+        let mut magic = [0u8; 4];
+        let mut writer = VmWriter::from(&mut magic[..]).to_fallible();
+        if vmar.read_remote(segment.range.start, &mut writer).is_ok() && magic == ELF_MAGIC {
+            segment.dump_size = PAGE_SIZE;
+        }
+    }
+
+    segments
+}
+
+fn build_notes(
+    vmar_guard: &ProcessVmarGuard,
+    ctx: &Context,
+    sig_num: SigNum,
+    threads: &[ThreadStatus],
+    segments: &[Segment],
+) -> Result<Vec<u8>> {
+    let mut notes = Vec::new();
+
+    let (first_thread, other_threads) = threads.split_first().unwrap();
+
+    // The notes of the process follow the status of the first thread, like Linux.
+    push_note(
+        &mut notes,
+        NT_PRSTATUS,
+        ElfPrstatus::new(ctx, sig_num, first_thread).as_bytes(),
+    );
+    push_note(
+        &mut notes,
+        NT_PRPSINFO,
+        ElfPrpsinfo::new(vmar_guard, ctx).as_bytes(),
+    );
+    push_note(&mut notes, NT_AUXV, &read_auxv(vmar_guard)?);
+    push_note(&mut notes, NT_FILE, &build_file_note(segments));
+
+    // TODO: Add the notes of the floating-point registers (e.g., `NT_PRFPREG`).
+    for thread in other_threads {
+        push_note(
+            &mut notes,
+            NT_PRSTATUS,
+            ElfPrstatus::new(ctx, sig_num, thread).as_bytes(),
+        );
+    }
+
+    Ok(notes)
+}
+
+fn push_note(notes: &mut Vec<u8>, note_type: u32, desc: &[u8]) {
+    const NOTE_NAME: &[u8] = b"CORE\0";
+
+    let nhdr = Elf64Nhdr {
+        n_namesz: NOTE_NAME.len() as u32,
+        n_descsz: desc.len() as u32,
+        n_type: note_type,
+    };
+    notes.extend_from_slice(nhdr.as_bytes());
+
+    notes.extend_from_slice(NOTE_NAME);
+    notes.resize(notes.len().align_up(4), 0);
+
+    notes.extend_from_slice(desc);
+    notes.resize(notes.len().align_up(4), 0);
+}
+
+fn read_auxv(vmar_guard: &ProcessVmarGuard) -> Result<Vec<u8>> {
+    let Some(init_stack_reader) = vmar_guard.init_stack_reader() else {
+        return Ok(Vec::new());
+    };
+
+    // The auxiliary vector contains only a few dozen entries, so one page is enough.
+    let mut auxv = vec![0u8; PAGE_SIZE];
+    let len = init_stack_reader.auxv(0, &mut VmWriter::from(auxv.as_mut_slice()).to_fallible())?;
+    auxv.truncate(len);
+
+    Ok(auxv)
+}
+
+/// Builds the `NT_FILE` note, which describes the mapped files.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/binfmt_elf.c#L1593>
+fn build_file_note(segments: &[Segment]) -> Vec<u8> {
+    let files: Vec<_> = segments
+        .iter()
+        .filter_map(|segment| segment.file.as_ref().map(|file| (&segment.range, file)))
+        .collect();
+
+    let mut desc = Vec::new();
+    desc.extend_from_slice((files.len() as u64).as_bytes());
+    desc.extend_from_slice((PAGE_SIZE as u64).as_bytes());
+    for (range, (_, offset)) in files.iter() {
+        desc.extend_from_slice((range.start as u64).as_bytes());
+        desc.extend_from_slice((range.end as u64).as_bytes());
+        desc.extend_from_slice(((offset / PAGE_SIZE) as u64).as_bytes());
+    }
+    for (_, (name, _)) in files.iter() {
+        desc.extend_from_slice(name.as_bytes());
+        desc.push(0);
+    }
+
+    desc
+}
+
+/// A writer that writes the core file sequentially under the size limit.
+struct CoreFile {
+    inode: Arc<dyn Inode>,
+    offset: usize,
+    limit: usize,
+}
+
+impl CoreFile {
+    fn new(inode: Arc<dyn Inode>, limit: usize) -> Self {
+        Self {
+            inode,
+            offset: 0,
+            limit,
+        }
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Result<()> {
+        self.check_limit(buf.len())?;
+        self.inode.write_bytes_at(self.offset, buf)?;
+        self.offset += buf.len();
+        Ok(())
+    }
+
+    /// Skips `len` bytes, leaving a hole in the core file.
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.check_limit(len)?;
+        self.offset += len;
+        Ok(())
+    }
+
+    fn check_limit(&self, len: usize) -> Result<()> {
+        if self.offset.saturating_add(len) > self.limit {
+            return_errno_with_message!(Errno::EFBIG, "the core file exceeds RLIMIT_CORE");
+        }
+        Ok(())
+    }
+
+    /// Finishes writing, extending the file if it ends with a hole.
+    fn finish(self) -> Result<()> {
+        if self.inode.size() < self.offset {
+            self.inode.resize(self.offset)?;
+        }
+        Ok(())
+    }
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/elf.h>
+
+const ELF_MAGIC: [u8; 4] = *b"\x7fELF";
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u8 = 1;
+const ET_CORE: u16 = 4;
+#[cfg(target_arch = "x86_64")]
+const EM_CURRENT: u16 = 62; // EM_X86_64
+#[cfg(target_arch = "riscv64")]
+const EM_CURRENT: u16 = 243; // EM_RISCV
+#[cfg(target_arch = "loongarch64")]
+const EM_CURRENT: u16 = 258; // EM_LOONGARCH
+
+const PN_XNUM: u16 = 0xffff;
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+const NT_PRSTATUS: u32 = 1;
+const NT_PRPSINFO: u32 = 3;
+const NT_AUXV: u32 = 6;
+const NT_FILE: u32 = 0x46494c45;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct Elf64Ehdr {
+    e_ident: [u8; 16],
+    e_type: u16,
+    e_machine: u16,
+    e_version: u32,
+    e_entry: u64,
+    e_phoff: u64,
+    e_shoff: u64,
+    e_flags: u32,
+    e_ehsize: u16,
+    e_phentsize: u16,
+    e_phnum: u16,
+    e_shentsize: u16,
+    e_shnum: u16,
+    e_shstrndx: u16,
+}
+
+impl Elf64Ehdr {
+    fn new_core(phnum: u16) -> Self {
+        let mut e_ident = [0u8; 16];
+        e_ident[..4].copy_from_slice(&ELF_MAGIC);
+        e_ident[4] = ELFCLASS64;
+        e_ident[5] = ELFDATA2LSB;
+        e_ident[6] = EV_CURRENT;
+
+        Self {
+            e_ident,
+            e_type: ET_CORE,
+            e_machine: EM_CURRENT,
+            e_version: EV_CURRENT as u32,
+            e_entry: 0,
+            e_phoff: size_of::<Self>() as u64,
+            e_shoff: 0,
+            e_flags: 0,
+            e_ehsize: size_of::<Self>() as u16,
+            e_phentsize: size_of::<Elf64Phdr>() as u16,
+            e_phnum: phnum,
+            e_shentsize: 0,
+            e_shnum: 0,
+            e_shstrndx: 0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct Elf64Phdr {
+    p_type: u32,
+    p_flags: u32,
+    p_offset: u64,
+    p_vaddr: u64,
+    p_paddr: u64,
+    p_filesz: u64,
+    p_memsz: u64,
+    p_align: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct Elf64Nhdr {
+    n_namesz: u32,
+    n_descsz: u32,
+    n_type: u32,
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/elfcore.h>
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct ElfPrstatus {
+    si_signo: i32,
+    si_code: i32,
+    si_errno: i32,
+    cursig: u16,
+    _pad0: u16,
+    sigpend: u64,
+    sighold: u64,
+    pid: i32,
+    ppid: i32,
+    pgrp: i32,
+    sid: i32,
+    utime: timeval_t,
+    stime: timeval_t,
+    cutime: timeval_t,
+    cstime: timeval_t,
+    reg: UserRegs,
+    fpvalid: i32,
+    _pad1: u32,
+}
+
+impl ElfPrstatus {
+    fn new(ctx: &Context, sig_num: SigNum, thread: &ThreadStatus) -> Self {
+        let process = ctx.process.as_ref();
+        let (cutime, cstime) = process.reaped_children_stats().lock().get();
+
+        Self {
+            si_signo: sig_num.as_u8() as i32,
+            si_code: 0,
+            si_errno: 0,
+            cursig: sig_num.as_u8() as u16,
+            _pad0: 0,
+            sigpend: thread.sig_pending,
+            sighold: thread.sig_blocked,
+            pid: thread.tid as i32,
+            ppid: process.parent().pid() as i32,
+            pgrp: process.pgid() as i32,
+            sid: process.sid() as i32,
+            utime: thread.user_time.into(),
+            stime: thread.kernel_time.into(),
+            cutime: cutime.into(),
+            cstime: cstime.into(),
+            reg: thread.regs,
+            fpvalid: 0,
+            _pad1: 0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct ElfPrpsinfo {
+    state: u8,
+    sname: u8,
+    zomb: u8,
+    nice: i8,
+    _pad0: u32,
+    flag: u64,
+    uid: u32,
+    gid: u32,
+    pid: i32,
+    ppid: i32,
+    pgrp: i32,
+    sid: i32,
+    fname: [u8; 16],
+    psargs: [u8; 80],
+}
+
+impl ElfPrpsinfo {
+    fn new(vmar_guard: &ProcessVmarGuard, ctx: &Context) -> Self {
+        let process = ctx.process.as_ref();
+        let credentials = ctx.posix_thread.credentials();
+
+        let mut fname = [0u8; 16];
+        let thread_name = ctx.posix_thread.thread_name().lock();
+        let name = thread_name.name().to_bytes();
+        let len = name.len().min(fname.len() - 1);
+        fname[..len].copy_from_slice(&name[..len]);
+        drop(thread_name);
+
+        // The arguments are separated by spaces instead of nul bytes.
+        let mut psargs = [0u8; 80];
+        if let Some(init_stack_reader) = vmar_guard.init_stack_reader() {
+            let mut writer = VmWriter::from(&mut psargs[..79]).to_fallible();
+            let len = init_stack_reader.argv(0, &mut writer).unwrap_or(0);
+            psargs[..len]
+                .iter_mut()
+                .filter(|byte| **byte == 0)
+                .for_each(|byte| *byte = b' ');
+        }
+
+        Self {
+            state: 0,
+            sname: b'R',
+            zomb: 0,
+            nice: process.nice().load(Ordering::Relaxed).value().get(),
+            _pad0: 0,
+            flag: 0,
+            uid: credentials.ruid().into(),
+            gid: credentials.rgid().into(),
+            pid: process.pid() as i32,
+            ppid: process.parent().pid() as i32,
+            pgrp: process.pgid() as i32,
+            sid: process.sid() as i32,
+            fname,
+            psargs,
+        }
+    }
+}
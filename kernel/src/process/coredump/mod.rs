@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Core dumps.
+//!
+//! When a process is terminated by a signal whose default action is to dump the core (e.g.,
+//! `SIGSEGV` or `SIGABRT`), an ELF core file is written according to
+//! `/proc/sys/kernel/core_pattern`. The core file records the registers of all threads and the
+//! memory of the process, so that the crash can be analyzed with debuggers like GDB.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/coredump.c>
+
+mod elf;
+mod pattern;
+
+use core::{sync::atomic::Ordering, time::Duration};
+
+use ostd::arch::cpu::context::UserContext;
+
+pub use self::pattern::{core_pattern, set_core_pattern, CORENAME_MAX_SIZE};
+use super::{
+    posix_thread::{sigkill_other_threads, wait_other_threads_exit},
+    signal::{sig_num::SigNum, HandlePendingSignal},
+    ResourceType, TermStatus,
+};
+use crate::{
+    arch::cpu::UserRegs,
+    fs::{
+        fs_resolver::{FsPath, SplitPath},
+        utils::{Inode, InodeMode, InodeType},
+    },
+    prelude::*,
+    thread::Tid,
+    time::Clock,
+};
+
+/// The status of a thread that is recorded in a core dump.
+pub(super) struct ThreadStatus {
+    tid: Tid,
+    regs: UserRegs,
+    sig_pending: u64,
+    sig_blocked: u64,
+    user_time: Duration,
+    kernel_time: Duration,
+}
+
+impl ThreadStatus {
+    fn new(ctx: &Context, user_ctx: &UserContext) -> Self {
+        let prof_clock = ctx.posix_thread.prof_clock();
+
+        Self {
+            tid: ctx.posix_thread.tid(),
+            regs: UserRegs::new(user_ctx, None),
+            sig_pending: ctx.pending_signals().into(),
+            sig_blocked: ctx.posix_thread.sig_mask().load(Ordering::Relaxed).into(),
+            user_time: prof_clock.user_clock().read_time(),
+            kernel_time: prof_clock.kernel_clock().read_time(),
+        }
+    }
+}
+
+/// Reports the status of the current thread if another thread is dumping the core.
+///
+/// This should be called before the current thread exits due to the `SIGKILL` sent by the thread
+/// that is dumping the core, so that the status of the current thread can be recorded in the core
+/// dump.
+pub(super) fn report_thread_status(ctx: &Context, user_ctx: &UserContext) {
+    let mut tasks = ctx.process.tasks().lock();
+    if let Some(threads) = tasks.core_dump_threads_mut() {
+        threads.push(ThreadStatus::new(ctx, user_ctx));
+    }
+}
+
+/// Dumps the core of the current process, which is being terminated by `sig_num`.
+///
+/// If the core is dumped, all other threads will have been killed and the exit code of the process
+/// will be set to [`TermStatus::Dumped`]. Either way, the caller should then exit the process.
+pub(super) fn do_coredump(ctx: &Context, user_ctx: &UserContext, sig_num: SigNum) {
+    if !ctx.user_space().vmar().process_vm().is_dumpable() {
+        return;
+    }
+
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/binfmt_elf.c#L96>
+    let limit = ctx
+        .process
+        .resource_limits()
+        .get_rlimit(ResourceType::RLIMIT_CORE)
+        .get_cur();
+    if limit < PAGE_SIZE as u64 {
+        return;
+    }
+
+    let pattern = core_pattern();
+    if pattern.starts_with('|') {
+        // TODO: Support piping the core dump to a user-space helper program.
+        warn!("piping core dumps to programs is not supported");
+        return;
+    }
+
+    if !zap_other_threads(ctx, sig_num) {
+        return;
+    }
+    let waited = wait_other_threads_exit(ctx);
+    let other_threads = ctx.process.tasks().lock().finish_core_dump();
+    if waited.is_err() {
+        return;
+    }
+
+    let mut threads = Vec::with_capacity(other_threads.len() + 1);
+    threads.push(ThreadStatus::new(ctx, user_ctx));
+    threads.extend(other_threads);
+
+    let core_name = pattern::expand(&pattern, ctx, sig_num, limit);
+    let res = create_core_file(ctx, &core_name).and_then(|file| {
+        let vmar_guard = ctx.process.lock_vmar();
+        elf::write_core(file, limit as usize, &vmar_guard, ctx, sig_num, &threads)
+    });
+
+    match res {
+        Ok(()) => {
+            ctx.process
+                .status()
+                .set_exit_code(TermStatus::Dumped(sig_num).as_u32());
+        }
+        Err(err) => {
+            warn!(
+                "PID {}: failed to dump the core to {}: {:?}",
+                ctx.process.pid(),
+                core_name,
+                err
+            );
+        }
+    }
+}
+
+/// Kills all other threads and starts collecting their status.
+///
+/// Returns `false` if the process is already exiting or executing a new program, in which case no
+/// core dump should be made.
+fn zap_other_threads(ctx: &Context, sig_num: SigNum) -> bool {
+    let mut tasks = ctx.process.tasks().lock();
+    if tasks.has_exited_group() || tasks.in_execve() {
+        return false;
+    }
+
+    sigkill_other_threads(ctx.task, &tasks);
+    tasks.set_exited_group();
+    tasks.start_core_dump();
+
+    // The exit code will not be overwritten by the exiting threads after `set_exited_group`, so
+    // we set it here. It will be updated if the core is dumped successfully.
+    ctx.process
+        .status()
+        .set_exit_code(TermStatus::Killed(sig_num).as_u32());
+
+    true
+}
+
+/// Creates a new core file at `core_name`, replacing the existing file (if any).
+fn create_core_file(ctx: &Context, core_name: &str) -> Result<Arc<dyn Inode>> {
+    let (dir_name, file_name) = core_name.split_dirname_and_filename()?;
+
+    let fs_ref = ctx.thread_local.borrow_fs();
+    let dir_path = fs_ref
+        .resolver()
+        .read()
+        .lookup(&FsPath::try_from(dir_name)?)?;
+
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/coredump.c#L879-L889>
+    match dir_path.unlink(file_name) {
+        Err(err) if err.error() != Errno::ENOENT => return Err(err),
+        _ => (),
+    }
+
+    let mode = InodeMode::from_bits_truncate(0o600 & !fs_ref.umask().get());
+    let path = dir_path.new_fs_child(file_name, InodeType::File, mode)?;
+
+    Ok(path.inode().clone())
+}
@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The pattern for naming core files (i.e., `/proc/sys/kernel/core_pattern`).
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/core.5.html>
+
+use alloc::borrow::Cow;
+use core::fmt::Write;
+
+use crate::{
+    prelude::*,
+    process::signal::sig_num::SigNum,
+    time::{clocks::RealTimeClock, Clock},
+};
+
+/// The maximum size of the core pattern, including the trailing nul byte.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/binfmts.h#L89>
+pub const CORENAME_MAX_SIZE: usize = 128;
+
+static CORE_PATTERN: Mutex<Cow<'static, str>> = Mutex::new(Cow::Borrowed("core"));
+
+/// Returns the pattern for naming core files.
+pub fn core_pattern() -> String {
+    CORE_PATTERN.lock().to_string()
+}
+
+/// Sets the pattern for naming core files.
+///
+/// The length of the pattern should be less than [`CORENAME_MAX_SIZE`].
+pub fn set_core_pattern(pattern: String) {
+    debug_assert!(pattern.len() < CORENAME_MAX_SIZE);
+    *CORE_PATTERN.lock() = Cow::Owned(pattern);
+}
+
+/// Expands the `%` specifiers in the core pattern.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/coredump.c#L225>
+pub(super) fn expand(pattern: &str, ctx: &Context, sig_num: SigNum, limit: u64) -> String {
+    let mut core_name = String::new();
+
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            core_name.push(c);
+            continue;
+        }
+
+        // Unknown specifiers and a trailing `%` are dropped.
+        let _ = match chars.next() {
+            Some('%') => write!(core_name, "%"),
+            // Without PID namespaces, `%p` and `%P` (as well as `%i` and `%I`) are the same.
+            Some('p') | Some('P') => write!(core_name, "{}", ctx.process.pid()),
+            Some('i') | Some('I') => write!(core_name, "{}", ctx.posix_thread.tid()),
+            Some('u') => write!(
+                core_name,
+                "{}",
+                u32::from(ctx.posix_thread.credentials().ruid())
+            ),
+            Some('g') => write!(
+                core_name,
+                "{}",
+                u32::from(ctx.posix_thread.credentials().rgid())
+            ),
+            // Only dumpable processes can dump the core, so this is always `SUID_DUMP_USER`.
+            Some('d') => write!(core_name, "1"),
+            Some('s') => write!(core_name, "{}", sig_num.as_u8()),
+            Some('t') => write!(core_name, "{}", RealTimeClock::get().read_time().as_secs()),
+            Some('h') => {
+                let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+                let uts_name = ns_proxy.unwrap().uts_ns().uts_name();
+                push_escaped(&mut core_name, &uts_name.nodename().to_string_lossy());
+                Ok(())
+            }
+            Some('e') => {
+                let thread_name = ctx.posix_thread.thread_name().lock();
+                push_escaped(&mut core_name, &thread_name.name().to_string_lossy());
+                Ok(())
+            }
+            Some('E') => {
+                let user_space = ctx.user_space();
+                let executable_path = user_space
+                    .vmar()
+                    .process_vm()
+                    .executable_file()
+                    .display_name();
+                push_escaped(&mut core_name, &executable_path);
+                Ok(())
+            }
+            Some('c') => write!(core_name, "{}", limit),
+            _ => Ok(()),
+        };
+    }
+
+    core_name
+}
+
+/// Pushes `s` to `core_name`, replacing `/` with `!` so that no new directories are introduced.
+fn push_escaped(core_name: &mut String, s: &str) {
+    core_name.extend(s.chars().map(|c| if c == '/' { '!' } else { c }));
+}
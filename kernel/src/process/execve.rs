@@ -3,24 +3,27 @@
 use aster_rights::WriteOp;
 use ostd::{
     arch::cpu::context::{FpuContext, GeneralRegs, UserContext},
-    sync::Waiter,
     user::UserContextApi,
 };
 
 use crate::{
     fs::{
         fs_resolver::{FsResolver, PathOrInode},
-        utils::Inode,
+        utils::{Inode, Permission},
     },
     prelude::*,
     process::{
-        posix_thread::{sigkill_other_threads, thread_table, PosixThread, ThreadLocal, ThreadName},
+        credentials::capabilities::CapSet,
+        posix_thread::{
+            ptrace::trace_exec, sigkill_other_threads, thread_table, wait_other_threads_exit,
+            PosixThread, ThreadLocal, ThreadName,
+        },
         process_vm::{unshare_and_renew_vmar, MAX_LEN_STRING_ARG, MAX_NR_STRING_ARGS},
         program_loader::elf::ElfLoadInfo,
         signal::{
             constants::{SIGCHLD, SIGKILL},
             signals::kernel::KernelSignal,
-            HandlePendingSignal, PauseReason, SigStack,
+            SigStack,
         },
         ContextUnshareAdminApi, Credentials, Process, ProgramToLoad,
     },
@@ -70,6 +73,7 @@ pub fn do_execve(
     // After this point, failures in subsequent operations are fatal: the process
     // state may be left inconsistent and it can never return to user mode.
 
+    let old_tid = ctx.posix_thread.tid();
     let res = do_execve_no_return(ctx, user_context, elf_file, &fs_resolver, program_to_load);
 
     if res.is_err() {
@@ -79,6 +83,10 @@ pub fn do_execve(
 
     ctx.process.tasks().lock().finish_execve();
 
+    if res.is_ok() {
+        trace_exec(ctx, old_tid);
+    }
+
     res
 }
 
@@ -154,8 +162,17 @@ fn do_execve_no_return(
     // Set up the CPU context.
     set_cpu_context(thread_local, user_context, &elf_load_info);
 
+    // Check whether the ELF file is readable with the credentials before the changes below.
+    let is_elf_readable = elf_file
+        .inode()
+        .check_permission(Permission::MAY_READ)
+        .is_ok();
+    let old_permitted_capset = posix_thread.credentials().permitted_capset();
     // Apply file-capability changes.
     apply_caps_from_exec(process, posix_thread, elf_file.inode())?;
+    // Disallow core dumps and unprivileged tracing if the credentials have been elevated or if the
+    // ELF file is unreadable.
+    update_dumpable(process, posix_thread, old_permitted_capset, is_elf_readable);
 
     // If this was a vfork child, reset vfork-specific state.
     reset_vfork_child(process);
@@ -177,41 +194,6 @@ fn do_execve_no_return(
     Ok(())
 }
 
-fn wait_other_threads_exit(ctx: &Context) -> Result<()> {
-    let is_main_thread = ctx.posix_thread.tid() == ctx.process.pid();
-
-    let mut tasks = ctx.process.tasks().lock();
-    loop {
-        if is_main_thread {
-            if tasks.as_slice().len() == 1 {
-                return Ok(());
-            }
-        } else if tasks.as_slice().len() == 2 && tasks.has_exited_main() {
-            return Ok(());
-        }
-
-        // Wait until any signal comes or any other thread exits.
-        let (waiter, waker) = Waiter::new_pair();
-
-        ctx.posix_thread
-            .set_signalled_waker(waker.clone(), PauseReason::Sleep);
-        if ctx.has_pending_sigkill() {
-            ctx.posix_thread.clear_signalled_waker();
-            return_errno_with_message!(Errno::EAGAIN, "the current thread has received SIGKILL");
-        }
-
-        tasks.set_execve_waker(waker);
-        drop(tasks);
-
-        waiter.wait();
-
-        ctx.posix_thread.clear_signalled_waker();
-
-        tasks = ctx.process.tasks().lock();
-        tasks.clear_execve_waker();
-    }
-}
-
 fn set_cpu_context(
     thread_local: &ThreadLocal,
     user_context: &mut UserContext,
@@ -250,6 +232,34 @@ fn apply_caps_from_exec(
     Ok(())
 }
 
+/// Updates whether the process is dumpable according to the credentials after execve.
+///
+/// The process is not dumpable if the effective IDs differ from the real IDs, if the permitted
+/// capabilities have been raised, or if the ELF file cannot be read by the caller of execve (see
+/// `would_dump` in Linux).
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/exec.c#L1318-L1322>
+//
+// FIXME: Linux also updates the flag whenever the credentials are changed, e.g., via `setuid`.
+fn update_dumpable(
+    process: &Process,
+    posix_thread: &PosixThread,
+    old_permitted_capset: CapSet,
+    is_elf_readable: bool,
+) {
+    let credentials = posix_thread.credentials();
+    let is_dumpable = credentials.euid() == credentials.ruid()
+        && credentials.egid() == credentials.rgid()
+        && old_permitted_capset.contains(credentials.permitted_capset())
+        && is_elf_readable;
+
+    process
+        .lock_vmar()
+        .unwrap()
+        .process_vm()
+        .set_dumpable(is_dumpable);
+}
+
 /// Sets the UID in the credentials according to the ELF inode.
 ///
 /// If the ELF inode has the `set_uid` bit, the effective UID is set to the same value as the ELF
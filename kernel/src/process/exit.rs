@@ -4,8 +4,10 @@ use core::sync::atomic::Ordering;
 
 use super::{process_table, Pid, Process};
 use crate::{
-    events::IoEvents, fs::cgroupfs::CgroupMembership, prelude::*,
-    process::signal::signals::kernel::KernelSignal,
+    events::IoEvents,
+    fs::cgroupfs::CgroupMembership,
+    prelude::*,
+    process::{posix_thread::ptrace::detach_all_tracees, signal::signals::kernel::KernelSignal},
 };
 
 /// Exits the current POSIX process.
@@ -19,6 +21,8 @@ pub(super) fn exit_process(current_process: &Process) {
     current_process.status().set_zombie();
     current_process.status().set_vfork_child(false);
 
+    detach_all_tracees(current_process);
+
     // Drop fields in `Process`.
     current_process.lock_vmar().set_vmar(None);
 
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod clone;
+pub mod coredump;
 pub mod credentials;
 mod execve;
 mod exit;
@@ -4,7 +4,7 @@ use spin::Once;
 
 use crate::{
     fs::path::MountNamespace,
-    net::UtsNamespace,
+    net::{NetNamespace, UtsNamespace},
     prelude::*,
     process::{posix_thread::PosixThread, CloneFlags, UserNamespace},
 };
@@ -19,6 +19,7 @@ use crate::{
 pub struct NsProxy {
     uts_ns: Arc<UtsNamespace>,
     mnt_ns: Arc<MountNamespace>,
+    net_ns: Arc<NetNamespace>,
 }
 
 impl NsProxy {
@@ -29,6 +30,7 @@ impl NsProxy {
             Arc::new(NsProxy {
                 uts_ns: UtsNamespace::get_init_singleton().clone(),
                 mnt_ns: MountNamespace::get_init_singleton().clone(),
+                net_ns: NetNamespace::get_init_singleton().clone(),
             })
         })
     }
@@ -73,6 +75,11 @@ impl NsProxy {
             builder.mnt_ns(new_mnt_ns);
         }
 
+        if clone_ns_flags.contains(CloneFlags::CLONE_NEWNET) {
+            let new_net_ns = self.net_ns.new_clone(user_ns.clone(), posix_thread)?;
+            builder.net_ns(new_net_ns);
+        }
+
         // TODO: Support other namespaces.
 
         Ok(Arc::new(builder.build()))
@@ -87,6 +94,11 @@ impl NsProxy {
     pub fn mnt_ns(&self) -> &Arc<MountNamespace> {
         &self.mnt_ns
     }
+
+    /// Returns the associated network namespace.
+    pub fn net_ns(&self) -> &Arc<NetNamespace> {
+        &self.net_ns
+    }
 }
 
 /// A builder for creating a new `NsProxy` by selectively cloning namespaces
@@ -97,6 +109,7 @@ pub struct NsProxyBuilder<'a> {
     // Fields for new namespaces.
     uts_ns: Option<Arc<UtsNamespace>>,
     mnt_ns: Option<Arc<MountNamespace>>,
+    net_ns: Option<Arc<NetNamespace>>,
 }
 
 impl<'a> NsProxyBuilder<'a> {
@@ -106,6 +119,7 @@ impl<'a> NsProxyBuilder<'a> {
             old_proxy,
             uts_ns: None,
             mnt_ns: None,
+            net_ns: None,
         }
     }
 
@@ -121,20 +135,29 @@ impl<'a> NsProxyBuilder<'a> {
         self
     }
 
+    /// Sets the new network namespace.
+    pub fn net_ns(&mut self, net_ns: Arc<NetNamespace>) -> &mut Self {
+        self.net_ns = Some(net_ns);
+        self
+    }
+
     /// Builds the new `NsProxy`.
     pub fn build(self) -> NsProxy {
         let Self {
             old_proxy,
             uts_ns: new_uts,
             mnt_ns: new_mnt,
+            net_ns: new_net,
         } = self;
 
         let new_uts = new_uts.unwrap_or_else(|| old_proxy.uts_ns.clone());
         let new_mnt = new_mnt.unwrap_or_else(|| old_proxy.mnt_ns.clone());
+        let new_net = new_net.unwrap_or_else(|| old_proxy.net_ns.clone());
 
         NsProxy {
             uts_ns: new_uts,
             mnt_ns: new_mnt,
+            net_ns: new_net,
         }
     }
 }
@@ -143,7 +166,9 @@ impl<'a> NsProxyBuilder<'a> {
 ///
 /// This method does _not_ check CLONE_NEWUSER since it's handled separately.
 pub fn check_unsupported_ns_flags(flags: CloneFlags) -> Result<()> {
-    const SUPPORTED_FLAGS: CloneFlags = CloneFlags::CLONE_NEWUTS.union(CloneFlags::CLONE_NEWNS);
+    const SUPPORTED_FLAGS: CloneFlags = CloneFlags::CLONE_NEWUTS
+        .union(CloneFlags::CLONE_NEWNS)
+        .union(CloneFlags::CLONE_NEWNET);
 
     let unsupported_flags =
         (flags & CloneFlags::CLONE_NS_FLAGS) - SUPPORTED_FLAGS - CloneFlags::CLONE_NEWUSER;
@@ -9,7 +9,7 @@ use ostd::{
     task::Task,
 };
 
-use super::{thread_table, PosixThread, ThreadLocal};
+use super::{ptrace::Tracee, thread_table, PosixThread, ThreadLocal};
 use crate::{
     fs::{file_table::FileTable, thread_info::ThreadFsInfo},
     prelude::*,
@@ -177,6 +177,7 @@ impl PosixThreadBuilder {
                     prof_timer_manager,
                     io_priority: AtomicU32::new(0),
                     ns_proxy: Mutex::new(Some(ns_proxy.clone())),
+                    tracee: Tracee::new(),
                 }
             };
 
@@ -1,17 +1,19 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use ostd::task::Task;
+use ostd::{sync::Waiter, task::Task};
 
 use super::{
-    futex::futex_wake, robust_list::wake_robust_futex, thread_table, AsPosixThread, AsThreadLocal,
-    ThreadLocal,
+    futex::futex_wake, ptrace, robust_list::wake_robust_futex, thread_table, AsPosixThread,
+    AsThreadLocal, ThreadLocal,
 };
 use crate::{
     current_userspace,
     prelude::*,
     process::{
         exit::exit_process,
-        signal::{constants::SIGKILL, signals::kernel::KernelSignal},
+        signal::{
+            constants::SIGKILL, signals::kernel::KernelSignal, HandlePendingSignal, PauseReason,
+        },
         task_set::TaskSet,
         TermStatus,
     },
@@ -74,6 +76,8 @@ fn exit_internal(term_status: TermStatus, is_exiting_group: bool) {
 
     wake_robust_list(thread_local, posix_thread.tid());
 
+    ptrace::exit_tracee(posix_thread);
+
     // According to Linux behavior, the main thread shouldn't be removed from the table until the
     // process is reaped by its parent.
     if posix_thread.tid() != posix_process.pid() {
@@ -111,6 +115,44 @@ pub(in crate::process) fn sigkill_other_threads(current_task: &Task, task_set: &
     }
 }
 
+/// Waits for all other threads in the current process to exit.
+///
+/// This method fails with `EAGAIN` if the current thread receives `SIGKILL` while waiting.
+pub(in crate::process) fn wait_other_threads_exit(ctx: &Context) -> Result<()> {
+    let is_main_thread = ctx.posix_thread.tid() == ctx.process.pid();
+
+    let mut tasks = ctx.process.tasks().lock();
+    loop {
+        if is_main_thread {
+            if tasks.as_slice().len() == 1 {
+                return Ok(());
+            }
+        } else if tasks.as_slice().len() == 2 && tasks.has_exited_main() {
+            return Ok(());
+        }
+
+        // Wait until any signal comes or any other thread exits.
+        let (waiter, waker) = Waiter::new_pair();
+
+        ctx.posix_thread
+            .set_signalled_waker(waker.clone(), PauseReason::Sleep);
+        if ctx.has_pending_sigkill() {
+            ctx.posix_thread.clear_signalled_waker();
+            return_errno_with_message!(Errno::EAGAIN, "the current thread has received SIGKILL");
+        }
+
+        tasks.set_exit_waker(waker);
+        drop(tasks);
+
+        waiter.wait();
+
+        ctx.posix_thread.clear_signalled_waker();
+
+        tasks = ctx.process.tasks().lock();
+        tasks.clear_exit_waker();
+    }
+}
+
 /// Writes zero to `clear_child_tid` and performs a futex wake.
 fn wake_clear_ctid(thread_local: &ThreadLocal) {
     let clear_ctid = thread_local.clear_child_tid().get();
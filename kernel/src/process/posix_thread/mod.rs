@@ -30,15 +30,17 @@ mod exit;
 pub mod futex;
 mod name;
 mod posix_thread_ext;
+pub mod ptrace;
 mod robust_list;
 mod thread_local;
 pub mod thread_table;
 
 pub use builder::PosixThreadBuilder;
-pub(super) use exit::sigkill_other_threads;
 pub use exit::{do_exit, do_exit_group};
+pub(super) use exit::{sigkill_other_threads, wait_other_threads_exit};
 pub use name::{ThreadName, MAX_THREAD_NAME_LEN};
 pub use posix_thread_ext::AsPosixThread;
+use ptrace::Tracee;
 pub use robust_list::RobustListHead;
 pub use thread_local::{AsThreadLocal, FileTableRefMut, ThreadLocal};
 
@@ -85,6 +87,9 @@ pub struct PosixThread {
 
     /// The namespaces that the thread belongs to.
     ns_proxy: Mutex<Option<Arc<NsProxy>>>,
+
+    /// The tracing state of the thread.
+    tracee: Tracee,
 }
 
 impl PosixThread {
@@ -312,6 +317,11 @@ impl PosixThread {
     pub fn ns_proxy(&self) -> &Mutex<Option<Arc<NsProxy>>> {
         &self.ns_proxy
     }
+
+    /// Returns the tracing state of the thread.
+    pub fn tracee(&self) -> &Tracee {
+        &self.tracee
+    }
 }
 
 static POSIX_TID_ALLOCATOR: AtomicU32 = AtomicU32::new(1);
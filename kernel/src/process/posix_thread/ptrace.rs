@@ -0,0 +1,801 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Process tracing.
+//!
+//! A thread can be traced by a process, which is called the tracer. Whenever the traced thread
+//! (i.e., the tracee) is about to handle a signal, enters or exits a system call, or encounters
+//! certain events (e.g., `fork` and `execve`), it may enter a ptrace-stop. The tracer is notified
+//! via `wait`, and can inspect and modify the tracee before resuming it.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man2/ptrace.2.html>
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use ostd::{arch::cpu::context::UserContext, sync::Waiter};
+
+use super::{thread_table, AsPosixThread, PosixThread};
+use crate::{
+    arch::cpu::UserRegs,
+    cpu::LinuxAbi,
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        signal::{
+            c_types::siginfo_t,
+            constants::{SIGCHLD, SIGKILL, SIGSTOP, SIGTRAP, SI_USER},
+            sig_num::SigNum,
+            signals::{kernel::KernelSignal, Signal},
+            HandlePendingSignal, PauseReason,
+        },
+        CloneFlags, Pid, Process, Uid,
+    },
+    thread::{Thread, Tid},
+    time::wait::WaitTimeout,
+};
+
+bitflags! {
+    /// The options of a tracee.
+    pub struct PtraceOptions: u32 {
+        const PTRACE_O_TRACESYSGOOD = 1 << 0;
+        const PTRACE_O_TRACEFORK = 1 << 1;
+        const PTRACE_O_TRACEVFORK = 1 << 2;
+        const PTRACE_O_TRACECLONE = 1 << 3;
+        const PTRACE_O_TRACEEXEC = 1 << 4;
+        const PTRACE_O_TRACEVFORKDONE = 1 << 5;
+        const PTRACE_O_TRACEEXIT = 1 << 6;
+        const PTRACE_O_TRACESECCOMP = 1 << 7;
+        const PTRACE_O_EXITKILL = 1 << 20;
+        const PTRACE_O_SUSPEND_SECCOMP = 1 << 21;
+    }
+}
+
+impl PtraceOptions {
+    /// Parses the options from the raw value provided by the tracer.
+    pub fn from_raw(raw: u32) -> Result<Self> {
+        let options = Self::from_bits(raw)
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid ptrace options"))?;
+
+        let unsupported_options = options
+            & (Self::PTRACE_O_TRACEVFORKDONE
+                | Self::PTRACE_O_TRACEEXIT
+                | Self::PTRACE_O_TRACESECCOMP
+                | Self::PTRACE_O_SUSPEND_SECCOMP);
+        if !unsupported_options.is_empty() {
+            warn!("unsupported ptrace options: {:?}", unsupported_options);
+        }
+
+        Ok(options)
+    }
+}
+
+/// The events that are reported to the tracer via ptrace-stops.
+#[derive(Debug, Clone, Copy)]
+enum PtraceEvent {
+    Fork = 1,
+    Vfork = 2,
+    Clone = 3,
+    Exec = 4,
+}
+
+/// The tracing state of a POSIX thread.
+pub struct Tracee {
+    /// Whether the thread is traced.
+    ///
+    /// This allows checking the state in hot paths (e.g., system calls) without locking.
+    is_traced: AtomicBool,
+    inner: Mutex<Option<TraceeInner>>,
+}
+
+struct TraceeInner {
+    tracer: Weak<Process>,
+    tracer_pid: Pid,
+    tracer_uid: Uid,
+    options: PtraceOptions,
+    /// Whether the tracee stops at system call entries and exits
+    is_syscall_traced: bool,
+    /// The message of the last event, which can be retrieved via `PTRACE_GETEVENTMSG`
+    event_msg: usize,
+    /// The event that occurs during the current system call and has not been reported
+    pending_event: Option<PtraceEvent>,
+    /// The ptrace-stop that the tracee is in
+    stop: Option<PtraceStop>,
+}
+
+struct PtraceStop {
+    /// The status reported to the tracer via `wait`
+    status: u32,
+    siginfo: siginfo_t,
+    regs: UserRegs,
+    /// Whether the stop has been reported to the tracer via `wait`
+    is_reported: bool,
+    /// The signal to inject after the tracee is resumed, if the tracee has been resumed
+    resumption: Option<Option<SigNum>>,
+}
+
+/// The result of a ptrace-stop after the tracee is resumed.
+struct Resumption {
+    signal: Option<SigNum>,
+    siginfo: siginfo_t,
+    regs: UserRegs,
+    tracer_pid: Pid,
+    tracer_uid: Uid,
+}
+
+impl Tracee {
+    pub(super) fn new() -> Self {
+        Self {
+            is_traced: AtomicBool::new(false),
+            inner: Mutex::new(None),
+        }
+    }
+
+    /// Returns whether the thread is traced.
+    pub fn is_traced(&self) -> bool {
+        self.is_traced.load(Ordering::Relaxed)
+    }
+
+    /// Returns the PID of the tracer, or zero if the thread is not traced.
+    pub fn tracer_pid(&self) -> Pid {
+        self.inner
+            .lock()
+            .as_ref()
+            .map_or(0, |inner| inner.tracer_pid)
+    }
+
+    /// Reports the entry of a system call to the tracer.
+    ///
+    /// Returns the number of the system call to execute, or `None` if the system call should be
+    /// skipped. Note that the tracer may change the system call arguments in `user_ctx`.
+    pub fn report_syscall_entry(&self, ctx: &Context, user_ctx: &mut UserContext) -> Option<usize> {
+        let syscall_num = user_ctx.syscall_num();
+        let Some(status) = self.syscall_stop_status() else {
+            return Some(syscall_num);
+        };
+
+        // On x86-64, the return value register holds `-ENOSYS` at syscall-entry stops.
+        #[cfg(target_arch = "x86_64")]
+        user_ctx.set_syscall_ret(-(Errno::ENOSYS as i32) as usize);
+
+        let siginfo = self.new_trap_siginfo(ctx, status);
+        match self.stop(ctx, user_ctx, status, siginfo, Some(syscall_num)) {
+            Ok(Some(resumption)) => {
+                inject_signal(ctx, resumption.signal);
+                let new_syscall_num = resumption.regs.syscall_num();
+                (new_syscall_num != usize::MAX).then_some(new_syscall_num)
+            }
+            Ok(None) => Some(syscall_num),
+            // The thread is killed, so the system call should not be executed.
+            Err(_) => None,
+        }
+    }
+
+    /// Reports the exit of a system call to the tracer, along with the events that occur during
+    /// the system call.
+    pub fn report_syscall_exit(
+        &self,
+        ctx: &Context,
+        user_ctx: &mut UserContext,
+        syscall_num: usize,
+    ) {
+        let pending_event = self
+            .inner
+            .lock()
+            .as_mut()
+            .and_then(|inner| inner.pending_event.take());
+        if let Some(event) = pending_event {
+            let status = SIGTRAP.as_u8() as u32 | ((event as u32) << 8);
+            let siginfo = self.new_trap_siginfo(ctx, status);
+            if let Ok(Some(resumption)) =
+                self.stop(ctx, user_ctx, status, siginfo, Some(syscall_num))
+            {
+                inject_signal(ctx, resumption.signal);
+            }
+        }
+
+        let Some(status) = self.syscall_stop_status() else {
+            return;
+        };
+        let siginfo = self.new_trap_siginfo(ctx, status);
+        if let Ok(Some(resumption)) = self.stop(ctx, user_ctx, status, siginfo, Some(syscall_num)) {
+            inject_signal(ctx, resumption.signal);
+        }
+    }
+
+    /// Reports a signal to the tracer before the signal is delivered.
+    ///
+    /// Returns the signal to deliver, which may be suppressed or replaced by the tracer.
+    pub(in crate::process) fn report_signal(
+        &self,
+        ctx: &Context,
+        user_ctx: &mut UserContext,
+        signal: Box<dyn Signal>,
+    ) -> Option<Box<dyn Signal>> {
+        let sig_num = signal.num();
+        // SIGKILL cannot be intercepted by the tracer.
+        if sig_num == SIGKILL {
+            return Some(signal);
+        }
+
+        let resumption = match self.stop(
+            ctx,
+            user_ctx,
+            sig_num.as_u8() as u32,
+            signal.to_info(),
+            None,
+        ) {
+            Ok(Some(resumption)) => resumption,
+            Ok(None) => return Some(signal),
+            Err(_) => return None,
+        };
+
+        let new_sig_num = resumption.signal?;
+        let signal = if resumption.siginfo.si_signo == new_sig_num.as_u8() as i32 {
+            // The tracer may have changed the signal information via `PTRACE_SETSIGINFO`.
+            TracedSignal {
+                num: new_sig_num,
+                info: resumption.siginfo,
+            }
+        } else {
+            let mut info = siginfo_t::new(new_sig_num, SI_USER);
+            info.set_pid_uid(resumption.tracer_pid, resumption.tracer_uid);
+            TracedSignal {
+                num: new_sig_num,
+                info,
+            }
+        };
+
+        // If the new signal is blocked, it will be delivered after it is unblocked.
+        if ctx.posix_thread.has_signal_blocked(new_sig_num) {
+            ctx.posix_thread.enqueue_signal(Box::new(signal));
+            return None;
+        }
+
+        Some(Box::new(signal))
+    }
+
+    /// Waits for a ptrace-stop of the tracee that has not been reported.
+    ///
+    /// Returns the status of the stop. The stop is marked as reported unless `is_nowait` is true.
+    pub(in crate::process) fn wait_stopped(&self, is_nowait: bool) -> Option<u32> {
+        let mut inner = self.inner.lock();
+        let stop = inner.as_mut()?.stop.as_mut()?;
+        if stop.is_reported || stop.resumption.is_some() {
+            return None;
+        }
+
+        if !is_nowait {
+            stop.is_reported = true;
+        }
+        Some(stop.status)
+    }
+
+    /// Locks the ptrace-stop that the tracee is in.
+    ///
+    /// # Errors
+    ///
+    /// This method fails with [`ESRCH`] if the tracee is not traced by `tracer` or is not in a
+    /// ptrace-stop.
+    ///
+    /// [`ESRCH`]: crate::error::Errno::ESRCH
+    pub fn lock_stop<'a>(&'a self, tracer: &Process) -> Result<PtraceStopGuard<'a>> {
+        let inner = self.inner.lock();
+
+        let is_stopped = inner.as_ref().is_some_and(|inner| {
+            core::ptr::eq(inner.tracer.as_ptr(), tracer)
+                && inner
+                    .stop
+                    .as_ref()
+                    .is_some_and(|stop| stop.resumption.is_none())
+        });
+        if !is_stopped {
+            return_errno_with_message!(Errno::ESRCH, "the tracee is not in a ptrace-stop");
+        }
+
+        Ok(PtraceStopGuard { inner })
+    }
+
+    fn attach(&self, thread: &Thread, tracer: &Arc<Process>, options: PtraceOptions) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.is_some() {
+            return_errno_with_message!(Errno::EPERM, "the thread is already traced");
+        }
+        if thread.is_exited() {
+            return_errno_with_message!(Errno::ESRCH, "the thread has exited");
+        }
+
+        let tracer_uid = tracer
+            .main_thread()
+            .as_posix_thread()
+            .unwrap()
+            .credentials()
+            .ruid();
+        *inner = Some(TraceeInner {
+            tracer: Arc::downgrade(tracer),
+            tracer_pid: tracer.pid(),
+            tracer_uid,
+            options,
+            is_syscall_traced: false,
+            event_msg: 0,
+            pending_event: None,
+            stop: None,
+        });
+        self.is_traced.store(true, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Detaches the tracee from its tracer.
+    ///
+    /// If the tracee is in a ptrace-stop, it will be resumed with `signal`. The caller is
+    /// responsible for waking up the tracee.
+    ///
+    /// Returns the options of the tracee, if the tracee was traced.
+    fn detach(&self, signal: Option<SigNum>) -> Option<PtraceOptions> {
+        let mut inner = self.inner.lock();
+        let inner_mut = inner.as_mut()?;
+        let options = inner_mut.options;
+
+        if let Some(stop) = inner_mut.stop.as_mut() {
+            // The tracee will clear the tracing state itself after it is resumed, since it needs
+            // the saved registers.
+            inner_mut.tracer = Weak::new();
+            stop.resumption.get_or_insert(signal);
+        } else {
+            *inner = None;
+            self.is_traced.store(false, Ordering::Relaxed);
+        }
+
+        Some(options)
+    }
+
+    /// Enters a ptrace-stop and waits until the tracee is resumed.
+    ///
+    /// Returns `Ok(None)` if the thread is not traced.
+    ///
+    /// # Errors
+    ///
+    /// This method fails with [`EINTR`] if the thread is killed during the ptrace-stop.
+    ///
+    /// [`EINTR`]: crate::error::Errno::EINTR
+    fn stop(
+        &self,
+        ctx: &Context,
+        user_ctx: &mut UserContext,
+        status: u32,
+        siginfo: siginfo_t,
+        syscall_num: Option<usize>,
+    ) -> Result<Option<Resumption>> {
+        if ctx.has_pending_sigkill() {
+            return_errno_with_message!(Errno::EINTR, "the tracee is killed");
+        }
+
+        let tracer = {
+            let mut inner = self.inner.lock();
+            let Some(inner) = inner.as_mut() else {
+                return Ok(None);
+            };
+            let Some(tracer) = inner.tracer.upgrade() else {
+                return Ok(None);
+            };
+
+            inner.stop = Some(PtraceStop {
+                status,
+                siginfo,
+                regs: UserRegs::new(user_ctx, syscall_num),
+                is_reported: false,
+                resumption: None,
+            });
+            tracer
+        };
+
+        tracer.children_wait_queue().wake_all();
+        tracer.enqueue_signal(KernelSignal::new(SIGCHLD));
+        drop(tracer);
+
+        let cond = || {
+            let mut inner = self.inner.lock();
+            let inner_mut = inner.as_mut().unwrap();
+            let signal = inner_mut.stop.as_ref().unwrap().resumption?;
+
+            let stop = inner_mut.stop.take().unwrap();
+            let resumption = Resumption {
+                signal,
+                siginfo: stop.siginfo,
+                regs: stop.regs,
+                tracer_pid: inner_mut.tracer_pid,
+                tracer_uid: inner_mut.tracer_uid,
+            };
+
+            // The tracee has been detached.
+            if inner_mut.tracer.strong_count() == 0 {
+                *inner = None;
+                self.is_traced.store(false, Ordering::Relaxed);
+            }
+
+            Some(resumption)
+        };
+        // Only SIGKILL can interrupt a ptrace-stop.
+        let cancel_cond = || {
+            if ctx.has_pending_sigkill() {
+                return_errno_with_message!(Errno::EINTR, "the tracee is killed");
+            }
+            Ok(())
+        };
+
+        let (waiter, _) = Waiter::new_pair();
+        ctx.posix_thread
+            .set_signalled_waker(waiter.waker(), PauseReason::StopByPtrace);
+        let res = waiter.wait_until_or_timeout_cancelled(cond, cancel_cond, None);
+        ctx.posix_thread.clear_signalled_waker();
+
+        let resumption = match res {
+            Ok(resumption) => resumption,
+            Err(err) => {
+                if let Some(inner) = self.inner.lock().as_mut() {
+                    inner.stop = None;
+                }
+                return Err(err);
+            }
+        };
+
+        resumption.regs.copy_user_regs_to(user_ctx);
+        Ok(Some(resumption))
+    }
+
+    fn syscall_stop_status(&self) -> Option<u32> {
+        let inner = self.inner.lock();
+        let inner = inner.as_ref()?;
+        if !inner.is_syscall_traced {
+            return None;
+        }
+
+        if inner.options.contains(PtraceOptions::PTRACE_O_TRACESYSGOOD) {
+            Some(SIGTRAP.as_u8() as u32 | 0x80)
+        } else {
+            Some(SIGTRAP.as_u8() as u32)
+        }
+    }
+
+    fn new_trap_siginfo(&self, ctx: &Context, status: u32) -> siginfo_t {
+        let mut siginfo = siginfo_t::new(SIGTRAP, status as i32);
+        siginfo.set_pid_uid(
+            ctx.posix_thread.tid(),
+            ctx.posix_thread.credentials().ruid(),
+        );
+        siginfo
+    }
+
+    fn set_pending_event(&self, event: PtraceEvent, event_msg: usize) {
+        if let Some(inner) = self.inner.lock().as_mut() {
+            inner.pending_event = Some(event);
+            inner.event_msg = event_msg;
+        }
+    }
+}
+
+/// A guard that holds the ptrace-stop of a tracee.
+pub struct PtraceStopGuard<'a> {
+    inner: MutexGuard<'a, Option<TraceeInner>>,
+}
+
+impl PtraceStopGuard<'_> {
+    /// Returns the registers of the tracee.
+    pub fn regs(&self) -> &UserRegs {
+        &self.stop().regs
+    }
+
+    /// Returns the mutable registers of the tracee.
+    ///
+    /// The modification takes effect when the tracee is resumed.
+    pub fn regs_mut(&mut self) -> &mut UserRegs {
+        &mut self.stop_mut().regs
+    }
+
+    /// Returns the information of the signal that causes the ptrace-stop.
+    pub fn siginfo(&self) -> &siginfo_t {
+        &self.stop().siginfo
+    }
+
+    /// Sets the information of the signal that causes the ptrace-stop.
+    ///
+    /// The new information takes effect only if the ptrace-stop is a signal-delivery-stop.
+    pub fn set_siginfo(&mut self, siginfo: siginfo_t) {
+        self.stop_mut().siginfo = siginfo;
+    }
+
+    /// Returns the message of the last event.
+    pub fn event_msg(&self) -> usize {
+        self.inner.as_ref().unwrap().event_msg
+    }
+
+    /// Sets the options of the tracee.
+    pub fn set_options(&mut self, options: PtraceOptions) {
+        self.inner.as_mut().unwrap().options = options;
+    }
+
+    /// Resumes the tracee.
+    ///
+    /// If `signal` is not `None`, the signal will be injected into the tracee. If
+    /// `is_syscall_traced` is true, the tracee will stop at the next system call entry or exit.
+    pub fn resume(
+        mut self,
+        posix_thread: &PosixThread,
+        signal: Option<SigNum>,
+        is_syscall_traced: bool,
+    ) {
+        let inner = self.inner.as_mut().unwrap();
+        inner.is_syscall_traced = is_syscall_traced;
+        inner.stop.as_mut().unwrap().resumption = Some(signal);
+        drop(self);
+
+        posix_thread.wake_signalled_waker();
+    }
+
+    fn stop(&self) -> &PtraceStop {
+        self.inner.as_ref().unwrap().stop.as_ref().unwrap()
+    }
+
+    fn stop_mut(&mut self) -> &mut PtraceStop {
+        self.inner.as_mut().unwrap().stop.as_mut().unwrap()
+    }
+}
+
+/// Makes the current thread traced by the parent process (`PTRACE_TRACEME`).
+pub fn trace_me(ctx: &Context) -> Result<()> {
+    let Some(parent) = ctx.process.parent().lock().process().upgrade() else {
+        return_errno_with_message!(Errno::EPERM, "the process has no parent");
+    };
+
+    link(&current_thread!(), &parent, PtraceOptions::empty())
+}
+
+/// Attaches the thread to the current process as its tracer.
+///
+/// If `options` is `None`, the thread is attached by `PTRACE_ATTACH` and will be stopped by a
+/// SIGSTOP. Otherwise, the thread is attached by `PTRACE_SEIZE` with the options and will not be
+/// stopped.
+pub fn attach(ctx: &Context, tid: Tid, options: Option<PtraceOptions>) -> Result<()> {
+    let thread = thread_table::get_thread(tid)
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the thread does not exist"))?;
+    let posix_thread = thread.as_posix_thread().unwrap();
+    let process = posix_thread.process();
+
+    if Arc::ptr_eq(&process, &ctx.process) {
+        return_errno_with_message!(Errno::EPERM, "a process cannot trace itself");
+    }
+    if process.is_init_process() {
+        return_errno_with_message!(Errno::EPERM, "the init process cannot be traced");
+    }
+    check_attach_perm(posix_thread, ctx)?;
+
+    link(
+        &thread,
+        &ctx.process,
+        options.unwrap_or(PtraceOptions::empty()),
+    )?;
+    if options.is_none() {
+        posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGSTOP)));
+    }
+
+    Ok(())
+}
+
+/// Returns the thread that is traced by the current process.
+pub fn get_tracee(ctx: &Context, tid: Tid) -> Result<Arc<Thread>> {
+    ctx.process
+        .tracees()
+        .lock()
+        .get(&tid)
+        .cloned()
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the thread is not traced"))
+}
+
+/// Detaches the tracee that is in a ptrace-stop from the current process.
+pub fn detach(ctx: &Context, tracee: &Thread, signal: Option<SigNum>) -> Result<()> {
+    let posix_thread = tracee.as_posix_thread().unwrap();
+
+    // Lock order: tracees of process -> tracing state of thread
+    let mut tracees = ctx.process.tracees().lock();
+    drop(posix_thread.tracee().lock_stop(&ctx.process)?);
+    posix_thread.tracee().detach(signal);
+    tracees.remove(&posix_thread.tid());
+    drop(tracees);
+
+    posix_thread.wake_signalled_waker();
+
+    Ok(())
+}
+
+/// Makes the new child traced by the tracer of the current thread, if necessary.
+pub(in crate::process) fn trace_child(
+    ctx: &Context,
+    child: &Arc<Thread>,
+    clone_flags: CloneFlags,
+    exit_signal: Option<SigNum>,
+) {
+    let tracee = ctx.posix_thread.tracee();
+    if !tracee.is_traced() || clone_flags.contains(CloneFlags::CLONE_UNTRACED) {
+        return;
+    }
+
+    let Some((tracer, options)) = tracee
+        .inner
+        .lock()
+        .as_ref()
+        .and_then(|inner| Some((inner.tracer.upgrade()?, inner.options)))
+    else {
+        return;
+    };
+
+    let (event, option) = if clone_flags.contains(CloneFlags::CLONE_VFORK) {
+        (PtraceEvent::Vfork, PtraceOptions::PTRACE_O_TRACEVFORK)
+    } else if exit_signal != Some(SIGCHLD) {
+        (PtraceEvent::Clone, PtraceOptions::PTRACE_O_TRACECLONE)
+    } else {
+        (PtraceEvent::Fork, PtraceOptions::PTRACE_O_TRACEFORK)
+    };
+    let is_event_traced = options.contains(option);
+    if !is_event_traced && !clone_flags.contains(CloneFlags::CLONE_PTRACE) {
+        return;
+    }
+
+    if link(child, &tracer, options).is_err() {
+        return;
+    }
+    let child_posix_thread = child.as_posix_thread().unwrap();
+    child_posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGSTOP)));
+
+    // FIXME: Linux reports the `PTRACE_EVENT_VFORK` event before waiting for the vfork child,
+    // whereas we report it after the `vfork` system call returns.
+    if is_event_traced {
+        tracee.set_pending_event(event, child_posix_thread.tid() as usize);
+    }
+}
+
+/// Reports a successful `execve` to the tracer of the current thread, if any.
+///
+/// `old_tid` is the thread ID before `execve`, which differs from the current one if `execve` is
+/// called by a non-main thread.
+pub(in crate::process) fn trace_exec(ctx: &Context, old_tid: Tid) {
+    let tracee = ctx.posix_thread.tracee();
+    if !tracee.is_traced() {
+        return;
+    }
+
+    let Some((tracer, options)) = tracee
+        .inner
+        .lock()
+        .as_ref()
+        .and_then(|inner| Some((inner.tracer.upgrade()?, inner.options)))
+    else {
+        return;
+    };
+
+    let new_tid = ctx.posix_thread.tid();
+    if new_tid != old_tid {
+        let mut tracees = tracer.tracees().lock();
+        if let Some(thread) = tracees.remove(&old_tid) {
+            tracees.insert(new_tid, thread);
+        }
+    }
+
+    if options.contains(PtraceOptions::PTRACE_O_TRACEEXEC) {
+        tracee.set_pending_event(PtraceEvent::Exec, old_tid as usize);
+    } else {
+        ctx.posix_thread
+            .enqueue_signal(Box::new(KernelSignal::new(SIGTRAP)));
+    }
+}
+
+/// Detaches all the tracees of the exiting tracer.
+pub(in crate::process) fn detach_all_tracees(tracer: &Process) {
+    let tracees = core::mem::take(&mut *tracer.tracees().lock());
+
+    for thread in tracees.values() {
+        let posix_thread = thread.as_posix_thread().unwrap();
+        let Some(options) = posix_thread.tracee().detach(None) else {
+            continue;
+        };
+
+        if options.contains(PtraceOptions::PTRACE_O_EXITKILL) {
+            posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGKILL)));
+        } else {
+            posix_thread.wake_signalled_waker();
+        }
+    }
+}
+
+/// Removes the exiting thread from the tracees of its tracer.
+//
+// FIXME: The exit status of the tracee should be reported to the tracer if the tracer is not
+// the parent process.
+pub(super) fn exit_tracee(posix_thread: &PosixThread) {
+    let tracee = posix_thread.tracee();
+
+    let tracer = {
+        let mut inner = tracee.inner.lock();
+        tracee.is_traced.store(false, Ordering::Relaxed);
+        inner.take().and_then(|inner| inner.tracer.upgrade())
+    };
+    let Some(tracer) = tracer else {
+        return;
+    };
+
+    tracer.tracees().lock().remove(&posix_thread.tid());
+    tracer.children_wait_queue().wake_all();
+}
+
+fn link(thread: &Arc<Thread>, tracer: &Arc<Process>, options: PtraceOptions) -> Result<()> {
+    let posix_thread = thread.as_posix_thread().unwrap();
+
+    // Lock order: tracees of process -> tracing state of thread
+    let mut tracees = tracer.tracees().lock();
+    if tracer.status().is_zombie() {
+        return_errno_with_message!(Errno::EPERM, "the tracer has exited");
+    }
+    posix_thread.tracee().attach(thread, tracer, options)?;
+    tracees.insert(posix_thread.tid(), thread.clone());
+
+    Ok(())
+}
+
+fn check_attach_perm(target: &PosixThread, ctx: &Context) -> Result<()> {
+    let current_cred = ctx.posix_thread.credentials();
+    let target_cred = target.credentials();
+
+    let uid = current_cred.ruid();
+    let gid = current_cred.rgid();
+    let is_dumpable = target
+        .process()
+        .lock_vmar()
+        .as_ref()
+        .is_some_and(|vmar| vmar.process_vm().is_dumpable());
+    if is_dumpable
+        && uid == target_cred.ruid()
+        && uid == target_cred.euid()
+        && uid == target_cred.suid()
+        && gid == target_cred.rgid()
+        && gid == target_cred.egid()
+        && gid == target_cred.sgid()
+    {
+        return Ok(());
+    }
+
+    target
+        .process()
+        .user_ns()
+        .lock()
+        .check_cap(CapSet::SYS_PTRACE, ctx.posix_thread)
+}
+
+fn inject_signal(ctx: &Context, signal: Option<SigNum>) {
+    if let Some(sig_num) = signal {
+        ctx.posix_thread
+            .enqueue_signal(Box::new(KernelSignal::new(sig_num)));
+    }
+}
+
+/// A signal that is delivered after a signal-delivery-stop.
+#[derive(Clone, Copy)]
+struct TracedSignal {
+    num: SigNum,
+    info: siginfo_t,
+}
+
+impl Debug for TracedSignal {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TracedSignal")
+            .field("num", &self.num)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signal for TracedSignal {
+    fn num(&self) -> SigNum {
+        self.num
+    }
+
+    fn to_info(&self) -> siginfo_t {
+        self.info
+    }
+}
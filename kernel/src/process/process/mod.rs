@@ -28,7 +28,7 @@ use crate::{
         UserNamespace, WaitOptions,
     },
     sched::{AtomicNice, Nice},
-    thread::{AsThread, Thread},
+    thread::{AsThread, Thread, Tid},
     time::clocks::ProfClock,
     vm::vmar::Vmar,
 };
@@ -94,6 +94,8 @@ pub struct Process {
     pub(super) parent: ParentProcess,
     /// Children processes
     children: Mutex<Option<BTreeMap<Pid, Arc<Process>>>>,
+    /// Threads traced by this process
+    tracees: Mutex<BTreeMap<Tid, Arc<Thread>>>,
     /// Process group
     pub(super) process_group: Mutex<Weak<ProcessGroup>>,
     /// The resource usage statistics of reaped child processes.
@@ -237,6 +239,7 @@ impl Process {
             status: ProcessStatus::default(),
             parent: ParentProcess::new(Weak::new()),
             children: Mutex::new(Some(BTreeMap::new())),
+            tracees: Mutex::new(BTreeMap::new()),
             process_group: Mutex::new(Weak::new()),
             reaped_children_stats: Mutex::new(ReapedChildrenStats::default()),
             is_child_subreaper: AtomicBool::new(false),
@@ -322,6 +325,10 @@ impl Process {
         &self.children_wait_queue
     }
 
+    pub(super) fn tracees(&self) -> &Mutex<BTreeMap<Tid, Arc<Thread>>> {
+        &self.tracees
+    }
+
     pub fn reaped_children_stats(&self) -> &Mutex<ReapedChildrenStats> {
         &self.reaped_children_stats
     }
@@ -684,9 +691,11 @@ impl Process {
     }
 
     /// Stops the process.
-    //
-    // FIXME: `ptrace` is another reason that can cause a process to stop.
-    // Consider extending the method signature to support `ptrace` if necessary.
+    ///
+    /// Note that this is the group-stop caused by signals. Ptrace-stops are per-thread and are
+    /// managed by [`Tracee`].
+    ///
+    /// [`Tracee`]: crate::process::posix_thread::ptrace::Tracee
     pub fn stop(&self, sig_num: SigNum) {
         if self.status.stop_status().stop(sig_num) {
             self.wake_up_parent();
@@ -110,6 +110,7 @@ pub struct InitStack {
     pos: AtomicUsize,
     argv_range: SpinLock<Range<Vaddr>>,
     envp_range: SpinLock<Range<Vaddr>>,
+    auxv_range: SpinLock<Range<Vaddr>>,
 }
 
 impl Clone for InitStack {
@@ -120,6 +121,7 @@ impl Clone for InitStack {
             pos: AtomicUsize::new(self.pos.load(Ordering::Relaxed)),
             argv_range: SpinLock::new(self.argv_range.lock().clone()),
             envp_range: SpinLock::new(self.envp_range.lock().clone()),
+            auxv_range: SpinLock::new(self.auxv_range.lock().clone()),
         }
     }
 }
@@ -148,6 +150,7 @@ impl InitStack {
             pos: AtomicUsize::new(initial_top),
             argv_range: SpinLock::new(0..0),
             envp_range: SpinLock::new(0..0),
+            auxv_range: SpinLock::new(0..0),
         }
     }
 
@@ -193,10 +196,11 @@ impl InitStack {
             auxvec,
             map_addr: self.initial_top - self.max_size,
         };
-        let (argv_range, envp_range) = writer.write()?;
+        let (argv_range, envp_range, auxv_range) = writer.write()?;
 
         *self.argv_range.lock() = argv_range;
         *self.envp_range.lock() = envp_range;
+        *self.auxv_range.lock() = auxv_range;
 
         Ok(())
     }
@@ -211,6 +215,7 @@ impl InitStack {
             map_addr: self.initial_top - self.max_size,
             argv_range: self.argv_range.lock().clone(),
             envp_range: self.envp_range.lock().clone(),
+            auxv_range: self.auxv_range.lock().clone(),
         }
     }
 
@@ -241,8 +246,8 @@ struct InitStackWriter<'a> {
 impl InitStackWriter<'_> {
     /// Writes the content to the init stack.
     ///
-    /// Returns the range of argv and envp in the init stack.
-    fn write(mut self) -> Result<(Range<Vaddr>, Range<Vaddr>)> {
+    /// Returns the range of argv, envp, and the auxiliary vector in the init stack.
+    fn write(mut self) -> Result<(Range<Vaddr>, Range<Vaddr>, Range<Vaddr>)> {
         // FIXME: Some OSes may put the first page of executable file here
         // for interpreting elf headers.
 
@@ -266,7 +271,9 @@ impl InitStackWriter<'_> {
         self.auxvec.set(AuxKey::AT_RANDOM, random_value_pointer)?;
 
         self.adjust_stack_alignment(&envp_pointers, &argv_pointers)?;
+        let auxv_end = self.pos();
         self.write_aux_vec()?;
+        let auxv_start = self.pos();
         self.write_envp_pointers(envp_pointers)?;
         self.write_argv_pointers(argv_pointers)?;
 
@@ -276,7 +283,11 @@ impl InitStackWriter<'_> {
         // Ensure stack top is 16-bytes aligned
         debug_assert_eq!(self.pos() & !0xf, self.pos());
 
-        Ok((argv_start..argv_end, envp_start..envp_end))
+        Ok((
+            argv_start..argv_end,
+            envp_start..envp_end,
+            auxv_start..auxv_end,
+        ))
     }
 
     fn write_envp_strings(&self) -> Result<Vec<u64>> {
@@ -401,6 +412,7 @@ pub struct InitStackReader<'a> {
     map_addr: usize,
     argv_range: Range<Vaddr>,
     envp_range: Range<Vaddr>,
+    auxv_range: Range<Vaddr>,
 }
 
 impl InitStackReader<'_> {
@@ -447,6 +459,19 @@ impl InitStackReader<'_> {
         Ok(bytes_read)
     }
 
+    /// Reads the auxiliary vector at the `offset` from the process init stack.
+    pub fn auxv(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        if offset >= self.auxv_range.end - self.auxv_range.start {
+            return Ok(0);
+        }
+
+        let read_at = self.auxv_range.start + offset;
+        writer.limit(self.auxv_range.end - read_at);
+        let bytes_read = self.vmar.read_remote(read_at, writer)?;
+
+        Ok(bytes_read)
+    }
+
     /// Returns the bottom address of the init stack (lowest address).
     pub const fn init_stack_bottom(&self) -> Vaddr {
         self.base
@@ -13,7 +13,8 @@ mod heap;
 mod init_stack;
 
 #[cfg(target_arch = "riscv64")]
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::{AtomicBool, Ordering};
 
 use ostd::{sync::MutexGuard, task::disable_preempt};
 
@@ -70,6 +71,8 @@ pub struct ProcessVm {
     heap: Heap,
     /// The executable `PathOrInode`.
     executable_file: PathOrInode,
+    /// Whether the process can be dumped or be attached by `ptrace` without privileges.
+    is_dumpable: AtomicBool,
     /// The base address for vDSO segment
     #[cfg(target_arch = "riscv64")]
     vdso_base: AtomicUsize,
@@ -82,6 +85,7 @@ impl ProcessVm {
             init_stack: InitStack::new(),
             heap: Heap::new(),
             executable_file,
+            is_dumpable: AtomicBool::new(true),
             #[cfg(target_arch = "riscv64")]
             vdso_base: AtomicUsize::new(0),
         }
@@ -93,6 +97,7 @@ impl ProcessVm {
             init_stack: process_vm.init_stack.clone(),
             heap: process_vm.heap.clone(),
             executable_file: process_vm.executable_file.clone(),
+            is_dumpable: AtomicBool::new(process_vm.is_dumpable()),
             #[cfg(target_arch = "riscv64")]
             vdso_base: AtomicUsize::new(process_vm.vdso_base.load(Ordering::Relaxed)),
         }
@@ -113,6 +118,19 @@ impl ProcessVm {
         &self.executable_file
     }
 
+    /// Returns whether the process is dumpable.
+    ///
+    /// A process that is not dumpable produces no core dumps, and cannot be attached by `ptrace`
+    /// unless the tracer has the `CAP_SYS_PTRACE` capability.
+    pub fn is_dumpable(&self) -> bool {
+        self.is_dumpable.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether the process is dumpable.
+    pub fn set_dumpable(&self, is_dumpable: bool) {
+        self.is_dumpable.store(is_dumpable, Ordering::Relaxed);
+    }
+
     /// Maps and writes the initial portion of the main stack of a process.
     pub(super) fn map_and_write_init_stack(
         &self,
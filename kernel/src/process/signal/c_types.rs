@@ -63,6 +63,10 @@ impl siginfo_t {
         self.siginfo_fields.common.first = pid_uid;
     }
 
+    pub fn set_si_value(&mut self, value: sigval_t) {
+        self.siginfo_fields.common.second.value = value;
+    }
+
     pub fn set_status(&mut self, status: i32) {
         self.siginfo_fields.common.second.sigchild.status = status;
     }
@@ -36,6 +36,7 @@ use crate::{
     current_userspace,
     prelude::*,
     process::{
+        coredump,
         posix_thread::do_exit_group,
         signal::{c_types::stack_t, signals::Signal},
         TermStatus,
@@ -65,7 +66,7 @@ pub fn handle_pending_signal(
         None
     };
 
-    let Some((signal, sig_action)) = dequeue_pending_signal(ctx) else {
+    let Some((signal, sig_action)) = dequeue_pending_signal(ctx, user_ctx) else {
         return;
     };
 
@@ -121,6 +122,11 @@ pub fn handle_pending_signal(
                         ctx.process.pid(),
                         sig_num.sig_name()
                     );
+                    if sig_default_action == SigDefaultAction::Core {
+                        coredump::do_coredump(ctx, user_ctx, sig_num);
+                    }
+                    // Another thread may be dumping the core and waiting for this thread.
+                    coredump::report_thread_status(ctx, user_ctx);
                     // We should exit current here, since we cannot restore a valid status from trap now.
                     do_exit_group(TermStatus::Killed(sig_num));
                 }
@@ -132,22 +138,38 @@ pub fn handle_pending_signal(
     }
 }
 
-fn dequeue_pending_signal(ctx: &Context) -> Option<(Box<dyn Signal>, SigAction)> {
+fn dequeue_pending_signal(
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+) -> Option<(Box<dyn Signal>, SigAction)> {
     let posix_thread = ctx.posix_thread;
 
-    let sig_dispositions = ctx.process.sig_dispositions().lock();
-    let mut sig_dispositions = sig_dispositions.lock();
+    let sig_dispositions = ctx.process.sig_dispositions().lock().clone();
 
     let sig_mask = posix_thread.sig_mask().load(Ordering::Relaxed);
-    let (signal, sig_num, sig_action) = loop {
-        let signal = ctx.dequeue_signal(&sig_mask)?;
+    let (signal, sig_num, sig_action, mut sig_dispositions) = loop {
+        let mut signal = ctx.dequeue_signal(&sig_mask)?;
+
+        // The tracer is notified before the signal is delivered, even if the signal will be
+        // ignored. The tracer may suppress or replace the signal.
+        if posix_thread.tracee().is_traced() {
+            let Some(traced_signal) = posix_thread.tracee().report_signal(ctx, user_ctx, signal)
+            else {
+                continue;
+            };
+            signal = traced_signal;
+        }
+
+        // Lock the dispositions after the ptrace-stop since the tracee may stop for a long time.
+        let sig_dispositions = sig_dispositions.lock();
+
         let sig_num = signal.num();
         let sig_action = sig_dispositions.get(sig_num);
         if sig_action.will_ignore(sig_num) {
             continue;
         }
 
-        break (signal, sig_num, sig_action);
+        break (signal, sig_num, sig_action, sig_dispositions);
     };
 
     if let SigAction::User { flags, .. } = &sig_action
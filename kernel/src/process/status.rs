@@ -165,8 +165,7 @@ impl StopStatus {
 
 #[derive(Debug)]
 pub(super) enum StopWaitStatus {
-    // FIXME: A process can also be stopped by ptrace.
-    // Extend this enum to support ptrace.
+    // Note that ptrace-stops are per-thread and are reported via `WaitStatus::PtraceStop`.
     Stopped(SigNum),
     Continue,
 }
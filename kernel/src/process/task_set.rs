@@ -7,7 +7,7 @@ use ostd::{
     task::{CurrentTask, Task},
 };
 
-use super::Pid;
+use super::{coredump::ThreadStatus, Pid};
 use crate::{
     events::{Events, Observer, Subject},
     prelude::*,
@@ -20,7 +20,8 @@ pub struct TaskSet {
     has_exited_main: bool,
     has_exited_group: bool,
     in_execve: bool,
-    execve_waker: Option<Arc<Waker>>,
+    exit_waker: Option<Arc<Waker>>,
+    core_dump_threads: Option<Vec<ThreadStatus>>,
     subject: Subject<TidEvent>,
 }
 
@@ -32,7 +33,8 @@ impl TaskSet {
             has_exited_main: false,
             has_exited_group: false,
             in_execve: false,
-            execve_waker: None,
+            exit_waker: None,
+            core_dump_threads: None,
             subject: Subject::new(),
         }
     }
@@ -75,7 +77,7 @@ impl TaskSet {
             self.notify_tid_exit(tid);
         }
 
-        if let Some(waker) = self.execve_waker.as_ref() {
+        if let Some(waker) = self.exit_waker.as_ref() {
             waker.wake_up();
         }
 
@@ -130,16 +132,37 @@ impl TaskSet {
 
     /// Registers a waker to be notified when any thread exits.
     ///
-    /// Only a thread performing execve should set this waker; it is used to
-    /// wake the execve-ing thread while it waits for other threads to exit.
-    pub(super) fn set_execve_waker(&mut self, waker: Arc<Waker>) {
-        debug_assert!(self.execve_waker.is_none());
-        self.execve_waker = Some(waker);
+    /// Only a thread performing execve or dumping the core should set this
+    /// waker; it is used to wake the thread while it waits for other threads
+    /// to exit.
+    pub(super) fn set_exit_waker(&mut self, waker: Arc<Waker>) {
+        debug_assert!(self.exit_waker.is_none());
+        self.exit_waker = Some(waker);
     }
 
-    /// Clears the waker previously set by [`Self::set_execve_waker`].
-    pub(super) fn clear_execve_waker(&mut self) {
-        self.execve_waker = None;
+    /// Clears the waker previously set by [`Self::set_exit_waker`].
+    pub(super) fn clear_exit_waker(&mut self) {
+        self.exit_waker = None;
+    }
+
+    /// Starts collecting the thread status for a core dump.
+    ///
+    /// This should only be called after [`Self::set_exited_group`] so that the
+    /// other threads are exiting. Before they exit, they should report their
+    /// status via [`Self::core_dump_threads_mut`].
+    pub(super) fn start_core_dump(&mut self) {
+        debug_assert!(self.has_exited_group && self.core_dump_threads.is_none());
+        self.core_dump_threads = Some(Vec::new());
+    }
+
+    /// Returns the collected thread status if a core dump is in progress.
+    pub(super) fn core_dump_threads_mut(&mut self) -> Option<&mut Vec<ThreadStatus>> {
+        self.core_dump_threads.as_mut()
+    }
+
+    /// Finishes collecting the thread status and returns the collected status.
+    pub(super) fn finish_core_dump(&mut self) -> Vec<ThreadStatus> {
+        self.core_dump_threads.take().unwrap_or_default()
     }
 
     /// Notifies `TidEvent::Exit` events to the subject.
@@ -14,6 +14,7 @@ use crate::{
         status::StopWaitStatus,
         ReapedChildrenStats, Uid,
     },
+    thread::{Thread, Tid},
     time::clocks::ProfClock,
 };
 
@@ -95,7 +96,25 @@ pub fn do_wait(
                     })
                     .collect::<Box<_>>();
 
-                if unwaited_children.is_empty() {
+                // Lock order: children of process -> tracees of process
+                let tracees = ctx.process.tracees().lock();
+                let unwaited_tracees = tracees
+                    .iter()
+                    .filter(|(tid, tracee)| match &child_filter {
+                        ProcessFilter::Any => true,
+                        ProcessFilter::WithPid(pid) => **tid == *pid,
+                        ProcessFilter::WithPgid(pgid) => {
+                            tracee.as_posix_thread().unwrap().process().pgid() == *pgid
+                        }
+                        ProcessFilter::WithPidfd(pid_file) => Arc::ptr_eq(
+                            pid_file.process(),
+                            &tracee.as_posix_thread().unwrap().process(),
+                        ),
+                    })
+                    .map(|(_, tracee)| tracee)
+                    .collect::<Box<_>>();
+
+                if unwaited_children.is_empty() && unwaited_tracees.is_empty() {
                     return Some(Err(Error::with_message(
                         Errno::ECHILD,
                         "the process has no child to wait",
@@ -113,6 +132,10 @@ pub fn do_wait(
                     return Some(Ok(Some(status)));
                 }
 
+                if let Some(status) = wait_ptrace_stopped(&unwaited_tracees, wait_options) {
+                    return Some(Ok(Some(status)));
+                }
+
                 if let Some(status) = wait_stopped_or_continued(&unwaited_children, wait_options) {
                     return Some(Ok(Some(status)));
                 }
@@ -141,10 +164,16 @@ pub enum WaitStatus {
     Zombie(Arc<Process>),
     Stop(Arc<Process>, SigNum),
     Continue(Arc<Process>),
+    /// A traced thread is in a ptrace-stop, with its thread ID and the stop status.
+    PtraceStop(Arc<Process>, Tid, u32),
 }
 
 impl WaitStatus {
     pub fn pid(&self) -> Pid {
+        if let WaitStatus::PtraceStop(_, tid, _) = self {
+            return *tid;
+        }
+
         self.process().pid()
     }
 
@@ -165,7 +194,8 @@ impl WaitStatus {
         match self {
             WaitStatus::Zombie(process)
             | WaitStatus::Stop(process, _)
-            | WaitStatus::Continue(process) => process,
+            | WaitStatus::Continue(process)
+            | WaitStatus::PtraceStop(process, _, _) => process,
         }
     }
 }
@@ -201,6 +231,25 @@ fn wait_stopped_or_continued(
     None
 }
 
+fn wait_ptrace_stopped(
+    unwaited_tracees: &[&Arc<Thread>],
+    wait_options: WaitOptions,
+) -> Option<WaitStatus> {
+    // Unlike group-stops, ptrace-stops are always reported regardless of `WSTOPPED`.
+    let is_nowait = wait_options.contains(WaitOptions::WNOWAIT);
+
+    // Lock order: tracees of process -> tracing state of thread
+    unwaited_tracees.iter().find_map(|tracee| {
+        let posix_thread = tracee.as_posix_thread().unwrap();
+        let status = posix_thread.tracee().wait_stopped(is_nowait)?;
+        Some(WaitStatus::PtraceStop(
+            posix_thread.process(),
+            posix_thread.tid(),
+            status,
+        ))
+    })
+}
+
 /// Free zombie child with `child_pid`, returns the exit code of child process.
 fn reap_zombie_child(
     child_pid: Pid,
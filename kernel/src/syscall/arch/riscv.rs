@@ -27,6 +27,8 @@ use super::{
     fallocate::sys_fallocate,
     fcntl::sys_fcntl,
     flock::sys_flock,
+    fsmount::sys_fsmount,
+    fsopen::{sys_fsconfig, sys_fsopen},
     fsync::{sys_fdatasync, sys_fsync},
     futex::sys_futex,
     get_ioprio::sys_ioprio_get,
@@ -54,6 +56,7 @@ use super::{
     getuid::sys_getuid,
     getxattr::{sys_fgetxattr, sys_getxattr, sys_lgetxattr},
     impl_syscall_nums_and_dispatch_fn,
+    inotify::{sys_inotify_add_watch, sys_inotify_init1, sys_inotify_rm_watch},
     ioctl::sys_ioctl,
     kill::sys_kill,
     link::sys_linkat,
@@ -66,25 +69,39 @@ use super::{
     mknod::sys_mknodat,
     mmap::sys_mmap,
     mount::sys_mount,
+    mount_setattr::sys_mount_setattr,
+    move_mount::sys_move_mount,
     mprotect::sys_mprotect,
+    mqueue::{
+        sys_mq_getsetattr, sys_mq_notify, sys_mq_open, sys_mq_timedreceive, sys_mq_timedsend,
+        sys_mq_unlink,
+    },
     mremap::sys_mremap,
+    msgctl::sys_msgctl,
+    msgget::sys_msgget,
+    msgrcv::sys_msgrcv,
+    msgsnd::sys_msgsnd,
     msync::sys_msync,
     munmap::sys_munmap,
     nanosleep::{sys_clock_nanosleep, sys_nanosleep},
     open::sys_openat,
+    open_tree::sys_open_tree,
     pidfd_open::sys_pidfd_open,
     pipe::sys_pipe2,
+    pivot_root::sys_pivot_root,
     ppoll::sys_ppoll,
     prctl::sys_prctl,
     pread64::sys_pread64,
     preadv::{sys_preadv, sys_preadv2, sys_readv},
     prlimit64::{sys_getrlimit, sys_prlimit64, sys_setrlimit},
     pselect6::sys_pselect6,
+    ptrace::sys_ptrace,
     pwrite64::sys_pwrite64,
     pwritev::{sys_pwritev, sys_pwritev2, sys_writev},
     read::sys_read,
     readlink::sys_readlinkat,
     recvfrom::sys_recvfrom,
+    recvmmsg::sys_recvmmsg,
     recvmsg::sys_recvmsg,
     removexattr::{sys_fremovexattr, sys_lremovexattr, sys_removexattr},
     rename::sys_renameat2,
@@ -107,6 +124,7 @@ use super::{
     semget::sys_semget,
     semop::{sys_semop, sys_semtimedop},
     sendfile::sys_sendfile,
+    sendmmsg::sys_sendmmsg,
     sendmsg::sys_sendmsg,
     sendto::sys_sendto,
     set_ioprio::sys_ioprio_set,
@@ -130,6 +148,10 @@ use super::{
     setsockopt::sys_setsockopt,
     setuid::sys_setuid,
     setxattr::{sys_fsetxattr, sys_lsetxattr, sys_setxattr},
+    shmat::sys_shmat,
+    shmctl::sys_shmctl,
+    shmdt::sys_shmdt,
+    shmget::sys_shmget,
     shutdown::sys_shutdown,
     sigaltstack::sys_sigaltstack,
     signalfd::sys_signalfd4,
@@ -180,6 +202,9 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_DUP = 23                     => sys_dup(args[..1]);
     SYS_DUP3 = 24                    => sys_dup3(args[..3]);
     SYS_FCNTL = 25                   => sys_fcntl(args[..3]);
+    SYS_INOTIFY_INIT1 = 26           => sys_inotify_init1(args[..1]);
+    SYS_INOTIFY_ADD_WATCH = 27       => sys_inotify_add_watch(args[..3]);
+    SYS_INOTIFY_RM_WATCH = 28        => sys_inotify_rm_watch(args[..2]);
     SYS_IOCTL = 29                   => sys_ioctl(args[..3]);
     SYS_IOPRIO_SET = 30              => sys_ioprio_set(args[..3]);
     SYS_IOPRIO_GET = 31              => sys_ioprio_get(args[..2]);
@@ -191,6 +216,7 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_LINKAT = 37                  => sys_linkat(args[..5]);
     SYS_UMOUNT = 39                  => sys_umount(args[..2]);
     SYS_MOUNT = 40                   => sys_mount(args[..5]);
+    SYS_PIVOT_ROOT = 41              => sys_pivot_root(args[..2]);
     SYS_STATFS = 43                  => sys_statfs(args[..2]);
     SYS_FSTATFS = 44                 => sys_fstatfs(args[..2]);
     SYS_TRUNCATE = 45                => sys_truncate(args[..2]);
@@ -249,6 +275,7 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_TIMER_DELETE = 111           => sys_timer_delete(args[..1]);
     SYS_CLOCK_GETTIME = 113          => sys_clock_gettime(args[..2]);
     SYS_CLOCK_NANOSLEEP = 115        => sys_clock_nanosleep(args[..4]);
+    SYS_PTRACE = 117                 => sys_ptrace(args[..4]);
     SYS_SCHED_SETPARAM = 118         => sys_sched_setparam(args[..2]);
     SYS_SCHED_SETSCHEDULER = 119     => sys_sched_setscheduler(args[..3]);
     SYS_SCHED_GETSCHEDULER = 120     => sys_sched_getscheduler(args[..1]);
@@ -302,10 +329,24 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_GETEGID = 177                => sys_getegid(args[..0]);
     SYS_GETTID = 178                 => sys_gettid(args[..0]);
     SYS_SYSINFO = 179                => sys_sysinfo(args[..1]);
+    SYS_MQ_OPEN = 180                => sys_mq_open(args[..4]);
+    SYS_MQ_UNLINK = 181              => sys_mq_unlink(args[..1]);
+    SYS_MQ_TIMEDSEND = 182           => sys_mq_timedsend(args[..5]);
+    SYS_MQ_TIMEDRECEIVE = 183        => sys_mq_timedreceive(args[..5]);
+    SYS_MQ_NOTIFY = 184              => sys_mq_notify(args[..2]);
+    SYS_MQ_GETSETATTR = 185          => sys_mq_getsetattr(args[..3]);
+    SYS_MSGGET = 186                 => sys_msgget(args[..2]);
+    SYS_MSGCTL = 187                 => sys_msgctl(args[..3]);
+    SYS_MSGRCV = 188                 => sys_msgrcv(args[..5]);
+    SYS_MSGSND = 189                 => sys_msgsnd(args[..4]);
     SYS_SEMGET = 190                 => sys_semget(args[..3]);
     SYS_SEMCTL = 191                 => sys_semctl(args[..4]);
     SYS_SEMTIMEDOP = 192             => sys_semtimedop(args[..4]);
     SYS_SEMOP = 193                  => sys_semop(args[..3]);
+    SYS_SHMGET = 194                 => sys_shmget(args[..3]);
+    SYS_SHMCTL = 195                 => sys_shmctl(args[..3]);
+    SYS_SHMAT = 196                  => sys_shmat(args[..3]);
+    SYS_SHMDT = 197                  => sys_shmdt(args[..1]);
     SYS_SOCKET = 198                 => sys_socket(args[..3]);
     SYS_SOCKETPAIR = 199             => sys_socketpair(args[..4]);
     SYS_BIND = 200                   => sys_bind(args[..3]);
@@ -332,9 +373,11 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_MSYNC = 227                  => sys_msync(args[..3]);
     SYS_MADVISE = 233                => sys_madvise(args[..3]);
     SYS_ACCEPT4 = 242                => sys_accept4(args[..4]);
+    SYS_RECVMMSG = 243               => sys_recvmmsg(args[..5]);
     SYS_WAIT4 = 260                  => sys_wait4(args[..4]);
     SYS_PRLIMIT64 = 261              => sys_prlimit64(args[..4]);
     SYS_SETNS = 268                  => sys_setns(args[..2]);
+    SYS_SENDMMSG = 269               => sys_sendmmsg(args[..4]);
     SYS_SCHED_SETATTR = 274          => sys_sched_setattr(args[..3]);
     SYS_SCHED_GETATTR = 275          => sys_sched_getattr(args[..4]);
     SYS_RENAMEAT2 = 276              => sys_renameat2(args[..5]);
@@ -344,9 +387,15 @@ impl_syscall_nums_and_dispatch_fn! {
     SYS_PREADV2 = 286                => sys_preadv2(args[..6]);
     SYS_PWRITEV2 = 287               => sys_pwritev2(args[..6]);
     SYS_STATX = 291                  => sys_statx(args[..5]);
+    SYS_OPEN_TREE = 428              => sys_open_tree(args[..3]);
+    SYS_MOVE_MOUNT = 429             => sys_move_mount(args[..5]);
+    SYS_FSOPEN = 430                 => sys_fsopen(args[..2]);
+    SYS_FSCONFIG = 431               => sys_fsconfig(args[..5]);
+    SYS_FSMOUNT = 432                => sys_fsmount(args[..3]);
     SYS_PIDFD_OPEN = 434             => sys_pidfd_open(args[..2]);
     SYS_CLONE3 = 435                 => sys_clone3(args[..2], &user_ctx);
     SYS_CLOSE_RANGE = 436            => sys_close_range(args[..3]);
     SYS_FACCESSAT2 = 439             => sys_faccessat2(args[..4]);
     SYS_EPOLL_PWAIT2 = 441           => sys_epoll_pwait2(args[..5]);
+    SYS_MOUNT_SETATTR = 442          => sys_mount_setattr(args[..5]);
 }
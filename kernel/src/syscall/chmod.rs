@@ -5,6 +5,7 @@ use crate::{
     fs::{
         file_table::{get_file_fast, FileDesc},
         fs_resolver::{FsPath, AT_FDCWD},
+        notify::FsEvents,
         utils::{InodeMode, PATH_MAX},
     },
     prelude::*,
@@ -16,6 +17,7 @@ pub fn sys_fchmod(fd: FileDesc, mode: u16, ctx: &Context) -> Result<SyscallRetur
     let mut file_table = ctx.thread_local.borrow_file_table_mut();
     let file = get_file_fast!(&mut file_table, fd);
     file.inode().set_mode(InodeMode::from_bits_truncate(mode))?;
+    file.notify_fs_events(FsEvents::ATTRIB);
     Ok(SyscallReturn::Return(0))
 }
 
@@ -50,5 +52,6 @@ pub fn sys_fchmodat(
     path_or_inode
         .inode()
         .set_mode(InodeMode::from_bits_truncate(mode))?;
+    path_or_inode.notify_fs_events(FsEvents::ATTRIB);
     Ok(SyscallReturn::Return(0))
 }
@@ -5,6 +5,7 @@ use crate::{
     fs::{
         file_table::{get_file_fast, FileDesc},
         fs_resolver::{FsPath, AT_FDCWD},
+        notify::FsEvents,
         utils::PATH_MAX,
     },
     prelude::*,
@@ -28,6 +29,7 @@ pub fn sys_fchown(fd: FileDesc, uid: i32, gid: i32, ctx: &Context) -> Result<Sys
     if let Some(gid) = gid {
         file.inode().set_group(gid)?;
     }
+    file.notify_fs_events(FsEvents::ATTRIB);
     Ok(SyscallReturn::Return(0))
 }
 
@@ -93,6 +95,7 @@ pub fn sys_fchownat(
     if let Some(gid) = gid {
         inode.set_group(gid)?;
     }
+    path_or_inode.notify_fs_events(FsEvents::ATTRIB);
     Ok(SyscallReturn::Return(0))
 }
 
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{
+    fsopen::FsContext, mount_setattr::MountAttr, open_tree::insert_path_file, SyscallReturn,
+};
+use crate::{
+    fs::{
+        file_table::{get_file_fast, FileDesc},
+        path::Path,
+    },
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+};
+
+pub fn sys_fsmount(
+    fs_fd: FileDesc,
+    flags: u32,
+    attr_flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = FsmountFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid fsmount flags"))?;
+    let attr_flags = MountAttr::from_bits(attr_flags as u64)
+        .filter(|attr_flags| !attr_flags.contains(MountAttr::MOUNT_ATTR_IDMAP))
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid mount attributes"))?;
+    debug!(
+        "fs_fd = {}, flags = {:?}, attr_flags = {:?}",
+        fs_fd, flags, attr_flags
+    );
+
+    let mount_flags = attr_flags.to_per_mount_flags()?;
+
+    ctx.thread_local
+        .borrow_ns_proxy()
+        .unwrap()
+        .mnt_ns()
+        .owner()
+        .check_cap(CapSet::SYS_ADMIN, ctx.posix_thread)?;
+
+    let fs = {
+        let mut file_table = ctx.thread_local.borrow_file_table_mut();
+        let file = get_file_fast!(&mut file_table, fs_fd);
+        let fs_context = file.downcast_ref::<FsContext>().ok_or_else(|| {
+            Error::with_message(Errno::EINVAL, "the file is not a filesystem context")
+        })?;
+        fs_context.fs()?
+    };
+
+    let path = Path::new_detached(fs, mount_flags);
+    let fd = insert_path_file(path, flags.contains(FsmountFlags::FSMOUNT_CLOEXEC), ctx)?;
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+bitflags! {
+    struct FsmountFlags: u32 {
+        const FSMOUNT_CLOEXEC = 1 << 0;
+    }
+}
@@ -0,0 +1,324 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! `fsopen()` creates a filesystem context (we name it as `FsContext`),
+//! and `fsconfig()` configures it.
+//!
+//! `FsContext` collects the parameters of a filesystem to be created.
+//! Once the filesystem is created by the `FSCONFIG_CMD_CREATE` command,
+//! it can be mounted via `fsmount()`.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/fsopen.c>
+
+use core::fmt::Display;
+
+use super::{mount::get_fs, SyscallReturn};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        file_table::{get_file_fast, FdFlags, FileDesc},
+        path::RESERVED_MOUNT_ID,
+        pseudofs::anon_inodefs_shared_inode,
+        registry,
+        utils::{CreationFlags, FileSystem, FsFlags, Inode},
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        signal::{PollHandle, Pollable},
+    },
+    syscall::constants::MAX_FILENAME_LEN,
+};
+
+pub fn sys_fsopen(fs_name_addr: Vaddr, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = FsopenFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid fsopen flags"))?;
+    let fs_name = ctx
+        .user_space()
+        .read_cstring(fs_name_addr, MAX_FILENAME_LEN)?;
+    debug!("fs_name = {:?}, flags = {:?}", fs_name, flags);
+
+    ctx.thread_local
+        .borrow_ns_proxy()
+        .unwrap()
+        .mnt_ns()
+        .owner()
+        .check_cap(CapSet::SYS_ADMIN, ctx.posix_thread)?;
+
+    let is_configured = fs_name.to_str().ok().and_then(registry::look_up).is_some();
+    if !is_configured {
+        return_errno_with_message!(
+            Errno::ENODEV,
+            "the filesystem is not configured in the kernel"
+        );
+    }
+
+    let fs_context = FsContext::new(fs_name);
+    let fd = {
+        let file_table = ctx.thread_local.borrow_file_table();
+        let mut file_table_locked = file_table.unwrap().write();
+        let fd_flags = if flags.contains(FsopenFlags::FSOPEN_CLOEXEC) {
+            FdFlags::CLOEXEC
+        } else {
+            FdFlags::empty()
+        };
+        file_table_locked.insert(Arc::new(fs_context), fd_flags)
+    };
+
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_fsconfig(
+    fd: FileDesc,
+    cmd: u32,
+    key_addr: Vaddr,
+    value_addr: Vaddr,
+    aux: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let cmd = FsconfigCmd::try_from(cmd)
+        .map_err(|_| Error::with_message(Errno::EINVAL, "invalid fsconfig command"))?;
+    debug!(
+        "fd = {}, cmd = {:?}, key_addr = 0x{:x}, value_addr = 0x{:x}, aux = {}",
+        fd, cmd, key_addr, value_addr, aux
+    );
+
+    let is_valid = match cmd {
+        FsconfigCmd::FSCONFIG_SET_FLAG => key_addr != 0 && value_addr == 0 && aux == 0,
+        FsconfigCmd::FSCONFIG_SET_STRING => key_addr != 0 && value_addr != 0 && aux == 0,
+        FsconfigCmd::FSCONFIG_CMD_CREATE
+        | FsconfigCmd::FSCONFIG_CMD_RECONFIGURE
+        | FsconfigCmd::FSCONFIG_CMD_CREATE_EXCL => key_addr == 0 && value_addr == 0 && aux == 0,
+        // These commands are rejected below.
+        FsconfigCmd::FSCONFIG_SET_BINARY
+        | FsconfigCmd::FSCONFIG_SET_PATH
+        | FsconfigCmd::FSCONFIG_SET_PATH_EMPTY
+        | FsconfigCmd::FSCONFIG_SET_FD => true,
+    };
+    if !is_valid {
+        return_errno_with_message!(Errno::EINVAL, "invalid fsconfig arguments");
+    }
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, fd).into_owned();
+    // Drop `file_table` as creating the filesystem may look up paths.
+    drop(file_table);
+
+    let fs_context = file.downcast_ref::<FsContext>().ok_or_else(|| {
+        Error::with_message(Errno::EINVAL, "the file is not a filesystem context")
+    })?;
+
+    let user_space = ctx.user_space();
+    match cmd {
+        FsconfigCmd::FSCONFIG_SET_FLAG => {
+            let key = user_space.read_cstring(key_addr, FSCONFIG_PARAM_MAX_LEN)?;
+            fs_context.set_flag(&key.to_string_lossy())?;
+        }
+        FsconfigCmd::FSCONFIG_SET_STRING => {
+            let key = user_space.read_cstring(key_addr, FSCONFIG_PARAM_MAX_LEN)?;
+            let value = user_space.read_cstring(value_addr, FSCONFIG_PARAM_MAX_LEN)?;
+            fs_context.set_string(&key.to_string_lossy(), value)?;
+        }
+        // The filesystems are never shared between the filesystem contexts, so
+        // `FSCONFIG_CMD_CREATE_EXCL` is the same as `FSCONFIG_CMD_CREATE`.
+        FsconfigCmd::FSCONFIG_CMD_CREATE | FsconfigCmd::FSCONFIG_CMD_CREATE_EXCL => {
+            fs_context.create(ctx)?;
+        }
+        // TODO: Support binary, path and file descriptor parameters, and reconfiguring
+        // existing filesystems.
+        FsconfigCmd::FSCONFIG_SET_BINARY
+        | FsconfigCmd::FSCONFIG_SET_PATH
+        | FsconfigCmd::FSCONFIG_SET_PATH_EMPTY
+        | FsconfigCmd::FSCONFIG_SET_FD
+        | FsconfigCmd::FSCONFIG_CMD_RECONFIGURE => {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the fsconfig command is not supported");
+        }
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// The maximum length of the keys and the string values, including the trailing null
+/// character.
+const FSCONFIG_PARAM_MAX_LEN: usize = 256;
+
+bitflags! {
+    struct FsopenFlags: u32 {
+        const FSOPEN_CLOEXEC = 1 << 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[repr(u32)]
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+enum FsconfigCmd {
+    FSCONFIG_SET_FLAG = 0,
+    FSCONFIG_SET_STRING = 1,
+    FSCONFIG_SET_BINARY = 2,
+    FSCONFIG_SET_PATH = 3,
+    FSCONFIG_SET_PATH_EMPTY = 4,
+    FSCONFIG_SET_FD = 5,
+    FSCONFIG_CMD_CREATE = 6,
+    FSCONFIG_CMD_RECONFIGURE = 7,
+    FSCONFIG_CMD_CREATE_EXCL = 8,
+}
+
+/// A filesystem context.
+pub(super) struct FsContext {
+    fs_type: CString,
+    inner: Mutex<FsContextInner>,
+}
+
+struct FsContextInner {
+    flags: FsFlags,
+    /// The source, which is the path of the device file for disk-backed filesystems.
+    source: Option<CString>,
+    /// The filesystem-specific options, separated by commas.
+    data: String,
+    /// The created filesystem.
+    fs: Option<Arc<dyn FileSystem>>,
+}
+
+impl FsContext {
+    fn new(fs_type: CString) -> Self {
+        Self {
+            fs_type,
+            inner: Mutex::new(FsContextInner {
+                flags: FsFlags::empty(),
+                source: None,
+                data: String::new(),
+                fs: None,
+            }),
+        }
+    }
+
+    /// Sets a flag, which is either a generic one or a filesystem-specific one.
+    fn set_flag(&self, key: &str) -> Result<()> {
+        let mut inner = self.lock_params()?;
+
+        match key {
+            "ro" => inner.flags |= FsFlags::RDONLY,
+            "rw" => inner.flags -= FsFlags::RDONLY,
+            "sync" => inner.flags |= FsFlags::SYNCHRONOUS,
+            "async" => inner.flags -= FsFlags::SYNCHRONOUS,
+            "dirsync" => inner.flags |= FsFlags::DIRSYNC,
+            "mand" => inner.flags |= FsFlags::MANDLOCK,
+            "nomand" => inner.flags -= FsFlags::MANDLOCK,
+            "lazytime" => inner.flags |= FsFlags::LAZYTIME,
+            "nolazytime" => inner.flags -= FsFlags::LAZYTIME,
+            "source" => {
+                return_errno_with_message!(Errno::EINVAL, "the source must be a string");
+            }
+            _ => inner.push_option(key),
+        }
+
+        Ok(())
+    }
+
+    /// Sets a parameter with a string value.
+    fn set_string(&self, key: &str, value: CString) -> Result<()> {
+        let mut inner = self.lock_params()?;
+
+        if key == "source" {
+            if inner.source.is_some() {
+                return_errno_with_message!(Errno::EINVAL, "the source is already set");
+            }
+            inner.source = Some(value);
+        } else {
+            inner.push_option(&format!("{}={}", key, value.to_string_lossy()));
+        }
+
+        Ok(())
+    }
+
+    /// Creates the filesystem with the parameters.
+    fn create(&self, ctx: &Context) -> Result<()> {
+        let mut inner = self.lock_params()?;
+
+        let data = if inner.data.is_empty() {
+            None
+        } else {
+            // The options are read from C strings, so they cannot contain null characters.
+            Some(CString::new(inner.data.as_str()).unwrap())
+        };
+        let fs = get_fs(
+            &self.fs_type,
+            inner.flags,
+            data,
+            inner.source.as_deref(),
+            ctx,
+        )?;
+        inner.fs = Some(fs);
+
+        Ok(())
+    }
+
+    /// Returns the created filesystem.
+    ///
+    /// This method fails with `EBUSY` if the filesystem has not been created.
+    pub(super) fn fs(&self) -> Result<Arc<dyn FileSystem>> {
+        self.inner
+            .lock()
+            .fs
+            .clone()
+            .ok_or_else(|| Error::with_message(Errno::EBUSY, "the filesystem has not been created"))
+    }
+
+    /// Locks the parameters, which cannot be changed once the filesystem is created.
+    fn lock_params(&self) -> Result<MutexGuard<'_, FsContextInner>> {
+        let inner = self.inner.lock();
+        if inner.fs.is_some() {
+            return_errno_with_message!(Errno::EBUSY, "the filesystem has been created");
+        }
+        Ok(inner)
+    }
+}
+
+impl FsContextInner {
+    fn push_option(&mut self, option: &str) {
+        if !self.data.is_empty() {
+            self.data.push(',');
+        }
+        self.data.push_str(option);
+    }
+}
+
+impl Pollable for FsContext {
+    fn poll(&self, mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
+        (IoEvents::IN | IoEvents::OUT) & mask
+    }
+}
+
+impl FileLike for FsContext {
+    fn inode(&self) -> &Arc<dyn Inode> {
+        anon_inodefs_shared_inode()
+    }
+
+    fn dump_proc_fdinfo(self: Arc<Self>, fd_flags: FdFlags) -> Box<dyn Display> {
+        struct FdInfo {
+            flags: u32,
+            ino: u64,
+        }
+
+        impl Display for FdInfo {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                writeln!(f, "pos:\t{}", 0)?;
+                writeln!(f, "flags:\t0{:o}", self.flags)?;
+                // TODO: This should be the mount ID of the pseudo filesystem.
+                writeln!(f, "mnt_id:\t{}", RESERVED_MOUNT_ID)?;
+                writeln!(f, "ino:\t{}", self.ino)
+            }
+        }
+
+        let mut flags = self.status_flags().bits() | self.access_mode() as u32;
+        if fd_flags.contains(FdFlags::CLOEXEC) {
+            flags |= CreationFlags::O_CLOEXEC.bits();
+        }
+
+        Box::new(FdInfo {
+            flags,
+            ino: self.inode().ino(),
+        })
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{get_file_fast, FdFlags, FileDesc},
+        fs_resolver::{FsPath, AT_FDCWD},
+        notify::{inotify::InotifyWatchFlags, FsEvents, InotifyFile},
+        utils::{CreationFlags, InodeType, Permission, StatusFlags, PATH_MAX},
+    },
+    prelude::*,
+};
+
+pub fn sys_inotify_init(ctx: &Context) -> Result<SyscallReturn> {
+    sys_inotify_init1(0, ctx)
+}
+
+pub fn sys_inotify_init1(flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = InotifyInitFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid flags"))?;
+    debug!("flags = {:?}", flags);
+
+    let inotify_file = InotifyFile::new(flags.contains(InotifyInitFlags::IN_NONBLOCK));
+    let fd_flags = if flags.contains(InotifyInitFlags::IN_CLOEXEC) {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+
+    let file_table = ctx.thread_local.borrow_file_table();
+    let fd = file_table.unwrap().write().insert(inotify_file, fd_flags);
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_inotify_add_watch(
+    fd: FileDesc,
+    path_ptr: Vaddr,
+    mask: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let path_name = ctx.user_space().read_cstring(path_ptr, PATH_MAX)?;
+    debug!(
+        "fd = {}, path_name = {:?}, mask = 0x{:x}",
+        fd, path_name, mask
+    );
+
+    let flags = InotifyWatchFlags::from_bits_truncate(mask);
+    let events = FsEvents::from_bits_truncate(mask) & FsEvents::ALL_EVENTS;
+    if events.is_empty() {
+        return_errno_with_message!(Errno::EINVAL, "no events are specified");
+    }
+    if flags.contains(InotifyWatchFlags::IN_MASK_ADD | InotifyWatchFlags::IN_MASK_CREATE) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "IN_MASK_ADD and IN_MASK_CREATE cannot be specified together"
+        );
+    }
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    let Some(inotify_file) = file.downcast_ref::<InotifyFile>() else {
+        return_errno_with_message!(Errno::EINVAL, "the file is not an inotify instance");
+    };
+
+    let path = {
+        let path_name = path_name.to_string_lossy();
+        let fs_path = FsPath::from_fd_and_path(AT_FDCWD, &path_name)?;
+        let fs_ref = ctx.thread_local.borrow_fs();
+        let fs = fs_ref.resolver().read();
+        if flags.contains(InotifyWatchFlags::IN_DONT_FOLLOW) {
+            fs.lookup_no_follow(&fs_path)?
+        } else {
+            fs.lookup(&fs_path)?
+        }
+    };
+
+    if flags.contains(InotifyWatchFlags::IN_ONLYDIR) && path.type_() != InodeType::Dir {
+        return_errno_with_message!(Errno::ENOTDIR, "the path is not a directory");
+    }
+    path.inode().check_permission(Permission::MAY_READ)?;
+
+    let wd = inotify_file.add_watch(path.inode(), events, flags)?;
+    Ok(SyscallReturn::Return(wd as _))
+}
+
+pub fn sys_inotify_rm_watch(fd: FileDesc, wd: i32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("fd = {}, wd = {}", fd, wd);
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, fd);
+    let Some(inotify_file) = file.downcast_ref::<InotifyFile>() else {
+        return_errno_with_message!(Errno::EINVAL, "the file is not an inotify instance");
+    };
+
+    if wd < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the watch descriptor is negative");
+    }
+    inotify_file.remove_watch(wd as u32)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+bitflags! {
+    struct InotifyInitFlags: u32 {
+        const IN_NONBLOCK = StatusFlags::O_NONBLOCK.bits();
+        const IN_CLOEXEC = CreationFlags::O_CLOEXEC.bits();
+    }
+}
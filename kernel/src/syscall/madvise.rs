@@ -48,6 +48,8 @@ pub fn sys_madvise(
         MadviseBehavior::MADV_NOHUGEPAGE => {
             warn!("MADV_NOHUGEPAGE isn't implemented, do nothing for now");
         }
+        MadviseBehavior::MADV_DONTDUMP => ctx.user_space().vmar().set_dontdump(true, start..end)?,
+        MadviseBehavior::MADV_DODUMP => ctx.user_space().vmar().set_dontdump(false, start..end)?,
         _ => todo!(),
     }
     Ok(SyscallReturn::Return(0))
@@ -46,6 +46,8 @@ mod fallocate;
 mod fcntl;
 mod flock;
 mod fork;
+mod fsmount;
+mod fsopen;
 mod fsync;
 mod futex;
 mod get_ioprio;
@@ -73,6 +75,7 @@ mod gettid;
 mod gettimeofday;
 mod getuid;
 mod getxattr;
+mod inotify;
 mod ioctl;
 mod kill;
 mod link;
@@ -85,15 +88,24 @@ mod mkdir;
 mod mknod;
 mod mmap;
 mod mount;
+mod mount_setattr;
+mod move_mount;
 mod mprotect;
+mod mqueue;
 mod mremap;
+mod msgctl;
+mod msgget;
+mod msgrcv;
+mod msgsnd;
 mod msync;
 mod munmap;
 mod nanosleep;
 mod open;
+mod open_tree;
 mod pause;
 mod pidfd_open;
 mod pipe;
+mod pivot_root;
 mod poll;
 mod ppoll;
 mod prctl;
@@ -101,11 +113,13 @@ mod pread64;
 mod preadv;
 mod prlimit64;
 mod pselect6;
+mod ptrace;
 mod pwrite64;
 mod pwritev;
 mod read;
 mod readlink;
 mod recvfrom;
+mod recvmmsg;
 mod recvmsg;
 mod removexattr;
 mod rename;
@@ -130,6 +144,7 @@ mod semctl;
 mod semget;
 mod semop;
 mod sendfile;
+mod sendmmsg;
 mod sendmsg;
 mod sendto;
 mod set_ioprio;
@@ -153,6 +168,10 @@ mod setsid;
 mod setsockopt;
 mod setuid;
 mod setxattr;
+mod shmat;
+mod shmctl;
+mod shmdt;
+mod shmget;
 mod shutdown;
 mod sigaltstack;
 mod signalfd;
@@ -359,7 +378,19 @@ impl SyscallArgument {
 }
 
 pub fn handle_syscall(ctx: &Context, user_ctx: &mut UserContext) {
-    let syscall_frame = SyscallArgument::new_from_context(user_ctx);
+    let tracee = ctx.posix_thread.tracee();
+
+    let mut syscall_frame = SyscallArgument::new_from_context(user_ctx);
+    if tracee.is_traced() {
+        // The tracer may change the system call at the syscall-entry stop.
+        let Some(syscall_number) = tracee.report_syscall_entry(ctx, user_ctx) else {
+            tracee.report_syscall_exit(ctx, user_ctx, usize::MAX);
+            return;
+        };
+        syscall_frame = SyscallArgument::new_from_context(user_ctx);
+        syscall_frame.syscall_number = syscall_number as u64;
+    }
+
     let syscall_return = arch::syscall_dispatch(
         syscall_frame.syscall_number,
         syscall_frame.args,
@@ -379,6 +410,10 @@ pub fn handle_syscall(ctx: &Context, user_ctx: &mut UserContext) {
             user_ctx.set_syscall_ret((-errno) as usize)
         }
     }
+
+    if tracee.is_traced() {
+        tracee.report_syscall_exit(ctx, user_ctx, syscall_frame.syscall_number as usize);
+    }
 }
 
 #[macro_export]
@@ -151,13 +151,19 @@ fn do_change_type(target_path: Path, flags: MountFlags, ctx: &Context) -> Result
         );
     }
 
-    if flags.contains(MountFlags::MS_PRIVATE) {
-        let recursive = flags.contains(MountFlags::MS_REC);
-        target_path.set_mount_propagation(MountPropType::Private, recursive, ctx)?;
-        Ok(())
+    let prop = if flags.contains(MountFlags::MS_SHARED) {
+        MountPropType::Shared
+    } else if flags.contains(MountFlags::MS_PRIVATE) {
+        MountPropType::Private
+    } else if flags.contains(MountFlags::MS_SLAVE) {
+        MountPropType::Slave
     } else {
-        return_errno_with_message!(Errno::EINVAL, "the mount propagation type is unsupported");
-    }
+        MountPropType::Unbindable
+    };
+
+    let recursive = flags.contains(MountFlags::MS_REC);
+    target_path.set_mount_propagation(prop, recursive, ctx)?;
+    Ok(())
 }
 
 /// Moves a mount from src location to dst location.
@@ -193,32 +199,38 @@ fn do_new_mount(
         return_errno_with_message!(Errno::ENOTDIR, "mountpoint must be directory");
     };
 
-    let fs_type = ctx
-        .user_space()
-        .read_cstring(fs_type_addr, MAX_FILENAME_LEN)?;
+    let user_space = ctx.user_space();
+    let fs_type = user_space.read_cstring(fs_type_addr, MAX_FILENAME_LEN)?;
     if fs_type.is_empty() {
         return_errno_with_message!(Errno::EINVAL, "fs_type is empty");
     }
-    let fs = get_fs(src_name_addr, flags, fs_type, data_addr, ctx)?;
+    let source = if src_name_addr == 0 {
+        None
+    } else {
+        Some(user_space.read_cstring(src_name_addr, MAX_FILENAME_LEN)?)
+    };
+    let data = if data_addr == 0 {
+        None
+    } else {
+        Some(user_space.read_cstring(data_addr, MAX_FILENAME_LEN)?)
+    };
+
+    let fs = get_fs(&fs_type, flags.into(), data, source.as_deref(), ctx)?;
     target_path.mount(fs, flags.into(), ctx)?;
     Ok(())
 }
 
 /// Gets the filesystem by fs_type and devname.
-fn get_fs(
-    src_name_addr: Vaddr,
-    flags: MountFlags,
-    fs_type: CString,
-    data_addr: Vaddr,
+///
+/// The `source` is the path of the device file, which is only used if the filesystem
+/// needs to be backed by a disk.
+pub(super) fn get_fs(
+    fs_type: &CStr,
+    flags: FsFlags,
+    data: Option<CString>,
+    source: Option<&CStr>,
     ctx: &Context,
 ) -> Result<Arc<dyn FileSystem>> {
-    let user_space = ctx.user_space();
-    let data = if data_addr == 0 {
-        None
-    } else {
-        Some(user_space.read_cstring(data_addr, MAX_FILENAME_LEN)?)
-    };
-
     let fs_type = fs_type
         .to_str()
         .map_err(|_| Error::with_message(Errno::ENODEV, "invalid file system type"))?;
@@ -228,7 +240,9 @@ fn get_fs(
     ))?;
 
     let disk = if fs_type.properties().contains(FsProperties::NEED_DISK) {
-        let devname = user_space.read_cstring(src_name_addr, MAX_FILENAME_LEN)?;
+        let Some(devname) = source else {
+            return_errno_with_message!(Errno::EINVAL, "the source is not specified");
+        };
         let path = devname.to_string_lossy();
         let fs_path = FsPath::from_fd_and_path(AT_FDCWD, path.as_ref())?;
         let path = ctx
@@ -252,7 +266,7 @@ fn get_fs(
         None
     };
 
-    fs_type.create(flags.into(), data, disk)
+    fs_type.create(flags, data, disk)
 }
 
 bitflags! {
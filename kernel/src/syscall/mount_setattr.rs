@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::FileDesc,
+        fs_resolver::FsPath,
+        path::{MountPropType, PerMountFlags},
+    },
+    prelude::*,
+    syscall::constants::MAX_FILENAME_LEN,
+    util::CopyCompat,
+};
+
+pub fn sys_mount_setattr(
+    dirfd: FileDesc,
+    path_addr: Vaddr,
+    flags: u32,
+    attr_addr: Vaddr,
+    size: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let user_space = ctx.user_space();
+    let path_name = user_space.read_cstring(path_addr, MAX_FILENAME_LEN)?;
+    let flags = MountSetattrFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid mount_setattr flags"))?;
+    debug!(
+        "dirfd = {}, path_name = {:?}, flags = {:?}, attr_addr = 0x{:x}, size = {}",
+        dirfd, path_name, flags, attr_addr, size
+    );
+
+    let attr = read_mount_attr(attr_addr, size, ctx)?;
+    debug!("attr = {:?}", attr);
+
+    let attr_set = MountAttr::from_bits(attr.attr_set)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid mount attributes to set"))?;
+    let attr_clr = MountAttr::from_bits(attr.attr_clr)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid mount attributes to clear"))?;
+    if attr_set.intersects(attr_clr) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the mount attributes cannot be both set and cleared"
+        );
+    }
+
+    let (set, mut clear) = (
+        attr_set.flags_without_atime()?,
+        attr_clr.flags_without_atime()?,
+    );
+    let set = if attr_clr.intersects(MountAttr::MOUNT_ATTR__ATIME) {
+        if !attr_clr.contains(MountAttr::MOUNT_ATTR__ATIME) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the atime attributes must be cleared together"
+            );
+        }
+        clear |= ATIME_FLAGS;
+        set | attr_set.atime_flags()?
+    } else if attr_set.intersects(MountAttr::MOUNT_ATTR__ATIME) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the atime attributes must be cleared before being set"
+        );
+    } else {
+        set
+    };
+
+    let prop = match attr.propagation {
+        0 => None,
+        MS_SHARED => Some(MountPropType::Shared),
+        MS_SLAVE => Some(MountPropType::Slave),
+        MS_PRIVATE => Some(MountPropType::Private),
+        MS_UNBINDABLE => Some(MountPropType::Unbindable),
+        _ => return_errno_with_message!(Errno::EINVAL, "invalid propagation type"),
+    };
+
+    // Nothing needs to be changed.
+    if set.is_empty() && clear.is_empty() && prop.is_none() {
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let path = {
+        let path_name = path_name.to_string_lossy();
+        let fs_path = if flags.contains(MountSetattrFlags::AT_EMPTY_PATH) && path_name.is_empty() {
+            FsPath::from_fd(dirfd)?
+        } else {
+            FsPath::from_fd_and_path(dirfd, &path_name)?
+        };
+
+        let fs_ref = ctx.thread_local.borrow_fs();
+        let fs = fs_ref.resolver().read();
+        if flags.contains(MountSetattrFlags::AT_SYMLINK_NOFOLLOW) {
+            fs.lookup_no_follow(&fs_path)?
+        } else {
+            fs.lookup(&fs_path)?
+        }
+    };
+
+    let recursive = flags.contains(MountSetattrFlags::AT_RECURSIVE);
+    if !set.is_empty() || !clear.is_empty() {
+        path.change_mount_flags(set, clear, recursive, ctx)?;
+    }
+    if let Some(prop) = prop {
+        path.set_mount_propagation(prop, recursive, ctx)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Reads `struct mount_attr` from the user space.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/namespace.c>
+fn read_mount_attr(attr_addr: Vaddr, size: usize, ctx: &Context) -> Result<CMountAttr> {
+    if size > PAGE_SIZE {
+        return_errno_with_message!(Errno::E2BIG, "the mount attribute size is too large");
+    }
+    if size < size_of::<CMountAttr>() {
+        return_errno_with_message!(Errno::EINVAL, "the mount attribute size is too small");
+    }
+
+    ctx.user_space()
+        .read_val_compat::<CMountAttr>(attr_addr, size)
+}
+
+/// The atime flags in `PerMountFlags`, which are mutually exclusive.
+const ATIME_FLAGS: PerMountFlags = PerMountFlags::RELATIME
+    .union(PerMountFlags::NOATIME)
+    .union(PerMountFlags::STRICTATIME);
+
+// The propagation types, which are the same as the flags of `mount`.
+const MS_UNBINDABLE: u64 = 1 << 17;
+const MS_PRIVATE: u64 = 1 << 18;
+const MS_SLAVE: u64 = 1 << 19;
+const MS_SHARED: u64 = 1 << 20;
+
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct CMountAttr {
+    attr_set: u64,
+    attr_clr: u64,
+    propagation: u64,
+    /// The user namespace of ID-mapped mounts, which are not supported yet.
+    #[expect(dead_code)]
+    userns_fd: u64,
+}
+
+bitflags! {
+    struct MountSetattrFlags: u32 {
+        const AT_SYMLINK_NOFOLLOW = 1 << 8;
+        const AT_NO_AUTOMOUNT = 1 << 11;
+        const AT_EMPTY_PATH = 1 << 12;
+        const AT_RECURSIVE = 1 << 15;
+    }
+}
+
+bitflags! {
+    /// The mount attributes used by `mount_setattr` and `fsmount`.
+    pub(super) struct MountAttr: u64 {
+        const MOUNT_ATTR_RDONLY = 1 << 0;
+        const MOUNT_ATTR_NOSUID = 1 << 1;
+        const MOUNT_ATTR_NODEV = 1 << 2;
+        const MOUNT_ATTR_NOEXEC = 1 << 3;
+        /// The mask of the atime attributes.
+        ///
+        /// If none of the atime attributes are specified, the atime policy is `relatime`.
+        const MOUNT_ATTR__ATIME = 7 << 4;
+        const MOUNT_ATTR_NOATIME = 1 << 4;
+        const MOUNT_ATTR_STRICTATIME = 1 << 5;
+        const MOUNT_ATTR_NODIRATIME = 1 << 7;
+        const MOUNT_ATTR_IDMAP = 1 << 20;
+        const MOUNT_ATTR_NOSYMFOLLOW = 1 << 21;
+    }
+}
+
+impl MountAttr {
+    /// Converts the attributes to `PerMountFlags`, including the atime policy.
+    pub(super) fn to_per_mount_flags(self) -> Result<PerMountFlags> {
+        Ok(self.flags_without_atime()? | self.atime_flags()?)
+    }
+
+    /// Converts the attributes except the atime ones to `PerMountFlags`.
+    fn flags_without_atime(self) -> Result<PerMountFlags> {
+        // TODO: Support ID-mapped mounts and the `nosymfollow` mount flag.
+        if self.intersects(MountAttr::MOUNT_ATTR_IDMAP | MountAttr::MOUNT_ATTR_NOSYMFOLLOW) {
+            return_errno_with_message!(Errno::EINVAL, "the mount attributes are not supported");
+        }
+
+        let mut flags = PerMountFlags::empty();
+        for (attr, flag) in [
+            (MountAttr::MOUNT_ATTR_RDONLY, PerMountFlags::RDONLY),
+            (MountAttr::MOUNT_ATTR_NOSUID, PerMountFlags::NOSUID),
+            (MountAttr::MOUNT_ATTR_NODEV, PerMountFlags::NODEV),
+            (MountAttr::MOUNT_ATTR_NOEXEC, PerMountFlags::NOEXEC),
+            (MountAttr::MOUNT_ATTR_NODIRATIME, PerMountFlags::NODIRATIME),
+        ] {
+            if self.contains(attr) {
+                flags |= flag;
+            }
+        }
+
+        Ok(flags)
+    }
+
+    /// Converts the atime attributes to `PerMountFlags`.
+    fn atime_flags(self) -> Result<PerMountFlags> {
+        let atime = self & MountAttr::MOUNT_ATTR__ATIME;
+        if atime.is_empty() {
+            Ok(PerMountFlags::RELATIME)
+        } else if atime == MountAttr::MOUNT_ATTR_NOATIME {
+            Ok(PerMountFlags::NOATIME)
+        } else if atime == MountAttr::MOUNT_ATTR_STRICTATIME {
+            Ok(PerMountFlags::STRICTATIME)
+        } else {
+            return_errno_with_message!(Errno::EINVAL, "invalid atime attributes");
+        }
+    }
+}
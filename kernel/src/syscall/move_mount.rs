@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{file_table::FileDesc, fs_resolver::FsPath, path::Path},
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    syscall::constants::MAX_FILENAME_LEN,
+};
+
+pub fn sys_move_mount(
+    from_dirfd: FileDesc,
+    from_path_addr: Vaddr,
+    to_dirfd: FileDesc,
+    to_path_addr: Vaddr,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let user_space = ctx.user_space();
+    let from_path_name = user_space.read_cstring(from_path_addr, MAX_FILENAME_LEN)?;
+    let to_path_name = user_space.read_cstring(to_path_addr, MAX_FILENAME_LEN)?;
+    let flags = MoveMountFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid move_mount flags"))?;
+    debug!(
+        "from_dirfd = {}, from_path_name = {:?}, to_dirfd = {}, to_path_name = {:?}, flags = {:?}",
+        from_dirfd, from_path_name, to_dirfd, to_path_name, flags
+    );
+
+    ctx.thread_local
+        .borrow_ns_proxy()
+        .unwrap()
+        .mnt_ns()
+        .owner()
+        .check_cap(CapSet::SYS_ADMIN, ctx.posix_thread)?;
+
+    let from_path = lookup_path(
+        from_dirfd,
+        &from_path_name.to_string_lossy(),
+        flags.contains(MoveMountFlags::MOVE_MOUNT_F_EMPTY_PATH),
+        flags.contains(MoveMountFlags::MOVE_MOUNT_F_SYMLINKS),
+        ctx,
+    )?;
+    let to_path = lookup_path(
+        to_dirfd,
+        &to_path_name.to_string_lossy(),
+        flags.contains(MoveMountFlags::MOVE_MOUNT_T_EMPTY_PATH),
+        flags.contains(MoveMountFlags::MOVE_MOUNT_T_SYMLINKS),
+        ctx,
+    )?;
+
+    from_path.move_mount_to(&to_path, ctx)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn lookup_path(
+    dirfd: FileDesc,
+    path_name: &str,
+    allows_empty_path: bool,
+    follows_symlinks: bool,
+    ctx: &Context,
+) -> Result<Path> {
+    let fs_path = if allows_empty_path && path_name.is_empty() {
+        FsPath::from_fd(dirfd)?
+    } else {
+        FsPath::from_fd_and_path(dirfd, path_name)?
+    };
+
+    let fs_ref = ctx.thread_local.borrow_fs();
+    let fs = fs_ref.resolver().read();
+    if follows_symlinks {
+        fs.lookup(&fs_path)
+    } else {
+        fs.lookup_no_follow(&fs_path)
+    }
+}
+
+bitflags! {
+    struct MoveMountFlags: u32 {
+        const MOVE_MOUNT_F_SYMLINKS = 1 << 0;
+        const MOVE_MOUNT_F_AUTOMOUNTS = 1 << 1;
+        const MOVE_MOUNT_F_EMPTY_PATH = 1 << 2;
+        const MOVE_MOUNT_T_SYMLINKS = 1 << 4;
+        const MOVE_MOUNT_T_AUTOMOUNTS = 1 << 5;
+        const MOVE_MOUNT_T_EMPTY_PATH = 1 << 6;
+    }
+}
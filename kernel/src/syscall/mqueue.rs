@@ -0,0 +1,329 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_handle::FileLike,
+        file_table::{get_file_fast, FdFlags, FileDesc},
+        inode_handle::InodeHandle,
+        mqueue::{
+            self, MqAttr, MqueueFs, Notification, NotifyKind, QueueInode, MQ_PRIO_MAX,
+            NOTIFY_COOKIE_LEN,
+        },
+        utils::{AccessMode, CreationFlags, InodeMode, Permission, StatusFlags, PATH_MAX},
+    },
+    net::socket::netlink::raw_receiver_of,
+    prelude::*,
+    process::signal::{
+        c_types::{sigevent_t, SigNotify},
+        sig_num::SigNum,
+    },
+    time::{clocks::RealTimeClock, timespec_t, Clock},
+};
+
+pub fn sys_mq_open(
+    name_addr: Vaddr,
+    oflag: u32,
+    mode: u16,
+    attr_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let name = ctx.user_space().read_cstring(name_addr, PATH_MAX)?;
+    let creation_flags = CreationFlags::from_bits_truncate(oflag);
+    let status_flags = StatusFlags::from_bits_truncate(oflag) & StatusFlags::O_NONBLOCK;
+    let access_mode = AccessMode::from_u32(oflag)?;
+    debug!(
+        "name = {:?}, creation_flags = {:?}, status_flags = {:?}, access_mode = {:?}, mode = {:o}, attr_addr = 0x{:x}",
+        name, creation_flags, status_flags, access_mode, mode, attr_addr
+    );
+
+    let name = name.to_string_lossy();
+    check_queue_name(&name)?;
+
+    let root_path = mqueue::root_path();
+    let (path, is_created) = match root_path.lookup(&name) {
+        Ok(_) if creation_flags.contains(CreationFlags::O_CREAT | CreationFlags::O_EXCL) => {
+            return_errno_with_message!(Errno::EEXIST, "the message queue already exists");
+        }
+        Ok(path) => (path, false),
+        Err(err)
+            if err.error() == Errno::ENOENT && creation_flags.contains(CreationFlags::O_CREAT) =>
+        {
+            let attr = if attr_addr != 0 {
+                Some(ctx.user_space().read_val::<MqAttr>(attr_addr)?)
+            } else {
+                None
+            };
+            let mode = {
+                let fs_ref = ctx.thread_local.borrow_fs();
+                InodeMode::from_bits_truncate(mode & 0o777 & !fs_ref.umask().get())
+            };
+
+            root_path.inode().check_permission(Permission::MAY_WRITE)?;
+            match MqueueFs::singleton().create_queue(&name, mode, attr.as_ref()) {
+                Ok(_) => (root_path.lookup(&name)?, true),
+                // Another process has created the queue just now.
+                Err(err)
+                    if err.error() == Errno::EEXIST
+                        && !creation_flags.contains(CreationFlags::O_EXCL) =>
+                {
+                    (root_path.lookup(&name)?, false)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(err) => return Err(err),
+    };
+
+    // The permission of a newly created queue is not checked, just like `open` with `O_CREAT`.
+    let inode_handle = if is_created {
+        InodeHandle::new_unchecked_access(path, access_mode, status_flags)?
+    } else {
+        InodeHandle::new(path, access_mode, status_flags)?
+    };
+
+    // Message queue descriptors are always closed on `execve`.
+    let file_table = ctx.thread_local.borrow_file_table();
+    let fd = file_table
+        .unwrap()
+        .write()
+        .insert(Arc::new(inode_handle), FdFlags::CLOEXEC);
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_mq_unlink(name_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let name = ctx.user_space().read_cstring(name_addr, PATH_MAX)?;
+    debug!("name = {:?}", name);
+
+    let name = name.to_string_lossy();
+    check_queue_name(&name)?;
+
+    let root_path = mqueue::root_path();
+    root_path.inode().check_permission(Permission::MAY_WRITE)?;
+    root_path.unlink(&name)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mq_timedsend(
+    mqdes: FileDesc,
+    msg_ptr: Vaddr,
+    msg_len: usize,
+    msg_prio: u32,
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, msg_ptr = 0x{:x}, msg_len = {}, msg_prio = {}, abs_timeout_addr = 0x{:x}",
+        mqdes, msg_ptr, msg_len, msg_prio, abs_timeout_addr
+    );
+
+    if msg_prio >= MQ_PRIO_MAX {
+        return_errno_with_message!(Errno::EINVAL, "the message priority is too large");
+    }
+    let timeout = read_abs_timeout(abs_timeout_addr, ctx)?;
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, mqdes);
+    let queue = queue_inode_of(&file)?.queue();
+    if !file.access_mode().is_writable() {
+        return_errno_with_message!(Errno::EBADF, "the queue is not opened for writing");
+    }
+
+    let attr = queue.attr();
+    if msg_len > attr.mq_msgsize as usize {
+        return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+    }
+    let mut message = vec![0u8; msg_len].into_boxed_slice();
+    ctx.user_space()
+        .read_bytes(msg_ptr, &mut VmWriter::from(&mut message[..]))?;
+
+    let is_nonblocking = file.status_flags().contains(StatusFlags::O_NONBLOCK);
+    queue
+        .send(message, msg_prio, is_nonblocking, timeout.as_ref())
+        .map_err(map_timeout_error)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mq_timedreceive(
+    mqdes: FileDesc,
+    msg_ptr: Vaddr,
+    msg_len: usize,
+    msg_prio_addr: Vaddr,
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, msg_ptr = 0x{:x}, msg_len = {}, msg_prio_addr = 0x{:x}, abs_timeout_addr = 0x{:x}",
+        mqdes, msg_ptr, msg_len, msg_prio_addr, abs_timeout_addr
+    );
+
+    let timeout = read_abs_timeout(abs_timeout_addr, ctx)?;
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, mqdes);
+    let queue = queue_inode_of(&file)?.queue();
+    if !file.access_mode().is_readable() {
+        return_errno_with_message!(Errno::EBADF, "the queue is not opened for reading");
+    }
+
+    let is_nonblocking = file.status_flags().contains(StatusFlags::O_NONBLOCK);
+    let (message, priority) = queue
+        .receive(msg_len, is_nonblocking, timeout.as_ref())
+        .map_err(map_timeout_error)?;
+
+    // FIXME: Linux puts the message back to the queue if the copy fails, while the message is
+    // lost here.
+    let user_space = ctx.user_space();
+    user_space.write_bytes(msg_ptr, &mut VmReader::from(&message[..]))?;
+    if msg_prio_addr != 0 {
+        user_space.write_val(msg_prio_addr, &priority)?;
+    }
+
+    Ok(SyscallReturn::Return(message.len() as _))
+}
+
+pub fn sys_mq_notify(mqdes: FileDesc, sevp_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("mqdes = {}, sevp_addr = 0x{:x}", mqdes, sevp_addr);
+
+    let sig_event = if sevp_addr != 0 {
+        Some(ctx.user_space().read_val::<sigevent_t>(sevp_addr)?)
+    } else {
+        None
+    };
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let queue = {
+        let file = get_file_fast!(&mut file_table, mqdes);
+        queue_inode_of(&file)?.queue().clone()
+    };
+
+    let Some(sig_event) = sig_event else {
+        queue.unregister_notification(ctx.process.pid());
+        return Ok(SyscallReturn::Return(0));
+    };
+
+    let kind = match SigNotify::try_from(sig_event.sigev_notify)? {
+        SigNotify::SIGEV_NONE => NotifyKind::None,
+        // Linux accepts the signal number zero, but no signal will be sent.
+        SigNotify::SIGEV_SIGNAL if sig_event.sigev_signo == 0 => NotifyKind::None,
+        SigNotify::SIGEV_SIGNAL => {
+            let Ok(signo) = u8::try_from(sig_event.sigev_signo) else {
+                return_errno_with_message!(Errno::EINVAL, "the signal number is invalid");
+            };
+            NotifyKind::Signal {
+                num: SigNum::try_from(signo)?,
+                value: sig_event.sigev_value,
+            }
+        }
+        // In Linux, `SIGEV_THREAD` is implemented by C libraries, which ask the kernel to send
+        // a cookie to a netlink socket and then start a thread to receive it.
+        SigNotify::SIGEV_THREAD => {
+            let cookie = ctx
+                .user_space()
+                .read_val::<[u8; NOTIFY_COOKIE_LEN]>(sig_event.sigev_value.read_ptr())?;
+
+            let socket_fd = sig_event.sigev_signo;
+            let socket = get_file_fast!(&mut file_table, socket_fd).into_owned();
+            let receiver = raw_receiver_of(socket.as_ref())?;
+
+            NotifyKind::Thread {
+                socket_fd,
+                receiver,
+                cookie,
+            }
+        }
+        SigNotify::SIGEV_THREAD_ID => {
+            return_errno_with_message!(Errno::EINVAL, "the notification method is invalid");
+        }
+    };
+    queue.register_notification(Notification::new(&ctx.process, kind))?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mq_getsetattr(
+    mqdes: FileDesc,
+    new_attr_addr: Vaddr,
+    old_attr_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, new_attr_addr = 0x{:x}, old_attr_addr = 0x{:x}",
+        mqdes, new_attr_addr, old_attr_addr
+    );
+
+    let new_attr = if new_attr_addr != 0 {
+        let new_attr = ctx.user_space().read_val::<MqAttr>(new_attr_addr)?;
+        if new_attr.mq_flags & !(StatusFlags::O_NONBLOCK.bits() as i64) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the queue flags are invalid");
+        }
+        Some(new_attr)
+    } else {
+        None
+    };
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, mqdes);
+    let queue = queue_inode_of(&file)?.queue();
+
+    let old_status_flags = file.status_flags();
+    if old_attr_addr != 0 {
+        let old_attr = MqAttr {
+            mq_flags: (old_status_flags & StatusFlags::O_NONBLOCK).bits() as i64,
+            ..queue.attr()
+        };
+        ctx.user_space().write_val(old_attr_addr, &old_attr)?;
+    }
+
+    if let Some(new_attr) = new_attr {
+        let mut new_status_flags = old_status_flags - StatusFlags::O_NONBLOCK;
+        if new_attr.mq_flags != 0 {
+            new_status_flags |= StatusFlags::O_NONBLOCK;
+        }
+        file.set_status_flags(new_status_flags)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Checks the name of a message queue.
+///
+/// C libraries strip the leading slash of the name given by users, so the name here must not
+/// contain any slashes.
+fn check_queue_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+        return_errno_with_message!(Errno::EACCES, "the queue name is invalid");
+    }
+    Ok(())
+}
+
+fn queue_inode_of(file: &Arc<dyn FileLike>) -> Result<&QueueInode> {
+    file.downcast_ref::<InodeHandle>()
+        .and_then(|inode_handle| inode_handle.path().inode().downcast_ref::<QueueInode>())
+        .ok_or_else(|| Error::with_message(Errno::EBADF, "the file is not a message queue"))
+}
+
+/// Reads the absolute timeout measured against `CLOCK_REALTIME` and converts it to the
+/// remaining time.
+fn read_abs_timeout(abs_timeout_addr: Vaddr, ctx: &Context) -> Result<Option<Duration>> {
+    if abs_timeout_addr == 0 {
+        return Ok(None);
+    }
+
+    let abs_timeout =
+        Duration::try_from(ctx.user_space().read_val::<timespec_t>(abs_timeout_addr)?)?;
+    let now = RealTimeClock::get().read_time();
+    Ok(Some(abs_timeout.saturating_sub(now)))
+}
+
+fn map_timeout_error(err: Error) -> Error {
+    if err.error() == Errno::ETIME {
+        Error::with_message(Errno::ETIMEDOUT, "the time limit is reached")
+    } else {
+        err
+    }
+}
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::msg::{
+        get_msg_queue, max_msg_queue_id, remove_msg_queue, MsgControlCmd, MsgInfo, MsqidDs,
+    },
+    prelude::*,
+};
+
+pub fn sys_msgctl(msqid: i32, cmd: i32, buf: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    // Some C libraries always set the `IPC_64` flag to request the 64-bit
+    // layout of structures, which is the only layout supported here.
+    const IPC_64: i32 = 0x100;
+
+    let cmd = MsgControlCmd::try_from(cmd & !IPC_64)?;
+    debug!(
+        "[sys_msgctl] msqid = {}, cmd = {:?}, buf = 0x{:x}",
+        msqid, cmd, buf
+    );
+
+    if msqid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the message queue ID is negative");
+    }
+
+    let credentials = ctx.posix_thread.credentials();
+    match cmd {
+        MsgControlCmd::IPC_RMID => {
+            remove_msg_queue(msqid, &credentials)?;
+        }
+        MsgControlCmd::IPC_SET => {
+            let msqid_ds: MsqidDs = ctx.user_space().read_val(buf)?;
+            get_msg_queue(msqid)?.set(&msqid_ds, &credentials)?;
+        }
+        MsgControlCmd::IPC_STAT => {
+            let queue = get_msg_queue(msqid)?;
+            queue.check_access(&credentials, 0o444)?;
+            ctx.user_space().write_val(buf, &queue.msqid_ds())?;
+        }
+        MsgControlCmd::IPC_INFO => {
+            ctx.user_space().write_val(buf, &MsgInfo::current())?;
+            let max_id = max_msg_queue_id().unwrap_or(0);
+            return Ok(SyscallReturn::Return(max_id as _));
+        }
+        MsgControlCmd::MSG_STAT | MsgControlCmd::MSG_STAT_ANY => {
+            // Message queue IDs are allocated as indices, so the index that
+            // `MSG_STAT` takes is exactly the message queue ID.
+            let queue = get_msg_queue(msqid)?;
+            if matches!(cmd, MsgControlCmd::MSG_STAT) {
+                queue.check_access(&credentials, 0o444)?;
+            }
+            ctx.user_space().write_val(buf, &queue.msqid_ds())?;
+            return Ok(SyscallReturn::Return(queue.id() as _));
+        }
+        MsgControlCmd::MSG_INFO => {
+            ctx.user_space().write_val(buf, &MsgInfo::usage())?;
+            let max_id = max_msg_queue_id().unwrap_or(0);
+            return Ok(SyscallReturn::Return(max_id as _));
+        }
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
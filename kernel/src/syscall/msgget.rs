@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{msg::get_or_create_msg_queue, IpcFlags},
+    prelude::*,
+};
+
+pub fn sys_msgget(key: i32, msgflg: i32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = IpcFlags::from_bits_truncate(msgflg as u32);
+    let mode = (msgflg as u32 & 0o777) as u16;
+    debug!(
+        "[sys_msgget] key = {}, flags = {:?}, mode = {:o}",
+        key, flags, mode
+    );
+
+    let credentials = ctx.posix_thread.credentials();
+    let msqid = get_or_create_msg_queue(key, flags, mode, &credentials)?;
+
+    Ok(SyscallReturn::Return(msqid as _))
+}
@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::msg::{get_msg_queue, MsgFlags, MsgSelector},
+    prelude::*,
+};
+
+pub fn sys_msgrcv(
+    msqid: i32,
+    msgp: Vaddr,
+    msgsz: isize,
+    msgtyp: i64,
+    msgflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = MsgFlags::from_bits_truncate(msgflg as u32);
+    debug!(
+        "[sys_msgrcv] msqid = {}, msgp = 0x{:x}, msgsz = {}, msgtyp = {}, flags = {:?}",
+        msqid, msgp, msgsz, msgtyp, flags
+    );
+
+    if msqid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the message queue ID is negative");
+    }
+    if msgsz < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the buffer size is negative");
+    }
+    if flags.contains(MsgFlags::MSG_COPY) {
+        return_errno_with_message!(Errno::EINVAL, "`MSG_COPY` is not supported");
+    }
+
+    let queue = get_msg_queue(msqid)?;
+    let credentials = ctx.posix_thread.credentials();
+    let message = queue.receive(
+        MsgSelector::new(msgtyp, flags),
+        msgsz as usize,
+        flags,
+        &credentials,
+        ctx.process.pid(),
+    )?;
+
+    // The buffer is in the layout of `struct msgbuf { long mtype; char mtext[]; }`.
+    let user_space = ctx.user_space();
+    user_space.write_val(msgp, &message.mtype())?;
+    user_space.write_bytes(
+        msgp + size_of::<i64>(),
+        &mut VmReader::from(message.mtext()),
+    )?;
+
+    Ok(SyscallReturn::Return(message.mtext().len() as _))
+}
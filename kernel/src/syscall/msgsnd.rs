@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::msg::{get_msg_queue, Message, MsgFlags, MSGMAX},
+    prelude::*,
+};
+
+pub fn sys_msgsnd(
+    msqid: i32,
+    msgp: Vaddr,
+    msgsz: usize,
+    msgflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = MsgFlags::from_bits_truncate(msgflg as u32);
+    debug!(
+        "[sys_msgsnd] msqid = {}, msgp = 0x{:x}, msgsz = {}, flags = {:?}",
+        msqid, msgp, msgsz, flags
+    );
+
+    if msqid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the message queue ID is negative");
+    }
+    if msgsz > MSGMAX {
+        return_errno_with_message!(Errno::EINVAL, "the message is too long");
+    }
+
+    // The buffer is in the layout of `struct msgbuf { long mtype; char mtext[]; }`.
+    let user_space = ctx.user_space();
+    let mtype = user_space.read_val::<i64>(msgp)?;
+    if mtype < 1 {
+        return_errno_with_message!(Errno::EINVAL, "the message type is not positive");
+    }
+    let mut mtext = vec![0u8; msgsz].into_boxed_slice();
+    user_space.read_bytes(msgp + size_of::<i64>(), &mut VmWriter::from(&mut mtext[..]))?;
+
+    let queue = get_msg_queue(msqid)?;
+    let credentials = ctx.posix_thread.credentials();
+    queue.send(
+        Message::new(mtype, mtext),
+        flags,
+        &credentials,
+        ctx.process.pid(),
+    )?;
+
+    Ok(SyscallReturn::Return(0))
+}
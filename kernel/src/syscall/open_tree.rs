@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{FdFlags, FileDesc},
+        fs_resolver::FsPath,
+        inode_handle::InodeHandle,
+        path::Path,
+        utils::{AccessMode, CreationFlags, StatusFlags},
+    },
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    syscall::constants::MAX_FILENAME_LEN,
+};
+
+pub fn sys_open_tree(
+    dirfd: FileDesc,
+    path_addr: Vaddr,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let path_name = ctx.user_space().read_cstring(path_addr, MAX_FILENAME_LEN)?;
+    let flags = OpenTreeFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid open_tree flags"))?;
+    debug!(
+        "dirfd = {}, path_name = {:?}, flags = {:?}",
+        dirfd, path_name, flags
+    );
+
+    let is_clone = flags.contains(OpenTreeFlags::OPEN_TREE_CLONE);
+    let is_recursive = flags.contains(OpenTreeFlags::AT_RECURSIVE);
+    if is_recursive && !is_clone {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "AT_RECURSIVE can only be used with OPEN_TREE_CLONE"
+        );
+    }
+    if is_clone {
+        ctx.thread_local
+            .borrow_ns_proxy()
+            .unwrap()
+            .mnt_ns()
+            .owner()
+            .check_cap(CapSet::SYS_ADMIN, ctx.posix_thread)?;
+    }
+
+    let path = {
+        let path_name = path_name.to_string_lossy();
+        let fs_path = if flags.contains(OpenTreeFlags::AT_EMPTY_PATH) && path_name.is_empty() {
+            FsPath::from_fd(dirfd)?
+        } else {
+            FsPath::from_fd_and_path(dirfd, &path_name)?
+        };
+
+        let fs_ref = ctx.thread_local.borrow_fs();
+        let fs = fs_ref.resolver().read();
+        if flags.contains(OpenTreeFlags::AT_SYMLINK_NOFOLLOW) {
+            fs.lookup_no_follow(&fs_path)?
+        } else {
+            fs.lookup(&fs_path)?
+        }
+    };
+
+    let path = if is_clone {
+        path.clone_detached(is_recursive, ctx)?
+    } else {
+        path
+    };
+
+    let fd = insert_path_file(path, flags.contains(OpenTreeFlags::OPEN_TREE_CLOEXEC), ctx)?;
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+/// Opens the `Path` with `O_PATH` and inserts the file into the file table.
+///
+/// This is how the mounts are returned to the user space by `open_tree` and `fsmount`.
+pub(super) fn insert_path_file(path: Path, is_cloexec: bool, ctx: &Context) -> Result<FileDesc> {
+    let inode_handle = InodeHandle::new(path, AccessMode::O_RDONLY, StatusFlags::O_PATH)?;
+
+    let fd_flags = if is_cloexec {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+    let file_table = ctx.thread_local.borrow_file_table();
+    let fd = file_table
+        .unwrap()
+        .write()
+        .insert(Arc::new(inode_handle), fd_flags);
+    Ok(fd)
+}
+
+bitflags! {
+    struct OpenTreeFlags: u32 {
+        const OPEN_TREE_CLONE = 1 << 0;
+        const AT_SYMLINK_NOFOLLOW = 1 << 8;
+        const AT_NO_AUTOMOUNT = 1 << 11;
+        const AT_EMPTY_PATH = 1 << 12;
+        const AT_RECURSIVE = 1 << 15;
+        const OPEN_TREE_CLOEXEC = CreationFlags::O_CLOEXEC.bits();
+    }
+}
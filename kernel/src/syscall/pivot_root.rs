@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        fs_resolver::{FsPath, AT_FDCWD},
+        path::Path,
+    },
+    prelude::*,
+    process::{credentials::capabilities::CapSet, process_table},
+    syscall::constants::MAX_FILENAME_LEN,
+};
+
+pub fn sys_pivot_root(
+    new_root_addr: Vaddr,
+    put_old_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let user_space = ctx.user_space();
+    let new_root_name = user_space.read_cstring(new_root_addr, MAX_FILENAME_LEN)?;
+    let put_old_name = user_space.read_cstring(put_old_addr, MAX_FILENAME_LEN)?;
+    debug!(
+        "new_root = {:?}, put_old = {:?}",
+        new_root_name, put_old_name
+    );
+
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    let mnt_ns = ns_proxy.unwrap().mnt_ns();
+    mnt_ns
+        .owner()
+        .check_cap(CapSet::SYS_ADMIN, ctx.posix_thread)?;
+
+    let (root, new_root, put_old) = {
+        let fs_ref = ctx.thread_local.borrow_fs();
+        let fs = fs_ref.resolver().read();
+
+        let new_root_name = new_root_name.to_string_lossy();
+        let new_root = fs.lookup(&FsPath::from_fd_and_path(AT_FDCWD, &new_root_name)?)?;
+        let put_old_name = put_old_name.to_string_lossy();
+        let put_old = fs.lookup(&FsPath::from_fd_and_path(AT_FDCWD, &put_old_name)?)?;
+
+        (fs.root().clone(), new_root, put_old)
+    };
+
+    mnt_ns.pivot_root(&root, &new_root, &put_old)?;
+
+    replace_root_of_all_threads(&root, &new_root);
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Changes the root directories and the current working directories of all threads from
+/// `old_root` to `new_root`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.17/source/fs/fs_struct.c>
+fn replace_root_of_all_threads(old_root: &Path, new_root: &Path) {
+    for process in process_table::process_table_mut().iter() {
+        for task in process.tasks().lock().as_slice() {
+            let Some(posix_thread) = task.as_posix_thread() else {
+                continue;
+            };
+
+            posix_thread
+                .read_fs()
+                .resolver()
+                .write()
+                .replace_root(old_root, new_root);
+        }
+    }
+}
@@ -33,15 +33,22 @@ pub fn sys_prctl(
             ctx.user_space().write_val(write_to_addr, &write_val)?;
         }
         PrctlCmd::PR_GET_DUMPABLE => {
-            // TODO: when coredump is supported, return the actual value
-            return Ok(SyscallReturn::Return(Dumpable::Disable as _));
+            let dumpable = if ctx.user_space().vmar().process_vm().is_dumpable() {
+                Dumpable::User
+            } else {
+                Dumpable::Disable
+            };
+            return Ok(SyscallReturn::Return(dumpable as _));
         }
         PrctlCmd::PR_SET_DUMPABLE(dumpable) => {
             if dumpable != Dumpable::Disable && dumpable != Dumpable::User {
                 return_errno!(Errno::EINVAL)
             }
 
-            // TODO: implement coredump
+            ctx.user_space()
+                .vmar()
+                .process_vm()
+                .set_dumpable(dumpable == Dumpable::User);
         }
         PrctlCmd::PR_GET_KEEPCAPS => {
             let keep_cap = {
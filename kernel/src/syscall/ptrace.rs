@@ -0,0 +1,274 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    arch::cpu::UserRegs,
+    prelude::*,
+    process::{
+        posix_thread::{
+            ptrace::{self, PtraceOptions},
+            AsPosixThread,
+        },
+        signal::{
+            c_types::siginfo_t, constants::SIGKILL, sig_num::SigNum, signals::kernel::KernelSignal,
+        },
+        Process,
+    },
+    thread::Tid,
+};
+
+pub fn sys_ptrace(
+    request: u64,
+    pid: u64,
+    addr: u64,
+    data: u64,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let request = PtraceRequest::try_from(request as u32)
+        .map_err(|_| Error::with_message(Errno::EIO, "invalid ptrace request"))?;
+    let tid = pid as Tid;
+    let addr = addr as Vaddr;
+    let data = data as usize;
+    debug!(
+        "request = {:?}, tid = {}, addr = {:#x}, data = {:#x}",
+        request, tid, addr, data
+    );
+
+    match request {
+        PtraceRequest::PTRACE_TRACEME => ptrace::trace_me(ctx)?,
+        PtraceRequest::PTRACE_ATTACH => ptrace::attach(ctx, tid, None)?,
+        PtraceRequest::PTRACE_SEIZE => {
+            if addr != 0 {
+                return_errno_with_message!(Errno::EIO, "the address must be zero");
+            }
+            let options = PtraceOptions::from_raw(data as u32)?;
+            ptrace::attach(ctx, tid, Some(options))?;
+        }
+        _ => return handle_tracee_request(request, tid, addr, data, ctx),
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn handle_tracee_request(
+    request: PtraceRequest,
+    tid: Tid,
+    addr: Vaddr,
+    data: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let tracee = ptrace::get_tracee(ctx, tid)?;
+    let posix_thread = tracee.as_posix_thread().unwrap();
+    let user_space = ctx.user_space();
+
+    // Except for `PTRACE_KILL`, the tracee must be in a ptrace-stop.
+    if request == PtraceRequest::PTRACE_KILL {
+        posix_thread.enqueue_signal(Box::new(KernelSignal::new(SIGKILL)));
+        return Ok(SyscallReturn::Return(0));
+    }
+    let stop = posix_thread.tracee().lock_stop(&ctx.process)?;
+
+    match request {
+        PtraceRequest::PTRACE_PEEKTEXT | PtraceRequest::PTRACE_PEEKDATA => {
+            drop(stop);
+            let word = peek_data(&posix_thread.process(), addr)?;
+            user_space.write_val(data, &word)?;
+        }
+        PtraceRequest::PTRACE_POKETEXT | PtraceRequest::PTRACE_POKEDATA => {
+            drop(stop);
+            poke_data(&posix_thread.process(), addr, data)?;
+        }
+        #[cfg(target_arch = "x86_64")]
+        PtraceRequest::PTRACE_PEEKUSR => {
+            let offset = check_user_offset(addr)?;
+            let mut word = 0usize;
+            word.as_bytes_mut()
+                .copy_from_slice(&stop.regs().as_bytes()[offset..offset + size_of::<usize>()]);
+            drop(stop);
+            user_space.write_val(data, &word)?;
+        }
+        #[cfg(target_arch = "x86_64")]
+        PtraceRequest::PTRACE_POKEUSR => {
+            let offset = check_user_offset(addr)?;
+            let mut stop = stop;
+            stop.regs_mut().as_bytes_mut()[offset..offset + size_of::<usize>()]
+                .copy_from_slice(data.as_bytes());
+        }
+        #[cfg(target_arch = "x86_64")]
+        PtraceRequest::PTRACE_GETREGS => {
+            let regs = *stop.regs();
+            drop(stop);
+            user_space.write_val(data, &regs)?;
+        }
+        #[cfg(target_arch = "x86_64")]
+        PtraceRequest::PTRACE_SETREGS => {
+            drop(stop);
+            let regs = user_space.read_val::<UserRegs>(data)?;
+            *posix_thread.tracee().lock_stop(&ctx.process)?.regs_mut() = regs;
+        }
+        PtraceRequest::PTRACE_GETREGSET => {
+            check_regset_type(addr)?;
+            let regs = *stop.regs();
+            drop(stop);
+
+            let mut iov = user_space.read_val::<UserIoVec>(data)?;
+            iov.len = iov.len.min(size_of::<UserRegs>());
+            user_space.write_bytes(iov.base, &mut VmReader::from(&regs.as_bytes()[..iov.len]))?;
+            user_space.write_val(data, &iov)?;
+        }
+        PtraceRequest::PTRACE_SETREGSET => {
+            check_regset_type(addr)?;
+            let mut regs = *stop.regs();
+            drop(stop);
+
+            let iov = user_space.read_val::<UserIoVec>(data)?;
+            let len = iov.len.min(size_of::<UserRegs>());
+            user_space.read_bytes(
+                iov.base,
+                &mut VmWriter::from(&mut regs.as_bytes_mut()[..len]),
+            )?;
+            *posix_thread.tracee().lock_stop(&ctx.process)?.regs_mut() = regs;
+        }
+        PtraceRequest::PTRACE_SETOPTIONS => {
+            let mut stop = stop;
+            stop.set_options(PtraceOptions::from_raw(data as u32)?);
+        }
+        PtraceRequest::PTRACE_GETEVENTMSG => {
+            let event_msg = stop.event_msg();
+            drop(stop);
+            user_space.write_val(data, &event_msg)?;
+        }
+        PtraceRequest::PTRACE_GETSIGINFO => {
+            let siginfo = *stop.siginfo();
+            drop(stop);
+            user_space.write_val(data, &siginfo)?;
+        }
+        PtraceRequest::PTRACE_SETSIGINFO => {
+            drop(stop);
+            let siginfo = user_space.read_val::<siginfo_t>(data)?;
+            posix_thread
+                .tracee()
+                .lock_stop(&ctx.process)?
+                .set_siginfo(siginfo);
+        }
+        PtraceRequest::PTRACE_CONT | PtraceRequest::PTRACE_SYSCALL => {
+            let signal = parse_signal(data)?;
+            let is_syscall_traced = request == PtraceRequest::PTRACE_SYSCALL;
+            stop.resume(posix_thread, signal, is_syscall_traced);
+        }
+        PtraceRequest::PTRACE_DETACH => {
+            drop(stop);
+            let signal = parse_signal(data)?;
+            ptrace::detach(ctx, &tracee, signal)?;
+        }
+        // TODO: Support single-stepping, `PTRACE_INTERRUPT`, `PTRACE_LISTEN`, and the requests for
+        // floating-point registers.
+        _ => {
+            return_errno_with_message!(Errno::EIO, "the ptrace request is not supported");
+        }
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn peek_data(process: &Process, addr: Vaddr) -> Result<usize> {
+    let vmar_guard = process.lock_vmar();
+    let Some(vmar) = vmar_guard.as_ref() else {
+        return_errno_with_message!(Errno::ESRCH, "the process has exited");
+    };
+
+    let mut word = 0usize;
+    vmar.read_remote(addr, &mut VmWriter::from(word.as_bytes_mut()))
+        .map_err(|_| Error::with_message(Errno::EIO, "the address cannot be read"))?;
+
+    Ok(word)
+}
+
+fn poke_data(process: &Process, addr: Vaddr, word: usize) -> Result<()> {
+    let vmar_guard = process.lock_vmar();
+    let Some(vmar) = vmar_guard.as_ref() else {
+        return_errno_with_message!(Errno::ESRCH, "the process has exited");
+    };
+
+    // Like Linux, tracers can write to read-only mappings (e.g., to insert breakpoints into the
+    // code).
+    vmar.force_write_remote(addr, &mut VmReader::from(word.as_bytes()))
+        .map_err(|_| Error::with_message(Errno::EIO, "the address cannot be written"))?;
+
+    Ok(())
+}
+
+/// Checks the offset into `struct user`, returning the offset into the registers.
+#[cfg(target_arch = "x86_64")]
+fn check_user_offset(offset: usize) -> Result<usize> {
+    // TODO: Support the fields after the registers (e.g., the debug registers).
+    if offset % size_of::<usize>() != 0 || offset >= size_of::<UserRegs>() {
+        return_errno_with_message!(Errno::EIO, "the offset is invalid or not supported");
+    }
+
+    Ok(offset)
+}
+
+fn check_regset_type(note_type: usize) -> Result<()> {
+    // Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/include/uapi/linux/elf.h>
+    const NT_PRSTATUS: usize = 1;
+
+    // TODO: Support other register sets, such as `NT_PRFPREG`.
+    if note_type != NT_PRSTATUS {
+        return_errno_with_message!(Errno::EINVAL, "the register set is not supported");
+    }
+
+    Ok(())
+}
+
+fn parse_signal(data: usize) -> Result<Option<SigNum>> {
+    if data == 0 {
+        return Ok(None);
+    }
+
+    u8::try_from(data)
+        .ok()
+        .and_then(|num| SigNum::try_from(num).ok())
+        .map(Some)
+        .ok_or_else(|| Error::with_message(Errno::EIO, "the signal is invalid"))
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct UserIoVec {
+    base: Vaddr,
+    len: usize,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+#[expect(non_camel_case_types)]
+enum PtraceRequest {
+    PTRACE_TRACEME = 0,
+    PTRACE_PEEKTEXT = 1,
+    PTRACE_PEEKDATA = 2,
+    PTRACE_PEEKUSR = 3,
+    PTRACE_POKETEXT = 4,
+    PTRACE_POKEDATA = 5,
+    PTRACE_POKEUSR = 6,
+    PTRACE_CONT = 7,
+    PTRACE_KILL = 8,
+    PTRACE_SINGLESTEP = 9,
+    PTRACE_GETREGS = 12,
+    PTRACE_SETREGS = 13,
+    PTRACE_GETFPREGS = 14,
+    PTRACE_SETFPREGS = 15,
+    PTRACE_ATTACH = 16,
+    PTRACE_DETACH = 17,
+    PTRACE_SYSCALL = 24,
+    PTRACE_SETOPTIONS = 0x4200,
+    PTRACE_GETEVENTMSG = 0x4201,
+    PTRACE_GETSIGINFO = 0x4202,
+    PTRACE_SETSIGINFO = 0x4203,
+    PTRACE_GETREGSET = 0x4204,
+    PTRACE_SETREGSET = 0x4205,
+    PTRACE_SEIZE = 0x4206,
+    PTRACE_INTERRUPT = 0x4207,
+    PTRACE_LISTEN = 0x4208,
+    PTRACE_PEEKSIGINFO = 0x4209,
+}
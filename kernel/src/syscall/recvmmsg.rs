@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use super::{recvmsg::recv_msg, SyscallReturn};
+use crate::{
+    fs::file_table::FileDesc,
+    net::socket::util::SendRecvFlags,
+    prelude::*,
+    time::{clocks::MonotonicClock, timespec_t, Clock},
+    util::net::{CUserMmsgHdr, MAX_MMSG_LENGTH},
+};
+
+pub fn sys_recvmmsg(
+    sockfd: FileDesc,
+    user_mmsghdr_ptr: Vaddr,
+    vlen: u32,
+    flags: i32,
+    timeout_ptr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let mut flags = SendRecvFlags::from_bits_truncate(flags);
+    let vlen = (vlen as usize).min(MAX_MMSG_LENGTH);
+
+    let user_space = ctx.user_space();
+    let timeout = if timeout_ptr != 0 {
+        let timeout = user_space.read_val::<timespec_t>(timeout_ptr)?;
+        Some(Duration::try_from(timeout)?)
+    } else {
+        None
+    };
+
+    debug!(
+        "sockfd = {}, user_mmsghdr_ptr = 0x{:x}, vlen = {}, flags = {:?}, timeout = {:?}",
+        sockfd, user_mmsghdr_ptr, vlen, flags, timeout
+    );
+
+    let deadline = timeout.map(|timeout| MonotonicClock::get().read_time() + timeout);
+    let mut remaining = None;
+
+    // `MSG_WAITFORONE` is handled here and should not be passed to the socket.
+    let is_wait_for_one = flags.contains(SendRecvFlags::MSG_WAITFORONE);
+    flags.remove(SendRecvFlags::MSG_WAITFORONE);
+
+    let mut nr_recv = 0;
+
+    while nr_recv < vlen {
+        let entry_ptr = user_mmsghdr_ptr + nr_recv * size_of::<CUserMmsgHdr>();
+
+        match recv_one_mmsg(sockfd, entry_ptr, flags, ctx) {
+            Ok(()) => nr_recv += 1,
+            // Errors are reported only if no messages have been received. This follows the Linux
+            // implementation.
+            // TODO: Linux will report errors other than `EAGAIN` in the next call on the socket.
+            Err(_) if nr_recv > 0 => break,
+            Err(err) => {
+                return Err(match err.error() {
+                    // FIXME: `recvmmsg` should not be restarted if a timeout has been set on the socket using `setsockopt`.
+                    Errno::EINTR => Error::new(Errno::ERESTARTSYS),
+                    _ => err,
+                });
+            }
+        }
+
+        // After the first message is received, `MSG_WAITFORONE` turns on `MSG_DONTWAIT`.
+        if is_wait_for_one {
+            flags.insert(SendRecvFlags::MSG_DONTWAIT);
+        }
+
+        // Like Linux, the timeout is only checked after a message is received. So this call can
+        // still block forever if no messages arrive.
+        if let Some(deadline) = deadline {
+            let now = MonotonicClock::get().read_time();
+            remaining = Some(deadline.saturating_sub(now));
+            if now >= deadline {
+                break;
+            }
+        }
+    }
+
+    // The remaining time is written back if any messages have been received.
+    if let Some(remaining) = remaining {
+        user_space.write_val(timeout_ptr, &timespec_t::from(remaining))?;
+    }
+
+    Ok(SyscallReturn::Return(nr_recv as _))
+}
+
+/// Receives one message into the buffers specified by the user-space `mmsghdr`.
+fn recv_one_mmsg(
+    sockfd: FileDesc,
+    user_mmsghdr_ptr: Vaddr,
+    flags: SendRecvFlags,
+    ctx: &Context,
+) -> Result<()> {
+    let user_space = ctx.user_space();
+
+    let mut c_user_mmsghdr: CUserMmsgHdr = user_space.read_val(user_mmsghdr_ptr)?;
+    let recv_bytes = recv_msg(sockfd, &mut c_user_mmsghdr.msg_hdr, flags, ctx)?;
+    c_user_mmsghdr.msg_len = recv_bytes as u32;
+
+    user_space.write_val(user_mmsghdr_ptr, &c_user_mmsghdr)
+}
@@ -23,19 +23,37 @@ pub fn sys_recvmsg(
         sockfd, c_user_msghdr, flags
     );
 
+    let total_bytes =
+        recv_msg(sockfd, &mut c_user_msghdr, flags, ctx).map_err(|err| match err.error() {
+            // FIXME: `recvmsg` should not be restarted if a timeout has been set on the socket using `setsockopt`.
+            Errno::EINTR => Error::new(Errno::ERESTARTSYS),
+            _ => err,
+        })?;
+
+    user_space.write_val(user_msghdr_ptr, &c_user_msghdr)?;
+
+    Ok(SyscallReturn::Return(total_bytes as _))
+}
+
+/// Receives a message from the socket into the buffers specified by the user-space `msghdr`.
+///
+/// The lengths of the socket address and the control messages in `c_user_msghdr` will be
+/// updated, but it is the caller's responsibility to write `c_user_msghdr` back to the user space.
+pub(super) fn recv_msg(
+    sockfd: FileDesc,
+    c_user_msghdr: &mut CUserMsgHdr,
+    flags: SendRecvFlags,
+    ctx: &Context,
+) -> Result<usize> {
+    let user_space = ctx.user_space();
+
     let mut file_table = ctx.thread_local.borrow_file_table_mut();
     let file = get_file_fast!(&mut file_table, sockfd);
     let socket = file.as_socket_or_err()?;
 
     let (total_bytes, message_header) = {
         let mut io_vec_writer = c_user_msghdr.copy_writer_array_from_user(&user_space)?;
-        socket
-            .recvmsg(&mut io_vec_writer, flags)
-            .map_err(|err| match err.error() {
-                // FIXME: `recvmsg` should not be restarted if a timeout has been set on the socket using `setsockopt`.
-                Errno::EINTR => Error::new(Errno::ERESTARTSYS),
-                _ => err,
-            })?
+        socket.recvmsg(&mut io_vec_writer, flags)?
     };
 
     // Writing control messages may access the file table, so it should be called after dropping
@@ -49,7 +67,5 @@ pub fn sys_recvmsg(
     c_user_msghdr.msg_controllen =
         c_user_msghdr.write_control_messages_to_user(control_messages, &user_space)?;
 
-    user_space.write_val(user_msghdr_ptr, &c_user_msghdr)?;
-
-    Ok(SyscallReturn::Return(total_bytes as _))
+    Ok(total_bytes)
 }
@@ -8,7 +8,10 @@ use super::{
     SyscallReturn,
 };
 use crate::{
-    fs::file_table::{get_file_fast, FileDesc},
+    fs::{
+        file_table::{get_file_fast, FileDesc},
+        notify::FsEvents,
+    },
     prelude::*,
     syscall::constants::MAX_FILENAME_LEN,
 };
@@ -53,5 +56,7 @@ fn removexattr(
     check_xattr_namespace(xattr_name.namespace(), ctx)?;
 
     let path = lookup_path_for_xattr(&file_ctx, ctx)?;
-    path.remove_xattr(xattr_name)
+    path.remove_xattr(xattr_name)?;
+    path.notify_fs_events(FsEvents::ATTRIB);
+    Ok(())
 }
@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::mem::offset_of;
+
+use super::{sendmsg::send_msg, SyscallReturn};
+use crate::{
+    fs::file_table::FileDesc,
+    net::socket::util::SendRecvFlags,
+    prelude::*,
+    util::net::{CUserMmsgHdr, MAX_MMSG_LENGTH},
+};
+
+pub fn sys_sendmmsg(
+    sockfd: FileDesc,
+    user_mmsghdr_ptr: Vaddr,
+    vlen: u32,
+    flags: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = SendRecvFlags::from_bits_truncate(flags);
+    let vlen = (vlen as usize).min(MAX_MMSG_LENGTH);
+
+    debug!(
+        "sockfd = {}, user_mmsghdr_ptr = 0x{:x}, vlen = {}, flags = {:?}",
+        sockfd, user_mmsghdr_ptr, vlen, flags
+    );
+
+    let mut nr_sent = 0;
+
+    while nr_sent < vlen {
+        let entry_ptr = user_mmsghdr_ptr + nr_sent * size_of::<CUserMmsgHdr>();
+
+        match send_one_mmsg(sockfd, entry_ptr, flags, ctx) {
+            Ok(()) => nr_sent += 1,
+            // Errors are reported only if no messages have been sent. This follows the Linux
+            // implementation.
+            Err(_) if nr_sent > 0 => break,
+            Err(err) => {
+                return Err(match err.error() {
+                    // FIXME: `sendmmsg` should not be restarted if a timeout has been set on the socket using `setsockopt`.
+                    Errno::EINTR => Error::new(Errno::ERESTARTSYS),
+                    _ => err,
+                });
+            }
+        }
+    }
+
+    Ok(SyscallReturn::Return(nr_sent as _))
+}
+
+/// Sends one message specified by the user-space `mmsghdr`.
+fn send_one_mmsg(
+    sockfd: FileDesc,
+    user_mmsghdr_ptr: Vaddr,
+    flags: SendRecvFlags,
+    ctx: &Context,
+) -> Result<()> {
+    let user_space = ctx.user_space();
+
+    let c_user_mmsghdr: CUserMmsgHdr = user_space.read_val(user_mmsghdr_ptr)?;
+    let sent_bytes = send_msg(sockfd, &c_user_mmsghdr.msg_hdr, flags, ctx)?;
+
+    let msg_len_ptr = user_mmsghdr_ptr + offset_of!(CUserMmsgHdr, msg_len);
+    user_space.write_val(msg_len_ptr, &(sent_bytes as u32))
+}
@@ -14,8 +14,7 @@ pub fn sys_sendmsg(
     flags: i32,
     ctx: &Context,
 ) -> Result<SyscallReturn> {
-    let user_space = ctx.user_space();
-    let c_user_msghdr: CUserMsgHdr = user_space.read_val(user_msghdr_ptr)?;
+    let c_user_msghdr: CUserMsgHdr = ctx.user_space().read_val(user_msghdr_ptr)?;
     let flags = SendRecvFlags::from_bits_truncate(flags);
 
     debug!(
@@ -23,6 +22,25 @@ pub fn sys_sendmsg(
         sockfd, c_user_msghdr, flags
     );
 
+    let total_bytes =
+        send_msg(sockfd, &c_user_msghdr, flags, ctx).map_err(|err| match err.error() {
+            // FIXME: `sendmsg` should not be restarted if a timeout has been set on the socket using `setsockopt`.
+            Errno::EINTR => Error::new(Errno::ERESTARTSYS),
+            _ => err,
+        })?;
+
+    Ok(SyscallReturn::Return(total_bytes as _))
+}
+
+/// Sends the message specified by the user-space `msghdr` on the socket.
+pub(super) fn send_msg(
+    sockfd: FileDesc,
+    c_user_msghdr: &CUserMsgHdr,
+    flags: SendRecvFlags,
+    ctx: &Context,
+) -> Result<usize> {
+    let user_space = ctx.user_space();
+
     let message_header = {
         let addr = c_user_msghdr.read_socket_addr_from_user()?;
         // Reading control messages may access the file table, so it should be called before
@@ -36,13 +54,5 @@ pub fn sys_sendmsg(
     let file = get_file_fast!(&mut file_table, sockfd);
     let socket = file.as_socket_or_err()?;
 
-    let total_bytes = socket
-        .sendmsg(&mut io_vec_reader, message_header, flags)
-        .map_err(|err| match err.error() {
-            // FIXME: `sendmsg` should not be restarted if a timeout has been set on the socket using `setsockopt`.
-            Errno::EINTR => Error::new(Errno::ERESTARTSYS),
-            _ => err,
-        })?;
-
-    Ok(SyscallReturn::Return(total_bytes as _))
+    socket.sendmsg(&mut io_vec_reader, message_header, flags)
 }
@@ -12,7 +12,7 @@
 
 use crate::{
     fs::{file_table::FileDesc, path::MountNamespace},
-    net::UtsNamespace,
+    net::{NetNamespace, UtsNamespace},
     prelude::*,
     process::{
         check_unsupported_ns_flags, credentials::capabilities::CapSet, posix_thread::AsPosixThread,
@@ -97,6 +97,11 @@ fn build_proxy_from_pid_file(
         set_mnt_ns(&mut builder, target_ns, ctx)?;
     }
 
+    if flags.contains(CloneFlags::CLONE_NEWNET) {
+        let target_ns = target_proxy.net_ns();
+        set_net_ns(&mut builder, target_ns, ctx)?;
+    }
+
     // TODO: Support setting other namespaces from the target process.
 
     Ok(builder.build())
@@ -143,3 +148,22 @@ fn set_mnt_ns(
 
     Ok(())
 }
+
+fn set_net_ns(
+    builder: &mut NsProxyBuilder,
+    target_ns: &Arc<NetNamespace>,
+    ctx: &Context,
+) -> Result<()> {
+    // Verify the thread has SYS_ADMIN capability in the target namespace's owner
+    // and the current user namespace.
+    target_ns
+        .owner_ns()
+        .check_cap(CapSet::SYS_ADMIN, ctx.posix_thread)?;
+    ctx.thread_local
+        .borrow_user_ns()
+        .check_cap(CapSet::SYS_ADMIN, ctx.posix_thread)?;
+
+    builder.net_ns(target_ns.clone());
+
+    Ok(())
+}
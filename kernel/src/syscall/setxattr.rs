@@ -8,6 +8,7 @@ use crate::{
         file_handle::FileLike,
         file_table::{get_file_fast, FileDesc},
         fs_resolver::{FsPath, AT_FDCWD},
+        notify::FsEvents,
         path::Path,
         utils::{
             XattrName, XattrNamespace, XattrSetFlags, XATTR_NAME_MAX_LEN, XATTR_VALUE_MAX_LEN,
@@ -114,7 +115,9 @@ fn setxattr(
     let mut value_reader = user_space.reader(value_ptr, value_len)?;
 
     let path = lookup_path_for_xattr(&file_ctx, ctx)?;
-    path.set_xattr(xattr_name, &mut value_reader, flags)
+    path.set_xattr(xattr_name, &mut value_reader, flags)?;
+    path.notify_fs_events(FsEvents::ATTRIB);
+    Ok(())
 }
 
 /// The context to describe the target file for xattr operations.
@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use align_ext::AlignExt;
+
+use super::SyscallReturn;
+use crate::{
+    ipc::shm::{get_shm, ShmAtFlags, SHMLBA},
+    prelude::*,
+};
+
+pub fn sys_shmat(shmid: i32, shmaddr: Vaddr, shmflg: i32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = ShmAtFlags::from_bits_truncate(shmflg as u32);
+    debug!(
+        "[sys_shmat] shmid = {}, shmaddr = 0x{:x}, flags = {:?}",
+        shmid, shmaddr, flags
+    );
+
+    if shmid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the segment ID is negative");
+    }
+
+    let addr = if shmaddr % SHMLBA == 0 {
+        shmaddr
+    } else if flags.contains(ShmAtFlags::SHM_RND) {
+        shmaddr.align_down(SHMLBA)
+    } else {
+        return_errno_with_message!(Errno::EINVAL, "the address is not aligned");
+    };
+    if addr == 0 && flags.contains(ShmAtFlags::SHM_REMAP) {
+        return_errno_with_message!(Errno::EINVAL, "`SHM_REMAP` requires a non-zero address");
+    }
+
+    let shm = get_shm(shmid)?;
+    let credentials = ctx.posix_thread.credentials();
+    let user_space = ctx.user_space();
+    let map_addr = shm.attach(
+        user_space.vmar(),
+        addr,
+        flags,
+        &credentials,
+        ctx.process.pid(),
+    )?;
+
+    Ok(SyscallReturn::Return(map_addr as _))
+}
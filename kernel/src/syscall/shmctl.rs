@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::shm::{get_shm, max_shm_id, remove_shm, shm_usage, ShmControlCmd, ShmInfo, ShmidDs},
+    prelude::*,
+};
+
+pub fn sys_shmctl(shmid: i32, cmd: i32, buf: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    // Some C libraries always set the `IPC_64` flag to request the 64-bit
+    // layout of structures, which is the only layout supported here.
+    const IPC_64: i32 = 0x100;
+
+    let cmd = ShmControlCmd::try_from(cmd & !IPC_64)?;
+    debug!(
+        "[sys_shmctl] shmid = {}, cmd = {:?}, buf = 0x{:x}",
+        shmid, cmd, buf
+    );
+
+    if shmid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the segment ID is negative");
+    }
+
+    let credentials = ctx.posix_thread.credentials();
+    match cmd {
+        ShmControlCmd::IPC_RMID => {
+            remove_shm(shmid, &credentials)?;
+        }
+        ShmControlCmd::IPC_SET => {
+            let shmid_ds: ShmidDs = ctx.user_space().read_val(buf)?;
+            get_shm(shmid)?.set(&shmid_ds, &credentials)?;
+        }
+        ShmControlCmd::IPC_STAT => {
+            let shm = get_shm(shmid)?;
+            shm.check_access(&credentials, 0o444)?;
+            ctx.user_space().write_val(buf, &shm.shmid_ds())?;
+        }
+        ShmControlCmd::IPC_INFO => {
+            ctx.user_space().write_val(buf, &ShmInfo::current())?;
+            let max_id = max_shm_id().unwrap_or(0);
+            return Ok(SyscallReturn::Return(max_id as _));
+        }
+        ShmControlCmd::SHM_LOCK => {
+            get_shm(shmid)?.set_locked(true, &credentials)?;
+        }
+        ShmControlCmd::SHM_UNLOCK => {
+            get_shm(shmid)?.set_locked(false, &credentials)?;
+        }
+        ShmControlCmd::SHM_STAT | ShmControlCmd::SHM_STAT_ANY => {
+            // Segment IDs are allocated as indices, so the index that
+            // `SHM_STAT` takes is exactly the segment ID.
+            let shm = get_shm(shmid)?;
+            if matches!(cmd, ShmControlCmd::SHM_STAT) {
+                shm.check_access(&credentials, 0o444)?;
+            }
+            ctx.user_space().write_val(buf, &shm.shmid_ds())?;
+            return Ok(SyscallReturn::Return(shm.id() as _));
+        }
+        ShmControlCmd::SHM_INFO => {
+            ctx.user_space().write_val(buf, &shm_usage())?;
+            let max_id = max_shm_id().unwrap_or(0);
+            return Ok(SyscallReturn::Return(max_id as _));
+        }
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{ipc::shm::detach_shm, prelude::*};
+
+pub fn sys_shmdt(shmaddr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("[sys_shmdt] shmaddr = 0x{:x}", shmaddr);
+
+    let user_space = ctx.user_space();
+    detach_shm(user_space.vmar(), shmaddr, ctx.process.pid())?;
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -1,17 +1,22 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use aster_bigtcp::wire::IpVersion;
+
 use super::SyscallReturn;
 use crate::{
     fs::{file_handle::FileLike, file_table::FdFlags},
     net::socket::{
-        ip::{DatagramSocket, StreamSocket},
+        ip::{DatagramSocket, PingSocket, RawSocket, StreamSocket},
         netlink::{
-            is_valid_protocol, NetlinkRouteSocket, NetlinkUeventSocket, StandardNetlinkProtocol,
+            is_valid_protocol, NetlinkGenericSocket, NetlinkRouteSocket, NetlinkUeventSocket,
+            StandardNetlinkProtocol,
         },
+        packet::PacketSocket,
         unix::{UnixDatagramSocket, UnixStreamSocket},
         vsock::VsockStreamSocket,
     },
     prelude::*,
+    process::credentials::capabilities::CapSet,
     util::net::{CSocketAddrFamily, Protocol, SockFlags, SockType, SOCK_TYPE_MASK},
 };
 
@@ -25,6 +30,7 @@ pub fn sys_socket(domain: i32, type_: i32, protocol: i32, ctx: &Context) -> Resu
     );
 
     let is_nonblocking = sock_flags.contains(SockFlags::SOCK_NONBLOCK);
+    let net_ns = ctx.thread_local.borrow_ns_proxy().unwrap().net_ns().clone();
     let file_like = match (domain, sock_type) {
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_STREAM) => {
             UnixStreamSocket::new(is_nonblocking, false) as Arc<dyn FileLike>
@@ -35,35 +41,64 @@ pub fn sys_socket(domain: i32, type_: i32, protocol: i32, ctx: &Context) -> Resu
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_RAW | SockType::SOCK_DGRAM) => {
             UnixDatagramSocket::new(is_nonblocking) as Arc<dyn FileLike>
         }
-        (CSocketAddrFamily::AF_INET, SockType::SOCK_STREAM) => {
+        (CSocketAddrFamily::AF_INET | CSocketAddrFamily::AF_INET6, SockType::SOCK_STREAM) => {
             let protocol = Protocol::try_from(protocol)?;
             debug!("protocol = {:?}", protocol);
             match protocol {
                 Protocol::IPPROTO_IP | Protocol::IPPROTO_TCP => {
-                    StreamSocket::new(is_nonblocking) as Arc<dyn FileLike>
+                    StreamSocket::new(is_nonblocking, ip_version_of(domain), net_ns)
+                        as Arc<dyn FileLike>
                 }
                 _ => return_errno_with_message!(Errno::EAFNOSUPPORT, "unsupported protocol"),
             }
         }
-        (CSocketAddrFamily::AF_INET, SockType::SOCK_DGRAM) => {
+        (CSocketAddrFamily::AF_INET | CSocketAddrFamily::AF_INET6, SockType::SOCK_DGRAM) => {
             let protocol = Protocol::try_from(protocol)?;
             debug!("protocol = {:?}", protocol);
             match protocol {
                 Protocol::IPPROTO_IP | Protocol::IPPROTO_UDP => {
-                    DatagramSocket::new(is_nonblocking) as Arc<dyn FileLike>
+                    DatagramSocket::new(is_nonblocking, ip_version_of(domain), net_ns)
+                        as Arc<dyn FileLike>
+                }
+                // FIXME: Linux only allows users in `net.ipv4.ping_group_range` to create ping
+                // sockets. Here we allow all users to do so.
+                Protocol::IPPROTO_ICMP if domain == CSocketAddrFamily::AF_INET => {
+                    PingSocket::new(is_nonblocking, net_ns) as Arc<dyn FileLike>
                 }
                 _ => return_errno_with_message!(Errno::EAFNOSUPPORT, "unsupported protocol"),
             }
         }
+        (CSocketAddrFamily::AF_INET, SockType::SOCK_RAW) => {
+            debug!("protocol = {:?}", protocol);
+            let protocol = match u8::try_from(protocol) {
+                Ok(0) => {
+                    return_errno_with_message!(
+                        Errno::EPROTONOSUPPORT,
+                        "raw sockets require a protocol"
+                    )
+                }
+                Ok(protocol) => protocol,
+                Err(_) => return_errno_with_message!(Errno::EINVAL, "invalid protocol"),
+            };
+
+            net_ns
+                .owner_ns()
+                .check_cap(CapSet::NET_RAW, ctx.posix_thread)?;
+
+            RawSocket::new(is_nonblocking, protocol, net_ns) as Arc<dyn FileLike>
+        }
         (CSocketAddrFamily::AF_NETLINK, SockType::SOCK_RAW | SockType::SOCK_DGRAM) => {
             let netlink_family = StandardNetlinkProtocol::try_from(protocol as u32);
             debug!("netlink family = {:?}", netlink_family);
             match netlink_family {
                 Ok(StandardNetlinkProtocol::ROUTE) => {
-                    NetlinkRouteSocket::new(is_nonblocking) as Arc<dyn FileLike>
+                    NetlinkRouteSocket::new(is_nonblocking, net_ns) as Arc<dyn FileLike>
                 }
                 Ok(StandardNetlinkProtocol::KOBJECT_UEVENT) => {
-                    NetlinkUeventSocket::new(is_nonblocking) as Arc<dyn FileLike>
+                    NetlinkUeventSocket::new(is_nonblocking, net_ns) as Arc<dyn FileLike>
+                }
+                Ok(StandardNetlinkProtocol::GENERIC) => {
+                    NetlinkGenericSocket::new(is_nonblocking, net_ns) as Arc<dyn FileLike>
                 }
                 Ok(_) => {
                     return_errno_with_message!(
@@ -82,6 +117,19 @@ pub fn sys_socket(domain: i32, type_: i32, protocol: i32, ctx: &Context) -> Resu
                 }
             }
         }
+        (CSocketAddrFamily::AF_PACKET, SockType::SOCK_RAW | SockType::SOCK_DGRAM) => {
+            // The protocol is in network byte order. See
+            // <https://man7.org/linux/man-pages/man7/packet.7.html>.
+            let protocol = u16::from_be(protocol as u16);
+            debug!("protocol = {:#x}", protocol);
+
+            net_ns
+                .owner_ns()
+                .check_cap(CapSet::NET_RAW, ctx.posix_thread)?;
+
+            let is_cooked = matches!(sock_type, SockType::SOCK_DGRAM);
+            PacketSocket::new(is_nonblocking, is_cooked, protocol, net_ns) as Arc<dyn FileLike>
+        }
         (CSocketAddrFamily::AF_VSOCK, SockType::SOCK_STREAM) => {
             Arc::new(VsockStreamSocket::new(is_nonblocking)?) as Arc<dyn FileLike>
         }
@@ -101,3 +149,11 @@ pub fn sys_socket(domain: i32, type_: i32, protocol: i32, ctx: &Context) -> Resu
 
     Ok(SyscallReturn::Return(fd as _))
 }
+
+fn ip_version_of(domain: CSocketAddrFamily) -> IpVersion {
+    if domain == CSocketAddrFamily::AF_INET6 {
+        IpVersion::Ipv6
+    } else {
+        IpVersion::Ipv4
+    }
+}
@@ -5,6 +5,7 @@ use crate::{
     fs::{
         file_table::{get_file_fast, FileDesc},
         fs_resolver::{FsPath, AT_FDCWD},
+        notify::FsEvents,
         utils::PATH_MAX,
     },
     prelude::*,
@@ -38,6 +39,7 @@ pub fn sys_truncate(path_ptr: Vaddr, len: isize, ctx: &Context) -> Result<Syscal
             .lookup(&fs_path)?
     };
     dir_path.resize(len as usize)?;
+    dir_path.notify_fs_events(FsEvents::MODIFY);
     Ok(SyscallReturn::Return(0))
 }
 
@@ -7,6 +7,7 @@ use crate::{
     fs::{
         file_table::FileDesc,
         fs_resolver::{FsPath, AT_FDCWD},
+        notify::FsEvents,
         path::Path,
     },
     prelude::*,
@@ -142,6 +143,7 @@ fn vfs_utimes(path: &Path, times: Option<TimeSpecPair>) -> Result<SyscallReturn>
     path.set_atime(atime);
     path.set_mtime(mtime);
     path.set_ctime(ctime);
+    path.notify_fs_events(FsEvents::ATTRIB);
 
     Ok(SyscallReturn::Return(0))
 }
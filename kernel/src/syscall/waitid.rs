@@ -7,7 +7,10 @@ use crate::{
         do_wait,
         signal::{
             c_types::siginfo_t,
-            constants::{CLD_CONTINUED, CLD_EXITED, CLD_KILLED, CLD_STOPPED, SIGCHLD, SIGCONT},
+            constants::{
+                CLD_CONTINUED, CLD_DUMPED, CLD_EXITED, CLD_KILLED, CLD_STOPPED, CLD_TRAPPED,
+                SIGCHLD, SIGCONT,
+            },
         },
         ProcessFilter, WaitOptions, WaitStatus,
     },
@@ -66,10 +69,10 @@ pub fn sys_waitid(
 }
 
 fn calculate_si_code_and_si_status(wait_status: &WaitStatus) -> (i32, i32) {
-    // TODO: Add supports for `CLD_DUMPED` and `CLD_TRAPPED`.
     match wait_status {
         WaitStatus::Zombie(process) => {
             const NORMAL_EXIT_MASK: u32 = 0xff;
+            const CORE_DUMP_MASK: u32 = 0x80;
 
             let exit_code = process.status().exit_code();
             // If the process exits normally, the lowest 8 bits of `status_code`
@@ -77,11 +80,14 @@ fn calculate_si_code_and_si_status(wait_status: &WaitStatus) -> (i32, i32) {
             // shifting the `status_code` right by 8 bits.
             if (exit_code & NORMAL_EXIT_MASK) == 0 {
                 (CLD_EXITED, (exit_code >> 8) as i32)
+            } else if (exit_code & CORE_DUMP_MASK) != 0 {
+                (CLD_DUMPED, (exit_code & !CORE_DUMP_MASK) as i32)
             } else {
                 (CLD_KILLED, exit_code as i32)
             }
         }
         WaitStatus::Stop(_process, signum) => (CLD_STOPPED, signum.as_u8() as i32),
         WaitStatus::Continue(_) => (CLD_CONTINUED, SIGCONT.as_u8() as i32),
+        WaitStatus::PtraceStop(_, _, status) => (CLD_TRAPPED, *status as i32),
     }
 }
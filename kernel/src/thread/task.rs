@@ -114,7 +114,6 @@ pub fn create_new_user_task(
             while !current_thread.is_exited() && ctx.process.is_stopped() {
                 let _ = stop_waiter.pause_until_by(
                     || (!ctx.process.is_stopped()).then_some(()),
-                    // We currently do not support ptrace.
                     PauseReason::StopBySignal,
                 );
                 handle_pending_signal(user_ctx, &ctx, None);
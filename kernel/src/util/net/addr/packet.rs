@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::EthernetAddress;
+
+use super::family::CSocketAddrFamily;
+use crate::{net::socket::packet::PacketSocketAddr, prelude::*};
+
+/// Link-layer socket address.
+///
+/// See <https://man7.org/linux/man-pages/man7/packet.7.html>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CSocketAddrPacket {
+    /// Address family (AF_PACKET).
+    sll_family: u16,
+    /// Physical-layer protocol in network byte order.
+    sll_protocol: u16,
+    /// Interface index.
+    sll_ifindex: i32,
+    /// ARP hardware type.
+    sll_hatype: u16,
+    /// Packet type.
+    sll_pkttype: u8,
+    /// Length of the address.
+    sll_halen: u8,
+    /// Physical-layer address.
+    sll_addr: [u8; 8],
+}
+
+const ETHER_ADDR_LEN: usize = 6;
+
+impl From<PacketSocketAddr> for CSocketAddrPacket {
+    fn from(value: PacketSocketAddr) -> Self {
+        let mut sll_addr = [0; 8];
+        let sll_halen = if let Some(hardware_addr) = value.hardware_addr {
+            sll_addr[..ETHER_ADDR_LEN].copy_from_slice(hardware_addr.as_bytes());
+            ETHER_ADDR_LEN as u8
+        } else {
+            0
+        };
+
+        Self {
+            sll_family: CSocketAddrFamily::AF_PACKET as u16,
+            sll_protocol: value.protocol.to_be(),
+            sll_ifindex: value.ifindex as i32,
+            sll_hatype: value.hatype,
+            sll_pkttype: value.pkttype,
+            sll_halen,
+            sll_addr,
+        }
+    }
+}
+
+impl From<CSocketAddrPacket> for PacketSocketAddr {
+    fn from(value: CSocketAddrPacket) -> Self {
+        debug_assert_eq!(value.sll_family, CSocketAddrFamily::AF_PACKET as u16);
+
+        // Only Ethernet addresses are supported, so shorter addresses are ignored.
+        let hardware_addr = if value.sll_halen as usize >= ETHER_ADDR_LEN {
+            Some(EthernetAddress::from_bytes(
+                &value.sll_addr[..ETHER_ADDR_LEN],
+            ))
+        } else {
+            None
+        };
+
+        Self {
+            protocol: u16::from_be(value.sll_protocol),
+            ifindex: value.sll_ifindex as u32,
+            hatype: value.sll_hatype,
+            pkttype: value.sll_pkttype,
+            hardware_addr,
+        }
+    }
+}
@@ -9,4 +9,6 @@ pub use addr::{
     CSocketAddrFamily,
 };
 pub use options::{new_raw_socket_option, CSocketOptionLevel};
-pub use socket::{CUserMsgHdr, Protocol, SockFlags, SockType, SOCK_TYPE_MASK};
+pub use socket::{
+    CUserMmsgHdr, CUserMsgHdr, Protocol, SockFlags, SockType, MAX_MMSG_LENGTH, SOCK_TYPE_MASK,
+};
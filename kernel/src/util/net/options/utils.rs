@@ -5,9 +5,13 @@ use core::{num::NonZeroU8, time::Duration};
 use crate::{
     current_userspace,
     net::socket::{
-        ip::{options::IpTtl, stream_options::CongestionControl},
+        ip::{
+            options::{CIpMreqn, IpTtl},
+            stream_options::{CTcpInfo, CongestionControl},
+        },
+        packet::CPacketMreq,
         unix::CUserCred,
-        util::LingerOption,
+        util::{CSockFilter, LingerOption, SocketFilter},
     },
     prelude::*,
 };
@@ -119,6 +123,45 @@ impl WriteToUser for IpTtl {
     }
 }
 
+impl ReadFromUser for CIpMreqn {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        if (max_len as usize) >= size_of::<CIpMreqn>() {
+            return current_userspace!().read_val::<CIpMreqn>(addr);
+        }
+
+        // Like Linux, `struct ip_mreq` is accepted, and so is `struct in_addr` (which is only
+        // meaningful for `IP_MULTICAST_IF`).
+        // Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/net/ipv4/ip_sockglue.c>.
+        let mut mreqn = CIpMreqn::new_unspecified();
+        if (max_len as usize) >= size_of::<CIpMreq>() {
+            let mreq = current_userspace!().read_val::<CIpMreq>(addr)?;
+            mreqn.imr_multiaddr = mreq.imr_multiaddr;
+            mreqn.imr_address = mreq.imr_interface;
+        } else if (max_len as usize) >= size_of::<[u8; 4]>() {
+            mreqn.imr_address = current_userspace!().read_val::<[u8; 4]>(addr)?;
+        } else {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        Ok(mreqn)
+    }
+}
+
+impl WriteToUser for CIpMreqn {
+    fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
+        // Only the address of the local iface is reported, as a `struct in_addr`. This is the
+        // value of `IP_MULTICAST_IF`.
+        let write_len = size_of::<[u8; 4]>();
+
+        if (max_len as usize) < write_len {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        current_userspace!().write_val(addr, &self.imr_address)?;
+        Ok(write_len)
+    }
+}
+
 impl WriteToUser for Option<Error> {
     fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
         let write_len = size_of::<i32>();
@@ -180,7 +223,9 @@ impl ReadFromUser for CongestionControl {
         #[expect(clippy::useless_asref)]
         current_userspace!().read_bytes(addr, &mut VmWriter::from(dst.as_mut()))?;
 
-        let name = core::str::from_utf8(dst)
+        // The name is terminated by a NUL byte if it is shorter than `max_len`.
+        let name_len = dst.iter().position(|byte| *byte == 0).unwrap_or(dst.len());
+        let name = core::str::from_utf8(&dst[..name_len])
             .map_err(|_| Error::with_message(Errno::ENOENT, "non-UTF8 congestion name"))?;
         CongestionControl::new(name)
     }
@@ -202,6 +247,16 @@ impl WriteToUser for CongestionControl {
     }
 }
 
+/// A request to join or leave an IPv4 multicast group.
+///
+/// This corresponds to `struct ip_mreq` in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIpMreq {
+    imr_multiaddr: [u8; 4],
+    imr_interface: [u8; 4],
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod)]
 struct CLinger {
@@ -227,6 +282,59 @@ impl From<CLinger> for LingerOption {
     }
 }
 
+impl ReadFromUser for Arc<SocketFilter> {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        if (max_len as usize) < size_of::<CSockFprog>() {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        let c_fprog = current_userspace!().read_val::<CSockFprog>(addr)?;
+
+        let mut insns = Vec::with_capacity(c_fprog.len as usize);
+        for i in 0..c_fprog.len as usize {
+            let insn_addr = c_fprog.filter as usize + i * size_of::<CSockFilter>();
+            insns.push(current_userspace!().read_val::<CSockFilter>(insn_addr)?);
+        }
+
+        Ok(Arc::new(SocketFilter::new(insns)?))
+    }
+}
+
+impl ReadFromUser for CPacketMreq {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        if (max_len as usize) < size_of::<CPacketMreq>() {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        current_userspace!().read_val::<CPacketMreq>(addr)
+    }
+}
+
+/// A cBPF program.
+///
+/// This corresponds to `struct sock_fprog` in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CSockFprog {
+    /// The number of instructions.
+    len: u16,
+    _pad: [u8; 6],
+    /// The pointer to the instructions.
+    filter: u64,
+}
+
+impl WriteToUser for CTcpInfo {
+    fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
+        // Like Linux, the information is truncated if the buffer is too short.
+        let write_len = size_of::<CTcpInfo>().min(max_len as usize);
+
+        current_userspace!()
+            .write_bytes(addr, &mut VmReader::from(&self.as_bytes()[..write_len]))?;
+
+        Ok(write_len)
+    }
+}
+
 impl WriteToUser for CUserCred {
     fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
         let write_len = size_of::<CUserCred>();
@@ -243,6 +243,17 @@ impl RingBuffer<u8> {
         };
         consumer.read_fallible(writer)
     }
+
+    /// Peeks data from the `RingBuffer` to the `writer` without removing them.
+    ///
+    /// Returns the number of bytes peeked.
+    pub fn peek_fallible(&self, writer: &mut dyn MultiWrite) -> Result<usize> {
+        let consumer = Consumer {
+            rb: self,
+            phantom: PhantomData,
+        };
+        consumer.peek_fallible_with_max_len(writer, usize::MAX)
+    }
 }
 
 impl<T: Pod, R: Deref<Target = RingBuffer<T>>> Producer<T, R> {
@@ -433,6 +444,24 @@ impl<R: Deref<Target = RingBuffer<u8>>> Consumer<u8, R> {
         &mut self,
         writer: &mut dyn MultiWrite,
         max_len: usize,
+    ) -> Result<usize> {
+        let head = self.rb.head();
+        let read_len = self.peek_fallible_with_max_len(writer, max_len)?;
+
+        self.rb.advance_head(head, read_len);
+        Ok(read_len)
+    }
+
+    /// Peeks data from the `RingBuffer` to the `VmWriter` with the maximum length.
+    ///
+    /// Unlike [`Self::read_fallible_with_max_len`], the data will not be removed from the
+    /// `RingBuffer`.
+    ///
+    /// Returns the number of bytes peeked.
+    pub fn peek_fallible_with_max_len(
+        &self,
+        writer: &mut dyn MultiWrite,
+        max_len: usize,
     ) -> Result<usize> {
         let rb = &self.rb;
         let len = rb.len().min(max_len);
@@ -459,7 +488,6 @@ impl<R: Deref<Target = RingBuffer<u8>>> Consumer<u8, R> {
             writer.write(&mut reader)?
         };
 
-        rb.advance_head(head, read_len);
         Ok(read_len)
     }
 
@@ -605,6 +633,34 @@ mod test {
         assert!(prod.is_empty());
     }
 
+    #[ktest]
+    fn test_rb_peek() {
+        let rb = RingBuffer::<u8>::new(4);
+
+        let (mut prod, mut cons) = rb.split();
+        prod.push_slice(&[1, 2, 3]).unwrap();
+        cons.skip(2);
+        prod.push_slice(&[4, 5]).unwrap();
+
+        let mut output = [0u8; 2];
+        assert_eq!(
+            cons.peek_fallible_with_max_len(&mut writer_from(output.as_mut_slice()), 2)
+                .unwrap(),
+            2
+        );
+        assert_eq!(output, [3, 4]);
+        assert_eq!(cons.len(), 3);
+
+        let mut output = [0u8; 4];
+        assert_eq!(
+            cons.read_fallible(&mut writer_from(output.as_mut_slice()))
+                .unwrap(),
+            3
+        );
+        assert_eq!(output[..3], [3, 4, 5]);
+        assert!(cons.is_empty());
+    }
+
     fn reader_from(buf: &[u8]) -> VmReader {
         VmReader::from(buf).to_fallible()
     }
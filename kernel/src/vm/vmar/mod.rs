@@ -28,6 +28,7 @@ use self::{
 use super::page_fault_handler::PageFaultHandler;
 use crate::{
     fs::{file_handle::Mappable, ramfs::memfd::MemfdInode},
+    ipc::shm::ShmAttachment,
     prelude::*,
     process::{Process, ProcessVm, ResourceType},
     thread::exception::PageFaultInfo,
@@ -140,6 +141,48 @@ impl Vmar {
         Ok(())
     }
 
+    /// Changes whether the mapped pages in the range are excluded from core dumps.
+    ///
+    /// The mapped pages are updated even if some pages in the range are not mapped, in which
+    /// case an error is returned after the update.
+    pub fn set_dontdump(&self, is_dontdump: bool, range: Range<usize>) -> Result<()> {
+        assert!(range.start % PAGE_SIZE == 0);
+        assert!(range.end % PAGE_SIZE == 0);
+
+        let mut inner = self.inner.write();
+
+        let mut advised_mappings = Vec::new();
+
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            if vm_mapping.is_dontdump() != is_dontdump {
+                advised_mappings.push(vm_mapping.map_to_addr());
+            }
+        }
+
+        for vm_mapping_addr in advised_mappings {
+            let vm_mapping = inner.remove(&vm_mapping_addr).unwrap();
+            let vm_mapping_range = vm_mapping.range();
+            let intersected_range = get_intersected_range(&range, &vm_mapping_range);
+
+            let (left, taken, right) = vm_mapping.split_range(&intersected_range);
+
+            if let Some(left) = left {
+                inner.insert_without_try_merge(left);
+            }
+            if let Some(right) = right {
+                inner.insert_without_try_merge(right);
+            }
+
+            inner.insert_try_merge(taken.set_dontdump(is_dontdump));
+        }
+
+        if inner.count_overlap_size(range.clone()) != range.len() {
+            return_errno_with_message!(Errno::ENOMEM, "the range contains unmapped pages");
+        }
+
+        Ok(())
+    }
+
     /// Finds all the mapped regions that intersect with the specified range.
     pub fn query(&self, range: Range<usize>) -> VmarQueryGuard<'_> {
         VmarQueryGuard {
@@ -477,7 +520,8 @@ impl Vmar {
             reader.read_fallible(writer)
         };
 
-        self.access_remote(vaddr, len, PageFlags::R, read)
+        let query = |vaddr| self.query_page_with_required_flags(vaddr, PageFlags::R);
+        self.access_remote(vaddr, len, query, read)
     }
 
     /// Writes memory to the process user space.
@@ -502,22 +546,67 @@ impl Vmar {
             writer.write_fallible(reader)
         };
 
-        self.access_remote(vaddr, len, PageFlags::W, write)
+        let query = |vaddr| self.query_page_with_required_flags(vaddr, PageFlags::W);
+        self.access_remote(vaddr, len, query, write)
+    }
+
+    /// Writes memory to the process user space, even if the memory is read-only.
+    ///
+    /// This method works like [`Self::write_remote`], except that private read-only mappings can
+    /// also be written to. The pages will be copied on write but remain read-only to the process.
+    /// This is useful for tracers to insert breakpoints into the code.
+    ///
+    /// The `VmSpace` of the process is not required be activated on the current CPU.
+    pub fn force_write_remote(
+        &self,
+        vaddr: Vaddr,
+        reader: &mut VmReader,
+    ) -> core::result::Result<usize, (Error, usize)> {
+        let len = reader.remain();
+        let write = |frame: UFrame, skip_offset: usize| {
+            let mut writer = frame.writer();
+            writer.skip(skip_offset);
+            writer.write_fallible(reader)
+        };
+
+        let query = |vaddr| self.query_page_for_forced_write(vaddr);
+        self.access_remote(vaddr, len, query, write)
+    }
+
+    /// Reads a page from the process user space if the page is present in memory.
+    ///
+    /// Unlike [`Self::read_remote`], this method will not handle page faults. If the page has
+    /// never been accessed (or is not backed by RAM), `Ok(false)` is returned and nothing is
+    /// written to the writer.
+    ///
+    /// The `VmSpace` of the process is not required be activated on the current CPU.
+    pub fn read_present_page(&self, vaddr: Vaddr, writer: &mut VmWriter) -> Result<bool> {
+        let Some(VmQueriedItem::MappedRam { frame, .. }) = self.query_page(vaddr)? else {
+            return Ok(false);
+        };
+
+        frame
+            .reader()
+            .read_fallible(writer)
+            .map_err(|(err, _)| Error::from(err))?;
+
+        Ok(true)
     }
 
     /// Accesses memory at `vaddr..vaddr+len` within the process user space using `op`.
     ///
     /// The `VmSpace` of the process is not required be activated on the current CPU.
-    /// If any page in the range is not mapped or does not have the required page
-    /// flags, a page fault will be handled to try to make the page accessible.
-    fn access_remote<F>(
+    /// The frame of each page in the range is obtained with `query`, which should try to make
+    /// the page accessible if it is not mapped or does not have the required page flags.
+    fn access_remote<Q, F>(
         &self,
         vaddr: Vaddr,
         len: usize,
-        required_page_flags: PageFlags,
+        mut query: Q,
         mut op: F,
     ) -> core::result::Result<usize, (Error, usize)>
     where
+        Q: FnMut(Vaddr) -> Result<UFrame>,
         F: FnMut(UFrame, usize) -> core::result::Result<usize, (ostd::Error, usize)>,
     {
         if len == 0 {
@@ -530,9 +619,7 @@ impl Vmar {
         let mut bytes = 0;
 
         while current_va < range.end {
-            let frame = self
-                .query_page_with_required_flags(current_va, required_page_flags)
-                .map_err(|err| (err, bytes))?;
+            let frame = query(current_va).map_err(|err| (err, bytes))?;
 
             let skip_offset = if current_va == range.start {
                 vaddr - range.start
@@ -590,6 +677,23 @@ impl Vmar {
         }
     }
 
+    fn query_page_for_forced_write(&self, vaddr: Vaddr) -> Result<UFrame> {
+        let inner = self.inner.read();
+
+        let Some(vm_mapping) = inner.vm_mappings.find_one(&vaddr) else {
+            return_errno_with_message!(Errno::EIO, "the page is not mapped");
+        };
+        if vm_mapping.perms().contains(VmPerms::WRITE) {
+            drop(inner);
+            return self.query_page_with_required_flags(vaddr, PageFlags::W);
+        }
+
+        let mut rss_delta = RssDelta::new(self);
+        vm_mapping
+            .prepare_forced_write(&self.vm_space, vaddr, &mut rss_delta)
+            .map_err(|_| Error::with_message(Errno::EIO, "the page cannot be written by force"))
+    }
+
     fn query_page(&self, vaddr: Vaddr) -> Result<Option<VmQueriedItem>> {
         debug_assert!(is_userspace_vaddr(vaddr) && vaddr % PAGE_SIZE == 0);
 
@@ -951,6 +1055,7 @@ pub struct VmarMapOptions<'a> {
     parent: &'a Vmar,
     vmo: Option<Arc<Vmo>>,
     mappable: Option<Mappable>,
+    shm_attachment: Option<ShmAttachment>,
     perms: VmPerms,
     may_perms: VmPerms,
     vmo_offset: usize,
@@ -972,6 +1077,7 @@ impl<'a> VmarMapOptions<'a> {
             parent,
             vmo: None,
             mappable: None,
+            shm_attachment: None,
             perms,
             may_perms: VmPerms::ALL_MAY_PERMS,
             vmo_offset: 0,
@@ -1025,6 +1131,17 @@ impl<'a> VmarMapOptions<'a> {
         self
     }
 
+    /// Marks the mapping as an attachment of a System V shared memory segment.
+    ///
+    /// The mapping must be backed by the VMO of the segment, which is set via
+    /// [`Self::vmo`]. The attachment is duplicated whenever the mapping is
+    /// duplicated (e.g., by forking or splitting), and dropped whenever the
+    /// mapping is unmapped.
+    pub fn shm_attachment(mut self, attachment: ShmAttachment) -> Self {
+        self.shm_attachment = Some(attachment);
+        self
+    }
+
     /// Sets the offset of the first memory page in the VMO that is to be
     /// mapped into the VMAR.
     ///
@@ -1126,6 +1243,7 @@ impl<'a> VmarMapOptions<'a> {
             parent,
             vmo,
             mappable,
+            shm_attachment,
             perms,
             mut may_perms,
             vmo_offset,
@@ -1199,6 +1317,7 @@ impl<'a> VmarMapOptions<'a> {
                         vmo.unwrap(),
                         vmo_offset,
                         is_writable_tracked,
+                        None,
                     )?);
                     (mapped_mem, Some(inode), None)
                 }
@@ -1206,7 +1325,7 @@ impl<'a> VmarMapOptions<'a> {
             }
         } else if let Some(vmo) = vmo {
             (
-                MappedMemory::Vmo(MappedVmo::new(vmo, vmo_offset, false)?),
+                MappedMemory::Vmo(MappedVmo::new(vmo, vmo_offset, false, shm_attachment)?),
                 None,
                 None,
             )
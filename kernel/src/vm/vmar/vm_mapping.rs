@@ -19,6 +19,7 @@ use ostd::{
 use super::{interval_set::Interval, RssDelta, RssType};
 use crate::{
     fs::utils::Inode,
+    ipc::shm::ShmAttachment,
     prelude::*,
     thread::exception::PageFaultInfo,
     vm::{
@@ -80,6 +81,8 @@ pub struct VmMapping {
     ///
     /// All pages within the same `VmMapping` have the same permissions.
     perms: VmPerms,
+    /// Whether the mapping is excluded from core dumps (i.e., `MADV_DONTDUMP`).
+    is_dontdump: bool,
 }
 
 impl Interval<Vaddr> for VmMapping {
@@ -108,6 +111,7 @@ impl VmMapping {
             is_shared,
             handle_page_faults_around,
             perms,
+            is_dontdump: false,
         }
     }
 
@@ -158,6 +162,23 @@ impl VmMapping {
         }
     }
 
+    /// Returns the mapped VMO and the offset in it if this mapping is VMO-backed.
+    pub fn mapped_vmo(&self) -> Option<(&Arc<Vmo>, usize)> {
+        self.vmo().map(|vmo| (vmo.vmo(), vmo.offset()))
+    }
+
+    /// Returns whether the mapping should be excluded from core dumps.
+    ///
+    /// Device mappings are always excluded, since reading them may have side effects.
+    pub fn is_dontdump(&self) -> bool {
+        self.is_dontdump || matches!(self.mapped_mem, MappedMemory::Device)
+    }
+
+    /// Returns whether the mapping is shared.
+    pub fn is_shared(&self) -> bool {
+        self.is_shared
+    }
+
     /// Returns the mapping's RSS type.
     pub fn rss_type(&self) -> RssType {
         match &self.mapped_mem {
@@ -370,6 +391,80 @@ impl VmMapping {
         Ok(())
     }
 
+    /// Prepares a page for a forced write access, returning the frame to write to.
+    ///
+    /// Unlike page faults, this succeeds even if the mapping is not writable, as long as the
+    /// mapping is a COW mapping. In this case, the page is copied on write but remains
+    /// read-only to the process. This is what `FOLL_FORCE` does in Linux (see `check_vma_flags`),
+    /// e.g., to allow tracers to insert breakpoints into the code.
+    pub(super) fn prepare_forced_write(
+        &self,
+        vm_space: &VmSpace,
+        page_aligned_addr: Vaddr,
+        rss_delta: &mut RssDelta,
+    ) -> Result<UFrame> {
+        if !self.is_cow() {
+            return_errno_with_message!(
+                Errno::EFAULT,
+                "forced writes to non-COW mappings are not allowed"
+            );
+        }
+
+        'retry: loop {
+            let preempt_guard = disable_preempt();
+            let mut cursor = vm_space.cursor_mut(
+                &preempt_guard,
+                &(page_aligned_addr..page_aligned_addr + PAGE_SIZE),
+            )?;
+
+            let (va, item) = cursor.query().unwrap();
+            let frame = match item {
+                Some(VmQueriedItem::MappedRam { frame, prop }) => {
+                    // If we are the only reference to the frame (see `handle_single_page_fault`
+                    // for details), the frame is private and can be written to directly.
+                    if prop.flags.contains(PageFlags::W) || frame.reference_count() == 2 {
+                        return Ok(frame);
+                    }
+
+                    let new_frame: UFrame = duplicate_frame(&frame)?.into();
+                    cursor.map(new_frame.clone(), prop);
+                    cursor.flusher().issue_tlb_flush(TlbFlushOp::for_range(va));
+                    cursor.flusher().dispatch_tlb_flush();
+                    cursor.flusher().sync_tlb_flush();
+                    rss_delta.add(self.rss_type(), 1);
+                    new_frame
+                }
+                Some(VmQueriedItem::MappedIoMem { .. }) => {
+                    return_errno_with_message!(
+                        Errno::EFAULT,
+                        "device memory cannot be written by force"
+                    );
+                }
+                None => {
+                    let frame = match self.prepare_page(page_aligned_addr, true) {
+                        Ok((frame, _)) => frame,
+                        Err(VmoCommitError::Err(e)) => return Err(e),
+                        Err(VmoCommitError::NeedIo(index)) => {
+                            drop(cursor);
+                            drop(preempt_guard);
+                            self.vmo().unwrap().commit_on(index, CommitFlags::empty())?;
+                            continue 'retry;
+                        }
+                    };
+
+                    let page_flags = PageFlags::from(self.perms) | PageFlags::ACCESSED;
+                    let map_prop = PageProperty::new_user(page_flags, CachePolicy::Writeback);
+
+                    cursor.map(frame.clone(), map_prop);
+                    rss_delta.add(self.rss_type(), 1);
+                    frame
+                }
+            };
+
+            return Ok(frame);
+        }
+    }
+
     fn prepare_page(
         &self,
         page_aligned_addr: Vaddr,
@@ -655,6 +750,14 @@ impl VmMapping {
 
         Self { perms, ..self }
     }
+
+    /// Changes whether the mapping is excluded from core dumps.
+    pub(super) fn set_dontdump(self, is_dontdump: bool) -> Self {
+        Self {
+            is_dontdump,
+            ..self
+        }
+    }
 }
 
 /// Memory mapped by a [`VmMapping`].
@@ -699,11 +802,19 @@ pub(super) struct MappedVmo {
     /// Whether the VMO's writable mappings need to be tracked, and the
     /// mapping is writable to the VMO.
     is_writable_tracked: bool,
+    /// The attachment of the System V shared memory segment, if the VMO
+    /// belongs to a segment.
+    shm_attachment: Option<ShmAttachment>,
 }
 
 impl MappedVmo {
     /// Creates a `MappedVmo` used for the mapping.
-    pub(super) fn new(vmo: Arc<Vmo>, offset: usize, is_writable_tracked: bool) -> Result<Self> {
+    pub(super) fn new(
+        vmo: Arc<Vmo>,
+        offset: usize,
+        is_writable_tracked: bool,
+        shm_attachment: Option<ShmAttachment>,
+    ) -> Result<Self> {
         if is_writable_tracked {
             vmo.writable_mapping_status().map()?;
         }
@@ -712,6 +823,7 @@ impl MappedVmo {
             vmo,
             offset,
             is_writable_tracked,
+            shm_attachment,
         })
     }
 
@@ -790,6 +902,7 @@ impl MappedVmo {
             vmo: self.vmo.clone(),
             offset,
             is_writable_tracked: self.is_writable_tracked,
+            shm_attachment: self.shm_attachment.clone(),
         }
     }
 }
@@ -813,7 +926,8 @@ fn try_merge(left: &VmMapping, right: &VmMapping) -> Option<VmMapping> {
     let is_adjacent = left.map_end() == right.map_to_addr();
     let is_type_equal = left.is_shared == right.is_shared
         && left.handle_page_faults_around == right.handle_page_faults_around
-        && left.perms == right.perms;
+        && left.perms == right.perms
+        && left.is_dontdump == right.is_dontdump;
 
     if !is_adjacent || !is_type_equal {
         return None;
@@ -828,6 +942,12 @@ fn try_merge(left: &VmMapping, right: &VmMapping) -> Option<VmMapping> {
             let l_vmo = l_vmo_obj.vmo();
             let r_vmo = r_vmo_obj.vmo();
 
+            // Like Linux, mappings of System V shared memory segments are never merged, since
+            // each mapping is counted as an attachment of the segment.
+            if l_vmo_obj.shm_attachment.is_some() || r_vmo_obj.shm_attachment.is_some() {
+                return None;
+            }
+
             if Arc::ptr_eq(l_vmo, r_vmo) {
                 let is_offset_contiguous =
                     l_vmo_obj.offset() + left.map_size() == r_vmo_obj.offset();
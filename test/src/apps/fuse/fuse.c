@@ -0,0 +1,387 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+#include <dirent.h>
+#include <linux/fuse.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/statfs.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <sys/xattr.h>
+
+#include "../test.h"
+
+#define MNT_DIR "/fuse_mnt"
+#define FILE_NAME "hello"
+#define FILE_PATH MNT_DIR "/" FILE_NAME
+#define FILE_NODEID 2
+#define FUSE_SUPER_MAGIC 0x65735546
+
+// The daemon below serves a file system with a single regular file in the root
+// directory. All the attributes and the entries expire immediately, so every
+// operation reaches the daemon.
+
+static char file_data[8192] = "Hello, FUSE!\n";
+static size_t file_size = 13;
+
+static char in_buf[FUSE_MIN_READ_BUFFER + 65536];
+static char out_buf[sizeof(struct fuse_out_header) + 8192];
+
+static void reply(int fd, uint64_t unique, int error, const void *data,
+		  size_t len)
+{
+	struct fuse_out_header *header = (struct fuse_out_header *)out_buf;
+
+	header->len = sizeof(*header) + len;
+	header->error = error;
+	header->unique = unique;
+	if (len > 0)
+		memmove(out_buf + sizeof(*header), data, len);
+
+	// The daemon fails if any reply is rejected.
+	if (write(fd, out_buf, header->len) != header->len)
+		_exit(EXIT_FAILURE);
+}
+
+static void fill_attr(uint64_t nodeid, struct fuse_attr *attr)
+{
+	memset(attr, 0, sizeof(*attr));
+	attr->ino = nodeid;
+	attr->blksize = 4096;
+
+	if (nodeid == FUSE_ROOT_ID) {
+		attr->mode = S_IFDIR | 0755;
+		attr->nlink = 2;
+	} else {
+		attr->mode = S_IFREG | 0644;
+		attr->nlink = 1;
+		attr->size = file_size;
+	}
+}
+
+static size_t fill_dirents(char *buf, size_t size, uint64_t offset)
+{
+	static const char *names[] = { ".", "..", FILE_NAME };
+	static const uint64_t inos[] = { FUSE_ROOT_ID, FUSE_ROOT_ID,
+					 FILE_NODEID };
+	size_t len = 0;
+
+	for (uint64_t i = offset; i < 3; i++) {
+		struct fuse_dirent *dirent = (struct fuse_dirent *)(buf + len);
+		size_t namelen = strlen(names[i]);
+		size_t entlen = FUSE_DIRENT_ALIGN(FUSE_NAME_OFFSET + namelen);
+
+		if (len + entlen > size)
+			break;
+
+		memset(dirent, 0, entlen);
+		dirent->ino = inos[i];
+		dirent->off = i + 1;
+		dirent->namelen = namelen;
+		dirent->type = i < 2 ? DT_DIR : DT_REG;
+		memcpy(dirent->name, names[i], namelen);
+		len += entlen;
+	}
+
+	return len;
+}
+
+static void serve(int fd)
+{
+	char data[8192];
+
+	for (;;) {
+		ssize_t len = read(fd, in_buf, sizeof(in_buf));
+		if (len < 0 && errno == EINTR)
+			continue;
+		// The connection is aborted after the file system is unmounted.
+		if (len < 0)
+			break;
+
+		struct fuse_in_header *in = (struct fuse_in_header *)in_buf;
+		void *arg = in_buf + sizeof(*in);
+
+		switch (in->opcode) {
+		case FUSE_INIT: {
+			struct fuse_init_out init_out = {
+				.major = FUSE_KERNEL_VERSION,
+				.minor = 31,
+				.max_readahead = 4096,
+				.max_write = 4096,
+			};
+			reply(fd, in->unique, 0, &init_out, sizeof(init_out));
+			break;
+		}
+		case FUSE_LOOKUP: {
+			struct fuse_entry_out entry_out = {
+				.nodeid = FILE_NODEID,
+			};
+			if (in->nodeid != FUSE_ROOT_ID ||
+			    strcmp(arg, FILE_NAME) != 0) {
+				reply(fd, in->unique, -ENOENT, NULL, 0);
+				break;
+			}
+			fill_attr(FILE_NODEID, &entry_out.attr);
+			reply(fd, in->unique, 0, &entry_out, sizeof(entry_out));
+			break;
+		}
+		case FUSE_SETATTR: {
+			struct fuse_setattr_in *setattr_in = arg;
+			struct fuse_attr_out attr_out = {};
+			if ((setattr_in->valid & FATTR_SIZE) &&
+			    setattr_in->size > sizeof(file_data)) {
+				reply(fd, in->unique, -EFBIG, NULL, 0);
+				break;
+			}
+			if (setattr_in->valid & FATTR_SIZE) {
+				if (setattr_in->size > file_size)
+					memset(file_data + file_size, 0,
+					       setattr_in->size - file_size);
+				file_size = setattr_in->size;
+			}
+			fill_attr(in->nodeid, &attr_out.attr);
+			reply(fd, in->unique, 0, &attr_out, sizeof(attr_out));
+			break;
+		}
+		case FUSE_GETATTR: {
+			struct fuse_attr_out attr_out = {};
+			fill_attr(in->nodeid, &attr_out.attr);
+			reply(fd, in->unique, 0, &attr_out, sizeof(attr_out));
+			break;
+		}
+		case FUSE_OPEN:
+		case FUSE_OPENDIR: {
+			struct fuse_open_out open_out = {};
+			reply(fd, in->unique, 0, &open_out, sizeof(open_out));
+			break;
+		}
+		case FUSE_READ: {
+			struct fuse_read_in *read_in = arg;
+			size_t size = 0;
+			if (read_in->offset < file_size)
+				size = file_size - read_in->offset;
+			if (size > read_in->size)
+				size = read_in->size;
+			if (size > sizeof(data))
+				size = sizeof(data);
+			memcpy(data, file_data + read_in->offset, size);
+			reply(fd, in->unique, 0, data, size);
+			break;
+		}
+		case FUSE_WRITE: {
+			struct fuse_write_in *write_in = arg;
+			struct fuse_write_out write_out = {
+				.size = write_in->size,
+			};
+			if (write_in->offset + write_in->size >
+			    sizeof(file_data)) {
+				reply(fd, in->unique, -EFBIG, NULL, 0);
+				break;
+			}
+			memcpy(file_data + write_in->offset, write_in + 1,
+			       write_in->size);
+			if (write_in->offset + write_in->size > file_size)
+				file_size = write_in->offset + write_in->size;
+			reply(fd, in->unique, 0, &write_out, sizeof(write_out));
+			break;
+		}
+		case FUSE_READDIR: {
+			struct fuse_read_in *read_in = arg;
+			size_t size = read_in->size;
+			if (size > sizeof(data))
+				size = sizeof(data);
+			size = fill_dirents(data, size, read_in->offset);
+			reply(fd, in->unique, 0, data, size);
+			break;
+		}
+		case FUSE_STATFS: {
+			struct fuse_statfs_out statfs_out = {};
+			statfs_out.st.bsize = 4096;
+			statfs_out.st.namelen = 255;
+			statfs_out.st.frsize = 4096;
+			reply(fd, in->unique, 0, &statfs_out,
+			      sizeof(statfs_out));
+			break;
+		}
+		case FUSE_RELEASE:
+		case FUSE_RELEASEDIR:
+			reply(fd, in->unique, 0, NULL, 0);
+			break;
+		case FUSE_FORGET:
+		case FUSE_INTERRUPT:
+			// No replies are expected.
+			break;
+		default:
+			reply(fd, in->unique, -ENOSYS, NULL, 0);
+			break;
+		}
+	}
+}
+
+static int fuse_fd;
+static pid_t daemon_pid;
+
+FN_SETUP(open_dev)
+{
+	CHECK_WITH(mkdir(MNT_DIR, 0755), _ret >= 0 || errno == EEXIST);
+	fuse_fd = CHECK(open("/dev/fuse", O_RDWR));
+}
+END_SETUP()
+
+FN_TEST(mount_options)
+{
+	char options[128];
+	int null_fd;
+
+	// The file descriptor is missing.
+	TEST_ERRNO(mount("fuse", MNT_DIR, "fuse", 0,
+			 "rootmode=40000,user_id=0,group_id=0"),
+		   EINVAL);
+
+	// The file descriptor is not an opened `/dev/fuse`.
+	null_fd = TEST_SUCC(open("/dev/null", O_RDWR));
+	snprintf(options, sizeof(options),
+		 "fd=%d,rootmode=40000,user_id=0,group_id=0", null_fd);
+	TEST_ERRNO(mount("fuse", MNT_DIR, "fuse", 0, options), EINVAL);
+	TEST_SUCC(close(null_fd));
+
+	// The root is not a directory.
+	snprintf(options, sizeof(options),
+		 "fd=%d,rootmode=100000,user_id=0,group_id=0", fuse_fd);
+	TEST_ERRNO(mount("fuse", MNT_DIR, "fuse", 0, options), EINVAL);
+
+	// The option is unknown.
+	snprintf(options, sizeof(options),
+		 "fd=%d,rootmode=40000,user_id=0,group_id=0,foo", fuse_fd);
+	TEST_ERRNO(mount("fuse", MNT_DIR, "fuse", 0, options), EINVAL);
+}
+END_TEST()
+
+FN_TEST(read_requests_before_mount)
+{
+	// The buffer is too small to hold any request.
+	TEST_ERRNO(read(fuse_fd, in_buf, FUSE_MIN_READ_BUFFER - 1), EINVAL);
+	TEST_ERRNO(lseek(fuse_fd, 0, SEEK_SET), ESPIPE);
+}
+END_TEST()
+
+FN_SETUP(mount)
+{
+	char options[128];
+
+	snprintf(options, sizeof(options),
+		 "fd=%d,rootmode=40000,user_id=0,group_id=0", fuse_fd);
+	CHECK(mount("fuse", MNT_DIR, "fuse", 0, options));
+
+	daemon_pid = CHECK(fork());
+	if (daemon_pid == 0) {
+		serve(fuse_fd);
+		_exit(EXIT_SUCCESS);
+	}
+
+	// The requests are served by the daemon.
+	CHECK(close(fuse_fd));
+}
+END_SETUP()
+
+FN_TEST(stat)
+{
+	struct stat stat_buf;
+	struct statfs statfs_buf;
+
+	TEST_RES(stat(MNT_DIR, &stat_buf),
+		 S_ISDIR(stat_buf.st_mode) && stat_buf.st_nlink == 2);
+	TEST_RES(stat(FILE_PATH, &stat_buf),
+		 S_ISREG(stat_buf.st_mode) &&
+			 (stat_buf.st_mode & 0777) == 0644 &&
+			 stat_buf.st_ino == FILE_NODEID &&
+			 stat_buf.st_size == 13);
+	TEST_ERRNO(stat(MNT_DIR "/no_such_file", &stat_buf), ENOENT);
+	TEST_ERRNO(stat(FILE_PATH "/no_such_file", &stat_buf), ENOTDIR);
+
+	TEST_RES(statfs(MNT_DIR, &statfs_buf),
+		 statfs_buf.f_type == FUSE_SUPER_MAGIC &&
+			 statfs_buf.f_namelen == 255);
+}
+END_TEST()
+
+FN_TEST(readdir)
+{
+	char buf[1024];
+	int fd, found = 0;
+	long len, pos;
+
+	fd = TEST_SUCC(open(MNT_DIR, O_RDONLY | O_DIRECTORY));
+
+	len = TEST_RES(syscall(SYS_getdents64, fd, buf, sizeof(buf)), _ret > 0);
+	for (pos = 0; pos < len;) {
+		struct dirent64 *dirent = (struct dirent64 *)(buf + pos);
+		if (strcmp(dirent->d_name, FILE_NAME) == 0 &&
+		    dirent->d_ino == FILE_NODEID && dirent->d_type == DT_REG)
+			found = 1;
+		pos += dirent->d_reclen;
+	}
+	TEST_RES(found, found == 1);
+
+	// All the entries have been read.
+	TEST_RES(syscall(SYS_getdents64, fd, buf, sizeof(buf)), _ret == 0);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(read_write)
+{
+	char buf[64];
+	int fd;
+
+	fd = TEST_SUCC(open(FILE_PATH, O_RDWR));
+
+	TEST_RES(read(fd, buf, sizeof(buf)),
+		 _ret == 13 && memcmp(buf, "Hello, FUSE!\n", 13) == 0);
+	TEST_RES(read(fd, buf, sizeof(buf)), _ret == 0);
+
+	TEST_RES(pwrite(fd, "fuse", 4, 7), _ret == 4);
+	TEST_RES(pread(fd, buf, sizeof(buf), 0),
+		 _ret == 13 && memcmp(buf, "Hello, fuse!\n", 13) == 0);
+
+	TEST_RES(pwrite(fd, "Bye!\n", 5, 13), _ret == 5);
+	TEST_RES(lseek(fd, 0, SEEK_END), _ret == 18);
+	TEST_RES(pread(fd, buf, sizeof(buf), 0),
+		 _ret == 18 && memcmp(buf, "Hello, fuse!\nBye!\n", 18) == 0);
+
+	TEST_SUCC(ftruncate(fd, 5));
+	TEST_RES(pread(fd, buf, sizeof(buf), 0),
+		 _ret == 5 && memcmp(buf, "Hello", 5) == 0);
+
+	TEST_SUCC(close(fd));
+
+	fd = TEST_SUCC(open(MNT_DIR, O_RDONLY));
+	TEST_ERRNO(read(fd, buf, sizeof(buf)), EISDIR);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(unsupported)
+{
+	// The daemon does not implement these operations.
+	TEST_ERRNO(unlink(FILE_PATH), ENOSYS);
+	TEST_ERRNO(mkdir(MNT_DIR "/dir", 0755), ENOSYS);
+	TEST_ERRNO(getxattr(FILE_PATH, "user.foo", NULL, 0), EOPNOTSUPP);
+}
+END_TEST()
+
+FN_SETUP(umount)
+{
+	int status;
+
+	CHECK(umount(MNT_DIR));
+
+	// The daemon exits once the connection is aborted.
+	CHECK_WITH(waitpid(daemon_pid, &status, 0),
+		   _ret == daemon_pid && WIFEXITED(status) &&
+			   WEXITSTATUS(status) == EXIT_SUCCESS);
+	CHECK(rmdir(MNT_DIR));
+}
+END_SETUP()
@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <limits.h>
+#include <string.h>
+#include <unistd.h>
+#include <sys/inotify.h>
+#include <sys/ioctl.h>
+#include <sys/stat.h>
+
+#include "../test.h"
+
+#define TEST_DIR "/tmp/inotify_test"
+#define TEST_FILE TEST_DIR "/file"
+#define TEST_FILE2 TEST_DIR "/file2"
+
+#define EVENT_BUF_LEN (sizeof(struct inotify_event) + NAME_MAX + 1)
+
+static int inotify_fd;
+static int dir_wd;
+static char event_buf[EVENT_BUF_LEN * 8] __attribute__((aligned(8)));
+
+FN_SETUP(init)
+{
+	CHECK(mkdir(TEST_DIR, 0755));
+	inotify_fd = CHECK(inotify_init1(IN_NONBLOCK | IN_CLOEXEC));
+}
+END_SETUP()
+
+static struct inotify_event *read_one_event(void)
+{
+	int len = read(inotify_fd, event_buf, sizeof(event_buf));
+	if (len < (int)sizeof(struct inotify_event))
+		return NULL;
+	return (struct inotify_event *)event_buf;
+}
+
+FN_TEST(invalid_args)
+{
+	TEST_ERRNO(inotify_init1(~(IN_NONBLOCK | IN_CLOEXEC)), EINVAL);
+	TEST_ERRNO(inotify_add_watch(inotify_fd, TEST_DIR, 0), EINVAL);
+	TEST_ERRNO(inotify_add_watch(inotify_fd, TEST_DIR,
+				     IN_CREATE | IN_MASK_ADD | IN_MASK_CREATE),
+		   EINVAL);
+	TEST_ERRNO(inotify_add_watch(inotify_fd, "/nonexistent", IN_CREATE),
+		   ENOENT);
+	TEST_ERRNO(inotify_add_watch(STDIN_FILENO, TEST_DIR, IN_CREATE),
+		   EINVAL);
+	TEST_ERRNO(inotify_rm_watch(inotify_fd, 12345), EINVAL);
+	TEST_ERRNO(read(inotify_fd, event_buf, sizeof(event_buf)), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(add_watch)
+{
+	dir_wd = TEST_RES(inotify_add_watch(inotify_fd, TEST_DIR,
+					    IN_CREATE | IN_DELETE | IN_MOVE),
+			  _ret > 0);
+	TEST_RES(inotify_add_watch(inotify_fd, TEST_DIR, IN_CREATE),
+		 _ret == dir_wd);
+	TEST_ERRNO(inotify_add_watch(inotify_fd, TEST_DIR,
+				     IN_CREATE | IN_MASK_CREATE),
+		   EEXIST);
+	TEST_RES(inotify_add_watch(inotify_fd, TEST_DIR,
+				   IN_DELETE | IN_MOVE | IN_MASK_ADD),
+		 _ret == dir_wd);
+}
+END_TEST()
+
+FN_TEST(create_event)
+{
+	struct inotify_event *event;
+	int fd, avail;
+
+	fd = TEST_SUCC(open(TEST_FILE, O_CREAT | O_WRONLY, 0644));
+	TEST_SUCC(close(fd));
+
+	TEST_RES(ioctl(inotify_fd, FIONREAD, &avail),
+		 avail >= (int)sizeof(struct inotify_event));
+	TEST_ERRNO(read(inotify_fd, event_buf, 1), EINVAL);
+
+	event = read_one_event();
+	TEST_RES(event != NULL, _ret);
+	if (event != NULL) {
+		TEST_RES(event->wd, _ret == dir_wd);
+		TEST_RES(event->mask, _ret == IN_CREATE);
+		TEST_RES(event->len, _ret > 0 && _ret % 16 == 0);
+		TEST_RES(strcmp(event->name, "file"), _ret == 0);
+	}
+	TEST_ERRNO(read(inotify_fd, event_buf, sizeof(event_buf)), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(move_event)
+{
+	struct inotify_event *from, *to;
+	int len;
+
+	TEST_SUCC(rename(TEST_FILE, TEST_FILE2));
+
+	len = TEST_RES(read(inotify_fd, event_buf, sizeof(event_buf)),
+		       _ret >= 2 * (int)sizeof(struct inotify_event));
+	if (len >= 2 * (int)sizeof(struct inotify_event)) {
+		from = (struct inotify_event *)event_buf;
+		to = (struct inotify_event *)(event_buf +
+					      sizeof(struct inotify_event) +
+					      from->len);
+		TEST_RES(from->mask, _ret == IN_MOVED_FROM);
+		TEST_RES(strcmp(from->name, "file"), _ret == 0);
+		TEST_RES(to->mask, _ret == IN_MOVED_TO);
+		TEST_RES(strcmp(to->name, "file2"), _ret == 0);
+		TEST_RES(from->cookie, _ret != 0 && _ret == to->cookie);
+	}
+}
+END_TEST()
+
+FN_TEST(file_watch)
+{
+	struct inotify_event *event;
+	int file_wd, fd;
+
+	file_wd = TEST_RES(inotify_add_watch(inotify_fd, TEST_FILE2,
+					     IN_MODIFY | IN_CLOSE_WRITE |
+						     IN_ONESHOT),
+			   _ret > 0 && _ret != dir_wd);
+	TEST_ERRNO(inotify_add_watch(inotify_fd, TEST_FILE2,
+				     IN_MODIFY | IN_ONLYDIR),
+		   ENOTDIR);
+
+	fd = TEST_SUCC(open(TEST_FILE2, O_WRONLY));
+	TEST_RES(write(fd, "hello", 5), _ret == 5);
+	TEST_SUCC(close(fd));
+
+	// The watch is one-shot, so only the first event and `IN_IGNORED` are
+	// reported.
+	event = read_one_event();
+	TEST_RES(event != NULL, _ret);
+	if (event != NULL) {
+		TEST_RES(event->wd, _ret == file_wd);
+		TEST_RES(event->mask, _ret == IN_MODIFY);
+		event = (struct inotify_event *)(event_buf +
+						 sizeof(struct inotify_event) +
+						 event->len);
+		TEST_RES(event->wd, _ret == file_wd);
+		TEST_RES(event->mask, _ret == IN_IGNORED);
+	}
+	TEST_ERRNO(inotify_rm_watch(inotify_fd, file_wd), EINVAL);
+}
+END_TEST()
+
+FN_TEST(delete_event)
+{
+	struct inotify_event *event;
+
+	TEST_SUCC(unlink(TEST_FILE2));
+
+	event = read_one_event();
+	TEST_RES(event != NULL, _ret);
+	if (event != NULL) {
+		TEST_RES(event->wd, _ret == dir_wd);
+		TEST_RES(event->mask, _ret == IN_DELETE);
+		TEST_RES(strcmp(event->name, "file2"), _ret == 0);
+	}
+}
+END_TEST()
+
+FN_TEST(rm_watch)
+{
+	struct inotify_event *event;
+
+	TEST_SUCC(inotify_rm_watch(inotify_fd, dir_wd));
+
+	event = read_one_event();
+	TEST_RES(event != NULL, _ret);
+	if (event != NULL) {
+		TEST_RES(event->wd, _ret == dir_wd);
+		TEST_RES(event->mask, _ret == IN_IGNORED);
+	}
+
+	TEST_ERRNO(inotify_rm_watch(inotify_fd, dir_wd), EINVAL);
+	TEST_SUCC(rmdir(TEST_DIR));
+	TEST_ERRNO(read(inotify_fd, event_buf, sizeof(event_buf)), EAGAIN);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(inotify_fd));
+}
+END_SETUP()
@@ -0,0 +1,345 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <mqueue.h>
+#include <poll.h>
+#include <signal.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include <linux/netlink.h>
+#include <sys/epoll.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+
+#include "../test.h"
+
+#define QUEUE_NAME "/asterinas_mq_test"
+#define QUEUE_PATH "/dev/mqueue/asterinas_mq_test"
+#define MAX_MSGS 4
+#define MSG_SIZE 32
+
+static mqd_t mqd;
+
+FN_SETUP(create)
+{
+	struct mq_attr attr = { .mq_maxmsg = MAX_MSGS, .mq_msgsize = MSG_SIZE };
+
+	mq_unlink(QUEUE_NAME);
+	mqd = CHECK(mq_open(QUEUE_NAME, O_RDWR | O_CREAT | O_EXCL, 0600,
+			    &attr));
+}
+END_SETUP()
+
+FN_TEST(open)
+{
+	struct mq_attr attr = { .mq_maxmsg = 0, .mq_msgsize = MSG_SIZE };
+	mqd_t fd;
+
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_RDWR | O_CREAT | O_EXCL, 0600, NULL),
+		   EEXIST);
+	TEST_ERRNO(mq_open("/asterinas_mq_none", O_RDWR), ENOENT);
+	TEST_ERRNO(mq_open("/asterinas_mq_none", O_RDWR | O_CREAT, 0600,
+			   &attr),
+		   EINVAL);
+	TEST_ERRNO(mq_open("/asterinas/mq", O_RDWR | O_CREAT, 0600, NULL),
+		   EACCES);
+
+	fd = TEST_SUCC(mq_open(QUEUE_NAME, O_RDONLY));
+	TEST_RES(fcntl(fd, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(attributes)
+{
+	struct mq_attr attr;
+	struct mq_attr new_attr = { .mq_flags = O_NONBLOCK };
+
+	TEST_RES(mq_getattr(mqd, &attr),
+		 attr.mq_flags == 0 && attr.mq_maxmsg == MAX_MSGS &&
+			 attr.mq_msgsize == MSG_SIZE && attr.mq_curmsgs == 0);
+
+	TEST_SUCC(mq_setattr(mqd, &new_attr, &attr));
+	TEST_RES(mq_getattr(mqd, &attr), attr.mq_flags == O_NONBLOCK);
+
+	new_attr.mq_flags = 0;
+	TEST_SUCC(mq_setattr(mqd, &new_attr, NULL));
+	TEST_RES(mq_getattr(mqd, &attr), attr.mq_flags == 0);
+}
+END_TEST()
+
+FN_TEST(priorities)
+{
+	char buf[MSG_SIZE];
+	unsigned int prio;
+
+	TEST_SUCC(mq_send(mqd, "low", 4, 1));
+	TEST_SUCC(mq_send(mqd, "high", 5, 10));
+	TEST_SUCC(mq_send(mqd, "low2", 5, 1));
+	TEST_SUCC(mq_send(mqd, "mid", 4, 5));
+	TEST_ERRNO(mq_send(mqd, buf, MSG_SIZE + 1, 0), EMSGSIZE);
+	TEST_ERRNO(mq_send(mqd, "bad", 4, 32768), EINVAL);
+
+	TEST_ERRNO(mq_receive(mqd, buf, MSG_SIZE - 1, &prio), EMSGSIZE);
+
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 5 && prio == 10 && strcmp(buf, "high") == 0);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 4 && prio == 5 && strcmp(buf, "mid") == 0);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 4 && prio == 1 && strcmp(buf, "low") == 0);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 5 && prio == 1 && strcmp(buf, "low2") == 0);
+}
+END_TEST()
+
+FN_TEST(nonblocking_and_timeout)
+{
+	char buf[MSG_SIZE];
+	struct timespec ts;
+	mqd_t fd;
+	int i;
+
+	fd = TEST_SUCC(mq_open(QUEUE_NAME, O_RDWR | O_NONBLOCK));
+
+	TEST_ERRNO(mq_receive(fd, buf, sizeof(buf), NULL), EAGAIN);
+	for (i = 0; i < MAX_MSGS; i++)
+		TEST_SUCC(mq_send(fd, "msg", 4, 0));
+	TEST_ERRNO(mq_send(fd, "msg", 4, 0), EAGAIN);
+
+	CHECK(clock_gettime(CLOCK_REALTIME, &ts));
+	ts.tv_nsec += 10 * 1000 * 1000;
+	if (ts.tv_nsec >= 1000 * 1000 * 1000) {
+		ts.tv_sec += 1;
+		ts.tv_nsec -= 1000 * 1000 * 1000;
+	}
+	TEST_ERRNO(mq_timedsend(mqd, "msg", 4, 0, &ts), ETIMEDOUT);
+
+	for (i = 0; i < MAX_MSGS; i++)
+		TEST_RES(mq_receive(fd, buf, sizeof(buf), NULL), _ret == 4);
+	TEST_ERRNO(mq_timedreceive(mqd, buf, sizeof(buf), NULL, &ts),
+		   ETIMEDOUT);
+
+	ts.tv_nsec = -1;
+	TEST_ERRNO(mq_timedreceive(mqd, buf, sizeof(buf), NULL, &ts), EINVAL);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(poll_and_epoll)
+{
+	char buf[MSG_SIZE];
+	struct pollfd pfd = { .fd = mqd, .events = POLLIN | POLLOUT };
+	struct epoll_event ev = { .events = EPOLLIN };
+	int epfd;
+
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLOUT);
+
+	epfd = TEST_SUCC(epoll_create1(0));
+	TEST_SUCC(epoll_ctl(epfd, EPOLL_CTL_ADD, mqd, &ev));
+	TEST_RES(epoll_wait(epfd, &ev, 1, 0), _ret == 0);
+
+	TEST_SUCC(mq_send(mqd, "ready", 6, 0));
+	TEST_RES(poll(&pfd, 1, 0),
+		 _ret == 1 && pfd.revents == (POLLIN | POLLOUT));
+	TEST_RES(epoll_wait(epfd, &ev, 1, 0),
+		 _ret == 1 && ev.events == EPOLLIN);
+
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), NULL), _ret == 6);
+	TEST_RES(epoll_wait(epfd, &ev, 1, 0), _ret == 0);
+
+	TEST_SUCC(close(epfd));
+}
+END_TEST()
+
+static volatile int received_signo;
+static volatile int received_value;
+static volatile int received_code;
+
+static void handle_signal(int signo, siginfo_t *info, void *ctx)
+{
+	received_signo = signo;
+	received_value = info->si_value.sival_int;
+	received_code = info->si_code;
+}
+
+FN_TEST(notify)
+{
+	char buf[MSG_SIZE];
+	struct sigaction sa = { .sa_sigaction = handle_signal,
+				.sa_flags = SA_SIGINFO };
+	struct sigevent sev = { .sigev_notify = SIGEV_SIGNAL,
+				.sigev_signo = SIGUSR1,
+				.sigev_value.sival_int = 42 };
+
+	TEST_SUCC(sigaction(SIGUSR1, &sa, NULL));
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_ERRNO(mq_notify(mqd, &sev), EBUSY);
+
+	TEST_SUCC(mq_send(mqd, "notify", 7, 0));
+	TEST_RES(received_signo, _ret == SIGUSR1 && received_value == 42 &&
+					 received_code == SI_MESGQ);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), NULL), _ret == 7);
+
+	// The registration is removed after the notification.
+	received_signo = 0;
+	TEST_SUCC(mq_send(mqd, "notify", 7, 0));
+	TEST_RES(received_signo, _ret == 0);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), NULL), _ret == 7);
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_notify(mqd, NULL));
+	TEST_SUCC(mq_send(mqd, "notify", 7, 0));
+	TEST_RES(received_signo, _ret == 0);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), NULL), _ret == 7);
+
+	sev.sigev_signo = 0;
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_notify(mqd, NULL));
+
+	sev.sigev_signo = 100;
+	TEST_ERRNO(mq_notify(mqd, &sev), EINVAL);
+	sev.sigev_notify = 100;
+	TEST_ERRNO(mq_notify(mqd, &sev), EINVAL);
+}
+END_TEST()
+
+static int thread_pipe[2];
+
+static void handle_thread_notify(union sigval value)
+{
+	char byte = value.sival_int;
+
+	CHECK(write(thread_pipe[1], &byte, 1));
+}
+
+FN_TEST(notify_thread)
+{
+	char buf[MSG_SIZE];
+	struct pollfd pfd;
+	struct sigevent sev = { .sigev_notify = SIGEV_THREAD,
+				.sigev_notify_function = handle_thread_notify,
+				.sigev_value.sival_int = 42 };
+
+	TEST_SUCC(pipe(thread_pipe));
+	pfd.fd = thread_pipe[0];
+	pfd.events = POLLIN;
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_ERRNO(mq_notify(mqd, &sev), EBUSY);
+
+	TEST_SUCC(mq_send(mqd, "thread", 7, 0));
+	TEST_RES(poll(&pfd, 1, 1000), _ret == 1);
+	TEST_RES(read(thread_pipe[0], buf, sizeof(buf)),
+		 _ret == 1 && buf[0] == 42);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), NULL), _ret == 7);
+
+	// The registration is removed after the notification.
+	TEST_SUCC(mq_send(mqd, "thread", 7, 0));
+	TEST_RES(poll(&pfd, 1, 100), _ret == 0);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), NULL), _ret == 7);
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_notify(mqd, NULL));
+	TEST_SUCC(mq_send(mqd, "thread", 7, 0));
+	TEST_RES(poll(&pfd, 1, 100), _ret == 0);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), NULL), _ret == 7);
+
+	TEST_SUCC(close(thread_pipe[0]));
+	TEST_SUCC(close(thread_pipe[1]));
+}
+END_TEST()
+
+#define NOTIFY_COOKIE_LEN 32
+#define NOTIFY_WOKENUP 1
+#define NOTIFY_REMOVED 2
+
+FN_TEST(notify_thread_raw)
+{
+	unsigned char cookie[NOTIFY_COOKIE_LEN];
+	unsigned char buf[NOTIFY_COOKIE_LEN + 1];
+	struct sigevent sev = { .sigev_notify = SIGEV_THREAD,
+				.sigev_value.sival_ptr = cookie };
+	int sk, pipefd[2], udp;
+
+	// This mirrors how glibc implements `SIGEV_THREAD`.
+	sk = TEST_SUCC(socket(AF_NETLINK, SOCK_RAW | SOCK_NONBLOCK, 0));
+	memset(cookie, 0xab, sizeof(cookie));
+
+	TEST_SUCC(pipe(pipefd));
+	sev.sigev_signo = pipefd[0];
+	TEST_ERRNO(syscall(SYS_mq_notify, mqd, &sev), ENOTSOCK);
+	TEST_SUCC(close(pipefd[0]));
+	TEST_SUCC(close(pipefd[1]));
+
+	udp = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	sev.sigev_signo = udp;
+	TEST_ERRNO(syscall(SYS_mq_notify, mqd, &sev), EINVAL);
+	TEST_SUCC(close(udp));
+
+	sev.sigev_signo = -1;
+	TEST_ERRNO(syscall(SYS_mq_notify, mqd, &sev), EBADF);
+
+	sev.sigev_signo = sk;
+	TEST_SUCC(syscall(SYS_mq_notify, mqd, &sev));
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(mq_send(mqd, "raw", 4, 0));
+	TEST_RES(recv(sk, buf, sizeof(buf), 0),
+		 _ret == NOTIFY_COOKIE_LEN && buf[0] == 0xab &&
+			 buf[NOTIFY_COOKIE_LEN - 1] == NOTIFY_WOKENUP);
+	TEST_RES(mq_receive(mqd, (char *)buf, MSG_SIZE, NULL), _ret == 4);
+
+	TEST_SUCC(syscall(SYS_mq_notify, mqd, &sev));
+	TEST_SUCC(syscall(SYS_mq_notify, mqd, NULL));
+	TEST_RES(recv(sk, buf, sizeof(buf), 0),
+		 _ret == NOTIFY_COOKIE_LEN && buf[0] == 0xab &&
+			 buf[NOTIFY_COOKIE_LEN - 1] == NOTIFY_REMOVED);
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(mqueue_fs)
+{
+	char buf[128];
+	struct stat st;
+	int fd;
+
+	TEST_RES(stat(QUEUE_PATH, &st),
+		 S_ISREG(st.st_mode) && (st.st_mode & 0777) == 0600);
+
+	TEST_SUCC(mq_send(mqd, "status", 7, 0));
+
+	fd = TEST_SUCC(open(QUEUE_PATH, O_RDONLY));
+	TEST_RES(read(fd, buf, sizeof(buf) - 1),
+		 _ret > 0 && strncmp(buf, "QSIZE:7 ", 8) == 0);
+	TEST_SUCC(close(fd));
+
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), NULL), _ret == 7);
+}
+END_TEST()
+
+FN_TEST(unlink)
+{
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+	TEST_ERRNO(mq_unlink(QUEUE_NAME), ENOENT);
+	TEST_ERRNO(access(QUEUE_PATH, F_OK), ENOENT);
+
+	// The queue is still usable after being unlinked.
+	TEST_SUCC(mq_send(mqd, "unlinked", 9, 0));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(mq_close(mqd));
+}
+END_SETUP()
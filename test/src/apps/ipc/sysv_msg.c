@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <string.h>
+#include <unistd.h>
+#include <sys/ipc.h>
+#include <sys/msg.h>
+#include <sys/wait.h>
+
+#include "../test.h"
+
+#define MSG_KEY 0x4321
+
+struct msgbuf_small {
+	long mtype;
+	char mtext[16];
+};
+
+static int msqid;
+
+static int send_msg(long mtype, const char *text, int flags)
+{
+	struct msgbuf_small buf;
+
+	buf.mtype = mtype;
+	strncpy(buf.mtext, text, sizeof(buf.mtext));
+	return msgsnd(msqid, &buf, strlen(text) + 1, flags);
+}
+
+FN_SETUP(create)
+{
+	msqid = CHECK(msgget(MSG_KEY, IPC_CREAT | IPC_EXCL | 0600));
+}
+END_SETUP()
+
+FN_TEST(get)
+{
+	TEST_RES(msgget(MSG_KEY, 0600), _ret == msqid);
+	TEST_RES(msgget(MSG_KEY, 0), _ret == msqid);
+	TEST_ERRNO(msgget(MSG_KEY, IPC_CREAT | IPC_EXCL | 0600), EEXIST);
+	TEST_ERRNO(msgget(MSG_KEY + 1, 0600), ENOENT);
+}
+END_TEST()
+
+FN_TEST(send_and_receive)
+{
+	struct msgbuf_small buf;
+
+	TEST_SUCC(send_msg(1, "one", 0));
+	TEST_SUCC(send_msg(2, "two", 0));
+	TEST_SUCC(send_msg(3, "three", 0));
+	TEST_ERRNO(send_msg(0, "zero", 0), EINVAL);
+
+	// Receives the first message of type 2.
+	TEST_RES(msgrcv(msqid, &buf, sizeof(buf.mtext), 2, 0),
+		 _ret == 4 && buf.mtype == 2 && strcmp(buf.mtext, "two") == 0);
+	// Receives the first message whose type is not 1.
+	TEST_RES(msgrcv(msqid, &buf, sizeof(buf.mtext), 1, MSG_EXCEPT),
+		 _ret == 6 && buf.mtype == 3 &&
+			 strcmp(buf.mtext, "three") == 0);
+	TEST_ERRNO(msgrcv(msqid, &buf, sizeof(buf.mtext), 2, IPC_NOWAIT),
+		   ENOMSG);
+	// Receives the first message in the queue.
+	TEST_RES(msgrcv(msqid, &buf, sizeof(buf.mtext), 0, 0),
+		 _ret == 4 && buf.mtype == 1 && strcmp(buf.mtext, "one") == 0);
+	TEST_ERRNO(msgrcv(msqid, &buf, sizeof(buf.mtext), 0, IPC_NOWAIT),
+		   ENOMSG);
+}
+END_TEST()
+
+FN_TEST(lowest_type)
+{
+	struct msgbuf_small buf;
+
+	TEST_SUCC(send_msg(5, "five", 0));
+	TEST_SUCC(send_msg(3, "three", 0));
+	TEST_SUCC(send_msg(4, "four", 0));
+
+	// Receives the message of the lowest type that is less than or equal to 4.
+	TEST_RES(msgrcv(msqid, &buf, sizeof(buf.mtext), -4, 0),
+		 _ret == 6 && buf.mtype == 3);
+	TEST_RES(msgrcv(msqid, &buf, sizeof(buf.mtext), -4, 0),
+		 _ret == 5 && buf.mtype == 4);
+	TEST_ERRNO(msgrcv(msqid, &buf, sizeof(buf.mtext), -4, IPC_NOWAIT),
+		   ENOMSG);
+	TEST_RES(msgrcv(msqid, &buf, sizeof(buf.mtext), -5, 0),
+		 _ret == 5 && buf.mtype == 5);
+}
+END_TEST()
+
+FN_TEST(too_long)
+{
+	struct msgbuf_small buf;
+
+	TEST_SUCC(send_msg(1, "hello", 0));
+	TEST_ERRNO(msgrcv(msqid, &buf, 2, 0, 0), E2BIG);
+	TEST_RES(msgrcv(msqid, &buf, 2, 0, MSG_NOERROR),
+		 _ret == 2 && buf.mtext[0] == 'h' && buf.mtext[1] == 'e');
+	TEST_ERRNO(msgrcv(msqid, &buf, sizeof(buf.mtext), 0, IPC_NOWAIT),
+		   ENOMSG);
+}
+END_TEST()
+
+FN_TEST(stat_and_set)
+{
+	struct msqid_ds ds;
+
+	TEST_SUCC(send_msg(1, "abc", 0));
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds),
+		 ds.msg_qnum == 1 && ds.msg_cbytes == 4 &&
+			 ds.msg_perm.__key == MSG_KEY &&
+			 (ds.msg_perm.mode & 0777) == 0600 &&
+			 ds.msg_lspid == getpid());
+
+	// Shrinks the queue so that it is full.
+	ds.msg_qbytes = 4;
+	ds.msg_perm.mode = 0640;
+	TEST_SUCC(msgctl(msqid, IPC_SET, &ds));
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds),
+		 ds.msg_qbytes == 4 && (ds.msg_perm.mode & 0777) == 0640);
+	TEST_ERRNO(send_msg(1, "x", IPC_NOWAIT), EAGAIN);
+
+	ds.msg_qbytes = 16384;
+	TEST_SUCC(msgctl(msqid, IPC_SET, &ds));
+	TEST_SUCC(send_msg(1, "x", IPC_NOWAIT));
+
+	TEST_ERRNO(msgctl(-1, IPC_STAT, &ds), EINVAL);
+	TEST_ERRNO(msgctl(msqid, 0x7fff, &ds), EINVAL);
+}
+END_TEST()
+
+FN_TEST(stat_by_index)
+{
+	struct msginfo info;
+	struct msqid_ds ds;
+	int max_index, index;
+
+	// This is how `ipcs -q` enumerates the message queues.
+	max_index = TEST_RES(msgctl(0, MSG_INFO, (struct msqid_ds *)&info),
+			     _ret >= 0 && info.msgpool >= 1 &&
+				     info.msgmap >= 2 && info.msgtql >= 6);
+
+	for (index = 0; index <= max_index; index++) {
+		if (msgctl(index, MSG_STAT, &ds) == msqid)
+			break;
+	}
+	TEST_RES(index, _ret <= max_index);
+
+	TEST_RES(msgctl(index, MSG_STAT, &ds),
+		 _ret == msqid && ds.msg_qnum == 2 &&
+			 ds.msg_perm.__key == MSG_KEY);
+	TEST_RES(msgctl(index, MSG_STAT_ANY, &ds),
+		 _ret == msqid && ds.msg_qnum == 2 &&
+			 ds.msg_perm.__key == MSG_KEY);
+}
+END_TEST()
+
+FN_TEST(drain)
+{
+	struct msgbuf_small buf;
+
+	TEST_RES(msgrcv(msqid, &buf, sizeof(buf.mtext), 0, IPC_NOWAIT),
+		 _ret == 4);
+	TEST_RES(msgrcv(msqid, &buf, sizeof(buf.mtext), 0, IPC_NOWAIT),
+		 _ret == 2);
+}
+END_TEST()
+
+FN_TEST(blocking_receive)
+{
+	struct msgbuf_small buf;
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		usleep(100 * 1000);
+		CHECK(send_msg(7, "late", 0));
+		_exit(0);
+	}
+
+	TEST_RES(msgrcv(msqid, &buf, sizeof(buf.mtext), 7, 0),
+		 _ret == 5 && buf.mtype == 7 && strcmp(buf.mtext, "late") == 0);
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+END_TEST()
+
+FN_TEST(remove)
+{
+	struct msgbuf_small buf;
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		// The blocked receiver should be woken up when the queue is removed.
+		if (msgrcv(msqid, &buf, sizeof(buf.mtext), 0, 0) < 0 &&
+		    errno == EIDRM)
+			_exit(0);
+		_exit(1);
+	}
+
+	usleep(100 * 1000);
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_ERRNO(msgget(MSG_KEY, 0600), ENOENT);
+	TEST_ERRNO(send_msg(1, "gone", IPC_NOWAIT), EINVAL);
+}
+END_TEST()
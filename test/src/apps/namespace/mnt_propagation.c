@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+#include <ctype.h>
+#include <sched.h>
+#include <sys/mount.h>
+#include <sys/wait.h>
+#include <sys/stat.h>
+
+#include "../test.h"
+
+#define BASE_DIR "/mnt_prop"
+#define SHARED_DIR BASE_DIR "/shared"
+#define PEER_DIR BASE_DIR "/peer"
+#define SLAVE_DIR BASE_DIR "/slave"
+#define BIND_DIR BASE_DIR "/bind"
+
+static int mount_ramfs(const char *dir)
+{
+	return mount("ramfs", dir, "ramfs", 0, NULL);
+}
+
+static int create_file(const char *path)
+{
+	int fd = open(path, O_CREAT | O_WRONLY, 0644);
+	if (fd < 0)
+		return -1;
+	return close(fd);
+}
+
+// Finds the optional field with the given prefix (e.g., "shared:") in the
+// mountinfo line of the mount point. Returns the ID in the field (or 1 if the
+// field has no ID), 0 if the field does not exist, or -1 if the mount point
+// does not exist.
+static int find_mountinfo_field(const char *mount_point, const char *prefix)
+{
+	static char buf[16384];
+	char pattern[128];
+	int fd, len;
+	char *line, *end, *field;
+
+	fd = open("/proc/self/mountinfo", O_RDONLY);
+	if (fd < 0)
+		return -1;
+	len = read(fd, buf, sizeof(buf) - 1);
+	close(fd);
+	if (len < 0)
+		return -1;
+	buf[len] = '\0';
+
+	snprintf(pattern, sizeof(pattern), " %s ", mount_point);
+	for (line = buf; *line != '\0'; line = end + 1) {
+		end = strchrnul(line, '\n');
+		*end = '\0';
+
+		if (strstr(line, pattern) == NULL) {
+			if (end - buf >= len)
+				break;
+			continue;
+		}
+
+		// Only the optional fields before the separator are checked.
+		*strstr(line, " - ") = '\0';
+		field = strstr(line, prefix);
+		if (field == NULL)
+			return 0;
+		field += strlen(prefix);
+		return isdigit(*field) ? atoi(field) : 1;
+	}
+
+	return -1;
+}
+
+FN_SETUP(mount_base)
+{
+	CHECK_WITH(mkdir(BASE_DIR, 0755), _ret >= 0 || errno == EEXIST);
+	CHECK(mount("none", BASE_DIR, "tmpfs", 0, NULL));
+
+	CHECK(mkdir(SHARED_DIR, 0755));
+	CHECK(mkdir(PEER_DIR, 0755));
+	CHECK(mkdir(SLAVE_DIR, 0755));
+	CHECK(mkdir(BIND_DIR, 0755));
+
+	CHECK(mount("none", SHARED_DIR, "tmpfs", 0, NULL));
+	CHECK(mkdir(SHARED_DIR "/sub", 0755));
+	CHECK(mkdir(SHARED_DIR "/sub2", 0755));
+}
+END_SETUP()
+
+FN_TEST(invalid_flags)
+{
+	TEST_ERRNO(mount(NULL, SHARED_DIR, NULL, MS_SHARED | MS_SLAVE, NULL),
+		   EINVAL);
+	TEST_ERRNO(mount(NULL, SHARED_DIR "/sub", NULL, MS_SHARED, NULL),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(shared)
+{
+	TEST_RES(find_mountinfo_field(SHARED_DIR, "shared:"), _ret == 0);
+	TEST_SUCC(mount(NULL, SHARED_DIR, NULL, MS_SHARED, NULL));
+	TEST_RES(find_mountinfo_field(SHARED_DIR, "shared:"), _ret > 0);
+
+	// A bind mount of a shared mount is a peer of it.
+	TEST_SUCC(mount(SHARED_DIR, PEER_DIR, NULL, MS_BIND, NULL));
+	TEST_RES(find_mountinfo_field(PEER_DIR, "shared:"),
+		 _ret == find_mountinfo_field(SHARED_DIR, "shared:"));
+
+	// Mount events propagate between peers.
+	TEST_SUCC(mount_ramfs(SHARED_DIR "/sub"));
+	TEST_SUCC(create_file(SHARED_DIR "/sub/file"));
+	TEST_SUCC(access(PEER_DIR "/sub/file", F_OK));
+	TEST_RES(find_mountinfo_field(PEER_DIR "/sub", "shared:"),
+		 _ret > 0 && _ret == find_mountinfo_field(SHARED_DIR "/sub",
+							   "shared:"));
+
+	// So do unmount events.
+	TEST_SUCC(umount(PEER_DIR "/sub"));
+	TEST_ERRNO(access(SHARED_DIR "/sub/file", F_OK), ENOENT);
+	TEST_RES(find_mountinfo_field(SHARED_DIR "/sub", "shared:"),
+		 _ret == -1);
+}
+END_TEST()
+
+FN_TEST(slave)
+{
+	TEST_SUCC(mount(SHARED_DIR, SLAVE_DIR, NULL, MS_BIND, NULL));
+	TEST_SUCC(mount(NULL, SLAVE_DIR, NULL, MS_SLAVE, NULL));
+	TEST_RES(find_mountinfo_field(SLAVE_DIR, "shared:"), _ret == 0);
+	TEST_RES(find_mountinfo_field(SLAVE_DIR, "master:"),
+		 _ret == find_mountinfo_field(SHARED_DIR, "shared:"));
+
+	// Mount events propagate from the master to the slave.
+	TEST_SUCC(mount_ramfs(SHARED_DIR "/sub"));
+	TEST_SUCC(create_file(SHARED_DIR "/sub/file"));
+	TEST_SUCC(access(SLAVE_DIR "/sub/file", F_OK));
+	TEST_SUCC(access(PEER_DIR "/sub/file", F_OK));
+
+	// Mount events do not propagate from the slave to the master.
+	TEST_SUCC(mount_ramfs(SLAVE_DIR "/sub2"));
+	TEST_SUCC(create_file(SLAVE_DIR "/sub2/file"));
+	TEST_ERRNO(access(SHARED_DIR "/sub2/file", F_OK), ENOENT);
+	TEST_ERRNO(access(PEER_DIR "/sub2/file", F_OK), ENOENT);
+	TEST_SUCC(umount(SLAVE_DIR "/sub2"));
+
+	// Unmount events propagate from the master to the slave.
+	TEST_SUCC(umount(SHARED_DIR "/sub"));
+	TEST_ERRNO(access(SLAVE_DIR "/sub/file", F_OK), ENOENT);
+	TEST_ERRNO(access(PEER_DIR "/sub/file", F_OK), ENOENT);
+
+	// A shared slave receives events from its master and propagates them to
+	// its peers.
+	TEST_SUCC(mount(NULL, SLAVE_DIR, NULL, MS_SHARED, NULL));
+	TEST_RES(find_mountinfo_field(SLAVE_DIR, "shared:"),
+		 _ret > 0 &&
+			 _ret != find_mountinfo_field(SHARED_DIR, "shared:"));
+	TEST_RES(find_mountinfo_field(SLAVE_DIR, "master:"),
+		 _ret == find_mountinfo_field(SHARED_DIR, "shared:"));
+	TEST_SUCC(mount(SLAVE_DIR, BIND_DIR, NULL, MS_BIND, NULL));
+	TEST_SUCC(mount_ramfs(SHARED_DIR "/sub"));
+	TEST_SUCC(create_file(SHARED_DIR "/sub/file"));
+	TEST_SUCC(access(BIND_DIR "/sub/file", F_OK));
+	TEST_SUCC(umount(SHARED_DIR "/sub"));
+	TEST_ERRNO(access(BIND_DIR "/sub/file", F_OK), ENOENT);
+
+	TEST_SUCC(umount(BIND_DIR));
+	TEST_SUCC(umount(SLAVE_DIR));
+}
+END_TEST()
+
+FN_TEST(unbindable)
+{
+	TEST_SUCC(mount(NULL, PEER_DIR, NULL, MS_UNBINDABLE, NULL));
+	TEST_RES(find_mountinfo_field(PEER_DIR, "shared:"), _ret == 0);
+	TEST_RES(find_mountinfo_field(PEER_DIR, "unbindable"), _ret == 1);
+	TEST_ERRNO(mount(PEER_DIR, BIND_DIR, NULL, MS_BIND, NULL), EINVAL);
+
+	// Mount events no longer propagate to the unbindable mount.
+	TEST_SUCC(mount_ramfs(SHARED_DIR "/sub"));
+	TEST_SUCC(create_file(SHARED_DIR "/sub/file"));
+	TEST_ERRNO(access(PEER_DIR "/sub/file", F_OK), ENOENT);
+	TEST_SUCC(umount(SHARED_DIR "/sub"));
+
+	TEST_SUCC(umount(PEER_DIR));
+}
+END_TEST()
+
+FN_TEST(namespace)
+{
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		// The shared mounts in the new mount namespace are peers of
+		// the original ones.
+		CHECK(unshare(CLONE_NEWNS));
+		CHECK(mount_ramfs(SHARED_DIR "/sub"));
+		CHECK(create_file(SHARED_DIR "/sub/file"));
+		exit(0);
+	}
+
+	TEST_SUCC(waitpid(pid, &status, 0));
+	TEST_RES(status, WIFEXITED(_ret) && WEXITSTATUS(_ret) == 0);
+	TEST_SUCC(access(SHARED_DIR "/sub/file", F_OK));
+	TEST_SUCC(umount(SHARED_DIR "/sub"));
+}
+END_TEST()
+
+FN_TEST(private)
+{
+	TEST_SUCC(mount(NULL, SHARED_DIR, NULL, MS_PRIVATE | MS_REC, NULL));
+	TEST_RES(find_mountinfo_field(SHARED_DIR, "shared:"), _ret == 0);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(umount(SHARED_DIR));
+	CHECK(umount(BASE_DIR));
+	CHECK(rmdir(BASE_DIR));
+}
+END_SETUP()
@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+#include <sched.h>
+#include <sys/mount.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <sys/stat.h>
+
+#include "../test.h"
+
+#define BASE_DIR "/mnt_api"
+#define DIR_A BASE_DIR "/a"
+#define DIR_B BASE_DIR "/b"
+#define ROOT_DIR BASE_DIR "/root"
+
+static int create_file(const char *path)
+{
+	int fd = open(path, O_CREAT | O_WRONLY, 0644);
+	if (fd < 0)
+		return -1;
+	return close(fd);
+}
+
+// Returns 1 if the mount point is read-only, 0 if it is read-write, or -1 if
+// the mount point does not exist.
+static int is_mount_readonly(const char *mount_point)
+{
+	static char buf[16384];
+	char pattern[128];
+	char *options;
+	int fd, len;
+
+	fd = open("/proc/self/mountinfo", O_RDONLY);
+	if (fd < 0)
+		return -1;
+	len = read(fd, buf, sizeof(buf) - 1);
+	close(fd);
+	if (len < 0)
+		return -1;
+	buf[len] = '\0';
+
+	// The mount options follow the mount point in each line.
+	snprintf(pattern, sizeof(pattern), " %s ", mount_point);
+	options = strstr(buf, pattern);
+	if (options == NULL)
+		return -1;
+	options += strlen(pattern);
+	return strncmp(options, "ro", 2) == 0;
+}
+
+FN_SETUP(mount_base)
+{
+	CHECK_WITH(mkdir(BASE_DIR, 0755), _ret >= 0 || errno == EEXIST);
+	CHECK(mount("none", BASE_DIR, "tmpfs", 0, NULL));
+
+	CHECK(mkdir(DIR_A, 0755));
+	CHECK(mkdir(DIR_B, 0755));
+	CHECK(mkdir(ROOT_DIR, 0755));
+}
+END_SETUP()
+
+FN_TEST(fsopen_fsmount)
+{
+	int fs_fd, mnt_fd;
+
+	TEST_ERRNO(syscall(SYS_fsopen, "no_such_fs", 0), ENODEV);
+	TEST_ERRNO(syscall(SYS_fsopen, "tmpfs", 0x100), EINVAL);
+
+	fs_fd = TEST_SUCC(syscall(SYS_fsopen, "tmpfs", FSOPEN_CLOEXEC));
+	TEST_ERRNO(syscall(SYS_fsmount, fs_fd, 0, 0), EBUSY);
+
+	TEST_SUCC(syscall(SYS_fsconfig, fs_fd, FSCONFIG_SET_STRING, "source",
+			  "none", 0));
+	TEST_ERRNO(syscall(SYS_fsconfig, fs_fd, FSCONFIG_SET_STRING, "source",
+			   "none", 0),
+		   EINVAL);
+	TEST_ERRNO(syscall(SYS_fsconfig, fs_fd, FSCONFIG_SET_FLAG, "ro", "ro",
+			   0),
+		   EINVAL);
+	TEST_SUCC(syscall(SYS_fsconfig, fs_fd, FSCONFIG_CMD_CREATE, NULL, NULL,
+			  0));
+	TEST_ERRNO(syscall(SYS_fsconfig, fs_fd, FSCONFIG_SET_FLAG, "ro", NULL,
+			   0),
+		   EBUSY);
+
+	mnt_fd = TEST_SUCC(syscall(SYS_fsmount, fs_fd, FSMOUNT_CLOEXEC,
+				   MOUNT_ATTR_RDONLY));
+	TEST_SUCC(close(fs_fd));
+
+	// The mount is detached until it is moved to a mount point.
+	TEST_RES(is_mount_readonly(DIR_A), _ret == -1);
+	TEST_SUCC(syscall(SYS_move_mount, mnt_fd, "", AT_FDCWD, DIR_A,
+			  MOVE_MOUNT_F_EMPTY_PATH));
+	TEST_RES(is_mount_readonly(DIR_A), _ret == 1);
+	TEST_SUCC(close(mnt_fd));
+
+	TEST_SUCC(umount(DIR_A));
+}
+END_TEST()
+
+FN_TEST(open_tree)
+{
+	int tree_fd;
+
+	TEST_SUCC(mount("none", DIR_A, "tmpfs", 0, NULL));
+	TEST_SUCC(create_file(DIR_A "/file"));
+
+	TEST_ERRNO(syscall(SYS_open_tree, AT_FDCWD, DIR_A, AT_RECURSIVE),
+		   EINVAL);
+
+	tree_fd = TEST_SUCC(syscall(SYS_open_tree, AT_FDCWD, DIR_A,
+				    OPEN_TREE_CLONE | OPEN_TREE_CLOEXEC));
+	TEST_SUCC(syscall(SYS_move_mount, tree_fd, "", AT_FDCWD, DIR_B,
+			  MOVE_MOUNT_F_EMPTY_PATH));
+	TEST_SUCC(close(tree_fd));
+
+	// The cloned mount shares the file system with the original one.
+	TEST_SUCC(access(DIR_B "/file", F_OK));
+	TEST_SUCC(create_file(DIR_B "/file2"));
+	TEST_SUCC(access(DIR_A "/file2", F_OK));
+
+	TEST_SUCC(umount(DIR_B));
+	TEST_SUCC(umount(DIR_A));
+}
+END_TEST()
+
+FN_TEST(mount_setattr)
+{
+	struct mount_attr attr = {};
+
+	TEST_SUCC(mount("none", DIR_A, "tmpfs", 0, NULL));
+	TEST_RES(is_mount_readonly(DIR_A), _ret == 0);
+
+	attr.attr_set = MOUNT_ATTR_RDONLY;
+	TEST_ERRNO(syscall(SYS_mount_setattr, AT_FDCWD, DIR_A, 0, &attr,
+			   sizeof(attr) - 1),
+		   EINVAL);
+	TEST_SUCC(syscall(SYS_mount_setattr, AT_FDCWD, DIR_A, 0, &attr,
+			  sizeof(attr)));
+	TEST_RES(is_mount_readonly(DIR_A), _ret == 1);
+
+	attr.attr_clr = MOUNT_ATTR_RDONLY;
+	TEST_ERRNO(syscall(SYS_mount_setattr, AT_FDCWD, DIR_A, 0, &attr,
+			   sizeof(attr)),
+		   EINVAL);
+
+	attr.attr_set = 0;
+	TEST_SUCC(syscall(SYS_mount_setattr, AT_FDCWD, DIR_A, 0, &attr,
+			  sizeof(attr)));
+	TEST_RES(is_mount_readonly(DIR_A), _ret == 0);
+
+	TEST_SUCC(umount(DIR_A));
+}
+END_TEST()
+
+FN_TEST(pivot_root)
+{
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK(unshare(CLONE_NEWNS));
+		CHECK(mount(NULL, "/", NULL, MS_PRIVATE | MS_REC, NULL));
+
+		CHECK(mount("none", ROOT_DIR, "tmpfs", 0, NULL));
+		CHECK(mkdir(ROOT_DIR "/new", 0755));
+		CHECK(mount("none", ROOT_DIR "/new", "tmpfs", 0, NULL));
+		CHECK(mkdir(ROOT_DIR "/new/old", 0755));
+		CHECK(create_file(ROOT_DIR "/new/file"));
+		CHECK(chroot(ROOT_DIR));
+		CHECK(chdir("/"));
+
+		CHECK_WITH(syscall(SYS_pivot_root, "/", "/new/old"),
+			   _ret < 0 && errno == EBUSY);
+		CHECK_WITH(syscall(SYS_pivot_root, "/new", "/"),
+			   _ret < 0 && errno == EBUSY);
+		CHECK_WITH(syscall(SYS_pivot_root, "/new", "/new/file"),
+			   _ret < 0 && errno == ENOTDIR);
+
+		CHECK(syscall(SYS_pivot_root, "/new", "/new/old"));
+
+		// The new root is now the root, and the old root is under it.
+		CHECK(access("/file", F_OK));
+		CHECK(access("/old/new", F_OK));
+		CHECK(umount2("/old", MNT_DETACH));
+		CHECK_WITH(access("/old/new", F_OK),
+			   _ret < 0 && errno == ENOENT);
+		exit(0);
+	}
+
+	TEST_SUCC(waitpid(pid, &status, 0));
+	TEST_RES(status, WIFEXITED(_ret) && WEXITSTATUS(_ret) == 0);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(umount(BASE_DIR));
+	CHECK(rmdir(BASE_DIR));
+}
+END_SETUP()
@@ -0,0 +1,384 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <linux/rtnetlink.h>
+#include <linux/veth.h>
+#include <net/if.h>
+#include <netinet/in.h>
+#include <sched.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../test.h"
+
+#define BUFFER_SIZE 4096
+static char buffer[BUFFER_SIZE];
+
+static int rtnl_fd;
+
+// --- Netlink helpers ---
+
+static void add_attr(struct nlmsghdr *hdr, unsigned short type,
+		     const void *data, size_t len)
+{
+	struct rtattr *rta =
+		(struct rtattr *)((char *)hdr + NLMSG_ALIGN(hdr->nlmsg_len));
+
+	rta->rta_type = type;
+	rta->rta_len = RTA_LENGTH(len);
+	memcpy(RTA_DATA(rta), data, len);
+	hdr->nlmsg_len = NLMSG_ALIGN(hdr->nlmsg_len) + RTA_ALIGN(rta->rta_len);
+}
+
+static struct rtattr *begin_nested(struct nlmsghdr *hdr, unsigned short type)
+{
+	struct rtattr *rta =
+		(struct rtattr *)((char *)hdr + NLMSG_ALIGN(hdr->nlmsg_len));
+
+	add_attr(hdr, type, NULL, 0);
+	return rta;
+}
+
+static void end_nested(struct nlmsghdr *hdr, struct rtattr *rta)
+{
+	rta->rta_len = (char *)hdr + hdr->nlmsg_len - (char *)rta;
+}
+
+// Sends the request and returns 0 if it is acknowledged, or -1 with `errno` set otherwise.
+static int rtnl_request(struct nlmsghdr *hdr)
+{
+	hdr->nlmsg_flags |= NLM_F_REQUEST | NLM_F_ACK;
+
+	if (send(rtnl_fd, hdr, hdr->nlmsg_len, 0) != hdr->nlmsg_len)
+		return -1;
+	if (recv(rtnl_fd, buffer, BUFFER_SIZE, 0) < 0)
+		return -1;
+
+	struct nlmsghdr *resp = (struct nlmsghdr *)buffer;
+	if (resp->nlmsg_type != NLMSG_ERROR) {
+		errno = EPROTO;
+		return -1;
+	}
+
+	int error = ((struct nlmsgerr *)NLMSG_DATA(resp))->error;
+	if (error != 0) {
+		errno = -error;
+		return -1;
+	}
+
+	return 0;
+}
+
+static int set_link_up(int index)
+{
+	struct {
+		struct nlmsghdr hdr;
+		struct ifinfomsg ifi;
+	} req;
+
+	memset(&req, 0, sizeof(req));
+	req.hdr.nlmsg_len = sizeof(req);
+	req.hdr.nlmsg_type = RTM_NEWLINK;
+	req.ifi.ifi_family = AF_UNSPEC;
+	req.ifi.ifi_index = index;
+	req.ifi.ifi_flags = IFF_UP;
+	req.ifi.ifi_change = IFF_UP;
+
+	return rtnl_request(&req.hdr);
+}
+
+static int add_addr(int index, const char *addr, int prefix_len)
+{
+	struct {
+		struct nlmsghdr hdr;
+		struct ifaddrmsg ifa;
+		char attrs[64];
+	} req;
+	struct in_addr in_addr;
+
+	if (inet_pton(AF_INET, addr, &in_addr) != 1)
+		return -1;
+
+	memset(&req, 0, sizeof(req));
+	req.hdr.nlmsg_len = NLMSG_LENGTH(sizeof(req.ifa));
+	req.hdr.nlmsg_type = RTM_NEWADDR;
+	req.hdr.nlmsg_flags = NLM_F_CREATE | NLM_F_EXCL;
+	req.ifa.ifa_family = AF_INET;
+	req.ifa.ifa_prefixlen = prefix_len;
+	req.ifa.ifa_index = index;
+	add_attr(&req.hdr, IFA_LOCAL, &in_addr, sizeof(in_addr));
+	add_attr(&req.hdr, IFA_ADDRESS, &in_addr, sizeof(in_addr));
+
+	return rtnl_request(&req.hdr);
+}
+
+// Creates a veth pair whose peer is placed in the network namespace of `peer_pid`.
+static int new_veth_pair(const char *name, const char *peer_name,
+			 pid_t peer_pid)
+{
+	struct {
+		struct nlmsghdr hdr;
+		struct ifinfomsg ifi;
+		char attrs[256];
+	} req;
+
+	memset(&req, 0, sizeof(req));
+	req.hdr.nlmsg_len = NLMSG_LENGTH(sizeof(req.ifi));
+	req.hdr.nlmsg_type = RTM_NEWLINK;
+	req.hdr.nlmsg_flags = NLM_F_CREATE | NLM_F_EXCL;
+	req.ifi.ifi_family = AF_UNSPEC;
+	add_attr(&req.hdr, IFLA_IFNAME, name, strlen(name) + 1);
+
+	struct rtattr *link_info = begin_nested(&req.hdr, IFLA_LINKINFO);
+	add_attr(&req.hdr, IFLA_INFO_KIND, "veth", strlen("veth"));
+	struct rtattr *info_data = begin_nested(&req.hdr, IFLA_INFO_DATA);
+	struct rtattr *peer = begin_nested(&req.hdr, VETH_INFO_PEER);
+
+	struct ifinfomsg peer_ifi = { .ifi_family = AF_UNSPEC };
+	memcpy((char *)&req.hdr + req.hdr.nlmsg_len, &peer_ifi,
+	       sizeof(peer_ifi));
+	req.hdr.nlmsg_len += NLMSG_ALIGN(sizeof(peer_ifi));
+	add_attr(&req.hdr, IFLA_IFNAME, peer_name, strlen(peer_name) + 1);
+	if (peer_pid != 0) {
+		uint32_t pid = peer_pid;
+		add_attr(&req.hdr, IFLA_NET_NS_PID, &pid, sizeof(pid));
+	}
+
+	end_nested(&req.hdr, peer);
+	end_nested(&req.hdr, info_data);
+	end_nested(&req.hdr, link_info);
+
+	return rtnl_request(&req.hdr);
+}
+
+// Returns the index of the link with the specified name, or -1 with `errno` set on errors.
+static int get_link_index(const char *name)
+{
+	struct {
+		struct nlmsghdr hdr;
+		struct ifinfomsg ifi;
+		char attrs[64];
+	} req;
+
+	memset(&req, 0, sizeof(req));
+	req.hdr.nlmsg_len = NLMSG_LENGTH(sizeof(req.ifi));
+	req.hdr.nlmsg_type = RTM_GETLINK;
+	req.hdr.nlmsg_flags = NLM_F_REQUEST;
+	req.ifi.ifi_family = AF_UNSPEC;
+	add_attr(&req.hdr, IFLA_IFNAME, name, strlen(name) + 1);
+
+	if (send(rtnl_fd, &req, req.hdr.nlmsg_len, 0) != req.hdr.nlmsg_len)
+		return -1;
+	if (recv(rtnl_fd, buffer, BUFFER_SIZE, 0) < 0)
+		return -1;
+
+	struct nlmsghdr *resp = (struct nlmsghdr *)buffer;
+	if (resp->nlmsg_type == NLMSG_ERROR) {
+		errno = -((struct nlmsgerr *)NLMSG_DATA(resp))->error;
+		return -1;
+	}
+	if (resp->nlmsg_type != RTM_NEWLINK) {
+		errno = EPROTO;
+		return -1;
+	}
+
+	return ((struct ifinfomsg *)NLMSG_DATA(resp))->ifi_index;
+}
+
+// Dumps all links and returns the number of links, or -1 with `errno` set on errors.
+//
+// The information of the first link is stored in `first_link`.
+static int dump_links(struct ifinfomsg *first_link)
+{
+	struct {
+		struct nlmsghdr hdr;
+		struct ifinfomsg ifi;
+	} req;
+	int nr_links = 0;
+
+	memset(&req, 0, sizeof(req));
+	req.hdr.nlmsg_len = sizeof(req);
+	req.hdr.nlmsg_type = RTM_GETLINK;
+	req.hdr.nlmsg_flags = NLM_F_REQUEST | NLM_F_DUMP;
+	req.ifi.ifi_family = AF_UNSPEC;
+
+	if (send(rtnl_fd, &req, sizeof(req), 0) != sizeof(req))
+		return -1;
+
+	for (;;) {
+		ssize_t len = recv(rtnl_fd, buffer, BUFFER_SIZE, 0);
+		if (len < 0)
+			return -1;
+
+		struct nlmsghdr *hdr;
+		for (hdr = (struct nlmsghdr *)buffer; NLMSG_OK(hdr, len);
+		     hdr = NLMSG_NEXT(hdr, len)) {
+			if (hdr->nlmsg_type == NLMSG_DONE)
+				return nr_links;
+			if (hdr->nlmsg_type != RTM_NEWLINK) {
+				errno = EPROTO;
+				return -1;
+			}
+			if (nr_links == 0)
+				memcpy(first_link, NLMSG_DATA(hdr),
+				       sizeof(*first_link));
+			nr_links++;
+		}
+	}
+}
+
+FN_SETUP(unshare)
+{
+	CHECK(unshare(CLONE_NEWNET));
+
+	rtnl_fd = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+}
+END_SETUP()
+
+// --- Tests for the loopback iface ---
+
+FN_TEST(new_ns_links)
+{
+	struct ifinfomsg lo;
+
+	// The new network namespace only contains the loopback iface, which is down.
+	TEST_RES(dump_links(&lo), _ret == 1 && lo.ifi_index == 1 &&
+					  (lo.ifi_flags & IFF_LOOPBACK) &&
+					  !(lo.ifi_flags & IFF_UP));
+	TEST_RES(get_link_index("lo"), _ret == 1);
+	TEST_ERRNO(get_link_index("eth0"), ENODEV);
+}
+END_TEST()
+
+FN_TEST(new_ns_loopback)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = htons(8080),
+		.sin_addr = { htonl(INADDR_LOOPBACK) },
+	};
+	char buf[6];
+
+	int sk_recv = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	int sk_send = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_SUCC(bind(sk_recv, (struct sockaddr *)&addr, sizeof(addr)));
+
+	// The loopback iface is down.
+	TEST_ERRNO(sendto(sk_send, "hello", 6, 0, (struct sockaddr *)&addr,
+			  sizeof(addr)),
+		   ENETUNREACH);
+
+	TEST_SUCC(set_link_up(1));
+
+	TEST_RES(sendto(sk_send, "hello", 6, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 6);
+	TEST_RES(recv(sk_recv, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "hello") == 0);
+
+	TEST_SUCC(close(sk_send));
+	TEST_SUCC(close(sk_recv));
+}
+END_TEST()
+
+// --- Tests for veth pairs ---
+
+#define VETH_PORT 9090
+#define VETH_ADDR "10.0.0.1"
+#define VETH_PEER_ADDR "10.0.0.2"
+
+static int veth_child_fn(int read_fd, int write_fd)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = htons(VETH_PORT),
+	};
+	char buf[6];
+
+	CHECK(unshare(CLONE_NEWNET));
+	rtnl_fd = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+
+	// Notify the parent that the network namespace is ready.
+	CHECK(write(write_fd, "a", 1));
+	// Wait for the parent to create the veth pair.
+	CHECK(read(read_fd, buf, 1));
+
+	// Only the loopback iface and the peer iface are visible.
+	CHECK_WITH(get_link_index("veth1"), _ret == 2);
+	CHECK_WITH(get_link_index("veth0"), _ret == -1 && errno == ENODEV);
+
+	CHECK(add_addr(2, VETH_PEER_ADDR, 24));
+	CHECK(set_link_up(2));
+
+	int sk = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+	CHECK(inet_pton(AF_INET, VETH_PEER_ADDR, &addr.sin_addr));
+	CHECK(bind(sk, (struct sockaddr *)&addr, sizeof(addr)));
+
+	// Notify the parent that the socket is bound.
+	CHECK(write(write_fd, "b", 1));
+
+	CHECK_WITH(recv(sk, buf, sizeof(buf), 0),
+		   _ret == 6 && strcmp(buf, "hello") == 0);
+
+	CHECK(close(sk));
+	return 0;
+}
+
+FN_TEST(veth_pair)
+{
+	int to_child[2], to_parent[2];
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = htons(VETH_PORT),
+	};
+	char buf[1];
+	int status;
+
+	TEST_SUCC(pipe(to_child));
+	TEST_SUCC(pipe(to_parent));
+
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		close(to_child[1]);
+		close(to_parent[0]);
+		exit(veth_child_fn(to_child[0], to_parent[1]));
+	}
+
+	TEST_SUCC(close(to_child[0]));
+	TEST_SUCC(close(to_parent[1]));
+
+	// Wait for the child to create its network namespace.
+	TEST_RES(read(to_parent[0], buf, 1), _ret == 1);
+
+	TEST_ERRNO(new_veth_pair("veth0", "veth1", 0x7fffffff), ESRCH);
+	TEST_ERRNO(new_veth_pair("lo", "veth1", pid), EEXIST);
+	TEST_SUCC(new_veth_pair("veth0", "veth1", pid));
+	TEST_ERRNO(new_veth_pair("veth0", "veth2", pid), EEXIST);
+
+	// The iface index is allocated per network namespace.
+	TEST_RES(get_link_index("veth0"), _ret == 2);
+	TEST_ERRNO(get_link_index("veth1"), ENODEV);
+
+	TEST_SUCC(add_addr(2, VETH_ADDR, 24));
+	TEST_SUCC(set_link_up(2));
+
+	TEST_RES(write(to_child[1], "c", 1), _ret == 1);
+	// Wait for the child to bind its socket.
+	TEST_RES(read(to_parent[0], buf, 1), _ret == 1);
+
+	int sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_RES(inet_pton(AF_INET, VETH_PEER_ADDR, &addr.sin_addr), _ret == 1);
+	TEST_RES(sendto(sk, "hello", 6, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 6);
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_SUCC(close(sk));
+	TEST_SUCC(close(to_child[1]));
+	TEST_SUCC(close(to_parent[0]));
+}
+END_TEST()
@@ -0,0 +1,279 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <string.h>
+#include <unistd.h>
+#include <sys/socket.h>
+#include <linux/netlink.h>
+#include <linux/genetlink.h>
+
+#include "../test.h"
+
+#define BUF_SIZE 8192
+
+static int sk_genl;
+
+static char send_buf[BUF_SIZE];
+static char recv_buf[BUF_SIZE];
+
+#define GENL_DATA(nlh) ((struct genlmsghdr *)NLMSG_DATA(nlh))
+#define GENL_ATTRS(nlh) \
+	((struct nlattr *)((char *)GENL_DATA(nlh) + GENL_HDRLEN))
+#define GENL_ATTRS_LEN(nlh) \
+	((int)(nlh)->nlmsg_len - NLMSG_HDRLEN - GENL_HDRLEN)
+
+#define NLA_OK(nla, len)                                       \
+	((len) >= (int)sizeof(struct nlattr) &&                \
+	 (nla)->nla_len >= sizeof(struct nlattr) &&            \
+	 (nla)->nla_len <= (len))
+#define NLA_NEXT(nla, len)                        \
+	((len) -= NLA_ALIGN((nla)->nla_len),      \
+	 (struct nlattr *)((char *)(nla) + NLA_ALIGN((nla)->nla_len)))
+#define NLA_DATA(nla) ((void *)((char *)(nla) + NLA_HDRLEN))
+#define NLA_LEN(nla) ((int)(nla)->nla_len - NLA_HDRLEN)
+
+static int send_request(__u16 type, __u16 flags, __u8 cmd, __u16 attr_type,
+			const void *attr_data, int attr_len)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)send_buf;
+	struct genlmsghdr *genl = GENL_DATA(nlh);
+	struct nlattr *nla = GENL_ATTRS(nlh);
+
+	memset(send_buf, 0, sizeof(send_buf));
+
+	nlh->nlmsg_len = NLMSG_HDRLEN + GENL_HDRLEN;
+	nlh->nlmsg_type = type;
+	nlh->nlmsg_flags = NLM_F_REQUEST | flags;
+	nlh->nlmsg_seq = 1;
+
+	genl->cmd = cmd;
+	genl->version = 1;
+
+	if (attr_type != 0) {
+		nla->nla_type = attr_type;
+		nla->nla_len = NLA_HDRLEN + attr_len;
+		memcpy(NLA_DATA(nla), attr_data, attr_len);
+		nlh->nlmsg_len += NLA_ALIGN(nla->nla_len);
+	}
+
+	return send(sk_genl, send_buf, nlh->nlmsg_len, 0);
+}
+
+static struct nlattr *find_attr(struct nlattr *nla, int len, __u16 type)
+{
+	for (; NLA_OK(nla, len); nla = NLA_NEXT(nla, len))
+		if ((nla->nla_type & NLA_TYPE_MASK) == type)
+			return nla;
+
+	return NULL;
+}
+
+static int nlmsg_error(struct nlmsghdr *nlh)
+{
+	return ((struct nlmsgerr *)NLMSG_DATA(nlh))->error;
+}
+
+static int check_ctrl_family(struct nlmsghdr *nlh)
+{
+	struct nlattr *attrs = GENL_ATTRS(nlh);
+	int attrs_len = GENL_ATTRS_LEN(nlh);
+	struct nlattr *nla, *grp, *grp_attr;
+	int grp_len;
+
+	nla = find_attr(attrs, attrs_len, CTRL_ATTR_FAMILY_ID);
+	if (nla == NULL || *(__u16 *)NLA_DATA(nla) != GENL_ID_CTRL)
+		return -1;
+
+	nla = find_attr(attrs, attrs_len, CTRL_ATTR_FAMILY_NAME);
+	if (nla == NULL || strcmp(NLA_DATA(nla), "nlctrl") != 0)
+		return -1;
+
+	nla = find_attr(attrs, attrs_len, CTRL_ATTR_OPS);
+	if (nla == NULL)
+		return -1;
+
+	nla = find_attr(attrs, attrs_len, CTRL_ATTR_MCAST_GROUPS);
+	if (nla == NULL)
+		return -1;
+
+	// The controller has exactly one multicast group named "notify", whose
+	// ID is the same as the family ID.
+	grp = find_attr(NLA_DATA(nla), NLA_LEN(nla), 1);
+	if (grp == NULL)
+		return -1;
+	grp_len = NLA_LEN(grp);
+
+	grp_attr = find_attr(NLA_DATA(grp), grp_len, CTRL_ATTR_MCAST_GRP_NAME);
+	if (grp_attr == NULL || strcmp(NLA_DATA(grp_attr), "notify") != 0)
+		return -1;
+
+	grp_attr = find_attr(NLA_DATA(grp), grp_len, CTRL_ATTR_MCAST_GRP_ID);
+	if (grp_attr == NULL || *(__u32 *)NLA_DATA(grp_attr) != GENL_ID_CTRL)
+		return -1;
+
+	return 0;
+}
+
+FN_SETUP(socket)
+{
+	struct sockaddr_nl addr = { .nl_family = AF_NETLINK };
+
+	sk_genl = CHECK(socket(PF_NETLINK, SOCK_RAW | SOCK_NONBLOCK,
+			       NETLINK_GENERIC));
+	CHECK(bind(sk_genl, (struct sockaddr *)&addr, sizeof(addr)));
+}
+END_SETUP()
+
+FN_TEST(get_family_by_name)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)recv_buf;
+
+	TEST_SUCC(send_request(GENL_ID_CTRL, 0, CTRL_CMD_GETFAMILY,
+			       CTRL_ATTR_FAMILY_NAME, "nlctrl", 7));
+	TEST_RES(recv(sk_genl, recv_buf, sizeof(recv_buf), 0),
+		 NLMSG_OK(nlh, _ret) && nlh->nlmsg_type == GENL_ID_CTRL &&
+			 nlh->nlmsg_seq == 1 &&
+			 GENL_DATA(nlh)->cmd == CTRL_CMD_NEWFAMILY &&
+			 check_ctrl_family(nlh) == 0);
+}
+END_TEST()
+
+FN_TEST(get_family_by_id)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)recv_buf;
+	__u16 id = GENL_ID_CTRL;
+
+	TEST_SUCC(send_request(GENL_ID_CTRL, 0, CTRL_CMD_GETFAMILY,
+			       CTRL_ATTR_FAMILY_ID, &id, sizeof(id)));
+	TEST_RES(recv(sk_genl, recv_buf, sizeof(recv_buf), 0),
+		 NLMSG_OK(nlh, _ret) && nlh->nlmsg_type == GENL_ID_CTRL &&
+			 check_ctrl_family(nlh) == 0);
+}
+END_TEST()
+
+FN_TEST(get_family_ack)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)recv_buf;
+	int len;
+
+	TEST_SUCC(send_request(GENL_ID_CTRL, NLM_F_ACK, CTRL_CMD_GETFAMILY,
+			       CTRL_ATTR_FAMILY_NAME, "nlctrl", 7));
+	len = TEST_RES(recv(sk_genl, recv_buf, sizeof(recv_buf), 0),
+		       NLMSG_OK(nlh, _ret) &&
+			       nlh->nlmsg_type == GENL_ID_CTRL);
+
+	// The acknowledgment may come in a separate message.
+	nlh = NLMSG_NEXT(nlh, len);
+	if (!NLMSG_OK(nlh, len)) {
+		nlh = (struct nlmsghdr *)recv_buf;
+		len = TEST_SUCC(recv(sk_genl, recv_buf, sizeof(recv_buf), 0));
+	}
+	TEST_RES(0, NLMSG_OK(nlh, len) && nlh->nlmsg_type == NLMSG_ERROR &&
+			    nlmsg_error(nlh) == 0);
+}
+END_TEST()
+
+FN_TEST(get_family_unknown)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)recv_buf;
+	__u16 id = GENL_MAX_ID;
+
+	TEST_SUCC(send_request(GENL_ID_CTRL, 0, CTRL_CMD_GETFAMILY,
+			       CTRL_ATTR_FAMILY_NAME, "no_such_family", 15));
+	TEST_RES(recv(sk_genl, recv_buf, sizeof(recv_buf), 0),
+		 NLMSG_OK(nlh, _ret) && nlh->nlmsg_type == NLMSG_ERROR &&
+			 nlmsg_error(nlh) == -ENOENT);
+
+	TEST_SUCC(send_request(GENL_ID_CTRL, 0, CTRL_CMD_GETFAMILY,
+			       CTRL_ATTR_FAMILY_ID, &id, sizeof(id)));
+	TEST_RES(recv(sk_genl, recv_buf, sizeof(recv_buf), 0),
+		 NLMSG_OK(nlh, _ret) && nlh->nlmsg_type == NLMSG_ERROR &&
+			 nlmsg_error(nlh) == -ENOENT);
+
+	// Neither the family name nor the family ID is specified.
+	TEST_SUCC(send_request(GENL_ID_CTRL, 0, CTRL_CMD_GETFAMILY, 0, NULL,
+			       0));
+	TEST_RES(recv(sk_genl, recv_buf, sizeof(recv_buf), 0),
+		 NLMSG_OK(nlh, _ret) && nlh->nlmsg_type == NLMSG_ERROR &&
+			 nlmsg_error(nlh) == -EINVAL);
+}
+END_TEST()
+
+FN_TEST(get_family_dump)
+{
+	struct nlmsghdr *nlh;
+	int len, found_ctrl = 0, found_done = 0;
+
+	TEST_SUCC(send_request(GENL_ID_CTRL, NLM_F_DUMP, CTRL_CMD_GETFAMILY, 0,
+			       NULL, 0));
+
+	// The dump may span multiple messages. It ends with `NLMSG_DONE`.
+	while (!found_done) {
+		len = TEST_SUCC(recv(sk_genl, recv_buf, sizeof(recv_buf), 0));
+		if (len <= 0)
+			break;
+
+		nlh = (struct nlmsghdr *)recv_buf;
+		for (; NLMSG_OK(nlh, len); nlh = NLMSG_NEXT(nlh, len)) {
+			if (nlh->nlmsg_type == NLMSG_DONE) {
+				found_done = 1;
+				break;
+			}
+			if (nlh->nlmsg_type == GENL_ID_CTRL &&
+			    (nlh->nlmsg_flags & NLM_F_MULTI) &&
+			    check_ctrl_family(nlh) == 0)
+				found_ctrl = 1;
+		}
+	}
+
+	TEST_RES(found_ctrl, _ret == 1);
+	TEST_RES(found_done, _ret == 1);
+}
+END_TEST()
+
+FN_TEST(unknown_family)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)recv_buf;
+
+	TEST_SUCC(send_request(GENL_MAX_ID, 0, 1, 0, NULL, 0));
+	TEST_RES(recv(sk_genl, recv_buf, sizeof(recv_buf), 0),
+		 NLMSG_OK(nlh, _ret) && nlh->nlmsg_type == NLMSG_ERROR &&
+			 nlmsg_error(nlh) == -ENOENT);
+}
+END_TEST()
+
+FN_TEST(unsupported_command)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)recv_buf;
+
+	TEST_SUCC(send_request(GENL_ID_CTRL, 0, CTRL_CMD_NEWFAMILY, 0, NULL,
+			       0));
+	TEST_RES(recv(sk_genl, recv_buf, sizeof(recv_buf), 0),
+		 NLMSG_OK(nlh, _ret) && nlh->nlmsg_type == NLMSG_ERROR &&
+			 nlmsg_error(nlh) == -EOPNOTSUPP);
+}
+END_TEST()
+
+FN_TEST(add_membership)
+{
+	int group;
+
+	group = GENL_ID_CTRL;
+	TEST_SUCC(setsockopt(sk_genl, SOL_NETLINK, NETLINK_ADD_MEMBERSHIP,
+			     &group, sizeof(group)));
+	TEST_SUCC(setsockopt(sk_genl, SOL_NETLINK, NETLINK_DROP_MEMBERSHIP,
+			     &group, sizeof(group)));
+
+	group = 0;
+	TEST_ERRNO(setsockopt(sk_genl, SOL_NETLINK, NETLINK_ADD_MEMBERSHIP,
+			      &group, sizeof(group)),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(no_more_messages)
+{
+	TEST_ERRNO(recv(sk_genl, recv_buf, sizeof(recv_buf), 0), EAGAIN);
+}
+END_TEST()
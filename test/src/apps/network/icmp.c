@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <netinet/ip_icmp.h>
+#include <arpa/inet.h>
+
+#include "../test.h"
+
+static struct sockaddr_in loopback_addr;
+
+struct echo_packet {
+	struct icmphdr hdr;
+	char data[6];
+};
+
+static void init_echo_request(struct echo_packet *packet, int seq)
+{
+	memset(packet, 0, sizeof(*packet));
+	packet->hdr.type = ICMP_ECHO;
+	packet->hdr.un.echo.sequence = htons(seq);
+	memcpy(packet->data, "hello", 6);
+}
+
+FN_SETUP(loopback_addr)
+{
+	loopback_addr.sin_family = AF_INET;
+	loopback_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+}
+END_SETUP()
+
+FN_TEST(ping_echo)
+{
+	int sk;
+	struct echo_packet packet;
+	struct sockaddr_in addr;
+	socklen_t addrlen = sizeof(addr);
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP));
+
+	init_echo_request(&packet, 1);
+	TEST_RES(sendto(sk, &packet, sizeof(packet), 0,
+			(struct sockaddr *)&loopback_addr,
+			sizeof(loopback_addr)),
+		 _ret == sizeof(packet));
+
+	memset(&packet, 0, sizeof(packet));
+	TEST_RES(recvfrom(sk, &packet, sizeof(packet), 0,
+			  (struct sockaddr *)&addr, &addrlen),
+		 _ret == sizeof(packet) && packet.hdr.type == ICMP_ECHOREPLY &&
+			 packet.hdr.code == 0 &&
+			 packet.hdr.un.echo.sequence == htons(1) &&
+			 strcmp(packet.data, "hello") == 0 &&
+			 addrlen == sizeof(addr) &&
+			 addr.sin_family == AF_INET && addr.sin_port == 0 &&
+			 addr.sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(ping_connect)
+{
+	int sk;
+	struct echo_packet packet;
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP));
+	TEST_SUCC(connect(sk, (struct sockaddr *)&loopback_addr,
+			  sizeof(loopback_addr)));
+
+	init_echo_request(&packet, 2);
+	TEST_RES(send(sk, &packet, sizeof(packet), 0), _ret == sizeof(packet));
+
+	memset(&packet, 0, sizeof(packet));
+	TEST_RES(recv(sk, &packet, sizeof(packet), 0),
+		 _ret == sizeof(packet) && packet.hdr.type == ICMP_ECHOREPLY &&
+			 packet.hdr.un.echo.sequence == htons(2));
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(ping_invalid_type)
+{
+	int sk;
+	struct echo_packet packet;
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP));
+
+	init_echo_request(&packet, 3);
+	packet.hdr.type = ICMP_ECHOREPLY;
+	TEST_ERRNO(sendto(sk, &packet, sizeof(packet), 0,
+			  (struct sockaddr *)&loopback_addr,
+			  sizeof(loopback_addr)),
+		   EINVAL);
+
+	TEST_ERRNO(sendto(sk, &packet, 4, 0, (struct sockaddr *)&loopback_addr,
+			  sizeof(loopback_addr)),
+		   EINVAL);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(raw_invalid_protocol)
+{
+	TEST_ERRNO(socket(AF_INET, SOCK_RAW, 0), EPROTONOSUPPORT);
+	TEST_ERRNO(socket(AF_INET, SOCK_RAW, 256), EINVAL);
+}
+END_TEST()
+
+FN_TEST(raw_getsockname)
+{
+	int sk;
+	struct sockaddr_in addr;
+	socklen_t addrlen = sizeof(addr);
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_ICMP));
+
+	TEST_RES(getsockname(sk, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) && addr.sin_family == AF_INET &&
+			 addr.sin_port == htons(IPPROTO_ICMP) &&
+			 addr.sin_addr.s_addr == htonl(INADDR_ANY));
+
+	TEST_ERRNO(getpeername(sk, (struct sockaddr *)&addr, &addrlen),
+		   ENOTCONN);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(raw_hdrincl)
+{
+	int sk;
+	int opt;
+	socklen_t optlen = sizeof(opt);
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_RAW));
+	TEST_RES(getsockopt(sk, IPPROTO_IP, IP_HDRINCL, &opt, &optlen),
+		 opt == 1);
+	TEST_SUCC(close(sk));
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_ICMP));
+	TEST_RES(getsockopt(sk, IPPROTO_IP, IP_HDRINCL, &opt, &optlen),
+		 opt == 0);
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(raw_echo)
+{
+	int sk_raw, sk_ping;
+	struct echo_packet packet;
+	struct sockaddr_in addr;
+	socklen_t addrlen = sizeof(addr);
+	char buf[128];
+	struct iphdr *iph = (struct iphdr *)buf;
+	struct icmphdr *icmph;
+
+	sk_raw = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_ICMP));
+	sk_ping = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP));
+
+	init_echo_request(&packet, 4);
+	TEST_RES(sendto(sk_ping, &packet, sizeof(packet), 0,
+			(struct sockaddr *)&loopback_addr,
+			sizeof(loopback_addr)),
+		 _ret == sizeof(packet));
+
+	// The raw socket receives both the echo request and the echo reply.
+	TEST_RES(recvfrom(sk_raw, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == sizeof(struct iphdr) + sizeof(packet) &&
+			 iph->version == 4 && iph->protocol == IPPROTO_ICMP &&
+			 addrlen == sizeof(addr) &&
+			 addr.sin_family == AF_INET && addr.sin_port == 0 &&
+			 addr.sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+	icmph = (struct icmphdr *)(buf + iph->ihl * 4);
+	TEST_RES(icmph->type, _ret == ICMP_ECHO);
+
+	TEST_RES(recv(sk_raw, buf, sizeof(buf), 0),
+		 _ret == sizeof(struct iphdr) + sizeof(packet));
+	icmph = (struct icmphdr *)(buf + iph->ihl * 4);
+	TEST_RES(icmph->type, _ret == ICMP_ECHOREPLY);
+
+	TEST_SUCC(close(sk_ping));
+	TEST_SUCC(close(sk_raw));
+}
+END_TEST()
@@ -0,0 +1,343 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include <net/if.h>
+#include <sys/socket.h>
+#include <sys/time.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <netinet/udp.h>
+#include <arpa/inet.h>
+
+#include "../test.h"
+
+#define S_PORT htons(0x1237)
+
+static struct sockaddr_in sk_addr;
+
+static int sk_recv;
+static int sk_send;
+
+static int lo_index;
+
+FN_SETUP(general)
+{
+	sk_addr.sin_family = AF_INET;
+	sk_addr.sin_port = S_PORT;
+	sk_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	sk_recv = CHECK(socket(PF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	CHECK(bind(sk_recv, (struct sockaddr *)&sk_addr, sizeof(sk_addr)));
+
+	sk_send = CHECK(socket(PF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+
+	lo_index = CHECK(if_nametoindex("lo"));
+}
+END_SETUP()
+
+static int get_bool(int sk, int level, int name)
+{
+	int val = -1;
+	socklen_t len = sizeof(val);
+
+	if (getsockopt(sk, level, name, &val, &len) < 0)
+		return -1;
+	return len == sizeof(val) ? val : -1;
+}
+
+static int set_bool(int sk, int level, int name, int val)
+{
+	return setsockopt(sk, level, name, &val, sizeof(val));
+}
+
+FN_TEST(options)
+{
+	TEST_RES(get_bool(sk_recv, SOL_IP, IP_PKTINFO), _ret == 0);
+	TEST_RES(get_bool(sk_recv, SOL_IP, IP_RECVTTL), _ret == 0);
+	TEST_RES(get_bool(sk_recv, SOL_IP, IP_RECVTOS), _ret == 0);
+	TEST_RES(get_bool(sk_recv, SOL_IP, IP_RECVERR), _ret == 0);
+	TEST_RES(get_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMP), _ret == 0);
+	TEST_RES(get_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMPNS), _ret == 0);
+
+	TEST_SUCC(set_bool(sk_recv, SOL_IP, IP_RECVERR, 1));
+	TEST_RES(get_bool(sk_recv, SOL_IP, IP_RECVERR), _ret == 1);
+	TEST_SUCC(set_bool(sk_recv, SOL_IP, IP_RECVERR, 0));
+	TEST_RES(get_bool(sk_recv, SOL_IP, IP_RECVERR), _ret == 0);
+
+	// `SO_TIMESTAMPNS` overrides `SO_TIMESTAMP`.
+	TEST_SUCC(set_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMP, 1));
+	TEST_RES(get_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMP), _ret == 1);
+	TEST_SUCC(set_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMPNS, 1));
+	TEST_RES(get_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMP), _ret == 0);
+	TEST_RES(get_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMPNS), _ret == 1);
+
+	// Disabling either option disables both.
+	TEST_SUCC(set_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMP, 0));
+	TEST_RES(get_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMP), _ret == 0);
+	TEST_RES(get_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMPNS), _ret == 0);
+}
+END_TEST()
+
+static char cmsg_buf[256];
+
+// Receives a datagram with control messages.
+static ssize_t recv_with_cmsg(int sk, struct msghdr *msg, char *buf,
+			      size_t len, int flags)
+{
+	static struct iovec iov;
+	static struct sockaddr_in addr;
+
+	iov.iov_base = buf;
+	iov.iov_len = len;
+
+	memset(msg, 0, sizeof(*msg));
+	msg->msg_name = &addr;
+	msg->msg_namelen = sizeof(addr);
+	msg->msg_iov = &iov;
+	msg->msg_iovlen = 1;
+	msg->msg_control = cmsg_buf;
+	msg->msg_controllen = sizeof(cmsg_buf);
+
+	return recvmsg(sk, msg, flags);
+}
+
+static struct cmsghdr *find_cmsg(struct msghdr *msg, int level, int type)
+{
+	struct cmsghdr *cmsg;
+
+	for (cmsg = CMSG_FIRSTHDR(msg); cmsg != NULL;
+	     cmsg = CMSG_NXTHDR(msg, cmsg))
+		if (cmsg->cmsg_level == level && cmsg->cmsg_type == type)
+			return cmsg;
+
+	return NULL;
+}
+
+// Copies the payload of a control message that has exactly `len` bytes.
+static int get_cmsg(struct msghdr *msg, int level, int type, void *data,
+		    size_t len)
+{
+	struct cmsghdr *cmsg = find_cmsg(msg, level, type);
+
+	memset(data, 0, len);
+
+	if (cmsg == NULL || cmsg->cmsg_len != CMSG_LEN(len)) {
+		errno = ENOMSG;
+		return -1;
+	}
+
+	memcpy(data, CMSG_DATA(cmsg), len);
+	return 0;
+}
+
+FN_TEST(no_cmsg)
+{
+	struct msghdr msg;
+	char buf[16];
+
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&sk_addr,
+			sizeof(sk_addr)),
+		 _ret == 5);
+	TEST_RES(recv_with_cmsg(sk_recv, &msg, buf, sizeof(buf), 0),
+		 _ret == 5 && msg.msg_controllen == 0);
+}
+END_TEST()
+
+FN_TEST(pktinfo_and_ttl)
+{
+	struct msghdr msg;
+	struct in_pktinfo pktinfo;
+	int ttl;
+	char buf[16];
+
+	TEST_SUCC(set_bool(sk_recv, SOL_IP, IP_PKTINFO, 1));
+	TEST_SUCC(set_bool(sk_recv, SOL_IP, IP_RECVTTL, 1));
+
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&sk_addr,
+			sizeof(sk_addr)),
+		 _ret == 5);
+	TEST_RES(recv_with_cmsg(sk_recv, &msg, buf, sizeof(buf), 0),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0);
+
+	TEST_SUCC(get_cmsg(&msg, SOL_IP, IP_PKTINFO, &pktinfo,
+			   sizeof(pktinfo)));
+	TEST_RES(pktinfo.ipi_ifindex,
+		 _ret == lo_index &&
+			 pktinfo.ipi_addr.s_addr == htonl(INADDR_LOOPBACK) &&
+			 pktinfo.ipi_spec_dst.s_addr ==
+				 htonl(INADDR_LOOPBACK));
+
+	TEST_SUCC(get_cmsg(&msg, SOL_IP, IP_TTL, &ttl, sizeof(ttl)));
+	TEST_RES(ttl, _ret == 64);
+
+	TEST_SUCC(set_bool(sk_recv, SOL_IP, IP_PKTINFO, 0));
+	TEST_SUCC(set_bool(sk_recv, SOL_IP, IP_RECVTTL, 0));
+}
+END_TEST()
+
+#define TEST_TOS 0x2a
+
+FN_TEST(recv_tos)
+{
+	struct {
+		struct iphdr iph;
+		struct udphdr udph;
+		char payload[5];
+	} __attribute__((packed)) packet;
+	struct msghdr msg;
+	unsigned char tos;
+	char buf[16];
+	int sk_raw;
+
+	TEST_SUCC(set_bool(sk_recv, SOL_IP, IP_RECVTOS, 1));
+
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&sk_addr,
+			sizeof(sk_addr)),
+		 _ret == 5);
+	TEST_RES(recv_with_cmsg(sk_recv, &msg, buf, sizeof(buf), 0),
+		 _ret == 5);
+	TEST_SUCC(get_cmsg(&msg, SOL_IP, IP_TOS, &tos, sizeof(tos)));
+	TEST_RES(tos, _ret == 0);
+
+	// Build the IP header manually to send a packet with a non-zero TOS.
+	memset(&packet, 0, sizeof(packet));
+	packet.iph.version = 4;
+	packet.iph.ihl = sizeof(packet.iph) / 4;
+	packet.iph.tos = TEST_TOS;
+	packet.iph.tot_len = htons(sizeof(packet));
+	packet.iph.ttl = 64;
+	packet.iph.protocol = IPPROTO_UDP;
+	packet.iph.saddr = htonl(INADDR_LOOPBACK);
+	packet.iph.daddr = htonl(INADDR_LOOPBACK);
+	packet.udph.source = htons(0x1238);
+	packet.udph.dest = S_PORT;
+	packet.udph.len = htons(sizeof(packet.udph) + sizeof(packet.payload));
+	memcpy(packet.payload, "world", 5);
+
+	sk_raw = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_RAW));
+	TEST_RES(sendto(sk_raw, &packet, sizeof(packet), 0,
+			(struct sockaddr *)&sk_addr, sizeof(sk_addr)),
+		 _ret == sizeof(packet));
+	TEST_SUCC(close(sk_raw));
+
+	TEST_RES(recv_with_cmsg(sk_recv, &msg, buf, sizeof(buf), 0),
+		 _ret == 5 && memcmp(buf, "world", 5) == 0);
+	TEST_SUCC(get_cmsg(&msg, SOL_IP, IP_TOS, &tos, sizeof(tos)));
+	TEST_RES(tos, _ret == TEST_TOS);
+
+	TEST_SUCC(set_bool(sk_recv, SOL_IP, IP_RECVTOS, 0));
+}
+END_TEST()
+
+FN_TEST(send_pktinfo)
+{
+	char cbuf[CMSG_SPACE(sizeof(struct in_pktinfo))];
+	struct in_pktinfo pktinfo;
+	struct iovec iov = { .iov_base = "hello", .iov_len = 5 };
+	struct msghdr msg = {
+		.msg_name = &sk_addr,
+		.msg_namelen = sizeof(sk_addr),
+		.msg_iov = &iov,
+		.msg_iovlen = 1,
+		.msg_control = cbuf,
+		.msg_controllen = sizeof(cbuf),
+	};
+	struct cmsghdr *cmsg;
+	struct sockaddr_in addr;
+	socklen_t addrlen = sizeof(addr);
+	char buf[16];
+
+	memset(cbuf, 0, sizeof(cbuf));
+	memset(&pktinfo, 0, sizeof(pktinfo));
+	pktinfo.ipi_spec_dst.s_addr = htonl(INADDR_LOOPBACK);
+
+	cmsg = CMSG_FIRSTHDR(&msg);
+	cmsg->cmsg_level = SOL_IP;
+	cmsg->cmsg_type = IP_PKTINFO;
+	cmsg->cmsg_len = CMSG_LEN(sizeof(pktinfo));
+	memcpy(CMSG_DATA(cmsg), &pktinfo, sizeof(pktinfo));
+
+	TEST_RES(sendmsg(sk_send, &msg, 0), _ret == 5);
+	TEST_RES(recvfrom(sk_recv, buf, sizeof(buf), 0,
+			  (struct sockaddr *)&addr, &addrlen),
+		 _ret == 5 && addr.sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+
+	// The size of the message must be exact.
+	cmsg->cmsg_len = CMSG_LEN(sizeof(pktinfo) - 1);
+	TEST_ERRNO(sendmsg(sk_send, &msg, 0), EINVAL);
+}
+END_TEST()
+
+FN_TEST(timestamp)
+{
+	struct msghdr msg;
+	struct timeval before, after, tv;
+	struct timespec ts;
+	char buf[16];
+
+	TEST_SUCC(set_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMP, 1));
+
+	TEST_SUCC(gettimeofday(&before, NULL));
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&sk_addr,
+			sizeof(sk_addr)),
+		 _ret == 5);
+	TEST_RES(recv_with_cmsg(sk_recv, &msg, buf, sizeof(buf), 0),
+		 _ret == 5);
+	TEST_SUCC(gettimeofday(&after, NULL));
+
+	TEST_SUCC(get_cmsg(&msg, SOL_SOCKET, SCM_TIMESTAMP, &tv,
+			   sizeof(tv)));
+	// Allow a one-second tolerance because the clocks may have different
+	// resolutions.
+	TEST_RES(tv.tv_sec,
+		 _ret >= before.tv_sec - 1 && _ret <= after.tv_sec + 1);
+
+	TEST_SUCC(set_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMPNS, 1));
+
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&sk_addr,
+			sizeof(sk_addr)),
+		 _ret == 5);
+	TEST_RES(recv_with_cmsg(sk_recv, &msg, buf, sizeof(buf), 0),
+		 _ret == 5 && find_cmsg(&msg, SOL_SOCKET, SCM_TIMESTAMP) ==
+				      NULL);
+	TEST_SUCC(gettimeofday(&after, NULL));
+
+	TEST_SUCC(get_cmsg(&msg, SOL_SOCKET, SCM_TIMESTAMPNS, &ts,
+			   sizeof(ts)));
+	TEST_RES(ts.tv_sec, _ret >= before.tv_sec - 1 &&
+				    _ret <= after.tv_sec + 1 &&
+				    ts.tv_nsec < 1000000000);
+
+	TEST_SUCC(set_bool(sk_recv, SOL_SOCKET, SO_TIMESTAMPNS, 0));
+}
+END_TEST()
+
+FN_TEST(error_queue)
+{
+	struct msghdr msg;
+	char buf[16];
+
+	TEST_SUCC(set_bool(sk_send, SOL_IP, IP_RECVERR, 1));
+
+	// The error queue is empty. Receiving from it never blocks.
+	TEST_ERRNO(recv_with_cmsg(sk_send, &msg, buf, sizeof(buf),
+				  MSG_ERRQUEUE),
+		   EAGAIN);
+	TEST_ERRNO(recv_with_cmsg(sk_recv, &msg, buf, sizeof(buf),
+				  MSG_ERRQUEUE),
+		   EAGAIN);
+
+	TEST_SUCC(set_bool(sk_send, SOL_IP, IP_RECVERR, 0));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_recv));
+	CHECK(close(sk_send));
+}
+END_SETUP()
@@ -0,0 +1,523 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+
+#include "../test.h"
+
+// The address of `eth0` (10.0.2.15/24).
+#define ETHER_ADDR "10.0.2.15"
+
+static int sk_listen6;
+static int sk_listen4;
+
+static struct sockaddr_in6 listen6_addr;
+static struct sockaddr_in listen4_addr;
+
+FN_SETUP(listen)
+{
+	socklen_t addrlen;
+
+	sk_listen6 = CHECK(socket(AF_INET6, SOCK_STREAM, 0));
+	listen6_addr.sin6_family = AF_INET6;
+	listen6_addr.sin6_addr = in6addr_loopback;
+	CHECK(bind(sk_listen6, (struct sockaddr *)&listen6_addr,
+		   sizeof(listen6_addr)));
+	CHECK(listen(sk_listen6, 3));
+	addrlen = sizeof(listen6_addr);
+	CHECK(getsockname(sk_listen6, (struct sockaddr *)&listen6_addr,
+			  &addrlen));
+
+	sk_listen4 = CHECK(socket(AF_INET, SOCK_STREAM, 0));
+	listen4_addr.sin_family = AF_INET;
+	listen4_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+	CHECK(bind(sk_listen4, (struct sockaddr *)&listen4_addr,
+		   sizeof(listen4_addr)));
+	CHECK(listen(sk_listen4, 3));
+	addrlen = sizeof(listen4_addr);
+	CHECK(getsockname(sk_listen4, (struct sockaddr *)&listen4_addr,
+			  &addrlen));
+}
+END_SETUP()
+
+FN_TEST(getsockname)
+{
+	struct sockaddr_in6 addr;
+	socklen_t addrlen = sizeof(addr);
+
+	TEST_RES(getsockname(sk_listen6, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) && addr.sin6_family == AF_INET6 &&
+			 addr.sin6_port != 0 &&
+			 IN6_IS_ADDR_LOOPBACK(&addr.sin6_addr));
+}
+END_TEST()
+
+FN_TEST(tcp_connect)
+{
+	int sk_client, sk_accepted;
+	struct sockaddr_in6 addr;
+	socklen_t addrlen = sizeof(addr);
+	char buf[6];
+
+	sk_client = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_client, (struct sockaddr *)&listen6_addr,
+			  sizeof(listen6_addr)));
+
+	sk_accepted = TEST_RES(
+		accept(sk_listen6, (struct sockaddr *)&addr, &addrlen),
+		addrlen == sizeof(addr) && addr.sin6_family == AF_INET6 &&
+			IN6_IS_ADDR_LOOPBACK(&addr.sin6_addr));
+
+	TEST_RES(getpeername(sk_client, (struct sockaddr *)&addr, &addrlen),
+		 addr.sin6_family == AF_INET6 &&
+			 addr.sin6_port == listen6_addr.sin6_port &&
+			 IN6_IS_ADDR_LOOPBACK(&addr.sin6_addr));
+
+	TEST_RES(send(sk_client, "hello", 6, 0), _ret == 6);
+	TEST_RES(recv(sk_accepted, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "hello") == 0);
+
+	TEST_SUCC(close(sk_accepted));
+	TEST_SUCC(close(sk_client));
+}
+END_TEST()
+
+FN_TEST(tcp_connect_v4mapped)
+{
+	int sk_client, sk_accepted;
+	struct sockaddr_in6 addr = { .sin6_family = AF_INET6 };
+	socklen_t addrlen = sizeof(addr);
+
+	addr.sin6_port = listen4_addr.sin_port;
+	TEST_SUCC(inet_pton(AF_INET6, "::ffff:127.0.0.1", &addr.sin6_addr));
+
+	sk_client = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_client, (struct sockaddr *)&addr, sizeof(addr)));
+
+	sk_accepted = TEST_SUCC(accept(sk_listen4, NULL, NULL));
+
+	TEST_RES(getsockname(sk_client, (struct sockaddr *)&addr, &addrlen),
+		 addr.sin6_family == AF_INET6 &&
+			 IN6_IS_ADDR_V4MAPPED(&addr.sin6_addr));
+
+	TEST_SUCC(close(sk_accepted));
+	TEST_SUCC(close(sk_client));
+}
+END_TEST()
+
+FN_TEST(v6only)
+{
+	int sk;
+	int opt;
+	socklen_t optlen = sizeof(opt);
+	struct sockaddr_in6 addr = { .sin6_family = AF_INET6 };
+
+	addr.sin6_port = listen4_addr.sin_port;
+	TEST_SUCC(inet_pton(AF_INET6, "::ffff:127.0.0.1", &addr.sin6_addr));
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+
+	TEST_RES(getsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, &optlen),
+		 opt == 0);
+
+	opt = 1;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, sizeof(opt)));
+	TEST_RES(getsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, &optlen),
+		 opt == 1);
+
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&addr, sizeof(addr)),
+		   ENETUNREACH);
+
+	addr.sin6_port = 0;
+	addr.sin6_addr = in6addr_loopback;
+	TEST_SUCC(bind(sk, (struct sockaddr *)&addr, sizeof(addr)));
+
+	opt = 0;
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, sizeof(opt)),
+		   EINVAL);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(unicast_hops)
+{
+	int sk;
+	int opt;
+	socklen_t optlen = sizeof(opt);
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+
+	TEST_RES(getsockopt(sk, IPPROTO_IPV6, IPV6_UNICAST_HOPS, &opt, &optlen),
+		 opt == 64);
+
+	opt = 255;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IPV6, IPV6_UNICAST_HOPS, &opt,
+			     sizeof(opt)));
+	TEST_RES(getsockopt(sk, IPPROTO_IPV6, IPV6_UNICAST_HOPS, &opt, &optlen),
+		 opt == 255);
+
+	opt = 256;
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IPV6, IPV6_UNICAST_HOPS, &opt,
+			      sizeof(opt)),
+		   EINVAL);
+
+	opt = -1;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IPV6, IPV6_UNICAST_HOPS, &opt,
+			     sizeof(opt)));
+	TEST_RES(getsockopt(sk, IPPROTO_IPV6, IPV6_UNICAST_HOPS, &opt, &optlen),
+		 opt == 64);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(udp_sendto)
+{
+	int sk_recv, sk_send;
+	struct sockaddr_in6 recv_addr = { .sin6_family = AF_INET6 };
+	struct sockaddr_in6 addr;
+	socklen_t addrlen = sizeof(recv_addr);
+	char buf[6];
+
+	recv_addr.sin6_addr = in6addr_loopback;
+
+	sk_recv = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	TEST_SUCC(bind(sk_recv, (struct sockaddr *)&recv_addr,
+		       sizeof(recv_addr)));
+	TEST_SUCC(getsockname(sk_recv, (struct sockaddr *)&recv_addr,
+			      &addrlen));
+
+	sk_send = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	TEST_RES(sendto(sk_send, "hello", 6, 0, (struct sockaddr *)&recv_addr,
+			sizeof(recv_addr)),
+		 _ret == 6);
+
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(sk_recv, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == 6 && strcmp(buf, "hello") == 0 &&
+			 addrlen == sizeof(addr) &&
+			 addr.sin6_family == AF_INET6 &&
+			 IN6_IS_ADDR_LOOPBACK(&addr.sin6_addr));
+
+	TEST_SUCC(close(sk_send));
+	TEST_SUCC(close(sk_recv));
+}
+END_TEST()
+
+FN_TEST(family_mismatch)
+{
+	int sk;
+	int opt;
+	socklen_t optlen = sizeof(opt);
+	struct sockaddr_in6 addr = { .sin6_family = AF_INET6 };
+
+	addr.sin6_addr = in6addr_loopback;
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&addr, sizeof(addr)),
+		   EAFNOSUPPORT);
+	TEST_ERRNO(getsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, &optlen),
+		   EOPNOTSUPP);
+	opt = 1;
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, sizeof(opt)),
+		   ENOPROTOOPT);
+	TEST_SUCC(close(sk));
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_ERRNO(getsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, &optlen),
+		   EOPNOTSUPP);
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, sizeof(opt)),
+		   ENOPROTOOPT);
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(tcp_bind_any)
+{
+	int sk_any, sk_client, sk_accepted, sk;
+	struct sockaddr_in6 any_addr = { .sin6_family = AF_INET6 };
+	struct sockaddr_in6 addr;
+	struct sockaddr_in addr4 = { .sin_family = AF_INET };
+	socklen_t addrlen = sizeof(any_addr);
+
+	any_addr.sin6_addr = in6addr_any;
+
+	sk_any = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_SUCC(
+		bind(sk_any, (struct sockaddr *)&any_addr, sizeof(any_addr)));
+	TEST_RES(getsockname(sk_any, (struct sockaddr *)&any_addr, &addrlen),
+		 addrlen == sizeof(any_addr) && any_addr.sin6_port != 0 &&
+			 IN6_IS_ADDR_UNSPECIFIED(&any_addr.sin6_addr));
+	TEST_SUCC(listen(sk_any, 3));
+
+	// IPv6 connections are accepted
+	addr = any_addr;
+	addr.sin6_addr = in6addr_loopback;
+	sk_client = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_client, (struct sockaddr *)&addr, sizeof(addr)));
+	addrlen = sizeof(addr);
+	sk_accepted = TEST_RES(
+		accept(sk_any, (struct sockaddr *)&addr, &addrlen),
+		addrlen == sizeof(addr) && addr.sin6_family == AF_INET6 &&
+			IN6_IS_ADDR_LOOPBACK(&addr.sin6_addr));
+	TEST_RES(getsockname(sk_accepted, (struct sockaddr *)&addr, &addrlen),
+		 addr.sin6_port == any_addr.sin6_port &&
+			 IN6_IS_ADDR_LOOPBACK(&addr.sin6_addr));
+	TEST_SUCC(close(sk_accepted));
+	TEST_SUCC(close(sk_client));
+
+	// IPv4 connections are accepted via IPv4-mapped IPv6 addresses
+	addr4.sin_port = any_addr.sin6_port;
+	addr4.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+	sk_client = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_client, (struct sockaddr *)&addr4, sizeof(addr4)));
+	addrlen = sizeof(addr);
+	sk_accepted = TEST_RES(
+		accept(sk_any, (struct sockaddr *)&addr, &addrlen),
+		addrlen == sizeof(addr) && addr.sin6_family == AF_INET6 &&
+			IN6_IS_ADDR_V4MAPPED(&addr.sin6_addr) &&
+			addr.sin6_addr.s6_addr32[3] == htonl(INADDR_LOOPBACK));
+	TEST_RES(getsockname(sk_accepted, (struct sockaddr *)&addr, &addrlen),
+		 addr.sin6_port == any_addr.sin6_port &&
+			 IN6_IS_ADDR_V4MAPPED(&addr.sin6_addr));
+	TEST_SUCC(close(sk_accepted));
+	TEST_SUCC(close(sk_client));
+
+	// The port is in use for both IPv4 and IPv6
+	sk = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&addr4, sizeof(addr4)),
+		   EADDRINUSE);
+	TEST_SUCC(close(sk));
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	addr = any_addr;
+	addr.sin6_addr = in6addr_loopback;
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&addr, sizeof(addr)),
+		   EADDRINUSE);
+	TEST_SUCC(close(sk));
+
+	TEST_SUCC(close(sk_any));
+}
+END_TEST()
+
+FN_TEST(tcp_bind_any_v6only)
+{
+	int sk_any, sk_client, sk_accepted, sk;
+	int opt = 1;
+	struct sockaddr_in6 any_addr = { .sin6_family = AF_INET6 };
+	struct sockaddr_in6 addr;
+	struct sockaddr_in addr4 = { .sin_family = AF_INET };
+	socklen_t addrlen = sizeof(any_addr);
+
+	any_addr.sin6_addr = in6addr_any;
+
+	sk_any = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_SUCC(setsockopt(sk_any, IPPROTO_IPV6, IPV6_V6ONLY, &opt,
+			     sizeof(opt)));
+	TEST_SUCC(
+		bind(sk_any, (struct sockaddr *)&any_addr, sizeof(any_addr)));
+	TEST_SUCC(getsockname(sk_any, (struct sockaddr *)&any_addr, &addrlen));
+	TEST_SUCC(listen(sk_any, 3));
+
+	// IPv6 connections are accepted
+	addr = any_addr;
+	addr.sin6_addr = in6addr_loopback;
+	sk_client = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_client, (struct sockaddr *)&addr, sizeof(addr)));
+	sk_accepted = TEST_SUCC(accept(sk_any, NULL, NULL));
+	TEST_SUCC(close(sk_accepted));
+	TEST_SUCC(close(sk_client));
+
+	// IPv4 connections are refused
+	addr4.sin_port = any_addr.sin6_port;
+	addr4.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+	sk_client = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_ERRNO(connect(sk_client, (struct sockaddr *)&addr4, sizeof(addr4)),
+		   ECONNREFUSED);
+	TEST_SUCC(close(sk_client));
+
+	// The port is not in use for IPv4
+	sk = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(bind(sk, (struct sockaddr *)&addr4, sizeof(addr4)));
+	TEST_SUCC(close(sk));
+
+	TEST_SUCC(close(sk_any));
+}
+END_TEST()
+
+FN_TEST(tcp_bind_any_ether)
+{
+	int sk_any, sk_client, sk_accepted;
+	struct sockaddr_in6 any_addr = { .sin6_family = AF_INET6 };
+	struct sockaddr_in6 addr;
+	struct sockaddr_in addr4 = { .sin_family = AF_INET };
+	socklen_t addrlen = sizeof(any_addr);
+	char buf[6];
+
+	any_addr.sin6_addr = in6addr_any;
+
+	sk_any = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_SUCC(
+		bind(sk_any, (struct sockaddr *)&any_addr, sizeof(any_addr)));
+	TEST_SUCC(getsockname(sk_any, (struct sockaddr *)&any_addr, &addrlen));
+	TEST_SUCC(listen(sk_any, 3));
+
+	// Connections via the Ethernet iface are accepted
+	addr4.sin_port = any_addr.sin6_port;
+	TEST_RES(inet_pton(AF_INET, ETHER_ADDR, &addr4.sin_addr), _ret == 1);
+	sk_client = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_client, (struct sockaddr *)&addr4, sizeof(addr4)));
+	addrlen = sizeof(addr);
+	sk_accepted = TEST_RES(
+		accept(sk_any, (struct sockaddr *)&addr, &addrlen),
+		addrlen == sizeof(addr) && addr.sin6_family == AF_INET6 &&
+			IN6_IS_ADDR_V4MAPPED(&addr.sin6_addr) &&
+			addr.sin6_addr.s6_addr32[3] == addr4.sin_addr.s_addr);
+	TEST_RES(getsockname(sk_accepted, (struct sockaddr *)&addr, &addrlen),
+		 addr.sin6_port == any_addr.sin6_port &&
+			 IN6_IS_ADDR_V4MAPPED(&addr.sin6_addr) &&
+			 addr.sin6_addr.s6_addr32[3] == addr4.sin_addr.s_addr);
+
+	TEST_RES(send(sk_client, "hello", 6, 0), _ret == 6);
+	TEST_RES(recv(sk_accepted, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "hello") == 0);
+
+	TEST_SUCC(close(sk_accepted));
+	TEST_SUCC(close(sk_client));
+	TEST_SUCC(close(sk_any));
+}
+END_TEST()
+
+FN_TEST(udp_bind_any_ether)
+{
+	int sk_any, sk_send4;
+	struct sockaddr_in6 any_addr = { .sin6_family = AF_INET6 };
+	struct sockaddr_in6 addr;
+	struct sockaddr_in addr4 = { .sin_family = AF_INET };
+	socklen_t addrlen = sizeof(any_addr);
+	char buf[6];
+
+	any_addr.sin6_addr = in6addr_any;
+
+	sk_any = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	TEST_SUCC(
+		bind(sk_any, (struct sockaddr *)&any_addr, sizeof(any_addr)));
+	TEST_SUCC(getsockname(sk_any, (struct sockaddr *)&any_addr, &addrlen));
+
+	// Datagrams via the Ethernet iface are received
+	addr4.sin_port = any_addr.sin6_port;
+	TEST_RES(inet_pton(AF_INET, ETHER_ADDR, &addr4.sin_addr), _ret == 1);
+	sk_send4 = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_RES(sendto(sk_send4, "hello", 6, 0, (struct sockaddr *)&addr4,
+			sizeof(addr4)),
+		 _ret == 6);
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(sk_any, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == 6 && strcmp(buf, "hello") == 0 &&
+			 IN6_IS_ADDR_V4MAPPED(&addr.sin6_addr) &&
+			 addr.sin6_addr.s6_addr32[3] == addr4.sin_addr.s_addr);
+
+	TEST_SUCC(close(sk_send4));
+	TEST_SUCC(close(sk_any));
+}
+END_TEST()
+
+FN_TEST(tcp_connect_from_any)
+{
+	int sk_client, sk_accepted;
+	struct sockaddr_in6 addr = { .sin6_family = AF_INET6 };
+	socklen_t addrlen = sizeof(addr);
+
+	addr.sin6_addr = in6addr_any;
+
+	sk_client = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_SUCC(bind(sk_client, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_SUCC(connect(sk_client, (struct sockaddr *)&listen6_addr,
+			  sizeof(listen6_addr)));
+	TEST_RES(getsockname(sk_client, (struct sockaddr *)&addr, &addrlen),
+		 addr.sin6_port != 0 && IN6_IS_ADDR_LOOPBACK(&addr.sin6_addr));
+
+	sk_accepted = TEST_SUCC(accept(sk_listen6, NULL, NULL));
+
+	TEST_SUCC(close(sk_accepted));
+	TEST_SUCC(close(sk_client));
+}
+END_TEST()
+
+FN_TEST(udp_bind_any)
+{
+	int sk_any, sk_send4, sk_send6;
+	struct sockaddr_in6 any_addr = { .sin6_family = AF_INET6 };
+	struct sockaddr_in6 addr;
+	struct sockaddr_in addr4 = { .sin_family = AF_INET };
+	socklen_t addrlen = sizeof(any_addr);
+	char buf[6];
+
+	any_addr.sin6_addr = in6addr_any;
+
+	sk_any = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	TEST_SUCC(
+		bind(sk_any, (struct sockaddr *)&any_addr, sizeof(any_addr)));
+	TEST_RES(getsockname(sk_any, (struct sockaddr *)&any_addr, &addrlen),
+		 any_addr.sin6_port != 0 &&
+			 IN6_IS_ADDR_UNSPECIFIED(&any_addr.sin6_addr));
+
+	// IPv6 datagrams are received
+	addr = any_addr;
+	addr.sin6_addr = in6addr_loopback;
+	sk_send6 = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	TEST_RES(sendto(sk_send6, "hello", 6, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 6);
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(sk_any, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == 6 && strcmp(buf, "hello") == 0 &&
+			 IN6_IS_ADDR_LOOPBACK(&addr.sin6_addr));
+
+	// IPv4 datagrams are received via IPv4-mapped IPv6 addresses
+	addr4.sin_port = any_addr.sin6_port;
+	addr4.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+	sk_send4 = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_RES(sendto(sk_send4, "world", 6, 0, (struct sockaddr *)&addr4,
+			sizeof(addr4)),
+		 _ret == 6);
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(sk_any, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == 6 && strcmp(buf, "world") == 0 &&
+			 IN6_IS_ADDR_V4MAPPED(&addr.sin6_addr) &&
+			 addr.sin6_addr.s6_addr32[3] == htonl(INADDR_LOOPBACK));
+
+	// Replies to IPv4-mapped IPv6 addresses are sent via IPv4
+	TEST_RES(sendto(sk_any, "reply", 6, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 6);
+	TEST_RES(recv(sk_send4, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "reply") == 0);
+
+	// The port is in use for IPv4
+	TEST_ERRNO(bind(sk_send4, (struct sockaddr *)&addr4, sizeof(addr4)),
+		   EINVAL);
+	TEST_SUCC(close(sk_send4));
+	sk_send4 = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	TEST_ERRNO(bind(sk_send4, (struct sockaddr *)&addr4, sizeof(addr4)),
+		   EADDRINUSE);
+
+	TEST_SUCC(close(sk_send4));
+	TEST_SUCC(close(sk_send6));
+	TEST_SUCC(close(sk_any));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_listen4));
+	CHECK(close(sk_listen6));
+}
+END_SETUP()
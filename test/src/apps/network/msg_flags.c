@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <signal.h>
+#include <string.h>
+#include <unistd.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+
+#include "../test.h"
+
+#define S_PORT htons(0x1239)
+
+static struct sockaddr_in sk_addr;
+
+static int sk_udp_recv;
+static int sk_udp_send;
+
+static int sk_dgram[2];
+static int sk_seqpacket[2];
+static int sk_stream[2];
+
+static volatile int sigpipe_count;
+
+static void sigpipe_handler(int sig)
+{
+	(void)sig;
+	sigpipe_count++;
+}
+
+FN_SETUP(general)
+{
+	sk_addr.sin_family = AF_INET;
+	sk_addr.sin_port = S_PORT;
+	sk_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	sk_udp_recv = CHECK(socket(PF_INET, SOCK_DGRAM, 0));
+	CHECK(bind(sk_udp_recv, (struct sockaddr *)&sk_addr, sizeof(sk_addr)));
+
+	sk_udp_send = CHECK(socket(PF_INET, SOCK_DGRAM, 0));
+	CHECK(connect(sk_udp_send, (struct sockaddr *)&sk_addr,
+		      sizeof(sk_addr)));
+
+	CHECK(socketpair(PF_UNIX, SOCK_DGRAM, 0, sk_dgram));
+	CHECK(socketpair(PF_UNIX, SOCK_SEQPACKET, 0, sk_seqpacket));
+	CHECK(socketpair(PF_UNIX, SOCK_STREAM, 0, sk_stream));
+
+	CHECK_WITH(signal(SIGPIPE, sigpipe_handler), _ret != SIG_ERR);
+}
+END_SETUP()
+
+FN_TEST(dontwait)
+{
+	char buf[16];
+
+	// The sockets are blocking, but `MSG_DONTWAIT` makes the calls
+	// nonblocking.
+	TEST_ERRNO(recv(sk_udp_recv, buf, sizeof(buf), MSG_DONTWAIT), EAGAIN);
+	TEST_ERRNO(recv(sk_dgram[0], buf, sizeof(buf), MSG_DONTWAIT), EAGAIN);
+	TEST_ERRNO(recv(sk_seqpacket[0], buf, sizeof(buf), MSG_DONTWAIT),
+		   EAGAIN);
+	TEST_ERRNO(recv(sk_stream[0], buf, sizeof(buf), MSG_DONTWAIT), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(peek_and_trunc_udp)
+{
+	char buf[16];
+
+	TEST_RES(send(sk_udp_send, "hello", 5, 0), _ret == 5);
+
+	TEST_RES(recv(sk_udp_recv, buf, 2, MSG_PEEK),
+		 _ret == 2 && memcmp(buf, "he", 2) == 0);
+	TEST_RES(recv(sk_udp_recv, buf, 2, MSG_PEEK | MSG_TRUNC),
+		 _ret == 5 && memcmp(buf, "he", 2) == 0);
+	TEST_RES(recv(sk_udp_recv, buf, 3, MSG_TRUNC),
+		 _ret == 5 && memcmp(buf, "hel", 3) == 0);
+
+	TEST_ERRNO(recv(sk_udp_recv, buf, sizeof(buf), MSG_DONTWAIT), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(peek_and_trunc_unix_dgram)
+{
+	char buf[16];
+
+	TEST_RES(send(sk_dgram[1], "hello", 5, 0), _ret == 5);
+
+	TEST_RES(recv(sk_dgram[0], buf, sizeof(buf), MSG_PEEK),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0);
+	TEST_RES(recv(sk_dgram[0], buf, 2, MSG_PEEK | MSG_TRUNC),
+		 _ret == 5 && memcmp(buf, "he", 2) == 0);
+	TEST_RES(recv(sk_dgram[0], buf, 3, MSG_TRUNC),
+		 _ret == 5 && memcmp(buf, "hel", 3) == 0);
+
+	TEST_ERRNO(recv(sk_dgram[0], buf, sizeof(buf), MSG_DONTWAIT), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(peek_and_trunc_unix_seqpacket)
+{
+	char buf[16];
+
+	TEST_RES(send(sk_seqpacket[1], "hello", 5, 0), _ret == 5);
+
+	TEST_RES(recv(sk_seqpacket[0], buf, sizeof(buf), MSG_PEEK),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0);
+	TEST_RES(recv(sk_seqpacket[0], buf, 2, MSG_PEEK | MSG_TRUNC),
+		 _ret == 5 && memcmp(buf, "he", 2) == 0);
+	TEST_RES(recv(sk_seqpacket[0], buf, 3, MSG_TRUNC),
+		 _ret == 5 && memcmp(buf, "hel", 3) == 0);
+
+	TEST_ERRNO(recv(sk_seqpacket[0], buf, sizeof(buf), MSG_DONTWAIT),
+		   EAGAIN);
+}
+END_TEST()
+
+FN_TEST(peek_unix_stream)
+{
+	char buf[16];
+
+	TEST_RES(send(sk_stream[1], "hello", 5, 0), _ret == 5);
+
+	TEST_RES(recv(sk_stream[0], buf, 2, MSG_PEEK),
+		 _ret == 2 && memcmp(buf, "he", 2) == 0);
+	TEST_RES(recv(sk_stream[0], buf, sizeof(buf), MSG_PEEK),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0);
+	TEST_RES(recv(sk_stream[0], buf, 3, 0),
+		 _ret == 3 && memcmp(buf, "hel", 3) == 0);
+	TEST_RES(recv(sk_stream[0], buf, sizeof(buf), 0),
+		 _ret == 2 && memcmp(buf, "lo", 2) == 0);
+}
+END_TEST()
+
+FN_TEST(waitall_unix_stream)
+{
+	char buf[16];
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		write(sk_stream[1], "hel", 3);
+		usleep(100 * 1000);
+		write(sk_stream[1], "lo", 2);
+		_exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(recv(sk_stream[0], buf, 5, MSG_WAITALL),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0);
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+}
+END_TEST()
+
+FN_TEST(oob)
+{
+	char buf[16];
+
+	TEST_ERRNO(send(sk_udp_send, "a", 1, MSG_OOB), EOPNOTSUPP);
+	TEST_ERRNO(send(sk_dgram[1], "a", 1, MSG_OOB), EOPNOTSUPP);
+	TEST_ERRNO(send(sk_stream[1], "a", 1, MSG_OOB), EOPNOTSUPP);
+
+	TEST_ERRNO(recv(sk_dgram[0], buf, sizeof(buf), MSG_OOB), EOPNOTSUPP);
+	TEST_ERRNO(recv(sk_stream[0], buf, sizeof(buf), MSG_OOB), EOPNOTSUPP);
+}
+END_TEST()
+
+FN_TEST(mmsg)
+{
+	char bufs[3][16];
+	struct iovec iovs[3];
+	struct mmsghdr msgs[3];
+	struct timespec timeout = { .tv_sec = 1, .tv_nsec = 0 };
+	int i;
+
+	memset(msgs, 0, sizeof(msgs));
+	for (i = 0; i < 3; i++) {
+		iovs[i].iov_base = bufs[i];
+		iovs[i].iov_len = sizeof(bufs[i]);
+		msgs[i].msg_hdr.msg_iov = &iovs[i];
+		msgs[i].msg_hdr.msg_iovlen = 1;
+	}
+
+	strcpy(bufs[0], "first");
+	iovs[0].iov_len = 5;
+	strcpy(bufs[1], "second");
+	iovs[1].iov_len = 6;
+
+	TEST_RES(sendmmsg(sk_udp_send, msgs, 2, 0),
+		 _ret == 2 && msgs[0].msg_len == 5 && msgs[1].msg_len == 6);
+
+	memset(bufs, 0, sizeof(bufs));
+	for (i = 0; i < 3; i++) {
+		iovs[i].iov_len = sizeof(bufs[i]);
+		msgs[i].msg_len = 0;
+	}
+
+	// `MSG_WAITFORONE` stops after the first message once no more messages
+	// are available.
+	TEST_RES(recvmmsg(sk_udp_recv, msgs, 3, MSG_WAITFORONE, NULL),
+		 _ret == 2 && msgs[0].msg_len == 5 && msgs[1].msg_len == 6 &&
+			 memcmp(bufs[0], "first", 5) == 0 &&
+			 memcmp(bufs[1], "second", 6) == 0);
+
+	TEST_ERRNO(recvmmsg(sk_udp_recv, msgs, 3, MSG_DONTWAIT, &timeout),
+		   EAGAIN);
+}
+END_TEST()
+
+FN_TEST(nosignal)
+{
+	int sk_pair[2];
+
+	TEST_SUCC(socketpair(PF_UNIX, SOCK_STREAM, 0, sk_pair));
+	TEST_SUCC(close(sk_pair[1]));
+
+	sigpipe_count = 0;
+
+	TEST_ERRNO(send(sk_pair[0], "a", 1, MSG_NOSIGNAL), EPIPE);
+	TEST_RES(sigpipe_count, _ret == 0);
+
+	TEST_ERRNO(send(sk_pair[0], "a", 1, 0), EPIPE);
+	TEST_RES(sigpipe_count, _ret == 1);
+
+	TEST_SUCC(close(sk_pair[0]));
+}
+END_TEST()
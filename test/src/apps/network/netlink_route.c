@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
+#include <arpa/inet.h>
 #include <net/if.h>
 #include <netlink/route/addr.h>
 #include <unistd.h>
@@ -328,6 +329,135 @@ FN_TEST(get_addr_error)
 }
 END_TEST()
 
+FN_TEST(new_addr)
+{
+	int sock_fd;
+	struct sockaddr_nl sa;
+
+	sock_fd = TEST_SUCC(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+
+	memset(&sa, 0, sizeof(sa));
+	sa.nl_family = AF_NETLINK;
+
+	TEST_SUCC(bind(sock_fd, (struct sockaddr *)&sa, sizeof(sa)));
+
+	struct nl_req req;
+	INIT_REQ(req);
+	req.hdr.nlmsg_len = sizeof(req);
+	req.hdr.nlmsg_type = RTM_NEWADDR;
+	req.hdr.nlmsg_flags = NLM_F_REQUEST | NLM_F_ACK;
+	req.ifa.ifa_family = AF_INET;
+	req.ifa.ifa_prefixlen = 8;
+	req.ifa.ifa_index = if_nametoindex(LOOPBACK_NAME);
+	req.ahdr.nla_type = IFA_LOCAL;
+	req.ahdr.nla_len = sizeof(req.ahdr) + sizeof(req.abuf);
+	*(in_addr_t *)req.abuf = htonl(INADDR_LOOPBACK);
+
+	struct iovec iov = { &req, sizeof(req) };
+	struct msghdr msg = { &sa, sizeof(sa), &iov, 1, NULL, 0, 0 };
+
+	// The address already exists
+	TEST_ERROR_SEGMENT(EEXIST);
+
+	// Replace the existing address
+	req.hdr.nlmsg_flags |= NLM_F_REPLACE;
+	TEST_ERROR_SEGMENT(0);
+
+	// Invalid prefix length
+	req.ifa.ifa_prefixlen = 33;
+	TEST_ERROR_SEGMENT(EINVAL);
+
+	// Invalid index
+	req.ifa.ifa_prefixlen = 8;
+	req.ifa.ifa_index = 9999;
+	TEST_ERROR_SEGMENT(ENODEV);
+
+	TEST_SUCC(close(sock_fd));
+}
+END_TEST()
+
+int get_link_flags(int sock_fd, int index)
+{
+	struct {
+		struct nlmsghdr hdr;
+		struct ifinfomsg ifi;
+	} req;
+
+	memset(&req, 0, sizeof(req));
+	req.hdr.nlmsg_len = sizeof(req);
+	req.hdr.nlmsg_type = RTM_GETLINK;
+	req.hdr.nlmsg_flags = NLM_F_REQUEST;
+	req.hdr.nlmsg_seq = 1;
+	req.ifi.ifi_family = AF_UNSPEC;
+	req.ifi.ifi_index = index;
+
+	if (send(sock_fd, &req, sizeof(req), 0) != sizeof(req))
+		return -1;
+	if (recv(sock_fd, buffer, BUFFER_SIZE, 0) < 0)
+		return -1;
+	if (((struct nlmsghdr *)buffer)->nlmsg_type != RTM_NEWLINK)
+		return -1;
+
+	return ((struct ifinfomsg *)NLMSG_DATA(buffer))->ifi_flags;
+}
+
+FN_TEST(new_link)
+{
+	int sock_fd;
+	struct sockaddr_nl sa;
+	int index = if_nametoindex(LOOPBACK_NAME);
+
+	sock_fd = TEST_SUCC(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+
+	memset(&sa, 0, sizeof(sa));
+	sa.nl_family = AF_NETLINK;
+
+	TEST_SUCC(bind(sock_fd, (struct sockaddr *)&sa, sizeof(sa)));
+
+	struct {
+		struct nlmsghdr hdr;
+		struct ifinfomsg ifi;
+	} req;
+
+	memset(&req, 0, sizeof(req));
+	req.hdr.nlmsg_len = sizeof(req);
+	req.hdr.nlmsg_type = RTM_NEWLINK;
+	req.hdr.nlmsg_flags = NLM_F_REQUEST | NLM_F_ACK;
+	req.hdr.nlmsg_seq = 1;
+	req.ifi.ifi_family = AF_UNSPEC;
+	req.ifi.ifi_index = index;
+
+	struct iovec iov = { &req, sizeof(req) };
+	struct msghdr msg = { &sa, sizeof(sa), &iov, 1, NULL, 0, 0 };
+
+	// Bring the link down
+	req.ifi.ifi_flags = 0;
+	req.ifi.ifi_change = IFF_UP;
+	TEST_ERROR_SEGMENT(0);
+	TEST_RES(get_link_flags(sock_fd, index),
+		 _ret >= 0 && !(_ret & IFF_UP) && !(_ret & IFF_RUNNING) &&
+			 (_ret & IFF_LOOPBACK));
+
+	// Bring the link up
+	req.ifi.ifi_flags = IFF_UP;
+	TEST_ERROR_SEGMENT(0);
+	TEST_RES(get_link_flags(sock_fd, index),
+		 _ret >= 0 && (_ret & IFF_UP) && (_ret & IFF_RUNNING) &&
+			 (_ret & IFF_LOOPBACK));
+
+	// The link already exists
+	req.hdr.nlmsg_flags |= NLM_F_EXCL;
+	TEST_ERROR_SEGMENT(EEXIST);
+
+	// The link does not exist
+	req.hdr.nlmsg_flags &= ~NLM_F_EXCL;
+	req.ifi.ifi_index = 9999;
+	TEST_ERROR_SEGMENT(ENODEV);
+
+	TEST_SUCC(close(sock_fd));
+}
+END_TEST()
+
 FN_TEST(bufsize_msgsize)
 {
 	int sock_fd;
@@ -0,0 +1,361 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <net/if.h>
+#include <net/if_arp.h>
+#include <net/ethernet.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <netinet/udp.h>
+#include <arpa/inet.h>
+#include <linux/if_packet.h>
+#include <linux/filter.h>
+
+#include "../test.h"
+
+#define UDP_PORT 9876
+#define MESSAGE "hello"
+
+static int lo_index;
+static int sk_udp;
+static struct sockaddr_in udp_addr;
+
+FN_SETUP(lo_index)
+{
+	lo_index = CHECK(if_nametoindex("lo"));
+}
+END_SETUP()
+
+FN_SETUP(udp)
+{
+	udp_addr.sin_family = AF_INET;
+	udp_addr.sin_port = htons(UDP_PORT);
+	udp_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	sk_udp = CHECK(socket(AF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	CHECK(bind(sk_udp, (struct sockaddr *)&udp_addr, sizeof(udp_addr)));
+}
+END_SETUP()
+
+static int open_packet_socket(int type, int protocol)
+{
+	int sk;
+	struct sockaddr_ll addr;
+
+	sk = socket(AF_PACKET, type | SOCK_NONBLOCK, htons(protocol));
+	if (sk < 0)
+		return sk;
+
+	memset(&addr, 0, sizeof(addr));
+	addr.sll_family = AF_PACKET;
+	addr.sll_protocol = htons(protocol);
+	addr.sll_ifindex = lo_index;
+	if (bind(sk, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+		close(sk);
+		return -1;
+	}
+
+	return sk;
+}
+
+static int send_udp(void)
+{
+	return sendto(sk_udp, MESSAGE, sizeof(MESSAGE), 0,
+		      (struct sockaddr *)&udp_addr, sizeof(udp_addr));
+}
+
+static int recv_udp(void)
+{
+	char buf[sizeof(MESSAGE)];
+
+	return recv(sk_udp, buf, sizeof(buf), 0);
+}
+
+#define IP_PACKET_LEN \
+	(sizeof(struct iphdr) + sizeof(struct udphdr) + sizeof(MESSAGE))
+#define FRAME_LEN (sizeof(struct ether_header) + IP_PACKET_LEN)
+
+FN_TEST(getsockname)
+{
+	int sk;
+	struct sockaddr_ll addr;
+	socklen_t addrlen = sizeof(addr);
+
+	sk = TEST_SUCC(socket(AF_PACKET, SOCK_RAW, htons(ETH_P_IP)));
+	TEST_RES(getsockname(sk, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) && addr.sll_family == AF_PACKET &&
+			 addr.sll_protocol == htons(ETH_P_IP) &&
+			 addr.sll_ifindex == 0 && addr.sll_halen == 0);
+
+	// Binding with a zero protocol keeps the current protocol.
+	memset(&addr, 0, sizeof(addr));
+	addr.sll_family = AF_PACKET;
+	addr.sll_ifindex = lo_index;
+	TEST_SUCC(bind(sk, (struct sockaddr *)&addr, sizeof(addr)));
+
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(sk, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) &&
+			 addr.sll_protocol == htons(ETH_P_IP) &&
+			 addr.sll_ifindex == lo_index &&
+			 addr.sll_hatype == ARPHRD_LOOPBACK &&
+			 addr.sll_halen == ETH_ALEN);
+
+	addr.sll_ifindex = 12345;
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&addr, sizeof(addr)), ENODEV);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(capture_raw)
+{
+	int sk;
+	char buf[128];
+	struct ether_header *eth = (struct ether_header *)buf;
+	struct iphdr *iph = (struct iphdr *)(buf + sizeof(*eth));
+	struct sockaddr_ll addr;
+	socklen_t addrlen = sizeof(addr);
+
+	sk = TEST_SUCC(open_packet_socket(SOCK_RAW, ETH_P_ALL));
+	TEST_RES(send_udp(), _ret == sizeof(MESSAGE));
+
+	// On the loopback interface, a frame is seen as both outgoing and incoming.
+	TEST_RES(recvfrom(sk, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == FRAME_LEN && eth->ether_type == htons(ETH_P_IP) &&
+			 iph->version == 4 && iph->protocol == IPPROTO_UDP &&
+			 addrlen == sizeof(addr) &&
+			 addr.sll_family == AF_PACKET &&
+			 addr.sll_protocol == htons(ETH_P_IP) &&
+			 addr.sll_ifindex == lo_index &&
+			 addr.sll_pkttype == PACKET_OUTGOING);
+	TEST_RES(recvfrom(sk, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == FRAME_LEN && addr.sll_pkttype == PACKET_HOST);
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_RES(recv_udp(), _ret == sizeof(MESSAGE));
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(capture_cooked)
+{
+	int sk;
+	char buf[128];
+	struct iphdr *iph = (struct iphdr *)buf;
+
+	sk = TEST_SUCC(open_packet_socket(SOCK_DGRAM, ETH_P_IP));
+	TEST_RES(send_udp(), _ret == sizeof(MESSAGE));
+
+	// Sockets that do not capture all protocols do not receive outgoing frames.
+	TEST_RES(recv(sk, buf, sizeof(buf), 0),
+		 _ret == IP_PACKET_LEN && iph->version == 4 &&
+			 iph->protocol == IPPROTO_UDP);
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_RES(recv_udp(), _ret == sizeof(MESSAGE));
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(protocol_mismatch)
+{
+	int sk;
+	char buf[128];
+
+	sk = TEST_SUCC(open_packet_socket(SOCK_RAW, ETH_P_ARP));
+	TEST_RES(send_udp(), _ret == sizeof(MESSAGE));
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_RES(recv_udp(), _ret == sizeof(MESSAGE));
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(filter)
+{
+	int sk;
+	char buf[128];
+	struct sock_filter drop_all[] = {
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_filter udp_only[] = {
+		// The socket is cooked, so the IP header is at offset 0.
+		BPF_STMT(BPF_LD | BPF_B | BPF_ABS, 9),
+		BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, IPPROTO_UDP, 0, 1),
+		BPF_STMT(BPF_RET | BPF_K, 10),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_fprog prog;
+	int dummy = 0;
+
+	sk = TEST_SUCC(open_packet_socket(SOCK_DGRAM, ETH_P_IP));
+
+	prog.len = 0;
+	prog.filter = drop_all;
+	TEST_ERRNO(setsockopt(sk, SOL_SOCKET, SO_ATTACH_FILTER, &prog,
+			      sizeof(prog)),
+		   EINVAL);
+
+	prog.len = sizeof(drop_all) / sizeof(drop_all[0]);
+	prog.filter = drop_all;
+	TEST_SUCC(setsockopt(sk, SOL_SOCKET, SO_ATTACH_FILTER, &prog,
+			     sizeof(prog)));
+	TEST_RES(send_udp(), _ret == sizeof(MESSAGE));
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), EAGAIN);
+	TEST_RES(recv_udp(), _ret == sizeof(MESSAGE));
+
+	// The filter accepts only the first 10 bytes of UDP packets.
+	prog.len = sizeof(udp_only) / sizeof(udp_only[0]);
+	prog.filter = udp_only;
+	TEST_SUCC(setsockopt(sk, SOL_SOCKET, SO_ATTACH_FILTER, &prog,
+			     sizeof(prog)));
+	TEST_RES(send_udp(), _ret == sizeof(MESSAGE));
+	TEST_RES(recv(sk, buf, sizeof(buf), 0), _ret == 10);
+	TEST_RES(recv_udp(), _ret == sizeof(MESSAGE));
+
+	TEST_SUCC(setsockopt(sk, SOL_SOCKET, SO_DETACH_FILTER, &dummy,
+			     sizeof(dummy)));
+	TEST_ERRNO(setsockopt(sk, SOL_SOCKET, SO_DETACH_FILTER, &dummy,
+			      sizeof(dummy)),
+		   ENOENT);
+	TEST_RES(send_udp(), _ret == sizeof(MESSAGE));
+	TEST_RES(recv(sk, buf, sizeof(buf), 0), _ret == IP_PACKET_LEN);
+	TEST_RES(recv_udp(), _ret == sizeof(MESSAGE));
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(membership)
+{
+	int sk;
+	struct packet_mreq mreq;
+
+	sk = TEST_SUCC(socket(AF_PACKET, SOCK_RAW, htons(ETH_P_ALL)));
+
+	memset(&mreq, 0, sizeof(mreq));
+	mreq.mr_ifindex = lo_index;
+	mreq.mr_type = PACKET_MR_PROMISC;
+
+	TEST_ERRNO(setsockopt(sk, SOL_PACKET, PACKET_DROP_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRNOTAVAIL);
+	TEST_SUCC(setsockopt(sk, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_SUCC(setsockopt(sk, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_SUCC(setsockopt(sk, SOL_PACKET, PACKET_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_SUCC(setsockopt(sk, SOL_PACKET, PACKET_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_ERRNO(setsockopt(sk, SOL_PACKET, PACKET_DROP_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRNOTAVAIL);
+
+	// The membership is released when the socket is closed.
+	TEST_SUCC(setsockopt(sk, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+
+	mreq.mr_ifindex = 12345;
+	TEST_ERRNO(setsockopt(sk, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   ENODEV);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+static unsigned short ip_checksum(const void *data, int len)
+{
+	const unsigned short *words = data;
+	unsigned int sum = 0;
+
+	for (; len > 1; len -= 2)
+		sum += *words++;
+	while (sum >> 16)
+		sum = (sum & 0xffff) + (sum >> 16);
+
+	return ~sum;
+}
+
+static void init_ip_packet(char *buf)
+{
+	struct iphdr *iph = (struct iphdr *)buf;
+	struct udphdr *udph = (struct udphdr *)(buf + sizeof(*iph));
+
+	memset(buf, 0, IP_PACKET_LEN);
+
+	iph->version = 4;
+	iph->ihl = sizeof(*iph) / 4;
+	iph->tot_len = htons(IP_PACKET_LEN);
+	iph->ttl = 64;
+	iph->protocol = IPPROTO_UDP;
+	iph->saddr = htonl(INADDR_LOOPBACK);
+	iph->daddr = htonl(INADDR_LOOPBACK);
+	iph->check = ip_checksum(iph, sizeof(*iph));
+
+	// A zero UDP checksum means that the checksum is not computed.
+	udph->source = htons(UDP_PORT + 1);
+	udph->dest = htons(UDP_PORT);
+	udph->len = htons(sizeof(*udph) + sizeof(MESSAGE));
+	memcpy(buf + sizeof(*iph) + sizeof(*udph), MESSAGE, sizeof(MESSAGE));
+}
+
+FN_TEST(send_raw)
+{
+	int sk;
+	char buf[FRAME_LEN];
+	struct ether_header *eth = (struct ether_header *)buf;
+
+	sk = TEST_SUCC(open_packet_socket(SOCK_RAW, 0));
+
+	memset(eth, 0, sizeof(*eth));
+	eth->ether_type = htons(ETH_P_IP);
+	init_ip_packet(buf + sizeof(*eth));
+
+	TEST_RES(send(sk, buf, sizeof(buf), 0), _ret == sizeof(buf));
+	TEST_RES(recv_udp(), _ret == sizeof(MESSAGE));
+
+	// The frame is too short to contain the link-layer header.
+	TEST_ERRNO(send(sk, buf, sizeof(*eth) - 1, 0), EINVAL);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(send_cooked)
+{
+	int sk;
+	char buf[IP_PACKET_LEN];
+	struct sockaddr_ll addr;
+
+	sk = TEST_SUCC(socket(AF_PACKET, SOCK_DGRAM, 0));
+	init_ip_packet(buf);
+
+	// The socket is not bound to an interface.
+	TEST_ERRNO(send(sk, buf, sizeof(buf), 0), ENXIO);
+
+	memset(&addr, 0, sizeof(addr));
+	addr.sll_family = AF_PACKET;
+	addr.sll_protocol = htons(ETH_P_IP);
+	addr.sll_ifindex = lo_index;
+	addr.sll_halen = ETH_ALEN;
+	TEST_RES(sendto(sk, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == sizeof(buf));
+	TEST_RES(recv_udp(), _ret == sizeof(MESSAGE));
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_udp));
+}
+END_SETUP()
@@ -0,0 +1,400 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <string.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <sys/socket.h>
+#include <linux/netlink.h>
+#include <linux/genetlink.h>
+
+#include "../test.h"
+
+#define BUF_SIZE 8192
+
+// The packet filter family is specific to Asterinas, so its definitions are
+// not available in the Linux headers.
+#define PKTFILTER_NAME "pktfilter"
+
+enum {
+	PKTFILTER_CMD_NEWRULE = 1,
+	PKTFILTER_CMD_DELRULE = 2,
+	PKTFILTER_CMD_GETRULE = 3,
+	PKTFILTER_CMD_FLUSH = 4,
+	PKTFILTER_CMD_ZERO = 5,
+};
+
+enum {
+	PKTFILTER_ATTR_CHAIN = 1,
+	PKTFILTER_ATTR_INDEX = 2,
+	PKTFILTER_ATTR_PROTO = 3,
+	PKTFILTER_ATTR_SRC_ADDR = 4,
+	PKTFILTER_ATTR_SRC_PREFIX_LEN = 5,
+	PKTFILTER_ATTR_DST_ADDR = 6,
+	PKTFILTER_ATTR_DST_PREFIX_LEN = 7,
+	PKTFILTER_ATTR_SRC_PORT_MIN = 8,
+	PKTFILTER_ATTR_SRC_PORT_MAX = 9,
+	PKTFILTER_ATTR_DST_PORT_MIN = 10,
+	PKTFILTER_ATTR_DST_PORT_MAX = 11,
+	PKTFILTER_ATTR_IFNAME = 12,
+	PKTFILTER_ATTR_ACTION = 13,
+	PKTFILTER_ATTR_PACKETS = 14,
+	PKTFILTER_ATTR_BYTES = 15,
+};
+
+enum {
+	PKTFILTER_CHAIN_INPUT = 0,
+	PKTFILTER_CHAIN_FORWARD = 1,
+	PKTFILTER_CHAIN_OUTPUT = 2,
+};
+
+enum {
+	PKTFILTER_ACTION_ACCEPT = 0,
+	PKTFILTER_ACTION_DROP = 1,
+	PKTFILTER_ACTION_REJECT = 2,
+};
+
+#define NO_CHAIN ((__u32)-1)
+
+// The gateway of `eth0` (10.0.2.15/24).
+#define BLOCKED_ADDR "10.0.2.2"
+#define BLOCKED_PORT 5555
+
+static int sk_genl;
+static int sk_udp;
+static __u16 family_id;
+
+static char send_buf[BUF_SIZE];
+static char recv_buf[BUF_SIZE];
+
+#define GENL_DATA(nlh) ((struct genlmsghdr *)NLMSG_DATA(nlh))
+#define GENL_ATTRS(nlh) \
+	((struct nlattr *)((char *)GENL_DATA(nlh) + GENL_HDRLEN))
+#define GENL_ATTRS_LEN(nlh) \
+	((int)(nlh)->nlmsg_len - NLMSG_HDRLEN - GENL_HDRLEN)
+#define NLMSG_TAIL(nlh) \
+	((struct nlattr *)((char *)(nlh) + NLMSG_ALIGN((nlh)->nlmsg_len)))
+
+#define NLA_OK(nla, len)                                       \
+	((len) >= (int)sizeof(struct nlattr) &&                \
+	 (nla)->nla_len >= sizeof(struct nlattr) &&            \
+	 (nla)->nla_len <= (len))
+#define NLA_NEXT(nla, len)                        \
+	((len) -= NLA_ALIGN((nla)->nla_len),      \
+	 (struct nlattr *)((char *)(nla) + NLA_ALIGN((nla)->nla_len)))
+#define NLA_DATA(nla) ((void *)((char *)(nla) + NLA_HDRLEN))
+
+static struct nlmsghdr *init_request(__u16 type, __u16 flags, __u8 cmd)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)send_buf;
+
+	memset(send_buf, 0, sizeof(send_buf));
+
+	nlh->nlmsg_len = NLMSG_HDRLEN + GENL_HDRLEN;
+	nlh->nlmsg_type = type;
+	nlh->nlmsg_flags = NLM_F_REQUEST | flags;
+	nlh->nlmsg_seq = 1;
+
+	GENL_DATA(nlh)->cmd = cmd;
+	GENL_DATA(nlh)->version = 1;
+
+	return nlh;
+}
+
+static void add_attr(struct nlmsghdr *nlh, __u16 type, const void *data,
+		     int len)
+{
+	struct nlattr *nla = NLMSG_TAIL(nlh);
+
+	nla->nla_type = type;
+	nla->nla_len = NLA_HDRLEN + len;
+	memcpy(NLA_DATA(nla), data, len);
+	nlh->nlmsg_len = NLMSG_ALIGN(nlh->nlmsg_len) + NLA_ALIGN(nla->nla_len);
+}
+
+static void add_u8_attr(struct nlmsghdr *nlh, __u16 type, __u8 value)
+{
+	add_attr(nlh, type, &value, sizeof(value));
+}
+
+static void add_u16_attr(struct nlmsghdr *nlh, __u16 type, __u16 value)
+{
+	add_attr(nlh, type, &value, sizeof(value));
+}
+
+static void add_u32_attr(struct nlmsghdr *nlh, __u16 type, __u32 value)
+{
+	add_attr(nlh, type, &value, sizeof(value));
+}
+
+static void add_addr_attr(struct nlmsghdr *nlh, __u16 type, const char *addr)
+{
+	struct in_addr in_addr;
+
+	inet_pton(AF_INET, addr, &in_addr);
+	add_attr(nlh, type, &in_addr, sizeof(in_addr));
+}
+
+static struct nlattr *find_attr(struct nlmsghdr *nlh, __u16 type)
+{
+	struct nlattr *nla = GENL_ATTRS(nlh);
+	int len = GENL_ATTRS_LEN(nlh);
+
+	for (; NLA_OK(nla, len); nla = NLA_NEXT(nla, len))
+		if ((nla->nla_type & NLA_TYPE_MASK) == type)
+			return nla;
+
+	return NULL;
+}
+
+// Sends the request and returns the error code in the acknowledgment.
+static int transact(void)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)send_buf;
+	int len;
+
+	nlh->nlmsg_flags |= NLM_F_ACK;
+	if (send(sk_genl, send_buf, nlh->nlmsg_len, 0) < 0)
+		return 1;
+
+	len = recv(sk_genl, recv_buf, sizeof(recv_buf), 0);
+	nlh = (struct nlmsghdr *)recv_buf;
+	if (!NLMSG_OK(nlh, len) || nlh->nlmsg_type != NLMSG_ERROR)
+		return 1;
+
+	return ((struct nlmsgerr *)NLMSG_DATA(nlh))->error;
+}
+
+struct rule_info {
+	__u32 action;
+	__u64 packets;
+	__u64 bytes;
+};
+
+// Dumps the rules in the chain (or in all chains if `chain` is `NO_CHAIN`),
+// and returns the number of rules. The first rule is stored in `first`.
+static int dump_rules(__u32 chain, struct rule_info *first)
+{
+	struct nlmsghdr *nlh;
+	struct nlattr *nla;
+	int len, count = 0;
+
+	nlh = init_request(family_id, NLM_F_DUMP, PKTFILTER_CMD_GETRULE);
+	if (chain != NO_CHAIN)
+		add_u32_attr(nlh, PKTFILTER_ATTR_CHAIN, chain);
+	if (send(sk_genl, send_buf, nlh->nlmsg_len, 0) < 0)
+		return -1;
+
+	for (;;) {
+		len = recv(sk_genl, recv_buf, sizeof(recv_buf), 0);
+		if (len < 0)
+			return -1;
+
+		nlh = (struct nlmsghdr *)recv_buf;
+		for (; NLMSG_OK(nlh, len); nlh = NLMSG_NEXT(nlh, len)) {
+			if (nlh->nlmsg_type == NLMSG_DONE)
+				return count;
+			if (nlh->nlmsg_type != family_id ||
+			    GENL_DATA(nlh)->cmd != PKTFILTER_CMD_NEWRULE)
+				return -1;
+
+			if (count++ != 0 || first == NULL)
+				continue;
+
+			nla = find_attr(nlh, PKTFILTER_ATTR_ACTION);
+			if (nla == NULL)
+				return -1;
+			first->action = *(__u32 *)NLA_DATA(nla);
+
+			nla = find_attr(nlh, PKTFILTER_ATTR_PACKETS);
+			if (nla == NULL)
+				return -1;
+			memcpy(&first->packets, NLA_DATA(nla), sizeof(__u64));
+
+			nla = find_attr(nlh, PKTFILTER_ATTR_BYTES);
+			if (nla == NULL)
+				return -1;
+			memcpy(&first->bytes, NLA_DATA(nla), sizeof(__u64));
+		}
+	}
+}
+
+FN_SETUP(socket)
+{
+	struct sockaddr_nl addr = { .nl_family = AF_NETLINK };
+
+	sk_genl = CHECK(socket(PF_NETLINK, SOCK_RAW, NETLINK_GENERIC));
+	CHECK(bind(sk_genl, (struct sockaddr *)&addr, sizeof(addr)));
+
+	sk_udp = CHECK(socket(PF_INET, SOCK_DGRAM, 0));
+}
+END_SETUP()
+
+FN_SETUP(family)
+{
+	struct nlmsghdr *nlh;
+	struct nlattr *nla;
+	int len;
+
+	nlh = init_request(GENL_ID_CTRL, 0, CTRL_CMD_GETFAMILY);
+	add_attr(nlh, CTRL_ATTR_FAMILY_NAME, PKTFILTER_NAME,
+		 sizeof(PKTFILTER_NAME));
+	CHECK(send(sk_genl, send_buf, nlh->nlmsg_len, 0));
+
+	len = CHECK(recv(sk_genl, recv_buf, sizeof(recv_buf), 0));
+	nlh = (struct nlmsghdr *)recv_buf;
+	CHECK_WITH(NLMSG_OK(nlh, len) && nlh->nlmsg_type == GENL_ID_CTRL,
+		   _ret);
+
+	nla = CHECK_WITH(find_attr(nlh, CTRL_ATTR_FAMILY_ID), _ret != NULL);
+	family_id = *(__u16 *)NLA_DATA(nla);
+}
+END_SETUP()
+
+FN_TEST(new_rule_invalid)
+{
+	struct nlmsghdr *nlh;
+
+	// The chain is missing.
+	nlh = init_request(family_id, 0, PKTFILTER_CMD_NEWRULE);
+	add_u32_attr(nlh, PKTFILTER_ATTR_ACTION, PKTFILTER_ACTION_DROP);
+	TEST_RES(transact(), _ret == -EINVAL);
+
+	// The action is missing.
+	nlh = init_request(family_id, 0, PKTFILTER_CMD_NEWRULE);
+	add_u32_attr(nlh, PKTFILTER_ATTR_CHAIN, PKTFILTER_CHAIN_INPUT);
+	TEST_RES(transact(), _ret == -EINVAL);
+
+	// The chain is invalid.
+	nlh = init_request(family_id, 0, PKTFILTER_CMD_NEWRULE);
+	add_u32_attr(nlh, PKTFILTER_ATTR_CHAIN, 3);
+	add_u32_attr(nlh, PKTFILTER_ATTR_ACTION, PKTFILTER_ACTION_DROP);
+	TEST_RES(transact(), _ret == -EINVAL);
+
+	// The port range is empty.
+	nlh = init_request(family_id, 0, PKTFILTER_CMD_NEWRULE);
+	add_u32_attr(nlh, PKTFILTER_ATTR_CHAIN, PKTFILTER_CHAIN_INPUT);
+	add_u16_attr(nlh, PKTFILTER_ATTR_DST_PORT_MIN, 81);
+	add_u16_attr(nlh, PKTFILTER_ATTR_DST_PORT_MAX, 80);
+	add_u32_attr(nlh, PKTFILTER_ATTR_ACTION, PKTFILTER_ACTION_DROP);
+	TEST_RES(transact(), _ret == -EINVAL);
+
+	// The index is out of range.
+	nlh = init_request(family_id, 0, PKTFILTER_CMD_NEWRULE);
+	add_u32_attr(nlh, PKTFILTER_ATTR_CHAIN, PKTFILTER_CHAIN_INPUT);
+	add_u32_attr(nlh, PKTFILTER_ATTR_INDEX, 1);
+	add_u32_attr(nlh, PKTFILTER_ATTR_ACTION, PKTFILTER_ACTION_DROP);
+	TEST_RES(transact(), _ret == -ERANGE);
+
+	TEST_RES(dump_rules(NO_CHAIN, NULL), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(new_rule)
+{
+	struct nlmsghdr *nlh;
+	struct rule_info info;
+
+	nlh = init_request(family_id, 0, PKTFILTER_CMD_NEWRULE);
+	add_u32_attr(nlh, PKTFILTER_ATTR_CHAIN, PKTFILTER_CHAIN_OUTPUT);
+	add_u8_attr(nlh, PKTFILTER_ATTR_PROTO, IPPROTO_UDP);
+	add_addr_attr(nlh, PKTFILTER_ATTR_DST_ADDR, BLOCKED_ADDR);
+	add_u16_attr(nlh, PKTFILTER_ATTR_DST_PORT_MIN, BLOCKED_PORT);
+	add_attr(nlh, PKTFILTER_ATTR_IFNAME, "eth0", 5);
+	add_u32_attr(nlh, PKTFILTER_ATTR_ACTION, PKTFILTER_ACTION_DROP);
+	TEST_RES(transact(), _ret == 0);
+
+	// Insert a rule before the first rule in the input chain.
+	nlh = init_request(family_id, 0, PKTFILTER_CMD_NEWRULE);
+	add_u32_attr(nlh, PKTFILTER_ATTR_CHAIN, PKTFILTER_CHAIN_INPUT);
+	add_u32_attr(nlh, PKTFILTER_ATTR_INDEX, 0);
+	add_u8_attr(nlh, PKTFILTER_ATTR_PROTO, IPPROTO_TCP);
+	add_addr_attr(nlh, PKTFILTER_ATTR_SRC_ADDR, "192.168.0.0");
+	add_u8_attr(nlh, PKTFILTER_ATTR_SRC_PREFIX_LEN, 16);
+	add_u16_attr(nlh, PKTFILTER_ATTR_DST_PORT_MIN, 1);
+	add_u16_attr(nlh, PKTFILTER_ATTR_DST_PORT_MAX, 1023);
+	add_u32_attr(nlh, PKTFILTER_ATTR_ACTION, PKTFILTER_ACTION_REJECT);
+	TEST_RES(transact(), _ret == 0);
+
+	TEST_RES(dump_rules(NO_CHAIN, NULL), _ret == 2);
+	TEST_RES(dump_rules(PKTFILTER_CHAIN_INPUT, &info),
+		 _ret == 1 && info.action == PKTFILTER_ACTION_REJECT);
+	TEST_RES(dump_rules(PKTFILTER_CHAIN_OUTPUT, &info),
+		 _ret == 1 && info.action == PKTFILTER_ACTION_DROP &&
+			 info.packets == 0 && info.bytes == 0);
+	TEST_RES(dump_rules(PKTFILTER_CHAIN_FORWARD, NULL), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(counters)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = htons(BLOCKED_PORT),
+	};
+	struct nlmsghdr *nlh;
+	struct rule_info info;
+
+	inet_pton(AF_INET, BLOCKED_ADDR, &addr.sin_addr);
+
+	// The packet is dropped silently, so sending it still succeeds.
+	TEST_RES(sendto(sk_udp, "hello", 5, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 5);
+
+	// The IP header (20 bytes) and the UDP header (8 bytes) are counted.
+	TEST_RES(dump_rules(PKTFILTER_CHAIN_OUTPUT, &info),
+		 _ret == 1 && info.packets == 1 && info.bytes == 20 + 8 + 5);
+
+	// Packets to other ports do not match the rule.
+	addr.sin_port = htons(BLOCKED_PORT + 1);
+	TEST_RES(sendto(sk_udp, "hello", 5, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 5);
+	TEST_RES(dump_rules(PKTFILTER_CHAIN_OUTPUT, &info),
+		 _ret == 1 && info.packets == 1);
+
+	nlh = init_request(family_id, 0, PKTFILTER_CMD_ZERO);
+	add_u32_attr(nlh, PKTFILTER_ATTR_CHAIN, PKTFILTER_CHAIN_OUTPUT);
+	TEST_RES(transact(), _ret == 0);
+	TEST_RES(dump_rules(PKTFILTER_CHAIN_OUTPUT, &info),
+		 _ret == 1 && info.packets == 0 && info.bytes == 0);
+}
+END_TEST()
+
+FN_TEST(del_rule)
+{
+	struct nlmsghdr *nlh;
+
+	nlh = init_request(family_id, 0, PKTFILTER_CMD_DELRULE);
+	add_u32_attr(nlh, PKTFILTER_ATTR_CHAIN, PKTFILTER_CHAIN_OUTPUT);
+	TEST_RES(transact(), _ret == -EINVAL);
+
+	nlh = init_request(family_id, 0, PKTFILTER_CMD_DELRULE);
+	add_u32_attr(nlh, PKTFILTER_ATTR_CHAIN, PKTFILTER_CHAIN_OUTPUT);
+	add_u32_attr(nlh, PKTFILTER_ATTR_INDEX, 0);
+	TEST_RES(transact(), _ret == 0);
+	TEST_RES(transact(), _ret == -ENOENT);
+
+	TEST_RES(dump_rules(PKTFILTER_CHAIN_OUTPUT, NULL), _ret == 0);
+	TEST_RES(dump_rules(NO_CHAIN, NULL), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(flush)
+{
+	init_request(family_id, 0, PKTFILTER_CMD_FLUSH);
+	TEST_RES(transact(), _ret == 0);
+
+	TEST_RES(dump_rules(NO_CHAIN, NULL), _ret == 0);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_udp));
+	CHECK(close(sk_genl));
+}
+END_SETUP()
@@ -0,0 +1,455 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <string.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <net/if.h>
+#include <sys/socket.h>
+#include <linux/netlink.h>
+#include <linux/rtnetlink.h>
+
+#include "../test.h"
+
+#define BUF_SIZE 8192
+
+#define ETHER_NAME "eth0"
+#define LOOPBACK_NAME "lo"
+
+// The route goes through the gateway of `eth0` (10.0.2.15/24).
+#define ROUTE_DST "192.168.100.0"
+#define ROUTE_GATEWAY "10.0.2.2"
+
+static int sk_route;
+static int sk_monitor;
+static int lo_index;
+static int eth0_index;
+
+static char send_buf[BUF_SIZE];
+static char recv_buf[BUF_SIZE];
+
+#define NLMSG_TAIL(nlh) \
+	((struct rtattr *)((char *)(nlh) + NLMSG_ALIGN((nlh)->nlmsg_len)))
+#define NDA_RTA(ndm) \
+	((struct rtattr *)((char *)(ndm) + NLMSG_ALIGN(sizeof(struct ndmsg))))
+#define NDA_PAYLOAD(nlh) NLMSG_PAYLOAD(nlh, sizeof(struct ndmsg))
+
+static struct nlmsghdr *init_request(__u16 type, __u16 flags, const void *body,
+				     int body_len)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)send_buf;
+
+	memset(send_buf, 0, sizeof(send_buf));
+
+	nlh->nlmsg_len = NLMSG_LENGTH(body_len);
+	nlh->nlmsg_type = type;
+	nlh->nlmsg_flags = NLM_F_REQUEST | flags;
+	nlh->nlmsg_seq = 1;
+	memcpy(NLMSG_DATA(nlh), body, body_len);
+
+	return nlh;
+}
+
+static void add_attr(struct nlmsghdr *nlh, __u16 type, const void *data,
+		     int len)
+{
+	struct rtattr *rta = NLMSG_TAIL(nlh);
+
+	rta->rta_type = type;
+	rta->rta_len = RTA_LENGTH(len);
+	memcpy(RTA_DATA(rta), data, len);
+	nlh->nlmsg_len = NLMSG_ALIGN(nlh->nlmsg_len) + RTA_ALIGN(rta->rta_len);
+}
+
+static void add_addr_attr(struct nlmsghdr *nlh, __u16 type, const char *addr)
+{
+	struct in_addr in_addr;
+
+	inet_pton(AF_INET, addr, &in_addr);
+	add_attr(nlh, type, &in_addr, sizeof(in_addr));
+}
+
+// Sends the request and returns the error code in the acknowledgment.
+static int transact(void)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)send_buf;
+	int len;
+
+	if (send(sk_route, send_buf, nlh->nlmsg_len, 0) < 0)
+		return 1;
+
+	len = recv(sk_route, recv_buf, sizeof(recv_buf), 0);
+	nlh = (struct nlmsghdr *)recv_buf;
+	if (!NLMSG_OK(nlh, len) || nlh->nlmsg_type != NLMSG_ERROR)
+		return 1;
+
+	return ((struct nlmsgerr *)NLMSG_DATA(nlh))->error;
+}
+
+// Receives the next notification and returns its type.
+static int recv_notification(void)
+{
+	struct nlmsghdr *nlh = (struct nlmsghdr *)recv_buf;
+	int len;
+
+	len = recv(sk_monitor, recv_buf, sizeof(recv_buf), 0);
+	if (!NLMSG_OK(nlh, len))
+		return -1;
+
+	return nlh->nlmsg_type;
+}
+
+static struct nlmsghdr *init_route_request(__u16 type, __u16 flags,
+					   const char *dst, int dst_len)
+{
+	struct rtmsg rtm = {
+		.rtm_family = AF_INET,
+		.rtm_dst_len = dst_len,
+		.rtm_table = RT_TABLE_MAIN,
+		.rtm_protocol = RTPROT_BOOT,
+		.rtm_scope = RT_SCOPE_UNIVERSE,
+		.rtm_type = RTN_UNICAST,
+	};
+	struct nlmsghdr *nlh;
+
+	nlh = init_request(type, NLM_F_ACK | flags, &rtm, sizeof(rtm));
+	add_addr_attr(nlh, RTA_DST, dst);
+
+	return nlh;
+}
+
+static struct rtattr *find_attr(struct rtattr *rta, int len, __u16 type)
+{
+	for (; RTA_OK(rta, len); rta = RTA_NEXT(rta, len))
+		if (rta->rta_type == type)
+			return rta;
+
+	return NULL;
+}
+
+static int is_route(struct nlmsghdr *nlh, const char *dst, int dst_len,
+		    const char *gateway, int oif)
+{
+	struct rtmsg *rtm = NLMSG_DATA(nlh);
+	int len = RTM_PAYLOAD(nlh);
+	struct in_addr in_addr;
+	struct rtattr *rta;
+
+	if (nlh->nlmsg_type != RTM_NEWROUTE || rtm->rtm_family != AF_INET ||
+	    rtm->rtm_dst_len != dst_len || rtm->rtm_table != RT_TABLE_MAIN)
+		return 0;
+
+	rta = find_attr(RTM_RTA(rtm), len, RTA_DST);
+	inet_pton(AF_INET, dst, &in_addr);
+	if (rta == NULL || memcmp(RTA_DATA(rta), &in_addr, 4) != 0)
+		return 0;
+
+	rta = find_attr(RTM_RTA(rtm), len, RTA_GATEWAY);
+	if (gateway == NULL) {
+		if (rta != NULL)
+			return 0;
+	} else {
+		inet_pton(AF_INET, gateway, &in_addr);
+		if (rta == NULL || memcmp(RTA_DATA(rta), &in_addr, 4) != 0)
+			return 0;
+	}
+
+	rta = find_attr(RTM_RTA(rtm), len, RTA_OIF);
+	if (rta == NULL || *(int *)RTA_DATA(rta) != oif)
+		return 0;
+
+	return 1;
+}
+
+// Dumps all routes and returns the number of routes that match the arguments.
+static int dump_routes(const char *dst, int dst_len, const char *gateway,
+		       int oif)
+{
+	struct rtmsg rtm = { .rtm_family = AF_INET };
+	struct nlmsghdr *nlh;
+	int len, found = 0;
+
+	nlh = init_request(RTM_GETROUTE, NLM_F_DUMP, &rtm, sizeof(rtm));
+	if (send(sk_route, send_buf, nlh->nlmsg_len, 0) < 0)
+		return -1;
+
+	for (;;) {
+		len = recv(sk_route, recv_buf, sizeof(recv_buf), 0);
+		if (len <= 0)
+			return -1;
+
+		nlh = (struct nlmsghdr *)recv_buf;
+		for (; NLMSG_OK(nlh, len); nlh = NLMSG_NEXT(nlh, len)) {
+			if (nlh->nlmsg_type == NLMSG_DONE)
+				return found;
+			if (!(nlh->nlmsg_flags & NLM_F_MULTI))
+				return -1;
+			found += is_route(nlh, dst, dst_len, gateway, oif);
+		}
+	}
+}
+
+FN_SETUP(socket)
+{
+	struct sockaddr_nl addr = { .nl_family = AF_NETLINK };
+
+	sk_route = CHECK(socket(PF_NETLINK, SOCK_RAW | SOCK_NONBLOCK,
+				NETLINK_ROUTE));
+	CHECK(bind(sk_route, (struct sockaddr *)&addr, sizeof(addr)));
+
+	addr.nl_groups = RTMGRP_LINK | RTMGRP_IPV4_IFADDR | RTMGRP_IPV4_ROUTE;
+	sk_monitor = CHECK(socket(PF_NETLINK, SOCK_RAW | SOCK_NONBLOCK,
+				  NETLINK_ROUTE));
+	CHECK(bind(sk_monitor, (struct sockaddr *)&addr, sizeof(addr)));
+
+	lo_index = CHECK(if_nametoindex(LOOPBACK_NAME));
+	eth0_index = CHECK(if_nametoindex(ETHER_NAME));
+}
+END_SETUP()
+
+FN_TEST(connected_route)
+{
+	TEST_RES(dump_routes("10.0.2.0", 24, NULL, eth0_index), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(new_route)
+{
+	struct nlmsghdr *nlh;
+	int metric;
+
+	// Add a route through the gateway
+	nlh = init_route_request(RTM_NEWROUTE, NLM_F_CREATE | NLM_F_EXCL,
+				 ROUTE_DST, 24);
+	add_addr_attr(nlh, RTA_GATEWAY, ROUTE_GATEWAY);
+	TEST_RES(transact(), _ret == 0);
+	TEST_RES(recv_notification(), _ret == RTM_NEWROUTE);
+	TEST_RES(dump_routes(ROUTE_DST, 24, ROUTE_GATEWAY, eth0_index),
+		 _ret == 1);
+
+	// The route already exists
+	TEST_RES(transact(), _ret == -EEXIST);
+	nlh->nlmsg_flags &= ~NLM_F_EXCL;
+	TEST_RES(transact(), _ret == -EEXIST);
+
+	// Replace the existing route
+	nlh->nlmsg_flags |= NLM_F_REPLACE;
+	TEST_RES(transact(), _ret == 0);
+	TEST_RES(recv_notification(), _ret == RTM_NEWROUTE);
+	TEST_RES(dump_routes(ROUTE_DST, 24, ROUTE_GATEWAY, eth0_index),
+		 _ret == 1);
+
+	// The route with another metric does not exist
+	metric = 100;
+	nlh = init_route_request(RTM_NEWROUTE, NLM_F_REPLACE, ROUTE_DST, 24);
+	add_addr_attr(nlh, RTA_GATEWAY, ROUTE_GATEWAY);
+	add_attr(nlh, RTA_PRIORITY, &metric, sizeof(metric));
+	TEST_RES(transact(), _ret == -ENOENT);
+}
+END_TEST()
+
+FN_TEST(new_route_error)
+{
+	struct nlmsghdr *nlh;
+	int oif;
+
+	// The gateway is not reachable
+	nlh = init_route_request(RTM_NEWROUTE, NLM_F_CREATE, "192.168.101.0",
+				 24);
+	add_addr_attr(nlh, RTA_GATEWAY, "192.168.1.1");
+	TEST_RES(transact(), _ret == -ENETUNREACH);
+
+	// The gateway is not reachable through the specified interface
+	nlh = init_route_request(RTM_NEWROUTE, NLM_F_CREATE, "192.168.101.0",
+				 24);
+	add_addr_attr(nlh, RTA_GATEWAY, ROUTE_GATEWAY);
+	add_attr(nlh, RTA_OIF, &lo_index, sizeof(lo_index));
+	TEST_RES(transact(), _ret == -ENETUNREACH);
+
+	// The interface does not exist
+	oif = 9999;
+	nlh = init_route_request(RTM_NEWROUTE, NLM_F_CREATE, "192.168.101.0",
+				 24);
+	add_attr(nlh, RTA_OIF, &oif, sizeof(oif));
+	TEST_RES(transact(), _ret == -ENODEV);
+
+	// The destination has bits set outside the prefix
+	nlh = init_route_request(RTM_NEWROUTE, NLM_F_CREATE, "192.168.101.1",
+				 24);
+	add_attr(nlh, RTA_OIF, &eth0_index, sizeof(eth0_index));
+	TEST_RES(transact(), _ret == -EINVAL);
+
+	// The prefix length is invalid
+	nlh = init_route_request(RTM_NEWROUTE, NLM_F_CREATE, "192.168.101.0",
+				 33);
+	add_attr(nlh, RTA_OIF, &eth0_index, sizeof(eth0_index));
+	TEST_RES(transact(), _ret == -EINVAL);
+}
+END_TEST()
+
+FN_TEST(del_route)
+{
+	struct nlmsghdr *nlh;
+
+	// The gateway does not match
+	nlh = init_route_request(RTM_DELROUTE, 0, ROUTE_DST, 24);
+	add_addr_attr(nlh, RTA_GATEWAY, "10.0.2.3");
+	TEST_RES(transact(), _ret == -ESRCH);
+
+	// Delete the route
+	nlh = init_route_request(RTM_DELROUTE, 0, ROUTE_DST, 24);
+	add_addr_attr(nlh, RTA_GATEWAY, ROUTE_GATEWAY);
+	TEST_RES(transact(), _ret == 0);
+	TEST_RES(recv_notification(), _ret == RTM_DELROUTE);
+	TEST_RES(dump_routes(ROUTE_DST, 24, ROUTE_GATEWAY, eth0_index),
+		 _ret == 0);
+
+	// The route no longer exists
+	TEST_RES(transact(), _ret == -ESRCH);
+}
+END_TEST()
+
+static int get_mtu(int index)
+{
+	struct ifinfomsg ifi = { .ifi_family = AF_UNSPEC, .ifi_index = index };
+	struct nlmsghdr *nlh;
+	struct rtattr *rta;
+	int len;
+
+	nlh = init_request(RTM_GETLINK, 0, &ifi, sizeof(ifi));
+	if (send(sk_route, send_buf, nlh->nlmsg_len, 0) < 0)
+		return -1;
+
+	len = recv(sk_route, recv_buf, sizeof(recv_buf), 0);
+	nlh = (struct nlmsghdr *)recv_buf;
+	if (!NLMSG_OK(nlh, len) || nlh->nlmsg_type != RTM_NEWLINK)
+		return -1;
+
+	rta = find_attr(IFLA_RTA(NLMSG_DATA(nlh)), IFLA_PAYLOAD(nlh),
+			IFLA_MTU);
+	if (rta == NULL)
+		return -1;
+
+	return *(int *)RTA_DATA(rta);
+}
+
+static struct nlmsghdr *init_set_mtu_request(int index, int mtu)
+{
+	struct ifinfomsg ifi = { .ifi_family = AF_UNSPEC, .ifi_index = index };
+	struct nlmsghdr *nlh;
+
+	nlh = init_request(RTM_SETLINK, NLM_F_ACK, &ifi, sizeof(ifi));
+	add_attr(nlh, IFLA_MTU, &mtu, sizeof(mtu));
+
+	return nlh;
+}
+
+FN_TEST(set_link_mtu)
+{
+	int old_mtu;
+
+	old_mtu = TEST_RES(get_mtu(lo_index), _ret > 0);
+
+	init_set_mtu_request(lo_index, 1500);
+	TEST_RES(transact(), _ret == 0);
+	TEST_RES(recv_notification(), _ret == RTM_NEWLINK);
+	TEST_RES(get_mtu(lo_index), _ret == 1500);
+
+	// The MTU is too small
+	init_set_mtu_request(lo_index, 10);
+	TEST_RES(transact(), _ret == -EINVAL);
+	TEST_RES(get_mtu(lo_index), _ret == 1500);
+
+	// The link does not exist
+	init_set_mtu_request(9999, 1500);
+	TEST_RES(transact(), _ret == -ENODEV);
+
+	init_set_mtu_request(lo_index, old_mtu);
+	TEST_RES(transact(), _ret == 0);
+	TEST_RES(recv_notification(), _ret == RTM_NEWLINK);
+	TEST_RES(get_mtu(lo_index), _ret == old_mtu);
+}
+END_TEST()
+
+static struct nlmsghdr *init_addr_request(__u16 type, __u16 flags,
+					  const char *addr)
+{
+	struct ifaddrmsg ifa = {
+		.ifa_family = AF_INET,
+		.ifa_prefixlen = 8,
+		.ifa_index = lo_index,
+	};
+	struct nlmsghdr *nlh;
+
+	nlh = init_request(type, NLM_F_ACK | flags, &ifa, sizeof(ifa));
+	add_addr_attr(nlh, IFA_LOCAL, addr);
+
+	return nlh;
+}
+
+FN_TEST(del_addr)
+{
+	// The address does not match
+	init_addr_request(RTM_DELADDR, 0, "127.0.0.2");
+	TEST_RES(transact(), _ret == -EADDRNOTAVAIL);
+
+	// Delete the address
+	init_addr_request(RTM_DELADDR, 0, "127.0.0.1");
+	TEST_RES(transact(), _ret == 0);
+	TEST_RES(recv_notification(), _ret == RTM_DELADDR);
+
+	// The address no longer exists
+	TEST_RES(transact(), _ret == -EADDRNOTAVAIL);
+
+	// Add the address back
+	init_addr_request(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL, "127.0.0.1");
+	TEST_RES(transact(), _ret == 0);
+	TEST_RES(recv_notification(), _ret == RTM_NEWADDR);
+}
+END_TEST()
+
+static int check_neigh_dump(void)
+{
+	struct ndmsg ndm = { .ndm_family = AF_INET };
+	struct nlmsghdr *nlh;
+	struct ndmsg *entry;
+	int len;
+
+	nlh = init_request(RTM_GETNEIGH, NLM_F_DUMP, &ndm, sizeof(ndm));
+	if (send(sk_route, send_buf, nlh->nlmsg_len, 0) < 0)
+		return -1;
+
+	for (;;) {
+		len = recv(sk_route, recv_buf, sizeof(recv_buf), 0);
+		if (len <= 0)
+			return -1;
+
+		nlh = (struct nlmsghdr *)recv_buf;
+		for (; NLMSG_OK(nlh, len); nlh = NLMSG_NEXT(nlh, len)) {
+			if (nlh->nlmsg_type == NLMSG_DONE)
+				return 0;
+			if (nlh->nlmsg_type != RTM_NEWNEIGH)
+				return -1;
+
+			entry = NLMSG_DATA(nlh);
+			if (entry->ndm_family != AF_INET ||
+			    find_attr(NDA_RTA(entry), NDA_PAYLOAD(nlh),
+				      NDA_DST) == NULL)
+				return -1;
+		}
+	}
+}
+
+FN_TEST(get_neigh)
+{
+	TEST_SUCC(check_neigh_dump());
+}
+END_TEST()
+
+FN_TEST(no_more_messages)
+{
+	TEST_ERRNO(recv(sk_route, recv_buf, sizeof(recv_buf), 0), EAGAIN);
+	TEST_ERRNO(recv(sk_monitor, recv_buf, sizeof(recv_buf), 0), EAGAIN);
+}
+END_TEST()
@@ -0,0 +1,310 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <string.h>
+#include <unistd.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <arpa/inet.h>
+#include <linux/filter.h>
+#include <linux/netlink.h>
+#include <linux/rtnetlink.h>
+
+#include "../test.h"
+
+#define UDP_PORT htons(0x1237)
+#define MESSAGE "hello"
+
+#define UDP_HEADER_LEN 8
+#define IP_HEADER_LEN 20
+
+#define ARRAY_LEN(a) (sizeof(a) / sizeof((a)[0]))
+
+static struct sockaddr_in udp_addr;
+
+static int sk_udp_recv;
+static int sk_udp_send;
+
+static struct sock_filter drop_all[] = {
+	BPF_STMT(BPF_RET | BPF_K, 0),
+};
+
+static struct sock_filter accept_all[] = {
+	BPF_STMT(BPF_RET | BPF_K, 0xffffffff),
+};
+
+static int attach_filter(int sk, struct sock_filter *insns, size_t len)
+{
+	struct sock_fprog prog = {
+		.len = len,
+		.filter = insns,
+	};
+
+	return setsockopt(sk, SOL_SOCKET, SO_ATTACH_FILTER, &prog,
+			  sizeof(prog));
+}
+
+static int detach_filter(int sk)
+{
+	int dummy = 0;
+
+	return setsockopt(sk, SOL_SOCKET, SO_DETACH_FILTER, &dummy,
+			  sizeof(dummy));
+}
+
+static int send_udp(const char *msg)
+{
+	return sendto(sk_udp_send, msg, strlen(msg), 0,
+		      (struct sockaddr *)&udp_addr, sizeof(udp_addr));
+}
+
+FN_SETUP(general)
+{
+	udp_addr.sin_family = AF_INET;
+	udp_addr.sin_port = UDP_PORT;
+	CHECK(inet_aton("127.0.0.1", &udp_addr.sin_addr));
+
+	sk_udp_recv = CHECK(socket(PF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	CHECK(bind(sk_udp_recv, (struct sockaddr *)&udp_addr,
+		   sizeof(udp_addr)));
+
+	sk_udp_send = CHECK(socket(PF_INET, SOCK_DGRAM, 0));
+}
+END_SETUP()
+
+FN_TEST(invalid_programs)
+{
+	struct sock_filter bad_jump[] = {
+		BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, 0, 1, 0),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_filter bad_ja[] = {
+		BPF_STMT(BPF_JMP | BPF_JA, 1),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_filter bad_mem[] = {
+		BPF_STMT(BPF_ST, BPF_MEMWORDS),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_filter bad_div[] = {
+		BPF_STMT(BPF_ALU | BPF_DIV | BPF_K, 0),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_filter bad_code[] = {
+		BPF_STMT(0xff, 0),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_filter no_ret[] = {
+		BPF_STMT(BPF_LD | BPF_W | BPF_LEN, 0),
+	};
+
+	TEST_ERRNO(attach_filter(sk_udp_recv, drop_all, 0), EINVAL);
+	TEST_ERRNO(attach_filter(sk_udp_recv, bad_jump, ARRAY_LEN(bad_jump)),
+		   EINVAL);
+	TEST_ERRNO(attach_filter(sk_udp_recv, bad_ja, ARRAY_LEN(bad_ja)),
+		   EINVAL);
+	TEST_ERRNO(attach_filter(sk_udp_recv, bad_mem, ARRAY_LEN(bad_mem)),
+		   EINVAL);
+	TEST_ERRNO(attach_filter(sk_udp_recv, bad_div, ARRAY_LEN(bad_div)),
+		   EINVAL);
+	TEST_ERRNO(attach_filter(sk_udp_recv, bad_code, ARRAY_LEN(bad_code)),
+		   EINVAL);
+	TEST_ERRNO(attach_filter(sk_udp_recv, no_ret, ARRAY_LEN(no_ret)),
+		   EINVAL);
+
+	TEST_ERRNO(detach_filter(sk_udp_recv), ENOENT);
+}
+END_TEST()
+
+FN_TEST(udp_filter)
+{
+	// Accepts the first three bytes of the payload if the payload starts
+	// with 'h'.
+	struct sock_filter starts_with_h[] = {
+		BPF_STMT(BPF_LD | BPF_B | BPF_ABS, UDP_HEADER_LEN),
+		BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, 'h', 0, 1),
+		BPF_STMT(BPF_RET | BPF_K, UDP_HEADER_LEN + 3),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	// The UDP header is never trimmed.
+	struct sock_filter trim_header[] = {
+		BPF_STMT(BPF_RET | BPF_K, 1),
+	};
+	char buf[16];
+
+	TEST_SUCC(attach_filter(sk_udp_recv, drop_all, ARRAY_LEN(drop_all)));
+	TEST_RES(send_udp(MESSAGE), _ret == strlen(MESSAGE));
+	TEST_ERRNO(recv(sk_udp_recv, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(attach_filter(sk_udp_recv, starts_with_h,
+				ARRAY_LEN(starts_with_h)));
+	TEST_RES(send_udp("world"), _ret == 5);
+	TEST_RES(send_udp(MESSAGE), _ret == strlen(MESSAGE));
+	TEST_RES(recv(sk_udp_recv, buf, sizeof(buf), 0),
+		 _ret == 3 && memcmp(buf, "hel", 3) == 0);
+	TEST_ERRNO(recv(sk_udp_recv, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(attach_filter(sk_udp_recv, trim_header,
+				ARRAY_LEN(trim_header)));
+	TEST_RES(send_udp(MESSAGE), _ret == strlen(MESSAGE));
+	TEST_RES(recv(sk_udp_recv, buf, sizeof(buf), 0), _ret == 0);
+
+	TEST_SUCC(detach_filter(sk_udp_recv));
+	TEST_ERRNO(detach_filter(sk_udp_recv), ENOENT);
+	TEST_RES(send_udp(MESSAGE), _ret == strlen(MESSAGE));
+	TEST_RES(recv(sk_udp_recv, buf, sizeof(buf), 0),
+		 _ret == strlen(MESSAGE) && memcmp(buf, MESSAGE, _ret) == 0);
+}
+END_TEST()
+
+FN_TEST(udp_filter_before_bind)
+{
+	struct sockaddr_in addr = udp_addr;
+	int sk;
+	char buf[16];
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	TEST_SUCC(attach_filter(sk, drop_all, ARRAY_LEN(drop_all)));
+
+	addr.sin_port = htons(ntohs(UDP_PORT) + 1);
+	TEST_SUCC(bind(sk, (struct sockaddr *)&addr, sizeof(addr)));
+
+	TEST_RES(sendto(sk_udp_send, MESSAGE, strlen(MESSAGE), 0,
+			(struct sockaddr *)&addr, sizeof(addr)),
+		 _ret == strlen(MESSAGE));
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(raw_filter)
+{
+	// Accepts only the IP header of packets destined for `UDP_PORT`.
+	struct sock_filter udp_port_only[] = {
+		BPF_STMT(BPF_LDX | BPF_B | BPF_MSH, 0),
+		BPF_STMT(BPF_LD | BPF_H | BPF_IND, 2),
+		BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, ntohs(UDP_PORT), 0, 1),
+		BPF_STMT(BPF_RET | BPF_K, IP_HEADER_LEN),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sockaddr_in addr = udp_addr;
+	int sk;
+	char buf[64];
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_RAW | SOCK_NONBLOCK, IPPROTO_UDP));
+	TEST_ERRNO(detach_filter(sk), ENOENT);
+	TEST_SUCC(attach_filter(sk, udp_port_only, ARRAY_LEN(udp_port_only)));
+
+	addr.sin_port = htons(ntohs(UDP_PORT) + 2);
+	TEST_RES(sendto(sk_udp_send, MESSAGE, strlen(MESSAGE), 0,
+			(struct sockaddr *)&addr, sizeof(addr)),
+		 _ret == strlen(MESSAGE));
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_RES(send_udp(MESSAGE), _ret == strlen(MESSAGE));
+	TEST_RES(recv(sk, buf, sizeof(buf), 0),
+		 _ret == IP_HEADER_LEN &&
+			 ((struct iphdr *)buf)->protocol == IPPROTO_UDP);
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(detach_filter(sk));
+	TEST_RES(send_udp(MESSAGE), _ret == strlen(MESSAGE));
+	TEST_RES(recv(sk, buf, sizeof(buf), 0),
+		 _ret == IP_HEADER_LEN + UDP_HEADER_LEN + strlen(MESSAGE));
+
+	TEST_SUCC(close(sk));
+
+	// Drain the UDP socket.
+	TEST_RES(recv(sk_udp_recv, buf, sizeof(buf), 0),
+		 _ret == strlen(MESSAGE));
+	TEST_RES(recv(sk_udp_recv, buf, sizeof(buf), 0),
+		 _ret == strlen(MESSAGE));
+}
+END_TEST()
+
+FN_TEST(unix_filter)
+{
+	struct sock_filter trim_to_two[] = {
+		BPF_STMT(BPF_RET | BPF_K, 2),
+	};
+	int sk[2];
+	char buf[16];
+
+	TEST_SUCC(socketpair(PF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0, sk));
+	TEST_ERRNO(detach_filter(sk[1]), ENOENT);
+
+	// Dropped messages are not reported as errors to the sender.
+	TEST_SUCC(attach_filter(sk[1], drop_all, ARRAY_LEN(drop_all)));
+	TEST_RES(send(sk[0], MESSAGE, strlen(MESSAGE), 0),
+		 _ret == strlen(MESSAGE));
+	TEST_ERRNO(recv(sk[1], buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(attach_filter(sk[1], trim_to_two, ARRAY_LEN(trim_to_two)));
+	TEST_RES(send(sk[0], MESSAGE, strlen(MESSAGE), 0),
+		 _ret == strlen(MESSAGE));
+	TEST_RES(recv(sk[1], buf, sizeof(buf), 0),
+		 _ret == 2 && memcmp(buf, "he", 2) == 0);
+
+	// The filter of the receiving socket does not affect the sending one.
+	TEST_RES(send(sk[1], MESSAGE, strlen(MESSAGE), 0),
+		 _ret == strlen(MESSAGE));
+	TEST_RES(recv(sk[0], buf, sizeof(buf), 0), _ret == strlen(MESSAGE));
+
+	TEST_SUCC(detach_filter(sk[1]));
+	TEST_RES(send(sk[0], MESSAGE, strlen(MESSAGE), 0),
+		 _ret == strlen(MESSAGE));
+	TEST_RES(recv(sk[1], buf, sizeof(buf), 0), _ret == strlen(MESSAGE));
+
+	TEST_SUCC(close(sk[0]));
+	TEST_SUCC(close(sk[1]));
+}
+END_TEST()
+
+static int send_getlink(int sk)
+{
+	struct {
+		struct nlmsghdr hdr;
+		struct ifinfomsg ifi;
+	} req;
+
+	memset(&req, 0, sizeof(req));
+	req.hdr.nlmsg_len = sizeof(req);
+	req.hdr.nlmsg_type = RTM_GETLINK;
+	req.hdr.nlmsg_flags = NLM_F_REQUEST | NLM_F_DUMP;
+	req.ifi.ifi_family = AF_UNSPEC;
+
+	return send(sk, &req, sizeof(req), 0);
+}
+
+FN_TEST(netlink_filter)
+{
+	int sk;
+	char buf[4096];
+
+	sk = TEST_SUCC(socket(PF_NETLINK, SOCK_RAW | SOCK_NONBLOCK,
+			      NETLINK_ROUTE));
+	TEST_ERRNO(detach_filter(sk), ENOENT);
+
+	TEST_SUCC(attach_filter(sk, drop_all, ARRAY_LEN(drop_all)));
+	TEST_SUCC(send_getlink(sk));
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(attach_filter(sk, accept_all, ARRAY_LEN(accept_all)));
+	TEST_SUCC(send_getlink(sk));
+	TEST_RES(recv(sk, buf, sizeof(buf), 0),
+		 _ret >= sizeof(struct nlmsghdr));
+
+	TEST_SUCC(detach_filter(sk));
+	TEST_ERRNO(detach_filter(sk), ENOENT);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_udp_recv));
+	CHECK(close(sk_udp_send));
+}
+END_SETUP()
@@ -0,0 +1,285 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <fcntl.h>
+#include <string.h>
+#include <unistd.h>
+#include <sys/poll.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <netinet/tcp.h>
+#include <arpa/inet.h>
+
+#include "../test.h"
+
+#define PROC_CONGESTION "/proc/sys/net/ipv4/tcp_congestion_control"
+
+#define TCP_CA_NAME_MAX 16
+
+#define S_PORT htons(0x1236)
+
+#define TRANSFER_LEN (256 * 1024)
+
+static struct sockaddr_in sk_addr;
+
+static int sk_unbound;
+static int sk_listen;
+
+static int read_proc_congestion(char *buf, size_t len)
+{
+	int fd;
+	ssize_t read_len;
+
+	fd = open(PROC_CONGESTION, O_RDONLY);
+	if (fd < 0)
+		return -1;
+
+	read_len = read(fd, buf, len - 1);
+	close(fd);
+	if (read_len < 0)
+		return -1;
+
+	buf[read_len] = '\0';
+	return read_len;
+}
+
+static int write_proc_congestion(const char *name)
+{
+	int fd;
+	ssize_t write_len;
+
+	fd = open(PROC_CONGESTION, O_WRONLY);
+	if (fd < 0)
+		return -1;
+
+	write_len = write(fd, name, strlen(name));
+	close(fd);
+
+	return write_len < 0 ? -1 : 0;
+}
+
+static int get_congestion(int sk, char *buf)
+{
+	socklen_t len = TCP_CA_NAME_MAX;
+
+	memset(buf, 0, TCP_CA_NAME_MAX);
+	return getsockopt(sk, IPPROTO_TCP, TCP_CONGESTION, buf, &len);
+}
+
+static int set_congestion(int sk, const char *name)
+{
+	return setsockopt(sk, IPPROTO_TCP, TCP_CONGESTION, name, strlen(name));
+}
+
+FN_SETUP(general)
+{
+	sk_addr.sin_family = AF_INET;
+	sk_addr.sin_port = S_PORT;
+	CHECK(inet_aton("127.0.0.1", &sk_addr.sin_addr));
+
+	sk_unbound = CHECK(socket(PF_INET, SOCK_STREAM, 0));
+
+	sk_listen = CHECK(socket(PF_INET, SOCK_STREAM, 0));
+	CHECK(bind(sk_listen, (struct sockaddr *)&sk_addr, sizeof(sk_addr)));
+	CHECK(listen(sk_listen, 4));
+}
+END_SETUP()
+
+FN_TEST(proc_default)
+{
+	char buf[TCP_CA_NAME_MAX + 1];
+
+	TEST_RES(read_proc_congestion(buf, sizeof(buf)),
+		 strcmp(buf, "cubic\n") == 0);
+
+	TEST_RES(get_congestion(sk_unbound, buf), strcmp(buf, "cubic") == 0);
+}
+END_TEST()
+
+FN_TEST(set_congestion)
+{
+	char buf[TCP_CA_NAME_MAX];
+
+	TEST_SUCC(set_congestion(sk_unbound, "reno"));
+	TEST_RES(get_congestion(sk_unbound, buf), strcmp(buf, "reno") == 0);
+
+	TEST_SUCC(set_congestion(sk_unbound, "bbr"));
+	TEST_RES(get_congestion(sk_unbound, buf), strcmp(buf, "bbr") == 0);
+
+	// The name may be terminated by a NUL byte.
+	TEST_SUCC(setsockopt(sk_unbound, IPPROTO_TCP, TCP_CONGESTION,
+			     "cubic\0xyz", 9));
+	TEST_RES(get_congestion(sk_unbound, buf), strcmp(buf, "cubic") == 0);
+
+	TEST_ERRNO(set_congestion(sk_unbound, "no_such_cc"), ENOENT);
+	TEST_RES(get_congestion(sk_unbound, buf), strcmp(buf, "cubic") == 0);
+}
+END_TEST()
+
+FN_TEST(proc_set_default)
+{
+	char buf[TCP_CA_NAME_MAX + 1];
+	int sk;
+
+	TEST_ERRNO(write_proc_congestion("no_such_cc\n"), ENOENT);
+
+	TEST_SUCC(write_proc_congestion("reno\n"));
+	TEST_RES(read_proc_congestion(buf, sizeof(buf)),
+		 strcmp(buf, "reno\n") == 0);
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_STREAM, 0));
+	TEST_RES(get_congestion(sk, buf), strcmp(buf, "reno") == 0);
+	TEST_SUCC(close(sk));
+
+	// Existing sockets are not affected.
+	TEST_RES(get_congestion(sk_unbound, buf), strcmp(buf, "cubic") == 0);
+
+	TEST_SUCC(write_proc_congestion("cubic"));
+	TEST_RES(read_proc_congestion(buf, sizeof(buf)),
+		 strcmp(buf, "cubic\n") == 0);
+}
+END_TEST()
+
+FN_TEST(info_unconnected)
+{
+	struct tcp_info info;
+	socklen_t len;
+
+	len = sizeof(info);
+	TEST_RES(getsockopt(sk_unbound, IPPROTO_TCP, TCP_INFO, &info, &len),
+		 len == sizeof(info) && info.tcpi_state == TCP_CLOSE &&
+			 info.tcpi_total_retrans == 0);
+
+	len = sizeof(info);
+	TEST_RES(getsockopt(sk_listen, IPPROTO_TCP, TCP_INFO, &info, &len),
+		 len == sizeof(info) && info.tcpi_state == TCP_LISTEN);
+
+	// The information is truncated if the buffer is too short.
+	len = 1;
+	info.tcpi_state = 0;
+	info.tcpi_ca_state = 0xff;
+	TEST_RES(getsockopt(sk_listen, IPPROTO_TCP, TCP_INFO, &info, &len),
+		 len == 1 && info.tcpi_state == TCP_LISTEN &&
+			 info.tcpi_ca_state == 0xff);
+
+	TEST_ERRNO(setsockopt(sk_listen, IPPROTO_TCP, TCP_INFO, &info,
+			      sizeof(info)),
+		   ENOPROTOOPT);
+}
+END_TEST()
+
+// Sends `TRANSFER_LEN` bytes from `sk_send` and receives them from `sk_recv`.
+static int transfer(int sk_send, int sk_recv)
+{
+	static char send_buf[4096 + 256];
+	static char recv_buf[4096];
+	size_t sent = 0;
+	size_t received = 0;
+	struct pollfd pfds[2];
+	nfds_t nfds;
+	ssize_t len;
+	ssize_t i;
+
+	for (i = 0; i < (ssize_t)sizeof(send_buf); ++i)
+		send_buf[i] = i;
+
+	while (received < TRANSFER_LEN) {
+		nfds = 0;
+		if (sent < TRANSFER_LEN) {
+			pfds[nfds].fd = sk_send;
+			pfds[nfds].events = POLLOUT;
+			++nfds;
+		}
+		pfds[nfds].fd = sk_recv;
+		pfds[nfds].events = POLLIN;
+		++nfds;
+
+		if (poll(pfds, nfds, 1000) <= 0)
+			return -1;
+
+		if (sent < TRANSFER_LEN && (pfds[0].revents & POLLOUT)) {
+			len = TRANSFER_LEN - sent;
+			if (len > 4096)
+				len = 4096;
+			len = send(sk_send, &send_buf[sent % 256], len,
+				   MSG_DONTWAIT);
+			if (len < 0 && errno != EAGAIN)
+				return -1;
+			if (len > 0)
+				sent += len;
+		}
+
+		if (pfds[nfds - 1].revents & POLLIN) {
+			len = recv(sk_recv, recv_buf, sizeof(recv_buf),
+				   MSG_DONTWAIT);
+			if (len == 0 || (len < 0 && errno != EAGAIN))
+				return -1;
+			for (i = 0; i < len; ++i)
+				if (recv_buf[i] != (char)(received + i))
+					return -1;
+			if (len > 0)
+				received += len;
+		}
+	}
+
+	return 0;
+}
+
+FN_TEST(transfer)
+{
+	static const char *const names[] = { "reno", "cubic", "bbr" };
+	int sk_connected;
+	int sk_accepted;
+	struct tcp_info info;
+	socklen_t len;
+	char buf[TCP_CA_NAME_MAX];
+	size_t i;
+
+	for (i = 0; i < sizeof(names) / sizeof(names[0]); ++i) {
+		sk_connected = TEST_SUCC(socket(PF_INET, SOCK_STREAM, 0));
+		TEST_SUCC(set_congestion(sk_connected, names[i]));
+		TEST_SUCC(connect(sk_connected, (struct sockaddr *)&sk_addr,
+				  sizeof(sk_addr)));
+		sk_accepted = TEST_SUCC(accept(sk_listen, NULL, NULL));
+
+		TEST_RES(get_congestion(sk_connected, buf),
+			 strcmp(buf, names[i]) == 0);
+		// The accepted socket inherits the algorithm of the listening
+		// socket.
+		TEST_RES(get_congestion(sk_accepted, buf),
+			 strcmp(buf, "cubic") == 0);
+
+		TEST_SUCC(transfer(sk_connected, sk_accepted));
+		len = sizeof(info);
+		TEST_RES(getsockopt(sk_connected, IPPROTO_TCP, TCP_INFO, &info,
+				    &len),
+			 len == sizeof(info) &&
+				 info.tcpi_state == TCP_ESTABLISHED &&
+				 info.tcpi_snd_cwnd > 0 &&
+				 info.tcpi_snd_ssthresh > 0 &&
+				 info.tcpi_snd_mss > 0 && info.tcpi_rto > 0);
+
+		// Switch the algorithm in the middle of a connection.
+		TEST_SUCC(set_congestion(sk_accepted, names[i]));
+		TEST_RES(get_congestion(sk_accepted, buf),
+			 strcmp(buf, names[i]) == 0);
+
+		TEST_SUCC(transfer(sk_accepted, sk_connected));
+		len = sizeof(info);
+		TEST_RES(getsockopt(sk_accepted, IPPROTO_TCP, TCP_INFO, &info,
+				    &len),
+			 len == sizeof(info) &&
+				 info.tcpi_state == TCP_ESTABLISHED &&
+				 info.tcpi_snd_cwnd > 0);
+
+		TEST_SUCC(close(sk_connected));
+		TEST_SUCC(close(sk_accepted));
+	}
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_unbound));
+	CHECK(close(sk_listen));
+}
+END_SETUP()
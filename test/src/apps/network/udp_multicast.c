@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <string.h>
+#include <unistd.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+
+#include "../test.h"
+
+#define GROUP_ADDR "239.1.2.3"
+#define GROUP_PORT htons(0x1234)
+
+static struct in_addr group_addr;
+static struct in_addr loopback_addr;
+
+static int sk_recv;
+static int sk_send;
+
+FN_SETUP(general)
+{
+	CHECK(inet_aton(GROUP_ADDR, &group_addr));
+	loopback_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	sk_recv = CHECK(socket(PF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	sk_send = CHECK(socket(PF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+}
+END_SETUP()
+
+FN_SETUP(bind)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = GROUP_PORT,
+		.sin_addr = loopback_addr,
+	};
+
+	CHECK(bind(sk_recv, (struct sockaddr *)&addr, sizeof(addr)));
+}
+END_SETUP()
+
+FN_TEST(default_options)
+{
+	int val;
+	struct in_addr addr;
+	socklen_t len;
+
+	len = sizeof(val);
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val, &len),
+		 len == sizeof(val) && val == 1);
+
+	len = sizeof(val);
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_LOOP, &val,
+			    &len),
+		 len == sizeof(val) && val == 1);
+
+	len = sizeof(addr);
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_IF, &addr, &len),
+		 len == sizeof(addr) && addr.s_addr == htonl(INADDR_ANY));
+}
+END_TEST()
+
+FN_TEST(multicast_ttl)
+{
+	int val;
+	socklen_t len = sizeof(val);
+
+	val = 0;
+	TEST_SUCC(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			     sizeof(val)));
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val, &len),
+		 len == sizeof(val) && val == 0);
+
+	val = 255;
+	TEST_SUCC(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			     sizeof(val)));
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val, &len),
+		 len == sizeof(val) && val == 255);
+
+	val = 256;
+	TEST_ERRNO(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			      sizeof(val)),
+		   EINVAL);
+
+	val = -2;
+	TEST_ERRNO(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			      sizeof(val)),
+		   EINVAL);
+
+	val = -1;
+	TEST_SUCC(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			     sizeof(val)));
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val, &len),
+		 len == sizeof(val) && val == 1);
+}
+END_TEST()
+
+FN_TEST(multicast_if)
+{
+	struct in_addr addr;
+	struct ip_mreqn mreqn;
+	socklen_t len = sizeof(addr);
+
+	CHECK(inet_aton("10.255.255.254", &addr));
+	TEST_ERRNO(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_IF, &addr,
+			      sizeof(addr)),
+		   EADDRNOTAVAIL);
+
+	memset(&mreqn, 0, sizeof(mreqn));
+	mreqn.imr_ifindex = 0x7fff;
+	TEST_ERRNO(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_IF, &mreqn,
+			      sizeof(mreqn)),
+		   EADDRNOTAVAIL);
+
+	TEST_ERRNO(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_IF, &addr, 3),
+		   EINVAL);
+
+	TEST_SUCC(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_IF,
+			     &loopback_addr, sizeof(loopback_addr)));
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_IF, &addr, &len),
+		 len == sizeof(addr) && addr.s_addr == loopback_addr.s_addr);
+}
+END_TEST()
+
+FN_TEST(membership_errors)
+{
+	struct ip_mreq mreq = {
+		.imr_multiaddr = loopback_addr,
+		.imr_interface = loopback_addr,
+	};
+	socklen_t len = sizeof(mreq);
+
+	TEST_ERRNO(setsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EINVAL);
+
+	mreq.imr_multiaddr = group_addr;
+	TEST_ERRNO(setsockopt(sk_recv, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRNOTAVAIL);
+
+	TEST_ERRNO(getsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      &len),
+		   ENOPROTOOPT);
+}
+END_TEST()
+
+FN_TEST(join_and_leave)
+{
+	struct ip_mreq mreq = {
+		.imr_multiaddr = group_addr,
+		.imr_interface = loopback_addr,
+	};
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = GROUP_PORT,
+		.sin_addr = group_addr,
+	};
+	char buf[16];
+
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_ERRNO(setsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRINUSE);
+
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 5);
+	TEST_RES(recv(sk_recv, buf, sizeof(buf), 0),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0);
+
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_ERRNO(setsockopt(sk_recv, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRNOTAVAIL);
+
+	TEST_RES(sendto(sk_send, "world", 5, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 5);
+	TEST_ERRNO(recv(sk_recv, buf, sizeof(buf), 0), EAGAIN);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_recv));
+	CHECK(close(sk_send));
+}
+END_SETUP()
@@ -0,0 +1,284 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <elf.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <signal.h>
+#include <sys/mman.h>
+#include <sys/prctl.h>
+#include <sys/resource.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../test.h"
+
+#define PAGE_SIZE 4096
+#define NOTE_ALIGN(size) (((size) + 3) & ~3)
+
+#define CORE_PATTERN_FILE "/proc/sys/kernel/core_pattern"
+#define CORE_PATTERN "/tmp/core.%p.%s"
+
+static char old_pattern[128];
+
+// These are filled after the child crashes, so they do not appear in the core file.
+static char dumped_page[PAGE_SIZE];
+static char dontdump_page[PAGE_SIZE];
+
+static void core_name(char *buf, size_t len, pid_t pid, int sig)
+{
+	snprintf(buf, len, "/tmp/core.%d.%d", pid, sig);
+}
+
+static void *map_core(pid_t pid, int sig, size_t *size)
+{
+	char path[64];
+	struct stat stat_buf;
+	void *core;
+	int fd;
+
+	core_name(path, sizeof(path), pid, sig);
+	fd = CHECK(open(path, O_RDONLY));
+	CHECK(fstat(fd, &stat_buf));
+	core = CHECK_WITH(mmap(NULL, stat_buf.st_size, PROT_READ, MAP_PRIVATE,
+			       fd, 0),
+			  _ret != MAP_FAILED);
+	CHECK(close(fd));
+	CHECK(unlink(path));
+
+	*size = stat_buf.st_size;
+	return core;
+}
+
+static int count_notes(const char *core, unsigned int type)
+{
+	const Elf64_Ehdr *ehdr = (const void *)core;
+	const Elf64_Phdr *phdr = (const void *)(core + ehdr->e_phoff);
+	size_t offset = phdr->p_offset;
+	size_t end = phdr->p_offset + phdr->p_filesz;
+	int count = 0;
+
+	while (offset < end) {
+		const Elf64_Nhdr *nhdr = (const void *)(core + offset);
+
+		if (nhdr->n_type == type)
+			++count;
+		offset += sizeof(*nhdr) + NOTE_ALIGN(nhdr->n_namesz) +
+			  NOTE_ALIGN(nhdr->n_descsz);
+	}
+
+	return count;
+}
+
+static void *sleep_forever(void *arg)
+{
+	(void)arg;
+
+	for (;;)
+		pause();
+
+	return NULL;
+}
+
+FN_SETUP(core_pattern)
+{
+	struct rlimit rlimit = { .rlim_cur = RLIM_INFINITY,
+				 .rlim_max = RLIM_INFINITY };
+	int fd;
+
+	CHECK(setrlimit(RLIMIT_CORE, &rlimit));
+
+	fd = CHECK(open(CORE_PATTERN_FILE, O_RDWR));
+	CHECK(read(fd, old_pattern, sizeof(old_pattern) - 1));
+	CHECK_WITH(write(fd, CORE_PATTERN, strlen(CORE_PATTERN)),
+		   _ret == strlen(CORE_PATTERN));
+	CHECK(close(fd));
+}
+END_SETUP()
+
+FN_TEST(read_core_pattern)
+{
+	char buf[128] = {};
+	int fd;
+
+	fd = TEST_SUCC(open(CORE_PATTERN_FILE, O_RDONLY));
+	TEST_RES(read(fd, buf, sizeof(buf)),
+		 _ret == strlen(CORE_PATTERN) + 1 &&
+			 strcmp(buf, CORE_PATTERN "\n") == 0);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(dump_core)
+{
+	pid_t pid;
+	int status;
+	const char *core;
+	const Elf64_Ehdr *ehdr;
+	const Elf64_Phdr *phdr;
+	size_t size;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		char *pages = CHECK_WITH(mmap(NULL, PAGE_SIZE * 2,
+					      PROT_READ | PROT_WRITE,
+					      MAP_PRIVATE | MAP_ANONYMOUS, -1,
+					      0),
+					 _ret != MAP_FAILED);
+
+		memset(pages, 0xa5, PAGE_SIZE);
+		memset(pages + PAGE_SIZE, 0x5a, PAGE_SIZE);
+		CHECK(madvise(pages + PAGE_SIZE, PAGE_SIZE, MADV_DONTDUMP));
+
+		abort();
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGABRT && WCOREDUMP(status));
+
+	core = map_core(pid, SIGABRT, &size);
+	ehdr = (const void *)core;
+	phdr = (const void *)(core + ehdr->e_phoff);
+
+	TEST_RES(memcmp(ehdr->e_ident, ELFMAG, SELFMAG),
+		 _ret == 0 && ehdr->e_ident[EI_CLASS] == ELFCLASS64);
+	TEST_RES(ehdr->e_type, _ret == ET_CORE && ehdr->e_phnum > 1);
+	TEST_RES(phdr->p_type, _ret == PT_NOTE);
+	TEST_RES(count_notes(core, NT_PRSTATUS), _ret == 1);
+	TEST_RES(count_notes(core, NT_PRPSINFO), _ret == 1);
+	TEST_RES(count_notes(core, NT_AUXV), _ret == 1);
+
+	memset(dumped_page, 0xa5, PAGE_SIZE);
+	memset(dontdump_page, 0x5a, PAGE_SIZE);
+	TEST_RES(memmem(core, size, dumped_page, PAGE_SIZE), _ret != NULL);
+	TEST_RES(memmem(core, size, dontdump_page, PAGE_SIZE), _ret == NULL);
+
+	TEST_SUCC(munmap((void *)core, size));
+}
+END_TEST()
+
+FN_TEST(dump_core_multithreaded)
+{
+	pid_t pid;
+	int status;
+	const char *core;
+	size_t size;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		pthread_t thread;
+
+		CHECK(pthread_create(&thread, NULL, sleep_forever, NULL));
+		CHECK(pthread_create(&thread, NULL, sleep_forever, NULL));
+
+		abort();
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGABRT && WCOREDUMP(status));
+
+	core = map_core(pid, SIGABRT, &size);
+	TEST_RES(count_notes(core, NT_PRSTATUS), _ret == 3);
+	TEST_SUCC(munmap((void *)core, size));
+}
+END_TEST()
+
+FN_TEST(waitid_dumped)
+{
+	pid_t pid;
+	siginfo_t info;
+	char path[64];
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		*(volatile int *)NULL = 0;
+		_exit(EXIT_FAILURE);
+	}
+
+	TEST_RES(waitid(P_PID, pid, &info, WEXITED),
+		 info.si_pid == pid && info.si_code == CLD_DUMPED &&
+			 info.si_status == SIGSEGV);
+
+	core_name(path, sizeof(path), pid, SIGSEGV);
+	TEST_SUCC(unlink(path));
+}
+END_TEST()
+
+FN_TEST(not_dumpable)
+{
+	pid_t pid;
+	int status;
+	char path[64];
+
+	TEST_RES(prctl(PR_GET_DUMPABLE), _ret == 1);
+	TEST_ERRNO(prctl(PR_SET_DUMPABLE, 2), EINVAL);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK(prctl(PR_SET_DUMPABLE, 0));
+		CHECK_WITH(prctl(PR_GET_DUMPABLE), _ret == 0);
+		abort();
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGABRT && !WCOREDUMP(status));
+
+	core_name(path, sizeof(path), pid, SIGABRT);
+	TEST_ERRNO(access(path, F_OK), ENOENT);
+}
+END_TEST()
+
+FN_TEST(zero_core_limit)
+{
+	pid_t pid;
+	int status;
+	char path[64];
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		struct rlimit rlimit = { .rlim_cur = 0,
+					 .rlim_max = RLIM_INFINITY };
+
+		CHECK(setrlimit(RLIMIT_CORE, &rlimit));
+		abort();
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGABRT && !WCOREDUMP(status));
+
+	core_name(path, sizeof(path), pid, SIGABRT);
+	TEST_ERRNO(access(path, F_OK), ENOENT);
+}
+END_TEST()
+
+FN_TEST(madvise_dontdump)
+{
+	char *pages;
+
+	pages = TEST_SUCC(mmap(NULL, PAGE_SIZE * 2, PROT_READ | PROT_WRITE,
+			       MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	TEST_SUCC(munmap(pages + PAGE_SIZE, PAGE_SIZE));
+
+	TEST_SUCC(madvise(pages, PAGE_SIZE, MADV_DONTDUMP));
+	TEST_SUCC(madvise(pages, PAGE_SIZE, MADV_DODUMP));
+	TEST_ERRNO(madvise(pages, PAGE_SIZE * 2, MADV_DONTDUMP), ENOMEM);
+
+	TEST_SUCC(munmap(pages, PAGE_SIZE));
+}
+END_TEST()
+
+FN_SETUP(restore_core_pattern)
+{
+	int fd;
+
+	fd = CHECK(open(CORE_PATTERN_FILE, O_WRONLY));
+	CHECK(write(fd, old_pattern, strlen(old_pattern)));
+	CHECK(close(fd));
+}
+END_SETUP()
@@ -0,0 +1,285 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <elf.h>
+#include <signal.h>
+#include <stddef.h>
+#include <sys/ptrace.h>
+#include <sys/syscall.h>
+#include <sys/uio.h>
+#include <sys/user.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../test.h"
+
+#define STOP_STATUS(status) ((status) >> 8)
+#define EVENT_STATUS(event) (SIGTRAP | ((event) << 8))
+
+static volatile long shared_word = 0x1234;
+static volatile const long readonly_word = 0x1234;
+
+static __attribute__((noinline)) int text_func(void)
+{
+	return 42;
+}
+
+static pid_t child;
+
+FN_SETUP(trace_me)
+{
+	int status;
+
+	child = CHECK(fork());
+	if (child == 0) {
+		CHECK(ptrace(PTRACE_TRACEME, 0, NULL, NULL));
+		CHECK(raise(SIGSTOP));
+
+		// Wait for the tracer to modify the words.
+		if (shared_word != 0x5678 || readonly_word != 0x5678)
+			_exit(EXIT_FAILURE);
+
+		// A traced system call.
+		syscall(SYS_getppid);
+
+		// A signal that will be suppressed by the tracer.
+		raise(SIGUSR1);
+
+		_exit(EXIT_SUCCESS);
+	}
+
+	CHECK_WITH(waitpid(child, &status, 0),
+		   _ret == child && WIFSTOPPED(status) &&
+			   WSTOPSIG(status) == SIGSTOP);
+}
+END_SETUP()
+
+FN_TEST(invalid_requests)
+{
+	TEST_ERRNO(ptrace(PTRACE_ATTACH, getpid(), NULL, NULL), EPERM);
+	TEST_ERRNO(ptrace(PTRACE_CONT, getpid(), NULL, NULL), ESRCH);
+	TEST_ERRNO(ptrace(PTRACE_SETOPTIONS, child, NULL, (void *)(1L << 30)),
+		   EINVAL);
+	TEST_ERRNO(ptrace(PTRACE_CONT, child, NULL, (void *)1000L), EIO);
+}
+END_TEST()
+
+FN_TEST(peek_and_poke)
+{
+	errno = 0;
+	TEST_RES(ptrace(PTRACE_PEEKDATA, child, &shared_word, NULL),
+		 _ret == 0x1234 && errno == 0);
+	TEST_SUCC(ptrace(PTRACE_POKEDATA, child, &shared_word, (void *)0x5678));
+	TEST_RES(ptrace(PTRACE_PEEKDATA, child, &shared_word, NULL),
+		 _ret == 0x5678);
+
+	// The tracer's own memory is not affected.
+	TEST_RES(shared_word, _ret == 0x1234);
+}
+END_TEST()
+
+FN_TEST(poke_readonly)
+{
+	long text_word;
+
+	// The tracer can write to read-only mappings.
+	TEST_SUCC(ptrace(PTRACE_POKEDATA, child, &readonly_word,
+			 (void *)0x5678));
+	TEST_RES(ptrace(PTRACE_PEEKDATA, child, &readonly_word, NULL),
+		 _ret == 0x5678);
+	TEST_RES(readonly_word, _ret == 0x1234);
+
+	// This includes the code (e.g., to insert breakpoints).
+	errno = 0;
+	text_word = TEST_RES(ptrace(PTRACE_PEEKTEXT, child, &text_func, NULL),
+			     errno == 0);
+	TEST_SUCC(ptrace(PTRACE_POKETEXT, child, &text_func,
+			 (void *)~text_word));
+	TEST_RES(ptrace(PTRACE_PEEKTEXT, child, &text_func, NULL),
+		 _ret == ~text_word);
+	TEST_RES(text_func(), _ret == 42);
+	TEST_SUCC(ptrace(PTRACE_POKETEXT, child, &text_func,
+			 (void *)text_word));
+	TEST_RES(ptrace(PTRACE_PEEKTEXT, child, &text_func, NULL),
+		 _ret == text_word);
+}
+END_TEST()
+
+FN_TEST(get_and_set_regs)
+{
+	struct user_regs_struct regs, regs2;
+	struct iovec iov = { .iov_base = &regs, .iov_len = sizeof(regs) };
+
+	TEST_RES(ptrace(PTRACE_GETREGSET, child, (void *)NT_PRSTATUS, &iov),
+		 iov.iov_len == sizeof(regs));
+	TEST_ERRNO(ptrace(PTRACE_GETREGSET, child, (void *)1000L, &iov),
+		   EINVAL);
+
+#ifdef __x86_64__
+	TEST_RES(ptrace(PTRACE_GETREGS, child, NULL, &regs2),
+		 regs2.rip == regs.rip && regs2.rsp == regs.rsp);
+	TEST_RES(ptrace(PTRACE_PEEKUSER, child,
+			(void *)offsetof(struct user_regs_struct, rip), NULL),
+		 _ret == regs.rip);
+	TEST_SUCC(ptrace(PTRACE_SETREGS, child, NULL, &regs2));
+#else
+	(void)regs2;
+#endif
+	TEST_SUCC(ptrace(PTRACE_SETREGSET, child, (void *)NT_PRSTATUS, &iov));
+}
+END_TEST()
+
+FN_TEST(syscall_stops)
+{
+	int status;
+	struct user_regs_struct regs;
+	struct iovec iov = { .iov_base = &regs, .iov_len = sizeof(regs) };
+	siginfo_t siginfo;
+
+	TEST_SUCC(ptrace(PTRACE_SETOPTIONS, child, NULL,
+			 (void *)PTRACE_O_TRACESYSGOOD));
+
+	// Skip the system calls until `getppid` is reached.
+	for (;;) {
+		TEST_SUCC(ptrace(PTRACE_SYSCALL, child, NULL, NULL));
+		TEST_RES(waitpid(child, &status, 0),
+			 _ret == child && WIFSTOPPED(status) &&
+				 WSTOPSIG(status) == (SIGTRAP | 0x80));
+		TEST_SUCC(ptrace(PTRACE_GETREGSET, child, (void *)NT_PRSTATUS,
+				 &iov));
+#ifdef __x86_64__
+		if (regs.orig_rax == SYS_getppid)
+			break;
+#else
+		break;
+#endif
+	}
+
+	TEST_RES(ptrace(PTRACE_GETSIGINFO, child, NULL, &siginfo),
+		 siginfo.si_signo == SIGTRAP &&
+			 siginfo.si_code == (SIGTRAP | 0x80));
+
+	// The exit stop.
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, child, NULL, NULL));
+	TEST_RES(waitpid(child, &status, 0),
+		 _ret == child && WIFSTOPPED(status) &&
+			 WSTOPSIG(status) == (SIGTRAP | 0x80));
+}
+END_TEST()
+
+FN_TEST(signal_stops)
+{
+	int status;
+	siginfo_t siginfo;
+
+	// Skip the remaining system calls until the signal is reported.
+	for (;;) {
+		TEST_SUCC(ptrace(PTRACE_CONT, child, NULL, NULL));
+		TEST_RES(waitpid(child, &status, 0),
+			 _ret == child && WIFSTOPPED(status));
+		if (WSTOPSIG(status) == SIGUSR1)
+			break;
+	}
+
+	TEST_RES(ptrace(PTRACE_GETSIGINFO, child, NULL, &siginfo),
+		 siginfo.si_signo == SIGUSR1 && siginfo.si_pid == child);
+
+	// The stop is reported only once.
+	TEST_RES(waitpid(child, &status, WNOHANG), _ret == 0);
+
+	// Suppress the signal, so the child exits normally.
+	TEST_SUCC(ptrace(PTRACE_CONT, child, NULL, NULL));
+	TEST_RES(waitpid(child, &status, 0),
+		 _ret == child && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+}
+END_TEST()
+
+FN_TEST(fork_and_exec_events)
+{
+	int status;
+	unsigned long msg;
+	pid_t grandchild;
+	sigset_t sigchld_set;
+
+	sigemptyset(&sigchld_set);
+	sigaddset(&sigchld_set, SIGCHLD);
+
+	child = TEST_SUCC(fork());
+	if (child == 0) {
+		CHECK(ptrace(PTRACE_TRACEME, 0, NULL, NULL));
+		CHECK(raise(SIGSTOP));
+
+		// Avoid signal-delivery-stops caused by the exit of the grandchild.
+		CHECK(sigprocmask(SIG_BLOCK, &sigchld_set, NULL));
+
+		if (CHECK(fork()) == 0)
+			_exit(EXIT_SUCCESS);
+
+		execl("/test/execve/hello", "hello", NULL);
+		_exit(EXIT_FAILURE);
+	}
+
+	TEST_RES(waitpid(child, &status, 0),
+		 _ret == child && WIFSTOPPED(status) &&
+			 WSTOPSIG(status) == SIGSTOP);
+	TEST_SUCC(ptrace(PTRACE_SETOPTIONS, child, NULL,
+			 (void *)(PTRACE_O_TRACEFORK | PTRACE_O_TRACEEXEC)));
+	TEST_SUCC(ptrace(PTRACE_CONT, child, NULL, NULL));
+
+	// The fork event.
+	TEST_RES(waitpid(child, &status, 0),
+		 _ret == child && WIFSTOPPED(status) &&
+			 STOP_STATUS(status) ==
+				 EVENT_STATUS(PTRACE_EVENT_FORK));
+	TEST_SUCC(ptrace(PTRACE_GETEVENTMSG, child, NULL, &msg));
+	grandchild = msg;
+
+	// The grandchild is traced automatically and starts with SIGSTOP.
+	TEST_RES(waitpid(grandchild, &status, 0),
+		 _ret == grandchild && WIFSTOPPED(status) &&
+			 WSTOPSIG(status) == SIGSTOP);
+	TEST_SUCC(ptrace(PTRACE_DETACH, grandchild, NULL, NULL));
+
+	// The exec event.
+	TEST_SUCC(ptrace(PTRACE_CONT, child, NULL, NULL));
+	TEST_RES(waitpid(child, &status, 0),
+		 _ret == child && WIFSTOPPED(status) &&
+			 STOP_STATUS(status) ==
+				 EVENT_STATUS(PTRACE_EVENT_EXEC));
+	TEST_RES(ptrace(PTRACE_GETEVENTMSG, child, NULL, &msg), msg == child);
+
+	TEST_SUCC(ptrace(PTRACE_DETACH, child, NULL, NULL));
+	TEST_RES(waitpid(child, &status, 0),
+		 _ret == child && WIFEXITED(status));
+}
+END_TEST()
+
+FN_TEST(attach)
+{
+	int status;
+
+	child = TEST_SUCC(fork());
+	if (child == 0) {
+		for (;;)
+			pause();
+	}
+
+	TEST_SUCC(ptrace(PTRACE_ATTACH, child, NULL, NULL));
+	TEST_ERRNO(ptrace(PTRACE_ATTACH, child, NULL, NULL), EPERM);
+	TEST_RES(waitpid(child, &status, 0),
+		 _ret == child && WIFSTOPPED(status) &&
+			 WSTOPSIG(status) == SIGSTOP);
+
+	// The tracee is not stopped after being resumed.
+	TEST_SUCC(ptrace(PTRACE_CONT, child, NULL, NULL));
+	TEST_ERRNO(ptrace(PTRACE_PEEKDATA, child, &shared_word, NULL), ESRCH);
+
+	TEST_SUCC(kill(child, SIGKILL));
+	TEST_RES(waitpid(child, &status, 0),
+		 _ret == child && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+	TEST_ERRNO(ptrace(PTRACE_CONT, child, NULL, NULL), ESRCH);
+}
+END_TEST()
@@ -4,4 +4,6 @@
 
 set -e
 
+/test/ipconfig.sh
+
 echo "Successfully booted."
@@ -104,7 +104,9 @@ epoll/epoll_err
 epoll/poll_err
 file_io/access_err
 file_io/iovec_err
+inotify/inotify
 devfs/full
 devfs/random
 devfs/framebuffer
 devfs/evdev
+fuse/fuse
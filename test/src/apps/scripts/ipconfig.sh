@@ -0,0 +1,103 @@
+#!/bin/sh
+
+# SPDX-License-Identifier: MPL-2.0
+
+# Checks `/proc/net/pnp` against the `ip=` kernel parameter, which can be set via the `IP_CONFIG`
+# variable in the Makefile (e.g., `make run AUTO_TEST=boot IP_CONFIG=dhcp`).
+
+set -e
+
+PNP_FILE=/proc/net/pnp
+
+fail() {
+    echo "Error: $1"
+    echo "The content of ${PNP_FILE}:"
+    cat ${PNP_FILE}
+    exit 1
+}
+
+# Returns whether the autoconfiguration method enables DHCP.
+is_dhcp() {
+    case "$1" in
+        "off" | "none") return 1 ;;
+        *) return 0 ;;
+    esac
+}
+
+echo "Start ipconfig test......"
+
+HAS_IP_CONFIG=
+IP_CONFIG=
+for arg in $(cat /proc/cmdline); do
+    case "$arg" in
+        ip=*)
+            HAS_IP_CONFIG=1
+            IP_CONFIG="${arg#ip=}"
+            ;;
+    esac
+done
+
+CLIENT=
+SERVER=
+AUTOCONF=
+DNS0=
+DNS1=
+if [ -z "$HAS_IP_CONFIG" ]; then
+    # Without the parameter, the default static configuration is used.
+    echo "No ip= parameter is specified"
+    AUTOCONF=off
+else
+    echo "ip=${IP_CONFIG}"
+    case "$IP_CONFIG" in
+        *:*)
+            OLD_IFS=$IFS
+            IFS=:
+            set -- $IP_CONFIG
+            IFS=$OLD_IFS
+            CLIENT=$1
+            SERVER=$2
+            AUTOCONF=$7
+            DNS0=$8
+            DNS1=$9
+            ;;
+        *.*.*.*)
+            CLIENT=$IP_CONFIG
+            ;;
+        *)
+            AUTOCONF=$IP_CONFIG
+            ;;
+    esac
+fi
+
+# The static configuration is used if the client address is specified.
+if [ -z "$CLIENT" ] && is_dhcp "$AUTOCONF"; then
+    # Wait for the DHCP lease, which contains the address of the DHCP server.
+    for i in $(seq 1 50); do
+        if grep -q "^bootserver " ${PNP_FILE}; then
+            break
+        fi
+        sleep 0.2
+    done
+
+    [ "$(head -n 1 ${PNP_FILE})" = "#PROTO: DHCP" ] || fail "the protocol is not DHCP"
+    grep -q "^bootserver " ${PNP_FILE} || fail "no DHCP lease is obtained"
+    # The DNS servers in the parameter take precedence over those in the DHCP lease.
+    if [ -n "$DNS0" ]; then
+        EXPECTED_DNS="nameserver ${DNS0}"
+        [ -n "$DNS1" ] && EXPECTED_DNS="${EXPECTED_DNS}
+nameserver ${DNS1}"
+        [ "$(grep "^nameserver " ${PNP_FILE})" = "$EXPECTED_DNS" ] || \
+            fail "the DNS servers are not reported"
+    fi
+else
+    EXPECTED="#MANUAL"
+    [ -n "$DNS0" ] && EXPECTED="${EXPECTED}
+nameserver ${DNS0}"
+    [ -n "$DNS1" ] && EXPECTED="${EXPECTED}
+nameserver ${DNS1}"
+    [ -n "$SERVER" ] && EXPECTED="${EXPECTED}
+bootserver ${SERVER}"
+    [ "$(cat ${PNP_FILE})" = "$EXPECTED" ] || fail "the static configuration is not reported"
+fi
+
+echo "Ipconfig test passed."
@@ -28,18 +28,29 @@ sleep 0.2
 ./socketpair
 ./sockoption
 ./sockoption_unix
+./icmp
+./ipv6
+./ip_cmsg
+./msg_flags
+./packet
 ./listen_backlog
 ./send_buf_full
+./sock_filter
+./tcp_congestion
 ./tcp_err
 ./tcp_poll
 ./tcp_reuseaddr
 ./udp_err
+./udp_multicast
 ./unix_stream_err
 ./unix_seqpacket_err
 ./unix_datagram_err
 
+./genl_ctrl
+./pktfilter
 ./netlink_route
 ./rtnl_err
+./rtnl_route
 ./uevent_err
 
 echo "All network test passed"
@@ -28,6 +28,8 @@ getcpu/getcpu
 getpid/getpid
 hello_pie/hello
 hello_world/hello_world
+ipc/sysv_msg
+ipc/posix_mqueue
 itimer/setitimer
 itimer/timer_create
 mmap/mmap_and_fork
@@ -38,11 +40,16 @@ mmap/mmap_shared_filebacked
 mmap/mmap_readahead
 mmap/mmap_vmrss
 namespace/mnt_ns
+namespace/mnt_propagation
+namespace/mount_api
+namespace/net_ns
 namespace/setns
 namespace/unshare
+process/coredump
 process/group_session
 process/job_control
 process/pidfd
+process/ptrace
 process/wait4
 procfs/pid_mem
 pseudofs/pseudo_inode
@@ -56,6 +63,7 @@ sched/sched_attr_getset
 sched/sched_param_getset
 sched/sched_param_idle
 shm/posix_shm
+shm/sysv_shm
 signal_c/kill
 signal_c/parent_death_signal
 signal_c/sigaltstack
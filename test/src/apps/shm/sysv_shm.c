@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <string.h>
+#include <unistd.h>
+#include <sys/ipc.h>
+#include <sys/mman.h>
+#include <sys/shm.h>
+#include <sys/wait.h>
+
+#include "../test.h"
+
+#define SHM_KEY 0x1234
+#define SHM_SIZE 5000
+#define MSG "Hello from the child process"
+
+static int shmid;
+
+FN_SETUP(create)
+{
+	shmid = CHECK(shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | IPC_EXCL | 0600));
+}
+END_SETUP()
+
+FN_TEST(get)
+{
+	TEST_RES(shmget(SHM_KEY, SHM_SIZE, 0600), _ret == shmid);
+	TEST_RES(shmget(SHM_KEY, 0, 0), _ret == shmid);
+	TEST_ERRNO(shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | IPC_EXCL | 0600),
+		   EEXIST);
+	TEST_ERRNO(shmget(SHM_KEY, SHM_SIZE * 2, 0600), EINVAL);
+	TEST_ERRNO(shmget(SHM_KEY + 1, SHM_SIZE, 0600), ENOENT);
+	TEST_ERRNO(shmget(IPC_PRIVATE, 0, IPC_CREAT | 0600), EINVAL);
+}
+END_TEST()
+
+FN_TEST(stat)
+{
+	struct shmid_ds ds;
+
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 ds.shm_segsz == SHM_SIZE && ds.shm_nattch == 0 &&
+			 ds.shm_perm.__key == SHM_KEY &&
+			 (ds.shm_perm.mode & 0777) == 0600 &&
+			 ds.shm_cpid == getpid());
+
+	ds.shm_perm.mode = 0640;
+	TEST_SUCC(shmctl(shmid, IPC_SET, &ds));
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 (ds.shm_perm.mode & 0777) == 0640);
+
+	TEST_ERRNO(shmctl(-1, IPC_STAT, &ds), EINVAL);
+	TEST_ERRNO(shmctl(shmid, 0x7fff, &ds), EINVAL);
+}
+END_TEST()
+
+FN_TEST(stat_by_index)
+{
+	struct shmid_ds ds;
+	struct shm_info info;
+	int max_index, index;
+
+	// This is how `ipcs -m` enumerates the segments.
+	max_index = TEST_RES(shmctl(0, SHM_INFO, (struct shmid_ds *)&info),
+			     _ret >= 0 && info.used_ids >= 1 &&
+				     info.shm_tot >= 2);
+
+	for (index = 0; index <= max_index; index++) {
+		if (shmctl(index, SHM_STAT, &ds) == shmid)
+			break;
+	}
+	TEST_RES(index, _ret <= max_index);
+
+	TEST_RES(shmctl(index, SHM_STAT, &ds),
+		 _ret == shmid && ds.shm_segsz == SHM_SIZE &&
+			 ds.shm_perm.__key == SHM_KEY);
+	TEST_RES(shmctl(index, SHM_STAT_ANY, &ds),
+		 _ret == shmid && ds.shm_segsz == SHM_SIZE &&
+			 ds.shm_perm.__key == SHM_KEY);
+}
+END_TEST()
+
+FN_TEST(attach_and_fork)
+{
+	struct shmid_ds ds;
+	char *addr;
+	int status;
+	pid_t pid;
+
+	addr = TEST_RES(shmat(shmid, NULL, 0), _ret != (void *)-1);
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 ds.shm_nattch == 1 && ds.shm_lpid == getpid());
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		strcpy(addr, MSG);
+		shmdt(addr);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_RES(strcmp(addr, MSG), _ret == 0);
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds), ds.shm_nattch == 1);
+
+	// The child inherits the attachment and detaches it implicitly on exit.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (shmctl(shmid, IPC_STAT, &ds) < 0 || ds.shm_nattch != 2)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds), ds.shm_nattch == 1);
+
+	TEST_ERRNO(shmdt(addr + 1), EINVAL);
+	TEST_SUCC(shmdt(addr));
+	TEST_ERRNO(shmdt(addr), EINVAL);
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds), ds.shm_nattch == 0);
+}
+END_TEST()
+
+FN_TEST(attach_split)
+{
+	struct shmid_ds ds;
+	char *addr;
+
+	addr = TEST_RES(shmat(shmid, NULL, 0), _ret != (void *)-1);
+
+	// Splitting the mapping counts as an additional attachment.
+	TEST_SUCC(mprotect(addr, getpagesize(), PROT_READ));
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds), ds.shm_nattch == 2);
+
+	TEST_SUCC(shmdt(addr));
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds), ds.shm_nattch == 0);
+}
+END_TEST()
+
+FN_TEST(attach_fixed)
+{
+	char *addr, *addr2;
+
+	addr = TEST_RES(shmat(shmid, NULL, SHM_RDONLY), _ret != (void *)-1);
+	TEST_RES(strcmp(addr, MSG), _ret == 0);
+	TEST_SUCC(shmdt(addr));
+
+	addr2 = TEST_RES(shmat(shmid, addr + 1, SHM_RND), _ret == addr);
+	TEST_ERRNO(shmat(shmid, addr2, 0), EINVAL);
+	TEST_ERRNO(shmat(shmid, addr + 1, 0), EINVAL);
+	TEST_RES(shmat(shmid, addr2, SHM_REMAP), _ret == addr2);
+	TEST_SUCC(shmdt(addr2));
+}
+END_TEST()
+
+FN_TEST(remove)
+{
+	struct shmid_ds ds;
+	char *addr;
+
+	addr = TEST_RES(shmat(shmid, NULL, 0), _ret != (void *)-1);
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+
+	// The segment is still accessible until it is detached.
+	TEST_ERRNO(shmget(SHM_KEY, SHM_SIZE, 0600), ENOENT);
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 ds.shm_nattch == 1 && (ds.shm_perm.mode & SHM_DEST) != 0);
+	TEST_RES(strcmp(addr, MSG), _ret == 0);
+
+	TEST_SUCC(shmdt(addr));
+	TEST_ERRNO(shmctl(shmid, IPC_STAT, &ds), EINVAL);
+}
+END_TEST()